@@ -0,0 +1,281 @@
+'use strict';
+
+// Read-only dashboard of a single Argon session, only ever sends GET requests.
+// Served at `<base>/ui/`, where base is empty or the name of a workspace member.
+// Mirror token of the page URL is passed along to every request.
+
+const BASE = location.pathname.replace(/\/ui(\/[^/]*)?$/, '');
+const TOKEN = new URLSearchParams(location.search).get('token');
+
+const POLL_INTERVAL = 2000;
+const MAX_EVENTS = 200;
+const MAX_TREE_PAGES = 20;
+
+const UNSUPPORTED = { unsupported: true };
+
+function url(path, params = {}) {
+	const query = new URLSearchParams(params);
+
+	if (TOKEN) {
+		query.set('token', TOKEN);
+	}
+
+	const search = query.toString();
+	return BASE + path + (search ? '?' + search : '');
+}
+
+// Endpoints missing on older servers resolve to `UNSUPPORTED` instead of failing
+async function api(path, params) {
+	const response = await fetch(url(path, params), { headers: { Accept: 'application/json' } });
+
+	if (response.status === 404) {
+		return UNSUPPORTED;
+	}
+
+	if (!response.ok) {
+		throw new Error(`${path}: ${response.status} ${await response.text()}`);
+	}
+
+	return response.json();
+}
+
+function element(tag, text, className) {
+	const node = document.createElement(tag);
+
+	if (text !== undefined) {
+		node.textContent = text;
+	}
+
+	if (className) {
+		node.className = className;
+	}
+
+	return node;
+}
+
+function body(panel) {
+	return document.querySelector(`#${panel} .body`);
+}
+
+function show(panel, content) {
+	body(panel).replaceChildren(content);
+}
+
+function message(panel, text, className = 'muted') {
+	show(panel, element('p', text, className));
+}
+
+function table(rows, columns) {
+	if (rows.length === 0) {
+		return element('p', 'Nothing here', 'muted');
+	}
+
+	const node = element('table');
+	const head = node.createTHead().insertRow();
+
+	for (const [title] of columns) {
+		head.append(element('th', title));
+	}
+
+	for (const row of rows) {
+		const tr = node.insertRow();
+
+		for (const [, value] of columns) {
+			tr.insertCell().textContent = value(row) ?? '';
+		}
+	}
+
+	return node;
+}
+
+async function panel(name, path, render, params) {
+	try {
+		const data = await api(path, params);
+
+		if (data === UNSUPPORTED) {
+			message(name, 'Not supported by this server');
+		} else {
+			show(name, render(data));
+		}
+	} catch (err) {
+		message(name, err.message, 'error');
+	}
+}
+
+async function poll() {
+	try {
+		const health = await api('/health');
+
+		document.getElementById('project').textContent = health.project;
+		document.getElementById('status').textContent = health.pause?.paused
+			? 'Paused'
+			: `v${health.version}, ${health.clients} client(s)`;
+	} catch (err) {
+		document.getElementById('status').textContent = 'Disconnected';
+	}
+
+	await Promise.all([
+		panel('clients', '/clients', (clients) =>
+			table(clients, [
+				['ID', (client) => client.id],
+				['Name', (client) => client.name],
+				['Place', (client) => client.placeId],
+			]),
+		),
+		panel('stats', '/stats', (stats) =>
+			table(
+				Object.entries(stats).filter(([, value]) => typeof value !== 'object'),
+				[
+					['Stat', ([key]) => key],
+					['Value', ([, value]) => value],
+				],
+			),
+		),
+		panel('pending', '/pending', (pending) =>
+			table(pending, [
+				['Path', (instance) => instance.path ?? instance.id],
+				['Kind', (instance) => instance.kind],
+				['Age', (instance) => `${instance.age}s`],
+			]),
+		),
+		panel('skipped', '/skipped', (skipped) =>
+			table(skipped, [
+				['Path', (entry) => entry.path],
+				['Operation', (entry) => entry.operation],
+				['Rule', (entry) => entry.rule?.pattern],
+				['Count', (entry) => entry.count],
+			]),
+		),
+	]);
+
+	setTimeout(poll, POLL_INTERVAL);
+}
+
+function describe(event) {
+	switch (event.type) {
+		case 'instanceAdded':
+			return `+ ${event.path} (${event.class})`;
+		case 'instanceUpdated':
+			return `~ ${event.path} [${event.changed.join(', ')}]`;
+		case 'instanceRemoved':
+			return `- ${event.id}`;
+		case 'resync':
+			return `Resync: ${event.reason}`;
+		default:
+			return event.message ?? event.type;
+	}
+}
+
+let lastSeq;
+
+function feed(event) {
+	lastSeq = event.seq;
+
+	const list = body('events');
+	list.prepend(element('li', describe(event), event.level === 'error' ? 'error' : undefined));
+
+	while (list.children.length > MAX_EVENTS) {
+		list.lastChild.remove();
+	}
+}
+
+// Live feed over the WebSocket, polling `/events` if it can't be opened
+function listen() {
+	const params = lastSeq === undefined ? {} : { since: lastSeq };
+	const protocol = location.protocol === 'https:' ? 'wss:' : 'ws:';
+	const socket = new WebSocket(protocol + '//' + location.host + url('/ws', params));
+
+	let opened = false;
+
+	socket.onopen = () => (opened = true);
+	socket.onmessage = (message) => feed(JSON.parse(message.data));
+	socket.onclose = () => (opened ? setTimeout(listen, POLL_INTERVAL) : pollEvents());
+}
+
+async function pollEvents() {
+	try {
+		const page = await api('/events', lastSeq === undefined ? {} : { since: lastSeq });
+
+		if (page === UNSUPPORTED) {
+			body('events').replaceChildren(element('li', 'Not supported by this server', 'muted'));
+			return;
+		}
+
+		page.events.forEach(feed);
+	} catch (err) {
+		feed({ seq: lastSeq, type: 'error', level: 'error', message: err.message });
+	}
+
+	setTimeout(pollEvents, POLL_INTERVAL);
+}
+
+function node(instance, children) {
+	const label = [element('span', instance.name + ' '), element('span', instance.class, 'class')];
+	const own = children.get(instance.id) ?? [];
+
+	if (own.length === 0) {
+		const leaf = element('div', undefined, 'leaf');
+		leaf.append(...label);
+		return leaf;
+	}
+
+	const details = element('details');
+	const summary = element('summary');
+
+	summary.append(...label);
+	details.append(summary, ...own.map((child) => node(child, children)));
+
+	return details;
+}
+
+async function loadTree() {
+	message('tree', 'Loading...');
+
+	const instances = [];
+	let cursor;
+
+	try {
+		for (let page = 0; page < MAX_TREE_PAGES; page++) {
+			const result = await api('/snapshot', cursor ? { cursor } : {});
+
+			if (result === UNSUPPORTED) {
+				return message('tree', 'Not supported by this server');
+			}
+
+			instances.push(...result.instances);
+			cursor = result.cursor;
+
+			if (!cursor) {
+				break;
+			}
+		}
+	} catch (err) {
+		return message('tree', err.message, 'error');
+	}
+
+	const ids = new Set(instances.map((instance) => instance.id));
+	const children = new Map();
+
+	for (const instance of instances) {
+		const siblings = children.get(instance.parent) ?? [];
+		siblings.push(instance);
+		children.set(instance.parent, siblings);
+	}
+
+	const roots = instances.filter((instance) => !ids.has(instance.parent));
+	const tree = element('div');
+
+	tree.append(...roots.map((root) => node(root, children)));
+
+	if (cursor) {
+		tree.append(element('p', 'Only the first part of the tree is shown', 'muted'));
+	}
+
+	show('tree', tree);
+}
+
+document.getElementById('refresh').addEventListener('click', loadTree);
+
+loadTree();
+listen();
+poll();
@@ -1,8 +1,17 @@
 use anyhow::{Context, Result};
 use self_update::backends::github::Update;
-use std::{env, fs::File, path::PathBuf};
+use std::{
+	collections::hash_map::DefaultHasher,
+	env,
+	fmt::Write,
+	fs::{self, File},
+	hash::Hasher,
+	path::{Path, PathBuf},
+};
 
 fn main() -> Result<()> {
+	package_dashboard().context("Failed to package web dashboard!")?;
+
 	let out_path = PathBuf::from(env::var("OUT_DIR")?).join("Lemonade.rbxm");
 
 	if !cfg!(feature = "plugin") {
@@ -32,3 +41,66 @@ fn main() -> Result<()> {
 
 	Ok(())
 }
+
+/// Packages the prebuilt dashboard from `assets/ui` into the binary, stylesheets
+/// and scripts are inlined into `index.html` so the page loads with a single
+/// request, which is the only one that carries the mirror token of its URL
+fn package_dashboard() -> Result<()> {
+	let source = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?).join("assets/ui");
+	let out_dir = PathBuf::from(env::var("OUT_DIR")?).join("ui");
+
+	println!("cargo:rerun-if-changed={}", source.display());
+
+	fs::create_dir_all(&out_dir)?;
+
+	let mut names = fs::read_dir(&source)?
+		.map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+		.collect::<Result<Vec<_>>>()?;
+
+	names.sort();
+
+	let mut files = String::from("&[\n");
+
+	for name in &names {
+		let mut contents = fs::read(source.join(name))?;
+
+		if name == "index.html" {
+			contents = inline(String::from_utf8(contents)?, &source, &names)?.into_bytes();
+		}
+
+		let mut hasher = DefaultHasher::new();
+		hasher.write(&contents);
+
+		let path = out_dir.join(name);
+		fs::write(&path, &contents)?;
+
+		writeln!(
+			files,
+			"\t({:?}, include_bytes!({:?}), \"\\\"{:016x}\\\"\"),",
+			name,
+			path,
+			hasher.finish()
+		)?;
+	}
+
+	files.push(']');
+
+	fs::write(out_dir.join("files.rs"), files)?;
+
+	Ok(())
+}
+
+fn inline(mut html: String, source: &Path, names: &[String]) -> Result<String> {
+	for name in names {
+		let (tag, replacement) = match Path::new(name).extension().and_then(|ext| ext.to_str()) {
+			Some("css") => (format!(r#"<link rel="stylesheet" href="{}">"#, name), "style"),
+			Some("js") => (format!(r#"<script src="{}"></script>"#, name), "script"),
+			_ => continue,
+		};
+
+		let contents = fs::read_to_string(source.join(name))?;
+		html = html.replace(&tag, &format!("<{0}>\n{1}</{0}>", replacement, contents));
+	}
+
+	Ok(html)
+}
@@ -0,0 +1,194 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use rbx_dom_weak::types::{ContentId, Variant};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	fmt::{self, Display, Formatter},
+	fs,
+	path::{Path, PathBuf},
+};
+
+use crate::{
+	ext::PathExt,
+	hooks::{self, ContentCache},
+};
+
+/// Mapping of stored assets, lives next to them in the store
+const MAPPING_FILE: &str = "assets.json";
+
+/// Stored assets are referenced with this prefix until they are uploaded
+const PLACEHOLDER_PREFIX: &str = "rbxasset://argon-assets/";
+
+/// Class of instances created for added assets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[value(rename_all = "PascalCase")]
+pub enum AssetClass {
+	Decal,
+	Sound,
+	MeshPart,
+}
+
+impl AssetClass {
+	/// Infers the class from the file extension, e.g. `Decal` for `.png`
+	pub fn infer(path: &Path) -> Option<Self> {
+		match path.get_ext().to_lowercase().as_str() {
+			"png" | "jpg" | "jpeg" | "bmp" | "tga" => Some(Self::Decal),
+			"ogg" | "mp3" | "wav" | "flac" => Some(Self::Sound),
+			"mesh" | "obj" | "fbx" => Some(Self::MeshPart),
+			_ => None,
+		}
+	}
+
+	/// Content property that references the asset
+	pub fn property(&self) -> &'static str {
+		match self {
+			Self::Decal => "Texture",
+			Self::Sound => "SoundId",
+			Self::MeshPart => "MeshId",
+		}
+	}
+}
+
+impl Display for AssetClass {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Decal => write!(f, "Decal"),
+			Self::Sound => write!(f, "Sound"),
+			Self::MeshPart => write!(f, "MeshPart"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetEntry {
+	/// Name of the file the asset was added from
+	pub source: String,
+	pub class: AssetClass,
+	/// Roblox asset ID, once the asset was uploaded
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub asset_id: Option<u64>,
+}
+
+impl AssetEntry {
+	/// URL instances of the asset should reference
+	pub fn url(&self, key: &str) -> String {
+		match self.asset_id {
+			Some(asset_id) => format!("rbxassetid://{}", asset_id),
+			None => placeholder(key),
+		}
+	}
+}
+
+/// Instance created for an added asset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddedAsset {
+	/// Full name of the instance, it differs from the requested
+	/// one when the parent already had a child with that name
+	pub path: String,
+	pub key: String,
+	pub class: AssetClass,
+}
+
+/// Asset files copied into the project, named after their content hash
+/// so adding the same file twice stores it once, see `ContentCache`
+pub struct AssetStore {
+	dir: PathBuf,
+	cache: ContentCache,
+}
+
+impl AssetStore {
+	pub fn new(dir: &Path) -> Self {
+		Self {
+			dir: dir.to_owned(),
+			cache: ContentCache::new(dir),
+		}
+	}
+
+	/// Writes contents of the file named `name` into the store, returns its key
+	pub fn store(&self, name: &str, contents: &[u8]) -> Result<String> {
+		let key = format!(
+			"{}.{}",
+			hooks::digest(&[contents]),
+			Path::new(name).get_ext().to_lowercase()
+		);
+
+		if !self.cache.contains(&key) {
+			self.cache.insert(&key, contents)?;
+		}
+
+		Ok(key)
+	}
+
+	pub fn path(&self, key: &str) -> PathBuf {
+		self.cache.path(key)
+	}
+
+	/// Keys of all stored assets, sorted
+	pub fn keys(&self) -> Vec<String> {
+		let Ok(entries) = fs::read_dir(&self.dir) else {
+			return vec![];
+		};
+
+		let mut keys: Vec<String> = entries
+			.flatten()
+			.map(|entry| entry.path())
+			.filter(|path| path.is_file() && path.get_name() != MAPPING_FILE)
+			.map(|path| path.get_name().to_owned())
+			.collect();
+
+		keys.sort();
+		keys
+	}
+
+	/// Entries of the mapping by asset key, empty if there is no mapping yet,
+	/// entries of assets removed from the store (e.g. by `argon clean`) are skipped
+	pub fn mapping(&self) -> Result<BTreeMap<String, AssetEntry>> {
+		let path = self.dir.join(MAPPING_FILE);
+
+		if !path.exists() {
+			return Ok(BTreeMap::new());
+		}
+
+		let contents = fs::read_to_string(&path)?;
+		let mut mapping: BTreeMap<String, AssetEntry> =
+			serde_json::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))?;
+
+		mapping.retain(|key, _| self.cache.contains(key));
+
+		Ok(mapping)
+	}
+
+	pub fn save_mapping(&self, mapping: &BTreeMap<String, AssetEntry>) -> Result<()> {
+		fs::create_dir_all(&self.dir)?;
+		fs::write(
+			self.dir.join(MAPPING_FILE),
+			serde_json::to_string_pretty(mapping)? + "\n",
+		)?;
+
+		Ok(())
+	}
+}
+
+/// URL of the stored asset that has not been uploaded yet
+pub fn placeholder(key: &str) -> String {
+	format!("{}{}", PLACEHOLDER_PREFIX, key)
+}
+
+pub fn content_value(url: &str) -> Variant {
+	ContentId::from(url).into()
+}
+
+/// Returns `name` or the first of `name2`, `name3`.. that is not taken
+pub fn unique_name(name: &str, is_taken: impl Fn(&str) -> bool) -> String {
+	if !is_taken(name) {
+		return name.to_owned();
+	}
+
+	(2..)
+		.map(|index| format!("{}{}", name, index))
+		.find(|name| !is_taken(name))
+		.unwrap()
+}
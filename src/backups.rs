@@ -0,0 +1,171 @@
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use std::{
+	collections::HashMap,
+	fs,
+	path::{Path, PathBuf},
+	sync::Mutex,
+};
+
+use crate::{
+	config::Config,
+	ext::PathExt,
+	lock,
+	sessions::{self, Session},
+	t,
+};
+
+/// Project files already backed up during the current syncback batch
+static BATCH: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backup {
+	pub path: PathBuf,
+	/// Name of the backed up project file
+	pub file: String,
+	/// Sortable time the backup was made, e.g. `20240906T153000123`
+	pub timestamp: String,
+}
+
+pub fn get_backups_dir(workspace_dir: &Path) -> PathBuf {
+	workspace_dir.join(".argon").join("backups")
+}
+
+/// Copies the project file to `.argon/backups` before it gets overwritten for
+/// the first time in the current syncback batch, unless the content is the same
+pub fn create(path: &Path, contents: &[u8]) -> Result<Option<Backup>> {
+	let config = Config::new();
+
+	if !config.project_backups || lock!(BATCH).iter().any(|backed_up| backed_up == path) {
+		return Ok(None);
+	}
+
+	let current = match fs::read(path) {
+		Ok(current) if current != contents => current,
+		_ => return Ok(None),
+	};
+
+	let backup = write_backup(path, &current)?;
+
+	lock!(BATCH).push(path.to_owned());
+	prune(path, config.max_backups)?;
+
+	Ok(Some(backup))
+}
+
+/// Marks the end of the syncback batch, the next
+/// modification of any project file will be backed up again
+pub fn end_batch() {
+	lock!(BATCH).clear();
+}
+
+/// Returns backups of the project file from the oldest to the newest
+pub fn list(path: &Path) -> Result<Vec<Backup>> {
+	let dir = get_backups_dir(path.get_parent());
+	let prefix = format!("{}.", path.get_name());
+
+	if !dir.exists() {
+		return Ok(vec![]);
+	}
+
+	let mut backups = vec![];
+
+	for entry in fs::read_dir(&dir)? {
+		let path = entry?.path();
+		let name = path.get_name().to_owned();
+
+		if let Some(timestamp) = name.strip_prefix(&prefix) {
+			backups.push(Backup {
+				file: prefix.trim_end_matches('.').to_owned(),
+				timestamp: timestamp.to_owned(),
+				path,
+			});
+		}
+	}
+
+	backups.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+	Ok(backups)
+}
+
+/// Removes the oldest backups of the project file above `max_backups`
+pub fn prune(path: &Path, max_backups: usize) -> Result<()> {
+	let backups = list(path)?;
+
+	for backup in backups.iter().take(backups.len().saturating_sub(max_backups)) {
+		fs::remove_file(&backup.path)?;
+	}
+
+	Ok(())
+}
+
+/// Restores the project file from the backup made at `timestamp` or the
+/// latest one, current content is backed up first so it can be restored too
+pub fn restore(path: &Path, timestamp: Option<&str>) -> Result<Backup> {
+	let backups = list(path)?;
+
+	let backup = match timestamp {
+		Some(timestamp) => backups
+			.into_iter()
+			.find(|backup| backup.timestamp == timestamp)
+			.with_context(|| format!("There is no backup of {} made at {}", path.display(), timestamp))?,
+		None => match backups.into_iter().last() {
+			Some(backup) => backup,
+			None => bail!("There are no backups of {}", path.display()),
+		},
+	};
+
+	let contents = fs::read(&backup.path)?;
+
+	if let Ok(current) = fs::read(path) {
+		if current != contents {
+			write_backup(path, &current)?;
+			prune(path, Config::new().max_backups)?;
+		}
+	}
+
+	fs::write(path, contents)?;
+
+	Ok(backup)
+}
+
+/// Fails if one of the sessions is using the project, restoring
+/// the file under a live tree would make them fight each other
+pub fn check_sessions(path: &Path, sessions: &HashMap<String, Session>) -> Result<()> {
+	if let Some((id, session)) = sessions::find_serving(sessions, path) {
+		bail!(
+			"{}",
+			t!(
+				"backups.live_session",
+				path = path.to_string().bold(),
+				id = id.bold(),
+				pid = session.pid.to_string().bold(),
+				flag = "--force".bold()
+			)
+		);
+	}
+
+	Ok(())
+}
+
+fn write_backup(path: &Path, contents: &[u8]) -> Result<Backup> {
+	let dir = get_backups_dir(path.get_parent());
+	let file = path.get_name().to_owned();
+	let mut timestamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%3f").to_string();
+
+	// Multiple backups can be made within the same millisecond
+	while dir.join(format!("{}.{}", file, timestamp)).exists() {
+		timestamp.push('_');
+	}
+
+	let backup_path = dir.join(format!("{}.{}", file, timestamp));
+
+	fs::create_dir_all(&dir)?;
+	fs::write(&backup_path, contents)?;
+
+	Ok(Backup {
+		path: backup_path,
+		file,
+		timestamp,
+	})
+}
@@ -0,0 +1,362 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	env,
+	fmt::{self, Display, Formatter},
+	fs,
+	path::{Path, PathBuf},
+	process,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use crate::{
+	core::{queue::PlaceDetails, Core},
+	project::Project,
+	server::{protocol::Negotiated, Message},
+	vfs::{Vfs, VfsEvent},
+};
+
+const CLIENT_ID: u32 = 0;
+
+/// How long to wait for a change to reach the client before the run fails
+const SYNC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of files written by a single batch operation
+const BATCH_SIZE: usize = 100;
+
+/// Size of the script edited by the large edit operation
+const LARGE_SCRIPT_SIZE: usize = 256 * 1024;
+
+const PROJECT: &str = r#"{
+	"name": "Bench",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": {
+			"$path": "src"
+		}
+	}
+}"#;
+
+/// Representative change made outside of Studio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+	SmallEdit,
+	LargeEdit,
+	Add,
+	Rename,
+	Batch,
+}
+
+impl Operation {
+	pub const ALL: [Operation; 5] = [
+		Operation::SmallEdit,
+		Operation::LargeEdit,
+		Operation::Add,
+		Operation::Rename,
+		Operation::Batch,
+	];
+}
+
+impl Display for Operation {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Operation::SmallEdit => write!(f, "smallEdit"),
+			Operation::LargeEdit => write!(f, "largeEdit"),
+			Operation::Add => write!(f, "add"),
+			Operation::Rename => write!(f, "rename"),
+			Operation::Batch => write!(f, "batch"),
+		}
+	}
+}
+
+/// Latencies in milliseconds and throughput in operations per second
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metrics {
+	pub p50: f64,
+	pub p95: f64,
+	pub throughput: f64,
+}
+
+impl Metrics {
+	pub fn new(latencies: &[Duration]) -> Self {
+		let mut sorted = latencies.to_vec();
+		sorted.sort();
+
+		let total = sorted.iter().sum::<Duration>().as_secs_f64();
+
+		Self {
+			p50: percentile(&sorted, 0.5),
+			p95: percentile(&sorted, 0.95),
+			throughput: if total > 0.0 { sorted.len() as f64 / total } else { 0.0 },
+		}
+	}
+
+	fn get(&self, metric: &str) -> f64 {
+		match metric {
+			"p50" => self.p50,
+			"p95" => self.p95,
+			_ => self.throughput,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchReport {
+	pub iterations: usize,
+	/// Metrics by operation name, e.g. `smallEdit`
+	pub operations: BTreeMap<String, Metrics>,
+}
+
+impl BenchReport {
+	pub fn load(path: &Path) -> Result<Self> {
+		let contents = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+		serde_json::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+	}
+}
+
+/// Metric that got worse than the baseline by more than the threshold
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Regression {
+	pub operation: String,
+	pub metric: String,
+	pub baseline: f64,
+	pub current: f64,
+	/// Relative change in percent, positive when worse
+	pub change: f64,
+}
+
+impl Display for Regression {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{} {}: {:.3} -> {:.3} ({:+.1}%)",
+			self.operation, self.metric, self.baseline, self.current, self.change
+		)
+	}
+}
+
+/// Returns metrics of `current` that regressed beyond `threshold` (e.g. `0.2` for 20%),
+/// latencies regress when they grow and throughput when it drops. Operations
+/// missing from the baseline are not compared
+pub fn compare(baseline: &BenchReport, current: &BenchReport, threshold: f64) -> Vec<Regression> {
+	let mut regressions = vec![];
+
+	for (operation, metrics) in &current.operations {
+		let Some(baseline) = baseline.operations.get(operation) else {
+			continue;
+		};
+
+		for metric in ["p50", "p95", "throughput"] {
+			let (old, new) = (baseline.get(metric), metrics.get(metric));
+
+			if old <= 0.0 {
+				continue;
+			}
+
+			// Higher throughput is better, unlike latencies
+			let change = if metric == "throughput" {
+				(old - new) / old
+			} else {
+				(new - old) / old
+			};
+
+			if change > threshold {
+				regressions.push(Regression {
+					operation: operation.to_owned(),
+					metric: metric.to_owned(),
+					baseline: old,
+					current: new,
+					change: change * 100.0,
+				});
+			}
+		}
+	}
+
+	regressions
+}
+
+/// Parses threshold like `20%` or `20` into a fraction
+pub fn parse_threshold(threshold: &str) -> Result<f64> {
+	let percent: f64 = threshold
+		.trim()
+		.trim_end_matches('%')
+		.parse()
+		.with_context(|| format!("Invalid threshold: {}", threshold))?;
+
+	if percent < 0.0 {
+		bail!("Threshold cannot be negative: {}", threshold);
+	}
+
+	Ok(percent / 100.0)
+}
+
+/// Measures how long changes take from the file system to a client, using the
+/// whole processing pipeline on top of the in-memory VFS so results only depend
+/// on Argon itself. Files of `fixture` directory are synced alongside the
+/// benchmark ones to make the tree representative of the measured project
+pub fn run(fixture: Option<&Path>, iterations: usize) -> Result<BenchReport> {
+	let dir = env::temp_dir().join(format!("argon-bench-{}", process::id()));
+	let result = Bench::new(&dir, fixture).and_then(|bench| bench.run(iterations));
+
+	fs::remove_dir_all(&dir).ok();
+
+	result
+}
+
+struct Bench {
+	core: Core,
+	vfs: Arc<Vfs>,
+	src: PathBuf,
+}
+
+impl Bench {
+	fn new(dir: &Path, fixture: Option<&Path>) -> Result<Self> {
+		let src = dir.join("src");
+
+		// Files only exist in the VFS, the directory keeps what Argon stores next to the project
+		fs::remove_dir_all(dir).ok();
+		fs::create_dir_all(dir)?;
+
+		let vfs = Vfs::new_virtual();
+
+		vfs.create_dir(&src)?;
+		vfs.write(&src.join("Small.luau"), b"return 0")?;
+		vfs.write(&src.join("Large.luau"), &large_script(0))?;
+		vfs.write(&src.join("Renamed0.luau"), b"return 0")?;
+
+		if let Some(fixture) = fixture {
+			copy_dir(fixture, &src.join("Fixture"), &vfs)?;
+		}
+
+		// Project is not read from the VFS, so it is loaded from memory as well
+		let core = Core::with_vfs(Project::load_from_str(PROJECT, dir)?, vfs)?;
+
+		// Only Argon's own writes are recorded, these simulate external ones
+		core.vfs().set_undo_log(None);
+		core.queue()
+			.subscribe(CLIENT_ID, "Bench", PlaceDetails::default(), Negotiated::default())?;
+
+		Ok(Self {
+			vfs: core.vfs(),
+			core,
+			src,
+		})
+	}
+
+	fn run(&self, iterations: usize) -> Result<BenchReport> {
+		let mut operations = BTreeMap::new();
+
+		for operation in Operation::ALL {
+			let mut latencies = vec![];
+
+			for iteration in 1..=iterations {
+				latencies.push(self.measure(operation, iteration)?);
+			}
+
+			operations.insert(operation.to_string(), Metrics::new(&latencies));
+		}
+
+		Ok(BenchReport { iterations, operations })
+	}
+
+	/// Performs the operation and waits until its changes reach the client
+	fn measure(&self, operation: Operation, iteration: usize) -> Result<Duration> {
+		let start = Instant::now();
+
+		let event = match operation {
+			Operation::SmallEdit => {
+				let path = self.src.join("Small.luau");
+
+				self.vfs.write(&path, format!("return {}", iteration).as_bytes())?;
+				VfsEvent::Write(path)
+			}
+			Operation::LargeEdit => {
+				let path = self.src.join("Large.luau");
+
+				self.vfs.write(&path, &large_script(iteration))?;
+				VfsEvent::Write(path)
+			}
+			Operation::Add => {
+				let path = self.src.join(format!("Added{}.luau", iteration));
+
+				self.vfs.write(&path, b"return 0")?;
+				VfsEvent::Create(path)
+			}
+			Operation::Rename => {
+				self.vfs.rename(
+					&self.src.join(format!("Renamed{}.luau", iteration - 1)),
+					&self.src.join(format!("Renamed{}.luau", iteration)),
+				)?;
+
+				// Editors report renames of files as changes of their directory
+				VfsEvent::Write(self.src.clone())
+			}
+			Operation::Batch => {
+				let path = self.src.join(format!("Batch{}", iteration));
+
+				self.vfs.create_dir(&path)?;
+
+				for index in 0..BATCH_SIZE {
+					self.vfs
+						.write(&path.join(format!("Module{}.luau", index)), b"return {}")?;
+				}
+
+				VfsEvent::Create(path)
+			}
+		};
+
+		self.core.processor().notify(event);
+
+		loop {
+			let remaining = SYNC_TIMEOUT.saturating_sub(start.elapsed());
+
+			match self.core.queue().get_with_timeout(CLIENT_ID, remaining)? {
+				Some(Message::SyncChanges(_)) | Some(Message::SyncBlobChanges(_)) => break,
+				Some(_) => continue,
+				None => bail!("Changes of {} operation did not reach the client", operation),
+			}
+		}
+
+		Ok(start.elapsed())
+	}
+}
+
+/// Milliseconds of the sample at the given rank, `sorted` has to be sorted
+fn percentile(sorted: &[Duration], rank: f64) -> f64 {
+	if sorted.is_empty() {
+		return 0.0;
+	}
+
+	let index = ((rank * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len()) - 1;
+
+	sorted[index].as_secs_f64() * 1000.0
+}
+
+fn large_script(iteration: usize) -> Vec<u8> {
+	let line = format!("local value = {}\n", iteration);
+
+	line.repeat(LARGE_SCRIPT_SIZE / line.len()).into_bytes()
+}
+
+fn copy_dir(from: &Path, to: &Path, vfs: &Vfs) -> Result<()> {
+	vfs.create_dir(to)?;
+
+	for entry in fs::read_dir(from)? {
+		let path = entry?.path();
+		let target = to.join(path.file_name().unwrap());
+
+		if path.is_dir() {
+			copy_dir(&path, &target, vfs)?;
+		} else {
+			vfs.write(&target, &fs::read(&path)?)?;
+		}
+	}
+
+	Ok(())
+}
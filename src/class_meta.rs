@@ -0,0 +1,258 @@
+use rbx_reflection::ClassTag;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	hash::{DefaultHasher, Hash, Hasher},
+};
+
+use crate::{constants::CLASS_METADATA_VERSION, glob::Glob};
+
+/// Value of the `enrich` query parameter that attaches class metadata
+pub const ENRICH: &str = "class-meta";
+
+/// Icon used for classes that no entry of the icon table applies to
+pub const DEFAULT_ICON: &str = "instance";
+
+/// Built-in icons as `(class, icon)`, they also apply to all
+/// subclasses of the class unless they have their own entry
+static ICONS: [(&str, &str); 44] = [
+	("Script", "script"),
+	("LocalScript", "local-script"),
+	("ModuleScript", "module-script"),
+	("Folder", "folder"),
+	("Configuration", "configuration"),
+	("Model", "model"),
+	("Actor", "actor"),
+	("Tool", "tool"),
+	("BasePart", "part"),
+	("MeshPart", "mesh-part"),
+	("UnionOperation", "union"),
+	("Terrain", "terrain"),
+	("Camera", "camera"),
+	("Attachment", "attachment"),
+	("Constraint", "constraint"),
+	("JointInstance", "joint"),
+	("Humanoid", "humanoid"),
+	("RemoteEvent", "remote-event"),
+	("UnreliableRemoteEvent", "remote-event"),
+	("RemoteFunction", "remote-function"),
+	("BindableEvent", "bindable-event"),
+	("BindableFunction", "bindable-function"),
+	("ValueBase", "value"),
+	("StringValue", "string-value"),
+	("LocalizationTable", "localization-table"),
+	("GuiBase2d", "gui"),
+	("UIComponent", "ui-component"),
+	("Sound", "sound"),
+	("SoundGroup", "sound-group"),
+	("Light", "light"),
+	("PostEffect", "effect"),
+	("Sky", "sky"),
+	("Atmosphere", "atmosphere"),
+	("Animation", "animation"),
+	("Decal", "decal"),
+	("ParticleEmitter", "particles"),
+	("Workspace", "workspace"),
+	("Players", "players"),
+	("Lighting", "lighting"),
+	("ReplicatedStorage", "replicated-storage"),
+	("ServerScriptService", "server-script-service"),
+	("ServerStorage", "server-storage"),
+	("StarterGui", "starter-gui"),
+	("StarterPlayer", "starter-player"),
+];
+
+/// Explorer categories as `(class, category)`, the closest
+/// superclass with an entry decides the category of a class
+static CATEGORIES: [(&str, &str); 18] = [
+	("LuaSourceContainer", "scripts"),
+	("ValueBase", "values"),
+	("RemoteEvent", "networking"),
+	("UnreliableRemoteEvent", "networking"),
+	("RemoteFunction", "networking"),
+	("BindableEvent", "networking"),
+	("BindableFunction", "networking"),
+	("Folder", "containers"),
+	("Configuration", "containers"),
+	("PVInstance", "world"),
+	("Attachment", "world"),
+	("Constraint", "physics"),
+	("JointInstance", "physics"),
+	("GuiBase", "gui"),
+	("UIComponent", "gui"),
+	("Light", "effects"),
+	("PostEffect", "effects"),
+	("Sound", "audio"),
+];
+
+/// Project entry that assigns an icon to instances of the class, optionally
+/// only to ones whose name matches the glob, e.g. `*Service` for Knit services
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IconRule {
+	pub class: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub name: Option<Glob>,
+	pub icon: String,
+}
+
+impl IconRule {
+	pub fn matches(&self, class: &str, name: &str) -> bool {
+		self.class == class && self.name.as_ref().is_none_or(|glob| glob.matches(name))
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassMetadata {
+	/// Superclasses ordered from the closest one
+	pub superclasses: Vec<String>,
+	pub service: bool,
+	pub creatable: bool,
+	pub deprecated: bool,
+	pub category: String,
+	pub icon: String,
+}
+
+/// Metadata of all classes, rules with name patterns can't be resolved
+/// per class so they are included for clients to apply themselves
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassMetadataTable {
+	/// Version of the reflection database the table was built from
+	pub version: String,
+	pub etag: String,
+	pub classes: BTreeMap<String, ClassMetadata>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub rules: Vec<IconRule>,
+}
+
+/// Class metadata attached to individual instances with `enrich=class-meta`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceMetadata {
+	pub category: String,
+	pub icon: String,
+	pub service: bool,
+}
+
+/// Returns the class and its superclasses up to `Instance`, starting with the class
+pub fn class_chain(class: &str) -> Vec<&str> {
+	let database = rbx_reflection_database::get();
+	let mut chain = vec![];
+	let mut current = Some(class);
+
+	while let Some(class) = current {
+		chain.push(class);
+
+		if class == "Instance" {
+			break;
+		}
+
+		current = database
+			.classes
+			.get(class)
+			.and_then(|descriptor| descriptor.superclass.as_deref());
+	}
+
+	chain
+}
+
+pub fn category(class: &str) -> &'static str {
+	if has_tag(class, ClassTag::Service) {
+		return "services";
+	}
+
+	class_chain(class)
+		.into_iter()
+		.find_map(|class| CATEGORIES.iter().find(|(name, _)| *name == class))
+		.map_or("other", |(_, category)| category)
+}
+
+/// Returns icon of the class, class-wide project rules take precedence
+pub fn icon(class: &str, rules: &[IconRule]) -> String {
+	class_chain(class)
+		.into_iter()
+		.find_map(|class| {
+			rules
+				.iter()
+				.find(|rule| rule.class == class && rule.name.is_none())
+				.map(|rule| rule.icon.clone())
+				.or_else(|| {
+					ICONS
+						.iter()
+						.find(|(name, _)| *name == class)
+						.map(|(_, icon)| icon.to_string())
+				})
+		})
+		.unwrap_or_else(|| DEFAULT_ICON.to_owned())
+}
+
+pub fn get(class: &str, rules: &[IconRule]) -> ClassMetadata {
+	ClassMetadata {
+		superclasses: class_chain(class).into_iter().skip(1).map(String::from).collect(),
+		service: has_tag(class, ClassTag::Service),
+		creatable: !has_tag(class, ClassTag::NotCreatable),
+		deprecated: has_tag(class, ClassTag::Deprecated),
+		category: category(class).to_owned(),
+		icon: icon(class, rules),
+	}
+}
+
+/// Returns metadata of the instance, rules with name patterns apply here too
+pub fn get_instance(class: &str, name: &str, rules: &[IconRule]) -> InstanceMetadata {
+	let icon = rules
+		.iter()
+		.filter(|rule| rule.name.is_some())
+		.find(|rule| rule.matches(class, name))
+		.map_or_else(|| icon(class, rules), |rule| rule.icon.clone());
+
+	InstanceMetadata {
+		category: category(class).to_owned(),
+		icon,
+		service: has_tag(class, ClassTag::Service),
+	}
+}
+
+pub fn table(rules: &[IconRule]) -> ClassMetadataTable {
+	let database = rbx_reflection_database::get();
+
+	ClassMetadataTable {
+		version: version_string(database.version),
+		etag: etag(database.version, rules),
+		classes: database
+			.classes
+			.keys()
+			.map(|class| (class.to_string(), get(class, rules)))
+			.collect(),
+		rules: rules.iter().filter(|rule| rule.name.is_some()).cloned().collect(),
+	}
+}
+
+/// Returns entity tag of the table, it only changes with the reflection
+/// database, built-in tables (`CLASS_METADATA_VERSION`) or project rules
+pub fn etag(version: [u32; 4], rules: &[IconRule]) -> String {
+	let mut hasher = DefaultHasher::new();
+
+	for rule in rules {
+		(&rule.class, rule.name.as_ref().map(Glob::as_str), &rule.icon).hash(&mut hasher);
+	}
+
+	format!(
+		"\"{}-{}-{:016x}\"",
+		version_string(version),
+		CLASS_METADATA_VERSION,
+		hasher.finish()
+	)
+}
+
+fn version_string(version: [u32; 4]) -> String {
+	version.map(|part| part.to_string()).join(".")
+}
+
+fn has_tag(class: &str, tag: ClassTag) -> bool {
+	rbx_reflection_database::get()
+		.classes
+		.get(class)
+		.is_some_and(|descriptor| descriptor.tags.contains(&tag))
+}
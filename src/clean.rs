@@ -0,0 +1,286 @@
+use anyhow::Result;
+use log::{trace, warn};
+use rbx_dom_weak::{types::Variant, Ustr};
+use std::{
+	collections::HashSet,
+	fmt::{self, Display, Formatter},
+	fs,
+	path::{Path, PathBuf},
+};
+
+use crate::{
+	assets::AssetStore,
+	constants::ASSET_ATTRIBUTE,
+	core::{meta::Context, tree::Tree},
+	daemon,
+	ext::PathExt,
+	middleware::{data, Middleware},
+	vfs::Vfs,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+	/// Change history recorded in `.argon`
+	Cache,
+	/// Daemon logs and the legacy `lemonlogs.txt`
+	Logs,
+	/// Temporary files left by interrupted writes
+	Trash,
+	/// Instance data files that no instance uses or that only contain defaults
+	OrphanedData,
+	/// Stored assets that no instance references anymore
+	UnusedAssets,
+}
+
+impl Display for Category {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Category::Cache => write!(f, "Cache"),
+			Category::Logs => write!(f, "Logs"),
+			Category::Trash => write!(f, "Trash"),
+			Category::OrphanedData => write!(f, "Orphaned data"),
+			Category::UnusedAssets => write!(f, "Unused assets"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Artifact {
+	pub category: Category,
+	pub path: PathBuf,
+	/// Size in bytes, for directories it includes all of their contents
+	pub size: u64,
+}
+
+impl Artifact {
+	fn new(category: Category, path: PathBuf) -> Self {
+		Self {
+			category,
+			size: size_of(&path),
+			path,
+		}
+	}
+}
+
+/// Finds artifacts of the given categories, except orphaned data and unused assets
+/// which require the project tree, see `find_orphaned_data` and `find_unused_assets`.
+/// Only the workspace is searched
+pub fn find(workspace_dir: &Path, categories: &[Category]) -> Vec<Artifact> {
+	let argon_dir = workspace_dir.join(".argon");
+	let mut artifacts = vec![];
+
+	if categories.contains(&Category::Cache) {
+		for name in ["history.jsonl", "history.1.jsonl"] {
+			let path = argon_dir.join(name);
+
+			if path.is_file() {
+				artifacts.push(Artifact::new(Category::Cache, path));
+			}
+		}
+	}
+
+	if categories.contains(&Category::Logs) {
+		let log_dir = daemon::get_log_dir(workspace_dir);
+
+		if log_dir.is_dir() {
+			artifacts.push(Artifact::new(Category::Logs, log_dir));
+		}
+
+		let legacy_log = workspace_dir.join("lemonlogs.txt");
+
+		if legacy_log.is_file() {
+			artifacts.push(Artifact::new(Category::Logs, legacy_log));
+		}
+	}
+
+	if categories.contains(&Category::Trash) {
+		if let Ok(entries) = fs::read_dir(&argon_dir) {
+			for entry in entries.flatten() {
+				let path = entry.path();
+
+				if path.is_file() && path.get_ext() == "tmp" {
+					artifacts.push(Artifact::new(Category::Trash, path));
+				}
+			}
+		}
+	}
+
+	artifacts
+}
+
+/// Compares instance data files in the directories of the tree with the sources
+/// of its instances, the same way `fsck` does, returns files that no instance uses
+/// or that only contain default values. Files matched by ignore globs are skipped
+pub fn find_orphaned_data(workspace_dir: &Path, tree: &Tree, context: &Context, vfs: &Vfs) -> Vec<Artifact> {
+	let mut referenced = HashSet::new();
+	let mut includes = HashSet::new();
+	let mut dirs = HashSet::new();
+
+	for meta in tree.meta_map().values() {
+		// Included files contain bare property values, not whole instance data
+		includes.extend(meta.source.get_includes().into_iter().map(Path::to_owned));
+
+		for path in meta.source.paths() {
+			referenced.insert(path.to_owned());
+
+			if path.is_dir() {
+				dirs.insert(path.to_owned());
+			}
+		}
+	}
+
+	let mut dirs: Vec<_> = dirs.into_iter().collect();
+	dirs.sort();
+
+	let mut artifacts = vec![];
+
+	for dir in dirs {
+		let Ok(entries) = fs::read_dir(&dir) else {
+			continue;
+		};
+
+		let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+		paths.sort();
+
+		for path in paths {
+			if !path.is_file() || !is_data_file(&path, context) || !is_inside(&path, workspace_dir) {
+				continue;
+			}
+
+			if context.ignore_rules().iter().any(|rule| rule.matches(&path)) {
+				trace!("Skipping ignored data file {}", path.display());
+				continue;
+			}
+
+			if includes.contains(&path) {
+				continue;
+			}
+
+			if !referenced.contains(&path) || is_default_data(&path, context, vfs) {
+				artifacts.push(Artifact::new(Category::OrphanedData, path));
+			}
+		}
+	}
+
+	artifacts
+}
+
+/// Returns files of the asset store that are not referenced by the asset
+/// attribute of any instance in the tree, see `Core::add_asset`
+pub fn find_unused_assets(assets_dir: &Path, tree: &Tree) -> Vec<Artifact> {
+	let mut referenced = HashSet::new();
+
+	for instance in tree.inner().descendants() {
+		if let Some(Variant::Attributes(attributes)) = instance.properties.get(&Ustr::from("Attributes")) {
+			if let Some(Variant::String(key)) = attributes.get(ASSET_ATTRIBUTE) {
+				referenced.insert(key.to_owned());
+			}
+		}
+	}
+
+	let store = AssetStore::new(assets_dir);
+
+	store
+		.keys()
+		.into_iter()
+		.filter(|key| !referenced.contains(key))
+		.map(|key| Artifact::new(Category::UnusedAssets, store.path(&key)))
+		.collect()
+}
+
+/// Removes artifacts that are inside of the workspace, returns the number of freed bytes
+pub fn remove(workspace_dir: &Path, artifacts: &[Artifact]) -> Result<u64> {
+	let mut freed = 0;
+
+	for artifact in artifacts {
+		let path = &artifact.path;
+
+		if !is_inside(path, workspace_dir) {
+			warn!(
+				"Refusing to remove {} as it is outside of the workspace",
+				path.display()
+			);
+			continue;
+		}
+
+		// Symlinks are removed themselves, never their targets
+		let metadata = fs::symlink_metadata(path)?;
+
+		if metadata.is_dir() {
+			fs::remove_dir_all(path)?;
+		} else {
+			fs::remove_file(path)?;
+		}
+
+		freed += artifact.size;
+	}
+
+	Ok(freed)
+}
+
+/// Formats number of bytes in a human readable way
+pub fn format_size(size: u64) -> String {
+	const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+	let mut size = size as f64;
+	let mut unit = 0;
+
+	while size >= 1024.0 && unit < UNITS.len() - 1 {
+		size /= 1024.0;
+		unit += 1;
+	}
+
+	if unit == 0 {
+		format!("{} {}", size, UNITS[unit])
+	} else {
+		format!("{:.1} {}", size, UNITS[unit])
+	}
+}
+
+fn is_data_file(path: &Path, context: &Context) -> bool {
+	context
+		.sync_rules_of_type(&Middleware::InstanceData, false)
+		.iter()
+		.any(|rule| {
+			rule.pattern.as_ref().is_some_and(|pattern| pattern.matches_path(path))
+				|| rule
+					.child_pattern
+					.as_ref()
+					.is_some_and(|pattern| pattern.matches(path.get_name()))
+		})
+}
+
+/// Whether the data file does not change anything about its instance
+fn is_default_data(path: &Path, context: &Context, vfs: &Vfs) -> bool {
+	match data::read_data(path, None, context, vfs) {
+		Ok(data) => {
+			data.class.is_none()
+				&& data.properties.is_empty()
+				&& data.keep_unknowns.is_none()
+				&& data.original_name.is_none()
+				&& data.mesh_source.is_none()
+		}
+		Err(_) => false,
+	}
+}
+
+fn is_inside(path: &Path, workspace_dir: &Path) -> bool {
+	match (path.parent().map(fs::canonicalize), fs::canonicalize(workspace_dir)) {
+		(Some(Ok(parent)), Ok(workspace_dir)) => parent.starts_with(workspace_dir),
+		_ => false,
+	}
+}
+
+fn size_of(path: &Path) -> u64 {
+	let Ok(metadata) = fs::symlink_metadata(path) else {
+		return 0;
+	};
+
+	if !metadata.is_dir() {
+		return metadata.len();
+	}
+
+	fs::read_dir(path)
+		.map(|entries| entries.flatten().map(|entry| size_of(&entry.path())).sum())
+		.unwrap_or(0)
+}
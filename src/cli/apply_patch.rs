@@ -0,0 +1,144 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use reqwest::{blocking::Client, header::CONTENT_TYPE};
+use serde_json::Value;
+use std::{fs, path::PathBuf};
+
+use crate::{
+	argon_info, argon_warn,
+	core::{
+		patch::{Patch, PatchReport},
+		Core,
+	},
+	ext::PathExt,
+	logger::Table,
+	project::{self, Project},
+	server::patch::ApplyPatchRequest,
+	sessions, t,
+};
+
+/// Apply property changes from a JSON patch file to all instances matched by its selectors
+#[derive(Parser)]
+pub struct ApplyPatch {
+	/// Patch file path
+	#[arg()]
+	patch: PathBuf,
+
+	/// Project path
+	#[arg()]
+	project: Option<PathBuf>,
+
+	/// Only print what would change, without writing anything
+	#[arg(short, long)]
+	dry_run: bool,
+
+	/// Stop at the first instance that fails to be patched
+	#[arg(short, long)]
+	strict: bool,
+}
+
+impl ApplyPatch {
+	pub fn main(self) -> Result<()> {
+		let project_path = project::resolve(self.project.unwrap_or_default())?;
+
+		if !project_path.exists() {
+			bail!(
+				"No project files found in {}. Run {} to create new one",
+				project_path.get_parent().to_string().bold(),
+				"argon init".bold(),
+			);
+		}
+
+		let contents = fs::read_to_string(&self.patch)
+			.with_context(|| format!("Failed to read patch file {}", self.patch.display()))?;
+
+		// Running session has to apply the patch itself, so its tree and clients stay in sync
+		let address =
+			sessions::find_serving(&sessions::get_all()?, &project_path).and_then(|(_, session)| session.get_address());
+
+		let report: PatchReport = if let Some(address) = address {
+			let body = rmp_serde::to_vec(&ApplyPatchRequest {
+				patch: contents,
+				dry_run: self.dry_run,
+				strict: self.strict,
+			})?;
+
+			let response = Client::new()
+				.post(format!("{}/apply-patch", address))
+				.header(CONTENT_TYPE, "application/msgpack")
+				.body(body)
+				.send()?;
+
+			if !response.status().is_success() {
+				bail!("Failed to apply patch: {}", response.text()?);
+			}
+
+			response.json()?
+		} else {
+			let patch: Patch = serde_json::from_str(&contents)
+				.with_context(|| format!("Failed to parse patch file {}", self.patch.display()))?;
+
+			Core::new(Project::load(&project_path)?, false)?.apply_patch(&patch, self.dry_run, self.strict)?
+		};
+
+		let format = |value: &Option<Value>| value.as_ref().map(Value::to_string).unwrap_or_default();
+
+		for (index, operation) in report.operations.iter().enumerate() {
+			if self.dry_run && !operation.instances.is_empty() {
+				let mut table = Table::new();
+				table.set_header(vec!["Instance", "Property", "From", "To"]);
+
+				for instance in &operation.instances {
+					for change in &instance.changes {
+						table.add_row(vec![
+							instance.path.clone(),
+							change.property.clone(),
+							format(&change.from),
+							format(&change.to),
+						]);
+					}
+				}
+
+				println!("{}", table);
+			}
+
+			for failure in &operation.failures {
+				argon_warn!(
+					"{}",
+					t!("apply_patch.failure", path = failure.path.bold(), error = failure.error)
+				);
+			}
+
+			let count = operation.instances.len().to_string().bold();
+
+			if self.dry_run {
+				argon_info!(
+					"{}",
+					t!(
+						"apply_patch.would_change",
+						index = index + 1,
+						count = count,
+						matched = operation.matched
+					)
+				);
+			} else {
+				argon_info!(
+					"{}",
+					t!(
+						"apply_patch.changed",
+						index = index + 1,
+						count = count,
+						matched = operation.matched
+					)
+				);
+			}
+		}
+
+		if self.strict && report.failures() > 0 {
+			bail!("{}", t!("apply_patch.stopped", count = report.operations.len()));
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,124 @@
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use colored::Colorize;
+use reqwest::{blocking::Client, header::CONTENT_TYPE};
+use serde_bytes::ByteBuf;
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::{
+	argon_info,
+	assets::{AddedAsset, AssetClass},
+	ext::PathExt,
+	server::assets::{AddAssetRequest, AssetIdsRequest},
+	sessions, t,
+};
+
+/// Add local image, audio or mesh files to the project as instances (requires running session)
+#[derive(Parser)]
+pub struct Asset {
+	/// Whether to `add` an asset or `upload` assets
+	#[arg(hide_possible_values = true)]
+	mode: AssetMode,
+
+	/// File to add or, when uploading, JSON file mapping asset keys to asset IDs
+	#[arg()]
+	file: PathBuf,
+
+	/// Dot separated path of the instance to create, e.g. `Workspace.Logo`
+	#[arg()]
+	path: Option<String>,
+
+	/// Class of the instance, inferred from the file extension by default
+	#[arg(short, long)]
+	class: Option<AssetClass>,
+
+	/// Session identifier
+	#[arg(short, long)]
+	session: Option<String>,
+
+	/// Server host name
+	#[arg(short = 'H', long)]
+	host: Option<String>,
+
+	/// Server port
+	#[arg(short = 'P', long)]
+	port: Option<u16>,
+}
+
+impl Asset {
+	pub fn main(self) -> Result<()> {
+		let address = sessions::get(self.session, self.host, self.port)?
+			.and_then(|session| session.get_address())
+			.context("No running session with an address was found")?;
+
+		let file = self
+			.file
+			.canonicalize()
+			.with_context(|| format!("File {} does not exist", self.file.display()))?;
+
+		let client = Client::new();
+
+		match self.mode {
+			AssetMode::Add => {
+				let body = rmp_serde::to_vec(&AddAssetRequest {
+					name: file.get_name().to_owned(),
+					contents: ByteBuf::from(fs::read(&file)?),
+					path: self.path.context("Path of the instance is required")?,
+					class: self.class,
+				})?;
+
+				let response = client
+					.post(format!("{}/asset", address))
+					.header(CONTENT_TYPE, "application/msgpack")
+					.body(body)
+					.send()?;
+
+				if !response.status().is_success() {
+					bail!("Failed to add asset: {}", response.text()?);
+				}
+
+				let asset: AddedAsset = response.json()?;
+
+				argon_info!(
+					"{}",
+					t!(
+						"asset.added",
+						class = asset.class.to_string().bold(),
+						path = asset.path.bold(),
+						key = asset.key
+					)
+				);
+			}
+			// Uploading to Roblox is not supported yet, assets have to be
+			// uploaded manually and their IDs provided in the mapping file
+			AssetMode::Upload => {
+				let ids: HashMap<String, u64> = serde_json::from_str(&fs::read_to_string(&file)?)
+					.with_context(|| format!("Failed to parse {}", file.display()))?;
+
+				let body = rmp_serde::to_vec(&AssetIdsRequest { ids })?;
+
+				let response = client
+					.post(format!("{}/assets/ids", address))
+					.header(CONTENT_TYPE, "application/msgpack")
+					.body(body)
+					.send()?;
+
+				if !response.status().is_success() {
+					bail!("Failed to apply asset IDs: {}", response.text()?);
+				}
+
+				let count: usize = response.json()?;
+
+				argon_info!("{}", t!("asset.uploaded", count = count));
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Clone, ValueEnum)]
+enum AssetMode {
+	Add,
+	Upload,
+}
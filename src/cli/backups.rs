@@ -0,0 +1,83 @@
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use colored::Colorize;
+use std::{fs, path::PathBuf};
+
+use crate::{argon_info, argon_warn, backups, ext::PathExt, logger::Table, project, sessions, t};
+
+/// Browse and restore automatic backups of the project file
+#[derive(Parser)]
+pub struct Backups {
+	/// Whether to `list` or `restore` backups
+	#[arg(hide_possible_values = true)]
+	mode: Option<BackupsMode>,
+
+	/// Project path
+	#[arg()]
+	project: Option<PathBuf>,
+
+	/// Timestamp of the backup to restore, the latest one by default
+	#[arg(short, long)]
+	to: Option<String>,
+
+	/// Restore even if the project is being served
+	#[arg(short, long)]
+	force: bool,
+}
+
+impl Backups {
+	pub fn main(self) -> Result<()> {
+		let project_path = project::resolve(self.project.clone().unwrap_or_default())?;
+
+		match self.mode.unwrap_or_default() {
+			BackupsMode::List => {
+				let backups = backups::list(&project_path)?;
+
+				if backups.is_empty() {
+					argon_warn!("{}", t!("backups.none", path = project_path.to_string().bold()));
+					return Ok(());
+				}
+
+				let mut table = Table::new();
+				table.set_header(vec!["Timestamp", "Size"]);
+
+				for backup in backups.iter().rev() {
+					let size = fs::metadata(&backup.path)
+						.map(|metadata| metadata.len())
+						.unwrap_or_default();
+					table.add_row(vec![backup.timestamp.clone(), format!("{} B", size)]);
+				}
+
+				argon_info!(
+					"{}",
+					t!("backups.list", path = project_path.to_string().bold(), table = table)
+				);
+			}
+			BackupsMode::Restore => {
+				if !self.force {
+					backups::check_sessions(&project_path, &sessions::get_all()?)?;
+				}
+
+				let backup = backups::restore(&project_path, self.to.as_deref())?;
+
+				argon_info!(
+					"{}",
+					t!(
+						"backups.restored",
+						path = project_path.to_string().bold(),
+						timestamp = backup.timestamp.bold()
+					)
+				);
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Clone, Default, ValueEnum)]
+enum BackupsMode {
+	#[default]
+	List,
+	Restore,
+}
@@ -0,0 +1,79 @@
+use anyhow::{bail, Result};
+use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+
+use crate::{
+	argon_error, argon_info,
+	bench::{self, BenchReport},
+	t,
+};
+
+/// Measure sync latency and throughput of the in-process pipeline
+#[derive(Parser)]
+pub struct Bench {
+	/// What to benchmark, only `sync` is supported
+	#[arg(hide_possible_values = true)]
+	mode: BenchMode,
+
+	/// Directory synced alongside the benchmark files, e.g. `src` of your project
+	#[arg()]
+	fixture: Option<PathBuf>,
+
+	/// How many times each operation is performed
+	#[arg(short, long, default_value_t = 20)]
+	iterations: usize,
+
+	/// Report of a previous run to compare the results with
+	#[arg(short, long)]
+	baseline: Option<PathBuf>,
+
+	/// How much any metric can regress compared to the baseline
+	#[arg(short, long, default_value = "20%")]
+	fail_threshold: String,
+}
+
+impl Bench {
+	pub fn main(self) -> Result<()> {
+		if self.iterations == 0 {
+			bail!("Number of iterations has to be greater than zero");
+		}
+
+		let threshold = bench::parse_threshold(&self.fail_threshold)?;
+		let baseline = self.baseline.as_deref().map(BenchReport::load).transpose()?;
+
+		let report = match self.mode {
+			BenchMode::Sync => bench::run(self.fixture.as_deref(), self.iterations)?,
+		};
+
+		println!("{}", serde_json::to_string_pretty(&report)?);
+
+		let Some(baseline) = baseline else {
+			return Ok(());
+		};
+
+		let regressions = bench::compare(&baseline, &report, threshold);
+
+		if regressions.is_empty() {
+			argon_info!("{}", t!("bench.no_regressions"));
+			return Ok(());
+		}
+
+		for regression in &regressions {
+			argon_error!("{}", regression);
+		}
+
+		bail!(
+			"{}",
+			t!(
+				"bench.regressed",
+				count = regressions.len(),
+				threshold = self.fail_threshold
+			)
+		)
+	}
+}
+
+#[derive(Clone, ValueEnum)]
+enum BenchMode {
+	Sync,
+}
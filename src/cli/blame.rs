@@ -0,0 +1,74 @@
+use anyhow::Result;
+use clap::Parser;
+use colored::Colorize;
+use std::path::PathBuf;
+
+use crate::{argon_info, argon_warn, ext::PathExt, history, logger::Table, project, t};
+
+/// Show recent changes made by Argon to the file or instance
+#[derive(Parser)]
+pub struct Blame {
+	/// File path or instance path (e.g. `ReplicatedStorage.Shared`)
+	#[arg()]
+	path: String,
+
+	/// Project path
+	#[arg(short, long)]
+	project: Option<PathBuf>,
+
+	/// Maximum number of changes to show
+	#[arg(short, long, default_value_t = 20)]
+	limit: usize,
+}
+
+impl Blame {
+	pub fn main(self) -> Result<()> {
+		let project_path = project::resolve(self.project.clone().unwrap_or_default())?;
+		let workspace_dir = project_path.get_parent();
+
+		let file_path = PathBuf::from(&self.path).resolve()?;
+
+		let entries = history::blame(workspace_dir, &file_path, &self.path)?;
+
+		if entries.is_empty() {
+			argon_warn!("{}", t!("blame.no_changes", path = self.path.bold()));
+			return Ok(());
+		}
+
+		let mut table = Table::new();
+		table.set_header(vec!["Time", "Operation", "Instance", "Origin", "Session"]);
+
+		for entry in entries.iter().skip(entries.len().saturating_sub(self.limit)) {
+			let time = chrono::DateTime::from_timestamp_millis(entry.timestamp)
+				.map(|time| {
+					time.with_timezone(&chrono::Local)
+						.format("%Y-%m-%d %H:%M:%S")
+						.to_string()
+				})
+				.unwrap_or_else(|| entry.timestamp.to_string());
+
+			let origin = if entry.is_external() {
+				String::from("external")
+			} else {
+				match (&entry.client_name, entry.client_id, entry.place_id) {
+					(Some(name), _, Some(place_id)) => format!("{} (place {})", name, place_id),
+					(Some(name), _, None) => name.to_owned(),
+					(None, Some(id), _) => format!("client {}", id),
+					_ => String::from("unknown"),
+				}
+			};
+
+			table.add_row(vec![
+				time,
+				format!("{:?}", entry.operation),
+				entry.instance.clone().unwrap_or_default(),
+				origin,
+				entry.session.to_string(),
+			]);
+		}
+
+		argon_info!("{}", t!("blame.changes", path = self.path.bold(), table = table));
+
+		Ok(())
+	}
+}
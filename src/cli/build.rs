@@ -1,5 +1,5 @@
 use anyhow::{bail, Result};
-use clap::Parser;
+use clap::{ArgAction, Parser};
 use colored::Colorize;
 use log::{debug, info};
 use roblox_install::RobloxStudio;
@@ -8,19 +8,19 @@ use std::{fs, path::PathBuf, process};
 use crate::{
 	argon_info,
 	config::Config,
-	core::Core,
+	core::{lifecycle, Core},
 	ext::PathExt,
 	integration,
 	program::{Program, ProgramName},
 	project::{self, Project},
-	sessions,
+	sessions, t,
 };
 
 /// Build project into Roblox binary or XML place or model
 #[derive(Parser)]
 pub struct Build {
 	/// Project path
-	#[arg()]
+	#[arg(conflicts_with = "stdin")]
 	project: Option<PathBuf>,
 
 	/// Session identifier
@@ -51,6 +51,28 @@ pub struct Build {
 	#[arg(short, long)]
 	ts: bool,
 
+	/// Whether output should be byte-identical for the same tree, enabled by default
+	#[arg(
+		long,
+		default_missing_value("true"),
+		hide_possible_values = true,
+		num_args(0..=1),
+		action = ArgAction::Set,
+	)]
+	reproducible: Option<bool>,
+
+	/// Build profile to use, overrides `ARGON_PROFILE` and the `profile` setting
+	#[arg(long)]
+	profile: Option<String>,
+
+	/// Read project from stdin instead of a file, requires `--workspace-dir`
+	#[arg(long, visible_alias = "stdin-project", conflicts_with = "watch")]
+	stdin: bool,
+
+	/// Directory `$path` entries of the project read from stdin are relative to
+	#[arg(long, requires = "stdin")]
+	workspace_dir: Option<PathBuf>,
+
 	/// Run Argon asynchronously
 	#[arg(short = 'A', long = "async")]
 	run_async: bool,
@@ -62,7 +84,11 @@ pub struct Build {
 
 impl Build {
 	pub fn main(self) -> Result<()> {
-		let project_path = project::resolve(self.project.clone().unwrap_or_default())?;
+		let project_path = if self.stdin {
+			project::stdin_path(self.workspace_dir.as_deref())?
+		} else {
+			project::resolve(self.project.clone().unwrap_or_default())?
+		};
 
 		Config::load_workspace(project_path.get_parent());
 		let config = Config::new();
@@ -77,14 +103,20 @@ impl Build {
 			None
 		};
 
-		if !project_path.exists() {
+		if !self.stdin && !project_path.exists() {
 			bail!(
 				"No project files found in {}",
 				project_path.get_parent().to_string().bold()
 			);
 		}
 
-		let project = Project::load(&project_path)?;
+		project::set_profile(self.profile.clone());
+
+		let project = if self.stdin {
+			project::load_stdin(&project_path)?
+		} else {
+			Project::load(&project_path)?
+		};
 
 		let mut xml = self.xml || config.build_xml;
 		let path = if self.plugin {
@@ -150,7 +182,7 @@ impl Build {
 		}
 
 		if use_ts {
-			argon_info!("Compiling TypeScript files..");
+			argon_info!("{}", t!("build.compiling"));
 
 			let working_dir = project_path.get_parent();
 
@@ -168,20 +200,25 @@ impl Build {
 			}
 		}
 
+		let reproducible = self.reproducible.unwrap_or(true);
 		let core = Core::new(project, self.watch)?;
+		lifecycle::print_status(&core);
 
-		core.build(&path, xml)?;
+		core.build(&path, xml, reproducible)?;
 
 		argon_info!(
-			"Successfully built project: {} to: {}",
-			project_path.to_string().bold(),
-			path.to_string().bold()
+			"{}",
+			t!(
+				"build.success",
+				project = project_path.to_string().bold(),
+				path = path.to_string().bold()
+			)
 		);
 
 		if let Some(path) = &sourcemap_path {
 			core.sourcemap(Some(path.clone()), false)?;
 
-			argon_info!("Generated sourcemap at: {}", path.to_string().bold());
+			argon_info!("{}", t!("sourcemap.generated_at", path = path.to_string().bold()));
 		}
 
 		if self.watch {
@@ -197,9 +234,17 @@ impl Build {
 					.spawn()?;
 			}
 
-			sessions::add(self.session, None, None, process::id(), config.run_async)?;
+			sessions::add(
+				self.session,
+				None,
+				None,
+				process::id(),
+				&project_path,
+				None,
+				config.run_async,
+			)?;
 
-			argon_info!("Watching for changes..");
+			argon_info!("{}", t!("common.watching"));
 
 			let queue = core.queue();
 			queue.subscribe_internal().unwrap();
@@ -208,7 +253,7 @@ impl Build {
 				let _message = queue.get(0).unwrap();
 
 				info!("Rebuilding project..");
-				core.build(&path, xml)?;
+				core.build(&path, xml, reproducible)?;
 
 				if let Some(path) = &sourcemap_path {
 					info!("Regenerating sourcemap..");
@@ -272,6 +317,16 @@ impl Build {
 			args.push(String::from("--ts"))
 		}
 
+		if let Some(reproducible) = self.reproducible {
+			args.push(String::from("--reproducible"));
+			args.push(reproducible.to_string());
+		}
+
+		if let Some(profile) = self.profile {
+			args.push(String::from("--profile"));
+			args.push(profile);
+		}
+
 		Program::new(ProgramName::Argon).args(args).spawn()?;
 
 		Ok(())
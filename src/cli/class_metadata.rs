@@ -0,0 +1,58 @@
+use anyhow::Result;
+use clap::Parser;
+use colored::Colorize;
+use std::{fs, path::PathBuf};
+
+use crate::{
+	argon_info, class_meta,
+	config::Config,
+	ext::PathExt,
+	project::{self, Project},
+	t,
+};
+
+/// Export class metadata with icons as JSON for editors to bundle
+#[derive(Parser)]
+pub struct ClassMetadata {
+	/// Project path, its `classIcons` are included if it exists
+	#[arg()]
+	project: Option<PathBuf>,
+
+	/// Output path
+	#[arg(short, long, default_value = "class-metadata.json")]
+	output: PathBuf,
+}
+
+impl ClassMetadata {
+	pub fn main(self) -> Result<()> {
+		let project_path = project::resolve(self.project.unwrap_or_default())?;
+
+		Config::load_workspace(project_path.get_parent());
+
+		let rules = if project_path.exists() {
+			Project::load(&project_path)?.class_icons
+		} else {
+			vec![]
+		};
+
+		let table = class_meta::table(&rules);
+
+		if let Some(parent) = self.output.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		fs::write(&self.output, serde_json::to_string_pretty(&table)?)?;
+
+		argon_info!(
+			"{}",
+			t!(
+				"class_meta.exported",
+				count = table.classes.len().to_string().bold(),
+				version = table.version.bold(),
+				path = self.output.resolve()?.to_string().bold()
+			)
+		);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,182 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+
+use crate::{
+	argon_info, argon_warn,
+	clean::{self, Artifact, Category},
+	core::{meta::Meta, Core},
+	daemon,
+	ext::PathExt,
+	logger::{self, Table},
+	project::{self, Project},
+	sessions, t, util,
+	vfs::Vfs,
+};
+
+/// Remove files generated by Argon from the workspace
+#[derive(Parser)]
+pub struct Clean {
+	/// Project path
+	#[arg()]
+	project: Option<PathBuf>,
+
+	/// Only list files that would be removed
+	#[arg(short, long)]
+	dry_run: bool,
+
+	/// Remove everything, including change history, orphaned data files and unused assets
+	#[arg(short = 'D', long)]
+	deep: bool,
+
+	/// Remove change history
+	#[arg(long)]
+	cache: bool,
+
+	/// Remove daemon logs and `lemonlogs.txt`
+	#[arg(long)]
+	logs: bool,
+
+	/// Remove temporary files left by interrupted writes
+	#[arg(long)]
+	trash: bool,
+
+	/// Remove instance data files that no instance uses or that only contain defaults
+	#[arg(long)]
+	orphaned_data: bool,
+
+	/// Remove stored assets that no instance references anymore
+	#[arg(long)]
+	unused_assets: bool,
+}
+
+impl Clean {
+	pub fn main(self) -> Result<()> {
+		let project_path = project::resolve(self.project.clone().unwrap_or_default())?;
+		let workspace_dir = project_path.get_parent();
+
+		let categories = self.categories();
+		let mut artifacts = clean::find(workspace_dir, &categories);
+
+		if let Some((_, session)) = sessions::get_daemon(workspace_dir)? {
+			if util::process_exists(session.pid) {
+				let log_dir = daemon::get_log_dir(workspace_dir);
+
+				if artifacts.iter().any(|artifact| artifact.path == log_dir) {
+					argon_warn!("{}", t!("clean.daemon_running", pid = session.pid.to_string().bold()));
+					artifacts.retain(|artifact| artifact.path != log_dir);
+				}
+			}
+		}
+
+		if categories.contains(&Category::OrphanedData) || categories.contains(&Category::UnusedAssets) {
+			if !project_path.exists() {
+				bail!(
+					"No project files found in {}",
+					project_path.get_parent().to_string().bold()
+				);
+			}
+
+			let project = Project::load(&project_path)?;
+			let context = Meta::from_project(&project).context;
+			let assets_dir = project.assets_dir();
+			let core = Core::new(project, false)?;
+
+			if categories.contains(&Category::OrphanedData) {
+				artifacts.extend(clean::find_orphaned_data(
+					workspace_dir,
+					&core.tree(),
+					&context,
+					&Vfs::new(false),
+				));
+			}
+
+			if categories.contains(&Category::UnusedAssets) {
+				artifacts.extend(clean::find_unused_assets(&assets_dir, &core.tree()));
+			}
+		}
+
+		if artifacts.is_empty() {
+			argon_info!("{}", t!("clean.nothing"));
+			return Ok(());
+		}
+
+		let size = artifacts.iter().map(|artifact| artifact.size).sum();
+
+		argon_info!(
+			"{}",
+			t!(
+				"clean.found",
+				count = artifacts.len().to_string().bold(),
+				size = clean::format_size(size).bold(),
+				table = Self::table(&artifacts, workspace_dir)
+			)
+		);
+
+		if self.dry_run {
+			argon_info!("{}", t!("clean.dry_run"));
+			return Ok(());
+		}
+
+		if !logger::prompt(&t!("clean.prompt"), false) {
+			return Ok(());
+		}
+
+		let freed = clean::remove(workspace_dir, &artifacts)?;
+
+		argon_info!("{}", t!("clean.removed", size = clean::format_size(freed).bold()));
+
+		Ok(())
+	}
+
+	fn categories(&self) -> Vec<Category> {
+		if self.deep {
+			return vec![
+				Category::Cache,
+				Category::Logs,
+				Category::Trash,
+				Category::OrphanedData,
+				Category::UnusedAssets,
+			];
+		}
+
+		let selected = [
+			(self.cache, Category::Cache),
+			(self.logs, Category::Logs),
+			(self.trash, Category::Trash),
+			(self.orphaned_data, Category::OrphanedData),
+			(self.unused_assets, Category::UnusedAssets),
+		];
+
+		if selected.iter().any(|(enabled, _)| *enabled) {
+			selected
+				.into_iter()
+				.filter_map(|(enabled, category)| enabled.then_some(category))
+				.collect()
+		} else {
+			// Change history, data files and assets are only removed when asked for
+			vec![Category::Logs, Category::Trash]
+		}
+	}
+
+	fn table(artifacts: &[Artifact], workspace_dir: &Path) -> Table {
+		let mut table = Table::new();
+		table.set_header(vec!["Category", "Path", "Size"]);
+
+		for artifact in artifacts {
+			table.add_row(vec![
+				artifact.category.to_string(),
+				artifact
+					.path
+					.strip_prefix(workspace_dir)
+					.unwrap_or(&artifact.path)
+					.display()
+					.to_string(),
+				clean::format_size(artifact.size),
+			]);
+		}
+
+		table
+	}
+}
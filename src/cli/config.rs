@@ -8,7 +8,7 @@ use crate::{
 	argon_info,
 	config::{Config as ArgonConfig, ConfigKind},
 	ext::PathExt,
-	logger, util,
+	logger, t, util,
 };
 
 /// Edit global or workspace config with editor or CLI
@@ -63,9 +63,12 @@ impl Config {
 
 		if self.list {
 			argon_info!(
-				"List of all available config options:\n\n{}\nVisit {} to learn more details!",
-				config.list(),
-				"https://argon.wiki/docs/configuration#global-config".bold()
+				"{}",
+				t!(
+					"config.list",
+					table = config.list(),
+					link = "https://argon.wiki/docs/configuration#global-config".bold()
+				)
 			);
 
 			return Ok(());
@@ -76,10 +79,7 @@ impl Config {
 				File::create(config_path)?;
 			}
 
-			argon_info!(
-				"Restored all settings to default values in {} config",
-				config.kind().to_string().bold()
-			);
+			argon_info!("{}", t!("config.restored", kind = config.kind().to_string().bold()));
 
 			return Ok(());
 		}
@@ -88,9 +88,12 @@ impl Config {
 			config.save(&path)?;
 
 			argon_info!(
-				"Exported {} to {} config",
-				config.kind().to_string().bold(),
-				path.to_string().bold()
+				"{}",
+				t!(
+					"config.exported",
+					kind = config.kind().to_string().bold(),
+					path = path.to_string().bold()
+				)
 			);
 
 			return Ok(());
@@ -109,10 +112,13 @@ impl Config {
 					config.save(&config_path)?;
 
 					argon_info!(
-						"Set {} setting to {} in {} config",
-						setting.bold(),
-						value.bold(),
-						config.kind().to_string().bold()
+						"{}",
+						t!(
+							"config.set",
+							setting = setting.bold(),
+							value = value.bold(),
+							kind = config.kind().to_string().bold()
+						)
 					);
 				} else {
 					bail!("Setting {} does not exist", setting.bold());
@@ -132,9 +138,12 @@ impl Config {
 					config.save(&config_path)?;
 
 					argon_info!(
-						"Set {} to its default value in {} config",
-						setting.bold(),
-						config.kind().to_string().bold()
+						"{}",
+						t!(
+							"config.reset",
+							setting = setting.bold(),
+							kind = config.kind().to_string().bold()
+						)
 					);
 				} else {
 					bail!("Setting {} does not exist", setting.bold());
@@ -143,10 +152,7 @@ impl Config {
 			_ => {
 				if !config_path.exists() {
 					let create_config = logger::prompt(
-						&format!(
-							"{} config does not exist. Would you like to create one?",
-							config.kind().to_string().bold()
-						),
+						&t!("config.create_prompt", kind = config.kind().to_string().bold()),
 						true,
 					);
 
@@ -157,7 +163,7 @@ impl Config {
 					}
 				}
 
-				argon_info!("Opened config file. Manually go to: {}", config_path.to_string().bold());
+				argon_info!("{}", t!("config.opened", path = config_path.to_string().bold()));
 
 				open::that(config_path)?;
 			}
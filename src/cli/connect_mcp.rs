@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use clap::Parser;
 use log::info;
 use rmcp::{
@@ -17,9 +17,15 @@ use rmcp::{
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json::json;
-use std::{env, sync::Arc};
+use std::{env, future::Future, path::PathBuf, sync::Arc};
 use tokio::sync::Mutex;
 
+use crate::{
+	constants::{MAX_SUGGEST_PATH_LIMIT, SUGGEST_PATH_LIMIT},
+	mcp::{self, Guard, Outcome, ProjectEdit},
+	project,
+};
+
 /// Connect to an MCP endpoint (used internally by Cursor)
 #[derive(Parser)]
 pub struct ConnectMcp {
@@ -35,23 +41,73 @@ struct PineconeQueryArgs {
 	query: String,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct ValidateProjectEditArgs {
+	#[schemars(
+		description = "Path of the project file or its directory, relative to the workspace. Defaults to default.project.json in the workspace."
+	)]
+	project: Option<String>,
+	#[schemars(
+		description = "Complete proposed contents of the project file as JSON text. Either this or merge_patch has to be set."
+	)]
+	contents: Option<String>,
+	#[schemars(
+		description = "JSON merge patch (RFC 7386) applied to the current project file, null values remove keys. Either this or contents has to be set."
+	)]
+	merge_patch: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct SuggestPathArgs {
+	#[schemars(
+		description = "Path of the project file or its directory, relative to the workspace. Defaults to default.project.json in the workspace."
+	)]
+	project: Option<String>,
+	#[schemars(
+		description = "Instance path separated with /, without the root, e.g. ReplicatedStorage/Shared/Util. Returns files backing it or the closest existing directory it would be created in."
+	)]
+	instance_path: Option<String>,
+	#[schemars(
+		description = "Roblox class name, e.g. ModuleScript. Returns directories that already contain instances of this class."
+	)]
+	class: Option<String>,
+	#[schemars(
+		description = "Maximum number of candidates, 10 by default.",
+		range(min = 1, max = 100)
+	)]
+	limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct ProjectSkeletonArgs {
+	#[schemars(
+		description = "Path of the project file or its directory, relative to the workspace. Defaults to default.project.json in the workspace."
+	)]
+	project: Option<String>,
+}
+
 // Main server struct (Removed host and port)
 #[derive(Clone)]
 struct ArgonMcpServer {
 	peer: Arc<Mutex<Option<Peer<RoleServer>>>>,
+	guard: Arc<Guard>,
 	// host: String, // Removed
 	// port: u16,    // Removed
 }
 
 impl ArgonMcpServer {
 	// Updated new function (Removed host and port parameters)
-	fn new() -> Self {
+	fn new(guard: Guard) -> Self {
 		// The peer field is initialized as None here, but it will be properly set during
 		// the server.serve(stdio()) call via the ServiceExt::serve method from the rmcp crate.
 		// The serve method sets up the transport, performs initialization handshake, and
 		// populates the peer field with the connected Peer object that handles communication.
 		Self {
 			peer: Arc::new(Mutex::new(None)),
+			guard: Arc::new(guard),
 			// host, // Removed
 			// port, // Removed
 		}
@@ -94,6 +150,46 @@ impl ArgonMcpServer {
 		});
 	}
 
+	// Run the tool only if its permission allows it, see `mcp::Guard`
+	async fn guarded_pinecone_call(
+		&self,
+		tool: &str,
+		assistant_name: &str,
+		query: &str,
+		top_k: u32,
+	) -> Result<CallToolResult, McpError> {
+		self.guarded_call(tool, || async {
+			self.call_pinecone_assistant(assistant_name, query, top_k)
+				.await
+				.map_err(|err| anyhow!("{}", err.message))
+		})
+		.await
+	}
+
+	async fn guarded_call<F, Fut>(&self, tool: &str, call: F) -> Result<CallToolResult, McpError>
+	where
+		F: FnOnce() -> Fut,
+		Fut: Future<Output = Result<String>>,
+	{
+		match self.guard.call(tool, call).await {
+			Outcome::Ran(Ok(response_body)) => Ok(CallToolResult {
+				content: vec![Content::text(response_body)],
+				is_error: Some(false),
+			}),
+			Outcome::Ran(Err(err)) => Err(McpError::internal_error(err.to_string(), None)),
+			Outcome::Refused(decision) => {
+				let refusal = decision.to_json(tool);
+
+				self.send_log(LoggingLevel::Warning, refusal["message"].as_str().unwrap_or_default());
+
+				Ok(CallToolResult {
+					content: vec![Content::text(refusal.to_string())],
+					is_error: Some(true),
+				})
+			}
+		}
+	}
+
 	// --- Internal Helper for Pinecone API Call ---
 	async fn call_pinecone_assistant(&self, assistant_name: &str, query: &str, top_k: u32) -> Result<String, McpError> {
 		// Hardcode the access credential directly
@@ -150,11 +246,8 @@ impl ArgonMcpServer {
 			LoggingLevel::Info,
 			&format!("Calling roblox-developer-forum with query: {}", args.query),
 		);
-		let response_body = self.call_pinecone_assistant("roblox-assistant", &args.query, 5).await?;
-		Ok(CallToolResult {
-			content: vec![Content::text(response_body)],
-			is_error: Some(false),
-		})
+		self.guarded_pinecone_call("roblox_developer_forum", "roblox-assistant", &args.query, 5)
+			.await
 	}
 
 	#[tool(description = "Query Luau documentation via Pinecone.")]
@@ -163,13 +256,8 @@ impl ArgonMcpServer {
 			LoggingLevel::Info,
 			&format!("Calling luau-documentation with query: {}", args.query),
 		);
-		let response_body = self
-			.call_pinecone_assistant("roblox-luau-assistant", &args.query, 5)
-			.await?;
-		Ok(CallToolResult {
-			content: vec![Content::text(response_body)],
-			is_error: Some(false),
-		})
+		self.guarded_pinecone_call("luau_documentation", "roblox-luau-assistant", &args.query, 5)
+			.await
 	}
 
 	#[tool(description = "Query Roblox engine documentation via Pinecone.")]
@@ -182,13 +270,78 @@ impl ArgonMcpServer {
 			&format!("Calling roblox-engine-documentation with query: {}", args.query),
 		);
 		// Note: top_k = 1 for this assistant
-		let response_body = self
-			.call_pinecone_assistant("roblox-engine-reference-assistant", &args.query, 1)
-			.await?;
-		Ok(CallToolResult {
-			content: vec![Content::text(response_body)],
-			is_error: Some(false),
+		self.guarded_pinecone_call(
+			"roblox_engine_documentation",
+			"roblox-engine-reference-assistant",
+			&args.query,
+			1,
+		)
+		.await
+	}
+
+	#[tool(
+		description = "Validate a proposed edit of an Argon *.project.json file without saving it, using the same checks as `argon validate`. Returns a JSON array of problems with severity, node and message, empty if the edit is valid."
+	)]
+	async fn validate_project_edit(
+		&self,
+		#[tool(aggr)] args: ValidateProjectEditArgs,
+	) -> Result<CallToolResult, McpError> {
+		self.guarded_call("validate_project_edit", || async {
+			let edit = match (args.contents, args.merge_patch) {
+				(Some(contents), None) => ProjectEdit::Contents(contents),
+				(None, Some(patch)) => ProjectEdit::MergePatch(patch),
+				_ => bail!("Exactly one of contents and merge_patch has to be set"),
+			};
+
+			let project_path = project::resolve(PathBuf::from(args.project.unwrap_or_default()))?;
+			let problems = mcp::validate_edit(&project_path, &edit)?;
+
+			Ok(serde_json::to_string_pretty(&problems)?)
+		})
+		.await
+	}
+
+	#[tool(
+		description = "Suggest existing files and directories of an Argon project for an instance path or a class, following the project's file naming conventions. Uses the running session when there is one. Read-only."
+	)]
+	async fn suggest_path(&self, #[tool(aggr)] args: SuggestPathArgs) -> Result<CallToolResult, McpError> {
+		self.guarded_call("suggest_path", || async {
+			if args.instance_path.is_none() && args.class.is_none() {
+				bail!("At least one of instance_path and class has to be set");
+			}
+
+			let project = mcp::load_project(args.project.as_deref())?;
+			let limit = args
+				.limit
+				.unwrap_or(SUGGEST_PATH_LIMIT)
+				.clamp(1, MAX_SUGGEST_PATH_LIMIT);
+
+			let instance = args.instance_path.as_deref();
+			let class = args.class.as_deref();
+
+			let (source, candidates) = match mcp::suggest_from_session(&project, instance, class, limit).await {
+				Some(candidates) => ("session", candidates),
+				None => ("project", mcp::suggest_paths(&project, instance, class, limit)),
+			};
+
+			Ok(serde_json::to_string_pretty(&json!({
+				"source": source,
+				"candidates": candidates,
+			}))?)
+		})
+		.await
+	}
+
+	#[tool(
+		description = "Return the node tree of an Argon project with each node's class, resolved absolute $path and the middleware it is read with. Read-only."
+	)]
+	async fn project_skeleton(&self, #[tool(aggr)] args: ProjectSkeletonArgs) -> Result<CallToolResult, McpError> {
+		self.guarded_call("project_skeleton", || async {
+			let project = mcp::load_project(args.project.as_deref())?;
+
+			Ok(serde_json::to_string_pretty(&mcp::project_skeleton(&project))?)
 		})
+		.await
 	}
 }
 
@@ -285,7 +438,7 @@ impl ConnectMcp {
 		eprintln!("Starting Argon MCP Server (stdio mode)...");
 
 		// Create server instance (Removed host and port arguments)
-		let server = ArgonMcpServer::new();
+		let server = ArgonMcpServer::new(Guard::load()?);
 
 		// Start MCP server using stdio transport
 		// The ServiceExt::serve method creates a Peer instance from the transport
@@ -2,7 +2,7 @@ use anyhow::Result;
 use clap::Parser;
 use colored::Colorize;
 
-use crate::argon_info;
+use crate::{argon_info, t};
 
 const LINK: &str = "https://argon.wiki";
 
@@ -12,7 +12,7 @@ pub struct Doc {}
 
 impl Doc {
 	pub fn main(self) -> Result<()> {
-		argon_info!("Launched browser. Manually go to: {}", LINK.bold());
+		argon_info!("{}", t!("doc.launched", link = LINK.bold()));
 
 		open::that(LINK)?;
 
@@ -0,0 +1,184 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::Colorize;
+use reqwest::blocking::Client;
+use std::{env, path::PathBuf, time::Duration};
+
+use crate::{
+	argon_info, argon_warn,
+	config::Config,
+	constants::PROTOCOL_VERSION,
+	core::queue,
+	crash_handler::{self, CrashReport, Privacy},
+	ext::PathExt,
+	logger::Table,
+	project::{self, Project},
+	sessions, t, util,
+};
+
+/// Check the project for common problems
+#[derive(Parser)]
+pub struct Doctor {
+	/// Project path
+	#[arg()]
+	project: Option<PathBuf>,
+
+	/// Send a test crash report to the configured `crash_report_url`
+	#[arg(long)]
+	crash_report: bool,
+}
+
+impl Doctor {
+	pub fn main(self) -> Result<()> {
+		if self.crash_report {
+			return send_test_report();
+		}
+
+		let project_path = project::resolve(self.project.unwrap_or_default())?;
+
+		if !project_path.exists() {
+			bail!(
+				"No project files found in {}. Run {} to create new one",
+				project_path.get_parent().to_string().bold(),
+				"argon init".bold(),
+			);
+		}
+
+		let project = Project::load(&project_path)?;
+		let mut problems = 0;
+
+		let sessions: Vec<_> = sessions::get_all()?
+			.into_values()
+			.filter(|session| util::process_exists(session.pid))
+			.collect();
+
+		if let Some(layout) = project.ts_layout() {
+			let is_serving = !sessions.is_empty();
+
+			if !layout.out_dir.exists() {
+				argon_warn!(
+					"{}",
+					t!(
+						"doctor.ts_output_missing",
+						path = layout.out_dir.to_string().bold(),
+						command = "npx rbxtsc".bold()
+					)
+				);
+
+				problems += 1;
+			} else if is_serving && layout.is_stale() {
+				argon_warn!(
+					"{}",
+					t!(
+						"doctor.rbxtsc_not_running",
+						path = layout.root_dir.to_string().bold(),
+						command = "npx rbxtsc --watch".bold(),
+						alternative = "argon serve --ts".bold()
+					)
+				);
+
+				problems += 1;
+			}
+		}
+
+		for address in sessions.iter().filter_map(|session| session.get_address()) {
+			let Some(clients) = get_clients(&address) else {
+				continue;
+			};
+
+			if clients.is_empty() {
+				continue;
+			}
+
+			let mut table = Table::new();
+			table.set_header(vec!["ID", "Name", "Protocol", "Capabilities"]);
+
+			for client in &clients {
+				let capabilities = client
+					.negotiated
+					.capabilities
+					.iter()
+					.map(|capability| format!("{:?}", capability))
+					.collect::<Vec<_>>();
+
+				table.add_row(vec![
+					client.id.to_string(),
+					client.name.clone(),
+					client.negotiated.protocol.to_string(),
+					if capabilities.is_empty() {
+						String::from("None")
+					} else {
+						capabilities.join(", ")
+					},
+				]);
+			}
+
+			argon_info!("{}", t!("doctor.clients", address = address.bold(), table = table));
+
+			for client in clients
+				.iter()
+				.filter(|client| client.negotiated.protocol < PROTOCOL_VERSION)
+			{
+				argon_warn!(
+					"{}",
+					t!(
+						"doctor.outdated_client",
+						name = client.name.bold(),
+						protocol = client.negotiated.protocol.to_string().bold(),
+						current = PROTOCOL_VERSION.to_string().bold()
+					)
+				);
+
+				problems += 1;
+			}
+		}
+
+		if problems == 0 {
+			argon_info!("{}", t!("doctor.ok", project = project.name.bold()));
+		}
+
+		Ok(())
+	}
+}
+
+fn get_clients(address: &str) -> Option<Vec<queue::Client>> {
+	let response = Client::new()
+		.get(format!("{}/clients", address))
+		.timeout(Duration::from_secs(3))
+		.send()
+		.and_then(|response| response.error_for_status())
+		.ok()?;
+
+	rmp_serde::from_slice(&response.bytes().ok()?).ok()
+}
+
+fn send_test_report() -> Result<()> {
+	let (url, privacy) = {
+		let config = Config::new();
+		(
+			config.crash_report_url.clone(),
+			Privacy::from_setting(&config.crash_report_privacy),
+		)
+	};
+
+	if url.is_empty() {
+		bail!(
+			"No crash report endpoint configured, set {} setting first",
+			"crash_report_url".bold()
+		);
+	}
+
+	let report = CrashReport::new(
+		"Test crash report sent by argon doctor",
+		None,
+		None,
+		&env::current_dir()?,
+		privacy,
+	);
+
+	crash_handler::send(&url, &report.to_json())?;
+
+	argon_info!("{}", t!("doctor.crash_report_sent", url = url.bold()));
+
+	Ok(())
+}
@@ -0,0 +1,125 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use reqwest::blocking::Client;
+
+use crate::{
+	argon_info, argon_warn,
+	constants::MAX_FIND_PAGE_LIMIT,
+	core::find::{FindPage, FindRequest, FoundInstance},
+	logger::Table,
+	sessions, t,
+};
+
+/// Find instances in the live tree of the running session (e.g. `ClassName~BasePart path~Map/*`)
+#[derive(Parser)]
+pub struct Find {
+	/// Search query, terms: `Name=` or `Name~regex`, `ClassName=` or `ClassName~superclass`,
+	/// `Path=` or `Path~glob`, `Id=` and `Property=value`
+	#[arg()]
+	query: String,
+
+	/// Session identifier
+	#[arg()]
+	session: Option<String>,
+
+	/// Server host name
+	#[arg(short = 'H', long)]
+	host: Option<String>,
+
+	/// Server port
+	#[arg(short = 'P', long)]
+	port: Option<u16>,
+
+	/// Maximum number of instances to show
+	#[arg(short, long)]
+	limit: Option<usize>,
+
+	/// Output results as JSON
+	#[arg(short, long)]
+	json: bool,
+}
+
+impl Find {
+	pub fn main(self) -> Result<()> {
+		let request = FindRequest::parse(&self.query)?;
+
+		let address = sessions::get(self.session, self.host, self.port)?
+			.and_then(|session| session.get_address())
+			.context("No running session with an address was found")?;
+
+		let limit = self.limit.unwrap_or(usize::MAX);
+		let instances = Self::fetch(&address, request, limit)?;
+
+		if self.json {
+			println!("{}", serde_json::to_string_pretty(&instances)?);
+			return Ok(());
+		}
+
+		if instances.is_empty() {
+			argon_warn!("{}", t!("find.no_match", query = self.query.bold()));
+			return Ok(());
+		}
+
+		let mut table = Table::new();
+		table.set_header(vec!["Path", "Class", "ID", "Files"]);
+
+		for instance in &instances {
+			table.add_row(vec![
+				instance.path.clone(),
+				instance.class.to_string(),
+				instance.stable_id.clone().unwrap_or_default(),
+				instance
+					.files
+					.iter()
+					.map(|path| path.display().to_string())
+					.collect::<Vec<String>>()
+					.join("\n"),
+			]);
+		}
+
+		argon_info!("{}", t!("find.found", count = instances.len(), table = table));
+
+		Ok(())
+	}
+
+	/// Follows cursors until all results are fetched, starts over if the tree changed
+	fn fetch(address: &str, mut request: FindRequest, limit: usize) -> Result<Vec<FoundInstance>> {
+		let client = Client::new();
+		let mut instances = vec![];
+		let mut restarts = 0;
+
+		loop {
+			request.limit = Some((limit - instances.len()).min(MAX_FIND_PAGE_LIMIT));
+
+			let response = client.get(format!("{}/find", address)).query(&request).send()?;
+
+			if !response.status().is_success() {
+				bail!("Search failed: {}", response.text()?);
+			}
+
+			let page: FindPage = response.json()?;
+
+			if page.restart {
+				if restarts == 3 {
+					bail!("Search failed: tree keeps changing");
+				}
+
+				restarts += 1;
+				instances.clear();
+				request.cursor = None;
+
+				continue;
+			}
+
+			instances.extend(page.instances);
+
+			match page.cursor {
+				Some(cursor) if instances.len() < limit => request.cursor = Some(cursor),
+				_ => break,
+			}
+		}
+
+		Ok(instances)
+	}
+}
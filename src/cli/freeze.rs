@@ -0,0 +1,99 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use reqwest::{blocking::Client, header::CONTENT_TYPE};
+
+use crate::{argon_info, argon_warn, server::frozen::FreezeRequest, sessions, t};
+
+/// Stop syncing the instance in both directions until it is synced manually
+#[derive(Parser)]
+pub struct Freeze {
+	/// Instance path (e.g. `Workspace.Terrain`)
+	#[arg()]
+	path: String,
+
+	/// Session identifier
+	#[arg()]
+	session: Option<String>,
+
+	/// Server host name
+	#[arg(short = 'H', long)]
+	host: Option<String>,
+
+	/// Server port
+	#[arg(short = 'P', long)]
+	port: Option<u16>,
+}
+
+impl Freeze {
+	pub fn main(self) -> Result<()> {
+		if request(&self.path, true, self.session, self.host, self.port)? {
+			argon_info!("{}", t!("freeze.frozen", path = self.path.bold()));
+		} else {
+			argon_warn!("{}", t!("freeze.already_frozen", path = self.path.bold()));
+		}
+
+		Ok(())
+	}
+}
+
+/// Resume syncing the frozen instance
+#[derive(Parser)]
+pub struct Unfreeze {
+	/// Instance path (e.g. `Workspace.Terrain`)
+	#[arg()]
+	path: String,
+
+	/// Session identifier
+	#[arg()]
+	session: Option<String>,
+
+	/// Server host name
+	#[arg(short = 'H', long)]
+	host: Option<String>,
+
+	/// Server port
+	#[arg(short = 'P', long)]
+	port: Option<u16>,
+}
+
+impl Unfreeze {
+	pub fn main(self) -> Result<()> {
+		if request(&self.path, false, self.session, self.host, self.port)? {
+			argon_info!("{}", t!("freeze.unfrozen", path = self.path.bold()));
+		} else {
+			argon_warn!("{}", t!("freeze.not_frozen", path = self.path.bold()));
+		}
+
+		Ok(())
+	}
+}
+
+/// Returns whether the frozen state of the instance changed
+fn request(path: &str, frozen: bool, session: Option<String>, host: Option<String>, port: Option<u16>) -> Result<bool> {
+	let address = sessions::get(session, host, port)?
+		.and_then(|session| session.get_address())
+		.context("No running session with an address was found")?;
+
+	let body = rmp_serde::to_vec(&FreezeRequest {
+		path: path.to_owned(),
+		frozen,
+	})?;
+
+	let response = Client::new()
+		.post(format!("{}/freeze", address))
+		.header(CONTENT_TYPE, "application/msgpack")
+		.body(body)
+		.send()?;
+
+	if !response.status().is_success() {
+		bail!(
+			"Failed to {} {}: {}",
+			if frozen { "freeze" } else { "unfreeze" },
+			path,
+			response.text()?
+		);
+	}
+
+	Ok(response.json()?)
+}
@@ -0,0 +1,67 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::Colorize;
+use std::path::PathBuf;
+
+use crate::{
+	argon_info,
+	core::Core,
+	ext::PathExt,
+	logger::Table,
+	project::{self, Project},
+	t,
+};
+
+/// Check whether instance sources match the files on disk
+#[derive(Parser)]
+pub struct Fsck {
+	/// Project path
+	#[arg()]
+	project: Option<PathBuf>,
+}
+
+impl Fsck {
+	pub fn main(self) -> Result<()> {
+		let project_path = project::resolve(self.project.unwrap_or_default())?;
+
+		if !project_path.exists() {
+			bail!(
+				"No project files found in {}. Run {} to create new one",
+				project_path.get_parent().to_string().bold(),
+				"argon init".bold(),
+			);
+		}
+
+		let core = Core::new(Project::load(&project_path)?, false)?;
+		let issues = core.fsck();
+
+		if issues.is_empty() {
+			argon_info!("{}", t!("fsck.valid"));
+			return Ok(());
+		}
+
+		let tree = core.tree();
+
+		let mut table = Table::new();
+		table.set_header(vec!["Instance", "Issue", "Entries"]);
+
+		for (id, issue) in &issues {
+			table.add_row(vec![
+				tree.get_full_name(*id).unwrap_or_else(|| id.to_string()),
+				issue.to_string(),
+				tree.get_meta(*id)
+					.map(|meta| {
+						meta.source
+							.paths()
+							.iter()
+							.map(|path| path.display().to_string())
+							.collect::<Vec<String>>()
+							.join("\n")
+					})
+					.unwrap_or_default(),
+			]);
+		}
+
+		bail!("Found {} invalid instance sources:\n\n{}", issues.len(), table)
+	}
+}
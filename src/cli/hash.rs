@@ -0,0 +1,129 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+use crate::{
+	argon_info, argon_warn,
+	core::{
+		tree_hash::{self, TreeHash, TREE_HASH_VERSION},
+		Core,
+	},
+	ext::PathExt,
+	project::{self, Project},
+	t,
+};
+
+/// Print canonical hash of the project tree or verify a built file against it
+#[derive(Parser)]
+pub struct Hash {
+	/// Project path
+	#[arg(conflicts_with = "stdin")]
+	project: Option<PathBuf>,
+
+	/// Built place or model file to compare with the project
+	#[arg(short, long)]
+	verify: Option<PathBuf>,
+
+	/// Read project from stdin instead of a file, requires `--workspace-dir`
+	#[arg(long, visible_alias = "stdin-project")]
+	stdin: bool,
+
+	/// Directory `$path` entries of the project read from stdin are relative to
+	#[arg(long, requires = "stdin")]
+	workspace_dir: Option<PathBuf>,
+}
+
+impl Hash {
+	pub fn main(self) -> Result<()> {
+		let project_path = if self.stdin {
+			project::stdin_path(self.workspace_dir.as_deref())?
+		} else {
+			project::resolve(self.project.unwrap_or_default())?
+		};
+
+		if !self.stdin && !project_path.exists() {
+			bail!(
+				"No project files found in {}. Run {} to create new one",
+				project_path.get_parent().to_string().bold(),
+				"argon init".bold(),
+			);
+		}
+
+		let project = if self.stdin {
+			project::load_stdin(&project_path)?
+		} else {
+			Project::load(&project_path)?
+		};
+
+		let core = Core::new(project, false)?;
+		let hash = core.tree_hash();
+
+		let Some(path) = self.verify else {
+			println!("{}", hash);
+			return Ok(());
+		};
+
+		let reader =
+			BufReader::new(File::open(&path).with_context(|| format!("Failed to open {}", path.to_string().bold()))?);
+
+		let dom = match path.get_ext() {
+			"rbxlx" | "rbxmx" => rbx_xml::from_reader_default(reader)?,
+			"rbxl" | "rbxm" => rbx_binary::from_reader(reader)?,
+			ext => bail!(
+				"Invalid file extension: {}. Only {}, {}, {}, {} extensions are allowed",
+				ext.bold(),
+				"rbxl".bold(),
+				"rbxlx".bold(),
+				"rbxm".bold(),
+				"rbxmx".bold(),
+			),
+		};
+
+		let top_level = dom.root().children().to_vec();
+
+		let Some(embedded) = tree_hash::find_embedded(&dom, &top_level) else {
+			bail!(
+				"File {} has no tree hash, rebuild it with {}",
+				path.to_string().bold(),
+				"argon build".bold()
+			);
+		};
+
+		let Some(embedded_hash) = TreeHash::from_embedded(&embedded) else {
+			bail!(
+				"File {} has tree hash of version {} but this Argon uses version {}, rebuild it with {}",
+				path.to_string().bold(),
+				tree_hash::embedded_version(&embedded)
+					.map_or(String::from("unknown"), |version| version.to_string())
+					.bold(),
+				TREE_HASH_VERSION.to_string().bold(),
+				"argon build".bold()
+			);
+		};
+
+		if tree_hash::hash_dom(&dom, &top_level) != embedded_hash {
+			argon_warn!("{}", t!("hash.modified", path = path.to_string().bold()));
+		}
+
+		if embedded_hash != hash {
+			bail!(
+				"File {} is out of sync with the project: {} (file) != {} (project)",
+				path.to_string().bold(),
+				embedded_hash.to_string().bold(),
+				hash.to_string().bold()
+			);
+		}
+
+		argon_info!(
+			"{}",
+			t!(
+				"hash.in_sync",
+				path = path.to_string().bold(),
+				hash = hash.to_string().bold()
+			)
+		);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use clap::{ArgAction, Parser};
+use colored::Colorize;
+use std::{env, path::PathBuf};
+
+use crate::{
+	argon_info, argon_warn,
+	config::Config,
+	ext::PathExt,
+	import::{self, ImportOptions},
+	logger, t,
+};
+
+/// Number of unrepresented instances listed before the rest is summarized
+const MAX_LISTED: usize = 10;
+
+/// Create Argon workspace from an existing place file
+#[derive(Parser)]
+pub struct Import {
+	/// Place file to import (.rbxl, .rbxlx)
+	#[arg()]
+	place: PathBuf,
+
+	/// Workspace directory, current one by default
+	#[arg(short, long)]
+	output: Option<PathBuf>,
+
+	/// Name of the generated project, place file name by default
+	#[arg(short = 'n', long)]
+	project_name: Option<String>,
+
+	/// Services to import, all non-empty ones by default
+	#[arg(short, long, value_delimiter = ',')]
+	services: Option<Vec<String>>,
+
+	/// Write files the way Rojo does
+	#[arg(
+		short,
+        long,
+        default_missing_value("true"),
+		hide_possible_values = true,
+        num_args(0..=1),
+    	action = ArgAction::Set,
+    )]
+	rojo: Option<bool>,
+}
+
+impl Import {
+	pub fn main(self) -> Result<()> {
+		let output = match self.output {
+			Some(output) => output.resolve()?,
+			None => env::current_dir()?,
+		};
+
+		let dom = import::read_place(&self.place)?;
+
+		Config::load_workspace(&output);
+
+		let rojo_mode = self
+			.rojo
+			.unwrap_or_else(|| logger::prompt(&t!("import.rojo_prompt"), Config::new().rojo_mode));
+
+		Config::new_mut().rojo_mode = rojo_mode;
+
+		let services = self.services.unwrap_or_else(|| {
+			import::get_services(&dom)
+				.into_iter()
+				.filter(|service| logger::prompt(&t!("import.service_prompt", service = service.bold()), true))
+				.collect()
+		});
+
+		let options = ImportOptions {
+			project_name: self.project_name.unwrap_or_else(|| self.place.get_stem().to_owned()),
+			services: Some(services),
+		};
+
+		let report = import::import(&dom, &output, &options)?;
+
+		argon_info!(
+			"{}",
+			t!(
+				"import.success",
+				path = report.project_path.to_string().bold(),
+				added = report.added,
+				updated = report.updated
+			)
+		);
+
+		if !report.unrepresented.is_empty() {
+			argon_warn!("{}", t!("import.unrepresented", count = report.unrepresented.len()));
+
+			for (path, class) in report.unrepresented.iter().take(MAX_LISTED) {
+				println!("  {} ({})", path.bold(), class);
+			}
+
+			if report.unrepresented.len() > MAX_LISTED {
+				println!(
+					"  {}",
+					t!("import.more", count = report.unrepresented.len() - MAX_LISTED)
+				);
+			}
+		}
+
+		if !report.lossy_properties.is_empty() {
+			argon_warn!("{}", t!("import.lossy", count = report.lossy_properties.len()));
+
+			for (property, count) in &report.lossy_properties {
+				println!("  {} ({})", property.bold(), count);
+			}
+		}
+
+		Ok(())
+	}
+}
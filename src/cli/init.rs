@@ -7,7 +7,7 @@ use crate::{
 	argon_error, argon_info,
 	config::Config,
 	ext::PathExt,
-	logger, project, stats,
+	logger, project, stats, t,
 	workspace::{self, WorkspaceConfig},
 };
 
@@ -114,19 +114,16 @@ impl Init {
 			if let Some(path) = workspace::init_ts(workspace_config)? {
 				let path = path.resolve()?.join("default.project.json");
 
-				argon_info!(
-					"Successfully initialized roblox-ts project: {}",
-					path.to_string().bold()
-				);
+				argon_info!("{}", t!("init.ts_success", path = path.to_string().bold()));
 			}
 
 			return Ok(());
 		}
 
 		if project_path.exists() {
-			argon_error!("Project {} already exists!", project_path.to_string().bold());
+			argon_error!("{}", t!("init.exists", path = project_path.to_string().bold()));
 
-			if !logger::prompt("Would you like to continue and add potentially missing files?", false) {
+			if !logger::prompt(&t!("init.continue_prompt"), false) {
 				return Ok(());
 			}
 		}
@@ -134,7 +131,7 @@ impl Init {
 		workspace_config.project = &project_path;
 		workspace::init(workspace_config)?;
 
-		argon_info!("Successfully initialized project: {}", project_path.to_string().bold());
+		argon_info!("{}", t!("init.success", path = project_path.to_string().bold()));
 
 		stats::projects_created(1);
 
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use clap::Parser;
+use colored::Colorize;
+use std::{
+	fs,
+	io::{self, Write},
+	path::PathBuf,
+	thread,
+	time::Duration,
+};
+
+use crate::{argon_warn, daemon, ext::PathExt, project, t};
+
+/// Show logs of the workspace daemon
+#[derive(Parser)]
+pub struct Logs {
+	/// Project path
+	#[arg()]
+	project: Option<PathBuf>,
+
+	/// Keep printing new log lines as they are written
+	#[arg(short, long)]
+	follow: bool,
+
+	/// Number of last lines to show
+	#[arg(short = 'n', long, default_value_t = 100)]
+	lines: usize,
+}
+
+impl Logs {
+	pub fn main(self) -> Result<()> {
+		let project_path = project::resolve(self.project.unwrap_or_default())?;
+		let log_path = daemon::get_log_path(project_path.get_parent());
+
+		if !log_path.exists() {
+			argon_warn!("{}", t!("logs.none", path = log_path.to_string().bold()));
+			return Ok(());
+		}
+
+		let contents = fs::read_to_string(&log_path)?;
+
+		for line in daemon::last_lines(&contents, self.lines) {
+			println!("{}", line);
+		}
+
+		if !self.follow {
+			return Ok(());
+		}
+
+		let mut offset = contents.len() as u64;
+
+		loop {
+			thread::sleep(Duration::from_millis(250));
+
+			let (appended, new_offset) = daemon::read_from(&log_path, offset)?;
+
+			print!("{}", appended);
+			io::stdout().flush()?;
+
+			offset = new_offset;
+		}
+	}
+}
@@ -0,0 +1,119 @@
+use anyhow::{bail, Context, Result};
+use chrono::{Local, TimeZone};
+use clap::{Parser, ValueEnum};
+use colored::Colorize;
+use reqwest::{blocking::Client, header::CONTENT_TYPE};
+
+use crate::{
+	argon_info, argon_warn, logger::Table, server::markers::MarkerRequest, sessions, t, vfs::undo_log::Marker,
+};
+
+/// Manage markers the workspace can be rolled back to with `argon undo` (requires running session)
+#[derive(Parser)]
+pub struct Markers {
+	/// Whether to `list`, `add` or `clear` markers
+	#[arg(hide_possible_values = true)]
+	mode: Option<MarkersMode>,
+
+	/// Name of the marker to add
+	#[arg()]
+	name: Option<String>,
+
+	/// Session identifier
+	#[arg(short, long)]
+	session: Option<String>,
+
+	/// Server host name
+	#[arg(short = 'H', long)]
+	host: Option<String>,
+
+	/// Server port
+	#[arg(short = 'P', long)]
+	port: Option<u16>,
+
+	/// Output results as JSON
+	#[arg(short, long)]
+	json: bool,
+}
+
+impl Markers {
+	pub fn main(self) -> Result<()> {
+		let address = sessions::get(self.session, self.host, self.port)?
+			.and_then(|session| session.get_address())
+			.context("No running session with an address was found")?;
+
+		let client = Client::new();
+
+		match self.mode.unwrap_or_default() {
+			MarkersMode::List => {
+				let response = client.get(format!("{}/markers", address)).send()?;
+
+				if !response.status().is_success() {
+					bail!("Failed to get markers: {}", response.text()?);
+				}
+
+				let markers: Vec<Marker> = response.json()?;
+
+				if self.json {
+					println!("{}", serde_json::to_string_pretty(&markers)?);
+					return Ok(());
+				}
+
+				if markers.is_empty() {
+					argon_warn!("{}", t!("markers.none"));
+					return Ok(());
+				}
+
+				let mut table = Table::new();
+				table.set_header(vec!["Name", "Created", "Operations since"]);
+
+				for marker in &markers {
+					let created = Local
+						.timestamp_millis_opt(marker.timestamp)
+						.single()
+						.map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+						.unwrap_or_default();
+
+					table.add_row(vec![marker.name.clone(), created, marker.operations.to_string()]);
+				}
+
+				argon_info!("{}", t!("markers.list", table = table));
+			}
+			MarkersMode::Add => {
+				let name = self.name.context("Name of the marker is required")?;
+				let body = rmp_serde::to_vec(&MarkerRequest { name: name.clone() })?;
+
+				let response = client
+					.post(format!("{}/marker", address))
+					.header(CONTENT_TYPE, "application/msgpack")
+					.body(body)
+					.send()?;
+
+				if !response.status().is_success() {
+					bail!("Failed to add marker: {}", response.text()?);
+				}
+
+				argon_info!("{}", t!("markers.added", name = name.bold()));
+			}
+			MarkersMode::Clear => {
+				let response = client.post(format!("{}/markers/clear", address)).send()?;
+
+				if !response.status().is_success() {
+					bail!("Failed to clear markers: {}", response.text()?);
+				}
+
+				argon_info!("{}", t!("markers.cleared"));
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Clone, Default, ValueEnum)]
+enum MarkersMode {
+	#[default]
+	List,
+	Add,
+	Clear,
+}
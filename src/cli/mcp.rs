@@ -0,0 +1,84 @@
+use anyhow::{bail, Result};
+use clap::{Parser, ValueEnum};
+use colored::Colorize;
+
+use crate::{
+	argon_info, argon_warn,
+	logger::Table,
+	mcp::{self, McpConfig, Permission},
+	t, util,
+};
+
+/// Manage permissions of the MCP server tools
+#[derive(Parser)]
+pub struct Mcp {
+	/// Whether to `list` tools or `approve` one
+	#[arg(hide_possible_values = true)]
+	mode: Option<McpMode>,
+
+	/// Tool to approve
+	#[arg()]
+	tool: Option<String>,
+}
+
+impl Mcp {
+	pub fn main(self) -> Result<()> {
+		let dir = util::get_argon_dir()?;
+		let config = McpConfig::load(&dir.join("mcp.toml"))?;
+
+		match self.mode.unwrap_or_default() {
+			McpMode::List => {
+				let pending = mcp::pending_approvals(&dir);
+
+				let mut table = Table::new();
+				table.set_header(vec!["Tool", "Permission", "Network"]);
+
+				for (tool, network) in mcp::TOOLS {
+					let mut permission = format!("{:?}", config.permission(tool)).to_lowercase();
+
+					if pending.iter().any(|approved| approved == tool) {
+						permission += " (approved)";
+					}
+
+					table.add_row(vec![tool.to_string(), permission, network.to_string()]);
+				}
+
+				argon_info!(
+					"{}",
+					t!("mcp.list", offline = config.offline.to_string().bold(), table = table)
+				);
+			}
+			McpMode::Approve => {
+				let Some(tool) = self.tool else {
+					bail!("No tool to approve was provided");
+				};
+
+				if !mcp::is_tool(&tool) {
+					bail!("Unknown MCP tool: {}", tool.bold());
+				}
+
+				match config.permission(&tool) {
+					Permission::Allow => {
+						argon_info!("{}", t!("mcp.already_allowed", tool = tool.bold()));
+					}
+					Permission::Deny => {
+						argon_warn!("{}", t!("mcp.denied", tool = tool.bold()));
+					}
+					Permission::Ask => {
+						mcp::approve(&dir, &tool)?;
+						argon_info!("{}", t!("mcp.approved", tool = tool.bold()));
+					}
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Clone, Default, ValueEnum)]
+enum McpMode {
+	#[default]
+	List,
+	Approve,
+}
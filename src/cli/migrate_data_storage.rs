@@ -0,0 +1,83 @@
+use anyhow::{bail, Result};
+use clap::{Parser, ValueEnum};
+use colored::Colorize;
+use std::path::PathBuf;
+
+use crate::{
+	argon_info, argon_warn,
+	core::Core,
+	ext::PathExt,
+	project::{self, DataStorage, Project},
+	t,
+};
+
+/// Move instance data between data files and per-directory data indexes
+#[derive(Parser)]
+pub struct MigrateDataStorage {
+	/// Target storage
+	#[arg(hide_possible_values = true)]
+	to: Storage,
+
+	/// Project path
+	#[arg()]
+	project: Option<PathBuf>,
+}
+
+impl MigrateDataStorage {
+	pub fn main(self) -> Result<()> {
+		let project_path = project::resolve(self.project.unwrap_or_default())?;
+
+		if !project_path.exists() {
+			bail!(
+				"No project files found in {}. Run {} to create new one",
+				project_path.get_parent().to_string().bold(),
+				"argon init".bold(),
+			);
+		}
+
+		let mut project = Project::load(&project_path)?;
+
+		let storage = match self.to {
+			Storage::Files => DataStorage::Files,
+			Storage::Index => DataStorage::Index,
+		};
+
+		let migration = Core::new(Project::load(&project_path)?, false)?.migrate_data_storage(storage)?;
+
+		// New data has to be written to the same storage
+		if project.data_storage.unwrap_or_default() != storage {
+			project.data_storage = Some(storage);
+			project.save(&project_path)?;
+		}
+
+		for path in &migration.skipped {
+			argon_warn!("{}", t!("migrate_data_storage.skipped", path = path.to_string().bold()));
+		}
+
+		argon_info!(
+			"{}",
+			t!(
+				"migrate_data_storage.success",
+				count = migration.migrated.len(),
+				storage = self.to.as_str().bold()
+			)
+		);
+
+		Ok(())
+	}
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Storage {
+	Files,
+	Index,
+}
+
+impl Storage {
+	fn as_str(self) -> &'static str {
+		match self {
+			Storage::Files => "files",
+			Storage::Index => "index",
+		}
+	}
+}
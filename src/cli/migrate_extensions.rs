@@ -0,0 +1,77 @@
+use anyhow::{bail, Result};
+use clap::{Parser, ValueEnum};
+use colored::Colorize;
+use std::path::PathBuf;
+
+use crate::{
+	argon_info, argon_warn,
+	core::Core,
+	ext::PathExt,
+	project::{self, Project, ScriptExtension},
+	t,
+};
+
+/// Rename script files to the preferred extension and update the project
+#[derive(Parser)]
+pub struct MigrateExtensions {
+	/// Project path
+	#[arg()]
+	project: Option<PathBuf>,
+
+	/// Target extension, the one set in `middlewareConfig` by default
+	#[arg(short, long, hide_possible_values = true)]
+	to: Option<Extension>,
+}
+
+impl MigrateExtensions {
+	pub fn main(self) -> Result<()> {
+		let project_path = project::resolve(self.project.unwrap_or_default())?;
+
+		if !project_path.exists() {
+			bail!(
+				"No project files found in {}. Run {} to create new one",
+				project_path.get_parent().to_string().bold(),
+				"argon init".bold(),
+			);
+		}
+
+		let mut project = Project::load(&project_path)?;
+		let mut config = project.middleware_config.clone().unwrap_or_default();
+
+		let extension = match self.to {
+			Some(Extension::Lua) => ScriptExtension::Lua,
+			Some(Extension::Luau) => ScriptExtension::Luau,
+			None => config.resolve().scripts.extension(),
+		};
+
+		let migration = Core::new(Project::load(&project_path)?, false)?.migrate_extensions(extension)?;
+
+		// New files have to be written with the same extension
+		if config.scripts.extension != Some(extension) {
+			config.scripts.extension = Some(extension);
+			project.middleware_config = Some(config);
+			project.save(&project_path)?;
+		}
+
+		for path in &migration.skipped {
+			argon_warn!("{}", t!("migrate_extensions.skipped", path = path.to_string().bold()));
+		}
+
+		argon_info!(
+			"{}",
+			t!(
+				"migrate_extensions.success",
+				count = migration.renamed.len(),
+				extension = extension.as_str().bold()
+			)
+		);
+
+		Ok(())
+	}
+}
+
+#[derive(Clone, ValueEnum)]
+enum Extension {
+	Lua,
+	Luau,
+}
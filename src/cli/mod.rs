@@ -1,25 +1,58 @@
 use anyhow::Result;
-use clap::{ColorChoice, Parser, Subcommand};
+use clap::{ColorChoice, CommandFactory, FromArgMatches, Parser, Subcommand};
 use clap_verbosity_flag::Verbosity;
 use env_logger::fmt::WriteStyle;
 use log::LevelFilter;
-use std::env;
+use std::{env, ffi::OsString, path::Path};
 
-use crate::util;
+use crate::{i18n, logger::LogFormat, util};
 
+mod apply_patch;
+mod asset;
+mod backups;
+mod bench;
+mod blame;
 mod build;
+mod class_metadata;
+mod clean;
 mod config;
 mod connect_mcp;
 mod debug;
 mod doc;
+mod doctor;
 mod exec;
+mod find;
+mod freeze;
+mod fsck;
+mod hash;
+mod import;
 mod init;
+mod logs;
+mod markers;
+mod mcp;
+mod migrate_data_storage;
+mod migrate_extensions;
+mod open;
+mod pause;
 mod plugin;
+mod replay;
+pub mod rojo;
 mod serve;
+mod sessions;
+mod skipped;
 mod sourcemap;
+mod status;
 mod stop;
 mod studio;
+mod sync;
+mod test;
+mod typegen;
+mod ui;
+mod undo;
+mod unknowns;
 mod update;
+mod validate;
+pub mod watch;
 
 macro_rules! about {
 	() => {
@@ -59,7 +92,7 @@ pub struct Cli {
 	backtrace: bool,
 
 	#[arg(long, hide = true, global = true)]
-	profile: bool,
+	profiler: bool,
 
 	/// Output coloring: auto, always, never
 	#[arg(
@@ -72,15 +105,53 @@ pub struct Cli {
 		hide_possible_values = true
 	)]
 	pub color: ColorChoice,
+
+	/// Format of log lines: human, json
+	#[arg(
+		long,
+		global = true,
+		value_name = "FORMAT",
+		default_value = "human",
+		hide_default_value = true,
+		hide_possible_values = true
+	)]
+	log_format: LogFormat,
 }
 
 impl Cli {
 	pub fn new() -> Cli {
-		Cli::parse()
+		let mut command = Cli::command();
+		let names: Vec<String> = command
+			.get_subcommands()
+			.map(|subcommand| subcommand.get_name().to_owned())
+			.collect();
+
+		// Subcommand descriptions come from the message catalog
+		// so they follow the selected language
+		for name in names {
+			if let Some(about) = i18n::get(&format!("cli.{}", name)) {
+				command = command.mut_subcommand(name, |subcommand| subcommand.about(about));
+			}
+		}
+
+		let mut args: Vec<OsString> = env::args_os().collect();
+
+		// Invoked through `rojo` symlink or hard link
+		if args
+			.first()
+			.and_then(|binary| Path::new(binary).file_stem())
+			.is_some_and(|stem| stem == "rojo")
+		{
+			args.insert(1, OsString::from("rojo"));
+		}
+
+		let matches = command.get_matches_from(args);
+
+		Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit())
 	}
 
 	pub fn profile(&self) -> bool {
-		self.profile
+		self.profiler
 	}
 
 	pub fn yes(&self) -> bool {
@@ -119,13 +190,25 @@ impl Cli {
 		}
 	}
 
+	pub fn log_format(&self) -> LogFormat {
+		if env::var("RUST_LOG_FORMAT").is_ok() {
+			return util::env_log_format();
+		}
+
+		self.log_format
+	}
+
 	pub fn main(self) -> Result<()> {
 		match self.command {
 			Commands::Init(command) => command.main(),
 			Commands::Serve(command) => command.main(),
+			Commands::Sessions(command) => command.main(),
 			Commands::Build(command) => command.main(),
 			Commands::Sourcemap(command) => command.main(),
+			Commands::Watch(command) => command.main(),
 			Commands::Stop(command) => command.main(),
+			Commands::Status(command) => command.main(),
+			Commands::Logs(command) => command.main(),
 			Commands::Studio(command) => command.main(),
 			Commands::Debug(command) => command.main(),
 			Commands::Exec(command) => command.main(),
@@ -134,6 +217,37 @@ impl Cli {
 			Commands::Config(command) => command.main(),
 			Commands::Doc(command) => command.main(),
 			Commands::ConnectMcp(command) => command.main(),
+			Commands::Mcp(command) => command.main(),
+			Commands::Blame(command) => command.main(),
+			Commands::Find(command) => command.main(),
+			Commands::Open(command) => command.main(),
+			Commands::Freeze(command) => command.main(),
+			Commands::Unfreeze(command) => command.main(),
+			Commands::Pause(command) => command.main(),
+			Commands::Resume(command) => command.main(),
+			Commands::Skipped(command) => command.main(),
+			Commands::Unknowns(command) => command.main(),
+			Commands::Markers(command) => command.main(),
+			Commands::Undo(command) => command.main(),
+			Commands::Sync(command) => command.main(),
+			Commands::Doctor(command) => command.main(),
+			Commands::Fsck(command) => command.main(),
+			Commands::Validate(command) => command.main(),
+			Commands::Hash(command) => command.main(),
+			Commands::Import(command) => command.main(),
+			Commands::Typegen(command) => command.main(),
+			Commands::Clean(command) => command.main(),
+			Commands::MigrateExtensions(command) => command.main(),
+			Commands::MigrateDataStorage(command) => command.main(),
+			Commands::ClassMetadata(command) => command.main(),
+			Commands::Rojo(command) => command.main(),
+			Commands::Backups(command) => command.main(),
+			Commands::Ui(command) => command.main(),
+			Commands::Asset(command) => command.main(),
+			Commands::Bench(command) => command.main(),
+			Commands::ApplyPatch(command) => command.main(),
+			Commands::Replay(command) => command.main(),
+			Commands::Test(command) => command.main(),
 		}
 	}
 }
@@ -142,9 +256,13 @@ impl Cli {
 pub enum Commands {
 	Init(init::Init),
 	Serve(serve::Serve),
+	Sessions(sessions::Sessions),
 	Build(build::Build),
 	Sourcemap(sourcemap::Sourcemap),
+	Watch(watch::Watch),
 	Stop(stop::Stop),
+	Status(status::Status),
+	Logs(logs::Logs),
 	Studio(studio::Studio),
 	Debug(debug::Debug),
 	Exec(exec::Exec),
@@ -153,4 +271,35 @@ pub enum Commands {
 	Config(config::Config),
 	Doc(doc::Doc),
 	ConnectMcp(connect_mcp::ConnectMcp),
+	Mcp(mcp::Mcp),
+	Blame(blame::Blame),
+	Find(find::Find),
+	Open(open::Open),
+	Freeze(freeze::Freeze),
+	Unfreeze(freeze::Unfreeze),
+	Pause(pause::Pause),
+	Resume(pause::Resume),
+	Skipped(skipped::Skipped),
+	Unknowns(unknowns::Unknowns),
+	Markers(markers::Markers),
+	Undo(undo::Undo),
+	Sync(sync::Sync),
+	Doctor(doctor::Doctor),
+	Fsck(fsck::Fsck),
+	Validate(validate::Validate),
+	Hash(hash::Hash),
+	Import(import::Import),
+	Typegen(typegen::Typegen),
+	Clean(clean::Clean),
+	MigrateExtensions(migrate_extensions::MigrateExtensions),
+	MigrateDataStorage(migrate_data_storage::MigrateDataStorage),
+	ClassMetadata(class_metadata::ClassMetadata),
+	Rojo(rojo::Rojo),
+	Backups(backups::Backups),
+	Ui(ui::Ui),
+	Asset(asset::Asset),
+	Bench(bench::Bench),
+	ApplyPatch(apply_patch::ApplyPatch),
+	Replay(replay::Replay),
+	Test(test::Test),
 }
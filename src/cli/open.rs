@@ -0,0 +1,52 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::Colorize;
+use std::path::PathBuf;
+
+use crate::{
+	argon_info,
+	core::Core,
+	ext::PathExt,
+	project::{self, Project},
+	t,
+	util::editor::OpenError,
+};
+
+/// Open source file of the instance in the editor
+#[derive(Parser)]
+pub struct Open {
+	/// Instance path, e.g. `ReplicatedStorage.Module`
+	#[arg()]
+	instance: String,
+
+	/// Project path
+	#[arg()]
+	project: Option<PathBuf>,
+
+	/// Line of the script to open the file at
+	#[arg(short, long)]
+	line: Option<u32>,
+}
+
+impl Open {
+	pub fn main(self) -> Result<()> {
+		let project_path = project::resolve(self.project.unwrap_or_default())?;
+
+		if !project_path.exists() {
+			bail!(
+				"No project files found in {}. Run {} to create new one",
+				project_path.get_parent().to_string().bold(),
+				"argon init".bold(),
+			);
+		}
+
+		let core = Core::new(Project::load(&project_path)?, false)?;
+		let instance = core.tree().find_by_path(&self.instance).ok_or(OpenError::NoInstance)?;
+
+		let file = core.open(instance, self.line)?;
+
+		argon_info!("{}", t!("open.opened", file = file.to_string().bold()));
+
+		Ok(())
+	}
+}
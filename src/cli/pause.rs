@@ -0,0 +1,93 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use reqwest::{blocking::Client, header::CONTENT_TYPE};
+
+use crate::{argon_info, argon_warn, core::processor::pause::PauseStatus, server::pause::PauseRequest, sessions, t};
+
+/// Stop syncing in both directions without disconnecting clients, e.g. during a rebase
+#[derive(Parser)]
+pub struct Pause {
+	/// Session identifier
+	#[arg()]
+	session: Option<String>,
+
+	/// Seconds after which syncing resumes on its own (0 to never resume)
+	#[arg(short, long)]
+	timeout: Option<u64>,
+
+	/// Server host name
+	#[arg(short = 'H', long)]
+	host: Option<String>,
+
+	/// Server port
+	#[arg(short = 'P', long)]
+	port: Option<u16>,
+}
+
+impl Pause {
+	pub fn main(self) -> Result<()> {
+		let address = get_address(self.session, self.host, self.port)?;
+		let body = rmp_serde::to_vec(&PauseRequest { timeout: self.timeout })?;
+
+		let response = Client::new()
+			.post(format!("{}/pause", address))
+			.header(CONTENT_TYPE, "application/msgpack")
+			.body(body)
+			.send()?;
+
+		if !response.status().is_success() {
+			bail!("Failed to pause syncing: {}", response.text()?);
+		}
+
+		let status: PauseStatus = response.json()?;
+
+		match status.resumes_in {
+			Some(seconds) => argon_info!("{}", t!("pause.paused_until", seconds = seconds.to_string().bold())),
+			None => argon_info!("{}", t!("pause.paused")),
+		}
+
+		Ok(())
+	}
+}
+
+/// Resume paused syncing, file changes made in the meantime are synced first
+#[derive(Parser)]
+pub struct Resume {
+	/// Session identifier
+	#[arg()]
+	session: Option<String>,
+
+	/// Server host name
+	#[arg(short = 'H', long)]
+	host: Option<String>,
+
+	/// Server port
+	#[arg(short = 'P', long)]
+	port: Option<u16>,
+}
+
+impl Resume {
+	pub fn main(self) -> Result<()> {
+		let address = get_address(self.session, self.host, self.port)?;
+		let response = Client::new().post(format!("{}/resume", address)).send()?;
+
+		if !response.status().is_success() {
+			bail!("Failed to resume syncing: {}", response.text()?);
+		}
+
+		if response.json()? {
+			argon_info!("{}", t!("pause.resumed"));
+		} else {
+			argon_warn!("{}", t!("pause.not_paused"));
+		}
+
+		Ok(())
+	}
+}
+
+fn get_address(session: Option<String>, host: Option<String>, port: Option<u16>) -> Result<String> {
+	sessions::get(session, host, port)?
+		.and_then(|session| session.get_address())
+		.context("No running session with an address was found")
+}
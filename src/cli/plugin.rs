@@ -2,7 +2,7 @@ use anyhow::Result;
 use clap::{Parser, ValueEnum};
 use std::{fs, path::PathBuf};
 
-use crate::{argon_info, config::Config, ext::PathExt, installer, util};
+use crate::{argon_info, config::Config, ext::PathExt, installer, t, util};
 
 /// Install Argon Roblox Studio plugin locally
 #[derive(Parser)]
@@ -35,11 +35,11 @@ impl Plugin {
 
 		match self.mode.unwrap_or_default() {
 			PluginMode::Install => {
-				argon_info!("Installing Argon plugin..");
+				argon_info!("{}", t!("plugin.installing"));
 				installer::install_plugin(&plugin_path, true)?;
 			}
 			PluginMode::Uninstall => {
-				argon_info!("Uninstalling Argon plugin..");
+				argon_info!("{}", t!("plugin.uninstalling"));
 				fs::remove_file(plugin_path)?;
 			}
 		}
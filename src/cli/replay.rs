@@ -0,0 +1,87 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use std::{env, fs, path::PathBuf, process};
+
+use crate::{
+	argon_info, argon_warn,
+	core::trace::{self, Trace},
+	ext::PathExt,
+	t,
+};
+
+/// Replay a trace recorded with `argon serve --record-trace` and report where the result diverges
+#[derive(Parser)]
+pub struct Replay {
+	/// Trace file path
+	#[arg()]
+	trace: PathBuf,
+
+	/// Starting workspace to use instead of the one stored in the trace, required for redacted traces
+	#[arg(short, long)]
+	workspace: Option<PathBuf>,
+
+	/// Replay speed relative to the recording, inputs are replayed as fast as possible by default
+	#[arg(short, long)]
+	speed: Option<f64>,
+}
+
+impl Replay {
+	pub fn main(self) -> Result<()> {
+		let trace =
+			Trace::read(&self.trace).with_context(|| format!("Failed to read trace {}", self.trace.display()))?;
+
+		let dir = env::temp_dir().join(format!("argon-replay-{}", process::id()));
+
+		trace::prepare(&trace, self.workspace.as_deref(), &dir)?;
+		let report = trace::replay(&trace, &dir, self.speed)?;
+
+		if !report.start_matches && !report.verify_only {
+			argon_warn!("{}", t!("replay.start_mismatch"));
+		}
+
+		if let Some(divergence) = &report.divergence {
+			argon_warn!(
+				"{}",
+				t!(
+					"replay.diverged",
+					index = divergence.index.to_string().bold(),
+					input = divergence.input,
+					time = divergence.time,
+					expected = divergence.expected,
+					actual = divergence.actual
+				)
+			);
+		}
+
+		match &report.expected {
+			Some(expected) => {
+				if expected.tree != report.actual.tree {
+					argon_warn!(
+						"{}",
+						t!("replay.end_tree", expected = expected.tree, actual = report.actual.tree)
+					);
+				}
+
+				if expected.files != report.actual.files {
+					argon_warn!("{}", t!("replay.end_files"));
+				}
+			}
+			None => argon_warn!("{}", t!("replay.no_end")),
+		}
+
+		if !report.is_identical() {
+			bail!("{}", t!("replay.different", path = dir.to_string().bold()));
+		}
+
+		fs::remove_dir_all(&dir).ok();
+
+		if report.verify_only {
+			argon_info!("{}", t!("replay.verified"));
+		} else {
+			argon_info!("{}", t!("replay.identical", count = report.replayed.to_string().bold()));
+		}
+
+		Ok(())
+	}
+}
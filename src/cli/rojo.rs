@@ -0,0 +1,166 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::Colorize;
+use log::debug;
+
+use super::Cli;
+use crate::{argon_warn, t};
+
+/// Run common Rojo commands using Argon equivalents
+#[derive(Parser)]
+pub struct Rojo {
+	/// Rojo command with its arguments, e.g. `build -o game.rbxlx`
+	#[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+	args: Vec<String>,
+}
+
+impl Rojo {
+	pub fn main(self) -> Result<()> {
+		let translation = translate(&self.args)?;
+
+		for flag in &translation.ignored {
+			argon_warn!("{}", t!("rojo.unsupported_flag", flag = flag.bold()));
+		}
+
+		debug!("Running Rojo command as: argon {}", translation.args.join(" "));
+
+		let cli = Cli::try_parse_from(["argon".to_owned()].into_iter().chain(translation.args))
+			.unwrap_or_else(|err| err.exit());
+
+		cli.main()
+	}
+}
+
+/// Argon command equivalent to the Rojo one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Translation {
+	/// Arguments of the Argon command, without the binary name
+	pub args: Vec<String>,
+	/// Rojo flags and arguments that have no Argon equivalent
+	pub ignored: Vec<String>,
+}
+
+enum Mapping {
+	/// Flag without a value, passed as the given Argon flag
+	Flag(&'static str),
+	/// Flag with a value, passed as the given Argon flag
+	Value(&'static str),
+	/// Flag without a value that has no Argon equivalent
+	Ignored,
+	/// Flag with a value that has no Argon equivalent
+	IgnoredValue,
+}
+
+/// Flags shared by every Rojo command, only `--help` is supported
+static GLOBAL_FLAGS: [(&[&str], Mapping); 3] = [
+	(&["-h", "--help"], Mapping::Flag("--help")),
+	(&["-v", "--verbose"], Mapping::Ignored),
+	(&["--color"], Mapping::IgnoredValue),
+];
+
+static SERVE_FLAGS: [(&[&str], Mapping); 2] = [
+	(&["--address"], Mapping::Value("--host")),
+	(&["--port"], Mapping::Value("--port")),
+];
+
+static BUILD_FLAGS: [(&[&str], Mapping); 3] = [
+	(&["-o", "--output"], Mapping::Value("--output")),
+	(&["--watch"], Mapping::Flag("--watch")),
+	(&["--plugin"], Mapping::IgnoredValue),
+];
+
+static SOURCEMAP_FLAGS: [(&[&str], Mapping); 4] = [
+	(&["-o", "--output"], Mapping::Value("--output")),
+	(&["--include-non-scripts"], Mapping::Flag("--non-scripts")),
+	(&["--watch"], Mapping::Flag("--watch")),
+	(&["--absolute"], Mapping::Ignored),
+];
+
+static INIT_FLAGS: [(&[&str], Mapping); 1] = [(&["--kind"], Mapping::Value("--template"))];
+
+/// Translates arguments of `rojo serve`, `build`, `sourcemap` and `init`
+/// to the matching Argon command, the first positional argument is always
+/// the project path. Unsupported flags are collected instead of failing
+pub fn translate(args: &[String]) -> Result<Translation> {
+	let Some((command, args)) = args.split_first() else {
+		bail!(
+			"Missing Rojo command, supported ones are: {}",
+			"serve, build, sourcemap, init".bold()
+		);
+	};
+
+	let flags: &[(&[&str], Mapping)] = match command.as_str() {
+		"serve" => &SERVE_FLAGS,
+		"build" => &BUILD_FLAGS,
+		"sourcemap" => &SOURCEMAP_FLAGS,
+		"init" => &INIT_FLAGS,
+		"-h" | "--help" | "-V" | "--version" => {
+			return Ok(Translation {
+				args: vec![command.to_owned()],
+				ignored: vec![],
+			});
+		}
+		_ => bail!(
+			"Rojo command {} is not supported by Argon, supported ones are: {}",
+			command.bold(),
+			"serve, build, sourcemap, init".bold()
+		),
+	};
+
+	let mut translation = Translation {
+		args: vec![command.to_owned()],
+		ignored: vec![],
+	};
+
+	let mut project = None;
+	let mut args = args.iter();
+
+	while let Some(arg) = args.next() {
+		if !arg.starts_with('-') || arg == "-" {
+			if project.is_none() {
+				project = Some(arg.to_owned());
+			} else {
+				translation.ignored.push(arg.to_owned());
+			}
+
+			continue;
+		}
+
+		let (name, inline) = match arg.split_once('=') {
+			Some((name, value)) if name.starts_with("--") => (name, Some(value.to_owned())),
+			_ => (arg.as_str(), None),
+		};
+
+		let mapping = flags
+			.iter()
+			.chain(GLOBAL_FLAGS.iter())
+			.find(|(names, _)| names.contains(&name))
+			.map(|(_, mapping)| mapping);
+
+		let mut value = || match inline.clone().or_else(|| args.next().cloned()) {
+			Some(value) => Ok(value),
+			None => bail!("Rojo flag {} requires a value", name.bold()),
+		};
+
+		match mapping {
+			Some(Mapping::Flag(flag)) => translation.args.push((*flag).to_owned()),
+			Some(Mapping::Value(flag)) => {
+				let value = value()?;
+				translation.args.extend([(*flag).to_owned(), value]);
+			}
+			Some(Mapping::Ignored) => translation.ignored.push(arg.to_owned()),
+			Some(Mapping::IgnoredValue) => {
+				let value = value()?;
+				translation.ignored.push(format!("{} {}", name, value));
+			}
+			None => translation.ignored.push(arg.to_owned()),
+		}
+	}
+
+	// Positional arguments have to come before the flags in Argon
+	if let Some(project) = project {
+		translation.args.insert(1, project);
+	}
+
+	Ok(translation)
+}
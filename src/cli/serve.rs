@@ -1,19 +1,29 @@
 use anyhow::{bail, Result};
 use clap::Parser;
 use colored::Colorize;
-use log::{debug, info};
-use std::{path::PathBuf, process, sync::Arc, thread};
+use log::{debug, info, warn};
+use std::{
+	path::{Path, PathBuf},
+	process,
+	sync::Arc,
+	thread,
+	time::Duration,
+};
 
 use crate::{
 	argon_error, argon_info, argon_warn,
 	config::Config,
-	core::Core,
+	constants::QUEUE_CHECKPOINT_INTERVAL,
+	core::{lifecycle, Core},
+	daemon,
 	ext::PathExt,
 	integration,
+	manifest::WorkspaceManifest,
 	program::{Program, ProgramName},
 	project::{self, Project},
 	server::{self, Server},
-	sessions,
+	sessions, t, util,
+	vfs::{debouncer::WatchHub, Vfs},
 };
 
 /// Start local server and listen for file changes
@@ -23,6 +33,10 @@ pub struct Serve {
 	#[arg()]
 	project: Option<PathBuf>,
 
+	/// Workspace file listing projects to serve together, e.g. `argon.workspace.json`
+	#[arg(short = 'W', long, conflicts_with = "project")]
+	workspace: Option<PathBuf>,
+
 	/// Session identifier
 	#[arg()]
 	session: Option<String>,
@@ -35,6 +49,14 @@ pub struct Serve {
 	#[arg(short = 'P', long)]
 	port: Option<u16>,
 
+	/// Also serve read-only endpoints on this port, for watching the live tree
+	#[arg(long)]
+	mirror_port: Option<u16>,
+
+	/// Token the mirror requires as `Authorization: Bearer` header or `token` query
+	#[arg(long, requires = "mirror_port")]
+	mirror_token: Option<String>,
+
 	/// Generate sourcemap every time files change
 	#[arg(short, long)]
 	sourcemap: bool,
@@ -43,10 +65,34 @@ pub struct Serve {
 	#[arg(short, long)]
 	ts: bool,
 
+	/// Accept clients connected to any place, ignoring `gameId` and `placeIds`
+	#[arg(long)]
+	allow_any_place: bool,
+
+	/// Build profile to use, overrides `ARGON_PROFILE` and the `profile` setting
+	#[arg(long)]
+	profile: Option<String>,
+
+	/// Record processed changes to a trace file that can be replayed with `argon replay`
+	#[arg(long, conflicts_with = "workspace")]
+	record_trace: Option<PathBuf>,
+
+	/// Store only hashes of file contents and client changes in the trace
+	#[arg(long, requires = "record_trace")]
+	redact_trace: bool,
+
 	/// Run Argon asynchronously
 	#[arg(short = 'A', long = "async")]
 	run_async: bool,
 
+	/// Run as a background daemon, writing logs to `.argon/logs`
+	#[arg(short = 'D', long)]
+	daemon: bool,
+
+	/// Not supported, projects read from stdin can't be reloaded
+	#[arg(long, visible_alias = "stdin-project", hide = true)]
+	stdin: bool,
+
 	/// Spawn the Argon child process (internal)
 	#[arg(long, hide = true)]
 	argon_spawn: bool,
@@ -54,11 +100,27 @@ pub struct Serve {
 
 impl Serve {
 	pub fn main(self) -> Result<()> {
+		if self.stdin {
+			bail!(
+				"Cannot serve project read from stdin as it can't be reloaded when it changes, \
+				save it to a file and run {} instead",
+				"argon serve <project>".bold()
+			);
+		}
+
+		if let Some(workspace) = self.workspace.clone() {
+			return self.serve_workspace(&workspace);
+		}
+
 		let project_path = project::resolve(self.project.clone().unwrap_or_default())?;
 
 		Config::load_workspace(project_path.get_parent());
 		let config = Config::new();
 
+		if !self.argon_spawn && self.daemon {
+			return self.spawn_daemon(project_path.get_parent());
+		}
+
 		if !self.argon_spawn && (self.run_async || config.run_async) {
 			return self.spawn();
 		}
@@ -77,12 +139,200 @@ impl Serve {
 			);
 		}
 
-		let project = Project::load(&project_path)?;
+		project::set_profile(self.profile.clone());
+
+		let mut project = Project::load(&project_path)?;
+		project.allow_any_place = self.allow_any_place;
 
 		if !project.is_place() {
 			bail!("Cannot serve non-place project!");
 		}
 
+		if !self.prepare(&project, &project_path, &config)? {
+			return Ok(());
+		}
+
+		let core = Core::new(project, true)?;
+		lifecycle::print_status(&core);
+		let host = self.host.unwrap_or(core.host().unwrap_or(config.host.clone()));
+		let port = Self::select_port(
+			&host,
+			self.port.unwrap_or(core.port().unwrap_or(config.port)),
+			config.scan_ports,
+		)?;
+
+		let mirror_port = self.mirror_port.or(core.project().mirror_port);
+		Self::check_mirror_port(&host, port, mirror_port)?;
+
+		let core = Arc::new(core);
+
+		if let Some(path) = &self.record_trace {
+			Self::record_trace(core.clone(), path, self.redact_trace)?;
+		}
+
+		if let Some(path) = sourcemap_path {
+			Self::watch_sourcemap(core.clone(), path)?;
+		}
+
+		if self.daemon {
+			sessions::add_daemon(
+				self.session,
+				host.clone(),
+				port,
+				process::id(),
+				project_path.get_parent(),
+				mirror_port,
+			)?;
+		} else {
+			sessions::add(
+				self.session,
+				Some(host.clone()),
+				Some(port),
+				process::id(),
+				&project_path,
+				mirror_port,
+				config.run_async,
+			)?;
+		}
+
+		Self::checkpoint_queues(vec![core.clone()]);
+		Self::save_sync_bases(vec![core.clone()]);
+		Self::send_heartbeats(vec![core.clone()]);
+		Self::scan_integrity(vec![core.clone()]);
+
+		let mut server = Server::new(core, &host, port);
+
+		argon_info!(
+			"{}",
+			t!(
+				"serve.serving",
+				address = server::format_address(&host, port).bold(),
+				project = project_path.to_string().bold()
+			)
+		);
+
+		if let Some(mirror_port) = mirror_port {
+			server = server.with_mirror(mirror_port, self.mirror_token.clone());
+			Self::announce_mirror(&host, mirror_port);
+		}
+
+		server.start()?;
+
+		Ok(())
+	}
+
+	fn serve_workspace(self, path: &Path) -> Result<()> {
+		let workspace_path = path.resolve()?;
+		let workspace_dir = workspace_path.get_parent();
+
+		if !workspace_path.is_file() {
+			bail!("Workspace file {} does not exist", workspace_path.to_string().bold());
+		}
+
+		Config::load_workspace(workspace_dir);
+		let config = Config::new();
+
+		if !self.argon_spawn && self.daemon {
+			return self.spawn_daemon(workspace_dir);
+		}
+
+		if !self.argon_spawn && (self.run_async || config.run_async) {
+			return self.spawn();
+		}
+
+		let members = WorkspaceManifest::load(&workspace_path)?.members(&workspace_path)?;
+
+		project::set_profile(self.profile.clone());
+
+		// Members share the OS watcher so directories used by several of
+		// them (e.g. `shared/` included with `$path`) are watched only once
+		let hub = Arc::new(WatchHub::new());
+		let mut cores = vec![];
+
+		for member in &members {
+			if !member.project.exists() {
+				bail!(
+					"No project files found for workspace member {} at {}",
+					member.name.bold(),
+					member.project.to_string().bold()
+				);
+			}
+
+			let mut project = Project::load(&member.project)?;
+			project.allow_any_place = self.allow_any_place;
+
+			if !project.is_place() {
+				bail!(
+					"Cannot serve non-place project of workspace member {}!",
+					member.name.bold()
+				);
+			}
+
+			if !self.prepare(&project, &member.project, &config)? {
+				return Ok(());
+			}
+
+			let core = Arc::new(Core::with_vfs(project, Vfs::with_hub(hub.clone()))?);
+			lifecycle::print_status(&core);
+
+			if self.sourcemap || config.with_sourcemap {
+				Self::watch_sourcemap(core.clone(), member.project.with_file_name("sourcemap.json"))?;
+			}
+
+			cores.push((member.name.clone(), core));
+		}
+
+		let host = self.host.clone().unwrap_or(config.host.clone());
+		let port = Self::select_port(&host, self.port.unwrap_or(config.port), config.scan_ports)?;
+		Self::check_mirror_port(&host, port, self.mirror_port)?;
+
+		sessions::add_workspace(
+			self.session.clone(),
+			host.clone(),
+			port,
+			process::id(),
+			&workspace_path,
+			members.iter().map(|member| member.project.clone()).collect(),
+			self.mirror_port,
+			self.daemon,
+			config.run_async,
+		)?;
+
+		Self::checkpoint_queues(cores.iter().map(|(_, core)| core.clone()).collect());
+		Self::save_sync_bases(cores.iter().map(|(_, core)| core.clone()).collect());
+		Self::send_heartbeats(cores.iter().map(|(_, core)| core.clone()).collect());
+		Self::scan_integrity(cores.iter().map(|(_, core)| core.clone()).collect());
+
+		let mut server = Server::new_workspace(cores, &host, port);
+
+		argon_info!(
+			"{}",
+			t!(
+				"serve.serving_workspace",
+				address = server::format_address(&host, port).bold(),
+				workspace = workspace_path.to_string().bold(),
+				members = members
+					.iter()
+					.map(|member| member.name.as_str())
+					.collect::<Vec<_>>()
+					.join(", ")
+					.bold()
+			)
+		);
+
+		if let Some(mirror_port) = self.mirror_port {
+			server = server.with_mirror(mirror_port, self.mirror_token.clone());
+			Self::announce_mirror(&host, mirror_port);
+		}
+
+		server.start()?;
+
+		Ok(())
+	}
+
+	/// Checks Wally packages and starts roblox-ts if the project uses them,
+	/// returns `false` if roblox-ts was not started and serving should stop
+	fn prepare(&self, project: &Project, project_path: &Path, config: &Config) -> Result<bool> {
 		let use_wally = config.use_wally || (config.detect_project && project.is_wally());
 		let use_ts = self.ts || config.ts_mode || (config.detect_project && project.is_ts());
 
@@ -103,110 +353,255 @@ impl Serve {
 				.spawn()?;
 
 			if child.is_none() {
-				return Ok(());
+				return Ok(false);
 			}
 		}
 
-		let core = Core::new(project, true)?;
-		let host = self.host.unwrap_or(core.host().unwrap_or(config.host.clone()));
-		let mut port = self.port.unwrap_or(core.port().unwrap_or(config.port));
-
-		if !server::is_port_free(&host, port) {
-			// Check if there's already an active Argon session using this host
-			if let Ok(all_sessions) = sessions::get_all() {
-				for (_, session) in all_sessions {
-					if let Some(session_host) = &session.host {
-						if *session_host == host && session.port.is_some() {
-							let address = server::format_address(&host, session.port.unwrap());
-							bail!(
-								"A Lemonade server is already running at {}. Please use this session instead of starting a new one. or use argon stop to stop the existing server",
-								address.bold()
-							);
-						}
+		Ok(true)
+	}
+
+	fn select_port(host: &str, port: u16, scan_ports: bool) -> Result<u16> {
+		if server::is_port_free(host, port) {
+			return Ok(port);
+		}
+
+		// Check if there's already an active Argon session using this host
+		if let Ok(all_sessions) = sessions::get_all() {
+			for (_, session) in all_sessions {
+				if let (Some(session_host), Some(port)) = (&session.host, session.port) {
+					if *session_host == host {
+						let address = server::format_address(host, port);
+						bail!(
+							"A Lemonade server is already running at {}. Please use this session instead of starting a new one. or use argon stop to stop the existing server",
+							address.bold()
+						);
 					}
 				}
 			}
+		}
 
-			// No existing session with this host found, handle as before
-			if config.scan_ports {
-				let new_port = server::get_free_port(&host, port);
+		// No existing session with this host found, handle as before
+		if scan_ports {
+			let new_port = server::get_free_port(host, port);
+
+			argon_warn!(
+				"{}",
+				t!(
+					"serve.port_in_use",
+					port = port.to_string().bold(),
+					new_port = new_port.to_string().bold()
+				)
+			);
 
-				argon_warn!(
-					"Port {} is already in use, using {} instead!",
-					port.to_string().bold(),
-					new_port.to_string().bold()
-				);
+			Ok(new_port)
+		} else {
+			bail!(
+				"Port {} is already in use! Enable {} setting to use first available port automatically",
+				port.to_string().bold(),
+				"scan_ports".bold()
+			);
+		}
+	}
 
-				port = new_port;
-			} else {
-				bail!(
-					"Port {} is already in use! Enable {} setting to use first available port automatically",
-					port.to_string().bold(),
-					"scan_ports".bold()
-				);
+	/// Mirror port is never scanned for, consumers are told where it is
+	fn check_mirror_port(host: &str, port: u16, mirror_port: Option<u16>) -> Result<()> {
+		let Some(mirror_port) = mirror_port else {
+			return Ok(());
+		};
+
+		if mirror_port == port {
+			bail!(
+				"Mirror port {} has to be different from the server port",
+				mirror_port.to_string().bold()
+			);
+		}
+
+		if !server::is_port_free(host, mirror_port) {
+			bail!("Mirror port {} is already in use!", mirror_port.to_string().bold());
+		}
+
+		Ok(())
+	}
+
+	fn announce_mirror(host: &str, mirror_port: u16) {
+		argon_info!(
+			"{}",
+			t!(
+				"serve.mirroring",
+				address = server::format_address(host, mirror_port).bold()
+			)
+		);
+	}
+
+	fn watch_sourcemap(core: Arc<Core>, path: PathBuf) -> Result<()> {
+		let queue = core.queue();
+
+		queue.subscribe_internal().unwrap();
+		core.sourcemap(Some(path.clone()), false)?;
+
+		argon_info!("{}", t!("sourcemap.generated_at", path = path.to_string().bold()));
+
+		thread::spawn(move || loop {
+			let _message = queue.get(0).unwrap();
+
+			info!("Regenerating sourcemap..");
+
+			match core.sourcemap(Some(path.clone()), false) {
+				Ok(()) => (),
+				Err(err) => {
+					argon_error!("{}", t!("sourcemap.regenerate_failed", error = err));
+				}
+			}
+		});
+
+		Ok(())
+	}
+
+	/// Checkpoints client queues periodically and once more before
+	/// shutdown so clients can continue after the server restarts
+	fn record_trace(core: Arc<Core>, path: &Path, redact: bool) -> Result<()> {
+		let path = path.resolve()?;
+		core.record_trace(&path, redact)?;
+
+		sessions::on_shutdown(move || {
+			if let Err(err) = core.finish_trace() {
+				warn!("Failed to finish trace of {}: {}", core.name(), err);
 			}
+		});
+
+		argon_info!("{}", t!("serve.recording_trace", path = path.to_string().bold()));
+
+		Ok(())
+	}
+
+	fn checkpoint_queues(cores: Vec<Arc<Core>>) {
+		if !Config::new().durable_queues {
+			return;
 		}
 
-		let core = Arc::new(core);
+		let checkpoint = move || {
+			for core in &cores {
+				if let Err(err) = core.checkpoint_queues() {
+					warn!("Failed to checkpoint client queues of {}: {}", core.name(), err);
+				}
+			}
+		};
 
-		if let Some(path) = sourcemap_path {
-			let core = core.clone();
-			let queue = core.queue();
+		sessions::on_shutdown(checkpoint.clone());
 
-			queue.subscribe_internal().unwrap();
-			core.sourcemap(Some(path.clone()), false)?;
+		thread::spawn(move || loop {
+			thread::sleep(QUEUE_CHECKPOINT_INTERVAL);
+			checkpoint();
+		});
+	}
 
-			argon_info!("Generated sourcemap at: {}", path.to_string().bold());
+	/// Saves trees clients had when the server stops,
+	/// so the next session knows which side changed
+	fn save_sync_bases(cores: Vec<Arc<Core>>) {
+		sessions::on_shutdown(move || {
+			for core in cores.iter().filter(|core| core.is_synced(None)) {
+				if let Err(err) = core.save_sync_base() {
+					warn!("Failed to save sync base of {}: {}", core.name(), err);
+				}
+			}
+		});
+	}
 
-			thread::spawn(move || loop {
-				let _message = queue.get(0).unwrap();
+	fn send_heartbeats(cores: Vec<Arc<Core>>) {
+		let interval = Config::new().heartbeat_interval;
 
-				info!("Regenerating sourcemap..");
+		if interval == 0 {
+			return;
+		}
 
-				match core.sourcemap(Some(path.clone()), false) {
-					Ok(()) => (),
-					Err(err) => {
-						argon_error!("Failed to regenerate sourcemap: {}", err);
-					}
+		thread::spawn(move || loop {
+			thread::sleep(Duration::from_secs(interval * 60));
+
+			for core in &cores {
+				if let Err(err) = core.heartbeat() {
+					warn!("Failed to send heartbeat to clients of {}: {}", core.name(), err);
 				}
-			});
+			}
+		});
+	}
+
+	fn scan_integrity(cores: Vec<Arc<Core>>) {
+		let interval = Config::new().integrity_scan_interval;
+
+		// Nothing to catch up on if the watcher never drops events
+		let cores: Vec<Arc<Core>> = cores.into_iter().filter(|core| !core.vfs().reliable_events()).collect();
+
+		if interval == 0 || cores.is_empty() {
+			return;
 		}
 
-		sessions::add(
-			self.session,
-			Some(host.clone()),
-			Some(port),
-			process::id(),
-			config.run_async,
-		)?;
+		thread::spawn(move || loop {
+			thread::sleep(Duration::from_secs(interval * 60));
 
-		let server = Server::new(core, &host, port);
+			for core in &cores {
+				core.scan_integrity();
+			}
+		});
+	}
 
-		argon_info!(
-			"Serving on: {}, project: {}",
-			server::format_address(&host, port).bold(),
-			project_path.to_string().bold()
-		);
+	fn spawn(self) -> Result<()> {
+		Program::new(ProgramName::Argon).args(self.get_args()).spawn()?;
 
-		server.start()?;
+		Ok(())
+	}
+
+	fn spawn_daemon(&self, workspace_dir: &Path) -> Result<()> {
+		if let Some((_, session)) = sessions::get_daemon(workspace_dir)? {
+			if util::process_exists(session.pid) {
+				bail!(
+					"Argon daemon is already running for this workspace (PID: {}). Run {} to stop it",
+					session.pid.to_string().bold(),
+					"argon stop".bold()
+				);
+			}
+		}
+
+		let mut args = self.get_args();
+		args.push(String::from("--daemon"));
+
+		let log = daemon::open_log(workspace_dir)?;
+		let child = Program::new(ProgramName::Argon)
+			.args(args)
+			.spawn_detached(log, &daemon::get_log_dir(workspace_dir))?;
+
+		if let Some(child) = child {
+			argon_info!(
+				"{}",
+				t!(
+					"serve.daemon_started",
+					pid = child.id().to_string().bold(),
+					logs = daemon::get_log_path(workspace_dir).to_string().bold()
+				)
+			);
+		}
 
 		Ok(())
 	}
 
-	fn spawn(self) -> Result<()> {
+	fn get_args(&self) -> Vec<String> {
 		let mut args = vec![String::from("serve")];
 
-		if let Some(project) = self.project {
+		if let Some(project) = &self.project {
 			args.push(project.to_string());
 		}
 
-		if let Some(session) = self.session {
-			args.push(session);
+		if let Some(workspace) = &self.workspace {
+			args.push(String::from("--workspace"));
+			args.push(workspace.to_string());
+		}
+
+		if let Some(session) = &self.session {
+			args.push(session.to_owned());
 		}
 
-		if let Some(host) = self.host {
+		if let Some(host) = &self.host {
 			args.push(String::from("--host"));
-			args.push(host)
+			args.push(host.to_owned())
 		}
 
 		if let Some(port) = self.port {
@@ -214,6 +609,16 @@ impl Serve {
 			args.push(port.to_string());
 		}
 
+		if let Some(mirror_port) = self.mirror_port {
+			args.push(String::from("--mirror-port"));
+			args.push(mirror_port.to_string());
+		}
+
+		if let Some(mirror_token) = &self.mirror_token {
+			args.push(String::from("--mirror-token"));
+			args.push(mirror_token.to_owned());
+		}
+
 		if self.sourcemap {
 			args.push(String::from("--sourcemap"));
 		}
@@ -222,8 +627,24 @@ impl Serve {
 			args.push(String::from("--ts"));
 		}
 
-		Program::new(ProgramName::Argon).args(args).spawn()?;
+		if self.allow_any_place {
+			args.push(String::from("--allow-any-place"));
+		}
 
-		Ok(())
+		if let Some(profile) = &self.profile {
+			args.push(String::from("--profile"));
+			args.push(profile.to_owned());
+		}
+
+		if let Some(record_trace) = &self.record_trace {
+			args.push(String::from("--record-trace"));
+			args.push(record_trace.to_string());
+		}
+
+		if self.redact_trace {
+			args.push(String::from("--redact-trace"));
+		}
+
+		args
 	}
 }
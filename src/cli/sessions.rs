@@ -0,0 +1,66 @@
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+
+use crate::{argon_info, argon_warn, ext::PathExt, logger::Table, sessions, t};
+
+/// List running sessions with their projects and workspace members
+#[derive(Parser)]
+pub struct Sessions {
+	/// What to do with sessions, only `list` is available
+	#[arg(hide_possible_values = true)]
+	mode: Option<SessionsMode>,
+}
+
+impl Sessions {
+	pub fn main(self) -> Result<()> {
+		match self.mode.unwrap_or_default() {
+			SessionsMode::List => {
+				let mut sessions: Vec<_> = sessions::get_all()?.into_iter().collect();
+
+				if sessions.is_empty() {
+					argon_warn!("{}", t!("sessions.none"));
+					return Ok(());
+				}
+
+				sessions.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+				let mut table = Table::new();
+				table.set_header(vec!["ID", "Address", "PID", "Project"]);
+
+				for (id, session) in sessions {
+					// Workspace members are listed below the workspace file
+					let project = if let Some(workspace) = &session.workspace {
+						let mut lines = vec![workspace.to_string()];
+						lines.extend(session.members.iter().map(|member| format!("- {}", member.to_string())));
+						lines.join("\n")
+					} else if let Some(project) = &session.project {
+						project.to_string()
+					} else if let Some(daemon) = &session.daemon {
+						daemon.to_string()
+					} else {
+						String::from("None")
+					};
+
+					// Read-only mirror is listed below the primary address
+					let mut address = session.get_address().unwrap_or(String::from("None"));
+
+					if let Some(mirror) = session.get_mirror_address() {
+						address.push_str(&format!("\n{} (mirror)", mirror));
+					}
+
+					table.add_row(vec![id, address, session.pid.to_string(), project]);
+				}
+
+				argon_info!("{}", t!("sessions.list", table = table));
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Clone, Default, ValueEnum)]
+enum SessionsMode {
+	#[default]
+	List,
+}
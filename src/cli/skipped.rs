@@ -0,0 +1,146 @@
+use anyhow::{bail, Context, Result};
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use clap::Parser;
+use reqwest::blocking::Client;
+
+use crate::{
+	argon_info, argon_warn,
+	core::skipped::{RuleKind, SkippedEntry, SkippedQuery},
+	logger::Table,
+	sessions, t,
+};
+
+/// Show syncback operations that were skipped by the filter (requires running session)
+#[derive(Parser)]
+pub struct Skipped {
+	/// Session identifier
+	#[arg()]
+	session: Option<String>,
+
+	/// Server host name
+	#[arg(short = 'H', long)]
+	host: Option<String>,
+
+	/// Server port
+	#[arg(short = 'P', long)]
+	port: Option<u16>,
+
+	/// Only operations skipped since the time (e.g. `30m`, `2h`, `2024-09-06 15:30`)
+	#[arg(short, long, value_parser = parse_time)]
+	since: Option<i64>,
+
+	/// Only operations skipped until the time, same format as `--since`
+	#[arg(short, long, value_parser = parse_time)]
+	until: Option<i64>,
+
+	/// Only operations skipped by rules of this kind
+	#[arg(short, long)]
+	kind: Option<RuleKind>,
+
+	/// Only operations skipped by the rule with this exact pattern
+	#[arg(short, long)]
+	rule: Option<String>,
+
+	/// Output results as JSON
+	#[arg(short, long)]
+	json: bool,
+}
+
+impl Skipped {
+	pub fn main(self) -> Result<()> {
+		let address = sessions::get(self.session, self.host, self.port)?
+			.and_then(|session| session.get_address())
+			.context("No running session with an address was found")?;
+
+		let query = SkippedQuery {
+			since: self.since,
+			until: self.until,
+			kind: self.kind,
+			pattern: self.rule,
+		};
+
+		let response = Client::new().get(format!("{}/skipped", address)).query(&query).send()?;
+
+		if !response.status().is_success() {
+			bail!("Failed to get skipped operations: {}", response.text()?);
+		}
+
+		let entries: Vec<SkippedEntry> = response.json()?;
+
+		if self.json {
+			println!("{}", serde_json::to_string_pretty(&entries)?);
+			return Ok(());
+		}
+
+		if entries.is_empty() {
+			argon_warn!("{}", t!("skipped.none"));
+			return Ok(());
+		}
+
+		let mut table = Table::new();
+		table.set_header(vec!["Path", "Operation", "Rule", "File", "Count", "Last"]);
+
+		for entry in &entries {
+			table.add_row(vec![
+				entry.path.clone(),
+				entry.operation.to_string(),
+				entry.rule.to_string(),
+				entry
+					.file
+					.as_ref()
+					.map(|file| file.display().to_string())
+					.unwrap_or_default(),
+				entry.count.to_string(),
+				format_time(entry.last),
+			]);
+		}
+
+		argon_info!("{}", t!("skipped.skipped", count = entries.len(), table = table));
+
+		Ok(())
+	}
+}
+
+/// Parses time relative to now like `90s`, `30m`, `2h` or `7d`,
+/// or local date and time like `2024-09-06 15:30` to Unix timestamp
+fn parse_time(time: &str) -> Result<i64> {
+	let time = time.trim();
+
+	if let Some(unit) = time.chars().last().filter(|unit| unit.is_ascii_alphabetic()) {
+		let amount: i64 = time[..time.len() - 1]
+			.parse()
+			.with_context(|| format!("Invalid time: {}", time))?;
+
+		let seconds = match unit {
+			's' => amount,
+			'm' => amount * 60,
+			'h' => amount * 3600,
+			'd' => amount * 86400,
+			_ => bail!("Invalid time unit: {}, expected `s`, `m`, `h` or `d`", unit),
+		};
+
+		return Ok(Utc::now().timestamp() - seconds);
+	}
+
+	let date = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+		.iter()
+		.find_map(|format| NaiveDateTime::parse_from_str(time, format).ok())
+		.or_else(|| {
+			NaiveDate::parse_from_str(time, "%Y-%m-%d")
+				.ok()
+				.and_then(|date| date.and_hms_opt(0, 0, 0))
+		})
+		.with_context(|| format!("Invalid time: {}", time))?;
+
+	Local
+		.from_local_datetime(&date)
+		.earliest()
+		.map(|date| date.timestamp())
+		.with_context(|| format!("Invalid local time: {}", time))
+}
+
+fn format_time(timestamp: i64) -> String {
+	chrono::DateTime::from_timestamp(timestamp, 0)
+		.map(|time| time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+		.unwrap_or_default()
+}
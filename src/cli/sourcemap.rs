@@ -7,18 +7,18 @@ use std::{path::PathBuf, process};
 use crate::{
 	argon_info,
 	config::Config,
-	core::Core,
+	core::{lifecycle, Core},
 	ext::PathExt,
 	program::{Program, ProgramName},
 	project::{self, Project},
-	sessions,
+	sessions, t,
 };
 
 /// Generate JSON sourcemap of the project
 #[derive(Parser)]
 pub struct Sourcemap {
 	/// Project path
-	#[arg()]
+	#[arg(conflicts_with = "stdin")]
 	project: Option<PathBuf>,
 
 	/// Session identifier
@@ -37,6 +37,18 @@ pub struct Sourcemap {
 	#[arg(short, long)]
 	non_scripts: bool,
 
+	/// Build profile to use, overrides `ARGON_PROFILE` and the `profile` setting
+	#[arg(long)]
+	profile: Option<String>,
+
+	/// Read project from stdin instead of a file, requires `--workspace-dir`
+	#[arg(long, visible_alias = "stdin-project", conflicts_with = "watch")]
+	stdin: bool,
+
+	/// Directory `$path` entries of the project read from stdin are relative to
+	#[arg(long, requires = "stdin")]
+	workspace_dir: Option<PathBuf>,
+
 	/// Run Argon asynchronously
 	#[arg(short = 'A', long = "async")]
 	run_async: bool,
@@ -48,7 +60,11 @@ pub struct Sourcemap {
 
 impl Sourcemap {
 	pub fn main(mut self) -> Result<()> {
-		let project_path = project::resolve(self.project.clone().unwrap_or_default())?;
+		let project_path = if self.stdin {
+			project::stdin_path(self.workspace_dir.as_deref())?
+		} else {
+			project::resolve(self.project.clone().unwrap_or_default())?
+		};
 
 		Config::load_workspace(project_path.get_parent());
 		let config = Config::new();
@@ -57,7 +73,7 @@ impl Sourcemap {
 			return self.spawn();
 		}
 
-		if !project_path.exists() {
+		if !self.stdin && !project_path.exists() {
 			bail!(
 				"No project files found in {}",
 				project_path.get_parent().to_string().bold()
@@ -76,24 +92,42 @@ impl Sourcemap {
 			}
 		}
 
-		let project = Project::load(&project_path)?;
+		project::set_profile(self.profile.clone());
+
+		let project = if self.stdin {
+			project::load_stdin(&project_path)?
+		} else {
+			Project::load(&project_path)?
+		};
 		let core = Core::new(project, self.watch)?;
+		lifecycle::print_status(&core);
 
 		core.sourcemap(self.output.clone(), self.non_scripts)?;
 
 		if let Some(output) = &self.output {
 			argon_info!(
-				"Generated sourcemap of project: {} at: {}",
-				project_path.to_string().bold(),
-				output.resolve()?.to_string().bold()
+				"{}",
+				t!(
+					"sourcemap.generated",
+					project = project_path.to_string().bold(),
+					path = output.resolve()?.to_string().bold()
+				)
 			);
 		}
 
 		if self.watch {
-			sessions::add(self.session, None, None, process::id(), config.run_async)?;
+			sessions::add(
+				self.session,
+				None,
+				None,
+				process::id(),
+				&project_path,
+				None,
+				config.run_async,
+			)?;
 
 			if self.output.is_some() {
-				argon_info!("Watching for changes..");
+				argon_info!("{}", t!("common.watching"));
 			}
 
 			let queue = core.queue();
@@ -134,6 +168,11 @@ impl Sourcemap {
 			args.push(String::from("--non-scripts"))
 		}
 
+		if let Some(profile) = self.profile {
+			args.push(String::from("--profile"));
+			args.push(profile);
+		}
+
 		Program::new(ProgramName::Argon).args(args).spawn()?;
 
 		Ok(())
@@ -0,0 +1,141 @@
+use anyhow::Result;
+use clap::Parser;
+use colored::Colorize;
+use reqwest::blocking::Client;
+use std::{path::PathBuf, time::Duration};
+
+use crate::{
+	argon_info, argon_warn,
+	core::{frozen::FrozenInstance, pending},
+	daemon,
+	ext::PathExt,
+	logger::Table,
+	project,
+	server::Health,
+	sessions, t, util,
+};
+
+/// Show whether the workspace daemon is running and healthy
+#[derive(Parser)]
+pub struct Status {
+	/// Project path
+	#[arg()]
+	project: Option<PathBuf>,
+}
+
+impl Status {
+	pub fn main(self) -> Result<()> {
+		let project_path = project::resolve(self.project.unwrap_or_default())?;
+		let workspace_dir = project_path.get_parent();
+
+		let (id, session) = match sessions::get_daemon(workspace_dir)? {
+			Some((id, session)) if util::process_exists(session.pid) => (id, session),
+			_ => {
+				argon_warn!("{}", t!("status.no_daemon", path = workspace_dir.to_string().bold()));
+				return Ok(());
+			}
+		};
+
+		let address = session.get_address().unwrap_or_default();
+		let health = Self::get_health(&address);
+
+		let mut table = Table::new();
+		table.set_header(vec!["ID", "Address", "PID", "Health", "Clients"]);
+
+		table.add_row(vec![
+			id,
+			address.clone(),
+			session.pid.to_string(),
+			if health.is_some() {
+				String::from("Healthy")
+			} else {
+				String::from("Unreachable")
+			},
+			health
+				.as_ref()
+				.map_or(String::from("None"), |health| health.clients.to_string()),
+		]);
+
+		argon_info!(
+			"{}",
+			t!(
+				"status.status",
+				table = table,
+				logs = daemon::get_log_path(workspace_dir).to_string().bold()
+			)
+		);
+
+		if let Some(pause) = health.as_ref().map(|health| health.pause).filter(|pause| pause.paused) {
+			let backlog = pause.backlog.to_string().bold();
+
+			match pause.resumes_in {
+				Some(seconds) => argon_info!(
+					"{}",
+					t!(
+						"status.paused_until",
+						backlog = backlog,
+						resumes_in = pending::format_age(seconds).bold()
+					)
+				),
+				None => argon_info!("{}", t!("status.paused", backlog = backlog)),
+			}
+		}
+
+		if let Some(health) = health.filter(|health| health.pending > 0) {
+			argon_info!(
+				"{}",
+				t!(
+					"status.pending",
+					count = health.pending.to_string().bold(),
+					oldest = pending::format_age(health.oldest_pending.unwrap_or_default()).bold()
+				)
+			);
+		}
+
+		for instance in Self::get_frozen(&address) {
+			let since = chrono::DateTime::from_timestamp(instance.since, 0)
+				.map(|time| {
+					time.with_timezone(&chrono::Local)
+						.format("%Y-%m-%d %H:%M:%S")
+						.to_string()
+				})
+				.unwrap_or_default();
+
+			argon_info!(
+				"{}",
+				t!(
+					"status.frozen",
+					path = instance.path.bold(),
+					count = instance.suppressed().to_string().bold(),
+					since = since
+				)
+			);
+		}
+
+		Ok(())
+	}
+
+	fn get_frozen(address: &str) -> Vec<FrozenInstance> {
+		let url = format!("{}/frozen", address);
+
+		Client::new()
+			.get(url)
+			.timeout(Duration::from_secs(3))
+			.send()
+			.and_then(|response| response.error_for_status())
+			.and_then(|response| response.json())
+			.unwrap_or_default()
+	}
+
+	fn get_health(address: &str) -> Option<Health> {
+		let url = format!("{}/health", address);
+
+		Client::new()
+			.get(url)
+			.timeout(Duration::from_secs(3))
+			.send()
+			.and_then(|response| response.error_for_status())
+			.and_then(|response| response.json())
+			.ok()
+	}
+}
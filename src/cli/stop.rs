@@ -3,7 +3,7 @@ use clap::Parser;
 use colored::Colorize;
 use reqwest::blocking::Client;
 
-use crate::{argon_info, argon_warn, logger::Table, sessions, util};
+use crate::{argon_info, argon_warn, logger::Table, sessions, t, util};
 
 /// Stop Argon session by address, ID or all running sessions
 #[derive(Parser)]
@@ -35,7 +35,7 @@ impl Stop {
 			let sessions = sessions::get_all()?;
 
 			if sessions.is_empty() {
-				argon_warn!("There are no running sessions");
+				argon_warn!("{}", t!("stop.no_sessions"));
 				return Ok(());
 			}
 
@@ -57,7 +57,7 @@ impl Stop {
 				]);
 			}
 
-			argon_info!("All running sessions:\n\n{}", table);
+			argon_info!("{}", t!("stop.sessions", table = table));
 
 			return Ok(());
 		}
@@ -66,7 +66,7 @@ impl Stop {
 			let sessions = sessions::get_all()?;
 
 			if sessions.is_empty() {
-				argon_warn!("There are no running sessions");
+				argon_warn!("{}", t!("stop.no_sessions"));
 				return Ok(());
 			}
 
@@ -91,13 +91,13 @@ impl Stop {
 
 				sessions::remove(&session)?;
 			} else {
-				argon_warn!("There is no matching session to stop");
+				argon_warn!("{}", t!("stop.no_match"));
 			}
 		} else {
 			let sessions = sessions::get_multiple(&self.session)?;
 
 			if sessions.is_empty() {
-				argon_warn!("There are no running sessions with provided IDs");
+				argon_warn!("{}", t!("stop.no_ids"));
 			} else {
 				for session in sessions.values() {
 					if let Some(address) = session.get_address() {
@@ -118,7 +118,7 @@ impl Stop {
 		let url = format!("{}/stop", address);
 
 		match Client::new().post(url).send() {
-			Ok(_) => argon_info!("Stopped Argon session with address: {}", address.bold()),
+			Ok(_) => argon_info!("{}", t!("stop.stopped_session", address = address.bold())),
 			Err(_) => {
 				Self::kill_process(pid);
 			}
@@ -127,6 +127,6 @@ impl Stop {
 
 	fn kill_process(pid: u32) {
 		util::kill_process(pid);
-		argon_info!("Stopped Argon process with PID: {}", pid.to_string().bold())
+		argon_info!("{}", t!("stop.stopped_process", pid = pid.to_string().bold()))
 	}
 }
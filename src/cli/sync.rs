@@ -0,0 +1,67 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use reqwest::{blocking::Client, header::CONTENT_TYPE};
+
+use crate::{argon_info, core::frozen::SyncDirection, server::frozen::FrozenSyncRequest, sessions, t};
+
+/// Manually sync the frozen instance once in the given direction
+#[derive(Parser)]
+pub struct Sync {
+	/// Instance path (e.g. `Workspace.Terrain`)
+	#[arg()]
+	path: String,
+
+	/// Session identifier
+	#[arg()]
+	session: Option<String>,
+
+	/// Whether disk or Studio changes are synced: to-studio, to-disk
+	#[arg(short, long, hide_possible_values = true)]
+	direction: SyncDirection,
+
+	/// Server host name
+	#[arg(short = 'H', long)]
+	host: Option<String>,
+
+	/// Server port
+	#[arg(short = 'P', long)]
+	port: Option<u16>,
+}
+
+impl Sync {
+	pub fn main(self) -> Result<()> {
+		let address = sessions::get(self.session, self.host, self.port)?
+			.and_then(|session| session.get_address())
+			.context("No running session with an address was found")?;
+
+		let body = rmp_serde::to_vec(&FrozenSyncRequest {
+			path: self.path.clone(),
+			direction: self.direction,
+		})?;
+
+		let response = Client::new()
+			.post(format!("{}/frozen/sync", address))
+			.header(CONTENT_TYPE, "application/msgpack")
+			.body(body)
+			.send()?;
+
+		if !response.status().is_success() {
+			bail!("Failed to sync {}: {}", self.path, response.text()?);
+		}
+
+		let total: usize = response.json()?;
+
+		argon_info!(
+			"{}",
+			t!(
+				"sync.synced",
+				count = total.to_string().bold(),
+				path = self.path.bold(),
+				direction = self.direction.to_string().bold()
+			)
+		);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,148 @@
+use anyhow::{bail, Result};
+use clap::{Parser, ValueEnum};
+use reqwest::{blocking::Client, header::CONTENT_TYPE, StatusCode};
+use std::{fs, path::PathBuf, process, time::Duration};
+
+use super::exec;
+use crate::{
+	argon_error, argon_info,
+	core::test_runs::TestRun,
+	ext::PathExt,
+	sessions, t,
+	test_runner::{self, TestBridge, TestOptions},
+};
+
+/// Run TestEZ specs in Roblox Studio and report their results (requires running session)
+#[derive(Parser)]
+pub struct Test {
+	/// Session identifier
+	#[arg()]
+	session: Option<String>,
+
+	/// Instance containing the specs, separated with `/`
+	#[arg(short, long, default_value = "ReplicatedStorage/Tests")]
+	root: String,
+
+	/// Runner module separated with `/`, TestEZ is searched for in common locations by default
+	#[arg(long)]
+	runner: Option<String>,
+
+	/// Format of the results
+	#[arg(short = 'R', long, default_value = "text")]
+	reporter: Reporter,
+
+	/// Write the results to this file instead of printing them
+	#[arg(short, long)]
+	output: Option<PathBuf>,
+
+	/// Seconds the whole run can take
+	#[arg(short, long, default_value_t = 300)]
+	timeout: u64,
+
+	/// Server host name
+	#[arg(short = 'H', long)]
+	host: Option<String>,
+
+	/// Server port
+	#[arg(short = 'P', long)]
+	port: Option<u16>,
+}
+
+impl Test {
+	pub fn main(self) -> Result<()> {
+		let Some(session) = sessions::get(self.session.clone(), self.host.clone(), self.port)? else {
+			bail!("{}", t!("test.no_session"));
+		};
+
+		let Some(address) = session.get_address() else {
+			bail!("{}", t!("test.no_address"));
+		};
+
+		let options = TestOptions {
+			root: self.root.clone(),
+			runner: self.runner.clone(),
+			timeout: Duration::from_secs(self.timeout),
+			start_timeout: Duration::from_secs(self.timeout.min(15)),
+		};
+
+		let bridge = HttpBridge {
+			client: Client::new(),
+			address: address.clone(),
+		};
+
+		let run = test_runner::run(&bridge, &options, &format!("{}/test-results", address))?;
+
+		let results = match self.reporter {
+			Reporter::Text => test_runner::text(&run),
+			Reporter::Junit => test_runner::junit(&run),
+		};
+
+		if let Some(output) = &self.output {
+			fs::write(output, &results)?;
+			argon_info!("{}", t!("test.written", path = output.to_string()));
+		} else {
+			println!("{}", results);
+		}
+
+		if let Some(error) = &run.error {
+			argon_error!("{}", t!("test.error", error = error.message));
+		}
+
+		let code = test_runner::exit_code(&run);
+
+		if code != 0 {
+			process::exit(code);
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Clone, Default, ValueEnum)]
+enum Reporter {
+	#[default]
+	Text,
+	Junit,
+}
+
+/// Executes the bootstrap with the `/exec` endpoint
+/// and polls `/test-results` of the running session
+struct HttpBridge {
+	client: Client,
+	address: String,
+}
+
+impl TestBridge for HttpBridge {
+	fn execute(&self, code: &str) -> Result<()> {
+		let body = rmp_serde::to_vec(&exec::Request {
+			code: code.to_owned(),
+			focus: false,
+		})?;
+
+		let response = self
+			.client
+			.post(format!("{}/exec", self.address))
+			.header(CONTENT_TYPE, "application/msgpack")
+			.body(body)
+			.send()?;
+
+		if !response.status().is_success() {
+			bail!("Failed to execute test bootstrap: {}", response.text()?);
+		}
+
+		Ok(())
+	}
+
+	fn poll(&self, run_id: &str) -> Result<Option<TestRun>> {
+		let response = self
+			.client
+			.get(format!("{}/test-results/{}", self.address, run_id))
+			.send()?;
+
+		match response.status() {
+			StatusCode::NOT_FOUND => Ok(None),
+			status if status.is_success() => Ok(Some(response.json()?)),
+			_ => bail!("Failed to get test results: {}", response.text()?),
+		}
+	}
+}
@@ -0,0 +1,156 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::Colorize;
+use log::{info, trace};
+use std::{fs, path::PathBuf, process};
+
+use crate::{
+	argon_info,
+	config::Config,
+	constants::TYPEGEN_DEBOUNCE_TIME,
+	core::{
+		lifecycle,
+		typegen::{self, TypegenOptions},
+		Core,
+	},
+	ext::PathExt,
+	project::{self, Project},
+	server::Message,
+	sessions, t,
+};
+
+/// Generate Luau type definitions of the project tree
+#[derive(Parser)]
+pub struct Typegen {
+	/// Project path
+	#[arg()]
+	project: Option<PathBuf>,
+
+	/// Session identifier
+	#[arg()]
+	session: Option<String>,
+
+	/// Output path
+	#[arg(short, long, default_value = "types/GameTree.luau")]
+	output: PathBuf,
+
+	/// Regenerate types every time project structure changes
+	#[arg(short, long)]
+	watch: bool,
+
+	/// Maximum depth of nested instances
+	#[arg(short, long)]
+	depth: Option<usize>,
+
+	/// Path of the instance to generate types for, e.g. `ReplicatedStorage/Shared`
+	#[arg(short, long)]
+	root: Option<String>,
+
+	/// Build profile to use, overrides `ARGON_PROFILE` and the `profile` setting
+	#[arg(long)]
+	profile: Option<String>,
+}
+
+impl Typegen {
+	pub fn main(self) -> Result<()> {
+		let project_path = project::resolve(self.project.clone().unwrap_or_default())?;
+
+		Config::load_workspace(project_path.get_parent());
+
+		if !project_path.exists() {
+			bail!(
+				"No project files found in {}",
+				project_path.get_parent().to_string().bold()
+			);
+		}
+
+		let options = TypegenOptions {
+			name: self.output.get_stem().to_owned(),
+			depth: self.depth,
+			root: self.root.clone(),
+		};
+
+		project::set_profile(self.profile.clone());
+
+		let project = Project::load(&project_path)?;
+		let core = Core::new(project, self.watch)?;
+		lifecycle::print_status(&core);
+
+		let mut types = core.typegen(&options)?;
+		self.write(&types)?;
+
+		argon_info!(
+			"{}",
+			t!(
+				"typegen.generated",
+				project = project_path.to_string().bold(),
+				path = self.output.resolve()?.to_string().bold()
+			)
+		);
+
+		if !self.watch {
+			return Ok(());
+		}
+
+		sessions::add(
+			self.session.clone(),
+			None,
+			None,
+			process::id(),
+			&project_path,
+			None,
+			false,
+		)?;
+
+		argon_info!("{}", t!("common.watching"));
+
+		let queue = core.queue();
+		queue.subscribe_internal().unwrap();
+
+		loop {
+			let mut structural = false;
+
+			if let Some(message) = queue.get(0)? {
+				structural |= is_structural(&message);
+			}
+
+			// Batch changes that arrive in quick succession
+			while let Some(message) = queue.get_with_timeout(0, TYPEGEN_DEBOUNCE_TIME)? {
+				structural |= is_structural(&message);
+			}
+
+			if !structural {
+				trace!("Only properties changed, skipping type regeneration..");
+				continue;
+			}
+
+			let regenerated = core.typegen(&options)?;
+
+			if regenerated != types {
+				info!("Regenerating types..");
+
+				types = regenerated;
+				self.write(&types)?;
+			}
+		}
+	}
+
+	fn write(&self, types: &str) -> Result<()> {
+		if let Some(parent) = self.output.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		fs::write(&self.output, types)?;
+
+		Ok(())
+	}
+}
+
+fn is_structural(message: &Message) -> bool {
+	match message {
+		Message::SyncChanges(changes) => typegen::is_structural(&changes.0),
+		Message::SyncBlobChanges(changes) => typegen::is_structural(&changes.changes),
+		Message::SyncbackChanges(_) => true,
+		_ => false,
+	}
+}
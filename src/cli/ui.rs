@@ -0,0 +1,242 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use crossbeam_channel::Receiver;
+use ratatui::{
+	crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+	DefaultTerminal,
+};
+use std::{
+	env,
+	path::PathBuf,
+	process::Command,
+	thread,
+	time::{Duration, Instant},
+};
+
+use crate::{
+	argon_warn,
+	dashboard::{
+		client::{Message, Poller, SessionClient},
+		render::{self, Overlay},
+		Dashboard, FeedEvent,
+	},
+	ext::PathExt,
+	logger,
+	program::{Program, ProgramName},
+	project, sessions, t, util,
+};
+
+/// How long to wait for key presses before redrawing
+const TICK: Duration = Duration::from_millis(100);
+/// How long to wait for a newly started session to show up
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Open read-only dashboard of running sessions
+#[derive(Parser)]
+pub struct Ui {
+	/// Session identifier, all running sessions are shown by default
+	#[arg()]
+	session: Option<String>,
+
+	/// Project to serve if there is no running session
+	#[arg(short, long)]
+	project: Option<PathBuf>,
+
+	/// How often to refresh the dashboard (ms)
+	#[arg(short, long, default_value_t = 1000)]
+	interval: u64,
+}
+
+impl Ui {
+	pub fn main(self) -> Result<()> {
+		let mut sessions = self.get_sessions()?;
+
+		if sessions.is_empty() {
+			argon_warn!("{}", t!("ui.no_sessions"));
+
+			if !logger::prompt(&t!("ui.start_session"), false) {
+				return Ok(());
+			}
+
+			sessions = self.start_session()?;
+		}
+
+		let mut dashboards = vec![];
+		let mut receivers = vec![];
+		let mut clients = vec![];
+
+		for (id, address) in sessions {
+			let (sender, receiver) = crossbeam_channel::unbounded();
+			let client = SessionClient::new(&address);
+
+			Poller::new(client.clone()).spawn(Duration::from_millis(self.interval), sender);
+
+			dashboards.push(Dashboard::new(&id, &address));
+			receivers.push(receiver);
+			clients.push(client);
+		}
+
+		let mut terminal = ratatui::init();
+		let result = run(&mut terminal, dashboards, &receivers, &clients);
+
+		ratatui::restore();
+
+		result
+	}
+
+	/// Returns IDs and addresses of running sessions
+	fn get_sessions(&self) -> Result<Vec<(String, String)>> {
+		let mut sessions: Vec<(String, String)> = sessions::get_all()?
+			.into_iter()
+			.filter(|(id, _)| self.session.as_ref().is_none_or(|session| session == id))
+			.filter(|(_, session)| util::process_exists(session.pid))
+			.filter_map(|(id, session)| session.get_address().map(|address| (id, address)))
+			.collect();
+
+		sessions.sort();
+
+		Ok(sessions)
+	}
+
+	fn start_session(&self) -> Result<Vec<(String, String)>> {
+		let project_path = project::resolve(self.project.clone().unwrap_or_default())?;
+
+		let mut program = Program::new(ProgramName::Argon);
+		program.arg("serve").arg(project_path.to_string()).arg("--daemon");
+
+		if let Some(session) = &self.session {
+			program.arg(session);
+		}
+
+		program.output()?;
+
+		let start = Instant::now();
+
+		while start.elapsed() < START_TIMEOUT {
+			thread::sleep(Duration::from_millis(500));
+
+			let sessions = self.get_sessions()?;
+
+			if !sessions.is_empty() {
+				return Ok(sessions);
+			}
+		}
+
+		bail!("{}", t!("ui.start_failed", path = project_path.to_string().bold()))
+	}
+}
+
+fn run(
+	terminal: &mut DefaultTerminal,
+	mut dashboards: Vec<Dashboard>,
+	receivers: &[Receiver<Message>],
+	clients: &[SessionClient],
+) -> Result<()> {
+	let mut current = 0;
+	let mut input: Option<String> = None;
+	let mut notice: Option<String> = None;
+
+	loop {
+		for (dashboard, receiver) in dashboards.iter_mut().zip(receivers) {
+			for message in receiver.try_iter() {
+				dashboard.apply(message);
+			}
+		}
+
+		terminal.draw(|frame| {
+			let overlay = Overlay {
+				input: input.as_deref(),
+				notice: notice.as_deref(),
+			};
+
+			render::draw(frame, &dashboards, current, overlay)
+		})?;
+
+		if !event::poll(TICK)? {
+			continue;
+		}
+
+		let Event::Key(key) = event::read()? else {
+			continue;
+		};
+
+		if key.kind != KeyEventKind::Press {
+			continue;
+		}
+
+		let dashboard = &mut dashboards[current];
+
+		// Typing the filter, it is applied as it changes
+		if let Some(text) = &mut input {
+			match key.code {
+				KeyCode::Enter | KeyCode::Esc => input = None,
+				KeyCode::Backspace => {
+					text.pop();
+					dashboard.set_filter(text);
+				}
+				KeyCode::Char(char) => {
+					text.push(char);
+					dashboard.set_filter(text);
+				}
+				_ => {}
+			}
+
+			continue;
+		}
+
+		notice = None;
+
+		match key.code {
+			KeyCode::Char('q') | KeyCode::Esc => break,
+			KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+			KeyCode::Tab => current = (current + 1) % receivers.len(),
+			KeyCode::BackTab => current = (current + receivers.len() - 1) % receivers.len(),
+			KeyCode::Down | KeyCode::Char('j') => dashboard.select_next(),
+			KeyCode::Up | KeyCode::Char('k') => dashboard.select_previous(),
+			KeyCode::Char(' ') | KeyCode::Char('p') => dashboard.toggle_pause(),
+			KeyCode::Char('/') => input = Some(dashboard.filter().to_owned()),
+			KeyCode::Enter | KeyCode::Char('o') => {
+				let event = dashboard.selected().cloned();
+
+				notice = Some(match open(terminal, &clients[current], event) {
+					Ok(message) => message,
+					Err(err) => err.to_string(),
+				});
+			}
+			_ => {}
+		}
+	}
+
+	Ok(())
+}
+
+/// Opens the first source file of the event's instance in
+/// `$VISUAL` or `$EDITOR`, falls back to the default application
+fn open(terminal: &mut DefaultTerminal, client: &SessionClient, event: Option<FeedEvent>) -> Result<String> {
+	let event = event.context("No event selected")?;
+
+	let file = client
+		.find_files(&event)?
+		.into_iter()
+		.next()
+		.context("Instance of this event has no source file")?;
+
+	match env::var("VISUAL").or_else(|_| env::var("EDITOR")) {
+		Ok(editor) => {
+			let mut words = editor.split_whitespace();
+			let program = words.next().context("Editor command is empty")?;
+
+			ratatui::restore();
+
+			let status = Command::new(program).args(words).arg(&file).status();
+
+			*terminal = ratatui::init();
+
+			status.with_context(|| format!("Failed to run editor {}", program))?;
+		}
+		Err(_) => open::that(&file)?,
+	}
+
+	Ok(format!("Opened {}", file.to_string()))
+}
@@ -0,0 +1,54 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use reqwest::{blocking::Client, header::CONTENT_TYPE};
+
+use crate::{argon_info, server::markers::UndoRequest, sessions, t};
+
+/// Roll the workspace back to the marker added with `argon markers add` (requires running session)
+#[derive(Parser)]
+pub struct Undo {
+	/// Name of the marker to roll back to
+	#[arg(long)]
+	to_marker: String,
+
+	/// Session identifier
+	#[arg()]
+	session: Option<String>,
+
+	/// Server host name
+	#[arg(short = 'H', long)]
+	host: Option<String>,
+
+	/// Server port
+	#[arg(short = 'P', long)]
+	port: Option<u16>,
+}
+
+impl Undo {
+	pub fn main(self) -> Result<()> {
+		let address = sessions::get(self.session, self.host, self.port)?
+			.and_then(|session| session.get_address())
+			.context("No running session with an address was found")?;
+
+		let body = rmp_serde::to_vec(&UndoRequest {
+			marker: self.to_marker.clone(),
+		})?;
+
+		let response = Client::new()
+			.post(format!("{}/undo", address))
+			.header(CONTENT_TYPE, "application/msgpack")
+			.body(body)
+			.send()?;
+
+		if !response.status().is_success() {
+			bail!("Failed to undo: {}", response.text()?);
+		}
+
+		let count: usize = response.json()?;
+
+		argon_info!("{}", t!("undo.undone", count = count, name = self.to_marker.bold()));
+
+		Ok(())
+	}
+}
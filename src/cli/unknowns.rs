@@ -0,0 +1,79 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use reqwest::blocking::Client;
+
+use crate::{argon_info, argon_warn, core::unknowns::UnknownsSummary, logger::Table, sessions, t};
+
+/// Show unknown instances preserved by clients because of `$keepUnknowns` (requires running session)
+#[derive(Parser)]
+pub struct Unknowns {
+	/// Session identifier
+	#[arg()]
+	session: Option<String>,
+
+	/// Server host name
+	#[arg(short = 'H', long)]
+	host: Option<String>,
+
+	/// Server port
+	#[arg(short = 'P', long)]
+	port: Option<u16>,
+
+	/// Output results as JSON
+	#[arg(short, long)]
+	json: bool,
+}
+
+impl Unknowns {
+	pub fn main(self) -> Result<()> {
+		let address = sessions::get(self.session, self.host, self.port)?
+			.and_then(|session| session.get_address())
+			.context("No running session with an address was found")?;
+
+		let response = Client::new().get(format!("{}/unknowns", address)).send()?;
+
+		if !response.status().is_success() {
+			bail!("Failed to get unknown instances: {}", response.text()?);
+		}
+
+		let summaries: Vec<UnknownsSummary> = response.json()?;
+
+		if self.json {
+			println!("{}", serde_json::to_string_pretty(&summaries)?);
+			return Ok(());
+		}
+
+		if summaries.is_empty() {
+			argon_warn!("{}", t!("unknowns.none"));
+			return Ok(());
+		}
+
+		let mut table = Table::new();
+		table.set_header(vec!["Parent", "Classes", "Total"]);
+
+		for summary in &summaries {
+			let classes = summary
+				.classes
+				.iter()
+				.map(|(class, count)| format!("{} x{}", class, count))
+				.collect::<Vec<_>>()
+				.join(", ");
+
+			table.add_row(vec![summary.path.clone(), classes, summary.total.to_string()]);
+		}
+
+		let total: usize = summaries.iter().map(|summary| summary.total).sum();
+
+		argon_info!(
+			"{}",
+			t!(
+				"unknowns.unknowns",
+				count = total,
+				parents = summaries.len(),
+				table = table
+			)
+		);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,71 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::Colorize;
+use std::path::PathBuf;
+
+use crate::{
+	argon_info, argon_warn,
+	ext::PathExt,
+	logger::Table,
+	project, t,
+	validation::{self, Severity},
+};
+
+/// Check the project file for mistakes without serving it
+#[derive(Parser)]
+pub struct Validate {
+	/// Project path
+	#[arg()]
+	project: Option<PathBuf>,
+
+	/// Output problems as JSON
+	#[arg(short, long)]
+	json: bool,
+}
+
+impl Validate {
+	pub fn main(self) -> Result<()> {
+		let project_path = project::resolve(self.project.unwrap_or_default())?;
+
+		if !project_path.exists() {
+			bail!(
+				"No project files found in {}. Run {} to create new one",
+				project_path.get_parent().to_string().bold(),
+				"argon init".bold(),
+			);
+		}
+
+		let problems = validation::validate_file(&project_path);
+
+		if self.json {
+			println!("{}", serde_json::to_string_pretty(&problems)?);
+		} else if problems.is_empty() {
+			argon_info!("{}", t!("validate.valid", path = project_path.to_string().bold()));
+			return Ok(());
+		} else {
+			let mut table = Table::new();
+			table.set_header(vec!["Severity", "Node", "Problem"]);
+
+			for problem in &problems {
+				let severity = match problem.severity {
+					Severity::Error => problem.severity.to_string().red(),
+					Severity::Warning => problem.severity.to_string().yellow(),
+				};
+
+				table.add_row(vec![
+					severity.to_string(),
+					problem.node.clone(),
+					problem.message.clone(),
+				]);
+			}
+
+			argon_warn!("{}", t!("validate.problems", count = problems.len(), table = table));
+		}
+
+		if validation::has_errors(&problems) {
+			bail!("Project {} is not valid", project_path.to_string().bold());
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,648 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use log::{debug, trace, warn};
+use rbx_dom_weak::types::Ref;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use std::{
+	collections::{BTreeSet, HashMap},
+	env,
+	path::{Path, PathBuf},
+	process::{self, Command},
+	thread,
+	time::Duration,
+};
+
+use crate::{
+	argon_error, argon_info, argon_warn,
+	config::Config,
+	constants::MAX_FIND_PAGE_LIMIT,
+	core::{
+		find::{FindPage, FindRequest},
+		lifecycle,
+		meta::{IgnoreRule, Meta, SyncbackFilter},
+		snapshot::Snapshot,
+		tree::Tree,
+		Core,
+	},
+	ext::PathExt,
+	glob::Glob,
+	project::{self, Project},
+	server::{Message, SyncChanges},
+	sessions::{self, Session},
+	t, util,
+};
+
+/// Run a command every time project instances change
+#[derive(Parser)]
+pub struct Watch {
+	/// Project path
+	#[arg()]
+	project: Option<PathBuf>,
+
+	/// Session to attach to, the one serving the project by default,
+	/// identifier of the new session if it is not running
+	#[arg()]
+	session: Option<String>,
+
+	/// Command to run when changes are detected
+	#[arg(short, long)]
+	exec: String,
+
+	/// Only react to changes matching this path glob or class name
+	#[arg(short, long)]
+	filter: Vec<String>,
+
+	/// How long to wait for more changes before running the command (ms)
+	#[arg(short, long, default_value_t = 200)]
+	debounce: u64,
+
+	/// Wait for the first matching change, run the command and exit with its status
+	#[arg(short, long)]
+	once: bool,
+
+	/// Build profile to use, overrides `ARGON_PROFILE` and the `profile` setting
+	#[arg(long)]
+	profile: Option<String>,
+}
+
+impl Watch {
+	pub fn main(self) -> Result<()> {
+		let code = self.run()?;
+		process::exit(code);
+	}
+
+	/// Attaches to the session serving the project or starts a new one,
+	/// returns status of the command once `--once` is set
+	pub fn run(self) -> Result<i32> {
+		let project_path = self.project_path()?;
+
+		match session_address(&sessions::get_all()?, self.session.as_deref(), &project_path)? {
+			Some(address) => self.attach_to(&address, &project_path),
+			None => self.start(&project_path),
+		}
+	}
+
+	/// Follows changes of the session running at the address
+	/// instead of building the tree again
+	pub fn attach(self, address: &str) -> Result<i32> {
+		let project_path = self.project_path()?;
+		self.attach_to(address, &project_path)
+	}
+
+	fn attach_to(self, address: &str, project_path: &Path) -> Result<i32> {
+		let project = Project::load(project_path)?;
+		let filter = WatchFilter::new(&self.filter, &project.workspace_dir)?;
+
+		let mut session = RemoteSession::new(address)?;
+		let debounce = Duration::from_millis(self.debounce);
+
+		argon_info!(
+			"{}",
+			t!(
+				"watch.attached",
+				address = address.bold(),
+				project = project_path.to_string().bold()
+			)
+		);
+
+		self.react(|| {
+			let mut batch = Batch::default();
+
+			while !session.poll(&mut batch, &filter)? {
+				thread::sleep(debounce);
+			}
+
+			// Wait for the storm to settle down so it triggers a single run
+			loop {
+				thread::sleep(debounce);
+
+				if !session.poll(&mut batch, &filter)? {
+					break;
+				}
+			}
+
+			Ok(batch)
+		})
+	}
+
+	/// Builds the tree and watches the file system itself, the session
+	/// entry is only added when watching until stopped
+	fn start(self, project_path: &Path) -> Result<i32> {
+		let project = Project::load(project_path)?;
+		let filter = WatchFilter::new(&self.filter, &project.workspace_dir)?;
+
+		let core = Core::new(project, true)?;
+		lifecycle::print_status(&core);
+		let debounce = Duration::from_millis(self.debounce);
+
+		let _session = if !self.once {
+			Some(SessionEntry(sessions::add(
+				self.session.clone(),
+				None,
+				None,
+				process::id(),
+				project_path,
+				None,
+				false,
+			)?))
+		} else {
+			None
+		};
+
+		let queue = core.queue();
+		queue
+			.subscribe_internal()
+			.context("Failed to subscribe to tree changes")?;
+
+		let mut index = InstanceIndex::new(&core.tree());
+
+		argon_info!("{}", t!("watch.watching", project = project_path.to_string().bold()));
+
+		self.react(|| {
+			let mut batch = Batch::default();
+
+			if let Some(message) = queue.get(0)? {
+				batch.collect(message, &filter, &mut index, &core.tree());
+			}
+
+			// Wait for the storm to settle down so it triggers a single run
+			while let Some(message) = queue.get_with_timeout(0, debounce)? {
+				batch.collect(message, &filter, &mut index, &core.tree());
+			}
+
+			Ok(batch)
+		})
+	}
+
+	fn project_path(&self) -> Result<PathBuf> {
+		let project_path = project::resolve(self.project.clone().unwrap_or_default())?;
+
+		Config::load_workspace(project_path.get_parent());
+
+		if !project_path.exists() {
+			bail!(
+				"No project files found in {}",
+				project_path.get_parent().to_string().bold()
+			);
+		}
+
+		project::set_profile(self.profile.clone());
+
+		Ok(project_path)
+	}
+
+	/// Runs the command for every non-empty batch, until the first one with `--once`
+	fn react(&self, mut next_batch: impl FnMut() -> Result<Batch>) -> Result<i32> {
+		loop {
+			let batch = next_batch()?;
+
+			if batch.is_empty() {
+				trace!("No matching changes in the batch, skipping..");
+				continue;
+			}
+
+			argon_info!(
+				"{}",
+				t!(
+					"watch.detected",
+					count = batch.total().to_string().bold(),
+					command = self.exec.bold()
+				)
+			);
+
+			let code = match batch.command(&self.exec).status() {
+				Ok(status) => {
+					if !status.success() {
+						argon_warn!(
+							"{}",
+							t!(
+								"watch.exit_status",
+								status = status
+									.code()
+									.map_or(String::from("unknown"), |code| code.to_string())
+									.bold()
+							)
+						);
+					}
+
+					status.code().unwrap_or(1)
+				}
+				Err(err) => {
+					argon_error!("{}", t!("watch.failed", error = err));
+					1
+				}
+			};
+
+			if self.once {
+				return Ok(code);
+			}
+		}
+	}
+}
+
+/// Returns address of the running session to attach to, either the one with
+/// given ID or the one serving the project, `None` if a new one has to be started
+pub fn session_address(
+	sessions: &HashMap<String, Session>,
+	id: Option<&str>,
+	project: &Path,
+) -> Result<Option<String>> {
+	let Some(id) = id else {
+		return Ok(sessions::find_serving(sessions, project).and_then(|(_, session)| session.get_address()));
+	};
+
+	match sessions.get(id) {
+		Some(session) if util::process_exists(session.pid) => match session.get_address() {
+			Some(address) => Ok(Some(address)),
+			None => bail!("Session {} has no address to attach to", id.bold()),
+		},
+		_ => Ok(None),
+	}
+}
+
+/// Removes entry of the session when watching stops, also after an error
+struct SessionEntry(Session);
+
+impl Drop for SessionEntry {
+	fn drop(&mut self) {
+		match sessions::remove(&self.0) {
+			Ok(()) => trace!("Session entry removed"),
+			Err(err) => warn!("Failed to remove session entry: {}", err),
+		}
+	}
+}
+
+/// Filter built from `--filter` values, uses the same
+/// matching rules as the project's syncback filter
+struct WatchFilter {
+	inner: SyncbackFilter,
+}
+
+impl WatchFilter {
+	fn new(values: &[String], workspace_dir: &Path) -> Result<Self> {
+		let mut globs = vec![];
+		let mut classes = vec![];
+
+		for value in values {
+			if value.contains(['/', '\\', '*', '.', '?', '[']) {
+				globs.push(Glob::new(value)?);
+			} else {
+				classes.push(value.to_owned());
+			}
+		}
+
+		Ok(Self {
+			inner: SyncbackFilter {
+				ignore_rules: IgnoreRule::from_globs(globs, workspace_dir.to_owned()),
+				ignore_classes: classes,
+				..SyncbackFilter::default()
+			},
+		})
+	}
+
+	fn matches(&self, entry: &Entry) -> bool {
+		if self.inner == SyncbackFilter::default() {
+			return true;
+		}
+
+		self.inner.matches_class(&entry.class) || entry.paths.iter().any(|path| self.inner.matches_path(path))
+	}
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+	name: String,
+	class: String,
+	paths: Vec<PathBuf>,
+}
+
+impl Entry {
+	fn new(name: String, class: &str, meta: &Meta) -> Self {
+		Self {
+			name,
+			class: class.to_owned(),
+			paths: meta.source.paths().into_iter().map(|path| path.to_owned()).collect(),
+		}
+	}
+
+	/// Entry of an instance of the attached session
+	fn remote(name: String, class: String, paths: Vec<PathBuf>) -> Self {
+		Self { name, class, paths }
+	}
+}
+
+/// Keeps track of instances that were already seen,
+/// so removals can still be matched against the filter
+struct InstanceIndex {
+	entries: HashMap<Ref, Entry>,
+}
+
+impl InstanceIndex {
+	fn new(tree: &Tree) -> Self {
+		let mut entries = HashMap::new();
+
+		for (id, meta) in tree.meta_map() {
+			if let (Some(instance), Some(name)) = (tree.get_instance(*id), tree.get_full_name(*id)) {
+				entries.insert(*id, Entry::new(name, &instance.class, meta));
+			}
+		}
+
+		Self { entries }
+	}
+
+	fn refresh(&mut self, id: Ref, tree: &Tree) -> Option<Entry> {
+		if let (Some(instance), Some(meta), Some(name)) =
+			(tree.get_instance(id), tree.get_meta(id), tree.get_full_name(id))
+		{
+			let entry = Entry::new(name, &instance.class, meta);
+			self.entries.insert(id, entry.clone());
+
+			Some(entry)
+		} else {
+			self.entries.get(&id).cloned()
+		}
+	}
+}
+
+/// Events of the running session, deserialized from `/events`
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum RemoteEvent {
+	InstanceAdded {
+		id: Ref,
+		path: String,
+		class: String,
+		#[serde(default)]
+		files: Vec<PathBuf>,
+	},
+	InstanceUpdated {
+		id: Ref,
+		path: String,
+		class: String,
+		#[serde(default)]
+		files: Vec<PathBuf>,
+	},
+	InstanceRemoved {
+		id: Ref,
+	},
+	#[serde(other)]
+	Other,
+}
+
+#[derive(Deserialize)]
+struct RemoteEventPage {
+	events: Vec<RemoteEvent>,
+	latest: u64,
+	resync: bool,
+}
+
+/// Polls events of the session running at the address, its instances
+/// are indexed through `/find` so removals can be matched against the filter
+struct RemoteSession {
+	client: Client,
+	address: String,
+	since: u64,
+	index: InstanceIndex,
+}
+
+impl RemoteSession {
+	fn new(address: &str) -> Result<Self> {
+		let mut session = Self {
+			client: Client::new(),
+			address: address.to_owned(),
+			since: 0,
+			index: InstanceIndex {
+				entries: HashMap::new(),
+			},
+		};
+
+		session.since = session.events(None)?.latest;
+		session.reindex()?;
+
+		Ok(session)
+	}
+
+	fn events(&self, since: Option<u64>) -> Result<RemoteEventPage> {
+		let mut request = self.client.get(format!("{}/events", self.address));
+
+		if let Some(since) = since {
+			request = request.query(&[("since", since)]);
+		}
+
+		let response = request.send()?;
+
+		if !response.status().is_success() {
+			bail!("Failed to get session events: {}", response.text()?);
+		}
+
+		Ok(response.json()?)
+	}
+
+	/// Indexes all instances of the session tree, starts over if it changes in the meantime
+	fn reindex(&mut self) -> Result<()> {
+		let mut request = FindRequest {
+			limit: Some(MAX_FIND_PAGE_LIMIT),
+			..FindRequest::default()
+		};
+
+		self.index.entries.clear();
+
+		loop {
+			let response = self
+				.client
+				.get(format!("{}/find", self.address))
+				.query(&request)
+				.send()?;
+
+			if !response.status().is_success() {
+				bail!("Failed to get session instances: {}", response.text()?);
+			}
+
+			let page: FindPage = response.json()?;
+
+			if page.restart {
+				self.index.entries.clear();
+				request.cursor = None;
+
+				continue;
+			}
+
+			for instance in page.instances {
+				let entry = Entry::remote(
+					instance.path.replace('/', "."),
+					instance.class.to_string(),
+					instance.files,
+				);
+				self.index.entries.insert(instance.id, entry);
+			}
+
+			match page.cursor {
+				Some(cursor) => request.cursor = Some(cursor),
+				None => return Ok(()),
+			}
+		}
+	}
+
+	/// Collects events published since the last poll, returns whether there were any
+	fn poll(&mut self, batch: &mut Batch, filter: &WatchFilter) -> Result<bool> {
+		let page = self.events(Some(self.since))?;
+
+		self.since = page.latest;
+
+		if page.resync {
+			debug!("Missed some session events, indexing the tree again");
+			self.reindex()?;
+
+			return Ok(true);
+		}
+
+		if page.events.is_empty() {
+			return Ok(false);
+		}
+
+		batch.collect_events(page.events, filter, &mut self.index);
+
+		Ok(true)
+	}
+}
+
+#[derive(Default)]
+struct Batch {
+	added: usize,
+	updated: usize,
+	removed: usize,
+	entries: Vec<Entry>,
+}
+
+impl Batch {
+	fn collect(&mut self, message: Message, filter: &WatchFilter, index: &mut InstanceIndex, tree: &Tree) {
+		let changes = match message {
+			Message::SyncChanges(SyncChanges(changes)) => changes,
+			_ => return,
+		};
+
+		debug!("Collecting {} changes", changes.total());
+
+		for snapshot in changes.additions {
+			let snapshot = Snapshot::from(snapshot);
+
+			let mut entries = vec![];
+			let mut stack = vec![&snapshot];
+
+			while let Some(snapshot) = stack.pop() {
+				if let Some(entry) = index.refresh(snapshot.id, tree) {
+					entries.push(entry);
+				}
+
+				// Reversed so descendants are listed in the same order as before
+				stack.extend(snapshot.children.iter().rev());
+			}
+
+			if entries.iter().any(|entry| filter.matches(entry)) {
+				self.added += 1;
+				self.entries.extend(entries);
+			}
+		}
+
+		for snapshot in changes.updates {
+			if let Some(entry) = index.refresh(snapshot.id, tree) {
+				if filter.matches(&entry) {
+					self.updated += 1;
+					self.entries.push(entry);
+				}
+			}
+		}
+
+		for id in changes.removals {
+			if let Some(entry) = index.entries.remove(&id) {
+				if filter.matches(&entry) {
+					self.removed += 1;
+					self.entries.push(entry);
+				}
+			}
+		}
+	}
+
+	/// Events only describe roots of added subtrees, so unlike local changes,
+	/// descendants of added instances are neither matched nor indexed
+	fn collect_events(&mut self, events: Vec<RemoteEvent>, filter: &WatchFilter, index: &mut InstanceIndex) {
+		debug!("Collecting {} events", events.len());
+
+		for event in events {
+			let (id, entry, added) = match event {
+				RemoteEvent::InstanceAdded { id, path, class, files } => (id, Entry::remote(path, class, files), true),
+				RemoteEvent::InstanceUpdated { id, path, class, files } => {
+					(id, Entry::remote(path, class, files), false)
+				}
+				RemoteEvent::InstanceRemoved { id } => {
+					if let Some(entry) = index.entries.remove(&id) {
+						if filter.matches(&entry) {
+							self.removed += 1;
+							self.entries.push(entry);
+						}
+					}
+
+					continue;
+				}
+				RemoteEvent::Other => continue,
+			};
+
+			index.entries.insert(id, entry.clone());
+
+			if filter.matches(&entry) {
+				if added {
+					self.added += 1;
+				} else {
+					self.updated += 1;
+				}
+
+				self.entries.push(entry);
+			}
+		}
+	}
+
+	fn is_empty(&self) -> bool {
+		self.total() == 0
+	}
+
+	fn total(&self) -> usize {
+		self.added + self.updated + self.removed
+	}
+
+	fn command(&self, exec: &str) -> Command {
+		#[cfg(not(target_os = "windows"))]
+		let mut command = Command::new("sh");
+		#[cfg(not(target_os = "windows"))]
+		command.arg("-c").arg(exec);
+
+		#[cfg(target_os = "windows")]
+		let mut command = Command::new("cmd");
+		#[cfg(target_os = "windows")]
+		command.arg("/C").arg(exec);
+
+		let paths: BTreeSet<&PathBuf> = self.entries.iter().flat_map(|entry| &entry.paths).collect();
+		let classes: BTreeSet<&str> = self.entries.iter().map(|entry| entry.class.as_str()).collect();
+		let names: BTreeSet<&str> = self.entries.iter().map(|entry| entry.name.as_str()).collect();
+
+		let paths = env::join_paths(paths).unwrap_or_default();
+
+		command
+			.env("ARGON_CHANGED_PATHS", paths)
+			.env(
+				"ARGON_CHANGED_INSTANCES",
+				names.into_iter().collect::<Vec<_>>().join("\n"),
+			)
+			.env(
+				"ARGON_CHANGED_CLASSES",
+				classes.into_iter().collect::<Vec<_>>().join(","),
+			)
+			.env("ARGON_CHANGED_COUNT", self.total().to_string())
+			.env("ARGON_ADDED_COUNT", self.added.to_string())
+			.env("ARGON_UPDATED_COUNT", self.updated.to_string())
+			.env("ARGON_REMOVED_COUNT", self.removed.to_string());
+
+		command
+	}
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// Stable codes of outcomes reported to clients, so they can react to them
+/// without parsing localized messages. Codes are never renamed nor reused,
+/// new ones are only added, clients should treat unknown codes as generic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCode {
+	/// Property is ignored by the syncback filter or is package metadata
+	Filtered,
+	/// Property can only be read and is never saved, e.g. `AbsoluteSize`
+	ReadOnly,
+	/// Property does not exist in the reflection database
+	Unknown,
+	/// Value of a new instance equals the class default so it is not written
+	DefaultStripped,
+	/// Property is a legacy alias, its value is written under the canonical name
+	CanonicalRename,
+	/// Value was out of its valid range, see `propertyRanges`
+	Clamped,
+}
+
+impl Display for ErrorCode {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Filtered => write!(f, "filtered"),
+			Self::ReadOnly => write!(f, "read-only"),
+			Self::Unknown => write!(f, "unknown"),
+			Self::DefaultStripped => write!(f, "default-stripped"),
+			Self::CanonicalRename => write!(f, "canonical-rename"),
+			Self::Clamped => write!(f, "clamped"),
+		}
+	}
+}
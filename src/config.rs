@@ -13,9 +13,13 @@ use std::{
 	path::{Path, PathBuf},
 	sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
 };
-use toml;
 
-use crate::{argon_error, logger::Table, util};
+use crate::{
+	argon_error, i18n,
+	logger::Table,
+	state::{self, Migration, State},
+	t, util,
+};
 
 lazy_static! {
 	static ref CONFIG: RwLock<Config> = RwLock::new(Config::default());
@@ -61,6 +65,8 @@ pub struct Config {
 	pub with_sourcemap: bool,
 	/// Build using XML format by default
 	pub build_xml: bool,
+	/// Default build profile of projects that define `profiles`
+	pub profile: String,
 
 	/// Check for new Argon releases on startup
 	pub check_updates: bool,
@@ -82,10 +88,67 @@ pub struct Config {
 	pub keep_duplicates: bool,
 	/// Move files to the bin instead of deleting them (two-way sync)
 	pub move_to_bin: bool,
+	/// Always use extended-length paths on Windows, not only for paths exceeding MAX_PATH
+	pub long_paths: bool,
+	/// Keep permissions of rewritten files and leave files with unchanged content untouched
+	pub preserve_file_modes: bool,
 	/// Number of changes allowed before prompting user for confirmation
 	pub changes_threshold: usize,
 	/// Maximum number of unsynced changes before showing a warning
 	pub max_unsynced_changes: u16,
+	/// Initial sync direction when both the disk and Studio changed since the last session (prompt, filesystem, studio)
+	pub sync_conflict_policy: String,
+	/// Record which session and client made every change in .argon/history.jsonl
+	pub history: bool,
+	/// Back up project files to .argon/backups before syncback modifies them
+	pub project_backups: bool,
+	/// Number of backups kept per project file, the oldest ones are removed
+	pub max_backups: usize,
+	/// Days to keep stable IDs of removed instances in .argon/ids.json (0 to keep forever)
+	pub id_retention: u32,
+	/// Write CFrames as position and Euler angles in degrees when syncing back, if exact
+	pub euler_cframes: bool,
+	/// Checkpoint message queues of clients to .argon/state/queues so they survive server restarts
+	pub durable_queues: bool,
+	/// Maximum size of a single queue checkpoint in bytes, clients with larger queues have to resync
+	pub max_queue_checkpoint_size: usize,
+	/// Minutes between tree consistency heartbeats sent to clients (0 to disable)
+	pub heartbeat_interval: u64,
+	/// Minutes between background checks for file changes the watcher missed (0 to disable)
+	pub integrity_scan_interval: u64,
+	/// Number of source paths checked by a single integrity scan
+	pub integrity_scan_batch: usize,
+	/// Number of syncback operations skipped by the filter that are kept for `argon skipped`
+	pub max_skipped_entries: usize,
+	/// Keep skipped syncback operations in .argon/skipped.json across sessions
+	pub persist_skipped: bool,
+	/// Rescan affected directories at once when files change in bulk, e.g. on `git checkout`
+	pub storm_detection: bool,
+	/// Number of file events waiting to be processed that engages storm mode (0 to disable)
+	pub storm_pending_threshold: usize,
+	/// Number of file events within a second that engages storm mode (0 to disable)
+	pub storm_rate_threshold: usize,
+	/// Minutes after which paused syncing resumes on its own (0 to disable)
+	pub pause_timeout: u64,
+	/// Number of file events held back while paused, the whole project is rescanned on resume beyond it
+	pub max_paused_events: usize,
+
+	/// Maximum size of request bodies in bytes, except for logs and syncback
+	pub max_request_size: usize,
+	/// Maximum size of log messages sent by clients in bytes
+	pub max_log_size: usize,
+	/// Maximum size of changes synced back from clients in bytes
+	pub max_payload_size: usize,
+	/// Maximum size of changes uploaded in chunks, shared by all unfinished uploads
+	pub max_upload_size: usize,
+	/// Number of requests per second allowed from a single client (0 to disable)
+	pub rate_limit: u32,
+	/// Number of requests a client can make at once before being rate limited
+	pub rate_limit_burst: u32,
+	/// Maximum number of snapshot and syncback requests handled at the same time
+	pub max_heavy_requests: usize,
+	/// Serve read-only web dashboard at /ui of the server
+	pub dashboard: bool,
 
 	/// Use .lua file extension instead of .luau when writing scripts
 	pub lua_extension: bool,
@@ -93,8 +156,18 @@ pub struct Config {
 	pub line_ending: String,
 	/// Package manager to use when running roblox-ts scripts (npm, bun, etc.)
 	pub package_manager: String,
+	/// Command opening files in the editor with {file} and {line} placeholders, VS Code or Cursor if empty
+	pub editor_command: String,
 	/// Share anonymous Argon usage statistics with the community
 	pub share_stats: bool,
+	/// Language of messages and prompts (auto, en, ja, etc.)
+	pub language: String,
+	/// Endpoint that crash reports are sent to instead of prompting to open a GitHub issue
+	pub crash_report_url: String,
+	/// Details included in sent crash reports (anonymous, full), full adds workspace path and backtrace symbols
+	pub crash_report_privacy: String,
+	/// Add counts of instances, project nodes and files per middleware, max tree depth, script source bytes and uptime to crash reports
+	pub crash_report_include_stats: bool,
 
 	#[serde(skip)]
 	/// Internal
@@ -119,6 +192,7 @@ impl Default for Config {
 			smart_paths: false,
 			with_sourcemap: false,
 			build_xml: false,
+			profile: String::new(),
 
 			check_updates: true,
 			auto_update: false,
@@ -131,19 +205,59 @@ impl Default for Config {
 			rename_instances: true,
 			keep_duplicates: false,
 			move_to_bin: false,
+			long_paths: false,
+			preserve_file_modes: true,
 			changes_threshold: 5,
 			max_unsynced_changes: 10,
+			sync_conflict_policy: String::from("prompt"),
+			history: true,
+			project_backups: true,
+			max_backups: 10,
+			id_retention: 30,
+			euler_cframes: true,
+			durable_queues: true,
+			max_queue_checkpoint_size: 8_388_608,
+			heartbeat_interval: 5,
+			integrity_scan_interval: 5,
+			integrity_scan_batch: 1000,
+			max_skipped_entries: 500,
+			persist_skipped: false,
+			storm_detection: true,
+			storm_pending_threshold: 500,
+			storm_rate_threshold: 1000,
+			pause_timeout: 30,
+			max_paused_events: 100_000,
+
+			max_request_size: 1_048_576,
+			max_log_size: 1_048_576,
+			max_payload_size: 134_217_728,
+			max_upload_size: 536_870_912,
+			rate_limit: 100,
+			rate_limit_burst: 500,
+			max_heavy_requests: 4,
+			dashboard: true,
 
 			lua_extension: false,
 			line_ending: String::from("LF"),
 			package_manager: String::from("npm"),
+			editor_command: String::new(),
 			share_stats: true,
+			language: String::from("auto"),
+			crash_report_url: String::new(),
+			crash_report_privacy: String::from("anonymous"),
+			crash_report_include_stats: false,
 
 			kind: ConfigKind::default(),
 		}
 	}
 }
 
+impl State for Config {
+	const NAME: &'static str = "Config";
+	const VERSION: u32 = 1;
+	const MIGRATIONS: &'static [Migration] = &[state::unversioned];
+}
+
 impl ConfigKind {
 	pub fn path(&self) -> Option<&Path> {
 		match self {
@@ -158,10 +272,20 @@ impl Config {
 		CONFIG.read().unwrap()
 	}
 
+	/// Same as `new` but never blocks nor panics, for use in the crash handler
+	pub fn try_new() -> Option<RwLockReadGuard<'static, Self>> {
+		CONFIG.try_read().ok()
+	}
+
 	pub fn new_mut() -> RwLockWriteGuard<'static, Self> {
 		CONFIG.try_write().expect("Failed to acquire write lock on config")
 	}
 
+	/// Replaces the whole config, unlike `new_mut` it waits for readers to finish
+	pub fn replace(config: Self) {
+		*CONFIG.write().unwrap() = config;
+	}
+
 	pub fn load() -> Result<ConfigKind> {
 		let mut config = Self::default();
 
@@ -178,7 +302,7 @@ impl Config {
 			};
 
 			if let Some(path) = kind.path() {
-				config.merge_opt(toml::from_str(&fs::read_to_string(path)?)?);
+				config.merge_opt(Self::read(path)?);
 			}
 
 			config.kind = kind.clone();
@@ -230,7 +354,7 @@ impl Config {
 		let mut config = Self::default();
 
 		let load_result = || -> Result<()> {
-			config.merge_opt(toml::from_str(&fs::read_to_string(path)?)?);
+			config.merge_opt(Self::read(path)?);
 
 			config.kind = match kind {
 				ConfigKind::Global(_) => ConfigKind::Global(path.to_owned()),
@@ -244,17 +368,26 @@ impl Config {
 		}();
 
 		match load_result {
-			Ok(()) => info!("{} config file loaded", kind),
+			Ok(()) => {
+				info!("{} config file loaded", kind);
+				i18n::init(&Self::new().language);
+			}
 			Err(err) => {
-				argon_error!("Failed to load {} config file: {}", kind.to_string().bold(), err);
+				argon_error!(
+					"{}",
+					t!("config.load_failed", kind = kind.to_string().bold(), error = err)
+				);
 			}
 		}
 	}
 
 	pub fn save(&self, path: &Path) -> Result<()> {
-		fs::write(path, toml::to_string(self)?)?;
+		state::save(path, self)
+	}
 
-		Ok(())
+	/// Reads settings of the config file, migrated to the current schema
+	fn read(path: &Path) -> Result<OptConfig> {
+		Ok(toml::Value::Table(state::upgrade::<Self>(&fs::read_to_string(path)?)?).try_into()?)
 	}
 
 	pub fn has_setting(&self, setting: &str) -> bool {
@@ -293,6 +426,16 @@ impl Config {
 	pub fn kind(&self) -> &ConfigKind {
 		&self.kind
 	}
+
+	/// Path of the loaded config file, default config
+	/// becomes the global one as it has no file of its own
+	pub fn layer_path(&mut self) -> Result<PathBuf> {
+		if self.kind == ConfigKind::Default {
+			self.kind = ConfigKind::Global(util::get_argon_dir()?.join("config.toml"));
+		}
+
+		Ok(self.kind.path().unwrap().to_owned())
+	}
 }
 
 impl Display for ConfigKind {
@@ -1,143 +1,326 @@
 use std::{sync::OnceLock, time::Duration};
 
-use crate::{core::meta::SyncRule, middleware::Middleware};
+use crate::{
+	core::meta::SyncRule,
+	middleware::Middleware,
+	project::{MiddlewareConfig, ScriptExtension, ScriptsConfig},
+};
 
 // Paths that should be ignored before they are even processed
 // useful to save ton of computing time, however users won't
 // be able to set them in `sync_rules` or project `$path`
-pub const BLACKLISTED_PATHS: [&str; 1] = [".DS_Store"];
+pub const BLACKLISTED_PATHS: [&str; 2] = [".DS_Store", ".argon"];
 
 // Current version of the project templates, this constant
 // should be manually bumped when there are any changes
 // made to the `assets/templates` directory
 pub const TEMPLATES_VERSION: u8 = 3;
 
-// Maximum payload size that can be sent from client
-// to the server, usually containing changes to apply,
-// currently it is 512 MiB but it is a huge overkill
-pub const MAX_PAYLOAD_SIZE: usize = 536_870_912;
+// Requests over the configured limits are logged at
+// most once per this interval for every offending client
+pub const LIMIT_WARN_INTERVAL: Duration = Duration::from_secs(60);
+
+// Chunked uploads that receive no chunk for this long are dropped
+pub const UPLOAD_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Number of recent `argon test` runs whose results are kept
+pub const TEST_RUNS_LIMIT: usize = 10;
+
+// How often `argon test` polls the server for results
+pub const TEST_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Default and maximum number of instances that can be
+// returned in a single page of the paginated snapshot
+pub const SNAPSHOT_PAGE_LIMIT: usize = 1000;
+pub const MAX_SNAPSHOT_PAGE_LIMIT: usize = 10_000;
+
+// Number of instances scanned for a single page of the paginated snapshot
+// per instance it can return, so pages of filtered trees end early
+pub const SNAPSHOT_PAGE_SCAN_FACTOR: usize = 10;
+
+// Number of instances in a single chunk of the initial sync,
+// how many chunks can wait for acknowledgement at a time and
+// how long the progress of a disconnected client is kept
+pub const SYNC_CHUNK_SIZE: usize = 1000;
+pub const SYNC_WINDOW: usize = 4;
+pub const SYNC_RESUME_TIMEOUT: Duration = Duration::from_secs(300);
+
+// How long choosing Studio's side of the initial
+// sync waits for its subtrees to be written to disk
+pub const STUDIO_SYNC_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Number of events kept for replaying to reconnecting `/ws` clients
+// and how many events can wait for a single slow client before
+// it gets dropped and told to resync
+pub const EVENT_BUFFER_SIZE: usize = 4096;
+pub const EVENT_SUBSCRIBER_CAPACITY: usize = 1024;
+
+// Lifecycle events kept for subscribers of `Core::subscribe` that fall behind
+pub const LIFECYCLE_CAPACITY: usize = 256;
+
+// Default and maximum number of instances returned in
+// a single page of `/find` results, the tree is scanned
+// in shards so it is not locked for the whole search
+pub const FIND_PAGE_LIMIT: usize = 100;
+pub const MAX_FIND_PAGE_LIMIT: usize = 1000;
+pub const FIND_SHARD_SIZE: usize = 1000;
+
+// Default and maximum number of instance paths
+// returned by a single `/complete` request
+pub const COMPLETE_LIMIT: usize = 20;
+pub const MAX_COMPLETE_LIMIT: usize = 500;
+
+// Default and maximum number of candidates
+// returned by the `suggest_path` MCP tool
+pub const SUGGEST_PATH_LIMIT: usize = 10;
+pub const MAX_SUGGEST_PATH_LIMIT: usize = 100;
+
+// Version of the built-in icon and category tables,
+// has to be bumped whenever they change so the
+// `/class-metadata` entity tag changes too
+pub const CLASS_METADATA_VERSION: u32 = 1;
+
+// Size of the `.argon/history.jsonl` file after which
+// it gets rotated, only one rotated file is kept
+pub const HISTORY_MAX_SIZE: u64 = 5_242_880;
+
+// Paths at least this long exceed Windows legacy `MAX_PATH`
+// limit (248 for directories as they need room for 8.3 file
+// names) so they get extended-length `\\?\` prefix
+pub const MAX_PATH_LENGTH: usize = 248;
+
+// Property values (script sources, binary strings) larger
+// than this are dropped from memory in low memory mode
+pub const EVICTION_THRESHOLD: usize = 4096;
+
+// Property values larger than this are sent to clients that
+// support it as blob references, served from `/blob/<hash>`
+pub const BLOB_THRESHOLD: usize = 1024;
+
+// Values of deferred properties (e.g. script sources) larger than this
+// are left out of the initial sync for clients that support it
+pub const LAZY_PROPERTY_THRESHOLD: usize = 4096;
+
+// Markdown files larger than this are not synced unless the
+// project sets `maxMarkdownSize`, it matches the maximum
+// length of `StringValue.Value` accepted by Roblox
+pub const MAX_MARKDOWN_SIZE: usize = 200_000;
+
+// Instances can't be nested deeper than this unless the project
+// sets `maxTreeDepth`, deeper files fail to load with a diagnostic
+// and deeper additions from Studio are rejected as a whole
+pub const MAX_TREE_DEPTH: usize = 1000;
+
+// Replay gives up when a traced input takes longer than
+// this to be processed, e.g. when the processor stopped
+pub const REPLAY_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Hook commands are killed after this long unless they set
+// their own `timeout`, the file is then synced unprocessed
+pub const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Version of the protocol spoken with clients, bumped on every
+// breaking change to the messages, clients older than the minimum
+// are rejected and ones that don't report it are assumed to be it
+pub const PROTOCOL_VERSION: u32 = 2;
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
 
 /// How long the server should wait for the changes to
 /// appear in the queue before manually "timing out"
 /// the client request and sending back an empty `Changes`
 pub const QUEUE_TIMEOUT: Duration = Duration::from_secs(60);
 
+/// How often message queues of connected clients are
+/// checkpointed to `.argon/state/queues` while serving
+pub const QUEUE_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
+
 // VFS events will be ignored for this amount of time
 // after the last change that has been made by the client,
 // this saves a lot of computing time
 pub const SYNCBACK_DEBOUNCE_TIME: Duration = Duration::from_millis(200);
 
+// How many times and with what initial (doubled every retry)
+// delay project parsing is attempted, editors that don't write
+// files atomically can leave them truncated for a moment
+pub const PROJECT_LOAD_RETRIES: usize = 5;
+pub const PROJECT_LOAD_BACKOFF: Duration = Duration::from_millis(20);
+
+// Structural changes arriving within this time
+// are batched into a single type regeneration
+pub const TYPEGEN_DEBOUNCE_TIME: Duration = Duration::from_millis(300);
+
+// Storm mode keeps collecting events until the watcher is quiet
+// for this long, rescanning anyway once the storm lasts too long
+pub const STORM_SETTLE_TIME: Duration = Duration::from_millis(100);
+pub const STORM_MAX_DURATION: Duration = Duration::from_secs(10);
+
+// Instances synced back with more properties than this only
+// report outcomes of this many of them, see `PropertyReport`
+pub const MAX_PROPERTY_OUTCOMES: usize = 200;
+
+// Attribute that marks instance created in Studio as ephemeral,
+// such instances (and their descendants) only live in memory
+// for the duration of the session and are never written to disk
+pub const EPHEMERAL_ATTRIBUTE: &str = "__ArgonEphemeral";
+
+// Attribute of placeholder instances created in place of
+// files that failed to load, its value is the error message
+pub const LOAD_ERROR_ATTRIBUTE: &str = "__ArgonLoadError";
+
+// Attribute of instances created with `argon asset add`,
+// its value is the key of the file in the asset store
+pub const ASSET_ATTRIBUTE: &str = "__ArgonAsset";
+
+// Directory added assets are stored in when the
+// project does not set `assetsPath`, relative to the workspace
+pub const DEFAULT_ASSETS_PATH: &str = "assets";
+
+// Maximum depth of nested `$include` references in instance
+// data files, deeper chains are most likely a mistake
+pub const MAX_INCLUDE_DEPTH: usize = 8;
+
+// File holding data of all instances in its directory keyed
+// by their names, used with `dataStorage: "index"`
+pub const DATA_INDEX_FILE: &str = ".argon-data.json";
+
+// Suffix of files listing instances of their directory,
+// e.g. `sounds.assets.json`, see `middleware::manifest`
+pub const ASSET_MANIFEST_SUFFIX: &str = ".assets.json";
+
+// Value written in place of `Ref` properties whose target is not in
+// the synced tree, such properties are left untouched, see `core::refs`
+pub const EXTERNAL_REF: &str = "@external";
+
 // Set of default sync rules that is used to determine
 // what middleware should be used to process a file
 // users can override these rules in the project file
 pub fn default_sync_rules() -> &'static Vec<SyncRule> {
 	static SYNC_RULES: OnceLock<Vec<SyncRule>> = OnceLock::new();
 
-	SYNC_RULES.get_or_init(|| {
-		vec![
-			SyncRule::new(Middleware::Project)
-				.with_pattern("*.project.json")
-				.with_child_pattern("default.project.json"),
+	SYNC_RULES.get_or_init(|| sync_rules_for(&MiddlewareConfig::default()))
+}
+
+// Default sync rules following conventions of the project's
+// `middlewareConfig`, rules of the preferred script extension
+// come first so they are used when writing new files
+pub fn sync_rules_for(config: &MiddlewareConfig) -> Vec<SyncRule> {
+	let data = &config.data.file_name;
+	let extension = config.scripts.extension();
+
+	// YAML variants of data files, e.g. `.data.yaml` and `.data.yml`
+	let data_stem = data.strip_suffix(".json").unwrap_or(data);
+	let yaml_data = [format!("{}.yaml", data_stem), format!("{}.yml", data_stem)];
+
+	let mut rules = vec![
+		SyncRule::new(Middleware::Project)
+			.with_pattern("*.project.json")
+			.with_child_pattern("default.project.json"),
+		SyncRule::new(Middleware::InstanceData)
+			.with_pattern(&format!("*{}", data))
+			.with_child_pattern(data),
+		SyncRule::new(Middleware::InstanceData) // Rojo
+			.with_pattern("*.meta.json")
+			.with_child_pattern("init.meta.json"),
+	];
+
+	for data in &yaml_data {
+		rules.push(
 			SyncRule::new(Middleware::InstanceData)
-				.with_pattern("*.data.json")
-				.with_child_pattern(".data.json"),
-			SyncRule::new(Middleware::InstanceData) // Rojo
-				.with_pattern("*.meta.json")
-				.with_child_pattern("init.meta.json"),
-			//////////////////////////////////////////////////////////////////////////////////////////
-			// Luau scripts for Argon
-			SyncRule::new(Middleware::ServerScript)
-				.with_pattern("*.server.luau")
-				.with_child_pattern(".src.server.luau")
-				.with_suffix(".server.luau")
-				.with_exclude("init.server.luau"),
-			SyncRule::new(Middleware::ClientScript)
-				.with_pattern("*.client.luau")
-				.with_child_pattern(".src.client.luau")
-				.with_suffix(".client.luau")
-				.with_exclude("init.client.luau"),
-			SyncRule::new(Middleware::ModuleScript)
-				.with_pattern("*.luau")
-				.with_child_pattern(".src.luau")
-				.with_exclude("init.luau"),
-			// Luau scripts for Rojo
-			SyncRule::new(Middleware::ServerScript)
-				.with_pattern("*.server.luau")
-				.with_child_pattern("init.server.luau")
-				.with_suffix(".server.luau"),
-			SyncRule::new(Middleware::ClientScript)
-				.with_pattern("*.client.luau")
-				.with_child_pattern("init.client.luau")
-				.with_suffix(".client.luau"),
-			SyncRule::new(Middleware::ModuleScript)
-				.with_pattern("*.luau")
-				.with_child_pattern("init.luau"),
-			//////////////////////////////////////////////////////////////////////////////////////////
-			// Lua scripts for Argon
-			SyncRule::new(Middleware::ServerScript)
-				.with_pattern("*.server.lua")
-				.with_child_pattern(".src.server.lua")
-				.with_suffix(".server.lua")
-				.with_exclude("init.server.lua"),
-			SyncRule::new(Middleware::ClientScript)
-				.with_pattern("*.client.lua")
-				.with_child_pattern(".src.client.lua")
-				.with_suffix(".client.lua")
-				.with_exclude("init.client.lua"),
-			SyncRule::new(Middleware::ModuleScript)
-				.with_pattern("*.lua")
-				.with_child_pattern(".src.lua")
-				.with_exclude("init.lua"),
-			// Lua scripts for Rojo
-			SyncRule::new(Middleware::ServerScript)
-				.with_pattern("*.server.lua")
-				.with_child_pattern("init.server.lua")
-				.with_suffix(".server.lua"),
-			SyncRule::new(Middleware::ClientScript)
-				.with_pattern("*.client.lua")
-				.with_child_pattern("init.client.lua")
-				.with_suffix(".client.lua"),
-			SyncRule::new(Middleware::ModuleScript)
-				.with_pattern("*.lua")
-				.with_child_pattern("init.lua"),
-			//////////////////////////////////////////////////////////////////////////////////////////
-			// Other file types, Argon only
-			SyncRule::new(Middleware::StringValue)
-				.with_pattern("*.txt")
-				.with_child_pattern(".src.txt"),
-			SyncRule::new(Middleware::RichStringValue)
-				.with_pattern("*.md")
-				.with_child_pattern(".src.md"),
-			SyncRule::new(Middleware::LocalizationTable)
-				.with_pattern("*.csv")
-				.with_child_pattern(".src.csv"),
-			SyncRule::new(Middleware::JsonModule)
-				.with_pattern("*.json")
-				.with_child_pattern(".src.json")
-				.with_excludes(&["*.model.json", "*.data.json", "*.meta.json"]),
-			SyncRule::new(Middleware::TomlModule)
-				.with_pattern("*.toml")
-				.with_child_pattern(".src.toml"),
-			SyncRule::new(Middleware::YamlModule)
-				.with_pattern("*.yaml")
-				.with_child_pattern(".src.yaml"),
-			SyncRule::new(Middleware::YamlModule)
-				.with_pattern("*.yml")
-				.with_child_pattern(".src.yml"),
-			SyncRule::new(Middleware::MsgpackModule)
-				.with_pattern("*.msgpack")
-				.with_child_pattern(".src.msgpack"),
-			// Model files, Argon only
-			SyncRule::new(Middleware::JsonModel)
-				.with_pattern("*.model.json")
-				.with_child_pattern(".src.model.json")
-				.with_suffix(".model.json"),
-			SyncRule::new(Middleware::RbxmModel)
-				.with_pattern("*.rbxm")
-				.with_child_pattern(".src.rbxm"),
-			SyncRule::new(Middleware::RbxmxModel)
-				.with_pattern("*.rbxmx")
-				.with_child_pattern(".src.rbxmx"),
-		]
-	})
+				.with_pattern(&format!("*{}", data))
+				.with_child_pattern(data),
+		);
+	}
+
+	//////////////////////////////////////////////////////////////////////////////////////////
+	// Scripts of the preferred extension, followed by the other one
+	for extension in [extension, extension.other()] {
+		rules.extend(script_sync_rules(&config.scripts, extension));
+	}
+
+	//////////////////////////////////////////////////////////////////////////////////////////
+	rules.extend([
+		// Other file types, Argon only
+		SyncRule::new(Middleware::StringValue)
+			.with_pattern("*.txt")
+			.with_child_pattern(".src.txt"),
+		SyncRule::new(Middleware::Markdown)
+			.with_pattern("*.md")
+			.with_child_pattern(".src.md"),
+		SyncRule::new(Middleware::RichStringValue)
+			.with_pattern("*.md")
+			.with_child_pattern(".src.md"),
+		SyncRule::new(Middleware::LocalizationTable)
+			.with_pattern("*.csv")
+			.with_child_pattern(".src.csv"),
+		SyncRule::new(Middleware::JsonModule)
+			.with_pattern("*.json")
+			.with_child_pattern(".src.json")
+			.with_excludes(&["*.model.json", &format!("*{}", data), "*.meta.json"]),
+		SyncRule::new(Middleware::TomlModule)
+			.with_pattern("*.toml")
+			.with_child_pattern(".src.toml"),
+		SyncRule::new(Middleware::YamlModule)
+			.with_pattern("*.yaml")
+			.with_child_pattern(".src.yaml")
+			.with_excludes(&[&format!("*{}", yaml_data[0])]),
+		SyncRule::new(Middleware::YamlModule)
+			.with_pattern("*.yml")
+			.with_child_pattern(".src.yml")
+			.with_excludes(&[&format!("*{}", yaml_data[1])]),
+		SyncRule::new(Middleware::MsgpackModule)
+			.with_pattern("*.msgpack")
+			.with_child_pattern(".src.msgpack"),
+		// Model files, Argon only
+		SyncRule::new(Middleware::JsonModel)
+			.with_pattern("*.model.json")
+			.with_child_pattern(".src.model.json")
+			.with_suffix(".model.json"),
+		SyncRule::new(Middleware::RbxmModel)
+			.with_pattern("*.rbxm")
+			.with_child_pattern(".src.rbxm"),
+		SyncRule::new(Middleware::RbxmxModel)
+			.with_pattern("*.rbxmx")
+			.with_child_pattern(".src.rbxmx"),
+	]);
+
+	rules
+}
+
+// Script rules of a single extension, e.g. `*.server.luau`,
+// `.src.server.luau` and `init.server.luau` by default
+fn script_sync_rules(config: &ScriptsConfig, extension: ScriptExtension) -> Vec<SyncRule> {
+	let server = format!("{}.{}", config.server_suffix, extension.as_str());
+	let client = format!("{}.{}", config.client_suffix, extension.as_str());
+	let module = format!(".{}", extension.as_str());
+	let init = &config.init_name;
+
+	vec![
+		// Scripts for Argon
+		SyncRule::new(Middleware::ServerScript)
+			.with_pattern(&format!("*{}", server))
+			.with_child_pattern(&format!(".src{}", server))
+			.with_suffix(&server)
+			.with_exclude(&format!("{}{}", init, server)),
+		SyncRule::new(Middleware::ClientScript)
+			.with_pattern(&format!("*{}", client))
+			.with_child_pattern(&format!(".src{}", client))
+			.with_suffix(&client)
+			.with_exclude(&format!("{}{}", init, client)),
+		SyncRule::new(Middleware::ModuleScript)
+			.with_pattern(&format!("*{}", module))
+			.with_child_pattern(&format!(".src{}", module))
+			.with_exclude(&format!("{}{}", init, module)),
+		// Scripts for Rojo
+		SyncRule::new(Middleware::ServerScript)
+			.with_pattern(&format!("*{}", server))
+			.with_child_pattern(&format!("{}{}", init, server))
+			.with_suffix(&server),
+		SyncRule::new(Middleware::ClientScript)
+			.with_pattern(&format!("*{}", client))
+			.with_child_pattern(&format!("{}{}", init, client))
+			.with_suffix(&client),
+		SyncRule::new(Middleware::ModuleScript)
+			.with_pattern(&format!("*{}", module))
+			.with_child_pattern(&format!("{}{}", init, module)),
+	]
 }
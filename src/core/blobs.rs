@@ -0,0 +1,261 @@
+use rbx_dom_weak::{
+	types::{Ref, Variant},
+	Ustr,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	hash::{DefaultHasher, Hash, Hasher},
+	io::{self, Read},
+	sync::{Arc, RwLock},
+};
+
+use super::{changes::Changes, snapshot::Snapshot};
+use crate::{constants::BLOB_THRESHOLD, Properties};
+
+/// Property value that was moved out of the message,
+/// clients fetch it from `/blob/<hash>` unless already cached
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlobRef {
+	pub id: Ref,
+	pub property: Ustr,
+	pub hash: String,
+	pub size: usize,
+}
+
+#[derive(Debug)]
+struct Blob {
+	value: Arc<Variant>,
+	refs: usize,
+}
+
+/// Content-addressed store of large property values shared by all queued messages,
+/// blobs are dropped once nothing references them anymore
+#[derive(Debug, Default)]
+pub struct BlobStore {
+	blobs: RwLock<HashMap<String, Blob>>,
+}
+
+impl BlobStore {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds one reference for every entry in `refs`,
+	/// values of blobs that are not stored yet are taken from `values`
+	pub fn retain(&self, refs: &[BlobRef], values: &HashMap<String, Arc<Variant>>) {
+		let mut blobs = self.blobs.write().unwrap();
+
+		for blob_ref in refs {
+			let blob = blobs.entry(blob_ref.hash.clone()).or_insert_with(|| Blob {
+				value: values[&blob_ref.hash].clone(),
+				refs: 0,
+			});
+
+			blob.refs += 1;
+		}
+	}
+
+	/// Removes one reference for every entry in `refs`
+	/// and drops blobs that are no longer referenced
+	pub fn release(&self, refs: &[BlobRef]) {
+		let mut blobs = self.blobs.write().unwrap();
+
+		for blob_ref in refs {
+			if let Some(blob) = blobs.get_mut(&blob_ref.hash) {
+				blob.refs -= 1;
+
+				if blob.refs == 0 {
+					blobs.remove(&blob_ref.hash);
+				}
+			}
+		}
+	}
+
+	pub fn get(&self, hash: &str) -> Option<Arc<Variant>> {
+		self.blobs.read().unwrap().get(hash).map(|blob| blob.value.clone())
+	}
+
+	pub fn refs(&self, hash: &str) -> usize {
+		self.blobs.read().unwrap().get(hash).map_or(0, |blob| blob.refs)
+	}
+
+	pub fn len(&self) -> usize {
+		self.blobs.read().unwrap().len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+/// Returns content hash and size of the property value if it is large enough to be deduplicated
+pub fn hash(value: &Variant) -> Option<(String, usize)> {
+	digest(value).filter(|(_, size)| *size >= BLOB_THRESHOLD)
+}
+
+/// Returns content hash and size of string and binary string values
+pub fn digest(value: &Variant) -> Option<(String, usize)> {
+	let bytes: &[u8] = match value {
+		Variant::String(string) => string.as_bytes(),
+		Variant::BinaryString(string) => string.as_ref(),
+		_ => return None,
+	};
+
+	let mut hasher = DefaultHasher::new();
+
+	value.ty().hash(&mut hasher);
+	bytes.hash(&mut hasher);
+
+	Some((format!("{:016x}{:x}", hasher.finish(), bytes.len()), bytes.len()))
+}
+
+/// Streaming variant of the content hash for data that is not a property
+/// value, e.g. files read back in chunks, in the same format as `digest`
+#[derive(Debug, Default)]
+pub struct ContentHasher {
+	hasher: DefaultHasher,
+	size: usize,
+}
+
+impl ContentHasher {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn update(&mut self, bytes: &[u8]) {
+		self.hasher.write(bytes);
+		self.size += bytes.len();
+	}
+
+	pub fn finish(&self) -> String {
+		format!("{:016x}{:x}", self.hasher.finish(), self.size)
+	}
+}
+
+/// Returns content hash of the bytes, see `ContentHasher`
+pub fn digest_bytes(bytes: &[u8]) -> String {
+	let mut hasher = ContentHasher::new();
+	hasher.update(bytes);
+	hasher.finish()
+}
+
+/// Returns content hash of everything read from the reader without buffering it whole
+pub fn digest_reader(mut reader: impl Read) -> io::Result<String> {
+	let mut hasher = ContentHasher::new();
+	let mut buffer = [0; 65536];
+
+	loop {
+		match reader.read(&mut buffer) {
+			Ok(0) => break,
+			Ok(read) => hasher.update(&buffer[..read]),
+			Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+			Err(err) => return Err(err),
+		}
+	}
+
+	Ok(hasher.finish())
+}
+
+/// Returns copy of the changes with large property values replaced
+/// by references and the values themselves keyed by their hash
+pub fn deduplicate(changes: &Changes) -> (Changes, Vec<BlobRef>, HashMap<String, Arc<Variant>>) {
+	fn extract(
+		id: Ref,
+		properties: &mut Properties,
+		refs: &mut Vec<BlobRef>,
+		values: &mut HashMap<String, Arc<Variant>>,
+	) {
+		let large: Vec<(Ustr, String, usize)> = properties
+			.iter()
+			.filter_map(|(name, value)| hash(value).map(|(hash, size)| (*name, hash, size)))
+			.collect();
+
+		for (property, hash, size) in large {
+			let value = properties.remove(&property).unwrap();
+
+			values.entry(hash.clone()).or_insert_with(|| Arc::new(value));
+			refs.push(BlobRef {
+				id,
+				property,
+				hash,
+				size,
+			});
+		}
+	}
+
+	fn walk(snapshot: &mut Snapshot, refs: &mut Vec<BlobRef>, values: &mut HashMap<String, Arc<Variant>>) {
+		extract(snapshot.id, &mut snapshot.properties, refs, values);
+
+		for child in &mut snapshot.children {
+			walk(child, refs, values);
+		}
+	}
+
+	let mut changes = changes.clone();
+	let mut refs = vec![];
+	let mut values = HashMap::new();
+
+	for snapshot in &mut changes.additions {
+		extract(snapshot.id, &mut snapshot.properties, &mut refs, &mut values);
+
+		for child in &mut snapshot.children {
+			walk(child, &mut refs, &mut values);
+		}
+	}
+
+	for snapshot in &mut changes.updates {
+		if let Some(properties) = &mut snapshot.properties {
+			extract(snapshot.id, properties, &mut refs, &mut values);
+		}
+	}
+
+	(changes, refs, values)
+}
+
+/// Puts fetched blobs back into the changes, what clients do after
+/// receiving deduplicated message, fails if any blob could not be fetched
+pub fn restore<F>(changes: &mut Changes, refs: &[BlobRef], mut fetch: F) -> Option<()>
+where
+	F: FnMut(&str) -> Option<Variant>,
+{
+	fn find(snapshot: &mut Snapshot, id: Ref) -> Option<&mut Properties> {
+		if snapshot.id == id {
+			return Some(&mut snapshot.properties);
+		}
+
+		snapshot.children.iter_mut().find_map(|child| find(child, id))
+	}
+
+	let mut fetched: HashMap<&str, Variant> = HashMap::new();
+
+	for blob_ref in refs {
+		if !fetched.contains_key(blob_ref.hash.as_str()) {
+			fetched.insert(&blob_ref.hash, fetch(&blob_ref.hash)?);
+		}
+
+		let value = fetched[blob_ref.hash.as_str()].clone();
+
+		let properties = changes
+			.additions
+			.iter_mut()
+			.find_map(|snapshot| {
+				if snapshot.id == blob_ref.id {
+					Some(&mut snapshot.properties)
+				} else {
+					snapshot.children.iter_mut().find_map(|child| find(child, blob_ref.id))
+				}
+			})
+			.or_else(|| {
+				changes
+					.updates
+					.iter_mut()
+					.find(|snapshot| snapshot.id == blob_ref.id)
+					.and_then(|snapshot| snapshot.properties.as_mut())
+			})?;
+
+		properties.insert(blob_ref.property, value);
+	}
+
+	Some(())
+}
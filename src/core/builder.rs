@@ -0,0 +1,47 @@
+use anyhow::Result;
+use std::sync::Arc;
+
+use super::{meta::SyncRule, Core};
+use crate::{
+	middleware::external::{self, MiddlewareImpl},
+	project::Project,
+};
+
+/// Builds `Core` with options that are only available when Argon is used as a library
+pub struct CoreBuilder {
+	project: Project,
+	watch: bool,
+	middleware: Vec<(SyncRule, Arc<dyn MiddlewareImpl>)>,
+}
+
+impl CoreBuilder {
+	pub fn new(project: Project) -> Self {
+		Self {
+			project,
+			watch: false,
+			middleware: Vec::new(),
+		}
+	}
+
+	/// Whether to watch the file system for changes
+	pub fn watch(mut self, watch: bool) -> Self {
+		self.watch = watch;
+		self
+	}
+
+	/// Adds custom middleware that reads and writes files matching the rule, its type
+	/// has to be `Middleware::External` with the ID of the middleware. Registration is
+	/// process-wide so later cores use the middleware too, see `external::register`
+	pub fn middleware(mut self, rule: SyncRule, implementation: impl MiddlewareImpl + 'static) -> Self {
+		self.middleware.push((rule, Arc::new(implementation)));
+		self
+	}
+
+	pub fn build(self) -> Result<Core> {
+		for (rule, implementation) in self.middleware {
+			external::register(rule, implementation)?;
+		}
+
+		Core::new(self.project, self.watch)
+	}
+}
@@ -20,15 +20,7 @@ impl Changes {
 	}
 
 	pub fn add(&mut self, snapshot: Snapshot, parent: Ref) {
-		self.additions.push(AddedSnapshot {
-			id: snapshot.id,
-			parent,
-			name: snapshot.name,
-			class: snapshot.class,
-			properties: snapshot.properties,
-			children: snapshot.children,
-			meta: snapshot.meta,
-		});
+		self.additions.push(snapshot.into_new(parent));
 	}
 
 	pub fn update(&mut self, modified_snapshot: UpdatedSnapshot) {
@@ -45,6 +37,38 @@ impl Changes {
 		self.removals.extend(changes.removals);
 	}
 
+	/// Returns IDs of added instances (with their descendants) and updated instances
+	pub fn changed_ids(&self) -> Vec<Ref> {
+		fn walk(snapshot: &Snapshot, ids: &mut Vec<Ref>) {
+			ids.push(snapshot.id);
+
+			for child in &snapshot.children {
+				walk(child, ids);
+			}
+		}
+
+		let mut ids = vec![];
+
+		for snapshot in &self.additions {
+			ids.push(snapshot.id);
+
+			for child in &snapshot.children {
+				walk(child, &mut ids);
+			}
+		}
+
+		ids.extend(self.updates.iter().map(|snapshot| snapshot.id));
+
+		ids
+	}
+
+	/// Whether instances were added, removed or renamed
+	pub fn is_structural(&self) -> bool {
+		!self.additions.is_empty()
+			|| !self.removals.is_empty()
+			|| self.updates.iter().any(|snapshot| snapshot.name.is_some())
+	}
+
 	pub fn is_empty(&self) -> bool {
 		self.additions.is_empty() && self.updates.is_empty() && self.removals.is_empty()
 	}
@@ -0,0 +1,245 @@
+use anyhow::{bail, Result};
+use log::{debug, warn};
+use rbx_dom_weak::types::Ref;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::{BTreeMap, HashMap},
+	fs,
+	path::{Path, PathBuf},
+};
+
+use super::{pending::PendingKind, tree::Tree, tree_hash::TreeHash};
+
+/// Version of the checkpoint format, checkpoints
+/// of other versions make their clients resync
+pub const CHECKPOINT_VERSION: u32 = 1;
+
+/// Returns the `.argon/state/queues` directory of the workspace
+pub fn queues_dir(workspace_dir: &Path) -> PathBuf {
+	workspace_dir.join(".argon").join("state").join("queues")
+}
+
+/// Returns path of the checkpoint of the client queue
+pub fn queue_path(workspace_dir: &Path, client_id: u32) -> PathBuf {
+	queues_dir(workspace_dir).join(format!("{}.bin", client_id))
+}
+
+/// Tells the reconnecting client what to do with its tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Resume {
+	/// Messages continue right after the last one the client received
+	Continue,
+	/// Client has to sync the whole tree again
+	Resync,
+}
+
+/// Position of the client in its queue, reported when reconnecting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueCursor {
+	/// Queue generation from the handshake of the previous session
+	pub generation: String,
+	/// Number of messages received in the previous session
+	pub received: u64,
+}
+
+/// Instance changed by a message the client has not received yet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointEntry {
+	pub id: Ref,
+	/// `None` for removed instances
+	pub stable_id: Option<String>,
+	pub kind: PendingKind,
+}
+
+/// Persisted state of a client queue, written to `.argon/state/queues/<client>.bin`
+/// periodically and on shutdown, then restored by the next server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueCheckpoint {
+	pub version: u32,
+	pub client_id: u32,
+	/// Queue generation of the server that wrote the checkpoint
+	pub generation: String,
+	/// Number of messages the client received
+	pub delivered: u64,
+	/// Hash of the tree the checkpoint was taken from
+	pub tree_hash: String,
+	/// Referents the client knows instances by, keyed by stable IDs
+	pub refs: BTreeMap<String, Ref>,
+	/// Instances changed by messages waiting in the queue
+	pub entries: Vec<CheckpointEntry>,
+	/// Set when the queue could not be persisted, e.g. it was too large
+	/// or held a part of the initial sync, the client has to resync
+	pub invalid: bool,
+}
+
+/// Checkpoint reconciled against the tree of the new server
+#[derive(Debug, Clone, PartialEq)]
+pub struct Restored {
+	pub generation: String,
+	pub delivered: u64,
+	/// `None` if the client has to resync
+	pub resumed: Option<Resumed>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resumed {
+	/// Old referents mapped to the new ones
+	pub refs: HashMap<Ref, Ref>,
+	/// Instances the client still has to receive, removed ones keep their old referents
+	pub pending: Vec<(Ref, PendingKind)>,
+}
+
+impl QueueCheckpoint {
+	/// Writes the checkpoint, if it exceeds `limit` bytes an invalid one is
+	/// written instead so the client resyncs, returns whether it fit
+	pub fn write(&self, path: &Path, limit: usize) -> Result<bool> {
+		let mut contents = rmp_serde::to_vec_named(self)?;
+		let fits = !self.invalid && contents.len() <= limit;
+
+		if !fits && !self.invalid {
+			debug!(
+				"Queue checkpoint of client {} exceeds {} bytes, invalidating its cursor",
+				self.client_id, limit
+			);
+
+			contents = rmp_serde::to_vec_named(&self.invalidated())?;
+		}
+
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		// Written atomically so a crash in the middle leaves the previous checkpoint
+		let temp = path.with_extension("bin.tmp");
+
+		fs::write(&temp, contents)?;
+		fs::rename(&temp, path)?;
+
+		Ok(fits)
+	}
+
+	pub fn read(path: &Path) -> Result<Self> {
+		let checkpoint: Self = rmp_serde::from_slice(&fs::read(path)?)?;
+
+		if checkpoint.version != CHECKPOINT_VERSION {
+			bail!("Unsupported queue checkpoint version: {}", checkpoint.version);
+		}
+
+		Ok(checkpoint)
+	}
+
+	/// Reads all checkpoints of the workspace keyed by client ID,
+	/// unreadable ones are removed and their clients resync
+	pub fn read_all(workspace_dir: &Path) -> HashMap<u32, Self> {
+		let mut checkpoints = HashMap::new();
+
+		let Ok(entries) = fs::read_dir(queues_dir(workspace_dir)) else {
+			return checkpoints;
+		};
+
+		for entry in entries.flatten() {
+			let path = entry.path();
+
+			if path.extension().is_none_or(|ext| ext != "bin") {
+				continue;
+			}
+
+			match Self::read(&path) {
+				Ok(checkpoint) => {
+					checkpoints.insert(checkpoint.client_id, checkpoint);
+				}
+				Err(err) => {
+					warn!("Failed to read queue checkpoint {}: {}", path.display(), err);
+					fs::remove_file(&path).ok();
+				}
+			}
+		}
+
+		checkpoints
+	}
+
+	/// Copy without any entries that makes the client resync
+	pub fn invalidated(&self) -> Self {
+		Self {
+			refs: BTreeMap::new(),
+			entries: vec![],
+			invalid: true,
+			..self.clone()
+		}
+	}
+
+	/// Checks whether the client can continue with the tree rebuilt by the new server:
+	/// the tree has to be the same as the checkpointed one and every instance the client
+	/// knows has to exist in it. Pending messages are replaced with instances they changed,
+	/// which are resent with their current state so ones the tree already reflects are dropped
+	pub fn reconcile(self, tree: &Tree, hash: TreeHash) -> Restored {
+		let resumed = if self.invalid || self.tree_hash != hash.to_string() {
+			None
+		} else {
+			self.resume(tree)
+		};
+
+		Restored {
+			generation: self.generation,
+			delivered: self.delivered,
+			resumed,
+		}
+	}
+
+	fn resume(&self, tree: &Tree) -> Option<Resumed> {
+		// Instances without stable IDs, e.g. ephemeral ones, can't be mapped
+		if tree.stable_id_map().len() != self.refs.len() || tree.meta_map().len() != self.refs.len() {
+			return None;
+		}
+
+		let mut refs = HashMap::new();
+
+		for (stable_id, old) in &self.refs {
+			refs.insert(*old, tree.find_by_stable_id(stable_id)?);
+		}
+
+		let mut pending = vec![];
+
+		for entry in &self.entries {
+			match (&entry.stable_id, entry.kind) {
+				(_, PendingKind::Removed) => pending.push((entry.id, PendingKind::Removed)),
+				(Some(stable_id), kind) => pending.push((tree.find_by_stable_id(stable_id)?, kind)),
+				(None, _) => return None,
+			}
+		}
+
+		Some(Resumed { refs, pending })
+	}
+}
+
+/// Coalesces instances changed by the queued messages the same way pending
+/// instances are, e.g. instances added and then removed are left out
+pub fn coalesce(changes: impl IntoIterator<Item = (Ref, PendingKind)>) -> Vec<(Ref, PendingKind)> {
+	let mut order = vec![];
+	let mut kinds: HashMap<Ref, PendingKind> = HashMap::new();
+
+	for (id, kind) in changes {
+		match (kinds.get(&id).copied(), kind) {
+			(Some(PendingKind::Added), PendingKind::Updated) => {}
+			(Some(PendingKind::Added), PendingKind::Removed) => {
+				kinds.remove(&id);
+			}
+			(Some(_), kind) => {
+				kinds.insert(id, kind);
+			}
+			(None, kind) => {
+				order.push(id);
+				kinds.insert(id, kind);
+			}
+		}
+	}
+
+	order
+		.into_iter()
+		.filter_map(|id| kinds.remove(&id).map(|kind| (id, kind)))
+		.collect()
+}
@@ -0,0 +1,96 @@
+use rbx_dom_weak::{
+	types::{Ref, Variant},
+	Ustr,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{blobs, helpers::memory, tree::Tree};
+use crate::{project::LazyProperties, vfs::Vfs, Properties};
+
+/// Placeholder of a property value that was left out of the initial sync,
+/// clients request the value from `/properties` once they need it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeferredProperty {
+	pub id: Ref,
+	pub property: Ustr,
+	/// Content hash, same as the one of blob references
+	pub hash: String,
+	pub size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyRequest {
+	pub id: Ref,
+	pub property: Ustr,
+}
+
+/// Requested property value, `None` if the instance
+/// no longer exists or does not have the property
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyValue {
+	pub id: Ref,
+	pub property: Ustr,
+	pub value: Option<Variant>,
+}
+
+/// Returns content hash and size of the value if it
+/// is heavy enough to be left out of the initial sync
+pub fn heavy_digest(property: &str, value: &Variant, settings: &LazyProperties) -> Option<(String, usize)> {
+	let designated = match value {
+		Variant::String(_) => settings.properties.iter().any(|name| name == property),
+		Variant::BinaryString(_) => true,
+		_ => false,
+	};
+
+	if !designated {
+		return None;
+	}
+
+	blobs::digest(value).filter(|(_, size)| *size >= settings.threshold)
+}
+
+/// Removes heavy values from the properties and returns their placeholders
+pub fn defer(id: Ref, properties: &mut Properties, settings: &LazyProperties) -> Vec<DeferredProperty> {
+	let heavy: Vec<(Ustr, String, usize)> = properties
+		.iter()
+		.filter_map(|(name, value)| heavy_digest(name, value, settings).map(|(hash, size)| (*name, hash, size)))
+		.collect();
+
+	heavy
+		.into_iter()
+		.map(|(property, hash, size)| {
+			properties.remove(&property);
+
+			DeferredProperty {
+				id,
+				property,
+				hash,
+				size,
+			}
+		})
+		.collect()
+}
+
+/// Returns requested values in the order they were requested, values
+/// evicted in low memory mode are read back from the VFS
+pub fn read(requests: &[PropertyRequest], tree: &Tree, vfs: &Vfs) -> Vec<PropertyValue> {
+	let mut materialized: HashMap<Ref, Option<Properties>> = HashMap::new();
+
+	requests
+		.iter()
+		.map(|request| {
+			let properties = materialized
+				.entry(request.id)
+				.or_insert_with(|| memory::materialize_lossy(request.id, tree, vfs));
+
+			PropertyValue {
+				id: request.id,
+				property: request.property,
+				value: properties
+					.as_ref()
+					.and_then(|properties| properties.get(&request.property).cloned()),
+			}
+		})
+		.collect()
+}
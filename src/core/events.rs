@@ -0,0 +1,336 @@
+use log::{trace, warn};
+use rbx_dom_weak::{types::Ref, Ustr};
+use serde::Serialize;
+use std::{
+	collections::{HashMap, VecDeque},
+	path::PathBuf,
+	sync::{Arc, Mutex},
+};
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+
+use super::{changes::Changes, processor::origin::Origin, tree::Tree};
+use crate::lock;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionState {
+	Connected,
+	Disconnected,
+}
+
+/// Compact events pushed to `/ws` subscribers, instances
+/// are described by their path, class and changed keys only
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum EventKind {
+	#[serde(rename_all = "camelCase")]
+	InstanceAdded {
+		id: Ref,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		stable_id: Option<String>,
+		path: String,
+		class: Ustr,
+		/// Files the instance is synced from
+		#[serde(skip_serializing_if = "Vec::is_empty")]
+		files: Vec<PathBuf>,
+	},
+	#[serde(rename_all = "camelCase")]
+	InstanceUpdated {
+		id: Ref,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		stable_id: Option<String>,
+		path: String,
+		class: Ustr,
+		#[serde(skip_serializing_if = "Vec::is_empty")]
+		files: Vec<PathBuf>,
+		changed: Vec<String>,
+	},
+	/// Path of removed instance is no longer known,
+	/// clients should look it up by the ID
+	#[serde(rename_all = "camelCase")]
+	InstanceRemoved { id: Ref },
+	#[serde(rename_all = "camelCase")]
+	Diagnostic {
+		level: String,
+		message: String,
+		/// Instance the diagnostic is about, if there is a single one
+		#[serde(skip_serializing_if = "Option::is_none")]
+		instance: Option<Origin>,
+	},
+	#[serde(rename_all = "camelCase")]
+	Log {
+		client_id: Option<u32>,
+		level: String,
+		message: String,
+	},
+	#[serde(rename_all = "camelCase")]
+	SyncProgress {
+		client_id: u32,
+		phase: String,
+		sent: usize,
+		total: usize,
+	},
+	#[serde(rename_all = "camelCase")]
+	Session {
+		client_id: u32,
+		name: Option<String>,
+		state: SessionState,
+	},
+	/// Client has missed some events and has to fetch the whole tree again,
+	/// never stored in the buffer, its sequence is the last published one
+	#[serde(rename_all = "camelCase")]
+	Resync { reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Event {
+	pub seq: u64,
+	#[serde(flatten)]
+	pub kind: EventKind,
+}
+
+/// IDs and changed keys of the changes, cheap to collect before
+/// the changes are consumed and turned into events afterwards
+#[derive(Debug, Clone, Default)]
+pub struct ChangeSummary {
+	additions: Vec<Ref>,
+	updates: Vec<(Ref, Vec<String>)>,
+	removals: Vec<Ref>,
+}
+
+impl ChangeSummary {
+	pub fn new(changes: &Changes) -> Self {
+		let updates = changes
+			.updates
+			.iter()
+			.filter_map(|snapshot| {
+				let mut changed = vec![];
+
+				if snapshot.name.is_some() {
+					changed.push(String::from("Name"));
+				}
+
+				if snapshot.class.is_some() {
+					changed.push(String::from("ClassName"));
+				}
+
+				if let Some(properties) = &snapshot.properties {
+					changed.extend(properties.keys().map(|key| key.to_string()));
+				}
+
+				changed.sort();
+
+				// Meta-only updates are not visible to clients
+				(!changed.is_empty()).then_some((snapshot.id, changed))
+			})
+			.collect();
+
+		Self {
+			additions: changes.additions.iter().map(|snapshot| snapshot.id).collect(),
+			updates,
+			removals: changes.removals.clone(),
+		}
+	}
+
+	/// Describes the changes using the current state of the tree,
+	/// instances that no longer exist are skipped
+	pub fn into_events(self, tree: &Tree) -> Vec<EventKind> {
+		let mut events = vec![];
+
+		for id in self.additions {
+			if let (Some(instance), Some(path)) = (tree.get_instance(id), tree.get_full_name(id)) {
+				events.push(EventKind::InstanceAdded {
+					id,
+					stable_id: get_stable_id(id, tree),
+					path,
+					class: instance.class,
+					files: get_files(id, tree),
+				});
+			}
+		}
+
+		for (id, changed) in self.updates {
+			if let (Some(instance), Some(path)) = (tree.get_instance(id), tree.get_full_name(id)) {
+				events.push(EventKind::InstanceUpdated {
+					id,
+					stable_id: get_stable_id(id, tree),
+					path,
+					class: instance.class,
+					files: get_files(id, tree),
+					changed,
+				});
+			}
+		}
+
+		for id in self.removals {
+			events.push(EventKind::InstanceRemoved { id });
+		}
+
+		events
+	}
+}
+
+fn get_stable_id(id: Ref, tree: &Tree) -> Option<String> {
+	tree.get_meta(id).and_then(|meta| meta.stable_id.clone())
+}
+
+fn get_files(id: Ref, tree: &Tree) -> Vec<PathBuf> {
+	tree.get_meta(id)
+		.map(|meta| meta.source.paths().into_iter().map(|path| path.to_owned()).collect())
+		.unwrap_or_default()
+}
+
+/// Events a new subscriber has missed, `None` if they
+/// are no longer buffered and a full resync is needed
+#[derive(Debug)]
+pub struct EventSubscription {
+	pub replay: Option<Vec<Arc<Event>>>,
+	pub receiver: Receiver<Arc<Event>>,
+	/// Sequence number of the last published event
+	pub latest: u64,
+}
+
+/// Events returned by `/events`, `resync` is set when some
+/// of the requested ones are no longer buffered
+#[derive(Debug, Clone, Serialize)]
+pub struct EventPage {
+	pub events: Vec<Event>,
+	/// Sequence number of the last published event
+	pub latest: u64,
+	pub resync: bool,
+}
+
+#[derive(Debug, Default)]
+struct State {
+	last_seq: u64,
+	buffer: VecDeque<Arc<Event>>,
+	subscribers: HashMap<u64, Sender<Arc<Event>>>,
+	next_subscriber: u64,
+}
+
+/// Append-only log of events with a bounded replay buffer, events are never
+/// waited on: subscribers that fall behind are dropped and have to resync
+#[derive(Debug)]
+pub struct EventLog {
+	state: Mutex<State>,
+	capacity: usize,
+	subscriber_capacity: usize,
+}
+
+impl EventLog {
+	pub fn new(capacity: usize, subscriber_capacity: usize) -> Self {
+		Self {
+			state: Mutex::new(State::default()),
+			capacity: capacity.max(1),
+			subscriber_capacity: subscriber_capacity.max(1),
+		}
+	}
+
+	/// Appends event to the log and sends it to all subscribers,
+	/// returns its sequence number
+	pub fn publish(&self, kind: EventKind) -> u64 {
+		let mut state = lock!(self.state);
+
+		state.last_seq += 1;
+
+		let event = Arc::new(Event {
+			seq: state.last_seq,
+			kind,
+		});
+
+		state.buffer.push_back(event.clone());
+
+		while state.buffer.len() > self.capacity {
+			state.buffer.pop_front();
+		}
+
+		state
+			.subscribers
+			.retain(|id, sender| match sender.try_send(event.clone()) {
+				Ok(()) => true,
+				Err(TrySendError::Full(_)) => {
+					warn!("Event subscriber {} is too slow, dropping it", id);
+					false
+				}
+				Err(TrySendError::Closed(_)) => {
+					trace!("Event subscriber {} disconnected", id);
+					false
+				}
+			});
+
+		event.seq
+	}
+
+	pub fn publish_all(&self, events: Vec<EventKind>) {
+		for event in events {
+			self.publish(event);
+		}
+	}
+
+	/// Subscribes to new events, `since` is the last sequence
+	/// number the client has seen before reconnecting
+	pub fn subscribe(&self, since: Option<u64>) -> EventSubscription {
+		let mut state = lock!(self.state);
+		let (sender, receiver) = mpsc::channel(self.subscriber_capacity);
+
+		let id = state.next_subscriber;
+		state.next_subscriber += 1;
+		state.subscribers.insert(id, sender);
+
+		let replay = match since {
+			None => Some(vec![]),
+			Some(since) => replay(&state, since),
+		};
+
+		EventSubscription {
+			replay,
+			receiver,
+			latest: state.last_seq,
+		}
+	}
+
+	/// Returns buffered events newer than `since` or all of them if
+	/// `since` is `None`, used by clients that poll instead of subscribing
+	pub fn page(&self, since: Option<u64>) -> EventPage {
+		let state = lock!(self.state);
+
+		let events = match since {
+			None => Some(state.buffer.iter().cloned().collect()),
+			Some(since) => replay(&state, since),
+		};
+
+		EventPage {
+			resync: events.is_none(),
+			events: events
+				.unwrap_or_default()
+				.into_iter()
+				.map(|event| event.as_ref().clone())
+				.collect(),
+			latest: state.last_seq,
+		}
+	}
+
+	pub fn last_seq(&self) -> u64 {
+		lock!(self.state).last_seq
+	}
+
+	pub fn subscribers(&self) -> usize {
+		lock!(self.state).subscribers.len()
+	}
+}
+
+/// Buffered events newer than `since`, `None` if some of them were already dropped
+fn replay(state: &State, since: u64) -> Option<Vec<Arc<Event>>> {
+	// Sequence from the future means the server has restarted
+	if since > state.last_seq {
+		return None;
+	}
+
+	let oldest = state.buffer.front().map_or(state.last_seq + 1, |event| event.seq);
+
+	if since + 1 < oldest {
+		None
+	} else {
+		Some(state.buffer.iter().filter(|event| event.seq > since).cloned().collect())
+	}
+}
@@ -0,0 +1,380 @@
+use anyhow::{bail, Context, Result};
+use glob::{MatchOptions, Pattern};
+use rbx_dom_weak::{
+	types::{Ref, Variant},
+	Instance, Ustr,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::{HashMap, HashSet},
+	path::PathBuf,
+	sync::Mutex,
+};
+
+use super::{helpers::memory, tree::Tree};
+use crate::{class_meta::InstanceMetadata, constants::FIND_SHARD_SIZE, lock, vfs::Vfs};
+
+/// Number of times the scan can start over because
+/// the tree changed in between shards before giving up
+const MAX_RESTARTS: usize = 3;
+
+/// Search parameters as received by the `/find` endpoint,
+/// `property` can contain multiple `Name:Value` pairs separated by commas
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FindRequest {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name_regex: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub class: Option<String>,
+	#[serde(default)]
+	pub subclasses: bool,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub path_glob: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub property: Option<String>,
+	/// Stable ID of the instance, persisted across sessions
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub id: Option<String>,
+
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub cursor: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub limit: Option<usize>,
+	/// `class-meta` attaches class metadata of found instances
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub enrich: Option<String>,
+}
+
+impl FindRequest {
+	/// Parses query used by `argon find`, e.g. `ClassName=RemoteEvent path~Remotes/*`,
+	/// terms are separated by whitespace and can use `=` for exact match or `~` for:
+	/// regex (`Name`), subclass-of (`ClassName`) or glob (`Path`), `Id` matches stable ID, other keys are properties
+	pub fn parse(query: &str) -> Result<Self> {
+		let mut request = Self::default();
+		let mut properties = vec![];
+
+		for term in query.split_whitespace() {
+			let index = term
+				.find(['=', '~'])
+				.with_context(|| format!("Invalid search term `{}`, expected `key=value` or `key~value`", term))?;
+
+			let (key, value) = (&term[..index], &term[index + 1..]);
+			let fuzzy = term[index..].starts_with('~');
+
+			if key.is_empty() || value.is_empty() {
+				bail!("Invalid search term `{}`, key and value cannot be empty", term);
+			}
+
+			match (key.to_lowercase().as_str(), fuzzy) {
+				("name", false) => request.name = Some(value.to_owned()),
+				("name", true) => request.name_regex = Some(value.to_owned()),
+				("classname" | "class", subclasses) => {
+					request.class = Some(value.to_owned());
+					request.subclasses = subclasses;
+				}
+				("path", false) => request.path_glob = Some(Pattern::escape(value)),
+				("path", true) => request.path_glob = Some(value.to_owned()),
+				("id", false) => request.id = Some(value.to_owned()),
+				(_, false) => properties.push(format!("{}:{}", key, value)),
+				(_, true) => bail!("Property `{}` can only be compared with `=`", key),
+			}
+		}
+
+		if !properties.is_empty() {
+			request.property = Some(properties.join(","));
+		}
+
+		Ok(request)
+	}
+}
+
+#[derive(Debug, Clone)]
+enum NameFilter {
+	Exact(String),
+	Regex(Regex),
+}
+
+#[derive(Debug, Clone)]
+pub struct FindQuery {
+	names: Vec<NameFilter>,
+	class: Option<(String, bool)>,
+	path: Option<Pattern>,
+	properties: Vec<(Ustr, String)>,
+	stable_id: Option<String>,
+}
+
+impl FindQuery {
+	pub fn new(request: &FindRequest) -> Result<Self> {
+		let mut names = vec![];
+
+		if let Some(name) = &request.name {
+			names.push(NameFilter::Exact(name.to_owned()));
+		}
+
+		if let Some(regex) = &request.name_regex {
+			names.push(NameFilter::Regex(
+				Regex::new(regex).with_context(|| format!("Invalid name regex `{}`", regex))?,
+			));
+		}
+
+		let path = if let Some(glob) = &request.path_glob {
+			// Relative patterns can match anywhere in the tree
+			let glob = if glob.starts_with('/') || glob.starts_with("**") {
+				glob.trim_start_matches('/').to_owned()
+			} else {
+				format!("**/{}", glob)
+			};
+
+			Some(Pattern::new(&glob).with_context(|| format!("Invalid path glob `{}`", glob))?)
+		} else {
+			None
+		};
+
+		let mut properties = vec![];
+
+		if let Some(property) = &request.property {
+			for pair in property.split(',') {
+				let (name, value) = pair
+					.split_once(':')
+					.with_context(|| format!("Invalid property filter `{}`, expected `Name:Value`", pair))?;
+
+				properties.push((Ustr::from(name), value.to_owned()));
+			}
+		}
+
+		Ok(Self {
+			names,
+			class: request.class.clone().map(|class| (class, request.subclasses)),
+			path,
+			properties,
+			stable_id: request.id.clone(),
+		})
+	}
+
+	/// Checks every predicate except properties which may need to be materialized
+	fn matches_instance(&self, instance: &Instance, path: &str) -> bool {
+		let names_match = self.names.iter().all(|filter| match filter {
+			NameFilter::Exact(name) => instance.name == *name,
+			NameFilter::Regex(regex) => regex.is_match(&instance.name),
+		});
+
+		let class_matches = match &self.class {
+			Some((class, true)) => is_a(&instance.class, class),
+			Some((class, false)) => instance.class == class.as_str(),
+			None => true,
+		};
+
+		let path_matches = self.path.as_ref().is_none_or(|pattern| {
+			pattern.matches_with(
+				path,
+				MatchOptions {
+					require_literal_separator: true,
+					..MatchOptions::default()
+				},
+			)
+		});
+
+		names_match && class_matches && path_matches
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoundInstance {
+	pub id: Ref,
+	/// Instance path separated with `/`, starting with the root
+	pub path: String,
+	pub class: Ustr,
+	/// Files the instance is synced from
+	pub files: Vec<PathBuf>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub stable_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindPage {
+	/// Tree generation the results were found in
+	pub generation: u64,
+	/// Whether the tree changed since the previous page
+	pub restart: bool,
+	/// Number of all matching instances
+	pub total: usize,
+	pub instances: Vec<FoundInstance>,
+	/// Cursor of the next page, `None` if this is the last one
+	pub cursor: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub class_meta: Option<HashMap<Ref, InstanceMetadata>>,
+}
+
+/// Checks if `class` is `superclass` or inherits from it
+pub fn is_a(class: &str, superclass: &str) -> bool {
+	let database = rbx_reflection_database::get();
+	let mut current = Some(class);
+
+	while let Some(class) = current {
+		if class == superclass {
+			return true;
+		}
+
+		current = database
+			.classes
+			.get(class)
+			.and_then(|descriptor| descriptor.superclass.as_deref());
+	}
+
+	false
+}
+
+/// Compares resolved property value with the one from the query,
+/// only strings, numbers and booleans are supported
+pub fn variant_eq(variant: &Variant, value: &str) -> bool {
+	match variant {
+		Variant::String(string) => string == value,
+		Variant::Bool(bool) => value.parse() == Ok(*bool),
+		Variant::Float32(float) => value.parse() == Ok(*float),
+		Variant::Float64(float) => value.parse() == Ok(*float),
+		Variant::Int32(int) => value.parse::<f64>() == Ok(*int as f64),
+		Variant::Int64(int) => value.parse::<f64>() == Ok(*int as f64),
+		Variant::Enum(item) => value.parse() == Ok(item.to_u32()),
+		_ => false,
+	}
+}
+
+/// Searches the whole tree, locking it only for `FIND_SHARD_SIZE` instances
+/// at a time so large scans do not block the processor, results are sorted by path
+pub fn find(tree: &Mutex<Tree>, vfs: &Vfs, query: &FindQuery, cursor: Option<&str>, limit: usize) -> Result<FindPage> {
+	let cursor = if let Some(cursor) = cursor {
+		let (generation, offset) = cursor.split_once(':').context("Invalid find cursor")?;
+		Some((generation.parse::<u64>()?, offset.parse::<usize>()?))
+	} else {
+		None
+	};
+
+	let mut found = vec![];
+	let mut visited = HashSet::new();
+	let mut restarts = 0;
+
+	let mut generation = lock!(tree).generation();
+	let mut next = Some(lock!(tree).root_ref());
+
+	while next.is_some() {
+		let mut tree = lock!(tree);
+
+		tree.refresh_path_index();
+
+		if tree.generation() != generation {
+			generation = tree.generation();
+
+			// Continue from the root if the instance we stopped at was removed
+			if next.is_some_and(|id| !tree.exists(id)) {
+				if restarts == MAX_RESTARTS {
+					bail!("Tree changed too many times during the search");
+				}
+
+				restarts += 1;
+				next = Some(tree.root_ref());
+			}
+		}
+
+		for _ in 0..FIND_SHARD_SIZE {
+			let id = match next {
+				Some(id) => id,
+				None => break,
+			};
+
+			next = tree.next_in_order(id);
+
+			if !visited.insert(id) {
+				continue;
+			}
+
+			let instance = tree.get_instance(id).unwrap();
+			let path = tree.path_index().path(id).unwrap_or_default();
+
+			let meta = tree.get_meta(id);
+
+			if !query.matches_instance(instance, &path) {
+				continue;
+			}
+
+			if query
+				.stable_id
+				.as_ref()
+				.is_some_and(|stable_id| meta.and_then(|meta| meta.stable_id.as_ref()) != Some(stable_id))
+			{
+				continue;
+			}
+
+			if !query.properties.is_empty() {
+				let evicted = tree.get_evicted(id);
+				let materialized = if query
+					.properties
+					.iter()
+					.any(|(name, _)| evicted.is_some_and(|evicted| evicted.contains_key(name)))
+				{
+					memory::materialize_lossy(id, &tree, vfs)
+				} else {
+					None
+				};
+
+				let properties = materialized.as_ref().unwrap_or(&instance.properties);
+
+				if !query
+					.properties
+					.iter()
+					.all(|(name, value)| properties.get(name).is_some_and(|variant| variant_eq(variant, value)))
+				{
+					continue;
+				}
+			}
+
+			let files = meta
+				.map(|meta| meta.source.paths().into_iter().map(|path| path.to_owned()).collect())
+				.unwrap_or_default();
+
+			found.push(FoundInstance {
+				id,
+				path,
+				class: instance.class,
+				files,
+				stable_id: meta.and_then(|meta| meta.stable_id.clone()),
+			});
+		}
+	}
+
+	if let Some((cursor_generation, _)) = cursor {
+		if cursor_generation != generation {
+			return Ok(FindPage {
+				generation,
+				restart: true,
+				total: found.len(),
+				instances: vec![],
+				cursor: None,
+				class_meta: None,
+			});
+		}
+	}
+
+	found.sort_by(|a, b| {
+		a.path
+			.cmp(&b.path)
+			.then_with(|| a.id.to_string().cmp(&b.id.to_string()))
+	});
+
+	let total = found.len();
+	let offset = cursor.map_or(0, |(_, offset)| offset).min(total);
+	let end = (offset + limit).min(total);
+
+	Ok(FindPage {
+		generation,
+		restart: false,
+		total,
+		instances: found.drain(offset..end).collect(),
+		cursor: (end < total).then(|| format!("{}:{}", generation, end)),
+		class_meta: None,
+	})
+}
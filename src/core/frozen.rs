@@ -0,0 +1,233 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use log::warn;
+use rbx_dom_weak::types::Ref;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	fmt::{self, Display, Formatter},
+	fs, mem,
+	path::{Path, PathBuf},
+};
+
+use super::changes::Changes;
+
+/// Direction of sync between the disk and Studio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncDirection {
+	/// Changes made on disk are sent to Studio
+	ToStudio,
+	/// Changes made in Studio are written to disk
+	ToDisk,
+}
+
+impl Display for SyncDirection {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			SyncDirection::ToStudio => write!(f, "to-studio"),
+			SyncDirection::ToDisk => write!(f, "to-disk"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+	/// Stable ID of the instance, keeps the entry across renames
+	#[serde(skip_serializing_if = "Option::is_none")]
+	stable_id: Option<String>,
+	/// Last known path of the instance, used when it has no stable ID
+	path: String,
+	/// Unix timestamp in seconds of freezing or the last manual sync
+	since: i64,
+	/// Number of disk changes that were not sent to Studio
+	#[serde(default)]
+	to_studio: usize,
+	/// Number of Studio changes that were not written to disk
+	#[serde(default)]
+	to_disk: usize,
+}
+
+/// Frozen instance with the number of changes suppressed since `since`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrozenInstance {
+	/// `None` if the instance is not in the tree right now
+	pub id: Option<Ref>,
+	pub path: String,
+	pub since: i64,
+	pub suppressed_to_studio: usize,
+	pub suppressed_to_disk: usize,
+}
+
+impl FrozenInstance {
+	pub fn suppressed(&self) -> usize {
+		self.suppressed_to_studio + self.suppressed_to_disk
+	}
+}
+
+/// Instances left alone by sync in both directions until they are synced
+/// manually, stored in `.argon/frozen.json` so they stay frozen after restart
+#[derive(Debug, Default)]
+pub struct Frozen {
+	/// `None` if frozen instances are not persisted
+	path: Option<PathBuf>,
+	entries: HashMap<Ref, Entry>,
+	/// Entries of instances that are not in the tree, kept until they come back
+	detached: Vec<Entry>,
+	/// Studio changes suppressed in frozen subtrees, applied by manual sync to disk
+	deferred: HashMap<Ref, Changes>,
+}
+
+impl Frozen {
+	/// Reads frozen instances of the workspace, they have to be
+	/// attached to the tree before they take effect
+	pub fn load(workspace_dir: &Path) -> Self {
+		let path = workspace_dir.join(".argon").join("frozen.json");
+
+		let detached = if path.exists() {
+			match read_entries(&path) {
+				Ok(entries) => entries,
+				Err(err) => {
+					warn!("Failed to read frozen instances from {}: {}", path.display(), err);
+					vec![]
+				}
+			}
+		} else {
+			vec![]
+		};
+
+		Self {
+			path: Some(path),
+			detached,
+			..Self::default()
+		}
+	}
+
+	/// Resolves detached entries to instances by their stable ID and path
+	pub fn attach(&mut self, resolve: impl Fn(Option<&str>, &str) -> Option<Ref>) {
+		for entry in mem::take(&mut self.detached) {
+			match resolve(entry.stable_id.as_deref(), &entry.path) {
+				Some(id) if !self.entries.contains_key(&id) => {
+					self.entries.insert(id, entry);
+				}
+				_ => self.detached.push(entry),
+			}
+		}
+	}
+
+	/// Keeps entry of the removed instance so it
+	/// is frozen again once the instance comes back
+	pub fn detach(&mut self, id: Ref) {
+		if let Some(entry) = self.entries.remove(&id) {
+			self.detached.push(entry);
+			self.deferred.remove(&id);
+		}
+	}
+
+	pub fn insert(&mut self, id: Ref, stable_id: Option<String>, path: String) -> bool {
+		if self.entries.contains_key(&id) {
+			return false;
+		}
+
+		self.entries.insert(
+			id,
+			Entry {
+				stable_id,
+				path,
+				since: chrono::Utc::now().timestamp(),
+				to_studio: 0,
+				to_disk: 0,
+			},
+		);
+
+		true
+	}
+
+	pub fn remove(&mut self, id: Ref) -> bool {
+		self.deferred.remove(&id);
+		self.entries.remove(&id).is_some()
+	}
+
+	pub fn contains(&self, id: Ref) -> bool {
+		self.entries.contains_key(&id)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// Counts changes that were not synced in the given direction
+	pub fn suppress(&mut self, id: Ref, direction: SyncDirection, count: usize) {
+		if let Some(entry) = self.entries.get_mut(&id) {
+			match direction {
+				SyncDirection::ToStudio => entry.to_studio += count,
+				SyncDirection::ToDisk => entry.to_disk += count,
+			}
+		}
+	}
+
+	pub fn defer(&mut self, id: Ref, changes: Changes) {
+		self.deferred.entry(id).or_insert_with(Changes::new).extend(changes);
+	}
+
+	pub fn take_deferred(&mut self, id: Ref) -> Changes {
+		self.deferred.remove(&id).unwrap_or_else(Changes::new)
+	}
+
+	/// Clears counters after the instance got manually synced
+	pub fn reset(&mut self, id: Ref) {
+		if let Some(entry) = self.entries.get_mut(&id) {
+			entry.since = chrono::Utc::now().timestamp();
+			entry.to_studio = 0;
+			entry.to_disk = 0;
+		}
+	}
+
+	/// Returns frozen instances sorted by their paths,
+	/// `path_of` provides the current path of attached ones
+	pub fn list(&self, path_of: impl Fn(Ref) -> Option<String>) -> Vec<FrozenInstance> {
+		let mut instances: Vec<FrozenInstance> = self
+			.entries
+			.iter()
+			.map(|(id, entry)| (Some(*id), entry))
+			.chain(self.detached.iter().map(|entry| (None, entry)))
+			.map(|(id, entry)| FrozenInstance {
+				id,
+				path: id.and_then(&path_of).unwrap_or_else(|| entry.path.clone()),
+				since: entry.since,
+				suppressed_to_studio: entry.to_studio,
+				suppressed_to_disk: entry.to_disk,
+			})
+			.collect();
+
+		instances.sort_by(|a, b| a.path.cmp(&b.path));
+		instances
+	}
+
+	/// Records current paths of the instances and writes everything to `.argon/frozen.json`
+	pub fn save(&mut self, path_of: impl Fn(Ref) -> Option<String>) -> Result<()> {
+		let Some(path) = &self.path else {
+			return Ok(());
+		};
+
+		for (id, entry) in self.entries.iter_mut() {
+			if let Some(current) = path_of(*id) {
+				entry.path = current;
+			}
+		}
+
+		let mut entries: Vec<&Entry> = self.entries.values().chain(self.detached.iter()).collect();
+		entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+		fs::create_dir_all(path.parent().unwrap())?;
+		fs::write(path, serde_json::to_string_pretty(&entries)?)?;
+
+		Ok(())
+	}
+}
+
+fn read_entries(path: &Path) -> Result<Vec<Entry>> {
+	Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
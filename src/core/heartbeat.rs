@@ -0,0 +1,274 @@
+use anyhow::Result;
+use colored::Colorize;
+use rbx_dom_weak::types::Ref;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::{queue::Queue, snapshot_subtree, tree::Tree};
+use crate::{
+	argon_warn,
+	server::{protocol::Capability, Heartbeat, SyncSubtree},
+	t,
+	vfs::Vfs,
+};
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Hash of an instance subtree as computed by either side
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeHash {
+	pub id: Ref,
+	pub hash: String,
+}
+
+/// Instance whose hash differs from the one sent by the client,
+/// along with hashes of its children to narrow the divergence down
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Divergent {
+	pub id: Ref,
+	pub children: Vec<NodeHash>,
+}
+
+/// Response to a single narrowing round, see `hash_subtree`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comparison {
+	pub divergent: Vec<Divergent>,
+	/// Compared instances that no longer exist, the client should remove them
+	pub removed: Vec<Ref>,
+	/// Number of instances sent back in `SyncSubtree` messages
+	pub resynced: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeartbeatStats {
+	/// Heartbeats sent to clients
+	pub sent: u64,
+	/// Heartbeats whose hash did not match the client's tree
+	pub mismatches: u64,
+	/// Subtrees resent to recover from mismatches
+	pub resynced_subtrees: u64,
+	/// Instances in the resent subtrees
+	pub resynced_instances: u64,
+}
+
+/// Periodic consistency checks of client trees
+#[derive(Debug, Default)]
+pub struct Heartbeats {
+	sent: AtomicU64,
+	mismatches: AtomicU64,
+	resynced_subtrees: AtomicU64,
+	resynced_instances: AtomicU64,
+}
+
+impl Heartbeats {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sends hash of the whole tree to clients that negotiated the
+	/// `heartbeat` capability and are not in the middle of the initial
+	/// sync, returns the number of clients the heartbeat was sent to.
+	/// Clients that subscribed to a part of the tree are left out
+	pub fn send<F>(&self, tree: &Tree, queue: &Queue, is_syncing: F) -> Result<usize>
+	where
+		F: Fn(u32) -> bool,
+	{
+		let clients: Vec<u32> = queue
+			.clients()
+			.into_iter()
+			.filter(|client| {
+				client.negotiated.supports(Capability::Heartbeat) && client.filter.is_none() && !is_syncing(client.id)
+			})
+			.map(|client| client.id)
+			.collect();
+
+		if clients.is_empty() {
+			return Ok(0);
+		}
+
+		let root = tree.root_ref();
+		let hash = format_hash(hash_subtree(tree, root).unwrap());
+
+		for client in &clients {
+			queue.push(
+				Heartbeat {
+					id: root,
+					hash: hash.clone(),
+				},
+				Some(*client),
+			)?;
+		}
+
+		self.sent.fetch_add(clients.len() as u64, Ordering::Relaxed);
+
+		Ok(clients.len())
+	}
+
+	/// Handles a narrowing round of the client, `compare` are the client's hashes
+	/// of instances it disagrees on and `resync` are the instances it wants resent
+	pub fn compare(
+		&self,
+		client: u32,
+		compare: &[NodeHash],
+		resync: &[Ref],
+		tree: &Tree,
+		vfs: &Vfs,
+		queue: &Queue,
+	) -> Result<Comparison> {
+		let mut comparison = Comparison::default();
+
+		for node in compare {
+			let Some(hash) = hash_subtree(tree, node.id) else {
+				comparison.removed.push(node.id);
+				continue;
+			};
+
+			if format_hash(hash) == node.hash {
+				continue;
+			}
+
+			if node.id == tree.root_ref() {
+				self.mismatches.fetch_add(1, Ordering::Relaxed);
+			}
+
+			let children = tree
+				.get_instance(node.id)
+				.unwrap()
+				.children()
+				.iter()
+				.map(|child| NodeHash {
+					id: *child,
+					hash: format_hash(hash_subtree(tree, *child).unwrap()),
+				})
+				.collect();
+
+			comparison.divergent.push(Divergent { id: node.id, children });
+		}
+
+		let mut paths = vec![];
+
+		for id in resync {
+			let Some(snapshot) = snapshot_subtree(*id, true, tree, vfs) else {
+				comparison.removed.push(*id);
+				continue;
+			};
+
+			let count = count_subtree(tree, *id);
+
+			queue.push(
+				SyncSubtree {
+					instance: Box::new(snapshot),
+				},
+				Some(client),
+			)?;
+			paths.extend(tree.get_full_name(*id));
+
+			comparison.resynced += count;
+
+			self.resynced_subtrees.fetch_add(1, Ordering::Relaxed);
+			self.resynced_instances.fetch_add(count as u64, Ordering::Relaxed);
+		}
+
+		if !paths.is_empty() {
+			argon_warn!(
+				"{}",
+				t!(
+					"heartbeat.diverged",
+					client = client.to_string().bold(),
+					paths = paths.join(", ").bold()
+				)
+			);
+		}
+
+		Ok(comparison)
+	}
+
+	pub fn stats(&self) -> HeartbeatStats {
+		HeartbeatStats {
+			sent: self.sent.load(Ordering::Relaxed),
+			mismatches: self.mismatches.load(Ordering::Relaxed),
+			resynced_subtrees: self.resynced_subtrees.load(Ordering::Relaxed),
+			resynced_instances: self.resynced_instances.load(Ordering::Relaxed),
+		}
+	}
+}
+
+/// Hashes the instance with all of its descendants, `None` if it does not exist.
+///
+/// The hash is FNV-1a (64 bit) of `<id>\t<name>\t<class>\n` followed by `<hash>\n`
+/// of every child in ascending order, so it covers the hierarchy but not property
+/// values and does not depend on the order of children.
+///
+/// Clients compare the hash of the tree received in `Heartbeat` with their own
+/// and on mismatch send their hash of the root to `/heartbeat`, getting hashes
+/// of its children back. Children with different hashes are compared in the next
+/// round, children the client is missing are requested to be resynced and ones
+/// the server does not have are removed by the client. When all children match
+/// but their parent does not, the parent itself is resynced. Resynced instances
+/// are sent whole with `SyncSubtree`, so only divergent subtrees are transferred
+pub fn hash_subtree(tree: &Tree, id: Ref) -> Option<u64> {
+	tree.get_instance(id)?;
+
+	let mut hashes: HashMap<Ref, u64> = HashMap::new();
+	let mut stack = vec![(id, false)];
+
+	// Children are hashed before their parents without recursion
+	while let Some((id, visited)) = stack.pop() {
+		let instance = tree.get_instance(id).unwrap();
+
+		if !visited {
+			stack.push((id, true));
+			stack.extend(instance.children().iter().map(|child| (*child, false)));
+			continue;
+		}
+
+		let mut children: Vec<u64> = instance
+			.children()
+			.iter()
+			.filter_map(|child| hashes.remove(child))
+			.collect();
+
+		children.sort();
+
+		let mut state = update(FNV_OFFSET, &format!("{}\t{}\t{}\n", id, instance.name, instance.class));
+
+		for child in children {
+			state = update(state, &format!("{:016x}\n", child));
+		}
+
+		hashes.insert(id, state);
+	}
+
+	hashes.remove(&id)
+}
+
+pub fn format_hash(hash: u64) -> String {
+	format!("{:016x}", hash)
+}
+
+fn count_subtree(tree: &Tree, id: Ref) -> usize {
+	let mut count = 0;
+	let mut stack = vec![id];
+
+	while let Some(id) = stack.pop() {
+		count += 1;
+		stack.extend(tree.get_instance(id).unwrap().children());
+	}
+
+	count
+}
+
+fn update(mut state: u64, line: &str) -> u64 {
+	for byte in line.bytes() {
+		state ^= byte as u64;
+		state = state.wrapping_mul(FNV_PRIME);
+	}
+
+	state
+}
@@ -0,0 +1,109 @@
+use rbx_dom_weak::types::Ref;
+use std::{
+	collections::HashMap,
+	fmt::{self, Display, Formatter},
+	path::{Path, PathBuf},
+};
+
+use crate::{
+	core::{meta::SourceEntry, tree::Tree},
+	ext::PathExt,
+};
+
+/// Sibling instances that would be written to the same path,
+/// kept in the tree until one of them is renamed or removed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collision {
+	/// Instance that was moved to `renamed` to avoid the collision
+	pub id: Ref,
+	/// Sibling that already claimed the contested path
+	pub other: Ref,
+	/// Name both instances share, ignoring case on case-insensitive file systems
+	pub name: String,
+	pub path: PathBuf,
+	pub renamed: PathBuf,
+}
+
+impl Display for Collision {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{} collides with its sibling at {} and was saved to {} instead",
+			self.name,
+			self.path.display(),
+			self.renamed.display()
+		)
+	}
+}
+
+/// Whether `Foo` and `foo` point to the same file,
+/// true for the default file systems of Windows and macOS
+pub fn is_case_insensitive() -> bool {
+	cfg!(any(windows, target_os = "macos"))
+}
+
+/// File and folder paths claimed by children of a single parent
+#[derive(Debug, Clone, Default)]
+pub struct Claims {
+	case_insensitive: bool,
+	paths: HashMap<PathBuf, Ref>,
+}
+
+impl Claims {
+	pub fn new(case_insensitive: bool) -> Self {
+		Self {
+			case_insensitive,
+			paths: HashMap::new(),
+		}
+	}
+
+	/// Collects paths of all current children of the parent
+	pub fn of_children(parent: Ref, tree: &Tree) -> Self {
+		let mut claims = Self::new(is_case_insensitive());
+
+		let Some(instance) = tree.get_instance(parent) else {
+			return claims;
+		};
+
+		for child in instance.children() {
+			let Some(meta) = tree.get_meta(*child) else {
+				continue;
+			};
+
+			for entry in meta.source.relevant() {
+				if let SourceEntry::File(path) | SourceEntry::Folder(path) = entry {
+					claims.claim(path, *child);
+				}
+			}
+		}
+
+		claims
+	}
+
+	/// Claims the path for the instance, returns
+	/// the previous owner if it was a different one
+	pub fn claim(&mut self, path: &Path, id: Ref) -> Option<Ref> {
+		let key = self.key(path);
+
+		match self.paths.get(&key) {
+			Some(owner) if *owner != id => Some(*owner),
+			Some(_) => None,
+			None => {
+				self.paths.insert(key, id);
+				None
+			}
+		}
+	}
+
+	pub fn owner(&self, path: &Path) -> Option<Ref> {
+		self.paths.get(&self.key(path)).copied()
+	}
+
+	fn key(&self, path: &Path) -> PathBuf {
+		if self.case_insensitive {
+			path.with_file_name(path.get_name().to_lowercase())
+		} else {
+			path.to_owned()
+		}
+	}
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use log::trace;
+use rbx_dom_weak::types::Ref;
+use std::path::{Path, PathBuf};
+
+use crate::{
+	core::{
+		meta::{Meta, SourceEntry, SourceKind},
+		tree::Tree,
+	},
+	ext::PathExt,
+	middleware::Middleware,
+	project::ScriptExtension,
+	vfs::Vfs,
+};
+
+/// Outcome of `migrate`
+#[derive(Debug, Default)]
+pub struct Migration {
+	/// Old and new paths of renamed script files
+	pub renamed: Vec<(PathBuf, PathBuf)>,
+	/// Script files that were left as they are because a file
+	/// with the new name exists or the project points directly to them
+	pub skipped: Vec<PathBuf>,
+}
+
+/// Renames script files of the instance and all of its descendants
+/// to the given extension and updates their sources in the same pass
+pub fn migrate(id: Ref, extension: ScriptExtension, tree: &mut Tree, vfs: &Vfs) -> Result<Migration> {
+	let mut migration = Migration::default();
+	let mut stack = vec![id];
+
+	while let Some(id) = stack.pop() {
+		if let Some(instance) = tree.get_instance(id) {
+			stack.extend(instance.children().iter().rev());
+		}
+
+		let Some(meta) = tree.get_meta(id) else {
+			continue;
+		};
+
+		let Some(path) = get_script_path(meta, extension) else {
+			continue;
+		};
+
+		// Project files would point to a path that no longer exists
+		if let SourceKind::Project(_, project_path, _, _) = meta.source.get() {
+			if *project_path == path {
+				migration.skipped.push(path);
+				continue;
+			}
+		}
+
+		let new_path = path.with_extension(extension.as_str());
+
+		if vfs.exists(&path) {
+			if vfs.exists(&new_path) {
+				migration.skipped.push(path);
+				continue;
+			}
+
+			trace!(from:% = path.display(), to:% = new_path.display(); "Migrating script extension");
+
+			vfs.rename(&path, &new_path)?;
+			migration.renamed.push((path.clone(), new_path.clone()));
+		} else if !vfs.exists(&new_path) {
+			continue;
+		}
+
+		// Files backing multiple instances are renamed once, the rest only get new sources
+		let mut meta = meta.clone();
+
+		if let SourceKind::Path(inner) = meta.source.get_mut() {
+			if *inner == path {
+				new_path.clone_into(inner);
+			}
+		}
+
+		if let Some(SourceEntry::File(file)) = meta.source.get_file_mut() {
+			*file = new_path;
+		}
+
+		tree.update_meta(id, meta);
+	}
+
+	Ok(migration)
+}
+
+/// Returns path of the script file of the instance if it does not have the extension yet
+fn get_script_path(meta: &Meta, extension: ScriptExtension) -> Option<PathBuf> {
+	let path = meta.source.get_file().map(SourceEntry::path)?;
+
+	if path.get_ext() != extension.other().as_str() {
+		return None;
+	}
+
+	let middleware = meta
+		.context
+		.sync_rules()
+		.find_map(|rule| rule.resolve(path).or_else(|| rule.resolve_child(path)))?
+		.middleware;
+
+	is_script(&middleware).then(|| Path::to_owned(path))
+}
+
+fn is_script(middleware: &Middleware) -> bool {
+	matches!(
+		middleware,
+		Middleware::ServerScript | Middleware::ClientScript | Middleware::ModuleScript
+	)
+}
@@ -0,0 +1,61 @@
+use log::error;
+use rbx_dom_weak::types::Ref;
+
+use crate::{
+	core::{meta::SourceIssue, tree::Tree},
+	vfs::Vfs,
+};
+
+/// Validates sources of the instance and optionally all of its descendants
+pub fn validate(id: Ref, recursive: bool, tree: &Tree, vfs: &Vfs) -> Vec<(Ref, SourceIssue)> {
+	let mut issues = vec![];
+	let mut stack = vec![id];
+
+	while let Some(id) = stack.pop() {
+		if let Some(meta) = tree.get_meta(id) {
+			issues.extend(meta.source.validate(vfs).into_iter().map(|issue| (id, issue)));
+		}
+
+		if !recursive {
+			break;
+		}
+
+		if let Some(instance) = tree.get_instance(id) {
+			stack.extend(instance.children().iter().rev());
+		}
+	}
+
+	issues
+}
+
+/// Validates sources after processor operation, every broken
+/// invariant is logged and the affected sources are repaired,
+/// runs in debug builds only as it checks every path on disk
+pub fn check(id: Ref, recursive: bool, tree: &mut Tree, vfs: &Vfs, operation: &str) {
+	if !cfg!(debug_assertions) {
+		return;
+	}
+
+	let mut repaired = vec![];
+
+	for (id, issue) in validate(id, recursive, tree, vfs) {
+		let meta = tree.get_meta(id).unwrap();
+
+		error!(
+			"Invalid source after {}: instance: {:?} ({}), issue: {}, entries: {:?}",
+			operation,
+			id,
+			tree.get_full_name(id).unwrap_or_default(),
+			issue,
+			meta.source.relevant()
+		);
+
+		repaired.push(id);
+	}
+
+	for id in repaired {
+		if let Some(meta) = tree.get_meta_mut(id) {
+			meta.source.repair(vfs);
+		}
+	}
+}
@@ -0,0 +1,153 @@
+use anyhow::{bail, Result};
+use log::{trace, warn};
+use rbx_dom_weak::{
+	types::{Ref, Variant},
+	UstrMap,
+};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::{
+	constants::EVICTION_THRESHOLD,
+	core::{
+		meta::{SourceEntry, SourceKind},
+		tree::Tree,
+	},
+	middleware::{manifest, new_snapshot, project::new_snapshot_node},
+	vfs::Vfs,
+	Properties,
+};
+
+/// Returns hash of the property value if it is large enough to be evicted
+pub fn hash_large(value: &Variant) -> Option<u64> {
+	let bytes: &[u8] = match value {
+		Variant::String(string) => string.as_bytes(),
+		Variant::BinaryString(string) => string.as_ref(),
+		_ => return None,
+	};
+
+	if bytes.len() < EVICTION_THRESHOLD {
+		return None;
+	}
+
+	let mut hasher = DefaultHasher::new();
+
+	value.ty().hash(&mut hasher);
+	bytes.hash(&mut hasher);
+
+	Some(hasher.finish())
+}
+
+/// Compares snapshot properties with instance properties
+/// whose large values might have been evicted from memory
+pub fn properties_eq(snapshot: &Properties, instance: &Properties, evicted: Option<&UstrMap<u64>>) -> bool {
+	let evicted = match evicted {
+		Some(evicted) => evicted,
+		None => return snapshot == instance,
+	};
+
+	if snapshot.len() != instance.len() + evicted.len() {
+		return false;
+	}
+
+	snapshot.iter().all(|(name, value)| match evicted.get(name) {
+		Some(hash) => hash_large(value) == Some(*hash),
+		None => instance.get(name) == Some(value),
+	})
+}
+
+/// Returns instance properties with evicted values read back from the file that owns
+/// the instance, without its children. It does not modify the tree so the memory usage
+/// stays the same, fails if any evicted value can't be recovered from the file
+/// or it changed since it was evicted
+pub fn materialize(id: Ref, tree: &Tree, vfs: &Vfs) -> Result<Properties> {
+	let Some(instance) = tree.get_instance(id) else {
+		bail!("Instance {:?} does not exist", id);
+	};
+
+	let mut properties = instance.properties.clone();
+
+	let evicted = match tree.get_evicted(id) {
+		Some(evicted) => evicted,
+		None => return Ok(properties),
+	};
+
+	trace!("Materializing {} evicted properties of {:?}", evicted.len(), id);
+
+	let Some(meta) = tree.get_meta(id) else {
+		bail!("Instance {:?} has no meta", id);
+	};
+
+	let context = meta.context.with_shallow();
+
+	let snapshot = match meta.source.get() {
+		SourceKind::Project(name, path, node, node_path) => Some(new_snapshot_node(
+			name,
+			path,
+			*node.clone(),
+			node_path.clone(),
+			&context,
+			vfs,
+		)?),
+		SourceKind::Path(path) => match meta.source.get_manifest() {
+			Some(SourceEntry::Manifest(path, key)) => manifest::read_entry(path, key, &context, vfs)?,
+			_ => new_snapshot(path, &context, vfs)?,
+		},
+		SourceKind::Ephemeral | SourceKind::None => None,
+	};
+
+	let Some(mut snapshot) = snapshot else {
+		bail!(
+			"Source of {:?} no longer exists, {} evicted properties can't be recovered",
+			id,
+			evicted.len()
+		);
+	};
+
+	for (name, hash) in evicted {
+		let Some(value) = snapshot.properties.remove(name) else {
+			bail!("Evicted property {} of {:?} no longer exists in its source", name, id);
+		};
+
+		// Value from the changed file is not the one the tree describes,
+		// it is read again when the pending VFS event is processed
+		if hash_large(&value) != Some(*hash) {
+			bail!(
+				"Evicted property {} of {:?} changed on disk, it is going to be read again",
+				name,
+				id
+			);
+		}
+
+		properties.insert(*name, value);
+	}
+
+	Ok(properties)
+}
+
+/// Same as `materialize` but values that can't be recovered are left out with a
+/// warning, for reads sent to clients that the pending VFS event corrects anyway.
+/// Returns `None` only if the instance does not exist
+pub fn materialize_lossy(id: Ref, tree: &Tree, vfs: &Vfs) -> Option<Properties> {
+	let instance = tree.get_instance(id)?;
+
+	match materialize(id, tree, vfs) {
+		Ok(properties) => Some(properties),
+		Err(err) => {
+			warn!("Failed to materialize properties of {:?}: {}", id, err);
+			Some(instance.properties.clone())
+		}
+	}
+}
+
+/// Moves materialized values back into the tree, used before operations that read
+/// the whole tree at once, values that can't be recovered stay evicted
+pub fn restore_all(tree: &mut Tree, vfs: &Vfs) {
+	let ids: Vec<Ref> = tree.evicted_ids().collect();
+
+	for id in ids {
+		match materialize(id, tree, vfs) {
+			Ok(properties) => tree.restore(id, properties),
+			Err(err) => warn!("Failed to restore evicted properties of {:?}: {}", id, err),
+		}
+	}
+}
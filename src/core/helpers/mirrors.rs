@@ -0,0 +1,131 @@
+use rbx_dom_weak::types::Ref;
+use std::path::{Path, PathBuf};
+
+use crate::core::{
+	changes::Changes,
+	meta::{Meta, SourceEntry, SourceKind},
+	tree::Tree,
+};
+
+/// Project node whose `$path` also backs other nodes,
+/// e.g. a shared module mirrored into a test folder.
+///
+/// File changes fan out to every copy as the processor handles all
+/// instances of the changed path. Only the copy of the node marked with
+/// `$primary: true` is synced back, its writes are then propagated to
+/// the other copies. The rest are read-only as syncback from them would
+/// write the same files and make the copies overwrite each other. When
+/// no node (or more than one) is marked, all copies are read-only
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mirror {
+	/// Instance of the project node
+	pub node: Ref,
+	/// Shared `$path` of the node
+	pub path: PathBuf,
+	pub primary: bool,
+}
+
+impl Mirror {
+	/// Instances of the other nodes backed by the same path
+	pub fn copies(&self, tree: &Tree) -> Vec<Ref> {
+		get_nodes(&self.path, tree)
+			.into_iter()
+			.filter(|id| *id != self.node)
+			.collect()
+	}
+}
+
+/// Returns the mirror the instance belongs to, which is the closest
+/// project node with `$path` of the instance itself or its ancestors
+pub fn find_mirror(id: Ref, tree: &Tree) -> Option<Mirror> {
+	let dom = tree.inner();
+	let mut current = dom.get_by_ref(id);
+
+	while let Some(instance) = current {
+		let meta = tree.get_meta(instance.referent());
+
+		if let Some((path, primary)) = meta.and_then(get_node_path) {
+			let nodes = get_nodes(path, tree);
+
+			if nodes.len() < 2 {
+				return None;
+			}
+
+			// Only one of the nodes can be the primary one
+			let primaries = nodes
+				.iter()
+				.filter_map(|node| tree.get_meta(*node).and_then(get_node_path))
+				.filter(|(_, primary)| *primary)
+				.count();
+
+			return Some(Mirror {
+				node: instance.referent(),
+				path: path.to_owned(),
+				primary: primary && primaries == 1,
+			});
+		}
+
+		current = dom.get_by_ref(instance.parent());
+	}
+
+	None
+}
+
+/// Returns resolved `$path` of the project node and whether it is `$primary`
+fn get_node_path(meta: &Meta) -> Option<(&Path, bool)> {
+	let SourceKind::Project(_, _, node, _) = meta.source.get() else {
+		return None;
+	};
+
+	node.path.as_ref()?;
+
+	let path = meta.source.relevant().iter().find_map(|entry| match entry {
+		SourceEntry::File(path) | SourceEntry::Folder(path) => Some(path.as_path()),
+		_ => None,
+	})?;
+
+	Some((path, node.primary.unwrap_or_default()))
+}
+
+/// Project node instances backed by the path
+fn get_nodes(path: &Path, tree: &Tree) -> Vec<Ref> {
+	tree.get_ids(path)
+		.map(|ids| {
+			ids.iter()
+				.copied()
+				.filter(|id| {
+					tree.get_meta(*id)
+						.and_then(get_node_path)
+						.is_some_and(|(node_path, _)| node_path == path)
+				})
+				.collect()
+		})
+		.unwrap_or_default()
+}
+
+/// Returns copies of the primary nodes that the changes are made in,
+/// they have to be read again after the changes are written
+pub fn find_stale_copies(changes: &Changes, tree: &Tree) -> Vec<Ref> {
+	let ids = changes
+		.additions
+		.iter()
+		.map(|snapshot| snapshot.parent)
+		.chain(changes.updates.iter().map(|snapshot| snapshot.id))
+		.chain(changes.removals.iter().copied());
+
+	let mut copies = vec![];
+
+	for id in ids {
+		for copy in find_mirror(id, tree)
+			.filter(|mirror| mirror.primary)
+			.map(|mirror| mirror.copies(tree))
+			.unwrap_or_default()
+		{
+			if !copies.contains(&copy) {
+				copies.push(copy);
+			}
+		}
+	}
+
+	copies
+}
@@ -2,6 +2,13 @@ use crate::Properties;
 
 mod migrations;
 
+pub mod collisions;
+pub mod extensions;
+pub mod fsck;
+pub mod memory;
+pub mod mirrors;
+pub mod names;
+pub mod packages;
 pub mod syncback;
 
 #[inline]
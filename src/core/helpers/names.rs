@@ -0,0 +1,67 @@
+use icu_normalizer::ComposingNormalizer;
+use std::borrow::Cow;
+
+const NFC: ComposingNormalizer = ComposingNormalizer::new_nfc();
+
+/// Characters that do not render but change the name: zero-width characters
+/// (U+200B-U+200D, U+2060), bidirectional controls (U+061C, U+200E, U+200F,
+/// U+202A-U+202E, U+2066-U+2069), byte order mark (U+FEFF), soft hyphen (U+00AD)
+/// and C1 control characters (U+0080-U+009F). They break path handling and
+/// git tooling, ASCII control characters are handled separately
+pub fn is_invisible(char: char) -> bool {
+	matches!(
+		char,
+		'\u{0080}'..='\u{009F}'
+			| '\u{00AD}'
+			| '\u{061C}'
+			| '\u{200B}'..='\u{200F}'
+			| '\u{202A}'..='\u{202E}'
+			| '\u{2060}'
+			| '\u{2066}'..='\u{2069}'
+			| '\u{FEFF}'
+	)
+}
+
+/// Returns the name in Unicode Normalization Form C, ASCII names are returned as they are
+pub fn normalize(name: &str) -> Cow<'_, str> {
+	if name.is_ascii() || NFC.is_normalized(name) {
+		Cow::Borrowed(name)
+	} else {
+		Cow::Owned(NFC.normalize(name))
+	}
+}
+
+/// Whether both names are the same once normalized, e.g. `é` composed on
+/// Windows and decomposed by the macOS file system. Names read from the disk
+/// have to be compared with instance names using this function
+pub fn names_match(a: &str, b: &str) -> bool {
+	if a == b {
+		return true;
+	}
+
+	if a.is_ascii() && b.is_ascii() {
+		return false;
+	}
+
+	normalize(a) == normalize(b)
+}
+
+/// Like `str::strip_prefix` but the prefix can be in a different normalization form
+pub fn strip_prefix<'a>(name: &'a str, prefix: &str) -> Option<&'a str> {
+	if let Some(stripped) = name.strip_prefix(prefix) {
+		return Some(stripped);
+	}
+
+	if name.is_ascii() && prefix.is_ascii() {
+		return None;
+	}
+
+	let prefix = normalize(prefix);
+
+	name.char_indices()
+		.map(|(index, _)| index)
+		.skip(1)
+		.chain([name.len()])
+		.find(|index| normalize(&name[..*index]) == prefix)
+		.map(|index| &name[index..])
+}
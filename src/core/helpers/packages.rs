@@ -0,0 +1,48 @@
+use rbx_dom_weak::types::Ref;
+
+use crate::core::{snapshot::Snapshot, tree::Tree};
+
+/// Class of the instance that turns its parent into a package
+pub const PACKAGE_LINK: &str = "PackageLink";
+
+/// Package metadata managed by Roblox, writing it to data files
+/// makes Studio mark the package as modified for everyone
+const PACKAGE_PROPERTIES: [&str; 6] = [
+	"AutoUpdate",
+	"ModifiedState",
+	"PackageId",
+	"PackageIdSerialize",
+	"VersionIdSerialize",
+	"VersionNumber",
+];
+
+pub fn is_package_property(property: &str) -> bool {
+	PACKAGE_PROPERTIES.contains(&property)
+}
+
+/// Whether the snapshot is a package root
+pub fn is_package(snapshot: &Snapshot) -> bool {
+	snapshot.children.iter().any(|child| child.class == PACKAGE_LINK)
+}
+
+/// Returns the package the instance belongs to, which is either
+/// the instance itself or its closest ancestor (also for `PackageLink`)
+pub fn find_package(id: Ref, tree: &Tree) -> Option<Ref> {
+	let dom = tree.inner();
+	let mut current = dom.get_by_ref(id);
+
+	while let Some(instance) = current {
+		if instance
+			.children()
+			.iter()
+			.filter_map(|child| dom.get_by_ref(*child))
+			.any(|child| child.class == PACKAGE_LINK)
+		{
+			return Some(instance.referent());
+		}
+
+		current = dom.get_by_ref(instance.parent());
+	}
+
+	None
+}
@@ -1,14 +1,27 @@
+use anyhow::Result;
 use colored::Colorize;
-use rbx_dom_weak::{HashMapExt, Ustr, UstrMap};
+use rbx_dom_weak::{
+	types::{Ref, Variant},
+	HashMapExt, Ustr, UstrMap,
+};
+use rbx_reflection::{PropertyKind, PropertySerialization, Scriptability};
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 use crate::{
 	argon_error, argon_warn,
+	codes::ErrorCode,
 	config::Config,
-	core::meta::{Meta, SyncbackFilter},
+	core::{
+		helpers::{collisions::Claims, names, packages},
+		meta::{Context, Meta},
+		property_reports::{self, PropertyOutcome, PropertyReport},
+	},
 	ext::PathExt,
-	resolution::UnresolvedValue,
+	middleware::data,
+	project::{NamePolicy, ProjectNode},
+	resolution::{self, UnresolvedValue},
+	t,
 	vfs::Vfs,
 	Properties,
 };
@@ -19,32 +32,75 @@ const FORBIDDEN_CHARACTERS: [char; 1] = ['/'];
 #[cfg(windows)]
 const FORBIDDEN_CHARACTERS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
 
-#[cfg(windows)]
+/// Characters forbidden on any of the supported platforms, used in `strict` mode
+const PORTABLE_FORBIDDEN_CHARACTERS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+// Reserved on Windows regardless of the case and extension, checked
+// on every platform so projects stay portable between systems
 const FORBIDDEN_FILE_NAMES: [&str; 22] = [
 	"CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1", "LPT2",
 	"LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
 ];
 
+/// Sanitizes the name so it can be used as a file name, following project
+/// `nameRules`. Returns `false` if the name cannot be used, the original
+/// name is kept in `originalName` when it had to be changed
 pub fn verify_name(name: &mut String, meta: &mut Meta) -> bool {
-	let (messages, renamed) = {
+	let rules = meta.context.name_rules();
+	let policy = rules.policy;
+
+	let (messages, renamed, rejected) = {
 		let mut messages = vec![];
-		let mut name = name.clone();
+		let mut rejected = false;
+		let mut name = names::normalize(name).into_owned();
+
+		let forbidden_characters: &[char] = if policy == NamePolicy::Strict {
+			&PORTABLE_FORBIDDEN_CHARACTERS
+		} else {
+			&FORBIDDEN_CHARACTERS
+		};
+
+		let replacement = rules.replacement.filter(|char| {
+			!forbidden_characters.contains(char)
+				&& !char.is_control()
+				&& !names::is_invisible(*char)
+				&& (policy != NamePolicy::AsciiOnly || char.is_ascii())
+		});
+
+		if name.chars().any(names::is_invisible) {
+			messages.push(format!("file name cannot contain {} characters", "invisible".bold()));
+
+			if policy == NamePolicy::Strict {
+				rejected = true;
+			} else {
+				name.retain(|char| !names::is_invisible(char));
+			}
+		}
 
 		if name.len() > 255 {
 			messages.push("file name cannot be longer than 255 characters".into());
-			name = name[..255].into();
+
+			let mut index = 255;
+
+			while !name.is_char_boundary(index) {
+				index -= 1;
+			}
+
+			name.truncate(index);
 		}
 
 		{
 			let mut forbidden_chars = vec![];
 
 			for char in name.chars() {
-				if FORBIDDEN_CHARACTERS.contains(&char) && !forbidden_chars.contains(&char) {
+				if forbidden_characters.contains(&char) && !forbidden_chars.contains(&char) {
 					forbidden_chars.push(char);
 				}
 
-				#[cfg(windows)]
-				if char.is_control() && !forbidden_chars.contains(&char) {
+				if (cfg!(windows) || policy == NamePolicy::Strict)
+					&& char.is_control()
+					&& !forbidden_chars.contains(&char)
+				{
 					forbidden_chars.push(char);
 				}
 			}
@@ -77,12 +133,26 @@ pub fn verify_name(name: &mut String, meta: &mut Meta) -> bool {
 				messages.push(message);
 
 				for char in forbidden_chars {
-					name = name.replace(char, "");
+					name = name.replace(char, &replacement.map(String::from).unwrap_or_default());
 				}
 			}
 		}
 
-		#[cfg(windows)]
+		if policy == NamePolicy::AsciiOnly && !name.is_ascii() {
+			messages.push(format!("file name cannot contain {} characters", "non-ASCII".bold()));
+
+			name = name
+				.chars()
+				.map(|char| {
+					if char.is_ascii() {
+						char
+					} else {
+						replacement.unwrap_or('_')
+					}
+				})
+				.collect();
+		}
+
 		if name.ends_with('.') || name.ends_with(' ') {
 			messages.push("file name cannot end with a period or space".into());
 
@@ -95,40 +165,52 @@ pub fn verify_name(name: &mut String, meta: &mut Meta) -> bool {
 			messages.push("file name cannot be empty".into());
 			name = "EmptyName".into();
 		} else {
-			#[cfg(windows)]
-			for file_name in FORBIDDEN_FILE_NAMES {
-				if name == file_name {
-					messages.push(format!("file cannot be named {}", file_name.bold()));
-					name = format!("{}{}", name, name.chars().last().unwrap());
-				}
+			let stem = name.split('.').next().unwrap_or_default().trim_end();
+
+			if let Some(file_name) = FORBIDDEN_FILE_NAMES
+				.iter()
+				.find(|file_name| file_name.eq_ignore_ascii_case(stem))
+			{
+				messages.push(format!("file cannot be named {}", file_name.bold()));
+
+				// Repeat the last character of the reserved part, e.g. `CON.txt` -> `CONN.txt`
+				let (index, last) = (stem.len(), stem.chars().last().unwrap());
+				name.insert(index, last);
 			}
 		}
 
-		(messages, name)
+		(messages, name, rejected)
 	};
 
+	if rejected || (!messages.is_empty() && !Config::new().rename_instances) {
+		argon_error!(
+			"{}",
+			t!(
+				"syncback.corrupted",
+				name = name.bold(),
+				reasons = messages.iter().map(|m| m.as_str()).collect::<Vec<&str>>().join(" & ")
+			)
+		);
+
+		return false;
+	}
+
 	if !messages.is_empty() {
-		if Config::new().rename_instances {
-			argon_warn!(
-				"Instance with name: {} got renamed to: {}, because: {}!",
-				name.bold(),
-				renamed.bold(),
-				messages.iter().map(|m| m.as_str()).collect::<Vec<&str>>().join(" & ")
-			);
-
-			meta.set_original_name(Some(name.to_owned()));
-			*name = renamed;
-
-			return true;
-		} else {
-			argon_error!(
-				"Instance with name: {} is corrupted: {}! Skipping..",
-				name.bold(),
-				messages.iter().map(|m| m.as_str()).collect::<Vec<&str>>().join(" & ")
-			);
+		argon_warn!(
+			"{}",
+			t!(
+				"syncback.renamed",
+				name = name.bold(),
+				renamed = renamed.bold(),
+				reasons = messages.iter().map(|m| m.as_str()).collect::<Vec<&str>>().join(" & ")
+			)
+		);
+	}
 
-			return false;
-		}
+	// Names that were only normalized are renamed silently
+	if renamed != *name {
+		meta.set_original_name(Some(name.to_owned()));
+		*name = renamed;
 	} else if meta.original_name.is_some() {
 		meta.set_original_name(None);
 	}
@@ -148,9 +230,12 @@ pub fn verify_path(path: &mut PathBuf, name: &mut String, meta: &mut Meta, vfs:
 		let renamed_path = path.with_file_name(format!("{}{}", renamed, suffix));
 
 		argon_warn!(
-			"Instance with path: {} got renamed to: {}, because it already exists!",
-			path.to_string().bold(),
-			renamed_path.to_string().bold()
+			"{}",
+			t!(
+				"syncback.path_renamed",
+				path = path.to_string().bold(),
+				renamed = renamed_path.to_string().bold()
+			)
 		);
 
 		meta.set_original_name(Some(name.to_owned()));
@@ -160,25 +245,142 @@ pub fn verify_path(path: &mut PathBuf, name: &mut String, meta: &mut Meta, vfs:
 
 		true
 	} else {
-		argon_error!(
-			"Instance with path: {} already exists! Skipping..",
-			path.to_string().bold()
-		);
+		argon_error!("{}", t!("syncback.path_exists", path = path.to_string().bold()));
 
 		false
 	}
 }
 
-pub fn validate_properties(properties: Properties, filter: &SyncbackFilter) -> Properties {
+/// Moves the path away from the one claimed by a sibling, the first free numeric
+/// suffix is used so the same instances always end up in the same files. Returns
+/// the sibling that owns the original path, the name is kept in `originalName`
+pub fn verify_claim(path: &mut PathBuf, name: &mut String, meta: &mut Meta, claims: &Claims, vfs: &Vfs) -> Option<Ref> {
+	let other = claims.owner(path)?;
+	let suffix = path
+		.get_name()
+		.strip_prefix(name.as_str())
+		.unwrap_or_default()
+		.to_owned();
+
+	let (renamed, renamed_path) = (2..)
+		.map(|index| {
+			let renamed = format!("{}_{}", name, index);
+			let renamed_path = path.with_file_name(format!("{}{}", renamed, suffix));
+
+			(renamed, renamed_path)
+		})
+		.find(|(_, path)| claims.owner(path).is_none() && !vfs.exists(path))
+		.unwrap();
+
+	// Name could have already been changed by `verify_name`
+	if meta.original_name.is_none() {
+		meta.set_original_name(Some(name.to_owned()));
+	}
+
+	*path = renamed_path;
+	*name = renamed;
+
+	Some(other)
+}
+
+/// Drops filtered, read-only and unknown properties and package metadata, writes
+/// legacy aliases under their canonical names and checks values against their
+/// valid ranges. Outcomes of all properties are recorded, see `property_reports`
+pub fn validate_properties(id: Ref, class: &str, properties: Properties, context: &Context) -> Properties {
+	validate(id, class, properties, context, false)
+}
+
+/// Same as `validate_properties` but also drops values equal to the class default,
+/// only for new instances as updates have to overwrite values that were reset.
+/// Strings are always kept as they can be contents of the file, e.g. empty `Source`
+pub fn validate_new_properties(id: Ref, class: &str, properties: Properties, context: &Context) -> Properties {
+	validate(id, class, properties, context, true)
+}
+
+fn validate(id: Ref, class: &str, properties: Properties, context: &Context, strip_defaults: bool) -> Properties {
 	// Temporary solution for empty Luau maps being serialized as arrays
 	if properties.contains_key(&Ustr::from("ArgonEmpty")) {
-		UstrMap::new()
-	} else {
-		properties
-			.into_iter()
-			.filter(|(property, _)| !filter.matches_property(property))
-			.collect()
+		return UstrMap::new();
 	}
+
+	let filter = context.syncback_filter();
+	let ranges = context.property_ranges();
+	let database = rbx_reflection_database::get();
+	let class_descriptor = database.classes.get(class);
+
+	let mut outcomes = property_reports::is_collecting().then(Vec::new);
+	let mut report = |outcome: PropertyOutcome| {
+		if let Some(outcomes) = &mut outcomes {
+			outcomes.push(outcome);
+		}
+	};
+
+	let mut validated = UstrMap::new();
+
+	for (name, mut value) in properties {
+		if filter.matches_property(&name) || packages::is_package_property(&name) {
+			report(PropertyOutcome::skipped(&name, ErrorCode::Filtered));
+			continue;
+		}
+
+		// Properties of classes newer than the database can't be checked
+		let mut property = name;
+
+		if let Some(class_descriptor) = class_descriptor {
+			let Some(descriptor) = resolution::find_descriptor(class, &name) else {
+				report(PropertyOutcome::skipped(&name, ErrorCode::Unknown));
+				continue;
+			};
+
+			match &descriptor.kind {
+				PropertyKind::Alias { alias_for } => property = Ustr::from(alias_for),
+				PropertyKind::Canonical {
+					serialization: PropertySerialization::DoesNotSerialize,
+				} if matches!(descriptor.scriptability, Scriptability::Read) => {
+					report(PropertyOutcome::skipped(&name, ErrorCode::ReadOnly));
+					continue;
+				}
+				_ => {}
+			}
+
+			if strip_defaults
+				&& !matches!(value, Variant::String(_))
+				&& database.find_default_property(class_descriptor, &property) == Some(&value)
+			{
+				report(PropertyOutcome::skipped(&name, ErrorCode::DefaultStripped));
+				continue;
+			}
+		}
+
+		let clamped = ranges.find(class, &property).is_some() && {
+			let checked = ranges.check(class, &property, value.clone(), None);
+			let clamped = checked != value;
+
+			value = checked;
+			clamped
+		};
+
+		if property != name {
+			report(PropertyOutcome::transformed(&name, ErrorCode::CanonicalRename).with_renamed(&property));
+
+			// Value of the canonical property wins when both are present
+			if validated.contains_key(&property) {
+				continue;
+			}
+		} else if clamped {
+			report(PropertyOutcome::transformed(&name, ErrorCode::Clamped));
+		} else {
+			report(PropertyOutcome::applied(&name));
+		}
+
+		validated.insert(property, value);
+	}
+
+	if let Some(outcomes) = outcomes {
+		property_reports::record(PropertyReport::new(id, class, outcomes));
+	}
+
+	validated
 }
 
 pub fn serialize_properties(class: &str, properties: Properties) -> UstrMap<UnresolvedValue> {
@@ -193,10 +395,51 @@ pub fn serialize_properties(class: &str, properties: Properties) -> UstrMap<Unre
 		.collect()
 }
 
+/// Same as `serialize_properties` but writes attributes
+/// and tags to their dedicated project node fields
+pub fn serialize_node_properties(node: &mut ProjectNode, class: &str, mut properties: Properties) {
+	node.attributes = match properties.remove(&Ustr::from("Attributes")) {
+		Some(Variant::Attributes(attributes)) if attributes.is_empty() => None,
+		Some(attributes) => Some(UnresolvedValue::from_variant(attributes, class, "Attributes")),
+		None => None,
+	};
+
+	node.tags = match properties.remove(&Ustr::from("Tags")) {
+		Some(Variant::Tags(tags)) => tags.iter().map(|tag| tag.to_owned()).collect(),
+		_ => vec![],
+	};
+
+	node.properties = serialize_properties(class, properties);
+}
+
+/// Writes string properties that were read with `$file` back to their files
+/// and puts the references back, `previous` are the properties before serialization
+pub fn keep_file_refs(
+	node: &mut ProjectNode,
+	previous: &UstrMap<UnresolvedValue>,
+	project_path: &Path,
+	vfs: &Vfs,
+) -> Result<()> {
+	for (property, value) in previous {
+		let UnresolvedValue::File(file) = value else {
+			continue;
+		};
+
+		let Some(contents) = node.properties.get(property).and_then(UnresolvedValue::as_str) else {
+			continue;
+		};
+
+		data::write_file_ref(project_path, file, contents, vfs)?;
+		node.properties.insert(*property, value.clone());
+	}
+
+	Ok(())
+}
+
 pub fn rename_path(path: &Path, from: &str, to: &str) -> PathBuf {
 	path.with_file_name(format!(
 		"{}{}",
 		to,
-		path.get_name().strip_prefix(from).unwrap_or_default()
+		names::strip_prefix(path.get_name(), from).unwrap_or_default()
 	))
 }
@@ -0,0 +1,268 @@
+use anyhow::{bail, Result};
+use rbx_dom_weak::types::Ref;
+use std::{
+	collections::HashMap,
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+use super::{deferred, helpers::memory, queue::Queue, snapshot::AddedSnapshot, tree::Tree};
+use crate::{
+	lock,
+	project::LazyProperties,
+	server::{protocol::Capability, SyncChunk, SyncComplete, SyncProgress},
+	vfs::Vfs,
+};
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Checksum of instances in the order they were sent, FNV-1a (64 bit) of
+/// `<id>\t<parent>\t<name>\t<class>\n` lines, so clients can verify it
+pub fn checksum<'a, I>(instances: I) -> String
+where
+	I: IntoIterator<Item = &'a AddedSnapshot>,
+{
+	let state = instances.into_iter().fold(FNV_OFFSET, update_checksum);
+
+	format!("{:016x}", state)
+}
+
+fn update_checksum(mut state: u64, instance: &AddedSnapshot) -> u64 {
+	let line = format!(
+		"{}\t{}\t{}\t{}\n",
+		instance.id, instance.parent, instance.name, instance.class
+	);
+
+	for byte in line.bytes() {
+		state ^= byte as u64;
+		state = state.wrapping_mul(FNV_PRIME);
+	}
+
+	state
+}
+
+/// Progress of a single client, kept until the sync
+/// completes so it can be resumed after reconnecting
+#[derive(Debug)]
+struct Cursor {
+	/// Instances in the order they are sent, collected when the sync starts
+	order: Vec<Ref>,
+	/// Number of chunks pushed to the client queue
+	sent: usize,
+	/// Number of chunks acknowledged by the client
+	acked: usize,
+	/// Number of instances in every sent chunk,
+	/// removed instances are skipped so it can vary
+	chunk_lengths: Vec<usize>,
+	/// Checksum state after every sent chunk
+	checksums: Vec<u64>,
+	last_active: Instant,
+}
+
+impl Cursor {
+	fn chunks(&self, chunk_size: usize) -> usize {
+		self.order.len().div_ceil(chunk_size)
+	}
+
+	fn rewind(&mut self) {
+		self.sent = self.acked;
+		self.chunk_lengths.truncate(self.acked);
+		self.checksums.truncate(self.acked);
+	}
+}
+
+/// Streams the whole tree to clients in chunks interleaved with progress frames,
+/// only `window` chunks can wait for acknowledgement at a time
+#[derive(Debug)]
+pub struct InitialSync {
+	cursors: Mutex<HashMap<u32, Cursor>>,
+	chunk_size: usize,
+	window: usize,
+	/// How long the cursor of a disconnected client is kept
+	timeout: Duration,
+	/// Values left out of chunks sent to clients that can request them later
+	lazy_properties: LazyProperties,
+}
+
+impl InitialSync {
+	pub fn new(chunk_size: usize, window: usize, timeout: Duration) -> Self {
+		Self {
+			cursors: Mutex::new(HashMap::new()),
+			chunk_size: chunk_size.max(1),
+			window: window.max(1),
+			timeout,
+			lazy_properties: LazyProperties::default(),
+		}
+	}
+
+	pub fn with_lazy_properties(mut self, lazy_properties: LazyProperties) -> Self {
+		self.lazy_properties = lazy_properties;
+		self
+	}
+
+	/// Starts new sync or resumes the existing one from the last acknowledged
+	/// chunk, chunks that were sent but not acknowledged are sent again
+	pub fn start(&self, id: u32, resume: bool, tree: &Mutex<Tree>, vfs: &Vfs, queue: &Queue) -> Result<SyncProgress> {
+		let mut cursors = lock!(self.cursors);
+
+		cursors.retain(|_, cursor| cursor.last_active.elapsed() < self.timeout);
+
+		if resume && cursors.contains_key(&id) {
+			let cursor = cursors.get_mut(&id).unwrap();
+
+			cursor.rewind();
+			cursor.last_active = Instant::now();
+		} else {
+			let tree = lock!(tree);
+			let filter = queue.filter(id);
+			let mut order = vec![];
+			let mut next = Some(tree.root_ref());
+
+			// Clients that subscribed to a part of the tree only get its instances
+			while let Some(id) = next {
+				if filter.as_ref().is_none_or(|filter| filter.contains(id, &tree)) {
+					order.push(id);
+				}
+
+				next = tree.next_in_order(id);
+			}
+
+			cursors.insert(
+				id,
+				Cursor {
+					order,
+					sent: 0,
+					acked: 0,
+					chunk_lengths: vec![],
+					checksums: vec![],
+					last_active: Instant::now(),
+				},
+			);
+		}
+
+		let cursor = cursors.get_mut(&id).unwrap();
+		let progress = self.progress(cursor);
+
+		queue.push(progress.clone(), Some(id))?;
+		self.fill(id, cursor, tree, vfs, queue)?;
+
+		if cursor.acked == cursor.chunks(self.chunk_size) {
+			cursors.remove(&id);
+		}
+
+		Ok(progress)
+	}
+
+	/// Marks chunk and all previous ones as received and sends more chunks,
+	/// completion frame is sent once every chunk is acknowledged
+	pub fn ack(&self, id: u32, chunk: usize, tree: &Mutex<Tree>, vfs: &Vfs, queue: &Queue) -> Result<SyncProgress> {
+		let mut cursors = lock!(self.cursors);
+
+		let cursor = match cursors.get_mut(&id) {
+			Some(cursor) => cursor,
+			None => bail!("No initial sync in progress"),
+		};
+
+		if chunk >= cursor.sent {
+			bail!("Chunk {} has not been sent yet", chunk);
+		}
+
+		cursor.acked = cursor.acked.max(chunk + 1);
+		cursor.last_active = Instant::now();
+
+		self.fill(id, cursor, tree, vfs, queue)?;
+
+		let progress = self.progress(cursor);
+
+		if cursor.acked == cursor.chunks(self.chunk_size) {
+			cursors.remove(&id);
+		}
+
+		Ok(progress)
+	}
+
+	pub fn is_syncing(&self, id: u32) -> bool {
+		lock!(self.cursors).contains_key(&id)
+	}
+
+	fn progress(&self, cursor: &Cursor) -> SyncProgress {
+		SyncProgress {
+			phase: String::from("initial_sync"),
+			sent: cursor.chunk_lengths.iter().sum(),
+			total: cursor.order.len(),
+		}
+	}
+
+	fn fill(&self, id: u32, cursor: &mut Cursor, tree: &Mutex<Tree>, vfs: &Vfs, queue: &Queue) -> Result<()> {
+		let chunks = cursor.chunks(self.chunk_size);
+		let lazy = queue.supports(id, Capability::LazyProperties);
+
+		while cursor.sent < chunks && cursor.sent < cursor.acked + self.window {
+			let start = cursor.sent * self.chunk_size;
+			let end = (start + self.chunk_size).min(cursor.order.len());
+
+			let mut instances = Vec::with_capacity(end - start);
+			let mut deferred = vec![];
+			let tree = lock!(tree);
+
+			// Instances removed since the sync started are skipped,
+			// the ones added later arrive with regular changes
+			for id in &cursor.order[start..end] {
+				let instance = match tree.get_instance(*id) {
+					Some(instance) => instance,
+					None => continue,
+				};
+
+				let mut properties = memory::materialize_lossy(*id, &tree, vfs).unwrap();
+
+				if lazy {
+					deferred.extend(deferred::defer(*id, &mut properties, &self.lazy_properties));
+				}
+
+				instances.push(AddedSnapshot {
+					id: *id,
+					meta: tree.get_meta(*id).unwrap().clone(),
+					parent: instance.parent(),
+					name: instance.name.clone(),
+					class: instance.class,
+					properties,
+					children: vec![],
+				});
+			}
+
+			drop(tree);
+
+			let state = cursor.checksums.last().copied().unwrap_or(FNV_OFFSET);
+			let state = instances.iter().fold(state, update_checksum);
+
+			cursor.chunk_lengths.push(instances.len());
+			cursor.checksums.push(state);
+
+			queue.push(
+				SyncChunk {
+					chunk: cursor.sent,
+					instances,
+					deferred,
+				},
+				Some(id),
+			)?;
+
+			cursor.sent += 1;
+
+			queue.push(self.progress(cursor), Some(id))?;
+		}
+
+		if cursor.acked == chunks {
+			queue.push(
+				SyncComplete {
+					total: cursor.chunk_lengths.iter().sum(),
+					checksum: format!("{:016x}", cursor.checksums.last().copied().unwrap_or(FNV_OFFSET)),
+				},
+				Some(id),
+			)?;
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,150 @@
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::{HashMap, HashSet},
+	path::PathBuf,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Mutex,
+	},
+	time::SystemTime,
+};
+
+use super::tree::Tree;
+use crate::{
+	lock,
+	vfs::{Vfs, VfsEvent},
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityStats {
+	/// Full passes over all source paths of the tree
+	pub passes: u64,
+	/// Source paths checked against the file system
+	pub checked: u64,
+	/// Changes the watcher did not report that were found by the scanner
+	pub misses: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+	/// Position of the next path to check in the sorted source paths
+	cursor: usize,
+	/// Number of processed events the modification times were recorded at
+	processed: u64,
+	/// Modification times seen by the previous pass
+	modified: HashMap<PathBuf, SystemTime>,
+	/// Paths already reported as missing, so they are not reported every pass
+	missing: HashSet<PathBuf>,
+}
+
+/// Background check of source paths against the file system that catches
+/// changes the watcher dropped, e.g. after inotify queue overflows
+#[derive(Debug, Default)]
+pub struct Integrity {
+	state: Mutex<State>,
+	passes: AtomicU64,
+	checked: AtomicU64,
+	misses: AtomicU64,
+}
+
+impl Integrity {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Checks up to `budget` source paths, continuing where the previous call
+	/// stopped, and returns events of the changes that were missed. `processed`
+	/// is the number of events processed so far, modification times recorded
+	/// before any of them are stale so they are only compared without activity
+	pub fn scan(&self, tree: &Mutex<Tree>, vfs: &Vfs, budget: usize, processed: u64) -> Vec<VfsEvent> {
+		let mut state = lock!(self.state);
+
+		if state.processed != processed {
+			state.processed = processed;
+			state.modified.clear();
+		}
+
+		// The tree is only locked to pick the paths, not for the I/O
+		let batch: Vec<PathBuf> = {
+			let tree = lock!(tree);
+			let id_map = tree.id_map();
+
+			let mut paths: Vec<&PathBuf> = id_map.keys().collect();
+			paths.sort();
+
+			if state.cursor >= paths.len() {
+				state.cursor = 0;
+				state.missing.retain(|path| id_map.contains_key(path));
+			}
+
+			let end = (state.cursor + budget.max(1)).min(paths.len());
+			let batch = paths[state.cursor..end].iter().map(|path| path.to_path_buf()).collect();
+
+			state.cursor = end;
+
+			if end == paths.len() {
+				self.passes.fetch_add(1, Ordering::Relaxed);
+			}
+
+			batch
+		};
+
+		let mut events: Vec<VfsEvent> = vec![];
+
+		for path in batch {
+			self.checked.fetch_add(1, Ordering::Relaxed);
+
+			// Descendants of a missing directory are covered by its event,
+			// which may not have been processed by the time they are checked
+			if state
+				.missing
+				.iter()
+				.any(|missing| *missing != path && path.starts_with(missing))
+			{
+				continue;
+			}
+
+			if !vfs.exists(&path) {
+				state.modified.remove(&path);
+
+				if state.missing.insert(path.clone()) {
+					events.push(VfsEvent::Delete(path));
+				}
+
+				continue;
+			}
+
+			state.missing.remove(&path);
+
+			let Some(modified) = vfs.modified(&path) else {
+				continue;
+			};
+
+			match state.modified.insert(path.clone(), modified) {
+				Some(previous) if previous != modified => events.push(VfsEvent::Write(path)),
+				_ => {}
+			}
+		}
+
+		for event in &events {
+			self.misses.fetch_add(1, Ordering::Relaxed);
+			warn!(
+				"Watcher missed a change of {}, reconciling: {:?}",
+				event.path().display(),
+				event
+			);
+		}
+
+		events
+	}
+
+	pub fn stats(&self) -> IntegrityStats {
+		IntegrityStats {
+			passes: self.passes.load(Ordering::Relaxed),
+			checked: self.checked.load(Ordering::Relaxed),
+			misses: self.misses.load(Ordering::Relaxed),
+		}
+	}
+}
@@ -0,0 +1,141 @@
+use anyhow::Result;
+use log::info;
+use rbx_dom_weak::types::Ref;
+use serde::Serialize;
+use serde_json::Value;
+use std::{path::PathBuf, thread};
+use tokio::sync::broadcast::{self, error::RecvError, Receiver, Sender};
+
+use super::{processor::origin::Origin, property_reports::PropertyReport, Core};
+use crate::{argon_error, history::Operation, project::Project};
+
+/// Top-level fields of the project that differ after a reload, e.g. `tree` or `port`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ProjectChanges {
+	pub fields: Vec<String>,
+}
+
+impl ProjectChanges {
+	/// Compares the project serialized before the reload with the reloaded one
+	pub fn new(old: Option<Value>, new: &Project) -> Self {
+		let (Some(Value::Object(old)), Ok(Value::Object(new))) = (old, serde_json::to_value(new)) else {
+			return Self::default();
+		};
+
+		let mut fields: Vec<String> = old
+			.keys()
+			.chain(new.keys())
+			.filter(|field| old.get(*field) != new.get(*field))
+			.cloned()
+			.collect();
+
+		fields.sort();
+		fields.dedup();
+
+		Self { fields }
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.fields.is_empty()
+	}
+}
+
+/// Outcome of a single operation of a syncback batch
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncbackResult {
+	pub operation: Operation,
+	pub id: Ref,
+	/// Why the operation failed, the rest of the batch is not applied then
+	pub error: Option<String>,
+	/// Instance the error originates from, so clients can highlight it
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub origin: Option<Origin>,
+	/// Outcomes of the synced back properties of every written instance
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub properties: Vec<PropertyReport>,
+}
+
+impl SyncbackResult {
+	pub fn new<T>(operation: Operation, id: Ref, result: &Result<T>) -> Self {
+		Self {
+			operation,
+			id,
+			error: result.as_ref().err().map(|err| format!("{:#}", err)),
+			origin: result.as_ref().err().and_then(Origin::find).cloned(),
+			properties: vec![],
+		}
+	}
+
+	pub fn with_properties(mut self, properties: Vec<PropertyReport>) -> Self {
+		self.properties = properties;
+		self
+	}
+}
+
+/// Moments in the life of `Core` that embedders can react to, see `Core::subscribe`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum CoreEvent {
+	/// Project file was modified and loaded again
+	#[serde(rename_all = "camelCase")]
+	ProjectReloaded { path: PathBuf, changes: ProjectChanges },
+	/// Client received the whole tree and acknowledged all of it
+	#[serde(rename_all = "camelCase")]
+	InitialSyncComplete { client_id: u32, instances: usize },
+	#[serde(rename_all = "camelCase")]
+	ClientConnected { client_id: u32, name: String },
+	#[serde(rename_all = "camelCase")]
+	ClientDisconnected { client_id: u32 },
+	#[serde(rename_all = "camelCase")]
+	SyncbackStarted { client_id: u32, changes: usize },
+	/// Results of the applied operations, in the order they were applied
+	#[serde(rename_all = "camelCase")]
+	SyncbackFinished {
+		client_id: u32,
+		results: Vec<SyncbackResult>,
+		error: Option<String>,
+	},
+	/// Error that stops the session from syncing, e.g. deleted project file
+	#[serde(rename_all = "camelCase")]
+	Fatal { message: String },
+}
+
+/// Broadcasts `CoreEvent`s without ever blocking the emitter,
+/// subscribers that fall behind get `RecvError::Lagged`
+#[derive(Debug, Clone)]
+pub struct Lifecycle {
+	sender: Sender<CoreEvent>,
+}
+
+impl Lifecycle {
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			sender: broadcast::channel(capacity).0,
+		}
+	}
+
+	/// Sends the event to current subscribers, if there are any
+	pub fn emit(&self, event: CoreEvent) {
+		self.sender.send(event).ok();
+	}
+
+	pub fn subscribe(&self) -> Receiver<CoreEvent> {
+		self.sender.subscribe()
+	}
+}
+
+/// Prints status of the session to the terminal for as long as the core lives
+pub fn print_status(core: &Core) {
+	let mut receiver = core.subscribe();
+
+	thread::spawn(move || loop {
+		match receiver.blocking_recv() {
+			Ok(CoreEvent::ProjectReloaded { .. }) => info!("Project reloaded"),
+			Ok(CoreEvent::Fatal { message }) => argon_error!("{}", message),
+			Ok(_) | Err(RecvError::Lagged(_)) => {}
+			Err(RecvError::Closed) => break,
+		}
+	});
+}
@@ -1,17 +1,36 @@
+use anyhow::{bail, Result};
+use colored::Colorize;
+use rbx_dom_weak::{types::Ref, Ustr};
 use serde::{Deserialize, Serialize};
 use std::{
 	boxed::Box,
+	collections::BTreeMap,
 	fmt::Display,
+	iter,
 	path::{Path, PathBuf},
+	sync::Arc,
 };
 
+use super::{
+	refs::RefPaths,
+	skipped::{self, FilterRule, RuleKind, Skipped, SkippedOperation},
+};
 use crate::{
+	argon_warn,
 	config::Config,
-	constants::default_sync_rules,
+	constants::{default_sync_rules, sync_rules_for, MAX_MARKDOWN_SIZE, MAX_TREE_DEPTH},
 	ext::PathExt,
 	glob::Glob,
-	middleware::Middleware,
-	project::{Project, ProjectNode},
+	hooks::Hooks,
+	integration::TsLayout,
+	middleware::{data, external, manifest, storage, InitConflict, LoadError, Middleware},
+	project::{
+		CollapseMode, DataFormat, DataIncludes, DataStorage, InferenceRule, KeepUnknowns, LayoutPreference,
+		MiddlewareConfig, NameRules, Project, ProjectFilter, ProjectNode, ScriptExtension, UnknownsFilter,
+	},
+	ranges::PropertyRanges,
+	t,
+	vfs::Vfs,
 };
 
 #[derive(Debug, Clone, PartialEq)]
@@ -57,6 +76,8 @@ impl Display for NodePath {
 pub enum SourceKind {
 	Path(PathBuf),
 	Project(String, PathBuf, Box<ProjectNode>, NodePath),
+	/// Session-scoped instance that only lives in memory
+	Ephemeral,
 	None,
 }
 
@@ -77,6 +98,12 @@ pub enum SourceEntry {
 	Folder(PathBuf),
 	Data(PathBuf),
 	Project(PathBuf),
+	/// File that a property value is read from with `$file`
+	Reference(PathBuf),
+	/// Entry of the data index, keyed by the name of the instance on disk
+	Index(PathBuf, String),
+	/// Entry of the asset manifest, keyed by its name and names of the entries it is nested in
+	Manifest(PathBuf, Vec<String>),
 }
 
 impl SourceEntry {
@@ -86,6 +113,9 @@ impl SourceEntry {
 			SourceEntry::Folder(path) => path,
 			SourceEntry::Data(path) => path,
 			SourceEntry::Project(path) => path,
+			SourceEntry::Reference(path) => path,
+			SourceEntry::Index(path, _) => path,
+			SourceEntry::Manifest(path, _) => path,
 		}
 	}
 
@@ -95,6 +125,9 @@ impl SourceEntry {
 			SourceEntry::Folder(_) => 1,
 			SourceEntry::Data(_) => 2,
 			SourceEntry::Project(_) => 3,
+			SourceEntry::Reference(_) => 4,
+			SourceEntry::Index(_, _) => 5,
+			SourceEntry::Manifest(_, _) => 6,
 		}
 	}
 }
@@ -146,6 +179,22 @@ impl Source {
 		}
 	}
 
+	/// Instance listed in the asset manifest, the whole directory
+	/// of the manifest is read again when it changes
+	pub fn manifest(path: &Path, key: &[String]) -> Self {
+		Self {
+			inner: SourceKind::Path(path.to_owned()),
+			relevant: vec![SourceEntry::Manifest(path.to_owned(), key.to_vec())],
+		}
+	}
+
+	pub fn ephemeral() -> Self {
+		Self {
+			inner: SourceKind::Ephemeral,
+			relevant: vec![],
+		}
+	}
+
 	pub fn with_relevant(mut self, relevant: Vec<SourceEntry>) -> Self {
 		self.relevant = relevant;
 		self
@@ -163,6 +212,14 @@ impl Source {
 		self.relevant.push(SourceEntry::Project(path.to_owned()))
 	}
 
+	pub fn add_reference(&mut self, path: &Path) {
+		self.relevant.push(SourceEntry::Reference(path.to_owned()))
+	}
+
+	pub fn add_index(&mut self, path: &Path, key: &str) {
+		self.relevant.push(SourceEntry::Index(path.to_owned(), key.to_owned()))
+	}
+
 	pub fn set_data(&mut self, path: Option<&Path>) {
 		self.relevant.retain(|entry| !matches!(entry, SourceEntry::Data(_)));
 
@@ -171,6 +228,22 @@ impl Source {
 		}
 	}
 
+	/// Replaces data entries with the data file followed by its included files
+	pub fn set_data_files(&mut self, paths: &[PathBuf]) {
+		self.relevant.retain(|entry| !matches!(entry, SourceEntry::Data(_)));
+
+		for path in paths {
+			self.add_data(path)
+		}
+	}
+
+	/// Replaces data and index entries with the given ones, see `DataStore::write`
+	pub fn set_data_entries(&mut self, entries: Vec<SourceEntry>) {
+		self.relevant
+			.retain(|entry| !matches!(entry, SourceEntry::Data(_) | SourceEntry::Index(_, _)));
+		self.relevant.extend(entries);
+	}
+
 	pub fn extend_relevant(&mut self, entries: Vec<SourceEntry>) {
 		self.relevant.extend(entries)
 	}
@@ -203,6 +276,47 @@ impl Source {
 		self.relevant.iter().find(|entry| matches!(entry, SourceEntry::Data(_)))
 	}
 
+	pub fn get_index(&self) -> Option<&SourceEntry> {
+		self.relevant
+			.iter()
+			.find(|entry| matches!(entry, SourceEntry::Index(_, _)))
+	}
+
+	pub fn get_manifest(&self) -> Option<&SourceEntry> {
+		self.relevant
+			.iter()
+			.find(|entry| matches!(entry, SourceEntry::Manifest(_, _)))
+	}
+
+	/// Returns the data file or, if there is none, the index entry
+	pub fn get_data_entry(&self) -> Option<&SourceEntry> {
+		self.get_data().or_else(|| self.get_index())
+	}
+
+	/// Returns the file that is opened in the editor: the instance file (e.g. script) first,
+	/// then its data file, data index, asset manifest and the project file of project nodes
+	pub fn get_editable(&self) -> Option<&Path> {
+		let entry = [0, 2, 5, 6, 3]
+			.into_iter()
+			.find_map(|index| self.relevant.iter().find(|entry| entry.index() == index));
+
+		match (entry, &self.inner) {
+			(Some(entry), _) => Some(entry.path()),
+			(None, SourceKind::Project(_, path, _, _)) => Some(path),
+			(None, _) => None,
+		}
+	}
+
+	/// Returns files included by the data file, they always follow its entry
+	pub fn get_includes(&self) -> Vec<&Path> {
+		self.relevant
+			.iter()
+			.filter(|entry| matches!(entry, SourceEntry::Data(_)))
+			.skip(1)
+			.map(|entry| entry.path())
+			.collect()
+	}
+
 	pub fn relevant(&self) -> &Vec<SourceEntry> {
 		&self.relevant
 	}
@@ -211,9 +325,151 @@ impl Source {
 		&mut self.relevant
 	}
 
+	pub fn is_ephemeral(&self) -> bool {
+		matches!(self.inner, SourceKind::Ephemeral)
+	}
+
 	pub fn paths(&self) -> Vec<&Path> {
 		self.relevant.iter().map(|entry| entry.path()).collect()
 	}
+
+	/// Moves all paths inside of `from` to `to`, e.g. after one of the ancestor folders got renamed
+	pub fn relocate(&mut self, from: &Path, to: &Path) {
+		let relocate = |path: &mut PathBuf| {
+			if let Ok(relative) = path.strip_prefix(from) {
+				*path = to.join(relative);
+			}
+		};
+
+		match &mut self.inner {
+			SourceKind::Path(path) | SourceKind::Project(_, path, _, _) => relocate(path),
+			_ => {}
+		}
+
+		for entry in &mut self.relevant {
+			match entry {
+				SourceEntry::File(path)
+				| SourceEntry::Folder(path)
+				| SourceEntry::Data(path)
+				| SourceEntry::Project(path)
+				| SourceEntry::Reference(path)
+				| SourceEntry::Index(path, _)
+				| SourceEntry::Manifest(path, _) => relocate(path),
+			}
+		}
+	}
+}
+
+impl Source {
+	/// Checks whether relevant entries still describe the files on disk,
+	/// returns every broken invariant, empty list means the source is valid
+	pub fn validate(&self, vfs: &Vfs) -> Vec<SourceIssue> {
+		let mut issues = vec![];
+
+		for (kind, index) in [("File", 0), ("Folder", 1), ("Data", 2)] {
+			let count = self
+				.relevant
+				.iter()
+				.filter(|entry| entry.index() == index && !self.is_include(entry))
+				.count();
+
+			if count > 1 {
+				issues.push(SourceIssue::Duplicate(kind, count));
+			}
+		}
+
+		for entry in &self.relevant {
+			let path = entry.path();
+
+			if !vfs.exists(path) {
+				issues.push(SourceIssue::Missing(entry.clone()));
+				continue;
+			}
+
+			match entry {
+				SourceEntry::Folder(_) if !vfs.is_dir(path) => issues.push(SourceIssue::WrongKind(entry.clone())),
+				SourceEntry::File(_)
+				| SourceEntry::Data(_)
+				| SourceEntry::Project(_)
+				| SourceEntry::Reference(_)
+				| SourceEntry::Index(_, _)
+				| SourceEntry::Manifest(_, _)
+					if vfs.is_dir(path) =>
+				{
+					issues.push(SourceIssue::WrongKind(entry.clone()))
+				}
+				// Index is shared with siblings, it has to have an entry of this instance too
+				SourceEntry::Index(_, key) if !storage::has_entry(path, key, vfs) => {
+					issues.push(SourceIssue::Missing(entry.clone()))
+				}
+				SourceEntry::Manifest(_, key) if !manifest::has_entry(path, key, vfs) => {
+					issues.push(SourceIssue::Missing(entry.clone()))
+				}
+				SourceEntry::Folder(_) => {}
+				_ => {
+					// Only files written with `verifyWrites` have their hash
+					if let Some(hash) = vfs.last_write_hash(path) {
+						if vfs.hash(path).is_ok_and(|actual| actual != hash) {
+							issues.push(SourceIssue::Corrupted(entry.clone()));
+						}
+					}
+				}
+			}
+		}
+
+		// Instance files have to live inside of its folder
+		if let Some(SourceEntry::Folder(folder)) = self.relevant.iter().find(|entry| entry.index() == 1) {
+			for entry in &self.relevant {
+				if let SourceEntry::File(path) | SourceEntry::Data(path) = entry {
+					if path.parent() != Some(folder.as_path()) {
+						issues.push(SourceIssue::OutsideFolder(entry.clone()));
+					}
+				}
+			}
+		}
+
+		if let SourceKind::Path(path) = &self.inner {
+			if !self.relevant.is_empty() && !self.relevant.iter().any(|entry| entry.path() == path) {
+				issues.push(SourceIssue::DetachedInner(path.to_owned()));
+			}
+		}
+
+		issues
+	}
+
+	/// Drops entries that point to missing or wrong kind of paths and
+	/// duplicates (the most recently added entry is kept), does not touch the disk
+	pub fn repair(&mut self, vfs: &Vfs) {
+		self.relevant.retain(|entry| match entry {
+			SourceEntry::Folder(path) => vfs.is_dir(path),
+			_ => vfs.is_file(entry.path()),
+		});
+
+		let includes: Vec<_> = self.relevant.iter().map(|entry| self.is_include(entry)).collect();
+		let mut seen = [false; 3];
+
+		for index in (0..self.relevant.len()).rev() {
+			let kind = self.relevant[index].index();
+
+			if kind < 3 && !includes[index] {
+				if seen[kind] {
+					self.relevant.remove(index);
+				}
+
+				seen[kind] = true;
+			}
+		}
+	}
+
+	/// Whether the entry is one of the files included by the data file
+	fn is_include(&self, entry: &SourceEntry) -> bool {
+		let SourceEntry::Data(path) = entry else {
+			return false;
+		};
+
+		self.get_data()
+			.is_some_and(|data| data.path() != path && data::is_include(data.path(), path))
+	}
 }
 
 impl Default for Source {
@@ -222,6 +478,36 @@ impl Default for Source {
 	}
 }
 
+/// Broken invariant of the `Source` relevant entries
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceIssue {
+	/// More than one entry of the same kind
+	Duplicate(&'static str, usize),
+	/// Entry points to a path that does not exist
+	Missing(SourceEntry),
+	/// Folder entry points to a file or file entry points to a directory
+	WrongKind(SourceEntry),
+	/// File or data entry is not a direct child of the folder entry
+	OutsideFolder(SourceEntry),
+	/// Path the snapshot is rebuilt from is not one of the relevant paths
+	DetachedInner(PathBuf),
+	/// File no longer matches its last verified write
+	Corrupted(SourceEntry),
+}
+
+impl Display for SourceIssue {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			SourceIssue::Duplicate(kind, count) => write!(f, "{} {} entries", count, kind),
+			SourceIssue::Missing(entry) => write!(f, "{:?} does not exist", entry),
+			SourceIssue::WrongKind(entry) => write!(f, "{:?} has wrong kind", entry),
+			SourceIssue::OutsideFolder(entry) => write!(f, "{:?} is outside of the folder", entry),
+			SourceIssue::DetachedInner(path) => write!(f, "{} is not a relevant path", path.display()),
+			SourceIssue::Corrupted(entry) => write!(f, "{:?} does not match its last write", entry),
+		}
+	}
+}
+
 #[derive(Debug, Clone)]
 pub struct ResolvedSyncRule {
 	pub middleware: Middleware,
@@ -376,67 +662,398 @@ impl IgnoreRule {
 	}
 }
 
+/// Rules from `$filter` of a single project node
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterLayer {
+	filter: ProjectFilter,
+	/// Directory that path globs are relative to
+	path: PathBuf,
+}
+
+impl FilterLayer {
+	pub fn new(filter: ProjectFilter, path: PathBuf) -> Self {
+		Self { filter, path }
+	}
+
+	/// Returns `Some(Some(pattern))` if excluded by `pattern`, `Some(None)`
+	/// if included, `None` if no rule of this layer applies
+	fn verdict<F>(include: &[Glob], exclude: &[Glob], matches: F) -> Option<Option<String>>
+	where
+		F: Fn(&Glob) -> bool,
+	{
+		if include.iter().any(&matches) {
+			Some(None)
+		} else {
+			exclude
+				.iter()
+				.find(|glob| matches(glob))
+				.map(|glob| Some(glob.as_str().to_owned()))
+		}
+	}
+
+	fn matches_name(&self, name: &str) -> Option<Option<String>> {
+		Self::verdict(&self.filter.include_names, &self.filter.exclude_names, |glob| {
+			glob.matches(name)
+		})
+	}
+
+	fn matches_class(&self, class: &str) -> Option<Option<String>> {
+		Self::verdict(&self.filter.include_classes, &self.filter.exclude_classes, |glob| {
+			glob.matches(class)
+		})
+	}
+
+	fn matches_path(&self, path: &Path) -> Option<Option<String>> {
+		let suffix = path.strip_prefix(&self.path).ok()?;
+
+		Self::verdict(&self.filter.include_paths, &self.filter.exclude_paths, |glob| {
+			glob.matches_path_with_dir(suffix)
+		})
+	}
+}
+
+/// Decides which instances and files are not synced back.
+///
+/// Global rules come from project's `syncback` settings, `$filter` of project
+/// nodes adds a layer for the node's subtree. Layers are checked starting with
+/// the most nested one and the first layer with a rule matching given name,
+/// class or path decides, so child-most rules win. Within a single layer
+/// `include*` rules win over `exclude*` ones, e.g. `excludeClasses: ["*"]`
+/// with `includeClasses: ["Model"]` blocks everything but models.
+/// Global rules only apply when no layer matches.
+///
+/// Properties are filtered globally only and compiled roblox-ts output
+/// is always read-only, regardless of the layers
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct SyncbackFilter {
 	pub ignore_rules: Vec<IgnoreRule>,
 	pub ignore_names: Vec<String>,
 	pub ignore_classes: Vec<String>,
 	pub ignore_properties: Vec<String>,
+	/// Compiled roblox-ts output that cannot be synced back
+	pub ts_layout: Option<TsLayout>,
+	/// Layers of `$filter` overrides ordered from the root to the most nested node
+	pub layers: Vec<FilterLayer>,
+	/// Where skipped operations are recorded, `None` outside of a workspace
+	pub skipped: Option<Arc<Skipped>>,
 }
 
 impl SyncbackFilter {
+	fn layer_verdict<F>(&self, verdict: F) -> Option<Option<String>>
+	where
+		F: Fn(&FilterLayer) -> Option<Option<String>>,
+	{
+		self.layers.iter().rev().find_map(verdict)
+	}
+
+	/// Returns the rule that excludes the path, if any
+	pub fn find_path(&self, path: &Path) -> Option<FilterRule> {
+		if let Some(layout) = self.ts_layout.as_ref().filter(|layout| layout.is_output(path)) {
+			argon_warn!(
+				"{}",
+				t!(
+					"meta.ts_output",
+					path = path.display().to_string().bold(),
+					root = layout.root_dir.display().to_string().bold()
+				)
+			);
+
+			return Some(FilterRule::new(RuleKind::Output, layout.out_dir.display().to_string()));
+		}
+
+		match self.layer_verdict(|layer| layer.matches_path(path)) {
+			Some(pattern) => pattern.map(|pattern| FilterRule::new(RuleKind::Path, pattern)),
+			None => self
+				.ignore_rules
+				.iter()
+				.find(|rule| rule.matches_with_dir(path))
+				.map(|rule| FilterRule::new(RuleKind::Path, rule.pattern.as_str())),
+		}
+	}
+
+	pub fn find_name(&self, name: &str) -> Option<FilterRule> {
+		match self.layer_verdict(|layer| layer.matches_name(name)) {
+			Some(pattern) => pattern.map(|pattern| FilterRule::new(RuleKind::Name, pattern)),
+			None => self
+				.ignore_names
+				.contains(&name.to_owned())
+				.then(|| FilterRule::new(RuleKind::Name, name)),
+		}
+	}
+
+	pub fn find_class(&self, class: &str) -> Option<FilterRule> {
+		match self.layer_verdict(|layer| layer.matches_class(class)) {
+			Some(pattern) => pattern.map(|pattern| FilterRule::new(RuleKind::Class, pattern)),
+			None => self
+				.ignore_classes
+				.contains(&class.to_owned())
+				.then(|| FilterRule::new(RuleKind::Class, class)),
+		}
+	}
+
+	/// Returns the rule that excludes the instance by its name or class
+	pub fn find_instance(&self, name: &str, class: &str) -> Option<FilterRule> {
+		self.find_name(name).or_else(|| self.find_class(class))
+	}
+
 	pub fn matches_path(&self, path: &Path) -> bool {
-		self.ignore_rules.iter().any(|rule| rule.matches_with_dir(path))
+		self.find_path(path).is_some()
 	}
 
 	pub fn matches_name(&self, name: &str) -> bool {
-		self.ignore_names.contains(&name.to_owned())
+		self.find_name(name).is_some()
 	}
 
 	pub fn matches_class(&self, class: &str) -> bool {
-		self.ignore_classes.contains(&class.to_owned())
+		self.find_class(class).is_some()
 	}
 
 	pub fn matches_property(&self, property: &str) -> bool {
 		self.ignore_properties.contains(&property.to_owned())
 	}
+
+	/// Records the skipped operation in the registry of the workspace, see `Skipped`
+	pub fn record_skip(&self, id: Ref, path: &str, operation: SkippedOperation, rule: FilterRule, file: Option<&Path>) {
+		if let Some(skipped) = &self.skipped {
+			skipped.record(id, path, operation, rule, file);
+		}
+	}
+}
+
+/// Why instances of a source can't be synced back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReadOnlyReason {
+	/// Binary or XML model, e.g. `.rbxm`
+	ModelFile,
+	Archive,
+	/// Output of a compiler, e.g. roblox-ts
+	CompilerOutput,
+	/// Internals of a package that is managed by Roblox
+	PackageInternal,
+	/// Directory with multiple files that could describe it
+	InitConflict,
+	/// Entry of an asset manifest that does not set `allowChildren`
+	AssetManifest,
+}
+
+impl ReadOnlyReason {
+	/// What to do instead of syncing back
+	pub fn remedy(&self) -> String {
+		match self {
+			Self::ModelFile => t!("read_only.model_file"),
+			Self::Archive => t!("read_only.archive"),
+			Self::CompilerOutput => t!("read_only.compiler_output"),
+			Self::PackageInternal => t!("read_only.package_internal"),
+			Self::InitConflict => t!("read_only.init_conflict"),
+			Self::AssetManifest => t!("read_only.asset_manifest"),
+		}
+		.to_string()
+	}
+}
+
+impl Display for ReadOnlyReason {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::ModelFile => write!(f, "model file"),
+			Self::Archive => write!(f, "archive"),
+			Self::CompilerOutput => write!(f, "compiler output"),
+			Self::PackageInternal => write!(f, "package internals"),
+			Self::InitConflict => write!(f, "init file conflict"),
+			Self::AssetManifest => write!(f, "asset manifest"),
+		}
+	}
 }
 
+/// Marks the instance and its descendants as read-only, the processor
+/// rejects syncback of all of them, see `write::find_read_only`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadOnly {
+	pub reason: ReadOnlyReason,
+	/// File or directory that has to be edited instead
+	pub path: PathBuf,
+}
+
+impl ReadOnly {
+	pub fn new(reason: ReadOnlyReason, path: &Path) -> Self {
+		Self {
+			reason,
+			path: path.to_owned(),
+		}
+	}
+}
+
+/// Syncback operation refused because it targets a read-only source,
+/// returned as the error of the operation and sent to the client, so
+/// its message is not colored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadOnlyRejection {
+	pub id: Ref,
+	/// Full name of the instance, for additions the one it would have
+	pub instance: String,
+	pub operation: SkippedOperation,
+	pub reason: ReadOnlyReason,
+	pub path: PathBuf,
+	/// Localized explanation with the suggested remedy
+	pub message: String,
+}
+
+impl ReadOnlyRejection {
+	pub fn new(id: Ref, instance: &str, operation: SkippedOperation, read_only: &ReadOnly) -> Self {
+		let message = t!(
+			"syncback.read_only",
+			instance = instance,
+			reason = read_only.reason.to_string(),
+			path = read_only.path.display(),
+			remedy = read_only.reason.remedy()
+		)
+		.to_string();
+
+		Self {
+			id,
+			instance: instance.to_owned(),
+			operation,
+			reason: read_only.reason,
+			path: read_only.path.clone(),
+			message,
+		}
+	}
+}
+
+impl Display for ReadOnlyRejection {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.message)
+	}
+}
+
+impl std::error::Error for ReadOnlyRejection {}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Context {
 	/// Rules that define how files are synced
 	sync_rules: Vec<SyncRule>,
+	/// Rules of middleware registered with `CoreBuilder`, checked first
+	middleware_rules: Vec<SyncRule>,
+	/// Conventions of the default sync rules
+	middleware_config: MiddlewareConfig,
+	/// Default sync rules generated from non-default `middleware_config`
+	convention_rules: Option<Arc<Vec<SyncRule>>>,
 	/// Rules that define which files are ignored
 	ignore_rules: Vec<IgnoreRule>,
 	/// Filter which ignores specific instances and properties
 	syncback_filter: SyncbackFilter,
 	/// Whether to use legacy script context
 	legacy_scripts: bool,
+	/// Layout of roblox-ts project, its sources are ignored
+	/// and project paths pointing to them are mapped to the output
+	ts_layout: Option<TsLayout>,
+	/// Active build profile, project nodes of other profiles are skipped
+	profile: Option<String>,
+	/// When folders are collapsed into files during syncback
+	collapse_mode: CollapseMode,
+	/// Layouts of instances added from Studio keyed by class
+	layout_preferences: BTreeMap<String, LayoutPreference>,
+	/// Valid ranges of numeric property values
+	property_ranges: PropertyRanges,
+	/// Paths of containers whose descendants are ephemeral
+	scratch_paths: Vec<String>,
+	/// Properties split into included files when writing instance data
+	data_includes: Option<DataIncludes>,
+	/// Format of new instance data files
+	data_format: DataFormat,
+	/// Where new instance data is written
+	data_storage: DataStorage,
+	/// Whether Markdown files are synced as plain text by the default
+	/// sync rules, when not set they are still converted to rich text
+	include_markdown: Option<bool>,
+	/// Size in bytes above which Markdown files are skipped
+	max_markdown_size: usize,
+	/// Whether instances inside packages are synced back
+	manage_packages: bool,
+	/// Project class inference rules, checked before the built-in ones
+	inference_rules: Vec<InferenceRule>,
+	/// Class of the parent instance, `None` if ancestry is unknown
+	parent_class: Option<Ustr>,
+	/// Class of the project node whose `$path` is being read
+	node_class: Option<Ustr>,
+	/// Whether files that fail to load fail their parent too
+	strict_load: bool,
+	/// Number of ancestors of the instance, the root has none
+	depth: usize,
+	/// Maximum depth of instances, see `check_depth`
+	max_tree_depth: usize,
+	/// Whether children are skipped, used to read properties of a single instance
+	shallow: bool,
+	/// Commands that preprocess files as they are synced
+	hooks: Option<Hooks>,
+	/// How names of synced back instances are sanitized
+	name_rules: NameRules,
 }
 
 impl Context {
 	fn new() -> Self {
 		Self {
 			sync_rules: Vec::new(),
+			middleware_rules: Vec::new(),
+			middleware_config: MiddlewareConfig::default(),
+			convention_rules: None,
 			ignore_rules: Vec::new(),
 			syncback_filter: SyncbackFilter::default(),
 			legacy_scripts: true,
+			ts_layout: None,
+			profile: None,
+			collapse_mode: CollapseMode::default(),
+			layout_preferences: BTreeMap::new(),
+			property_ranges: PropertyRanges::default(),
+			scratch_paths: Vec::new(),
+			data_includes: None,
+			data_format: DataFormat::default(),
+			data_storage: DataStorage::default(),
+			include_markdown: None,
+			max_markdown_size: MAX_MARKDOWN_SIZE,
+			manage_packages: false,
+			inference_rules: Vec::new(),
+			parent_class: None,
+			node_class: None,
+			strict_load: false,
+			depth: 0,
+			max_tree_depth: MAX_TREE_DEPTH,
+			shallow: false,
+			hooks: None,
+			name_rules: NameRules::default(),
 		}
 	}
 
-	pub fn sync_rules(&self) -> &Vec<SyncRule> {
-		if self.sync_rules.is_empty() {
-			default_sync_rules()
-		} else {
+	/// Returns rules of registered middleware followed by project sync rules or the default
+	/// ones following `middlewareConfig`, with the default Markdown rule chosen by `includeMarkdown`
+	pub fn sync_rules(&self) -> impl Iterator<Item = &SyncRule> {
+		let is_default = self.sync_rules.is_empty();
+
+		let rules = if !is_default {
 			&self.sync_rules
-		}
+		} else if let Some(rules) = &self.convention_rules {
+			rules.as_ref()
+		} else {
+			default_sync_rules()
+		};
+
+		let rules = rules.iter().filter(move |rule| {
+			!is_default
+				|| match rule.middleware {
+					Middleware::Markdown => self.include_markdown == Some(true),
+					Middleware::RichStringValue => self.include_markdown.is_none(),
+					_ => true,
+				}
+		});
+
+		self.middleware_rules.iter().chain(rules)
 	}
 
 	pub fn sync_rules_of_type(&self, middleware: &Middleware, syncback: bool) -> Vec<&SyncRule> {
 		let config = Config::new();
 
 		self.sync_rules()
-			.iter()
 			.filter(|rule| {
 				if let Some(child_pattern) = rule.child_pattern.as_ref() {
 					if child_pattern.as_str().starts_with(".src") && config.rojo_mode && syncback {
@@ -449,7 +1066,7 @@ impl Context {
 						return false;
 					}
 
-					if pattern.as_str().ends_with(".luau") && config.lua_extension {
+					if pattern.as_str().ends_with(".luau") && self.prefers_lua(&config) {
 						return false;
 					}
 				}
@@ -459,6 +1076,20 @@ impl Context {
 			.collect()
 	}
 
+	/// Resolved conventions of the default sync rules
+	pub fn middleware_config(&self) -> &MiddlewareConfig {
+		&self.middleware_config
+	}
+
+	/// Whether new scripts are written as `.lua` files, contexts
+	/// created without a project follow the global config
+	fn prefers_lua(&self, config: &Config) -> bool {
+		self.middleware_config
+			.scripts
+			.extension
+			.map_or(config.lua_extension, |extension| extension == ScriptExtension::Lua)
+	}
+
 	pub fn ignore_rules(&self) -> &Vec<IgnoreRule> {
 		&self.ignore_rules
 	}
@@ -470,6 +1101,163 @@ impl Context {
 	pub fn use_legacy_scripts(&self) -> bool {
 		self.legacy_scripts
 	}
+
+	pub fn ts_layout(&self) -> Option<&TsLayout> {
+		self.ts_layout.as_ref()
+	}
+
+	pub fn profile(&self) -> Option<&str> {
+		self.profile.as_deref()
+	}
+
+	pub fn collapse_mode(&self) -> CollapseMode {
+		self.collapse_mode
+	}
+
+	/// Returns layout preference of the class, looking through superclasses
+	pub fn layout_preference(&self, class: &str) -> LayoutPreference {
+		let database = rbx_reflection_database::get();
+
+		iter::successors(Some(class), |class| database.classes.get(*class)?.superclass.as_deref())
+			.find_map(|class| self.layout_preferences.get(class).copied())
+			.unwrap_or_default()
+	}
+
+	pub fn property_ranges(&self) -> &PropertyRanges {
+		&self.property_ranges
+	}
+
+	pub fn scratch_paths(&self) -> &Vec<String> {
+		&self.scratch_paths
+	}
+
+	pub fn data_includes(&self) -> Option<&DataIncludes> {
+		self.data_includes.as_ref()
+	}
+
+	/// Returns instance data rules used for writing, rules of the same format
+	/// as the `existing` data file come first, or of the `dataFormat` setting
+	pub fn data_rules(&self, existing: Option<&Path>) -> Vec<&SyncRule> {
+		let extension = existing
+			.and_then(|path| path.extension())
+			.and_then(|extension| extension.to_str())
+			.unwrap_or(self.data_format.extension());
+
+		let mut rules = self.sync_rules_of_type(&Middleware::InstanceData, true);
+
+		rules.sort_by_key(|rule| {
+			!rule
+				.pattern
+				.as_ref()
+				.or(rule.child_pattern.as_ref())
+				.is_some_and(|pattern| pattern.as_str().ends_with(&format!(".{}", extension)))
+		});
+
+		rules
+	}
+
+	pub fn data_storage(&self) -> DataStorage {
+		self.data_storage
+	}
+
+	pub fn max_markdown_size(&self) -> usize {
+		self.max_markdown_size
+	}
+
+	pub fn manage_packages(&self) -> bool {
+		self.manage_packages
+	}
+
+	pub fn inference_rules(&self) -> &Vec<InferenceRule> {
+		&self.inference_rules
+	}
+
+	pub fn parent_class(&self) -> Option<&str> {
+		self.parent_class.as_deref()
+	}
+
+	pub fn node_class(&self) -> Option<&str> {
+		self.node_class.as_deref()
+	}
+
+	pub fn strict_load(&self) -> bool {
+		self.strict_load
+	}
+
+	pub fn name_rules(&self) -> &NameRules {
+		&self.name_rules
+	}
+
+	pub fn hooks(&self) -> Option<&Hooks> {
+		self.hooks.as_ref()
+	}
+
+	pub fn depth(&self) -> usize {
+		self.depth
+	}
+
+	pub fn is_shallow(&self) -> bool {
+		self.shallow
+	}
+
+	/// Fails if instances `levels` below the instance of this context would be
+	/// deeper than `maxTreeDepth`, so deep hierarchies get a diagnostic naming
+	/// `location` instead of overflowing the stack of recursive walks
+	pub fn check_depth(&self, levels: usize, location: impl Display) -> Result<()> {
+		let depth = self.depth + levels;
+
+		if depth > self.max_tree_depth {
+			bail!(
+				"{} is nested {} levels deep which exceeds the maximum of {}, set {} in the project to change it",
+				location,
+				depth,
+				self.max_tree_depth,
+				"maxTreeDepth".bold()
+			);
+		}
+
+		Ok(())
+	}
+
+	/// Returns context whose syncback filter has `filter` of
+	/// a project node on top, path globs are relative to `path`
+	pub fn with_filter(&self, filter: &ProjectFilter, path: &Path) -> Self {
+		let mut context = self.clone();
+
+		context
+			.syncback_filter
+			.layers
+			.push(FilterLayer::new(filter.clone(), path.to_owned()));
+
+		context
+	}
+
+	/// Returns context for children of an instance of the given `class`
+	pub fn with_parent_class(&self, class: impl Into<Ustr>) -> Self {
+		let mut context = self.clone();
+
+		context.parent_class = Some(class.into());
+		context.node_class = None;
+		context.depth += 1;
+
+		context
+	}
+
+	/// Returns context that reads only the instance itself, without its children
+	pub fn with_shallow(&self) -> Self {
+		let mut context = self.clone();
+		context.shallow = true;
+
+		context
+	}
+
+	/// Returns context for the `$path` of a project node of the given `class`
+	pub fn with_node_class(&self, class: impl Into<Ustr>) -> Self {
+		let mut context = self.clone();
+		context.node_class = Some(class.into());
+
+		context
+	}
 }
 
 impl Default for Context {
@@ -489,11 +1277,31 @@ pub struct Meta {
 	pub context: Context,
 	/// Whether to keep unknown child instances
 	pub keep_unknowns: bool,
+	/// Unknown children kept when `keep_unknowns` is set, all of them if `None`,
+	/// clients that don't know the filter fall back to keeping all of them
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub keep_unknowns_filter: Option<UnknownsFilter>,
 	#[serde(skip)]
 	/// Original name of the instance
 	pub original_name: Option<String>,
 	/// Custom Mesh Part source path
 	pub mesh_source: Option<String>,
+	/// Paths of instances that `Ref` properties point at, as written in
+	/// data files, resolved once the whole tree is read, see `core::refs`
+	#[serde(skip)]
+	pub ref_paths: RefPaths,
+	/// Identifier that persists across sessions, assigned by the tree
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub stable_id: Option<String>,
+	/// Why the source failed to load, only set for placeholder instances
+	#[serde(skip)]
+	pub load_error: Option<LoadError>,
+	/// Why the instance and its descendants can't be synced back, if they can't
+	#[serde(skip)]
+	pub read_only: Option<ReadOnly>,
+	/// Conflicting child files of the directory, if there are any
+	#[serde(skip)]
+	pub init_conflict: Option<InitConflict>,
 }
 
 impl Meta {
@@ -504,28 +1312,81 @@ impl Meta {
 			source: Source::new(),
 			context: Context::new(),
 			keep_unknowns: false,
+			keep_unknowns_filter: None,
 			original_name: None,
 			mesh_source: None,
+			ref_paths: RefPaths::new(),
+			stable_id: None,
+			load_error: None,
+			read_only: None,
+			init_conflict: None,
 		}
 	}
 
 	pub fn from_project(project: &Project) -> Self {
+		let ts_layout = project.ts_layout();
+
 		let syncback_filter = if let Some(syncback) = &project.syncback {
 			SyncbackFilter {
 				ignore_rules: IgnoreRule::from_globs(syncback.ignore_globs.clone(), project.workspace_dir.clone()),
 				ignore_names: syncback.ignore_names.clone(),
 				ignore_classes: syncback.ignore_classes.clone(),
 				ignore_properties: syncback.ignore_properties.clone(),
+				ts_layout: ts_layout.clone(),
+				layers: vec![],
+				skipped: Some(skipped::get(&project.workspace_dir)),
+			}
+		} else {
+			SyncbackFilter {
+				ts_layout: ts_layout.clone(),
+				skipped: Some(skipped::get(&project.workspace_dir)),
+				..SyncbackFilter::default()
 			}
+		};
+
+		let middleware_config = project.middleware_config.clone().unwrap_or_default().resolve();
+
+		let convention_rules = if middleware_config.is_default() {
+			None
 		} else {
-			SyncbackFilter::default()
+			Some(Arc::new(sync_rules_for(&middleware_config)))
 		};
 
 		let context = Context {
 			sync_rules: project.sync_rules.clone(),
-			ignore_rules: IgnoreRule::from_globs(project.ignore_globs.clone(), project.workspace_dir.clone()),
+			middleware_rules: external::sync_rules(),
+			middleware_config,
+			convention_rules,
+			ignore_rules: project.ignore_rules(),
 			syncback_filter,
 			legacy_scripts: project.legacy_scripts.unwrap_or(true),
+			ts_layout,
+			profile: project.profile.clone(),
+			collapse_mode: project.collapse_empty_folders.unwrap_or_default(),
+			layout_preferences: project.layout_preferences.clone(),
+			property_ranges: PropertyRanges::new(
+				project.property_ranges.clone(),
+				project.clamp_property_ranges.unwrap_or_default(),
+			),
+			scratch_paths: project.scratch_paths.clone(),
+			data_includes: project.data_includes.clone(),
+			data_format: project.data_format.unwrap_or_default(),
+			data_storage: project.data_storage.unwrap_or_default(),
+			include_markdown: project.include_markdown,
+			max_markdown_size: project.max_markdown_size.unwrap_or(MAX_MARKDOWN_SIZE),
+			manage_packages: project.manage_packages.unwrap_or_default(),
+			inference_rules: project.class_inference.clone(),
+			parent_class: None,
+			node_class: None,
+			strict_load: project.strict_load.unwrap_or_default(),
+			depth: 0,
+			max_tree_depth: project.max_tree_depth.unwrap_or(MAX_TREE_DEPTH),
+			shallow: false,
+			hooks: project
+				.hooks
+				.clone()
+				.map(|hooks| hooks.with_workspace_dir(&project.workspace_dir)),
+			name_rules: project.name_rules.clone().unwrap_or_default(),
 		};
 
 		Self {
@@ -544,8 +1405,8 @@ impl Meta {
 		self
 	}
 
-	pub fn with_keep_unknowns(mut self, keep_unknowns: bool) -> Self {
-		self.keep_unknowns = keep_unknowns;
+	pub fn with_keep_unknowns(mut self, keep_unknowns: KeepUnknowns) -> Self {
+		self.set_keep_unknowns(keep_unknowns);
 		self
 	}
 
@@ -559,6 +1420,16 @@ impl Meta {
 		self
 	}
 
+	pub fn with_load_error(mut self, load_error: LoadError) -> Self {
+		self.load_error = Some(load_error);
+		self
+	}
+
+	pub fn with_read_only(mut self, read_only: ReadOnly) -> Self {
+		self.read_only = Some(read_only);
+		self
+	}
+
 	// Overwriting meta fields
 
 	pub fn set_source<S: Into<Source>>(&mut self, source: S) {
@@ -569,8 +1440,9 @@ impl Meta {
 		self.context = context.clone();
 	}
 
-	pub fn set_keep_unknowns(&mut self, keep_unknowns: bool) {
-		self.keep_unknowns = keep_unknowns;
+	pub fn set_keep_unknowns(&mut self, keep_unknowns: KeepUnknowns) {
+		self.keep_unknowns = keep_unknowns.is_kept();
+		self.keep_unknowns_filter = keep_unknowns.filter().cloned();
 	}
 
 	pub fn set_original_name(&mut self, original_name: Option<String>) {
@@ -580,4 +1452,30 @@ impl Meta {
 	pub fn set_mesh_source(&mut self, mesh_source: Option<String>) {
 		self.mesh_source = mesh_source;
 	}
+
+	pub fn set_ref_paths(&mut self, ref_paths: RefPaths) {
+		self.ref_paths = ref_paths;
+	}
+
+	pub fn set_stable_id(&mut self, stable_id: Option<String>) {
+		self.stable_id = stable_id;
+	}
+
+	pub fn set_read_only(&mut self, read_only: Option<ReadOnly>) {
+		self.read_only = read_only;
+	}
+
+	pub fn set_init_conflict(&mut self, init_conflict: Option<InitConflict>) {
+		self.init_conflict = init_conflict;
+	}
+
+	// Getting meta fields
+
+	/// Returns which unknown children are kept, as written in project and data files
+	pub fn get_keep_unknowns(&self) -> KeepUnknowns {
+		match &self.keep_unknowns_filter {
+			Some(filter) if self.keep_unknowns => KeepUnknowns::Filter(filter.clone()),
+			_ => KeepUnknowns::All(self.keep_unknowns),
+		}
+	}
 }
@@ -1,52 +1,174 @@
-use anyhow::{bail, Result};
-use log::trace;
-use rbx_dom_weak::{types::Ref, Ustr};
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use log::{trace, warn};
+use rbx_dom_weak::{
+	types::{Attributes, Ref, Variant},
+	Ustr,
+};
 use serde::Serialize;
 use snapshot::AddedSnapshot;
 use std::{
-	fs::File,
+	collections::HashMap,
+	fs::{self, File},
 	io::BufWriter,
 	path::{Path, PathBuf},
 	sync::{Arc, Mutex, MutexGuard},
+	thread,
+	time::{Duration, Instant},
 };
+use uuid::Uuid;
 
 use self::{
-	meta::{Meta, SourceEntry},
+	builder::CoreBuilder,
+	changes::Changes,
+	checkpoint::{CheckpointEntry, QueueCheckpoint, QueueCursor, Restored, Resume, CHECKPOINT_VERSION},
+	deferred::{PropertyRequest, PropertyValue},
+	events::{EventKind, EventLog},
+	find::{FindPage, FindQuery},
+	frozen::{Frozen, FrozenInstance, SyncDirection},
+	heartbeat::{Comparison, HeartbeatStats, Heartbeats, NodeHash},
+	helpers::{
+		extensions::{self, Migration},
+		fsck, memory,
+	},
+	initial_sync::InitialSync,
+	integrity::{Integrity, IntegrityStats},
+	lifecycle::{CoreEvent, Lifecycle},
+	meta::{Meta, SourceEntry, SourceIssue},
+	patch::{OperationReport, Patch, PatchFailure, PatchReport, PatchedInstance},
+	path_index::Completion,
+	pending::{Pending, PendingKind},
 	processor::Processor,
 	queue::Queue,
+	skipped::{SkippedEntry, SkippedQuery, SkippedStats},
+	snapshot::UpdatedSnapshot,
+	stable_ids::StableIds,
+	subscription::SubscriptionFilter,
+	sync_direction::{StudioSubtree, SyncPlan, TreeSummary},
+	test_runs::{TestReport, TestRun, TestRuns},
+	trace::TraceRecorder,
 	tree::Tree,
+	tree_hash::TreeHash,
+	typegen::TypegenOptions,
+	unknowns::{Unknowns, UnknownsGroup, UnknownsSummary},
+};
+use crate::{
+	argon_warn,
+	assets::{self, AddedAsset, AssetClass, AssetEntry, AssetStore},
+	class_meta::{self, ClassMetadataTable, InstanceMetadata},
+	config::Config,
+	constants::{
+		ASSET_ATTRIBUTE, EVENT_BUFFER_SIZE, EVENT_SUBSCRIBER_CAPACITY, LIFECYCLE_CAPACITY, SNAPSHOT_PAGE_SCAN_FACTOR,
+		STUDIO_SYNC_TIMEOUT, SYNC_CHUNK_SIZE, SYNC_RESUME_TIMEOUT, SYNC_WINDOW,
+	},
+	core::snapshot::Snapshot,
+	crash_handler, lock,
+	middleware::{
+		new_snapshot,
+		project::snapshot_project,
+		storage::{self, DataMigration},
+	},
+	project::{DataStorage, Project, ScriptExtension},
+	server::{self, protocol::Capability, SyncProgress},
+	stats, t,
+	util::{
+		self,
+		editor::{self, OpenError},
+	},
+	vfs::{
+		undo_log::{self, Marker},
+		Vfs,
+	},
+	Properties,
 };
-use crate::{core::snapshot::Snapshot, lock, middleware::new_snapshot, project::Project, stats, util, vfs::Vfs};
 
+pub mod blobs;
+pub mod builder;
 pub mod changes;
+pub mod checkpoint;
+pub mod deferred;
+pub mod events;
+pub mod find;
+pub mod frozen;
+pub mod heartbeat;
 pub mod helpers;
+pub mod initial_sync;
+pub mod integrity;
+pub mod lifecycle;
 pub mod meta;
+pub mod patch;
+pub mod path_index;
+pub mod pending;
 pub mod processor;
+pub mod property_reports;
 pub mod queue;
+pub mod refs;
+pub mod skipped;
 pub mod snapshot;
+pub mod stable_ids;
+pub mod subscription;
+pub mod sync_direction;
+pub mod test_runs;
+pub mod trace;
 pub mod tree;
+pub mod tree_hash;
+pub mod typegen;
+pub mod unknowns;
 
 pub struct Core {
 	project: Arc<Mutex<Project>>,
 	tree: Arc<Mutex<Tree>>,
 	queue: Arc<Queue>,
 	processor: Arc<Processor>,
-	_vfs: Arc<Vfs>,
+	vfs: Arc<Vfs>,
+	initial_sync: InitialSync,
+	events: Arc<EventLog>,
+	pending: Arc<Pending>,
+	/// Tree hash and the tree generation it was computed for
+	tree_hash: Mutex<Option<(u64, TreeHash)>>,
+	/// Identifies queues of this server, see `Core::resume`
+	queue_generation: String,
+	/// Queue checkpoints of the previous server waiting for their clients
+	restored: Mutex<HashMap<u32, Restored>>,
+	/// Last checkpointed state of client queues, unchanged ones are not written again
+	checkpoints: Mutex<HashMap<u32, (u64, u64, usize, bool)>>,
+	heartbeats: Heartbeats,
+	/// Initial sync directions agreed on with clients that did not sync yet
+	sync_plans: Mutex<HashMap<u32, SyncPlan>>,
+	unknowns: Unknowns,
+	test_runs: TestRuns,
+	integrity: Integrity,
+	lifecycle: Lifecycle,
 }
 
 impl Core {
-	#[profiling::function]
 	pub fn new(project: Project, watch: bool) -> Result<Self> {
-		profiling::start_frame!();
-
 		trace!("Initializing VFS");
 
-		let vfs = Vfs::new(watch);
+		Self::with_vfs(project, Vfs::new(watch))
+	}
+
+	/// Creates core on top of the provided VFS, e.g. one
+	/// that shares the OS watcher with other workspace members
+	#[profiling::function]
+	pub fn with_vfs(project: Project, vfs: Vfs) -> Result<Self> {
+		profiling::start_frame!();
+
+		vfs.set_ignore_rules(project.ignore_rules());
+		vfs.set_verify_writes(project.verifies_writes());
+		vfs.set_undo_log(Some(undo_log::get(&project.workspace_dir)));
 
 		trace!("Snapshotting root project");
 
 		let meta = Meta::from_project(&project);
-		let snapshot = new_snapshot(&project.path, &meta.context, &vfs)?.expect(
+
+		// Projects read from stdin don't exist on disk so they can't be snapshotted from their path
+		let snapshot = if project.from_stdin {
+			Some(snapshot_project(&project, &project.path, &vfs)?)
+		} else {
+			new_snapshot(&project.path, &meta.context, &vfs)?
+		}
+		.expect(
 			"Failed to snapshot root project. \
 		If you are using custom sync rules make sure you have one with the `Project` type. \
 		Otherwise, this is a bug.",
@@ -54,20 +176,97 @@ impl Core {
 
 		trace!("Building Tree and Queue");
 
+		let mut tree = Tree::new(snapshot);
+		tree.set_project_node(project.node.clone());
+
+		let checkpoints = if Config::new().durable_queues {
+			QueueCheckpoint::read_all(&project.workspace_dir)
+		} else {
+			HashMap::new()
+		};
+
+		// Computed before eviction so checkpoints can be compared with the tree
+		let hash = (!checkpoints.is_empty()).then(|| {
+			(
+				tree.generation(),
+				tree_hash::hash_dom(tree.inner(), &top_level_refs(&tree, project.is_place())),
+			)
+		});
+
+		if project.is_low_memory() {
+			trace!("Evicting large property values");
+			tree.evict_all();
+		}
+
+		trace!("Loading stable instance IDs");
+
+		let stable_ids = StableIds::load(&project.workspace_dir, Config::new().id_retention);
+
+		if let Err(err) = tree.load_stable_ids(stable_ids) {
+			warn!("Failed to save stable instance IDs: {}", err);
+		}
+
+		tree.load_frozen(Frozen::load(&project.workspace_dir));
+
+		let restored: HashMap<u32, Restored> = match hash {
+			Some((_, hash)) => checkpoints
+				.into_iter()
+				.map(|(id, checkpoint)| (id, checkpoint.reconcile(&tree, hash)))
+				.collect(),
+			None => HashMap::new(),
+		};
+
+		if !restored.is_empty() {
+			trace!("Restored {} queue checkpoints", restored.len());
+		}
+
 		let vfs = Arc::new(vfs);
-		let tree = Arc::new(Mutex::new(Tree::new(snapshot)));
+		let tree = Arc::new(Mutex::new(tree));
 		let queue = Arc::new(Queue::new());
+		let events = Arc::new(EventLog::new(EVENT_BUFFER_SIZE, EVENT_SUBSCRIBER_CAPACITY));
+		let pending = Arc::new(Pending::new());
+		let lifecycle = Lifecycle::new(LIFECYCLE_CAPACITY);
+
+		for load_error in lock!(tree)
+			.meta_map()
+			.values()
+			.filter_map(|meta| meta.load_error.as_ref())
+		{
+			events.publish(EventKind::Diagnostic {
+				level: String::from("error"),
+				message: format!("Failed to load {}", load_error),
+				instance: None,
+			});
+		}
+
+		for conflict in lock!(tree)
+			.meta_map()
+			.values()
+			.filter_map(|meta| meta.init_conflict.as_ref())
+		{
+			events.publish(EventKind::Diagnostic {
+				level: String::from("warning"),
+				message: format!("Conflicting files in {}: {}", conflict.dir.display(), conflict),
+				instance: None,
+			});
+		}
 
 		trace!("Starting Processor");
 
+		let lazy_properties = project.lazy_properties.clone().unwrap_or_default();
 		let project = Arc::new(Mutex::new(project));
 		let processor = Arc::new(Processor::new(
 			queue.clone(),
 			tree.clone(),
 			vfs.clone(),
 			project.clone(),
+			events.clone(),
+			pending.clone(),
+			lifecycle.clone(),
 		));
 
+		crash_handler::track(&tree, &project);
+
 		trace!("Core initialized successfully!");
 
 		Ok(Core {
@@ -75,10 +274,29 @@ impl Core {
 			tree,
 			queue,
 			processor,
-			_vfs: vfs,
+			vfs,
+			initial_sync: InitialSync::new(SYNC_CHUNK_SIZE, SYNC_WINDOW, SYNC_RESUME_TIMEOUT)
+				.with_lazy_properties(lazy_properties),
+			events,
+			pending,
+			tree_hash: Mutex::new(hash),
+			queue_generation: Uuid::new_v4().to_string(),
+			restored: Mutex::new(restored),
+			checkpoints: Mutex::new(HashMap::new()),
+			heartbeats: Heartbeats::new(),
+			sync_plans: Mutex::new(HashMap::new()),
+			unknowns: Unknowns::new(),
+			test_runs: TestRuns::new(),
+			integrity: Integrity::new(),
+			lifecycle,
 		})
 	}
 
+	/// Returns builder of the core, e.g. for registering custom middleware
+	pub fn builder(project: Project) -> CoreBuilder {
+		CoreBuilder::new(project)
+	}
+
 	pub fn name(&self) -> String {
 		self.project().name.clone()
 	}
@@ -103,69 +321,1137 @@ impl Core {
 		self.queue.clone()
 	}
 
+	pub fn events(&self) -> Arc<EventLog> {
+		self.events.clone()
+	}
+
 	pub fn processor(&self) -> Arc<Processor> {
 		self.processor.clone()
 	}
 
-	/// Create snapshot of the tree or a subtree
-	pub fn snapshot(&self, instance: Ref) -> Option<AddedSnapshot> {
+	pub fn vfs(&self) -> Arc<Vfs> {
+		self.vfs.clone()
+	}
+
+	pub fn pending(&self) -> Arc<Pending> {
+		self.pending.clone()
+	}
+
+	/// Subscribes to lifecycle events of the core. Events are never held back
+	/// for slow subscribers, they get `RecvError::Lagged` instead
+	///
+	/// ```no_run
+	/// use argon::{
+	///     core::{lifecycle::CoreEvent, Core},
+	///     project::Project,
+	/// };
+	/// use std::path::Path;
+	///
+	/// let project = Project::load(Path::new("default.project.json"))?;
+	/// let core = Core::new(project, true)?;
+	/// let mut events = core.subscribe();
+	///
+	/// std::thread::spawn(move || {
+	///     while let Ok(event) = events.blocking_recv() {
+	///         if let CoreEvent::ClientConnected { name, .. } = event {
+	///             println!("{} connected", name);
+	///         }
+	///     }
+	/// });
+	/// # Ok::<(), anyhow::Error>(())
+	/// ```
+	pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<CoreEvent> {
+		self.lifecycle.subscribe()
+	}
+
+	/// Emits lifecycle event to all subscribers, see `Core::subscribe`
+	pub fn emit(&self, event: CoreEvent) {
+		self.lifecycle.emit(event);
+	}
+
+	/// Returns instance addressed by its stable ID if provided,
+	/// `None` if there is no instance with such ID
+	pub fn resolve(&self, instance: Ref, stable_id: Option<&str>) -> Option<Ref> {
+		match stable_id {
+			Some(stable_id) => self.tree().find_by_stable_id(stable_id),
+			None => Some(instance),
+		}
+	}
+
+	/// Create snapshot of the tree or a subtree,
+	/// ephemeral instances are skipped unless requested
+	pub fn snapshot(&self, instance: Ref, include_ephemeral: bool) -> Option<AddedSnapshot> {
+		let tree = self.tree();
+		let instance = if instance.is_some() { instance } else { tree.root_ref() };
+
+		snapshot_subtree(instance, include_ephemeral, &tree, &self.vfs)
+	}
+
+	/// Create snapshot of the tree or a subtree pruned to the scope of the filter,
+	/// followed by instances in scope whose parents are not, see `SubscriptionFilter::prune`
+	pub fn snapshot_filtered(
+		&self,
+		instance: Ref,
+		include_ephemeral: bool,
+		filter: &SubscriptionFilter,
+	) -> Option<Vec<AddedSnapshot>> {
 		let tree = self.tree();
+		let instance = if instance.is_some() { instance } else { tree.root_ref() };
 
-		fn walk(children: &[Ref], tree: &Tree) -> Vec<Snapshot> {
-			let mut snapshot_children = vec![];
+		snapshot_subtree(instance, include_ephemeral, &tree, &self.vfs).map(|snapshot| filter.prune(snapshot, &tree))
+	}
 
-			for child in children {
-				let meta = tree.get_meta(*child).unwrap();
-				let child = tree.get_instance(*child).unwrap();
+	/// Create a single page of the flattened tree snapshot, cursor has to come from the previous
+	/// page, instances out of scope of the filter are skipped. Only `limit` times
+	/// `SNAPSHOT_PAGE_SCAN_FACTOR` instances are scanned so a page with a cursor can be empty
+	pub fn snapshot_page(
+		&self,
+		cursor: Option<&str>,
+		limit: usize,
+		include_ephemeral: bool,
+		filter: Option<&SubscriptionFilter>,
+	) -> Result<SnapshotPage> {
+		let mut tree = self.tree();
+		let generation = tree.generation();
 
-				let snapshot = Snapshot::new()
-					.with_id(child.referent())
-					.with_name(&child.name)
-					.with_class(&child.class)
-					.with_properties(child.properties.clone())
-					.with_children(walk(child.children(), tree))
-					.with_meta(meta.clone());
+		// Cursor is the generation, the ID of the next instance and its position,
+		// e.g. `12:0123456789abcdef0123456789abcdef:0.4.17`
+		let mut next = if let Some(cursor) = cursor {
+			let mut parts = cursor.splitn(3, ':');
+			let (Some(cursor_generation), Some(id), Some(indices)) = (parts.next(), parts.next(), parts.next()) else {
+				bail!("Invalid snapshot cursor");
+			};
 
-				snapshot_children.push(snapshot);
+			if cursor_generation.parse::<u64>()? != generation {
+				return Ok(SnapshotPage {
+					generation,
+					restart: true,
+					instances: vec![],
+					cursor: None,
+					class_meta: None,
+					tree_hash: None,
+				});
 			}
 
-			snapshot_children
-		}
+			let id = id.parse::<Ref>().ok().context("Invalid snapshot cursor")?;
+			let indices = if indices.is_empty() {
+				vec![]
+			} else {
+				indices
+					.split('.')
+					.map(str::parse)
+					.collect::<Result<Vec<usize>, _>>()
+					.ok()
+					.context("Invalid snapshot cursor")?
+			};
+
+			let position = tree
+				.position_at(indices)
+				.filter(|position| position.id == id)
+				.context("Snapshot cursor points to missing instance")?;
 
-		let root = if instance.is_some() {
-			tree.get_instance(instance)?
+			Some(position)
 		} else {
-			tree.root()
+			Some(tree.root_position())
+		};
+
+		let mut instances = Vec::with_capacity(limit);
+		let mut scanned = 0;
+
+		while instances.len() < limit && scanned < limit * SNAPSHOT_PAGE_SCAN_FACTOR {
+			let Some(position) = next else {
+				break;
+			};
+
+			scanned += 1;
+
+			let id = position.id;
+			let instance = tree.get_instance(id).unwrap();
+			let meta = tree.get_meta(id).unwrap();
+
+			next = tree.next_position(position);
+
+			// Descendants of ephemeral instances are ephemeral too so they are skipped one by one
+			if meta.source.is_ephemeral() && !include_ephemeral {
+				continue;
+			}
+
+			if filter.is_some_and(|filter| !filter.contains(id, &tree)) {
+				continue;
+			}
+
+			instances.push(AddedSnapshot {
+				id,
+				meta: meta.clone(),
+				parent: instance.parent(),
+				name: instance.name.clone(),
+				class: instance.class,
+				properties: memory::materialize_lossy(id, &tree, &self.vfs).unwrap(),
+				children: vec![],
+			});
+		}
+
+		// Hash is computed before unlocking so it belongs to the same generation as the last page
+		let tree_hash = next.is_none().then(|| self.hash_tree(&mut tree).to_string());
+
+		drop(tree);
+
+		let cursor = next.map(|position| {
+			let indices: Vec<String> = position.indices.iter().map(usize::to_string).collect();
+			format!("{}:{}:{}", generation, position.id, indices.join("."))
+		});
+
+		Ok(SnapshotPage {
+			generation,
+			restart: false,
+			instances,
+			cursor,
+			tree_hash,
+			class_meta: None,
+		})
+	}
+
+	/// Start or resume streaming the whole tree to the client
+	pub fn start_sync(&self, id: u32, resume: bool) -> Result<SyncProgress> {
+		if let Some(plan) = lock!(self.sync_plans).get(&id) {
+			match plan.direction {
+				Some(SyncDirection::ToStudio) => {}
+				Some(SyncDirection::ToDisk) => bail!("Subtrees from Studio have to be sent to /sync/studio first"),
+				None => bail!("Initial sync direction has to be resolved first"),
+			}
+		}
+
+		lock!(self.sync_plans).remove(&id);
+
+		// Whole tree includes every pending instance
+		if !resume {
+			self.pending.clear();
+		}
+
+		let progress = self
+			.initial_sync
+			.start(id, resume, &self.tree, &self.vfs, &self.queue)?;
+		self.publish_progress(id, &progress);
+
+		Ok(progress)
+	}
+
+	/// Read values of deferred properties, in the order they were requested
+	pub fn read_properties(&self, requests: &[PropertyRequest]) -> Vec<PropertyValue> {
+		deferred::read(requests, &self.tree(), &self.vfs)
+	}
+
+	/// Acknowledge chunk of the initial sync
+	pub fn ack_sync(&self, id: u32, chunk: usize) -> Result<()> {
+		let progress = self.initial_sync.ack(id, chunk, &self.tree, &self.vfs, &self.queue)?;
+		self.publish_progress(id, &progress);
+
+		Ok(())
+	}
+
+	/// Unsubscribe the client, changes it has not received
+	/// are kept as pending until a client reconnects
+	pub fn unsubscribe(&self, id: u32) -> Result<()> {
+		let synced = self.is_synced(id);
+		let undelivered = self.queue.unsubscribe(id)?;
+
+		// Both sides agree on the tree when the client leaves with nothing left to receive
+		if synced && undelivered.iter().all(|changes| changes.is_empty()) {
+			if let Err(err) = self.save_sync_base() {
+				warn!("Failed to save sync base: {}", err);
+			}
+		}
+
+		let tree = self.tree();
+
+		for changes in undelivered {
+			self.pending.record(&changes, &tree);
+		}
+
+		drop(tree);
+
+		// Client left on purpose so there is nothing to resume
+		lock!(self.checkpoints).remove(&id);
+		lock!(self.sync_plans).remove(&id);
+		self.unknowns.remove(id);
+		fs::remove_file(checkpoint::queue_path(&self.project().workspace_dir, id)).ok();
+
+		Ok(())
+	}
+
+	/// Changes the part of the tree the client subscribed to,
+	/// see `Queue::change_filter`, returns the number of pushed changes
+	pub fn change_filter(&self, id: u32, filter: Option<SubscriptionFilter>) -> Result<usize> {
+		let tree = self.tree();
+		self.queue.change_filter(id, filter, &tree, &self.vfs)
+	}
+
+	/// Push instances that changed while no client was connected to the
+	/// reconnected client instead of the whole tree, returns their number
+	pub fn resync_pending(&self, id: u32) -> Result<usize> {
+		let tree = self.tree();
+		let mut changes = Changes::new();
+
+		for (instance, kind) in self.pending.take(&tree) {
+			match kind {
+				PendingKind::Added => {
+					changes
+						.additions
+						.extend(snapshot_subtree(instance, true, &tree, &self.vfs));
+				}
+				PendingKind::Updated => {
+					let Some(name) = tree.get_instance(instance).map(|instance| instance.name.clone()) else {
+						continue;
+					};
+
+					let mut snapshot = UpdatedSnapshot::new(instance);
+					snapshot.name = Some(name);
+					snapshot.properties = memory::materialize_lossy(instance, &tree, &self.vfs);
+
+					changes.update(snapshot);
+				}
+				PendingKind::Removed => changes.remove(instance),
+			}
+		}
+
+		let total = changes.total();
+
+		if total > 0 {
+			self.queue.push_changes(changes, Some(id), &tree, &self.vfs)?;
+		}
+
+		Ok(total)
+	}
+
+	/// Freezes instance at the dot separated path, e.g. `Workspace.Terrain`,
+	/// returns `false` if it already was frozen
+	pub fn freeze(&self, path: &str) -> Result<bool> {
+		let mut tree = self.tree();
+		let id = tree
+			.find_by_path(path)
+			.with_context(|| format!("No instance at {}", path))?;
+
+		tree.freeze(id)
+	}
+
+	/// Unfreezes instance at the path, returns `false` if it was not frozen
+	pub fn unfreeze(&self, path: &str) -> Result<bool> {
+		let mut tree = self.tree();
+		let id = tree
+			.find_by_path(path)
+			.with_context(|| format!("No instance at {}", path))?;
+
+		tree.unfreeze(id)
+	}
+
+	pub fn frozen(&self) -> Vec<FrozenInstance> {
+		self.tree().frozen_instances()
+	}
+
+	/// Reconciles frozen instance at the path once, `ToStudio` sends its state
+	/// on disk to Studio reverting Studio changes made while it was frozen,
+	/// `ToDisk` writes those changes to disk, returns number of synced changes
+	pub fn sync_frozen(&self, path: &str, direction: SyncDirection) -> Result<usize> {
+		let mut tree = self.tree();
+		let id = tree
+			.find_by_path(path)
+			.with_context(|| format!("No instance at {}", path))?;
+
+		if !tree.is_frozen(id) {
+			bail!("{} is not frozen", path);
+		}
+
+		let held = tree.take_frozen(id);
+
+		match direction {
+			SyncDirection::ToStudio => {
+				let mut changes =
+					processor::read::process_frozen(id, &mut tree, &self.vfs).unwrap_or_else(Changes::new);
+				let removed = held.removals.clone();
+
+				// Instances added in Studio are removed, removed ones are added back
+				for snapshot in held.additions {
+					if !tree.exists(snapshot.id) {
+						changes.remove(snapshot.id);
+					}
+				}
+
+				for snapshot in held.updates {
+					let Some(instance) = tree.get_instance(snapshot.id) else {
+						continue;
+					};
+
+					if removed.contains(&snapshot.id) {
+						continue;
+					}
+
+					let mut update = UpdatedSnapshot::new(snapshot.id);
+					update.name = Some(instance.name.clone());
+					update.class = Some(instance.class);
+					update.properties = memory::materialize_lossy(snapshot.id, &tree, &self.vfs);
+
+					changes.update(update);
+				}
+
+				for removed in removed {
+					changes
+						.additions
+						.extend(snapshot_subtree(removed, false, &tree, &self.vfs));
+				}
+
+				let total = changes.total();
+
+				if total > 0 {
+					if self.queue.clients().is_empty() {
+						self.pending.record(&changes, &tree);
+					}
+
+					self.queue.push_changes(changes, None, &tree, &self.vfs)?;
+				}
+
+				Ok(total)
+			}
+			SyncDirection::ToDisk => {
+				let total = held.total();
+				let structural = held.is_structural();
+
+				self.vfs.pause();
+
+				let result = || -> Result<()> {
+					for snapshot in held.additions {
+						processor::write::apply_addition(snapshot, &mut tree, &self.vfs)?;
+					}
+
+					for snapshot in held.updates {
+						processor::write::apply_update(snapshot, &mut tree, &self.vfs)?;
+					}
+
+					for id in held.removals {
+						processor::write::apply_removal(id, &mut tree, &self.vfs)?;
+					}
+
+					processor::write::finish_batch(&mut tree, &self.vfs)
+				}();
+
+				self.vfs.resume();
+
+				if structural {
+					if let Err(err) = tree.save_stable_ids() {
+						warn!("Failed to save stable instance IDs: {}", err);
+					}
+				}
+
+				result.map(|_| total)
+			}
+		}
+	}
+
+	/// Identifies queues of this server, clients report it back when reconnecting
+	pub fn queue_generation(&self) -> &str {
+		&self.queue_generation
+	}
+
+	/// Writes checkpoints of queues of clients that negotiated `Resume`,
+	/// queues that did not change since the last checkpoint are skipped
+	pub fn checkpoint_queues(&self) -> Result<()> {
+		let config = Config::new();
+
+		if !config.durable_queues {
+			return Ok(());
+		}
+
+		let workspace_dir = self.project().workspace_dir.clone();
+
+		let mut tree = self.tree();
+		let hash = self.hash_tree(&mut tree);
+
+		let pending: Vec<_> = self
+			.pending
+			.list()
+			.into_iter()
+			.map(|instance| (instance.id, instance.kind))
+			.collect();
+
+		let mut checkpoints = lock!(self.checkpoints);
+
+		for client in self.queue.clients() {
+			if !client.negotiated.supports(Capability::Resume) {
+				continue;
+			}
+
+			let Some(journal) = self.queue.journal(client.id) else {
+				continue;
+			};
+
+			let state = (
+				tree.generation(),
+				journal.delivered,
+				journal.changes.len(),
+				journal.volatile,
+			);
+
+			if checkpoints.get(&client.id) == Some(&state) {
+				continue;
+			}
+
+			let entries = checkpoint::coalesce(pending.iter().copied().chain(journal.changes))
+				.into_iter()
+				.map(|(id, kind)| CheckpointEntry {
+					id,
+					stable_id: tree.get_meta(id).and_then(|meta| meta.stable_id.clone()),
+					kind,
+				})
+				.collect();
+
+			let checkpoint = QueueCheckpoint {
+				version: CHECKPOINT_VERSION,
+				client_id: client.id,
+				generation: self.queue_generation.clone(),
+				delivered: journal.delivered,
+				tree_hash: hash.to_string(),
+				refs: tree
+					.stable_id_map()
+					.iter()
+					.map(|(stable_id, id)| (stable_id.clone(), *id))
+					.collect(),
+				entries,
+				invalid: journal.volatile,
+			};
+
+			checkpoint.write(
+				&checkpoint::queue_path(&workspace_dir, client.id),
+				config.max_queue_checkpoint_size,
+			)?;
+
+			checkpoints.insert(client.id, state);
+		}
+
+		Ok(())
+	}
+
+	/// Decides whether the reconnecting client can continue from its cursor. If the
+	/// previous server checkpointed its queue and the tree did not change since, the
+	/// client receives `SyncResume` and instances it missed are marked as pending
+	pub fn resume(&self, id: u32, cursor: &QueueCursor) -> Result<Resume> {
+		// Client reconnected to the same server, see `Core::unsubscribe`
+		if cursor.generation == self.queue_generation {
+			return Ok(Resume::Continue);
+		}
+
+		let restored = lock!(self.restored).remove(&id);
+		fs::remove_file(checkpoint::queue_path(&self.project().workspace_dir, id)).ok();
+
+		let Some(restored) = restored else {
+			return Ok(Resume::Resync);
+		};
+
+		if restored.generation != cursor.generation || restored.delivered != cursor.received {
+			return Ok(Resume::Resync);
+		}
+
+		let Some(resumed) = restored.resumed else {
+			return Ok(Resume::Resync);
+		};
+
+		let tree = self.tree();
+
+		for (instance, kind) in resumed.pending {
+			self.pending.restore(instance, kind, tree.get_full_name(instance));
+		}
+
+		drop(tree);
+
+		self.queue.push(server::SyncResume { refs: resumed.refs }, Some(id))?;
+
+		Ok(Resume::Continue)
+	}
+
+	/// Summary of the tree clients compare their own with, see `TreeSummary`
+	pub fn sync_summary(&self) -> TreeSummary {
+		TreeSummary::new(&self.tree(), &self.vfs)
+	}
+
+	/// Saves summary of the tree as the state both sides agree on,
+	/// used as the base of `plan_sync` in the next session
+	pub fn save_sync_base(&self) -> Result<()> {
+		let workspace_dir = self.project().workspace_dir.clone();
+		self.sync_summary().save_base(&workspace_dir)
+	}
+
+	/// Whether the client has the whole tree, `None` checks all connected clients
+	pub fn is_synced(&self, id: impl Into<Option<u32>>) -> bool {
+		let plans = lock!(self.sync_plans);
+		let synced = |id: u32| !self.initial_sync.is_syncing(id) && !plans.contains_key(&id);
+
+		match id.into() {
+			Some(id) => self.queue.is_subscribed(id) && synced(id),
+			None => self.queue.clients().iter().any(|client| synced(client.id)),
+		}
+	}
+
+	/// Decides direction of the initial sync from the summary of the client's tree,
+	/// see `sync_direction::compare`. When both sides changed and no policy is set,
+	/// the client has to choose one with `resolve_sync` before it can sync
+	pub fn plan_sync(&self, id: u32, studio: &TreeSummary) -> SyncPlan {
+		let base = TreeSummary::load_base(&self.project().workspace_dir);
+		let divergent = sync_direction::compare(base.as_ref(), &self.sync_summary(), studio);
+		let plan = sync_direction::plan(divergent, &Config::new().sync_conflict_policy);
+
+		if plan.direction.is_none() {
+			let services: Vec<&str> = plan.divergent.iter().map(|service| service.name.as_str()).collect();
+
+			argon_warn!(
+				"{}",
+				t!(
+					"sync.conflict",
+					client = id.to_string().bold(),
+					services = services.join(", ").bold(),
+					setting = "sync_conflict_policy".bold()
+				)
+			);
+		}
+
+		lock!(self.sync_plans).insert(id, plan.clone());
+
+		plan
+	}
+
+	/// Chooses direction of the initial sync when both sides changed
+	pub fn resolve_sync(&self, id: u32, direction: SyncDirection) -> Result<SyncPlan> {
+		let mut plans = lock!(self.sync_plans);
+		let plan = plans.get_mut(&id).context("Client has no initial sync to resolve")?;
+
+		plan.direction = Some(direction);
+
+		Ok(plan.clone())
+	}
+
+	/// Writes divergent subtrees as they exist in Studio to disk with regular syncback,
+	/// replacing the ones on disk, the client syncs the whole tree afterwards. Subtrees
+	/// that are not divergent are ignored, returns the number of written ones
+	pub fn sync_from_studio(&self, id: u32, subtrees: Vec<StudioSubtree>) -> Result<usize> {
+		let plan = lock!(self.sync_plans)
+			.get(&id)
+			.cloned()
+			.context("Client has no initial sync in progress")?;
+
+		if plan.direction != Some(SyncDirection::ToDisk) {
+			bail!("Initial sync of the client does not use Studio's side");
+		}
+
+		let mut removals = Changes::new();
+		let mut additions = Changes::new();
+		let mut written = 0;
+
+		{
+			let tree = self.tree();
+			let root = tree.get_instance(tree.root_ref()).unwrap();
+
+			for subtree in subtrees {
+				if !plan.is_divergent(&subtree.service, &subtree.name) {
+					warn!(
+						"Ignoring {}.{} from Studio as it is not divergent",
+						subtree.service, subtree.name
+					);
+					continue;
+				}
+
+				let Some(service) = root
+					.children()
+					.iter()
+					.find(|child| tree.get_instance(**child).unwrap().name == subtree.service)
+					.copied()
+				else {
+					continue;
+				};
+
+				for child in tree.get_instance(service).unwrap().children() {
+					if tree.get_instance(*child).unwrap().name == subtree.name {
+						removals.remove(*child);
+					}
+				}
+
+				for snapshot in subtree.instances {
+					additions.additions.push(snapshot.into_new(service));
+				}
+
+				written += 1;
+			}
+		}
+
+		// Removals go first so the written subtrees don't collide with the old ones
+		for changes in [removals, additions] {
+			if !changes.is_empty() {
+				self.write_and_wait(id, changes)?;
+			}
+		}
+
+		lock!(self.sync_plans).remove(&id);
+
+		Ok(written)
+	}
+
+	/// Applies changes of the client and waits until they are written
+	fn write_and_wait(&self, id: u32, changes: Changes) -> Result<()> {
+		let processed = self.processor.processed();
+		let started = Instant::now();
+
+		self.processor.write(processor::WriteRequest { changes, client_id: id });
+
+		while self.processor.processed() == processed || self.processor.is_busy() {
+			if started.elapsed() > STUDIO_SYNC_TIMEOUT {
+				bail!("Subtrees from Studio were not written in time");
+			}
+
+			thread::sleep(Duration::from_millis(10));
+		}
+
+		Ok(())
+	}
+
+	fn publish_progress(&self, id: u32, progress: &SyncProgress) {
+		self.events.publish(EventKind::SyncProgress {
+			client_id: id,
+			phase: progress.phase.clone(),
+			sent: progress.sent,
+			total: progress.total,
+		});
+
+		if !self.initial_sync.is_syncing(id) {
+			self.lifecycle.emit(CoreEvent::InitialSyncComplete {
+				client_id: id,
+				instances: progress.total,
+			});
+
+			if let Err(err) = self.save_sync_base() {
+				warn!("Failed to save sync base: {}", err);
+			}
+		}
+	}
+
+	/// Send tree hash to clients that support heartbeats, see `Heartbeats::send`
+	pub fn heartbeat(&self) -> Result<usize> {
+		let tree = self.tree();
+		self.heartbeats
+			.send(&tree, &self.queue, |id| self.initial_sync.is_syncing(id))
+	}
+
+	/// Narrow down divergence of the client's tree, see `heartbeat::hash_subtree`
+	pub fn compare_hashes(&self, id: u32, compare: &[NodeHash], resync: &[Ref]) -> Result<Comparison> {
+		let tree = self.tree();
+		self.heartbeats
+			.compare(id, compare, resync, &tree, &self.vfs, &self.queue)
+	}
+
+	pub fn heartbeat_stats(&self) -> HeartbeatStats {
+		self.heartbeats.stats()
+	}
+
+	/// Returns syncback operations skipped by the filter, see `Skipped`
+	pub fn skipped(&self, query: &SkippedQuery) -> Vec<SkippedEntry> {
+		let skipped = skipped::get(&self.project().workspace_dir);
+		let tree = self.tree();
+
+		skipped.list(query, |id| tree.get_full_name(id))
+	}
+
+	pub fn skipped_stats(&self) -> SkippedStats {
+		skipped::get(&self.project().workspace_dir).stats()
+	}
+
+	/// Checks the next slice of source paths for changes the watcher missed and
+	/// processes them like regular events, see `Integrity::scan`. Skipped while
+	/// syncback is applying changes, returns the number of missed changes
+	pub fn scan_integrity(&self) -> usize {
+		if self.processor.is_busy() {
+			trace!("Syncback in progress, skipping integrity scan");
+			return 0;
+		}
+
+		let events = self.integrity.scan(
+			&self.tree,
+			&self.vfs,
+			Config::new().integrity_scan_batch,
+			self.processor.processed(),
+		);
+		let misses = events.len();
+
+		for event in events {
+			self.processor.notify(event);
+		}
+
+		misses
+	}
+
+	pub fn integrity_stats(&self) -> IntegrityStats {
+		self.integrity.stats()
+	}
+
+	/// Store unknown instances preserved by the client, see `Unknowns`
+	pub fn report_unknowns(&self, id: u32, groups: Vec<UnknownsGroup>) {
+		self.unknowns.report(id, groups);
+	}
+
+	/// Returns unknown instances preserved by clients, grouped by parent
+	pub fn unknowns(&self) -> Vec<UnknownsSummary> {
+		let tree = self.tree();
+		self.unknowns.summary(|id| tree.get_full_name(id))
+	}
+
+	/// Store results reported by the bootstrap of `argon test`, see `TestRuns`
+	pub fn report_test_results(&self, report: TestReport) -> Result<()> {
+		self.test_runs.report(report)
+	}
+
+	pub fn test_run(&self, run_id: &str) -> Option<TestRun> {
+		self.test_runs.get(run_id)
+	}
+
+	/// Mark the current state of the workspace so it can be restored later, see `UndoLog`
+	pub fn add_marker(&self, name: &str) -> Result<Marker> {
+		undo_log::get(&self.project().workspace_dir).add_marker(name)
+	}
+
+	pub fn markers(&self) -> Vec<Marker> {
+		undo_log::get(&self.project().workspace_dir).markers()
+	}
+
+	pub fn clear_markers(&self) -> Result<()> {
+		undo_log::get(&self.project().workspace_dir).clear()
+	}
+
+	/// Restore the workspace to the state it had when the marker was added,
+	/// returns the number of undone operations. Changes are processed as
+	/// external ones so they are synced to clients
+	pub fn undo_to_marker(&self, name: &str) -> Result<usize> {
+		let (count, events) = {
+			let _tree = self.tree();
+
+			self.vfs.pause();
+			let result = self.vfs.rollback_to_marker(name);
+			self.vfs.resume();
+
+			result?
+		};
+
+		for event in events {
+			self.processor.notify(event);
+		}
+
+		Ok(count)
+	}
+
+	/// Stores contents of the file named `name` in the asset store and creates
+	/// an instance of `class` (inferred from the extension if `None`) at the dot
+	/// separated path that references it, the instance is added through the
+	/// processor like one added in Studio and synced to clients. Name of an
+	/// existing child gets a number appended, blocks while syncing is paused
+	pub fn add_asset(&self, name: &str, contents: &[u8], path: &str, class: Option<AssetClass>) -> Result<AddedAsset> {
+		let class = class
+			.or_else(|| AssetClass::infer(Path::new(name)))
+			.with_context(|| format!("Cannot infer asset class of {}, pass it explicitly", name))?;
+
+		let (parent_path, instance_name) = path
+			.rsplit_once('.')
+			.with_context(|| format!("{} has no parent, use e.g. Workspace.{}", path, path))?;
+
+		let parent = self
+			.tree()
+			.find_by_path(parent_path)
+			.with_context(|| format!("No instance at {}", parent_path))?;
+
+		let store = AssetStore::new(&self.project().assets_dir());
+		let mut mapping = store.mapping()?;
+
+		let key = store.store(name, contents)?;
+		let entry = mapping.entry(key.clone()).or_insert_with(|| AssetEntry {
+			source: name.to_owned(),
+			class,
+			asset_id: None,
+		});
+
+		let mut attributes = Attributes::new();
+		attributes.insert(ASSET_ATTRIBUTE.into(), Variant::String(key.clone()));
+
+		let mut properties = Properties::default();
+		properties.insert(Ustr::from(class.property()), assets::content_value(&entry.url(&key)));
+		properties.insert(Ustr::from("Attributes"), Variant::Attributes(attributes));
+
+		let id = Ref::new();
+		let mut changes = Changes::new();
+
+		changes.additions.push(AddedSnapshot {
+			id,
+			meta: Meta::new(),
+			parent,
+			name: instance_name.to_owned(),
+			class: Ustr::from(&class.to_string()),
+			properties,
+			children: vec![],
+		});
+
+		self.processor.apply(changes)?;
+		store.save_mapping(&mapping)?;
+
+		Ok(AddedAsset {
+			path: self.tree().get_full_name(id).unwrap_or_default(),
+			key,
+			class,
+		})
+	}
+
+	/// Records Roblox asset IDs of stored assets by their keys and replaces
+	/// placeholders of their instances with the IDs through the processor,
+	/// instances that reference something else are left as they are.
+	/// Returns number of updated instances, blocks while syncing is paused
+	pub fn apply_asset_ids(&self, ids: &HashMap<String, u64>) -> Result<usize> {
+		let store = AssetStore::new(&self.project().assets_dir());
+		let mut mapping = store.mapping()?;
+
+		for (key, asset_id) in ids {
+			match mapping.get_mut(key) {
+				Some(entry) => entry.asset_id = Some(*asset_id),
+				None => warn!("Asset {} is not in the asset store", key),
+			}
+		}
+
+		let mut changes = Changes::new();
+		let tree = self.tree();
+
+		for instance in tree.inner().descendants() {
+			let id = instance.referent();
+
+			let Some(Variant::Attributes(attributes)) = instance.properties.get(&Ustr::from("Attributes")) else {
+				continue;
+			};
+
+			let Some(Variant::String(key)) = attributes.get(ASSET_ATTRIBUTE) else {
+				continue;
+			};
+
+			let (Some(entry), true) = (mapping.get(key), ids.contains_key(key)) else {
+				continue;
+			};
+
+			let property = Ustr::from(entry.class.property());
+
+			if instance.properties.get(&property) != Some(&assets::content_value(&assets::placeholder(key))) {
+				continue;
+			}
+
+			let mut properties = match memory::materialize(id, &tree, &self.vfs) {
+				Ok(properties) => properties,
+				Err(err) => {
+					warn!("Failed to replace asset placeholder of {:?}: {}", id, err);
+					continue;
+				}
+			};
+
+			properties.insert(property, assets::content_value(&entry.url(key)));
+
+			let mut update = UpdatedSnapshot::new(id);
+			update.properties = Some(properties);
+
+			changes.update(update);
+		}
+
+		drop(tree);
+
+		let total = changes.total();
+
+		if total > 0 {
+			self.processor.apply(changes)?;
+		}
+
+		store.save_mapping(&mapping)?;
+
+		Ok(total)
+	}
+
+	/// Applies operations of the patch to instances matched by their selectors, instances are
+	/// written one by one like updates from Studio, so files of each of them change together
+	/// and the changes are synced to clients. Failed instances are reported and skipped,
+	/// unless `strict` is set, then the first failure stops the whole run
+	pub fn apply_patch(&self, patch: &Patch, dry_run: bool, strict: bool) -> Result<PatchReport> {
+		let queries = patch.queries()?;
+
+		let mut report = PatchReport {
+			dry_run,
+			operations: vec![],
 		};
-		let meta = tree.get_meta(root.referent()).unwrap();
-
-		Some(
-			Snapshot::new()
-				.with_id(root.referent())
-				.with_name(&root.name)
-				.with_class(&root.class)
-				.with_properties(root.properties.clone())
-				.with_children(walk(root.children(), &tree))
-				.with_meta(meta.clone())
-				.as_new(root.parent()),
-		)
-	}
-
-	/// Build the tree into a file, either XML or binary
-	pub fn build(&self, path: &Path, xml: bool) -> Result<()> {
+
+		for (operation, query) in patch.operations.iter().zip(queries) {
+			let found = self.find(&query, None, usize::MAX)?.instances;
+
+			let mut tree = self.tree();
+			let mut changes = Changes::new();
+			let mut operation_report = OperationReport {
+				matched: found.len(),
+				..OperationReport::default()
+			};
+
+			if !dry_run {
+				self.vfs.pause();
+			}
+
+			for found in found {
+				let Some(class) = tree.get_instance(found.id).map(|instance| instance.class) else {
+					continue;
+				};
+
+				let result = memory::materialize(found.id, &tree, &self.vfs)
+					.and_then(|properties| operation.action.apply(&class, properties))
+					.and_then(|patched| {
+						let Some((properties, property_changes)) = patched else {
+							return Ok(None);
+						};
+
+						if !dry_run {
+							let mut update = UpdatedSnapshot::new(found.id);
+							update.properties = Some(properties);
+
+							processor::write::apply_update(update.clone(), &mut tree, &self.vfs)?;
+							changes.update(update);
+						}
+
+						Ok(Some(property_changes))
+					});
+
+				match result {
+					Ok(Some(property_changes)) => operation_report.instances.push(PatchedInstance {
+						path: found.path,
+						changes: property_changes,
+					}),
+					Ok(None) => {}
+					Err(err) => {
+						operation_report.failures.push(PatchFailure {
+							path: found.path,
+							error: format!("{:#}", err),
+						});
+
+						if strict {
+							break;
+						}
+					}
+				}
+			}
+
+			if !dry_run {
+				let result = processor::write::finish_batch(&mut tree, &self.vfs);
+
+				self.vfs.resume();
+				result?;
+
+				if !changes.is_empty() {
+					if self.queue.clients().is_empty() {
+						self.pending.record(&changes, &tree);
+					}
+
+					self.queue.push_changes(changes, None, &tree, &self.vfs)?;
+				}
+			}
+
+			let failed = !operation_report.failures.is_empty();
+			report.operations.push(operation_report);
+
+			if strict && failed {
+				break;
+			}
+		}
+
+		Ok(report)
+	}
+
+	/// Validate sources of all instances, see `Source::validate`
+	pub fn fsck(&self) -> Vec<(Ref, SourceIssue)> {
+		let tree = self.tree();
+		fsck::validate(tree.root_ref(), true, &tree, &self.vfs)
+	}
+
+	/// Rename script files of all instances to the extension, see `extensions::migrate`
+	pub fn migrate_extensions(&self, extension: ScriptExtension) -> Result<Migration> {
+		let mut tree = self.tree();
+		let root = tree.root_ref();
+
+		extensions::migrate(root, extension, &mut tree, &self.vfs)
+	}
+
+	/// Move instance data of all instances to the storage, see `storage::migrate`
+	pub fn migrate_data_storage(&self, target: DataStorage) -> Result<DataMigration> {
+		let mut tree = self.tree();
+		let root = tree.root_ref();
+
+		storage::migrate(root, target, &mut tree, &self.vfs)
+	}
+
+	/// Search the live tree, see `find::find` for details
+	pub fn find(&self, query: &FindQuery, cursor: Option<&str>, limit: usize) -> Result<FindPage> {
+		find::find(&self.tree, &self.vfs, query, cursor, limit)
+	}
+
+	/// Returns instances whose path starts with or contains `prefix`, see `PathIndex::complete`
+	pub fn complete(&self, prefix: &str, limit: usize) -> Vec<Completion> {
+		let mut tree = self.tree();
+
+		tree.refresh_path_index();
+		tree.path_index().complete(prefix, limit)
+	}
+
+	/// Returns metadata of all classes with icons configured by the project
+	pub fn class_metadata(&self) -> ClassMetadataTable {
+		class_meta::table(&lock!(self.project).class_icons)
+	}
+
+	/// Returns class metadata of the instances, used with `enrich=class-meta`
+	pub fn instance_metadata(&self, ids: impl IntoIterator<Item = Ref>) -> HashMap<Ref, InstanceMetadata> {
+		let rules = lock!(self.project).class_icons.clone();
+		let tree = self.tree();
+
+		ids.into_iter()
+			.filter_map(|id| {
+				let instance = tree.get_instance(id)?;
+				Some((id, class_meta::get_instance(&instance.class, &instance.name, &rules)))
+			})
+			.collect()
+	}
+
+	/// Build the tree into a file, either XML or binary, reproducible builds
+	/// write instances in the same order no matter the order they were added in
+	pub fn build(&self, path: &Path, xml: bool, reproducible: bool) -> Result<()> {
 		let writer = BufWriter::new(File::create(path)?);
-		let tree = lock!(&self.tree);
+		let mut tree = lock!(&self.tree);
+		let low_memory = self.project().is_low_memory();
 
-		let root_refs = if self.project().is_place() {
-			tree.place_root_refs().to_vec()
+		// Serializers read the whole DOM directly
+		if low_memory {
+			memory::restore_all(&mut tree, &self.vfs);
+		}
+
+		// Serializers assign referents and write instances in tree order
+		if reproducible {
+			tree.sort_children();
+		}
+
+		let root_refs = top_level_refs(&tree, self.project().is_place());
+
+		// Hash is embedded only for the time of serialization so the tree stays unchanged
+		let hash = tree_hash::hash_dom(tree.inner(), &root_refs);
+		let attributes = root_refs.first().map(|&id| (id, embed_hash(&mut tree, id, hash)));
+
+		let result = if xml {
+			rbx_xml::to_writer_default(writer, tree.inner(), &root_refs).map_err(anyhow::Error::from)
 		} else {
-			vec![tree.root_ref()]
+			rbx_binary::to_writer(writer, tree.inner(), &root_refs).map_err(anyhow::Error::from)
 		};
 
-		if xml {
-			rbx_xml::to_writer_default(writer, tree.inner(), &root_refs)?;
-		} else {
-			rbx_binary::to_writer(writer, tree.inner(), &root_refs)?;
+		if let Some((id, attributes)) = attributes {
+			restore_attributes(&mut tree, id, attributes);
+		}
+
+		result?;
+
+		if low_memory {
+			tree.evict_all();
 		}
 
 		stats::projects_built(1);
@@ -173,24 +1459,86 @@ impl Core {
 		Ok(())
 	}
 
+	/// Starts recording inputs of the processor to the trace file, see `TraceRecorder`
+	pub fn record_trace(&self, path: &Path, redact: bool) -> Result<()> {
+		// Tree stays locked so no input is processed before the header is written
+		let mut tree = lock!(self.tree);
+		let hash = self.hash_tree(&mut tree);
+
+		let recorder = TraceRecorder::create(path, &self.project(), redact, hash)?;
+		self.processor.set_recorder(Some(Arc::new(recorder)));
+
+		Ok(())
+	}
+
+	/// Writes the end state of the recorded trace and stops recording
+	pub fn finish_trace(&self) -> Result<()> {
+		let Some(recorder) = self.processor.recorder() else {
+			return Ok(());
+		};
+
+		self.processor.set_recorder(None);
+		recorder.finish(self.tree_hash())
+	}
+
+	/// Canonical hash of the whole tree, cached until the tree changes
+	pub fn tree_hash(&self) -> TreeHash {
+		let mut tree = lock!(&self.tree);
+		self.hash_tree(&mut tree)
+	}
+
+	fn hash_tree(&self, tree: &mut Tree) -> TreeHash {
+		let mut cached = lock!(self.tree_hash);
+
+		if let Some((generation, hash)) = *cached {
+			if generation == tree.generation() {
+				return hash;
+			}
+		}
+
+		let hash = compute_tree_hash(tree, &self.vfs, &self.project());
+
+		*cached = Some((tree.generation(), hash));
+
+		hash
+	}
+
 	/// Write sourcemap of the tree
 	pub fn sourcemap(&self, path: Option<PathBuf>, non_scripts: bool) -> Result<()> {
 		let tree = lock!(&self.tree);
 		let dom = tree.inner();
 
-		let workspace_dir = &self.project().workspace_dir;
+		let (workspace_dir, synthetic) = {
+			let project = self.project();
+			(
+				project.workspace_dir.clone(),
+				project.from_stdin.then(|| project.path.clone()),
+			)
+		};
 
-		fn walk(tree: &Tree, id: Ref, workspace_dir: &Path, non_scripts: bool) -> Option<SourcemapNode> {
+		// Parents are always visited before their children, so walking
+		// the order backwards completes children first without recursion
+		let mut order = vec![];
+		let mut stack = vec![dom.root_ref()];
+
+		while let Some(id) = stack.pop() {
+			order.push(id);
+			stack.extend(tree.get_instance(id).unwrap().children());
+		}
+
+		let mut completed: HashMap<Ref, SourcemapNode> = HashMap::new();
+
+		for id in order.into_iter().rev() {
 			let instance = tree.get_instance(id).unwrap();
 
 			let children: Vec<SourcemapNode> = instance
 				.children()
 				.iter()
-				.filter_map(|&child_id| walk(tree, child_id, workspace_dir, non_scripts))
+				.filter_map(|child_id| completed.remove(child_id))
 				.collect();
 
 			if children.is_empty() && (!non_scripts && !util::is_script(&instance.class)) {
-				return None;
+				continue;
 			}
 
 			let file_paths = tree.get_meta(id).map_or(vec![], |meta| {
@@ -198,8 +1546,14 @@ impl Core {
 					.relevant()
 					.iter()
 					.filter_map(|entry| match entry {
+						// Project read from stdin does not exist on disk
+						SourceEntry::File(path) | SourceEntry::Project(path)
+							if Some(path.as_path()) == synthetic.as_deref() =>
+						{
+							None
+						}
 						SourceEntry::File(path) | SourceEntry::Data(path) | SourceEntry::Project(path) => {
-							if let Ok(path) = path.strip_prefix(workspace_dir) {
+							if let Ok(path) = path.strip_prefix(&workspace_dir) {
 								Some(path.to_owned())
 							} else {
 								Some(path.to_owned())
@@ -210,15 +1564,18 @@ impl Core {
 					.collect()
 			});
 
-			Some(SourcemapNode {
-				name: instance.name.clone(),
-				class_name: instance.class,
-				file_paths,
-				children,
-			})
+			completed.insert(
+				id,
+				SourcemapNode {
+					name: instance.name.clone(),
+					class_name: instance.class,
+					file_paths,
+					children,
+				},
+			);
 		}
 
-		let sourcemap = walk(&tree, dom.root_ref(), workspace_dir, non_scripts);
+		let sourcemap = completed.remove(&dom.root_ref());
 
 		if let Some(path) = path {
 			let writer = BufWriter::new(File::create(path)?);
@@ -230,23 +1587,155 @@ impl Core {
 		Ok(())
 	}
 
-	pub fn open(&self, instance: Ref) -> Result<()> {
-		let tree = self.tree();
+	/// Generate Luau type definitions of the tree
+	pub fn typegen(&self, options: &TypegenOptions) -> Result<String> {
+		typegen::generate(&lock!(&self.tree), options)
+	}
 
-		let mut sources = if let Some(meta) = tree.get_meta(instance) {
-			meta.source.relevant().to_owned()
-		} else {
-			vec![]
+	/// Opens the source file of the instance in the editor, see `Source::get_editable`.
+	/// Line is only used for instance files as clients only know lines of scripts
+	pub fn open(&self, instance: Ref, line: Option<u32>) -> Result<PathBuf, OpenError> {
+		let (file, line) = {
+			let tree = self.tree();
+			let meta = tree.get_meta(instance).ok_or(OpenError::NoInstance)?;
+			let file = meta.source.get_editable().ok_or(OpenError::NoSource)?;
+
+			let is_instance_file = meta.source.get_file().is_some_and(|entry| entry.path() == file);
+
+			(file.to_owned(), line.filter(|_| is_instance_file))
 		};
 
-		sources.sort_by_key(|source| source.index());
+		editor::open(&file, line)?;
 
-		if let Some(source) = sources.first() {
-			open::that(source.path())?;
-			Ok(())
-		} else {
-			bail!("No matching file was found")
+		Ok(file)
+	}
+}
+
+/// Snapshot of the instance with all of its descendants
+fn snapshot_subtree(instance: Ref, include_ephemeral: bool, tree: &Tree, vfs: &Vfs) -> Option<AddedSnapshot> {
+	let root = tree.get_instance(instance)?;
+	let properties = memory::materialize_lossy(instance, tree, vfs)?;
+
+	// Parents are always visited before their children, so walking
+	// the order backwards completes children first without recursion
+	let mut order = vec![];
+	let mut stack = root.children().to_vec();
+
+	while let Some(id) = stack.pop() {
+		if tree.get_meta(id).unwrap().source.is_ephemeral() && !include_ephemeral {
+			continue;
 		}
+
+		order.push(id);
+		stack.extend(tree.get_instance(id).unwrap().children());
+	}
+
+	let mut completed: HashMap<Ref, Snapshot> = HashMap::new();
+
+	let take_children = |id: Ref, completed: &mut HashMap<Ref, Snapshot>| -> Vec<Snapshot> {
+		tree.get_instance(id)
+			.unwrap()
+			.children()
+			.iter()
+			.filter_map(|child| completed.remove(child))
+			.collect()
+	};
+
+	for id in order.into_iter().rev() {
+		let instance = tree.get_instance(id).unwrap();
+
+		let snapshot = Snapshot::new()
+			.with_id(id)
+			.with_name(&instance.name)
+			.with_class(instance.class)
+			.with_properties(memory::materialize_lossy(id, tree, vfs).unwrap())
+			.with_children(take_children(id, &mut completed))
+			.with_meta(tree.get_meta(id).unwrap().clone());
+
+		completed.insert(id, snapshot);
+	}
+
+	Some(AddedSnapshot {
+		id: instance,
+		meta: tree.get_meta(instance).unwrap().clone(),
+		parent: root.parent(),
+		name: root.name.clone(),
+		class: root.class,
+		properties,
+		children: take_children(instance, &mut completed),
+	})
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotPage {
+	/// Tree generation the page was created from
+	pub generation: u64,
+	/// Whether the tree changed since the previous page
+	pub restart: bool,
+	pub instances: Vec<AddedSnapshot>,
+	/// Cursor of the next page, `None` if this is the last one
+	pub cursor: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub class_meta: Option<HashMap<Ref, InstanceMetadata>>,
+	/// Hash of the whole tree, included in the last page only
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub tree_hash: Option<String>,
+}
+
+/// Adds tree hash to attributes of the instance, returns its previous attributes
+/// Instances that get serialized when building the project
+/// Hashes the whole tree without caching, evicted values are restored first
+pub(crate) fn compute_tree_hash(tree: &mut Tree, vfs: &Vfs, project: &Project) -> TreeHash {
+	let low_memory = project.is_low_memory();
+
+	if low_memory {
+		memory::restore_all(tree, vfs);
+	}
+
+	let hash = tree_hash::hash_dom(tree.inner(), &top_level_refs(tree, project.is_place()));
+
+	if low_memory {
+		tree.evict_all();
+	}
+
+	hash
+}
+
+fn top_level_refs(tree: &Tree, place: bool) -> Vec<Ref> {
+	if place {
+		tree.place_root_refs().to_vec()
+	} else {
+		vec![tree.root_ref()]
+	}
+}
+
+fn embed_hash(tree: &mut Tree, id: Ref, hash: TreeHash) -> Option<Variant> {
+	let instance = tree.get_instance_mut(id)?;
+	let previous = instance.properties.get(&Ustr::from("Attributes")).cloned();
+
+	let mut attributes = match &previous {
+		Some(Variant::Attributes(attributes)) => attributes.clone(),
+		_ => Attributes::new(),
+	};
+
+	attributes.insert(
+		tree_hash::TREE_HASH_ATTRIBUTE.into(),
+		Variant::String(hash.to_embedded()),
+	);
+	instance
+		.properties
+		.insert(Ustr::from("Attributes"), Variant::Attributes(attributes));
+
+	previous
+}
+
+fn restore_attributes(tree: &mut Tree, id: Ref, attributes: Option<Variant>) {
+	if let Some(instance) = tree.get_instance_mut(id) {
+		match attributes {
+			Some(attributes) => instance.properties.insert(Ustr::from("Attributes"), attributes),
+			None => instance.properties.remove(&Ustr::from("Attributes")),
+		};
 	}
 }
 
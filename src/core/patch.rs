@@ -0,0 +1,202 @@
+use anyhow::{bail, Context, Result};
+use rbx_dom_weak::{
+	types::{Attributes, Tags, Variant},
+	Ustr,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, fs, path::Path};
+
+use super::find::{FindQuery, FindRequest};
+use crate::{resolution::UnresolvedValue, Properties};
+
+/// List of operations read from a patch file, applied in order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Patch {
+	pub operations: Vec<PatchOperation>,
+}
+
+impl Patch {
+	pub fn load(path: &Path) -> Result<Self> {
+		let contents = fs::read_to_string(path)?;
+
+		serde_json::from_str(&contents).with_context(|| format!("Failed to parse patch file {}", path.display()))
+	}
+
+	/// Parses selectors of all operations, so invalid ones fail the whole patch before anything is written
+	pub fn queries(&self) -> Result<Vec<FindQuery>> {
+		self.operations
+			.iter()
+			.enumerate()
+			.map(|(index, operation)| {
+				FindRequest::parse(&operation.select)
+					.and_then(|request| FindQuery::new(&request))
+					.with_context(|| format!("Invalid selector of operation {}", index + 1))
+			})
+			.collect()
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchOperation {
+	/// Query in the `argon find` syntax, e.g. `ClassName~BasePart Path~Map/* Material=256`
+	pub select: String,
+	#[serde(flatten)]
+	pub action: PatchAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PatchAction {
+	/// Sets properties to values written the same way as in data files
+	Set(BTreeMap<String, UnresolvedValue>),
+	Delete(Vec<Ustr>),
+	RenameAttribute {
+		from: String,
+		to: String,
+	},
+	AddTag(String),
+}
+
+impl PatchAction {
+	/// Returns new properties of the instance and what changed,
+	/// `None` if the action does not change anything
+	pub fn apply(&self, class: &str, mut properties: Properties) -> Result<Option<(Properties, Vec<PropertyChange>)>> {
+		let mut changes = vec![];
+
+		match self {
+			PatchAction::Set(values) => {
+				for (property, value) in values {
+					let value = value.clone().resolve(class, property, None)?;
+					let previous = properties.insert(Ustr::from(property), value.clone());
+
+					if previous.as_ref() != Some(&value) {
+						changes.push(PropertyChange::new(class, property, previous, Some(value)));
+					}
+				}
+			}
+			PatchAction::Delete(names) => {
+				for property in names {
+					if let Some(previous) = properties.remove(property) {
+						changes.push(PropertyChange::new(class, property, Some(previous), None));
+					}
+				}
+			}
+			PatchAction::RenameAttribute { from, to } => {
+				let mut attributes = match properties.get(&Ustr::from("Attributes")) {
+					Some(Variant::Attributes(attributes)) => attributes.clone(),
+					_ => Attributes::new(),
+				};
+
+				if let Some(value) = attributes.remove(from.as_str()) {
+					if attributes.get(to.as_str()).is_some() {
+						bail!("Attribute {} already exists", to);
+					}
+
+					changes.push(PropertyChange::new(
+						class,
+						&format!("Attributes.{}", from),
+						Some(value.clone()),
+						None,
+					));
+					changes.push(PropertyChange::new(
+						class,
+						&format!("Attributes.{}", to),
+						None,
+						Some(value.clone()),
+					));
+
+					attributes.insert(to.to_owned(), value);
+					properties.insert(Ustr::from("Attributes"), attributes.into());
+				}
+			}
+			PatchAction::AddTag(tag) => {
+				let previous = match properties.get(&Ustr::from("Tags")) {
+					Some(Variant::Tags(tags)) => tags.clone(),
+					_ => Tags::new(),
+				};
+
+				if !previous.iter().any(|existing| existing == tag) {
+					let mut tags = previous.clone();
+					tags.push(tag);
+
+					changes.push(PropertyChange::new(
+						class,
+						"Tags",
+						(!previous.is_empty()).then(|| previous.into()),
+						Some(tags.clone().into()),
+					));
+
+					properties.insert(Ustr::from("Tags"), tags.into());
+				}
+			}
+		}
+
+		Ok((!changes.is_empty()).then_some((properties, changes)))
+	}
+}
+
+/// Single property change, values are in the data file format
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropertyChange {
+	pub property: String,
+	pub from: Option<Value>,
+	pub to: Option<Value>,
+}
+
+impl PropertyChange {
+	fn new(class: &str, property: &str, from: Option<Variant>, to: Option<Variant>) -> Self {
+		let value = |variant: Variant| {
+			serde_json::to_value(UnresolvedValue::from_variant(variant, class, property)).unwrap_or_default()
+		};
+
+		Self {
+			property: property.to_owned(),
+			from: from.map(value),
+			to: to.map(value),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchedInstance {
+	/// Instance path separated with `/`, like in `argon find` results
+	pub path: String,
+	pub changes: Vec<PropertyChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchFailure {
+	pub path: String,
+	pub error: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationReport {
+	/// Number of instances matched by the selector
+	pub matched: usize,
+	/// Instances that were (or would be, with dry run) changed
+	pub instances: Vec<PatchedInstance>,
+	pub failures: Vec<PatchFailure>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchReport {
+	pub dry_run: bool,
+	/// Reports of operations in the order of the patch, operations
+	/// after the first failure are missing when the run is strict
+	pub operations: Vec<OperationReport>,
+}
+
+impl PatchReport {
+	pub fn failures(&self) -> usize {
+		self.operations.iter().map(|operation| operation.failures.len()).sum()
+	}
+}
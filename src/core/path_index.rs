@@ -0,0 +1,212 @@
+use rbx_dom_weak::{types::Ref, Instance, Ustr, WeakDom};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Debug, Clone)]
+struct Entry {
+	name: Ustr,
+	/// Lowercase name, used for case-insensitive lookups
+	key: Ustr,
+	class: Ustr,
+	parent: Ref,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Completion {
+	pub id: Ref,
+	/// Instance path separated with `/`, without the root
+	pub path: String,
+	pub class: Ustr,
+}
+
+/// Index of instance paths used for completion and path lookups, every
+/// instance only keeps its own interned name segment and a link to its
+/// parent so renames are constant time and full paths are never stored
+#[derive(Debug, Clone)]
+pub struct PathIndex {
+	entries: HashMap<Ref, Entry>,
+	by_key: BTreeMap<Ustr, HashSet<Ref>>,
+	/// Instances that could have been renamed since the last refresh
+	stale: HashSet<Ref>,
+	root: Ref,
+}
+
+impl PathIndex {
+	pub fn new(root: Ref) -> Self {
+		Self {
+			entries: HashMap::new(),
+			by_key: BTreeMap::new(),
+			stale: HashSet::new(),
+			root,
+		}
+	}
+
+	pub fn insert(&mut self, instance: &Instance) {
+		let id = instance.referent();
+		let key = Ustr::from(&instance.name.as_str().to_lowercase());
+
+		if let Some(entry) = self.entries.get(&id) {
+			if entry.key == key {
+				self.entries.insert(id, Self::entry(instance, key));
+				return;
+			}
+
+			self.unlink(id);
+		}
+
+		self.by_key.entry(key).or_default().insert(id);
+		self.entries.insert(id, Self::entry(instance, key));
+	}
+
+	pub fn remove(&mut self, id: Ref) {
+		self.unlink(id);
+		self.entries.remove(&id);
+		self.stale.remove(&id);
+	}
+
+	/// Marks instance to be re-read on the next refresh
+	pub fn mark(&mut self, id: Ref) {
+		self.stale.insert(id);
+	}
+
+	/// Re-reads instances marked since the last refresh
+	pub fn refresh(&mut self, dom: &WeakDom) {
+		for id in self.stale.drain().collect::<Vec<_>>() {
+			match dom.get_by_ref(id) {
+				Some(instance) => self.insert(instance),
+				None => self.remove(id),
+			}
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// Returns instance path separated with `/`, starting with the root
+	pub fn path(&self, id: Ref) -> Option<String> {
+		let mut names = self.segments(id)?;
+
+		if let Some(root) = self.entries.get(&self.root) {
+			names.push(root.name.as_str());
+		}
+
+		names.reverse();
+
+		Some(names.join("/"))
+	}
+
+	/// Returns instances whose path starts with `prefix` (case-insensitive, relative to
+	/// the root), ones where it only matches later in the path are ranked after them,
+	/// both groups are ordered by depth and then by path so the ranking is stable
+	pub fn complete(&self, prefix: &str, limit: usize) -> Vec<Completion> {
+		let prefix = prefix.trim_start_matches('/').to_lowercase();
+		let mut segments: Vec<&str> = prefix.split('/').collect();
+		let last = segments.pop().unwrap_or_default();
+
+		let mut matches = vec![];
+
+		for (key, ids) in self.by_key.range(Ustr::from(last)..) {
+			if !key.starts_with(last) {
+				break;
+			}
+
+			for id in ids {
+				if *id == self.root {
+					continue;
+				}
+
+				if let Some(rank) = self.rank(*id, &segments) {
+					let path = self.relative_path(*id);
+					let depth = path.matches('/').count();
+
+					matches.push((rank, depth, path.to_lowercase(), path, *id));
+				}
+			}
+		}
+
+		matches.sort_by(|a, b| {
+			(a.0, a.1, &a.2, &a.3)
+				.cmp(&(b.0, b.1, &b.2, &b.3))
+				.then_with(|| a.4.to_string().cmp(&b.4.to_string()))
+		});
+
+		matches
+			.into_iter()
+			.take(limit)
+			.map(|(_, _, _, path, id)| Completion {
+				id,
+				path,
+				class: self.entries[&id].class,
+			})
+			.collect()
+	}
+
+	/// Returns `0` if the ancestors match `segments` all the way to the root,
+	/// `1` if they match somewhere deeper and `None` if they don't match
+	fn rank(&self, id: Ref, segments: &[&str]) -> Option<u8> {
+		let mut parent = self.entries.get(&id)?.parent;
+
+		for segment in segments.iter().rev() {
+			let entry = self.entries.get(&parent).filter(|_| parent != self.root)?;
+
+			if entry.key != *segment {
+				return None;
+			}
+
+			parent = entry.parent;
+		}
+
+		Some(if parent == self.root { 0 } else { 1 })
+	}
+
+	fn relative_path(&self, id: Ref) -> String {
+		let mut names = self.segments(id).unwrap_or_default();
+
+		names.reverse();
+		names.join("/")
+	}
+
+	/// Returns names of the instance and its ancestors, without the root
+	fn segments(&self, id: Ref) -> Option<Vec<&str>> {
+		let mut names = vec![];
+		let mut current = id;
+
+		while current != self.root {
+			let entry = self.entries.get(&current)?;
+
+			names.push(entry.name.as_str());
+			current = entry.parent;
+		}
+
+		Some(names)
+	}
+
+	fn unlink(&mut self, id: Ref) {
+		let Some(entry) = self.entries.get(&id) else {
+			return;
+		};
+
+		if let Some(ids) = self.by_key.get_mut(&entry.key) {
+			ids.remove(&id);
+
+			if ids.is_empty() {
+				self.by_key.remove(&entry.key);
+			}
+		}
+	}
+
+	fn entry(instance: &Instance, key: Ustr) -> Entry {
+		Entry {
+			name: Ustr::from(instance.name.as_str()),
+			key,
+			class: instance.class,
+			parent: instance.parent(),
+		}
+	}
+}
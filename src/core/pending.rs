@@ -0,0 +1,194 @@
+use rbx_dom_weak::types::Ref;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+use super::{changes::Changes, tree::Tree};
+use crate::lock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PendingKind {
+	Added,
+	Updated,
+	Removed,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+	kind: PendingKind,
+	/// Full name of the instance, the last known one for removed instances
+	path: Option<String>,
+	since: Instant,
+}
+
+/// Instance that changed on disk without any client receiving the change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingInstance {
+	pub id: Ref,
+	pub path: Option<String>,
+	pub kind: PendingKind,
+	/// Seconds since the instance first became dirty
+	pub age: u64,
+}
+
+/// Instances that diverged from what clients have seen, collected while no
+/// client is connected and used to resync only them once one reconnects
+#[derive(Debug, Default)]
+pub struct Pending {
+	entries: Mutex<HashMap<Ref, Entry>>,
+}
+
+impl Pending {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Marks instances of the changes as dirty, changes have
+	/// to be already applied to the tree so paths are current
+	pub fn record(&self, changes: &Changes, tree: &Tree) {
+		let mut entries = lock!(self.entries);
+		let now = Instant::now();
+
+		for snapshot in &changes.additions {
+			mark(
+				&mut entries,
+				snapshot.id,
+				PendingKind::Added,
+				tree.get_full_name(snapshot.id),
+				now,
+			);
+		}
+
+		for snapshot in &changes.updates {
+			mark(
+				&mut entries,
+				snapshot.id,
+				PendingKind::Updated,
+				tree.get_full_name(snapshot.id),
+				now,
+			);
+		}
+
+		for id in &changes.removals {
+			// Instance was added and removed before any client saw it
+			if entries.get(id).is_some_and(|entry| entry.kind == PendingKind::Added) {
+				entries.remove(id);
+				continue;
+			}
+
+			mark(&mut entries, *id, PendingKind::Removed, None, now);
+		}
+	}
+
+	/// Marks instance as dirty unless it already is, used for
+	/// instances restored from checkpoints of the previous server
+	pub fn restore(&self, id: Ref, kind: PendingKind, path: Option<String>) {
+		lock!(self.entries).entry(id).or_insert(Entry {
+			kind,
+			path,
+			since: Instant::now(),
+		});
+	}
+
+	/// Returns dirty instances, oldest first
+	pub fn list(&self) -> Vec<PendingInstance> {
+		let entries = lock!(self.entries);
+
+		let mut instances: Vec<_> = entries
+			.iter()
+			.map(|(id, entry)| PendingInstance {
+				id: *id,
+				path: entry.path.clone(),
+				kind: entry.kind,
+				age: entry.since.elapsed().as_secs(),
+			})
+			.collect();
+
+		instances.sort_by(|a, b| b.age.cmp(&a.age).then_with(|| a.path.cmp(&b.path)));
+		instances
+	}
+
+	/// Removes all dirty instances and returns them, instances updated
+	/// inside of added subtrees are skipped as additions include them
+	pub fn take(&self, tree: &Tree) -> Vec<(Ref, PendingKind)> {
+		let entries: HashMap<Ref, Entry> = lock!(self.entries).drain().collect();
+
+		let is_added = |id: &Ref| entries.get(id).is_some_and(|entry| entry.kind == PendingKind::Added);
+
+		let mut taken: Vec<_> = entries
+			.iter()
+			.filter(|(id, entry)| {
+				if entry.kind == PendingKind::Removed {
+					return true;
+				}
+
+				let mut parent = tree.get_instance(**id).map(|instance| instance.parent());
+
+				while let Some(id) = parent.filter(|parent| parent.is_some()) {
+					if is_added(&id) {
+						return false;
+					}
+
+					parent = tree.get_instance(id).map(|instance| instance.parent());
+				}
+
+				true
+			})
+			.map(|(id, entry)| (*id, entry.kind, entry.since))
+			.collect();
+
+		taken.sort_by_key(|(_, _, since)| *since);
+		taken.into_iter().map(|(id, kind, _)| (id, kind)).collect()
+	}
+
+	pub fn clear(&self) {
+		lock!(self.entries).clear();
+	}
+
+	pub fn len(&self) -> usize {
+		lock!(self.entries).len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		lock!(self.entries).is_empty()
+	}
+
+	/// Time since the oldest dirty instance changed
+	pub fn oldest(&self) -> Option<Duration> {
+		lock!(self.entries).values().map(|entry| entry.since.elapsed()).max()
+	}
+}
+
+fn mark(entries: &mut HashMap<Ref, Entry>, id: Ref, kind: PendingKind, path: Option<String>, now: Instant) {
+	match entries.get_mut(&id) {
+		Some(entry) => {
+			entry.kind = match (entry.kind, kind) {
+				// Client has never seen the instance so it is still an addition
+				(PendingKind::Added, PendingKind::Updated) => PendingKind::Added,
+				(_, kind) => kind,
+			};
+
+			if path.is_some() {
+				entry.path = path;
+			}
+		}
+		None => {
+			entries.insert(id, Entry { kind, path, since: now });
+		}
+	}
+}
+
+/// Formats age in seconds using its largest unit, e.g. `4m`
+pub fn format_age(age: u64) -> String {
+	match age {
+		0..=59 => format!("{}s", age),
+		60..=3599 => format!("{}m", age / 60),
+		3600..=86399 => format!("{}h", age / 3600),
+		_ => format!("{}d", age / 86400),
+	}
+}
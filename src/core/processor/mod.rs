@@ -1,25 +1,55 @@
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use colored::Colorize;
 use crossbeam_channel::{select, Sender};
-use log::{debug, error, info, trace, warn};
+use log::{debug, error, trace, warn};
+use rbx_dom_weak::types::Ref;
 use serde::Deserialize;
 use std::{
-	sync::{Arc, Mutex},
+	mem,
+	path::Path,
+	sync::{
+		atomic::{AtomicBool, AtomicU64, Ordering},
+		Arc, Mutex,
+	},
 	thread::Builder,
+	time::{Duration, Instant},
 };
 
-use super::{changes::Changes, queue::Queue, tree::Tree};
+use self::{
+	origin::Origin,
+	pause::{Backlog, PauseState, PauseStatus},
+	storm::StormDetector,
+};
+use super::{
+	changes::Changes,
+	events::{ChangeSummary, EventKind, EventLog},
+	helpers::{collisions::Collision, fsck, mirrors},
+	lifecycle::{CoreEvent, Lifecycle, ProjectChanges, SyncbackResult},
+	meta::ReadOnlyRejection,
+	pending::Pending,
+	property_reports,
+	queue::Queue,
+	trace::{TraceInput, TraceRecorder},
+	tree::Tree,
+};
 use crate::{
-	argon_error,
+	argon_info, argon_warn, assets, backups,
 	config::Config,
 	constants::BLACKLISTED_PATHS,
+	ext::PathExt,
+	history::{self, HistoryEntry, Operation},
 	lock, logger,
-	project::{Project, ProjectDetails},
-	server, stats,
+	middleware::{manifest, storage, InitConflict},
+	project::{self, Project, ProjectDetails},
+	server::{self, protocol::Capability},
+	stats, t,
 	vfs::{Vfs, VfsEvent},
 };
 
+pub mod origin;
+pub mod pause;
 pub mod read;
+pub mod storm;
 pub mod write;
 
 #[derive(Debug, Deserialize)]
@@ -29,49 +59,274 @@ pub struct WriteRequest {
 	pub client_id: u32,
 }
 
+/// Changes made by Argon itself with the sender of their result, see `Processor::apply`
+type LocalChanges = (Changes, Sender<Result<()>>);
+
 pub struct Processor {
 	writer: Sender<WriteRequest>,
+	local: Sender<LocalChanges>,
+	notifier: Sender<VfsEvent>,
+	/// Whether changes of a client are being applied
+	busy: Arc<AtomicBool>,
+	/// Number of events and write requests processed so far
+	processed: Arc<AtomicU64>,
+	recorder: Arc<Mutex<Option<Arc<TraceRecorder>>>>,
+	pause: Arc<Mutex<PauseState>>,
+	/// Wakes the processor up when syncing is paused or resumed
+	control: Sender<()>,
 }
 
 impl Processor {
-	pub fn new(queue: Arc<Queue>, tree: Arc<Mutex<Tree>>, vfs: Arc<Vfs>, project: Arc<Mutex<Project>>) -> Self {
+	pub fn new(
+		queue: Arc<Queue>,
+		tree: Arc<Mutex<Tree>>,
+		vfs: Arc<Vfs>,
+		project: Arc<Mutex<Project>>,
+		events: Arc<EventLog>,
+		pending: Arc<Pending>,
+		lifecycle: Lifecycle,
+	) -> Self {
+		let recorder = Arc::new(Mutex::new(None));
+
 		let handler = Arc::new(Handler {
 			queue,
 			tree,
 			vfs: vfs.clone(),
 			project,
+			events,
+			pending,
+			lifecycle: lifecycle.clone(),
+			recorder: recorder.clone(),
 		});
 
 		let handler = handler.clone();
 		let (sender, receiver) = crossbeam_channel::unbounded();
+		let (notifier, notify_receiver) = crossbeam_channel::unbounded();
+		let (local, local_receiver) = crossbeam_channel::unbounded::<LocalChanges>();
+		let (control, control_receiver) = crossbeam_channel::unbounded();
+
+		let busy = Arc::new(AtomicBool::new(false));
+		let processed = Arc::new(AtomicU64::new(0));
+
+		let pause = Arc::new(Mutex::new(PauseState::default()));
+
+		let thread_busy = busy.clone();
+		let thread_processed = processed.clone();
+		let thread_pause = pause.clone();
 
 		Builder::new()
 			.name("processor".to_owned())
-			.spawn(move || -> Result<()> {
+			.spawn(move || {
 				let vfs_receiver = vfs.receiver();
 				let client_receiver = receiver;
+				let mut detector = StormDetector::new();
+				let mut backlog = Backlog::default();
 
-				loop {
-					select! {
-						recv(vfs_receiver) -> event => {
-							handler.on_vfs_event(event?);
+				// Channels of the processor are disconnected when it is dropped, that
+				// stops the thread normally, only watcher errors are reported as fatal
+				let result = || -> Result<()> {
+					loop {
+						let until = {
+							let pause = lock!(thread_pause);
+							pause.paused.then_some(pause.until)
+						};
+
+						// Only watcher events are taken while paused, write requests wait in their channel
+						if let Some(until) = until {
+							let timer = until.map_or_else(crossbeam_channel::never, crossbeam_channel::at);
+
+							select! {
+								recv(vfs_receiver) -> event => backlog.hold(event?, Config::new().max_paused_events),
+								recv(notify_receiver) -> event => {
+									let Ok(event) = event else { break };
+									backlog.hold(event, Config::new().max_paused_events);
+								}
+								recv(control_receiver) -> control => {
+									let Ok(()) = control else { break };
+									handler.publish_pause(&thread_pause);
+								}
+								recv(timer) -> _ => {
+									lock!(thread_pause).paused = false;
+									argon_info!("{}", t!("processor.auto_resumed"));
+									handler.publish_pause(&thread_pause);
+								}
+							}
+
+							lock!(thread_pause).backlog = backlog.len();
+							continue;
 						}
-						recv(client_receiver) -> request => {
-							vfs.pause();
-							handler.on_client_event(request?);
-							vfs.resume();
+
+						if !backlog.is_empty() {
+							let (events, overflowed) = backlog.take();
+
+							handler.on_resume(events, overflowed);
+							lock!(thread_pause).backlog = 0;
+							thread_processed.fetch_add(1, Ordering::Relaxed);
 						}
+
+						select! {
+							recv(vfs_receiver) -> event => {
+								let event = event?;
+
+								// Every traced input needs its own tree hash, so storms are not collapsed then.
+								// Write requests wait in their channel until the rescan is done
+								if handler.recorder().is_none() && detector.record(Instant::now(), vfs_receiver.len()) {
+									handler.on_storm(storm::collect(event, &vfs_receiver));
+									thread_processed.fetch_add(1, Ordering::Relaxed);
+									continue;
+								}
+
+								let captured = handler.capture(|recorder| recorder.capture_event(&event));
+
+								handler.on_vfs_event(event);
+								handler.record(captured);
+							}
+							recv(client_receiver) -> request => {
+								let Ok(request) = request else { break };
+								let captured = handler.capture(|recorder| {
+									recorder.capture_request(&request, &lock!(handler.tree))
+								});
+
+								thread_busy.store(true, Ordering::Relaxed);
+								vfs.pause();
+								handler.on_client_event(request);
+								vfs.resume();
+								thread_busy.store(false, Ordering::Relaxed);
+
+								handler.record(captured);
+							}
+							recv(local_receiver) -> request => {
+								let Ok((changes, reply)) = request else { break };
+
+								vfs.pause();
+								let result = handler.on_local_changes(changes);
+								vfs.resume();
+
+								reply.send(result).ok();
+							}
+							recv(notify_receiver) -> event => {
+								let Ok(event) = event else { break };
+								let captured = handler.capture(|recorder| recorder.capture_event(&event));
+
+								handler.on_vfs_event(event);
+								handler.record(captured);
+							}
+							recv(control_receiver) -> control => {
+								let Ok(()) = control else { break };
+								handler.publish_pause(&thread_pause);
+								continue;
+							}
+						}
+
+						thread_processed.fetch_add(1, Ordering::Relaxed);
 					}
+
+					trace!("Processor stopped as it was dropped");
+
+					Ok(())
+				}();
+
+				if let Err(err) = result {
+					error!("Processor stopped: {}", err);
+
+					lifecycle.emit(CoreEvent::Fatal {
+						message: format!("Processor stopped: {}", err),
+					});
 				}
 			})
 			.unwrap();
 
-		Self { writer: sender }
+		Self {
+			writer: sender,
+			local,
+			notifier,
+			busy,
+			processed,
+			recorder,
+			pause,
+			control,
+		}
 	}
 
 	pub fn write(&self, request: WriteRequest) {
 		self.writer.send(request).unwrap();
 	}
+
+	/// Applies changes made by Argon itself (e.g. added assets) like the ones
+	/// of clients and syncs them to all clients, additions get a number
+	/// appended to names that are taken. Blocks until the changes are applied,
+	/// which takes until syncing is resumed when it is paused
+	pub fn apply(&self, changes: Changes) -> Result<()> {
+		let (reply, receiver) = crossbeam_channel::bounded(1);
+
+		self.local
+			.send((changes, reply))
+			.map_err(|_| anyhow!("Processor is not running"))?;
+
+		receiver.recv()?
+	}
+
+	/// Processes changes Argon made to the file system itself while
+	/// the watcher was paused, as if they were made externally
+	pub fn notify(&self, event: VfsEvent) {
+		self.notifier.send(event).unwrap();
+	}
+
+	/// Whether a syncback batch is being applied or waits in the queue
+	pub fn is_busy(&self) -> bool {
+		self.busy.load(Ordering::Relaxed) || !self.writer.is_empty()
+	}
+
+	/// Number of events and write requests processed so far,
+	/// changes after any of them could have been made by Argon
+	pub fn processed(&self) -> u64 {
+		self.processed.load(Ordering::Relaxed)
+	}
+
+	/// Starts or stops recording processed inputs to a trace, see `TraceRecorder`
+	pub fn set_recorder(&self, recorder: Option<Arc<TraceRecorder>>) {
+		*lock!(self.recorder) = recorder;
+	}
+
+	pub fn recorder(&self) -> Option<Arc<TraceRecorder>> {
+		lock!(self.recorder).clone()
+	}
+
+	/// Stops processing file changes and syncback batches without disconnecting clients,
+	/// file changes are held back until `resume` is called or the timeout passes.
+	/// Returns whether syncing was running, the timeout is updated either way
+	pub fn pause(&self, timeout: Option<Duration>) -> bool {
+		let mut pause = lock!(self.pause);
+		let changed = !pause.paused;
+
+		pause.paused = true;
+		pause.until = timeout.map(|timeout| Instant::now() + timeout);
+
+		drop(pause);
+		self.control.send(()).unwrap();
+
+		changed
+	}
+
+	/// Resumes paused syncing, held back file changes are processed first.
+	/// Returns whether syncing was paused
+	pub fn resume(&self) -> bool {
+		let changed = mem::take(&mut lock!(self.pause).paused);
+
+		if changed {
+			self.control.send(()).unwrap();
+		}
+
+		changed
+	}
+
+	pub fn is_paused(&self) -> bool {
+		lock!(self.pause).paused
+	}
+
+	pub fn pause_status(&self) -> PauseStatus {
+		lock!(self.pause).status()
+	}
 }
 
 struct Handler {
@@ -79,26 +334,84 @@ struct Handler {
 	tree: Arc<Mutex<Tree>>,
 	vfs: Arc<Vfs>,
 	project: Arc<Mutex<Project>>,
+	events: Arc<EventLog>,
+	pending: Arc<Pending>,
+	lifecycle: Lifecycle,
+	recorder: Arc<Mutex<Option<Arc<TraceRecorder>>>>,
 }
 
 impl Handler {
+	fn recorder(&self) -> Option<Arc<TraceRecorder>> {
+		lock!(self.recorder).clone()
+	}
+
+	/// Captures the input before it is processed, if a trace is being recorded
+	fn capture(
+		&self,
+		capture: impl FnOnce(&TraceRecorder) -> Result<TraceInput>,
+	) -> Option<(Arc<TraceRecorder>, TraceInput)> {
+		let recorder = self.recorder()?;
+
+		match capture(&recorder) {
+			Ok(input) => Some((recorder, input)),
+			Err(err) => {
+				warn!(
+					"Failed to capture input for trace {}: {}",
+					recorder.path().display(),
+					err
+				);
+				None
+			}
+		}
+	}
+
+	/// Appends the captured input with the tree hash it resulted in to the trace
+	fn record(&self, captured: Option<(Arc<TraceRecorder>, TraceInput)>) {
+		let Some((recorder, input)) = captured else {
+			return;
+		};
+
+		let hash = super::compute_tree_hash(&mut lock!(self.tree), &self.vfs, &lock!(self.project));
+
+		if let Err(err) = recorder.record(input, hash) {
+			warn!("Failed to record input to trace {}: {}", recorder.path().display(), err);
+		}
+	}
+
 	#[profiling::function]
 	fn on_vfs_event(&self, event: VfsEvent) {
 		profiling::start_frame!();
 
-		trace!("Received VFS event: {:?}", event);
+		trace!(event:? = event; "Received VFS event");
+
+		self.vfs.track(&event);
 
 		let mut tree = lock!(self.tree);
 		let path = event.path();
 
+		// The tree can't be rebuilt without its project, it is kept as is
+		if matches!(event, VfsEvent::Delete(_)) && lock!(self.project).path == path {
+			self.lifecycle.emit(CoreEvent::Fatal {
+				message: t!("processor.project_deleted").to_string(),
+			});
+
+			return;
+		}
+
 		let changes = {
-			if BLACKLISTED_PATHS.iter().any(|blacklisted| path.ends_with(blacklisted)) {
-				trace!("Processing of {:?} aborted: blacklisted", path);
+			if is_blacklisted(path) {
+				trace!(path:% = path.display(); "Path is blacklisted, skipping");
 				return;
 			}
 
 			let ids = {
-				let mut current_path = path;
+				// Entries of the data index can be added for any of the siblings and
+				// asset manifests list many of them, so the whole directory is processed again instead
+				let mut current_path = if storage::is_index(path) || manifest::is_manifest(path) {
+					path.get_parent()
+				} else {
+					path
+				};
 
 				loop {
 					if let Some(ids) = tree.get_ids(current_path) {
@@ -108,7 +421,7 @@ impl Handler {
 					match current_path.parent() {
 						Some(parent) => current_path = parent,
 						None => {
-							trace!("No ID found for path {:?}", path);
+							trace!(path:% = path.display(); "No instance found for path");
 							return;
 						}
 					}
@@ -116,6 +429,12 @@ impl Handler {
 			};
 
 			let mut changes = Changes::new();
+			let instance = ids.first().and_then(|id| tree.get_full_name(*id));
+			let collisions: Vec<Collision> = tree.collisions().cloned().collect();
+			let conflicts: Vec<InitConflict> = ids
+				.iter()
+				.filter_map(|id| tree.get_meta(*id).and_then(|meta| meta.init_conflict.clone()))
+				.collect();
 
 			for id in ids {
 				if let Some(processed) = read::process_changes(id, &mut tree, &self.vfs) {
@@ -123,31 +442,46 @@ impl Handler {
 				}
 			}
 
+			if !changes.is_empty() {
+				let entry = HistoryEntry {
+					instance,
+					files: vec![path.to_owned()],
+					..HistoryEntry::new(Operation::External)
+				};
+
+				history::record(&lock!(self.project).workspace_dir, &[entry]);
+			}
+
+			// Files of colliding instances can be renamed or removed outside of Studio
+			self.publish_collisions(&collisions, &tree);
+			self.publish_init_conflicts(&conflicts, &changes.changed_ids(), &tree);
+
 			changes
 		};
 
 		if !changes.is_empty() {
-			stats::files_synced(changes.total() as u32);
-
-			let result = self.queue.push(server::SyncChanges(changes), None);
-
-			match result {
-				Ok(()) => trace!("Added changes to the queue"),
-				Err(err) => {
-					error!("Failed to add changes to the queue: {}", err);
-				}
-			}
+			self.publish(changes, &mut tree);
 		} else {
-			trace!("No changes detected when processing path: {:?}", path);
+			trace!(path:% = path.display(); "No changes detected");
 		}
 
 		if lock!(self.project).path == path {
 			if let VfsEvent::Write(_) = event {
 				debug!("Project file was modified. Reloading project..");
 
-				match lock!(self.project).reload() {
+				let mut project = lock!(self.project);
+				let previous = serde_json::to_value(&*project).ok();
+
+				match project.reload() {
 					Ok(project) => {
-						info!("Project reloaded");
+						self.lifecycle.emit(CoreEvent::ProjectReloaded {
+							path: project.path.clone(),
+							changes: ProjectChanges::new(previous, project),
+						});
+
+						self.vfs.set_ignore_rules(project.ignore_rules());
+						self.vfs.set_verify_writes(project.verifies_writes());
+						tree.set_project_node(project.node.clone());
 
 						let details = server::SyncDetails(ProjectDetails::from_project(project, &tree));
 
@@ -155,12 +489,352 @@ impl Handler {
 							Ok(()) => trace!("Project details synced"),
 							Err(err) => warn!("Failed to sync project details: {}", err),
 						}
+
+						self.verify_clients(project);
+					}
+					Err(err) => {
+						error!(
+							"Failed to reload project, keeping the last valid configuration: {}",
+							err
+						);
+
+						self.events.publish(EventKind::Diagnostic {
+							level: String::from("error"),
+							message: format!("Failed to reload project: {}", err),
+							instance: None,
+						});
 					}
-					Err(err) => error!("Failed to reload project: {}", err),
 				}
-			} else if let VfsEvent::Delete(_) = event {
-				argon_error!("Warning! Top level project file was deleted. This might cause unexpected behavior. Skipping processing of changes!");
-				return;
+			}
+		}
+	}
+
+	/// Processes file changes held back while paused, large backlogs
+	/// are rescanned at once the same way as storms, see `on_storm`
+	fn on_resume(&self, mut events: Vec<VfsEvent>, overflowed: bool) {
+		// Every traced input needs its own tree hash, see `on_storm`
+		let config = Config::new();
+		let bulk = self.recorder().is_none()
+			&& config.storm_detection
+			&& config.storm_pending_threshold > 0
+			&& events.len() >= config.storm_pending_threshold;
+
+		drop(config);
+
+		// Processing the project file rescans the whole tree, it stands in for the dropped events
+		if overflowed {
+			events.push(VfsEvent::Write(lock!(self.project).path.clone()));
+		}
+
+		argon_info!("{}", t!("processor.resumed", events = events.len().to_string().bold()));
+
+		if bulk || overflowed {
+			self.on_storm(events);
+		} else {
+			for event in events {
+				let captured = self.capture(|recorder| recorder.capture_event(&event));
+
+				self.on_vfs_event(event);
+				self.record(captured);
+			}
+		}
+	}
+
+	/// Tells clients that support it whether syncing is paused
+	fn publish_pause(&self, pause: &Mutex<PauseState>) {
+		let status = lock!(pause).status();
+
+		for client in self.queue.clients() {
+			if !client.negotiated.supports(Capability::Pause) {
+				continue;
+			}
+
+			if let Err(err) = self.queue.push(server::PauseChanged(status), Some(client.id)) {
+				warn!("Failed to send pause status: {}", err);
+			}
+		}
+	}
+
+	/// Collapses a burst of events into a single rescan of the roots they touched,
+	/// the net changes are sent to clients as one batch, see `StormDetector`
+	#[profiling::function]
+	fn on_storm(&self, events: Vec<VfsEvent>) {
+		profiling::start_frame!();
+
+		let project_path = lock!(self.project).path.clone();
+
+		// Changes of the project file reload it, they are processed after the rescan
+		let (project_events, events): (Vec<VfsEvent>, Vec<VfsEvent>) =
+			events.into_iter().partition(|event| event.path() == project_path);
+
+		for event in &events {
+			self.vfs.track(event);
+		}
+
+		let mut tree = lock!(self.tree);
+
+		let paths = events
+			.iter()
+			.map(|event| event.path())
+			.filter(|path| !is_blacklisted(path));
+		let roots = storm::roots(paths, &tree);
+
+		argon_info!(
+			"{}",
+			t!(
+				"processor.storm",
+				events = events.len().to_string().bold(),
+				roots = roots.len().to_string().bold()
+			)
+		);
+
+		let collisions: Vec<Collision> = tree.collisions().cloned().collect();
+		let conflicts: Vec<InitConflict> = tree
+			.meta_map()
+			.values()
+			.filter_map(|meta| meta.init_conflict.clone())
+			.collect();
+
+		let mut changes = Changes::new();
+		let mut entries = vec![];
+
+		for root in &roots {
+			let Some(ids) = tree.get_ids(root).cloned() else {
+				continue;
+			};
+
+			let instance = ids.first().and_then(|id| tree.get_full_name(*id));
+			let total = changes.total();
+
+			for id in ids {
+				if let Some(processed) = read::process_changes(id, &mut tree, &self.vfs) {
+					changes.extend(processed);
+				}
+			}
+
+			if changes.total() > total {
+				entries.push(HistoryEntry {
+					instance,
+					files: vec![root.to_owned()],
+					..HistoryEntry::new(Operation::External)
+				});
+			}
+		}
+
+		history::record(&lock!(self.project).workspace_dir, &entries);
+
+		self.publish_collisions(&collisions, &tree);
+		self.publish_init_conflicts(&conflicts, &changes.changed_ids(), &tree);
+
+		if !changes.is_empty() {
+			self.publish(changes, &mut tree);
+		}
+
+		drop(tree);
+
+		for event in project_events {
+			self.on_vfs_event(event);
+		}
+	}
+
+	/// Sends changes read from the file system to clients,
+	/// or records them as pending if none are connected
+	fn publish(&self, changes: Changes, tree: &mut Tree) {
+		stats::files_synced(changes.total() as u32);
+
+		if changes.is_structural() {
+			self.save_stable_ids(tree);
+		}
+
+		self.publish_load_errors(&changes.changed_ids(), tree);
+		self.evict(tree, &changes.changed_ids());
+		self.events.publish_all(ChangeSummary::new(&changes).into_events(tree));
+
+		if self.queue.clients().is_empty() {
+			self.pending.record(&changes, tree);
+		}
+
+		let result = self.queue.push_changes(changes, None, tree, &self.vfs);
+
+		match result {
+			Ok(()) => trace!("Added changes to the queue"),
+			Err(err) => {
+				error!("Failed to add changes to the queue: {}", err);
+			}
+		}
+	}
+
+	/// Drops large property values of changed instances in low memory mode,
+	/// they have already been cloned into the queue so clients still get them
+	fn evict(&self, tree: &mut Tree, ids: &[Ref]) {
+		if !lock!(self.project).is_low_memory() {
+			return;
+		}
+
+		for id in ids {
+			tree.evict(*id);
+		}
+	}
+
+	fn save_stable_ids(&self, tree: &mut Tree) {
+		if let Err(err) = tree.save_stable_ids() {
+			warn!("Failed to save stable instance IDs: {}", err);
+		}
+	}
+
+	/// Publishes diagnostics for placeholders of files that failed to load
+	fn publish_load_errors(&self, ids: &[Ref], tree: &Tree) {
+		for id in ids {
+			if let Some(load_error) = tree.get_meta(*id).and_then(|meta| meta.load_error.as_ref()) {
+				self.events.publish(EventKind::Diagnostic {
+					level: String::from("error"),
+					message: format!("Failed to load {}", load_error),
+					instance: Some(Origin::of(*id, tree)),
+				});
+			}
+		}
+	}
+
+	/// Publishes diagnostics for collisions that were recorded or resolved
+	/// since `previous` was taken, they stay in the tree until resolved
+	fn publish_collisions(&self, previous: &[Collision], tree: &Tree) {
+		for collision in tree.collisions() {
+			if !previous.contains(collision) {
+				self.events.publish(EventKind::Diagnostic {
+					level: String::from("warning"),
+					message: format!(
+						"{} (instances: {} and {})",
+						collision,
+						tree.get_full_name(collision.id).unwrap_or_default(),
+						tree.get_full_name(collision.other).unwrap_or_default()
+					),
+					instance: None,
+				});
+			}
+		}
+
+		let current: Vec<&Collision> = tree.collisions().collect();
+
+		for collision in previous {
+			if !current.contains(&collision) {
+				self.events.publish(EventKind::Diagnostic {
+					level: String::from("info"),
+					message: format!("Collision at {} was resolved", collision.path.display()),
+					instance: None,
+				});
+			}
+		}
+	}
+
+	/// Publishes diagnostics for init file conflicts of changed instances that
+	/// were not there before and for `previous` conflicts that are now gone
+	fn publish_init_conflicts(&self, previous: &[InitConflict], ids: &[Ref], tree: &Tree) {
+		for id in ids {
+			if let Some(conflict) = tree.get_meta(*id).and_then(|meta| meta.init_conflict.as_ref()) {
+				if !previous.contains(conflict) {
+					self.events.publish(EventKind::Diagnostic {
+						level: String::from("warning"),
+						message: format!("Conflicting files in {}: {}", conflict.dir.display(), conflict),
+						instance: None,
+					});
+				}
+			}
+		}
+
+		for conflict in previous {
+			let resolved = tree.get_ids(&conflict.dir).is_none_or(|ids| {
+				ids.iter()
+					.all(|id| tree.get_meta(*id).is_none_or(|meta| meta.init_conflict.is_none()))
+			});
+
+			if resolved {
+				self.events.publish(EventKind::Diagnostic {
+					level: String::from("info"),
+					message: format!("Conflicting files in {} were resolved", conflict.dir.display()),
+					instance: None,
+				});
+			}
+		}
+	}
+
+	/// Reads copies of shared directories written by syncback from their primary
+	/// node again and sends the changes to all clients, including the author,
+	/// as VFS events of files written by syncback are ignored
+	fn sync_mirrors(&self, copies: &[Ref], tree: &mut Tree) {
+		let mut changes = Changes::new();
+
+		for id in copies {
+			if let Some(processed) = read::process_changes(*id, tree, &self.vfs) {
+				changes.extend(processed);
+			}
+		}
+
+		if changes.is_empty() {
+			return;
+		}
+
+		trace!(copies = copies.len(), changes = changes.total(); "Synced copies of shared directories");
+
+		if self.queue.clients().is_empty() {
+			self.pending.record(&changes, tree);
+		}
+
+		if let Err(err) = self.queue.push_changes(changes, None, tree, &self.vfs) {
+			error!("Failed to add changes to the queue: {}", err);
+		}
+	}
+
+	/// Sends the rejection of the operation to the client that supports them,
+	/// returns whether it was rejected, the rest of the batch is applied then
+	fn reject(&self, client_id: u32, result: &Result<()>) -> bool {
+		let Some(rejection) = result
+			.as_ref()
+			.err()
+			.and_then(|err| err.downcast_ref::<ReadOnlyRejection>())
+		else {
+			return false;
+		};
+
+		if self.queue.supports(client_id, Capability::Rejections) {
+			let message = server::SyncbackRejected(rejection.clone());
+
+			if let Err(err) = self.queue.push(message, Some(client_id)) {
+				warn!("Failed to send syncback rejection: {}", err);
+			}
+		}
+
+		true
+	}
+
+	/// Creates history entry describing change made by the client
+	fn annotate(&self, operation: Operation, id: Ref, tree: &Tree, client_id: u32) -> Option<HistoryEntry> {
+		let meta = tree.get_meta(id)?;
+		let client = self.queue.clients().into_iter().find(|client| client.id == client_id);
+
+		Some(HistoryEntry {
+			instance: tree.get_full_name(id),
+			files: meta.source.paths().into_iter().map(|path| path.to_owned()).collect(),
+			client_id: Some(client_id),
+			client_name: client.as_ref().map(|client| client.name.clone()),
+			game_id: client.as_ref().and_then(|client| client.place.game_id),
+			place_id: client.as_ref().and_then(|client| client.place.place_id),
+			..HistoryEntry::new(operation)
+		})
+	}
+
+	/// Disconnects clients that no longer match project's `gameId` and `placeIds`
+	fn verify_clients(&self, project: &Project) {
+		for client in self.queue.clients() {
+			if let Err(err) = project.check_place(client.place.game_id, client.place.place_id) {
+				if !project.is_strict_place() {
+					argon_warn!("{}", err);
+					continue;
+				}
+
+				match self.queue.disconnect(&err.to_string(), client.id) {
+					Ok(()) => trace!("Client {} disconnected", client.id),
+					Err(err) => warn!("Failed to disconnect client: {}", err),
+				}
 			}
 		}
 	}
@@ -172,24 +846,23 @@ impl Handler {
 		let changes = request.changes;
 		let client_id = request.client_id;
 
-		trace!("Received client event: {:?} changes", changes.total());
+		trace!(client_id, changes = changes.total(); "Received client event");
 
 		if changes.total() > Config::new().changes_threshold {
 			let accept = logger::prompt(
-				&format!(
-					"You are about to apply {}, {} and {}. Do you want to continue?",
-					format!("{} additions", changes.additions.len()).bold().green(),
-					format!("{} updates", changes.updates.len()).bold().blue(),
-					format!("{} removals", changes.removals.len()).bold().red(),
+				&t!(
+					"processor.apply_prompt",
+					additions = t!("processor.additions", count = changes.additions.len())
+						.bold()
+						.green(),
+					updates = t!("processor.updates", count = changes.updates.len()).bold().blue(),
+					removals = t!("processor.removals", count = changes.removals.len()).bold().red()
 				),
 				true,
 			);
 
 			if !accept {
-				trace!(
-					"Aborted applying client event! {} changes were not applied",
-					changes.total()
-				);
+				trace!(client_id, changes = changes.total(); "Client event rejected, changes were not applied");
 
 				match self.queue.disconnect("Client and server got out of sync!", client_id) {
 					Ok(()) => trace!("Client {} disconnected", client_id),
@@ -200,29 +873,218 @@ impl Handler {
 			}
 		}
 
+		self.lifecycle.emit(CoreEvent::SyncbackStarted {
+			client_id,
+			changes: changes.total(),
+		});
+
 		let mut tree = lock!(self.tree);
+		let mut entries = vec![];
+		let mut results = vec![];
+
+		let changes = tree.hold_frozen(changes);
+
+		let collisions: Vec<Collision> = tree.collisions().cloned().collect();
+
+		let changed_ids = changes.changed_ids();
+		let summary = ChangeSummary::new(&changes);
+		let structural = changes.is_structural();
+		let stale_copies = mirrors::find_stale_copies(&changes, &tree);
+
+		// Project files are saved once at the end of the batch,
+		// while the VFS is still paused so the write is not echoed
+		project::begin_batch();
 
 		let result = || -> Result<()> {
 			for snapshot in changes.additions {
-				write::apply_addition(snapshot, &mut tree, &self.vfs)?;
+				let id = snapshot.id;
+
+				project::checkpoint_batch();
+				property_reports::begin();
+
+				let result = write::apply_addition(snapshot, &mut tree, &self.vfs);
+				results.push(
+					SyncbackResult::new(Operation::Addition, id, &result).with_properties(property_reports::finish()),
+				);
+
+				if self.reject(client_id, &result) {
+					continue;
+				}
+
+				result?;
+
+				fsck::check(id, true, &mut tree, &self.vfs, "addition");
+				entries.extend(self.annotate(Operation::Addition, id, &tree, client_id));
 			}
 
 			for snapshot in changes.updates {
-				write::apply_update(snapshot, &mut tree, &self.vfs)?;
+				let id = snapshot.id;
+
+				project::checkpoint_batch();
+				property_reports::begin();
+
+				let result = write::apply_update(snapshot, &mut tree, &self.vfs);
+				results.push(
+					SyncbackResult::new(Operation::Update, id, &result).with_properties(property_reports::finish()),
+				);
+
+				if self.reject(client_id, &result) {
+					continue;
+				}
+
+				result?;
+
+				fsck::check(id, true, &mut tree, &self.vfs, "update");
+				entries.extend(self.annotate(Operation::Update, id, &tree, client_id));
 			}
 
 			for id in changes.removals {
-				write::apply_removal(id, &mut tree, &self.vfs)?;
+				project::checkpoint_batch();
+
+				let entry = self.annotate(Operation::Removal, id, &tree, client_id);
+				let parent = tree.get_instance(id).map(|instance| instance.parent());
+
+				let result = write::apply_removal(id, &mut tree, &self.vfs);
+				results.push(SyncbackResult::new(Operation::Removal, id, &result));
+
+				if self.reject(client_id, &result) {
+					continue;
+				}
+
+				result?;
+
+				if let Some(parent) = parent {
+					fsck::check(parent, false, &mut tree, &self.vfs, "removal");
+				}
+				entries.extend(entry);
 			}
 
-			Ok(())
+			project::checkpoint_batch();
+
+			write::finish_batch(&mut tree, &self.vfs)?;
+			project::end_batch().map(|saved| trace!(saved; "Saved batched project edits"))
 		}();
 
-		match result {
-			Ok(()) => trace!("Changes applied successfully"),
-			Err(err) => error!("Failed to apply changes: {}", err),
+		let error = match result {
+			Ok(()) => {
+				trace!("Changes applied successfully");
+				None
+			}
+			Err(err) => {
+				// Operations applied before the failing one stay in the tree and files,
+				// so their project edits are saved too, only the failing one's are dropped
+				match project::fail_batch() {
+					Ok(saved) => trace!(saved; "Saved project edits of applied changes"),
+					Err(err) => warn!("Failed to save project edits of applied changes: {}", err),
+				}
+
+				error!("Failed to apply changes: {:#}", err);
+
+				self.events.publish(EventKind::Diagnostic {
+					level: String::from("error"),
+					message: format!("Failed to apply changes: {:#}", err),
+					instance: Origin::find(&err).cloned(),
+				});
+
+				Some(format!("{:#}", err))
+			}
+		};
+
+		backups::end_batch();
+
+		history::record(&lock!(self.project).workspace_dir, &entries);
+
+		if structural {
+			self.save_stable_ids(&mut tree);
 		}
 
+		self.evict(&mut tree, &changed_ids);
+		self.events.publish_all(summary.into_events(&tree));
+		self.publish_collisions(&collisions, &tree);
+		self.sync_mirrors(&stale_copies, &mut tree);
+
 		self.queue.push(server::SyncbackChanges(), Some(0)).ok();
+
+		if self.queue.supports(client_id, Capability::PropertyReports) {
+			let message = server::SyncbackReport {
+				results: results.clone(),
+			};
+
+			if let Err(err) = self.queue.push(message, Some(client_id)) {
+				warn!("Failed to send syncback report: {}", err);
+			}
+		}
+
+		self.lifecycle.emit(CoreEvent::SyncbackFinished {
+			client_id,
+			results,
+			error,
+		});
+	}
+
+	/// Applies changes made by Argon itself, see `Processor::apply`
+	fn on_local_changes(&self, mut changes: Changes) -> Result<()> {
+		trace!(changes = changes.total(); "Received local changes");
+
+		let mut tree = lock!(self.tree);
+
+		for snapshot in &mut changes.additions {
+			let dom = tree.inner();
+			let children = dom
+				.get_by_ref(snapshot.parent)
+				.with_context(|| format!("Parent of {} does not exist", snapshot.name))?
+				.children();
+
+			snapshot.name = assets::unique_name(&snapshot.name, |name| {
+				children
+					.iter()
+					.any(|child| dom.get_by_ref(*child).is_some_and(|child| child.name == name))
+			});
+		}
+
+		let result = || -> Result<()> {
+			for snapshot in changes.additions.clone() {
+				write::apply_addition(snapshot, &mut tree, &self.vfs)?;
+			}
+
+			for snapshot in changes.updates.clone() {
+				write::apply_update(snapshot, &mut tree, &self.vfs)?;
+			}
+
+			write::finish_batch(&mut tree, &self.vfs)
+		}();
+
+		backups::end_batch();
+		result?;
+
+		if changes.is_structural() {
+			self.save_stable_ids(&mut tree);
+		}
+
+		// Clients receive additions with their descendants and all properties
+		let mut synced = Changes::new();
+
+		for snapshot in &changes.additions {
+			synced
+				.additions
+				.extend(super::snapshot_subtree(snapshot.id, false, &tree, &self.vfs));
+		}
+
+		synced.updates = changes.updates;
+
+		if self.queue.clients().is_empty() {
+			self.pending.record(&synced, &tree);
+		}
+
+		self.queue.push_changes(synced, None, &tree, &self.vfs)
 	}
 }
+
+/// Whether the path is inside of a directory that is never synced, e.g. `.git`
+fn is_blacklisted(path: &Path) -> bool {
+	path.components().any(|component| {
+		BLACKLISTED_PATHS
+			.iter()
+			.any(|blacklisted| component.as_os_str() == *blacklisted)
+	})
+}
@@ -0,0 +1,65 @@
+use anyhow::Error;
+use rbx_dom_weak::types::Ref;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+use crate::core::{meta::ReadOnlyRejection, tree::Tree};
+
+/// Instance a processor operation was applied to, attached as context to every
+/// error of the operation so a single error line is enough to find the culprit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Origin {
+	pub id: Ref,
+	/// Full name of the instance, the same as in events, e.g. `ReplicatedStorage.Modules.Foo`
+	pub path: String,
+	pub class: String,
+}
+
+impl Origin {
+	/// Origin of an instance that exists in the tree
+	pub fn of(id: Ref, tree: &Tree) -> Self {
+		Self {
+			id,
+			path: tree.get_full_name(id).unwrap_or_default(),
+			class: tree
+				.get_instance(id)
+				.map(|instance| instance.class.to_string())
+				.unwrap_or_default(),
+		}
+	}
+
+	/// Origin of an instance that is about to be added to the parent
+	pub fn of_child(id: Ref, parent: Ref, name: &str, class: &str, tree: &Tree) -> Self {
+		let path = match tree.get_full_name(parent) {
+			Some(parent) => format!("{}.{}", parent, name),
+			None => name.to_owned(),
+		};
+
+		Self {
+			id,
+			path,
+			class: class.to_owned(),
+		}
+	}
+
+	/// Attaches the origin to the error, rejections already describe their instance
+	pub fn attach(&self, err: Error) -> Error {
+		if err.is::<ReadOnlyRejection>() || Self::find(&err).is_some() {
+			return err;
+		}
+
+		err.context(self.clone())
+	}
+
+	/// Returns the origin attached to the error, if any
+	pub fn find(err: &Error) -> Option<&Self> {
+		err.downcast_ref::<Self>()
+	}
+}
+
+impl Display for Origin {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{} {} ({})", self.class, self.path, self.id)
+	}
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use std::{mem, time::Instant};
+
+use crate::vfs::VfsEvent;
+
+/// Whether syncing is paused, reported by `/health` and sent to clients that support it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PauseStatus {
+	pub paused: bool,
+	/// Seconds until syncing resumes on its own, `None` if it never does
+	pub resumes_in: Option<u64>,
+	/// Number of file changes held back until syncing resumes
+	pub backlog: usize,
+}
+
+/// Pause shared between `Processor` and its thread
+#[derive(Debug, Default)]
+pub struct PauseState {
+	pub paused: bool,
+	/// When syncing resumes on its own
+	pub until: Option<Instant>,
+	pub backlog: usize,
+}
+
+impl PauseState {
+	pub fn status(&self) -> PauseStatus {
+		PauseStatus {
+			paused: self.paused,
+			resumes_in: self
+				.until
+				.filter(|_| self.paused)
+				.map(|until| until.saturating_duration_since(Instant::now()).as_secs()),
+			backlog: self.backlog,
+		}
+	}
+}
+
+/// Watcher events received while paused, once there are more than `limit` of
+/// them the rest is dropped and the whole tree is rescanned on resume instead
+#[derive(Debug, Default)]
+pub struct Backlog {
+	events: Vec<VfsEvent>,
+	overflowed: bool,
+}
+
+impl Backlog {
+	pub fn hold(&mut self, event: VfsEvent, limit: usize) {
+		if self.events.len() < limit {
+			self.events.push(event);
+		} else {
+			self.overflowed = true;
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.events.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.events.is_empty() && !self.overflowed
+	}
+
+	/// Returns held events and whether any were dropped, leaving the backlog empty
+	pub fn take(&mut self) -> (Vec<VfsEvent>, bool) {
+		(mem::take(&mut self.events), mem::take(&mut self.overflowed))
+	}
+}
@@ -1,20 +1,38 @@
 use log::{error, trace};
 use rbx_dom_weak::types::Ref;
+use std::mem;
 
+use super::origin::Origin;
 use crate::{
 	core::{
 		changes::Changes,
+		frozen::SyncDirection,
+		helpers::{memory, names},
 		meta::SourceKind,
+		refs,
 		snapshot::{Snapshot, UpdatedSnapshot},
 		tree::Tree,
 	},
-	middleware::{new_snapshot, project::new_snapshot_node},
+	middleware::{manifest, new_snapshot_isolated, project::new_snapshot_node},
 	stats, util,
 	vfs::Vfs,
 };
 
+/// Processes changes of the instance from the file system,
+/// changes of frozen instances are counted and skipped
 pub fn process_changes(id: Ref, tree: &mut Tree, vfs: &Vfs) -> Option<Changes> {
-	trace!("Processing changes for instance: {:?}", id);
+	if let Some(frozen) = tree.get_frozen(id) {
+		tree.suppress(frozen, SyncDirection::ToStudio, 1);
+		return Some(Changes::new());
+	}
+
+	process_frozen(id, tree, vfs)
+}
+
+/// Processes changes of the instance even if it is frozen, used by manual
+/// sync, frozen descendants are still skipped by `process_child_changes`
+pub fn process_frozen(id: Ref, tree: &mut Tree, vfs: &Vfs) -> Option<Changes> {
+	trace!(id:% = id; "Processing changes");
 
 	let mut changes = Changes::new();
 
@@ -26,22 +44,43 @@ pub fn process_changes(id: Ref, tree: &mut Tree, vfs: &Vfs) -> Option<Changes> {
 			match new_snapshot_node(name, path, *node.clone(), node_path.clone(), &meta.context, vfs) {
 				Ok(snapshot) => Some(snapshot),
 				Err(err) => {
-					error!("Failed to process changes: {}, source: {:?}", err, source);
+					error!(
+						"Failed to process changes of {}: {:#}, source: {:?}",
+						Origin::of(id, tree),
+						err,
+						source
+					);
 					return Some(changes);
 				}
 			}
 		}
-		SourceKind::Path(path) => match new_snapshot(path, &meta.context, vfs) {
+		// Instances listed in asset manifests are read along with their siblings
+		SourceKind::Path(path) if manifest::is_manifest(path) => {
+			let parent = tree.get_instance(id)?.parent();
+			return process_frozen(parent, tree, vfs);
+		}
+		SourceKind::Path(path) => match new_snapshot_isolated(path, &meta.context, vfs) {
 			Ok(snapshot) => snapshot,
 			Err(err) => {
-				error!("Failed to process changes: {}, source: {:?}", err, source);
+				error!(
+					"Failed to process changes of {}: {:#}, source: {:?}",
+					Origin::of(id, tree),
+					err,
+					source
+				);
 				return Some(changes);
 			}
 		},
-		SourceKind::None => panic!(
-			"Fatal processing error: `SourceKind::None` should not be present in the tree! Id: {:?}, meta: {:#?}",
-			id, meta
-		),
+		// Ephemeral instances are not backed by any file
+		SourceKind::Ephemeral => return Some(changes),
+		// Only the changes of this instance are lost, not the whole processor thread
+		SourceKind::None => {
+			error!(
+				"Failed to process changes of {}: instance has no source",
+				Origin::of(id, tree)
+			);
+			return Some(changes);
+		}
 	};
 
 	// Handle additions, modifications and child removals
@@ -53,25 +92,61 @@ pub fn process_changes(id: Ref, tree: &mut Tree, vfs: &Vfs) -> Option<Changes> {
 		changes.remove(id);
 	}
 
+	// References can point at instances that were just added
+	for id in tree.resolve_refs() {
+		let properties = memory::materialize_lossy(id, tree, vfs);
+
+		match changes.updates.iter_mut().find(|snapshot| snapshot.id == id) {
+			Some(snapshot) => snapshot.properties = properties,
+			None => {
+				let mut snapshot = UpdatedSnapshot::new(id);
+				snapshot.properties = properties;
+
+				changes.update(snapshot);
+			}
+		}
+	}
+
 	Some(changes)
 }
 
 fn process_child_changes(id: Ref, mut snapshot: Snapshot, changes: &mut Changes, tree: &mut Tree) {
+	// References to instances that are not in the tree yet are resolved by `Tree::resolve_refs`
+	for (property, path) in &snapshot.meta.ref_paths {
+		if let Some(value) = refs::resolve(id, property, path, tree) {
+			snapshot.properties.insert(*property, value);
+		}
+	}
+
 	// Process instance changes
 	let mut updated_snapshot = UpdatedSnapshot::new(id);
+	let meta = tree.get_meta(id).expect("Instance meta not found");
 
-	updated_snapshot.meta = if snapshot.meta != *tree.get_meta(id).expect("Instance meta not found") {
+	// Identifiers are assigned by the tree, never by snapshots
+	snapshot.meta.stable_id.clone_from(&meta.stable_id);
+
+	updated_snapshot.meta = if snapshot.meta != *meta {
 		tree.update_meta(id, snapshot.meta.clone());
-		Some(snapshot.meta)
+		Some(mem::take(&mut snapshot.meta))
 	} else {
 		None
 	};
 
+	let properties_changed = {
+		let instance = tree.get_instance(id).unwrap();
+		!memory::properties_eq(&snapshot.properties, &instance.properties, tree.get_evicted(id))
+	};
+
+	if properties_changed {
+		tree.clear_evicted(id);
+	}
+
 	let instance = tree.get_instance_mut(id).unwrap();
 
-	updated_snapshot.name = if snapshot.name != instance.name {
+	// File names can be in a different normalization form than the instance name
+	updated_snapshot.name = if !names::names_match(&snapshot.name, &instance.name) {
 		instance.name.clone_from(&snapshot.name);
-		Some(snapshot.name)
+		Some(mem::take(&mut snapshot.name))
 	} else {
 		None
 	};
@@ -83,9 +158,9 @@ fn process_child_changes(id: Ref, mut snapshot: Snapshot, changes: &mut Changes,
 		None
 	};
 
-	updated_snapshot.properties = if snapshot.properties != instance.properties {
+	updated_snapshot.properties = if properties_changed {
 		instance.properties.clone_from(&snapshot.properties);
-		Some(snapshot.properties)
+		Some(mem::take(&mut snapshot.properties))
 	} else {
 		None
 	};
@@ -100,11 +175,23 @@ fn process_child_changes(id: Ref, mut snapshot: Snapshot, changes: &mut Changes,
 		changes.update(updated_snapshot);
 	}
 
+	let child_ids = instance.children().to_owned();
+
+	if properties_changed {
+		tree.link_refs(id);
+	}
+
 	let mut hydrated = vec![false; snapshot.children.len()];
+	let mut skipped = vec![];
 
 	// Pair instances and find removed children
-	#[allow(clippy::unnecessary_to_owned)]
-	for child_id in instance.children().to_owned() {
+	for child_id in child_ids {
+		// Ephemeral children never appear in snapshots from the file system
+		if tree.get_meta(child_id).is_some_and(|meta| meta.source.is_ephemeral()) {
+			continue;
+		}
+
+		let is_frozen = tree.is_frozen(child_id);
 		let instance = tree.get_instance(child_id).unwrap();
 
 		let snapshot = snapshot.children.iter_mut().enumerate().find(|(index, child)| {
@@ -112,7 +199,7 @@ fn process_child_changes(id: Ref, mut snapshot: Snapshot, changes: &mut Changes,
 				return false;
 			}
 
-			if child.name == instance.name && child.class == instance.class {
+			if names::names_match(&child.name, &instance.name) && child.class == instance.class {
 				hydrated[*index] = true;
 				return true;
 			}
@@ -120,8 +207,17 @@ fn process_child_changes(id: Ref, mut snapshot: Snapshot, changes: &mut Changes,
 			false
 		});
 
-		if let Some((_, child)) = snapshot {
+		if let Some((index, child)) = snapshot {
+			// Frozen instances only change through manual sync
+			if is_frozen {
+				trace!(id:% = child_id; "Skipping frozen instance");
+				skipped.push(index);
+				continue;
+			}
+
 			child.set_id(child_id);
+		} else if is_frozen {
+			trace!(id:% = child_id; "Keeping removed frozen instance");
 		} else {
 			tree.remove_instance(child_id);
 			changes.remove(child_id);
@@ -129,7 +225,11 @@ fn process_child_changes(id: Ref, mut snapshot: Snapshot, changes: &mut Changes,
 	}
 
 	// Process child changes and find new children
-	for child in snapshot.children {
+	for (index, child) in mem::take(&mut snapshot.children).into_iter().enumerate() {
+		if skipped.contains(&index) {
+			continue;
+		}
+
 		if child.id.is_some() {
 			process_child_changes(child.id, child, changes, tree);
 		} else {
@@ -147,9 +247,13 @@ fn process_child_changes(id: Ref, mut snapshot: Snapshot, changes: &mut Changes,
 }
 
 fn insert_children(snapshot: &mut Snapshot, parent: Ref, tree: &mut Tree) {
+	// Children are inserted separately so there is no need to clone them
+	let children = mem::take(&mut snapshot.children);
 	let id = tree.insert_instance(snapshot.clone(), parent);
 
 	snapshot.set_id(id);
+	snapshot.meta.stable_id = tree.get_meta(id).and_then(|meta| meta.stable_id.clone());
+	snapshot.set_children(children);
 
 	for child in snapshot.children.iter_mut() {
 		insert_children(child, id, tree);
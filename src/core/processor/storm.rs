@@ -0,0 +1,132 @@
+use crossbeam_channel::Receiver;
+use std::{
+	collections::{BTreeSet, VecDeque},
+	path::{Path, PathBuf},
+	time::{Duration, Instant},
+};
+
+use crate::{
+	config::Config,
+	constants::{STORM_MAX_DURATION, STORM_SETTLE_TIME},
+	core::tree::Tree,
+	ext::PathExt,
+	middleware::{manifest, storage},
+	vfs::VfsEvent,
+};
+
+/// Detects bursts of watcher events, e.g. from `git checkout` of a divergent branch,
+/// that are faster to handle by rescanning the roots they touched than one by one
+#[derive(Debug, Default)]
+pub struct StormDetector {
+	/// Arrival times of events within the last second
+	recent: VecDeque<Instant>,
+}
+
+impl StormDetector {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Records an event received at `now` and returns whether storm mode should
+	/// be engaged, `pending` is the number of events still waiting to be processed
+	pub fn record(&mut self, now: Instant, pending: usize) -> bool {
+		let config = Config::new();
+
+		if !config.storm_detection {
+			self.recent.clear();
+			return false;
+		}
+
+		self.check(
+			now,
+			pending,
+			config.storm_pending_threshold,
+			config.storm_rate_threshold,
+		)
+	}
+
+	/// Same as `record` but with explicit thresholds, 0 disables the threshold
+	pub fn check(&mut self, now: Instant, pending: usize, pending_threshold: usize, rate_threshold: usize) -> bool {
+		while self
+			.recent
+			.front()
+			.is_some_and(|time| now.duration_since(*time) >= Duration::from_secs(1))
+		{
+			self.recent.pop_front();
+		}
+
+		self.recent.push_back(now);
+
+		let engaged = (pending_threshold > 0 && pending >= pending_threshold)
+			|| (rate_threshold > 0 && self.recent.len() >= rate_threshold);
+
+		// Events of the storm are rescanned together, the rate starts over after it
+		if engaged {
+			self.recent.clear();
+		}
+
+		engaged
+	}
+}
+
+/// Takes events of the storm from the receiver until the watcher stays
+/// quiet for `STORM_SETTLE_TIME` or the storm lasts `STORM_MAX_DURATION`
+pub fn collect(first: VfsEvent, receiver: &Receiver<VfsEvent>) -> Vec<VfsEvent> {
+	let start = Instant::now();
+	let mut events = vec![first];
+
+	while start.elapsed() < STORM_MAX_DURATION {
+		match receiver.recv_timeout(STORM_SETTLE_TIME) {
+			Ok(event) => events.push(event),
+			Err(_) => break,
+		}
+	}
+
+	events
+}
+
+/// Returns the topmost directories of the tree that contain the paths, usually
+/// `$path`s of project nodes, without ones nested in another returned root
+pub fn roots<'a>(paths: impl IntoIterator<Item = &'a Path>, tree: &Tree) -> Vec<PathBuf> {
+	let mut roots = BTreeSet::new();
+
+	for path in paths {
+		// Entries of the data index can be added for any of the siblings,
+		// asset manifests list them
+		let mut current = if storage::is_index(path) || manifest::is_manifest(path) {
+			path.get_parent()
+		} else {
+			path
+		};
+
+		while tree.get_ids(current).is_none() {
+			match current.parent() {
+				Some(parent) => current = parent,
+				None => break,
+			}
+		}
+
+		if tree.get_ids(current).is_none() {
+			continue;
+		}
+
+		while let Some(parent) = current.parent().filter(|parent| tree.get_ids(parent).is_some()) {
+			current = parent;
+		}
+
+		roots.insert(current.to_owned());
+	}
+
+	// Sorted by components, so descendants follow their ancestors
+	let mut collapsed: Vec<PathBuf> = vec![];
+
+	for root in roots {
+		if collapsed.last().is_some_and(|last| root.starts_with(last)) {
+			continue;
+		}
+
+		collapsed.push(root);
+	}
+
+	collapsed
+}
@@ -1,49 +1,397 @@
-use anyhow::{anyhow, Context as AnyhowContext, Result};
+use anyhow::{anyhow, bail, Context as AnyhowContext, Result};
+use colored::Colorize;
 use log::{error, trace, warn};
 use path_clean::PathClean;
-use rbx_dom_weak::{types::Ref, HashMapExt, Instance, Ustr, UstrMap};
-use std::path::{Path, PathBuf};
+use rbx_dom_weak::{
+	types::{Ref, Variant},
+	ustr, HashMapExt, Instance, Ustr, UstrMap,
+};
+use std::{
+	collections::HashSet,
+	mem,
+	path::{Path, PathBuf},
+	vec,
+};
 
+use super::origin::Origin;
 use crate::{
+	argon_warn,
 	config::Config,
+	constants::{EPHEMERAL_ATTRIBUTE, EXTERNAL_REF},
 	core::{
-		helpers::syncback::{rename_path, serialize_properties, validate_properties, verify_name, verify_path},
-		meta::{Meta, NodePath, Source, SourceEntry, SourceKind},
+		helpers::{
+			collisions::{Claims, Collision},
+			memory, mirrors,
+			packages::{self, PACKAGE_LINK},
+			syncback::{
+				keep_file_refs, rename_path, serialize_node_properties, validate_new_properties, validate_properties,
+				verify_claim, verify_name, verify_path,
+			},
+		},
+		meta::{Context, Meta, NodePath, ReadOnly, ReadOnlyReason, ReadOnlyRejection, Source, SourceEntry, SourceKind},
+		refs::{self, RefPaths},
+		skipped::{FilterRule, RuleKind, SkippedOperation},
 		snapshot::{AddedSnapshot, Snapshot, UpdatedSnapshot},
 		tree::Tree,
 	},
 	ext::PathExt,
-	middleware::{
-		data::{self, write_original_name},
-		dir, Middleware,
-	},
-	project::{Project, ProjectNode},
+	logger::Span,
+	middleware::{data, dir, manifest, storage, Middleware},
+	project::{CollapseMode, LayoutPreference, Project, ProjectNode},
+	t,
 	vfs::Vfs,
 	Properties,
 };
 
+/// Warns about the skipped operation and records it, see `Skipped`
 macro_rules! filter_warn {
-	($id:expr) => {
-		warn!("Instance {} does not pass syncback filter! Skipping..", $id);
+	($filter:expr, $id:expr, $location:expr, $operation:expr, $rule:expr) => {
+		warn!("Instance {} does not pass syncback filter ({})! Skipping..", $id, $rule);
+		$filter.record_skip($id, $location, $operation, $rule, None);
 	};
-	($id:expr, $path:expr) => {
+	($filter:expr, $id:expr, $location:expr, $operation:expr, $rule:expr, $path:expr) => {
 		warn!(
-			"Path: {} (source of instance: {}) does not pass syncback filter! Skipping..",
+			"Path: {} (source of instance: {}) does not pass syncback filter ({})! Skipping..",
 			$path.display(),
-			$id
+			$id,
+			$rule
 		);
+		$filter.record_skip($id, $location, $operation, $rule, Some($path.as_ref()));
 	};
 }
 
-pub fn apply_addition(snapshot: AddedSnapshot, tree: &mut Tree, vfs: &Vfs) -> Result<()> {
-	trace!(
-		"Adding Ref({:?}) '{}' [{}] with parent Ref({:?})",
-		snapshot.id,
-		snapshot.name,
-		snapshot.class,
-		snapshot.parent
+/// Warns about syncback inside of a package, which is disabled unless `managePackages` is set
+fn package_warn(instance: &str, package: &str) {
+	argon_warn!(
+		"{}",
+		t!(
+			"syncback.package",
+			instance = instance.bold(),
+			package = package.bold(),
+			setting = "managePackages".bold()
+		)
+	);
+}
+
+/// Warns about syncback of a placeholder, writing it would overwrite the file that failed to load
+fn load_error_warn(instance: &str, path: &Path) {
+	argon_warn!(
+		"{}",
+		t!(
+			"syncback.load_error",
+			instance = instance.bold(),
+			path = path.display().to_string().bold()
+		)
+	);
+}
+
+/// Warns about syncback of a read-only copy of a directory shared by multiple project nodes
+fn mirror_warn(instance: &str, path: &Path) {
+	argon_warn!(
+		"{}",
+		t!(
+			"syncback.mirror",
+			instance = instance.bold(),
+			path = path.display().to_string().bold(),
+			setting = "$primary: true".bold()
+		)
+	);
+}
+
+/// Warns about references to instances outside of the synced tree, they are written as placeholders
+fn external_refs_warn(id: Ref, ref_paths: &RefPaths, tree: &Tree) {
+	for (property, _) in ref_paths.iter().filter(|(_, path)| *path == EXTERNAL_REF) {
+		argon_warn!(
+			"{}",
+			t!(
+				"syncback.external_ref",
+				instance = tree.get_full_name(id).unwrap_or_default().bold(),
+				property = property.bold(),
+				placeholder = EXTERNAL_REF.bold()
+			)
+		);
+	}
+}
+
+/// Whether the instance belongs to a copy of a shared directory that is not synced back
+fn is_read_only_mirror(id: Ref, tree: &Tree) -> bool {
+	match mirrors::find_mirror(id, tree) {
+		Some(mirror) if !mirror.primary => {
+			mirror_warn(&tree.get_full_name(id).unwrap_or_default(), &mirror.path);
+			true
+		}
+		_ => false,
+	}
+}
+
+/// Returns the read-only source the instance belongs to, either its own
+/// or one of its ancestors', as its descendants share the source
+pub fn find_read_only(id: Ref, tree: &Tree) -> Option<ReadOnly> {
+	let mut current = id;
+
+	loop {
+		if let Some(read_only) = tree.get_meta(current).and_then(|meta| meta.read_only.as_ref()) {
+			return Some(read_only.clone());
+		}
+
+		current = tree.get_instance(current)?.parent();
+
+		if current.is_none() {
+			return None;
+		}
+	}
+}
+
+/// Returns the read-only source of the asset manifest entry
+/// the instance comes from, unless the entry allows children
+fn find_sealed_manifest(id: Ref, tree: &Tree, vfs: &Vfs) -> Option<ReadOnly> {
+	match tree.get_meta(id)?.source.get_manifest()? {
+		SourceEntry::Manifest(path, key) if !manifest::allows_children(path, key, vfs) => {
+			Some(ReadOnly::new(ReadOnlyReason::AssetManifest, path))
+		}
+		_ => None,
+	}
+}
+
+/// Warns about and records syncback of an instance from a read-only source,
+/// returns the rejection as an error so the client can be told about it
+fn reject_read_only(
+	id: Ref,
+	instance: &str,
+	operation: SkippedOperation,
+	read_only: &ReadOnly,
+	context: &Context,
+) -> Result<()> {
+	let rejection = ReadOnlyRejection::new(id, instance, operation, read_only);
+
+	argon_warn!("{}", rejection);
+
+	context.syncback_filter().record_skip(
+		id,
+		instance,
+		operation,
+		FilterRule::new(RuleKind::ReadOnly, read_only.reason.to_string()),
+		Some(&read_only.path),
 	);
 
+	Err(rejection.into())
+}
+
+/// Returns the path of the placeholder if the instance is one
+fn get_load_error_path(id: Ref, tree: &Tree) -> Option<PathBuf> {
+	tree.get_meta(id)
+		.and_then(|meta| meta.load_error.as_ref())
+		.map(|load_error| load_error.path.clone())
+}
+
+/// Package links are managed by Roblox and never written, packages
+/// themselves are skipped as a whole unless their internals are managed
+fn is_unmanaged_package(snapshot: &Snapshot, context: &Context) -> bool {
+	if snapshot.class == PACKAGE_LINK {
+		trace!(id:% = snapshot.id; "Skipping package link");
+		return true;
+	}
+
+	if !context.manage_packages() && packages::is_package(snapshot) {
+		package_warn(&snapshot.name, &snapshot.name);
+		return true;
+	}
+
+	false
+}
+
+/// Returns full name of the unmanaged package that children of `parent` belong to
+fn get_unmanaged_package(parent: Ref, context: &Context, tree: &Tree) -> Option<String> {
+	if context.manage_packages() {
+		return None;
+	}
+
+	tree.get_full_name(packages::find_package(parent, tree)?)
+}
+
+/// Instance created from a project file (e.g. the root DataModel) is sourced
+/// from the file itself, so it has to be resolved to its root project node.
+/// The root of the tree uses the node of the already loaded project, only
+/// nested project files have to be read again
+fn resolve_project_root(meta: &Meta, name: &str, loaded: Option<ProjectNode>) -> Result<SourceKind> {
+	match meta.source.get() {
+		SourceKind::Path(path)
+			if meta
+				.source
+				.relevant()
+				.iter()
+				.any(|entry| matches!(entry, SourceEntry::Project(project) if project == path)) =>
+		{
+			trace!(path:% = path.display(); "Resolving project root source");
+
+			let node = match loaded {
+				Some(node) => node,
+				None => Project::load(path)?.node,
+			};
+
+			Ok(SourceKind::Project(
+				name.to_owned(),
+				path.to_owned(),
+				Box::new(node),
+				NodePath::new(),
+			))
+		}
+		source => Ok(source.clone()),
+	}
+}
+
+/// Node of the loaded project if the instance is the root of the tree
+fn loaded_root(id: Ref, tree: &Tree) -> Option<ProjectNode> {
+	tree.project_node().filter(|_| id == tree.root_ref()).cloned()
+}
+
+/// Instance is ephemeral if it has the ephemeral attribute set, its
+/// parent is ephemeral or it is a descendant of one of the scratch paths
+fn is_ephemeral(snapshot: &Snapshot, parent_id: Ref, parent_meta: &Meta, tree: &Tree) -> bool {
+	if parent_meta.source.is_ephemeral() {
+		return true;
+	}
+
+	if let Some(Variant::Attributes(attributes)) = snapshot.properties.get(&ustr("Attributes")) {
+		if attributes.get(EPHEMERAL_ATTRIBUTE) == Some(&Variant::Bool(true)) {
+			return true;
+		}
+	}
+
+	let scratch_paths = parent_meta.context.scratch_paths();
+
+	if scratch_paths.is_empty() {
+		return false;
+	}
+
+	// Full name starts with the name of the root which is not part of scratch paths
+	let parent_path = tree
+		.get_full_name(parent_id)
+		.and_then(|name| name.split_once('.').map(|(_, path)| path.to_owned()))
+		.unwrap_or_default();
+
+	scratch_paths
+		.iter()
+		.any(|scratch| parent_path == *scratch || parent_path.starts_with(&format!("{}.", scratch)))
+}
+
+/// Inserts instance and all of its descendants into the tree without touching the file system
+fn insert_ephemeral(snapshot: Snapshot, parent_id: Ref, context: &Context, tree: &mut Tree) {
+	let mut stack = vec![(snapshot, parent_id)];
+
+	while let Some((mut snapshot, parent_id)) = stack.pop() {
+		let id = snapshot.id;
+		let children = mem::take(&mut snapshot.children);
+		let snapshot = snapshot.with_meta(Meta::new().with_context(context).with_source(Source::ephemeral()));
+
+		tree.insert_instance_with_ref(snapshot, parent_id);
+
+		stack.extend(children.into_iter().rev().map(|child| (child, id)));
+	}
+}
+
+/// Applies name, class and property changes to the ephemeral instance in memory
+fn update_ephemeral(snapshot: UpdatedSnapshot, tree: &mut Tree) {
+	let Some(instance) = tree.get_instance_mut(snapshot.id) else {
+		return;
+	};
+
+	if let Some(name) = snapshot.name {
+		instance.name = name;
+	}
+
+	if let Some(class) = snapshot.class {
+		instance.class = class;
+	}
+
+	if let Some(properties) = snapshot.properties {
+		instance.properties = properties;
+	}
+}
+
+/// Moves sources of all descendants of the instance whose folder got renamed,
+/// file system events are paused during syncback so they won't do that
+fn relocate_descendants(id: Ref, from: &Path, to: &Path, tree: &mut Tree) {
+	let mut stack = tree.inner().get_by_ref(id).unwrap().children().to_vec();
+
+	while let Some(id) = stack.pop() {
+		if let Some(mut meta) = tree.get_meta(id).cloned() {
+			meta.source.relocate(from, to);
+			tree.update_meta(id, meta);
+		}
+
+		stack.extend(tree.inner().get_by_ref(id).unwrap().children());
+	}
+}
+
+pub fn apply_addition(snapshot: AddedSnapshot, tree: &mut Tree, vfs: &Vfs) -> Result<()> {
+	let origin = Origin::of_child(snapshot.id, snapshot.parent, &snapshot.name, &snapshot.class, tree);
+
+	let _span = Span::new("apply_addition")
+		.with_field("id", snapshot.id)
+		.with_field("name", &snapshot.name)
+		.with_field("class", snapshot.class)
+		.with_field("path", &origin.path)
+		.enter();
+
+	let id = snapshot.id;
+
+	add(snapshot, tree, vfs)
+		.and_then(|_| rewrite_subtree_refs(id, tree, vfs))
+		.map_err(|err| origin.attach(err))
+}
+
+/// References of added instances are written once the whole subtree is in
+/// the tree, as they can point at instances that were added along with them
+fn rewrite_subtree_refs(id: Ref, tree: &mut Tree, vfs: &Vfs) -> Result<()> {
+	let mut stack = vec![id];
+
+	while let Some(id) = stack.pop() {
+		let Some(instance) = tree.get_instance(id) else {
+			continue;
+		};
+
+		stack.extend(instance.children());
+
+		if instance
+			.properties
+			.values()
+			.any(|value| matches!(value, Variant::Ref(_)))
+		{
+			rewrite_refs(id, tree, vfs)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Rewrites properties of the instance if paths of its references are outdated,
+/// e.g. because the instances they point at were renamed or just added
+fn rewrite_refs(id: Ref, tree: &mut Tree, vfs: &Vfs) -> Result<()> {
+	if tree.get_instance(id).is_none() {
+		return Ok(());
+	}
+
+	let properties = memory::materialize(id, tree, vfs)?;
+
+	let ref_paths = refs::to_paths(id, &properties, tree);
+
+	if tree.get_meta(id).is_none_or(|meta| meta.ref_paths == ref_paths) {
+		return Ok(());
+	}
+
+	trace!(id:% = id, ref_paths:? = ref_paths; "Rewriting references");
+
+	external_refs_warn(id, &ref_paths, tree);
+	tree.set_ref_paths(id, ref_paths);
+
+	let mut snapshot = UpdatedSnapshot::new(id);
+	snapshot.properties = Some(properties);
+
+	update(snapshot, tree, vfs)
+}
+
+fn add(snapshot: AddedSnapshot, tree: &mut Tree, vfs: &Vfs) -> Result<()> {
 	if !tree.exists(snapshot.parent) {
 		warn!(
 			"Attempted to add instance: {:?} whose parent doesn't exist: {:?}",
@@ -52,6 +400,28 @@ pub fn apply_addition(snapshot: AddedSnapshot, tree: &mut Tree, vfs: &Vfs) -> Re
 		return Ok(());
 	}
 
+	if let Some(read_only) =
+		find_read_only(snapshot.parent, tree).or_else(|| find_sealed_manifest(snapshot.parent, tree, vfs))
+	{
+		let instance = format!(
+			"{}.{}",
+			tree.get_full_name(snapshot.parent).unwrap_or_default(),
+			snapshot.name
+		);
+		let context = &tree.get_meta(snapshot.parent).unwrap().context;
+
+		return reject_read_only(snapshot.id, &instance, SkippedOperation::Add, &read_only, context);
+	}
+
+	if let Some(path) = get_load_error_path(snapshot.parent, tree) {
+		load_error_warn(&tree.get_full_name(snapshot.parent).unwrap_or_default(), &path);
+		return Ok(());
+	}
+
+	if is_read_only_mirror(snapshot.parent, tree) {
+		return Ok(());
+	}
+
 	let parent_id = snapshot.parent;
 	let mut snapshot = Snapshot::from(snapshot);
 	let parent_instance = tree.get_instance(parent_id).unwrap();
@@ -59,1354 +429,1548 @@ pub fn apply_addition(snapshot: AddedSnapshot, tree: &mut Tree, vfs: &Vfs) -> Re
 	let filter = parent_meta.context.syncback_filter();
 
 	trace!(
-		"Parent Instance: ID={:?}, Name='{}', Class='{}'",
-		parent_id,
-		parent_instance.name,
-		parent_instance.class
+		parent:% = parent_id,
+		parent_name = parent_instance.name.as_str(),
+		parent_class = parent_instance.class.as_str(),
+		parent_path:? = parent_meta.source.get().path();
+		"Resolved parent"
 	);
-	trace!("Parent Meta Source: {:?}", parent_meta.source);
-	if let Some(path) = parent_meta.source.get().path() {
-		trace!("Parent VFS Path Lookup: SUCCESS -> {}", path.display());
-	} else {
-		trace!("Parent VFS Path Lookup: FAILED -> No path found in source");
+
+	// Hierarchies deeper than `maxTreeDepth` are rejected before anything is written
+	let location = format!(
+		"{}.{}",
+		tree.get_full_name(parent_id).unwrap_or_default(),
+		snapshot.name
+	);
+
+	parent_meta.context.check_depth(snapshot.height() + 1, &location)?;
+
+	if is_ephemeral(&snapshot, parent_id, &parent_meta, tree) {
+		trace!("Instance is ephemeral, keeping it in memory only");
+		insert_ephemeral(snapshot, parent_id, &parent_meta.context, tree);
+		return Ok(());
+	}
+
+	if let Some(rule) = filter.find_instance(&snapshot.name, &snapshot.class) {
+		filter_warn!(filter, snapshot.id, &location, SkippedOperation::Add, rule);
+		return Ok(());
+	}
+
+	if let Some(package) = get_unmanaged_package(parent_id, &parent_meta.context, tree) {
+		let parent = tree.get_full_name(parent_id).unwrap_or_default();
+
+		package_warn(&format!("{}.{}", parent, snapshot.name), &package);
+		return Ok(());
 	}
 
-	if filter.matches_name(&snapshot.name) || filter.matches_class(&snapshot.class) {
-		filter_warn!(snapshot.id);
+	if is_unmanaged_package(&snapshot, &parent_meta.context) {
 		return Ok(());
 	}
 
-	snapshot.properties = validate_properties(snapshot.properties, filter);
+	snapshot.properties = validate_new_properties(
+		snapshot.id,
+		&snapshot.class,
+		mem::take(&mut snapshot.properties),
+		&parent_meta.context,
+	);
+
+	fn locate_instance_data(is_dir: bool, path: &Path, snapshot: &Snapshot, parent_meta: &Meta) -> Result<SourceEntry> {
+		let located = storage::for_new(&parent_meta.context).locate(&snapshot.name, path, is_dir, &parent_meta.context);
 
-	fn locate_instance_data(is_dir: bool, path: &Path, snapshot: &Snapshot, parent_meta: &Meta) -> Result<PathBuf> {
 		trace!(
-			"locate_instance_data: Entering function with is_dir={}, path={}, snapshot_name={}",
 			is_dir,
-			path.display(),
-			snapshot.name
+			parent:% = path.display(),
+			entry:? = located;
+			"Located instance data"
 		);
-		let result = parent_meta
-			.context
-			.sync_rules_of_type(&Middleware::InstanceData, true)
-			.iter()
-			.find_map(|rule| {
-				trace!("locate_instance_data: Checking rule: {:?}", rule);
-				let located = rule.locate(path, &snapshot.name, is_dir);
-				trace!("locate_instance_data: Rule locate result: {:?}", located);
-				located
-			})
-			.with_context(|| format!("Failed to locate data path for parent: {}", path.display()));
-		trace!("locate_instance_data: Result: {:?}", result);
-		trace!("locate_instance_data: Exiting function");
-		result
+
+		located.with_context(|| format!("Failed to locate data path for parent: {}", path.display()))
 	}
 
 	fn write_instance(
-		has_children: bool,
+		location: &str,
 		path: &mut PathBuf,
 		snapshot: &mut Snapshot,
 		parent_meta: &Meta,
+		claims: &Claims,
+		collision: &mut Option<Collision>,
 		vfs: &Vfs,
 	) -> Result<Option<Meta>> {
-		trace!(
-			"write_instance: Entering function with has_children={}, path={}, snapshot_name={}, snapshot_class={}",
-			has_children,
-			path.display(),
-			snapshot.name,
-			snapshot.class
-		);
 		let mut meta = snapshot.meta.clone().with_context(&parent_meta.context);
 		let filter = parent_meta.context.syncback_filter();
-		let mut properties = snapshot.properties.clone();
+		let legacy_scripts = parent_meta.context.use_legacy_scripts();
+
+		let has_children = !snapshot.children.is_empty();
 
-		trace!("write_instance: Initial meta: {:?}", meta);
-		trace!("write_instance: Initial properties: {:?}", properties);
+		// Instances with children always need a folder, the rest follow the preference
+		let as_folder =
+			has_children || parent_meta.context.layout_preference(&snapshot.class) == LayoutPreference::Folder;
+
+		let mut claim = |path: &mut PathBuf, snapshot: &mut Snapshot, meta: &mut Meta| {
+			let contested = path.clone();
+			let name = snapshot.name.clone();
+
+			if let Some(other) = verify_claim(path, &mut snapshot.name, meta, claims, vfs) {
+				trace!(path:% = contested.display(), other:% = other; "Path already claimed");
+
+				*collision = Some(Collision {
+					id: snapshot.id,
+					other,
+					name,
+					path: contested,
+					renamed: path.clone(),
+				});
+			}
+		};
 
 		if let Some(middleware) = Middleware::from_class(
 			&snapshot.class,
-			if !parent_meta.context.use_legacy_scripts() {
-				trace!("write_instance: Using modern script handling");
-				Some(&mut properties)
+			if !legacy_scripts {
+				Some(&snapshot.properties)
 			} else {
-				trace!("write_instance: Using legacy script handling");
 				None
 			},
 		) {
-			trace!("write_instance: Found middleware: {:?}", middleware);
-			let mut file_path = parent_meta
-				.context
-				.sync_rules_of_type(&middleware, true)
-				.iter()
-				.find_map(|rule| {
-					trace!("write_instance: Checking file sync rule: {:?}", rule);
-					let located = rule.locate(path, &snapshot.name, has_children);
-					trace!("write_instance: Rule locate result: {:?}", located);
-					located
-				})
-				.with_context(|| format!("Failed to locate file path for parent: {}", path.display()))?;
+			let locate_file = |path: &Path, name: &str| {
+				parent_meta
+					.context
+					.sync_rules_of_type(&middleware, true)
+					.iter()
+					.find_map(|rule| rule.locate(path, name, as_folder))
+					.with_context(|| format!("Failed to locate file path for parent: {}", path.display()))
+			};
+
+			let mut file_path = locate_file(path, &snapshot.name)?;
 
-			trace!("write_instance: Located file path: {}", file_path.display());
+			trace!(
+				middleware:? = middleware,
+				legacy_scripts,
+				has_children,
+				as_folder,
+				file_path:% = file_path.display();
+				"Located file"
+			);
 
-			if has_children {
-				trace!("write_instance: Handling instance with children (directory like)");
-				if filter.matches_path(path) {
-					filter_warn!(snapshot.id, path);
-					trace!("write_instance: Exiting function early (directory path filtered)");
+			if as_folder {
+				if let Some(rule) = filter.find_path(path) {
+					filter_warn!(filter, snapshot.id, location, SkippedOperation::Add, rule, path);
 					return Ok(None);
 				}
 
+				claim(path, snapshot, &mut meta);
+
 				if !verify_path(path, &mut snapshot.name, &mut meta, vfs) {
-					trace!("write_instance: Exiting function early (directory path verification failed)");
+					trace!(path:% = path.display(); "Directory path verification failed");
 					return Ok(None);
 				}
 
-				trace!(
-					"write_instance: Verified directory path: {}, updated name: {}, updated meta: {:?}",
-					path.display(),
-					snapshot.name,
-					meta
-				);
+				// Directory might have been renamed during verification
+				file_path = locate_file(path, &snapshot.name)?;
 
 				dir::write_dir(path, vfs)?;
 
-				trace!("write_instance: Wrote directory: {}", path.display());
-
 				meta.set_source(Source::child_file(path, &file_path));
-				trace!("write_instance: Set meta source to child_file: {:?}", meta.source);
 			} else {
-				trace!("write_instance: Handling instance without children (file like)");
+				claim(&mut file_path, snapshot, &mut meta);
+
 				if !verify_path(&mut file_path, &mut snapshot.name, &mut meta, vfs) {
-					trace!("write_instance: Exiting function early (file path verification failed)");
+					trace!(path:% = file_path.display(); "File path verification failed");
 					return Ok(None);
 				}
-				trace!(
-					"write_instance: Verified file path: {}, updated name: {}, updated meta: {:?}",
-					file_path.display(),
-					snapshot.name,
-					meta
-				);
 
 				meta.set_source(Source::file(&file_path));
-				trace!("write_instance: Set meta source to file: {:?}", meta.source);
 			}
 
-			if filter.matches_path(&file_path) {
-				filter_warn!(snapshot.id, &file_path);
-				trace!("write_instance: Exiting function early (file path filtered)");
+			if let Some(rule) = filter.find_path(&file_path) {
+				filter_warn!(filter, snapshot.id, location, SkippedOperation::Add, rule, &file_path);
 				return Ok(None);
 			}
 
-			let properties = middleware.write(properties, &file_path, vfs)?;
-			trace!(
-				"write_instance: Middleware wrote to file: {}, remaining properties: {:?}",
-				file_path.display(),
-				properties
-			);
-			let data_path = locate_instance_data(has_children, path, snapshot, parent_meta)?;
-			trace!("write_instance: Located data path: {}", data_path.display());
+			let mut properties = middleware.write(&snapshot.properties, &file_path, &meta.context, vfs)?;
+
+			if !legacy_scripts {
+				Middleware::strip_run_context(&snapshot.class, &mut properties);
+			}
 
-			if filter.matches_path(&data_path) {
-				filter_warn!(snapshot.id, &data_path);
-				trace!("write_instance: Data path filtered, skipping data write.");
+			let data_entry = locate_instance_data(as_folder, path, snapshot, parent_meta)?;
+
+			if let Some(rule) = filter.find_path(data_entry.path()) {
+				filter_warn!(
+					filter,
+					snapshot.id,
+					location,
+					SkippedOperation::Add,
+					rule,
+					data_entry.path()
+				);
 			} else {
-				let data_path = data::write_data(true, &snapshot.class, properties, &data_path, &meta, vfs)?;
-				trace!("write_instance: Wrote data to path: {:?}", data_path);
-				meta.source.set_data(data_path);
-				trace!("write_instance: Updated meta source with data path: {:?}", meta.source);
+				let data_entries =
+					storage::of(&data_entry).write(&data_entry, true, &snapshot.class, properties, &meta, vfs)?;
+				meta.source.set_data_entries(data_entries);
 			}
 		} else {
-			trace!(
-				"write_instance: No specific middleware found for class: {}",
-				snapshot.class
-			);
-			if filter.matches_path(path) {
-				filter_warn!(snapshot.id, path);
-				trace!("write_instance: Exiting function early (directory path filtered)");
+			if let Some(rule) = filter.find_path(path) {
+				filter_warn!(filter, snapshot.id, location, SkippedOperation::Add, rule, path);
 				return Ok(None);
 			}
 
+			claim(path, snapshot, &mut meta);
+
 			if !verify_path(path, &mut snapshot.name, &mut meta, vfs) {
-				trace!("write_instance: Exiting function early (directory path verification failed)");
+				trace!(path:% = path.display(); "Directory path verification failed");
 				return Ok(None);
 			}
 
-			trace!(
-				"write_instance: Verified directory path: {}, updated name: {}, updated meta: {:?}",
-				path.display(),
-				snapshot.name,
-				meta
-			);
-
 			dir::write_dir(path, vfs)?;
 
-			trace!("write_instance: Wrote directory: {}", path.display());
-
 			meta.set_source(Source::directory(path));
-			trace!("write_instance: Set meta source to directory: {:?}", meta.source);
 
-			let data_path = locate_instance_data(true, path, snapshot, parent_meta)?;
-			trace!("write_instance: Located data path: {}", data_path.display());
+			let data_entry = locate_instance_data(true, path, snapshot, parent_meta)?;
 
-			if filter.matches_path(&data_path) {
-				filter_warn!(snapshot.id, &data_path);
-				trace!("write_instance: Data path filtered, skipping data write.");
+			if let Some(rule) = filter.find_path(data_entry.path()) {
+				filter_warn!(
+					filter,
+					snapshot.id,
+					location,
+					SkippedOperation::Add,
+					rule,
+					data_entry.path()
+				);
 			} else {
-				let data_path = data::write_data(false, &snapshot.class, properties, &data_path, &meta, vfs)?;
-				trace!("write_instance: Wrote data to path: {:?}", data_path);
-				meta.source.set_data(data_path);
-				trace!("write_instance: Updated meta source with data path: {:?}", meta.source);
+				let data_entries = storage::of(&data_entry).write(
+					&data_entry,
+					false,
+					&snapshot.class,
+					snapshot.properties.clone(),
+					&meta,
+					vfs,
+				)?;
+				meta.source.set_data_entries(data_entries);
 			}
 		}
 
-		trace!("write_instance: Exiting function successfully with meta: {:?}", meta);
+		trace!(name = snapshot.name.as_str(), source:? = meta.source; "Wrote instance");
+
 		Ok(Some(meta))
 	}
 
+	/// Children of an instance written as a folder, added after the instance itself
+	struct PendingChildren {
+		parent_id: Ref,
+		path: PathBuf,
+		meta: Meta,
+		children: vec::IntoIter<Snapshot>,
+		/// Reported once all descendants are added
+		collision: Option<Collision>,
+	}
+
 	fn add_non_project_instances(
 		parent_id: Ref,
 		parent_path: &Path,
-		mut snapshot: Snapshot,
+		snapshot: Snapshot,
 		parent_meta: &mut Meta,
 		tree: &mut Tree,
 		vfs: &Vfs,
 	) -> Result<Source> {
-		trace!(
-			"add_non_project_instances: Entering function with parent_id={:?}, parent_path={}, snapshot_name={}",
-			parent_id,
-			parent_path.display(),
-			snapshot.name
-		);
+		let (parent_source, pending) =
+			add_non_project_instance(parent_id, parent_path, snapshot, parent_meta, tree, vfs)?;
+
+		// Descendants are added depth-first in their original order,
+		// with explicit stack so hierarchies of any depth can be added
+		let mut stack: Vec<PendingChildren> = pending.into_iter().collect();
+
+		while let Some(pending) = stack.last_mut() {
+			let Some(mut child) = pending.children.next() else {
+				let pending = stack.pop().unwrap();
+				report_collision(pending.collision, tree);
+				continue;
+			};
+
+			child.properties = validate_new_properties(
+				child.id,
+				&child.class,
+				mem::take(&mut child.properties),
+				&pending.meta.context,
+			);
+
+			let (_, child_pending) =
+				add_non_project_instance(pending.parent_id, &pending.path, child, &mut pending.meta, tree, vfs)?;
+
+			stack.extend(child_pending);
+		}
+
+		Ok(parent_source)
+	}
+
+	fn add_non_project_instance(
+		parent_id: Ref,
+		parent_path: &Path,
+		mut snapshot: Snapshot,
+		parent_meta: &mut Meta,
+		tree: &mut Tree,
+		vfs: &Vfs,
+	) -> Result<(Source, Option<PendingChildren>)> {
+		if is_unmanaged_package(&snapshot, &parent_meta.context) {
+			return Ok((parent_meta.source.clone(), None));
+		}
+
 		let config = Config::new();
 
+		// Folder transformation of the parent and files of the instance are written together
+		let transaction = vfs.transaction();
+
 		let mut parent_path = parent_path.to_owned();
-		trace!(
-			"add_non_project_instances: Initial parent_path: {}",
-			parent_path.display()
-		);
 
 		// Transform parent instance source from file to folder
 		let parent_source = if vfs.is_file(&parent_path) {
-			trace!(
-				"add_non_project_instances: Parent path {} is a file, transforming to folder source",
-				parent_path.display()
-			);
+			let parent_class = tree
+				.get_instance(parent_id)
+				.map(|parent| parent.class)
+				.unwrap_or_default();
+
+			// Preferred file layout takes precedence, so it is transformed back as well
+			if parent_meta.context.collapse_mode() == CollapseMode::Never
+				&& parent_meta.context.layout_preference(&parent_class) != LayoutPreference::File
+			{
+				argon_warn!(
+					"{}",
+					t!(
+						"syncback.expansion_disabled",
+						name = snapshot.name.bold(),
+						path = parent_path.to_string().bold()
+					)
+				);
+
+				return Ok((parent_meta.source.clone(), None));
+			}
+
 			let sync_rule = parent_meta
 				.context
 				.sync_rules()
-				.iter()
 				.filter(|rule| {
 					if let Some(pattern) = rule.child_pattern.as_ref() {
-						let skip = (pattern.as_str().starts_with(".src") || pattern.as_str().ends_with(".data.json"))
-							&& config.rojo_mode;
-						trace!(
-							"add_non_project_instances: Filtering sync rule: {:?}, skip={}",
-							rule,
-							skip
-						);
-						!skip
+						!((pattern.as_str().starts_with(".src") || pattern.as_str().ends_with(".data.json"))
+							&& config.rojo_mode)
 					} else {
 						true
 					}
 				})
-				.find(|rule| {
-					let matches = rule.matches(&parent_path);
-					trace!(
-						"add_non_project_instances: Checking sync rule {:?} against path {}: matches={}",
-						rule,
-						parent_path.display(),
-						matches
-					);
-					matches
-				})
+				.find(|rule| rule.matches(&parent_path))
 				.with_context(|| format!("Failed to find sync rule for path: {}", parent_path.display()))?
 				.clone();
 
-			trace!("add_non_project_instances: Found sync rule: {:?}", sync_rule);
-
 			let name = sync_rule.get_name(&parent_path);
-			trace!("add_non_project_instances: Extracted name from sync rule: {}", name);
 			let mut folder_path = parent_path.with_file_name(&name);
-			trace!(
-				"add_non_project_instances: Proposed folder path: {}",
-				folder_path.display()
-			);
 
 			if !verify_path(&mut folder_path, &mut snapshot.name, parent_meta, vfs) {
-				trace!(
-					"add_non_project_instances: Folder path verification failed, returning original parent source: {:?}",
-					parent_meta.source
-				);
-				return Ok(parent_meta.source.clone());
+				trace!(path:% = folder_path.display(); "Folder path verification failed");
+				return Ok((parent_meta.source.clone(), None));
 			}
 
-			trace!(
-				"add_non_project_instances: Verified folder path: {}, updated name: {}, updated meta: {:?}",
-				folder_path.display(),
-				snapshot.name,
-				parent_meta
-			);
-
 			let file_path = sync_rule
 				.locate(&folder_path, &name, true)
 				.with_context(|| format!("Failed to locate file path for parent: {}", folder_path.display()))?;
 
-			trace!(
-				"add_non_project_instances: Located new file path within folder: {}",
-				file_path.display()
-			);
-
 			let data_paths = if let Some(data) = parent_meta.source.get_data() {
-				trace!(
-					"add_non_project_instances: Found existing data path: {}",
-					data.path().display()
-				);
 				let new_path = parent_meta
 					.context
-					.sync_rules_of_type(&Middleware::InstanceData, true)
+					.data_rules(Some(data.path()))
 					.iter()
 					.find_map(|rule| rule.locate(&folder_path, &name, true))
 					.with_context(|| format!("Failed to locate data path for parent: {}", folder_path.display()))?;
 
-				trace!(
-					"add_non_project_instances: Located new data path: {}",
-					new_path.display()
-				);
 				Some((data.path().to_owned(), new_path))
 			} else {
-				trace!("add_non_project_instances: No existing data path found.");
 				None
 			};
 
+			trace!(
+				rule:? = sync_rule,
+				from:% = parent_path.display(),
+				to:% = folder_path.display(),
+				file:% = file_path.display(),
+				data:? = data_paths;
+				"Expanding parent file into folder"
+			);
+
 			let mut source = Source::child_file(&folder_path, &file_path);
-			trace!("add_non_project_instances: Created new child_file source: {:?}", source);
 
 			dir::write_dir(&folder_path, vfs)?;
-			trace!(
-				"add_non_project_instances: Wrote new directory: {}",
-				folder_path.display()
-			);
 			vfs.rename(&parent_path, &file_path)?;
-			trace!(
-				"add_non_project_instances: Renamed original file {} to {}",
-				parent_path.display(),
-				file_path.display()
-			);
 
 			if let Some(data_paths) = data_paths {
-				trace!(
-					"add_non_project_instances: Processing data path rename from {} to {}",
-					data_paths.0.display(),
-					data_paths.1.display()
-				);
-				source.add_data(&data_paths.1);
-				vfs.rename(&data_paths.0, &data_paths.1)?;
-				trace!(
-					"add_non_project_instances: Renamed data file and updated source: {:?}",
-					source
-				);
+				for path in data::move_data(&data_paths.0, &data_paths.1, vfs)? {
+					source.add_data(&path);
+				}
+			}
+
+			// Data index stays in the same directory, only its key can change
+			if let Some(index) = parent_meta.source.get_index() {
+				source.extend_relevant(vec![storage::rename_entry(index, folder_path.get_name(), vfs)?]);
 			}
 
 			parent_path = folder_path;
-			trace!(
-				"add_non_project_instances: Updated parent_path to new folder path: {}",
-				parent_path.display()
-			);
 
 			source
 		} else {
-			trace!("add_non_project_instances: Parent path {} is already a directory or does not exist, using original source: {:?}", parent_path.display(), parent_meta.source);
 			parent_meta.source.clone()
 		};
 
 		if !verify_name(&mut snapshot.name, &mut snapshot.meta) {
-			trace!(
-				"add_non_project_instances: Name verification failed for {}, returning parent source: {:?}",
-				snapshot.name,
-				parent_source
-			);
-			return Ok(parent_source);
+			trace!(name = snapshot.name.as_str(); "Name verification failed");
+			transaction.commit()?;
+			return Ok((parent_source, None));
 		}
 
+		let mut path = parent_path.join(&snapshot.name);
+
+		let claims = Claims::of_children(parent_id, tree);
+		let mut collision = None;
+
+		let parent_class = tree.get_instance(parent_id).unwrap().class;
+		let context = parent_meta.context.with_parent_class(parent_class);
+
+		let location = format!(
+			"{}.{}",
+			tree.get_full_name(parent_id).unwrap_or_default(),
+			snapshot.name
+		);
+
+		let has_children = !snapshot.children.is_empty();
+		let written = write_instance(
+			&location,
+			&mut path,
+			&mut snapshot,
+			parent_meta,
+			&claims,
+			&mut collision,
+			vfs,
+		)?;
+
+		transaction.commit()?;
+
 		trace!(
-			"add_non_project_instances: Verified name: {}, updated meta: {:?}",
-			snapshot.name,
-			snapshot.meta
+			id:% = snapshot.id,
+			path:% = path.display(),
+			has_children,
+			written = written.is_some();
+			"Inserting instance"
 		);
 
-		let mut path = parent_path.join(&snapshot.name);
-		trace!("add_non_project_instances: Constructed child path: {}", path.display());
-
-		if snapshot.children.is_empty() {
-			trace!("add_non_project_instances: Snapshot has no children, writing as potential file");
-			if let Some(meta) = write_instance(false, &mut path, &mut snapshot, parent_meta, vfs)? {
-				trace!("add_non_project_instances: write_instance succeeded, meta: {:?}", meta);
-				let snapshot_id = snapshot.id;
-				let snapshot = snapshot.with_meta(meta);
-				tree.insert_instance_with_ref(snapshot.clone(), parent_id);
-				trace!(
-					"add_non_project_instances: Inserted instance into tree: {:?}",
-					snapshot_id
-				);
-			} else {
-				trace!("add_non_project_instances: write_instance returned None, instance not added.");
+		if !has_children {
+			if let Some(meta) = written {
+				let snapshot = snapshot.with_meta(meta.with_context(&context));
+				tree.insert_instance_with_ref(snapshot, parent_id);
 			}
-		} else if let Some(mut meta) = write_instance(true, &mut path, &mut snapshot, parent_meta, vfs)? {
-			trace!("add_non_project_instances: Snapshot has children, writing as potential directory");
-			trace!("add_non_project_instances: write_instance succeeded, meta: {:?}", meta);
+		} else if let Some(mut meta) = written {
+			meta.set_context(&context);
+
 			let snapshot_id = snapshot.id;
-			let snapshot = snapshot.with_meta(meta.clone());
+			let mut snapshot = snapshot.with_meta(meta.clone());
+			let children = mem::take(&mut snapshot.children);
 
-			tree.insert_instance_with_ref(snapshot.clone(), parent_id);
-			trace!(
-				"add_non_project_instances: Inserted instance into tree: {:?}",
-				snapshot_id
-			);
+			tree.insert_instance_with_ref(snapshot, parent_id);
 
-			for mut child in snapshot.children {
-				trace!("add_non_project_instances: Processing child: {:?}", child.id);
-				child.properties = validate_properties(child.properties.clone(), meta.context.syncback_filter());
-				trace!(
-					"add_non_project_instances: Validated child properties: {:?}",
-					child.properties
-				);
-				add_non_project_instances(snapshot.id, &path, child, &mut meta, tree, vfs)?;
-			}
-		} else {
-			trace!("add_non_project_instances: write_instance returned None, instance and children not added.");
+			let pending = PendingChildren {
+				parent_id: snapshot_id,
+				path,
+				meta,
+				children: children.into_iter(),
+				collision,
+			};
+
+			return Ok((parent_source, Some(pending)));
 		}
 
-		trace!(
-			"add_non_project_instances: Exiting function successfully, returning parent_source: {:?}",
-			parent_source
-		);
-		Ok(parent_source)
+		report_collision(collision, tree);
+
+		Ok((parent_source, None))
+	}
+
+	fn report_collision(collision: Option<Collision>, tree: &mut Tree) {
+		if let Some(collision) = collision.filter(|collision| tree.exists(collision.id)) {
+			let other = tree.get_full_name(collision.other).unwrap_or_default();
+			let instance = tree.get_full_name(collision.id).unwrap_or_default();
+
+			argon_warn!(
+				"{}",
+				t!(
+					"syncback.collision",
+					instance = instance.bold(),
+					other = other.bold(),
+					path = collision.path.to_string().bold(),
+					renamed = collision.renamed.to_string().bold()
+				)
+			);
+
+			tree.insert_collision(collision);
+		}
 	}
 
 	fn add_project_instances(
 		parent_id: Ref,
 		path: &Path,
 		node_path: NodePath,
-		mut snapshot: Snapshot,
+		snapshot: Snapshot,
 		parent_node: &mut ProjectNode,
 		parent_meta: &Meta,
 		tree: &mut Tree,
 	) {
-		trace!(
-			"add_project_instances: Entering function with parent_id={:?}, path={}, node_path={:?}, snapshot_name={}",
-			parent_id,
-			path.display(),
-			node_path,
-			snapshot.name
-		);
-		let mut node = ProjectNode {
-			class_name: Some(snapshot.class),
-			properties: serialize_properties(&snapshot.class, snapshot.properties.clone()),
-			..ProjectNode::default()
-		};
-		trace!("add_project_instances: Created initial project node: {:?}", node);
-
-		if snapshot.meta.keep_unknowns {
-			trace!("add_project_instances: Setting keep_unknowns=true on project node");
-			node.keep_unknowns = Some(true);
+		// Nodes of other profiles are not in the tree but still occupy their names
+		if parent_node
+			.tree
+			.get(&snapshot.name)
+			.is_some_and(|node| !node.is_visible(parent_meta.context.profile()))
+		{
+			warn!(
+				"Cannot add {} as its name is already used by a project node of another profile",
+				snapshot.name
+			);
+			return;
 		}
 
-		let node_path = node_path.join(&snapshot.name);
-		trace!("add_project_instances: Constructed new node_path: {:?}", node_path);
-		let source = Source::project(&snapshot.name, path, node.clone(), node_path.clone());
-		trace!("add_project_instances: Created project source: {:?}", source);
-		let meta = snapshot
-			.meta
-			.clone()
-			.with_context(&parent_meta.context)
-			.with_source(source);
-		trace!("add_project_instances: Created final meta: {:?}", meta);
-
-		snapshot.meta = meta;
-		tree.insert_instance_with_ref(snapshot.clone(), parent_id);
-		trace!("add_project_instances: Inserted instance into tree: {:?}", snapshot.id);
-
-		let filter = snapshot.meta.context.syncback_filter();
-		trace!("add_project_instances: Syncback filter for children: {:?}", filter);
-
-		for mut child in snapshot.children {
-			trace!("add_project_instances: Processing child: {:?}", child.id);
-			child.properties = validate_properties(child.properties, filter);
+		// Instances are inserted parent first with explicit stack so hierarchies of any depth
+		// can be added, their nodes are nested in each other starting from the deepest ones
+		let mut nodes: Vec<(Option<usize>, String, ProjectNode)> = vec![];
+		let mut stack = vec![(None, parent_id, node_path, snapshot)];
+
+		while let Some((parent, parent_id, node_path, mut snapshot)) = stack.pop() {
+			if is_unmanaged_package(&snapshot, &parent_meta.context) {
+				continue;
+			}
+
+			let mut node = ProjectNode {
+				class_name: Some(snapshot.class),
+				..ProjectNode::default()
+			};
+			let properties = refs::portable(snapshot.properties.clone(), &snapshot.meta.ref_paths);
+			serialize_node_properties(&mut node, &snapshot.class, properties);
+
+			if snapshot.meta.keep_unknowns {
+				node.keep_unknowns = Some(snapshot.meta.get_keep_unknowns());
+			}
+
+			let node_path = node_path.join(&snapshot.name);
+			let source = Source::project(&snapshot.name, path, node.clone(), node_path.clone());
+			let parent_class = tree.get_instance(parent_id).unwrap().class;
+			let meta = snapshot
+				.meta
+				.clone()
+				.with_context(&parent_meta.context.with_parent_class(parent_class))
+				.with_source(source);
+
+			snapshot.meta = meta;
+
 			trace!(
-				"add_project_instances: Validated child properties: {:?}",
-				child.properties
+				id:% = snapshot.id,
+				path:% = path.display(),
+				node_path:? = node_path;
+				"Inserting project node"
 			);
-			add_project_instances(parent_id, path, node_path.clone(), child, &mut node, parent_meta, tree);
+
+			let children = mem::take(&mut snapshot.children);
+			tree.insert_instance_with_ref(snapshot.clone(), parent_id);
+
+			let index = Some(nodes.len());
+			nodes.push((parent, snapshot.name.clone(), node));
+
+			// Reversed so children are inserted in their original order
+			for mut child in children.into_iter().rev() {
+				child.properties = validate_new_properties(
+					child.id,
+					&child.class,
+					mem::take(&mut child.properties),
+					&snapshot.meta.context,
+				);
+				stack.push((index, snapshot.id, node_path.clone(), child));
+			}
+		}
+
+		while let Some((parent, name, node)) = nodes.pop() {
+			match parent {
+				Some(parent) => nodes[parent].2.tree.insert(name, node),
+				None => parent_node.tree.insert(name, node),
+			};
+		}
+	}
+
+	fn add_manifest_instances(
+		parent_id: Ref,
+		path: &Path,
+		parent_key: &[String],
+		mut snapshot: Snapshot,
+		parent_meta: &Meta,
+		tree: &mut Tree,
+		vfs: &Vfs,
+	) -> Result<()> {
+		let mut stack: Vec<&mut Snapshot> = snapshot.children.iter_mut().collect();
+
+		while let Some(child) = stack.pop() {
+			child.properties = validate_new_properties(
+				child.id,
+				&child.class,
+				mem::take(&mut child.properties),
+				&parent_meta.context,
+			);
+
+			stack.extend(child.children.iter_mut());
 		}
 
-		parent_node.tree.insert(snapshot.name.clone(), node);
 		trace!(
-			"add_project_instances: Inserted node for {} into parent node's tree",
-			snapshot.name
+			manifest:% = path.display(),
+			parent_key:? = parent_key;
+			"Adding to asset manifest entry"
 		);
-		trace!("add_project_instances: Exiting function");
+
+		manifest::add_entry(path, parent_key, &snapshot, vfs)?;
+
+		let parent_class = tree.get_instance(parent_id).unwrap().class;
+		let context = parent_meta.context.with_parent_class(parent_class);
+		let mut stack = vec![(snapshot, parent_id, parent_key.to_vec(), context)];
+
+		while let Some((mut snapshot, parent_id, parent_key, context)) = stack.pop() {
+			let key = [parent_key, vec![snapshot.name.clone()]].concat();
+			let children = mem::take(&mut snapshot.children);
+			let child_context = context.with_parent_class(snapshot.class);
+
+			let meta = snapshot
+				.meta
+				.clone()
+				.with_context(&context)
+				.with_source(Source::manifest(path, &key));
+
+			stack.extend(
+				children
+					.into_iter()
+					.rev()
+					.map(|child| (child, snapshot.id, key.clone(), child_context.clone())),
+			);
+
+			tree.insert_instance_with_ref(snapshot.with_meta(meta), parent_id);
+		}
+
+		Ok(())
 	}
 
-	trace!(
-		"apply_addition: Matching parent source kind: {:?}",
-		parent_meta.source.get()
-	);
-	match parent_meta.source.get().clone() {
+	// Instances added to asset manifest entries are listed in the manifest as well
+	if let Some(SourceEntry::Manifest(path, key)) = parent_meta.source.get_manifest().cloned() {
+		return add_manifest_instances(parent_id, &path, &key, snapshot, &parent_meta, tree, vfs);
+	}
+
+	match resolve_project_root(
+		&parent_meta,
+		&parent_instance.name.clone(),
+		loaded_root(parent_id, tree),
+	)? {
 		SourceKind::Path(path) => {
-			trace!("apply_addition: Parent source is Path: {}", path.display());
+			trace!(path:% = path.display(); "Adding to path");
+
 			let parent_source = add_non_project_instances(parent_id, &path, snapshot, &mut parent_meta, tree, vfs)?;
-			trace!(
-				"apply_addition: add_non_project_instances returned parent_source: {:?}",
-				parent_source
-			);
 
 			parent_meta.set_source(parent_source);
 			tree.update_meta(parent_id, parent_meta);
-			trace!("apply_addition: Updated parent meta in tree with new source");
 		}
 		SourceKind::Project(name, path, node, node_path) => {
-			trace!(
-				"apply_addition: Parent source is Project: name={}, path={}, node_path={:?}",
-				name,
-				path.display(),
-				node_path
-			);
 			if let Some(custom_path) = &node.path {
-				trace!("apply_addition: Parent project node has custom path: {:?}", custom_path);
 				let custom_path = path.with_file_name(custom_path.path()).clean();
-				trace!("apply_addition: Resolved custom path: {}", custom_path.display());
 
-				let parent_source =
-					add_non_project_instances(parent_id, &custom_path, snapshot, &mut parent_meta, tree, vfs)?;
 				trace!(
-					"apply_addition: add_non_project_instances (for custom path) returned parent_source: {:?}",
-					parent_source
+					project:% = path.display(),
+					node_path:? = node_path,
+					path:% = custom_path.display();
+					"Adding to project node path"
 				);
 
+				let parent_source =
+					add_non_project_instances(parent_id, &custom_path, snapshot, &mut parent_meta, tree, vfs)?;
+
 				let parent_source = Source::project(&name, &path, *node.clone(), node_path.clone())
 					.with_relevant(parent_source.relevant().to_owned());
-				trace!("apply_addition: Created new parent project source: {:?}", parent_source);
 
 				parent_meta.set_source(parent_source);
 				tree.update_meta(parent_id, parent_meta);
-				trace!("apply_addition: Updated parent meta in tree with new project source");
 			} else {
-				trace!("apply_addition: Parent project node does not have custom path");
-				let mut project = Project::load(&path)?;
-				trace!("apply_addition: Loaded project from {}", path.display());
+				trace!(project:% = path.display(), node_path:? = node_path; "Adding to project node");
+
+				let mut project = Project::edit(&path)?;
 
 				let node = project
 					.find_node_by_path(&node_path)
 					.context(format!("Failed to find project node with path {:?}", node_path))?;
-				trace!("apply_addition: Found parent project node: {:?}", node);
 
 				add_project_instances(parent_id, &path, node_path.clone(), snapshot, node, &parent_meta, tree);
 
 				project.save(&path)?;
-				trace!("apply_addition: Saved project to {}", path.display());
 			}
 		}
-		SourceKind::None => {
-			let msg = format!(
-				"apply_addition: Attempted to add instance {:?} whose parent {:?} has no source",
-				snapshot.id, parent_id
-			);
-			error!("{}", msg);
-			panic!("{}", msg);
+		SourceKind::Ephemeral => {
+			unreachable!("apply_addition: Children of ephemeral instances are ephemeral too")
 		}
+		SourceKind::None => bail!("Parent instance {} has no source", parent_id),
 	}
 
-	trace!("apply_addition: Exiting function successfully");
 	Ok(())
 }
 
 pub fn apply_update(snapshot: UpdatedSnapshot, tree: &mut Tree, vfs: &Vfs) -> Result<()> {
-	trace!("Updating {:?}", snapshot.id);
+	let origin = Origin::of(snapshot.id, tree);
+
+	let _span = match tree.get_instance(snapshot.id) {
+		Some(instance) => Span::new("apply_update")
+			.with_field("id", snapshot.id)
+			.with_field("name", &instance.name)
+			.with_field("class", instance.class)
+			.with_field("path", &origin.path),
+		None => Span::new("apply_update").with_field("id", snapshot.id),
+	}
+	.enter();
+
+	let id = snapshot.id;
+	let renamed = snapshot.name.is_some();
+
+	// Paths of references are written along with the rest of the properties
+	if let Some(properties) = &snapshot.properties {
+		let ref_paths = refs::to_paths(id, properties, tree);
+
+		external_refs_warn(id, &ref_paths, tree);
+		tree.set_ref_paths(id, ref_paths);
+	}
+
+	update(snapshot, tree, vfs)
+		.and_then(|_| {
+			tree.link_refs(id);
+
+			if renamed {
+				rewrite_referrers(id, tree, vfs)
+			} else {
+				Ok(())
+			}
+		})
+		.map_err(|err| origin.attach(err))
+}
+
+/// Paths of references to the instance and its descendants
+/// change when it gets renamed, so files that have them are rewritten
+fn rewrite_referrers(id: Ref, tree: &mut Tree, vfs: &Vfs) -> Result<()> {
+	let mut referrers = HashSet::new();
+	let mut stack = vec![id];
+
+	while let Some(id) = stack.pop() {
+		let Some(instance) = tree.get_instance(id) else {
+			continue;
+		};
+
+		stack.extend(instance.children());
+		referrers.extend(tree.get_referrers(id));
+	}
+
+	for referrer in referrers {
+		rewrite_refs(referrer, tree, vfs)?;
+	}
+
+	Ok(())
+}
+
+fn update(snapshot: UpdatedSnapshot, tree: &mut Tree, vfs: &Vfs) -> Result<()> {
+	if let Some(read_only) = find_read_only(snapshot.id, tree) {
+		let instance = tree.get_full_name(snapshot.id).unwrap_or_default();
+		let context = &tree.get_meta(snapshot.id).unwrap().context;
+
+		return reject_read_only(snapshot.id, &instance, SkippedOperation::Update, &read_only, context);
+	}
+
+	if tree
+		.get_meta(snapshot.id)
+		.is_some_and(|meta| meta.source.is_ephemeral())
+	{
+		trace!("Instance is ephemeral, updating it in memory only");
+		update_ephemeral(snapshot, tree);
+		return Ok(());
+	}
+
+	if let Some(path) = get_load_error_path(snapshot.id, tree) {
+		load_error_warn(&tree.get_full_name(snapshot.id).unwrap_or_default(), &path);
+		return Ok(());
+	}
+
+	if is_read_only_mirror(snapshot.id, tree) {
+		return Ok(());
+	}
 
 	if let Some(instance) = tree.get_instance(snapshot.id) {
 		let filter = tree.get_meta(snapshot.id).unwrap().context.syncback_filter();
-		trace!("apply_update: Instance {:?} exists. Filter: {:?}", snapshot.id, filter);
 
-		if filter.matches_name(&instance.name) || filter.matches_class(&instance.class) {
-			filter_warn!(snapshot.id);
-			trace!("apply_update: Exiting function early (instance filtered by current name/class)");
-			return Ok(());
-		}
+		let rule = filter
+			.find_instance(&instance.name, &instance.class)
+			.or_else(|| snapshot.name.as_ref().and_then(|name| filter.find_name(name)))
+			.or_else(|| snapshot.class.as_ref().and_then(|class| filter.find_class(class)));
 
-		if snapshot.name.as_ref().is_some_and(|name| filter.matches_name(name)) {
-			filter_warn!(snapshot.id);
-			trace!("apply_update: Exiting function early (instance filtered by new name)");
+		if let Some(rule) = rule {
+			let location = tree.get_full_name(snapshot.id).unwrap_or_default();
+
+			filter_warn!(filter, snapshot.id, &location, SkippedOperation::Update, rule);
 			return Ok(());
 		}
 
-		if snapshot.class.as_ref().is_some_and(|class| filter.matches_class(class)) {
-			filter_warn!(snapshot.id);
-			trace!("apply_update: Exiting function early (instance filtered by new class)");
+		let context = &tree.get_meta(snapshot.id).unwrap().context;
+
+		if let Some(package) = get_unmanaged_package(instance.parent(), context, tree) {
+			package_warn(&tree.get_full_name(snapshot.id).unwrap_or_default(), &package);
 			return Ok(());
 		}
 	} else {
-		warn!(
-			"apply_update: Attempted to update instance that doesn't exist: {:?}",
-			snapshot.id
-		);
-		trace!("apply_update: Exiting function early (instance does not exist)");
+		warn!(id:% = snapshot.id; "Attempted to update instance that doesn't exist");
 		return Ok(());
 	}
 
+	// Properties sent by the client replace all of the current ones
+	if snapshot.properties.is_some() {
+		tree.clear_evicted(snapshot.id);
+	}
+
+	// Renaming either of the siblings is how users resolve the collision
+	if snapshot.name.is_some() {
+		tree.resolve_collisions(snapshot.id);
+	}
+
+	if let Some(SourceEntry::Manifest(path, key)) = tree
+		.get_meta(snapshot.id)
+		.and_then(|meta| meta.source.get_manifest())
+		.cloned()
+	{
+		return update_manifest_entry(snapshot, &path, &key, tree, vfs);
+	}
+
 	let mut meta = tree.get_meta(snapshot.id).unwrap().clone();
+	let loaded = loaded_root(snapshot.id, tree);
 	let instance = tree.get_instance_mut(snapshot.id).unwrap();
-	trace!(
-		"apply_update: Got meta: {:?} and mutable instance: {:?}",
-		meta,
-		instance
-	);
 
-	fn locate_instance_data(name: &str, path: &Path, meta: &Meta, vfs: &Vfs) -> Option<PathBuf> {
-		trace!(
-			"locate_instance_data (update): Entering function with name={}, path={}, meta={:?}",
-			name,
-			path.display(),
-			meta
-		);
-		let data_path = if let Some(data) = meta.source.get_data() {
-			trace!(
-				"locate_instance_data (update): Found existing data path in meta: {}",
-				data.path().display()
-			);
-			Some(data.path().to_owned())
+	fn locate_instance_data(name: &str, path: &Path, meta: &Meta, vfs: &Vfs) -> Option<SourceEntry> {
+		// Existing data is kept where it is, regardless of the data storage setting
+		let data_entry = if let Some(data) = meta.source.get_data_entry() {
+			Some(data.to_owned())
 		} else {
-			trace!("locate_instance_data (update): No data path in meta, searching using sync rules");
-			meta.context
-				.sync_rules_of_type(&Middleware::InstanceData, true)
-				.iter()
-				.find_map(|rule| {
-					trace!("locate_instance_data (update): Checking rule: {:?}", rule);
-					let located = rule.locate(path, name, vfs.is_dir(path));
-					trace!("locate_instance_data (update): Rule locate result: {:?}", located);
-					located
-				})
+			storage::for_new(&meta.context).locate(name, path, vfs.is_dir(path), &meta.context)
 		};
 
-		if data_path.is_none() {
-			warn!(
-				"locate_instance_data (update): Failed to locate instance data for {}",
-				path.display()
-			)
+		if data_entry.is_none() {
+			warn!(path:% = path.display(); "Failed to locate instance data");
 		}
-		trace!("locate_instance_data (update): Result: {:?}", data_path);
-		trace!("locate_instance_data (update): Exiting function");
-		data_path
+
+		data_entry
 	}
 
+	/// Writes properties to the files of the instance, returns them
+	/// so they can be set once the transaction is committed
 	fn update_non_project_properties(
 		path: &Path,
+		name: &str,
 		properties: Properties,
-		instance: &mut Instance,
+		instance: &Instance,
 		meta: &mut Meta,
 		vfs: &Vfs,
-	) -> Result<()> {
-		trace!(
-			"update_non_project_properties: Entering function with path={}, properties={:?}, instance={:?}, meta={:?}",
-			path.display(),
-			properties,
-			instance.referent(),
-			meta
-		);
+	) -> Result<Option<Properties>> {
 		let filter = meta.context.syncback_filter();
-		trace!("update_non_project_properties: Filter: {:?}", filter);
 
-		if filter.matches_path(path) {
-			filter_warn!(instance.referent(), path);
-			trace!("update_non_project_properties: Exiting function early (path filtered)");
-			return Ok(());
+		if let Some(rule) = filter.find_path(path) {
+			filter_warn!(filter, instance.referent(), name, SkippedOperation::Update, rule, path);
+			return Ok(None);
 		}
 
-		let mut properties = validate_properties(properties, filter);
-		trace!("update_non_project_properties: Validated properties: {:?}", properties);
+		let mut properties = validate_properties(instance.referent(), &instance.class, properties, &meta.context);
+
+		// Instances described by their data file alone stay that way when it reads properties from other files
+		let data_only = meta.source.get_file().is_none()
+			&& meta
+				.source
+				.get_data()
+				.is_some_and(|data| data::has_file_refs(data.path(), vfs));
 
 		if let Some(middleware) = Middleware::from_class(
 			&instance.class,
 			if !meta.context.use_legacy_scripts() {
-				trace!("update_non_project_properties: Using modern script handling");
-				Some(&mut properties)
+				Some(&properties)
 			} else {
-				trace!("update_non_project_properties: Using legacy script handling");
 				None
 			},
-		) {
-			trace!("update_non_project_properties: Found middleware: {:?}", middleware);
+		)
+		.filter(|_| !data_only)
+		{
+			if !meta.context.use_legacy_scripts() {
+				Middleware::strip_run_context(&instance.class, &mut properties);
+			}
+
+			let middleware = middleware.or_existing(meta.source.get_file().map(SourceEntry::path), &meta.context);
+
 			let new_path = meta
 				.context
 				.sync_rules_of_type(&middleware, true)
 				.iter()
-				.find_map(|rule| {
-					trace!("update_non_project_properties: Checking file sync rule: {:?}", rule);
-					let located = rule.locate(path, &instance.name, vfs.is_dir(path));
-					trace!("update_non_project_properties: Rule locate result: {:?}", located);
-					located
-				});
-			trace!(
-				"update_non_project_properties: Located potential new file path: {:?}",
-				new_path
-			);
+				.find_map(|rule| rule.locate(path, name, vfs.is_dir(path)));
 
 			let file_path = if let Some(SourceEntry::File(path_entry)) = meta.source.get_file_mut() {
 				let mut current_path = path_entry.to_owned();
-				trace!(
-					"update_non_project_properties: Found existing file path in meta: {}",
-					current_path.display()
-				);
 
 				if let Some(new_path) = new_path {
 					if current_path != new_path {
-						trace!(
-							"update_non_project_properties: Renaming file path from {} to {}",
-							current_path.display(),
-							new_path.display()
-						);
+						trace!(from:% = current_path.display(), to:% = new_path.display(); "Renaming file");
 						vfs.rename(&current_path, &new_path)?;
 
 						*path_entry = new_path.clone();
 						current_path = new_path;
-					} else {
-						trace!("update_non_project_properties: New path is same as current path, no rename needed.");
 					}
 				}
 
 				Some(current_path)
 			} else {
-				trace!("update_non_project_properties: No existing file path in meta.");
 				if let Some(new_path) = &new_path {
-					trace!(
-						"update_non_project_properties: Adding located path {} to meta",
-						new_path.display()
-					);
 					meta.source.add_file(new_path);
 				}
 
 				new_path
 			};
 
-			trace!(
-				"update_non_project_properties: Final file_path for writing: {:?}",
-				file_path
-			);
+			trace!(middleware:? = middleware, file_path:? = file_path; "Writing properties");
 
 			if let Some(file_path) = file_path {
-				trace!(
-					"update_non_project_properties: Writing properties using middleware to {}",
-					file_path.display()
-				);
-				let properties = middleware.write(properties.clone(), &file_path, vfs)?;
-				trace!(
-					"update_non_project_properties: Middleware write complete, remaining properties: {:?}",
-					properties
-				);
-
-				if let Some(data_path) = locate_instance_data(&instance.name, path, meta, vfs) {
-					trace!(
-						"update_non_project_properties: Located data path: {}",
-						data_path.display()
-					);
-					if filter.matches_path(&data_path) {
-						filter_warn!(instance.referent(), &data_path);
-						trace!("update_non_project_properties: Data path filtered, skipping data write.");
-					} else {
-						trace!("update_non_project_properties: Writing data to {}", data_path.display());
-						let data_path =
-							data::write_data(true, &instance.class, properties.clone(), &data_path, meta, vfs)?;
-						trace!("update_non_project_properties: Wrote data to path: {:?}", data_path);
-						meta.source.set_data(data_path);
-						trace!(
-							"update_non_project_properties: Updated meta source with data path: {:?}",
-							meta.source
+				let data_properties = middleware.write(&properties, &file_path, &meta.context, vfs)?;
+
+				if let Some(data_entry) = locate_instance_data(name, path, meta, vfs) {
+					if let Some(rule) = filter.find_path(data_entry.path()) {
+						filter_warn!(
+							filter,
+							instance.referent(),
+							name,
+							SkippedOperation::Update,
+							rule,
+							data_entry.path()
 						);
-					}
-				} else {
-					trace!("update_non_project_properties: Could not locate data path, skipping data write.");
-				}
-			} else {
-				error!(
-					"update_non_project_properties: Failed to locate file for path {:?}",
-					path.display()
-				);
-			}
-		} else if let Some(data_path) = locate_instance_data(&instance.name, path, meta, vfs) {
-			trace!(
-				"update_non_project_properties: No middleware found, but located data path: {}",
-				data_path.display()
-			);
-			if filter.matches_path(&data_path) {
-				filter_warn!(instance.referent(), &data_path);
-				trace!("update_non_project_properties: Data path filtered, skipping data write.");
+					} else {
+						let data_entries = storage::of(&data_entry).write(
+							&data_entry,
+							true,
+							&instance.class,
+							data_properties,
+							meta,
+							vfs,
+						)?;
+						meta.source.set_data_entries(data_entries);
+					}
+				}
 			} else {
-				trace!("update_non_project_properties: Writing data to {}", data_path.display());
-				let data_path = data::write_data(false, &instance.class, properties.clone(), &data_path, meta, vfs)?;
-				trace!("update_non_project_properties: Wrote data to path: {:?}", data_path);
-				meta.source.set_data(data_path);
-				trace!(
-					"update_non_project_properties: Updated meta source with data path: {:?}",
-					meta.source
+				error!(path:% = path.display(); "Failed to locate file");
+			}
+		} else if let Some(data_entry) = locate_instance_data(name, path, meta, vfs) {
+			trace!(data_entry:? = data_entry; "Writing properties to instance data");
+
+			if let Some(rule) = filter.find_path(data_entry.path()) {
+				filter_warn!(
+					filter,
+					instance.referent(),
+					name,
+					SkippedOperation::Update,
+					rule,
+					data_entry.path()
 				);
+			} else {
+				let data_entries = storage::of(&data_entry).write(
+					&data_entry,
+					false,
+					&instance.class,
+					properties.clone(),
+					meta,
+					vfs,
+				)?;
+				meta.source.set_data_entries(data_entries);
 			}
-		} else {
-			trace!(
-				"update_non_project_properties: No middleware and could not locate data path, skipping property write."
-			);
 		}
 
-		instance.properties = properties;
-		trace!(
-			"update_non_project_properties: Updated instance properties in tree: {:?}",
-			instance.properties
-		);
-
-		trace!("update_non_project_properties: Exiting function successfully");
-		Ok(())
+		Ok(Some(properties))
 	}
 
-	trace!("apply_update: Matching source kind: {:?}", meta.source.get());
-	match meta.source.get().clone() {
+	// Files of the instance are renamed and written together, the tree
+	// is only updated once all of them are, so both stay consistent
+	let transaction = vfs.transaction();
+
+	match resolve_project_root(&meta, &instance.name, loaded)? {
 		SourceKind::Path(mut path) => {
-			trace!("apply_update: Source is Path: {}", path.display());
+			let mut new_name = None;
+			let mut relocated = None;
+
 			if let Some(mut name) = snapshot.name {
-				trace!("apply_update: Handling name update to: {}", name);
 				let original_name = meta.original_name.clone();
-				trace!("apply_update: Original name from meta: {:?}", original_name);
+
+				// Files of instances renamed during syncback do not start with the instance name
+				let current_name = if original_name.is_none() {
+					instance.name.clone()
+				} else if vfs.is_dir(&path) {
+					path.get_name().to_owned()
+				} else {
+					meta.context
+						.sync_rules()
+						.find_map(|rule| rule.resolve(&path))
+						.map_or_else(|| instance.name.clone(), |resolved| resolved.name)
+				};
 
 				if !verify_name(&mut name, &mut meta) {
-					trace!("apply_update: Name verification failed for {}, exiting early.", name);
+					trace!(name = name.as_str(); "Name verification failed");
 					return Ok(());
 				}
-				trace!("apply_update: Verified name: {}, updated meta: {:?}", name, meta);
 
-				path = rename_path(&path, &instance.name, &name);
-				trace!("apply_update: Calculated new path based on rename: {}", path.display());
+				path = rename_path(&path, &current_name, &name);
 
 				if !verify_path(&mut path, &mut name, &mut meta, vfs) {
-					trace!(
-						"apply_update: Path verification failed for {}, exiting early.",
-						path.display()
-					);
+					trace!(path:% = path.display(); "Path verification failed");
 					return Ok(());
 				}
+
 				trace!(
-					"apply_update: Verified path: {}, updated name: {}, updated meta: {:?}",
-					path.display(),
-					name,
-					meta
+					from = current_name.as_str(),
+					to = name.as_str(),
+					path:% = path.display(),
+					original_name:? = meta.original_name;
+					"Renaming instance"
 				);
 
 				*meta.source.get_mut() = SourceKind::Path(path.clone());
-				trace!("apply_update: Updated source kind in meta: {:?}", meta.source.get());
 
 				let filter = meta.context.syncback_filter();
-				trace!("apply_update: Filter for renaming relevant paths: {:?}", filter);
 
 				if let Some(SourceEntry::Folder(folder_path_entry)) = meta.source.get_folder_mut() {
 					let current_folder_path = folder_path_entry.to_owned();
-					trace!(
-						"apply_update: Renaming folder source from {}",
-						current_folder_path.display()
-					);
 					let new_path = current_folder_path.with_file_name(&name);
-					trace!("apply_update: New folder path: {}", new_path.display());
 
-					if filter.matches_path(&current_folder_path) && filter.matches_path(&new_path) {
-						filter_warn!(snapshot.id, &current_folder_path);
-						trace!("apply_update: Both old and new folder paths filtered, skipping rename.");
-					} else {
-						trace!(
-							"apply_update: Performing VFS rename for folder: {} -> {}",
-							current_folder_path.display(),
-							new_path.display()
+					if let Some(rule) = filter
+						.find_path(&current_folder_path)
+						.filter(|_| filter.matches_path(&new_path))
+					{
+						filter_warn!(
+							filter,
+							snapshot.id,
+							&name,
+							SkippedOperation::Update,
+							rule,
+							&current_folder_path
 						);
-						vfs.rename(&current_folder_path, &new_path)?;
+					} else {
+						vfs.rename(&current_folder_path, &new_path).with_context(|| {
+							format!(
+								"Failed to rename {} to {}",
+								current_folder_path.display(),
+								new_path.display()
+							)
+						})?;
 						*folder_path_entry = new_path.clone();
-						trace!("apply_update: Updated folder path in meta source.");
+						relocated = Some((current_folder_path, new_path.clone()));
 
 						for entry in meta.source.relevant_mut() {
-							trace!("apply_update: Updating relevant path entry: {:?}", entry);
 							match entry {
 								SourceEntry::File(path_entry) | SourceEntry::Data(path_entry) => {
-									let original_relevant_path = path_entry.clone();
 									*path_entry = new_path.join(path_entry.get_name());
-									trace!(
-										"apply_update: Updated relevant path from {} to {}",
-										original_relevant_path.display(),
-										path_entry.display()
-									);
 								}
-								_ => {
-									trace!("apply_update: Skipping non-file/data relevant entry.");
-									continue;
+								SourceEntry::Index(_, _) => {
+									let renamed = storage::rename_entry(entry, &name, vfs)?;
+									*entry = renamed;
 								}
+								_ => continue,
 							}
 						}
 					}
 				} else {
-					trace!("apply_update: Renaming relevant file/data sources (not folder source)");
 					for entry in meta.source.relevant_mut() {
-						trace!("apply_update: Updating relevant path entry: {:?}", entry);
 						match entry {
 							SourceEntry::File(path_entry) | SourceEntry::Data(path_entry) => {
 								let current_path = path_entry.clone();
-								let new_path = rename_path(&current_path, &instance.name, &name);
-								trace!("apply_update: Calculated new relevant path: {}", new_path.display());
-
-								if filter.matches_path(&current_path) && filter.matches_path(&new_path) {
-									filter_warn!(snapshot.id, &current_path);
-									trace!("apply_update: Both old and new relevant paths filtered, skipping rename.");
+								let new_path = rename_path(&current_path, &current_name, &name);
+
+								if let Some(rule) = filter
+									.find_path(&current_path)
+									.filter(|_| filter.matches_path(&new_path))
+								{
+									filter_warn!(
+										filter,
+										snapshot.id,
+										&name,
+										SkippedOperation::Update,
+										rule,
+										&current_path
+									);
 									continue;
 								}
 
-								trace!(
-									"apply_update: Performing VFS rename for relevant path: {} -> {}",
-									current_path.display(),
-									new_path.display()
-								);
-								vfs.rename(&current_path, &new_path)?;
+								vfs.rename(&current_path, &new_path).with_context(|| {
+									format!("Failed to rename {} to {}", current_path.display(), new_path.display())
+								})?;
 								*path_entry = new_path;
-								trace!("apply_update: Updated relevant path in meta source.");
 							}
-							_ => {
-								trace!("apply_update: Skipping non-file/data relevant entry.");
-								continue;
+							SourceEntry::Index(_, _) => {
+								let renamed = storage::rename_entry(entry, &name, vfs)?;
+								*entry = renamed;
 							}
+							_ => continue,
 						}
 					}
 				}
 
+				// Included files are renamed along with the data file so references to them have to follow
+				if let Some(data) = meta
+					.source
+					.get_data()
+					.filter(|_| !meta.source.get_includes().is_empty())
+				{
+					trace!(data_path:% = data.path().display(); "Relinking includes");
+					data::relink_includes(data.path(), vfs)?;
+				}
+
 				if original_name != meta.original_name && snapshot.properties.is_none() {
-					trace!("apply_update: Name changed and no properties updated, attempting to write original name metadata.");
-					if let Some(data_path) = locate_instance_data(&name, &path, &meta, vfs) {
-						trace!(
-							"apply_update: Located data path for original name metadata: {}",
-							data_path.display()
-						);
-						if filter.matches_path(&data_path) {
-							filter_warn!(instance.referent(), &data_path);
-							trace!("apply_update: Data path filtered, skipping original name write.");
+					if let Some(data_entry) = locate_instance_data(&name, &path, &meta, vfs) {
+						if let Some(rule) = filter.find_path(data_entry.path()) {
+							filter_warn!(
+								filter,
+								instance.referent(),
+								&name,
+								SkippedOperation::Update,
+								rule,
+								data_entry.path()
+							);
 						} else {
-							trace!("apply_update: Writing original name to {}", data_path.display());
-							write_original_name(&data_path, &meta, vfs)?;
+							trace!(data_entry:? = data_entry; "Writing original name");
+							storage::of(&data_entry).write_original_name(&data_entry, &meta, vfs)?;
+
+							if meta.source.get_data_entry().is_none() && meta.original_name.is_some() {
+								meta.source.set_data_entries(vec![data_entry]);
+							}
 						}
-					} else {
-						trace!("apply_update: Could not locate data path for original name metadata.");
 					}
 				}
 
-				instance.name = meta.original_name.clone().unwrap_or(name);
-				trace!("apply_update: Updated instance name in tree to: {}", instance.name);
+				new_name = Some(meta.original_name.clone().unwrap_or(name));
 			}
 
-			if let Some(properties) = snapshot.properties {
-				trace!("apply_update: Handling property update: {:?}", properties);
-				update_non_project_properties(&path, properties, instance, &mut meta, vfs)?;
-			} else {
-				trace!("apply_update: No properties to update.");
+			let properties = match snapshot.properties {
+				Some(properties) => {
+					let name = new_name.clone().unwrap_or_else(|| instance.name.clone());
+					update_non_project_properties(&path, &name, properties, instance, &mut meta, vfs)?
+				}
+				None => None,
+			};
+
+			// Written files are only moved into place on commit, which can fail too
+			transaction.commit()?;
+
+			if let Some(properties) = properties {
+				instance.properties = properties;
+			}
+
+			if let Some(name) = new_name {
+				instance.name = name;
 			}
 
 			tree.update_meta(snapshot.id, meta);
-			trace!("apply_update: Updated meta in tree for instance {:?}", snapshot.id);
+
+			if let Some((from, to)) = relocated {
+				relocate_descendants(snapshot.id, &from, &to, tree);
+			}
 
 			if let Some(class) = snapshot.class {
 				// You can't change the class of an instance inside Roblox Studio
-				warn!(
-					"apply_update: Received unexpected class update for {:?} to {}, ignoring.",
-					snapshot.id, class
-				);
+				warn!(class = class.as_str(); "Received unexpected class update, ignoring");
 				unreachable!()
 			}
 
 			if let Some(meta_update) = snapshot.meta {
 				// Currently Argon client does not update meta
-				warn!(
-					"apply_update: Received unexpected meta update for {:?}: {:?}, ignoring.",
-					snapshot.id, meta_update
-				);
+				warn!(meta:? = meta_update; "Received unexpected meta update, ignoring");
 				unreachable!()
 			}
 		}
 		SourceKind::Project(name, path, node, node_path) => {
-			trace!(
-				"apply_update: Source is Project: name={}, path={}, node_path={:?}",
-				name,
-				path.display(),
-				node_path
-			);
-			let mut project = Project::load(&path)?;
-			trace!("apply_update: Loaded project from {}", path.display());
+			let mut project = Project::edit(&path)?;
+
+			// Restored if the project fails to save
+			let previous_instance = (instance.name.clone(), instance.properties.clone());
 
 			if let Some(properties) = snapshot.properties {
-				trace!(
-					"apply_update: Handling property update for project node: {:?}",
-					properties
-				);
 				if let Some(custom_path) = node.path {
-					trace!("apply_update: Project node has custom path: {:?}", custom_path);
 					let custom_path = path.with_file_name(custom_path.path()).clean();
-					trace!("apply_update: Resolved custom path: {}", custom_path.display());
 
-					update_non_project_properties(&custom_path, properties, instance, &mut meta, vfs)?;
-					trace!("apply_update: Updated properties via non-project logic due to custom path.");
+					// Path that does not exist is backed by its data file alone (e.g. singleton
+					// services in `servicesPath`), which is created with the first update
+					let data_only = !vfs.exists(&custom_path) && meta.source.get_data_entry().is_none();
+
+					trace!(
+						project:% = path.display(),
+						node_path:? = node_path,
+						path:% = custom_path.display(),
+						data_only;
+						"Updating project node path properties"
+					);
+
+					if data_only {
+						dir::write_dir(custom_path.get_parent(), vfs)?;
+					}
+
+					let name = instance.name.clone();
+					if let Some(properties) =
+						update_non_project_properties(&custom_path, &name, properties, instance, &mut meta, vfs)?
+					{
+						instance.properties = properties;
+					}
+
+					if let Some(data) = meta.source.get_data_entry().filter(|_| data_only) {
+						vfs.watch(data.path(), false)?;
+					}
 
 					let node = project
 						.find_node_by_path(&node_path)
 						.context(format!("Failed to find project node with path {:?}", node_path))?;
-					trace!("apply_update: Found project node: {:?}", node);
 
 					// Clear project node properties as they are now managed externally
 					node.properties = UstrMap::new();
 					node.attributes = None;
 					node.tags = vec![];
 					node.keep_unknowns = None;
-					trace!("apply_update: Cleared properties/attributes/tags on project node.");
 				} else {
-					trace!("apply_update: Project node does not have custom path, updating node directly.");
 					let node = project
 						.find_node_by_path(&node_path)
 						.context(format!("Failed to find project node with path {:?}", node_path))?;
-					trace!("apply_update: Found project node: {:?}", node);
 
 					let class = node.class_name.unwrap_or_else(|| Ustr::from(&name));
-					trace!("apply_update: Determined class for property serialization: {}", class);
-					let properties = validate_properties(properties, meta.context.syncback_filter());
-					trace!("apply_update: Validated properties for project node: {:?}", properties);
+					let properties = validate_properties(snapshot.id, &class, properties, &meta.context);
 
-					node.properties = serialize_properties(&class, properties.clone());
 					trace!(
-						"apply_update: Serialized and set properties on project node: {:?}",
-						node.properties
+						project:% = path.display(),
+						node_path:? = node_path,
+						class = class.as_str();
+						"Updating project node properties"
 					);
-					node.tags = vec![]; // TODO: Handle tags if necessary
-					node.keep_unknowns = None; // TODO: Handle keep_unknowns
+
+					let previous = node.properties.clone();
+
+					serialize_node_properties(node, &class, refs::portable(properties.clone(), &meta.ref_paths));
+					node.keep_profile_overrides(meta.context.profile(), &previous);
+					keep_file_refs(node, &previous, &path, vfs)?;
 
 					instance.properties = properties;
-					trace!(
-						"apply_update: Updated instance properties in tree: {:?}",
-						instance.properties
-					);
 				}
-			} else {
-				trace!("apply_update: No properties to update for project node.");
 			}
 
 			// It has to be done after updating properties as it may change the node path
-			if let Some(new_name) = snapshot.name {
-				trace!("apply_update: Handling name update for project node to: {}", new_name);
+			if let Some(new_name) = snapshot.name.as_ref().filter(|_| node_path.is_root()) {
+				warn!(to = new_name.as_str(); "Cannot rename root project node, ignoring");
+			} else if let Some(new_name) = snapshot.name {
 				let parent_node_path = node_path.parent();
-				trace!(
-					"apply_update: Finding parent project node at path: {:?}",
-					parent_node_path
-				);
 				let parent_node = project
 					.find_node_by_path(&parent_node_path)
 					.with_context(|| format!("Failed to find parent project node with path {:?}", parent_node_path))?;
-				trace!("apply_update: Found parent project node.");
 
-				trace!("apply_update: Removing old node '{}' from parent's tree", name);
+				if parent_node
+					.tree
+					.get(&new_name)
+					.is_some_and(|node| !node.is_visible(meta.context.profile()))
+				{
+					(instance.name, instance.properties) = previous_instance;
+
+					bail!(
+						"Cannot rename {} to {} as this name is already used by a project node of another profile",
+						name,
+						new_name
+					);
+				}
+
 				let node = parent_node
 					.tree
 					.remove(&name)
 					.context(format!("Failed to remove project node with path {:?}", node_path))?;
-				trace!("apply_update: Removed node: {:?}", node);
 
-				trace!(
-					"apply_update: Inserting node with new name '{}' into parent's tree",
-					new_name
-				);
 				parent_node.tree.insert(new_name.clone(), node.clone());
 
 				let new_node_path = parent_node_path.join(&new_name);
-				trace!("apply_update: New node path: {:?}", new_node_path);
+
+				trace!(
+					from = name.as_str(),
+					to = new_name.as_str(),
+					node_path:? = new_node_path;
+					"Renaming project node"
+				);
 
 				*meta.source.get_mut() =
 					SourceKind::Project(new_name.clone(), path.clone(), Box::new(node), new_node_path);
-				trace!("apply_update: Updated source kind in meta: {:?}", meta.source.get());
 
 				instance.name = new_name;
-				trace!("apply_update: Updated instance name in tree to: {}", instance.name);
-			} else {
-				trace!("apply_update: No name update for project node.");
+			}
+
+			if let Err(err) = project.save(&path) {
+				(instance.name, instance.properties) = previous_instance;
+				return Err(err);
+			}
+
+			if let Err(err) = transaction.commit() {
+				(instance.name, instance.properties) = previous_instance;
+				return Err(err.into());
 			}
 
 			tree.update_meta(snapshot.id, meta);
-			trace!("apply_update: Updated meta in tree for instance {:?}", snapshot.id);
-			project.save(&path)?;
-			trace!("apply_update: Saved project to {}", path.display());
 
 			if let Some(class) = snapshot.class {
 				// You can't change the class of an instance inside Roblox Studio
-				warn!(
-					"apply_update: Received unexpected class update for project node {:?} to {}, ignoring.",
-					snapshot.id, class
-				);
+				warn!(class = class.as_str(); "Received unexpected class update, ignoring");
 				unreachable!()
 			}
 
 			if let Some(meta_update) = snapshot.meta {
 				// Currently Argon client does not update meta
-				warn!(
-					"apply_update: Received unexpected meta update for project node {:?}: {:?}, ignoring.",
-					snapshot.id, meta_update
-				);
+				warn!(meta:? = meta_update; "Received unexpected meta update, ignoring");
 				unreachable!()
 			}
 		}
-		SourceKind::None => {
-			let msg = format!(
-				"apply_update: Attempted to update instance {:?} with no source",
-				snapshot.id
-			);
-			error!("{}", msg);
-			panic!("{}", msg);
+		SourceKind::Ephemeral => {
+			unreachable!("apply_update: Ephemeral instances are updated in memory only")
+		}
+		SourceKind::None => bail!("Instance has no source"),
+	}
+
+	Ok(())
+}
+
+/// Writes name and property changes of the instance to its asset manifest
+/// entry, renaming the entry changes keys of all entries nested in it
+fn update_manifest_entry(
+	snapshot: UpdatedSnapshot,
+	path: &Path,
+	key: &[String],
+	tree: &mut Tree,
+	vfs: &Vfs,
+) -> Result<()> {
+	let meta = tree.get_meta(snapshot.id).unwrap();
+	let class = tree.get_instance(snapshot.id).unwrap().class;
+	let filter = meta.context.syncback_filter();
+
+	if let Some(rule) = filter.find_path(path) {
+		let location = tree.get_full_name(snapshot.id).unwrap_or_default();
+
+		filter_warn!(filter, snapshot.id, &location, SkippedOperation::Update, rule, path);
+		return Ok(());
+	}
+
+	let properties = snapshot
+		.properties
+		.map(|properties| validate_properties(snapshot.id, &class, properties, &meta.context));
+
+	trace!(
+		manifest:% = path.display(),
+		key:? = key,
+		name:? = snapshot.name;
+		"Updating asset manifest entry"
+	);
+
+	let transaction = vfs.transaction();
+
+	if let Some(properties) = &properties {
+		let portable = refs::portable(properties.clone(), &meta.ref_paths);
+		manifest::write_properties(path, key, &class, portable, vfs)?;
+	}
+
+	let new_key = match &snapshot.name {
+		Some(name) => Some(manifest::rename_entry(path, key, name, vfs)?),
+		None => None,
+	};
+
+	transaction.commit()?;
+
+	let instance = tree.get_instance_mut(snapshot.id).unwrap();
+
+	if let Some(properties) = properties {
+		instance.properties = properties;
+	}
+
+	if let Some(name) = snapshot.name {
+		instance.name = name;
+	}
+
+	if let Some(new_key) = new_key {
+		let mut stack = vec![snapshot.id];
+
+		while let Some(id) = stack.pop() {
+			if let Some(mut meta) = tree.get_meta(id).cloned() {
+				for entry in meta.source.relevant_mut() {
+					if let SourceEntry::Manifest(_, entry_key) = entry {
+						if entry_key.starts_with(key) {
+							entry_key.splice(..key.len(), new_key.iter().cloned());
+						}
+					}
+				}
+
+				tree.update_meta(id, meta);
+			}
+
+			stack.extend(tree.inner().get_by_ref(id).unwrap().children());
 		}
 	}
 
-	trace!("apply_update: Exiting function successfully");
 	Ok(())
 }
 
 pub fn apply_removal(id: Ref, tree: &mut Tree, vfs: &Vfs) -> Result<()> {
-	trace!("Removing {:?}", id);
+	let origin = Origin::of(id, tree);
+
+	let _span = match tree.get_instance(id) {
+		Some(instance) => Span::new("apply_removal")
+			.with_field("id", id)
+			.with_field("name", &instance.name)
+			.with_field("class", instance.class)
+			.with_field("path", &origin.path),
+		None => Span::new("apply_removal").with_field("id", id),
+	}
+	.enter();
+
+	remove(id, tree, vfs).map_err(|err| origin.attach(err))
+}
+
+fn remove(id: Ref, tree: &mut Tree, vfs: &Vfs) -> Result<()> {
+	// Removing the root of a read-only source removes the whole source
+	if let Some(read_only) = tree
+		.get_instance(id)
+		.and_then(|instance| find_read_only(instance.parent(), tree))
+	{
+		let instance = tree.get_full_name(id).unwrap_or_default();
+		let context = &tree.get_meta(id).unwrap().context;
+
+		return reject_read_only(id, &instance, SkippedOperation::Remove, &read_only, context);
+	}
+
+	if tree.get_meta(id).is_some_and(|meta| meta.source.is_ephemeral()) {
+		trace!("Instance is ephemeral, removing it from memory only");
+		tree.remove_instance(id);
+		return Ok(());
+	}
+
+	if let Some(path) = get_load_error_path(id, tree) {
+		load_error_warn(&tree.get_full_name(id).unwrap_or_default(), &path);
+		return Ok(());
+	}
+
+	if is_read_only_mirror(id, tree) {
+		return Ok(());
+	}
 
 	if let Some(instance) = tree.get_instance(id) {
 		let filter = tree.get_meta(id).unwrap().context.syncback_filter();
-		trace!("apply_removal: Instance {:?} exists. Filter: {:?}", id, filter);
 
-		if filter.matches_name(&instance.name) || filter.matches_class(&instance.class) {
-			filter_warn!(id);
-			trace!("apply_removal: Exiting function early (instance filtered)");
+		if let Some(rule) = filter.find_instance(&instance.name, &instance.class) {
+			let location = tree.get_full_name(id).unwrap_or_default();
+
+			filter_warn!(filter, id, &location, SkippedOperation::Remove, rule);
+			return Ok(());
+		}
+
+		let context = &tree.get_meta(id).unwrap().context;
+
+		if let Some(package) = get_unmanaged_package(instance.parent(), context, tree) {
+			package_warn(&tree.get_full_name(id).unwrap_or_default(), &package);
 			return Ok(());
 		}
 	} else {
-		warn!(
-			"apply_removal: Attempted to remove instance that doesn't exist: {:?}",
-			id
-		);
-		trace!("apply_removal: Exiting function early (instance does not exist)");
+		warn!(id:% = id; "Attempted to remove instance that doesn't exist");
 		return Ok(());
 	}
 
 	let meta = tree.get_meta(id).unwrap().clone();
-	trace!("apply_removal: Got meta for instance {:?}: {:?}", id, meta);
 
 	fn remove_non_project_instances(id: Ref, meta: &Meta, tree: &mut Tree, vfs: &Vfs) -> Result<()> {
-		trace!(
-			"remove_non_project_instances: Entering function with id={:?}, meta={:?}",
-			id,
-			meta
-		);
 		let filter = meta.context.syncback_filter();
-		trace!("remove_non_project_instances: Filter: {:?}", filter);
 
 		for entry in meta.source.relevant() {
-			trace!(
-				"remove_non_project_instances: Processing relevant source entry: {:?}",
-				entry
-			);
 			match entry {
-				SourceEntry::Project(_) => {
-					trace!("remove_non_project_instances: Skipping project entry.");
-					continue;
-				}
+				SourceEntry::Project(_) => continue,
 				_ => {
 					let path = entry.path();
-					trace!("remove_non_project_instances: Processing path: {}", path.display());
 
 					if vfs.exists(path) {
-						trace!("remove_non_project_instances: Path exists.");
-						if filter.matches_path(path) {
-							filter_warn!(id, path);
-							trace!("remove_non_project_instances: Path filtered, skipping removal.");
+						if let Some(rule) = filter.find_path(path) {
+							let location = tree.get_full_name(id).unwrap_or_default();
+
+							filter_warn!(filter, id, &location, SkippedOperation::Remove, rule, path);
+						} else if let SourceEntry::Index(_, key) = entry {
+							// Data index is shared with siblings
+							trace!(path:% = path.display(), key = key.as_str(); "Removing data index entry");
+							storage::of(entry).remove(entry, vfs)?
+						} else if let SourceEntry::Manifest(_, key) = entry {
+							// And so are asset manifests
+							trace!(path:% = path.display(), key:? = key; "Removing asset manifest entry");
+							manifest::remove_entry(path, key, vfs)?
 						} else {
-							trace!("remove_non_project_instances: Removing path via VFS.");
+							trace!(path:% = path.display(); "Removing path");
 							vfs.remove(path)?
 						}
-					} else {
-						trace!("remove_non_project_instances: Path does not exist, skipping removal.");
 					}
 				}
 			}
 		}
 
+		// Parents of instances listed in asset manifests are never collapsed, as the manifest would go with them
+		if meta.source.get_manifest().is_some() {
+			return Ok(());
+		}
+
 		// Transform parent instance source from folder to file
 		// if it no longer has any children
-		trace!("remove_non_project_instances: Checking if parent needs transformation from folder to file");
-
 		let parent = tree
 			.get_instance(id)
-			.and_then(|instance| {
-				let parent_id = instance.parent();
-				trace!(
-					"remove_non_project_instances: Instance {:?} parent ID: {:?}",
-					id,
-					parent_id
-				);
-				tree.get_instance(parent_id)
-			})
+			.and_then(|instance| tree.get_instance(instance.parent()))
 			.context("Instance has no parent or parent does not exist in tree")?;
-		trace!(
-			"remove_non_project_instances: Found parent instance: {:?}",
-			parent.referent()
-		);
 
 		if parent.children().len() != 1 {
-			trace!(
-				"remove_non_project_instances: Parent {:?} has {} children (expected 1 after removal), skipping transformation.",
-				parent.referent(), parent.children().len()
-			);
 			return Ok(());
 		}
 
-		trace!(
-			"remove_non_project_instances: Parent {:?} has only 1 child remaining, proceeding with potential transformation.",
-			parent.referent()
-		);
 		let parent_ref = parent.referent();
-		let meta = tree.get_meta_mut(parent_ref).unwrap();
-		trace!("remove_non_project_instances: Got mutable meta for parent: {:?}", meta);
-
-		if let SourceKind::Path(folder_path) = meta.source.get().clone() {
-			trace!(
-				"remove_non_project_instances: Parent source is Path (potential folder): {}",
-				folder_path.display()
-			);
-			let name = folder_path.get_name();
-			trace!("remove_non_project_instances: Parent folder name: {}", name);
-
-			if let Some(file_entry) = meta.source.get_file().cloned() {
-				let file_path_in_folder = file_entry.path();
-				trace!(
-					"remove_non_project_instances: Parent meta has associated file: {}",
-					file_path_in_folder.display()
-				);
-				let file_path_outside_folder =
-					meta.context
-						.sync_rules()
-						.iter()
-						.find(|rule| {
-							let matches = rule.matches_child(file_path_in_folder);
-							trace!(
-							"remove_non_project_instances: Checking sync rule {:?} against child file {}: matches={}",
-							rule, file_path_in_folder.display(), matches
-						);
-							matches
-						})
-						.and_then(|rule| {
-							let located = rule.locate(&folder_path, name, false);
-							trace!(
-								"remove_non_project_instances: Located potential new path using rule {:?}: {:?}",
-								rule,
-								located
-							);
-							located
-						});
+		let parent_class = parent.class;
+
+		let (collapse_mode, preference) = tree
+			.get_meta(parent_ref)
+			.map(|meta| {
+				(
+					Some(meta.context.collapse_mode()),
+					meta.context.layout_preference(&parent_class),
+				)
+			})
+			.unwrap_or_default();
 
-				if let Some(new_path) = file_path_outside_folder {
-					trace!(
-						"remove_non_project_instances: Located new path for file: {}",
-						new_path.display()
-					);
-					vfs.rename(file_path_in_folder, &new_path)?;
-					trace!(
-						"remove_non_project_instances: Renamed file {} to {}",
-						file_path_in_folder.display(),
-						new_path.display()
-					);
-					let mut source = Source::file(&new_path);
-					trace!("remove_non_project_instances: Created new file source: {:?}", source);
-
-					if let Some(data_entry) = meta.source.get_data().cloned() {
-						let data_path_in_folder = data_entry.path();
-						trace!(
-							"remove_non_project_instances: Parent meta has associated data: {}",
-							data_path_in_folder.display()
-						);
-						let data_path_outside_folder = meta
-							.context
-							.sync_rules_of_type(&Middleware::InstanceData, true)
-							.iter()
-							.find_map(|rule| {
-								let located = rule.locate(&folder_path, name, false);
-								trace!(
-									"remove_non_project_instances: Checking data sync rule {:?} for potential new path: {:?}",
-									rule, located
-								);
-								located
-							});
-
-						if let Some(new_data_path) = data_path_outside_folder {
-							trace!(
-								"remove_non_project_instances: Located new path for data: {}",
-								new_data_path.display()
-							);
-							vfs.rename(data_path_in_folder, &new_data_path)?;
-							trace!(
-								"remove_non_project_instances: Renamed data {} to {}",
-								data_path_in_folder.display(),
-								new_data_path.display()
-							);
-							source.add_data(&new_data_path);
-							trace!(
-								"remove_non_project_instances: Added data path to new source: {:?}",
-								source
-							);
-						} else {
-							trace!("remove_non_project_instances: Could not locate new path for data.");
-						}
-					} else {
-						trace!("remove_non_project_instances: No data associated with parent meta.");
-					}
+		trace!(
+			parent:% = parent_ref,
+			collapse_mode:? = collapse_mode,
+			preference:? = preference;
+			"Parent has no other children"
+		);
 
-					vfs.remove(&folder_path)?;
-					trace!(
-						"remove_non_project_instances: Removed original folder {}",
-						folder_path.display()
-					);
-					meta.set_source(source);
-					trace!(
-						"remove_non_project_instances: Set parent meta source to new file source: {:?}",
-						meta.source
-					);
-				} else {
-					trace!("remove_non_project_instances: Could not locate new path for file, transformation aborted.");
-				}
-			} else {
-				trace!("remove_non_project_instances: Parent meta does not have an associated file entry, cannot transform.");
-			}
-		} else {
-			trace!("remove_non_project_instances: Parent source is not a Path or is not a folder, skipping transformation.");
+		// Layout preference of the parent class takes precedence over collapse mode
+		match (preference, collapse_mode) {
+			(LayoutPreference::Folder, _) | (LayoutPreference::Auto, Some(CollapseMode::Never)) => {}
+			(LayoutPreference::Auto, Some(CollapseMode::BatchEnd)) => tree.defer_collapse(parent_ref),
+			_ => collapse_folder(parent_ref, tree, vfs)?,
 		}
 
-		trace!("remove_non_project_instances: Exiting function successfully");
 		Ok(())
 	}
 
-	trace!("apply_removal: Matching source kind: {:?}", meta.source.get());
 	match meta.source.get().clone() {
 		SourceKind::Path(_) => {
-			trace!("apply_removal: Source is Path, calling remove_non_project_instances");
 			remove_non_project_instances(id, &meta, tree, vfs)?;
 		}
 		SourceKind::Project(name, path, node, node_path) => {
 			trace!(
-				"apply_removal: Source is Project: name={}, path={}, node_path={:?}",
-				name,
-				path.display(),
-				node_path
+				project:% = path.display(),
+				node_path:? = node_path,
+				custom_path = node.path.is_some();
+				"Removing project node"
 			);
-			let mut project = Project::load(&path)?;
-			trace!("apply_removal: Loaded project from {}", path.display());
+
+			let mut project = Project::edit(&path)?;
 			let parent_node_path = node_path.parent();
-			trace!(
-				"apply_removal: Finding parent project node at path: {:?}",
-				parent_node_path
-			);
 			let parent_node = project.find_node_by_path(&parent_node_path);
 
-			trace!("apply_removal: Attempting to remove node '{}' from parent's tree", name);
-			let removed_node = parent_node.and_then(|node| node.tree.remove(&name)).ok_or_else(|| {
+			parent_node.and_then(|node| node.tree.remove(&name)).ok_or_else(|| {
 				let msg = format!(
 					"apply_removal: Failed to remove instance {:?} (name: {}) from project node tree at path {:?}",
 					id, name, parent_node_path
@@ -1414,31 +1978,118 @@ pub fn apply_removal(id: Ref, tree: &mut Tree, vfs: &Vfs) -> Result<()> {
 				error!("{}", msg);
 				anyhow!(msg)
 			})?;
-			trace!(
-				"apply_removal: Successfully removed node from project tree: {:?}",
-				removed_node
-			);
 
 			if node.path.is_some() {
-				trace!("apply_removal: Project node had a custom path, calling remove_non_project_instances to clean up external files.");
 				remove_non_project_instances(id, &meta, tree, vfs)?;
-			} else {
-				trace!("apply_removal: Project node did not have a custom path.");
 			}
 
 			project.save(&path)?;
-			trace!("apply_removal: Saved project to {}", path.display());
 		}
-		SourceKind::None => {
-			let msg = format!("apply_removal: Attempted to remove instance {:?} with no source", id);
-			error!("{}", msg);
-			panic!("{}", msg);
+		SourceKind::Ephemeral => {
+			unreachable!("apply_removal: Ephemeral instances are removed from memory only")
 		}
+		SourceKind::None => bail!("Instance has no source"),
 	}
 
 	tree.remove_instance(id);
-	trace!("apply_removal: Removed instance {:?} from tree.", id);
 
-	trace!("apply_removal: Exiting function successfully");
+	Ok(())
+}
+
+/// Transforms folder instance source back to a single file, used once
+/// the instance no longer has any children, see `CollapseMode`
+fn collapse_folder(id: Ref, tree: &mut Tree, vfs: &Vfs) -> Result<()> {
+	let Some(meta) = tree.get_meta_mut(id) else {
+		trace!(id:% = id; "Instance no longer exists, skipping collapse");
+		return Ok(());
+	};
+
+	if let SourceKind::Path(folder_path) = meta.source.get().clone() {
+		let name = folder_path.get_name();
+
+		let filter = meta.context.syncback_filter();
+
+		if let Some(rule) = filter.find_path(&folder_path) {
+			filter_warn!(filter, id, name, SkippedOperation::Update, rule, folder_path);
+			return Ok(());
+		}
+
+		if let Some(file_entry) = meta.source.get_file().cloned() {
+			let file_path_in_folder = file_entry.path();
+			let file_path_outside_folder = meta
+				.context
+				.sync_rules()
+				.find(|rule| rule.matches_child(file_path_in_folder))
+				.and_then(|rule| rule.locate(&folder_path, name, false));
+
+			trace!(
+				id:% = id,
+				folder:% = folder_path.display(),
+				file:% = file_path_in_folder.display(),
+				new_path:? = file_path_outside_folder;
+				"Collapsing folder"
+			);
+
+			if let Some(new_path) = file_path_outside_folder {
+				// Moved files are put back if the folder can't be removed
+				let transaction = vfs.transaction();
+
+				vfs.rename(file_path_in_folder, &new_path)?;
+				let mut source = Source::file(&new_path);
+
+				if let Some(data_entry) = meta.source.get_data().cloned() {
+					let data_path_in_folder = data_entry.path();
+					let data_path_outside_folder = meta
+						.context
+						.data_rules(Some(data_path_in_folder))
+						.iter()
+						.find_map(|rule| rule.locate(&folder_path, name, false));
+
+					trace!(
+						data_path:% = data_path_in_folder.display(),
+						new_path:? = data_path_outside_folder;
+						"Collapsing folder data"
+					);
+
+					if let Some(new_data_path) = data_path_outside_folder {
+						let data_paths = data::move_data(data_path_in_folder, &new_data_path, vfs)?;
+
+						for path in data_paths {
+							source.add_data(&path);
+						}
+					}
+				}
+
+				// Data index is outside of the folder and the name stays the same
+				if let Some(index) = meta.source.get_index().cloned() {
+					source.extend_relevant(vec![index]);
+				}
+
+				vfs.remove(&folder_path)?;
+
+				transaction.commit()?;
+				meta.set_source(source);
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Collapses folders deferred with `CollapseMode::BatchEnd`
+/// that are still empty, has to be called after every batch
+pub fn finish_batch(tree: &mut Tree, vfs: &Vfs) -> Result<()> {
+	for id in tree.take_deferred_collapses() {
+		let is_empty = tree
+			.get_instance(id)
+			.is_some_and(|instance| instance.children().is_empty());
+
+		trace!(id:% = id, is_empty; "Finishing deferred collapse");
+
+		if is_empty {
+			collapse_folder(id, tree, vfs)?;
+		}
+	}
+
 	Ok(())
 }
@@ -0,0 +1,119 @@
+use rbx_dom_weak::types::Ref;
+use serde::Serialize;
+use std::cell::RefCell;
+
+use crate::{codes::ErrorCode, constants::MAX_PROPERTY_OUTCOMES};
+
+/// What happened to a property of a snapshot synced back from Studio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PropertyStatus {
+	Applied,
+	Skipped,
+	Transformed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropertyOutcome {
+	pub property: String,
+	pub status: PropertyStatus,
+	/// Why the property was skipped or transformed, `None` if applied as is
+	pub code: Option<ErrorCode>,
+	/// Name the value was written under, only for canonical renames
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub renamed: Option<String>,
+}
+
+impl PropertyOutcome {
+	pub fn applied(property: &str) -> Self {
+		Self {
+			property: property.to_owned(),
+			status: PropertyStatus::Applied,
+			code: None,
+			renamed: None,
+		}
+	}
+
+	pub fn skipped(property: &str, code: ErrorCode) -> Self {
+		Self {
+			property: property.to_owned(),
+			status: PropertyStatus::Skipped,
+			code: Some(code),
+			renamed: None,
+		}
+	}
+
+	pub fn transformed(property: &str, code: ErrorCode) -> Self {
+		Self {
+			property: property.to_owned(),
+			status: PropertyStatus::Transformed,
+			code: Some(code),
+			renamed: None,
+		}
+	}
+
+	pub fn with_renamed(mut self, renamed: &str) -> Self {
+		self.renamed = Some(renamed.to_owned());
+		self
+	}
+}
+
+/// Outcomes of all properties of a single instance, instances with more than
+/// `MAX_PROPERTY_OUTCOMES` properties only list the skipped and transformed ones
+/// first and as many applied ones as fit, the rest are counted in `truncated`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropertyReport {
+	pub id: Ref,
+	pub class: String,
+	pub properties: Vec<PropertyOutcome>,
+	pub truncated: usize,
+}
+
+impl PropertyReport {
+	pub fn new(id: Ref, class: &str, mut properties: Vec<PropertyOutcome>) -> Self {
+		// Outcomes clients have to show come first, in a stable order
+		properties.sort_by(|a, b| {
+			(a.status == PropertyStatus::Applied, &a.property).cmp(&(b.status == PropertyStatus::Applied, &b.property))
+		});
+
+		let truncated = properties.len().saturating_sub(MAX_PROPERTY_OUTCOMES);
+		properties.truncate(MAX_PROPERTY_OUTCOMES);
+
+		Self {
+			id,
+			class: class.to_owned(),
+			properties,
+			truncated,
+		}
+	}
+}
+
+thread_local! {
+	static REPORTS: RefCell<Option<Vec<PropertyReport>>> = const { RefCell::new(None) };
+}
+
+/// Starts collecting property reports of the current thread, see `finish`
+pub fn begin() {
+	REPORTS.with_borrow_mut(|reports| *reports = Some(vec![]));
+}
+
+/// Returns reports collected since `begin` and stops collecting
+pub fn finish() -> Vec<PropertyReport> {
+	REPORTS.with_borrow_mut(Option::take).unwrap_or_default()
+}
+
+/// Whether the current thread is collecting reports, outcomes don't have to be tracked otherwise
+pub fn is_collecting() -> bool {
+	REPORTS.with_borrow(Option::is_some)
+}
+
+/// Records the report if the current thread is collecting them
+pub fn record(report: PropertyReport) {
+	REPORTS.with_borrow_mut(|reports| {
+		if let Some(reports) = reports {
+			reports.push(report);
+		}
+	});
+}
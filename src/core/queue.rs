@@ -1,13 +1,33 @@
 use anyhow::{bail, Result};
 use colored::Colorize;
 use crossbeam_channel::{Receiver, Sender};
-use std::{collections::HashMap, sync::RwLock};
+use rbx_dom_weak::types::{Ref, Variant};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::{HashMap, VecDeque},
+	sync::{Arc, Mutex, RwLock},
+	time::Duration,
+};
 
+use super::{
+	blobs::{self, BlobRef, BlobStore},
+	changes::Changes,
+	pending::PendingKind,
+	subscription::{SubscriptionFilter, View},
+	tree::Tree,
+};
 use crate::{
 	argon_warn,
 	config::Config,
 	constants::QUEUE_TIMEOUT,
-	server::{self, Message},
+	lock,
+	server::{
+		self,
+		protocol::{Capability, Negotiated},
+		Message,
+	},
+	t,
+	vfs::Vfs,
 };
 
 macro_rules! read {
@@ -27,6 +47,35 @@ struct Listener {
 	pub id: u32,
 	pub name: String,
 	pub is_internal: bool,
+	pub place: PlaceDetails,
+	/// Protocol version and capabilities agreed on during handshake
+	pub negotiated: Negotiated,
+	/// Blobs referenced by the last message the client received
+	pub pending: Vec<BlobRef>,
+	/// Instances the client has, `None` if it subscribed to the whole tree
+	pub view: Option<View>,
+}
+
+/// Game and place reported by the client during handshake
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceDetails {
+	pub game_id: Option<u64>,
+	pub place_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Client {
+	pub id: u32,
+	pub name: String,
+	#[serde(flatten)]
+	pub place: PlaceDetails,
+	#[serde(flatten)]
+	pub negotiated: Negotiated,
+	/// Part of the tree the client subscribed to, `None` for the whole tree
+	#[serde(default)]
+	pub filter: Option<SubscriptionFilter>,
 }
 
 #[derive(Debug)]
@@ -35,11 +84,41 @@ struct Channel {
 	receiver: Receiver<Message>,
 }
 
+/// What a queued message changes, recorded for every message
+/// in the client queue so it can be checkpointed without draining it
+#[derive(Debug, Clone)]
+enum JournalEntry {
+	Changes(Vec<(Ref, PendingKind)>),
+	/// Message that does not matter after the client reconnects, e.g. a log
+	Transient,
+	/// Message that can't be restored, e.g. a chunk of the initial sync
+	Volatile,
+}
+
+#[derive(Debug, Default)]
+struct Journal {
+	delivered: u64,
+	entries: VecDeque<JournalEntry>,
+}
+
+/// State of the client queue at the moment, see `Queue::journal`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueueJournal {
+	/// Number of messages the client received
+	pub delivered: u64,
+	/// Instances changed by messages the client has not received yet
+	pub changes: Vec<(Ref, PendingKind)>,
+	/// Whether any message waiting in the queue can't be restored
+	pub volatile: bool,
+}
+
 #[derive(Debug)]
 pub struct Queue {
 	queues: RwLock<HashMap<u32, Channel>>,
 	listeners: RwLock<Vec<Listener>>,
+	journals: Mutex<HashMap<u32, Journal>>,
 	unsynced_changes: RwLock<u16>,
+	blobs: BlobStore,
 }
 
 impl Queue {
@@ -47,7 +126,9 @@ impl Queue {
 		Self {
 			queues: RwLock::new(HashMap::new()),
 			listeners: RwLock::new(Vec::new()),
+			journals: Mutex::new(HashMap::new()),
 			unsynced_changes: RwLock::new(0),
+			blobs: BlobStore::new(),
 		}
 	}
 
@@ -60,24 +141,85 @@ impl Queue {
 				bail!("Not subscribed")
 			}
 
+			let message: Message = message.into();
+			let mut deduplicated = None;
+
+			let supports_blobs = self.supports(id, Capability::Blobs);
+			let entry = journal_entry(&message);
+
+			// Journal is locked until the message is recorded so it can't be delivered before
+			let mut journals = lock!(self.journals);
 			let queues = read!(self.queues);
 			let sender = queues.get(&id).unwrap().sender.clone();
 
-			sender.send(message.into())?;
+			sender.send(self.prepare(message, supports_blobs, &mut deduplicated))?;
+
+			if let Some(journal) = journals.get_mut(&id) {
+				journal.entries.push_back(entry);
+			}
 
 			return Ok(());
 		}
 
-		let message: Message = message.into();
+		self.broadcast(message.into(), false)
+	}
+
+	/// Pushes changes to clients, clients that subscribed to a part
+	/// of the tree only get changes in its scope, see `View::apply`
+	pub fn push_changes(&self, changes: Changes, id: Option<u32>, tree: &Tree, vfs: &Vfs) -> Result<()> {
+		let mut scoped = vec![];
+
+		for listener in write!(self.listeners).iter_mut() {
+			if id.is_some_and(|id| id != listener.id) {
+				continue;
+			}
+
+			if let Some(view) = &mut listener.view {
+				scoped.push((listener.id, view.apply(&changes, tree, vfs)));
+			}
+		}
+
+		for (id, changes) in scoped {
+			if !changes.is_empty() {
+				self.push(server::SyncChanges(changes), Some(id))?;
+			}
+		}
+
+		match id {
+			Some(id) if self.filter(id).is_some() => Ok(()),
+			Some(id) => self.push(server::SyncChanges(changes), Some(id)),
+			None => self.broadcast(server::SyncChanges(changes).into(), true),
+		}
+	}
+
+	/// Pushes the message to every client, `skip_filtered`
+	/// leaves out clients that subscribed to a part of the tree
+	fn broadcast(&self, message: Message, skip_filtered: bool) -> Result<()> {
+		let entry = journal_entry(&message);
+		let mut deduplicated = None;
 		let mut did_push = false;
 
 		for listener in read!(self.listeners).iter() {
+			// Changes are not counted as unsynced when only filtered clients are connected
+			did_push = true;
+
+			if skip_filtered && listener.view.is_some() {
+				continue;
+			}
+
+			let mut journals = lock!(self.journals);
 			let queues = read!(self.queues);
 			let sender = queues.get(&listener.id).unwrap().sender.clone();
 
-			sender.send(message.clone())?;
+			sender.send(self.prepare(
+				message.clone(),
+				listener.negotiated.supports(Capability::Blobs),
+				&mut deduplicated,
+			))?;
 
-			did_push = true;
+			if let Some(journal) = journals.get_mut(&listener.id) {
+				journal.entries.push_back(entry.clone());
+			}
 		}
 
 		if !did_push {
@@ -87,10 +229,7 @@ impl Queue {
 			*unsynced_changes += 1;
 
 			if max_unsynced_changes > 0 && *unsynced_changes >= max_unsynced_changes {
-				argon_warn!(
-					"There are {} unsynced changes. Connect at least one client to this server or increase max_unsynced_changes setting to suppress this warning",
-					unsynced_changes.to_string().bold()
-				);
+				argon_warn!("{}", t!("queue.unsynced", count = unsynced_changes.to_string().bold()));
 			}
 		}
 
@@ -108,11 +247,16 @@ impl Queue {
 		drop(queues);
 
 		let message = receiver.recv().ok();
+		self.deliver(id, message.as_ref());
 
 		Ok(message)
 	}
 
 	pub fn get_timeout(&self, id: u32) -> Result<Option<Message>> {
+		self.get_with_timeout(id, QUEUE_TIMEOUT)
+	}
+
+	pub fn get_with_timeout(&self, id: u32, timeout: Duration) -> Result<Option<Message>> {
 		if !self.is_subscribed(id) {
 			bail!("Not subscribed")
 		}
@@ -122,12 +266,13 @@ impl Queue {
 
 		drop(queues);
 
-		let message = receiver.recv_timeout(QUEUE_TIMEOUT).ok();
+		let message = receiver.recv_timeout(timeout).ok();
+		self.deliver(id, message.as_ref());
 
 		Ok(message)
 	}
 
-	pub fn subscribe(&self, id: u32, name: &str) -> Result<()> {
+	pub fn subscribe(&self, id: u32, name: &str, place: PlaceDetails, negotiated: Negotiated) -> Result<()> {
 		if self.is_subscribed(id) {
 			bail!("Already subscribed")
 		}
@@ -139,14 +284,70 @@ impl Queue {
 			id,
 			name: name.to_owned(),
 			is_internal: false,
+			place,
+			negotiated,
+			pending: vec![],
+			view: None,
 		};
 
 		write!(self.listeners).push(listener);
+		lock!(self.journals).insert(id, Journal::default());
 		write!(self.queues).insert(id.to_owned(), channel);
 
 		Ok(())
 	}
 
+	/// Sets the part of the tree the client subscribed to during handshake,
+	/// the client is expected to fetch instances in its scope afterwards
+	pub fn set_filter(&self, id: u32, filter: Option<SubscriptionFilter>, tree: &Tree) -> Result<()> {
+		let mut listeners = write!(self.listeners);
+
+		let Some(listener) = listeners.iter_mut().find(|listener| listener.id == id) else {
+			bail!("Not subscribed")
+		};
+
+		listener.view = filter
+			.filter(|filter| !filter.is_empty())
+			.map(|filter| View::new(filter, tree));
+
+		Ok(())
+	}
+
+	/// Changes the part of the tree the client subscribed to, instances that
+	/// entered the scope are pushed as additions and the ones that left it
+	/// as removals, returns the number of pushed changes
+	pub fn change_filter(&self, id: u32, filter: Option<SubscriptionFilter>, tree: &Tree, vfs: &Vfs) -> Result<usize> {
+		let mut listeners = write!(self.listeners);
+
+		let Some(listener) = listeners.iter_mut().find(|listener| listener.id == id) else {
+			bail!("Not subscribed")
+		};
+
+		let filter = filter.filter(|filter| !filter.is_empty());
+		let mut view = listener.view.take().unwrap_or_else(|| View::whole(tree));
+		let changes = view.set_filter(filter.clone().unwrap_or_default(), tree, vfs);
+
+		listener.view = filter.map(|_| view);
+		drop(listeners);
+
+		let total = changes.total();
+
+		if total > 0 {
+			self.push(server::SyncChanges(changes), Some(id))?;
+		}
+
+		Ok(total)
+	}
+
+	/// Part of the tree the client subscribed to, `None` for the whole tree
+	pub fn filter(&self, id: u32) -> Option<SubscriptionFilter> {
+		read!(self.listeners)
+			.iter()
+			.find(|listener| listener.id == id)
+			.and_then(|listener| listener.view.as_ref())
+			.map(|view| view.filter().clone())
+	}
+
 	pub fn subscribe_internal(&self) -> Result<()> {
 		let mut id = 0;
 
@@ -165,6 +366,10 @@ impl Queue {
 			id,
 			name: format!("Internal listener #{}", id),
 			is_internal: true,
+			place: PlaceDetails::default(),
+			negotiated: Negotiated::default(),
+			pending: vec![],
+			view: None,
 		};
 
 		write!(self.listeners).push(listener);
@@ -173,15 +378,117 @@ impl Queue {
 		Ok(())
 	}
 
-	pub fn unsubscribe(&self, id: u32) -> Result<()> {
+	/// Removes the client, returns changes it has not received yet
+	pub fn unsubscribe(&self, id: u32) -> Result<Vec<Changes>> {
 		if !self.is_subscribed(id) {
 			bail!("Not subscribed")
 		}
 
-		write!(self.listeners).retain(|listener| listener.id != id);
-		write!(self.queues).remove(&id);
+		let mut listeners = write!(self.listeners);
 
-		Ok(())
+		if let Some(listener) = listeners.iter().find(|listener| listener.id == id) {
+			self.blobs.release(&listener.pending);
+		}
+
+		listeners.retain(|listener| listener.id != id);
+		drop(listeners);
+
+		lock!(self.journals).remove(&id);
+
+		let mut undelivered = vec![];
+
+		// Release blobs of messages the client will never receive
+		if let Some(channel) = write!(self.queues).remove(&id) {
+			while let Ok(message) = channel.receiver.try_recv() {
+				match message {
+					Message::SyncChanges(server::SyncChanges(changes)) => undelivered.push(changes),
+					Message::SyncBlobChanges(message) => {
+						self.blobs.release(&message.blobs);
+						undelivered.push(message.changes);
+					}
+					_ => {}
+				}
+			}
+		}
+
+		Ok(undelivered)
+	}
+
+	pub fn blobs(&self) -> &BlobStore {
+		&self.blobs
+	}
+
+	/// Returns what the client received so far and what is still waiting
+	/// in its queue, `None` for internal listeners and unknown clients
+	pub fn journal(&self, id: u32) -> Option<QueueJournal> {
+		let journals = lock!(self.journals);
+		let journal = journals.get(&id)?;
+
+		let mut changes = vec![];
+		let mut volatile = false;
+
+		for entry in &journal.entries {
+			match entry {
+				JournalEntry::Changes(entry) => changes.extend(entry.iter().copied()),
+				JournalEntry::Transient => {}
+				JournalEntry::Volatile => volatile = true,
+			}
+		}
+
+		Some(QueueJournal {
+			delivered: journal.delivered,
+			changes,
+			volatile,
+		})
+	}
+
+	/// Replaces large values in sync changes with blob references if the client
+	/// supports them, changes are deduplicated only once for all clients
+	fn prepare(
+		&self,
+		message: Message,
+		supports_blobs: bool,
+		deduplicated: &mut Option<(server::SyncBlobChanges, HashMap<String, Arc<Variant>>)>,
+	) -> Message {
+		let changes = match &message {
+			Message::SyncChanges(server::SyncChanges(changes)) if supports_blobs => changes,
+			_ => return message,
+		};
+
+		let (message, values) = deduplicated.get_or_insert_with(|| {
+			let (changes, refs, values) = blobs::deduplicate(changes);
+			(server::SyncBlobChanges { changes, blobs: refs }, values)
+		});
+
+		self.blobs.retain(&message.blobs, values);
+
+		Message::SyncBlobChanges(message.clone())
+	}
+
+	/// Moves blob references of the received message to the client,
+	/// the previous ones are released as the client has already fetched them
+	fn deliver(&self, id: u32, message: Option<&Message>) {
+		let mut listeners = write!(self.listeners);
+
+		if message.is_some() {
+			if let Some(journal) = lock!(self.journals).get_mut(&id) {
+				journal.entries.pop_front();
+				journal.delivered += 1;
+			}
+		}
+
+		if let Some(listener) = listeners.iter_mut().find(|listener| listener.id == id) {
+			if listener.pending.is_empty() && message.is_none() {
+				return;
+			}
+
+			self.blobs.release(&listener.pending);
+
+			listener.pending = match message {
+				Some(Message::SyncBlobChanges(message)) => message.blobs.clone(),
+				_ => vec![],
+			};
+		}
 	}
 
 	pub fn disconnect(&self, message: &str, id: u32) -> Result<()> {
@@ -203,6 +510,27 @@ impl Queue {
 		read!(self.listeners).iter().any(|listener| listener.id == id)
 	}
 
+	/// Whether the client negotiated the capability during handshake
+	pub fn supports(&self, id: u32, capability: Capability) -> bool {
+		read!(self.listeners)
+			.iter()
+			.any(|listener| listener.id == id && listener.negotiated.supports(capability))
+	}
+
+	pub fn clients(&self) -> Vec<Client> {
+		read!(self.listeners)
+			.iter()
+			.filter(|listener| !listener.is_internal)
+			.map(|listener| Client {
+				id: listener.id,
+				name: listener.name.to_owned(),
+				place: listener.place.clone(),
+				negotiated: listener.negotiated.clone(),
+				filter: listener.view.as_ref().map(|view| view.filter().clone()),
+			})
+			.collect()
+	}
+
 	pub fn get_first_non_internal_listener_name(&self) -> Option<String> {
 		read!(self.listeners)
 			.iter()
@@ -210,3 +538,36 @@ impl Queue {
 			.map(|listener| listener.name.to_owned())
 	}
 }
+
+fn journal_entry(message: &Message) -> JournalEntry {
+	let changes = match message {
+		Message::SyncChanges(server::SyncChanges(changes)) => changes,
+		Message::SyncBlobChanges(message) => &message.changes,
+		Message::SyncProgress(_)
+		| Message::SyncDetails(_)
+		| Message::Heartbeat(_)
+		| Message::Log(_)
+		| Message::SyncbackRejected(_)
+		| Message::SyncbackReport(_)
+		| Message::PauseChanged(_) => return JournalEntry::Transient,
+		_ => return JournalEntry::Volatile,
+	};
+
+	let mut entry = vec![];
+
+	entry.extend(
+		changes
+			.additions
+			.iter()
+			.map(|snapshot| (snapshot.id, PendingKind::Added)),
+	);
+	entry.extend(
+		changes
+			.updates
+			.iter()
+			.map(|snapshot| (snapshot.id, PendingKind::Updated)),
+	);
+	entry.extend(changes.removals.iter().map(|id| (*id, PendingKind::Removed)));
+
+	JournalEntry::Changes(entry)
+}
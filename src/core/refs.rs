@@ -0,0 +1,145 @@
+use rbx_dom_weak::{
+	types::{Ref, Variant, VariantType},
+	Ustr,
+};
+use rbx_reflection::DataType;
+use std::collections::BTreeMap;
+
+use super::tree::Tree;
+use crate::{constants::EXTERNAL_REF, resolution, Properties};
+
+/// Paths of the instances that `Ref` properties point at, keyed by property
+pub type RefPaths = BTreeMap<Ustr, String>;
+
+/// Whether the property points at another instance, e.g. `Model.PrimaryPart`
+pub fn is_ref(class: &str, property: &str) -> bool {
+	resolution::find_descriptor(class, property)
+		.is_some_and(|descriptor| matches!(descriptor.data_type, DataType::Value(VariantType::Ref)))
+}
+
+/// Returns portable path of the target as seen from the source, relative to their
+/// nearest common ancestor, e.g. `./Handle` or `../Tools/Sword`, or absolute when
+/// it is the root, e.g. `/Workspace/Baseplate`. `None` if either of them is not
+/// in the tree or one of the names can't be a part of the path
+pub fn to_path(source: Ref, target: Ref, tree: &Tree) -> Option<String> {
+	let source_chain = chain(source, tree)?;
+	let target_chain = chain(target, tree)?;
+
+	let common = source_chain
+		.iter()
+		.zip(&target_chain)
+		.take_while(|(source, target)| source == target)
+		.count();
+
+	let names = target_chain[common..]
+		.iter()
+		.map(|id| {
+			let name = tree.get_instance(*id).unwrap().name.as_str();
+			(!name.contains('/') && name != "." && name != "..").then_some(name)
+		})
+		.collect::<Option<Vec<_>>>()?;
+
+	if common <= 1 {
+		return Some(format!("/{}", names.join("/")));
+	}
+
+	let mut segments = vec![".."; source_chain.len() - common];
+
+	if segments.is_empty() {
+		segments.push(".");
+	}
+
+	segments.extend(names);
+
+	Some(segments.join("/"))
+}
+
+/// Returns the instance at the path written by `to_path`, the first
+/// one if there are multiple siblings with the same name
+pub fn find(source: Ref, path: &str, tree: &Tree) -> Option<Ref> {
+	let (mut id, path) = match path.strip_prefix('/') {
+		Some(path) => (tree.root_ref(), path),
+		None if path.starts_with('.') => (source, path),
+		None => return None,
+	};
+
+	for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+		let instance = tree.get_instance(id)?;
+
+		id = match segment {
+			"." => id,
+			".." => tree.get_instance(instance.parent()).map(|parent| parent.referent())?,
+			name => *instance
+				.children()
+				.iter()
+				.find(|child| tree.get_instance(**child).is_some_and(|child| child.name == name))?,
+		};
+	}
+
+	Some(id)
+}
+
+/// Returns paths of all `Ref` properties of the instance, targets that are
+/// not in the tree get `EXTERNAL_REF` and cleared properties are left out
+pub fn to_paths(id: Ref, properties: &Properties, tree: &Tree) -> RefPaths {
+	properties
+		.iter()
+		.filter_map(|(property, value)| match value {
+			Variant::Ref(target) if target.is_some() => Some((
+				*property,
+				to_path(id, *target, tree).unwrap_or_else(|| EXTERNAL_REF.to_owned()),
+			)),
+			_ => None,
+		})
+		.collect()
+}
+
+/// Returns value of the property at the path, external targets keep the
+/// current value of the instance as Argon does not know where they are
+pub fn resolve(id: Ref, property: &Ustr, path: &str, tree: &Tree) -> Option<Variant> {
+	if path == EXTERNAL_REF {
+		return tree
+			.get_instance(id)
+			.and_then(|instance| instance.properties.get(property))
+			.filter(|value| matches!(value, Variant::Ref(_)))
+			.cloned();
+	}
+
+	find(id, path, tree).map(Variant::Ref)
+}
+
+/// Replaces `Ref` values with their paths so they can be written to
+/// files, the ones without a path point at nothing and are left out
+pub fn portable(mut properties: Properties, paths: &RefPaths) -> Properties {
+	properties.retain(|property, value| match value {
+		Variant::Ref(_) => match paths.get(property) {
+			Some(path) => {
+				*value = Variant::String(path.clone());
+				true
+			}
+			None => false,
+		},
+		_ => true,
+	});
+
+	properties
+}
+
+/// Instance and its ancestors, starting from the root
+fn chain(id: Ref, tree: &Tree) -> Option<Vec<Ref>> {
+	let mut chain = vec![];
+	let mut current = tree.get_instance(id)?;
+
+	loop {
+		chain.push(current.referent());
+
+		match tree.get_instance(current.parent()) {
+			Some(parent) => current = parent,
+			None => break,
+		}
+	}
+
+	chain.reverse();
+
+	Some(chain)
+}
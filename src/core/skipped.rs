@@ -0,0 +1,322 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use log::warn;
+use rbx_dom_weak::types::Ref;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::{HashMap, VecDeque},
+	fmt::{self, Display, Formatter},
+	fs,
+	path::{Path, PathBuf},
+	ptr,
+	sync::{Arc, Mutex, OnceLock},
+};
+
+use crate::{config::Config, lock};
+
+/// Syncback operation that did not pass the filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SkippedOperation {
+	Add,
+	Update,
+	Remove,
+}
+
+impl Display for SkippedOperation {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			SkippedOperation::Add => write!(f, "add"),
+			SkippedOperation::Update => write!(f, "update"),
+			SkippedOperation::Remove => write!(f, "remove"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum RuleKind {
+	Name,
+	Class,
+	Path,
+	/// Compiled roblox-ts output, `pattern` is the output directory
+	Output,
+	/// Read-only source like a model file, `pattern` is the reason
+	ReadOnly,
+}
+
+impl Display for RuleKind {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			RuleKind::Name => write!(f, "name"),
+			RuleKind::Class => write!(f, "class"),
+			RuleKind::Path => write!(f, "path"),
+			RuleKind::Output => write!(f, "output"),
+			RuleKind::ReadOnly => write!(f, "read-only"),
+		}
+	}
+}
+
+/// Rule of the syncback filter that excluded an instance or path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterRule {
+	pub kind: RuleKind,
+	pub pattern: String,
+}
+
+impl FilterRule {
+	pub fn new(kind: RuleKind, pattern: impl Into<String>) -> Self {
+		Self {
+			kind,
+			pattern: pattern.into(),
+		}
+	}
+}
+
+impl Display for FilterRule {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{}: {}", self.kind, self.pattern)
+	}
+}
+
+/// Repeated skips of the same instance by the same rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedEntry {
+	pub id: Ref,
+	/// Path of the instance when it was last skipped
+	pub path: String,
+	/// Operation of the last skip
+	pub operation: SkippedOperation,
+	pub rule: FilterRule,
+	/// File or directory that matched the path rule
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub file: Option<PathBuf>,
+	pub count: u64,
+	/// Unix timestamps in seconds of the first and the last skip
+	pub first: i64,
+	pub last: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedQuery {
+	/// Only entries skipped at or after this timestamp
+	pub since: Option<i64>,
+	/// Only entries first skipped at or before this timestamp
+	pub until: Option<i64>,
+	pub kind: Option<RuleKind>,
+	/// Only entries of the rule with this exact pattern
+	pub pattern: Option<String>,
+}
+
+impl SkippedQuery {
+	fn matches(&self, entry: &SkippedEntry) -> bool {
+		self.since.is_none_or(|since| entry.last >= since)
+			&& self.until.is_none_or(|until| entry.first <= until)
+			&& self.kind.is_none_or(|kind| entry.rule.kind == kind)
+			&& self
+				.pattern
+				.as_ref()
+				.is_none_or(|pattern| entry.rule.pattern == *pattern)
+	}
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedStats {
+	/// Entries currently kept
+	pub entries: usize,
+	/// Skipped operations, including coalesced ones
+	pub skips: u64,
+	/// Entries dropped to stay within `max_skipped_entries`
+	pub evicted: u64,
+	/// Skipped operations that targeted read-only sources
+	pub read_only: u64,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+	/// Least recently skipped first
+	entries: VecDeque<SkippedEntry>,
+	skips: u64,
+	evicted: u64,
+	read_only: u64,
+}
+
+/// Syncback operations that did not pass the filter, so it can be answered
+/// later what was not written and why. Repeated skips of the same instance
+/// by the same rule are coalesced and the least recently skipped entries are
+/// dropped above `capacity`. Stored in `.argon/skipped.json` if persisted
+#[derive(Debug)]
+pub struct Skipped {
+	capacity: usize,
+	/// `None` if entries are not persisted
+	path: Option<PathBuf>,
+	inner: Mutex<Inner>,
+}
+
+impl Skipped {
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			capacity,
+			path: None,
+			inner: Mutex::new(Inner::default()),
+		}
+	}
+
+	/// Reads entries of the previous sessions of the workspace
+	pub fn load(workspace_dir: &Path, capacity: usize) -> Self {
+		let path = workspace_dir.join(".argon").join("skipped.json");
+
+		let entries = if path.exists() {
+			match read_entries(&path) {
+				Ok(entries) => entries,
+				Err(err) => {
+					warn!(
+						"Failed to read skipped syncback entries from {}: {}",
+						path.display(),
+						err
+					);
+					VecDeque::new()
+				}
+			}
+		} else {
+			VecDeque::new()
+		};
+
+		let skipped = Self {
+			capacity,
+			path: Some(path),
+			inner: Mutex::new(Inner {
+				entries,
+				..Inner::default()
+			}),
+		};
+
+		skipped.evict(&mut lock!(skipped.inner));
+		skipped
+	}
+
+	pub fn record(&self, id: Ref, path: &str, operation: SkippedOperation, rule: FilterRule, file: Option<&Path>) {
+		let now = chrono::Utc::now().timestamp();
+		let mut inner = lock!(self.inner);
+
+		inner.skips += 1;
+
+		if rule.kind == RuleKind::ReadOnly {
+			inner.read_only += 1;
+		}
+
+		let position = inner
+			.entries
+			.iter()
+			.position(|entry| entry.id == id && entry.rule == rule);
+
+		let entry = match position.and_then(|position| inner.entries.remove(position)) {
+			Some(entry) => SkippedEntry {
+				path: path.to_owned(),
+				operation,
+				file: file.map(Path::to_owned),
+				count: entry.count + 1,
+				last: now,
+				..entry
+			},
+			None => SkippedEntry {
+				id,
+				path: path.to_owned(),
+				operation,
+				rule,
+				file: file.map(Path::to_owned),
+				count: 1,
+				first: now,
+				last: now,
+			},
+		};
+
+		inner.entries.push_back(entry);
+
+		self.evict(&mut inner);
+
+		if let Err(err) = self.save(&inner) {
+			warn!("Failed to save skipped syncback entries: {}", err);
+		}
+	}
+
+	/// Returns entries matching the query, the most recently skipped first,
+	/// `path_of` provides the current path of instances that still exist
+	pub fn list(&self, query: &SkippedQuery, path_of: impl Fn(Ref) -> Option<String>) -> Vec<SkippedEntry> {
+		lock!(self.inner)
+			.entries
+			.iter()
+			.rev()
+			.filter(|entry| query.matches(entry))
+			.map(|entry| SkippedEntry {
+				path: path_of(entry.id).unwrap_or_else(|| entry.path.clone()),
+				..entry.clone()
+			})
+			.collect()
+	}
+
+	pub fn stats(&self) -> SkippedStats {
+		let inner = lock!(self.inner);
+
+		SkippedStats {
+			entries: inner.entries.len(),
+			skips: inner.skips,
+			evicted: inner.evicted,
+			read_only: inner.read_only,
+		}
+	}
+
+	fn evict(&self, inner: &mut Inner) {
+		while inner.entries.len() > self.capacity {
+			inner.entries.pop_front();
+			inner.evicted += 1;
+		}
+	}
+
+	fn save(&self, inner: &Inner) -> Result<()> {
+		let Some(path) = &self.path else {
+			return Ok(());
+		};
+
+		fs::create_dir_all(path.parent().unwrap())?;
+		fs::write(path, serde_json::to_string_pretty(&inner.entries)?)?;
+
+		Ok(())
+	}
+}
+
+/// Registries are shared by all contexts of the workspace
+/// so they are not identified by their contents
+impl PartialEq for Skipped {
+	fn eq(&self, other: &Self) -> bool {
+		ptr::eq(self, other)
+	}
+}
+
+/// Returns registry of the workspace, the same one is kept
+/// for the whole process so it survives project reloads
+pub fn get(workspace_dir: &Path) -> Arc<Skipped> {
+	static REGISTRIES: OnceLock<Mutex<HashMap<PathBuf, Arc<Skipped>>>> = OnceLock::new();
+
+	let mut registries = lock!(REGISTRIES.get_or_init(Mutex::default));
+
+	registries
+		.entry(workspace_dir.to_owned())
+		.or_insert_with(|| {
+			let config = Config::new();
+
+			Arc::new(if config.persist_skipped {
+				Skipped::load(workspace_dir, config.max_skipped_entries)
+			} else {
+				Skipped::new(config.max_skipped_entries)
+			})
+		})
+		.clone()
+}
+
+fn read_entries(path: &Path) -> Result<VecDeque<SkippedEntry>> {
+	Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
@@ -3,7 +3,10 @@ use rbx_dom_weak::{
 	HashMapExt, Ustr, UstrMap,
 };
 use serde::{Deserialize, Serialize};
-use std::fmt::{self, Debug, Formatter};
+use std::{
+	fmt::{self, Debug, Formatter},
+	mem,
+};
 
 use super::{helpers::apply_migrations, meta::Meta};
 use crate::{middleware::data::DataSnapshot, Properties};
@@ -34,36 +37,43 @@ impl Snapshot {
 		}
 	}
 
+	#[inline]
 	pub fn with_id(mut self, id: Ref) -> Self {
 		self.set_id(id);
 		self
 	}
 
+	#[inline]
 	pub fn with_meta(mut self, meta: Meta) -> Self {
 		self.set_meta(meta);
 		self
 	}
 
-	pub fn with_name(mut self, name: &str) -> Self {
+	#[inline]
+	pub fn with_name(mut self, name: impl Into<String>) -> Self {
 		self.set_name(name);
 		self
 	}
 
-	pub fn with_class(mut self, class: &str) -> Self {
+	#[inline]
+	pub fn with_class(mut self, class: impl Into<Ustr>) -> Self {
 		self.set_class(class);
 		self
 	}
 
+	#[inline]
 	pub fn with_properties(mut self, properties: Properties) -> Self {
 		self.set_properties(properties);
 		self
 	}
 
+	#[inline]
 	pub fn with_children(mut self, children: Vec<Snapshot>) -> Self {
 		self.set_children(children);
 		self
 	}
 
+	#[inline]
 	pub fn with_data(mut self, data: DataSnapshot) -> Self {
 		self.apply_data(data);
 		self
@@ -71,27 +81,33 @@ impl Snapshot {
 
 	// Overwriting snapshot fields
 
+	#[inline]
 	pub fn set_id(&mut self, id: Ref) {
 		self.id = id;
 	}
 
+	#[inline]
 	pub fn set_meta(&mut self, meta: Meta) {
 		self.meta = meta;
 	}
 
-	pub fn set_name(&mut self, name: &str) {
-		name.clone_into(&mut self.name);
+	#[inline]
+	pub fn set_name(&mut self, name: impl Into<String>) {
+		self.name = name.into();
 	}
 
-	pub fn set_class(&mut self, class: &str) {
+	#[inline]
+	pub fn set_class(&mut self, class: impl Into<Ustr>) {
 		self.class = class.into();
 	}
 
+	#[inline]
 	pub fn set_properties(&mut self, properties: Properties) {
 		self.properties = properties;
 		apply_migrations(&self.class, &mut self.properties);
 	}
 
+	#[inline]
 	pub fn set_children(&mut self, children: Vec<Snapshot>) {
 		self.children = children;
 	}
@@ -102,19 +118,36 @@ impl Snapshot {
 		}
 
 		if let Some(keep_unknowns) = data.keep_unknowns {
-			self.meta.keep_unknowns = keep_unknowns;
+			self.meta.set_keep_unknowns(keep_unknowns);
 		}
 
 		if let Some(original_name) = data.original_name {
-			self.name = original_name;
+			self.name = original_name.clone();
+			self.meta.set_original_name(Some(original_name));
 		}
 
 		if let Some(mesh_source) = data.mesh_source {
 			self.meta.set_mesh_source(Some(mesh_source));
 		}
 
+		if !data.ref_paths.is_empty() {
+			self.meta.set_ref_paths(data.ref_paths);
+		}
+
 		self.extend_properties(data.properties);
-		self.meta.source.add_data(&data.path);
+
+		match data.key {
+			Some(key) => self.meta.source.add_index(&data.path, &key),
+			None => self.meta.source.add_data(&data.path),
+		}
+
+		for include in &data.includes {
+			self.meta.source.add_data(include);
+		}
+
+		for reference in &data.references {
+			self.meta.source.add_reference(reference);
+		}
 	}
 
 	// Adding to snapshot fields
@@ -141,6 +174,31 @@ impl Snapshot {
 
 	// Miscellaneous
 
+	/// Returns number of levels of descendants, `0` if there are no children
+	pub fn height(&self) -> usize {
+		let mut height = 0;
+		let mut stack = vec![(self, 0)];
+
+		while let Some((snapshot, depth)) = stack.pop() {
+			height = height.max(depth);
+			stack.extend(snapshot.children.iter().map(|child| (child, depth + 1)));
+		}
+
+		height
+	}
+
+	pub fn into_new(mut self, parent: Ref) -> AddedSnapshot {
+		AddedSnapshot {
+			id: self.id,
+			meta: mem::take(&mut self.meta),
+			parent,
+			name: mem::take(&mut self.name),
+			class: self.class,
+			properties: mem::take(&mut self.properties),
+			children: mem::take(&mut self.children),
+		}
+	}
+
 	pub fn as_new(&self, parent: Ref) -> AddedSnapshot {
 		AddedSnapshot {
 			id: self.id,
@@ -154,6 +212,18 @@ impl Snapshot {
 	}
 }
 
+/// Drops descendants one by one instead of recursively,
+/// so hierarchies of any depth don't overflow the stack
+impl Drop for Snapshot {
+	fn drop(&mut self) {
+		let mut stack = mem::take(&mut self.children);
+
+		while let Some(mut snapshot) = stack.pop() {
+			stack.append(&mut snapshot.children);
+		}
+	}
+}
+
 impl Debug for Snapshot {
 	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
 		let mut debug = f.debug_struct("Snapshot");
@@ -164,19 +234,7 @@ impl Debug for Snapshot {
 		debug.field("meta", &self.meta);
 
 		if !self.properties.is_empty() {
-			let mut properties = self.properties.clone();
-
-			if let Some(property) = properties.get_mut(&Ustr::from("Source")) {
-				if let Variant::String(source) = property {
-					let lines = source.lines().count();
-
-					if lines > 1 {
-						*property = Variant::String(format!("Truncated... ({} lines)", lines));
-					}
-				}
-			}
-
-			debug.field("properties", &properties);
+			debug.field("properties", &DebugProperties(&self.properties));
 		}
 
 		if !self.children.is_empty() {
@@ -187,6 +245,31 @@ impl Debug for Snapshot {
 	}
 }
 
+/// Formats properties without cloning them, multi-line sources are truncated
+struct DebugProperties<'a>(&'a Properties);
+
+impl Debug for DebugProperties<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let mut debug = f.debug_map();
+
+		for (name, value) in self.0 {
+			match value {
+				Variant::String(source) if *name == "Source" && source.lines().nth(1).is_some() => {
+					debug.entry(
+						name,
+						&format_args!("String(\"Truncated... ({} lines)\")", source.lines().count()),
+					);
+				}
+				_ => {
+					debug.entry(name, value);
+				}
+			}
+		}
+
+		debug.finish()
+	}
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddedSnapshot {
 	pub id: Ref,
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::{BTreeMap, HashMap},
+	fs,
+	path::{Path, PathBuf},
+};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+	id: String,
+	/// Unix timestamp in seconds of the last time the instance existed
+	seen: i64,
+}
+
+/// Identifiers of instances that persist across sessions, stored
+/// in `.argon/ids.json` and keyed by the full name of the instance
+#[derive(Debug, Clone, Default)]
+pub struct StableIds {
+	/// `None` if identifiers are not persisted
+	path: Option<PathBuf>,
+	entries: BTreeMap<String, Entry>,
+	/// Number of days identifiers of removed instances are kept, 0 keeps them forever
+	retention: u32,
+}
+
+impl StableIds {
+	pub fn load(workspace_dir: &Path, retention: u32) -> Self {
+		let path = workspace_dir.join(".argon").join("ids.json");
+
+		let entries = if path.exists() {
+			match read_entries(&path) {
+				Ok(entries) => entries,
+				Err(err) => {
+					warn!("Failed to read stable instance IDs from {}: {}", path.display(), err);
+					BTreeMap::new()
+				}
+			}
+		} else {
+			BTreeMap::new()
+		};
+
+		Self {
+			path: Some(path),
+			entries,
+			retention,
+		}
+	}
+
+	pub fn is_enabled(&self) -> bool {
+		self.path.is_some()
+	}
+
+	/// Returns the identifier recorded for the instance or a new one,
+	/// `is_used` tells whether the recorded one already belongs to another
+	/// instance, e.g. one of siblings sharing the same name
+	pub fn assign(&self, full_name: &str, is_used: impl Fn(&str) -> bool) -> String {
+		match self.entries.get(full_name) {
+			Some(entry) if !is_used(&entry.id) => entry.id.clone(),
+			_ => Uuid::new_v4().to_string(),
+		}
+	}
+
+	/// Remembers identifier of the removed instance so it
+	/// is reused if the instance comes back with the same name
+	pub fn retire(&mut self, full_name: String, id: String) {
+		self.entries.insert(
+			full_name,
+			Entry {
+				id,
+				seen: chrono::Utc::now().timestamp(),
+			},
+		);
+	}
+
+	/// Records identifiers of the live instances under their current names,
+	/// drops expired ones and writes everything to `.argon/ids.json`
+	pub fn save(&mut self, live: &HashMap<String, String>) -> Result<()> {
+		let Some(path) = &self.path else {
+			return Ok(());
+		};
+
+		let now = chrono::Utc::now().timestamp();
+		let expiry = self.retention as i64 * 86_400;
+
+		let live_ids: HashMap<&String, &String> = live.iter().map(|(name, id)| (id, name)).collect();
+
+		// Renamed instances leave their old names behind
+		self.entries.retain(|name, entry| match live_ids.get(&entry.id) {
+			Some(live_name) => *live_name == name,
+			None => expiry == 0 || now - entry.seen <= expiry,
+		});
+
+		for (name, id) in live {
+			self.entries.insert(
+				name.clone(),
+				Entry {
+					id: id.clone(),
+					seen: now,
+				},
+			);
+		}
+
+		fs::create_dir_all(path.parent().unwrap())?;
+		fs::write(path, serde_json::to_string_pretty(&self.entries)?)?;
+
+		Ok(())
+	}
+}
+
+fn read_entries(path: &Path) -> Result<BTreeMap<String, Entry>> {
+	Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
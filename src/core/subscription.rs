@@ -0,0 +1,304 @@
+use rbx_dom_weak::types::Ref;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::{HashMap, HashSet},
+	mem,
+};
+
+use super::{
+	changes::Changes,
+	helpers::memory,
+	snapshot::{AddedSnapshot, Snapshot},
+	tree::Tree,
+};
+use crate::{class_meta, vfs::Vfs};
+
+/// Part of the tree a client subscribed to, e.g. a tree view that only
+/// shows scripts does not need changes of the Workspace geometry
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionFilter {
+	/// Paths of subtrees, e.g. `ReplicatedStorage.Modules`, their
+	/// ancestors are in scope too so the subtrees stay connected to the root
+	#[serde(default)]
+	pub paths: Vec<String>,
+	/// Classes of instances, including their subclasses
+	#[serde(default)]
+	pub classes: Vec<String>,
+}
+
+impl SubscriptionFilter {
+	/// Parses comma separated lists of paths and classes, as used in query strings
+	pub fn parse(paths: Option<&str>, classes: Option<&str>) -> Self {
+		let split = |list: Option<&str>| {
+			list.unwrap_or_default()
+				.split(',')
+				.map(str::trim)
+				.filter(|item| !item.is_empty())
+				.map(String::from)
+				.collect()
+		};
+
+		Self {
+			paths: split(paths),
+			classes: split(classes),
+		}
+	}
+
+	/// Filter without paths and classes lets everything through
+	pub fn is_empty(&self) -> bool {
+		self.paths.is_empty() && self.classes.is_empty()
+	}
+
+	/// Prunes the snapshot to the scope of the filter, it is followed by
+	/// instances in scope whose parents are not, the same way as in `View::apply`
+	pub fn prune(&self, snapshot: AddedSnapshot, tree: &Tree) -> Vec<AddedSnapshot> {
+		let mut view = View {
+			filter: self.clone(),
+			visible: HashMap::new(),
+		};
+
+		let mut changes = Changes::new();
+		let parent = snapshot.parent;
+
+		view.add(Snapshot::from(snapshot), parent, tree, &mut changes);
+
+		changes.additions
+	}
+
+	/// Whether the instance is in scope of the filter,
+	/// ancestors of the paths are regardless of their class
+	pub fn contains(&self, id: Ref, tree: &Tree) -> bool {
+		let Some(instance) = tree.get_instance(id) else {
+			return false;
+		};
+
+		let path = relative_path(id, tree);
+		let mut below = self.paths.is_empty();
+
+		for filter in &self.paths {
+			let filter: Vec<&str> = filter.split('.').filter(|name| !name.is_empty()).collect();
+
+			if filter.len() > path.len() && filter.starts_with(&path) {
+				return true;
+			}
+
+			if path.starts_with(&filter) {
+				below = true;
+			}
+		}
+
+		below
+			&& (self.classes.is_empty()
+				|| class_meta::class_chain(&instance.class)
+					.iter()
+					.any(|class| self.classes.iter().any(|filter| filter == class)))
+	}
+}
+
+/// Names of the instance and its ancestors, without the root
+fn relative_path(id: Ref, tree: &Tree) -> Vec<&str> {
+	let mut path = vec![];
+	let mut current = tree.get_instance(id);
+
+	while let Some(instance) = current {
+		let parent = tree.get_instance(instance.parent());
+
+		if parent.is_none() {
+			break;
+		}
+
+		path.push(instance.name.as_str());
+		current = parent;
+	}
+
+	path.reverse();
+	path
+}
+
+/// Instances a filtered client has, used to translate changes of the whole tree
+/// to changes of its part. Instances that cross the boundary of the scope,
+/// e.g. because they or their ancestors were renamed, are sent as additions
+/// when they enter it and as removals when they leave it
+#[derive(Debug, Clone)]
+pub struct View {
+	filter: SubscriptionFilter,
+	/// Instances the client has, along with their parents
+	visible: HashMap<Ref, Ref>,
+}
+
+impl View {
+	/// View of a client that is about to receive instances in scope of the filter
+	pub fn new(filter: SubscriptionFilter, tree: &Tree) -> Self {
+		let mut visible = HashMap::new();
+		let mut next = Some(tree.root_ref());
+
+		while let Some(id) = next {
+			if filter.contains(id, tree) {
+				visible.insert(id, tree.get_instance(id).unwrap().parent());
+			}
+
+			next = tree.next_in_order(id);
+		}
+
+		Self { filter, visible }
+	}
+
+	/// View of a client that has the whole tree
+	pub fn whole(tree: &Tree) -> Self {
+		Self::new(SubscriptionFilter::default(), tree)
+	}
+
+	pub fn filter(&self) -> &SubscriptionFilter {
+		&self.filter
+	}
+
+	/// Replaces the filter, returns changes that bring the client to the new scope
+	pub fn set_filter(&mut self, filter: SubscriptionFilter, tree: &Tree, vfs: &Vfs) -> Changes {
+		let mut changes = Changes::new();
+
+		self.filter = filter;
+		self.reconcile(tree.root_ref(), tree, vfs, &mut changes);
+
+		changes
+	}
+
+	/// Translates changes of the whole tree, which have already
+	/// been applied to it, to changes in scope of the filter
+	pub fn apply(&mut self, changes: &Changes, tree: &Tree, vfs: &Vfs) -> Changes {
+		let mut scoped = Changes::new();
+
+		// Instances removed and added back in the same batch
+		for id in &changes.removals {
+			if tree.exists(*id) && self.visible.remove(id).is_some() {
+				scoped.remove(*id);
+			}
+		}
+
+		let missing: HashSet<Ref> = self.visible.keys().filter(|id| !tree.exists(**id)).copied().collect();
+
+		// Only the topmost ones are removed, the client removes their descendants,
+		// which covers descendants in scope of instances that are not
+		for id in &missing {
+			let mut parent = self.visible.get(id);
+			let mut covered = false;
+
+			while let Some(id) = parent {
+				if missing.contains(id) {
+					covered = true;
+					break;
+				}
+
+				parent = self.visible.get(id);
+			}
+
+			if !covered {
+				scoped.remove(*id);
+			}
+		}
+
+		self.visible.retain(|id, _| !missing.contains(id));
+
+		for snapshot in &changes.additions {
+			let parent = snapshot.parent;
+			self.add(Snapshot::from(snapshot.clone()), parent, tree, &mut scoped);
+		}
+
+		for snapshot in &changes.updates {
+			let Some(instance) = tree.get_instance(snapshot.id) else {
+				continue;
+			};
+
+			let visible = self.visible.contains_key(&snapshot.id);
+			let contains = self.filter.contains(snapshot.id, tree);
+
+			if visible && contains {
+				scoped.update(snapshot.clone());
+
+				// Descendants can cross the boundary when the instance is renamed
+				if snapshot.name.is_some() || snapshot.class.is_some() {
+					for child in instance.children() {
+						self.reconcile(*child, tree, vfs, &mut scoped);
+					}
+				}
+			} else if visible || contains {
+				self.reconcile(snapshot.id, tree, vfs, &mut scoped);
+			}
+		}
+
+		scoped
+	}
+
+	/// Adds the snapshot if it is in scope, children that are not are left
+	/// out and their descendants that are get added on their own
+	fn add(&mut self, mut snapshot: Snapshot, parent: Ref, tree: &Tree, changes: &mut Changes) {
+		let children = mem::take(&mut snapshot.children);
+		let id = snapshot.id;
+
+		if !self.filter.contains(id, tree) {
+			for child in children {
+				self.add(child, id, tree, changes);
+			}
+
+			return;
+		}
+
+		self.visible.insert(id, parent);
+
+		let mut nested = Changes::new();
+
+		for child in children {
+			self.add(child, id, tree, &mut nested);
+		}
+
+		let (direct, rest): (Vec<_>, Vec<_>) = nested.additions.into_iter().partition(|child| child.parent == id);
+
+		snapshot.children = direct.into_iter().map(Snapshot::from).collect();
+
+		changes.add(snapshot, parent);
+		changes.additions.extend(rest);
+	}
+
+	/// Brings the subtree in line with the filter, instances
+	/// that entered the scope are added and the ones that left it removed
+	fn reconcile(&mut self, id: Ref, tree: &Tree, vfs: &Vfs, changes: &mut Changes) {
+		let mut stack = vec![(id, false)];
+
+		while let Some((id, removed)) = stack.pop() {
+			let Some(instance) = tree.get_instance(id) else {
+				continue;
+			};
+
+			// Client has already removed the instance along with its ancestor
+			if removed {
+				self.visible.remove(&id);
+			}
+
+			let visible = self.visible.contains_key(&id);
+			let contains = self.filter.contains(id, tree);
+
+			if contains && !visible {
+				self.visible.insert(id, instance.parent());
+
+				changes.additions.push(AddedSnapshot {
+					id,
+					meta: tree.get_meta(id).unwrap().clone(),
+					parent: instance.parent(),
+					name: instance.name.clone(),
+					class: instance.class,
+					properties: memory::materialize_lossy(id, tree, vfs).unwrap_or_default(),
+					children: vec![],
+				});
+			} else if !contains && visible {
+				self.visible.remove(&id);
+				changes.remove(id);
+			}
+
+			let removed = removed || (!contains && visible);
+
+			for child in instance.children().iter().rev() {
+				stack.push((*child, removed));
+			}
+		}
+	}
+}
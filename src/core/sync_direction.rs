@@ -0,0 +1,367 @@
+use anyhow::Result;
+use log::warn;
+use rbx_dom_weak::{
+	types::{Ref, Variant},
+	Ustr,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap},
+	fs,
+	path::{Path, PathBuf},
+};
+
+use super::{frozen::SyncDirection, helpers::memory, snapshot::Snapshot, tree::Tree};
+use crate::vfs::Vfs;
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Returns path of the summary of the tree both sides last agreed on
+pub fn base_path(workspace_dir: &Path) -> PathBuf {
+	workspace_dir.join(".argon").join("state").join("sync_base.json")
+}
+
+/// Summary of an instance subtree, see `hash_node` for the algorithm
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeSummary {
+	pub name: String,
+	pub class: String,
+	pub hash: String,
+	/// Number of instances in the subtree, including the instance itself
+	pub count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceSummary {
+	pub hash: String,
+	pub count: usize,
+	/// Summaries of children, used to narrow down divergent subtrees
+	pub children: Vec<NodeSummary>,
+}
+
+/// Summary of a tree keyed by names of top-level services.
+/// Clients only summarize instances they would sync back,
+/// so ones Studio creates on its own don't count as changes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeSummary {
+	pub services: BTreeMap<String, ServiceSummary>,
+}
+
+impl TreeSummary {
+	/// Summarizes children of the root of the tree
+	pub fn new(tree: &Tree, vfs: &Vfs) -> Self {
+		let root = tree.get_instance(tree.root_ref()).unwrap();
+		let mut services = BTreeMap::new();
+
+		for service in root.children() {
+			let instance = tree.get_instance(*service).unwrap();
+			let (hash, count) = hash_node(*service, tree, vfs);
+
+			let children = instance
+				.children()
+				.iter()
+				.map(|child| {
+					let instance = tree.get_instance(*child).unwrap();
+					let (hash, count) = hash_node(*child, tree, vfs);
+
+					NodeSummary {
+						name: instance.name.clone(),
+						class: instance.class.to_string(),
+						hash: format_hash(hash),
+						count,
+					}
+				})
+				.collect();
+
+			services.insert(
+				instance.name.clone(),
+				ServiceSummary {
+					hash: format_hash(hash),
+					count,
+					children,
+				},
+			);
+		}
+
+		Self { services }
+	}
+
+	/// Reads the summary both sides last agreed on, `None` if there was no session yet
+	pub fn load_base(workspace_dir: &Path) -> Option<Self> {
+		let path = base_path(workspace_dir);
+
+		if !path.exists() {
+			return None;
+		}
+
+		match fs::read(&path)
+			.map_err(anyhow::Error::from)
+			.and_then(|contents| serde_json::from_slice(&contents).map_err(anyhow::Error::from))
+		{
+			Ok(summary) => Some(summary),
+			Err(err) => {
+				warn!("Failed to read sync base {}: {}", path.display(), err);
+				None
+			}
+		}
+	}
+
+	pub fn save_base(&self, workspace_dir: &Path) -> Result<()> {
+		let path = base_path(workspace_dir);
+
+		fs::create_dir_all(path.parent().unwrap())?;
+		fs::write(path, serde_json::to_vec_pretty(self)?)?;
+
+		Ok(())
+	}
+}
+
+/// Which sides changed since the last session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangedSide {
+	None,
+	Filesystem,
+	Studio,
+	Both,
+}
+
+impl ChangedSide {
+	fn merge(self, other: Self) -> Self {
+		match (self, other) {
+			(Self::None, side) | (side, Self::None) => side,
+			(left, right) if left == right => left,
+			_ => Self::Both,
+		}
+	}
+}
+
+/// Child of a divergent service whose subtree differs between the sides
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DivergentChild {
+	pub name: String,
+	/// `None` if the side does not have a child with this name
+	pub filesystem: Option<NodeSummary>,
+	pub studio: Option<NodeSummary>,
+	pub base: Option<NodeSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DivergentService {
+	pub name: String,
+	pub changed: ChangedSide,
+	pub children: Vec<DivergentChild>,
+}
+
+/// Direction of the initial sync agreed on during handshake
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPlan {
+	pub changed: ChangedSide,
+	/// `None` when both sides changed and the user has to choose,
+	/// `to-disk` means divergent subtrees have to be sent to `/sync/studio`
+	pub direction: Option<SyncDirection>,
+	pub divergent: Vec<DivergentService>,
+}
+
+impl SyncPlan {
+	/// Whether the child of the service is one of the divergent subtrees
+	pub fn is_divergent(&self, service: &str, name: &str) -> bool {
+		self.divergent
+			.iter()
+			.filter(|divergent| divergent.name == service)
+			.any(|divergent| divergent.children.iter().any(|child| child.name == name))
+	}
+}
+
+/// Subtree of a divergent child as it exists in Studio,
+/// no `instances` means Studio does not have it at all
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StudioSubtree {
+	pub service: String,
+	pub name: String,
+	#[serde(default)]
+	pub instances: Vec<Snapshot>,
+}
+
+/// Compares summaries of both sides with the one they last agreed on, services
+/// not summarized by the client are skipped. Without a base only the disk is
+/// considered changed, so the first session behaves like it always did
+pub fn compare(base: Option<&TreeSummary>, filesystem: &TreeSummary, studio: &TreeSummary) -> Vec<DivergentService> {
+	let mut divergent = vec![];
+
+	for (name, local) in &filesystem.services {
+		let Some(remote) = studio.services.get(name) else {
+			continue;
+		};
+
+		if local.hash == remote.hash {
+			continue;
+		}
+
+		let base_service = base.and_then(|base| base.services.get(name));
+
+		let changed = match base_service {
+			Some(base) => match (base.hash != local.hash, base.hash != remote.hash) {
+				(true, false) => ChangedSide::Filesystem,
+				(false, true) => ChangedSide::Studio,
+				_ => ChangedSide::Both,
+			},
+			None => ChangedSide::Filesystem,
+		};
+
+		let names: BTreeSet<&String> = local
+			.children
+			.iter()
+			.chain(&remote.children)
+			.map(|child| &child.name)
+			.collect();
+
+		let find = |children: &[NodeSummary], name: &str| -> Vec<NodeSummary> {
+			let mut found: Vec<NodeSummary> = children.iter().filter(|child| child.name == name).cloned().collect();
+			found.sort_by(|a, b| a.hash.cmp(&b.hash));
+			found
+		};
+
+		let children = names
+			.into_iter()
+			.filter_map(|child| {
+				let local = find(&local.children, child);
+				let remote = find(&remote.children, child);
+
+				if local == remote {
+					return None;
+				}
+
+				Some(DivergentChild {
+					name: child.to_owned(),
+					filesystem: local.into_iter().next(),
+					studio: remote.into_iter().next(),
+					base: base_service.and_then(|base| find(&base.children, child).into_iter().next()),
+				})
+			})
+			.collect();
+
+		divergent.push(DivergentService {
+			name: name.to_owned(),
+			changed,
+			children,
+		});
+	}
+
+	divergent
+}
+
+/// Decides the direction, the newer side wins unless both changed, then
+/// `policy` is used: `filesystem`, `studio` or `prompt` to let the user choose
+pub fn plan(divergent: Vec<DivergentService>, policy: &str) -> SyncPlan {
+	let changed = divergent
+		.iter()
+		.fold(ChangedSide::None, |changed, service| changed.merge(service.changed));
+
+	let direction = match changed {
+		ChangedSide::None | ChangedSide::Filesystem => Some(SyncDirection::ToStudio),
+		ChangedSide::Studio => Some(SyncDirection::ToDisk),
+		ChangedSide::Both => match policy.to_lowercase().as_str() {
+			"filesystem" => Some(SyncDirection::ToStudio),
+			"studio" => Some(SyncDirection::ToDisk),
+			_ => None,
+		},
+	};
+
+	SyncPlan {
+		changed,
+		direction,
+		divergent,
+	}
+}
+
+/// Hashes the instance with all of its descendants, returns the hash and number of instances.
+///
+/// The hash is FNV-1a (64 bit) of `<name>\t<class>\n`, followed by `<source>\n` if the
+/// instance has a string `Source` property, and `<hash>\n` of every child in ascending
+/// order. Unlike `heartbeat::hash_subtree` it does not include referents, so it can be
+/// compared with trees of other sessions, e.g. a place edited while Argon was not running
+pub fn hash_node(id: Ref, tree: &Tree, vfs: &Vfs) -> (u64, usize) {
+	let mut hashes: HashMap<Ref, (u64, usize)> = HashMap::new();
+	let mut stack = vec![(id, false)];
+
+	// Children are hashed before their parents without recursion
+	while let Some((id, visited)) = stack.pop() {
+		let instance = tree.get_instance(id).unwrap();
+
+		if !visited {
+			stack.push((id, true));
+			stack.extend(instance.children().iter().map(|child| (*child, false)));
+			continue;
+		}
+
+		let mut count = 1;
+		let mut children: Vec<u64> = instance
+			.children()
+			.iter()
+			.filter_map(|child| hashes.remove(child))
+			.map(|(hash, child_count)| {
+				count += child_count;
+				hash
+			})
+			.collect();
+
+		children.sort();
+
+		let mut state = update(
+			FNV_OFFSET,
+			format!("{}\t{}\n", instance.name, instance.class).as_bytes(),
+		);
+
+		if let Some(source) = source(id, tree, vfs) {
+			state = update(state, source.as_bytes());
+			state = update(state, b"\n");
+		}
+
+		for child in children {
+			state = update(state, format!("{:016x}\n", child).as_bytes());
+		}
+
+		hashes.insert(id, (state, count));
+	}
+
+	hashes.remove(&id).unwrap()
+}
+
+fn source(id: Ref, tree: &Tree, vfs: &Vfs) -> Option<String> {
+	let name = Ustr::from("Source");
+
+	let value = match tree.get_instance(id)?.properties.get(&name) {
+		Some(value) => value.clone(),
+		None if tree.get_evicted(id).is_some_and(|evicted| evicted.contains_key(&name)) => {
+			memory::materialize(id, tree, vfs).ok()?.remove(&name)?
+		}
+		None => return None,
+	};
+
+	match value {
+		Variant::String(source) => Some(source),
+		_ => None,
+	}
+}
+
+fn format_hash(hash: u64) -> String {
+	format!("{:016x}", hash)
+}
+
+fn update(mut state: u64, bytes: &[u8]) -> u64 {
+	for byte in bytes {
+		state ^= *byte as u64;
+		state = state.wrapping_mul(FNV_PRIME);
+	}
+
+	state
+}
@@ -0,0 +1,163 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, sync::Mutex};
+
+use crate::{constants::TEST_RUNS_LIMIT, lock};
+
+/// Version of the result schema, has to be bumped whenever the
+/// reports sent by the test bootstrap change in incompatible way
+pub const TEST_RESULTS_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TestStatus {
+	Passed,
+	Failed,
+	Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestCase {
+	/// Names of the enclosing blocks and the case itself, joined with spaces
+	pub name: String,
+	pub status: TestStatus,
+	/// Error messages of failed cases
+	#[serde(default)]
+	pub message: Option<String>,
+	/// Duration in seconds, if the runner measures it
+	#[serde(default)]
+	pub duration: Option<f64>,
+}
+
+/// Cases of a single spec module
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestSuite {
+	/// Path of the module, e.g. `ReplicatedStorage/Tests/Math.spec`
+	pub name: String,
+	pub cases: Vec<TestCase>,
+	/// Duration in seconds of the whole suite
+	#[serde(default)]
+	pub duration: Option<f64>,
+}
+
+impl TestSuite {
+	pub fn count(&self, status: TestStatus) -> usize {
+		self.cases.iter().filter(|case| case.status == status).count()
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TestErrorKind {
+	/// Test root does not exist in the place
+	RootNotFound,
+	/// Neither the configured runner nor TestEZ was found
+	RunnerNotFound,
+	/// Runner itself failed, not a test case
+	Runtime,
+	/// Studio did not start the run, e.g. no client is connected
+	NotStarted,
+	/// Run did not finish in time, e.g. because Studio crashed
+	Timeout,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestError {
+	pub kind: TestErrorKind,
+	pub message: String,
+}
+
+/// Report sent by the test bootstrap, every report carries suites
+/// finished since the previous one so results of a run that
+/// did not finish are still available
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestReport {
+	pub version: u32,
+	pub run_id: String,
+	#[serde(default)]
+	pub suites: Vec<TestSuite>,
+	/// Whether this is the last report of the run
+	#[serde(default)]
+	pub done: bool,
+	#[serde(default)]
+	pub error: Option<TestError>,
+}
+
+/// Results of a run collected from all its reports so far
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestRun {
+	pub suites: Vec<TestSuite>,
+	pub done: bool,
+	pub error: Option<TestError>,
+}
+
+impl TestRun {
+	pub fn count(&self, status: TestStatus) -> usize {
+		self.suites.iter().map(|suite| suite.count(status)).sum()
+	}
+
+	pub fn duration(&self) -> f64 {
+		self.suites.iter().filter_map(|suite| suite.duration).sum()
+	}
+}
+
+/// Runs reported by test bootstraps, only the most recent ones are kept
+#[derive(Debug, Default)]
+pub struct TestRuns {
+	runs: Mutex<VecDeque<(String, TestRun)>>,
+}
+
+impl TestRuns {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds suites of the report to its run, reports of other schema versions are rejected
+	pub fn report(&self, report: TestReport) -> Result<()> {
+		if report.version != TEST_RESULTS_VERSION {
+			bail!(
+				"Test results version {} is not supported, expected {}. Make sure Argon and its plugin are up to date",
+				report.version,
+				TEST_RESULTS_VERSION
+			);
+		}
+
+		let mut runs = lock!(self.runs);
+
+		let index = match runs.iter().position(|(id, _)| *id == report.run_id) {
+			Some(index) => index,
+			None => {
+				if runs.len() >= TEST_RUNS_LIMIT {
+					runs.pop_front();
+				}
+
+				runs.push_back((report.run_id.clone(), TestRun::default()));
+				runs.len() - 1
+			}
+		};
+
+		let run = &mut runs[index].1;
+
+		if run.done {
+			bail!("Test run {} has already finished", report.run_id);
+		}
+
+		run.suites.extend(report.suites);
+		run.done = report.done;
+		run.error = report.error.or(run.error.take());
+
+		Ok(())
+	}
+
+	pub fn get(&self, run_id: &str) -> Option<TestRun> {
+		lock!(self.runs)
+			.iter()
+			.find(|(id, _)| id == run_id)
+			.map(|(_, run)| run.clone())
+	}
+}
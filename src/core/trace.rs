@@ -0,0 +1,617 @@
+use anyhow::{bail, Context, Result};
+use rbx_dom_weak::types::Ref;
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use std::{
+	collections::HashMap,
+	fs::{self, File},
+	io::{BufReader, BufWriter, ErrorKind, Write},
+	path::{Path, PathBuf},
+	sync::Mutex,
+	thread,
+	time::{Duration, Instant},
+};
+
+use super::{blobs, changes::Changes, processor::WriteRequest, tree::Tree, tree_hash::TreeHash, Core};
+use crate::{
+	constants::{BLACKLISTED_PATHS, REPLAY_TIMEOUT},
+	lock,
+	project::Project,
+	vfs::{Vfs, VfsEvent},
+};
+
+/// Version of the trace format, traces with a different one can't be replayed
+pub const TRACE_VERSION: u32 = 1;
+
+/// Hashes of the tree and workspace files at some point of the session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceState {
+	/// Tree hash in its embedded form, see `TreeHash::to_embedded`
+	pub tree: String,
+	/// Digest of all workspace files, see `digest_files`
+	pub files: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceFile {
+	/// Path relative to the workspace or the traced directory
+	pub path: PathBuf,
+	/// `None` for directories, so empty ones are kept too
+	pub contents: Option<ByteBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileChange {
+	Create,
+	Write,
+	Delete,
+}
+
+/// State of the changed path when its event was processed,
+/// contents are missing in redacted traces
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PathState {
+	Missing,
+	File {
+		hash: String,
+		contents: Option<ByteBuf>,
+	},
+	Directory {
+		hash: String,
+		files: Option<Vec<TraceFile>>,
+	},
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TraceInput {
+	/// File system event, reported by the watcher or made by Argon itself
+	Vfs {
+		change: FileChange,
+		/// Path relative to the workspace
+		path: PathBuf,
+		state: PathState,
+	},
+	/// Changes submitted by a client
+	Syncback {
+		client_id: u32,
+		/// Paths of instances the changes refer to, as refs differ between sessions
+		refs: Vec<(Ref, String)>,
+		hash: String,
+		/// Missing in redacted traces
+		changes: Option<Changes>,
+	},
+}
+
+impl TraceInput {
+	/// Short description used in replay reports
+	pub fn describe(&self) -> String {
+		match self {
+			TraceInput::Vfs { change, path, .. } => format!("{:?} {}", change, path.display()),
+			TraceInput::Syncback { client_id, .. } => format!("Syncback of client {}", client_id),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceHeader {
+	pub version: u32,
+	/// Project path relative to the workspace
+	pub project: PathBuf,
+	/// Path of the trace relative to the workspace if it is
+	/// stored inside, it is never part of the workspace files
+	pub trace: Option<PathBuf>,
+	pub redacted: bool,
+	/// Starting workspace, missing in redacted traces
+	pub files: Option<Vec<TraceFile>>,
+	pub start: TraceState,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TracedInput {
+	/// Milliseconds since the recording started
+	pub time: u64,
+	pub input: TraceInput,
+	/// Tree hash after the input was processed
+	pub tree: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum TraceRecord {
+	Header(TraceHeader),
+	Input(TracedInput),
+	End(TraceState),
+}
+
+/// Trace read from a file, `end` is missing if the recording session did not stop gracefully
+#[derive(Debug, Clone)]
+pub struct Trace {
+	pub header: TraceHeader,
+	pub inputs: Vec<TracedInput>,
+	pub end: Option<TraceState>,
+}
+
+impl Trace {
+	pub fn read(path: &Path) -> Result<Self> {
+		let mut reader = BufReader::new(File::open(path)?);
+		let mut records = vec![];
+
+		loop {
+			let record: TraceRecord = match rmp_serde::from_read(&mut reader) {
+				Ok(record) => record,
+				Err(rmp_serde::decode::Error::InvalidMarkerRead(err)) if err.kind() == ErrorKind::UnexpectedEof => {
+					break;
+				}
+				Err(err) => return Err(err).with_context(|| format!("Failed to read trace {}", path.display())),
+			};
+
+			records.push(record);
+		}
+
+		let mut records = records.into_iter();
+
+		let Some(TraceRecord::Header(header)) = records.next() else {
+			bail!("Trace {} has no header", path.display());
+		};
+
+		if header.version != TRACE_VERSION {
+			bail!(
+				"Trace {} has version {} but only version {} is supported",
+				path.display(),
+				header.version,
+				TRACE_VERSION
+			);
+		}
+
+		let mut trace = Self {
+			header,
+			inputs: vec![],
+			end: None,
+		};
+
+		for record in records {
+			match record {
+				TraceRecord::Header(_) => bail!("Trace {} has more than one header", path.display()),
+				TraceRecord::Input(input) => trace.inputs.push(input),
+				TraceRecord::End(state) => trace.end = Some(state),
+			}
+		}
+
+		Ok(trace)
+	}
+
+	/// Path of the trace itself if it was recorded inside of the workspace
+	fn exclude(&self, workspace: &Path) -> Option<PathBuf> {
+		self.header.trace.as_ref().map(|path| workspace.join(path))
+	}
+}
+
+/// Appends inputs of the processor to a trace file, so sync
+/// bugs can be reproduced by replaying them, see `replay`
+pub struct TraceRecorder {
+	/// `None` once the trace is finished
+	writer: Mutex<Option<BufWriter<File>>>,
+	path: PathBuf,
+	workspace_dir: PathBuf,
+	redact: bool,
+	start: Instant,
+}
+
+impl TraceRecorder {
+	/// Creates the trace file and writes its header, `tree` is the current tree hash
+	pub fn create(path: &Path, project: &Project, redact: bool, tree: TreeHash) -> Result<Self> {
+		let workspace_dir = project.workspace_dir.clone();
+		let files = read_files(&workspace_dir, Some(path))?;
+
+		let header = TraceHeader {
+			version: TRACE_VERSION,
+			project: project.path.strip_prefix(&workspace_dir)?.to_owned(),
+			trace: path.strip_prefix(&workspace_dir).ok().map(Path::to_owned),
+			redacted: redact,
+			start: TraceState {
+				tree: tree.to_embedded(),
+				files: digest_files(&files),
+			},
+			files: (!redact).then_some(files),
+		};
+
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		let mut writer = BufWriter::new(File::create(path)?);
+
+		rmp_serde::encode::write_named(&mut writer, &TraceRecord::Header(header))?;
+		writer.flush()?;
+
+		Ok(Self {
+			writer: Mutex::new(Some(writer)),
+			path: path.to_owned(),
+			workspace_dir,
+			redact,
+			start: Instant::now(),
+		})
+	}
+
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Captures the event with the current state of its path,
+	/// has to be called before the event is processed
+	pub fn capture_event(&self, event: &VfsEvent) -> Result<TraceInput> {
+		let (change, path) = match event {
+			VfsEvent::Create(path) => (FileChange::Create, path),
+			VfsEvent::Write(path) => (FileChange::Write, path),
+			VfsEvent::Delete(path) => (FileChange::Delete, path),
+		};
+
+		let state = if path.is_dir() {
+			let files = read_files(path, Some(&self.path))?;
+
+			PathState::Directory {
+				hash: digest_files(&files),
+				files: (!self.redact).then_some(files),
+			}
+		} else if path.is_file() {
+			let contents = fs::read(path)?;
+
+			PathState::File {
+				hash: blobs::digest_bytes(&contents),
+				contents: (!self.redact).then(|| ByteBuf::from(contents)),
+			}
+		} else {
+			PathState::Missing
+		};
+
+		Ok(TraceInput::Vfs {
+			change,
+			path: path.strip_prefix(&self.workspace_dir).unwrap_or(path).to_owned(),
+			state,
+		})
+	}
+
+	/// Captures changes of a client with paths of instances
+	/// they refer to, has to be called before they are applied
+	pub fn capture_request(&self, request: &WriteRequest, tree: &Tree) -> Result<TraceInput> {
+		let changes = &request.changes;
+
+		let refs = changes
+			.additions
+			.iter()
+			.map(|snapshot| snapshot.parent)
+			.chain(changes.updates.iter().map(|snapshot| snapshot.id))
+			.chain(changes.removals.iter().copied())
+			.filter_map(|id| tree.get_full_name(id).map(|name| (id, strip_root(&name).to_owned())))
+			.collect();
+
+		Ok(TraceInput::Syncback {
+			client_id: request.client_id,
+			refs,
+			hash: blobs::digest_bytes(&rmp_serde::to_vec_named(changes)?),
+			changes: (!self.redact).then(|| changes.clone()),
+		})
+	}
+
+	/// Appends the input with the tree hash it resulted in
+	pub fn record(&self, input: TraceInput, tree: TreeHash) -> Result<()> {
+		let record = TraceRecord::Input(TracedInput {
+			time: self.start.elapsed().as_millis() as u64,
+			input,
+			tree: tree.to_embedded(),
+		});
+
+		self.write(&record)
+	}
+
+	/// Writes the end state, inputs after that are not recorded
+	pub fn finish(&self, tree: TreeHash) -> Result<()> {
+		let files = read_files(&self.workspace_dir, Some(&self.path))?;
+
+		self.write(&TraceRecord::End(TraceState {
+			tree: tree.to_embedded(),
+			files: digest_files(&files),
+		}))?;
+
+		lock!(self.writer).take();
+
+		Ok(())
+	}
+
+	fn write(&self, record: &TraceRecord) -> Result<()> {
+		let mut writer = lock!(self.writer);
+
+		if let Some(writer) = writer.as_mut() {
+			rmp_serde::encode::write_named(writer, record)?;
+			writer.flush()?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Input after which the replayed tree stopped matching the recorded one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+	pub index: usize,
+	pub time: u64,
+	pub input: String,
+	pub expected: String,
+	pub actual: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayReport {
+	/// Number of replayed inputs, zero when only verifying a redacted trace
+	pub replayed: usize,
+	pub verify_only: bool,
+	/// Whether the workspace replay started from matches the recorded one
+	pub start_matches: bool,
+	pub divergence: Option<Divergence>,
+	/// End state of the recording, if it was finished
+	pub expected: Option<TraceState>,
+	pub actual: TraceState,
+}
+
+impl ReplayReport {
+	pub fn is_identical(&self) -> bool {
+		self.divergence.is_none() && self.expected.as_ref() == Some(&self.actual)
+	}
+}
+
+/// Copies `workspace` or the starting workspace embedded in the trace to `dir`
+pub fn prepare(trace: &Trace, workspace: Option<&Path>, dir: &Path) -> Result<()> {
+	if dir.exists() {
+		fs::remove_dir_all(dir)?;
+	}
+
+	fs::create_dir_all(dir)?;
+
+	let files = match (workspace, &trace.header.files) {
+		(Some(workspace), _) => read_files(workspace, trace.exclude(workspace).as_deref())?,
+		(None, Some(files)) => files.clone(),
+		(None, None) => bail!("Trace is redacted, the workspace to verify has to be provided"),
+	};
+
+	write_files(dir, &files)
+}
+
+/// Feeds inputs of the trace through the processor of a new core serving
+/// the workspace in `dir`, prepared with `prepare`. Inputs are replayed as
+/// fast as possible unless `speed` is given, `1.0` being the recorded pace.
+/// Redacted traces can't be replayed, the workspace is only compared with their end state
+pub fn replay(trace: &Trace, dir: &Path, speed: Option<f64>) -> Result<ReplayReport> {
+	let project = Project::load(&dir.join(&trace.header.project))?;
+	let core = Core::with_vfs(project, Vfs::new(false))?;
+
+	let start_matches = current_state(&core, trace, dir)? == trace.header.start;
+	let verify_only = trace.header.redacted;
+
+	let mut divergence = None;
+	let mut previous = 0;
+
+	if !verify_only {
+		for (index, traced) in trace.inputs.iter().enumerate() {
+			if let Some(speed) = speed.filter(|speed| *speed > 0.0) {
+				let delay = traced.time.saturating_sub(previous) as f64 / speed;
+				thread::sleep(Duration::from_secs_f64(delay / 1000.0));
+			}
+
+			previous = traced.time;
+
+			let processor = core.processor();
+			let processed = processor.processed();
+
+			match &traced.input {
+				TraceInput::Vfs { change, path, state } => {
+					let path = dir.join(path);
+					restore_path(&path, state)?;
+
+					processor.notify(match change {
+						FileChange::Create => VfsEvent::Create(path),
+						FileChange::Write => VfsEvent::Write(path),
+						FileChange::Delete => VfsEvent::Delete(path),
+					});
+				}
+				TraceInput::Syncback {
+					client_id,
+					refs,
+					changes,
+					..
+				} => {
+					let mut changes = changes.clone().context("Syncback input has no changes")?;
+					remap(&mut changes, refs, &core.tree());
+
+					processor.write(WriteRequest {
+						changes,
+						client_id: *client_id,
+					});
+				}
+			}
+
+			let started = Instant::now();
+
+			while processor.processed() == processed {
+				if started.elapsed() > REPLAY_TIMEOUT {
+					bail!("Input {} was not processed in time", index + 1);
+				}
+
+				thread::sleep(Duration::from_millis(1));
+			}
+
+			let tree = core.tree_hash().to_embedded();
+
+			if divergence.is_none() && tree != traced.tree {
+				divergence = Some(Divergence {
+					index: index + 1,
+					time: traced.time,
+					input: traced.input.describe(),
+					expected: traced.tree.clone(),
+					actual: tree,
+				});
+			}
+		}
+	}
+
+	Ok(ReplayReport {
+		replayed: if verify_only { 0 } else { trace.inputs.len() },
+		verify_only,
+		start_matches,
+		divergence,
+		expected: trace.end.clone(),
+		actual: current_state(&core, trace, dir)?,
+	})
+}
+
+fn current_state(core: &Core, trace: &Trace, dir: &Path) -> Result<TraceState> {
+	Ok(TraceState {
+		tree: core.tree_hash().to_embedded(),
+		files: digest_files(&read_files(dir, trace.exclude(dir).as_deref())?),
+	})
+}
+
+/// Makes the path match the recorded state before its event is processed
+fn restore_path(path: &Path, state: &PathState) -> Result<()> {
+	if path.is_dir() {
+		fs::remove_dir_all(path)?;
+	} else if path.exists() {
+		fs::remove_file(path)?;
+	}
+
+	match state {
+		PathState::Missing => {}
+		PathState::File { contents, .. } => {
+			let contents = contents.as_ref().context("File input has no contents")?;
+
+			if let Some(parent) = path.parent() {
+				fs::create_dir_all(parent)?;
+			}
+
+			fs::write(path, contents)?;
+		}
+		PathState::Directory { files, .. } => {
+			let files = files.as_ref().context("Directory input has no contents")?;
+
+			fs::create_dir_all(path)?;
+			write_files(path, files)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Replaces refs of the recording session with refs of instances at the same paths
+fn remap(changes: &mut Changes, refs: &[(Ref, String)], tree: &Tree) {
+	let refs: HashMap<Ref, Ref> = refs
+		.iter()
+		.filter_map(|(id, path)| tree.find_by_path(path).map(|new| (*id, new)))
+		.collect();
+
+	let remap = |id: &mut Ref| {
+		if let Some(new) = refs.get(id) {
+			*id = *new;
+		}
+	};
+
+	changes
+		.additions
+		.iter_mut()
+		.for_each(|snapshot| remap(&mut snapshot.parent));
+	changes.updates.iter_mut().for_each(|snapshot| remap(&mut snapshot.id));
+	changes.removals.iter_mut().for_each(remap);
+}
+
+/// Full name of the instance without the root, as accepted by `Tree::find_by_path`
+fn strip_root(name: &str) -> &str {
+	name.split_once('.').map(|(_, path)| path).unwrap_or_default()
+}
+
+/// Reads all files and directories sorted by their relative paths, hidden
+/// directories (e.g. `.git` or `.argon`) and the `exclude` path are skipped
+fn read_files(dir: &Path, exclude: Option<&Path>) -> Result<Vec<TraceFile>> {
+	fn walk(dir: &Path, root: &Path, exclude: Option<&Path>, files: &mut Vec<TraceFile>) -> Result<()> {
+		for entry in fs::read_dir(dir)? {
+			let path = entry?.path();
+			let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+			if Some(path.as_path()) == exclude || BLACKLISTED_PATHS.contains(&name.as_ref()) {
+				continue;
+			}
+
+			if path.is_dir() {
+				if !name.starts_with('.') {
+					files.push(TraceFile {
+						path: path.strip_prefix(root)?.to_owned(),
+						contents: None,
+					});
+
+					walk(&path, root, exclude, files)?;
+				}
+			} else {
+				files.push(TraceFile {
+					path: path.strip_prefix(root)?.to_owned(),
+					contents: Some(ByteBuf::from(fs::read(&path)?)),
+				});
+			}
+		}
+
+		Ok(())
+	}
+
+	let mut files = vec![];
+	walk(dir, dir, exclude, &mut files)?;
+
+	files.sort_by(|a, b| a.path.cmp(&b.path));
+
+	Ok(files)
+}
+
+fn write_files(dir: &Path, files: &[TraceFile]) -> Result<()> {
+	for file in files {
+		let path = dir.join(&file.path);
+
+		let Some(contents) = &file.contents else {
+			fs::create_dir_all(path)?;
+			continue;
+		};
+
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		fs::write(path, contents)?;
+	}
+
+	Ok(())
+}
+
+/// Digest of relative paths and contents of the files and
+/// directories, it is the same on every platform
+fn digest_files(files: &[TraceFile]) -> String {
+	let mut bytes = vec![];
+
+	for file in files {
+		let path = file
+			.path
+			.components()
+			.map(|component| component.as_os_str().to_string_lossy());
+
+		bytes.extend(path.collect::<Vec<_>>().join("/").as_bytes());
+		bytes.push(0);
+
+		if let Some(contents) = &file.contents {
+			bytes.extend(blobs::digest_bytes(contents).as_bytes());
+		}
+
+		bytes.push(0);
+	}
+
+	blobs::digest_bytes(&bytes)
+}
@@ -1,97 +1,186 @@
+use anyhow::{bail, Result};
+use log::{info, warn};
 use multimap::MultiMap;
-use rbx_dom_weak::{types::Ref, Instance, InstanceBuilder, WeakDom};
+use rbx_dom_weak::{
+	types::{Ref, Variant},
+	Instance, InstanceBuilder, UstrMap, WeakDom,
+};
 use std::{
-	collections::HashMap,
+	collections::{HashMap, HashSet},
+	mem,
 	path::{Path, PathBuf},
 };
 
-use super::{meta::Meta, snapshot::Snapshot};
+use super::{
+	changes::Changes,
+	frozen::{Frozen, FrozenInstance, SyncDirection},
+	helpers::{collisions::Collision, memory},
+	meta::{Meta, SourceKind},
+	path_index::PathIndex,
+	refs::{self, RefPaths},
+	snapshot::Snapshot,
+	stable_ids::StableIds,
+};
+use crate::{project::ProjectNode, Properties};
+
+/// Instance in depth-first order along with the sibling indices of its ancestors below
+/// the root and of itself, so moving to the next instance never has to look for the
+/// instance among its siblings, see `Tree::next_position`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreePosition {
+	pub id: Ref,
+	pub indices: Vec<usize>,
+}
 
 #[derive(Debug)]
 pub struct Tree {
 	dom: WeakDom,
 	path_to_ids: MultiMap<PathBuf, Ref>,
 	id_to_meta: HashMap<Ref, Meta>,
+	id_to_evicted: HashMap<Ref, UstrMap<u64>>,
+	id_to_collision: HashMap<Ref, Collision>,
+	deferred_collapses: HashSet<Ref>,
+	/// Instances with `Ref` properties read from files, see `resolve_refs`
+	ref_holders: HashSet<Ref>,
+	/// Instances that `Ref` properties point at, along with the instances that have them
+	id_to_referrers: HashMap<Ref, HashSet<Ref>>,
+	id_to_targets: HashMap<Ref, HashSet<Ref>>,
+	stable_ids: StableIds,
+	stable_to_id: HashMap<String, Ref>,
+	frozen: Frozen,
+	path_index: PathIndex,
+	generation: u64,
+	/// Root node of the project the tree is built from, see `set_project_node`
+	project_node: Option<ProjectNode>,
 }
 
 impl Tree {
-	pub fn new(snapshot: Snapshot) -> Self {
+	pub fn new(mut snapshot: Snapshot) -> Self {
 		let builder = InstanceBuilder::new(snapshot.class)
-			.with_name(snapshot.name)
-			.with_properties(snapshot.properties);
+			.with_name(mem::take(&mut snapshot.name))
+			.with_properties(mem::take(&mut snapshot.properties));
+
+		let dom = WeakDom::new(builder);
+		let path_index = PathIndex::new(dom.root_ref());
 
 		let mut tree = Self {
-			dom: WeakDom::new(builder),
+			dom,
 			id_to_meta: HashMap::new(),
 			path_to_ids: MultiMap::new(),
+			id_to_evicted: HashMap::new(),
+			id_to_collision: HashMap::new(),
+			deferred_collapses: HashSet::new(),
+			ref_holders: HashSet::new(),
+			id_to_referrers: HashMap::new(),
+			id_to_targets: HashMap::new(),
+			stable_ids: StableIds::default(),
+			stable_to_id: HashMap::new(),
+			frozen: Frozen::default(),
+			path_index,
+			generation: 0,
+			project_node: None,
 		};
 
 		let root_ref = tree.dom.root_ref();
 
-		tree.insert_meta(root_ref, snapshot.meta);
+		tree.path_index.insert(tree.dom.root());
 
-		for child in snapshot.children {
+		tree.insert_meta(root_ref, mem::take(&mut snapshot.meta));
+
+		for child in mem::take(&mut snapshot.children) {
 			tree.insert_instance_recursive(child, root_ref);
 		}
 
+		tree.resolve_refs();
+
 		tree
 	}
 
-	pub fn insert_instance(&mut self, snapshot: Snapshot, parent: Ref) -> Ref {
+	pub fn insert_instance(&mut self, mut snapshot: Snapshot, parent: Ref) -> Ref {
+		self.generation += 1;
+
+		let name = match &snapshot.meta.original_name {
+			Some(original_name) => original_name.clone(),
+			None => mem::take(&mut snapshot.name),
+		};
+
 		let builder = InstanceBuilder::new(snapshot.class)
-			.with_name(snapshot.meta.original_name.as_ref().unwrap_or(&snapshot.name))
-			.with_properties(snapshot.properties);
+			.with_name(name)
+			.with_properties(mem::take(&mut snapshot.properties));
 
 		let id = self.dom.insert(parent, builder);
 
-		self.insert_meta(id, snapshot.meta);
+		self.path_index.insert(self.dom.get_by_ref(id).unwrap());
+		self.insert_meta(id, mem::take(&mut snapshot.meta));
+		self.link_refs(id);
 
 		id
 	}
 
-	pub fn insert_instance_recursive(&mut self, snapshot: Snapshot, parent: Ref) -> Ref {
-		let builder = InstanceBuilder::new(snapshot.class)
-			.with_name(snapshot.meta.original_name.as_ref().unwrap_or(&snapshot.name))
-			.with_properties(snapshot.properties);
+	/// Inserts the instance with all of its descendants in depth-first order,
+	/// without recursion so hierarchies of any depth can be inserted
+	pub fn insert_instance_recursive(&mut self, mut snapshot: Snapshot, parent: Ref) -> Ref {
+		let children = mem::take(&mut snapshot.children);
+		let root = self.insert_instance(snapshot, parent);
 
-		let id = self.dom.insert(parent, builder);
+		let mut stack = vec![(root, children.into_iter())];
+
+		while let Some((parent, children)) = stack.last_mut() {
+			let parent = *parent;
 
-		self.insert_meta(id, snapshot.meta);
+			let Some(mut child) = children.next() else {
+				stack.pop();
+				continue;
+			};
 
-		for child in snapshot.children {
-			self.insert_instance_recursive(child, id);
+			let children = mem::take(&mut child.children);
+			let id = self.insert_instance(child, parent);
+
+			stack.push((id, children.into_iter()));
 		}
 
-		id
+		root
 	}
 
-	pub fn insert_instance_with_ref(&mut self, snapshot: Snapshot, parent: Ref) {
+	pub fn insert_instance_with_ref(&mut self, mut snapshot: Snapshot, parent: Ref) {
+		self.generation += 1;
+
+		let name = match &snapshot.meta.original_name {
+			Some(original_name) => original_name.clone(),
+			None => mem::take(&mut snapshot.name),
+		};
+
 		let builder = InstanceBuilder::new(snapshot.class)
-			.with_name(snapshot.meta.original_name.as_ref().unwrap_or(&snapshot.name))
+			.with_name(name)
 			.with_referent(snapshot.id)
-			.with_properties(snapshot.properties);
+			.with_properties(mem::take(&mut snapshot.properties));
 
 		let id = self.dom.insert(parent, builder);
 
-		self.insert_meta(id, snapshot.meta);
+		self.path_index.insert(self.dom.get_by_ref(id).unwrap());
+		self.insert_meta(id, mem::take(&mut snapshot.meta));
+		self.link_refs(id);
 	}
 
 	pub fn remove_instance(&mut self, id: Ref) {
-		let mut to_remove = vec![id];
+		self.generation += 1;
 
-		fn walk(id: Ref, dom: &WeakDom, to_remove: &mut Vec<Ref>) {
-			let instance = dom.get_by_ref(id).unwrap();
+		let mut to_remove = vec![];
+		let mut stack = vec![id];
 
-			for child in instance.children() {
-				to_remove.push(*child);
-				walk(*child, dom, to_remove);
-			}
+		while let Some(id) = stack.pop() {
+			to_remove.push(id);
+			stack.extend(self.dom.get_by_ref(id).unwrap().children().iter().rev());
 		}
 
-		walk(id, &self.dom, &mut to_remove);
-
 		for id in to_remove {
+			self.frozen.detach(id);
+			self.retire_stable_id(id);
 			self.remove_meta(id);
+			self.id_to_evicted.remove(&id);
+			self.path_index.remove(id);
+			self.unlink_refs(id);
+			self.resolve_collisions(id);
 		}
 
 		self.dom.destroy(id);
@@ -102,21 +191,50 @@ impl Tree {
 	}
 
 	pub fn get_instance_mut(&mut self, id: Ref) -> Option<&mut Instance> {
+		self.generation += 1;
+		self.path_index.mark(id);
+
 		self.dom.get_by_ref_mut(id)
 	}
 
-	pub fn insert_meta(&mut self, id: Ref, meta: Meta) -> Option<Meta> {
+	pub fn insert_meta(&mut self, id: Ref, mut meta: Meta) -> Option<Meta> {
 		for path in meta.source.paths() {
 			self.path_to_ids.insert(path.to_owned(), id);
 		}
 
+		// Clients may send back identifiers of instances that still exist
+		if meta
+			.stable_id
+			.as_ref()
+			.and_then(|stable_id| self.stable_to_id.get(stable_id))
+			.is_some_and(|other| *other != id)
+		{
+			meta.stable_id = None;
+		}
+
+		if meta.stable_id.is_none() && self.stable_ids.is_enabled() && !meta.source.is_ephemeral() {
+			meta.stable_id = self.assign_stable_id(id);
+		}
+
+		if let Some(stable_id) = &meta.stable_id {
+			self.stable_to_id.insert(stable_id.clone(), id);
+		}
+
+		self.track_ref_paths(id, &meta);
 		self.id_to_meta.insert(id, meta)
 	}
 
-	pub fn update_meta(&mut self, id: Ref, meta: Meta) -> Option<Meta> {
+	pub fn update_meta(&mut self, id: Ref, mut meta: Meta) -> Option<Meta> {
+		self.generation += 1;
+
 		let old_meta = self.id_to_meta.remove(&id);
 
 		if let Some(old_meta) = &old_meta {
+			// Snapshots never carry the identifier, e.g. after folder <-> file transformation
+			if meta.stable_id.is_none() {
+				meta.stable_id.clone_from(&old_meta.stable_id);
+			}
+
 			let removed: Vec<&Path> = old_meta
 				.source
 				.paths()
@@ -132,7 +250,7 @@ impl Tree {
 				.collect();
 
 			for path in removed {
-				self.path_to_ids.remove(path);
+				self.unlink_path(path, id);
 			}
 
 			for path in added {
@@ -140,6 +258,7 @@ impl Tree {
 			}
 		}
 
+		self.track_ref_paths(id, &meta);
 		self.id_to_meta.insert(id, meta);
 
 		old_meta
@@ -150,10 +269,16 @@ impl Tree {
 
 		if let Some(meta) = &meta {
 			for path in meta.source.paths() {
-				self.path_to_ids.remove(path);
+				self.unlink_path(path, id);
+			}
+
+			if let Some(stable_id) = &meta.stable_id {
+				self.stable_to_id.remove(stable_id);
 			}
 		}
 
+		self.ref_holders.remove(&id);
+
 		meta
 	}
 
@@ -169,6 +294,611 @@ impl Tree {
 		self.path_to_ids.get_vec(path)
 	}
 
+	/// Removes the instance from instances backed by the path,
+	/// the same directory can back multiple project nodes
+	fn unlink_path(&mut self, path: &Path, id: Ref) {
+		if let Some(ids) = self.path_to_ids.get_vec_mut(path) {
+			ids.retain(|other| *other != id);
+
+			if ids.is_empty() {
+				self.path_to_ids.remove(path);
+			}
+		}
+	}
+
+	/// Replaces paths of `Ref` properties of the instance, used by syncback
+	/// before properties get written so the paths are written along with them
+	pub fn set_ref_paths(&mut self, id: Ref, ref_paths: RefPaths) {
+		if let Some(meta) = self.id_to_meta.get_mut(&id) {
+			meta.set_ref_paths(ref_paths);
+
+			if meta.ref_paths.is_empty() {
+				self.ref_holders.remove(&id);
+			} else {
+				self.ref_holders.insert(id);
+			}
+		}
+	}
+
+	fn track_ref_paths(&mut self, id: Ref, meta: &Meta) {
+		if meta.ref_paths.is_empty() {
+			self.ref_holders.remove(&id);
+		} else {
+			self.ref_holders.insert(id);
+		}
+	}
+
+	/// Sets `Ref` properties of instances read from files to the instances at
+	/// their paths, which can only be done once all of them are in the tree,
+	/// returns instances whose properties changed
+	pub fn resolve_refs(&mut self) -> Vec<Ref> {
+		let mut changed = vec![];
+
+		for id in self.ref_holders.iter().copied().collect::<Vec<_>>() {
+			let Some(meta) = self.id_to_meta.get(&id) else {
+				continue;
+			};
+
+			let values: Vec<_> = meta
+				.ref_paths
+				.iter()
+				.map(|(property, path)| (*property, refs::resolve(id, property, path, self)))
+				.collect();
+
+			let Some(instance) = self.dom.get_by_ref_mut(id) else {
+				continue;
+			};
+
+			let mut is_changed = false;
+
+			for (property, value) in values {
+				if instance.properties.get(&property) == value.as_ref() {
+					continue;
+				}
+
+				match value {
+					Some(value) => instance.properties.insert(property, value),
+					None => instance.properties.remove(&property),
+				};
+
+				is_changed = true;
+			}
+
+			if is_changed {
+				self.generation += 1;
+				self.link_refs(id);
+				changed.push(id);
+			}
+		}
+
+		changed
+	}
+
+	/// Updates instances that `Ref` properties of the instance point at,
+	/// should be called after its properties get replaced
+	pub fn link_refs(&mut self, id: Ref) {
+		self.unlink_refs(id);
+
+		let Some(instance) = self.dom.get_by_ref(id) else {
+			return;
+		};
+
+		let targets: HashSet<Ref> = instance
+			.properties
+			.values()
+			.filter_map(|value| match value {
+				Variant::Ref(target) if target.is_some() => Some(*target),
+				_ => None,
+			})
+			.collect();
+
+		if targets.is_empty() {
+			return;
+		}
+
+		for target in &targets {
+			self.id_to_referrers.entry(*target).or_default().insert(id);
+		}
+
+		self.id_to_targets.insert(id, targets);
+	}
+
+	fn unlink_refs(&mut self, id: Ref) {
+		for target in self.id_to_targets.remove(&id).unwrap_or_default() {
+			if let Some(referrers) = self.id_to_referrers.get_mut(&target) {
+				referrers.remove(&id);
+
+				if referrers.is_empty() {
+					self.id_to_referrers.remove(&target);
+				}
+			}
+		}
+	}
+
+	/// Returns instances whose `Ref` properties point at the instance
+	pub fn get_referrers(&self, id: Ref) -> Vec<Ref> {
+		self.id_to_referrers
+			.get(&id)
+			.map(|referrers| referrers.iter().copied().collect())
+			.unwrap_or_default()
+	}
+
+	/// Drops large property values of the instance from memory,
+	/// keeping only their hashes so changes can still be detected
+	pub fn evict(&mut self, id: Ref) {
+		if self
+			.get_meta(id)
+			.is_none_or(|meta| matches!(meta.source.get(), SourceKind::Ephemeral | SourceKind::None))
+		{
+			return;
+		}
+
+		let instance = match self.dom.get_by_ref_mut(id) {
+			Some(instance) => instance,
+			None => return,
+		};
+
+		let hashes: Vec<_> = instance
+			.properties
+			.iter()
+			.filter_map(|(name, value)| memory::hash_large(value).map(|hash| (*name, hash)))
+			.collect();
+
+		if hashes.is_empty() {
+			return;
+		}
+
+		let evicted = self.id_to_evicted.entry(id).or_default();
+
+		for (name, hash) in hashes {
+			instance.properties.remove(&name);
+			evicted.insert(name, hash);
+		}
+	}
+
+	pub fn evict_all(&mut self) {
+		let ids: Vec<Ref> = self.id_to_meta.keys().copied().collect();
+
+		for id in ids {
+			self.evict(id);
+		}
+	}
+
+	/// Puts materialized properties back into the instance
+	pub fn restore(&mut self, id: Ref, properties: Properties) {
+		if let Some(instance) = self.dom.get_by_ref_mut(id) {
+			instance.properties = properties;
+		}
+
+		self.id_to_evicted.remove(&id);
+	}
+
+	/// Forgets evicted values, should be called when
+	/// instance properties get replaced entirely
+	pub fn clear_evicted(&mut self, id: Ref) {
+		self.id_to_evicted.remove(&id);
+	}
+
+	pub fn get_evicted(&self, id: Ref) -> Option<&UstrMap<u64>> {
+		self.id_to_evicted.get(&id)
+	}
+
+	pub fn evicted_ids(&self) -> impl Iterator<Item = Ref> + '_ {
+		self.id_to_evicted.keys().copied()
+	}
+
+	pub fn insert_collision(&mut self, collision: Collision) {
+		self.id_to_collision.insert(collision.id, collision);
+	}
+
+	/// Removes collisions the instance takes part in, returns them
+	pub fn resolve_collisions(&mut self, id: Ref) -> Vec<Collision> {
+		let ids: Vec<Ref> = self
+			.id_to_collision
+			.values()
+			.filter(|collision| collision.id == id || collision.other == id)
+			.map(|collision| collision.id)
+			.collect();
+
+		ids.iter().filter_map(|id| self.id_to_collision.remove(id)).collect()
+	}
+
+	pub fn collisions(&self) -> impl Iterator<Item = &Collision> + '_ {
+		self.id_to_collision.values()
+	}
+
+	/// Marks folder instance to be collapsed into a file
+	/// at the end of the current syncback batch if still empty
+	pub fn defer_collapse(&mut self, id: Ref) {
+		self.deferred_collapses.insert(id);
+	}
+
+	pub fn take_deferred_collapses(&mut self) -> Vec<Ref> {
+		self.deferred_collapses.drain().collect()
+	}
+
+	/// Returns dot separated path of the instance, starting from the root
+	pub fn get_full_name(&self, id: Ref) -> Option<String> {
+		let mut instance = self.dom.get_by_ref(id)?;
+		let mut names = vec![instance.name.as_str()];
+
+		while let Some(parent) = self.dom.get_by_ref(instance.parent()) {
+			names.push(parent.name.as_str());
+			instance = parent;
+		}
+
+		names.reverse();
+
+		Some(names.join("."))
+	}
+
+	/// Starts assigning persistent identifiers, existing
+	/// instances get theirs in depth-first order
+	pub fn load_stable_ids(&mut self, stable_ids: StableIds) -> Result<()> {
+		self.stable_ids = stable_ids;
+
+		let mut next = Some(self.root_ref());
+
+		while let Some(id) = next {
+			if let Some(meta) = self.id_to_meta.get(&id) {
+				if meta.stable_id.is_none() && !meta.source.is_ephemeral() {
+					if let Some(stable_id) = self.assign_stable_id(id) {
+						self.stable_to_id.insert(stable_id.clone(), id);
+						self.id_to_meta.get_mut(&id).unwrap().stable_id = Some(stable_id);
+					}
+				}
+			}
+
+			next = self.next_in_order(id);
+		}
+
+		self.save_stable_ids()
+	}
+
+	/// Writes identifiers of all instances to `.argon/ids.json`,
+	/// should be called after instances get added, removed or renamed
+	pub fn save_stable_ids(&mut self) -> Result<()> {
+		if !self.stable_ids.is_enabled() {
+			return Ok(());
+		}
+
+		let live = self
+			.stable_to_id
+			.iter()
+			.filter_map(|(stable_id, id)| Some((self.get_full_name(*id)?, stable_id.clone())))
+			.collect();
+
+		self.stable_ids.save(&live)
+	}
+
+	/// Returns dot separated path of the instance without
+	/// the root, e.g. `ReplicatedStorage.Shared`
+	pub fn get_path(&self, id: Ref) -> Option<String> {
+		let full_name = self.get_full_name(id)?;
+
+		Some(
+			full_name
+				.split_once('.')
+				.map(|(_, path)| path.to_owned())
+				.unwrap_or_default(),
+		)
+	}
+
+	/// Returns instance at the dot separated path without the root, the first
+	/// one if there are multiple siblings with the same name
+	pub fn find_by_path(&self, path: &str) -> Option<Ref> {
+		let mut id = self.root_ref();
+
+		for name in path.split('.').filter(|name| !name.is_empty()) {
+			id = *self
+				.dom
+				.get_by_ref(id)?
+				.children()
+				.iter()
+				.find(|child| self.dom.get_by_ref(**child).is_some_and(|child| child.name == name))?;
+		}
+
+		Some(id)
+	}
+
+	/// Sets root node of the loaded project, so changes of the root
+	/// instance can be synced back without loading the project again
+	pub fn set_project_node(&mut self, node: ProjectNode) {
+		self.project_node = Some(node);
+	}
+
+	pub fn project_node(&self) -> Option<&ProjectNode> {
+		self.project_node.as_ref()
+	}
+
+	/// Starts tracking frozen instances, stable
+	/// identifiers have to be loaded before
+	pub fn load_frozen(&mut self, mut frozen: Frozen) {
+		frozen.attach(|stable_id, path| {
+			stable_id
+				.and_then(|stable_id| self.find_by_stable_id(stable_id))
+				.or_else(|| self.find_by_path(path))
+		});
+
+		self.frozen = frozen;
+	}
+
+	/// Freezes the instance, returns `false` if it already is
+	pub fn freeze(&mut self, id: Ref) -> Result<bool> {
+		let Some(path) = self.get_path(id) else {
+			bail!("Instance {} does not exist", id);
+		};
+
+		if id == self.root_ref() {
+			bail!("The root instance can't be frozen");
+		}
+
+		let stable_id = self.get_meta(id).and_then(|meta| meta.stable_id.clone());
+		let frozen = self.frozen.insert(id, stable_id, path);
+
+		self.save_frozen()?;
+
+		Ok(frozen)
+	}
+
+	/// Unfreezes the instance, returns `false` if it was not frozen
+	pub fn unfreeze(&mut self, id: Ref) -> Result<bool> {
+		let unfrozen = self.frozen.remove(id);
+		self.save_frozen()?;
+
+		Ok(unfrozen)
+	}
+
+	pub fn is_frozen(&self, id: Ref) -> bool {
+		self.frozen.contains(id)
+	}
+
+	/// Returns the instance or its closest ancestor that is frozen
+	pub fn get_frozen(&self, id: Ref) -> Option<Ref> {
+		if self.frozen.is_empty() {
+			return None;
+		}
+
+		let mut current = id;
+
+		while let Some(instance) = self.dom.get_by_ref(current) {
+			if self.frozen.contains(current) {
+				return Some(current);
+			}
+
+			current = instance.parent();
+		}
+
+		None
+	}
+
+	/// Counts changes of the frozen instance that were not synced
+	pub fn suppress(&mut self, id: Ref, direction: SyncDirection, count: usize) {
+		info!(
+			"Suppressed {} {} of frozen instance {}",
+			count,
+			if direction == SyncDirection::ToStudio {
+				"disk changes"
+			} else {
+				"Studio changes"
+			},
+			self.get_path(id).unwrap_or_default()
+		);
+
+		self.frozen.suppress(id, direction, count);
+
+		if let Err(err) = self.save_frozen() {
+			warn!("Failed to save frozen instances: {}", err);
+		}
+	}
+
+	/// Removes Studio changes of frozen subtrees from `changes`, they
+	/// are kept until the frozen instance is manually synced to disk
+	pub fn hold_frozen(&mut self, changes: Changes) -> Changes {
+		if self.frozen.is_empty() {
+			return changes;
+		}
+
+		let mut allowed = Changes::new();
+		let mut held: HashMap<Ref, Changes> = HashMap::new();
+
+		for snapshot in changes.additions {
+			match self.get_frozen(snapshot.parent) {
+				Some(frozen) => held.entry(frozen).or_insert_with(Changes::new).additions.push(snapshot),
+				None => allowed.additions.push(snapshot),
+			}
+		}
+
+		for snapshot in changes.updates {
+			match self.get_frozen(snapshot.id) {
+				Some(frozen) => held.entry(frozen).or_insert_with(Changes::new).updates.push(snapshot),
+				None => allowed.updates.push(snapshot),
+			}
+		}
+
+		for id in changes.removals {
+			match self.get_frozen(id) {
+				Some(frozen) => held.entry(frozen).or_insert_with(Changes::new).removals.push(id),
+				None => allowed.removals.push(id),
+			}
+		}
+
+		for (frozen, changes) in held {
+			self.suppress(frozen, SyncDirection::ToDisk, changes.total());
+			self.frozen.defer(frozen, changes);
+		}
+
+		allowed
+	}
+
+	/// Returns Studio changes held for the frozen instance and
+	/// clears its counters, used when it gets manually synced
+	pub fn take_frozen(&mut self, id: Ref) -> Changes {
+		self.frozen.reset(id);
+
+		if let Err(err) = self.save_frozen() {
+			warn!("Failed to save frozen instances: {}", err);
+		}
+
+		self.frozen.take_deferred(id)
+	}
+
+	pub fn frozen_instances(&self) -> Vec<FrozenInstance> {
+		self.frozen.list(|id| self.get_path(id))
+	}
+
+	fn save_frozen(&mut self) -> Result<()> {
+		let paths: HashMap<Ref, String> = self
+			.frozen_instances()
+			.into_iter()
+			.filter_map(|instance| Some((instance.id?, instance.path)))
+			.collect();
+
+		self.frozen.save(|id| paths.get(&id).cloned())
+	}
+
+	pub fn find_by_stable_id(&self, stable_id: &str) -> Option<Ref> {
+		self.stable_to_id.get(stable_id).copied()
+	}
+
+	pub fn stable_id_map(&self) -> &HashMap<String, Ref> {
+		&self.stable_to_id
+	}
+
+	fn order_key(&self, id: Ref) -> (&str, &str, Option<&Path>) {
+		let instance = self.dom.get_by_ref(id).unwrap();
+		let path = self.get_meta(id).and_then(|meta| meta.source.get().path());
+
+		(&instance.name, &instance.class, path)
+	}
+
+	fn assign_stable_id(&self, id: Ref) -> Option<String> {
+		let full_name = self.get_full_name(id)?;
+
+		Some(
+			self.stable_ids
+				.assign(&full_name, |stable_id| self.stable_to_id.contains_key(stable_id)),
+		)
+	}
+
+	fn retire_stable_id(&mut self, id: Ref) {
+		let Some(stable_id) = self.get_meta(id).and_then(|meta| meta.stable_id.clone()) else {
+			return;
+		};
+
+		if let Some(full_name) = self.get_full_name(id) {
+			self.stable_ids.retire(full_name, stable_id);
+		}
+	}
+
+	/// Returns the next instance in depth-first order,
+	/// parents are always visited before their children
+	pub fn next_in_order(&self, id: Ref) -> Option<Ref> {
+		let mut instance = self.dom.get_by_ref(id)?;
+
+		if let Some(child) = instance.children().first() {
+			return Some(*child);
+		}
+
+		while instance.referent() != self.dom.root_ref() {
+			let parent = self.dom.get_by_ref(instance.parent())?;
+			let siblings = parent.children();
+			let index = siblings.iter().position(|sibling| *sibling == instance.referent())?;
+
+			if let Some(sibling) = siblings.get(index + 1) {
+				return Some(*sibling);
+			}
+
+			instance = parent;
+		}
+
+		None
+	}
+
+	/// Returns position of the root instance, the first one in depth-first order
+	pub fn root_position(&self) -> TreePosition {
+		TreePosition {
+			id: self.dom.root_ref(),
+			indices: vec![],
+		}
+	}
+
+	/// Returns position of the instance reached by following the sibling indices from the root
+	pub fn position_at(&self, indices: Vec<usize>) -> Option<TreePosition> {
+		let mut id = self.dom.root_ref();
+
+		for index in &indices {
+			id = *self.dom.get_by_ref(id)?.children().get(*index)?;
+		}
+
+		Some(TreePosition { id, indices })
+	}
+
+	/// Returns position of the next instance in depth-first order, same as `next_in_order`
+	/// but in constant time per step no matter how many siblings the instances have
+	pub fn next_position(&self, position: TreePosition) -> Option<TreePosition> {
+		let TreePosition { id, mut indices } = position;
+		let mut instance = self.dom.get_by_ref(id)?;
+
+		if let Some(child) = instance.children().first() {
+			indices.push(0);
+			return Some(TreePosition { id: *child, indices });
+		}
+
+		while let Some(index) = indices.pop() {
+			let parent = self.dom.get_by_ref(instance.parent())?;
+
+			if let Some(sibling) = parent.children().get(index + 1) {
+				indices.push(index + 1);
+				return Some(TreePosition { id: *sibling, indices });
+			}
+
+			instance = parent;
+		}
+
+		None
+	}
+
+	/// Orders children of every instance by name, class and source path,
+	/// so the tree serializes the same no matter the order they were added in
+	pub fn sort_children(&mut self) {
+		let mut stack = vec![self.dom.root_ref()];
+
+		while let Some(id) = stack.pop() {
+			let Some(instance) = self.dom.get_by_ref(id) else {
+				continue;
+			};
+
+			let mut children = instance.children().to_vec();
+			children.sort_by(|a, b| self.order_key(*a).cmp(&self.order_key(*b)));
+
+			// Transferring within the same parent moves the child to the end
+			if children != self.dom.get_by_ref(id).unwrap().children() {
+				for child in &children {
+					self.dom.transfer_within(*child, id);
+				}
+			}
+
+			stack.extend(children);
+		}
+	}
+
+	/// Updates path index with instances that were modified in place
+	pub fn refresh_path_index(&mut self) {
+		self.path_index.refresh(&self.dom);
+	}
+
+	/// Returns path index of the tree, `refresh_path_index`
+	/// should be called first if instances could have been renamed
+	pub fn path_index(&self) -> &PathIndex {
+		&self.path_index
+	}
+
+	/// Returns number that changes every time the tree gets modified
+	pub fn generation(&self) -> u64 {
+		self.generation
+	}
+
 	pub fn exists(&self, id: Ref) -> bool {
 		self.dom.get_by_ref(id).is_some()
 	}
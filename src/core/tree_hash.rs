@@ -0,0 +1,491 @@
+use rbx_dom_weak::{
+	types::{Attributes, CFrame, ContentType, PhysicalProperties, Ref, Variant},
+	Ustr, WeakDom,
+};
+use std::{
+	collections::HashMap,
+	fmt::{self, Display, Formatter},
+};
+
+use super::snapshot::Snapshot;
+
+/// Version of the hashing rules below, has to be bumped
+/// whenever they change the hash of an unchanged tree
+pub const TREE_HASH_VERSION: u32 = 1;
+
+/// Attribute of the first top-level instance that built files carry their hash in,
+/// it is never part of the hash itself
+pub const TREE_HASH_ATTRIBUTE: &str = "__ArgonTreeHash";
+
+const FNV_OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+const FNV_PRIME: u128 = 0x0000000001000000000000000000013b;
+
+/// Numbers are truncated to this many decimal places like in data files
+const FLOAT_PRECISION: f64 = 1_000_000.0;
+/// Infinities are clamped to this value like in data files
+const FLOAT_INFINITY: f64 = 999_999_999.0;
+
+/// Canonical hash of a tree, the same tree gives the same hash on every
+/// platform as long as `TREE_HASH_VERSION` stays the same
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TreeHash(u128);
+
+impl TreeHash {
+	/// Parses hash in the form it is embedded in built files,
+	/// `None` if it is malformed or comes from a different hash version
+	pub fn from_embedded(value: &str) -> Option<Self> {
+		let (version, digest) = value.split_once(':')?;
+
+		if version.parse::<u32>().ok()? != TREE_HASH_VERSION {
+			return None;
+		}
+
+		u128::from_str_radix(digest, 16).ok().map(Self)
+	}
+
+	/// Formats hash as `<version>:<digest>` to embed it in built files
+	pub fn to_embedded(&self) -> String {
+		format!("{}:{}", TREE_HASH_VERSION, self)
+	}
+}
+
+impl Display for TreeHash {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{:032x}", self.0)
+	}
+}
+
+/// Returns the version of hash embedded in `value`, if it can be parsed
+pub fn embedded_version(value: &str) -> Option<u32> {
+	value.split_once(':')?.0.parse().ok()
+}
+
+/// Normalizes number the same way data files serialize it: truncated to six decimal places,
+/// infinities clamped and negative zero turned into positive one, `None` for NaN
+pub fn normalize_float(number: f64) -> Option<f64> {
+	if number.is_nan() {
+		return None;
+	}
+
+	let number = if number.is_infinite() {
+		FLOAT_INFINITY * number.signum()
+	} else {
+		(number * FLOAT_PRECISION).trunc() / FLOAT_PRECISION
+	};
+
+	if number.is_infinite() {
+		Some(FLOAT_INFINITY * number.signum())
+	} else {
+		Some(number + 0.0)
+	}
+}
+
+/// Hashes the whole snapshot tree, places are hashed without the root
+/// `DataModel` because only its children get serialized
+pub fn hash_snapshot(snapshot: &Snapshot, place: bool) -> TreeHash {
+	let top_level: Vec<&Snapshot> = if place {
+		snapshot.children.iter().collect()
+	} else {
+		vec![snapshot]
+	};
+
+	let mut paths = HashMap::new();
+
+	for snapshot in &top_level {
+		collect_snapshot_paths(snapshot, String::new(), &mut paths);
+	}
+
+	hash_top_level(
+		top_level
+			.iter()
+			.map(|snapshot| hash_snapshot_instance(snapshot, &paths)),
+	)
+}
+
+/// Hashes the given top-level instances of the DOM and their descendants,
+/// e.g. `place_root_refs` of the tree or children of the root of a built file
+pub fn hash_dom(dom: &WeakDom, top_level: &[Ref]) -> TreeHash {
+	let mut paths = HashMap::new();
+
+	for &id in top_level {
+		collect_dom_paths(dom, id, String::new(), &mut paths);
+	}
+
+	hash_top_level(top_level.iter().map(|&id| hash_dom_instance(dom, id, &paths)))
+}
+
+/// Returns hash embedded in the given top-level instances, as is
+pub fn find_embedded(dom: &WeakDom, top_level: &[Ref]) -> Option<String> {
+	top_level.iter().find_map(|&id| {
+		let attributes = dom.get_by_ref(id)?.properties.get(&Ustr::from("Attributes"))?;
+
+		match attributes {
+			Variant::Attributes(attributes) => match attributes.get(TREE_HASH_ATTRIBUTE)? {
+				Variant::String(value) => Some(value.to_owned()),
+				// Binary models read string attributes back as binary strings
+				Variant::BinaryString(value) => {
+					let bytes: &[u8] = value.as_ref();
+					String::from_utf8(bytes.to_vec()).ok()
+				}
+				_ => None,
+			},
+			_ => None,
+		}
+	})
+}
+
+fn hash_top_level(hashes: impl Iterator<Item = TreeHash>) -> TreeHash {
+	let mut hashes: Vec<TreeHash> = hashes.collect();
+	hashes.sort();
+
+	let mut hasher = Hasher::new();
+
+	hasher.write_u64(TREE_HASH_VERSION as u64);
+	hasher.write_hashes(&hashes);
+
+	hasher.finish()
+}
+
+fn hash_snapshot_instance(snapshot: &Snapshot, paths: &HashMap<Ref, String>) -> TreeHash {
+	let children = snapshot
+		.children
+		.iter()
+		.map(|child| hash_snapshot_instance(child, paths))
+		.collect();
+
+	hash_instance(
+		&snapshot.name,
+		&snapshot.class,
+		snapshot.properties.iter().map(|(key, value)| (key.as_str(), value)),
+		children,
+		paths,
+	)
+}
+
+fn hash_dom_instance(dom: &WeakDom, id: Ref, paths: &HashMap<Ref, String>) -> TreeHash {
+	let instance = dom.get_by_ref(id).expect("Instance of the hashed tree does not exist");
+
+	let children = instance
+		.children()
+		.iter()
+		.map(|&child| hash_dom_instance(dom, child, paths))
+		.collect();
+
+	hash_instance(
+		&instance.name,
+		&instance.class,
+		instance.properties.iter().map(|(key, value)| (key.as_str(), value)),
+		children,
+		paths,
+	)
+}
+
+/// Hashes name, class, properties sorted by name and children sorted by their hashes
+fn hash_instance<'a>(
+	name: &str,
+	class: &str,
+	properties: impl Iterator<Item = (&'a str, &'a Variant)>,
+	mut children: Vec<TreeHash>,
+	paths: &HashMap<Ref, String>,
+) -> TreeHash {
+	let mut properties: Vec<(&str, &Variant)> = properties.filter(|(_, value)| is_hashed(value)).collect();
+	properties.sort_by(|a, b| a.0.cmp(b.0));
+
+	children.sort();
+
+	let mut hasher = Hasher::new();
+
+	hasher.write_str(name);
+	hasher.write_str(class);
+	hasher.write_u64(properties.len() as u64);
+
+	for (key, value) in properties {
+		hasher.write_str(key);
+		hasher.write_variant(value, paths);
+	}
+
+	hasher.write_hashes(&children);
+
+	hasher.finish()
+}
+
+/// Unique IDs are random for every load and attributes
+/// that are empty or hold only the hash are the same as missing ones
+fn is_hashed(value: &Variant) -> bool {
+	match value {
+		Variant::UniqueId(_) => false,
+		Variant::Attributes(attributes) => attributes.iter().any(|(key, _)| key.as_str() != TREE_HASH_ATTRIBUTE),
+		_ => true,
+	}
+}
+
+/// Referents are random for every load so referenced
+/// instances are hashed by their path in the hashed tree
+fn collect_snapshot_paths(snapshot: &Snapshot, parent: String, paths: &mut HashMap<Ref, String>) {
+	let path = join_path(parent, &snapshot.name);
+
+	for child in &snapshot.children {
+		collect_snapshot_paths(child, path.clone(), paths);
+	}
+
+	if snapshot.id.is_some() {
+		paths.insert(snapshot.id, path);
+	}
+}
+
+fn collect_dom_paths(dom: &WeakDom, id: Ref, parent: String, paths: &mut HashMap<Ref, String>) {
+	let instance = dom.get_by_ref(id).expect("Instance of the hashed tree does not exist");
+	let path = join_path(parent, &instance.name);
+
+	for &child in instance.children() {
+		collect_dom_paths(dom, child, path.clone(), paths);
+	}
+
+	paths.insert(id, path);
+}
+
+fn join_path(parent: String, name: &str) -> String {
+	if parent.is_empty() {
+		name.to_owned()
+	} else {
+		// NUL can't be a part of instance name so paths stay unambiguous
+		format!("{}\0{}", parent, name)
+	}
+}
+
+/// FNV-1a (128 bit), every variable-length value is prefixed with its length
+struct Hasher(u128);
+
+impl Hasher {
+	fn new() -> Self {
+		Self(FNV_OFFSET)
+	}
+
+	fn finish(&self) -> TreeHash {
+		TreeHash(self.0)
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		for byte in bytes {
+			self.0 ^= *byte as u128;
+			self.0 = self.0.wrapping_mul(FNV_PRIME);
+		}
+	}
+
+	fn write_tag(&mut self, tag: u8) {
+		self.write(&[tag]);
+	}
+
+	fn write_u64(&mut self, value: u64) {
+		self.write(&value.to_le_bytes());
+	}
+
+	fn write_bytes(&mut self, bytes: &[u8]) {
+		self.write_u64(bytes.len() as u64);
+		self.write(bytes);
+	}
+
+	fn write_str(&mut self, value: &str) {
+		self.write_bytes(value.as_bytes());
+	}
+
+	fn write_hashes(&mut self, hashes: &[TreeHash]) {
+		self.write_u64(hashes.len() as u64);
+
+		for hash in hashes {
+			self.write(&hash.0.to_le_bytes());
+		}
+	}
+
+	fn write_int(&mut self, value: i64) {
+		self.write_tag(b'i');
+		self.write(&value.to_le_bytes());
+	}
+
+	/// Integral numbers are hashed as integers because data files store them that way
+	fn write_float(&mut self, value: f64) {
+		match normalize_float(value) {
+			Some(value) if value.fract() == 0.0 && value.abs() < i64::MAX as f64 => self.write_int(value as i64),
+			Some(value) => {
+				self.write_tag(b'f');
+				self.write(&value.to_bits().to_le_bytes());
+			}
+			None => self.write_tag(b'n'),
+		}
+	}
+
+	fn write_floats(&mut self, values: &[f32]) {
+		for value in values {
+			self.write_float(*value as f64);
+		}
+	}
+
+	fn write_ref(&mut self, referent: Ref, paths: &HashMap<Ref, String>) {
+		if referent.is_none() {
+			self.write_tag(b'0');
+		} else if let Some(path) = paths.get(&referent) {
+			self.write_tag(b'r');
+			self.write_str(path);
+		} else {
+			// Instance outside of the hashed tree
+			self.write_tag(b'x');
+		}
+	}
+
+	fn write_attributes(&mut self, attributes: &Attributes, paths: &HashMap<Ref, String>) {
+		let attributes: Vec<(&String, &Variant)> = attributes
+			.iter()
+			.filter(|(key, _)| key.as_str() != TREE_HASH_ATTRIBUTE)
+			.collect();
+
+		self.write_u64(attributes.len() as u64);
+
+		// Attributes are already sorted by their names
+		for (key, value) in attributes {
+			self.write_str(key);
+			self.write_variant(value, paths);
+		}
+	}
+
+	fn write_variant(&mut self, variant: &Variant, paths: &HashMap<Ref, String>) {
+		self.write_str(&format!("{:?}", variant.ty()));
+
+		match variant {
+			Variant::Bool(bool) => self.write_tag(*bool as u8),
+			Variant::Int32(int) => self.write_int(*int as i64),
+			Variant::Int64(int) => self.write_int(*int),
+			Variant::Float32(float) => self.write_float(*float as f64),
+			Variant::Float64(float) => self.write_float(*float),
+			Variant::String(string) => self.write_str(string),
+			Variant::ContentId(content) => self.write_str(content.as_str()),
+			Variant::BinaryString(bytes) => self.write_bytes(AsRef::<[u8]>::as_ref(bytes)),
+			Variant::SharedString(shared) => self.write_bytes(shared.data()),
+			Variant::Enum(value) => self.write_int(value.to_u32() as i64),
+			Variant::EnumItem(item) => {
+				self.write_str(&item.ty);
+				self.write_int(item.value as i64);
+			}
+			Variant::Ref(referent) => self.write_ref(*referent, paths),
+			Variant::Content(content) => match content.value() {
+				ContentType::Uri(uri) => {
+					self.write_tag(b'u');
+					self.write_str(uri);
+				}
+				ContentType::Object(referent) => self.write_ref(*referent, paths),
+				_ => self.write_tag(b'0'),
+			},
+
+			Variant::Vector2(vector) => self.write_floats(&[vector.x, vector.y]),
+			Variant::Vector3(vector) => self.write_floats(&[vector.x, vector.y, vector.z]),
+			Variant::Vector2int16(vector) => {
+				self.write_int(vector.x as i64);
+				self.write_int(vector.y as i64);
+			}
+			Variant::Vector3int16(vector) => {
+				self.write_int(vector.x as i64);
+				self.write_int(vector.y as i64);
+				self.write_int(vector.z as i64);
+			}
+			Variant::CFrame(cf) => self.write_cframe(cf),
+			Variant::OptionalCFrame(cf) => match cf {
+				Some(cf) => {
+					self.write_tag(1);
+					self.write_cframe(cf);
+				}
+				None => self.write_tag(0),
+			},
+			Variant::Color3(color) => self.write_floats(&[color.r, color.g, color.b]),
+			Variant::Color3uint8(color) => {
+				self.write_int(color.r as i64);
+				self.write_int(color.g as i64);
+				self.write_int(color.b as i64);
+			}
+			Variant::UDim(udim) => {
+				self.write_float(udim.scale as f64);
+				self.write_int(udim.offset as i64);
+			}
+			Variant::UDim2(udim) => {
+				self.write_float(udim.x.scale as f64);
+				self.write_int(udim.x.offset as i64);
+				self.write_float(udim.y.scale as f64);
+				self.write_int(udim.y.offset as i64);
+			}
+			Variant::Rect(rect) => self.write_floats(&[rect.min.x, rect.min.y, rect.max.x, rect.max.y]),
+			Variant::Ray(ray) => self.write_floats(&[
+				ray.origin.x,
+				ray.origin.y,
+				ray.origin.z,
+				ray.direction.x,
+				ray.direction.y,
+				ray.direction.z,
+			]),
+			Variant::Region3(region) => self.write_floats(&[
+				region.min.x,
+				region.min.y,
+				region.min.z,
+				region.max.x,
+				region.max.y,
+				region.max.z,
+			]),
+			Variant::NumberRange(range) => self.write_floats(&[range.min, range.max]),
+			Variant::NumberSequence(sequence) => {
+				self.write_u64(sequence.keypoints.len() as u64);
+
+				for keypoint in &sequence.keypoints {
+					self.write_floats(&[keypoint.time, keypoint.value, keypoint.envelope]);
+				}
+			}
+			Variant::ColorSequence(sequence) => {
+				self.write_u64(sequence.keypoints.len() as u64);
+
+				for keypoint in &sequence.keypoints {
+					let color = keypoint.color;
+					self.write_floats(&[keypoint.time, color.r, color.g, color.b]);
+				}
+			}
+			Variant::PhysicalProperties(properties) => match properties {
+				PhysicalProperties::Custom(custom) => {
+					self.write_tag(1);
+					self.write_floats(&[
+						custom.density,
+						custom.friction,
+						custom.elasticity,
+						custom.friction_weight,
+						custom.elasticity_weight,
+					]);
+				}
+				PhysicalProperties::Default => self.write_tag(0),
+			},
+			Variant::Tags(tags) => {
+				self.write_u64(tags.len() as u64);
+
+				for tag in tags.iter() {
+					self.write_str(tag);
+				}
+			}
+			Variant::Attributes(attributes) => self.write_attributes(attributes, paths),
+
+			// Remaining types hold no numbers that need normalization
+			_ => self.write_str(&serde_json::to_string(variant).unwrap_or_default()),
+		}
+	}
+
+	fn write_cframe(&mut self, cf: &CFrame) {
+		let (position, orientation) = (cf.position, cf.orientation);
+
+		self.write_floats(&[
+			position.x,
+			position.y,
+			position.z,
+			orientation.x.x,
+			orientation.x.y,
+			orientation.x.z,
+			orientation.y.x,
+			orientation.y.y,
+			orientation.y.z,
+			orientation.z.x,
+			orientation.z.y,
+			orientation.z.z,
+		]);
+	}
+}
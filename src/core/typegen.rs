@@ -0,0 +1,148 @@
+use anyhow::{Context, Result};
+use rbx_dom_weak::types::Ref;
+use std::{collections::HashMap, fmt::Write};
+
+use super::{changes::Changes, tree::Tree};
+
+const KEYWORDS: [&str; 22] = [
+	"and", "break", "continue", "do", "else", "elseif", "end", "export", "false", "for", "function", "if", "in",
+	"local", "nil", "not", "or", "repeat", "return", "then", "true", "until",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct TypegenOptions {
+	/// Name of the exported type
+	pub name: String,
+	/// Maximum depth of nested instances, `None` means unlimited
+	pub depth: Option<usize>,
+	/// Path of the instance to start from, separated with `/` or `.`
+	pub root: Option<String>,
+}
+
+/// Turns instance name into a valid Luau identifier,
+/// invalid characters are replaced with underscores
+pub fn sanitize(name: &str) -> String {
+	let mut identifier: String = name
+		.chars()
+		.map(|char| if char.is_ascii_alphanumeric() { char } else { '_' })
+		.collect();
+
+	if identifier.is_empty()
+		|| identifier.starts_with(|char: char| char.is_ascii_digit())
+		|| KEYWORDS.contains(&identifier.as_str())
+	{
+		identifier.insert(0, '_');
+	}
+
+	identifier
+}
+
+/// Whether changes can affect generated types,
+/// property-only updates do not change the structure
+pub fn is_structural(changes: &Changes) -> bool {
+	!changes.additions.is_empty()
+		|| !changes.removals.is_empty()
+		|| changes
+			.updates
+			.iter()
+			.any(|update| update.name.is_some() || update.class.is_some())
+}
+
+/// Generates deterministic Luau module exporting type of the tree,
+/// children are sorted by name and class so the output only changes with the structure
+pub fn generate(tree: &Tree, options: &TypegenOptions) -> Result<String> {
+	let root = match &options.root {
+		Some(path) => find(tree, path).with_context(|| format!("Instance {} does not exist", path))?,
+		None => tree.root_ref(),
+	};
+
+	let mut output = String::from("-- This file is generated by Argon, do not edit it manually\n\n");
+
+	write!(output, "export type {} = ", sanitize(&options.name))?;
+	write_instance(tree, root, 0, options.depth, &mut output)?;
+	output.push_str("\n\nreturn nil\n");
+
+	Ok(output)
+}
+
+fn find(tree: &Tree, path: &str) -> Option<Ref> {
+	let dom = tree.inner();
+	let mut current = tree.root_ref();
+
+	for name in path.split(['/', '.']).filter(|name| !name.is_empty()) {
+		current = dom
+			.get_by_ref(current)?
+			.children()
+			.iter()
+			.copied()
+			.find(|child| dom.get_by_ref(*child).is_some_and(|child| child.name == name))?;
+	}
+
+	Some(current)
+}
+
+fn class_name(class: &str) -> &str {
+	if rbx_reflection_database::get().classes.contains_key(class) {
+		class
+	} else {
+		"Instance"
+	}
+}
+
+fn write_instance(tree: &Tree, id: Ref, depth: usize, max_depth: Option<usize>, output: &mut String) -> Result<()> {
+	let instance = tree.get_instance(id).unwrap();
+	let class = class_name(&instance.class);
+
+	if instance.children().is_empty() || max_depth.is_some_and(|max_depth| depth >= max_depth) {
+		output.push_str(class);
+		return Ok(());
+	}
+
+	let mut children: Vec<_> = instance
+		.children()
+		.iter()
+		.filter_map(|child| tree.get_instance(*child))
+		.collect();
+
+	children.sort_by(|a, b| {
+		a.name
+			.cmp(&b.name)
+			.then_with(|| a.class.cmp(&b.class))
+			.then_with(|| a.children().len().cmp(&b.children().len()))
+	});
+
+	let indent = "\t".repeat(depth + 1);
+	let mut used: HashMap<String, usize> = HashMap::new();
+
+	write!(output, "{} & {{", class)?;
+
+	for child in children {
+		let mut identifier = sanitize(&child.name);
+
+		// Duplicate siblings get a numeric suffix in the sorted order
+		let count = used.entry(identifier.clone()).or_insert(0);
+		*count += 1;
+
+		if *count > 1 {
+			identifier = format!("{}_{}", identifier, count);
+
+			while used.contains_key(&identifier) {
+				identifier.push('_');
+			}
+
+			used.insert(identifier.clone(), 1);
+		}
+
+		if identifier != child.name {
+			write!(output, "\n{}-- {:?}", indent, child.name)?;
+		}
+
+		write!(output, "\n{}{}: ", indent, identifier)?;
+		write_instance(tree, child.referent(), depth + 1, max_depth, output)?;
+		output.push(',');
+	}
+
+	write!(output, "\n{}}}", "\t".repeat(depth))?;
+
+	Ok(())
+}
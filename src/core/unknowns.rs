@@ -0,0 +1,120 @@
+use rbx_dom_weak::types::Ref;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::{BTreeMap, HashMap},
+	sync::Mutex,
+};
+
+use crate::lock;
+
+/// Unknown instances preserved by the client under a single parent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnknownsGroup {
+	pub parent: Ref,
+	/// Number of preserved instances of each class
+	pub classes: BTreeMap<String, usize>,
+}
+
+/// Unknown instances under a single parent as reported by all clients
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnknownsSummary {
+	pub parent: Ref,
+	pub path: String,
+	/// The highest number of instances of each class reported by any client
+	pub classes: BTreeMap<String, usize>,
+	pub total: usize,
+	/// Clients that reported unknown instances under the parent
+	pub clients: Vec<u32>,
+	/// Unix timestamp in seconds of the most recent report
+	pub reported: i64,
+}
+
+#[derive(Debug)]
+struct Report {
+	groups: Vec<UnknownsGroup>,
+	time: i64,
+}
+
+/// Unknown instances that clients keep because of `$keepUnknowns`,
+/// only the latest report of each client is stored
+#[derive(Debug, Default)]
+pub struct Unknowns {
+	reports: Mutex<HashMap<u32, Report>>,
+}
+
+impl Unknowns {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Replaces the previous report of the client
+	pub fn report(&self, client_id: u32, groups: Vec<UnknownsGroup>) {
+		let report = Report {
+			groups,
+			time: chrono::Utc::now().timestamp(),
+		};
+
+		lock!(self.reports).insert(client_id, report);
+	}
+
+	pub fn remove(&self, client_id: u32) {
+		lock!(self.reports).remove(&client_id);
+	}
+
+	/// Aggregates reports of all clients per parent, sorted by path. Clients
+	/// connected to the same place see the same instances so counts are not
+	/// summed up. `path_of` provides the path of the parent, groups of
+	/// parents that no longer exist are omitted
+	pub fn summary(&self, path_of: impl Fn(Ref) -> Option<String>) -> Vec<UnknownsSummary> {
+		let reports = lock!(self.reports);
+		let mut summaries: HashMap<Ref, UnknownsSummary> = HashMap::new();
+
+		for (client_id, report) in reports.iter() {
+			for group in &report.groups {
+				let summary = match summaries.get_mut(&group.parent) {
+					Some(summary) => summary,
+					None => {
+						let Some(path) = path_of(group.parent) else {
+							continue;
+						};
+
+						summaries.entry(group.parent).or_insert(UnknownsSummary {
+							parent: group.parent,
+							path,
+							classes: BTreeMap::new(),
+							total: 0,
+							clients: vec![],
+							reported: report.time,
+						})
+					}
+				};
+
+				for (class, count) in &group.classes {
+					let current = summary.classes.entry(class.clone()).or_default();
+					*current = (*current).max(*count);
+				}
+
+				if !summary.clients.contains(client_id) {
+					summary.clients.push(*client_id);
+				}
+
+				summary.reported = summary.reported.max(report.time);
+			}
+		}
+
+		let mut summaries: Vec<UnknownsSummary> = summaries
+			.into_values()
+			.filter(|summary| !summary.classes.is_empty())
+			.map(|mut summary| {
+				summary.total = summary.classes.values().sum();
+				summary.clients.sort();
+				summary
+			})
+			.collect();
+
+		summaries.sort_by(|a, b| a.path.cmp(&b.path));
+		summaries
+	}
+}
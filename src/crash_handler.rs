@@ -1,109 +1,532 @@
+use anyhow::{anyhow, Result};
 use backtrace::Backtrace;
 use colored::Colorize;
 use log::{error, trace, LevelFilter};
 use open;
 use panic_message::get_panic_info_message;
-use std::{env, panic, process};
+use rbx_dom_weak::{types::Variant, ustr};
+use reqwest::{blocking::Client, header::CONTENT_TYPE};
+use serde::Serialize;
+use std::{
+	cell::Cell,
+	collections::{hash_map::DefaultHasher, BTreeMap},
+	env, fs,
+	hash::{Hash, Hasher},
+	io::{self, IsTerminal},
+	panic::{self, AssertUnwindSafe},
+	path::{Path, PathBuf},
+	process,
+	sync::{Arc, Mutex, Weak},
+	thread,
+	time::{Duration, Instant},
+};
 
-use crate::{argon_error, logger, util};
+use crate::{
+	argon_error,
+	config::Config,
+	core::{
+		meta::{Meta, SourceEntry, SourceKind},
+		tree::Tree,
+	},
+	logger,
+	project::Project,
+	t, util,
+};
 
 const MAX_BACKTRACE_LEN: usize = 6500;
+const SEND_TIMEOUT: Duration = Duration::from_secs(3);
+const STATS_TIMEOUT: Duration = Duration::from_millis(500);
 
-pub fn hook() {
-	panic::set_hook(Box::new(|panic_info| {
-		let message = get_panic_info_message(panic_info).unwrap_or("Failed to get panic info message");
-		let backtrace_enabled = util::env_backtrace();
-		let verbosity = util::env_verbosity();
+/// Workspace whose stats are included in crash reports, see `track`
+struct Tracked {
+	tree: Weak<Mutex<Tree>>,
+	project: Weak<Mutex<Project>>,
+	started: Instant,
+}
 
-		let mut report = String::from("> This crash report was automatically generated by Argon");
-		report.push_str("\n\nDetails:\n--------\n");
-		report.push_str("*Provide extra details about your problem or what happened here*");
+static TRACKED: Mutex<Option<Tracked>> = Mutex::new(None);
 
-		argon_error!("{}", "Argon has crashed!".bold());
+thread_local! {
+	/// Whether the thread is collecting stats, panics
+	/// are then caught instead of reported
+	static COLLECTING: Cell<bool> = const { Cell::new(false) };
+}
 
-		report.push_str("\n\nMessage:\n----------\n");
-		report.push_str(message);
+/// Details included in crash reports sent to `crash_report_url`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Privacy {
+	/// Workspace is only identified by its hash, backtrace contains bare addresses
+	#[default]
+	Anonymous,
+	/// Workspace path and backtrace symbols are included
+	Full,
+}
 
-		argon_error!("{}: {}", "Message".bold(), message);
+impl Privacy {
+	pub fn from_setting(setting: &str) -> Self {
+		if setting.eq_ignore_ascii_case("full") {
+			Self::Full
+		} else {
+			Self::Anonymous
+		}
+	}
+}
 
-		report.push_str("\n\nLocation:\n----------\n");
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+	pub message: String,
+	pub location: Option<String>,
+	pub backtrace: Option<String>,
+	pub version: String,
+	pub os: String,
+	/// Hash of the workspace path, the same for all crashes in one workspace
+	pub workspace: String,
+	pub workspace_path: Option<String>,
+	/// Only included when `crash_report_include_stats` setting is enabled
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub stats: Option<WorkspaceStats>,
+}
 
-		if let Some(location) = panic_info.location() {
-			report.push_str(location.file());
-			report.push_str(": ");
-			report.push_str(&location.line().to_string());
+impl CrashReport {
+	pub fn new(
+		message: &str,
+		location: Option<String>,
+		backtrace: Option<Backtrace>,
+		workspace_dir: &Path,
+		privacy: Privacy,
+	) -> Self {
+		let mut hasher = DefaultHasher::new();
+		workspace_dir.hash(&mut hasher);
 
-			argon_error!("{}: {}: {}", "Location".bold(), location.file(), location.line());
-		} else {
-			report.push_str("Failed to get panic info location");
+		Self {
+			message: message.to_owned(),
+			location,
+			backtrace: backtrace.map(|backtrace| format_backtrace(backtrace, privacy)),
+			version: env!("CARGO_PKG_VERSION").to_owned(),
+			os: format!("{} {}", env::consts::OS, env::consts::ARCH),
+			workspace: format!("{:016x}", hasher.finish()),
+			workspace_path: match privacy {
+				Privacy::Full => Some(workspace_dir.display().to_string()),
+				Privacy::Anonymous => None,
+			},
+			stats: None,
 		}
+	}
 
-		report.push_str("\n\nVerbose output:\n----------------\n");
+	pub fn with_stats(mut self, stats: Option<WorkspaceStats>) -> Self {
+		self.stats = stats;
+		self
+	}
 
-		if verbosity != LevelFilter::Trace {
-			report.push_str("Verbose output disabled, run Argon with `-vvvv` flag to enable");
+	pub fn to_json(&self) -> String {
+		serde_json::to_string(self).unwrap_or_default()
+	}
+}
 
-			argon_error!(
-				"{}: Run Argon with {} flag to show detailed tracing",
-				"Verbosity".bold(),
-				"-vvvv".bold()
-			);
-		} else {
-			report.push_str("```\nPast your verbose output here\n```");
-		}
+/// Anonymized scale of the workspace that crashed, it contains only
+/// the counts below, never any instance names, paths or source content
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceStats {
+	/// Number of instances in the tree
+	pub instances: usize,
+	/// Number of ancestors of the most nested instance
+	pub max_depth: usize,
+	/// Total size of script sources that are in memory
+	pub script_source_bytes: usize,
+	/// Number of nodes in the project file
+	pub project_nodes: usize,
+	/// Number of file-backed instances read by each middleware
+	pub middleware: BTreeMap<String, usize>,
+	/// Seconds since the workspace was loaded
+	pub uptime: u64,
+}
+
+impl WorkspaceStats {
+	/// Computes stats from the tree, gives up with `None` once `deadline` passes
+	pub fn collect(tree: &Tree, project: &Project, uptime: Duration, deadline: Instant) -> Option<Self> {
+		let mut stats = Self {
+			uptime: uptime.as_secs(),
+			..Self::default()
+		};
 
-		report.push_str("\n\nBacktrace:\n-----------\n");
+		let dom = tree.inner();
+		let mut stack = vec![(dom.root_ref(), 0)];
 
-		if backtrace_enabled {
-			let backtrace = Backtrace::new();
+		while let Some((id, depth)) = stack.pop() {
+			if Instant::now() > deadline {
+				return None;
+			}
 
-			argon_error!("{}:\n{:?}", "Backtrace".bold(), backtrace);
+			let Some(instance) = dom.get_by_ref(id) else {
+				continue;
+			};
 
-			// Temporary solution for broken OsString parser
-			let mut backtrace = format!("{:?}", backtrace);
-			backtrace = backtrace.replace("             ", "\t\t");
-			backtrace = backtrace.replace("    ", "\t");
-			backtrace = backtrace.replace("   ", "");
-			backtrace = backtrace.replace("  ", "");
-			backtrace = backtrace.replace('&', "ptr");
+			stats.instances += 1;
+			stats.max_depth = stats.max_depth.max(depth);
 
-			if backtrace.len() > MAX_BACKTRACE_LEN {
-				backtrace.drain(..MAX_BACKTRACE_LEN);
-				backtrace.push_str("\n...\n");
+			if let Some(Variant::String(source)) = instance.properties.get(&ustr("Source")) {
+				stats.script_source_bytes += source.len();
 			}
 
-			report.push_str("```\n");
-			report.push_str(&backtrace);
-			report.push_str("```");
-		} else {
-			report.push_str("Backtrace disabled, run Argon with `--backtrace` flag to enable");
+			if let Some(middleware) = tree.get_meta(id).and_then(get_middleware) {
+				*stats.middleware.entry(middleware).or_default() += 1;
+			}
 
-			argon_error!(
-				"{}: Run Argon with {} flag to show full backtrace\n",
-				"Backtrace".bold(),
-				"--backtrace".bold()
-			);
+			stack.extend(instance.children().iter().map(|child| (*child, depth + 1)));
 		}
 
-		let report_issue = logger::prompt(
-			"Would you like to create new issue on GitHub with current report?",
-			false,
+		let mut nodes = vec![&project.node];
+
+		while let Some(node) = nodes.pop() {
+			stats.project_nodes += 1;
+			nodes.extend(node.tree.values());
+		}
+
+		Some(stats)
+	}
+
+	/// Returns stats of the workspace registered with `track`, `None` if there
+	/// is none, its tree is locked (e.g. by the panicking thread) or it takes too long
+	pub fn current() -> Option<Self> {
+		guarded(|| {
+			let tracked = TRACKED.try_lock().ok()?;
+			let tracked = tracked.as_ref()?;
+
+			let tree = tracked.tree.upgrade()?;
+			let project = tracked.project.upgrade()?;
+
+			let tree = tree.try_lock().ok()?;
+			let project = project.try_lock().ok()?;
+
+			Self::collect(
+				&tree,
+				&project,
+				tracked.started.elapsed(),
+				Instant::now() + STATS_TIMEOUT,
+			)
+		})
+	}
+
+	fn to_markdown(&self) -> String {
+		let mut markdown = format!(
+			"Instances: {}\nMax depth: {}\nScript source bytes: {}\nProject nodes: {}\nUptime: {}s\nMiddleware:",
+			self.instances, self.max_depth, self.script_source_bytes, self.project_nodes, self.uptime
 		);
 
-		if report_issue {
-			let mut url = env!("CARGO_PKG_REPOSITORY").to_owned();
-			url.push_str(&format!("/issues/new?title=Argon crashed: {}&body=", message));
+		for (middleware, count) in &self.middleware {
+			markdown.push_str(&format!("\n- {}: {}", middleware, count));
+		}
+
+		markdown
+	}
+}
+
+/// Middleware of the file the instance was read from
+fn get_middleware(meta: &Meta) -> Option<String> {
+	if !matches!(meta.source.get(), SourceKind::Path(_)) {
+		return None;
+	}
+
+	let path = meta.source.relevant().iter().find_map(|entry| match entry {
+		SourceEntry::File(path) => Some(path),
+		_ => None,
+	})?;
+
+	meta.context
+		.sync_rules()
+		.find_map(|rule| rule.resolve(path).or_else(|| rule.resolve_child(path)))
+		.map(|rule| rule.middleware.to_string())
+}
+
+/// Registers the workspace whose stats are included in crash reports,
+/// the latest one wins, nothing is computed until the crash
+pub fn track(tree: &Arc<Mutex<Tree>>, project: &Arc<Mutex<Project>>) {
+	if let Ok(mut tracked) = TRACKED.lock() {
+		*tracked = Some(Tracked {
+			tree: Arc::downgrade(tree),
+			project: Arc::downgrade(project),
+			started: Instant::now(),
+		});
+	}
+}
+
+/// Runs `f` inside of the panic hook, returns `None` if it panics
+pub fn guarded<T>(f: impl FnOnce() -> Option<T>) -> Option<T> {
+	COLLECTING.with(|collecting| collecting.set(true));
+	let result = panic::catch_unwind(AssertUnwindSafe(f));
+	COLLECTING.with(|collecting| collecting.set(false));
+
+	result.ok().flatten()
+}
+
+/// Where the crash report ended up, see `deliver`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Delivery {
+	/// Report was accepted by the crash report endpoint
+	Sent,
+	/// Report was written to the local crash file
+	Saved(PathBuf),
+	/// User should be asked to open a GitHub issue
+	Prompt,
+	/// Report could not be delivered anywhere
+	Failed,
+}
+
+/// Sends the report to the endpoint if there is one, the local crash file is written
+/// when sending fails or when there is no one to prompt, otherwise the user is prompted.
+/// The local file contains the `local` report, see `hook` for its format
+pub fn deliver<F>(
+	report: &CrashReport,
+	local: &str,
+	url: Option<&str>,
+	crash_dir: Option<&Path>,
+	interactive: bool,
+	send: F,
+) -> Delivery
+where
+	F: FnOnce(&str, &str) -> Result<()>,
+{
+	if let Some(url) = url {
+		match send(url, &report.to_json()) {
+			Ok(()) => return Delivery::Sent,
+			Err(err) => error!("Failed to send crash report to {}: {}", url, err),
+		}
+	} else if interactive {
+		return Delivery::Prompt;
+	}
+
+	let Some(crash_dir) = crash_dir else {
+		error!("Failed to save crash report: crash directory is unknown");
+		return Delivery::Failed;
+	};
+
+	let path = crash_dir.join(format!("crash-{}.md", chrono::Utc::now().timestamp_millis()));
+
+	match fs::create_dir_all(crash_dir).and_then(|_| fs::write(&path, local)) {
+		Ok(()) => Delivery::Saved(path),
+		Err(err) => {
+			error!("Failed to save crash report: {}", err);
+			Delivery::Failed
+		}
+	}
+}
+
+/// Posts the report once with a short timeout. Runs on its own thread
+/// as the blocking client cannot be used inside of an async runtime
+pub fn send(url: &str, body: &str) -> Result<()> {
+	let (url, body) = (url.to_owned(), body.to_owned());
+
+	thread::spawn(move || -> Result<()> {
+		Client::builder()
+			.timeout(SEND_TIMEOUT)
+			.build()?
+			.post(url)
+			.header(CONTENT_TYPE, "application/json")
+			.body(body)
+			.send()?
+			.error_for_status()?;
+
+		Ok(())
+	})
+	.join()
+	.map_err(|_| anyhow!("Crash report thread stopped unexpectedly"))?
+}
+
+fn format_backtrace(mut backtrace: Backtrace, privacy: Privacy) -> String {
+	let mut formatted = match privacy {
+		Privacy::Full => {
+			backtrace.resolve();
+			format!("{:?}", backtrace)
+		}
+		Privacy::Anonymous => backtrace
+			.frames()
+			.iter()
+			.enumerate()
+			.map(|(index, frame)| format!("{:>4}: {:?}", index, frame.ip()))
+			.collect::<Vec<_>>()
+			.join("\n"),
+	};
+
+	if formatted.len() > MAX_BACKTRACE_LEN {
+		let mut end = MAX_BACKTRACE_LEN;
+
+		while !formatted.is_char_boundary(end) {
+			end -= 1;
+		}
+
+		formatted.truncate(end);
+		formatted.push_str("\n...");
+	}
+
+	formatted
+}
+
+pub fn hook() {
+	panic::set_hook(Box::new(|panic_info| {
+		// Panics of `guarded` are caught and not reported
+		if COLLECTING.with(|collecting| collecting.get()) {
+			return;
+		}
+
+		// Panicking inside of the hook would abort without any report
+		let result = panic::catch_unwind(AssertUnwindSafe(|| {
+			let message = get_panic_info_message(panic_info).unwrap_or("Failed to get panic info message");
+			let backtrace_enabled = util::env_backtrace();
+			let verbosity = util::env_verbosity();
+
+			let (url, privacy, include_stats) = match Config::try_new() {
+				Some(config) => (
+					Some(config.crash_report_url.clone()).filter(|url| !url.is_empty()),
+					Privacy::from_setting(&config.crash_report_privacy),
+					config.crash_report_include_stats,
+				),
+				None => (None, Privacy::default(), false),
+			};
 
-			#[cfg(not(target_os = "windows"))]
-			url.push_str(&report);
+			let stats = if include_stats { WorkspaceStats::current() } else { None };
 
-			#[cfg(target_os = "windows")]
-			url.push_str(&report.replace('\n', "%0A").replace('\t', "%09"));
+			let mut report = String::from("> This crash report was automatically generated by Argon");
+			report.push_str("\n\nDetails:\n--------\n");
+			report.push_str("*Provide extra details about your problem or what happened here*");
 
-			match open::that(url) {
-				Err(err) => error!("Failed to launch system browser: {}", err),
-				Ok(()) => trace!("Browser launched successfully"),
+			argon_error!("{}", t!("crash.crashed").bold());
+
+			report.push_str("\n\nMessage:\n----------\n");
+			report.push_str(message);
+
+			argon_error!("{}: {}", t!("crash.message").bold(), message);
+
+			report.push_str("\n\nLocation:\n----------\n");
+
+			if let Some(location) = panic_info.location() {
+				report.push_str(location.file());
+				report.push_str(": ");
+				report.push_str(&location.line().to_string());
+
+				argon_error!(
+					"{}: {}: {}",
+					t!("crash.location").bold(),
+					location.file(),
+					location.line()
+				);
+			} else {
+				report.push_str("Failed to get panic info location");
 			}
+
+			report.push_str("\n\nVerbose output:\n----------------\n");
+
+			if verbosity != LevelFilter::Trace {
+				report.push_str("Verbose output disabled, run Argon with `-vvvv` flag to enable");
+
+				argon_error!(
+					"{}: {}",
+					t!("crash.verbosity").bold(),
+					t!("crash.verbosity_hint", flag = "-vvvv".bold())
+				);
+			} else {
+				report.push_str("```\nPast your verbose output here\n```");
+			}
+
+			report.push_str("\n\nBacktrace:\n-----------\n");
+
+			if backtrace_enabled {
+				let backtrace = Backtrace::new();
+
+				argon_error!("{}:\n{:?}", t!("crash.backtrace").bold(), backtrace);
+
+				// Temporary solution for broken OsString parser
+				let mut backtrace = format!("{:?}", backtrace);
+				backtrace = backtrace.replace("             ", "\t\t");
+				backtrace = backtrace.replace("    ", "\t");
+				backtrace = backtrace.replace("   ", "");
+				backtrace = backtrace.replace("  ", "");
+				backtrace = backtrace.replace('&', "ptr");
+
+				if backtrace.len() > MAX_BACKTRACE_LEN {
+					backtrace.drain(..MAX_BACKTRACE_LEN);
+					backtrace.push_str("\n...\n");
+				}
+
+				report.push_str("```\n");
+				report.push_str(&backtrace);
+				report.push_str("```");
+			} else {
+				report.push_str("Backtrace disabled, run Argon with `--backtrace` flag to enable");
+
+				argon_error!(
+					"{}: {}\n",
+					t!("crash.backtrace").bold(),
+					t!("crash.backtrace_hint", flag = "--backtrace".bold())
+				);
+			}
+
+			report.push_str("\n\nWorkspace stats:\n----------------\n");
+
+			if let Some(stats) = &stats {
+				report.push_str("Included as `crash_report_include_stats` setting is enabled, ");
+				report.push_str("these are the only details, no names, paths or source content are included\n\n");
+				report.push_str(&stats.to_markdown());
+			} else if include_stats {
+				report.push_str("Workspace stats could not be collected");
+			} else {
+				report
+					.push_str("Workspace stats disabled, enable `crash_report_include_stats` setting to include them");
+			}
+
+			let crash_report = CrashReport::new(
+				message,
+				panic_info
+					.location()
+					.map(|location| format!("{}: {}", location.file(), location.line())),
+				url.as_ref().map(|_| Backtrace::new_unresolved()),
+				&env::current_dir().unwrap_or_default(),
+				privacy,
+			)
+			.with_stats(stats);
+
+			// There is no one to prompt when running as a daemon,
+			// so the report is saved next to the daemon log instead
+			let daemon_dir = util::env_daemon();
+			let interactive = daemon_dir.is_none() && io::stderr().is_terminal();
+			let crash_dir = daemon_dir.or_else(|| util::get_argon_dir().ok().map(|dir| dir.join("crashes")));
+
+			match deliver(
+				&crash_report,
+				&report,
+				url.as_deref(),
+				crash_dir.as_deref(),
+				interactive,
+				send,
+			) {
+				Delivery::Sent => argon_error!("{}", t!("crash.report_sent", url = url.unwrap_or_default())),
+				Delivery::Saved(path) => argon_error!("{}", t!("crash.report_saved", path = path.display())),
+				Delivery::Failed => {}
+				Delivery::Prompt => {
+					let report_issue = logger::prompt(&t!("crash.report_issue"), false);
+
+					if report_issue {
+						let mut url = env!("CARGO_PKG_REPOSITORY").to_owned();
+						url.push_str(&format!("/issues/new?title=Argon crashed: {}&body=", message));
+
+						#[cfg(not(target_os = "windows"))]
+						url.push_str(&report);
+
+						#[cfg(target_os = "windows")]
+						url.push_str(&report.replace('\n', "%0A").replace('\t', "%09"));
+
+						match open::that(url) {
+							Err(err) => error!("Failed to launch system browser: {}", err),
+							Ok(()) => trace!("Browser launched successfully"),
+						}
+					}
+				}
+			}
+		}));
+
+		if result.is_err() {
+			eprintln!("Argon has crashed and failed to create a crash report");
 		}
 
 		process::exit(1)
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use std::{
+	fs::{self, File, OpenOptions},
+	io::{Read, Seek, SeekFrom},
+	path::{Path, PathBuf},
+};
+
+/// Returns directory where daemon logs and crash reports are stored
+pub fn get_log_dir(workspace_dir: &Path) -> PathBuf {
+	workspace_dir.join(".argon").join("logs")
+}
+
+pub fn get_log_path(workspace_dir: &Path) -> PathBuf {
+	get_log_dir(workspace_dir).join("daemon.log")
+}
+
+/// Opens daemon log file for appending, creating it if necessary
+pub fn open_log(workspace_dir: &Path) -> Result<File> {
+	fs::create_dir_all(get_log_dir(workspace_dir))?;
+
+	let file = OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(get_log_path(workspace_dir))?;
+
+	Ok(file)
+}
+
+/// Returns last `count` lines of the text
+pub fn last_lines(text: &str, count: usize) -> Vec<&str> {
+	let lines: Vec<&str> = text.lines().collect();
+	lines[lines.len().saturating_sub(count)..].to_vec()
+}
+
+/// Reads everything that was appended to the file since `offset`,
+/// starts from the beginning if the file was truncated in the meantime
+pub fn read_from(path: &Path, offset: u64) -> Result<(String, u64)> {
+	let mut file = File::open(path)?;
+	let len = file.metadata()?.len();
+
+	let offset = if len < offset { 0 } else { offset };
+
+	file.seek(SeekFrom::Start(offset))?;
+
+	let mut buffer = Vec::new();
+	file.read_to_end(&mut buffer)?;
+
+	Ok((
+		String::from_utf8_lossy(&buffer).into_owned(),
+		offset + buffer.len() as u64,
+	))
+}
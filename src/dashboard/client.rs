@@ -0,0 +1,279 @@
+use anyhow::{bail, Result};
+use crossbeam_channel::Sender;
+use glob::Pattern;
+use reqwest::{
+	blocking::{Client, Response},
+	StatusCode,
+};
+use serde::de::DeserializeOwned;
+use std::{
+	path::PathBuf,
+	thread,
+	time::{Duration, Instant},
+};
+
+use super::{FeedEvent, FeedPage};
+use crate::{
+	core::{find::FindPage, pending::PendingInstance, queue},
+	server::{limits::LimitStats, Health},
+};
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+const MIN_RETRY: Duration = Duration::from_millis(500);
+const MAX_RETRY: Duration = Duration::from_secs(10);
+
+/// Result of a single request, endpoints added in newer versions
+/// are `Missing` on older servers instead of failing the whole poll
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fetched<T> {
+	Ok(T),
+	Missing,
+	Failed(String),
+}
+
+impl<T> Fetched<T> {
+	pub fn ok(self) -> Option<T> {
+		match self {
+			Fetched::Ok(value) => Some(value),
+			_ => None,
+		}
+	}
+}
+
+/// Everything fetched from the session during one poll
+#[derive(Debug, Clone)]
+pub struct Update {
+	pub health: Fetched<Health>,
+	pub clients: Fetched<Vec<queue::Client>>,
+	pub pending: Fetched<Vec<PendingInstance>>,
+	pub stats: Fetched<LimitStats>,
+	pub events: Fetched<FeedPage>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+	Update(Box<Update>),
+	/// Session is unreachable, the next attempt is made after `retry_in`
+	Disconnected {
+		error: String,
+		retry_in: Duration,
+	},
+}
+
+/// Read-only HTTP client of a running session
+#[derive(Debug, Clone)]
+pub struct SessionClient {
+	address: String,
+	client: Client,
+}
+
+impl SessionClient {
+	pub fn new(address: &str) -> Self {
+		Self {
+			address: address.trim_end_matches('/').to_owned(),
+			client: Client::new(),
+		}
+	}
+
+	pub fn address(&self) -> &str {
+		&self.address
+	}
+
+	pub fn health(&self) -> Fetched<Health> {
+		self.get_json("/health", &[])
+	}
+
+	pub fn clients(&self) -> Fetched<Vec<queue::Client>> {
+		match self.get("/clients", &[]) {
+			Fetched::Ok(response) => match response.bytes() {
+				Ok(bytes) => match rmp_serde::from_slice(&bytes) {
+					Ok(clients) => Fetched::Ok(clients),
+					Err(err) => Fetched::Failed(err.to_string()),
+				},
+				Err(err) => Fetched::Failed(err.to_string()),
+			},
+			Fetched::Missing => Fetched::Missing,
+			Fetched::Failed(err) => Fetched::Failed(err),
+		}
+	}
+
+	pub fn pending(&self) -> Fetched<Vec<PendingInstance>> {
+		self.get_json("/pending", &[])
+	}
+
+	pub fn stats(&self) -> Fetched<LimitStats> {
+		self.get_json("/stats", &[])
+	}
+
+	pub fn events(&self, since: Option<u64>) -> Fetched<FeedPage> {
+		match since {
+			Some(since) => self.get_json("/events", &[("since", since.to_string())]),
+			None => self.get_json("/events", &[]),
+		}
+	}
+
+	/// Fetches everything at once, fails only if the session is unreachable
+	pub fn poll(&self, since: Option<u64>) -> Result<Update> {
+		let health = self.health();
+
+		if let Fetched::Failed(err) = &health {
+			bail!("{}", err);
+		}
+
+		Ok(Update {
+			health,
+			clients: self.clients(),
+			pending: self.pending(),
+			stats: self.stats(),
+			events: self.events(since),
+		})
+	}
+
+	/// Returns files the instance of the event is synced from,
+	/// looked up by its stable ID if the server reports one
+	pub fn find_files(&self, event: &FeedEvent) -> Result<Vec<PathBuf>> {
+		let query = if let Some(stable_id) = &event.stable_id {
+			("id", stable_id.clone())
+		} else if let Some(path) = &event.path {
+			("path_glob", Pattern::escape(&path.replace('.', "/")))
+		} else {
+			return Ok(vec![]);
+		};
+
+		match self.get_json::<FindPage>("/find", &[query]) {
+			Fetched::Ok(page) => Ok(page.instances.into_iter().flat_map(|instance| instance.files).collect()),
+			Fetched::Missing => bail!("Session does not support looking up instance files"),
+			Fetched::Failed(err) => bail!("{}", err),
+		}
+	}
+
+	fn get(&self, endpoint: &str, query: &[(&str, String)]) -> Fetched<Response> {
+		let response = self
+			.client
+			.get(format!("{}{}", self.address, endpoint))
+			.query(query)
+			.timeout(TIMEOUT)
+			.send();
+
+		match response {
+			Ok(response) if response.status() == StatusCode::NOT_FOUND => Fetched::Missing,
+			Ok(response) => match response.error_for_status() {
+				Ok(response) => Fetched::Ok(response),
+				Err(err) => Fetched::Failed(err.to_string()),
+			},
+			Err(err) => Fetched::Failed(err.to_string()),
+		}
+	}
+
+	fn get_json<T: DeserializeOwned>(&self, endpoint: &str, query: &[(&str, String)]) -> Fetched<T> {
+		match self.get(endpoint, query) {
+			Fetched::Ok(response) => match response.json() {
+				Ok(value) => Fetched::Ok(value),
+				Err(err) => Fetched::Failed(err.to_string()),
+			},
+			Fetched::Missing => Fetched::Missing,
+			Fetched::Failed(err) => Fetched::Failed(err),
+		}
+	}
+}
+
+/// Exponential delay between reconnect attempts
+#[derive(Debug, Clone)]
+pub struct Backoff {
+	failures: u32,
+	next: Instant,
+}
+
+impl Backoff {
+	pub fn new() -> Self {
+		Self {
+			failures: 0,
+			next: Instant::now(),
+		}
+	}
+
+	pub fn failures(&self) -> u32 {
+		self.failures
+	}
+
+	pub fn is_due(&self, now: Instant) -> bool {
+		now >= self.next
+	}
+
+	/// Records failed attempt and returns delay before the next one
+	pub fn failure(&mut self, now: Instant) -> Duration {
+		let delay = MIN_RETRY
+			.saturating_mul(2u32.saturating_pow(self.failures))
+			.min(MAX_RETRY);
+
+		self.failures += 1;
+		self.next = now + delay;
+
+		delay
+	}
+
+	pub fn success(&mut self) {
+		self.failures = 0;
+	}
+}
+
+impl Default for Backoff {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Polls the session and keeps track of the last seen event,
+/// reconnecting with backoff while the session is unreachable
+#[derive(Debug, Clone)]
+pub struct Poller {
+	client: SessionClient,
+	backoff: Backoff,
+	since: Option<u64>,
+}
+
+impl Poller {
+	pub fn new(client: SessionClient) -> Self {
+		Self {
+			client,
+			backoff: Backoff::new(),
+			since: None,
+		}
+	}
+
+	/// Returns `None` if the next reconnect attempt is not due yet
+	pub fn tick(&mut self, now: Instant) -> Option<Message> {
+		if !self.backoff.is_due(now) {
+			return None;
+		}
+
+		match self.client.poll(self.since) {
+			Ok(update) => {
+				self.backoff.success();
+
+				if let Fetched::Ok(page) = &update.events {
+					self.since = Some(page.latest);
+				}
+
+				Some(Message::Update(Box::new(update)))
+			}
+			Err(err) => Some(Message::Disconnected {
+				error: err.to_string(),
+				retry_in: self.backoff.failure(now),
+			}),
+		}
+	}
+
+	/// Polls the session every `interval` until the receiver is dropped
+	pub fn spawn(mut self, interval: Duration, sender: Sender<Message>) {
+		thread::spawn(move || loop {
+			if let Some(message) = self.tick(Instant::now()) {
+				if sender.send(message).is_err() {
+					break;
+				}
+			}
+
+			thread::sleep(interval);
+		});
+	}
+}
@@ -0,0 +1,296 @@
+use serde::Deserialize;
+use std::{collections::VecDeque, time::Duration};
+
+use crate::{
+	core::{pending::PendingInstance, queue},
+	server::{limits::LimitStats, Health},
+};
+use client::{Fetched, Message, Update};
+
+pub mod client;
+pub mod render;
+
+/// Number of change events kept in the feed
+const MAX_EVENTS: usize = 500;
+/// Number of diagnostics and error logs kept
+const MAX_DIAGNOSTICS: usize = 100;
+/// Number of polls shown in throughput sparklines
+const MAX_SAMPLES: usize = 120;
+
+/// Event received from `/events`, fields of unknown event
+/// types are ignored so newer servers can still be displayed
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedEvent {
+	pub seq: u64,
+	#[serde(rename = "type")]
+	pub kind: String,
+	/// Removed instances are only described by their ID
+	pub id: Option<String>,
+	pub path: Option<String>,
+	pub class: Option<String>,
+	pub stable_id: Option<String>,
+	#[serde(default)]
+	pub changed: Vec<String>,
+	pub level: Option<String>,
+	pub message: Option<String>,
+}
+
+impl FeedEvent {
+	pub fn is_change(&self) -> bool {
+		self.kind.starts_with("instance")
+	}
+
+	/// Diagnostics and client logs that are warnings or errors
+	pub fn is_diagnostic(&self) -> bool {
+		match self.kind.as_str() {
+			"diagnostic" => true,
+			"log" => matches!(self.level.as_deref(), Some("warn" | "warning" | "error")),
+			_ => false,
+		}
+	}
+
+	/// Whether path or class of the event contains the filter, ignoring case
+	pub fn matches(&self, filter: &str) -> bool {
+		if filter.is_empty() {
+			return true;
+		}
+
+		let filter = filter.to_lowercase();
+
+		[&self.path, &self.class]
+			.into_iter()
+			.flatten()
+			.any(|value| value.to_lowercase().contains(&filter))
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FeedPage {
+	pub events: Vec<FeedEvent>,
+	pub latest: u64,
+	pub resync: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Connection {
+	Connecting,
+	Connected,
+	Disconnected { error: String, retry_in: Duration },
+}
+
+/// Read-only state of a single session, built from messages of its `Poller`
+#[derive(Debug, Clone)]
+pub struct Dashboard {
+	pub id: String,
+	pub address: String,
+	pub connection: Connection,
+	pub health: Option<Health>,
+	/// `None` until fetched or if not supported by the server, same as below
+	pub clients: Option<Vec<queue::Client>>,
+	pub pending: Option<Vec<PendingInstance>>,
+	pub stats: Option<LimitStats>,
+	/// Endpoints the server does not have, e.g. `/events` on older versions
+	pub missing: Vec<&'static str>,
+	/// Number of times events were missed and the feed is incomplete
+	pub resyncs: u64,
+	events: VecDeque<FeedEvent>,
+	diagnostics: VecDeque<FeedEvent>,
+	changes_per_poll: VecDeque<u64>,
+	requests_per_poll: VecDeque<u64>,
+	last_requests: Option<u64>,
+	last_seq: Option<u64>,
+	/// Number of change events received since the start
+	received: usize,
+	filter: String,
+	/// Number of change events visible while the feed is paused
+	paused: Option<usize>,
+	selected: usize,
+}
+
+impl Dashboard {
+	pub fn new(id: &str, address: &str) -> Self {
+		Self {
+			id: id.to_owned(),
+			address: address.to_owned(),
+			connection: Connection::Connecting,
+			health: None,
+			clients: None,
+			pending: None,
+			stats: None,
+			missing: vec![],
+			resyncs: 0,
+			events: VecDeque::new(),
+			diagnostics: VecDeque::new(),
+			changes_per_poll: VecDeque::new(),
+			requests_per_poll: VecDeque::new(),
+			last_requests: None,
+			last_seq: None,
+			received: 0,
+			filter: String::new(),
+			paused: None,
+			selected: 0,
+		}
+	}
+
+	pub fn apply(&mut self, message: Message) {
+		match message {
+			Message::Update(update) => self.apply_update(*update),
+			Message::Disconnected { error, retry_in } => {
+				self.connection = Connection::Disconnected { error, retry_in };
+			}
+		}
+	}
+
+	fn apply_update(&mut self, update: Update) {
+		self.connection = Connection::Connected;
+		self.missing.clear();
+
+		// Limiter only counts rejected and delayed requests,
+		// their growth is the closest thing to throughput it reports
+		if let Fetched::Ok(stats) = &update.stats {
+			let total = stats.rate_limited + stats.payload_too_large + stats.queued;
+			let delta = self.last_requests.map_or(0, |last| total.saturating_sub(last));
+
+			push_capped(&mut self.requests_per_poll, delta, MAX_SAMPLES);
+			self.last_requests = Some(total);
+		}
+
+		let previous = self.health.take();
+		self.health = self.merge("/health", update.health, previous);
+
+		let previous = self.clients.take();
+		self.clients = self.merge("/clients", update.clients, previous);
+
+		let previous = self.pending.take();
+		self.pending = self.merge("/pending", update.pending, previous);
+
+		let previous = self.stats.take();
+		self.stats = self.merge("/stats", update.stats, previous);
+
+		let changes = match self.merge("/events", update.events, None) {
+			Some(page) => self.merge_events(page),
+			None => 0,
+		};
+
+		push_capped(&mut self.changes_per_poll, changes, MAX_SAMPLES);
+	}
+
+	/// Keeps the previous value if the request failed
+	/// and drops it if the server does not have the endpoint
+	fn merge<T>(&mut self, endpoint: &'static str, fetched: Fetched<T>, previous: Option<T>) -> Option<T> {
+		match fetched {
+			Fetched::Ok(value) => Some(value),
+			Fetched::Missing => {
+				self.missing.push(endpoint);
+				None
+			}
+			Fetched::Failed(_) => previous,
+		}
+	}
+
+	/// Appends new events to the feed, returns the number of changes
+	fn merge_events(&mut self, page: FeedPage) -> u64 {
+		if page.resync {
+			self.resyncs += 1;
+		}
+
+		let mut changes = 0;
+
+		for event in page.events {
+			// Server restarts start counting from the beginning
+			if !page.resync && self.last_seq.is_some_and(|last_seq| event.seq <= last_seq) {
+				continue;
+			}
+
+			if event.is_diagnostic() {
+				push_capped(&mut self.diagnostics, event, MAX_DIAGNOSTICS);
+			} else if event.is_change() {
+				changes += 1;
+				self.received += 1;
+				push_capped(&mut self.events, event, MAX_EVENTS);
+			}
+		}
+
+		self.last_seq = Some(page.latest);
+
+		changes
+	}
+
+	pub fn is_missing(&self, endpoint: &str) -> bool {
+		self.missing.contains(&endpoint)
+	}
+
+	/// Change events matching the filter, newest first,
+	/// ones received after pausing the feed are hidden
+	pub fn events(&self) -> Vec<&FeedEvent> {
+		let first = self.received - self.events.len();
+
+		self.events
+			.iter()
+			.enumerate()
+			.rev()
+			.filter(|(index, _)| self.paused.is_none_or(|paused| first + index < paused))
+			.map(|(_, event)| event)
+			.filter(|event| event.matches(&self.filter))
+			.collect()
+	}
+
+	/// Diagnostics, newest first
+	pub fn diagnostics(&self) -> impl Iterator<Item = &FeedEvent> {
+		self.diagnostics.iter().rev()
+	}
+
+	pub fn changes_per_poll(&self) -> Vec<u64> {
+		self.changes_per_poll.iter().copied().collect()
+	}
+
+	pub fn requests_per_poll(&self) -> Vec<u64> {
+		self.requests_per_poll.iter().copied().collect()
+	}
+
+	pub fn filter(&self) -> &str {
+		&self.filter
+	}
+
+	pub fn set_filter(&mut self, filter: &str) {
+		self.filter = filter.to_owned();
+		self.selected = 0;
+	}
+
+	pub fn is_paused(&self) -> bool {
+		self.paused.is_some()
+	}
+
+	pub fn toggle_pause(&mut self) {
+		self.paused = match self.paused {
+			Some(_) => None,
+			None => Some(self.received),
+		};
+	}
+
+	pub fn selected_index(&self) -> usize {
+		self.selected
+	}
+
+	pub fn selected(&self) -> Option<&FeedEvent> {
+		self.events().get(self.selected).copied()
+	}
+
+	pub fn select_next(&mut self) {
+		let len = self.events().len();
+		self.selected = (self.selected + 1).min(len.saturating_sub(1));
+	}
+
+	pub fn select_previous(&mut self) {
+		self.selected = self.selected.saturating_sub(1);
+	}
+}
+
+fn push_capped<T>(queue: &mut VecDeque<T>, value: T, capacity: usize) {
+	queue.push_back(value);
+
+	while queue.len() > capacity {
+		queue.pop_front();
+	}
+}
@@ -0,0 +1,283 @@
+use ratatui::{
+	layout::{Constraint, Layout, Rect},
+	style::{Color, Modifier, Style},
+	text::{Line, Span},
+	widgets::{Block, List, ListItem, ListState, Paragraph, Sparkline},
+	Frame,
+};
+
+use super::{Connection, Dashboard, FeedEvent};
+use crate::core::pending;
+
+const HELP: &str = "q quit  tab session  ↑↓ select  / filter  space pause  enter open";
+
+/// What is shown on top of the dashboards, e.g. the filter being typed
+#[derive(Debug, Clone, Default)]
+pub struct Overlay<'a> {
+	pub input: Option<&'a str>,
+	pub notice: Option<&'a str>,
+}
+
+pub fn draw(frame: &mut Frame, dashboards: &[Dashboard], current: usize, overlay: Overlay) {
+	let dashboard = &dashboards[current];
+
+	let [header, body, footer] =
+		Layout::vertical([Constraint::Length(3), Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+
+	let [left, right] = Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)]).areas(body);
+
+	let [clients, pending, throughput] = Layout::vertical([
+		Constraint::Percentage(35),
+		Constraint::Percentage(30),
+		Constraint::Min(6),
+	])
+	.areas(left);
+
+	let [feed, diagnostics] = Layout::vertical([Constraint::Percentage(70), Constraint::Percentage(30)]).areas(right);
+
+	draw_header(frame, header, dashboards, current);
+	draw_clients(frame, clients, dashboard);
+	draw_pending(frame, pending, dashboard);
+	draw_throughput(frame, throughput, dashboard);
+	draw_feed(frame, feed, dashboard);
+	draw_diagnostics(frame, diagnostics, dashboard);
+
+	let footer_text = match (overlay.input, overlay.notice) {
+		(Some(input), _) => format!("/{}", input),
+		(None, Some(notice)) => notice.to_owned(),
+		(None, None) => HELP.to_owned(),
+	};
+
+	frame.render_widget(Paragraph::new(footer_text).style(dim()), footer);
+}
+
+fn draw_header(frame: &mut Frame, area: Rect, dashboards: &[Dashboard], current: usize) {
+	let dashboard = &dashboards[current];
+
+	let mut tabs = vec![];
+
+	for (index, dashboard) in dashboards.iter().enumerate() {
+		let style = if index == current {
+			Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD)
+		} else {
+			Style::default()
+		};
+
+		tabs.push(Span::styled(format!(" {} ", dashboard.id), style));
+		tabs.push(Span::raw(" "));
+	}
+
+	let status = match &dashboard.connection {
+		Connection::Connecting => Span::styled("Connecting..", Style::default().fg(Color::Yellow)),
+		Connection::Connected => Span::styled("Connected", Style::default().fg(Color::Green)),
+		Connection::Disconnected { error, retry_in } => Span::styled(
+			format!("Disconnected ({}), retrying in {}s", error, retry_in.as_secs().max(1)),
+			Style::default().fg(Color::Red),
+		),
+	};
+
+	let mut details = vec![Span::raw(format!("{}  ", dashboard.address)), status];
+
+	if let Some(health) = &dashboard.health {
+		details.push(Span::raw(format!("  {} (Argon {})", health.project, health.version)));
+
+		if let Some(stale_config) = &health.stale_config {
+			details.push(Span::styled(
+				format!("  Stale project: {}", stale_config),
+				Style::default().fg(Color::Yellow),
+			));
+		}
+	}
+
+	let paragraph = Paragraph::new(vec![Line::from(tabs), Line::from(details)]).block(Block::bordered().title("Argon"));
+
+	frame.render_widget(paragraph, area);
+}
+
+fn draw_clients(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+	let block = Block::bordered().title("Clients");
+
+	let items: Vec<ListItem> = match &dashboard.clients {
+		_ if dashboard.is_missing("/clients") => return unsupported(frame, area, block),
+		None => return placeholder(frame, area, block, "Loading.."),
+		Some(clients) if clients.is_empty() => return placeholder(frame, area, block, "No clients connected"),
+		Some(clients) => clients
+			.iter()
+			.map(|client| {
+				let place = client
+					.place
+					.place_id
+					.map_or(String::new(), |place_id| format!(" place {}", place_id));
+
+				ListItem::new(format!(
+					"{} #{}{} v{}",
+					client.name, client.id, place, client.negotiated.protocol
+				))
+			})
+			.collect(),
+	};
+
+	frame.render_widget(List::new(items).block(block), area);
+}
+
+fn draw_pending(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+	let pending = match &dashboard.pending {
+		_ if dashboard.is_missing("/pending") => {
+			return unsupported(frame, area, Block::bordered().title("Pending"));
+		}
+		Some(pending) => pending,
+		None => return placeholder(frame, area, Block::bordered().title("Pending"), "Loading.."),
+	};
+
+	let block = Block::bordered().title(format!("Pending ({})", pending.len()));
+
+	if pending.is_empty() {
+		return placeholder(frame, area, block, "Nothing pending");
+	}
+
+	let items: Vec<ListItem> = pending
+		.iter()
+		.map(|instance| {
+			ListItem::new(format!(
+				"{:?} {} {}",
+				instance.kind,
+				instance.path.as_deref().unwrap_or("?"),
+				pending::format_age(instance.age)
+			))
+		})
+		.collect();
+
+	frame.render_widget(List::new(items).block(block), area);
+}
+
+fn draw_throughput(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+	let [changes, requests] = Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(area);
+
+	let data = dashboard.changes_per_poll();
+	let sparkline = Sparkline::default()
+		.block(Block::bordered().title(format!("Changes ({})", data.last().copied().unwrap_or_default())))
+		.style(Style::default().fg(Color::Cyan))
+		.data(latest(&data, changes));
+
+	frame.render_widget(sparkline, changes);
+
+	if dashboard.is_missing("/stats") {
+		return unsupported(frame, requests, Block::bordered().title("Limited requests"));
+	}
+
+	let data = dashboard.requests_per_poll();
+	let sparkline = Sparkline::default()
+		.block(Block::bordered().title(format!(
+			"Limited requests ({})",
+			data.last().copied().unwrap_or_default()
+		)))
+		.style(Style::default().fg(Color::Magenta))
+		.data(latest(&data, requests));
+
+	frame.render_widget(sparkline, requests);
+}
+
+fn draw_feed(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+	let mut title = String::from("Events");
+
+	if !dashboard.filter().is_empty() {
+		title.push_str(&format!(" [{}]", dashboard.filter()));
+	}
+
+	if dashboard.is_paused() {
+		title.push_str(" (paused)");
+	}
+
+	if dashboard.resyncs > 0 {
+		title.push_str(" (some events were missed)");
+	}
+
+	let block = Block::bordered().title(title);
+
+	if dashboard.is_missing("/events") {
+		return unsupported(frame, area, block);
+	}
+
+	let items: Vec<ListItem> = dashboard.events().into_iter().map(describe).collect();
+	let mut state = ListState::default().with_selected(Some(dashboard.selected_index()));
+
+	let list = List::new(items)
+		.block(block)
+		.highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+	frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_diagnostics(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+	let items: Vec<ListItem> = dashboard
+		.diagnostics()
+		.map(|event| {
+			let level = event.level.as_deref().unwrap_or("info");
+			let color = match level {
+				"error" => Color::Red,
+				"warn" | "warning" => Color::Yellow,
+				_ => Color::Reset,
+			};
+
+			ListItem::new(Line::from(vec![
+				Span::styled(format!("[{}] ", level), Style::default().fg(color)),
+				Span::raw(event.message.clone().unwrap_or_default()),
+			]))
+		})
+		.collect();
+
+	frame.render_widget(List::new(items).block(Block::bordered().title("Diagnostics")), area);
+}
+
+fn describe(event: &FeedEvent) -> ListItem<'static> {
+	let (symbol, color) = match event.kind.as_str() {
+		"instanceAdded" => ("+", Color::Green),
+		"instanceUpdated" => ("~", Color::Yellow),
+		"instanceRemoved" => ("-", Color::Red),
+		_ => ("?", Color::Reset),
+	};
+
+	let target = match (&event.path, &event.id) {
+		(Some(path), _) => path.clone(),
+		(None, Some(id)) => format!("<{}>", id),
+		(None, None) => String::new(),
+	};
+
+	let mut spans = vec![
+		Span::styled(format!("{} ", symbol), Style::default().fg(color)),
+		Span::raw(target),
+	];
+
+	if let Some(class) = &event.class {
+		spans.push(Span::styled(format!(" {}", class), dim()));
+	}
+
+	if !event.changed.is_empty() {
+		spans.push(Span::styled(format!(" {}", event.changed.join(", ")), dim()));
+	}
+
+	ListItem::new(Line::from(spans))
+}
+
+fn placeholder(frame: &mut Frame, area: Rect, block: Block, text: &str) {
+	frame.render_widget(Paragraph::new(text).style(dim()).block(block), area);
+}
+
+fn unsupported(frame: &mut Frame, area: Rect, block: Block) {
+	placeholder(
+		frame,
+		area,
+		block,
+		"Not supported by this server, update Argon to see it",
+	);
+}
+
+/// Samples that fit in the area, sparklines are drawn from the oldest one
+fn latest(data: &[u64], area: Rect) -> &[u64] {
+	let width = area.width.saturating_sub(2) as usize;
+	&data[data.len().saturating_sub(width)..]
+}
+
+fn dim() -> Style {
+	Style::default().fg(Color::DarkGray)
+}
@@ -0,0 +1,239 @@
+use anyhow::{bail, Result};
+use serde_json::{Map, Value};
+use std::env;
+
+use crate::core::meta::NodePath;
+
+/// Project property whose value came from `$defs`,
+/// used to bring back the reference when saving the project
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedRef {
+	/// JSON pointer of the property value
+	pub pointer: String,
+	pub original: Value,
+	pub resolved: Value,
+}
+
+struct Resolver {
+	defs: Map<String, Value>,
+	allow_env: bool,
+	/// Whether `${}` should be interpolated, disabled when there is nothing
+	/// to reference so existing string values are left untouched
+	interpolate: bool,
+}
+
+impl Resolver {
+	/// Returns value of the definition, e.g. `colors.primary` or `env:BUILD_NUMBER`
+	fn lookup(&self, name: &str, stack: &mut Vec<String>) -> Result<Value> {
+		if let Some(variable) = name.strip_prefix("env:") {
+			if !self.allow_env {
+				bail!(
+					"Environment variable reference `{}` is not allowed, set `allowEnvRefs` in the project to enable it",
+					name
+				);
+			}
+
+			return match env::var(variable) {
+				Ok(value) => Ok(Value::String(value)),
+				Err(_) => bail!("Environment variable `{}` is not set", variable),
+			};
+		}
+
+		if stack.iter().any(|entry| entry == name) {
+			bail!("Circular reference: {} -> {}", stack.join(" -> "), name);
+		}
+
+		let mut current: Option<&Value> = None;
+
+		for key in name.split('.') {
+			let next = match current {
+				Some(value) => value.get(key),
+				None => self.defs.get(key),
+			};
+
+			match next {
+				Some(value) => current = Some(value),
+				None => bail!("Undefined reference `{}`, missing key: `{}`", name, key),
+			}
+		}
+
+		let mut value = match current {
+			Some(value) => value.clone(),
+			None => bail!("Undefined reference `{}`", name),
+		};
+
+		stack.push(name.to_owned());
+		self.resolve(&mut value, stack)?;
+		stack.pop();
+
+		Ok(value)
+	}
+
+	/// Replaces all references in the value, returns whether anything changed
+	fn resolve(&self, value: &mut Value, stack: &mut Vec<String>) -> Result<bool> {
+		match value {
+			Value::Object(object) if object.len() == 1 && object.contains_key("$ref") => {
+				let name = match &object["$ref"] {
+					Value::String(name) => name.to_owned(),
+					_ => bail!("`$ref` has to be a string"),
+				};
+
+				*value = self.lookup(&name, stack)?;
+
+				Ok(true)
+			}
+			Value::String(string) if self.interpolate && string.contains("${") => {
+				*string = self.interpolate(string, stack)?;
+
+				Ok(true)
+			}
+			Value::Array(array) => {
+				let mut changed = false;
+
+				for value in array {
+					changed |= self.resolve(value, stack)?;
+				}
+
+				Ok(changed)
+			}
+			Value::Object(object) => {
+				let mut changed = false;
+
+				for value in object.values_mut() {
+					changed |= self.resolve(value, stack)?;
+				}
+
+				Ok(changed)
+			}
+			_ => Ok(false),
+		}
+	}
+
+	fn interpolate(&self, string: &str, stack: &mut Vec<String>) -> Result<String> {
+		let mut result = String::new();
+		let mut rest = string;
+
+		while let Some(start) = rest.find("${") {
+			let end = match rest[start..].find('}') {
+				Some(end) => start + end,
+				None => bail!("Unterminated reference in `{}`", string),
+			};
+
+			result.push_str(&rest[..start]);
+
+			match self.lookup(&rest[start + 2..end], stack)? {
+				Value::String(value) => result.push_str(&value),
+				Value::Number(value) => result.push_str(&value.to_string()),
+				Value::Bool(value) => result.push_str(&value.to_string()),
+				_ => bail!(
+					"Reference `{}` cannot be interpolated as it is not a string, number or boolean",
+					&rest[start + 2..end]
+				),
+			}
+
+			rest = &rest[end + 1..];
+		}
+
+		result.push_str(rest);
+
+		Ok(result)
+	}
+}
+
+/// Resolves `$ref` objects and `${}` interpolations
+/// in all properties and attributes of the project tree
+pub fn resolve(project: &mut Value) -> Result<Vec<ResolvedRef>> {
+	let defs = match project.get("$defs") {
+		Some(Value::Object(defs)) => defs.clone(),
+		Some(_) => bail!("`$defs` has to be an object"),
+		None => Map::new(),
+	};
+
+	let allow_env = project.get("allowEnvRefs").and_then(Value::as_bool).unwrap_or(false);
+
+	let resolver = Resolver {
+		interpolate: !defs.is_empty() || allow_env,
+		defs,
+		allow_env,
+	};
+
+	let mut refs = vec![];
+
+	if let Some(node) = project.get_mut("tree") {
+		walk(node, &NodePath::new(), "/tree", &resolver, &mut refs)?;
+	}
+
+	Ok(refs)
+}
+
+/// Puts back references whose values were not changed since the project was loaded
+pub fn restore(project: &mut Value, refs: &[ResolvedRef]) {
+	for entry in refs {
+		if let Some(value) = project.pointer_mut(&entry.pointer) {
+			if *value == entry.resolved {
+				value.clone_from(&entry.original);
+			}
+		}
+	}
+}
+
+fn walk(
+	node: &mut Value,
+	node_path: &NodePath,
+	pointer: &str,
+	resolver: &Resolver,
+	refs: &mut Vec<ResolvedRef>,
+) -> Result<()> {
+	let node = match node {
+		Value::Object(node) => node,
+		_ => return Ok(()),
+	};
+
+	for (key, value) in node.iter_mut() {
+		let pointer = format!("{}/{}", pointer, escape(key));
+
+		match key.as_str() {
+			"$properties" => {
+				if let Value::Object(properties) = value {
+					for (property, value) in properties.iter_mut() {
+						let pointer = format!("{}/{}", pointer, escape(property));
+						resolve_value(value, pointer, node_path, property, resolver, refs)?;
+					}
+				}
+			}
+			"$attributes" => resolve_value(value, pointer, node_path, key, resolver, refs)?,
+			key if !key.starts_with('$') => walk(value, &node_path.join(key), &pointer, resolver, refs)?,
+			_ => {}
+		}
+	}
+
+	Ok(())
+}
+
+fn resolve_value(
+	value: &mut Value,
+	pointer: String,
+	node_path: &NodePath,
+	property: &str,
+	resolver: &Resolver,
+	refs: &mut Vec<ResolvedRef>,
+) -> Result<()> {
+	let original = value.clone();
+
+	match resolver.resolve(value, &mut vec![]) {
+		Ok(true) => refs.push(ResolvedRef {
+			pointer,
+			original,
+			resolved: value.clone(),
+		}),
+		Ok(false) => {}
+		Err(err) => bail!("{} in property `{}` at {}", err, property, node_path),
+	}
+
+	Ok(())
+}
+
+/// Escapes JSON pointer token
+fn escape(key: &str) -> String {
+	key.replace('~', "~0").replace('/', "~1")
+}
@@ -1,3 +1,4 @@
+use anyhow::{Context, Result};
 use glob::{glob, Paths, Pattern, PatternError};
 use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 use std::{
@@ -11,36 +12,48 @@ pub struct Glob {
 }
 
 impl Glob {
+	/// Separators are always stored as `/`, so the same pattern
+	/// works on every platform and is saved back unchanged
 	pub fn new(pattern: &str) -> Result<Self, PatternError> {
-		#[cfg(not(target_os = "windows"))]
-		let pattern = pattern.replace('\\', "/");
+		Ok(Self {
+			pattern: Pattern::new(&pattern.replace('\\', "/"))?,
+		})
+	}
+
+	/// Creates glob matching `pattern` inside `dir`, the directory is matched
+	/// literally even if its path contains metacharacters like `[` or `*`
+	pub fn in_dir(dir: &Path, pattern: &str) -> Result<Self> {
+		let dir = dir
+			.to_str()
+			.with_context(|| format!("Path {} is not valid UTF-8", dir.display()))?;
 
-		#[cfg(target_os = "windows")]
-		let pattern = pattern.replace('/', "\\");
+		let pattern = format!("{}/{}", Self::escape(dir), pattern.replace('\\', "/"));
 
 		Ok(Self {
 			pattern: Pattern::new(&pattern)?,
 		})
 	}
 
-	pub fn from_path(path: &Path) -> Result<Self, PatternError> {
-		Self::new(path.to_str().unwrap_or_default())
+	/// Escapes metacharacters so the string is matched literally
+	pub fn escape(str: &str) -> String {
+		Pattern::escape(str)
 	}
 
 	pub fn matches(&self, str: &str) -> bool {
 		self.pattern.matches(str)
 	}
 
+	/// Paths that are not valid UTF-8 are matched lossily instead of never matching
 	pub fn matches_path(&self, path: &Path) -> bool {
-		self.pattern.matches_path(path)
+		self.pattern.matches(&path.to_string_lossy())
 	}
 
 	pub fn matches_path_with_dir(&self, path: &Path) -> bool {
-		let matches = self.pattern.matches_path(path);
+		let matches = self.matches_path(path);
 
 		if !matches && self.pattern.as_str().ends_with("/**") {
 			if let Ok(pattern) = Pattern::new(self.pattern.as_str().strip_suffix("/**").unwrap()) {
-				return pattern.matches_path(path);
+				return pattern.matches(&path.to_string_lossy());
 			} else {
 				return false;
 			}
@@ -0,0 +1,152 @@
+use anyhow::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+	fs::{self, OpenOptions},
+	io::Write,
+	path::{Path, PathBuf},
+	process,
+};
+
+use crate::{config::Config, constants::HISTORY_MAX_SIZE};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Operation {
+	Addition,
+	Update,
+	Removal,
+	External,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+	/// Unix timestamp in milliseconds
+	pub timestamp: i64,
+	/// PID of the Argon process that made the change
+	pub session: u32,
+	pub operation: Operation,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub instance: Option<String>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub files: Vec<PathBuf>,
+
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub client_id: Option<u32>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub client_name: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub game_id: Option<u64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub place_id: Option<u64>,
+}
+
+impl HistoryEntry {
+	pub fn new(operation: Operation) -> Self {
+		Self {
+			timestamp: chrono::Utc::now().timestamp_millis(),
+			session: process::id(),
+			operation,
+			instance: None,
+			files: vec![],
+			client_id: None,
+			client_name: None,
+			game_id: None,
+			place_id: None,
+		}
+	}
+
+	pub fn is_external(&self) -> bool {
+		self.operation == Operation::External
+	}
+
+	/// Whether the entry affected given file or instance
+	pub fn affects(&self, path: &Path, instance: &str) -> bool {
+		if self.files.iter().any(|file| file.starts_with(path)) {
+			return true;
+		}
+
+		if let Some(name) = &self.instance {
+			return name == instance || name.starts_with(&format!("{}.", instance));
+		}
+
+		false
+	}
+}
+
+fn get_history_path(workspace_dir: &Path) -> PathBuf {
+	workspace_dir.join(".argon").join("history.jsonl")
+}
+
+fn get_rotated_path(workspace_dir: &Path) -> PathBuf {
+	workspace_dir.join(".argon").join("history.1.jsonl")
+}
+
+/// Appends entries to the workspace history, does nothing if `history` setting is disabled
+pub fn record(workspace_dir: &Path, entries: &[HistoryEntry]) {
+	if !Config::new().history || entries.is_empty() {
+		return;
+	}
+
+	let result = || -> Result<()> {
+		let path = get_history_path(workspace_dir);
+
+		fs::create_dir_all(path.parent().unwrap())?;
+
+		if fs::metadata(&path).is_ok_and(|metadata| metadata.len() > HISTORY_MAX_SIZE) {
+			fs::rename(&path, get_rotated_path(workspace_dir))?;
+		}
+
+		let mut buffer = Vec::new();
+
+		for entry in entries {
+			serde_json::to_writer(&mut buffer, entry)?;
+			buffer.push(b'\n');
+		}
+
+		OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&path)?
+			.write_all(&buffer)?;
+
+		Ok(())
+	}();
+
+	if let Err(err) = result {
+		warn!("Failed to record change history: {}", err);
+	}
+}
+
+/// Returns all recorded entries, from the oldest to the newest
+pub fn read(workspace_dir: &Path) -> Result<Vec<HistoryEntry>> {
+	let mut entries = vec![];
+
+	for path in [get_rotated_path(workspace_dir), get_history_path(workspace_dir)] {
+		if !path.exists() {
+			continue;
+		}
+
+		for line in fs::read_to_string(&path)?.lines() {
+			if line.is_empty() {
+				continue;
+			}
+
+			match serde_json::from_str(line) {
+				Ok(entry) => entries.push(entry),
+				Err(err) => warn!("Skipping corrupted history entry in {}: {}", path.display(), err),
+			}
+		}
+	}
+
+	Ok(entries)
+}
+
+/// Returns recorded entries that affected given file or instance, from the oldest to the newest
+pub fn blame(workspace_dir: &Path, path: &Path, instance: &str) -> Result<Vec<HistoryEntry>> {
+	Ok(read(workspace_dir)?
+		.into_iter()
+		.filter(|entry| entry.affects(path, instance))
+		.collect())
+}
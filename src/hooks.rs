@@ -0,0 +1,292 @@
+use anyhow::{bail, Result};
+use colored::Colorize;
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::{
+	fmt::{self, Display, Formatter},
+	fs,
+	hash::{DefaultHasher, Hash, Hasher},
+	io::{Read, Write},
+	path::{Path, PathBuf},
+	process::{Command, Stdio},
+	thread::{self, JoinHandle},
+	time::{Duration, Instant},
+};
+
+use crate::{argon_warn, constants::HOOK_TIMEOUT, ext::ResultExt, glob::Glob, middleware::Middleware};
+
+/// Point of the sync at which hooks run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HookStage {
+	/// After a file is read, before its contents reach the middleware,
+	/// the file itself is never changed
+	PostSnapshot,
+	/// After syncback produces contents of a file, before they are written to disk
+	PreWrite,
+}
+
+impl Display for HookStage {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			HookStage::PostSnapshot => write!(f, "postsnapshot"),
+			HookStage::PreWrite => write!(f, "prewrite"),
+		}
+	}
+}
+
+/// Project `hooks` that preprocess files like images or audio as they are synced
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Hooks {
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub postsnapshot: Vec<Hook>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub prewrite: Vec<Hook>,
+
+	/// Directory commands run in, only files inside of it are processed
+	#[serde(skip)]
+	pub workspace_dir: PathBuf,
+}
+
+/// Command that gets file contents on stdin and prints the processed ones to stdout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hook {
+	/// Program followed by its arguments, programs given
+	/// as paths have to be inside of the workspace
+	pub command: Vec<String>,
+	/// Files the hook runs on relative to the workspace, all if `None`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub glob: Option<Glob>,
+	/// Middleware of the files the hook runs on, any if empty
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub middleware: Vec<Middleware>,
+	/// Whether a failure fails the file instead of syncing it unprocessed
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub required: Option<bool>,
+	/// Seconds after which the command is killed
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub timeout: Option<u64>,
+}
+
+impl Hooks {
+	pub fn with_workspace_dir(mut self, workspace_dir: &Path) -> Self {
+		workspace_dir.clone_into(&mut self.workspace_dir);
+		self
+	}
+
+	/// Whether any hook of the stage runs on the file
+	pub fn matches(&self, stage: HookStage, path: &Path, middleware: &Middleware) -> bool {
+		self.matching(stage, path, middleware).next().is_some()
+	}
+
+	/// Runs matching hooks of the stage one after another, failed hooks are skipped
+	/// with a warning unless they are required, then the whole run fails
+	pub fn run(&self, stage: HookStage, path: &Path, middleware: &Middleware, contents: Vec<u8>) -> Result<Vec<u8>> {
+		let mut contents = contents;
+
+		for hook in self.matching(stage, path, middleware) {
+			match hook.run(&contents, &self.workspace_dir) {
+				Ok(processed) => contents = processed,
+				Err(err) if hook.is_required() => {
+					return Err(err).with_desc(|| format!("Required {} hook {} failed", stage, hook));
+				}
+				Err(err) => {
+					argon_warn!(
+						"{} hook {} failed for {}, file is synced unprocessed: {}",
+						stage,
+						hook.to_string().bold(),
+						path.display().to_string().bold(),
+						err
+					);
+				}
+			}
+		}
+
+		Ok(contents)
+	}
+
+	fn matching<'a>(
+		&'a self,
+		stage: HookStage,
+		path: &'a Path,
+		middleware: &'a Middleware,
+	) -> impl Iterator<Item = &'a Hook> {
+		let hooks = match stage {
+			HookStage::PostSnapshot => &self.postsnapshot,
+			HookStage::PreWrite => &self.prewrite,
+		};
+
+		// Files outside of the workspace are never processed,
+		// project files are read together with files they include
+		let relative = path
+			.strip_prefix(&self.workspace_dir)
+			.ok()
+			.filter(|_| *middleware != Middleware::Project);
+
+		hooks.iter().filter(move |hook| {
+			relative.is_some_and(|relative| {
+				hook.glob.as_ref().is_none_or(|glob| glob.matches_path(relative))
+					&& (hook.middleware.is_empty() || hook.middleware.contains(middleware))
+			})
+		})
+	}
+}
+
+impl Hook {
+	pub fn is_required(&self) -> bool {
+		self.required.unwrap_or_default()
+	}
+
+	/// Returns cached output for the same command and contents or runs the command
+	fn run(&self, contents: &[u8], workspace_dir: &Path) -> Result<Vec<u8>> {
+		let cache = ContentCache::new(&workspace_dir.join(".argon").join("cache").join("hooks"));
+		let key = digest(&[self.command.join("\0").as_bytes(), contents]);
+
+		if let Some(output) = cache.get(&key) {
+			trace!("Using cached output of hook {}", self);
+			return Ok(output);
+		}
+
+		let mut command = Command::new(self.program(workspace_dir)?);
+		command.args(&self.command[1..]).current_dir(workspace_dir);
+
+		let timeout = self.timeout.map(Duration::from_secs).unwrap_or(HOOK_TIMEOUT);
+		let output = run_command(&mut command, contents, timeout)?;
+
+		if let Err(err) = cache.insert(&key, &output) {
+			trace!("Failed to cache output of hook {}: {}", self, err);
+		}
+
+		Ok(output)
+	}
+
+	/// Programs given by name are looked up in `PATH`,
+	/// the ones given as paths can't point outside of the workspace
+	fn program(&self, workspace_dir: &Path) -> Result<PathBuf> {
+		let Some(program) = self.command.first() else {
+			bail!("Hook command is empty");
+		};
+
+		let program = Path::new(program);
+
+		if program.components().count() == 1 && program.is_relative() {
+			return Ok(program.to_owned());
+		}
+
+		let resolved = workspace_dir
+			.join(program)
+			.canonicalize()
+			.with_desc(|| format!("Hook program {} does not exist", program.display()))?;
+
+		if !resolved.starts_with(workspace_dir.canonicalize()?) {
+			bail!("Hook program {} is outside of the workspace", program.display());
+		}
+
+		Ok(resolved)
+	}
+}
+
+impl Display for Hook {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.command.join(" "))
+	}
+}
+
+/// Content-addressed cache of processed files stored in a directory,
+/// so unchanged inputs are not processed again, not even after restart
+pub struct ContentCache {
+	dir: PathBuf,
+}
+
+impl ContentCache {
+	pub fn new(dir: &Path) -> Self {
+		Self { dir: dir.to_owned() }
+	}
+
+	pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+		fs::read(self.path(key)).ok()
+	}
+
+	pub fn contains(&self, key: &str) -> bool {
+		self.path(key).is_file()
+	}
+
+	pub fn insert(&self, key: &str, contents: &[u8]) -> Result<()> {
+		fs::create_dir_all(&self.dir)?;
+		fs::write(self.path(key), contents)?;
+
+		Ok(())
+	}
+
+	pub fn path(&self, key: &str) -> PathBuf {
+		self.dir.join(key)
+	}
+}
+
+/// Returns content hash of all `parts` together, used as a `ContentCache` key
+pub fn digest(parts: &[&[u8]]) -> String {
+	let mut hasher = DefaultHasher::new();
+	let mut size = 0;
+
+	for part in parts {
+		part.hash(&mut hasher);
+		size += part.len();
+	}
+
+	format!("{:016x}{:x}", hasher.finish(), size)
+}
+
+/// Runs the command with `input` on stdin and returns its stdout,
+/// the command is killed if it does not exit within `timeout`
+pub fn run_command(command: &mut Command, input: &[u8], timeout: Duration) -> Result<Vec<u8>> {
+	let mut child = command
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()?;
+
+	let mut stdin = child.stdin.take().unwrap();
+	let input = input.to_owned();
+
+	// Pipes are handled by separate threads, otherwise commands
+	// that write before reading all of the input would deadlock
+	let writer = thread::spawn(move || stdin.write_all(&input));
+	let stdout = read_pipe(child.stdout.take().unwrap());
+	let stderr = read_pipe(child.stderr.take().unwrap());
+
+	let start = Instant::now();
+
+	let status = loop {
+		if let Some(status) = child.try_wait()? {
+			break status;
+		}
+
+		if start.elapsed() > timeout {
+			child.kill().ok();
+			child.wait().ok();
+
+			bail!("Timed out after {} seconds", timeout.as_secs());
+		}
+
+		thread::sleep(Duration::from_millis(10));
+	};
+
+	// Commands don't have to read all of the input
+	writer.join().ok();
+
+	if !status.success() {
+		let stderr = stderr.join().unwrap_or_default();
+		bail!("Exited with {}: {}", status, String::from_utf8_lossy(&stderr).trim());
+	}
+
+	Ok(stdout.join().unwrap_or_default())
+}
+
+fn read_pipe(mut pipe: impl Read + Send + 'static) -> JoinHandle<Vec<u8>> {
+	thread::spawn(move || {
+		let mut contents = vec![];
+		pipe.read_to_end(&mut contents).ok();
+
+		contents
+	})
+}
@@ -0,0 +1,171 @@
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use std::{collections::HashMap, env, fmt::Display, sync::RwLock};
+
+/// Language used when the message is missing in the selected catalog
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+// Catalogs bundled with the executable, every
+// catalog has to be a flat JSON object of `id: message`
+const BUNDLED_CATALOGS: [(&str, &str); 2] = [
+	("en", include_str!("../assets/locales/en.json")),
+	("ja", include_str!("../assets/locales/ja.json")),
+];
+
+lazy_static! {
+	static ref CATALOGS: HashMap<&'static str, HashMap<String, String>> = BUNDLED_CATALOGS
+		.iter()
+		.filter_map(|(language, catalog)| match serde_json::from_str(catalog) {
+			Ok(catalog) => Some((*language, catalog)),
+			Err(err) => {
+				warn!("Failed to parse {} message catalog: {}", language, err);
+				None
+			}
+		})
+		.collect();
+	static ref LANGUAGE: RwLock<Option<String>> = RwLock::new(None);
+}
+
+/// Sets language of user-facing messages, `auto` detects it from the environment
+pub fn init(language: &str) {
+	let language = if language.eq_ignore_ascii_case("auto") {
+		detect()
+	} else {
+		normalize(language)
+	};
+
+	if !CATALOGS.contains_key(language.as_str()) {
+		warn!(
+			"Language {} is not available, using {} instead",
+			language, FALLBACK_LANGUAGE
+		);
+	} else {
+		debug!("Using {} language", language);
+	}
+
+	*LANGUAGE.write().unwrap() = Some(language);
+}
+
+/// Currently selected language, detected from the environment if not set
+pub fn language() -> String {
+	LANGUAGE.read().unwrap().clone().unwrap_or_else(detect)
+}
+
+/// Languages that have a bundled catalog
+pub fn languages() -> Vec<&'static str> {
+	let mut languages: Vec<_> = CATALOGS.keys().copied().collect();
+	languages.sort();
+	languages
+}
+
+pub fn catalog(language: &str) -> Option<&'static HashMap<String, String>> {
+	CATALOGS.get(language)
+}
+
+/// Returns message template in the selected language or the fallback one
+pub fn get(id: &str) -> Option<&'static str> {
+	let language = language();
+
+	catalog(&language)
+		.and_then(|catalog| catalog.get(id))
+		.or_else(|| catalog(FALLBACK_LANGUAGE).and_then(|catalog| catalog.get(id)))
+		.map(|message| message.as_str())
+}
+
+/// Returns formatted message, the id itself is returned if it does not exist in any catalog
+pub fn translate(id: &str, args: &[(&str, &dyn Display)]) -> String {
+	match get(id) {
+		Some(template) => format(template, args),
+		None => {
+			warn!("Message {} does not exist in any catalog", id);
+			id.to_owned()
+		}
+	}
+}
+
+/// Replaces named `{placeholders}` with their values, so arguments
+/// can appear in any order, `{{` and `}}` produce literal braces
+pub fn format(template: &str, args: &[(&str, &dyn Display)]) -> String {
+	let mut output = String::with_capacity(template.len());
+	let mut chars = template.chars().peekable();
+
+	while let Some(char) = chars.next() {
+		match char {
+			'{' if chars.peek() == Some(&'{') => {
+				chars.next();
+				output.push('{');
+			}
+			'}' if chars.peek() == Some(&'}') => {
+				chars.next();
+				output.push('}');
+			}
+			'{' => {
+				let name: String = chars.by_ref().take_while(|char| *char != '}').collect();
+
+				match args.iter().find(|(arg, _)| *arg == name) {
+					Some((_, value)) => output.push_str(&value.to_string()),
+					None => {
+						output.push('{');
+						output.push_str(&name);
+						output.push('}');
+					}
+				}
+			}
+			_ => output.push(char),
+		}
+	}
+
+	output
+}
+
+/// Names of all placeholders used in the message template
+pub fn placeholders(template: &str) -> Vec<String> {
+	let mut names = vec![];
+	let mut chars = template.chars().peekable();
+
+	while let Some(char) = chars.next() {
+		match char {
+			'{' if chars.peek() == Some(&'{') => {
+				chars.next();
+			}
+			'{' => names.push(chars.by_ref().take_while(|char| *char != '}').collect()),
+			_ => {}
+		}
+	}
+
+	names.sort();
+	names.dedup();
+	names
+}
+
+fn detect() -> String {
+	["LC_ALL", "LC_MESSAGES", "LANG"]
+		.iter()
+		.filter_map(|var| env::var(var).ok())
+		.find(|value| !value.is_empty())
+		.map(|value| normalize(&value))
+		.unwrap_or_else(|| FALLBACK_LANGUAGE.to_owned())
+}
+
+/// Turns locale like `ja_JP.UTF-8` into language code, `C` and `POSIX` mean English
+fn normalize(locale: &str) -> String {
+	let language = locale
+		.split(['_', '-', '.', '@'])
+		.next()
+		.unwrap_or_default()
+		.to_lowercase();
+
+	match language.as_str() {
+		"" | "c" | "posix" => FALLBACK_LANGUAGE.to_owned(),
+		_ => language,
+	}
+}
+
+/// Translates user-facing message with named arguments:
+/// `t!("serve.serving", address = address, project = name)`
+#[macro_export]
+macro_rules! t {
+	($id:literal $(, $name:ident = $value:expr)* $(,)?) => {
+		$crate::i18n::translate($id, &[$((stringify!($name), &$value as &dyn std::fmt::Display)),*])
+	};
+}
@@ -0,0 +1,360 @@
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use rbx_dom_weak::{
+	types::{Ref, Variant},
+	Ustr, WeakDom,
+};
+use std::{
+	collections::{BTreeMap, HashMap},
+	fs::{self, File},
+	io::BufReader,
+	path::{Path, PathBuf},
+};
+
+use crate::{
+	core::{
+		processor::write,
+		snapshot::{Snapshot, UpdatedSnapshot},
+		tree::Tree,
+		Core,
+	},
+	ext::PathExt,
+	project::{Project, ProjectNode, ProjectPath},
+	util,
+	vfs::Vfs,
+	Properties,
+};
+
+/// Directory that imported services are written to, relative to the workspace
+const SOURCE_DIR: &str = "src";
+
+pub struct ImportOptions {
+	/// Name of the generated project, existing projects keep theirs
+	pub project_name: String,
+	/// Services to import, all that are not empty if `None`
+	pub services: Option<Vec<String>>,
+}
+
+/// Outcome of the import, lossy parts are found by reading
+/// the written workspace again and comparing it with the place
+#[derive(Debug, Default)]
+pub struct ImportReport {
+	pub project_path: PathBuf,
+	/// Number of instances added to the workspace, including descendants
+	pub added: usize,
+	/// Number of instances already in the workspace whose properties changed
+	pub updated: usize,
+	/// Full names and classes of instances missing from the workspace, e.g. filtered ones
+	pub unrepresented: Vec<(String, String)>,
+	/// Number of values that could not be represented keyed by `Class.Property`
+	pub lossy_properties: BTreeMap<String, usize>,
+}
+
+/// Reads place or model file based on its extension
+pub fn read_place(path: &Path) -> Result<WeakDom> {
+	let reader =
+		BufReader::new(File::open(path).with_context(|| format!("Failed to open {}", path.to_string().bold()))?);
+
+	Ok(match path.get_ext() {
+		"rbxlx" | "rbxmx" => rbx_xml::from_reader_default(reader)?,
+		"rbxl" | "rbxm" => rbx_binary::from_reader(reader)?,
+		ext => bail!(
+			"Invalid file extension: {}. Only {}, {}, {}, {} extensions are allowed",
+			ext.bold(),
+			"rbxl".bold(),
+			"rbxlx".bold(),
+			"rbxm".bold(),
+			"rbxmx".bold(),
+		),
+	})
+}
+
+/// Returns names of services of the place that have any children
+pub fn get_services(dom: &WeakDom) -> Vec<String> {
+	dom.root()
+		.children()
+		.iter()
+		.filter_map(|id| dom.get_by_ref(*id))
+		.filter(|service| util::is_service(&service.class) && !service.children().is_empty())
+		.map(|service| service.name.clone())
+		.collect()
+}
+
+/// Writes services of the place to the workspace in `output` through the regular syncback,
+/// the project is generated with `$path` of every service unless it already exists. Importing
+/// into existing workspace updates instances with matching names and classes instead of
+/// adding them again, instances that are not in the place are kept
+pub fn import(dom: &WeakDom, output: &Path, options: &ImportOptions) -> Result<ImportReport> {
+	let project_path = output.join("default.project.json");
+
+	let services: Vec<Ref> = {
+		let names = options.services.clone().unwrap_or_else(|| get_services(dom));
+
+		dom.root()
+			.children()
+			.iter()
+			.copied()
+			.filter(|id| {
+				dom.get_by_ref(*id)
+					.is_some_and(|service| util::is_service(&service.class) && names.contains(&service.name))
+			})
+			.collect()
+	};
+
+	prepare_project(dom, &services, &project_path, &options.project_name)?;
+
+	let mut report = ImportReport {
+		project_path: project_path.clone(),
+		..ImportReport::default()
+	};
+
+	{
+		let core = Core::new(Project::load(&project_path)?, false)?;
+		let vfs = Vfs::new(false);
+		let mut tree = core.tree();
+
+		for service in &services {
+			let instance = dom.get_by_ref(*service).unwrap();
+
+			let Some(id) = tree.find_by_path(&instance.name) else {
+				report
+					.unrepresented
+					.push((instance.name.clone(), instance.class.to_string()));
+				continue;
+			};
+
+			apply(dom, *service, id, &mut tree, &vfs, &mut report)?;
+		}
+
+		write::finish_batch(&mut tree, &vfs)?;
+		tree.save_stable_ids()?;
+	}
+
+	// Fresh session shows how the workspace will look like when served
+	let core = Core::new(Project::load(&project_path)?, false)?;
+	let tree = core.tree();
+
+	for service in &services {
+		if let Some(id) = tree.find_by_path(&dom.get_by_ref(*service).unwrap().name) {
+			compare(dom, *service, id, &tree, &mut report);
+		}
+	}
+
+	Ok(report)
+}
+
+/// Creates the project file if needed and adds nodes of the imported services to it
+fn prepare_project(dom: &WeakDom, services: &[Ref], project_path: &Path, name: &str) -> Result<()> {
+	if !project_path.exists() {
+		fs::create_dir_all(project_path.get_parent())?;
+		fs::write(
+			project_path,
+			serde_json::to_string_pretty(&serde_json::json!({
+				"name": name,
+				"tree": { "$className": "DataModel" }
+			}))?,
+		)?;
+	}
+
+	let mut project = Project::load(project_path)?;
+
+	for service in services {
+		let service = dom.get_by_ref(*service).unwrap();
+
+		if project.node.tree.contains_key(&service.name) {
+			continue;
+		}
+
+		let path = PathBuf::from(SOURCE_DIR).join(&service.name);
+		fs::create_dir_all(project_path.get_parent().join(&path))?;
+
+		project.node.tree.insert(
+			service.name.clone(),
+			ProjectNode {
+				path: Some(ProjectPath::Required(path)),
+				..ProjectNode::default()
+			},
+		);
+	}
+
+	project.save(project_path)
+}
+
+/// Updates the instance to match its counterpart in the place and
+/// adds children that are missing, descendants are handled without recursion
+fn apply(dom: &WeakDom, place: Ref, id: Ref, tree: &mut Tree, vfs: &Vfs, report: &mut ImportReport) -> Result<()> {
+	let mut stack = vec![(place, id)];
+
+	while let Some((place, id)) = stack.pop() {
+		let instance = dom.get_by_ref(place).unwrap();
+		let (properties, _) = strip_properties(&instance.class, &instance.properties);
+
+		let current = tree.get_instance(id).unwrap();
+
+		if properties
+			.iter()
+			.any(|(name, value)| current.properties.get(name) != Some(value))
+		{
+			let mut update = UpdatedSnapshot::new(id);
+			update.properties = Some(properties);
+
+			write::apply_update(update, tree, vfs)?;
+			report.updated += 1;
+		}
+
+		let mut matched = vec![];
+
+		for child in instance.children() {
+			let child_instance = dom.get_by_ref(*child).unwrap();
+
+			let existing = tree.get_instance(id).and_then(|current| {
+				current.children().iter().copied().find(|existing| {
+					!matched.contains(existing)
+						&& tree.get_instance(*existing).is_some_and(|existing| {
+							existing.name == child_instance.name && existing.class == child_instance.class
+						})
+				})
+			});
+
+			if let Some(existing) = existing {
+				matched.push(existing);
+				stack.push((*child, existing));
+				continue;
+			}
+
+			let snapshot = snapshot_subtree(dom, *child);
+			let count = count_instances(&snapshot);
+
+			write::apply_addition(snapshot.into_new(id), tree, vfs)?;
+
+			report.added += count;
+		}
+	}
+
+	Ok(())
+}
+
+/// Records instances and property values of the place that are not in the tree
+fn compare(dom: &WeakDom, place: Ref, id: Ref, tree: &Tree, report: &mut ImportReport) {
+	let mut stack = vec![(place, id)];
+
+	while let Some((place, id)) = stack.pop() {
+		let instance = dom.get_by_ref(place).unwrap();
+		let current = tree.get_instance(id).unwrap();
+		let (properties, dropped) = strip_properties(&instance.class, &instance.properties);
+
+		let lossy = properties
+			.iter()
+			.filter(|(name, value)| current.properties.get(*name) != Some(*value))
+			.map(|(name, _)| *name)
+			.chain(dropped);
+
+		for property in lossy {
+			*report
+				.lossy_properties
+				.entry(format!("{}.{}", instance.class, property))
+				.or_default() += 1;
+		}
+
+		let mut matched = vec![];
+
+		for child in instance.children() {
+			let child_instance = dom.get_by_ref(*child).unwrap();
+
+			let existing = current.children().iter().copied().find(|existing| {
+				!matched.contains(existing)
+					&& tree.get_instance(*existing).is_some_and(|existing| {
+						existing.name == child_instance.name && existing.class == child_instance.class
+					})
+			});
+
+			match existing {
+				Some(existing) => {
+					matched.push(existing);
+					stack.push((*child, existing));
+				}
+				None => report.unrepresented.push((
+					format!("{}.{}", tree.get_full_name(id).unwrap_or_default(), child_instance.name),
+					child_instance.class.to_string(),
+				)),
+			}
+		}
+	}
+}
+
+/// Returns properties without default values and without the ones
+/// that can't be written to files, along with names of the latter
+fn strip_properties(class: &str, properties: &Properties) -> (Properties, Vec<Ustr>) {
+	let database = rbx_reflection_database::get();
+	let descriptor = database.classes.get(class);
+
+	let mut kept = Properties::default();
+	let mut dropped = vec![];
+
+	for (name, value) in properties {
+		match value {
+			Variant::Ref(_) | Variant::UniqueId(_) | Variant::SharedString(_) => {
+				dropped.push(*name);
+				continue;
+			}
+			_ => {}
+		}
+
+		let default = descriptor.and_then(|descriptor| database.find_default_property(descriptor, name));
+
+		if default != Some(value) {
+			kept.insert(*name, value.clone());
+		}
+	}
+
+	(kept, dropped)
+}
+
+/// Converts the instance and its descendants, like `snapshot_from_dom` but without
+/// taking the whole DOM and with properties stripped. Mesh source of `MeshPart`s is
+/// resolved from the written properties once the workspace is read again
+fn snapshot_subtree(dom: &WeakDom, id: Ref) -> Snapshot {
+	let mut order = vec![];
+	let mut stack = vec![id];
+
+	while let Some(id) = stack.pop() {
+		order.push(id);
+		stack.extend(dom.get_by_ref(id).unwrap().children());
+	}
+
+	let mut completed: HashMap<Ref, Snapshot> = HashMap::new();
+
+	for id in order.into_iter().rev() {
+		let instance = dom.get_by_ref(id).unwrap();
+		let (properties, _) = strip_properties(&instance.class, &instance.properties);
+
+		let children = instance
+			.children()
+			.iter()
+			.map(|child| completed.remove(child).unwrap())
+			.collect();
+
+		let snapshot = Snapshot::new()
+			.with_id(Ref::new())
+			.with_name(&instance.name)
+			.with_class(instance.class)
+			.with_properties(properties)
+			.with_children(children);
+
+		completed.insert(id, snapshot);
+	}
+
+	completed.remove(&id).unwrap()
+}
+
+fn count_instances(snapshot: &Snapshot) -> usize {
+	let mut count = 0;
+	let mut stack = vec![snapshot];
+
+	while let Some(snapshot) = stack.pop() {
+		count += 1;
+		stack.extend(&snapshot.children);
+	}
+
+	count
+}
@@ -9,7 +9,7 @@ use std::{env, fs, path::Path};
 use crate::{
 	argon_error, argon_info,
 	ext::PathExt,
-	logger, updater,
+	logger, t, updater,
 	util::{self, get_plugin_path},
 };
 
@@ -50,7 +50,7 @@ pub fn verify(is_managed: bool, with_plugin: bool) -> Result<()> {
 		if !exe_path.exists() {
 			fs::copy(env::current_exe()?, &exe_path)?;
 
-			if logger::prompt("Installation completed! Do you want to remove this executable?", true) {
+			if logger::prompt(&t!("installer.remove_prompt"), true) {
 				self_replace::self_delete()?;
 			}
 		}
@@ -87,7 +87,7 @@ pub fn install_plugin(path: &Path, show_progress: bool) -> Result<()> {
 	match update.download() {
 		Ok(status) => match status {
 			UpdateStatus::Updated(release) => {
-				argon_info!("Installed Lemonade plugin, version: {}", release.version.bold());
+				argon_info!("{}", t!("installer.plugin_installed", version = release.version.bold()));
 
 				if path.contains(&["Roblox", "Plugins"]) {
 					let mut status = updater::get_status()?;
@@ -103,13 +103,13 @@ pub fn install_plugin(path: &Path, show_progress: bool) -> Result<()> {
 
 			#[allow(clippy::const_is_empty)]
 			if ARGON_PLUGIN.is_empty() {
-				argon_error!("No internet connection! Failed to install Lemonade plugin - no bundled binary found");
+				argon_error!("{}", t!("installer.no_bundled"));
 				return Ok(());
 			}
 
 			fs::write(path, ARGON_PLUGIN)?;
 
-			argon_info!("No internet connection! Installed Lemonade plugin from bundled binary")
+			argon_info!("{}", t!("installer.bundled"))
 		}
 	}
 
@@ -2,11 +2,17 @@ use anyhow::Result;
 use colored::Colorize;
 use log::debug;
 use serde::Deserialize;
-use std::{collections::HashMap, fs, path::Path};
+use std::{
+	collections::HashMap,
+	fs,
+	path::{Path, PathBuf},
+	time::SystemTime,
+};
 
 use crate::{
 	logger,
 	program::{Program, ProgramName},
+	t,
 };
 
 #[derive(Debug, Deserialize)]
@@ -15,12 +21,7 @@ struct WallyManifest {
 }
 
 fn install_wally_packages(workspace_path: &Path) -> Result<()> {
-	let install = logger::prompt(
-        &format!("Looks like your project uses Wally but Packages directory is missing or one of the dependencies is not installed. Would you like to run {} now?",
-            "wally install".bold()
-        ),
-        true
-    );
+	let install = logger::prompt(&t!("integration.wally_prompt", command = "wally install".bold()), true);
 
 	if !install {
 		return Ok(());
@@ -63,3 +64,150 @@ pub fn check_wally_packages(workspace_path: &Path) -> Result<()> {
 
 	Ok(())
 }
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TsConfig {
+	#[serde(default)]
+	compiler_options: TsCompilerOptions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TsCompilerOptions {
+	root_dir: Option<PathBuf>,
+	out_dir: Option<PathBuf>,
+}
+
+/// Source and output directories of roblox-ts project, both absolute
+#[derive(Debug, Clone, PartialEq)]
+pub struct TsLayout {
+	pub root_dir: PathBuf,
+	pub out_dir: PathBuf,
+}
+
+impl TsLayout {
+	/// Reads `rootDir` and `outDir` from `tsconfig.json`,
+	/// falls back to `src` and `out` used by roblox-ts templates
+	pub fn load(workspace_path: &Path) -> Self {
+		let config = fs::read_to_string(workspace_path.join("tsconfig.json"))
+			.ok()
+			.and_then(|config| serde_json::from_str::<TsConfig>(&strip_json_comments(&config)).ok())
+			.unwrap_or_default();
+
+		let options = config.compiler_options;
+
+		Self {
+			root_dir: workspace_path.join(options.root_dir.unwrap_or(PathBuf::from("src"))),
+			out_dir: workspace_path.join(options.out_dir.unwrap_or(PathBuf::from("out"))),
+		}
+	}
+
+	/// Whether the path is a part of TypeScript sources
+	pub fn is_source(&self, path: &Path) -> bool {
+		path.starts_with(&self.root_dir)
+	}
+
+	/// Whether the path is owned by the compiler
+	pub fn is_output(&self, path: &Path) -> bool {
+		path.starts_with(&self.out_dir)
+	}
+
+	/// Maps path pointing to TypeScript sources to its compiled counterpart,
+	/// e.g. `src/shared/index.ts` becomes `out/shared/init.luau`
+	pub fn map_path(&self, path: &Path) -> PathBuf {
+		let relative = match path.strip_prefix(&self.root_dir) {
+			Ok(relative) => relative,
+			Err(_) => return path.to_owned(),
+		};
+
+		let mut path = self.out_dir.join(relative);
+
+		if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+			if extension == "ts" || extension == "tsx" {
+				if path.file_stem().is_some_and(|stem| stem == "index") {
+					path.set_file_name("init");
+				}
+
+				path.set_extension("luau");
+			}
+		}
+
+		path
+	}
+
+	/// Whether TypeScript sources were modified after the last compilation,
+	/// which usually means that `rbxtsc --watch` is not running
+	pub fn is_stale(&self) -> bool {
+		match (get_last_modified(&self.root_dir), get_last_modified(&self.out_dir)) {
+			(Some(source), Some(output)) => source > output,
+			(Some(_), None) => true,
+			_ => false,
+		}
+	}
+}
+
+fn get_last_modified(path: &Path) -> Option<SystemTime> {
+	let metadata = fs::metadata(path).ok()?;
+	let mut modified = metadata.modified().ok();
+
+	if metadata.is_dir() {
+		for entry in fs::read_dir(path).ok()?.flatten() {
+			modified = modified.max(get_last_modified(&entry.path()));
+		}
+	}
+
+	modified
+}
+
+/// `tsconfig.json` allows comments which `serde_json` does not
+fn strip_json_comments(json: &str) -> String {
+	let mut stripped = String::with_capacity(json.len());
+	let mut chars = json.chars().peekable();
+	let mut in_string = false;
+
+	while let Some(char) = chars.next() {
+		if in_string {
+			stripped.push(char);
+
+			match char {
+				'\\' => stripped.extend(chars.next()),
+				'"' => in_string = false,
+				_ => {}
+			}
+
+			continue;
+		}
+
+		match (char, chars.peek()) {
+			('"', _) => {
+				in_string = true;
+				stripped.push(char);
+			}
+			('/', Some('/')) => {
+				for char in chars.by_ref() {
+					if char == '\n' {
+						stripped.push(char);
+						break;
+					}
+				}
+			}
+			('/', Some('*')) => {
+				chars.next();
+
+				let mut previous = ' ';
+
+				for char in chars.by_ref() {
+					if previous == '*' && char == '/' {
+						break;
+					}
+
+					previous = char;
+				}
+			}
+			_ => stripped.push(char),
+		}
+	}
+
+	stripped
+}
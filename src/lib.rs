@@ -2,26 +2,46 @@
 
 use rbx_dom_weak::{types::Variant, UstrMap};
 
+pub mod assets;
+pub mod backups;
+pub mod bench;
+pub mod class_meta;
+pub mod clean;
 pub mod cli;
+pub mod codes;
 pub mod config;
 pub mod constants;
 pub mod core;
 pub mod crash_handler;
+pub mod daemon;
+pub mod dashboard;
+pub mod defs;
 pub mod ext;
 pub mod glob;
+pub mod history;
+pub mod hooks;
+pub mod i18n;
+pub mod import;
 pub mod installer;
 pub mod integration;
 pub mod logger;
+pub mod manifest;
+pub mod mcp;
 pub mod middleware;
 pub mod program;
 pub mod project;
+pub mod ranges;
 pub mod resolution;
 pub mod server;
 pub mod sessions;
+pub mod settings;
+pub mod state;
 pub mod stats;
 pub mod studio;
+pub mod test_runner;
 pub mod updater;
 pub mod util;
+pub mod validation;
 pub mod vfs;
 pub mod workspace;
 
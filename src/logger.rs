@@ -1,14 +1,35 @@
+use clap::ValueEnum;
 use colored::{Color, Colorize};
 use dialoguer::console::{style, Style, StyledObject};
 use dialoguer::theme::Theme;
 use dialoguer::Confirm;
 use env_logger::{Builder, WriteStyle};
-use log::{Level, LevelFilter};
+use log::{
+	kv::{self, Key, Value, VisitSource},
+	Level, LevelFilter, Record,
+};
+use serde_json::{json, Map};
 use std::fmt::{Display, Formatter};
-use std::{fmt, io::Write};
+use std::{
+	cell::RefCell,
+	fmt,
+	io::Write,
+	sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
 
 use crate::util;
 
+/// Correlation ID of the next span
+static NEXT_SPAN_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Current verbosity as `LevelFilter` discriminant, see `set_verbosity`
+static VERBOSITY: AtomicUsize = AtomicUsize::new(LevelFilter::Off as usize);
+
+thread_local! {
+	/// Spans entered on this thread, the innermost one is the last
+	static SPANS: RefCell<Vec<Span>> = const { RefCell::new(Vec::new()) };
+}
+
 // These Argon logs ignore verbosity level, aside of `Off`
 #[macro_export]
 macro_rules! argon_error {
@@ -25,49 +46,208 @@ macro_rules! argon_info {
     ($($arg:tt)+) => (log::log!(target: "argon_log", log::Level::Info, $($arg)+))
 }
 
-pub fn init(verbosity: LevelFilter, log_style: WriteStyle) {
+/// Format of log lines written to stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogFormat {
+	/// Compact lines meant to be read by people
+	#[default]
+	Human,
+	/// One JSON object per line with structured fields and the current span
+	Json,
+}
+
+impl LogFormat {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			LogFormat::Human => "human",
+			LogFormat::Json => "json",
+		}
+	}
+}
+
+/// Operation that records logged while it is entered belong to, e.g. a
+/// single syncback change. Its correlation ID and fields are attached to
+/// every record so interleaved operations can be told apart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+	pub name: &'static str,
+	/// Correlation ID, unique within the process
+	pub id: u64,
+	pub fields: Vec<(&'static str, String)>,
+}
+
+impl Span {
+	pub fn new(name: &'static str) -> Self {
+		Self {
+			name,
+			id: NEXT_SPAN_ID.fetch_add(1, Ordering::Relaxed),
+			fields: vec![],
+		}
+	}
+
+	pub fn with_field(mut self, key: &'static str, value: impl Display) -> Self {
+		self.fields.push((key, value.to_string()));
+		self
+	}
+
+	/// Enters the span on this thread until the returned guard is dropped
+	pub fn enter(self) -> SpanGuard {
+		SPANS.with_borrow_mut(|spans| spans.push(self));
+		SpanGuard(())
+	}
+}
+
+/// Leaves the span when dropped, see `Span::enter`
+#[must_use]
+pub struct SpanGuard(());
+
+impl Drop for SpanGuard {
+	fn drop(&mut self) {
+		SPANS.with_borrow_mut(|spans| spans.pop());
+	}
+}
+
+/// Returns the innermost span entered on this thread
+pub fn current_span() -> Option<Span> {
+	SPANS.with_borrow(|spans| spans.last().cloned())
+}
+
+/// Returns key-value fields of the record in the order they were logged
+pub fn record_fields(record: &Record) -> Vec<(String, String)> {
+	struct Visitor(Vec<(String, String)>);
+
+	impl<'kvs> VisitSource<'kvs> for Visitor {
+		fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+			self.0.push((key.to_string(), value.to_string()));
+			Ok(())
+		}
+	}
+
+	let mut visitor = Visitor(vec![]);
+	record.key_values().visit(&mut visitor).ok();
+
+	visitor.0
+}
+
+/// Formats the record as a single line without the line break, records of
+/// user facing `argon_*` macros are printed as they are in the human format
+pub fn format_record(record: &Record, format: LogFormat) -> String {
+	let fields = record_fields(record);
+	let span = current_span();
+
+	match format {
+		LogFormat::Human => {
+			let color = match record.level() {
+				Level::Error => Color::Red,
+				Level::Warn => Color::Yellow,
+				Level::Info => Color::Green,
+				Level::Debug => Color::Cyan,
+				Level::Trace => Color::White,
+			};
+
+			let level = record.level().to_string().color(color).bold();
+
+			if record.target() == "argon_log" {
+				return format!("{}: {}", level, record.args());
+			}
+
+			let mut line = format!("{}: {}", level, record.args());
+
+			for (key, value) in &fields {
+				line.push_str(&format!(" {}={}", key, value));
+			}
+
+			if let Some(span) = span {
+				line.push_str(&format!(" {{{}#{}", span.name, span.id));
+
+				for (key, value) in &span.fields {
+					line.push_str(&format!(" {}={}", key, value));
+				}
+
+				line.push('}');
+			}
+
+			line.push_str(&format!(
+				" [{}:{}]",
+				record.module_path().unwrap_or_default(),
+				record.line().unwrap_or_default()
+			));
+
+			line
+		}
+		LogFormat::Json => {
+			let mut line = Map::new();
+
+			line.insert(String::from("level"), json!(record.level().as_str()));
+			line.insert(String::from("target"), json!(record.target()));
+			line.insert(String::from("message"), json!(strip_ansi(&record.args().to_string())));
+
+			if !fields.is_empty() {
+				line.insert(
+					String::from("fields"),
+					fields.into_iter().map(|(key, value)| (key, json!(value))).collect(),
+				);
+			}
+
+			if let Some(span) = span {
+				let mut fields: Map<_, _> = span
+					.fields
+					.into_iter()
+					.map(|(key, value)| (key.to_owned(), json!(value)))
+					.collect();
+
+				fields.insert(String::from("name"), json!(span.name));
+				fields.insert(String::from("id"), json!(span.id));
+
+				line.insert(String::from("span"), fields.into());
+			}
+
+			if let Some(module) = record.module_path() {
+				line.insert(String::from("module"), json!(module));
+			}
+
+			if let Some(number) = record.line() {
+				line.insert(String::from("line"), json!(number));
+			}
+
+			serde_json::Value::Object(line).to_string()
+		}
+	}
+}
+
+/// Removes terminal color codes added by `colored`
+pub fn strip_ansi(text: &str) -> String {
+	let mut stripped = String::with_capacity(text.len());
+	let mut chars = text.chars();
+
+	while let Some(char) = chars.next() {
+		if char == '\u{1b}' {
+			for char in chars.by_ref() {
+				if char.is_ascii_alphabetic() {
+					break;
+				}
+			}
+		} else {
+			stripped.push(char);
+		}
+	}
+
+	stripped
+}
+
+pub fn init(verbosity: LevelFilter, log_style: WriteStyle, log_format: LogFormat) {
 	let mut builder = Builder::new();
 
 	builder.format(move |buffer, record| {
-		if record.level() > verbosity && record.target() != "argon_log" {
+		if record.level() > self::verbosity() && record.target() != "argon_log" {
 			return Ok(());
 		}
 
-		let color = match record.level() {
-			Level::Error => Color::Red,
-			Level::Warn => Color::Yellow,
-			Level::Info => Color::Green,
-			Level::Debug => Color::Cyan,
-			Level::Trace => Color::White,
-		};
-
-		if record.target() == "argon_log" {
-			writeln!(
-				buffer,
-				"{}: {:?}",
-				record.level().to_string().color(color).bold(),
-				record.args()
-			)
-		} else {
-			writeln!(
-				buffer,
-				"{}: {:?} [{}:{}]",
-				record.level().to_string().color(color).bold(),
-				record.args(),
-				record.module_path().unwrap(),
-				record.line().unwrap()
-			)
-		}
+		writeln!(buffer, "{}", format_record(record, log_format))
 	});
 
-	if verbosity == LevelFilter::Off {
-		builder.filter_level(LevelFilter::Off);
-	} else if verbosity <= LevelFilter::Info {
-		builder.filter_level(LevelFilter::Info);
-	} else {
-		builder.filter_level(verbosity);
-	}
-
+	// Everything passes the builder, so verbosity can be raised at runtime
+	builder.filter_level(LevelFilter::Trace);
 	builder.write_style(log_style);
 
 	// We want to see only important logs from these crates
@@ -84,6 +264,25 @@ pub fn init(verbosity: LevelFilter, log_style: WriteStyle) {
 	builder.filter_module("rbx_binary", LevelFilter::Warn);
 
 	builder.init();
+	set_verbosity(verbosity);
+}
+
+pub fn verbosity() -> LevelFilter {
+	LevelFilter::iter()
+		.nth(VERBOSITY.load(Ordering::Relaxed))
+		.unwrap_or(LevelFilter::Off)
+}
+
+/// Changes verbosity of the running logger, Argon logs
+/// are still shown unless the logger is turned `Off`
+pub fn set_verbosity(verbosity: LevelFilter) {
+	VERBOSITY.store(verbosity as usize, Ordering::Relaxed);
+
+	log::set_max_level(if verbosity == LevelFilter::Off {
+		LevelFilter::Off
+	} else {
+		verbosity.max(LevelFilter::Info)
+	});
 }
 
 pub fn prompt(prompt: &str, default: bool) -> bool {
@@ -9,7 +9,7 @@ use std::{
 	thread,
 };
 
-use argon::{argon_error, cli::Cli, config::Config, crash_handler, installer, logger, stats, updater};
+use argon::{argon_error, cli::Cli, config::Config, crash_handler, i18n, installer, logger, stats, updater};
 
 const PROFILER_ADDRESS: &str = "localhost:8888";
 
@@ -19,6 +19,8 @@ fn main() -> ExitCode {
 	let config_kind = Config::load();
 	let config = Config::new().clone();
 
+	i18n::init(&config.language);
+
 	let is_managed = installer::is_managed();
 	let installation = installer::verify(is_managed, config.install_plugin);
 
@@ -28,6 +30,7 @@ fn main() -> ExitCode {
 	let backtrace = cli.backtrace();
 	let verbosity = cli.verbosity();
 	let log_style = cli.log_style();
+	let log_format = cli.log_format();
 
 	if log_style == WriteStyle::Auto && io::stdin().is_terminal() {
 		env::set_var("RUST_LOG_STYLE", "always");
@@ -42,10 +45,11 @@ fn main() -> ExitCode {
 	}
 
 	env::set_var("RUST_VERBOSE", verbosity.as_str());
+	env::set_var("RUST_LOG_FORMAT", log_format.as_str());
 	env::set_var("RUST_YES", if yes { "1" } else { "0" });
 	env::set_var("RUST_BACKTRACE", if backtrace { "1" } else { "0" });
 
-	logger::init(verbosity, log_style);
+	logger::init(verbosity, log_style, log_format);
 
 	match config_kind {
 		Ok(kind) => info!("{:?} config loaded", kind),
@@ -0,0 +1,124 @@
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::{
+	fs,
+	path::{Path, PathBuf},
+};
+
+use crate::{ext::PathExt, project};
+
+/// First path segments of server endpoints, members with these names
+/// would shadow endpoints of the member served at the root
+const RESERVED_NAMES: [&str; 22] = [
+	"blob",
+	"class-metadata",
+	"clients",
+	"complete",
+	"details",
+	"events",
+	"exec",
+	"find",
+	"health",
+	"log",
+	"open",
+	"pending",
+	"properties",
+	"read",
+	"snapshot",
+	"stats",
+	"stop",
+	"subscribe",
+	"sync",
+	"unsubscribe",
+	"write",
+	"ws",
+];
+
+/// Workspace manifest (e.g. `argon.workspace.json`) listing projects served
+/// together by `argon serve --workspace`, every member gets its own tree
+/// served under `/<name>` and the first one is served at the root as well
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceManifest {
+	pub members: Vec<MemberEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberEntry {
+	/// Name of the member, name of its directory by default
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+	/// Project file or directory relative to the manifest
+	pub path: PathBuf,
+}
+
+/// Member with resolved name and project path
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+	pub name: String,
+	pub project: PathBuf,
+}
+
+impl WorkspaceManifest {
+	pub fn load(path: &Path) -> Result<Self> {
+		let contents = fs::read_to_string(path)
+			.with_context(|| format!("Failed to read workspace file {}", path.to_string().bold()))?;
+
+		serde_json::from_str(&contents)
+			.with_context(|| format!("Failed to parse workspace file {}", path.to_string().bold()))
+	}
+
+	/// Resolves members relative to the manifest at `path`
+	pub fn members(&self, path: &Path) -> Result<Vec<Member>> {
+		if self.members.is_empty() {
+			bail!("Workspace file {} has no members", path.to_string().bold());
+		}
+
+		let mut members: Vec<Member> = vec![];
+
+		for entry in &self.members {
+			let project = project::resolve(path.get_parent().join(&entry.path))?;
+
+			let name = match &entry.name {
+				Some(name) => name.to_owned(),
+				None => project.get_parent().get_name().to_owned(),
+			};
+
+			if name.is_empty()
+				|| !name
+					.chars()
+					.all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_')
+			{
+				bail!(
+					"Invalid workspace member name {}, only letters, digits, `-` and `_` are allowed",
+					name.bold()
+				);
+			}
+
+			if RESERVED_NAMES.contains(&name.as_str()) {
+				bail!(
+					"Workspace member name {} is reserved, set a different {}",
+					name.bold(),
+					"name".bold()
+				);
+			}
+
+			if members.iter().any(|member| member.name == name) {
+				bail!("Workspace member name {} is used more than once", name.bold());
+			}
+
+			if members.iter().any(|member| member.project == project) {
+				bail!(
+					"Project {} is a member of the workspace more than once",
+					project.to_string().bold()
+				);
+			}
+
+			members.push(Member { name, project });
+		}
+
+		Ok(members)
+	}
+}
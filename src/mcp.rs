@@ -0,0 +1,727 @@
+use anyhow::{bail, Result};
+use glob::Pattern;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+	collections::{HashMap, HashSet},
+	fs::{self, OpenOptions},
+	future::Future,
+	io::Write,
+	path::{Path, PathBuf},
+	sync::Mutex,
+};
+
+use crate::{
+	constants::BLACKLISTED_PATHS,
+	core::{
+		find::FindPage,
+		meta::{Context, Meta},
+	},
+	lock,
+	middleware::Middleware,
+	project::{Project, ProjectNode, ProjectPath},
+	sessions,
+	state::{self, Migration, State},
+	util,
+	validation::{self, Problem},
+};
+
+/// Tools served by `argon connect-mcp` and whether they reach external networks
+pub const TOOLS: &[(&str, bool)] = &[
+	("roblox_developer_forum", true),
+	("luau_documentation", true),
+	("roblox_engine_documentation", true),
+	("validate_project_edit", false),
+	("suggest_path", false),
+	("project_skeleton", false),
+];
+
+pub fn is_tool(tool: &str) -> bool {
+	TOOLS.iter().any(|(name, _)| *name == tool)
+}
+
+/// Unknown tools are treated as network ones
+pub fn is_network_tool(tool: &str) -> bool {
+	TOOLS
+		.iter()
+		.find(|(name, _)| *name == tool)
+		.is_none_or(|(_, network)| *network)
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+	Allow,
+	Deny,
+	/// Tool has to be approved with `argon mcp approve` once per session
+	#[default]
+	Ask,
+}
+
+/// Contents of `~/.argon/mcp.toml`, written by the user
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct McpConfig {
+	/// Refuse all tools that reach external networks
+	pub offline: bool,
+	/// Permission of tools that are not listed in `tools`
+	pub default: Permission,
+	pub tools: HashMap<String, Permission>,
+}
+
+impl McpConfig {
+	/// Missing file is the same as an empty one
+	pub fn load(path: &Path) -> Result<Self> {
+		if !path.exists() {
+			return Ok(Self::default());
+		}
+
+		Ok(toml::from_str(&fs::read_to_string(path)?)?)
+	}
+
+	pub fn permission(&self, tool: &str) -> Permission {
+		self.tools.get(tool).copied().unwrap_or(self.default)
+	}
+}
+
+/// Tools approved with `argon mcp approve` that no server picked up yet
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Approvals {
+	pub tools: Vec<String>,
+}
+
+impl State for Approvals {
+	const NAME: &'static str = "MCP approvals";
+	const VERSION: u32 = 0;
+	const MIGRATIONS: &'static [Migration] = &[];
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+	Allowed,
+	/// Approved for the session of the server
+	Approved,
+	NeedsApproval,
+	Denied,
+	/// Network tool refused because of the `offline` setting
+	Offline,
+}
+
+impl Decision {
+	pub fn is_allowed(&self) -> bool {
+		matches!(self, Decision::Allowed | Decision::Approved)
+	}
+
+	/// Structured result returned instead of running the tool
+	pub fn to_json(self, tool: &str) -> Value {
+		let message = match self {
+			Decision::Allowed | Decision::Approved => format!("Tool {} is allowed", tool),
+			Decision::NeedsApproval => format!(
+				"Tool {} needs approval, run `argon mcp approve {}` and call it again",
+				tool, tool
+			),
+			Decision::Denied => format!("Tool {} is denied in mcp.toml", tool),
+			Decision::Offline => format!(
+				"Tool {} reaches external networks which is disabled by the `offline` setting in mcp.toml",
+				tool
+			),
+		};
+
+		json!({
+			"status": self,
+			"tool": tool,
+			"message": message,
+		})
+	}
+}
+
+/// Result of a guarded tool call
+#[derive(Debug)]
+pub enum Outcome {
+	Ran(Result<String>),
+	Refused(Decision),
+}
+
+/// Checks permissions of tool calls and keeps audit log of them
+pub struct Guard {
+	config: McpConfig,
+	approvals_path: PathBuf,
+	audit_path: PathBuf,
+	approved: Mutex<HashSet<String>>,
+}
+
+impl Guard {
+	/// Uses `mcp.toml`, `mcp-approvals.toml` and `mcp-audit.jsonl` of the given directory
+	pub fn new(config: McpConfig, dir: &Path) -> Self {
+		Self {
+			config,
+			approvals_path: dir.join("mcp-approvals.toml"),
+			audit_path: dir.join("mcp-audit.jsonl"),
+			approved: Mutex::new(HashSet::new()),
+		}
+	}
+
+	/// Loads config from the Argon directory of the user
+	pub fn load() -> Result<Self> {
+		let dir = util::get_argon_dir()?;
+		let config = McpConfig::load(&dir.join("mcp.toml"))?;
+
+		Ok(Self::new(config, &dir))
+	}
+
+	/// Approvals written by `argon mcp approve` are
+	/// taken over by the first server that asks for them
+	pub fn check(&self, tool: &str) -> Decision {
+		if self.config.offline && is_network_tool(tool) {
+			return Decision::Offline;
+		}
+
+		match self.config.permission(tool) {
+			Permission::Allow => Decision::Allowed,
+			Permission::Deny => Decision::Denied,
+			Permission::Ask => {
+				let mut approved = lock!(self.approved);
+
+				if approved.contains(tool) || self.take_approval(tool) {
+					approved.insert(tool.to_owned());
+					Decision::Approved
+				} else {
+					Decision::NeedsApproval
+				}
+			}
+		}
+	}
+
+	/// Runs the tool if it is permitted, every call is audited
+	pub async fn call<F, Fut>(&self, tool: &str, call: F) -> Outcome
+	where
+		F: FnOnce() -> Fut,
+		Fut: Future<Output = Result<String>>,
+	{
+		let decision = self.check(tool);
+
+		if !decision.is_allowed() {
+			self.audit(tool, decision, None);
+			return Outcome::Refused(decision);
+		}
+
+		let result = call().await;
+		self.audit(tool, decision, Some(&result));
+
+		Outcome::Ran(result)
+	}
+
+	fn take_approval(&self, tool: &str) -> bool {
+		let Some(mut approvals) = state::load::<Approvals>(&self.approvals_path) else {
+			return false;
+		};
+
+		let Some(index) = approvals.tools.iter().position(|approved| approved == tool) else {
+			return false;
+		};
+
+		approvals.tools.remove(index);
+
+		if let Err(err) = state::save(&self.approvals_path, &approvals) {
+			warn!("Failed to save MCP approvals: {}", err);
+		}
+
+		debug!("Tool {} approved for this session", tool);
+
+		true
+	}
+
+	fn audit(&self, tool: &str, decision: Decision, result: Option<&Result<String>>) {
+		let mut entry = json!({
+			"timestamp": chrono::Utc::now().to_rfc3339(),
+			"tool": tool,
+			"permission": self.config.permission(tool),
+			"decision": decision,
+		});
+
+		if let Some(result) = result {
+			entry["success"] = json!(result.is_ok());
+
+			if let Err(err) = result {
+				entry["error"] = json!(err.to_string());
+			}
+		}
+
+		let written = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&self.audit_path)
+			.and_then(|mut file| writeln!(file, "{}", entry));
+
+		if let Err(err) = written {
+			warn!("Failed to write MCP audit log: {}", err);
+		}
+	}
+}
+
+/// Approves the tool for the next server session that asks for it
+pub fn approve(dir: &Path, tool: &str) -> Result<()> {
+	fs::create_dir_all(dir)?;
+
+	let path = dir.join("mcp-approvals.toml");
+	let mut approvals = state::load::<Approvals>(&path).unwrap_or_default();
+
+	if !approvals.tools.iter().any(|approved| approved == tool) {
+		approvals.tools.push(tool.to_owned());
+	}
+
+	state::save(&path, &approvals)
+}
+
+/// Returns tools approved with `approve` that no server picked up yet
+pub fn pending_approvals(dir: &Path) -> Vec<String> {
+	state::load::<Approvals>(&dir.join("mcp-approvals.toml"))
+		.map(|approvals| approvals.tools)
+		.unwrap_or_default()
+}
+
+/// Proposed change of the project file, either its whole new contents
+/// or a JSON merge patch (RFC 7386) applied to the current ones
+#[derive(Debug, Clone)]
+pub enum ProjectEdit {
+	Contents(String),
+	MergePatch(Value),
+}
+
+/// Validates the edit with the same checks as `argon validate`, nothing is saved
+pub fn validate_edit(project_path: &Path, edit: &ProjectEdit) -> Result<Vec<Problem>> {
+	let contents = match edit {
+		ProjectEdit::Contents(contents) => contents.to_owned(),
+		ProjectEdit::MergePatch(patch) => {
+			let mut project: Value = serde_json::from_str(&fs::read_to_string(project_path)?)?;
+			merge_patch(&mut project, patch);
+
+			serde_json::to_string_pretty(&project)?
+		}
+	};
+
+	Ok(validation::validate(&contents, project_path))
+}
+
+/// Node of the project tree with its `$path` resolved, see `project_skeleton`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SkeletonNode {
+	pub name: String,
+	/// Node path as reported by `argon validate`, e.g. `tree/ReplicatedStorage`
+	pub node: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub class_name: Option<String>,
+	/// Absolute `$path` of the node
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub path: Option<PathBuf>,
+	#[serde(skip_serializing_if = "std::ops::Not::not")]
+	pub optional: bool,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub exists: Option<bool>,
+	/// Middleware the path is read with, `Directory` for plain directories
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub middleware: Option<String>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub profiles: Vec<String>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub children: Vec<SkeletonNode>,
+}
+
+/// Returns the node tree of the project, implicit nodes are left out
+pub fn project_skeleton(project: &Project) -> SkeletonNode {
+	let context = Meta::from_project(project).context;
+
+	skeleton_node(project, &project.name, &project.node, "tree", &context)
+}
+
+/// Existing file or directory where an instance lives or could be created
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PathCandidate {
+	/// Absolute path, the project file when the instance comes from a node without `$path`
+	pub path: PathBuf,
+	/// Instance path synced from `path`, separated with `/` without the root
+	pub instance: String,
+	/// Whether `path` backs the requested instance itself, otherwise it
+	/// is the closest existing ancestor the instance would be created in
+	pub exact: bool,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub middleware: Option<String>,
+}
+
+/// Finds candidates in the running session serving the project,
+/// `None` if there is none or it could not be reached
+pub async fn suggest_from_session(
+	project: &Project,
+	instance: Option<&str>,
+	class: Option<&str>,
+	limit: usize,
+) -> Option<Vec<PathCandidate>> {
+	let address = sessions::find_serving(&sessions::get_all().ok()?, &project.path)?
+		.1
+		.get_address()?;
+
+	let context = Meta::from_project(project).context;
+	let client = reqwest::Client::new();
+
+	let find = |query: Vec<(&'static str, String)>| {
+		let request = client.get(format!("{}/find", address)).query(&query);
+
+		async move {
+			match request.send().await.and_then(|response| response.error_for_status()) {
+				Ok(response) => response.json::<FindPage>().await.ok(),
+				Err(err) => {
+					debug!("Failed to query session for path suggestions: {}", err);
+					None
+				}
+			}
+		}
+	};
+
+	let mut candidates = vec![];
+
+	if let Some(instance) = instance {
+		let mut segments = split_instance(instance);
+		let mut exact = true;
+
+		while !segments.is_empty() {
+			let glob = segments
+				.iter()
+				.map(|segment| Pattern::escape(segment))
+				.collect::<Vec<_>>()
+				.join("/");
+
+			let page = find(vec![("pathGlob", glob), ("limit", limit.to_string())]).await?;
+
+			let found: Vec<_> = page
+				.instances
+				.into_iter()
+				.filter(|found| strip_root(&found.path) == segments.join("/"))
+				.collect();
+
+			for found in &found {
+				for path in &found.files {
+					candidates.push(PathCandidate {
+						path: path.to_owned(),
+						instance: strip_root(&found.path).to_owned(),
+						exact,
+						middleware: middleware_of(path, &context),
+					});
+				}
+			}
+
+			if !found.is_empty() {
+				break;
+			}
+
+			segments.pop();
+			exact = false;
+		}
+	}
+
+	if let Some(class) = class {
+		let page = find(vec![("class", class.to_owned()), ("limit", limit.to_string())]).await?;
+
+		for found in page.instances {
+			let Some(path) = found.files.first() else {
+				continue;
+			};
+
+			let parent = strip_root(&found.path).rsplit_once('/').map(|(parent, _)| parent);
+
+			candidates.push(PathCandidate {
+				path: path.parent().unwrap_or(path).to_owned(),
+				instance: parent.unwrap_or_default().to_owned(),
+				exact: false,
+				middleware: None,
+			});
+		}
+	}
+
+	Some(dedup(candidates, limit))
+}
+
+/// Finds candidates by mapping the project tree to the files on disk the
+/// same way the project middleware does. Instance paths are resolved to their
+/// files or closest existing ancestor, classes to directories that already
+/// contain instances of the class
+pub fn suggest_paths(
+	project: &Project,
+	instance: Option<&str>,
+	class: Option<&str>,
+	limit: usize,
+) -> Vec<PathCandidate> {
+	let context = Meta::from_project(project).context;
+	let mut candidates = vec![];
+
+	if let Some(instance) = instance {
+		candidates.extend(suggest_instance(project, &split_instance(instance), &context));
+	}
+
+	if let Some(class) = class {
+		suggest_class(project, &project.node, &[], class, &context, limit, &mut candidates);
+	}
+
+	dedup(candidates, limit)
+}
+
+fn suggest_instance(project: &Project, segments: &[&str], context: &Context) -> Option<PathCandidate> {
+	let mut node = &project.node;
+	let mut consumed = 0;
+
+	// Paths can start with the name of the root
+	let segments = match segments.first() {
+		Some(first) if *first == project.name && !node.tree.contains_key(*first) => &segments[1..],
+		_ => segments,
+	};
+
+	while let Some(child) = segments.get(consumed).and_then(|segment| node.tree.get(*segment)) {
+		node = child;
+		consumed += 1;
+	}
+
+	let Some(path) = node.path.as_ref().map(|path| validation::resolve_path(project, path)) else {
+		// Instance can only be added as a new node of the project
+		return Some(PathCandidate {
+			path: project.path.clone(),
+			instance: segments[..consumed].join("/"),
+			exact: consumed == segments.len(),
+			middleware: Some(Middleware::Project.to_string()),
+		});
+	};
+
+	if !path.exists() {
+		return None;
+	}
+
+	let mut path = path;
+
+	while consumed < segments.len() {
+		let Some(child) = (path.is_dir())
+			.then(|| children(&path, context))
+			.into_iter()
+			.flatten()
+			.find(|(_, name)| name == segments[consumed])
+		else {
+			break;
+		};
+
+		path = child.0;
+		consumed += 1;
+	}
+
+	Some(PathCandidate {
+		middleware: middleware_of(&path, context),
+		path,
+		instance: segments[..consumed].join("/"),
+		exact: consumed == segments.len(),
+	})
+}
+
+#[allow(clippy::too_many_arguments)]
+fn suggest_class(
+	project: &Project,
+	node: &ProjectNode,
+	instance: &[String],
+	class: &str,
+	context: &Context,
+	limit: usize,
+	candidates: &mut Vec<PathCandidate>,
+) {
+	if candidates.len() >= limit {
+		return;
+	}
+
+	if let Some(path) = &node.path {
+		let path = validation::resolve_path(project, path);
+
+		if path.is_dir() {
+			find_class(&path, instance.to_vec(), class, context, limit, candidates);
+		}
+	}
+
+	for (name, child) in &node.tree {
+		let mut instance = instance.to_vec();
+		instance.push(name.to_owned());
+
+		if child.class_name.is_some_and(|child_class| child_class == class) {
+			candidates.push(PathCandidate {
+				path: project.path.clone(),
+				instance: instance[..instance.len() - 1].join("/"),
+				exact: false,
+				middleware: Some(Middleware::Project.to_string()),
+			});
+		}
+
+		suggest_class(project, child, &instance, class, context, limit, candidates);
+	}
+}
+
+fn find_class(
+	dir: &Path,
+	instance: Vec<String>,
+	class: &str,
+	context: &Context,
+	limit: usize,
+	candidates: &mut Vec<PathCandidate>,
+) {
+	let Some(middleware) = Middleware::from_class(class, None) else {
+		return;
+	};
+
+	for (path, name) in children(dir, context) {
+		if candidates.len() >= limit {
+			return;
+		}
+
+		if middleware_of(&path, context).is_some_and(|found| found == middleware.to_string()) {
+			candidates.push(PathCandidate {
+				path: dir.to_owned(),
+				instance: instance.join("/"),
+				exact: false,
+				middleware: middleware_of(dir, context),
+			});
+		}
+
+		if path.is_dir() {
+			let mut instance = instance.clone();
+			instance.push(name);
+
+			find_class(&path, instance, class, context, limit, candidates);
+		}
+	}
+}
+
+fn skeleton_node(
+	project: &Project,
+	name: &str,
+	node: &ProjectNode,
+	node_path: &str,
+	context: &Context,
+) -> SkeletonNode {
+	let path = node.path.as_ref().map(|path| validation::resolve_path(project, path));
+
+	SkeletonNode {
+		name: name.to_owned(),
+		node: node_path.to_owned(),
+		class_name: node.class_name.map(|class| class.to_string()),
+		optional: matches!(node.path, Some(ProjectPath::Optional { .. })),
+		exists: path.as_ref().map(|path| path.exists()),
+		middleware: path.as_ref().and_then(|path| middleware_of(path, context)),
+		profiles: node.profiles.clone(),
+		children: node
+			.tree
+			.iter()
+			.filter(|(_, child)| !child.implicit)
+			.map(|(name, child)| skeleton_node(project, name, child, &format!("{}/{}", node_path, name), context))
+			.collect(),
+		path,
+	}
+}
+
+/// Returns children of the directory with names of instances they are
+/// synced to, ignored files and ones describing the directory are left out
+fn children(dir: &Path, context: &Context) -> Vec<(PathBuf, String)> {
+	let Ok(entries) = fs::read_dir(dir) else {
+		return vec![];
+	};
+
+	let mut children: Vec<(PathBuf, String)> = entries
+		.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		.filter(|path| !BLACKLISTED_PATHS.iter().any(|blacklisted| path.ends_with(blacklisted)))
+		.filter(|path| !context.ignore_rules().iter().any(|rule| rule.matches(path)))
+		.filter_map(|path| {
+			if path.is_dir() {
+				let name = path.file_name()?.to_string_lossy().to_string();
+				return Some((path, name));
+			}
+
+			if context.sync_rules().any(|rule| rule.matches_child(&path)) {
+				return None;
+			}
+
+			let name = context.sync_rules().find_map(|rule| rule.resolve(&path))?.name;
+
+			Some((path, name))
+		})
+		.collect();
+
+	children.sort();
+	children
+}
+
+/// Returns middleware of the existing path, `Directory` for directories without a child file
+fn middleware_of(path: &Path, context: &Context) -> Option<String> {
+	if path.is_file() {
+		return context
+			.sync_rules()
+			.find_map(|rule| rule.resolve(path))
+			.map(|resolved| resolved.middleware.to_string());
+	}
+
+	if !path.is_dir() {
+		return None;
+	}
+
+	let child = fs::read_dir(path)
+		.ok()?
+		.filter_map(|entry| entry.ok())
+		.find_map(|entry| context.sync_rules().find_map(|rule| rule.resolve_child(&entry.path())));
+
+	Some(child.map_or_else(|| String::from("Directory"), |resolved| resolved.middleware.to_string()))
+}
+
+fn split_instance(instance: &str) -> Vec<&str> {
+	instance.split('/').filter(|segment| !segment.is_empty()).collect()
+}
+
+/// Removes name of the root from instance path reported by `/find`
+fn strip_root(path: &str) -> &str {
+	path.split_once('/').map_or("", |(_, path)| path)
+}
+
+fn dedup(candidates: Vec<PathCandidate>, limit: usize) -> Vec<PathCandidate> {
+	let mut seen = HashSet::new();
+
+	candidates
+		.into_iter()
+		.filter(|candidate| seen.insert(candidate.path.clone()))
+		.take(limit)
+		.collect()
+}
+
+/// Applies JSON merge patch as described in RFC 7386
+fn merge_patch(target: &mut Value, patch: &Value) {
+	let Value::Object(patch) = patch else {
+		*target = patch.clone();
+		return;
+	};
+
+	if !target.is_object() {
+		*target = json!({});
+	}
+
+	let target = target.as_object_mut().unwrap();
+
+	for (key, value) in patch {
+		if value.is_null() {
+			target.remove(key);
+		} else {
+			merge_patch(target.entry(key.to_owned()).or_insert(Value::Null), value);
+		}
+	}
+}
+
+/// Resolves project of the MCP tool, relative to the working directory of the server
+pub fn load_project(project: Option<&str>) -> Result<Project> {
+	let path = crate::project::resolve(PathBuf::from(project.unwrap_or_default()))?;
+
+	if !path.exists() {
+		bail!("No project files found at {}", path.display());
+	}
+
+	Project::load(&path)
+}
@@ -2,9 +2,14 @@ use anyhow::Result;
 use csv::{ReaderBuilder, WriterBuilder};
 use rbx_dom_weak::{types::Variant, HashMapExt, Ustr, UstrMap};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::Path};
+use std::{collections::BTreeMap, path::Path};
 
-use crate::{core::snapshot::Snapshot, vfs::Vfs, Properties};
+use crate::{
+	core::{meta::Context, snapshot::Snapshot},
+	middleware::{external::MiddlewareImpl, helpers},
+	vfs::Vfs,
+	Properties,
+};
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 struct LocalizationEntry {
@@ -12,7 +17,8 @@ struct LocalizationEntry {
 	context: Option<String>,
 	example: Option<String>,
 	source: Option<String>,
-	values: HashMap<String, String>,
+	/// Sorted so `Contents` is the same for every read
+	values: BTreeMap<String, String>,
 }
 
 #[profiling::function]
@@ -70,9 +76,11 @@ pub fn read_csv(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
 }
 
 #[profiling::function]
-pub fn write_csv(mut properties: Properties, path: &Path, vfs: &Vfs) -> Result<Properties> {
-	if let Some(Variant::String(contents)) = properties.remove(&Ustr::from("Contents")) {
-		let entries: Vec<LocalizationEntry> = serde_json::from_str(&contents)?;
+pub fn write_csv(properties: &Properties, path: &Path, vfs: &Vfs) -> Result<Properties> {
+	let contents_name = Ustr::from("Contents");
+
+	if let Some(Variant::String(contents)) = properties.get(&contents_name) {
+		let entries: Vec<LocalizationEntry> = serde_json::from_str(contents)?;
 		let mut contents = Vec::new();
 
 		let mut writer = WriterBuilder::new()
@@ -103,5 +111,18 @@ pub fn write_csv(mut properties: Properties, path: &Path, vfs: &Vfs) -> Result<P
 		vfs.write(path, &contents)?;
 	}
 
-	Ok(properties)
+	Ok(helpers::without(properties, contents_name))
+}
+
+/// CSV file read as `LocalizationTable`
+pub struct CsvMiddleware;
+
+impl MiddlewareImpl for CsvMiddleware {
+	fn read(&self, path: &Path, _context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+		read_csv(path, vfs)
+	}
+
+	fn write(&self, properties: &Properties, path: &Path, vfs: &Vfs) -> Result<Properties> {
+		write_csv(properties, path, vfs)
+	}
 }
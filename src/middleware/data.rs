@@ -1,27 +1,38 @@
-use anyhow::Result;
+use anyhow::{bail, Context as _, Result};
 use json_formatter::JsonFormatter;
 use log::error;
-use rbx_dom_weak::{types::Tags, HashMapExt, Ustr, UstrMap};
-use serde::{Deserialize, Serialize};
-use serde_json::Serializer;
+use path_clean::PathClean;
+use rbx_dom_weak::{
+	types::{Tags, Variant},
+	HashMapExt, Ustr, UstrMap,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Serializer, Value};
 use std::{
 	collections::{BTreeMap, HashMap},
 	path::{Path, PathBuf},
 };
 
 use crate::{
-	core::meta::Meta,
+	constants::MAX_INCLUDE_DEPTH,
+	core::{
+		meta::{Context, Meta},
+		refs::{self, RefPaths},
+	},
 	ext::{PathExt, WriterExt},
 	middleware::helpers,
-	resolution::UnresolvedValue,
-	util,
+	project::{DataFormat, DataIncludes, KeepUnknowns},
+	resolution::{FileRef, UnresolvedValue},
 	vfs::Vfs,
 	Properties,
 };
 
+const INCLUDE_KEY: &str = "$include";
+const FILE_KEY: &str = "$file";
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct Data {
+pub(crate) struct Data {
 	class_name: Option<Ustr>,
 
 	#[serde(default)]
@@ -31,7 +42,7 @@ struct Data {
 	tags: Vec<String>,
 
 	#[serde(alias = "ignoreUnknownInstances", default)]
-	keep_unknowns: Option<bool>,
+	keep_unknowns: Option<KeepUnknowns>,
 	#[serde(default)]
 	original_name: Option<String>,
 }
@@ -41,61 +52,90 @@ pub struct DataSnapshot {
 	pub path: PathBuf,
 	pub class: Option<Ustr>,
 	pub properties: Properties,
-	pub keep_unknowns: Option<bool>,
+	pub keep_unknowns: Option<KeepUnknowns>,
 	pub original_name: Option<String>,
 	pub mesh_source: Option<String>,
+	/// Paths of instances that `Ref` properties point at,
+	/// they can only be resolved once the whole tree is read
+	pub ref_paths: RefPaths,
+	/// Files that properties of the data file are included from
+	pub includes: Vec<PathBuf>,
+	/// Files that string properties are read from with `$file`
+	pub references: Vec<PathBuf>,
+	/// Key of the entry in the data index at `path`, `None` for data files
+	pub key: Option<String>,
 }
 
 #[profiling::function]
-pub fn read_data(path: &Path, class: Option<&str>, vfs: &Vfs) -> Result<DataSnapshot> {
+pub fn read_data(path: &Path, class: Option<&str>, context: &Context, vfs: &Vfs) -> Result<DataSnapshot> {
 	let data = vfs.read_to_string(path)?;
 
 	if data.is_empty() {
 		return Ok(DataSnapshot::default());
 	}
 
-	let data: Data = serde_json::from_str(&data)?;
+	let mut includes = vec![];
 
-	let mut properties = UstrMap::new();
+	// Most data files have no includes so they can skip the intermediate value
+	let data: Data = if data.contains(INCLUDE_KEY) {
+		let mut value = parse_value(path, &data)?;
 
-	let class = if let Some(class) = class.or(data.class_name.as_deref()) {
-		class.to_owned()
+		includes = find_includes(&value, path).into_values().collect();
+		resolve_includes(&mut value, path, &mut vec![path.clean()], vfs)?;
+
+		serde_json::from_value(value)?
 	} else {
-		let name = path.get_name();
+		parse(path, &data)?
+	};
 
-		if util::is_service(name) {
-			name.to_owned()
-		} else {
-			let parent_name = path.get_parent().get_name();
+	let mut snapshot = resolve_data(data, path, class, context, vfs);
+	snapshot.includes = includes;
 
-			if util::is_service(parent_name) {
-				parent_name.to_owned()
-			} else {
-				String::from("Folder")
-			}
-		}
-	};
+	Ok(snapshot)
+}
+
+/// Resolves properties of parsed instance data, `path` is
+/// the file it was read from and `$file` references are relative to
+pub(crate) fn resolve_data(data: Data, path: &Path, class: Option<&str>, context: &Context, vfs: &Vfs) -> DataSnapshot {
+	let mut properties = UstrMap::new();
+	let mut ref_paths = RefPaths::new();
+	let mut references = vec![];
+
+	// Explicit class of the data file wins over the one of its instance
+	let class = data.class_name.as_deref().or(class).unwrap_or("Folder");
 
 	// Resolve properties
 	for (property, value) in data.properties {
-		match value.resolve(&class, &property) {
+		if let Some(path) = value.as_str().filter(|_| refs::is_ref(class, &property)) {
+			ref_paths.insert(property, path.to_owned());
+			continue;
+		}
+
+		let value = match value {
+			UnresolvedValue::File(file) => {
+				helpers::resolve_file_ref(&file, class, &property, path, context, vfs, &mut references)
+			}
+			value => value.resolve_checked(class, &property, context.property_ranges(), Some(path)),
+		};
+
+		match value {
 			Ok(value) => {
 				properties.insert(property, value);
 			}
 			Err(err) => {
-				error!("Failed to parse property: {} at {}", err, path.display());
+				error!("Failed to parse property: {}", err);
 			}
 		}
 	}
 
 	// Resolve attributes
 	if let Some(attributes) = data.attributes {
-		match attributes.resolve(&class, "Attributes") {
+		match attributes.resolve(class, "Attributes", Some(path)) {
 			Ok(value) => {
 				properties.insert(Ustr::from("Attributes"), value);
 			}
 			Err(err) => {
-				error!("Failed to parse attributes: {} at {}", err, path.display());
+				error!("Failed to parse attributes: {}", err);
 			}
 		}
 	}
@@ -111,88 +151,504 @@ pub fn read_data(path: &Path, class: Option<&str>, vfs: &Vfs) -> Result<DataSnap
 		None
 	};
 
-	Ok(DataSnapshot {
+	DataSnapshot {
 		path: path.to_owned(),
 		class: data.class_name,
 		properties,
 		keep_unknowns: data.keep_unknowns,
 		original_name: data.original_name,
 		mesh_source,
+		ref_paths,
+		references,
+		..DataSnapshot::default()
+	}
+}
+
+/// Replaces `{"$include": "file"}` objects with the contents of the files,
+/// relative to the including file, `stack` holds files that are being included
+fn resolve_includes(value: &mut Value, path: &Path, stack: &mut Vec<PathBuf>, vfs: &Vfs) -> Result<()> {
+	match value {
+		Value::Object(object) => {
+			if let Some(include) = as_include(object) {
+				let include_path = path.get_parent().join(include).clean();
+
+				if let Some(index) = stack.iter().position(|path| *path == include_path) {
+					let cycle = stack[index..]
+						.iter()
+						.chain([&include_path])
+						.map(|path| path.display().to_string())
+						.collect::<Vec<_>>();
+
+					bail!("Cyclic data file include: {}", cycle.join(" -> "));
+				}
+
+				if stack.len() > MAX_INCLUDE_DEPTH {
+					bail!(
+						"Data file includes are nested more than {} levels deep at {}",
+						MAX_INCLUDE_DEPTH,
+						include_path.display()
+					);
+				}
+
+				let mut included = parse_value(&include_path, &vfs.read_to_string(&include_path)?)?;
+
+				stack.push(include_path.clone());
+				resolve_includes(&mut included, &include_path, stack, vfs)?;
+				stack.pop();
+
+				*value = included;
+			} else {
+				for value in object.values_mut() {
+					resolve_includes(value, path, stack, vfs)?;
+				}
+			}
+		}
+		Value::Array(array) => {
+			for value in array {
+				resolve_includes(value, path, stack, vfs)?;
+			}
+		}
+		_ => {}
+	}
+
+	Ok(())
+}
+
+fn as_include(object: &Map<String, Value>) -> Option<&str> {
+	if object.len() == 1 {
+		object.get(INCLUDE_KEY)?.as_str()
+	} else {
+		None
+	}
+}
+
+/// Returns properties of the data file that are included from files
+/// named after the data file, these are owned by its instance
+fn find_includes(value: &Value, path: &Path) -> BTreeMap<Ustr, PathBuf> {
+	let Some(properties) = value.get("properties").and_then(Value::as_object) else {
+		return BTreeMap::new();
+	};
+
+	properties
+		.iter()
+		.filter_map(|(property, value)| {
+			let include = as_include(value.as_object()?)?;
+			let include_path = include_path(path, property);
+
+			(include_path.get_name() == include).then(|| (Ustr::from(property), include_path))
+		})
+		.collect()
+}
+
+/// Reads includes of the existing data file, see `find_includes`
+pub(crate) fn read_includes(path: &Path, vfs: &Vfs) -> BTreeMap<Ustr, PathBuf> {
+	if !vfs.is_file(path) {
+		return BTreeMap::new();
+	}
+
+	vfs.read_to_string(path)
+		.ok()
+		.filter(|data| data.contains(INCLUDE_KEY))
+		.and_then(|data| parse_value(path, &data).ok())
+		.map(|value| find_includes(&value, path))
+		.unwrap_or_default()
+}
+
+/// Reads `$file` references of properties of the existing data file
+fn read_file_refs(path: &Path, vfs: &Vfs) -> BTreeMap<Ustr, FileRef> {
+	if !vfs.is_file(path) {
+		return BTreeMap::new();
+	}
+
+	let Some(value) = vfs
+		.read_to_string(path)
+		.ok()
+		.filter(|data| data.contains(FILE_KEY))
+		.and_then(|data| parse_value(path, &data).ok())
+	else {
+		return BTreeMap::new();
+	};
+
+	let Some(properties) = value.get("properties").and_then(Value::as_object) else {
+		return BTreeMap::new();
+	};
+
+	properties
+		.iter()
+		.filter_map(|(property, value)| {
+			let file = serde_json::from_value::<FileRef>(value.clone()).ok()?;
+			Some((Ustr::from(property), file))
+		})
+		.collect()
+}
+
+/// Whether properties of the existing data file are read from other files
+pub fn has_file_refs(path: &Path, vfs: &Vfs) -> bool {
+	!read_file_refs(path, vfs).is_empty()
+}
+
+/// Writes value of the string property back to the file it is read from,
+/// relative to `origin`, the file is left untouched if nothing changed
+pub fn write_file_ref(origin: &Path, file: &FileRef, contents: &str, vfs: &Vfs) -> Result<()> {
+	let path = origin.get_parent().join(&file.file).clean();
+
+	if vfs.is_file(&path) && vfs.read_to_string(&path)? == contents {
+		return Ok(());
+	}
+
+	vfs.write(&path, contents.as_bytes())?;
+
+	Ok(())
+}
+
+/// Returns path of the file that `property` of the data file is
+/// included from, e.g. `Name.Attributes.data.json` for `Name.data.json`
+pub fn include_path(path: &Path, property: &str) -> PathBuf {
+	let (stem, suffix) = split_name(path);
+	path.with_file_name(format!("{}.{}{}", stem, property, suffix))
+}
+
+/// Whether the file is one of the included files of the data file
+pub fn is_include(data_path: &Path, path: &Path) -> bool {
+	let (stem, suffix) = split_name(data_path);
+
+	path.parent() == data_path.parent()
+		&& path
+			.get_name()
+			.strip_prefix(stem)
+			.and_then(|name| name.strip_prefix('.'))
+			.and_then(|name| name.strip_suffix(suffix))
+			.is_some_and(|property| !property.is_empty())
+}
+
+/// Splits name of the data file into its stem and suffix, e.g. `Name` and `.data.json`
+fn split_name(path: &Path) -> (&str, &str) {
+	let name = path.get_name();
+
+	[".data.json", ".meta.json", ".data.yaml", ".data.yml"]
+		.into_iter()
+		.find_map(|suffix| name.strip_suffix(suffix).map(|stem| (stem, suffix)))
+		.or_else(|| name.rfind('.').map(|index| name.split_at(index)))
+		.unwrap_or((name, ""))
+}
+
+/// Moves the data file along with its included files and updates
+/// references to them, returns new paths of all moved files
+pub fn move_data(from: &Path, to: &Path, vfs: &Vfs) -> Result<Vec<PathBuf>> {
+	let includes = read_includes(from, vfs);
+	let mut paths = vec![to.to_owned()];
+
+	vfs.rename(from, to)?;
+
+	for (property, include_path) in &includes {
+		let new_path = self::include_path(to, property);
+
+		if vfs.exists(include_path) {
+			vfs.rename(include_path, &new_path)?;
+		}
+
+		paths.push(new_path);
+	}
+
+	if !includes.is_empty() {
+		relink_includes(to, vfs)?;
+	}
+
+	Ok(paths)
+}
+
+/// Points `$include` references of the data file that no longer exist
+/// to the included files named after the data file, used after renaming
+pub fn relink_includes(path: &Path, vfs: &Vfs) -> Result<()> {
+	let mut data = vfs.read_to_string(path)?;
+	let value = parse_value(path, &data)?;
+
+	let Some(properties) = value.get("properties").and_then(Value::as_object) else {
+		return Ok(());
+	};
+
+	let mut changed = false;
+
+	for (property, value) in properties {
+		let Some(include) = value.as_object().and_then(as_include) else {
+			continue;
+		};
+
+		let include_path = self::include_path(path, property);
+
+		// Replacing the raw string keeps formatting (and YAML comments) of the file intact
+		if !vfs.exists(&path.get_parent().join(include)) && vfs.exists(&include_path) {
+			let new_include = include_path.get_name();
+
+			data = match DataFormat::of(path) {
+				DataFormat::Json => {
+					data.replace(&serde_json::to_string(include)?, &serde_json::to_string(new_include)?)
+				}
+				// YAML strings don't have to be quoted
+				DataFormat::Yaml => data
+					.replace(&format!("\"{}\"", include), &format!("\"{}\"", new_include))
+					.replace(&format!("'{}'", include), &format!("'{}'", new_include))
+					.replace(&format!(" {}\n", include), &format!(" {}\n", new_include)),
+			};
+			changed = true;
+		}
+	}
+
+	if changed {
+		vfs.write(path, data.as_bytes())?;
+	}
+
+	Ok(())
+}
+
+/// Whether the property should be written to an included file, properties
+/// that are already included stay that way so syncbacks don't move them around
+fn should_include(property: &str, value: &UnresolvedValue, included: bool, settings: &DataIncludes) -> bool {
+	included
+		|| settings.properties.iter().any(|name| name == property)
+		|| settings
+			.threshold
+			.is_some_and(|threshold| serde_json::to_vec(value).is_ok_and(|value| value.len() >= threshold))
+}
+
+/// Parses the data file as JSON or YAML depending on its extension
+fn parse<T: DeserializeOwned>(path: &Path, data: &str) -> Result<T> {
+	match DataFormat::of(path) {
+		DataFormat::Json => Ok(serde_json::from_str(data)?),
+		DataFormat::Yaml => Ok(serde_json::from_value(parse_value(path, data)?)?),
+	}
+}
+
+/// Parses the data file into JSON value, so YAML files go through
+/// the same include and `$file` handling as the JSON ones
+fn parse_value(path: &Path, data: &str) -> Result<Value> {
+	match DataFormat::of(path) {
+		DataFormat::Json => Ok(serde_json::from_str(data)?),
+		DataFormat::Yaml => yaml_to_json(serde_yaml_ng::from_str(data)?),
+	}
+}
+
+/// Mapping keys that YAML reads as other scalars, like `1` or `true`, are kept
+/// as strings since they are property and attribute names in data files
+fn yaml_to_json(value: serde_yaml_ng::Value) -> Result<Value> {
+	use serde_yaml_ng::Value as Yaml;
+
+	Ok(match value {
+		Yaml::Null => Value::Null,
+		Yaml::Bool(bool) => Value::Bool(bool),
+		Yaml::Number(number) => {
+			if let Some(int) = number.as_i64() {
+				Value::from(int)
+			} else if let Some(uint) = number.as_u64() {
+				Value::from(uint)
+			} else {
+				number
+					.as_f64()
+					.and_then(serde_json::Number::from_f64)
+					.map(Value::Number)
+					.with_context(|| format!("Unsupported number in YAML data file: {}", number))?
+			}
+		}
+		Yaml::String(string) => Value::String(string),
+		Yaml::Sequence(sequence) => Value::Array(sequence.into_iter().map(yaml_to_json).collect::<Result<_>>()?),
+		Yaml::Mapping(mapping) => Value::Object(
+			mapping
+				.into_iter()
+				.map(|(key, value)| {
+					let key = match key {
+						Yaml::String(string) => string,
+						Yaml::Bool(bool) => bool.to_string(),
+						Yaml::Number(number) => number.to_string(),
+						Yaml::Null => String::from("null"),
+						key => bail!("Unsupported key in YAML data file: {:?}", key),
+					};
+
+					Ok((key, yaml_to_json(value)?))
+				})
+				.collect::<Result<_>>()?,
+		),
+		Yaml::Tagged(tagged) => yaml_to_json(tagged.value)?,
 	})
 }
 
+/// Writes the data file in the format of its extension, see `DataFormat::of`
+pub(crate) fn write_value(path: &Path, value: &impl Serialize, vfs: &Vfs) -> Result<()> {
+	if DataFormat::of(path) == DataFormat::Yaml {
+		vfs.write(path, serde_yaml_ng::to_string(value)?.as_bytes())?;
+		return Ok(());
+	}
+
+	let formatter = JsonFormatter::with_array_breaks(false);
+
+	let mut writer = Vec::new();
+	let mut serializer = Serializer::with_formatter(&mut writer, formatter);
+
+	value.serialize(&mut serializer)?;
+	writer.end()?;
+
+	vfs.write(path, &writer)?;
+
+	Ok(())
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(untagged)]
+enum WritableValue {
+	Include {
+		#[serde(rename = "$include")]
+		include: String,
+	},
+	Value(UnresolvedValue),
+}
+
 #[derive(Debug, Default, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 struct WritableData {
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub class_name: Option<Ustr>,
 	#[serde(skip_serializing_if = "BTreeMap::is_empty")]
-	pub properties: BTreeMap<Ustr, UnresolvedValue>,
+	pub properties: BTreeMap<Ustr, WritableValue>,
 
 	#[serde(skip_serializing_if = "Option::is_none")]
-	pub keep_unknowns: Option<bool>,
+	pub keep_unknowns: Option<KeepUnknowns>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub original_name: Option<String>,
 }
 
+/// Writes instance data, properties selected by the `dataIncludes` project setting
+/// are written to included files, returns paths of the data file and its included
+/// files or nothing if there is no data to write (existing files are removed then)
 #[profiling::function]
-pub fn write_data<'a>(
+pub fn write_data(
 	has_file: bool,
 	class: &str,
 	properties: Properties,
-	path: &'a Path,
+	path: &Path,
 	meta: &Meta,
 	vfs: &Vfs,
-) -> Result<Option<&'a Path>> {
+) -> Result<Vec<PathBuf>> {
 	let class_name = if !has_file && class != "Folder" {
 		Some(Ustr::from(class))
 	} else {
 		None
 	};
 
-	let properties = properties
-		.iter()
-		.map(|(property, variant)| {
-			(
-				*property,
-				UnresolvedValue::from_variant(variant.clone(), class, property),
-			)
-		})
-		.collect();
+	let existing = read_includes(path, vfs);
+	let file_refs = read_file_refs(path, vfs);
+	let settings = meta.context.data_includes();
 
 	let mut data = WritableData {
 		class_name,
-		properties,
 		..WritableData::default()
 	};
 
+	let mut includes = vec![];
+
+	for (property, variant) in refs::portable(properties, &meta.ref_paths) {
+		// String properties read from other files are written back to them
+		if let (Some(file), Variant::String(contents)) = (file_refs.get(&property), &variant) {
+			write_file_ref(path, file, contents, vfs)?;
+
+			data.properties
+				.insert(property, WritableValue::Value(UnresolvedValue::File(file.clone())));
+
+			continue;
+		}
+
+		let value = UnresolvedValue::from_variant(variant, class, &property);
+
+		let is_included = settings
+			.is_some_and(|settings| should_include(&property, &value, existing.contains_key(&property), settings));
+
+		let value = if is_included {
+			let include_path = include_path(path, &property);
+			let include = include_path.get_name().to_owned();
+
+			includes.push((include_path, value));
+
+			WritableValue::Include { include }
+		} else {
+			WritableValue::Value(value)
+		};
+
+		data.properties.insert(property, value);
+	}
+
 	if meta.keep_unknowns {
-		data.keep_unknowns = Some(true);
+		data.keep_unknowns = Some(meta.get_keep_unknowns());
 	}
 
 	if let Some(original_name) = meta.original_name.as_ref() {
 		data.original_name = Some(original_name.to_owned());
 	}
 
+	// Properties that are no longer included or no longer exist
+	for (property, include_path) in &existing {
+		if !matches!(data.properties.get(property), Some(WritableValue::Include { .. })) && vfs.exists(include_path) {
+			vfs.remove(include_path)?;
+		}
+	}
+
 	if data == WritableData::default() {
 		if vfs.exists(path) {
 			vfs.remove(path)?;
 		}
 
+		return Ok(vec![]);
+	}
+
+	let mut paths = vec![path.to_owned()];
+
+	for (include_path, value) in includes {
+		write_value(&include_path, &value, vfs)?;
+		paths.push(include_path);
+	}
+
+	write_value(path, &data, vfs)?;
+
+	Ok(paths)
+}
+
+/// Returns instance data the way it is stored in the data index, properties
+/// are never included from other files there, `None` if there is no data
+pub(crate) fn data_value(has_file: bool, class: &str, properties: Properties, meta: &Meta) -> Result<Option<Value>> {
+	let mut data = WritableData {
+		class_name: (!has_file && class != "Folder").then(|| Ustr::from(class)),
+		properties: refs::portable(properties, &meta.ref_paths)
+			.into_iter()
+			.map(|(property, variant)| {
+				let value = UnresolvedValue::from_variant(variant, class, &property);
+				(property, WritableValue::Value(value))
+			})
+			.collect(),
+		..WritableData::default()
+	};
+
+	if meta.keep_unknowns {
+		data.keep_unknowns = Some(meta.get_keep_unknowns());
+	}
+
+	data.original_name.clone_from(&meta.original_name);
+
+	if data == WritableData::default() {
 		return Ok(None);
 	}
 
-	let formatter = JsonFormatter::with_array_breaks(false);
+	Ok(Some(serde_json::to_value(data)?))
+}
 
-	let mut writer = Vec::new();
-	let mut serializer = Serializer::with_formatter(&mut writer, formatter);
+/// Reads the data file as a single value with its includes resolved
+pub(crate) fn read_value(path: &Path, vfs: &Vfs) -> Result<Value> {
+	let data = vfs.read_to_string(path)?;
 
-	data.serialize(&mut serializer)?;
-	writer.end()?;
+	if data.trim().is_empty() {
+		return Ok(Value::Object(Map::new()));
+	}
 
-	vfs.write(path, &writer)?;
+	let mut value = parse_value(path, &data)?;
+	resolve_includes(&mut value, path, &mut vec![path.clean()], vfs)?;
 
-	Ok(Some(path))
+	Ok(value)
 }
 
 #[profiling::function]
@@ -204,7 +660,7 @@ pub fn write_original_name(path: &Path, meta: &Meta, vfs: &Vfs) -> Result<()> {
 			return Ok(());
 		}
 
-		let data: Data = serde_json::from_str(&data)?;
+		let data: Data = parse(path, &data)?;
 
 		if data.original_name == meta.original_name {
 			return Ok(());
@@ -212,7 +668,11 @@ pub fn write_original_name(path: &Path, meta: &Meta, vfs: &Vfs) -> Result<()> {
 
 		let data = WritableData {
 			class_name: data.class_name,
-			properties: data.properties.into_iter().collect(),
+			properties: data
+				.properties
+				.into_iter()
+				.map(|(property, value)| (property, WritableValue::Value(value)))
+				.collect(),
 			keep_unknowns: data.keep_unknowns,
 			original_name: meta.original_name.clone(),
 		};
@@ -236,15 +696,5 @@ pub fn write_original_name(path: &Path, meta: &Meta, vfs: &Vfs) -> Result<()> {
 		data
 	};
 
-	let formatter = JsonFormatter::with_array_breaks(false);
-
-	let mut writer = Vec::new();
-	let mut serializer = Serializer::with_formatter(&mut writer, formatter);
-
-	data.serialize(&mut serializer)?;
-	writer.end()?;
-
-	vfs.write(path, &writer)?;
-
-	Ok(())
+	write_value(path, &data, vfs)
 }
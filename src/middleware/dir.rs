@@ -1,30 +1,49 @@
 use anyhow::Result;
-use std::path::Path;
+use colored::Colorize;
+use rbx_dom_weak::Ustr;
+use std::{collections::HashMap, path::Path};
 
-use super::new_snapshot;
+use super::new_snapshots_isolated;
 use crate::{
+	argon_warn,
 	core::{
 		meta::{Context, Meta, Source},
 		snapshot::Snapshot,
 	},
 	ext::PathExt,
+	t,
 	vfs::Vfs,
 };
 
+/// Children are read knowing that their parent is of the given `class`
 #[profiling::function]
-pub fn read_dir(path: &Path, context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+pub fn read_dir(path: &Path, class: Ustr, context: &Context, vfs: &Vfs) -> Result<Snapshot> {
 	let name = path.get_name();
 
 	let mut snapshot = Snapshot::new()
 		.with_name(name)
+		.with_class(class)
 		.with_meta(Meta::new().with_context(context).with_source(Source::directory(path)));
 
-	for path in vfs.read_dir(path)? {
-		if let Some(child_snapshot) = new_snapshot(&path, context, vfs)? {
+	if context.is_shallow() {
+		return Ok(snapshot);
+	}
+
+	let child_context = context.with_parent_class(class);
+	let paths = vfs.read_dir(path)?;
+
+	if !paths.is_empty() {
+		context.check_depth(1, path.to_string())?;
+	}
+
+	for path in paths {
+		for child_snapshot in new_snapshots_isolated(&path, &child_context, vfs)? {
 			snapshot.add_child(child_snapshot);
 		}
 	}
 
+	warn_duplicate_names(&snapshot.children);
+
 	Ok(snapshot)
 }
 
@@ -34,3 +53,31 @@ pub fn write_dir(path: &Path, vfs: &Vfs) -> Result<()> {
 
 	Ok(())
 }
+
+/// Siblings renamed during syncback keep their name in `originalName`
+/// on purpose, every other pair of files with the same name is reported
+fn warn_duplicate_names(children: &[Snapshot]) {
+	let mut names: HashMap<&str, &Path> = HashMap::new();
+
+	for child in children {
+		if child.meta.original_name.is_some() {
+			continue;
+		}
+
+		let Some(path) = child.meta.source.get().path() else {
+			continue;
+		};
+
+		if let Some(first) = names.insert(&child.name, path) {
+			argon_warn!(
+				"{}",
+				t!(
+					"snapshot.duplicate_names",
+					first = first.to_string().bold(),
+					second = path.to_string().bold(),
+					name = child.name.bold()
+				)
+			);
+		}
+	}
+}
@@ -0,0 +1,81 @@
+use anyhow::{bail, Result};
+use std::{
+	collections::HashMap,
+	path::Path,
+	sync::{Arc, OnceLock, RwLock},
+};
+
+use super::Middleware;
+use crate::{
+	core::{
+		meta::{Context, SyncRule},
+		snapshot::Snapshot,
+	},
+	vfs::Vfs,
+	Properties,
+};
+
+/// Middleware that reads and writes files of one format, implemented by all built-in
+/// ones and by custom middleware registered with `CoreBuilder::middleware`,
+/// which is used through `Middleware::External`
+///
+/// Implementations are shared by all threads of the process: the initial snapshot
+/// is created on the thread that builds the core and later changes are processed
+/// on its processor thread. Calls made by a single core never overlap, as they all
+/// happen while its tree is locked, but multiple cores (e.g. members of a workspace)
+/// may call the same implementation at the same time
+pub trait MiddlewareImpl: Send + Sync {
+	/// Returns snapshot of the file, its name, source and instance data are set by the caller
+	fn read(&self, path: &Path, context: &Context, vfs: &Vfs) -> Result<Snapshot>;
+
+	/// Writes file-backed properties to the given path, returns remaining properties
+	/// that belong to the data file. Middleware that only reads files can leave it out
+	fn write(&self, _properties: &Properties, _path: &Path, _vfs: &Vfs) -> Result<Properties> {
+		bail!("Middleware does not support writing")
+	}
+}
+
+#[derive(Default)]
+struct Registry {
+	implementations: HashMap<String, Arc<dyn MiddlewareImpl>>,
+	rules: Vec<SyncRule>,
+}
+
+fn registry() -> &'static RwLock<Registry> {
+	static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+	REGISTRY.get_or_init(RwLock::default)
+}
+
+/// Registers implementation of the `External` middleware of the rule, registering the same
+/// ID again replaces both. Rules are checked in the order of registration and before
+/// the project ones, so they can claim files that built-in rules would match too,
+/// e.g. `*.upper.txt` that would otherwise be read as a regular `*.txt` file
+pub fn register(rule: SyncRule, implementation: Arc<dyn MiddlewareImpl>) -> Result<()> {
+	let Middleware::External(id) = &rule.middleware else {
+		bail!(
+			"Sync rule of custom middleware has to be of `External` type, got {}",
+			rule.middleware
+		);
+	};
+
+	let mut registry = registry().write().unwrap();
+
+	registry.implementations.insert(id.to_owned(), implementation);
+	registry.rules.retain(|existing| existing.middleware != rule.middleware);
+	registry.rules.push(rule);
+
+	Ok(())
+}
+
+/// Returns implementation registered under the ID
+pub fn get(id: &str) -> Result<Arc<dyn MiddlewareImpl>> {
+	match registry().read().unwrap().implementations.get(id) {
+		Some(implementation) => Ok(implementation.clone()),
+		None => bail!("Middleware {} is not registered", id),
+	}
+}
+
+/// Returns sync rules of all registered middleware
+pub fn sync_rules() -> Vec<SyncRule> {
+	registry().read().unwrap().rules.clone()
+}
@@ -0,0 +1,92 @@
+use anyhow::{bail, Result};
+use path_clean::PathClean;
+use rbx_dom_weak::types::Variant;
+use std::path::{Path, PathBuf};
+
+use crate::{
+	core::meta::Context,
+	middleware::Middleware,
+	resolution::{AmbiguousValue, FileRef, UnresolvedValue},
+	vfs::Vfs,
+};
+
+/// Reads value of the string property from the referenced file, its path is added
+/// to `references` so changes of the file are picked up, unless another middleware
+/// already reads the file as an instance on its own
+pub fn resolve(
+	file: &FileRef,
+	class: &str,
+	property: &str,
+	origin: &Path,
+	context: &Context,
+	vfs: &Vfs,
+	references: &mut Vec<PathBuf>,
+) -> Result<Variant> {
+	let path = origin.parent().unwrap_or(origin).join(&file.file).clean();
+
+	if path == origin.clean() {
+		bail!(
+			"Property `{}` of {} references the file it is defined in",
+			property,
+			origin.display()
+		);
+	}
+
+	if let Some(middleware) = get_claiming_middleware(&path, context) {
+		bail!(
+			"Property `{}` of {} references {} which is already read as {}",
+			property,
+			origin.display(),
+			path.display(),
+			middleware
+		);
+	}
+
+	references.push(path.clone());
+
+	if !vfs.is_file(&path) {
+		bail!(
+			"Property `{}` of {} references {} which does not exist",
+			property,
+			origin.display(),
+			path.display()
+		);
+	}
+
+	// Referenced files can live outside of the synced directories
+	vfs.watch(&path, false)?;
+
+	let contents = vfs.read_to_string(&path)?;
+	let value = UnresolvedValue::Ambiguous(AmbiguousValue::String(contents)).resolve_checked(
+		class,
+		property,
+		context.property_ranges(),
+		Some(&path),
+	)?;
+
+	if !matches!(value, Variant::String(_)) {
+		bail!(
+			"Property `{}` of {} is not a string so it cannot be read from {}",
+			property,
+			origin.display(),
+			path.display()
+		);
+	}
+
+	Ok(value)
+}
+
+/// Returns middleware of script and data files that are snapshotted by their
+/// sync rules, reading them as property values would claim them twice
+fn get_claiming_middleware(path: &Path, context: &Context) -> Option<Middleware> {
+	let middleware = context
+		.sync_rules()
+		.find_map(|rule| rule.resolve(path).or_else(|| rule.resolve_child(path)))?
+		.middleware;
+
+	matches!(
+		middleware,
+		Middleware::ServerScript | Middleware::ClientScript | Middleware::ModuleScript | Middleware::InstanceData
+	)
+	.then_some(middleware)
+}
@@ -0,0 +1,77 @@
+use colored::Colorize;
+use rbx_dom_weak::Ustr;
+use std::{
+	collections::HashSet,
+	sync::{Mutex, OnceLock},
+};
+
+use crate::{argon_warn, core::meta::Context, t, util};
+
+/// Instances that only have their class when placed
+/// directly in the given parent, `(parent, name)`
+const POSITIONAL: [(&str, &str); 3] = [
+	("StarterPlayer", "StarterPlayerScripts"),
+	("StarterPlayer", "StarterCharacterScripts"),
+	("Workspace", "Terrain"),
+];
+
+static REPORTED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+pub fn infer_class(name: &str, context: &Context) -> Ustr {
+	if let Some(class) = context.node_class() {
+		return Ustr::from(class);
+	}
+
+	let parent = context.parent_class();
+
+	for rule in context.inference_rules() {
+		if rule.name == name && rule.parent.as_ref().is_none_or(|class| Some(class.as_str()) == parent) {
+			return Ustr::from(&rule.class);
+		}
+	}
+
+	let Some(parent) = parent else {
+		return legacy_class(name);
+	};
+
+	// `util::is_service` also covers the positional `StarterPlayer` children
+	let is_positional = POSITIONAL.iter().any(|(_, positional)| *positional == name);
+	let is_service = parent == "DataModel" && !is_positional && util::is_service(name);
+
+	if is_service || POSITIONAL.contains(&(parent, name)) {
+		Ustr::from(name)
+	} else {
+		Ustr::from("Folder")
+	}
+}
+
+/// Class inferred from the name only, regardless of the instance position
+pub fn legacy_class(name: &str) -> Ustr {
+	if util::is_service(name) {
+		Ustr::from(name)
+	} else {
+		Ustr::from("Folder")
+	}
+}
+
+pub fn warn_reclassified(location: &str, legacy: &str, class: &str) {
+	if legacy == class {
+		return;
+	}
+
+	let mut reported = REPORTED.get_or_init(Default::default).lock().unwrap();
+
+	if !reported.insert(location.to_owned()) {
+		return;
+	}
+
+	argon_warn!(
+		"{}",
+		t!(
+			"inference.reclassified",
+			location = location.bold(),
+			legacy = legacy.bold(),
+			class = class.bold()
+		)
+	);
+}
@@ -2,22 +2,20 @@ use anyhow::Result;
 use log::{debug, error, trace};
 use rbx_dom_weak::{types::Variant, InstanceBuilder, Ustr, WeakDom};
 use roblox_install::RobloxStudio;
-use std::{
-	collections::HashMap,
-	fs::{self, File},
-	io::BufWriter,
-	path::Path,
-	process,
-	sync::RwLock,
-	thread,
-};
+use std::{collections::HashMap, fs, path::Path, process, sync::Once, thread};
 
-use crate::{ext::PathExt, util, Properties};
+use crate::{
+	ext::PathExt,
+	hooks::{self, ContentCache},
+	util, Properties,
+};
 
 const CUSTOM_MESH_PART_PROPERTIES: [&str; 2] = ["MeshContent", "InitialSize"];
 
-static INDEX: RwLock<u32> = RwLock::new(0);
+static CLEARED: Once = Once::new();
 
+/// Writes temporary model with custom mesh properties for Studio to load,
+/// identical meshes share one model named after its content hash
 pub fn save_mesh(properties: &Properties) -> Option<String> {
 	let mut mesh_properties: HashMap<&str, Variant> = HashMap::new();
 
@@ -39,36 +37,30 @@ pub fn save_mesh(properties: &Properties) -> Option<String> {
 		let pid = process::id().to_string();
 		let path = RobloxStudio::locate()?.content_path().join("argon").join(&pid);
 
-		let index = *INDEX.read().unwrap();
-
-		if index == 0 {
+		CLEARED.call_once(|| {
 			let path = path.clone();
 
 			thread::spawn(move || match clear(&path) {
 				Ok(_) => debug!("Cleared temporary mesh models"),
 				Err(err) => error!("Failed to clear temporary mesh models: {}", err),
 			});
-		}
+		});
 
-		if !path.exists() {
-			fs::create_dir_all(&path)?;
-		}
+		let mut model = vec![];
+		rbx_binary::to_writer(&mut model, &dom, &[dom.root_ref()])?;
 
-		let name = index.to_string();
-		let writer = BufWriter::new(File::create(path.join(&name))?);
+		let cache = ContentCache::new(&path);
+		let name = hooks::digest(&[&model]);
 
-		rbx_binary::to_writer(writer, &dom, &[dom.root_ref()])?;
+		if !cache.contains(&name) {
+			cache.insert(&name, &model)?;
+		}
 
 		Ok(pid + "/" + &name)
 	}();
 
 	match result {
-		Ok(name) => {
-			let mut index = INDEX.write().unwrap();
-			*index += 1;
-
-			Some(name)
-		}
+		Ok(name) => Some(name),
 		Err(err) => {
 			error!("Failed to write MeshPart temporary model: {}", err);
 			None
@@ -1,10 +1,19 @@
-use rbx_dom_weak::{types::Ref, WeakDom};
+use anyhow::Result;
+use rbx_dom_weak::{
+	types::{Ref, Variant},
+	HashMapExt, Ustr, UstrMap, WeakDom,
+};
+use std::path::{Path, PathBuf};
 
 use crate::{
-	core::{helpers::apply_migrations, snapshot::Snapshot},
+	core::{helpers::apply_migrations, meta::Context, snapshot::Snapshot},
+	resolution::FileRef,
+	vfs::Vfs,
 	Properties,
 };
 
+mod file_ref;
+mod inference;
 mod markdown;
 mod mesh_part;
 mod snapshot;
@@ -17,12 +26,66 @@ pub fn save_mesh(properties: &Properties) -> Option<String> {
 	mesh_part::save_mesh(&properties)
 }
 
+/// Returns copy of the properties without the given one,
+/// so the possibly large removed value is never cloned
+#[inline]
+pub fn without(properties: &Properties, name: Ustr) -> Properties {
+	let mut remaining = UstrMap::with_capacity(properties.len());
+
+	for (property, value) in properties {
+		if *property != name {
+			remaining.insert(*property, value.clone());
+		}
+	}
+
+	remaining
+}
+
 #[inline]
 pub fn snapshot_from_dom(dom: WeakDom, id: Ref) -> Snapshot {
 	snapshot::snapshot_from_dom(dom, id)
 }
 
+#[inline]
+pub fn check_depth(snapshot: Snapshot, path: &Path, context: &Context) -> Result<Snapshot> {
+	snapshot::check_depth(snapshot, path, context)
+}
+
 #[inline]
 pub fn markdown_to_rich_text(text: &str) -> String {
 	markdown::parse(text)
 }
+
+/// Infers class of an instance without an explicit one from project rules,
+/// its name and the class of its parent if known
+#[inline]
+pub fn infer_class(name: &str, context: &Context) -> Ustr {
+	inference::infer_class(name, context)
+}
+
+/// Class older versions inferred from the instance name alone
+#[inline]
+pub fn legacy_class(name: &str) -> Ustr {
+	inference::legacy_class(name)
+}
+
+/// Reports, once per location, that an instance is now inferred differently
+#[inline]
+pub fn warn_reclassified(location: &str, legacy: &str, class: &str) {
+	inference::warn_reclassified(location, legacy, class)
+}
+
+/// Reads `{"$file": ...}` value of the string property relative to
+/// the `origin` file, see `file_ref::resolve` for the rules
+#[inline]
+pub fn resolve_file_ref(
+	file: &FileRef,
+	class: &str,
+	property: &str,
+	origin: &Path,
+	context: &Context,
+	vfs: &Vfs,
+	references: &mut Vec<PathBuf>,
+) -> Result<Variant> {
+	file_ref::resolve(file, class, property, origin, context, vfs, references)
+}
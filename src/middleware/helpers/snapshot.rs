@@ -1,20 +1,40 @@
-use rbx_dom_weak::{types::Ref, AHashMap, Instance, WeakDom};
+use anyhow::Result;
+use rbx_dom_weak::{types::Ref, WeakDom};
+use std::{collections::HashMap, path::Path};
 
-use crate::core::{meta::Meta, snapshot::Snapshot};
+use crate::{
+	core::{
+		meta::{Context, Meta},
+		snapshot::Snapshot,
+	},
+	ext::PathExt,
+};
 
 // Based on Rojo's InstanceSnapshot::from_tree (https://github.com/rojo-rbx/rojo/blob/master/src/snapshot/instance_snapshot.rs#L105)
 pub fn snapshot_from_dom(dom: WeakDom, id: Ref) -> Snapshot {
 	let (_, mut raw_dom) = dom.into_raw();
 
-	fn walk(id: Ref, raw_dom: &mut AHashMap<Ref, Instance>) -> Snapshot {
-		let instance = raw_dom
-			.remove(&id)
-			.expect("Provided ID does not exist in the current DOM");
+	// Parents are always visited before their children, so walking
+	// the order backwards completes children first without recursion
+	let mut order = vec![];
+	let mut stack = vec![id];
+
+	while let Some(id) = stack.pop() {
+		let instance = raw_dom.get(&id).expect("Provided ID does not exist in the current DOM");
+
+		order.push(id);
+		stack.extend(instance.children());
+	}
+
+	let mut completed: HashMap<Ref, Snapshot> = HashMap::new();
+
+	for id in order.into_iter().rev() {
+		let instance = raw_dom.remove(&id).unwrap();
 
 		let children = instance
 			.children()
 			.iter()
-			.map(|&child_id| walk(child_id, raw_dom))
+			.map(|child_id| completed.remove(child_id).unwrap())
 			.collect();
 
 		let mut meta = Meta::new();
@@ -23,13 +43,23 @@ pub fn snapshot_from_dom(dom: WeakDom, id: Ref) -> Snapshot {
 			meta.set_mesh_source(super::save_mesh(&instance.properties));
 		}
 
-		Snapshot::new()
+		let snapshot = Snapshot::new()
 			.with_meta(meta)
-			.with_name(&instance.name)
-			.with_class(&instance.class)
+			.with_name(instance.name)
+			.with_class(instance.class)
 			.with_properties(instance.properties)
-			.with_children(children)
+			.with_children(children);
+
+		completed.insert(id, snapshot);
 	}
 
-	walk(id, &mut raw_dom)
+	completed.remove(&id).unwrap()
+}
+
+/// Model files can hold hierarchies of any depth, deeper
+/// ones than `maxTreeDepth` are rejected before they are used
+pub fn check_depth(snapshot: Snapshot, path: &Path, context: &Context) -> Result<Snapshot> {
+	context.check_depth(snapshot.height(), path.to_string())?;
+
+	Ok(snapshot)
 }
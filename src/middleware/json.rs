@@ -2,7 +2,11 @@ use anyhow::Result;
 use rbx_dom_weak::{types::Variant, HashMapExt, Ustr, UstrMap};
 use std::path::Path;
 
-use crate::{core::snapshot::Snapshot, vfs::Vfs};
+use crate::{
+	core::{meta::Context, snapshot::Snapshot},
+	middleware::external::MiddlewareImpl,
+	vfs::Vfs,
+};
 
 #[profiling::function]
 pub fn read_json(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
@@ -21,3 +25,12 @@ pub fn read_json(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
 
 	Ok(Snapshot::new().with_class("ModuleScript").with_properties(properties))
 }
+
+/// JSON file read as `ModuleScript` returning its contents
+pub struct JsonMiddleware;
+
+impl MiddlewareImpl for JsonMiddleware {
+	fn read(&self, path: &Path, _context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+		read_json(path, vfs)
+	}
+}
@@ -4,8 +4,13 @@ use rbx_dom_weak::{types::Tags, HashMapExt, Ustr, UstrMap};
 use serde::Deserialize;
 use std::path::Path;
 
-use super::helpers;
-use crate::{core::snapshot::Snapshot, resolution::UnresolvedValue, vfs::Vfs};
+use super::{external::MiddlewareImpl, helpers};
+use crate::{
+	core::{meta::Context, snapshot::Snapshot},
+	ranges::PropertyRanges,
+	resolution::UnresolvedValue,
+	vfs::Vfs,
+};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -27,7 +32,7 @@ struct JsonModel {
 }
 
 #[profiling::function]
-pub fn read_json_model(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
+pub fn read_json_model(path: &Path, context: &Context, vfs: &Vfs) -> Result<Snapshot> {
 	let contents = vfs.read_to_string(path)?;
 
 	if contents.is_empty() {
@@ -35,33 +40,33 @@ pub fn read_json_model(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
 	}
 
 	let model = serde_json::from_str(&contents)?;
-	let snapshot = walk(model, path)?;
+	let snapshot = walk(model, path, context.property_ranges())?;
 
 	Ok(snapshot)
 }
 
-fn walk(model: JsonModel, path: &Path) -> Result<Snapshot> {
+fn walk(model: JsonModel, path: &Path, ranges: &PropertyRanges) -> Result<Snapshot> {
 	let mut snapshot = Snapshot::new();
 	let mut properties = UstrMap::new();
 
 	// Apply class
 	let class = model.class_name.unwrap_or(snapshot.class);
-	snapshot.set_class(&class);
+	snapshot.set_class(class);
 
 	// Apply name
 	if let Some(name) = model.name {
-		snapshot.set_name(&name);
+		snapshot.set_name(name);
 	}
 
 	// Resolve properties
 	if let Some(model_properties) = model.properties {
 		for (property, value) in model_properties {
-			match value.resolve(&class, &property) {
+			match value.resolve_checked(&class, &property, ranges, Some(path)) {
 				Ok(value) => {
 					properties.insert(property, value);
 				}
 				Err(err) => {
-					error!("Failed to parse property: {} at {}", err, path.display());
+					error!("Failed to parse property: {}", err);
 				}
 			}
 		}
@@ -69,12 +74,12 @@ fn walk(model: JsonModel, path: &Path) -> Result<Snapshot> {
 
 	// Resolve attributes
 	if let Some(attributes) = model.attributes {
-		match attributes.resolve(&class, "Attributes") {
+		match attributes.resolve(&class, "Attributes", Some(path)) {
 			Ok(value) => {
 				properties.insert(Ustr::from("Attributes"), value);
 			}
 			Err(err) => {
-				error!("Failed to parse attributes: {} at {}", err, path.display());
+				error!("Failed to parse attributes: {}", err);
 			}
 		}
 	}
@@ -92,8 +97,17 @@ fn walk(model: JsonModel, path: &Path) -> Result<Snapshot> {
 
 	// Append children
 	for child in model.children.unwrap_or_default() {
-		snapshot.add_child(walk(child, path)?);
+		snapshot.add_child(walk(child, path, ranges)?);
 	}
 
 	Ok(snapshot)
 }
+
+/// JSON model describing instances
+pub struct JsonModelMiddleware;
+
+impl MiddlewareImpl for JsonModelMiddleware {
+	fn read(&self, path: &Path, context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+		read_json_model(path, context, vfs)
+	}
+}
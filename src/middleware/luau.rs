@@ -5,7 +5,7 @@ use rbx_dom_weak::{
 };
 use std::path::Path;
 
-use super::Middleware;
+use super::{external::MiddlewareImpl, helpers, Middleware};
 use crate::{
 	core::{meta::Context, snapshot::Snapshot},
 	vfs::Vfs,
@@ -41,7 +41,7 @@ pub fn read_luau(path: &Path, context: &Context, vfs: &Vfs, script_type: ScriptT
 	};
 
 	let mut snapshot = Snapshot::new().with_class(class_name);
-	let mut properties = UstrMap::new();
+	let mut properties = UstrMap::with_capacity(2);
 
 	let source = vfs.read_to_string(path)?;
 
@@ -58,10 +58,25 @@ pub fn read_luau(path: &Path, context: &Context, vfs: &Vfs, script_type: ScriptT
 }
 
 #[profiling::function]
-pub fn write_luau(mut properties: Properties, path: &Path, vfs: &Vfs) -> Result<Properties> {
-	if let Some(Variant::String(value)) = properties.remove(&Ustr::from("Source")) {
+pub fn write_luau(properties: &Properties, path: &Path, vfs: &Vfs) -> Result<Properties> {
+	let source = Ustr::from("Source");
+
+	if let Some(Variant::String(value)) = properties.get(&source) {
 		vfs.write(path, value.as_bytes())?;
 	}
 
-	Ok(properties)
+	Ok(helpers::without(properties, source))
+}
+
+/// Luau script of the given type
+pub struct LuauMiddleware(pub ScriptType);
+
+impl MiddlewareImpl for LuauMiddleware {
+	fn read(&self, path: &Path, context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+		read_luau(path, context, vfs, self.0.clone())
+	}
+
+	fn write(&self, properties: &Properties, path: &Path, vfs: &Vfs) -> Result<Properties> {
+		write_luau(properties, path, vfs)
+	}
 }
@@ -0,0 +1,364 @@
+use anyhow::{bail, Context as _, Result};
+use colored::Colorize;
+use rbx_dom_weak::{types::Variant, Ustr};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{collections::HashSet, mem, path::Path, slice};
+
+use super::data::{self, Data};
+use crate::{
+	constants::ASSET_MANIFEST_SUFFIX,
+	core::{
+		meta::{Context, Meta, Source},
+		refs,
+		snapshot::Snapshot,
+	},
+	ext::{PathExt, ResultExt},
+	resolution::{FileRef, UnresolvedValue},
+	vfs::Vfs,
+	Properties,
+};
+
+/// Part of the manifest entry that is not instance data, the rest
+/// of the entry is read the same way as data files
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Header {
+	name: String,
+	/// Short form of `className`
+	class: Option<Ustr>,
+	#[serde(default)]
+	children: Vec<Value>,
+}
+
+/// Whether the file is an asset manifest, e.g. `sounds.assets.json`
+pub fn is_manifest(path: &Path) -> bool {
+	path.get_name().ends_with(ASSET_MANIFEST_SUFFIX)
+}
+
+/// Name of the manifest without its suffix, e.g. `sounds` for `sounds.assets.json`
+pub fn get_name(path: &Path) -> &str {
+	let name = path.get_name();
+	name.strip_suffix(ASSET_MANIFEST_SUFFIX).unwrap_or(name)
+}
+
+/// Reads instances listed in the manifest, they become children of the
+/// instance of its directory and are keyed by their names, see `Source::manifest`
+#[profiling::function]
+pub fn read_manifest(path: &Path, context: &Context, vfs: &Vfs) -> Result<Vec<Snapshot>> {
+	read_entries(read_list(path, vfs)?, path, &[], context, vfs).with_desc(|| {
+		format!(
+			"Failed to read {} at {}",
+			"asset manifest".bold(),
+			path.display().to_string().bold()
+		)
+	})
+}
+
+/// Reads the instance of the manifest entry with the given key
+pub fn read_entry(path: &Path, key: &[String], context: &Context, vfs: &Vfs) -> Result<Option<Snapshot>> {
+	let mut snapshots = read_manifest(path, context, vfs)?;
+
+	for (depth, name) in key.iter().enumerate() {
+		let Some(index) = snapshots.iter().position(|snapshot| snapshot.name == *name) else {
+			return Ok(None);
+		};
+
+		let mut snapshot = snapshots.swap_remove(index);
+
+		if depth == key.len() - 1 {
+			return Ok(Some(snapshot));
+		}
+
+		snapshots = mem::take(&mut snapshot.children);
+	}
+
+	Ok(None)
+}
+
+fn read_entries(
+	entries: Vec<Value>,
+	path: &Path,
+	key: &[String],
+	context: &Context,
+	vfs: &Vfs,
+) -> Result<Vec<Snapshot>> {
+	let mut names = HashSet::new();
+	let mut snapshots = vec![];
+
+	for entry in entries {
+		let header: Header = serde_json::from_value(entry.clone()).context("Failed to parse entry")?;
+		let name = header.name;
+
+		// Entries are located by name during syncback, so they have to be unique
+		if !names.insert(name.clone()) {
+			bail!(
+				"Entry {} is listed more than once, names have to be unique",
+				name.bold()
+			);
+		}
+
+		let data: Data = serde_json::from_value(entry).with_context(|| format!("Failed to parse entry {}", name))?;
+		let data = data::resolve_data(data, path, header.class.as_deref(), context, vfs);
+
+		let class = data
+			.class
+			.or(header.class)
+			.with_context(|| format!("Entry {} has no class", name.bold()))?;
+
+		let key = [key, slice::from_ref(&name)].concat();
+		let mut source = Source::manifest(path, &key);
+
+		for reference in &data.references {
+			source.add_reference(reference);
+		}
+
+		let mut snapshot = Snapshot::new()
+			.with_name(&name)
+			.with_class(class)
+			.with_meta(Meta::new().with_context(context).with_source(source));
+
+		snapshot.meta.set_ref_paths(data.ref_paths);
+		snapshot.extend_properties(data.properties);
+
+		if !header.children.is_empty() {
+			context.check_depth(1, format!("{} in {}", key.join("."), path.display()))?;
+
+			let children = read_entries(header.children, path, &key, &context.with_parent_class(class), vfs)?;
+			snapshot.set_children(children);
+		}
+
+		snapshots.push(snapshot);
+	}
+
+	Ok(snapshots)
+}
+
+/// Whether the manifest has an entry with the given key
+pub fn has_entry(path: &Path, key: &[String], vfs: &Vfs) -> bool {
+	read_list(path, vfs).is_ok_and(|mut entries| locate(&mut entries, key).is_some())
+}
+
+/// Whether instances can be added to the entry, which is the case when
+/// the entry or one of the entries it is nested in sets `allowChildren`
+pub fn allows_children(path: &Path, key: &[String], vfs: &Vfs) -> bool {
+	let Ok(mut entries) = read_list(path, vfs) else {
+		return false;
+	};
+
+	(1..=key.len()).any(|length| {
+		locate(&mut entries, &key[..length])
+			.is_some_and(|(entries, index)| entries[index].get("allowChildren") == Some(&Value::Bool(true)))
+	})
+}
+
+/// Replaces properties of the entry, attributes and tags are written as properties too
+pub fn write_properties(path: &Path, key: &[String], class: &str, properties: Properties, vfs: &Vfs) -> Result<()> {
+	update_list(path, vfs, |entries| {
+		let (entries, index) = locate(entries, key).with_context(|| missing(key))?;
+		let entry = entries[index].as_object_mut().with_context(|| missing(key))?;
+
+		let properties = properties_value(class, properties, entry.get("properties"), path, vfs)?;
+
+		entry.remove("attributes");
+		entry.remove("tags");
+
+		if properties.is_empty() {
+			entry.remove("properties");
+		} else {
+			entry.insert(String::from("properties"), Value::Object(properties));
+		}
+
+		Ok(())
+	})
+}
+
+/// Renames the entry, returns its new key
+pub fn rename_entry(path: &Path, key: &[String], name: &str, vfs: &Vfs) -> Result<Vec<String>> {
+	update_list(path, vfs, |entries| {
+		let (entries, index) = locate(entries, key).with_context(|| missing(key))?;
+
+		if entries
+			.iter()
+			.enumerate()
+			.any(|(other, entry)| other != index && name_of(entry) == Some(name))
+		{
+			bail!("Asset manifest already has an entry named {}", name.bold());
+		}
+
+		entries[index]
+			.as_object_mut()
+			.with_context(|| missing(key))?
+			.insert(String::from("name"), Value::from(name));
+
+		Ok(())
+	})?;
+
+	let mut key = key.to_vec();
+
+	if let Some(last) = key.last_mut() {
+		*last = name.to_owned();
+	}
+
+	Ok(key)
+}
+
+/// Adds the instance with its descendants as a child entry of the entry with the given key
+pub fn add_entry(path: &Path, parent_key: &[String], snapshot: &Snapshot, vfs: &Vfs) -> Result<()> {
+	let value = entry_value(snapshot, path, vfs)?;
+
+	update_list(path, vfs, |entries| {
+		let (entries, index) = locate(entries, parent_key).with_context(|| missing(parent_key))?;
+
+		let children = entries[index]
+			.as_object_mut()
+			.with_context(|| missing(parent_key))?
+			.entry("children")
+			.or_insert_with(|| Value::Array(vec![]))
+			.as_array_mut()
+			.with_context(|| format!("Children of entry {} are not a list", parent_key.join(".")))?;
+
+		if children.iter().any(|entry| name_of(entry) == Some(&snapshot.name)) {
+			bail!(
+				"Entry {} already has a child named {}",
+				parent_key.join(".").bold(),
+				snapshot.name.bold()
+			);
+		}
+
+		children.push(value);
+
+		Ok(())
+	})
+}
+
+/// Removes the entry along with its children
+pub fn remove_entry(path: &Path, key: &[String], vfs: &Vfs) -> Result<()> {
+	update_list(path, vfs, |entries| {
+		if let Some((entries, index)) = locate(entries, key) {
+			entries.remove(index);
+		}
+
+		// Empty list of children is left out like the rest of empty fields
+		if let Some((entries, index)) = locate(entries, &key[..key.len().saturating_sub(1)]) {
+			if let Some(entry) = entries[index].as_object_mut() {
+				if entry
+					.get("children")
+					.and_then(Value::as_array)
+					.is_some_and(Vec::is_empty)
+				{
+					entry.remove("children");
+				}
+			}
+		}
+
+		Ok(())
+	})
+}
+
+fn read_list(path: &Path, vfs: &Vfs) -> Result<Vec<Value>> {
+	let contents = vfs.read_to_string(path)?;
+
+	if contents.trim().is_empty() {
+		return Ok(vec![]);
+	}
+
+	serde_json::from_str(&contents).with_context(|| format!("Failed to parse asset manifest {}", path.display()))
+}
+
+fn update_list<T>(path: &Path, vfs: &Vfs, update: impl FnOnce(&mut Vec<Value>) -> Result<T>) -> Result<T> {
+	let mut entries = read_list(path, vfs)?;
+	let result = update(&mut entries)?;
+
+	data::write_value(path, &entries, vfs)?;
+
+	Ok(result)
+}
+
+/// Returns the list that the entry with the given key is in and its index there
+fn locate<'a>(entries: &'a mut Vec<Value>, key: &[String]) -> Option<(&'a mut Vec<Value>, usize)> {
+	let (name, rest) = key.split_first()?;
+	let index = entries.iter().position(|entry| name_of(entry) == Some(name))?;
+
+	if rest.is_empty() {
+		return Some((entries, index));
+	}
+
+	let children = entries[index].get_mut("children")?.as_array_mut()?;
+
+	locate(children, rest)
+}
+
+fn name_of(entry: &Value) -> Option<&str> {
+	entry.get("name").and_then(Value::as_str)
+}
+
+fn missing(key: &[String]) -> String {
+	format!("Asset manifest has no entry {}", key.join(".").bold())
+}
+
+/// Returns the entry describing the instance and its descendants
+fn entry_value(snapshot: &Snapshot, path: &Path, vfs: &Vfs) -> Result<Value> {
+	let mut entry = Map::new();
+
+	entry.insert(String::from("name"), Value::from(snapshot.name.as_str()));
+	entry.insert(String::from("className"), Value::from(snapshot.class.as_str()));
+
+	let properties = refs::portable(snapshot.properties.clone(), &snapshot.meta.ref_paths);
+	let properties = properties_value(&snapshot.class, properties, None, path, vfs)?;
+
+	if !properties.is_empty() {
+		entry.insert(String::from("properties"), Value::Object(properties));
+	}
+
+	if !snapshot.children.is_empty() {
+		let mut names = HashSet::new();
+		let mut children = vec![];
+
+		for child in &snapshot.children {
+			if !names.insert(&child.name) {
+				bail!(
+					"Instance {} has more than one child named {}, names have to be unique in asset manifests",
+					snapshot.name.bold(),
+					child.name.bold()
+				);
+			}
+
+			children.push(entry_value(child, path, vfs)?);
+		}
+
+		entry.insert(String::from("children"), Value::Array(children));
+	}
+
+	Ok(Value::Object(entry))
+}
+
+/// Returns properties the way they are written in the manifest, string
+/// properties read with `$file` are written back to their files
+fn properties_value(
+	class: &str,
+	properties: Properties,
+	existing: Option<&Value>,
+	path: &Path,
+	vfs: &Vfs,
+) -> Result<Map<String, Value>> {
+	let mut values = Map::new();
+
+	for (property, variant) in properties {
+		let file = existing
+			.and_then(|existing| existing.get(property.as_str()))
+			.and_then(|value| serde_json::from_value::<FileRef>(value.clone()).ok());
+
+		let value = match (file, variant) {
+			(Some(file), Variant::String(contents)) => {
+				data::write_file_ref(path, &file, &contents, vfs)?;
+				UnresolvedValue::File(file)
+			}
+			(_, variant) => UnresolvedValue::from_variant(variant, class, &property),
+		};
+
+		values.insert(property.to_string(), serde_json::to_value(value)?);
+	}
+
+	Ok(values)
+}
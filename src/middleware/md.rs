@@ -1,8 +1,24 @@
 use anyhow::Result;
-use rbx_dom_weak::{types::Variant, HashMapExt, Ustr, UstrMap};
-use std::path::Path;
+use colored::Colorize;
+use rbx_dom_weak::{
+	types::{Attributes, Variant},
+	HashMapExt, Ustr, UstrMap,
+};
+use std::{collections::BTreeMap, path::Path};
 
-use crate::{core::snapshot::Snapshot, middleware::helpers::markdown_to_rich_text, vfs::Vfs};
+use crate::{
+	argon_warn,
+	core::{meta::Context, snapshot::Snapshot},
+	middleware::{
+		external::MiddlewareImpl,
+		helpers::{self, markdown_to_rich_text},
+	},
+	t,
+	vfs::Vfs,
+	Properties,
+};
+
+const FRONT_MATTER_DELIMITER: &str = "---";
 
 #[profiling::function]
 pub fn read_md(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
@@ -14,3 +30,257 @@ pub fn read_md(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
 
 	Ok(Snapshot::new().with_class("StringValue").with_properties(properties))
 }
+
+/// Reads Markdown file as a plain `StringValue`,
+/// its front-matter entries become attributes
+#[profiling::function]
+pub fn read_markdown(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
+	let markdown = vfs.read_to_string(path)?;
+	let (front_matter, body) = split_front_matter(&markdown);
+
+	let mut properties = UstrMap::with_capacity(2);
+	properties.insert(Ustr::from("Value"), Variant::String(body.to_owned()));
+
+	if let Some(front_matter) = front_matter {
+		let mut attributes = Attributes::new();
+
+		for (key, value) in parse_front_matter(front_matter) {
+			attributes.insert(key, value);
+		}
+
+		if !attributes.is_empty() {
+			properties.insert(Ustr::from("Attributes"), Variant::Attributes(attributes));
+		}
+	}
+
+	Ok(Snapshot::new().with_class("StringValue").with_properties(properties))
+}
+
+/// Writes `Value` to the Markdown file keeping its front-matter, which
+/// is updated with attributes if all of them can be represented in it
+#[profiling::function]
+pub fn write_markdown(properties: &Properties, path: &Path, vfs: &Vfs) -> Result<Properties> {
+	let value_name = Ustr::from("Value");
+	let attributes_name = Ustr::from("Attributes");
+
+	let existing = if vfs.exists(path) {
+		vfs.read_to_string(path)?
+	} else {
+		String::new()
+	};
+
+	let mut front_matter = split_front_matter(&existing).0.map(str::to_owned);
+	let mut remaining = helpers::without(properties, value_name);
+
+	let attributes = match properties.get(&attributes_name) {
+		Some(Variant::Attributes(attributes)) => attributes
+			.iter()
+			.map(|(key, value)| Some((key.to_owned(), format_value(value)?)))
+			.collect::<Option<BTreeMap<_, _>>>(),
+		Some(_) => None,
+		None => Some(BTreeMap::new()),
+	};
+
+	if let Some(attributes) = attributes {
+		front_matter = update_front_matter(front_matter.as_deref(), &attributes);
+		remaining.remove(&attributes_name);
+	}
+
+	let value = match properties.get(&value_name) {
+		Some(Variant::String(value)) => value.as_str(),
+		_ => split_front_matter(&existing).1,
+	};
+
+	let markdown = match front_matter {
+		Some(front_matter) => format!(
+			"{}\n{}{}\n{}",
+			FRONT_MATTER_DELIMITER, front_matter, FRONT_MATTER_DELIMITER, value
+		),
+		None => value.to_owned(),
+	};
+
+	vfs.write(path, markdown.as_bytes())?;
+
+	Ok(remaining)
+}
+
+/// Checks whether the file exceeds the project's `maxMarkdownSize`,
+/// such files are not synced so books do not end up in the place
+pub fn is_oversized(path: &Path, context: &Context, vfs: &Vfs) -> Result<bool> {
+	let size = vfs.read(path)?.len();
+
+	if size <= context.max_markdown_size() {
+		return Ok(false);
+	}
+
+	argon_warn!(
+		"{}",
+		t!(
+			"markdown.too_large",
+			path = path.display().to_string().bold(),
+			size = size.to_string().bold(),
+			limit = context.max_markdown_size().to_string().bold()
+		)
+	);
+
+	Ok(true)
+}
+
+/// Returns front-matter without its delimiters and the rest of the file
+fn split_front_matter(markdown: &str) -> (Option<&str>, &str) {
+	let Some(rest) = markdown
+		.strip_prefix(FRONT_MATTER_DELIMITER)
+		.and_then(|rest| rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix('\n')))
+	else {
+		return (None, markdown);
+	};
+
+	let mut offset = 0;
+
+	for line in rest.split_inclusive('\n') {
+		if line.trim_end() == FRONT_MATTER_DELIMITER {
+			return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+		}
+
+		offset += line.len();
+	}
+
+	(None, markdown)
+}
+
+/// Parses `key: value` lines, anything else is ignored
+fn parse_front_matter(front_matter: &str) -> Vec<(String, Variant)> {
+	front_matter
+		.lines()
+		.filter_map(parse_line)
+		.map(|(key, value)| (key.to_owned(), parse_value(value)))
+		.collect()
+}
+
+fn parse_line(line: &str) -> Option<(&str, &str)> {
+	if line.trim_start().starts_with('#') {
+		return None;
+	}
+
+	let (key, value) = line.split_once(':')?;
+	let key = key.trim();
+
+	if key.is_empty() {
+		return None;
+	}
+
+	Some((key, value.trim()))
+}
+
+/// Parses booleans and numbers, everything else is kept as a string
+fn parse_value(value: &str) -> Variant {
+	match value {
+		"true" => return Variant::Bool(true),
+		"false" => return Variant::Bool(false),
+		_ => {}
+	}
+
+	if let Ok(number) = value.parse::<f64>() {
+		if number.is_finite() {
+			return Variant::Float64(number);
+		}
+	}
+
+	for quote in ['"', '\''] {
+		if let Some(value) = value.strip_prefix(quote).and_then(|value| value.strip_suffix(quote)) {
+			return Variant::String(value.to_owned());
+		}
+	}
+
+	Variant::String(value.to_owned())
+}
+
+/// Returns front-matter representation of the attribute value,
+/// `None` if it can't be represented as a plain value
+fn format_value(value: &Variant) -> Option<String> {
+	let formatted = match value {
+		Variant::Bool(value) => value.to_string(),
+		Variant::Float32(value) => value.to_string(),
+		Variant::Float64(value) => value.to_string(),
+		Variant::Int32(value) => value.to_string(),
+		Variant::Int64(value) => value.to_string(),
+		Variant::String(value) => {
+			if value.contains('\n') {
+				return None;
+			}
+
+			if parse_value(value) == Variant::String(value.clone()) && value.trim() == value {
+				value.clone()
+			} else if value.contains('"') {
+				format!("'{}'", value)
+			} else {
+				format!("\"{}\"", value)
+			}
+		}
+		_ => return None,
+	};
+
+	Some(formatted)
+}
+
+/// Updates changed entries in place so comments and order are
+/// preserved, removed ones are dropped and new ones appended
+fn update_front_matter(front_matter: Option<&str>, attributes: &BTreeMap<String, String>) -> Option<String> {
+	let mut updated = String::new();
+	let mut written = Vec::new();
+
+	for line in front_matter.unwrap_or_default().lines() {
+		match parse_line(line) {
+			Some((key, value)) => {
+				let Some(formatted) = attributes.get(key) else {
+					continue;
+				};
+
+				if parse_value(value) == parse_value(formatted) {
+					updated.push_str(line);
+				} else {
+					updated.push_str(&format!("{}: {}", key, formatted));
+				}
+
+				written.push(key.to_owned());
+			}
+			None => updated.push_str(line),
+		}
+
+		updated.push('\n');
+	}
+
+	for (key, formatted) in attributes {
+		if !written.contains(key) {
+			updated.push_str(&format!("{}: {}\n", key, formatted));
+		}
+	}
+
+	if front_matter.is_none() && updated.is_empty() {
+		None
+	} else {
+		Some(updated)
+	}
+}
+
+/// Markdown file read as rich text `StringValue`
+pub struct MdMiddleware;
+
+impl MiddlewareImpl for MdMiddleware {
+	fn read(&self, path: &Path, _context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+		read_md(path, vfs)
+	}
+}
+
+/// Markdown file read as plain `StringValue` with front-matter attributes
+pub struct MarkdownMiddleware;
+
+impl MiddlewareImpl for MarkdownMiddleware {
+	fn read(&self, path: &Path, _context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+		read_markdown(path, vfs)
+	}
+
+	fn write(&self, properties: &Properties, path: &Path, vfs: &Vfs) -> Result<Properties> {
+		write_markdown(properties, path, vfs)
+	}
+}
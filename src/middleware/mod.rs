@@ -1,24 +1,28 @@
-use anyhow::Result;
+use anyhow::{bail, Error, Result};
 use colored::Colorize;
 use log::trace;
 use rbx_dom_weak::{
-	types::{Enum, Variant},
-	Ustr,
+	types::{Attributes, Variant},
+	HashMapExt, Ustr, UstrMap,
 };
 use serde::{Deserialize, Serialize};
 use std::{
 	fmt::{self, Display, Formatter},
-	path::Path,
+	path::{Path, PathBuf},
 };
 
-use self::data::DataSnapshot;
+use self::{data::DataSnapshot, external::MiddlewareImpl, luau::ScriptType};
 use crate::{
-	constants::BLACKLISTED_PATHS,
+	argon_error, argon_warn,
+	constants::{BLACKLISTED_PATHS, LOAD_ERROR_ATTRIBUTE},
 	core::{
-		meta::{Context, Source},
+		meta::{Context, Meta, ReadOnly, ReadOnlyReason, Source},
 		snapshot::Snapshot,
 	},
 	ext::{PathExt, ResultExt},
+	hooks::HookStage,
+	project::DataFormat,
+	t,
 	vfs::Vfs,
 	Properties,
 };
@@ -28,14 +32,17 @@ mod helpers;
 pub mod csv;
 pub mod data;
 pub mod dir;
+pub mod external;
 pub mod json;
 pub mod json_model;
 pub mod luau;
+pub mod manifest;
 pub mod md;
 pub mod msgpack;
 pub mod project;
 pub mod rbxm;
 pub mod rbxmx;
+pub mod storage;
 pub mod toml;
 pub mod txt;
 pub mod yaml;
@@ -51,6 +58,7 @@ pub enum Middleware {
 
 	StringValue,
 	RichStringValue,
+	Markdown,
 	LocalizationTable,
 
 	JsonModule,
@@ -61,37 +69,39 @@ pub enum Middleware {
 	JsonModel,
 	RbxmModel,
 	RbxmxModel,
+
+	/// Middleware registered with `CoreBuilder`, by its ID
+	External(String),
 }
 
 impl Display for Middleware {
 	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-		write!(f, "{:?}", self)
+		match self {
+			Middleware::External(id) => write!(f, "{}", id),
+			_ => write!(f, "{:?}", self),
+		}
 	}
 }
 
 impl Middleware {
 	fn read(&self, path: &Path, context: &Context, vfs: &Vfs) -> Result<Snapshot> {
-		match self {
-			Middleware::Project => project::read_project(path, vfs),
-			Middleware::InstanceData => unreachable!(),
-			//
-			Middleware::ServerScript | Middleware::ClientScript | Middleware::ModuleScript => {
-				luau::read_luau(path, context, vfs, self.clone().into())
+		self.with_impl(|middleware| {
+			match context
+				.hooks()
+				.filter(|hooks| hooks.matches(HookStage::PostSnapshot, path, self))
+			{
+				Some(hooks) => {
+					// Middleware reads processed contents from memory so the file stays untouched
+					let contents = hooks.run(HookStage::PostSnapshot, path, self, vfs.read(path)?)?;
+
+					let processed = Vfs::new_virtual();
+					processed.write(path, &contents)?;
+
+					middleware.read(path, context, &processed)
+				}
+				None => middleware.read(path, context, vfs),
 			}
-			//
-			Middleware::StringValue => txt::read_txt(path, vfs),
-			Middleware::RichStringValue => md::read_md(path, vfs),
-			Middleware::LocalizationTable => csv::read_csv(path, vfs),
-			//
-			Middleware::JsonModule => json::read_json(path, vfs),
-			Middleware::TomlModule => toml::read_toml(path, vfs),
-			Middleware::YamlModule => yaml::read_yaml(path, vfs),
-			Middleware::MsgpackModule => msgpack::read_msgpack(path, vfs),
-			//
-			Middleware::JsonModel => json_model::read_json_model(path, vfs),
-			Middleware::RbxmModel => rbxm::read_rbxm(path, vfs),
-			Middleware::RbxmxModel => rbxmx::read_rbxmx(path, vfs),
-		}
+		})
 		.with_desc(|| {
 			format!(
 				"Failed to read {} at {}",
@@ -101,16 +111,38 @@ impl Middleware {
 		})
 	}
 
-	pub fn write(&self, properties: Properties, path: &Path, vfs: &Vfs) -> Result<Properties> {
-		match self {
-			Middleware::ServerScript | Middleware::ClientScript | Middleware::ModuleScript => {
-				luau::write_luau(properties, path, vfs)
+	/// Writes file-backed property to the given path,
+	/// returns remaining properties that belong to the data file
+	pub fn write(&self, properties: &Properties, path: &Path, context: &Context, vfs: &Vfs) -> Result<Properties> {
+		self.with_impl(|middleware| {
+			match context
+				.hooks()
+				.filter(|hooks| hooks.matches(HookStage::PreWrite, path, self))
+			{
+				Some(hooks) => {
+					// Middleware writes to memory first, existing file is
+					// copied there as some middleware update it in place
+					let existing = if vfs.is_file(path) { Some(vfs.read(path)?) } else { None };
+
+					let staged = Vfs::new_virtual();
+
+					if let Some(existing) = &existing {
+						staged.write(path, existing)?;
+					}
+
+					let remaining = middleware.write(properties, path, &staged)?;
+
+					if let Ok(contents) = staged.read(path) {
+						if Some(&contents) != existing.as_ref() {
+							vfs.write(path, &hooks.run(HookStage::PreWrite, path, self, contents)?)?;
+						}
+					}
+
+					Ok(remaining)
+				}
+				None => middleware.write(properties, path, vfs),
 			}
-			Middleware::StringValue => txt::write_txt(properties, path, vfs),
-			Middleware::LocalizationTable => csv::write_csv(properties, path, vfs),
-			// TODO: Add support for other middleware
-			_ => unimplemented!(),
-		}
+		})
 		.with_desc(|| {
 			format!(
 				"Failed to write {} at {}",
@@ -120,24 +152,51 @@ impl Middleware {
 		})
 	}
 
-	pub fn from_class(class: &str, properties: Option<&mut Properties>) -> Option<Self> {
+	/// Runs `f` with implementation of the middleware, built-in
+	/// or the one registered under the ID of `External` middleware
+	fn with_impl<T>(&self, f: impl FnOnce(&dyn MiddlewareImpl) -> Result<T>) -> Result<T> {
+		match self {
+			Middleware::Project => f(&project::ProjectMiddleware),
+			Middleware::InstanceData => bail!("Instance data is only read together with the instance it describes"),
+			//
+			Middleware::ServerScript => f(&luau::LuauMiddleware(ScriptType::Server)),
+			Middleware::ClientScript => f(&luau::LuauMiddleware(ScriptType::Client)),
+			Middleware::ModuleScript => f(&luau::LuauMiddleware(ScriptType::Module)),
+			//
+			Middleware::StringValue => f(&txt::TxtMiddleware),
+			Middleware::RichStringValue => f(&md::MdMiddleware),
+			Middleware::Markdown => f(&md::MarkdownMiddleware),
+			Middleware::LocalizationTable => f(&csv::CsvMiddleware),
+			//
+			Middleware::JsonModule => f(&json::JsonMiddleware),
+			Middleware::TomlModule => f(&toml::TomlMiddleware),
+			Middleware::YamlModule => f(&yaml::YamlMiddleware),
+			Middleware::MsgpackModule => f(&msgpack::MsgpackMiddleware),
+			//
+			Middleware::JsonModel => f(&json_model::JsonModelMiddleware),
+			Middleware::RbxmModel => f(&rbxm::RbxmMiddleware),
+			Middleware::RbxmxModel => f(&rbxmx::RbxmxMiddleware),
+			//
+			Middleware::External(id) => f(external::get(id)?.as_ref()),
+		}
+	}
+
+	/// Whether the middleware reads binary or XML models that syncback can't write
+	pub fn is_model(&self) -> bool {
+		matches!(self, Middleware::RbxmModel | Middleware::RbxmxModel)
+	}
+
+	/// Returns middleware matching the class, `properties` should only
+	/// be provided when `RunContext` should decide the type of the script
+	pub fn from_class(class: &str, properties: Option<&Properties>) -> Option<Self> {
 		// TODO: Implement matcher for detecting remaining middleware
 		match class {
 			"Script" => {
-				if let Some(properties) = properties {
-					if let Some(Variant::Enum(run_context)) = properties.remove(&Ustr::from("RunContext")) {
-						let run_context = run_context.to_u32();
-
-						return Some(match run_context {
-							1 => Middleware::ServerScript,
-							2 => Middleware::ClientScript,
-							_ => {
-								// This is currently unreachable so we can handle it inefficiently just for safety
-								properties.insert(Ustr::from("RunContext"), Variant::Enum(Enum::from_u32(run_context)));
-
-								Middleware::ServerScript
-							}
-						});
+				if let Some(Variant::Enum(run_context)) =
+					properties.and_then(|properties| properties.get(&Ustr::from("RunContext")))
+				{
+					if run_context.to_u32() == 2 {
+						return Some(Middleware::ClientScript);
 					}
 				}
 
@@ -150,13 +209,159 @@ impl Middleware {
 			_ => None,
 		}
 	}
+
+	/// Returns middleware of the existing file if it is an external one or if it
+	/// writes the same class as `self`, so e.g. Markdown `StringValue` is not moved
+	/// to a `.txt` file and files of external middleware keep their format
+	pub fn or_existing(self, path: Option<&Path>, context: &Context) -> Self {
+		let existing = path.and_then(|path| {
+			context
+				.sync_rules()
+				.find_map(|rule| rule.resolve(path).or_else(|| rule.resolve_child(path)))
+		});
+
+		match existing {
+			Some(resolved) if matches!(resolved.middleware, Middleware::External(_)) => resolved.middleware,
+			Some(resolved) if self == Middleware::StringValue && resolved.middleware == Middleware::Markdown => {
+				resolved.middleware
+			}
+			_ => self,
+		}
+	}
+
+	/// Removes `RunContext` of the script as it is already encoded in the file name,
+	/// unknown run contexts are kept so they are not lost when writing data
+	pub fn strip_run_context(class: &str, properties: &mut Properties) {
+		if class != "Script" {
+			return;
+		}
+
+		let name = Ustr::from("RunContext");
+
+		match properties.get(&name) {
+			Some(Variant::Enum(run_context)) if !matches!(run_context.to_u32(), 1 | 2) => {}
+			Some(_) => {
+				properties.remove(&name);
+			}
+			None => {}
+		}
+	}
 }
 
-/// Returns a snapshot of the given path, `None` if path no longer exists
-pub fn new_snapshot(path: &Path, context: &Context, vfs: &Vfs) -> Result<Option<Snapshot>> {
-	if BLACKLISTED_PATHS.iter().any(|blacklisted| path.ends_with(blacklisted))
+/// Why a file or directory failed to load, kept in the meta of its placeholder
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadError {
+	pub path: PathBuf,
+	/// Middleware of the file, `None` for directories and asset manifests
+	pub middleware: Option<Middleware>,
+	pub error: String,
+}
+
+impl Display for LoadError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match &self.middleware {
+			Some(middleware) => write!(f, "{} ({}): {}", self.path.display(), middleware, self.error),
+			None => write!(f, "{}: {}", self.path.display(), self.error),
+		}
+	}
+}
+
+/// Child files of a directory that could all describe it, e.g. `init.luau` and
+/// `init.server.luau` after a bad merge, or a data file with a different class.
+/// The directory can't be synced back until the conflict is resolved
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitConflict {
+	pub dir: PathBuf,
+	/// File the directory is read from
+	pub used: PathBuf,
+	/// Init files that were skipped and data files whose class was ignored
+	pub ignored: Vec<PathBuf>,
+}
+
+impl Display for InitConflict {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let ignored: Vec<String> = self.ignored.iter().map(|path| path.display().to_string()).collect();
+
+		write!(f, "{} is used, {} ignored", self.used.display(), ignored.join(", "))
+	}
+}
+
+/// Returns a snapshot of the given path like `new_snapshot`, but failures are
+/// turned into a placeholder folder so siblings of the path still load,
+/// unless the project sets `strictLoad`
+pub fn new_snapshot_isolated(path: &Path, context: &Context, vfs: &Vfs) -> Result<Option<Snapshot>> {
+	match new_snapshot(path, context, vfs) {
+		Err(err) if !context.strict_load() => Ok(Some(new_placeholder(path, err, context, vfs))),
+		result => result,
+	}
+}
+
+fn new_placeholder(path: &Path, err: Error, context: &Context, vfs: &Vfs) -> Snapshot {
+	let resolved = context
+		.sync_rules()
+		.find_map(|rule| rule.resolve(path).or_else(|| rule.resolve_child(path)));
+
+	let (name, middleware, source) = match resolved {
+		_ if manifest::is_manifest(path) => (manifest::get_name(path).to_owned(), None, Source::file(path)),
+		Some(resolved) if !vfs.is_dir(path) => (resolved.name, Some(resolved.middleware), Source::file(path)),
+		_ => (path.get_name().to_owned(), None, Source::directory(path)),
+	};
+
+	let load_error = LoadError {
+		path: path.to_owned(),
+		middleware,
+		error: format!("{:#}", err),
+	};
+
+	argon_error!(
+		"{}",
+		t!(
+			"load.failed",
+			error = load_error.to_string().bold(),
+			setting = "strictLoad".bold()
+		)
+	);
+
+	let mut attributes = Attributes::new();
+	attributes.insert(
+		LOAD_ERROR_ATTRIBUTE.to_owned(),
+		Variant::String(load_error.error.clone()),
+	);
+
+	let mut properties = UstrMap::new();
+	properties.insert(Ustr::from("Attributes"), attributes.into());
+
+	Snapshot::new().with_name(&name).with_properties(properties).with_meta(
+		Meta::new()
+			.with_context(context)
+			.with_source(source)
+			.with_load_error(load_error),
+	)
+}
+
+/// Returns snapshots of all instances the path describes as children of its
+/// directory, which can be many for asset manifests, see `new_snapshot_isolated`
+pub fn new_snapshots_isolated(path: &Path, context: &Context, vfs: &Vfs) -> Result<Vec<Snapshot>> {
+	if !manifest::is_manifest(path) || is_ignored(path, context) || !vfs.is_file(path) {
+		return Ok(new_snapshot_isolated(path, context, vfs)?.into_iter().collect());
+	}
+
+	match manifest::read_manifest(path, context, vfs) {
+		Err(err) if !context.strict_load() => Ok(vec![new_placeholder(path, err, context, vfs)]),
+		result => result,
+	}
+}
+
+fn is_ignored(path: &Path, context: &Context) -> bool {
+	BLACKLISTED_PATHS.iter().any(|blacklisted| path.ends_with(blacklisted))
 		|| context.ignore_rules().iter().any(|rule| rule.matches(path))
-	{
+		|| context.ts_layout().is_some_and(|layout| layout.is_source(path))
+}
+
+/// Returns a snapshot of the given path, `None` if path no longer exists.
+/// Data indexes and asset manifests are read along with their directory
+pub fn new_snapshot(path: &Path, context: &Context, vfs: &Vfs) -> Result<Option<Snapshot>> {
+	if is_ignored(path, context) || storage::is_index(path) || manifest::is_manifest(path) {
 		trace!("Snapshot of {} not created: ignored or blacklisted", path.display());
 		return Ok(None);
 	}
@@ -172,7 +377,13 @@ pub fn new_snapshot(path: &Path, context: &Context, vfs: &Vfs) -> Result<Option<
 	trace!("Creating snapshot of {}", path.display());
 
 	if vfs.is_file(path) {
-		if let Some(snapshot) = new_snapshot_file_child(path, context, vfs)? {
+		let is_child = context.sync_rules().any(|rule| rule.matches_child(path));
+
+		if let Some(snapshot) = is_child
+			.then(|| new_snapshot_file_child(path.get_parent(), context, vfs))
+			.transpose()?
+			.flatten()
+		{
 			Ok(Some(snapshot))
 		} else if let Some(snapshot) = new_snapshot_file(path, context, vfs)? {
 			Ok(Some(snapshot))
@@ -180,13 +391,9 @@ pub fn new_snapshot(path: &Path, context: &Context, vfs: &Vfs) -> Result<Option<
 			trace!("Snapshot of {} not created: no middleware matched", path.display());
 			Ok(None)
 		}
+	} else if let Some(snapshot) = new_snapshot_file_child(path, context, vfs)? {
+		Ok(Some(snapshot))
 	} else {
-		for path in vfs.read_dir(path)? {
-			if let Some(snapshot) = new_snapshot_file_child(&path, context, vfs)? {
-				return Ok(Some(snapshot));
-			}
-		}
-
 		new_snapshot_dir(path, context, vfs)
 	}
 }
@@ -194,10 +401,14 @@ pub fn new_snapshot(path: &Path, context: &Context, vfs: &Vfs) -> Result<Option<
 /// Create a snapshot of a regular file,
 /// example: `foo/bar.luau`
 fn new_snapshot_file(path: &Path, context: &Context, vfs: &Vfs) -> Result<Option<Snapshot>> {
-	if let Some(resolved) = context.sync_rules().iter().find_map(|rule| rule.resolve(path)) {
+	if let Some(resolved) = context.sync_rules().find_map(|rule| rule.resolve(path)) {
 		let middleware = resolved.middleware;
 		let name = resolved.name;
 
+		if middleware == Middleware::Markdown && md::is_oversized(path, context, vfs)? {
+			return Ok(None);
+		}
+
 		let mut snapshot = middleware.read(path, context, vfs)?;
 
 		if middleware != Middleware::Project {
@@ -206,6 +417,12 @@ fn new_snapshot_file(path: &Path, context: &Context, vfs: &Vfs) -> Result<Option
 			snapshot.meta.set_source(Source::file(path));
 		}
 
+		if middleware.is_model() {
+			snapshot
+				.meta
+				.set_read_only(Some(ReadOnly::new(ReadOnlyReason::ModelFile, path)));
+		}
+
 		if let Some(instance_data) = get_instance_data(&name, Some(&snapshot.class), path, context, vfs)? {
 			snapshot.apply_data(instance_data);
 		}
@@ -218,47 +435,123 @@ fn new_snapshot_file(path: &Path, context: &Context, vfs: &Vfs) -> Result<Option
 
 /// Create a snapshot of a directory that has a child source or data,
 /// example: `foo/bar/.src.luau`
-fn new_snapshot_file_child(path: &Path, context: &Context, vfs: &Vfs) -> Result<Option<Snapshot>> {
-	if let Some(resolved) = context.sync_rules().iter().find_map(|rule| rule.resolve_child(path)) {
-		let middleware = resolved.middleware;
-		let name = resolved.name;
-		let parent = path.get_parent();
+fn new_snapshot_file_child(dir: &Path, context: &Context, vfs: &Vfs) -> Result<Option<Snapshot>> {
+	let Some((path, mut ignored)) = find_child_file(dir, context, vfs)? else {
+		return Ok(None);
+	};
 
-		let mut snapshot = middleware.read(path, context, vfs)?;
+	let Some(resolved) = context.sync_rules().find_map(|rule| rule.resolve_child(&path)) else {
+		return Ok(None);
+	};
 
-		if middleware != Middleware::Project {
-			snapshot.set_name(&name);
-			snapshot.meta.set_context(context);
-			snapshot.meta.set_source(Source::child_file(parent, path));
+	let middleware = resolved.middleware;
+	let name = resolved.name;
 
-			for entry in vfs.read_dir(parent)? {
-				if entry == path {
-					continue;
-				}
+	if middleware == Middleware::Markdown && md::is_oversized(&path, context, vfs)? {
+		return Ok(None);
+	}
 
-				if let Some(child_snapshot) = new_snapshot(&entry, context, vfs)? {
-					snapshot.add_child(child_snapshot);
-				}
-			}
+	let mut snapshot = middleware.read(&path, context, vfs)?;
+
+	if middleware != Middleware::Project {
+		snapshot.set_name(&name);
+		snapshot.meta.set_context(context);
+		snapshot.meta.set_source(Source::child_file(dir, &path));
+	}
+
+	if let Some(mut instance_data) = get_instance_data(&name, Some(&snapshot.class), dir, context, vfs)? {
+		// Class of the child file wins, data can't turn a script into something else
+		if middleware != Middleware::Project && instance_data.class.is_some_and(|class| class != snapshot.class) {
+			instance_data.class = None;
+			ignored.push(instance_data.path.clone());
 		}
 
-		if let Some(instance_data) = get_instance_data(&name, Some(&snapshot.class), parent, context, vfs)? {
-			snapshot.apply_data(instance_data);
+		snapshot.apply_data(instance_data);
+	}
+
+	if middleware == Middleware::Project {
+		return Ok(Some(snapshot));
+	}
+
+	if !ignored.is_empty() {
+		let conflict = InitConflict {
+			dir: dir.to_owned(),
+			used: path.clone(),
+			ignored: ignored.clone(),
+		};
+
+		argon_warn!("{}", t!("load.init_conflict", conflict = conflict.to_string().bold()));
+
+		snapshot
+			.meta
+			.set_read_only(Some(ReadOnly::new(ReadOnlyReason::InitConflict, dir)));
+		snapshot.meta.set_init_conflict(Some(conflict));
+	}
+
+	if context.is_shallow() {
+		return Ok(Some(snapshot));
+	}
+
+	let child_context = context.with_parent_class(snapshot.class);
+	let entries = vfs.read_dir(dir)?;
+
+	if entries.len() > 1 {
+		context.check_depth(1, dir.to_string())?;
+	}
+
+	for entry in entries {
+		if entry == path || ignored.contains(&entry) {
+			continue;
 		}
 
-		Ok(Some(snapshot))
-	} else {
-		Ok(None)
+		for child_snapshot in new_snapshots_isolated(&entry, &child_context, vfs)? {
+			snapshot.add_child(child_snapshot);
+		}
+	}
+
+	Ok(Some(snapshot))
+}
+
+/// Returns the child file the directory is read from and other child files that
+/// are ignored because of it. Files of sync rules listed first take precedence,
+/// e.g. `init.server.luau` over `init.client.luau` over `init.luau` with default
+/// rules, ties are broken by file name so listing order of the file system
+/// never matters
+fn find_child_file(dir: &Path, context: &Context, vfs: &Vfs) -> Result<Option<(PathBuf, Vec<PathBuf>)>> {
+	let mut files = vec![];
+
+	for path in vfs.read_dir(dir)? {
+		if let Some(index) = context.sync_rules().position(|rule| rule.matches_child(&path)) {
+			files.push((index, path));
+		}
 	}
+
+	files.sort();
+
+	let mut files = files.into_iter().map(|(_, path)| path);
+
+	Ok(files.next().map(|used| (used, files.collect())))
 }
 
 /// Create snapshot of a directory,
 /// example: `foo/bar`
 fn new_snapshot_dir(path: &Path, context: &Context, vfs: &Vfs) -> Result<Option<Snapshot>> {
-	let mut snapshot = dir::read_dir(path, context, vfs)?;
+	let name = path.get_name();
+	let inferred = helpers::infer_class(name, context);
 
-	if let Some(instance_data) = get_instance_data(&snapshot.name, None, path, context, vfs)? {
-		snapshot.apply_data(instance_data);
+	let data = get_instance_data(name, Some(&inferred), path, context, vfs)?;
+	let class = data.as_ref().and_then(|data| data.class).unwrap_or(inferred);
+
+	// Directories used to always be folders, unless read from a project node
+	if data.as_ref().is_none_or(|data| data.class.is_none()) {
+		let legacy = context.node_class().unwrap_or("Folder");
+		helpers::warn_reclassified(&path.to_string(), legacy, &class);
+	}
+
+	let mut snapshot = dir::read_dir(path, class, context, vfs)?;
+
+	if let Some(data) = data {
+		snapshot.apply_data(data);
 	}
 
 	Ok(Some(snapshot))
@@ -271,20 +564,49 @@ fn get_instance_data(
 	context: &Context,
 	vfs: &Vfs,
 ) -> Result<Option<DataSnapshot>> {
+	let mut located: Option<PathBuf> = None;
+
 	for sync_rule in context.sync_rules_of_type(&Middleware::InstanceData, false) {
-		if let Some(data_path) = sync_rule.locate(path, name, vfs.is_dir(path)) {
-			if vfs.exists(&data_path) {
-				let data = data::read_data(&data_path, class, vfs).with_desc(|| {
-					format!(
-						"Failed to get instance data at {}",
-						data_path.display().to_string().bold()
-					)
-				})?;
-
-				return Ok(Some(data));
+		let Some(data_path) = sync_rule.locate(path, name, vfs.is_dir(path)) else {
+			continue;
+		};
+
+		if !vfs.exists(&data_path) {
+			continue;
+		}
+
+		match &located {
+			None => located = Some(data_path),
+			// JSON and YAML data files of the same instance would silently shadow each other
+			Some(other)
+				if DataFormat::of(other) == DataFormat::Yaml || DataFormat::of(&data_path) == DataFormat::Yaml =>
+			{
+				bail!(
+					"Instance data is ambiguous, both {} and {} exist, remove one of them",
+					other.display().to_string().bold(),
+					data_path.display().to_string().bold()
+				);
 			}
+			Some(_) => {}
 		}
 	}
 
-	Ok(None)
+	// Data files take precedence over entries of the data index
+	let Some(data_path) = located else {
+		return storage::read_entry(name, class, path, context, vfs).with_desc(|| {
+			format!(
+				"Failed to get instance data from the data index of {}",
+				path.display().to_string().bold()
+			)
+		});
+	};
+
+	let data = data::read_data(&data_path, class, context, vfs).with_desc(|| {
+		format!(
+			"Failed to get instance data at {}",
+			data_path.display().to_string().bold()
+		)
+	})?;
+
+	Ok(Some(data))
 }
@@ -4,7 +4,11 @@ use rmp_serde::Deserializer;
 use rmpv::Value;
 use std::path::Path;
 
-use crate::{core::snapshot::Snapshot, vfs::Vfs};
+use crate::{
+	core::{meta::Context, snapshot::Snapshot},
+	middleware::external::MiddlewareImpl,
+	vfs::Vfs,
+};
 
 #[profiling::function]
 pub fn read_msgpack(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
@@ -91,3 +95,12 @@ fn escape_chars(string: &str) -> String {
 
 	validated
 }
+
+/// MessagePack file read as `ModuleScript` returning its contents
+pub struct MsgpackMiddleware;
+
+impl MiddlewareImpl for MsgpackMiddleware {
+	fn read(&self, path: &Path, _context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+		read_msgpack(path, vfs)
+	}
+}
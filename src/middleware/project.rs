@@ -1,21 +1,23 @@
 use anyhow::{bail, Result};
 use colored::Colorize;
-use log::error;
+use log::{error, trace};
 use path_clean::PathClean;
 use rbx_dom_weak::{types::Tags, HashMapExt, Ustr, UstrMap};
-use std::path::Path;
+use std::{borrow::Cow, mem, path::Path, rc::Rc};
 
-use super::new_snapshot;
+use super::{external::MiddlewareImpl, get_instance_data, new_snapshot_isolated};
 use crate::{
 	argon_warn,
 	core::{
 		meta::{Context, Meta, NodePath, Source},
+		refs::{self, RefPaths},
 		snapshot::Snapshot,
 	},
 	ext::PathExt,
 	middleware::helpers,
 	project::{Project, ProjectNode, ProjectPath},
-	util,
+	resolution::UnresolvedValue,
+	t, util,
 	vfs::Vfs,
 };
 
@@ -25,8 +27,22 @@ pub fn read_project(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
 
 	vfs.watch(path, false)?;
 
-	let meta = Meta::from_project(&project);
-	let mut snapshot = new_snapshot_node(&project.name, path, project.node, NodePath::new(), &meta.context, vfs)?;
+	snapshot_project(&project, path, vfs)
+}
+
+/// Snapshots already loaded project, used directly for
+/// projects read from stdin that don't exist on disk
+#[profiling::function]
+pub fn snapshot_project(project: &Project, path: &Path, vfs: &Vfs) -> Result<Snapshot> {
+	let meta = Meta::from_project(project);
+	let mut snapshot = new_snapshot_node(
+		&project.name,
+		path,
+		project.node.clone(),
+		NodePath::new(),
+		&meta.context,
+		vfs,
+	)?;
 
 	let mut source = Source::file(path).with_relevant(snapshot.meta.source.relevant().to_owned());
 	source.add_project(path);
@@ -36,6 +52,10 @@ pub fn read_project(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
 	Ok(snapshot)
 }
 
+/// Project node waiting to be snapshotted with its context and index of its
+/// parent among the snapshotted nodes, `None` if the parent is the root
+type PendingNode = (Option<usize>, String, ProjectNode, NodePath, Rc<Context>);
+
 #[profiling::function]
 pub fn new_snapshot_node(
 	name: &str,
@@ -44,50 +64,110 @@ pub fn new_snapshot_node(
 	node_path: NodePath,
 	context: &Context,
 	vfs: &Vfs,
+) -> Result<Snapshot> {
+	let mut stack = vec![];
+	let mut root = snapshot_node(name, path, node, node_path, context, None, &mut stack, vfs)?;
+	let existing = root.children.len();
+
+	// Descendants are snapshotted parent first with explicit stack so projects of any
+	// depth can be loaded, then attached to their parents starting from the deepest
+	let mut snapshots: Vec<(Option<usize>, usize, Snapshot)> = vec![];
+
+	while let Some((parent, name, node, node_path, context)) = stack.pop() {
+		let index = Some(snapshots.len());
+		let snapshot = snapshot_node(&name, path, node, node_path, &context, index, &mut stack, vfs)?;
+
+		snapshots.push((parent, snapshot.children.len(), snapshot));
+	}
+
+	while let Some((parent, existing, mut snapshot)) = snapshots.pop() {
+		// Children from nodes were attached last to first
+		snapshot.children[existing..].reverse();
+
+		match parent {
+			Some(parent) => snapshots[parent].2.add_child(snapshot),
+			None => root.add_child(snapshot),
+		}
+	}
+
+	root.children[existing..].reverse();
+
+	Ok(root)
+}
+
+/// Snapshots the node without its child nodes, these are pushed to
+/// the `stack` with `index` of the node in their original order
+#[allow(clippy::too_many_arguments)]
+fn snapshot_node(
+	name: &str,
+	path: &Path,
+	node: ProjectNode,
+	node_path: NodePath,
+	context: &Context,
+	index: Option<usize>,
+	stack: &mut Vec<PendingNode>,
+	vfs: &Vfs,
 ) -> Result<Snapshot> {
 	if node.class_name.is_some() && node.path.is_some() {
 		bail!("Failed to load project: $className and $path cannot be set at the same time");
 	}
 
+	// Node's own filter applies to itself and all of its descendants
+	let context = match &node.filter {
+		Some(filter) => Cow::Owned(context.with_filter(filter, path.get_parent())),
+		None => Cow::Borrowed(context),
+	};
+	let context = context.as_ref();
+
 	let class = if let Some(class_name) = &node.class_name {
 		class_name.to_owned()
-	} else if util::is_service(name) {
-		name.to_owned()
 	} else {
-		String::from("Folder")
+		let class = helpers::infer_class(name, context);
+		let legacy = helpers::legacy_class(name);
+
+		helpers::warn_reclassified(&format!("{}: {}", path.to_string(), node_path), &legacy, &class);
+
+		class.to_string()
 	};
 
+	let mut references = vec![];
+	let mut ref_paths = RefPaths::new();
+
 	let properties = {
 		let mut properties = UstrMap::new();
 
-		for (property, value) in &node.properties {
-			match value.clone().resolve(&class, property) {
+		for (property, value) in &node.properties_for(context.profile()) {
+			if let Some(path) = value.as_str().filter(|_| refs::is_ref(&class, property)) {
+				ref_paths.insert(*property, path.to_owned());
+				continue;
+			}
+
+			let value = match value {
+				UnresolvedValue::File(file) => {
+					helpers::resolve_file_ref(file, &class, property, path, context, vfs, &mut references)
+				}
+				value => value
+					.clone()
+					.resolve_checked(&class, property, context.property_ranges(), Some(path)),
+			};
+
+			match value {
 				Ok(value) => {
 					properties.insert(*property, value);
 				}
 				Err(err) => {
-					error!(
-						"Failed to parse property: {} at {}, JSON path: {}",
-						err,
-						path.display(),
-						node_path
-					);
+					error!("Failed to parse property: {}, JSON path: {}", err, node_path);
 				}
 			}
 		}
 
 		if let Some(attributes) = &node.attributes {
-			match attributes.clone().resolve(&class, "Attributes") {
+			match attributes.clone().resolve(&class, "Attributes", Some(path)) {
 				Ok(value) => {
 					properties.insert(Ustr::from("Attributes"), value);
 				}
 				Err(err) => {
-					error!(
-						"Failed to parse attributes: {} at {}, JSON path: {}",
-						err,
-						path.display(),
-						node_path
-					);
+					error!("Failed to parse attributes: {}, JSON path: {}", err, node_path);
 				}
 			}
 		}
@@ -102,7 +182,17 @@ pub fn new_snapshot_node(
 	let mut meta = Meta::new()
 		.with_source(Source::project(name, path, node.clone(), node_path.clone()))
 		.with_context(context)
-		.with_keep_unknowns(node.keep_unknowns.unwrap_or_else(|| util::is_service(&class)));
+		.with_keep_unknowns(
+			node.keep_unknowns
+				.clone()
+				.unwrap_or_else(|| util::is_service(&class).into()),
+		);
+
+	for reference in &references {
+		meta.source.add_reference(reference);
+	}
+
+	meta.set_ref_paths(ref_paths);
 
 	if class == "MeshPart" {
 		meta.set_mesh_source(helpers::save_mesh(&properties));
@@ -110,22 +200,32 @@ pub fn new_snapshot_node(
 
 	let mut snapshot = Snapshot::new()
 		.with_name(name)
-		.with_class(&class)
+		.with_class(class.as_str())
 		.with_properties(properties)
 		.with_meta(meta);
 
 	if let Some(path_node) = node.path {
-		let path = path.with_file_name(path_node.path()).clean();
+		let mut path = path.with_file_name(path_node.path()).clean();
+
+		if let Some(layout) = context.ts_layout() {
+			path = layout.map_path(&path);
+		}
 
 		if vfs.exists(&path) {
 			vfs.watch(&path, vfs.is_dir(&path))?;
 
-			if let Some(mut path_snapshot) = new_snapshot(&path, context, vfs)? {
-				path_snapshot.extend_properties(snapshot.properties);
-				path_snapshot.set_name(&snapshot.name);
+			if let Some(mut path_snapshot) =
+				new_snapshot_isolated(&path, &context.with_node_class(class.as_str()), vfs)?
+			{
+				path_snapshot.extend_properties(mem::take(&mut snapshot.properties));
+				path_snapshot
+					.meta
+					.ref_paths
+					.extend(mem::take(&mut snapshot.meta.ref_paths));
+				path_snapshot.set_name(mem::take(&mut snapshot.name));
 
 				if path_snapshot.class == "Folder" {
-					path_snapshot.set_class(&snapshot.class);
+					path_snapshot.set_class(snapshot.class);
 				}
 
 				// We want to keep the original inner source
@@ -135,28 +235,53 @@ pub fn new_snapshot_node(
 					.source
 					.extend_relevant(path_snapshot.meta.source.relevant().to_owned());
 
-				path_snapshot.meta.set_source(snapshot.meta.source);
-				path_snapshot
+				let keep_unknowns = path_snapshot
 					.meta
-					.set_keep_unknowns(path_snapshot.meta.keep_unknowns || snapshot.meta.keep_unknowns);
+					.get_keep_unknowns()
+					.merge(snapshot.meta.get_keep_unknowns());
+
+				path_snapshot.meta.set_source(mem::take(&mut snapshot.meta.source));
+				path_snapshot.meta.set_keep_unknowns(keep_unknowns);
 
 				snapshot = path_snapshot;
 			}
+		} else if let Some(data) = get_instance_data(name, Some(class.as_str()), &path, context, vfs)? {
+			// Path can be backed by its data file alone, e.g. `Lighting.data.json` of singleton services
+			vfs.watch(&data.path, false)?;
+			snapshot.apply_data(data);
 		} else if let ProjectPath::Required(_) = path_node {
-			argon_warn!(
-				"Path specified in the project does not exist: {}. Please create this path and restart Argon \
-				to watch for file changes in this path or remove it from the project to suppress this warning",
-				path.to_string().bold()
-			);
+			argon_warn!("{}", t!("project.path_missing", path = path.to_string().bold()));
 		}
 	}
 
-	for (node_name, node) in node.tree {
-		let node_path = node_path.join(&node_name);
-		let child = new_snapshot_node(&node_name, path, node, node_path, context, vfs)?;
+	if context.is_shallow() {
+		return Ok(snapshot);
+	}
 
-		snapshot.add_child(child);
+	let child_context = Rc::new(context.with_parent_class(snapshot.class));
+
+	// Reversed so the first child is snapshotted first
+	for (node_name, node) in node.tree.into_iter().rev() {
+		if !node.is_visible(context.profile()) {
+			trace!(
+				"Skipping project node {} as it is not part of the active profile",
+				node_path.join(&node_name)
+			);
+			continue;
+		}
+
+		let node_path = node_path.join(&node_name);
+		stack.push((index, node_name, node, node_path, child_context.clone()));
 	}
 
 	Ok(snapshot)
 }
+
+/// Project file with its whole tree
+pub struct ProjectMiddleware;
+
+impl MiddlewareImpl for ProjectMiddleware {
+	fn read(&self, path: &Path, _context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+		read_project(path, vfs)
+	}
+}
@@ -1,11 +1,14 @@
 use anyhow::Result;
 use std::path::Path;
 
-use super::helpers;
-use crate::{core::snapshot::Snapshot, vfs::Vfs};
+use super::{external::MiddlewareImpl, helpers};
+use crate::{
+	core::{meta::Context, snapshot::Snapshot},
+	vfs::Vfs,
+};
 
 #[profiling::function]
-pub fn read_rbxm(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
+pub fn read_rbxm(path: &Path, context: &Context, vfs: &Vfs) -> Result<Snapshot> {
 	let dom = rbx_binary::from_reader(vfs.read(path)?.as_slice())?;
 
 	let snapshot = if dom.root().children().len() == 1 {
@@ -16,5 +19,14 @@ pub fn read_rbxm(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
 		helpers::snapshot_from_dom(dom, id).with_class("Folder")
 	};
 
-	Ok(snapshot)
+	helpers::check_depth(snapshot, path, context)
+}
+
+/// Binary Roblox model
+pub struct RbxmMiddleware;
+
+impl MiddlewareImpl for RbxmMiddleware {
+	fn read(&self, path: &Path, context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+		read_rbxm(path, context, vfs)
+	}
 }
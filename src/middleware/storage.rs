@@ -0,0 +1,379 @@
+use anyhow::{Context as _, Result};
+use log::trace;
+use rbx_dom_weak::types::Ref;
+use serde_json::{Map, Value};
+use std::{
+	collections::BTreeMap,
+	path::{Path, PathBuf},
+};
+
+use super::data::{self, Data, DataSnapshot};
+use crate::{
+	constants::DATA_INDEX_FILE,
+	core::{
+		meta::{Context, Meta, SourceEntry, SourceKind},
+		tree::Tree,
+	},
+	ext::PathExt,
+	project::DataStorage,
+	vfs::Vfs,
+	Properties,
+};
+
+type Index = BTreeMap<String, Value>;
+
+/// Storage of instance data, either per-instance data files or entries
+/// of per-directory data indexes. Instances keep their data where it
+/// already is, `dataStorage` project setting only decides about new data
+pub trait DataStore {
+	/// Returns entry that new data of the instance at `path` named `name` is written to,
+	/// `path` is the file or the folder of the instance, even if it does not exist yet
+	fn locate(&self, name: &str, path: &Path, is_dir: bool, context: &Context) -> Option<SourceEntry>;
+
+	/// Writes instance data to the entry, returns entries that describe the
+	/// data afterwards, empty if there was no data to write (it is removed then)
+	fn write(
+		&self,
+		entry: &SourceEntry,
+		has_file: bool,
+		class: &str,
+		properties: Properties,
+		meta: &Meta,
+		vfs: &Vfs,
+	) -> Result<Vec<SourceEntry>>;
+
+	/// Updates only the original name kept in the data
+	fn write_original_name(&self, entry: &SourceEntry, meta: &Meta, vfs: &Vfs) -> Result<()>;
+
+	/// Removes data of the instance
+	fn remove(&self, entry: &SourceEntry, vfs: &Vfs) -> Result<()>;
+}
+
+pub struct FileStore;
+
+impl DataStore for FileStore {
+	fn locate(&self, name: &str, path: &Path, is_dir: bool, context: &Context) -> Option<SourceEntry> {
+		context
+			.data_rules(None)
+			.iter()
+			.find_map(|rule| rule.locate(path, name, is_dir))
+			.map(SourceEntry::Data)
+	}
+
+	fn write(
+		&self,
+		entry: &SourceEntry,
+		has_file: bool,
+		class: &str,
+		properties: Properties,
+		meta: &Meta,
+		vfs: &Vfs,
+	) -> Result<Vec<SourceEntry>> {
+		let paths = data::write_data(has_file, class, properties, entry.path(), meta, vfs)?;
+
+		Ok(paths.into_iter().map(SourceEntry::Data).collect())
+	}
+
+	fn write_original_name(&self, entry: &SourceEntry, meta: &Meta, vfs: &Vfs) -> Result<()> {
+		data::write_original_name(entry.path(), meta, vfs)
+	}
+
+	fn remove(&self, entry: &SourceEntry, vfs: &Vfs) -> Result<()> {
+		if vfs.exists(entry.path()) {
+			vfs.remove(entry.path())?;
+		}
+
+		Ok(())
+	}
+}
+
+pub struct IndexStore;
+
+impl DataStore for IndexStore {
+	fn locate(&self, name: &str, path: &Path, _is_dir: bool, _context: &Context) -> Option<SourceEntry> {
+		Some(SourceEntry::Index(index_path(path), name.to_owned()))
+	}
+
+	fn write(
+		&self,
+		entry: &SourceEntry,
+		has_file: bool,
+		class: &str,
+		properties: Properties,
+		meta: &Meta,
+		vfs: &Vfs,
+	) -> Result<Vec<SourceEntry>> {
+		let SourceEntry::Index(path, key) = entry else {
+			return FileStore.write(entry, has_file, class, properties, meta, vfs);
+		};
+
+		let value = data::data_value(has_file, class, properties, meta)?;
+		let is_empty = value.is_none();
+
+		update_index(path, vfs, |index| match value {
+			Some(value) => {
+				index.insert(key.to_owned(), value);
+			}
+			None => {
+				index.remove(key);
+			}
+		})?;
+
+		Ok(if is_empty { vec![] } else { vec![entry.clone()] })
+	}
+
+	fn write_original_name(&self, entry: &SourceEntry, meta: &Meta, vfs: &Vfs) -> Result<()> {
+		let SourceEntry::Index(path, key) = entry else {
+			return FileStore.write_original_name(entry, meta, vfs);
+		};
+
+		update_index(path, vfs, |index| {
+			let value = index.entry(key.to_owned()).or_insert_with(|| Value::Object(Map::new()));
+
+			if let Value::Object(object) = value {
+				match &meta.original_name {
+					Some(original_name) => object.insert(String::from("originalName"), original_name.as_str().into()),
+					None => object.remove("originalName"),
+				};
+
+				if object.is_empty() {
+					index.remove(key);
+				}
+			}
+		})
+	}
+
+	fn remove(&self, entry: &SourceEntry, vfs: &Vfs) -> Result<()> {
+		let SourceEntry::Index(path, key) = entry else {
+			return FileStore.remove(entry, vfs);
+		};
+
+		update_index(path, vfs, |index| {
+			index.remove(key);
+		})
+	}
+}
+
+/// Store of the existing data entry
+pub fn of(entry: &SourceEntry) -> &'static dyn DataStore {
+	match entry {
+		SourceEntry::Index(_, _) => &IndexStore,
+		_ => &FileStore,
+	}
+}
+
+/// Store new instance data is written to
+pub fn for_new(context: &Context) -> &'static dyn DataStore {
+	match context.data_storage() {
+		DataStorage::Files => &FileStore,
+		DataStorage::Index => &IndexStore,
+	}
+}
+
+/// Path of the data index that holds data of the instance at `path`
+pub fn index_path(path: &Path) -> PathBuf {
+	path.get_parent().join(DATA_INDEX_FILE)
+}
+
+pub fn is_index(path: &Path) -> bool {
+	path.get_name() == DATA_INDEX_FILE
+}
+
+/// Whether the data index has an entry with the given key
+pub fn has_entry(path: &Path, key: &str, vfs: &Vfs) -> bool {
+	read_index(path, vfs).is_ok_and(|index| index.contains_key(key))
+}
+
+/// Reads data of the instance named `name` from the data index next to `path`
+pub fn read_entry(
+	name: &str,
+	class: Option<&str>,
+	path: &Path,
+	context: &Context,
+	vfs: &Vfs,
+) -> Result<Option<DataSnapshot>> {
+	let index_path = index_path(path);
+
+	if !vfs.exists(&index_path) {
+		return Ok(None);
+	}
+
+	let Some(value) = read_index(&index_path, vfs)?.remove(name) else {
+		return Ok(None);
+	};
+
+	let data: Data = serde_json::from_value(value)
+		.with_context(|| format!("Failed to parse entry {} of {}", name, index_path.display()))?;
+
+	let mut snapshot = data::resolve_data(data, &index_path, class, context, vfs);
+	snapshot.key = Some(name.to_owned());
+
+	Ok(Some(snapshot))
+}
+
+/// Renames the entry of the data index, returns the renamed entry
+pub fn rename_entry(entry: &SourceEntry, name: &str, vfs: &Vfs) -> Result<SourceEntry> {
+	let SourceEntry::Index(path, key) = entry else {
+		return Ok(entry.clone());
+	};
+
+	if key != name {
+		update_index(path, vfs, |index| {
+			if let Some(value) = index.remove(key) {
+				index.insert(name.to_owned(), value);
+			}
+		})?;
+	}
+
+	Ok(SourceEntry::Index(path.to_owned(), name.to_owned()))
+}
+
+pub fn read_index(path: &Path, vfs: &Vfs) -> Result<Index> {
+	let contents = vfs.read_to_string(path)?;
+
+	if contents.trim().is_empty() {
+		return Ok(Index::new());
+	}
+
+	serde_json::from_str(&contents).with_context(|| format!("Failed to parse data index {}", path.display()))
+}
+
+/// Writes the data index, removes it once it has no entries
+pub fn write_index(path: &Path, index: &Index, vfs: &Vfs) -> Result<()> {
+	if index.is_empty() {
+		if vfs.exists(path) {
+			vfs.remove(path)?;
+		}
+
+		return Ok(());
+	}
+
+	data::write_value(path, index, vfs)
+}
+
+fn update_index(path: &Path, vfs: &Vfs, update: impl FnOnce(&mut Index)) -> Result<()> {
+	let mut index = if vfs.exists(path) {
+		read_index(path, vfs)?
+	} else {
+		Index::new()
+	};
+
+	let previous = index.clone();
+	update(&mut index);
+
+	if index == previous && vfs.exists(path) {
+		return Ok(());
+	}
+
+	write_index(path, &index, vfs)
+}
+
+/// Outcome of `migrate`
+#[derive(Debug, Default)]
+pub struct DataMigration {
+	/// Data files or index entries that were moved to the other storage
+	pub migrated: Vec<PathBuf>,
+	/// Data that was left where it is, because it reads properties
+	/// from other files or the target already has data of the instance
+	pub skipped: Vec<PathBuf>,
+}
+
+/// Moves data of the instance and all of its descendants to the given
+/// storage and updates their sources in the same pass. Project nodes
+/// always keep their data files as they are not part of any directory
+pub fn migrate(id: Ref, target: DataStorage, tree: &mut Tree, vfs: &Vfs) -> Result<DataMigration> {
+	let mut migration = DataMigration::default();
+	let mut stack = vec![id];
+
+	while let Some(id) = stack.pop() {
+		if let Some(instance) = tree.get_instance(id) {
+			stack.extend(instance.children().iter().rev());
+		}
+
+		let Some(meta) = tree.get_meta(id) else {
+			continue;
+		};
+
+		let SourceKind::Path(path) = meta.source.get() else {
+			continue;
+		};
+
+		let Some(entry) = meta.source.get_data_entry() else {
+			continue;
+		};
+
+		let entry = match (target, entry) {
+			(DataStorage::Index, SourceEntry::Data(data_path)) => {
+				let Some(name) = get_name(path, &meta.context, vfs) else {
+					continue;
+				};
+
+				let index_path = index_path(path);
+
+				if data::has_file_refs(data_path, vfs) || has_entry(&index_path, &name, vfs) {
+					migration.skipped.push(data_path.to_owned());
+					continue;
+				}
+
+				trace!(from:% = data_path.display(), to:% = index_path.display(), key = name.as_str(); "Migrating data to index");
+
+				let value = data::read_value(data_path, vfs)?;
+				update_index(&index_path, vfs, |index| {
+					index.insert(name.clone(), value);
+				})?;
+
+				for path in [data_path.as_path()].into_iter().chain(meta.source.get_includes()) {
+					vfs.remove(path)?;
+				}
+
+				migration.migrated.push(data_path.to_owned());
+
+				SourceEntry::Index(index_path, name)
+			}
+			(DataStorage::Files, SourceEntry::Index(index_path, key)) => {
+				let Some(SourceEntry::Data(data_path)) = FileStore.locate(key, path, vfs.is_dir(path), &meta.context)
+				else {
+					continue;
+				};
+
+				if vfs.exists(&data_path) {
+					migration.skipped.push(index_path.to_owned());
+					continue;
+				}
+
+				trace!(from:% = index_path.display(), key = key.as_str(), to:% = data_path.display(); "Migrating data to file");
+
+				let Some(value) = read_index(index_path, vfs)?.remove(key) else {
+					continue;
+				};
+
+				data::write_value(&data_path, &value, vfs)?;
+				IndexStore.remove(entry, vfs)?;
+
+				migration.migrated.push(data_path.to_owned());
+
+				SourceEntry::Data(data_path)
+			}
+			_ => continue,
+		};
+
+		let mut meta = meta.clone();
+		meta.source.set_data_entries(vec![entry]);
+
+		tree.update_meta(id, meta);
+	}
+
+	Ok(migration)
+}
+
+/// Name that data of the instance at `path` is looked up by, see `get_instance_data`
+fn get_name(path: &Path, context: &Context, vfs: &Vfs) -> Option<String> {
+	if vfs.is_dir(path) {
+		Some(path.get_name().to_owned())
+	} else {
+		context
+			.sync_rules()
+			.find_map(|rule| rule.resolve(path))
+			.map(|resolved| resolved.name)
+	}
+}
@@ -2,7 +2,11 @@ use anyhow::Result;
 use rbx_dom_weak::{types::Variant, HashMapExt, Ustr, UstrMap};
 use std::path::Path;
 
-use crate::{core::snapshot::Snapshot, vfs::Vfs};
+use crate::{
+	core::{meta::Context, snapshot::Snapshot},
+	middleware::external::MiddlewareImpl,
+	vfs::Vfs,
+};
 
 #[profiling::function]
 pub fn read_toml(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
@@ -16,3 +20,12 @@ pub fn read_toml(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
 
 	Ok(Snapshot::new().with_class("ModuleScript").with_properties(properties))
 }
+
+/// TOML file read as `ModuleScript` returning its contents
+pub struct TomlMiddleware;
+
+impl MiddlewareImpl for TomlMiddleware {
+	fn read(&self, path: &Path, _context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+		read_toml(path, vfs)
+	}
+}
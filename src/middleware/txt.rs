@@ -2,23 +2,43 @@ use anyhow::Result;
 use rbx_dom_weak::{types::Variant, HashMapExt, Ustr, UstrMap};
 use std::path::Path;
 
-use crate::{core::snapshot::Snapshot, vfs::Vfs, Properties};
+use crate::{
+	core::{meta::Context, snapshot::Snapshot},
+	middleware::{external::MiddlewareImpl, helpers},
+	vfs::Vfs,
+	Properties,
+};
 
 #[profiling::function]
 pub fn read_txt(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
 	let value = vfs.read_to_string(path)?;
 
-	let mut properties = UstrMap::new();
+	let mut properties = UstrMap::with_capacity(1);
 	properties.insert(Ustr::from("Value"), Variant::String(value));
 
 	Ok(Snapshot::new().with_class("StringValue").with_properties(properties))
 }
 
 #[profiling::function]
-pub fn write_txt(mut properties: Properties, path: &Path, vfs: &Vfs) -> Result<Properties> {
-	if let Some(Variant::String(value)) = properties.remove(&Ustr::from("Value")) {
+pub fn write_txt(properties: &Properties, path: &Path, vfs: &Vfs) -> Result<Properties> {
+	let value_name = Ustr::from("Value");
+
+	if let Some(Variant::String(value)) = properties.get(&value_name) {
 		vfs.write(path, value.as_bytes())?;
 	}
 
-	Ok(properties)
+	Ok(helpers::without(properties, value_name))
+}
+
+/// Plain text file read as `StringValue`
+pub struct TxtMiddleware;
+
+impl MiddlewareImpl for TxtMiddleware {
+	fn read(&self, path: &Path, _context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+		read_txt(path, vfs)
+	}
+
+	fn write(&self, properties: &Properties, path: &Path, vfs: &Vfs) -> Result<Properties> {
+		write_txt(properties, path, vfs)
+	}
 }
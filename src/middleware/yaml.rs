@@ -2,7 +2,11 @@ use anyhow::Result;
 use rbx_dom_weak::{types::Variant, HashMapExt, Ustr, UstrMap};
 use std::path::Path;
 
-use crate::{core::snapshot::Snapshot, vfs::Vfs};
+use crate::{
+	core::{meta::Context, snapshot::Snapshot},
+	middleware::external::MiddlewareImpl,
+	vfs::Vfs,
+};
 
 #[profiling::function]
 pub fn read_yaml(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
@@ -16,3 +20,12 @@ pub fn read_yaml(path: &Path, vfs: &Vfs) -> Result<Snapshot> {
 
 	Ok(Snapshot::new().with_class("ModuleScript").with_properties(properties))
 }
+
+/// YAML file read as `ModuleScript` returning its contents
+pub struct YamlMiddleware;
+
+impl MiddlewareImpl for YamlMiddleware {
+	fn read(&self, path: &Path, _context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+		read_yaml(path, vfs)
+	}
+}
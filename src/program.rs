@@ -3,12 +3,13 @@ use colored::Colorize;
 use log::LevelFilter;
 use std::{
 	env,
+	fs::File,
 	io::{Error, ErrorKind},
 	path::{Path, PathBuf},
 	process::{Child, Command, Output, Stdio},
 };
 
-use crate::{argon_error, config::Config, ext::WriteStyleExt, logger, util};
+use crate::{argon_error, config::Config, ext::WriteStyleExt, logger, t, util};
 
 #[derive(PartialEq)]
 pub enum ProgramName {
@@ -85,6 +86,50 @@ impl Program {
 		}
 	}
 
+	/// Spawns the process detached from the current terminal, so it survives
+	/// it being closed, both stdout and stderr are redirected to the `log`
+	pub fn spawn_detached(&mut self, log: File, log_dir: &Path) -> Result<Option<Child>> {
+		let mut command = self.get_command();
+
+		command
+			.stdin(Stdio::null())
+			.stdout(log.try_clone()?)
+			.stderr(log)
+			.env("RUST_LOG_STYLE", "never")
+			.env("RUST_YES", "1")
+			.env("ARGON_DAEMON", log_dir);
+
+		// Start a new session so the process is no longer
+		// attached to the terminal and won't receive its SIGHUP
+		#[cfg(unix)]
+		unsafe {
+			use std::os::unix::process::CommandExt;
+
+			command.pre_exec(|| {
+				if libc::setsid() == -1 {
+					return Err(Error::last_os_error());
+				}
+
+				Ok(())
+			});
+		}
+
+		#[cfg(target_os = "windows")]
+		{
+			use std::os::windows::process::CommandExt;
+
+			const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
+			const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+			command.creation_flags(CREATE_NEW_PROCESS_GROUP | CREATE_NO_WINDOW);
+		}
+
+		match command.spawn() {
+			Ok(child) => Ok(Some(child)),
+			Err(err) => self.handle_error(err),
+		}
+	}
+
 	pub fn output(&mut self) -> Result<Option<Output>> {
 		let result = self.get_command().output();
 
@@ -164,21 +209,19 @@ impl Program {
 
 	fn get_error(&self, error: &str) -> String {
 		match self.program {
-			ProgramName::Git => format!(
-				"{}: {} is not installed. To suppress this message remove {} option or disable {} setting",
-				error,
-				"Git".bold(),
-				"--git".bold(),
-				"use_git".bold()
+			ProgramName::Git => t!(
+				"program.git_missing",
+				error = error,
+				program = "Git".bold(),
+				flag = "--git".bold(),
+				setting = "use_git".bold()
 			),
-			ProgramName::Npm | ProgramName::Npx => {
-				format!(
-					"{}: {} is not installed",
-					error,
-					Config::new().package_manager.as_str().bold()
-				)
-			}
-			ProgramName::Wally => format!("{}: {} is not installed", error, "Wally"),
+			ProgramName::Npm | ProgramName::Npx => t!(
+				"program.missing",
+				error = error,
+				program = Config::new().package_manager.as_str().bold()
+			),
+			ProgramName::Wally => t!("program.missing", error = error, program = "Wally"),
 			ProgramName::Argon => unreachable!(),
 		}
 	}
@@ -193,7 +236,7 @@ impl Program {
 			ProgramName::Argon => unreachable!(),
 		};
 
-		format!("Do you want to install {} now?", program.bold())
+		t!("program.install_prompt", program = program.bold())
 	}
 
 	fn get_link(&self) -> String {
@@ -1,26 +1,340 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use colored::Colorize;
 use json_formatter::JsonFormatter;
+use log::{trace, warn};
 use rbx_dom_weak::{types::Ref, Ustr, UstrMap};
 use serde::{Deserialize, Serialize};
-use serde_json::Serializer;
+use serde_json::{Map, Serializer, Value};
 use std::{
+	cell::RefCell,
 	collections::{BTreeMap, HashMap},
-	fs, mem,
+	env, fs,
+	io::{self, Read},
+	mem,
+	ops::{Deref, DerefMut},
 	path::{Path, PathBuf},
+	sync::{Condvar, Mutex, OnceLock, RwLock},
+	thread::{self, ThreadId},
 };
 
 use crate::{
+	backups,
+	class_meta::IconRule,
 	config::Config,
+	constants::{DEFAULT_ASSETS_PATH, LAZY_PROPERTY_THRESHOLD, PROJECT_LOAD_BACKOFF, PROJECT_LOAD_RETRIES},
 	core::{
-		meta::{NodePath, SyncRule},
+		blobs,
+		meta::{IgnoreRule, NodePath, SyncRule},
 		tree::Tree,
 	},
+	defs::{self, ResolvedRef},
 	ext::{PathExt, ResultExt, WriterExt},
 	glob::Glob,
+	hooks::Hooks,
+	integration::TsLayout,
+	ranges::Range,
 	resolution::UnresolvedValue,
+	util,
+	vfs::{std_backend, undo_log, verify},
 };
 
+/// Build profile selected from the command line
+static PROFILE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Sets build profile selected from the command line, it
+/// takes precedence over `ARGON_PROFILE` and the `profile` setting
+pub fn set_profile(profile: Option<String>) {
+	*PROFILE.write().unwrap() = profile;
+}
+
+/// Returns build profile requested by the user, if any
+pub fn active_profile() -> Option<String> {
+	PROFILE
+		.read()
+		.unwrap()
+		.clone()
+		.or_else(|| env::var("ARGON_PROFILE").ok())
+		.or_else(|| Some(Config::new().profile.clone()))
+		.filter(|profile| !profile.is_empty())
+}
+
+/// File name of the synthetic path of projects read from stdin
+pub const STDIN_PROJECT_NAME: &str = "stdin.project.json";
+
+/// Reads raw project JSON, editors that don't write atomically can leave the file
+/// empty or truncated for a moment so parse failures are retried with backoff
+fn read(path: &Path) -> Result<Value> {
+	let mut backoff = PROJECT_LOAD_BACKOFF;
+	let mut attempt = 1;
+
+	loop {
+		let result = fs::read_to_string(path)
+			.map_err(anyhow::Error::from)
+			.and_then(|project| Ok(serde_json::from_str(&project)?));
+
+		match result {
+			Ok(project) => return Ok(project),
+			Err(err) => {
+				let missing = err
+					.downcast_ref::<io::Error>()
+					.is_some_and(|err| err.kind() == io::ErrorKind::NotFound);
+
+				if missing || attempt >= PROJECT_LOAD_RETRIES {
+					return Err(err);
+				}
+
+				trace!("Failed to read project (attempt {}): {}, retrying..", attempt, err);
+
+				thread::sleep(backoff);
+
+				backoff *= 2;
+				attempt += 1;
+			}
+		}
+	}
+}
+
+#[derive(Default)]
+struct EditLocks {
+	/// Path being edited with its owner thread and
+	/// nesting depth, so nested edits don't deadlock
+	paths: Mutex<HashMap<PathBuf, (ThreadId, usize)>>,
+	released: Condvar,
+}
+
+fn edit_locks() -> &'static EditLocks {
+	static LOCKS: OnceLock<EditLocks> = OnceLock::new();
+	LOCKS.get_or_init(EditLocks::default)
+}
+
+/// Edit lock of a single project path, released when dropped
+struct EditLock {
+	path: PathBuf,
+}
+
+impl EditLock {
+	fn acquire(path: &Path) -> Self {
+		let locks = edit_locks();
+		let thread = thread::current().id();
+		let mut paths = locks.paths.lock().unwrap();
+
+		loop {
+			match paths.get_mut(path) {
+				Some((owner, depth)) if *owner == thread => {
+					*depth += 1;
+					break;
+				}
+				Some(_) => paths = locks.released.wait(paths).unwrap(),
+				None => {
+					paths.insert(path.to_owned(), (thread, 1));
+					break;
+				}
+			}
+		}
+
+		Self { path: path.to_owned() }
+	}
+}
+
+impl Drop for EditLock {
+	fn drop(&mut self) {
+		let locks = edit_locks();
+		let mut paths = locks.paths.lock().unwrap();
+
+		if let Some((_, depth)) = paths.get_mut(&self.path) {
+			*depth -= 1;
+
+			if *depth == 0 {
+				paths.remove(&self.path);
+				locks.released.notify_all();
+			}
+		}
+	}
+}
+
+/// Project files edited during the current syncback batch of a thread,
+/// their edit locks are held until the batch ends
+#[derive(Default)]
+struct Batch {
+	/// Edited projects with whether they were modified
+	projects: HashMap<PathBuf, (Project, bool)>,
+	/// Projects edited since the last `checkpoint_batch` as they were
+	/// before, `None` if they were not edited earlier in the batch
+	checkpoint: HashMap<PathBuf, Option<(Project, bool)>>,
+	locks: HashMap<PathBuf, EditLock>,
+}
+
+thread_local! {
+	/// `None` if project edits are saved right away
+	static BATCH: RefCell<Option<Batch>> = const { RefCell::new(None) };
+}
+
+/// Starts batching project edits of the current thread, every file is then
+/// loaded once and saved only when the batch ends, see `end_batch`
+pub fn begin_batch() {
+	BATCH.with_borrow_mut(|batch| *batch = Some(Batch::default()));
+}
+
+/// Saves projects modified during the batch, returns number of saved files
+pub fn end_batch() -> Result<usize> {
+	let Some(batch) = BATCH.with_borrow_mut(Option::take) else {
+		return Ok(0);
+	};
+
+	let mut saved = 0;
+	let mut result = Ok(());
+
+	for (path, (project, modified)) in batch.projects {
+		if !modified {
+			continue;
+		}
+
+		trace!(path:% = path.display(); "Saving batched project edits");
+
+		match project.save(&path) {
+			Ok(()) => saved += 1,
+			Err(err) => {
+				warn!("Failed to save project {}: {}", path.display(), err);
+				result = result.and(Err(err));
+			}
+		}
+	}
+
+	result.map(|_| saved)
+}
+
+/// Marks project edits made so far as kept even if the batch fails, see `fail_batch`
+pub fn checkpoint_batch() {
+	BATCH.with_borrow_mut(|batch| {
+		if let Some(batch) = batch {
+			batch.checkpoint.clear();
+		}
+	});
+}
+
+/// Drops project edits made after the last checkpoint and saves the rest,
+/// returns number of saved files. Used when an operation of the batch fails
+/// while the operations before it stay applied to the tree and files
+pub fn fail_batch() -> Result<usize> {
+	BATCH.with_borrow_mut(|batch| {
+		if let Some(batch) = batch {
+			for (path, edited) in mem::take(&mut batch.checkpoint) {
+				match edited {
+					Some(edited) => batch.projects.insert(path, edited),
+					None => batch.projects.remove(&path),
+				};
+			}
+		}
+	});
+
+	end_batch()
+}
+
+/// Discards project edits of the batch, nothing is saved
+pub fn abort_batch() {
+	BATCH.with_borrow_mut(|batch| *batch = None);
+}
+
+/// Project loaded for a load-modify-save sequence,
+/// other edits of the same path wait until it is dropped.
+/// During a batch the project is shared by all edits of the
+/// batch and saving it is deferred until the batch ends
+pub struct ProjectEdit {
+	project: Option<Project>,
+	path: PathBuf,
+	batched: bool,
+	modified: bool,
+	_lock: Option<EditLock>,
+}
+
+impl ProjectEdit {
+	fn new(path: &Path) -> Result<Self> {
+		let batched = BATCH.with_borrow_mut(|batch| -> Result<Option<(Project, bool)>> {
+			let Some(batch) = batch else {
+				return Ok(None);
+			};
+
+			if !batch.checkpoint.contains_key(path) {
+				batch
+					.checkpoint
+					.insert(path.to_owned(), batch.projects.get(path).cloned());
+			}
+
+			if let Some(edited) = batch.projects.remove(path) {
+				return Ok(Some(edited));
+			}
+
+			if !batch.locks.contains_key(path) {
+				batch.locks.insert(path.to_owned(), EditLock::acquire(path));
+			}
+
+			Ok(Some((Project::load(path)?, false)))
+		})?;
+
+		if let Some((project, modified)) = batched {
+			return Ok(Self {
+				project: Some(project),
+				path: path.to_owned(),
+				batched: true,
+				modified,
+				_lock: None,
+			});
+		}
+
+		let lock = EditLock::acquire(path);
+
+		Ok(Self {
+			project: Some(Project::load(path)?),
+			path: path.to_owned(),
+			batched: false,
+			modified: false,
+			_lock: Some(lock),
+		})
+	}
+
+	/// Saves the project, or only marks it as modified if it is
+	/// part of the batch so it is saved once when the batch ends
+	pub fn save(&mut self, path: &Path) -> Result<()> {
+		if self.batched {
+			self.modified = true;
+			Ok(())
+		} else {
+			Project::save(self, path)
+		}
+	}
+}
+
+/// Returns batched project so the next edit of the batch continues from it
+impl Drop for ProjectEdit {
+	fn drop(&mut self) {
+		if !self.batched {
+			return;
+		}
+
+		let project = self.project.take().unwrap();
+
+		BATCH.with_borrow_mut(|batch| {
+			if let Some(batch) = batch {
+				batch.projects.insert(self.path.clone(), (project, self.modified));
+			}
+		});
+	}
+}
+
+impl Deref for ProjectEdit {
+	type Target = Project;
+
+	fn deref(&self) -> &Self::Target {
+		self.project.as_ref().unwrap()
+	}
+}
+
+impl DerefMut for ProjectEdit {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		self.project.as_mut().unwrap()
+	}
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ProjectPath {
@@ -53,16 +367,249 @@ pub struct ProjectNode {
 	#[serde(rename = "$tags", default, skip_serializing_if = "Vec::is_empty")]
 	pub tags: Vec<String>,
 
+	#[serde(rename = "$filter", skip_serializing_if = "Option::is_none")]
+	pub filter: Option<ProjectFilter>,
+
+	/// Build profiles this node is part of, all if empty
+	#[serde(rename = "$profiles", default, skip_serializing_if = "Vec::is_empty")]
+	pub profiles: Vec<String>,
+	/// Properties that override `$properties` in the given profile
+	#[serde(rename = "$profileProperties", default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub profile_properties: BTreeMap<String, UstrMap<UnresolvedValue>>,
+
 	#[serde(
 		rename = "$keepUnknowns",
 		alias = "$ignoreUnknownInstances",
 		default,
 		skip_serializing_if = "Option::is_none"
 	)]
-	pub keep_unknowns: Option<bool>,
+	pub keep_unknowns: Option<KeepUnknowns>,
+
+	/// Whether syncback is allowed from this node when its `$path`
+	/// backs other nodes too, see `core::helpers::mirrors::Mirror`
+	#[serde(rename = "$primary", skip_serializing_if = "Option::is_none")]
+	pub primary: Option<bool>,
+
+	/// Whether the node was added by Argon, e.g. for singleton
+	/// services in `servicesPath`, such nodes are never saved
+	#[serde(skip)]
+	pub implicit: bool,
+}
+
+impl ProjectNode {
+	/// Whether this node is part of the tree in the given profile
+	pub fn is_visible(&self, profile: Option<&str>) -> bool {
+		self.profiles.is_empty() || profile.is_some_and(|profile| self.profiles.iter().any(|name| name == profile))
+	}
+
+	/// Returns `$properties` merged with overrides of the given profile
+	pub fn properties_for(&self, profile: Option<&str>) -> UstrMap<UnresolvedValue> {
+		let mut properties = self.properties.clone();
+
+		if let Some(overrides) = profile.and_then(|profile| self.profile_properties.get(profile)) {
+			properties.extend(overrides.iter().map(|(key, value)| (*key, value.clone())));
+		}
+
+		properties
+	}
+
+	/// Moves values of properties overridden in the given profile from `$properties`
+	/// back to the override map, `previous` are the properties before serialization
+	/// so the values shared with other profiles are kept intact
+	pub fn keep_profile_overrides(&mut self, profile: Option<&str>, previous: &UstrMap<UnresolvedValue>) {
+		let Some(overrides) = profile.and_then(|profile| self.profile_properties.get_mut(profile)) else {
+			return;
+		};
+
+		overrides.retain(|key, value| {
+			let current = self.properties.remove(key);
+
+			if let Some(previous) = previous.get(key) {
+				self.properties.insert(*key, previous.clone());
+			}
+
+			match current {
+				Some(current) => {
+					*value = current;
+					true
+				}
+				// Property was removed in the active profile
+				None => false,
+			}
+		});
+	}
+
+	fn check_profiles(&self, profiles: &[String], name: &str) -> Result<()> {
+		let unknown = self
+			.profiles
+			.iter()
+			.chain(self.profile_properties.keys())
+			.find(|profile| !profiles.contains(profile));
+
+		if let Some(profile) = unknown {
+			bail!(
+				"Node {} uses profile {} which is not listed in project's {}",
+				name.bold(),
+				profile.bold(),
+				"profiles".bold()
+			);
+		}
+
+		for (name, node) in &self.tree {
+			node.check_profiles(profiles, name)?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Syncback rules of a single project node and its descendants,
+/// merged with the inherited ones as described in `SyncbackFilter`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectFilter {
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub include_names: Vec<Glob>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub exclude_names: Vec<Glob>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub include_classes: Vec<Glob>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub exclude_classes: Vec<Glob>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub include_paths: Vec<Glob>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub exclude_paths: Vec<Glob>,
+}
+
+/// Which unknown children of an instance the client keeps, either a plain
+/// boolean or a filter that keeps only the matching ones, e.g.
+/// `{"classes": ["Camera"], "names": ["Debug*"]}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeepUnknowns {
+	All(bool),
+	Filter(UnknownsFilter),
+}
+
+impl KeepUnknowns {
+	/// Whether any unknown children are kept
+	pub fn is_kept(&self) -> bool {
+		match self {
+			KeepUnknowns::All(keep) => *keep,
+			KeepUnknowns::Filter(_) => true,
+		}
+	}
+
+	pub fn filter(&self) -> Option<&UnknownsFilter> {
+		match self {
+			KeepUnknowns::All(_) => None,
+			KeepUnknowns::Filter(filter) => Some(filter),
+		}
+	}
+
+	/// Whether unknown child of the given class and name is kept
+	pub fn matches(&self, class: &str, name: &str) -> bool {
+		match self {
+			KeepUnknowns::All(keep) => *keep,
+			KeepUnknowns::Filter(filter) => filter.matches(class, name),
+		}
+	}
+
+	/// Combines settings of two sources of the same instance,
+	/// keeping everything that either of them keeps
+	pub fn merge(self, other: Self) -> Self {
+		match (self, other) {
+			(KeepUnknowns::All(true), _) | (_, KeepUnknowns::All(true)) => KeepUnknowns::All(true),
+			(KeepUnknowns::Filter(mut filter), KeepUnknowns::Filter(other)) => {
+				filter.extend(other);
+				KeepUnknowns::Filter(filter)
+			}
+			(KeepUnknowns::Filter(filter), _) | (_, KeepUnknowns::Filter(filter)) => KeepUnknowns::Filter(filter),
+			_ => KeepUnknowns::All(false),
+		}
+	}
+}
+
+impl Default for KeepUnknowns {
+	fn default() -> Self {
+		KeepUnknowns::All(false)
+	}
+}
+
+impl From<bool> for KeepUnknowns {
+	fn from(keep: bool) -> Self {
+		KeepUnknowns::All(keep)
+	}
+}
+
+/// Unknown children kept when they match any of the classes or name globs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UnknownsFilter {
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub classes: Vec<String>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub names: Vec<Glob>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl UnknownsFilter {
+	pub fn matches(&self, class: &str, name: &str) -> bool {
+		self.classes.iter().any(|filtered| filtered == class) || self.names.iter().any(|glob| glob.matches(name))
+	}
+
+	fn extend(&mut self, other: Self) {
+		for class in other.classes {
+			if !self.classes.contains(&class) {
+				self.classes.push(class);
+			}
+		}
+
+		for name in other.names {
+			if !self.names.contains(&name) {
+				self.names.push(name);
+			}
+		}
+	}
+}
+
+/// Which property values are left out of the initial sync
+/// for clients that can request them later from `/properties`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LazyProperties {
+	/// Names of string properties that are deferred, `Source` by default
+	#[serde(default = "default_lazy_properties")]
+	pub properties: Vec<String>,
+	/// Smaller values are always sent, binary strings
+	/// larger than this are deferred regardless of their name
+	#[serde(default = "default_lazy_threshold")]
+	pub threshold: usize,
+}
+
+impl Default for LazyProperties {
+	fn default() -> Self {
+		Self {
+			properties: default_lazy_properties(),
+			threshold: default_lazy_threshold(),
+		}
+	}
+}
+
+/// Project convention for classes of instances without an explicit one,
+/// e.g. every `Remotes` inside `ReplicatedStorage` is a `Configuration`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InferenceRule {
+	/// Exact name of the instance
+	pub name: String,
+	/// Class of the parent instance, any parent matches if not set
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub parent: Option<String>,
+	pub class: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SyncbackSettings {
 	#[serde(alias = "excludeGlobs", default, skip_serializing_if = "Vec::is_empty")]
@@ -76,22 +623,299 @@ pub struct SyncbackSettings {
 	pub ignore_properties: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MemoryMode {
+	/// Keep all property values in memory
+	#[default]
+	Normal,
+	/// Drop large property values from memory and re-read them when needed
+	Low,
+}
+
+/// Whether folder instances are turned back into files
+/// once their last child is removed and the other way around
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CollapseMode {
+	/// Collapse folders as soon as their last child is removed
+	#[default]
+	Always,
+	/// Never collapse folders nor expand files into folders
+	Never,
+	/// Collapse folders that are still empty once the whole syncback batch is applied
+	BatchEnd,
+}
+
+/// How instances of a class added from Studio are written, only classes written
+/// by a file middleware (scripts, `StringValue`, `LocalizationTable`) can be either
+/// a file or a folder with `init` file, other classes are always written as folders
+/// with a data file as a lone data file is not read back as an instance
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayoutPreference {
+	/// Folder only when the instance has children, collapsed per `collapseEmptyFolders`
+	#[default]
+	Auto,
+	/// Always a folder, even without children, and never collapsed back into a file
+	Folder,
+	/// File whenever possible, expanded into a folder by its first child
+	/// and collapsed as soon as the last one is removed
+	File,
+}
+
+/// Which properties of instance data files are split into included
+/// files (e.g. `Name.Attributes.data.json`) during syncback
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataIncludes {
+	/// Names of properties that are always included, e.g. `Attributes`
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub properties: Vec<String>,
+	/// Properties whose serialized value has at least this many bytes are included too
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub threshold: Option<usize>,
+}
+
+/// Syntax of instance data files, existing files keep theirs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFormat {
+	#[default]
+	Json,
+	Yaml,
+}
+
+impl DataFormat {
+	/// Returns format of the data file, e.g. `Yaml` for `Name.data.yml`
+	pub fn of(path: &Path) -> Self {
+		match path.extension().and_then(|extension| extension.to_str()) {
+			Some("yaml" | "yml") => Self::Yaml,
+			_ => Self::Json,
+		}
+	}
+
+	pub fn extension(&self) -> &'static str {
+		match self {
+			Self::Json => "json",
+			Self::Yaml => "yaml",
+		}
+	}
+}
+
+/// Which characters names of synced back instances can contain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NamePolicy {
+	/// Non-ASCII characters are replaced too
+	AsciiOnly,
+	/// Names are NFC normalized and stripped of invisible characters
+	#[default]
+	Unicode,
+	/// Like `unicode` but names with invisible characters are rejected and
+	/// characters forbidden on Windows are replaced on every platform
+	Strict,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NameRules {
+	#[serde(default)]
+	pub policy: NamePolicy,
+	/// Character forbidden ones are replaced with, they are removed by default
+	/// while non-ASCII characters are replaced with `_` in `ascii-only` mode
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub replacement: Option<char>,
+}
+
+/// Where instance data is written, both are always read and data
+/// files win over index entries when an instance has both
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataStorage {
+	/// Data file next to every instance, e.g. `Name.data.json`
+	#[default]
+	Files,
+	/// Entry in the data index of the directory the instance lives in
+	Index,
+}
+
+/// Extension of script files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScriptExtension {
+	Lua,
+	#[default]
+	Luau,
+}
+
+impl ScriptExtension {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			ScriptExtension::Lua => "lua",
+			ScriptExtension::Luau => "luau",
+		}
+	}
+
+	pub fn other(&self) -> Self {
+		match self {
+			ScriptExtension::Lua => ScriptExtension::Luau,
+			ScriptExtension::Luau => ScriptExtension::Lua,
+		}
+	}
+}
+
+/// Naming conventions of script files used by the default sync rules
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScriptsConfig {
+	/// Extension of new files, files with the other one are still read.
+	/// Follows `lua_extension` global setting if not set
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub extension: Option<ScriptExtension>,
+	pub server_suffix: String,
+	pub client_suffix: String,
+	/// Name of script files that represent their parent folder
+	pub init_name: String,
+}
+
+impl ScriptsConfig {
+	pub fn extension(&self) -> ScriptExtension {
+		self.extension.unwrap_or_default()
+	}
+}
+
+impl Default for ScriptsConfig {
+	fn default() -> Self {
+		Self {
+			extension: None,
+			server_suffix: String::from(".server"),
+			client_suffix: String::from(".client"),
+			init_name: String::from("init"),
+		}
+	}
+}
+
+/// Naming conventions of instance data files used by the default sync rules
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DataConfig {
+	/// Name of data files inside of instance folders, other instances
+	/// use it as a suffix, e.g. `.data.json` and `Name.data.json`
+	pub file_name: String,
+}
+
+impl Default for DataConfig {
+	fn default() -> Self {
+		Self {
+			file_name: String::from(".data.json"),
+		}
+	}
+}
+
+/// Conventions that the default sync rules are generated from,
+/// so they don't have to be restated with custom `syncRules`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MiddlewareConfig {
+	pub scripts: ScriptsConfig,
+	pub data: DataConfig,
+}
+
+impl MiddlewareConfig {
+	/// Returns config with script extension taken from the global config if not set
+	pub fn resolve(&self) -> Self {
+		let mut config = self.clone();
+
+		if config.scripts.extension.is_none() {
+			config.scripts.extension = Some(if Config::new().lua_extension {
+				ScriptExtension::Lua
+			} else {
+				ScriptExtension::Luau
+			});
+		}
+
+		config
+	}
+
+	/// Whether the default sync rules follow this config
+	pub fn is_default(&self) -> bool {
+		let default = Self::default();
+
+		self.scripts.extension() == ScriptExtension::Luau
+			&& self.scripts.server_suffix == default.scripts.server_suffix
+			&& self.scripts.client_suffix == default.scripts.client_suffix
+			&& self.scripts.init_name == default.scripts.init_name
+			&& self.data == default.data
+	}
+
+	fn validate(&self) -> Result<()> {
+		let suffixes = [
+			("scripts.serverSuffix", &self.scripts.server_suffix),
+			("scripts.clientSuffix", &self.scripts.client_suffix),
+			("scripts.initName", &self.scripts.init_name),
+			("data.fileName", &self.data.file_name),
+		];
+
+		for (name, value) in suffixes {
+			if value.is_empty() || value.contains(['*', '/', '\\']) {
+				bail!(
+					"Invalid {} in {}: {:?}, it has to be a non-empty file name part",
+					name.bold(),
+					"middlewareConfig".bold(),
+					value
+				);
+			}
+		}
+
+		if self.scripts.server_suffix == self.scripts.client_suffix {
+			bail!(
+				"Server and client script suffixes in {} have to be different",
+				"middlewareConfig".bold()
+			);
+		}
+
+		if !self.data.file_name.ends_with(".json") {
+			bail!(
+				"Invalid {} in {}: {:?}, it has to end with {}",
+				"data.fileName".bold(),
+				"middlewareConfig".bold(),
+				self.data.file_name,
+				".json".bold()
+			);
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Project {
 	#[serde(default = "default_project_name")]
 	pub name: String,
+	#[serde(rename = "$defs", default, skip_serializing_if = "Map::is_empty")]
+	pub defs: Map<String, Value>,
 	#[serde(rename = "tree")]
 	pub node: ProjectNode,
+	/// Build profiles that nodes can be limited to with `$profiles`,
+	/// the first one is used when no profile is selected
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub profiles: Vec<String>,
 
 	#[serde(alias = "serveAddress", skip_serializing_if = "Option::is_none")]
 	pub host: Option<String>,
 	#[serde(alias = "servePort", skip_serializing_if = "Option::is_none")]
 	pub port: Option<u16>,
+	/// Port of the read-only mirror of the session, see `Server::with_mirror`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub mirror_port: Option<u16>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub game_id: Option<u64>,
 	#[serde(alias = "servePlaceIds", default, skip_serializing_if = "Vec::is_empty")]
 	pub place_ids: Vec<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub strict_place_ids: Option<bool>,
 
 	#[serde(alias = "globIgnorePaths", default, skip_serializing_if = "Vec::is_empty")]
 	pub ignore_globs: Vec<Glob>,
@@ -103,53 +927,319 @@ pub struct Project {
 
 	#[serde(alias = "emitLegacyScripts", skip_serializing_if = "Option::is_none")]
 	pub legacy_scripts: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub memory_mode: Option<MemoryMode>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub collapse_empty_folders: Option<CollapseMode>,
+	/// Layouts of instances added from Studio keyed by class, subclasses
+	/// included, they take precedence over `collapseEmptyFolders`
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub layout_preferences: BTreeMap<String, LayoutPreference>,
+	/// Ranges of numeric properties keyed by `Class.Property`, on top of the built-in ones
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub property_ranges: BTreeMap<String, Range>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub clamp_property_ranges: Option<bool>,
+	/// Paths of containers like `Workspace.Scratch` whose descendants
+	/// only live in the session and are never written to disk
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub scratch_paths: Vec<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub data_includes: Option<DataIncludes>,
+	/// Format of new instance data files
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub data_format: Option<DataFormat>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub data_storage: Option<DataStorage>,
+	/// Whether `.md` files are synced as plain `StringValue` instances, when
+	/// not set they keep being converted to rich text and `false` skips them
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub include_markdown: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub max_markdown_size: Option<usize>,
+	/// Maximum number of ancestors an instance can have
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub max_tree_depth: Option<usize>,
+	/// Conventions of file names used by the default sync rules
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub middleware_config: Option<MiddlewareConfig>,
+	/// Commands that preprocess matching files when they are
+	/// read or before syncback writes them, e.g. to compress images
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub hooks: Option<Hooks>,
+	/// Whether instances inside packages can be synced back
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub manage_packages: Option<bool>,
+	/// Directory with data files of singleton services that are not
+	/// part of the tree, e.g. `Lighting.data.json`, places only
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub services_path: Option<PathBuf>,
+	/// Directory assets added with `argon asset add` are copied to
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub assets_path: Option<PathBuf>,
+	/// How names of synced back instances are sanitized
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name_rules: Option<NameRules>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub lazy_properties: Option<LazyProperties>,
+	/// Whether a file that fails to load fails the whole snapshot,
+	/// otherwise it is replaced with a placeholder instance
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub strict_load: Option<bool>,
+	/// Whether written files are read back to catch silent disk corruption
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub verify_writes: Option<bool>,
+	/// Class inference rules checked before the built-in ones
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub class_inference: Vec<InferenceRule>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub allow_env_refs: Option<bool>,
+	/// Overrides roblox-ts detection
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub roblox_ts: Option<bool>,
+	/// Icons shown by editors on top of the built-in ones,
+	/// e.g. for Knit services matched by their names
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub class_icons: Vec<IconRule>,
 
 	#[serde(skip)]
 	pub path: PathBuf,
 	#[serde(skip)]
 	pub workspace_dir: PathBuf,
+	#[serde(skip)]
+	pub allow_any_place: bool,
+	#[serde(skip)]
+	pub refs: Vec<ResolvedRef>,
+	/// Active build profile, nodes of other profiles are not
+	/// snapshotted but are still kept when saving the project
+	#[serde(skip)]
+	pub profile: Option<String>,
+	/// Error of the last failed reload, the project
+	/// keeps its last good configuration until it succeeds
+	#[serde(skip)]
+	pub stale_config: Option<String>,
+	/// Whether the project was read from stdin, its `path` does not exist then
+	#[serde(skip)]
+	pub from_stdin: bool,
 }
 
 impl Project {
+	/// Loads the project with the profile selected by the user
 	pub fn load(project_path: &Path) -> Result<Self> {
-		let project = fs::read_to_string(project_path)?;
-		let mut project: Project = serde_json::from_str(&project).with_desc(|| {
+		Self::load_with_profile(project_path, active_profile().as_deref())
+	}
+
+	pub fn load_with_profile(project_path: &Path, profile: Option<&str>) -> Result<Self> {
+		let project = read(project_path).with_desc(|| {
 			format!(
 				"Failed to parse project at {}",
 				project_path.display().to_string().bold()
 			)
 		})?;
 
+		Self::from_value(project, project_path, profile)
+	}
+
+	/// Loads the project from its contents, e.g. piped to stdin. It does not exist on disk
+	/// so its path is synthetic and `$path` entries are resolved relative to `workspace_dir`
+	pub fn load_from_str(contents: &str, workspace_dir: &Path) -> Result<Self> {
+		let project_path = workspace_dir.join(STDIN_PROJECT_NAME);
+
+		let project =
+			serde_json::from_str(contents).with_desc(|| String::from("Failed to parse project from stdin"))?;
+
+		let mut project = Self::from_value(project, &project_path, active_profile().as_deref())?;
+		project.from_stdin = true;
+
+		Ok(project)
+	}
+
+	/// Loads the project from contents that are not saved yet, e.g. a proposed
+	/// edit, `$path` entries are resolved relative to `project_path` as usual
+	pub fn load_unsaved(contents: &str, project_path: &Path) -> Result<Self> {
+		let project = serde_json::from_str(contents).with_desc(|| {
+			format!(
+				"Failed to parse project at {}",
+				project_path.display().to_string().bold()
+			)
+		})?;
+
+		Self::from_value(project, project_path, active_profile().as_deref())
+	}
+
+	fn from_value(mut project: Value, project_path: &Path, profile: Option<&str>) -> Result<Self> {
+		// References have to be resolved before deserializing
+		// so the rest of the pipeline only sees plain values
+		let refs = defs::resolve(&mut project).with_desc(|| {
+			format!(
+				"Failed to resolve project references at {}",
+				project_path.display().to_string().bold()
+			)
+		})?;
+
+		let mut project: Project = serde_json::from_value(project).with_desc(|| {
+			format!(
+				"Failed to parse project at {}",
+				project_path.display().to_string().bold()
+			)
+		})?;
+
+		project.refs = refs;
+		project.profile = project.select_profile(profile)?;
+
+		if let Some(config) = &project.middleware_config {
+			config.validate()?;
+		}
+
 		let workspace_dir = project_path.get_parent();
 
 		project_path.clone_into(&mut project.path);
 		workspace_dir.clone_into(&mut project.workspace_dir);
 
+		project.add_service_nodes();
+
 		Ok(project)
 	}
 
+	/// Adds nodes of `SINGLETON_SERVICES` missing from the tree, their
+	/// properties live in data files in `servicesPath` that are created
+	/// with the first change synced back from Studio
+	fn add_service_nodes(&mut self) {
+		let Some(services_path) = self.services_path.clone() else {
+			return;
+		};
+
+		if !self.is_place() {
+			return;
+		}
+
+		for service in util::SINGLETON_SERVICES {
+			self.node
+				.tree
+				.entry(service.to_string())
+				.or_insert_with(|| ProjectNode {
+					path: Some(ProjectPath::Optional {
+						optional: services_path.join(service),
+					}),
+					implicit: true,
+					..ProjectNode::default()
+				});
+		}
+	}
+
 	pub fn save(&self, path: &Path) -> Result<()> {
 		let formatter = JsonFormatter::with_array_breaks(false);
 
 		let mut writer = Vec::new();
 		let mut serializer = Serializer::with_formatter(&mut writer, formatter);
 
-		self.serialize(&mut serializer)?;
+		let mut project = serde_json::to_value(self)?;
+		defs::restore(&mut project, &self.refs);
+
+		if let Some(tree) = project.get_mut("tree").and_then(Value::as_object_mut) {
+			for (name, node) in &self.node.tree {
+				if node.implicit {
+					tree.remove(name);
+				}
+			}
+		}
+
+		project.serialize(&mut serializer)?;
 		writer.end()?;
 
-		fs::write(path, &writer)?;
+		// Nothing to do if the project would stay the same
+		if fs::read(path).is_ok_and(|current| current == writer) {
+			return Ok(());
+		}
+
+		undo_log::record_write(&self.workspace_dir, path);
+
+		if let Err(err) = backups::create(path, &writer) {
+			warn!("Failed to back up project file {}: {}", path.display(), err);
+		}
+
+		// Write to a temporary file first so the project
+		// is never left partially written if Argon is interrupted
+		let temp_dir = path.get_parent().join(".argon");
+		let temp_path = temp_dir.join(format!("{}.tmp", path.get_name()));
+
+		fs::create_dir_all(&temp_dir)?;
+		fs::write(&temp_path, &writer)?;
+
+		// Temporary file is created with default permissions
+		if Config::new().preserve_file_modes && path.exists() {
+			if let Err(err) = std_backend::copy_permissions(path, &temp_path) {
+				warn!("Failed to copy permissions of {}: {}", path.display(), err);
+			}
+		}
+
+		fs::rename(&temp_path, path)?;
+
+		if self.verifies_writes() {
+			verify::verify(
+				path,
+				&blobs::digest_bytes(&writer),
+				|| verify::hash_file(path),
+				|| fs::write(path, &writer),
+			)?;
+		}
 
 		Ok(())
 	}
 
+	fn select_profile(&self, profile: Option<&str>) -> Result<Option<String>> {
+		self.node.check_profiles(&self.profiles, &self.name)?;
+
+		match profile {
+			// Projects without profiles can still be used with a default profile set
+			Some(profile) if self.profiles.is_empty() => {
+				trace!("Project {} has no profiles, ignoring profile {}", self.name, profile);
+				Ok(None)
+			}
+			Some(profile) if !self.profiles.iter().any(|name| name == profile) => {
+				bail!(
+					"Profile {} is not defined in the project, available profiles: {}",
+					profile.bold(),
+					self.profiles.join(", ").bold()
+				)
+			}
+			Some(profile) => Ok(Some(profile.to_owned())),
+			None => Ok(self.profiles.first().cloned()),
+		}
+	}
+
+	/// Loads the project again, if that fails the current configuration
+	/// is kept and the error is stored in `stale_config`
 	pub fn reload(&mut self) -> Result<&Self> {
-		let new = Self::load(&self.path)?;
+		if self.from_stdin {
+			bail!("Project read from stdin cannot be reloaded");
+		}
+
+		let mut new = match Self::load(&self.path) {
+			Ok(new) => new,
+			Err(err) => {
+				self.stale_config = Some(err.to_string());
+				return Err(err);
+			}
+		};
+
+		new.allow_any_place = self.allow_any_place;
 
 		drop(mem::replace(self, new));
 
 		Ok(self)
 	}
 
+	/// Loads the project for modification, other edits
+	/// of the same file wait until the returned guard is dropped
+	pub fn edit(path: &Path) -> Result<ProjectEdit> {
+		ProjectEdit::new(path)
+	}
+
+	pub fn is_stale(&self) -> bool {
+		self.stale_config.is_some()
+	}
+
 	pub fn is_place(&self) -> bool {
 		if let Some(class) = &self.node.class_name {
 			class == "DataModel"
@@ -158,6 +1248,66 @@ impl Project {
 		}
 	}
 
+	pub fn is_low_memory(&self) -> bool {
+		self.memory_mode == Some(MemoryMode::Low)
+	}
+
+	pub fn verifies_writes(&self) -> bool {
+		self.verify_writes.unwrap_or(false)
+	}
+
+	/// Directory of the asset store, see `assets::AssetStore`
+	pub fn assets_dir(&self) -> PathBuf {
+		self.workspace_dir
+			.join(self.assets_path.as_deref().unwrap_or(Path::new(DEFAULT_ASSETS_PATH)))
+	}
+
+	/// Whether clients connected to the wrong place should be rejected
+	pub fn is_strict_place(&self) -> bool {
+		!self.allow_any_place && self.strict_place_ids.unwrap_or(true)
+	}
+
+	/// Checks if the place reported by the client matches `gameId` and `placeIds`,
+	/// IDs the client did not report are unknown so they can't mismatch
+	pub fn check_place(&self, game_id: Option<u64>, place_id: Option<u64>) -> Result<()> {
+		let game_matches = match (self.game_id, game_id) {
+			(Some(expected), Some(actual)) => expected == actual,
+			_ => true,
+		};
+
+		let place_matches = match place_id {
+			Some(place_id) if !self.place_ids.is_empty() => self.place_ids.contains(&place_id),
+			_ => true,
+		};
+
+		if !game_matches || !place_matches {
+			bail!(
+				"Client is connected to the wrong place! Expected game ID: {}, place IDs: {}, got game ID: {}, place ID: {}",
+				self.game_id.map_or(String::from("any"), |id| id.to_string()),
+				if self.place_ids.is_empty() {
+					String::from("any")
+				} else {
+					format!("{:?}", self.place_ids)
+				},
+				game_id.map_or(String::from("none"), |id| id.to_string()),
+				place_id.map_or(String::from("none"), |id| id.to_string()),
+			);
+		}
+
+		Ok(())
+	}
+
+	/// Whether `gameId` or `placeIds` are set but the client
+	/// did not report the ID they should be compared with
+	pub fn is_place_unknown(&self, game_id: Option<u64>, place_id: Option<u64>) -> bool {
+		(self.game_id.is_some() && game_id.is_none()) || (!self.place_ids.is_empty() && place_id.is_none())
+	}
+
+	/// Returns rules of `ignoreGlobs`, relative to the workspace
+	pub fn ignore_rules(&self) -> Vec<IgnoreRule> {
+		IgnoreRule::from_globs(self.ignore_globs.clone(), self.workspace_dir.clone())
+	}
+
 	pub fn is_ts(&self) -> bool {
 		for glob in &self.ignore_globs {
 			if glob.matches("**/tsconfig.json") {
@@ -186,6 +1336,16 @@ impl Project {
 		walk(&self.node)
 	}
 
+	/// Returns layout of roblox-ts project if it is enabled with `robloxTs`
+	/// or detected (requires `tsconfig.json` to be present next to the project)
+	pub fn ts_layout(&self) -> Option<TsLayout> {
+		let enabled = self.roblox_ts.unwrap_or_else(|| {
+			Config::new().detect_project && self.is_ts() && self.workspace_dir.join("tsconfig.json").exists()
+		});
+
+		enabled.then(|| TsLayout::load(&self.workspace_dir))
+	}
+
 	pub fn is_wally(&self) -> bool {
 		fn walk(node: &ProjectNode) -> bool {
 			if node.path.as_ref().is_some_and(|p| p.path() == Path::new("Packages")) {
@@ -204,11 +1364,13 @@ impl Project {
 		walk(&self.node)
 	}
 
+	/// Returns node visible in the active profile
 	pub fn find_node_by_path(&mut self, node_path: &NodePath) -> Option<&mut ProjectNode> {
+		let profile = self.profile.as_deref();
 		let mut node = &mut self.node;
 
 		for name in node_path.iter() {
-			node = node.tree.get_mut(name)?;
+			node = node.tree.get_mut(name).filter(|node| node.is_visible(profile))?;
 		}
 
 		Some(node)
@@ -236,15 +1398,48 @@ pub fn resolve(path: PathBuf) -> Result<PathBuf> {
 		return Ok(default_project);
 	}
 
-	let glob = path.clone().join("*.project.json");
-
-	if let Some(path) = Glob::from_path(&glob)?.first() {
+	if let Some(path) = Glob::in_dir(&path, "*.project.json")?.first() {
 		Ok(path)
 	} else {
 		Ok(default_project)
 	}
 }
 
+/// Returns synthetic path of the project read from stdin, `$path` entries are resolved
+/// relative to `workspace_dir` which is required as there is no project file to take it from
+pub fn stdin_path(workspace_dir: Option<&Path>) -> Result<PathBuf> {
+	let Some(workspace_dir) = workspace_dir else {
+		bail!(
+			"Reading the project from stdin requires {} to resolve {} entries against",
+			"--workspace-dir".bold(),
+			"$path".bold()
+		);
+	};
+
+	let workspace_dir = workspace_dir.resolve()?;
+
+	if !workspace_dir.is_dir() {
+		bail!(
+			"Workspace directory {} does not exist",
+			workspace_dir.to_string().bold()
+		);
+	}
+
+	Ok(workspace_dir.join(STDIN_PROJECT_NAME))
+}
+
+/// Reads the project piped to stdin, see `Project::load_from_str`
+pub fn load_stdin(project_path: &Path) -> Result<Project> {
+	let mut contents = String::new();
+	io::stdin().read_to_string(&mut contents)?;
+
+	if contents.trim().is_empty() {
+		bail!("No project was piped to stdin");
+	}
+
+	Project::load_from_str(&contents, project_path.get_parent())
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProjectDetails {
@@ -276,3 +1471,11 @@ impl ProjectDetails {
 fn default_project_name() -> String {
 	String::from("default")
 }
+
+fn default_lazy_properties() -> Vec<String> {
+	vec![String::from("Source")]
+}
+
+fn default_lazy_threshold() -> usize {
+	LAZY_PROPERTY_THRESHOLD
+}
@@ -0,0 +1,185 @@
+use colored::Colorize;
+use rbx_dom_weak::types::{Variant, Vector2, Vector3};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	fmt::{self, Display, Formatter},
+	iter,
+	path::Path,
+};
+
+use crate::{argon_warn, t};
+
+/// Inclusive range of valid values of a numeric property,
+/// vectors are checked component by component
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Range {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub min: Option<f64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max: Option<f64>,
+}
+
+impl Range {
+	const fn new(min: Option<f64>, max: Option<f64>) -> Self {
+		Self { min, max }
+	}
+
+	pub fn contains(&self, value: f64) -> bool {
+		self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+	}
+
+	pub fn clamp(&self, value: f64) -> f64 {
+		let value = self.min.map_or(value, |min| value.max(min));
+		self.max.map_or(value, |max| value.min(max))
+	}
+}
+
+impl Display for Range {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match (self.min, self.max) {
+			(Some(min), Some(max)) => write!(f, "[{}, {}]", min, max),
+			(Some(min), None) => write!(f, "[{}, inf)", min),
+			(None, Some(max)) => write!(f, "(-inf, {}]", max),
+			(None, None) => write!(f, "(-inf, inf)"),
+		}
+	}
+}
+
+const UNIT: Range = Range::new(Some(0.0), Some(1.0));
+const NON_NEGATIVE: Range = Range::new(Some(0.0), None);
+
+/// Built-in ranges as `(class, property, range)`, they also
+/// apply to all subclasses of the class, e.g. `Part` of `BasePart`
+static RANGES: [(&str, &str, Range); 9] = [
+	("BasePart", "Transparency", UNIT),
+	("BasePart", "Reflectance", UNIT),
+	("BasePart", "Size", NON_NEGATIVE),
+	("Decal", "Transparency", UNIT),
+	("GuiObject", "BackgroundTransparency", UNIT),
+	("Sound", "Volume", Range::new(Some(0.0), Some(10.0))),
+	("Sound", "PlaybackSpeed", NON_NEGATIVE),
+	("Light", "Brightness", NON_NEGATIVE),
+	("Light", "Range", NON_NEGATIVE),
+];
+
+/// Checks resolved property values against the built-in ranges
+/// and the ones from the project's `propertyRanges` field
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropertyRanges {
+	/// Ranges keyed by `Class.Property`, they take precedence over built-in ones
+	overrides: BTreeMap<String, Range>,
+	/// Whether to clamp out-of-range values instead of only warning about them
+	clamp: bool,
+}
+
+impl PropertyRanges {
+	pub fn new(overrides: BTreeMap<String, Range>, clamp: bool) -> Self {
+		Self { overrides, clamp }
+	}
+
+	/// Returns range of the property, looking through superclasses
+	pub fn find(&self, class: &str, property: &str) -> Option<Range> {
+		let database = rbx_reflection_database::get();
+		let classes = iter::successors(Some(class), |class| database.classes.get(*class)?.superclass.as_deref());
+
+		for class in classes {
+			if let Some(range) = self.overrides.get(&format!("{}.{}", class, property)) {
+				return Some(*range);
+			}
+
+			if let Some((_, _, range)) = RANGES.iter().find(|(c, p, _)| *c == class && *p == property) {
+				return Some(*range);
+			}
+		}
+
+		None
+	}
+
+	/// Warns about the value if it is out of its range and clamps it if enabled,
+	/// `path` is the file the value comes from, `None` for values from Studio
+	pub fn check(&self, class: &str, property: &str, value: Variant, path: Option<&Path>) -> Variant {
+		let Some(range) = self.find(class, property) else {
+			return value;
+		};
+
+		let Some(clamped) = clamp(&value, &range) else {
+			return value;
+		};
+
+		let source = match path {
+			Some(path) => path.display().to_string(),
+			None => t!("ranges.studio").to_string(),
+		};
+
+		let property = format!("{}.{}", class, property);
+
+		if self.clamp {
+			argon_warn!(
+				"{}",
+				t!(
+					"ranges.clamped",
+					property = property.bold(),
+					value = describe(&value).bold(),
+					expected = range.to_string().bold(),
+					source = source.bold(),
+					clamped = describe(&clamped).bold()
+				)
+			);
+
+			clamped
+		} else {
+			argon_warn!(
+				"{}",
+				t!(
+					"ranges.out_of_range",
+					property = property.bold(),
+					value = describe(&value).bold(),
+					expected = range.to_string().bold(),
+					source = source.bold()
+				)
+			);
+
+			value
+		}
+	}
+}
+
+/// Returns the clamped value if any of its components is
+/// out of range, `None` if it is in range or not numeric
+fn clamp(value: &Variant, range: &Range) -> Option<Variant> {
+	let clamped = match value {
+		Variant::Float32(value) => Variant::Float32(range.clamp(*value as f64) as f32),
+		Variant::Float64(value) => Variant::Float64(range.clamp(*value)),
+		Variant::Int32(value) => Variant::Int32(range.clamp(*value as f64) as i32),
+		Variant::Int64(value) => Variant::Int64(range.clamp(*value as f64) as i64),
+		Variant::Vector2(value) => Variant::Vector2(Vector2::new(
+			range.clamp(value.x as f64) as f32,
+			range.clamp(value.y as f64) as f32,
+		)),
+		Variant::Vector3(value) => Variant::Vector3(Vector3::new(
+			range.clamp(value.x as f64) as f32,
+			range.clamp(value.y as f64) as f32,
+			range.clamp(value.z as f64) as f32,
+		)),
+		_ => return None,
+	};
+
+	if &clamped == value {
+		None
+	} else {
+		Some(clamped)
+	}
+}
+
+fn describe(value: &Variant) -> String {
+	match value {
+		Variant::Float32(value) => value.to_string(),
+		Variant::Float64(value) => value.to_string(),
+		Variant::Int32(value) => value.to_string(),
+		Variant::Int64(value) => value.to_string(),
+		Variant::Vector2(value) => format!("{}, {}", value.x, value.y),
+		Variant::Vector3(value) => format!("{}, {}, {}", value.x, value.y, value.z),
+		_ => format!("{:?}", value),
+	}
+}
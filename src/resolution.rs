@@ -7,27 +7,105 @@ use rbx_dom_weak::types::{
 	NumberSequence, NumberSequenceKeypoint, PhysicalProperties, Ray, Rect, Region3, Region3int16, Tags, UDim, UDim2,
 	Variant, VariantType, Vector2, Vector2int16, Vector3, Vector3int16,
 };
-use rbx_reflection::{DataType, PropertyDescriptor};
-use serde::{ser::SerializeSeq, Deserialize, Serialize, Serializer};
-use std::{borrow::Borrow, collections::HashMap, fmt::Write};
+use rbx_dom_weak::Ustr;
+use rbx_reflection::{DataType, EnumDescriptor, PropertyDescriptor};
+use serde::{
+	de::{self, MapAccess, Visitor},
+	ser::SerializeSeq,
+	Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{
+	borrow::Borrow,
+	collections::{BTreeMap, HashMap},
+	fmt,
+	path::Path,
+	sync::{OnceLock, RwLock},
+};
+
+use crate::{config::Config, ranges::PropertyRanges};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum UnresolvedValue {
+	/// Value read from another file, see `FileRef`
+	File(FileRef),
 	FullyQualified(Variant),
 	Ambiguous(AmbiguousValue),
 }
 
+/// `{"$file": "./config.json"}` reference to a file whose contents are
+/// the value of a string property, relative to the file it is used in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileRef {
+	#[serde(rename = "$file")]
+	pub file: String,
+}
+
+// Derived implementation would also accept one element arrays like `["X"]`
+// which are valid `Axes` and `Faces` values, so only objects are accepted
+impl<'de> Deserialize<'de> for FileRef {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		struct FileRefVisitor;
+
+		impl<'de> Visitor<'de> for FileRefVisitor {
+			type Value = FileRef;
+
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("an object with a single `$file` key")
+			}
+
+			fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+				let mut file = None;
+
+				while let Some(key) = map.next_key::<String>()? {
+					if key != "$file" || file.is_some() {
+						return Err(de::Error::custom(format!("unexpected key `{}`", key)));
+					}
+
+					file = Some(map.next_value()?);
+				}
+
+				file.map(|file| FileRef { file })
+					.ok_or_else(|| de::Error::missing_field("$file"))
+			}
+		}
+
+		deserializer.deserialize_map(FileRefVisitor)
+	}
+}
+
 impl UnresolvedValue {
-	pub fn resolve(self, class: &str, property: &str) -> anyhow::Result<Variant> {
+	/// Resolves the value to a variant of the property's type,
+	/// `origin` is the file the value comes from, included in errors
+	pub fn resolve(self, class: &str, property: &str, origin: Option<&Path>) -> anyhow::Result<Variant> {
 		match self {
+			UnresolvedValue::File(file) => bail!(
+				"File reference `{}` of {}.{} has to be read before resolving",
+				file.file,
+				class,
+				property
+			),
 			UnresolvedValue::FullyQualified(full) => Ok(full),
-			UnresolvedValue::Ambiguous(partial) => partial.resolve(class, property),
+			UnresolvedValue::Ambiguous(partial) => partial.resolve(class, property, origin),
 		}
 	}
 
+	/// Same as `resolve` but also checks the resolved value against
+	/// its valid range, `path` is the file the value comes from
+	pub fn resolve_checked(
+		self,
+		class: &str,
+		property: &str,
+		ranges: &PropertyRanges,
+		path: Option<&Path>,
+	) -> anyhow::Result<Variant> {
+		self.resolve(class, property, path)
+			.map(|value| ranges.check(class, property, value, path))
+	}
+
 	pub fn resolve_unambiguous(self) -> anyhow::Result<Variant> {
 		match self {
+			UnresolvedValue::File(file) => bail!("File reference `{}` has to be read before resolving", file.file),
 			UnresolvedValue::FullyQualified(full) => Ok(full),
 			UnresolvedValue::Ambiguous(partial) => partial.resolve_unambiguous(),
 		}
@@ -44,7 +122,7 @@ impl UnresolvedValue {
 	pub fn from_variant(variant: Variant, class: &str, property: &str) -> Self {
 		Self::Ambiguous(match variant {
 			Variant::Attributes(attr) => {
-				let mut object = HashMap::new();
+				let mut object = BTreeMap::new();
 
 				for (key, value) in attr {
 					object.insert(
@@ -87,20 +165,7 @@ impl UnresolvedValue {
 
 			Variant::BrickColor(color) => AmbiguousValue::String(color.to_string()),
 
-			Variant::CFrame(cf) => AmbiguousValue::Array12([
-				cf.position.x as f64,
-				cf.position.y as f64,
-				cf.position.z as f64,
-				cf.orientation.x.x as f64,
-				cf.orientation.x.y as f64,
-				cf.orientation.x.z as f64,
-				cf.orientation.y.x as f64,
-				cf.orientation.y.y as f64,
-				cf.orientation.y.z as f64,
-				cf.orientation.z.x as f64,
-				cf.orientation.z.y as f64,
-				cf.orientation.z.z as f64,
-			]),
+			Variant::CFrame(cf) => AmbiguousValue::from_cframe(&cf, Config::new().euler_cframes),
 
 			Variant::Color3(color) => AmbiguousValue::Array3([color.r as f64, color.g as f64, color.b as f64]),
 			Variant::Color3uint8(color) => AmbiguousValue::Array3([color.r as f64, color.g as f64, color.b as f64]),
@@ -117,14 +182,8 @@ impl UnresolvedValue {
 			Variant::Enum(rbx_enum) => {
 				if let Some(property) = find_descriptor(class, property) {
 					if let DataType::Enum(enum_name) = &property.data_type {
-						let database = rbx_reflection_database::get();
-
-						if let Some(enum_descriptor) = database.enums.get(enum_name) {
-							for (variant_name, id) in &enum_descriptor.items {
-								if *id == rbx_enum.to_u32() {
-									return Self::Ambiguous(AmbiguousValue::String(variant_name.to_string()));
-								}
-							}
+						if let Some(name) = find_enum(enum_name).and_then(|table| table.name_of(rbx_enum.to_u32())) {
+							return Self::Ambiguous(AmbiguousValue::String(name.to_owned()));
 						}
 					}
 				}
@@ -178,20 +237,7 @@ impl UnresolvedValue {
 
 			Variant::OptionalCFrame(cf) => {
 				if let Some(cf) = cf {
-					AmbiguousValue::Array12([
-						cf.position.x as f64,
-						cf.position.y as f64,
-						cf.position.z as f64,
-						cf.orientation.x.x as f64,
-						cf.orientation.x.y as f64,
-						cf.orientation.x.z as f64,
-						cf.orientation.y.x as f64,
-						cf.orientation.y.y as f64,
-						cf.orientation.y.z as f64,
-						cf.orientation.z.x as f64,
-						cf.orientation.z.y as f64,
-						cf.orientation.z.z as f64,
-					])
+					AmbiguousValue::from_cframe(&cf, Config::new().euler_cframes)
 				} else {
 					AmbiguousValue::String("null".to_owned())
 				}
@@ -274,28 +320,37 @@ pub enum AmbiguousValue {
 	Array2Array2([[f64; 2]; 2]),
 	#[serde(serialize_with = "serialize_nested_array")]
 	Array3Array2([[f64; 3]; 2]),
+	CFrame(CFrameForm),
 	Attributes(Attributes),
 	MaterialColors(MaterialColors),
 	ColorSequence(Vec<ColorSequenceKeypoint>),
 	NumberSequence(Vec<NumberSequenceKeypoint>),
 	Font(Font),
 	PhysicalProperties(CustomPhysicalProperties),
-	Object(HashMap<String, UnresolvedValue>),
+	/// Sorted so data files are written the same way every time
+	Object(BTreeMap<String, UnresolvedValue>),
 }
 
 impl AmbiguousValue {
-	pub fn resolve(self, class: &str, property: &str) -> anyhow::Result<Variant> {
+	pub fn resolve(self, class: &str, property: &str, origin: Option<&Path>) -> anyhow::Result<Variant> {
+		self.resolve_property(class, property).map_err(|err| match origin {
+			Some(origin) => format_err!("{} in {}", err, origin.display()),
+			None => err,
+		})
+	}
+
+	fn resolve_property(self, class: &str, property: &str) -> anyhow::Result<Variant> {
 		let descriptor =
 			find_descriptor(class, property).ok_or_else(|| format_err!("Unknown property {}.{}", class, property))?;
 
 		match &descriptor.data_type {
 			DataType::Enum(enum_name) => {
-				let descriptor = rbx_reflection_database::get()
-					.enums
-					.get(enum_name)
-					.ok_or_else(|| format_err!("Unknown enum {}. Probably not implemented yet!", enum_name))?;
+				let descriptor = find_enum(enum_name)
+					.ok_or_else(|| format_err!("Unknown enum {}. Probably not implemented yet!", enum_name))?
+					.descriptor;
 
-				let error = |value: &str| {
+				// `input` is the invalid string used to find the closest members
+				let error = |value: &str, input: Option<&str>| {
 					let mut examples = descriptor
 						.items
 						.keys()
@@ -303,27 +358,34 @@ impl AmbiguousValue {
 						.collect::<Vec<&str>>();
 					examples.sort();
 
-					let examples = list_examples(&examples);
+					let expected = if examples.is_empty() {
+						format!("the {} enum has no members", enum_name)
+					} else {
+						format!(
+							"expected a member of the {} enum such as {}",
+							enum_name,
+							list_examples(&examples, input)
+						)
+					};
 
 					format_err!(
-						"Invalid value for property {}.{}. Got {} but expected a member of the {} enum such as {}",
+						"Invalid value for property {}.{}. Got {} but {}",
 						class,
 						property,
-						value,
-						enum_name,
-						examples,
+						truncate_value(value),
+						expected,
 					)
 				};
 
 				let value = match self {
 					AmbiguousValue::String(value) => value,
-					unresolved => return Err(error(unresolved.describe())),
+					unresolved => return Err(error(unresolved.describe(), None)),
 				};
 
 				let resolved = descriptor
 					.items
 					.get(value.as_str())
-					.ok_or_else(|| error(value.as_str()))?;
+					.ok_or_else(|| error(value.as_str(), Some(value.as_str())))?;
 
 				Ok(Enum::from_u32(*resolved).into())
 			}
@@ -362,14 +424,27 @@ impl AmbiguousValue {
 
 				(VariantType::Bool, AmbiguousValue::Bool(bool)) => Ok(bool.into()),
 
-				(VariantType::BrickColor, AmbiguousValue::Number(num)) => Ok(BrickColor::from_number(num as u16)
-					.context(format!("{} is not valid BrickColor number", num))?
-					.into()),
+				(VariantType::BrickColor, AmbiguousValue::Number(num)) => {
+					// Casting alone would silently turn e.g. `-1` or `1.5` into valid numbers
+					if num.fract() != 0.0 || !(0.0..=u16::MAX as f64).contains(&num) {
+						bail!("{} is not valid BrickColor number", num);
+					}
+
+					Ok(BrickColor::from_number(num as u16)
+						.context(format!("{} is not valid BrickColor number", num))?
+						.into())
+				}
 				(VariantType::BrickColor, AmbiguousValue::String(name)) => Ok(BrickColor::from_name(&name)
 					.context(format!("{} is not valid BrickColor name", name))?
 					.into()),
 
-				(VariantType::CFrame, AmbiguousValue::Array12(cf)) => {
+				(VariantType::CFrame | VariantType::OptionalCFrame, AmbiguousValue::CFrame(form)) => {
+					Ok(form.to_cframe().into())
+				}
+				(VariantType::CFrame | VariantType::OptionalCFrame, AmbiguousValue::Array3(position)) => {
+					Ok(CFrameForm::from_position(position).to_cframe().into())
+				}
+				(VariantType::CFrame | VariantType::OptionalCFrame, AmbiguousValue::Array12(cf)) => {
 					let cf = cf.map(|v| v as f32);
 
 					let pos = Vector3::new(cf[0], cf[1], cf[2]);
@@ -434,19 +509,6 @@ impl AmbiguousValue {
 					Ok(NumberSequence { keypoints }.into())
 				}
 
-				(VariantType::OptionalCFrame, AmbiguousValue::Array12(cf)) => {
-					let cf = cf.map(|v| v as f32);
-
-					let pos = Vector3::new(cf[0], cf[1], cf[2]);
-					let orientation = Matrix3::new(
-						Vector3::new(cf[3], cf[4], cf[5]),
-						Vector3::new(cf[6], cf[7], cf[8]),
-						Vector3::new(cf[9], cf[10], cf[11]),
-					);
-
-					Ok(CFrame::new(pos, orientation).into())
-				}
-
 				(VariantType::PhysicalProperties, AmbiguousValue::PhysicalProperties(custom)) => {
 					Ok(PhysicalProperties::Custom(custom).into())
 				}
@@ -524,6 +586,31 @@ impl AmbiguousValue {
 		}
 	}
 
+	/// Returns the CFrame in the object form if its rotation can be written
+	/// as clean Euler angles (and `euler` is set), as twelve numbers otherwise
+	pub fn from_cframe(cf: &CFrame, euler: bool) -> Self {
+		if euler {
+			if let Some(form) = CFrameForm::from_cframe(cf) {
+				return AmbiguousValue::CFrame(form);
+			}
+		}
+
+		AmbiguousValue::Array12([
+			cf.position.x as f64,
+			cf.position.y as f64,
+			cf.position.z as f64,
+			cf.orientation.x.x as f64,
+			cf.orientation.x.y as f64,
+			cf.orientation.x.z as f64,
+			cf.orientation.y.x as f64,
+			cf.orientation.y.y as f64,
+			cf.orientation.y.z as f64,
+			cf.orientation.z.x as f64,
+			cf.orientation.z.y as f64,
+			cf.orientation.z.z as f64,
+		])
+	}
+
 	pub fn resolve_unambiguous(self) -> anyhow::Result<Variant> {
 		match self {
 			AmbiguousValue::Bool(value) => Ok(value.into()),
@@ -545,6 +632,7 @@ impl AmbiguousValue {
 			AmbiguousValue::Array12(_) => "an array of twelve numbers",
 			AmbiguousValue::Array2Array2(_) => "an array of two arrays of two numbers",
 			AmbiguousValue::Array3Array2(_) => "an array of two arrays of three numbers",
+			AmbiguousValue::CFrame(_) => "an object describing a CFrame",
 			AmbiguousValue::Attributes(_) => "an object containing attributes",
 			AmbiguousValue::MaterialColors(_) => "an object describing MaterialColors",
 			AmbiguousValue::ColorSequence(_) => "an object describing a ColorSequence",
@@ -556,7 +644,193 @@ impl AmbiguousValue {
 	}
 }
 
-fn find_descriptor(class: &str, property: &str) -> Option<&'static PropertyDescriptor<'static>> {
+/// Decimal places of Euler angles written by `from_variant`
+const EULER_PRECISION: f64 = 1000.0;
+/// Maximum difference of rotation matrix components for
+/// Euler angles to be considered an exact representation
+const EULER_EPSILON: f64 = 1e-6;
+
+/// CFrame written as its position and rotation in degrees, applied in the XYZ
+/// order, the same as `CFrame.Angles(x, y, z)` does (`R = Rx * Ry * Rz`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CFrameForm {
+	#[serde(serialize_with = "serialize_array")]
+	pub position: [f64; 3],
+	#[serde(default, serialize_with = "serialize_array", skip_serializing_if = "is_zero")]
+	pub rotation: [f64; 3],
+}
+
+impl CFrameForm {
+	pub fn from_position(position: [f64; 3]) -> Self {
+		Self {
+			position,
+			rotation: [0.0; 3],
+		}
+	}
+
+	/// Returns `None` if the rotation can't be written as Euler angles
+	/// rounded to `EULER_PRECISION` without changing the CFrame
+	pub fn from_cframe(cf: &CFrame) -> Option<Self> {
+		let matrix = [
+			[cf.orientation.x.x, cf.orientation.x.y, cf.orientation.x.z],
+			[cf.orientation.y.x, cf.orientation.y.y, cf.orientation.y.z],
+			[cf.orientation.z.x, cf.orientation.z.y, cf.orientation.z.z],
+		]
+		.map(|row| row.map(|value| value as f64));
+
+		// Negative zeros are turned into positive ones so they are not written as `-0`
+		let rotation = euler_angles(&matrix).map(|angle| (angle * EULER_PRECISION).round() / EULER_PRECISION + 0.0);
+		let rebuilt = rotation_matrix(rotation);
+
+		let exact =
+			(0..3).all(|row| (0..3).all(|column| (rebuilt[row][column] - matrix[row][column]).abs() <= EULER_EPSILON));
+
+		if !exact {
+			return None;
+		}
+
+		Some(Self {
+			position: [cf.position.x as f64, cf.position.y as f64, cf.position.z as f64],
+			rotation,
+		})
+	}
+
+	pub fn to_cframe(&self) -> CFrame {
+		let matrix = rotation_matrix(self.rotation).map(|row| row.map(|value| value as f32));
+		let row = |index: usize| Vector3::new(matrix[index][0], matrix[index][1], matrix[index][2]);
+
+		CFrame::new(
+			Vector3::new(
+				self.position[0] as f32,
+				self.position[1] as f32,
+				self.position[2] as f32,
+			),
+			Matrix3::new(row(0), row(1), row(2)),
+		)
+	}
+}
+
+/// Builds rotation matrix rows from Euler angles in degrees (XYZ order)
+fn rotation_matrix(rotation: [f64; 3]) -> [[f64; 3]; 3] {
+	let [x, y, z] = rotation.map(f64::to_radians);
+	let (sx, cx) = x.sin_cos();
+	let (sy, cy) = y.sin_cos();
+	let (sz, cz) = z.sin_cos();
+
+	[
+		[cy * cz, -cy * sz, sy],
+		[cx * sz + sx * sy * cz, cx * cz - sx * sy * sz, -sx * cy],
+		[sx * sz - cx * sy * cz, sx * cz + cx * sy * sz, cx * cy],
+	]
+}
+
+/// Extracts Euler angles in degrees (XYZ order) from rotation matrix rows,
+/// in gimbal lock (Y rotation of ±90°) the whole X and Z rotation goes to X
+fn euler_angles(matrix: &[[f64; 3]; 3]) -> [f64; 3] {
+	let sy = matrix[0][2].clamp(-1.0, 1.0);
+	let y = sy.asin();
+
+	let (x, z) = if sy.abs() < 1.0 - EULER_EPSILON {
+		((-matrix[1][2]).atan2(matrix[2][2]), (-matrix[0][1]).atan2(matrix[0][0]))
+	} else {
+		(matrix[2][1].atan2(matrix[1][1]), 0.0)
+	};
+
+	[x, y, z].map(f64::to_degrees)
+}
+
+fn is_zero(array: &[f64; 3]) -> bool {
+	array.iter().all(|value| *value == 0.0)
+}
+
+type DescriptorCache = RwLock<HashMap<(Ustr, Ustr), Option<&'static PropertyDescriptor<'static>>>>;
+type EnumCache = RwLock<HashMap<Ustr, Option<&'static EnumTable>>>;
+
+static DESCRIPTORS: OnceLock<DescriptorCache> = OnceLock::new();
+static ENUMS: OnceLock<EnumCache> = OnceLock::new();
+
+/// Maximum number of enum members listed in error messages
+const MAX_EXAMPLES: usize = 5;
+/// Invalid values longer than this are truncated in error messages
+const MAX_VALUE_LENGTH: usize = 64;
+
+/// Enum descriptor with items indexed by their values
+#[derive(Debug)]
+pub struct EnumTable {
+	pub descriptor: &'static EnumDescriptor<'static>,
+	names: HashMap<u32, &'static str>,
+}
+
+impl EnumTable {
+	fn new(descriptor: &'static EnumDescriptor<'static>) -> Self {
+		let mut items: Vec<_> = descriptor.items.iter().collect();
+
+		// Some enums have aliases, always pick the same name for them
+		items.sort_by(|a, b| a.0.cmp(b.0));
+
+		let mut names = HashMap::with_capacity(items.len());
+
+		for (name, value) in items {
+			names.entry(*value).or_insert(name.borrow());
+		}
+
+		Self { descriptor, names }
+	}
+
+	pub fn name_of(&self, value: u32) -> Option<&'static str> {
+		self.names.get(&value).copied()
+	}
+
+	pub fn value_of(&self, name: &str) -> Option<u32> {
+		self.descriptor.items.get(name).copied()
+	}
+}
+
+/// Returns descriptor of the property, looking through superclasses,
+/// results are cached as this is called for every synced property
+pub fn find_descriptor(class: &str, property: &str) -> Option<&'static PropertyDescriptor<'static>> {
+	let cache = DESCRIPTORS.get_or_init(Default::default);
+	let key = (Ustr::from(class), Ustr::from(property));
+
+	if let Some(descriptor) = cache.read().unwrap().get(&key) {
+		return *descriptor;
+	}
+
+	let descriptor = find_descriptor_uncached(class, property);
+	cache.write().unwrap().insert(key, descriptor);
+
+	descriptor
+}
+
+/// Returns enum with the given name, cached the same way as property descriptors
+pub fn find_enum(name: &str) -> Option<&'static EnumTable> {
+	let cache = ENUMS.get_or_init(Default::default);
+	let key = Ustr::from(name);
+
+	if let Some(table) = cache.read().unwrap().get(&key) {
+		return *table;
+	}
+
+	let mut cache = cache.write().unwrap();
+
+	// Another thread might have built the table in the meantime
+	if let Some(table) = cache.get(&key) {
+		return *table;
+	}
+
+	// Tables live as long as the database, there is a single one per enum
+	let table = rbx_reflection_database::get()
+		.enums
+		.get(name)
+		.map(|descriptor| &*Box::leak(Box::new(EnumTable::new(descriptor))));
+
+	cache.insert(key, table);
+
+	table
+}
+
+pub fn find_descriptor_uncached(class: &str, property: &str) -> Option<&'static PropertyDescriptor<'static>> {
 	let database = rbx_reflection_database::get();
 	let mut current_class = class;
 
@@ -571,23 +845,52 @@ fn find_descriptor(class: &str, property: &str) -> Option<&'static PropertyDescr
 	}
 }
 
-fn list_examples(values: &[&str]) -> String {
-	let mut output = String::new();
-	let length = (values.len() - 1).min(5);
+/// Lists up to `MAX_EXAMPLES` of the `values` in a sentence, if there are more of
+/// them the ones closest to `input` are listed first, ties keep the original order
+pub fn list_examples(values: &[&str], input: Option<&str>) -> String {
+	let mut values = values.to_vec();
 
-	for value in &values[..length] {
-		output.push_str(value);
-		output.push_str(", ");
+	if let Some(input) = input.filter(|_| values.len() > MAX_EXAMPLES) {
+		let input = input.to_lowercase();
+		values.sort_by_cached_key(|value| edit_distance(&input, &value.to_lowercase()));
 	}
 
-	if values.len() > 5 {
-		write!(output, "or {} more", values.len() - length).unwrap();
-	} else {
-		output.push_str("or ");
-		output.push_str(values[values.len() - 1]);
+	let (listed, rest) = values.split_at(values.len().min(MAX_EXAMPLES));
+
+	match (listed, rest.len()) {
+		([], _) => String::new(),
+		([value], 0) => value.to_string(),
+		([init @ .., last], 0) => format!("{} or {}", init.join(", "), last),
+		(listed, more) => format!("{} or {} more", listed.join(", "), more),
 	}
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+	let b = b.chars().collect::<Vec<_>>();
+	let mut previous = (0..=b.len()).collect::<Vec<_>>();
 
-	output
+	for (i, a) in a.chars().enumerate() {
+		let mut current = vec![i + 1; b.len() + 1];
+
+		for (j, b) in b.iter().enumerate() {
+			let cost = if a == *b { 0 } else { 1 };
+			current[j + 1] = (previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1);
+		}
+
+		previous = current;
+	}
+
+	previous[b.len()]
+}
+
+/// Shortens invalid values so they don't flood the error message
+fn truncate_value(value: &str) -> String {
+	if value.chars().count() > MAX_VALUE_LENGTH {
+		format!("{}...", value.chars().take(MAX_VALUE_LENGTH).collect::<String>())
+	} else {
+		value.to_owned()
+	}
 }
 
 #[inline]
@@ -0,0 +1,69 @@
+use actix_msgpack::MsgPack;
+use actix_web::{
+	post,
+	web::{self, Data},
+	HttpResponse, Responder,
+};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use std::{collections::HashMap, sync::Arc};
+
+use super::pause;
+use crate::{assets::AssetClass, core::Core};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AddAssetRequest {
+	/// Name of the file, its extension is used to infer the class
+	pub name: String,
+	/// Contents of the file, uploaded by the CLI as the server never reads local paths
+	pub contents: ByteBuf,
+	pub path: String,
+	pub class: Option<AssetClass>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetIdsRequest {
+	pub ids: HashMap<String, u64>,
+}
+
+/// Store the file and create an instance that references it, see `Core::add_asset`
+#[post("/asset")]
+async fn add(request: MsgPack<AddAssetRequest>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: add asset {}", request.name);
+
+	if let Some(response) = pause::reject(&core) {
+		return response;
+	}
+
+	let request = request.0;
+	let core = core.into_inner();
+
+	// Waits until the processor adds the instance so it can't block the server
+	match web::block(move || core.add_asset(&request.name, &request.contents, &request.path, request.class)).await {
+		Ok(Ok(asset)) => HttpResponse::Ok().json(asset),
+		Ok(Err(err)) => HttpResponse::BadRequest().body(err.to_string()),
+		Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+	}
+}
+
+/// Replace placeholders of uploaded assets, responds with the number of updated instances
+#[post("/assets/ids")]
+async fn ids(request: MsgPack<AssetIdsRequest>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: asset IDs");
+
+	if let Some(response) = pause::reject(&core) {
+		return response;
+	}
+
+	let request = request.0;
+	let core = core.into_inner();
+
+	match web::block(move || core.apply_asset_ids(&request.ids)).await {
+		Ok(Ok(count)) => HttpResponse::Ok().json(count),
+		Ok(Err(err)) => HttpResponse::BadRequest().body(err.to_string()),
+		Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+	}
+}
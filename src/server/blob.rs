@@ -0,0 +1,20 @@
+use actix_msgpack::MsgPackResponseBuilder;
+use actix_web::{
+	get,
+	web::{Data, Path},
+	HttpResponse, Responder,
+};
+use log::trace;
+use std::sync::Arc;
+
+use crate::core::Core;
+
+#[get("/blob/{hash}")]
+async fn main(hash: Path<String>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: blob");
+
+	match core.queue().blobs().get(&hash) {
+		Some(value) => HttpResponse::Ok().msgpack(value.as_ref()),
+		None => HttpResponse::NotFound().body("Blob not found"),
+	}
+}
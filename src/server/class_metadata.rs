@@ -0,0 +1,43 @@
+use actix_web::{
+	get,
+	http::header::{self, CacheDirective},
+	web::Data,
+	HttpRequest, HttpResponse, Responder,
+};
+use log::trace;
+use std::sync::Arc;
+
+use crate::core::Core;
+
+#[get("/class-metadata")]
+async fn main(request: HttpRequest, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: class metadata");
+
+	let table = core.class_metadata();
+
+	let not_modified = request
+		.headers()
+		.get(header::IF_NONE_MATCH)
+		.and_then(|value| value.to_str().ok())
+		.is_some_and(|value| {
+			value
+				.split(',')
+				.any(|tag| tag.trim() == table.etag || tag.trim() == "*")
+		});
+
+	let mut response = if not_modified {
+		HttpResponse::NotModified()
+	} else {
+		HttpResponse::Ok()
+	};
+
+	response
+		.insert_header((header::ETAG, table.etag.clone()))
+		.insert_header(header::CacheControl(vec![CacheDirective::NoCache]));
+
+	if not_modified {
+		response.finish()
+	} else {
+		response.json(table)
+	}
+}
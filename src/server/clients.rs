@@ -0,0 +1,19 @@
+use actix_msgpack::MsgPackResponseBuilder;
+use actix_web::{get, web::Data, HttpRequest, HttpResponse, Responder};
+use log::trace;
+use std::sync::Arc;
+
+use super::ui;
+use crate::core::Core;
+
+/// Responds with JSON instead of MessagePack if asked to, e.g. by the dashboard
+#[get("/clients")]
+async fn main(request: HttpRequest, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: clients");
+
+	if ui::wants_json(&request) {
+		HttpResponse::Ok().json(core.queue().clients())
+	} else {
+		HttpResponse::Ok().msgpack(core.queue().clients())
+	}
+}
@@ -0,0 +1,29 @@
+use actix_web::{
+	get,
+	web::{Data, Query},
+	HttpResponse, Responder,
+};
+use log::trace;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::{
+	constants::{COMPLETE_LIMIT, MAX_COMPLETE_LIMIT},
+	core::Core,
+};
+
+#[derive(Deserialize, Debug)]
+struct Request {
+	#[serde(default)]
+	prefix: String,
+	limit: Option<usize>,
+}
+
+#[get("/complete")]
+async fn main(request: Query<Request>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: complete");
+
+	let limit = request.limit.unwrap_or(COMPLETE_LIMIT).clamp(1, MAX_COMPLETE_LIMIT);
+
+	HttpResponse::Ok().json(core.complete(&request.prefix, limit))
+}
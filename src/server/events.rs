@@ -0,0 +1,23 @@
+use actix_web::{
+	get,
+	web::{Data, Query},
+	HttpResponse, Responder,
+};
+use log::trace;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::core::Core;
+
+#[derive(Deserialize, Debug)]
+struct Request {
+	/// Last sequence number the client has seen
+	since: Option<u64>,
+}
+
+/// Polling alternative to `/ws` for clients that cannot keep a socket open
+#[get("/events")]
+async fn main(query: Query<Request>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: events");
+	HttpResponse::Ok().json(core.events().page(query.since))
+}
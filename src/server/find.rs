@@ -0,0 +1,39 @@
+use actix_web::{
+	get,
+	web::{Data, Query},
+	HttpResponse, Responder,
+};
+use log::trace;
+use std::sync::Arc;
+
+use crate::{
+	class_meta,
+	constants::{FIND_PAGE_LIMIT, MAX_FIND_PAGE_LIMIT},
+	core::{
+		find::{FindQuery, FindRequest},
+		Core,
+	},
+};
+
+#[get("/find")]
+async fn main(request: Query<FindRequest>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: find");
+
+	let query = match FindQuery::new(&request) {
+		Ok(query) => query,
+		Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+	};
+
+	let limit = request.limit.unwrap_or(FIND_PAGE_LIMIT).clamp(1, MAX_FIND_PAGE_LIMIT);
+
+	match core.find(&query, request.cursor.as_deref(), limit) {
+		Ok(mut page) => {
+			if request.enrich.as_deref() == Some(class_meta::ENRICH) {
+				page.class_meta = Some(core.instance_metadata(page.instances.iter().map(|instance| instance.id)));
+			}
+
+			HttpResponse::Ok().json(page)
+		}
+		Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+	}
+}
@@ -0,0 +1,55 @@
+use actix_msgpack::MsgPack;
+use actix_web::{get, post, web::Data, HttpResponse, Responder};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::core::{frozen::SyncDirection, Core};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FreezeRequest {
+	/// Dot separated path of the instance, e.g. `Workspace.Terrain`
+	pub path: String,
+	/// Whether to freeze or unfreeze the instance
+	pub frozen: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FrozenSyncRequest {
+	pub path: String,
+	pub direction: SyncDirection,
+}
+
+#[get("/frozen")]
+async fn main(core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: frozen");
+	HttpResponse::Ok().json(core.frozen())
+}
+
+#[post("/freeze")]
+async fn freeze(request: MsgPack<FreezeRequest>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: freeze");
+
+	let result = if request.frozen {
+		core.freeze(&request.path)
+	} else {
+		core.unfreeze(&request.path)
+	};
+
+	match result {
+		Ok(changed) => HttpResponse::Ok().json(changed),
+		Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+	}
+}
+
+#[post("/frozen/sync")]
+async fn sync(request: MsgPack<FrozenSyncRequest>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: frozen sync");
+
+	match core.sync_frozen(&request.path, request.direction) {
+		Ok(total) => HttpResponse::Ok().json(total),
+		Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+	}
+}
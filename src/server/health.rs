@@ -0,0 +1,28 @@
+use actix_web::{get, web::Data, HttpResponse, Responder};
+use log::trace;
+use std::{process, sync::Arc};
+
+use super::{protocol, Health};
+use crate::{constants::PROTOCOL_VERSION, core::Core};
+
+#[get("/health")]
+async fn main(core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: health");
+
+	// Project lock can't be held while hashing the tree as it locks the project too
+	let stale_config = core.project().stale_config.clone();
+
+	HttpResponse::Ok().json(Health {
+		version: env!("CARGO_PKG_VERSION").to_owned(),
+		project: core.name(),
+		pid: process::id(),
+		clients: core.queue().clients().len(),
+		stale_config,
+		pending: core.pending().len(),
+		oldest_pending: core.pending().oldest().map(|age| age.as_secs()),
+		protocol: PROTOCOL_VERSION,
+		capabilities: protocol::CAPABILITIES.to_vec(),
+		tree_hash: core.tree_hash().to_string(),
+		pause: core.processor().pause_status(),
+	})
+}
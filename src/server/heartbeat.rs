@@ -0,0 +1,34 @@
+use actix_msgpack::{MsgPack, MsgPackResponseBuilder};
+use actix_web::{post, web::Data, HttpResponse, Responder};
+use log::trace;
+use rbx_dom_weak::types::Ref;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::core::{heartbeat::NodeHash, Core};
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+	client_id: u32,
+	#[serde(default)]
+	compare: Vec<NodeHash>,
+	#[serde(default)]
+	resync: Vec<Ref>,
+}
+
+/// Round of narrowing down divergence after a mismatched `Heartbeat`,
+/// see `heartbeat::hash_subtree` for the whole exchange
+#[post("/heartbeat")]
+async fn main(request: MsgPack<Request>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: heartbeat");
+
+	if !core.queue().is_subscribed(request.client_id) {
+		return HttpResponse::Unauthorized().body("Not subscribed");
+	}
+
+	match core.compare_hashes(request.client_id, &request.compare, &request.resync) {
+		Ok(comparison) => HttpResponse::Ok().msgpack(comparison),
+		Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+	}
+}
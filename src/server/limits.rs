@@ -0,0 +1,351 @@
+use actix_msgpack::{MsgPackConfig, MsgPackError};
+use actix_web::{
+	body::{BoxBody, MessageBody},
+	dev::{ServiceRequest, ServiceResponse},
+	error::InternalError,
+	http::header,
+	middleware::Next,
+	web::Data,
+	Error, HttpResponse,
+};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, Mutex,
+	},
+	time::{Duration, Instant},
+};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::{argon_warn, config::Config, constants::LIMIT_WARN_INTERVAL, t};
+
+/// Header clients can send so they get their own rate limit
+/// instead of sharing one with everything on their address
+pub const CLIENT_HEADER: &str = "X-Argon-Client";
+
+/// Endpoints that serialize snapshots, read deferred values or apply syncback
+const HEAVY_ENDPOINTS: [&str; 4] = ["/snapshot", "/properties", "/write", "/commit"];
+
+/// Limits applied to all requests, reported to clients
+/// during handshake so they can throttle themselves
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Limits {
+	pub max_request_size: usize,
+	pub max_log_size: usize,
+	pub max_payload_size: usize,
+	/// Changes over `max_payload_size` have to be sent to `/upload` in chunks
+	pub max_upload_size: usize,
+	pub rate_limit: u32,
+	pub rate_limit_burst: u32,
+	pub max_heavy_requests: usize,
+}
+
+impl Limits {
+	pub fn new(config: &Config) -> Self {
+		Self {
+			max_request_size: config.max_request_size,
+			max_log_size: config.max_log_size,
+			max_payload_size: config.max_payload_size,
+			max_upload_size: config.max_upload_size,
+			rate_limit: config.rate_limit,
+			rate_limit_burst: config.rate_limit_burst,
+			max_heavy_requests: config.max_heavy_requests,
+		}
+	}
+
+	/// Returns maximum body size of requests to the endpoint
+	pub fn body_limit(&self, path: &str) -> usize {
+		match path {
+			"/log" => self.max_log_size,
+			"/write" => self.max_payload_size,
+			_ => self.max_request_size,
+		}
+	}
+
+	/// Returns the largest body size accepted by any endpoint
+	pub fn max_body_limit(&self) -> usize {
+		self.max_request_size.max(self.max_log_size).max(self.max_payload_size)
+	}
+}
+
+/// Body of 413 responses, so clients know how much they have to split the request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadTooLarge {
+	pub endpoint: String,
+	pub limit: usize,
+	/// Size of the request, unknown if it was streamed without `Content-Length`
+	pub size: Option<usize>,
+}
+
+impl PayloadTooLarge {
+	pub fn response(&self) -> HttpResponse {
+		HttpResponse::PayloadTooLarge().json(self)
+	}
+}
+
+/// Number of requests rejected or delayed by the limiter
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitStats {
+	pub payload_too_large: u64,
+	pub rate_limited: u64,
+	/// Heavy requests that had to wait for other ones to finish
+	pub queued: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Violation {
+	PayloadTooLarge,
+	RateLimited,
+}
+
+#[derive(Debug)]
+struct Bucket {
+	tokens: f64,
+	updated: Instant,
+}
+
+#[derive(Debug)]
+struct Offence {
+	warned: Instant,
+	suppressed: u64,
+}
+
+#[derive(Debug)]
+pub struct Limiter {
+	limits: Limits,
+	buckets: Mutex<HashMap<String, Bucket>>,
+	offences: Mutex<HashMap<(String, Violation), Offence>>,
+	heavy: Semaphore,
+	payload_too_large: AtomicU64,
+	rate_limited: AtomicU64,
+	queued: AtomicU64,
+}
+
+impl Limiter {
+	pub fn new(limits: Limits) -> Self {
+		Self {
+			heavy: Semaphore::new(limits.max_heavy_requests),
+			limits,
+			buckets: Mutex::new(HashMap::new()),
+			offences: Mutex::new(HashMap::new()),
+			payload_too_large: AtomicU64::new(0),
+			rate_limited: AtomicU64::new(0),
+			queued: AtomicU64::new(0),
+		}
+	}
+
+	pub fn limits(&self) -> &Limits {
+		&self.limits
+	}
+
+	pub fn stats(&self) -> LimitStats {
+		LimitStats {
+			payload_too_large: self.payload_too_large.load(Ordering::Relaxed),
+			rate_limited: self.rate_limited.load(Ordering::Relaxed),
+			queued: self.queued.load(Ordering::Relaxed),
+		}
+	}
+
+	/// Takes a token from the offender's bucket,
+	/// returns how long to wait if there is none left
+	fn take(&self, offender: &str) -> Result<(), Duration> {
+		let rate = self.limits.rate_limit as f64;
+		let burst = self.limits.rate_limit_burst.max(1) as f64;
+
+		if rate == 0.0 {
+			return Ok(());
+		}
+
+		let now = Instant::now();
+		let mut buckets = self.buckets.lock().unwrap();
+
+		// Buckets that would be full again are the same as missing ones
+		if buckets.len() > 64 {
+			buckets.retain(|_, bucket| bucket.tokens + (now - bucket.updated).as_secs_f64() * rate < burst);
+		}
+
+		let bucket = buckets.entry(offender.to_owned()).or_insert(Bucket {
+			tokens: burst,
+			updated: now,
+		});
+
+		bucket.tokens = (bucket.tokens + (now - bucket.updated).as_secs_f64() * rate).min(burst);
+		bucket.updated = now;
+
+		if bucket.tokens >= 1.0 {
+			bucket.tokens -= 1.0;
+			Ok(())
+		} else {
+			Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+		}
+	}
+
+	/// Waits until there is a free slot for a heavy request, `None` if unlimited
+	async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+		if self.limits.max_heavy_requests == 0 {
+			return None;
+		}
+
+		if let Ok(permit) = self.heavy.try_acquire() {
+			return Some(permit);
+		}
+
+		self.queued.fetch_add(1, Ordering::Relaxed);
+		self.heavy.acquire().await.ok()
+	}
+
+	/// Counts the violation and warns about it once per offender every
+	/// `LIMIT_WARN_INTERVAL`, the warning includes the suppressed ones
+	fn report(&self, offender: &str, violation: Violation, describe: impl FnOnce(u64) -> String) {
+		match violation {
+			Violation::PayloadTooLarge => &self.payload_too_large,
+			Violation::RateLimited => &self.rate_limited,
+		}
+		.fetch_add(1, Ordering::Relaxed);
+
+		let mut offences = self.offences.lock().unwrap();
+
+		match offences.get_mut(&(offender.to_owned(), violation)) {
+			Some(offence) if offence.warned.elapsed() < LIMIT_WARN_INTERVAL => {
+				offence.suppressed += 1;
+			}
+			Some(offence) => {
+				argon_warn!("{}", describe(offence.suppressed + 1));
+
+				offence.warned = Instant::now();
+				offence.suppressed = 0;
+			}
+			None => {
+				argon_warn!("{}", describe(1));
+
+				offences.insert(
+					(offender.to_owned(), violation),
+					Offence {
+						warned: Instant::now(),
+						suppressed: 0,
+					},
+				);
+			}
+		}
+	}
+}
+
+/// Rejects requests over the rate or body size limit
+/// and caps the number of concurrent heavy requests
+pub async fn middleware(
+	request: ServiceRequest,
+	next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+	let Some(limiter) = request.app_data::<Data<Arc<Limiter>>>().cloned() else {
+		return Ok(next.call(request).await?.map_into_boxed_body());
+	};
+
+	let path = endpoint(request.path()).to_owned();
+	let offender = get_offender(&request);
+
+	if let Err(retry_after) = limiter.take(&offender) {
+		limiter.report(&offender, Violation::RateLimited, |count| {
+			t!(
+				"limits.rate_limited",
+				count = count.to_string().bold(),
+				offender = offender.bold(),
+				rate = limiter.limits.rate_limit.to_string().bold()
+			)
+			.to_string()
+		});
+
+		let response = HttpResponse::TooManyRequests()
+			.insert_header((
+				header::RETRY_AFTER,
+				retry_after.as_secs_f64().ceil().max(1.0).to_string(),
+			))
+			.body("Too many requests");
+
+		return Ok(request.into_response(response));
+	}
+
+	let limit = limiter.limits.body_limit(&path);
+
+	let size = request
+		.headers()
+		.get(header::CONTENT_LENGTH)
+		.and_then(|length| length.to_str().ok()?.parse::<usize>().ok());
+
+	if let Some(size) = size.filter(|size| *size > limit) {
+		limiter.report(&offender, Violation::PayloadTooLarge, |count| {
+			t!(
+				"limits.payload_too_large",
+				count = count.to_string().bold(),
+				offender = offender.bold(),
+				size = size.to_string().bold(),
+				endpoint = path.bold(),
+				limit = limit.to_string().bold()
+			)
+			.to_string()
+		});
+
+		let response = PayloadTooLarge {
+			endpoint: path,
+			limit,
+			size: Some(size),
+		}
+		.response();
+
+		return Ok(request.into_response(response));
+	}
+
+	let _permit = if HEAVY_ENDPOINTS.contains(&path.as_str()) {
+		limiter.acquire().await
+	} else {
+		None
+	};
+
+	Ok(next.call(request).await?.map_into_boxed_body())
+}
+
+/// Extractor config accepting the largest body of any endpoint, the ones
+/// with smaller limits are checked by `middleware` beforehand
+pub fn msgpack_config(limits: &Limits) -> MsgPackConfig {
+	let max_limit = limits.max_body_limit();
+	let mut config = MsgPackConfig::default();
+
+	config.limit(max_limit).error_handler(move |err, request| {
+		let response = match &err {
+			MsgPackError::Overflow => PayloadTooLarge {
+				endpoint: endpoint(request.path()).to_owned(),
+				limit: request
+					.app_data::<Data<Arc<Limiter>>>()
+					.map_or(max_limit, |limiter| limiter.limits.body_limit(endpoint(request.path()))),
+				size: None,
+			}
+			.response(),
+			_ => HttpResponse::BadRequest().body(format!("Failed to decode request: {}", err)),
+		};
+
+		InternalError::from_response(err, response).into()
+	});
+
+	config
+}
+
+fn get_offender(request: &ServiceRequest) -> String {
+	if let Some(id) = request.headers().get(CLIENT_HEADER).and_then(|id| id.to_str().ok()) {
+		return format!("client {}", id);
+	}
+
+	request
+		.peer_addr()
+		.map_or_else(|| String::from("unknown"), |address| address.ip().to_string())
+}
+
+/// Returns the last segment of the request path, so endpoints of workspace
+/// members served under their names (e.g. `/game/write`) get the same limits
+fn endpoint(path: &str) -> &str {
+	path.rfind('/').map_or(path, |index| &path[index..])
+}
@@ -4,7 +4,7 @@ use log::{error, info, trace, warn};
 use serde::Deserialize;
 use std::{fs::OpenOptions, io::Write, path::PathBuf, sync::Arc};
 
-use crate::core::Core;
+use crate::core::{events::EventKind, Core};
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -83,11 +83,18 @@ fn clear_log_file(log_path: &PathBuf, client_id_str: &str) -> bool {
 }
 
 #[post("/log")]
-async fn main(request: MsgPack<LogRequest>, _core: Data<Arc<Core>>) -> impl Responder {
+async fn main(request: MsgPack<LogRequest>, core: Data<Arc<Core>>) -> impl Responder {
 	// Log the raw request first for detailed debugging
 	trace!("RAW /log request: {:#?}", request);
 
 	let log_request_data = request.0;
+
+	core.events().publish(EventKind::Log {
+		client_id: log_request_data.client_id,
+		level: log_request_data.level.clone(),
+		message: log_request_data.message.clone(),
+	});
+
 	let log_path = get_log_path();
 	let client_id_str = log_request_data
 		.client_id
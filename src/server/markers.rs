@@ -0,0 +1,58 @@
+use actix_msgpack::MsgPack;
+use actix_web::{get, post, web::Data, HttpResponse, Responder};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::core::Core;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkerRequest {
+	pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoRequest {
+	pub marker: String,
+}
+
+/// Markers the workspace can be rolled back to, from the oldest
+#[get("/markers")]
+async fn main(core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: markers");
+	HttpResponse::Ok().json(core.markers())
+}
+
+/// Mark the current state of the workspace
+#[post("/marker")]
+async fn add(request: MsgPack<MarkerRequest>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: add marker {}", request.name);
+
+	match core.add_marker(&request.name) {
+		Ok(marker) => HttpResponse::Ok().json(marker),
+		Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+	}
+}
+
+#[post("/markers/clear")]
+async fn clear(core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: clear markers");
+
+	match core.clear_markers() {
+		Ok(()) => HttpResponse::Ok().body("Cleared successfully"),
+		Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+	}
+}
+
+/// Roll the workspace back to the marker, responds with the number of undone operations
+#[post("/undo")]
+async fn undo(request: MsgPack<UndoRequest>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: undo to marker {}", request.marker);
+
+	match core.undo_to_marker(&request.marker) {
+		Ok(count) => HttpResponse::Ok().json(count),
+		Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+	}
+}
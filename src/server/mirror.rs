@@ -0,0 +1,41 @@
+use actix_web::{
+	body::{BoxBody, MessageBody},
+	dev::{ServiceRequest, ServiceResponse},
+	http::header,
+	middleware::Next,
+	web::Data,
+	Error, HttpResponse,
+};
+
+/// Token the mirror requires from its consumers, either as
+/// `Authorization: Bearer <token>` or `?token=<token>` query
+/// for consumers that can't set headers, like WebSockets in browsers
+#[derive(Debug, Clone)]
+pub struct MirrorToken(pub String);
+
+pub async fn middleware(
+	request: ServiceRequest,
+	next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+	let Some(token) = request.app_data::<Data<MirrorToken>>().cloned() else {
+		return Ok(next.call(request).await?.map_into_boxed_body());
+	};
+
+	let header = request
+		.headers()
+		.get(header::AUTHORIZATION)
+		.and_then(|value| value.to_str().ok())
+		.and_then(|value| value.strip_prefix("Bearer "));
+
+	let query = request
+		.query_string()
+		.split('&')
+		.find_map(|pair| pair.strip_prefix("token="));
+
+	if header.or(query) != Some(token.0.as_str()) {
+		let response = HttpResponse::Unauthorized().body("Invalid or missing mirror token");
+		return Ok(request.into_response(response));
+	}
+
+	Ok(next.call(request).await?.map_into_boxed_body())
+}
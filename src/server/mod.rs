@@ -1,49 +1,168 @@
-use actix_msgpack::MsgPackConfig;
 use actix_web::{
+	middleware,
 	web::{self, Data},
-	App, HttpServer, Responder,
+	App, HttpRequest, HttpResponse, HttpServer, Responder, Scope,
 };
 use derive_from_one::FromOne;
+use rbx_dom_weak::types::Ref;
 use serde::{Deserialize, Serialize};
-use std::{io::Result, net::TcpListener, sync::Arc};
+use std::{collections::HashMap, io::Result, net::TcpListener, sync::Arc};
 
 use crate::{
-	constants::MAX_PAYLOAD_SIZE,
-	core::{changes::Changes, Core},
+	config::Config,
+	constants::UPLOAD_TIMEOUT,
+	core::{
+		blobs::BlobRef, changes::Changes, deferred::DeferredProperty, lifecycle::SyncbackResult,
+		meta::ReadOnlyRejection, processor::pause::PauseStatus, snapshot::AddedSnapshot, Core,
+	},
 	project::ProjectDetails,
 };
+use limits::{Limiter, Limits};
+use mirror::MirrorToken;
+use protocol::Capability;
+use upload::Uploads;
 
+pub mod limits;
+pub mod protocol;
+
+pub mod assets;
+mod blob;
+mod class_metadata;
+mod clients;
+mod complete;
 mod details;
+mod events;
 mod exec;
+mod find;
+pub mod frozen;
+mod health;
+mod heartbeat;
 mod home;
 mod log;
+pub mod markers;
+pub mod mirror;
 mod open;
+pub mod patch;
+pub mod pause;
+mod pending;
+mod properties;
 mod read;
+mod settings;
+mod skipped;
 mod snapshot;
+mod stats;
 mod stop;
 mod subscribe;
+mod sync;
+mod test_results;
+pub mod ui;
+mod unknowns;
 mod unsubscribe;
+pub mod upload;
 mod write;
+mod ws;
 
 #[derive(Debug, Clone, Serialize, FromOne)]
 pub enum Message {
 	SyncChanges(SyncChanges),
+	SyncBlobChanges(SyncBlobChanges),
+	SyncChunk(SyncChunk),
+	SyncProgress(SyncProgress),
+	SyncComplete(SyncComplete),
+	SyncResume(SyncResume),
 	SyncbackChanges(SyncbackChanges),
 	SyncDetails(SyncDetails),
+	SyncSubtree(SyncSubtree),
+	Heartbeat(Heartbeat),
 	ExecuteCode(ExecuteCode),
 	Disconnect(Disconnect),
 	Log(LogMessage),
+	SyncbackRejected(SyncbackRejected),
+	SyncbackReport(SyncbackReport),
+	PauseChanged(PauseChanged),
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct SyncChanges(pub Changes);
 
+/// Changes with large property values replaced by blob references,
+/// only sent to clients that support them
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncBlobChanges {
+	pub changes: Changes,
+	pub blobs: Vec<BlobRef>,
+}
+
+/// Part of the initial sync, has to be acknowledged with `/sync/ack`
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncChunk {
+	pub chunk: usize,
+	pub instances: Vec<AddedSnapshot>,
+	/// Heavy values left out of the instances, only for
+	/// clients that negotiated the `lazyProperties` capability
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub deferred: Vec<DeferredProperty>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncProgress {
+	pub phase: String,
+	pub sent: usize,
+	pub total: usize,
+}
+
+/// Sent once every chunk is acknowledged, see
+/// `initial_sync::checksum` for the checksum algorithm
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncComplete {
+	pub total: usize,
+	pub checksum: String,
+}
+
+/// First message of a client that continued from its cursor after the server
+/// restarted, maps referents it knows instances by to their new referents
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncResume {
+	pub refs: HashMap<Ref, Ref>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct SyncbackChanges();
 
 #[derive(Debug, Clone, Serialize)]
 pub struct SyncDetails(pub ProjectDetails);
 
+/// Syncback operation of the client that was not applied
+/// as it targets a read-only source, e.g. a model file
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncbackRejected(pub ReadOnlyRejection);
+
+/// Results of all operations of the client's syncback batch with
+/// outcomes of their properties, e.g. skipped read-only ones
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncbackReport {
+	pub results: Vec<SyncbackResult>,
+}
+
+/// Syncing was paused or resumed, syncback is rejected while paused
+#[derive(Debug, Clone, Serialize)]
+pub struct PauseChanged(pub PauseStatus);
+
+/// Instance with all of its descendants that diverged from the client's
+/// tree, replaces the client's copy or is added if the client lacks it
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncSubtree {
+	pub instance: Box<AddedSnapshot>,
+}
+
+/// Hash of the tree the client compares with its own,
+/// see `heartbeat::hash_subtree` for the algorithm
+#[derive(Debug, Clone, Serialize)]
+pub struct Heartbeat {
+	pub id: Ref,
+	pub hash: String,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ExecuteCode {
 	pub code: String,
@@ -61,6 +180,34 @@ pub struct LogMessage {
 	pub level: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Health {
+	pub version: String,
+	pub project: String,
+	pub pid: u32,
+	pub clients: usize,
+	/// Error of the last failed project reload
+	pub stale_config: Option<String>,
+	/// Number of instances changed while no client was connected
+	#[serde(default)]
+	pub pending: usize,
+	/// Age of the oldest pending change in seconds
+	#[serde(default)]
+	pub oldest_pending: Option<u64>,
+	/// Protocol version spoken by the server
+	#[serde(default)]
+	pub protocol: u32,
+	/// Capabilities supported by the server
+	#[serde(default)]
+	pub capabilities: Vec<Capability>,
+	/// Canonical hash of the whole tree
+	#[serde(default)]
+	pub tree_hash: String,
+	#[serde(default)]
+	pub pause: PauseStatus,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthRequest {
@@ -68,53 +215,192 @@ pub struct AuthRequest {
 }
 
 pub struct Server {
-	core: Arc<Core>,
+	/// Served cores and names of workspace members, empty for
+	/// a single project, the first one is served at the root
+	members: Vec<(String, Arc<Core>)>,
 	host: String,
 	port: u16,
+	mirror: Option<(u16, Option<MirrorToken>)>,
 }
 
 impl Server {
 	pub fn new(core: Arc<Core>, host: &str, port: u16) -> Self {
+		Self::new_workspace(vec![(String::new(), core)], host, port)
+	}
+
+	/// Serves members of the workspace under their names on the same port
+	pub fn new_workspace(members: Vec<(String, Arc<Core>)>, host: &str, port: u16) -> Self {
+		assert!(!members.is_empty(), "Server has to serve at least one core");
+
 		Self {
-			core,
+			members,
 			host: host.to_owned(),
 			port,
+			mirror: None,
 		}
 	}
 
+	/// Also serves read-only endpoints on the second port, so the live tree can be
+	/// watched by others without any way to change it. Consumers of the mirror get
+	/// events from the same event log as primary clients but never touch their queues
+	pub fn with_mirror(mut self, port: u16, token: Option<String>) -> Self {
+		self.mirror = Some((port, token.map(MirrorToken)));
+		self
+	}
+
 	#[actix_web::main]
 	pub async fn start(&self) -> Result<()> {
-		let core = self.core.clone();
+		let members = self.members.clone();
+		let limiter = Arc::new(Limiter::new(Limits::new(&Config::new())));
+		let primary_limiter = limiter.clone();
+		let uploads = Arc::new(Uploads::new(limiter.limits().max_upload_size, UPLOAD_TIMEOUT));
 
-		HttpServer::new(move || {
-			let mut msgpack_config = MsgPackConfig::default();
-			msgpack_config.limit(MAX_PAYLOAD_SIZE);
+		let primary = HttpServer::new(move || {
+			let limiter = primary_limiter.clone();
+			let msgpack_config = limits::msgpack_config(limiter.limits());
 
-			App::new()
-				.app_data(Data::new(core.clone()))
+			let mut app = App::new()
+				.app_data(Data::new(limiter))
+				.app_data(Data::new(uploads.clone()))
 				.app_data(msgpack_config)
-				.service(details::main)
-				.service(subscribe::main)
-				.service(unsubscribe::main)
-				.service(snapshot::main)
-				.service(read::main)
-				.service(write::main)
-				.service(exec::main)
-				.service(open::main)
-				.service(stop::main)
-				.service(home::main)
-				.service(log::main)
+				.wrap(middleware::from_fn(limits::middleware));
+
+			for (name, core) in members.iter().filter(|(name, _)| !name.is_empty()) {
+				app = app.service(Self::scope(&format!("/{}", name), core.clone()));
+			}
+
+			app.service(Self::scope("", members[0].1.clone()))
 				.default_service(web::to(Self::default_redirect))
 		})
 		.backlog(0)
 		.disable_signals()
 		.bind((self.host.clone(), self.port))?
-		.run()
-		.await
+		.run();
+
+		let Some((port, token)) = self.mirror.clone() else {
+			return primary.await;
+		};
+
+		let members = self.members.clone();
+
+		let mirror = HttpServer::new(move || {
+			let mut app = App::new()
+				.app_data(Data::new(limiter.clone()))
+				.wrap(middleware::from_fn(mirror::middleware))
+				.wrap(middleware::from_fn(limits::middleware));
+
+			if let Some(token) = &token {
+				app = app.app_data(Data::new(token.clone()));
+			}
+
+			for (name, core) in members.iter().filter(|(name, _)| !name.is_empty()) {
+				app = app.service(Self::mirror_scope(&format!("/{}", name), core.clone()));
+			}
+
+			app.service(Self::mirror_scope("", members[0].1.clone()))
+				.default_service(web::to(ui::not_supported))
+		})
+		.backlog(0)
+		.disable_signals()
+		.bind((self.host.clone(), port))?
+		.run();
+
+		futures::try_join!(primary, mirror).map(|_| ())
 	}
 
-	async fn default_redirect() -> impl Responder {
-		web::Redirect::to("/")
+	/// All endpoints of a single project
+	pub fn scope(path: &str, core: Arc<Core>) -> Scope {
+		web::scope(path)
+			.app_data(Data::new(core))
+			.service(details::main)
+			.service(clients::main)
+			.service(subscribe::main)
+			.service(subscribe::filter)
+			.service(unsubscribe::main)
+			.service(snapshot::main)
+			.service(snapshot::page)
+			.service(find::main)
+			.service(complete::main)
+			.service(class_metadata::main)
+			.service(blob::main)
+			.service(sync::main)
+			.service(sync::ack)
+			.service(sync::resolve)
+			.service(sync::studio)
+			.service(read::main)
+			.service(write::main)
+			.service(upload::main)
+			.service(upload::commit)
+			.service(exec::main)
+			.service(open::main)
+			.service(patch::main)
+			.service(stop::main)
+			.service(pause::main)
+			.service(pause::resume)
+			.service(home::main)
+			.service(health::main)
+			.service(heartbeat::main)
+			.service(pending::main)
+			.service(frozen::main)
+			.service(frozen::freeze)
+			.service(frozen::sync)
+			.service(properties::main)
+			.service(settings::main)
+			.service(settings::update)
+			.service(skipped::main)
+			.service(unknowns::main)
+			.service(unknowns::report)
+			.service(test_results::main)
+			.service(test_results::report)
+			.service(markers::main)
+			.service(markers::add)
+			.service(markers::clear)
+			.service(markers::undo)
+			.service(assets::add)
+			.service(assets::ids)
+			.service(stats::main)
+			.service(log::main)
+			.service(ws::main)
+			.service(events::main)
+			.service(ui::main)
+			.service(ui::file)
+	}
+
+	/// Read-only subset of the endpoints, mutating ones are not registered at all
+	pub fn mirror_scope(path: &str, core: Arc<Core>) -> Scope {
+		web::scope(path)
+			.app_data(Data::new(core))
+			.service(details::main)
+			.service(clients::main)
+			.service(snapshot::main)
+			.service(snapshot::page)
+			.service(find::main)
+			.service(complete::main)
+			.service(class_metadata::main)
+			.service(blob::main)
+			.service(home::main)
+			.service(health::main)
+			.service(pending::main)
+			.service(frozen::main)
+			.service(settings::main)
+			.service(skipped::main)
+			.service(unknowns::main)
+			.service(markers::main)
+			.service(stats::main)
+			.service(ws::main)
+			.service(events::main)
+			.service(ui::main)
+			.service(ui::file)
+	}
+
+	/// Requests for JSON, e.g. from the dashboard, are answered with
+	/// a not supported marker instead of being redirected to the home page
+	async fn default_redirect(request: HttpRequest) -> HttpResponse {
+		if ui::wants_json(&request) {
+			return ui::not_supported().await;
+		}
+
+		web::Redirect::to("/").respond_to(&request).map_into_boxed_body()
 	}
 }
 
@@ -2,24 +2,46 @@ use actix_msgpack::MsgPack;
 use actix_web::{post, web::Data, HttpResponse, Responder};
 use log::trace;
 use rbx_dom_weak::types::Ref;
-use serde::Deserialize;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, sync::Arc};
 
-use crate::core::Core;
+use crate::{core::Core, util::editor::OpenError};
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 struct Request {
+	#[serde(default = "Ref::none")]
 	instance: Ref,
-	_line: u32,
+	/// Stable ID, takes precedence over `instance`
+	id: Option<String>,
+	/// Instance path like `ReplicatedStorage.Module`, used when neither ID is known
+	path: Option<String>,
+	/// Line of the script, ignored for other files
+	line: Option<u32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+	file: PathBuf,
 }
 
 #[post("/open")]
 async fn main(request: MsgPack<Request>, core: Data<Arc<Core>>) -> impl Responder {
 	trace!("Received request: open");
 
-	match core.open(request.instance) {
-		Ok(_) => HttpResponse::Ok().body("Opened file successfully"),
-		Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+	let instance = core
+		.resolve(request.instance, request.id.as_deref())
+		.filter(|instance| instance.is_some())
+		.or_else(|| request.path.as_deref().and_then(|path| core.tree().find_by_path(path)));
+
+	let Some(instance) = instance else {
+		return HttpResponse::NotFound().json(OpenError::NoInstance);
+	};
+
+	match core.open(instance, request.line) {
+		Ok(file) => HttpResponse::Ok().json(Response { file }),
+		Err(err @ (OpenError::NoInstance | OpenError::NoSource)) => HttpResponse::NotFound().json(err),
+		Err(err) => HttpResponse::InternalServerError().json(err),
 	}
 }
@@ -0,0 +1,32 @@
+use actix_msgpack::MsgPack;
+use actix_web::{post, web::Data, HttpResponse, Responder};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::core::{patch::Patch, Core};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyPatchRequest {
+	/// Contents of the patch file, parsed by the server so values keep their JSON forms
+	pub patch: String,
+	pub dry_run: bool,
+	pub strict: bool,
+}
+
+/// Apply the patch to the live tree, responds with the report, see `Core::apply_patch`
+#[post("/apply-patch")]
+async fn main(request: MsgPack<ApplyPatchRequest>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: apply patch");
+
+	let patch: Patch = match serde_json::from_str(&request.patch) {
+		Ok(patch) => patch,
+		Err(err) => return HttpResponse::BadRequest().body(format!("Failed to parse patch: {}", err)),
+	};
+
+	match core.apply_patch(&patch, request.dry_run, request.strict) {
+		Ok(report) => HttpResponse::Ok().json(report),
+		Err(err) => HttpResponse::BadRequest().body(format!("{:#}", err)),
+	}
+}
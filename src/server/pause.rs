@@ -0,0 +1,76 @@
+use actix_msgpack::MsgPack;
+use actix_web::{http::StatusCode, post, web::Data, HttpResponse, Responder};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+	argon_info,
+	config::Config,
+	core::{processor::pause::PauseStatus, Core},
+	t,
+};
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PauseRequest {
+	/// Seconds after which syncing resumes on its own, 0 to never resume,
+	/// `pause_timeout` setting is used when not specified
+	pub timeout: Option<u64>,
+}
+
+/// Response to syncback submitted while paused, so clients can tell the user
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Paused {
+	/// Always `paused`
+	pub status: String,
+	#[serde(flatten)]
+	pub pause: PauseStatus,
+}
+
+/// Rejects the syncback if syncing is paused, it is not queued to be applied later
+/// as Studio would keep showing changes as synced while they are not
+pub fn reject(core: &Core) -> Option<HttpResponse> {
+	let pause = core.processor().pause_status();
+
+	if !pause.paused {
+		return None;
+	}
+
+	Some(HttpResponse::build(StatusCode::LOCKED).json(Paused {
+		status: String::from("paused"),
+		pause,
+	}))
+}
+
+#[post("/pause")]
+async fn main(request: Option<MsgPack<PauseRequest>>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: pause");
+
+	let timeout = match request.and_then(|request| request.timeout) {
+		Some(seconds) => seconds,
+		None => Config::new().pause_timeout * 60,
+	};
+
+	let timeout = (timeout > 0).then(|| Duration::from_secs(timeout));
+
+	if core.processor().pause(timeout) {
+		argon_info!("{}", t!("pause.paused"));
+	}
+
+	HttpResponse::Ok().json(core.processor().pause_status())
+}
+
+#[post("/resume")]
+async fn resume(core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: resume");
+
+	let resumed = core.processor().resume();
+
+	if resumed {
+		argon_info!("{}", t!("pause.resumed"));
+	}
+
+	HttpResponse::Ok().json(resumed)
+}
@@ -0,0 +1,11 @@
+use actix_web::{get, web::Data, HttpResponse, Responder};
+use log::trace;
+use std::sync::Arc;
+
+use crate::core::Core;
+
+#[get("/pending")]
+async fn main(core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: pending");
+	HttpResponse::Ok().json(core.pending().list())
+}
@@ -0,0 +1,21 @@
+use actix_msgpack::{MsgPack, MsgPackResponseBuilder};
+use actix_web::{post, web::Data, HttpResponse, Responder};
+use log::trace;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::core::{deferred::PropertyRequest, Core};
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+	properties: Vec<PropertyRequest>,
+}
+
+/// Values of properties deferred during the initial sync, returned in the
+/// requested order, `value` is empty for unknown instances and properties
+#[post("/properties")]
+async fn main(request: MsgPack<Request>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: properties");
+	HttpResponse::Ok().msgpack(core.read_properties(&request.properties))
+}
@@ -0,0 +1,167 @@
+use anyhow::{bail, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use super::limits::Limits;
+use crate::{
+	argon_warn,
+	constants::{MIN_PROTOCOL_VERSION, PROTOCOL_VERSION},
+	core::{checkpoint::Resume, sync_direction::SyncPlan},
+	t,
+};
+
+/// Optional protocol features, clients list the ones they
+/// support during handshake and only the common ones are used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Capability {
+	/// Large values are sent as references fetched from `/blob/<hash>`
+	Blobs,
+	/// Instances changed while disconnected are resynced on reconnect
+	Reconnect,
+	/// Heavy values are left out of the initial sync as `deferred`
+	/// placeholders and requested from `/properties` when needed
+	LazyProperties,
+	/// Queues are checkpointed so the client can continue
+	/// from its cursor after the server restarts
+	Resume,
+	/// Periodic hashes of the tree, divergent subtrees
+	/// are narrowed down with `/heartbeat` and resynced
+	Heartbeat,
+	/// Changes over `maxPayloadSize` are sent to `/upload` in
+	/// chunks of `maxRequestSize` and processed on `/commit`
+	ChunkedUpload,
+	/// Syncback operations targeting read-only sources
+	/// are reported back with `SyncbackRejected`
+	Rejections,
+	/// Client summarizes its tree during handshake so the initial sync goes
+	/// from the side that changed since the last session, see `sync`
+	SyncDirection,
+	/// Per-property outcomes of applied syncback operations
+	/// are reported back with `SyncbackReport`
+	PropertyReports,
+	/// Clients are told with `PauseChanged` when syncing is paused or resumed
+	Pause,
+	/// Capability added in a newer protocol version
+	#[serde(other)]
+	Unknown,
+}
+
+/// Capabilities supported by this version of the server
+pub const CAPABILITIES: [Capability; 10] = [
+	Capability::Blobs,
+	Capability::Reconnect,
+	Capability::LazyProperties,
+	Capability::Resume,
+	Capability::Heartbeat,
+	Capability::ChunkedUpload,
+	Capability::Rejections,
+	Capability::SyncDirection,
+	Capability::PropertyReports,
+	Capability::Pause,
+];
+
+/// Protocol version and capabilities agreed on with a client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Negotiated {
+	pub protocol: u32,
+	pub capabilities: Vec<Capability>,
+}
+
+impl Negotiated {
+	/// Negotiates with the client, ones that don't report protocol
+	/// version are assumed to speak the oldest supported one
+	pub fn new(name: &str, protocol: Option<u32>, capabilities: &[Capability]) -> Result<Self> {
+		let protocol = protocol.unwrap_or(MIN_PROTOCOL_VERSION);
+
+		if protocol < MIN_PROTOCOL_VERSION {
+			bail!(
+				"{}",
+				t!(
+					"protocol.outdated_client",
+					name = name.bold(),
+					protocol = protocol.to_string().bold(),
+					minimum = MIN_PROTOCOL_VERSION.to_string().bold()
+				)
+			);
+		}
+
+		if protocol > PROTOCOL_VERSION {
+			argon_warn!(
+				"{}",
+				t!(
+					"protocol.outdated_server",
+					name = name.bold(),
+					protocol = protocol.to_string().bold(),
+					supported = PROTOCOL_VERSION.to_string().bold(),
+					command = "argon update".bold()
+				)
+			);
+		}
+
+		let mut capabilities: Vec<Capability> = capabilities
+			.iter()
+			.filter(|capability| CAPABILITIES.contains(capability))
+			.copied()
+			.collect();
+
+		capabilities.sort();
+		capabilities.dedup();
+
+		Ok(Self {
+			protocol: protocol.min(PROTOCOL_VERSION),
+			capabilities,
+		})
+	}
+
+	pub fn supports(&self, capability: Capability) -> bool {
+		self.capabilities.contains(&capability)
+	}
+}
+
+impl Default for Negotiated {
+	fn default() -> Self {
+		Self {
+			protocol: MIN_PROTOCOL_VERSION,
+			capabilities: vec![],
+		}
+	}
+}
+
+/// Sent back to clients that reported their protocol version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Handshake {
+	pub version: String,
+	pub protocol: u32,
+	pub capabilities: Vec<Capability>,
+	/// Request limits the client should stay within
+	pub limits: Limits,
+	/// Generation of the server queues, clients resuming
+	/// after a restart report it back with their cursor
+	#[serde(default)]
+	pub generation: String,
+	/// Whether the client can continue from its cursor, only
+	/// set for clients that asked to resume
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub resume: Option<Resume>,
+	/// Direction of the initial sync, only set for clients
+	/// that sent a summary of their tree
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub sync: Option<SyncPlan>,
+}
+
+impl Handshake {
+	pub fn new(negotiated: &Negotiated, limits: &Limits) -> Self {
+		Self {
+			version: env!("CARGO_PKG_VERSION").to_owned(),
+			protocol: negotiated.protocol,
+			capabilities: negotiated.capabilities.clone(),
+			limits: limits.clone(),
+			generation: String::new(),
+			resume: None,
+			sync: None,
+		}
+	}
+}
@@ -0,0 +1,52 @@
+use actix_msgpack::MsgPack;
+use actix_web::{get, patch, web::Data, HttpResponse, Responder};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::{core::Core, settings};
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+	client_id: u32,
+	settings: BTreeMap<String, Value>,
+	/// Whether changes are saved to the config file or only last for this session
+	#[serde(default)]
+	persist: bool,
+}
+
+/// Error messages keyed by settings that were rejected
+#[derive(Serialize, Debug)]
+struct Rejected {
+	errors: BTreeMap<String, String>,
+}
+
+/// Effective settings of the server and the project
+#[get("/settings")]
+async fn main(core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: settings");
+	HttpResponse::Ok().json(settings::list(&core.project()))
+}
+
+/// Changes runtime settings, nothing is applied if any of them is invalid
+#[patch("/settings")]
+async fn update(request: MsgPack<Request>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: update settings");
+
+	if !core.queue().is_subscribed(request.client_id) {
+		return HttpResponse::Unauthorized().body("Not subscribed");
+	}
+
+	let errors = settings::validate(&request.settings, request.persist);
+
+	if !errors.is_empty() {
+		return HttpResponse::BadRequest().json(Rejected { errors });
+	}
+
+	match settings::update(&request.settings, request.persist) {
+		Ok(()) => HttpResponse::Ok().json(settings::list(&core.project())),
+		Err(err) => HttpResponse::InternalServerError().body(format!("Failed to update settings: {}", err)),
+	}
+}
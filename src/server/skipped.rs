@@ -0,0 +1,16 @@
+use actix_web::{
+	get,
+	web::{Data, Query},
+	HttpResponse, Responder,
+};
+use log::trace;
+use std::sync::Arc;
+
+use crate::core::{skipped::SkippedQuery, Core};
+
+/// Syncback operations skipped by the filter, the most recent first
+#[get("/skipped")]
+async fn main(query: Query<SkippedQuery>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: skipped");
+	HttpResponse::Ok().json(core.skipped(&query))
+}
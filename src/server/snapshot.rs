@@ -1,20 +1,90 @@
 use actix_msgpack::{MsgPack, MsgPackResponseBuilder};
-use actix_web::{post, web::Data, HttpResponse, Responder};
+use actix_web::{
+	get, post,
+	web::{Data, Query},
+	HttpRequest, HttpResponse, Responder,
+};
 use log::trace;
 use rbx_dom_weak::types::Ref;
 use serde::Deserialize;
 use std::sync::Arc;
 
-use crate::core::Core;
+use super::ui;
+use crate::{
+	class_meta,
+	constants::{MAX_SNAPSHOT_PAGE_LIMIT, SNAPSHOT_PAGE_LIMIT},
+	core::{subscription::SubscriptionFilter, Core},
+};
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 struct Request {
+	#[serde(default = "Ref::none")]
 	instance: Ref,
+	/// Stable ID, takes precedence over `instance`
+	id: Option<String>,
+	#[serde(default)]
+	include_ephemeral: bool,
+	/// Prunes the snapshot to the part of the tree a filtered client
+	/// receives, the response is a list of snapshots then
+	#[serde(default)]
+	filter: Option<SubscriptionFilter>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PageRequest {
+	cursor: Option<String>,
+	limit: Option<usize>,
+	#[serde(default)]
+	include_ephemeral: bool,
+	/// `class-meta` attaches class metadata of the page instances
+	enrich: Option<String>,
+	/// Comma separated paths of subtrees, see `SubscriptionFilter`
+	paths: Option<String>,
+	/// Comma separated classes, see `SubscriptionFilter`
+	classes: Option<String>,
 }
 
 #[post("/snapshot")]
 async fn main(request: MsgPack<Request>, core: Data<Arc<Core>>) -> impl Responder {
 	trace!("Received request: snapshot");
-	HttpResponse::Ok().msgpack(core.snapshot(request.instance))
+
+	let Some(instance) = core.resolve(request.instance, request.id.as_deref()) else {
+		return HttpResponse::NotFound().body("No instance with such ID");
+	};
+
+	match &request.filter {
+		Some(filter) => HttpResponse::Ok().msgpack(core.snapshot_filtered(instance, request.include_ephemeral, filter)),
+		None => HttpResponse::Ok().msgpack(core.snapshot(instance, request.include_ephemeral)),
+	}
+}
+
+/// Responds with JSON instead of MessagePack if asked to, e.g. by the dashboard
+#[get("/snapshot")]
+async fn page(http: HttpRequest, request: Query<PageRequest>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: snapshot page");
+
+	let limit = request
+		.limit
+		.unwrap_or(SNAPSHOT_PAGE_LIMIT)
+		.clamp(1, MAX_SNAPSHOT_PAGE_LIMIT);
+
+	let filter = SubscriptionFilter::parse(request.paths.as_deref(), request.classes.as_deref());
+	let filter = (!filter.is_empty()).then_some(&filter);
+
+	match core.snapshot_page(request.cursor.as_deref(), limit, request.include_ephemeral, filter) {
+		Ok(mut page) => {
+			if request.enrich.as_deref() == Some(class_meta::ENRICH) {
+				page.class_meta = Some(core.instance_metadata(page.instances.iter().map(|instance| instance.id)));
+			}
+
+			if ui::wants_json(&http) {
+				HttpResponse::Ok().json(page)
+			} else {
+				HttpResponse::Ok().msgpack(page)
+			}
+		}
+		Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+	}
 }
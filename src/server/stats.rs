@@ -0,0 +1,34 @@
+use actix_web::{get, web::Data, HttpResponse, Responder};
+use log::trace;
+use serde::Serialize;
+use std::sync::Arc;
+
+use super::limits::{LimitStats, Limiter};
+use crate::{
+	core::{heartbeat::HeartbeatStats, integrity::IntegrityStats, skipped::SkippedStats, Core},
+	vfs::verify::{self, VerifyStats},
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Stats {
+	#[serde(flatten)]
+	limits: LimitStats,
+	heartbeat: HeartbeatStats,
+	integrity: IntegrityStats,
+	skipped: SkippedStats,
+	verify: VerifyStats,
+}
+
+#[get("/stats")]
+async fn main(limiter: Data<Arc<Limiter>>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: stats");
+
+	HttpResponse::Ok().json(Stats {
+		limits: limiter.stats(),
+		heartbeat: core.heartbeat_stats(),
+		integrity: core.integrity_stats(),
+		skipped: core.skipped_stats(),
+		verify: verify::stats(),
+	})
+}
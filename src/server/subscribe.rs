@@ -1,27 +1,208 @@
-use actix_msgpack::MsgPack;
+use actix_msgpack::{MsgPack, MsgPackResponseBuilder};
 use actix_web::{post, web::Data, HttpResponse, Responder};
-use log::trace;
+use colored::Colorize;
+use log::{debug, trace, warn};
 use serde::Deserialize;
 use std::sync::Arc;
 
-use crate::core::Core;
+use crate::{
+	argon_warn,
+	core::{
+		checkpoint::{QueueCursor, Resume},
+		events::{EventKind, SessionState},
+		lifecycle::CoreEvent,
+		queue::PlaceDetails,
+		subscription::SubscriptionFilter,
+		sync_direction::TreeSummary,
+		Core,
+	},
+	server::{
+		limits::Limiter,
+		protocol::{Capability, Handshake, Negotiated},
+		PauseChanged,
+	},
+};
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 struct Request {
 	client_id: u32,
 	name: String,
+	#[serde(default)]
+	game_id: Option<u64>,
+	#[serde(default)]
+	place_id: Option<u64>,
+	/// Protocol version of the client, not reported by legacy clients
+	#[serde(default)]
+	protocol: Option<u32>,
+	/// Optional protocol features supported by the client
+	#[serde(default)]
+	capabilities: Vec<Capability>,
+	/// Whether the client can fetch large values from `/blob/<hash>`,
+	/// legacy alternative of the `blobs` capability
+	#[serde(default)]
+	blobs: bool,
+	/// Whether the client still has the tree from its previous session
+	/// and only needs instances that changed since it disconnected
+	#[serde(default)]
+	reconnect: bool,
+	/// Position of the client in its queue of the previous session,
+	/// used to continue from it after the server restarted
+	#[serde(default)]
+	resume: Option<QueueCursor>,
+	/// Summary of the tree in Studio, used to decide direction of the initial sync
+	#[serde(default)]
+	summary: Option<TreeSummary>,
+	/// Part of the tree the client wants to receive, the whole tree if not specified
+	#[serde(default)]
+	filter: Option<SubscriptionFilter>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct FilterRequest {
+	client_id: u32,
+	/// `None` subscribes the client to the whole tree again
+	#[serde(default)]
+	filter: Option<SubscriptionFilter>,
 }
 
 #[post("/subscribe")]
-async fn main(request: MsgPack<Request>, core: Data<Arc<Core>>) -> impl Responder {
+async fn main(request: MsgPack<Request>, core: Data<Arc<Core>>, limiter: Data<Arc<Limiter>>) -> impl Responder {
 	trace!("Received request: subscribe");
 
-	let subscribed = core.queue().subscribe(request.client_id, &request.name);
+	{
+		let project = core.project();
+
+		if let Err(err) = project.check_place(request.game_id, request.place_id) {
+			if project.is_strict_place() {
+				return HttpResponse::Forbidden().body(err.to_string());
+			}
+
+			argon_warn!("{}", err);
+		} else if project.is_place_unknown(request.game_id, request.place_id) {
+			argon_warn!(
+				"Client {} did not report its game or place ID, it can't be checked against the project",
+				request.name.bold()
+			);
+		}
+	}
+
+	let mut capabilities = request.capabilities.clone();
+
+	if request.blobs {
+		capabilities.push(Capability::Blobs);
+	}
+
+	if request.reconnect && request.protocol.is_none() {
+		capabilities.push(Capability::Reconnect);
+	}
+
+	let negotiated = match Negotiated::new(&request.name, request.protocol, &capabilities) {
+		Ok(negotiated) => negotiated,
+		Err(err) => {
+			argon_warn!("{}", err);
+			return HttpResponse::UpgradeRequired().body(err.to_string());
+		}
+	};
+
+	let place = PlaceDetails {
+		game_id: request.game_id,
+		place_id: request.place_id,
+	};
+
+	let subscribed = core
+		.queue()
+		.subscribe(request.client_id, &request.name, place, negotiated.clone());
 
 	if subscribed.is_ok() {
-		HttpResponse::Ok().body("Subscribed successfully")
+		if request.filter.is_some() {
+			if let Err(err) = core
+				.queue()
+				.set_filter(request.client_id, request.filter.clone(), &core.tree())
+			{
+				warn!("Failed to set subscription filter: {}", err);
+			}
+		}
+
+		core.events().publish(EventKind::Session {
+			client_id: request.client_id,
+			name: Some(request.name.clone()),
+			state: SessionState::Connected,
+		});
+
+		core.emit(CoreEvent::ClientConnected {
+			client_id: request.client_id,
+			name: request.name.clone(),
+		});
+
+		// Clients connecting while paused would not know about it otherwise
+		let pause = core.processor().pause_status();
+
+		if pause.paused && negotiated.supports(Capability::Pause) {
+			if let Err(err) = core.queue().push(PauseChanged(pause), Some(request.client_id)) {
+				warn!("Failed to send pause status: {}", err);
+			}
+		}
+
+		let resume = match &request.resume {
+			Some(cursor) if negotiated.supports(Capability::Resume) => match core.resume(request.client_id, cursor) {
+				Ok(resume) => Some(resume),
+				Err(err) => {
+					warn!("Failed to resume queue of client {}: {}", request.client_id, err);
+					Some(Resume::Resync)
+				}
+			},
+			_ => None,
+		};
+
+		let resumed = resume == Some(Resume::Continue);
+
+		if (request.reconnect && negotiated.supports(Capability::Reconnect)) || resumed {
+			match core.resync_pending(request.client_id) {
+				Ok(count) => debug!("Resynced {} pending instances", count),
+				Err(err) => warn!("Failed to resync pending instances: {}", err),
+			}
+		}
+
+		// Clients that keep their tree don't sync it from scratch
+		let sync = match &request.summary {
+			Some(summary) if negotiated.supports(Capability::SyncDirection) && !request.reconnect && !resumed => {
+				Some(core.plan_sync(request.client_id, summary))
+			}
+			_ => None,
+		};
+
+		// Legacy clients expect plain text response
+		if request.protocol.is_some() {
+			let mut handshake = Handshake::new(&negotiated, limiter.limits());
+
+			handshake.generation = core.queue_generation().to_owned();
+			handshake.resume = resume;
+			handshake.sync = sync;
+
+			HttpResponse::Ok().msgpack(handshake)
+		} else {
+			HttpResponse::Ok().body("Subscribed successfully")
+		}
 	} else {
 		HttpResponse::BadRequest().body("Already subscribed")
 	}
 }
+
+/// Changes the part of the tree the client receives, instances that entered
+/// the scope are sent as additions and the ones that left it as removals,
+/// so the client does not have to resync after changing the filter
+#[post("/subscribe/filter")]
+async fn filter(request: MsgPack<FilterRequest>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: subscribe filter");
+
+	if !core.queue().is_subscribed(request.client_id) {
+		return HttpResponse::Unauthorized().body("Not subscribed");
+	}
+
+	match core.change_filter(request.client_id, request.filter.clone()) {
+		Ok(changes) => HttpResponse::Ok().msgpack(changes),
+		Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+	}
+}
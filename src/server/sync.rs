@@ -0,0 +1,111 @@
+use actix_msgpack::{MsgPack, MsgPackResponseBuilder};
+use actix_web::{
+	post,
+	web::{self, Data},
+	HttpResponse, Responder,
+};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::core::{
+	frozen::SyncDirection,
+	sync_direction::{StudioSubtree, SyncPlan},
+	Core,
+};
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+	client_id: u32,
+	/// Continue from the last acknowledged chunk instead of starting over
+	#[serde(default)]
+	resume: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct AckRequest {
+	client_id: u32,
+	chunk: usize,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ResolveRequest {
+	client_id: u32,
+	direction: SyncDirection,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct StudioRequest {
+	client_id: u32,
+	subtrees: Vec<StudioSubtree>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct StudioResponse {
+	/// Number of subtrees written to disk
+	written: usize,
+}
+
+#[post("/sync")]
+async fn main(request: MsgPack<Request>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: sync");
+
+	if !core.queue().is_subscribed(request.client_id) {
+		return HttpResponse::Unauthorized().body("Not subscribed");
+	}
+
+	match core.start_sync(request.client_id, request.resume) {
+		Ok(progress) => HttpResponse::Ok().msgpack(progress),
+		Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+	}
+}
+
+#[post("/sync/ack")]
+async fn ack(request: MsgPack<AckRequest>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: sync ack");
+
+	match core.ack_sync(request.client_id, request.chunk) {
+		Ok(()) => HttpResponse::Ok().body("Chunk acknowledged"),
+		Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+	}
+}
+
+/// Chooses direction of the initial sync when both sides changed since the last session
+#[post("/sync/resolve")]
+async fn resolve(request: MsgPack<ResolveRequest>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: sync resolve");
+
+	if !core.queue().is_subscribed(request.client_id) {
+		return HttpResponse::Unauthorized().body("Not subscribed");
+	}
+
+	match core.resolve_sync(request.client_id, request.direction) {
+		Ok(plan) => HttpResponse::Ok().msgpack::<SyncPlan>(plan),
+		Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+	}
+}
+
+/// Writes divergent subtrees from Studio to disk before the initial sync
+#[post("/sync/studio")]
+async fn studio(request: MsgPack<StudioRequest>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: sync studio");
+
+	if !core.queue().is_subscribed(request.client_id) {
+		return HttpResponse::Unauthorized().body("Not subscribed");
+	}
+
+	let request = request.0;
+	let core = core.into_inner();
+
+	// Waits until the subtrees are written so it can't block the server
+	match web::block(move || core.sync_from_studio(request.client_id, request.subtrees)).await {
+		Ok(Ok(written)) => HttpResponse::Ok().msgpack(StudioResponse { written }),
+		Ok(Err(err)) => HttpResponse::BadRequest().body(err.to_string()),
+		Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+	}
+}
@@ -0,0 +1,32 @@
+use actix_web::{
+	get, post,
+	web::{Data, Json, Path},
+	HttpResponse, Responder,
+};
+use log::trace;
+use std::sync::Arc;
+
+use crate::core::{test_runs::TestReport, Core};
+
+/// Results collected so far for the `argon test` run
+#[get("/test-results/{id}")]
+async fn main(id: Path<String>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: test results");
+
+	match core.test_run(&id) {
+		Some(run) => HttpResponse::Ok().json(run),
+		None => HttpResponse::NotFound().body("Test run not found"),
+	}
+}
+
+/// Results reported by the test bootstrap, which
+/// runs in Studio so it sends JSON instead of MessagePack
+#[post("/test-results")]
+async fn report(request: Json<TestReport>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: report test results");
+
+	match core.report_test_results(request.into_inner()) {
+		Ok(()) => HttpResponse::Ok().body("Reported successfully"),
+		Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+	}
+}
@@ -0,0 +1,125 @@
+use actix_web::{
+	get,
+	http::header::{self, ContentType},
+	mime,
+	web::Path,
+	HttpRequest, HttpResponse, Responder,
+};
+use log::trace;
+use serde::Serialize;
+
+use crate::config::Config;
+
+/// Name, contents and ETag of every dashboard file, packaged by the build script
+const FILES: &[(&str, &[u8], &str)] = include!(concat!(env!("OUT_DIR"), "/ui/files.rs"));
+
+/// Response to requests the server has no endpoint for, so the dashboard
+/// of a newer version can tell the user instead of failing the whole panel
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NotSupported {
+	/// Always `false`
+	pub supported: bool,
+	pub message: String,
+}
+
+/// Whether the client asked for JSON instead of the default response format
+pub fn wants_json(request: &HttpRequest) -> bool {
+	request
+		.headers()
+		.get(header::ACCEPT)
+		.and_then(|value| value.to_str().ok())
+		.is_some_and(|value| value.contains(mime::APPLICATION_JSON.essence_str()))
+}
+
+pub async fn not_supported() -> HttpResponse {
+	HttpResponse::NotFound().json(NotSupported {
+		supported: false,
+		message: String::from("Not supported by this server"),
+	})
+}
+
+/// Responds to every dashboard request with 404 if it is disabled
+fn disabled() -> Option<HttpResponse> {
+	if Config::new().dashboard {
+		return None;
+	}
+
+	Some(HttpResponse::NotFound().body("Dashboard is disabled, see `dashboard` setting"))
+}
+
+fn content_type(name: &str) -> ContentType {
+	match name.rsplit_once('.').map(|(_, ext)| ext) {
+		Some("html") => ContentType::html(),
+		Some("js") => ContentType(mime::APPLICATION_JAVASCRIPT_UTF_8),
+		Some("css") => ContentType(mime::TEXT_CSS_UTF_8),
+		Some("json") => ContentType::json(),
+		Some("svg") => ContentType(mime::IMAGE_SVG),
+		Some("png") => ContentType::png(),
+		_ => ContentType::octet_stream(),
+	}
+}
+
+/// Files are not fingerprinted, so browsers revalidate them on every load
+/// and only download them again once the server was updated
+fn serve(request: &HttpRequest, name: &str) -> HttpResponse {
+	if let Some(response) = disabled() {
+		return response;
+	}
+
+	let Some((_, contents, etag)) = FILES.iter().find(|(file_name, _, _)| *file_name == name) else {
+		return HttpResponse::NotFound().body("No such dashboard file");
+	};
+
+	let cached = request
+		.headers()
+		.get(header::IF_NONE_MATCH)
+		.and_then(|value| value.to_str().ok())
+		.is_some_and(|value| value.split(',').any(|tag| tag.trim() == *etag));
+
+	let mut response = if cached {
+		HttpResponse::NotModified()
+	} else {
+		HttpResponse::Ok()
+	};
+
+	response
+		.insert_header((header::CACHE_CONTROL, "no-cache"))
+		.insert_header((header::ETAG, *etag));
+
+	if cached {
+		return response.finish();
+	}
+
+	response.content_type(content_type(name)).body(*contents)
+}
+
+/// Relative links of the dashboard only work with the trailing slash
+#[get("/ui")]
+async fn main(request: HttpRequest) -> impl Responder {
+	trace!("Received request: ui");
+
+	if let Some(response) = disabled() {
+		return response;
+	}
+
+	// Relative, so it works for workspace members served under their names too
+	let location = match request.query_string() {
+		"" => String::from("ui/"),
+		query => format!("ui/?{}", query),
+	};
+
+	HttpResponse::SeeOther()
+		.insert_header((header::LOCATION, location))
+		.finish()
+}
+
+#[get("/ui/{file:.*}")]
+async fn file(request: HttpRequest, file: Path<String>) -> impl Responder {
+	trace!("Received request: ui {}", file);
+
+	match file.as_str() {
+		"" => serve(&request, "index.html"),
+		file => serve(&request, file),
+	}
+}
@@ -0,0 +1,36 @@
+use actix_msgpack::MsgPack;
+use actix_web::{get, post, web::Data, HttpResponse, Responder};
+use log::trace;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::core::{unknowns::UnknownsGroup, Core};
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+	client_id: u32,
+	unknowns: Vec<UnknownsGroup>,
+}
+
+/// Unknown instances preserved by clients, grouped by parent
+#[get("/unknowns")]
+async fn main(core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: unknowns");
+	HttpResponse::Ok().json(core.unknowns())
+}
+
+/// Latest summary of unknown instances the client preserves
+#[post("/unknowns")]
+async fn report(request: MsgPack<Request>, core: Data<Arc<Core>>) -> impl Responder {
+	trace!("Received request: report unknowns");
+
+	if !core.queue().is_subscribed(request.client_id) {
+		return HttpResponse::Unauthorized().body("Not subscribed");
+	}
+
+	let request = request.0;
+	core.report_unknowns(request.client_id, request.unknowns);
+
+	HttpResponse::Ok().body("Reported successfully")
+}
@@ -3,15 +3,32 @@ use actix_web::{post, web::Data, HttpResponse, Responder};
 use log::trace;
 use std::sync::Arc;
 
-use crate::{core::Core, server::AuthRequest};
+use crate::{
+	core::{
+		events::{EventKind, SessionState},
+		lifecycle::CoreEvent,
+		Core,
+	},
+	server::AuthRequest,
+};
 
 #[post("/unsubscribe")]
 async fn main(request: MsgPack<AuthRequest>, core: Data<Arc<Core>>) -> impl Responder {
 	trace!("Received request: unsubscribe");
 
-	let unsubscribed = core.queue().unsubscribe(request.client_id);
+	let unsubscribed = core.unsubscribe(request.client_id);
 
 	if unsubscribed.is_ok() {
+		core.events().publish(EventKind::Session {
+			client_id: request.client_id,
+			name: None,
+			state: SessionState::Disconnected,
+		});
+
+		core.emit(CoreEvent::ClientDisconnected {
+			client_id: request.client_id,
+		});
+
 		HttpResponse::Ok().body("Unsubscribed successfully")
 	} else {
 		HttpResponse::BadRequest().body("Not subscribed")
@@ -0,0 +1,265 @@
+use actix_msgpack::MsgPack;
+use actix_web::{post, web::Data, HttpResponse, Responder};
+use log::{trace, warn};
+use serde::Deserialize;
+use serde_bytes::ByteBuf;
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt::{self, Display, Formatter},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use super::{limits::PayloadTooLarge, pause};
+use crate::{
+	core::{processor::WriteRequest, Core},
+	lock,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadError {
+	/// Chunks of all unfinished uploads would exceed `max_upload_size`
+	TooLarge {
+		limit: usize,
+		size: usize,
+	},
+	/// Upload was never started, already committed or expired
+	Unknown,
+	/// Chunk index is out of range or the total differs from the previous chunks
+	InvalidChunk,
+	Incomplete {
+		missing: usize,
+	},
+}
+
+impl Display for UploadError {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			UploadError::TooLarge { limit, size } => {
+				write!(f, "Upload of {} bytes exceeds the limit of {} bytes", size, limit)
+			}
+			UploadError::Unknown => write!(f, "Upload does not exist or has expired"),
+			UploadError::InvalidChunk => write!(f, "Chunk does not match the upload"),
+			UploadError::Incomplete { missing } => write!(f, "Upload is missing {} chunks", missing),
+		}
+	}
+}
+
+#[derive(Debug)]
+struct Transfer {
+	chunks: BTreeMap<usize, Vec<u8>>,
+	total: usize,
+	updated: Instant,
+}
+
+impl Transfer {
+	fn size(&self) -> usize {
+		self.chunks.values().map(Vec::len).sum()
+	}
+}
+
+/// Syncback changes too large for a single `/write` request, uploaded in
+/// chunks and reassembled on commit. Chunks are kept in memory up to
+/// `max_size` for all uploads and ones not touched for `timeout` are dropped
+#[derive(Debug)]
+pub struct Uploads {
+	max_size: usize,
+	timeout: Duration,
+	transfers: Mutex<HashMap<(u32, String), Transfer>>,
+}
+
+impl Uploads {
+	pub fn new(max_size: usize, timeout: Duration) -> Self {
+		Self {
+			max_size,
+			timeout,
+			transfers: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Stores the chunk, sending the same chunk again replaces it
+	pub fn add(
+		&self,
+		client_id: u32,
+		transfer_id: &str,
+		index: usize,
+		total: usize,
+		data: Vec<u8>,
+	) -> Result<(), UploadError> {
+		self.expire();
+
+		if index >= total {
+			return Err(UploadError::InvalidChunk);
+		}
+
+		let mut transfers = lock!(self.transfers);
+		let key = (client_id, transfer_id.to_owned());
+
+		let previous = transfers
+			.get(&key)
+			.and_then(|transfer| transfer.chunks.get(&index))
+			.map_or(0, Vec::len);
+		let size = transfers.values().map(Transfer::size).sum::<usize>() - previous + data.len();
+
+		if size > self.max_size {
+			return Err(UploadError::TooLarge {
+				limit: self.max_size,
+				size,
+			});
+		}
+
+		let transfer = transfers.entry(key).or_insert_with(|| Transfer {
+			chunks: BTreeMap::new(),
+			total,
+			updated: Instant::now(),
+		});
+
+		if transfer.total != total {
+			return Err(UploadError::InvalidChunk);
+		}
+
+		transfer.chunks.insert(index, data);
+		transfer.updated = Instant::now();
+
+		Ok(())
+	}
+
+	/// Removes the upload and returns its reassembled contents,
+	/// incomplete uploads are kept so missing chunks can be sent
+	pub fn commit(&self, client_id: u32, transfer_id: &str) -> Result<Vec<u8>, UploadError> {
+		self.expire();
+
+		let mut transfers = lock!(self.transfers);
+		let key = (client_id, transfer_id.to_owned());
+
+		let transfer = transfers.get(&key).ok_or(UploadError::Unknown)?;
+
+		if transfer.chunks.len() < transfer.total {
+			return Err(UploadError::Incomplete {
+				missing: transfer.total - transfer.chunks.len(),
+			});
+		}
+
+		let transfer = transfers.remove(&key).unwrap();
+
+		Ok(transfer.chunks.into_values().flatten().collect())
+	}
+
+	/// Drops uploads abandoned by their clients, returns how many were dropped
+	pub fn expire(&self) -> usize {
+		let mut transfers = lock!(self.transfers);
+		let count = transfers.len();
+
+		transfers.retain(|(client_id, transfer_id), transfer| {
+			let expired = transfer.updated.elapsed() > self.timeout;
+
+			if expired {
+				warn!(
+					"Upload {} of client {} expired with {} of {} chunks",
+					transfer_id,
+					client_id,
+					transfer.chunks.len(),
+					transfer.total
+				);
+			}
+
+			!expired
+		});
+
+		count - transfers.len()
+	}
+
+	/// Number of unfinished uploads
+	pub fn len(&self) -> usize {
+		lock!(self.transfers).len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct UploadRequest {
+	client_id: u32,
+	transfer_id: String,
+	index: usize,
+	total: usize,
+	data: ByteBuf,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CommitRequest {
+	client_id: u32,
+	transfer_id: String,
+}
+
+fn error_response(endpoint: &str, err: UploadError) -> HttpResponse {
+	match err {
+		UploadError::TooLarge { limit, size } => PayloadTooLarge {
+			endpoint: endpoint.to_owned(),
+			limit,
+			size: Some(size),
+		}
+		.response(),
+		UploadError::Unknown => HttpResponse::NotFound().body(err.to_string()),
+		UploadError::InvalidChunk | UploadError::Incomplete { .. } => HttpResponse::BadRequest().body(err.to_string()),
+	}
+}
+
+#[post("/upload")]
+async fn main(request: MsgPack<UploadRequest>, core: Data<Arc<Core>>, uploads: Data<Arc<Uploads>>) -> impl Responder {
+	trace!("Received request: upload");
+
+	let request = request.0;
+
+	if !core.queue().is_subscribed(request.client_id) {
+		return HttpResponse::Unauthorized().body("Not subscribed");
+	}
+
+	match uploads.add(
+		request.client_id,
+		&request.transfer_id,
+		request.index,
+		request.total,
+		request.data.into_vec(),
+	) {
+		Ok(()) => HttpResponse::Ok().body("Chunk uploaded successfully"),
+		Err(err) => error_response("/upload", err),
+	}
+}
+
+/// Processes the reassembled upload the same way as `/write`
+#[post("/commit")]
+async fn commit(request: MsgPack<CommitRequest>, core: Data<Arc<Core>>, uploads: Data<Arc<Uploads>>) -> impl Responder {
+	trace!("Received request: commit");
+
+	if !core.queue().is_subscribed(request.client_id) {
+		return HttpResponse::Unauthorized().body("Not subscribed");
+	}
+
+	// Chunks are kept, so the upload can be committed again once resumed
+	if let Some(response) = pause::reject(&core) {
+		return response;
+	}
+
+	let contents = match uploads.commit(request.client_id, &request.transfer_id) {
+		Ok(contents) => contents,
+		Err(err) => return error_response("/commit", err),
+	};
+
+	let write: WriteRequest = match rmp_serde::from_slice(&contents) {
+		Ok(write) => write,
+		Err(err) => return HttpResponse::BadRequest().body(format!("Failed to decode upload: {}", err)),
+	};
+
+	if write.client_id != request.client_id {
+		return HttpResponse::BadRequest().body("Upload belongs to a different client");
+	}
+
+	core.processor().write(write);
+
+	HttpResponse::Ok().body("Written changes successfully")
+}
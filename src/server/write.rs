@@ -3,6 +3,7 @@ use actix_web::{post, web::Data, HttpResponse, Responder};
 use log::trace;
 use std::sync::Arc;
 
+use super::pause;
 use crate::core::{processor::WriteRequest, Core};
 
 #[post("/write")]
@@ -15,6 +16,10 @@ async fn main(request: MsgPack<WriteRequest>, core: Data<Arc<Core>>) -> impl Res
 		return HttpResponse::Unauthorized().body("Not subscribed");
 	}
 
+	if let Some(response) = pause::reject(&core) {
+		return response;
+	}
+
 	core.processor().write(request);
 
 	HttpResponse::Ok().body("Written changes successfully")
@@ -0,0 +1,106 @@
+use actix_web::{
+	get, rt,
+	web::{Data, Payload, Query},
+	HttpRequest, HttpResponse,
+};
+use actix_ws::{AggregatedMessage, Session};
+use futures::StreamExt;
+use log::{trace, warn};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::core::{
+	events::{Event, EventKind},
+	Core,
+};
+
+#[derive(Deserialize, Debug)]
+struct Request {
+	/// Last sequence number the client has seen
+	since: Option<u64>,
+}
+
+#[get("/ws")]
+async fn main(
+	request: HttpRequest,
+	query: Query<Request>,
+	body: Payload,
+	core: Data<Arc<Core>>,
+) -> actix_web::Result<HttpResponse> {
+	trace!("Received request: ws");
+
+	let (response, mut session, stream) = actix_ws::handle(&request, body)?;
+	let mut stream = stream.aggregate_continuations();
+
+	let mut subscription = core.events().subscribe(query.since);
+
+	rt::spawn(async move {
+		match subscription.replay.take() {
+			Some(replay) => {
+				for event in replay {
+					if send(&mut session, &event).await.is_err() {
+						return;
+					}
+				}
+			}
+			None => {
+				let resync = resync(subscription.latest, "Missed events are no longer available");
+
+				if send(&mut session, &resync).await.is_err() {
+					return;
+				}
+			}
+		}
+
+		loop {
+			tokio::select! {
+				event = subscription.receiver.recv() => match event {
+					Some(event) => {
+						if send(&mut session, &event).await.is_err() {
+							break;
+						}
+					}
+					// Subscriber was dropped for being too slow
+					None => {
+						let resync = resync(core.events().last_seq(), "Client is lagging behind");
+
+						send(&mut session, &resync).await.ok();
+						break;
+					}
+				},
+				message = stream.next() => match message {
+					Some(Ok(AggregatedMessage::Ping(bytes))) if session.pong(&bytes).await.is_err() => break,
+					Some(Ok(AggregatedMessage::Close(_))) | None => break,
+					Some(Err(err)) => {
+						warn!("WebSocket error: {}", err);
+						break;
+					}
+					_ => {}
+				},
+			}
+		}
+
+		session.close(None).await.ok();
+	});
+
+	Ok(response)
+}
+
+fn resync(seq: u64, reason: &str) -> Event {
+	Event {
+		seq,
+		kind: EventKind::Resync {
+			reason: reason.to_owned(),
+		},
+	}
+}
+
+async fn send(session: &mut Session, event: &Event) -> Result<(), actix_ws::Closed> {
+	match serde_json::to_string(event) {
+		Ok(json) => session.text(json).await,
+		Err(err) => {
+			warn!("Failed to serialize event: {}", err);
+			Ok(())
+		}
+	}
+}
@@ -1,15 +1,44 @@
 use anyhow::{Context, Result};
 use log::{debug, trace, warn};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, process, thread};
-
-use crate::util;
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+	process,
+	sync::Mutex,
+	thread,
+};
+
+use crate::{
+	ext::PathExt,
+	lock,
+	state::{self, Migration, State},
+	util,
+};
+
+/// Functions run before the session stops gracefully
+static SHUTDOWN_HOOKS: Mutex<Vec<Box<dyn Fn() + Send>>> = Mutex::new(Vec::new());
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Session {
 	pub pid: u32,
 	pub host: Option<String>,
 	pub port: Option<u16>,
+	/// Workspace directory of the session running as a daemon
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub daemon: Option<PathBuf>,
+	/// Project file the session was started with
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub project: Option<PathBuf>,
+	/// Workspace file the session was started with
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub workspace: Option<PathBuf>,
+	/// Project files of workspace members
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub members: Vec<PathBuf>,
+	/// Port of the read-only mirror, see `Server::with_mirror`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub mirror_port: Option<u16>,
 }
 
 impl Session {
@@ -22,30 +51,37 @@ impl Session {
 
 		None
 	}
+
+	pub fn get_mirror_address(&self) -> Option<String> {
+		let host = self.host.as_ref()?;
+		let port = self.mirror_port?;
+
+		Some(format!("http://{}:{}", host, port))
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Sessions {
+	pub last_session: String,
+	pub active_sessions: HashMap<String, Session>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Sessions {
-	last_session: String,
-	active_sessions: HashMap<String, Session>,
+impl State for Sessions {
+	const NAME: &'static str = "Session data";
+	const VERSION: u32 = 1;
+	const MIGRATIONS: &'static [Migration] = &[state::unversioned];
 }
 
 fn get_sessions() -> Result<Sessions> {
 	let path = util::get_argon_dir()?.join("sessions.toml");
 
-	if path.exists() {
-		match toml::from_str(&fs::read_to_string(&path)?) {
-			Ok(sessions) => return Ok(sessions),
-			Err(_) => warn!("Session data file is corrupted! Creating new one.."),
-		}
+	if let Some(sessions) = state::load(&path) {
+		return Ok(sessions);
 	}
 
-	let sessions = Sessions {
-		last_session: String::new(),
-		active_sessions: HashMap::new(),
-	};
+	let sessions = Sessions::default();
 
-	fs::write(path, toml::to_string(&sessions)?)?;
+	state::save(&path, &sessions)?;
 
 	Ok(sessions)
 }
@@ -53,15 +89,91 @@ fn get_sessions() -> Result<Sessions> {
 fn set_sessions(sessions: &Sessions) -> Result<()> {
 	let path = util::get_argon_dir()?.join("sessions.toml");
 
-	fs::write(path, toml::to_string(sessions)?)?;
+	state::save(&path, sessions)
+}
+
+/// Adds session of the project, returns the entry so it can be removed later
+pub fn add(
+	id: Option<String>,
+	host: Option<String>,
+	port: Option<u16>,
+	pid: u32,
+	project: &Path,
+	mirror_port: Option<u16>,
+	run_async: bool,
+) -> Result<Session> {
+	let session = Session {
+		pid,
+		host,
+		port,
+		daemon: None,
+		project: Some(project.to_owned()),
+		workspace: None,
+		members: vec![],
+		mirror_port,
+	};
+
+	insert(id, session, run_async)
+}
+
+/// Adds session of the daemon serving given workspace
+pub fn add_daemon(
+	id: Option<String>,
+	host: String,
+	port: u16,
+	pid: u32,
+	workspace_dir: &Path,
+	mirror_port: Option<u16>,
+) -> Result<()> {
+	let session = Session {
+		pid,
+		host: Some(host),
+		port: Some(port),
+		daemon: Some(workspace_dir.to_owned()),
+		project: None,
+		workspace: None,
+		members: vec![],
+		mirror_port,
+	};
+
+	insert(id, session, true)?;
+
+	Ok(())
+}
+
+/// Adds session serving members of the workspace file, `daemon`
+/// if it runs as a daemon of the workspace file directory
+#[allow(clippy::too_many_arguments)]
+pub fn add_workspace(
+	id: Option<String>,
+	host: String,
+	port: u16,
+	pid: u32,
+	workspace: &Path,
+	members: Vec<PathBuf>,
+	mirror_port: Option<u16>,
+	daemon: bool,
+	run_async: bool,
+) -> Result<()> {
+	let session = Session {
+		pid,
+		host: Some(host),
+		port: Some(port),
+		daemon: daemon.then(|| workspace.get_parent().to_owned()),
+		project: None,
+		workspace: Some(workspace.to_owned()),
+		members,
+		mirror_port,
+	};
+
+	insert(id, session, run_async || daemon)?;
 
 	Ok(())
 }
 
-pub fn add(id: Option<String>, host: Option<String>, port: Option<u16>, pid: u32, run_async: bool) -> Result<()> {
+fn insert(id: Option<String>, session: Session, run_async: bool) -> Result<Session> {
 	let mut sessions = get_sessions()?;
 
-	let session = Session { host, port, pid };
 	let id = id.unwrap_or(generate_id(&sessions));
 
 	sessions.last_session.clone_from(&id);
@@ -70,7 +182,11 @@ pub fn add(id: Option<String>, host: Option<String>, port: Option<u16>, pid: u32
 	set_sessions(&sessions)?;
 
 	if !run_async {
+		let session = session.clone();
+
 		ctrlc::set_handler(move || {
+			shutdown();
+
 			match remove(&session) {
 				Ok(()) => trace!("Session entry removed"),
 				Err(err) => warn!("Failed to remove session entry: {}", err),
@@ -88,7 +204,7 @@ pub fn add(id: Option<String>, host: Option<String>, port: Option<u16>, pid: u32
 		Err(err) => warn!("Failed to cleanup sessions: {}", err),
 	});
 
-	Ok(())
+	Ok(session)
 }
 
 pub fn get(id: Option<String>, host: Option<String>, port: Option<u16>) -> Result<Option<Session>> {
@@ -109,6 +225,32 @@ pub fn get(id: Option<String>, host: Option<String>, port: Option<u16>) -> Resul
 	Ok(None)
 }
 
+/// Returns the daemon session serving given workspace
+pub fn get_daemon(workspace_dir: &Path) -> Result<Option<(String, Session)>> {
+	Ok(find_daemon(&get_sessions()?.active_sessions, workspace_dir))
+}
+
+pub fn find_daemon(sessions: &HashMap<String, Session>, workspace_dir: &Path) -> Option<(String, Session)> {
+	sessions
+		.iter()
+		.find(|(_, session)| session.daemon.as_deref() == Some(workspace_dir))
+		.map(|(id, session)| (id.to_owned(), session.to_owned()))
+}
+
+/// Returns a running session that uses given project file, either started
+/// with it directly, as a workspace member or as a daemon serving its workspace
+pub fn find_serving(sessions: &HashMap<String, Session>, project: &Path) -> Option<(String, Session)> {
+	sessions
+		.iter()
+		.filter(|(_, session)| util::process_exists(session.pid))
+		.find(|(_, session)| {
+			session.project.as_deref() == Some(project)
+				|| session.members.iter().any(|member| member == project)
+				|| session.daemon.as_deref() == project.parent()
+		})
+		.map(|(id, session)| (id.to_owned(), session.to_owned()))
+}
+
 pub fn get_multiple(ids: &Vec<String>) -> Result<HashMap<String, Session>> {
 	let sessions = get_sessions()?;
 
@@ -166,14 +308,7 @@ pub fn remove_multiple(ids: &Vec<String>) -> Result<()> {
 }
 
 pub fn remove_all() -> Result<()> {
-	let sessions = Sessions {
-		last_session: String::new(),
-		active_sessions: HashMap::new(),
-	};
-
-	set_sessions(&sessions)?;
-
-	Ok(())
+	set_sessions(&Sessions::default())
 }
 
 fn cleanup(mut sessions: Sessions) -> Result<()> {
@@ -206,3 +341,16 @@ fn generate_id(sessions: &Sessions) -> String {
 		index += 1;
 	}
 }
+
+/// Registers function that runs before the session stops gracefully,
+/// e.g. on Ctrl+C or when stopped with `argon stop`
+pub fn on_shutdown(hook: impl Fn() + Send + 'static) {
+	lock!(SHUTDOWN_HOOKS).push(Box::new(hook));
+}
+
+/// Runs all registered shutdown hooks
+pub fn shutdown() {
+	for hook in lock!(SHUTDOWN_HOOKS).iter() {
+		hook();
+	}
+}
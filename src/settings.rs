@@ -0,0 +1,281 @@
+use anyhow::{anyhow, bail, Context, Result};
+use documented::DocumentedFields;
+use lazy_static::lazy_static;
+use log::{info, LevelFilter};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::{
+	collections::{BTreeMap, HashMap},
+	sync::Mutex,
+};
+
+use crate::{
+	config::{Config, ConfigKind, Value},
+	lock, logger,
+	project::Project,
+};
+
+/// Setting that changes verbosity of the logger, it is not part of the config
+pub const LOG_LEVEL: &str = "log_level";
+
+/// Settings of the config relevant to clients, `true` ones can be changed at runtime
+/// as they are read whenever used instead of once when the server starts
+const CONFIG_SETTINGS: &[(&str, bool)] = &[
+	("rename_instances", true),
+	("keep_duplicates", true),
+	("move_to_bin", true),
+	("euler_cframes", true),
+	("history", true),
+	("project_backups", true),
+	("changes_threshold", true),
+	("max_unsynced_changes", true),
+	("sync_conflict_policy", true),
+	("lua_extension", false),
+	("line_ending", false),
+	("heartbeat_interval", false),
+	("max_payload_size", false),
+	("rate_limit", false),
+];
+
+lazy_static! {
+	/// Settings changed only for this session, with values they had before the first change
+	static ref SESSION: Mutex<HashMap<String, Value>> = Mutex::new(HashMap::new());
+}
+
+/// Layer the effective value of a setting comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SettingSource {
+	Default,
+	Global,
+	Workspace,
+	/// Changed for this session only
+	Session,
+	/// Command line arguments or environment variables
+	Cli,
+	Project,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Setting {
+	pub value: JsonValue,
+	pub source: SettingSource,
+	pub changeable: bool,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+}
+
+/// Effective settings shown by the plugin settings UI
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+	pub config: BTreeMap<String, Setting>,
+	pub project: BTreeMap<String, Setting>,
+}
+
+impl Settings {
+	pub fn get(&self, setting: &str) -> Option<&Setting> {
+		self.config.get(setting).or_else(|| self.project.get(setting))
+	}
+}
+
+/// Lists curated config settings and active settings of the project
+pub fn list(project: &Project) -> Settings {
+	let config = Config::new();
+	let defaults = Config::default();
+	let session = lock!(SESSION);
+
+	let mut settings = Settings {
+		config: BTreeMap::new(),
+		project: BTreeMap::new(),
+	};
+
+	for (setting, changeable) in CONFIG_SETTINGS {
+		let value = config.get(setting).unwrap();
+
+		let source = if session.contains_key(*setting) {
+			SettingSource::Session
+		} else if Some(&value) == defaults.get(setting).as_ref() {
+			SettingSource::Default
+		} else {
+			match config.kind() {
+				ConfigKind::Default => SettingSource::Default,
+				ConfigKind::Global(_) => SettingSource::Global,
+				ConfigKind::Workspace(_) => SettingSource::Workspace,
+			}
+		};
+
+		settings.config.insert(
+			setting.to_string(),
+			Setting {
+				value: serde_json::to_value(value).unwrap_or_default(),
+				source,
+				changeable: *changeable,
+				description: Config::get_field_docs(setting).ok().map(|doc| doc.trim().to_owned()),
+			},
+		);
+	}
+
+	settings.config.insert(
+		LOG_LEVEL.to_owned(),
+		Setting {
+			value: JsonValue::String(logger::verbosity().to_string()),
+			source: if session.contains_key(LOG_LEVEL) {
+				SettingSource::Session
+			} else {
+				SettingSource::Cli
+			},
+			changeable: true,
+			description: Some(String::from(
+				"Verbosity of the server logs (OFF, ERROR, WARN, INFO, DEBUG, TRACE)",
+			)),
+		},
+	);
+
+	let middleware_config = project.middleware_config.clone().unwrap_or_default().resolve();
+
+	let project_settings = [
+		("ignoreGlobs", serde_json::to_value(&project.ignore_globs)),
+		("syncback", serde_json::to_value(&project.syncback)),
+		("middlewareConfig", serde_json::to_value(&middleware_config)),
+		("memoryMode", serde_json::to_value(project.memory_mode)),
+		(
+			"collapseEmptyFolders",
+			serde_json::to_value(project.collapse_empty_folders),
+		),
+		("layoutPreferences", serde_json::to_value(&project.layout_preferences)),
+		("strictLoad", serde_json::to_value(project.strict_load)),
+		("verifyWrites", serde_json::to_value(project.verify_writes)),
+		("managePackages", serde_json::to_value(project.manage_packages)),
+		("profile", serde_json::to_value(&project.profile)),
+	];
+
+	for (setting, value) in project_settings {
+		settings.project.insert(
+			setting.to_owned(),
+			Setting {
+				value: value.unwrap_or_default(),
+				source: SettingSource::Project,
+				changeable: false,
+				description: None,
+			},
+		);
+	}
+
+	settings
+}
+
+/// Checks the changes without applying them, returns error message of every invalid setting
+pub fn validate(changes: &BTreeMap<String, JsonValue>, persist: bool) -> BTreeMap<String, String> {
+	let mut config = Config::new().clone();
+	let mut errors = BTreeMap::new();
+
+	for (setting, value) in changes {
+		let error = if setting == LOG_LEVEL {
+			match value.as_str().map(str::parse::<LevelFilter>) {
+				_ if persist => Some(String::from(
+					"Log level can't be persisted, use verbosity flags or RUST_VERBOSE instead",
+				)),
+				Some(Ok(_)) => None,
+				_ => Some(String::from("Expected one of OFF, ERROR, WARN, INFO, DEBUG or TRACE")),
+			}
+		} else {
+			match CONFIG_SETTINGS.iter().find(|(name, _)| name == setting) {
+				Some((_, true)) => match to_string(value) {
+					Some(value) => config
+						.set(setting, &value)
+						.err()
+						.map(|err| format!("Invalid value: {}", err)),
+					None => Some(String::from("Expected a string, number or boolean")),
+				},
+				Some((_, false)) => Some(String::from("Setting can't be changed at runtime")),
+				None if config.has_setting(setting) => Some(String::from("Setting is not available to clients")),
+				None => Some(String::from("Unknown setting")),
+			}
+		};
+
+		if let Some(error) = error {
+			errors.insert(setting.to_owned(), error);
+		}
+	}
+
+	errors
+}
+
+/// Applies the changes live, persisted ones are also saved to the loaded
+/// config file while the others only last until the server stops
+pub fn update(changes: &BTreeMap<String, JsonValue>, persist: bool) -> Result<()> {
+	if let Some((setting, error)) = validate(changes, persist).into_iter().next() {
+		bail!("Invalid setting {}: {}", setting, error);
+	}
+
+	let mut session = lock!(SESSION);
+
+	let mut updated = Config::new().clone();
+	let mut updated_session = session.clone();
+	let mut level = None;
+
+	for (setting, value) in changes {
+		if setting == LOG_LEVEL {
+			level = value.as_str().and_then(|level| level.parse::<LevelFilter>().ok());
+
+			updated_session
+				.entry(setting.to_owned())
+				.or_insert_with(|| Value::String(logger::verbosity().to_string()));
+		} else {
+			let previous = updated
+				.get(setting)
+				.with_context(|| format!("Setting {} does not exist", setting))?;
+			let value = to_string(value).with_context(|| format!("Invalid value of {}", setting))?;
+
+			updated
+				.set(setting, &value)
+				.map_err(|err| anyhow!("Failed to set {}: {}", setting, err))?;
+
+			if persist {
+				updated_session.remove(setting);
+			} else {
+				updated_session.entry(setting.to_owned()).or_insert(previous);
+			}
+		}
+	}
+
+	if persist {
+		let path = updated.layer_path()?;
+
+		// Other settings changed for this session keep their stored values
+		let mut stored = updated.clone();
+
+		for (setting, value) in updated_session.iter().filter(|(setting, _)| *setting != LOG_LEVEL) {
+			stored
+				.set(setting, &value.to_string())
+				.map_err(|err| anyhow!("Failed to keep stored value of {}: {}", setting, err))?;
+		}
+
+		stored.save(&path)?;
+	}
+
+	if let Some(level) = level {
+		logger::set_verbosity(level);
+	}
+
+	*session = updated_session;
+	Config::replace(updated);
+
+	info!(
+		"Settings {} changed{}",
+		changes.keys().cloned().collect::<Vec<_>>().join(", "),
+		if persist { " and persisted" } else { " for this session" }
+	);
+
+	Ok(())
+}
+
+fn to_string(value: &JsonValue) -> Option<String> {
+	match value {
+		JsonValue::String(value) => Some(value.to_owned()),
+		JsonValue::Bool(_) | JsonValue::Number(_) => Some(value.to_string()),
+		_ => None,
+	}
+}
@@ -0,0 +1,159 @@
+use anyhow::{bail, Result};
+use colored::Colorize;
+use log::{debug, warn};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, path::Path};
+use toml::{Table, Value};
+
+use crate::{argon_warn, ext::PathExt, t};
+
+/// Key of the schema version stored in every state file
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Upgrades raw contents of a state file by a single schema version
+pub type Migration = fn(&mut Table) -> Result<()>;
+
+/// File persisted between Argon runs (e.g. `update.toml`), changing its
+/// schema requires bumping `VERSION` and appending a migration from the
+/// previous version, so existing files are upgraded instead of being reset
+pub trait State {
+	/// Name used in messages, e.g. `Update status`
+	const NAME: &'static str;
+	/// Current schema version, files written before
+	/// schema versions were introduced have version 0
+	const VERSION: u32;
+	/// Migrations ordered by the version they upgrade from,
+	/// there has to be exactly `VERSION` of them
+	const MIGRATIONS: &'static [Migration];
+}
+
+/// Migration from files written before schema versions were
+/// introduced, for state whose schema did not change since
+pub fn unversioned(_table: &mut Table) -> Result<()> {
+	Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schema {
+	Current,
+	/// File was upgraded from the given version
+	Migrated(u32),
+	/// File was written by a newer Argon version with the given schema
+	Newer(u32),
+}
+
+/// Returns schema version of the raw state file contents
+pub fn version(table: &Table) -> Result<u32> {
+	match table.get(SCHEMA_VERSION_KEY) {
+		Some(Value::Integer(version)) => Ok(u32::try_from(*version)?),
+		Some(value) => bail!("Invalid schema version: {}", value),
+		None => Ok(0),
+	}
+}
+
+/// Runs all migrations from the stored schema version to the current one,
+/// contents of newer schemas are left untouched
+pub fn migrate<T: State>(table: &mut Table) -> Result<Schema> {
+	debug_assert_eq!(T::MIGRATIONS.len(), T::VERSION as usize);
+
+	let stored = version(table)?;
+
+	if stored > T::VERSION {
+		return Ok(Schema::Newer(stored));
+	}
+
+	for migration in &T::MIGRATIONS[stored as usize..] {
+		migration(table)?;
+	}
+
+	table.insert(SCHEMA_VERSION_KEY.into(), Value::Integer(T::VERSION.into()));
+
+	Ok(if stored == T::VERSION {
+		Schema::Current
+	} else {
+		Schema::Migrated(stored)
+	})
+}
+
+/// Parses and migrates state file contents, files of newer schemas
+/// are read on a best-effort basis with a downgrade warning
+pub fn upgrade<T: State>(contents: &str) -> Result<Table> {
+	let mut table: Table = toml::from_str(contents)?;
+
+	match migrate::<T>(&mut table)? {
+		Schema::Current => {}
+		Schema::Migrated(version) => debug!("{} migrated from schema version {}", T::NAME, version),
+		Schema::Newer(version) => argon_warn!(
+			"{}",
+			t!(
+				"state.newer",
+				name = T::NAME.bold(),
+				version = version.to_string().bold(),
+				current = T::VERSION.to_string().bold()
+			)
+		),
+	}
+
+	table.remove(SCHEMA_VERSION_KEY);
+
+	Ok(table)
+}
+
+pub fn parse<T: State + DeserializeOwned>(contents: &str) -> Result<T> {
+	Ok(Value::Table(upgrade::<T>(contents)?).try_into()?)
+}
+
+pub fn to_string<T: State + Serialize>(state: &T) -> Result<String> {
+	let Value::Table(mut table) = Value::try_from(state)? else {
+		bail!("{} has to be serialized as a table", T::NAME);
+	};
+
+	table.insert(SCHEMA_VERSION_KEY.into(), Value::Integer(T::VERSION.into()));
+
+	Ok(toml::to_string(&table)?)
+}
+
+/// Loads state file, returns `None` if it does not exist or is
+/// corrupted in which case callers start over with the default state
+pub fn load<T: State + DeserializeOwned>(path: &Path) -> Option<T> {
+	if !path.exists() {
+		return None;
+	}
+
+	match fs::read_to_string(path)
+		.map_err(Into::into)
+		.and_then(|contents| parse(&contents))
+	{
+		Ok(state) => Some(state),
+		Err(err) => {
+			warn!("{} file is corrupted! Creating new one.. ({})", T::NAME, err);
+			None
+		}
+	}
+}
+
+/// Saves state file through a temporary file so it is never left
+/// partially written, files of newer schemas are never overwritten
+pub fn save<T: State + Serialize>(path: &Path, state: &T) -> Result<()> {
+	let stored = fs::read_to_string(path)
+		.ok()
+		.and_then(|contents| toml::from_str::<Table>(&contents).ok())
+		.and_then(|table| version(&table).ok());
+
+	if let Some(stored) = stored.filter(|stored| *stored > T::VERSION) {
+		debug!(
+			"{} was written by newer schema version {}, not overwriting it",
+			T::NAME,
+			stored
+		);
+
+		return Ok(());
+	}
+
+	let temp_path = path.with_file_name(format!("{}.tmp", path.get_name()));
+
+	fs::write(&temp_path, to_string(state)?)?;
+	fs::rename(&temp_path, path)?;
+
+	Ok(())
+}
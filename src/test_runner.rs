@@ -0,0 +1,244 @@
+use anyhow::Result;
+use std::{
+	fmt::Write,
+	thread,
+	time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+use crate::{
+	constants::TEST_POLL_INTERVAL,
+	core::test_runs::{TestError, TestErrorKind, TestRun, TestStatus, TEST_RESULTS_VERSION},
+};
+
+/// Luau script that runs the specs in Studio and reports their results
+const BOOTSTRAP: &str = include_str!("../assets/scripts/test_bootstrap.luau");
+
+/// Way of executing code in Studio and reading the results back, the Argon server normally
+pub trait TestBridge {
+	/// Sends the code to the client to execute
+	fn execute(&self, code: &str) -> Result<()>;
+
+	/// Results of the run reported so far, `None` if nothing was reported yet
+	fn poll(&self, run_id: &str) -> Result<Option<TestRun>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct TestOptions {
+	/// Instance containing the specs separated with `/`, e.g. `ReplicatedStorage/Tests`
+	pub root: String,
+	/// Module running the specs, TestEZ is searched for in common locations if not set
+	pub runner: Option<String>,
+	/// Time the whole run can take
+	pub timeout: Duration,
+	/// Time Studio has to report the start of the run
+	pub start_timeout: Duration,
+}
+
+/// Generates the bootstrap script that reports results of the run to `url`
+pub fn bootstrap(options: &TestOptions, url: &str, run_id: &str) -> String {
+	BOOTSTRAP
+		.replace("{{VERSION}}", &TEST_RESULTS_VERSION.to_string())
+		.replace("{{URL}}", &luau_string(url))
+		.replace("{{RUN_ID}}", &luau_string(run_id))
+		.replace("{{ROOT}}", &luau_string(&options.root))
+		.replace(
+			"{{RUNNER}}",
+			&options.runner.as_deref().map_or(String::from("nil"), luau_string),
+		)
+}
+
+/// Sends the bootstrap through the bridge and waits for the run to finish,
+/// runs that do not finish in time keep partial results with the timeout error
+pub fn run(bridge: &impl TestBridge, options: &TestOptions, url: &str) -> Result<TestRun> {
+	let run_id = Uuid::new_v4().to_string();
+	bridge.execute(&bootstrap(options, url, &run_id))?;
+
+	let started = Instant::now();
+
+	loop {
+		let elapsed = started.elapsed();
+
+		match bridge.poll(&run_id)? {
+			Some(run) if run.done => return Ok(run),
+			Some(run) if elapsed >= options.timeout => {
+				return Ok(TestRun {
+					error: Some(TestError {
+						kind: TestErrorKind::Timeout,
+						message: format!(
+							"Run did not finish in {}s, Studio may have crashed. Results are partial",
+							options.timeout.as_secs()
+						),
+					}),
+					..run
+				});
+			}
+			None if elapsed >= options.start_timeout => {
+				return Ok(TestRun {
+					error: Some(TestError {
+						kind: TestErrorKind::NotStarted,
+						message: String::from("Studio did not start the run, make sure the Argon plugin is connected"),
+					}),
+					..TestRun::default()
+				});
+			}
+			_ => {}
+		}
+
+		thread::sleep(TEST_POLL_INTERVAL);
+	}
+}
+
+/// `0` if all cases passed, `1` if any failed and `2` if the run itself failed
+pub fn exit_code(run: &TestRun) -> i32 {
+	if run.error.is_some() {
+		2
+	} else if run.count(TestStatus::Failed) > 0 {
+		1
+	} else {
+		0
+	}
+}
+
+/// Human readable results, one line per case
+pub fn text(run: &TestRun) -> String {
+	let mut text = String::new();
+
+	for suite in &run.suites {
+		writeln!(text, "{} ({:.2}s)", suite.name, suite.duration.unwrap_or_default()).unwrap();
+
+		for case in &suite.cases {
+			let status = match case.status {
+				TestStatus::Passed => "PASS",
+				TestStatus::Failed => "FAIL",
+				TestStatus::Skipped => "SKIP",
+			};
+
+			writeln!(text, "  {} {}", status, case.name).unwrap();
+
+			if let Some(message) = &case.message {
+				for line in message.lines() {
+					writeln!(text, "       {}", line).unwrap();
+				}
+			}
+		}
+
+		text.push('\n');
+	}
+
+	if let Some(error) = &run.error {
+		writeln!(text, "Error: {}", error.message).unwrap();
+	}
+
+	write!(
+		text,
+		"{} passed, {} failed, {} skipped in {:.2}s",
+		run.count(TestStatus::Passed),
+		run.count(TestStatus::Failed),
+		run.count(TestStatus::Skipped),
+		run.duration()
+	)
+	.unwrap();
+
+	text
+}
+
+/// Results in the JUnit XML format understood by most CI systems,
+/// error of the run is reported as an extra errored suite
+pub fn junit(run: &TestRun) -> String {
+	let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+	let count = |status| run.count(status);
+	let tests: usize = run.suites.iter().map(|suite| suite.cases.len()).sum();
+
+	writeln!(
+		xml,
+		"<testsuites name=\"argon\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" errors=\"{}\" time=\"{:.3}\">",
+		tests,
+		count(TestStatus::Failed),
+		count(TestStatus::Skipped),
+		usize::from(run.error.is_some()),
+		run.duration()
+	)
+	.unwrap();
+
+	for suite in &run.suites {
+		writeln!(
+			xml,
+			"\t<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+			escape(&suite.name),
+			suite.cases.len(),
+			suite.count(TestStatus::Failed),
+			suite.count(TestStatus::Skipped),
+			suite.duration.unwrap_or_default()
+		)
+		.unwrap();
+
+		for case in &suite.cases {
+			write!(
+				xml,
+				"\t\t<testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+				escape(&suite.name),
+				escape(&case.name),
+				case.duration.unwrap_or_default()
+			)
+			.unwrap();
+
+			match case.status {
+				TestStatus::Passed => xml.push_str("/>\n"),
+				TestStatus::Skipped => xml.push_str(">\n\t\t\t<skipped/>\n\t\t</testcase>\n"),
+				TestStatus::Failed => {
+					let message = case.message.clone().unwrap_or_default();
+
+					writeln!(
+						xml,
+						">\n\t\t\t<failure message=\"{}\">{}</failure>\n\t\t</testcase>",
+						escape(message.lines().next().unwrap_or_default()),
+						escape(&message)
+					)
+					.unwrap();
+				}
+			}
+		}
+
+		xml.push_str("\t</testsuite>\n");
+	}
+
+	if let Some(error) = &run.error {
+		writeln!(
+			xml,
+			"\t<testsuite name=\"argon\" tests=\"1\" errors=\"1\">\n\t\t<testcase classname=\"argon\" name=\"run\">\n\t\t\t<error type=\"{:?}\" message=\"{}\"/>\n\t\t</testcase>\n\t</testsuite>",
+			error.kind,
+			escape(&error.message)
+		)
+		.unwrap();
+	}
+
+	xml.push_str("</testsuites>\n");
+	xml
+}
+
+/// Escapes XML special characters and drops characters XML 1.0 can't contain
+fn escape(str: &str) -> String {
+	let mut escaped = String::with_capacity(str.len());
+
+	for char in str.chars() {
+		match char {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&apos;"),
+			'\t' | '\n' | '\r' => escaped.push(char),
+			char if char < ' ' => {}
+			char => escaped.push(char),
+		}
+	}
+
+	escaped
+}
+
+/// Luau string literal, Rust escapes are valid in Luau
+fn luau_string(str: &str) -> String {
+	format!("{:?}", str)
+}
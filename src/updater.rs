@@ -3,11 +3,11 @@ use log::{debug, info, trace, warn};
 use self_update::{backends::github::Update, version::bump_is_greater, Status};
 use serde::{Deserialize, Serialize};
 use std::{
-	env, fs,
-	path::PathBuf,
+	env,
 	sync::Once,
 	time::{Duration, SystemTime},
 };
+use toml::{Table, Value};
 use yansi::Paint;
 
 use crate::{
@@ -15,17 +15,11 @@ use crate::{
 	constants::TEMPLATES_VERSION,
 	installer::{get_plugin_version, install_templates},
 	logger, sessions,
-	util::{self, get_plugin_path},
+	state::{self, Migration, State},
+	t,
+	util::{self, editor::EditorCli, get_plugin_path},
 };
 
-// Enum to represent the detected editor CLI
-#[cfg(windows)] // Only compile this enum on Windows where it's actually used
-#[derive(Debug)]
-enum EditorCli {
-	VsCode(PathBuf),
-	Cursor(PathBuf, PathBuf), // (cursor_cmd_path, extensions_dir_path)
-}
-
 static UPDATE_FORCED: Once = Once::new();
 const UPDATE_CHECK_INTERVAL: u64 = 3600;
 
@@ -37,6 +31,12 @@ pub struct UpdateStatus {
 	pub vscode_version: String,
 }
 
+impl State for UpdateStatus {
+	const NAME: &'static str = "Update status";
+	const VERSION: u32 = 1;
+	const MIGRATIONS: &'static [Migration] = &[add_vscode_version];
+}
+
 impl Default for UpdateStatus {
 	fn default() -> Self {
 		Self {
@@ -48,14 +48,21 @@ impl Default for UpdateStatus {
 	}
 }
 
+/// Files written before VS Code extension updates were introduced,
+/// the extension version gets detected on the next update check
+fn add_vscode_version(table: &mut Table) -> Result<()> {
+	if !table.contains_key("vscode_version") {
+		table.insert("vscode_version".into(), Value::String("0.0.0".into()));
+	}
+
+	Ok(())
+}
+
 pub fn get_status() -> Result<UpdateStatus> {
 	let path = util::get_argon_dir()?.join("update.toml");
 
-	if path.exists() {
-		match toml::from_str(&fs::read_to_string(&path)?) {
-			Ok(status) => return Ok(status),
-			Err(_) => warn!("Update status file is corrupted! Creating new one.."),
-		}
+	if let Some(status) = state::load(&path) {
+		return Ok(status);
 	}
 
 	// Try to get installed VS Code extension version
@@ -77,7 +84,7 @@ pub fn get_status() -> Result<UpdateStatus> {
 		vscode_version,
 	};
 
-	fs::write(path, toml::to_string(&status)?)?;
+	state::save(&path, &status)?;
 
 	Ok(status)
 }
@@ -85,9 +92,7 @@ pub fn get_status() -> Result<UpdateStatus> {
 pub fn set_status(status: &UpdateStatus) -> Result<()> {
 	let path = util::get_argon_dir()?.join("update.toml");
 
-	fs::write(path, toml::to_string(status)?)?;
-
-	Ok(())
+	state::save(&path, status)
 }
 
 async fn stop_running_sessions() -> Result<()> {
@@ -195,24 +200,31 @@ pub fn update_cli(auto_update: bool) -> Result<bool> {
 
 				match update.update()? {
 					Status::Updated(version) => {
-						argon_info!("Successfully updated to version {}!", version);
+						argon_info!("{}", t!("update.updated", version = version));
 						set_status(&status)?;
 						Ok(true)
 					}
 					Status::UpToDate(_) => {
-						argon_warn!("Already using the latest version!");
+						argon_warn!("{}", t!("update.latest"));
 						Ok(false)
 					}
 				}
 			} else {
 				println!("DEBUG: auto_update is false, not performing actual update");
-				argon_info!("New version {} is available! Run {}", release.version, "argon update");
+				argon_info!(
+					"{}",
+					t!(
+						"update.available",
+						version = release.version,
+						command = "argon update".bold()
+					)
+				);
 				Ok(false)
 			}
 		}
 		Err(err) => {
 			println!("DEBUG: Failed to get latest release: {}", err);
-			argon_error!("Failed to check for updates: {}", err);
+			argon_error!("{}", t!("update.check_failed", error = err));
 			bail!("Update check failed: {}", err);
 		}
 	}
@@ -236,27 +248,20 @@ fn update_plugin(status: &mut UpdateStatus, prompt: bool, force: bool) -> Result
 	let release = update.get_latest_release()?;
 
 	if bump_is_greater(current_version, &release.version)? || force {
-		if !prompt
-			|| logger::prompt(
-				&format!(
-					"New version of Lemonade plugin: {} is available! Would you like to update?",
-					release.version.bold()
-				),
-				true,
-			) {
+		if !prompt || logger::prompt(&t!("update.plugin_prompt", version = release.version.bold()), true) {
 			if !prompt {
-				argon_info!(
-					"New version of Lemonade plugin: {} is available! Updating..",
-					release.version.bold()
-				);
+				argon_info!("{}", t!("update.plugin_updating", version = release.version.bold()));
 			}
 
 			match update.download() {
 				Ok(_) => {
 					argon_info!(
-						"Roblox plugin updated! Make sure you have {} setting enabled to see changes. Visit {} to read the changelog",
-						Paint::bold(&"Reload plugins on file changed"),
-						Paint::bold(&"https://argon.wiki/changelog/argon-roblox")
+						"{}",
+						t!(
+							"update.plugin_updated",
+							setting = Paint::bold(&"Reload plugins on file changed"),
+							changelog = Paint::bold(&"https://argon.wiki/changelog/argon-roblox")
+						)
 					);
 
 					status.plugin_version = release.version;
@@ -264,7 +269,7 @@ fn update_plugin(status: &mut UpdateStatus, prompt: bool, force: bool) -> Result
 				}
 				Err(err) => {
 					println!("DEBUG: update_plugin failed: {}", err);
-					argon_error!("Failed to update Lemonade plugin: {}", err);
+					argon_error!("{}", t!("update.plugin_failed", error = err));
 					Ok(false)
 				}
 			}
@@ -280,9 +285,9 @@ fn update_plugin(status: &mut UpdateStatus, prompt: bool, force: bool) -> Result
 
 fn update_templates(status: &mut UpdateStatus, prompt: bool, force: bool) -> Result<bool> {
 	if status.templates_version < TEMPLATES_VERSION || force {
-		if !prompt || logger::prompt("Default templates have changed! Would you like to update?", true) {
+		if !prompt || logger::prompt(&t!("update.templates_prompt"), true) {
 			if !prompt {
-				argon_info!("Default templates have changed! Updating..",);
+				argon_info!("{}", t!("update.templates_updating"));
 			}
 
 			install_templates(true)?;
@@ -304,38 +309,10 @@ fn update_templates(status: &mut UpdateStatus, prompt: bool, force: bool) -> Res
 fn get_vscode_version() -> Option<String> {
 	// Try to get version using VS Code CLI
 
-	// Determine the command to use
-	let mut command;
-	#[cfg(windows)]
-	{
-		// On Windows, try to find the specific executable first
-		match find_editor_cli_windows() {
-			Some(EditorCli::VsCode(path)) => {
-				trace!("Using VS Code CLI for version check: {}", path.display());
-				command = std::process::Command::new(path);
-			}
-			Some(EditorCli::Cursor(path, extensions_dir)) => {
-				trace!(
-					"Using Cursor CLI for version check: {} with extensions {}",
-					path.display(),
-					extensions_dir.display()
-				);
-				command = std::process::Command::new(path);
-				command.arg("--extensions-dir").arg(&extensions_dir);
-			}
-			None => {
-				// Fallback to PATH lookup if specific path not found
-				trace!("No specific editor CLI found, falling back to 'code' from PATH for version check");
-				command = std::process::Command::new("code");
-			}
-		}
-	}
-	#[cfg(not(windows))]
-	{
-		// On other platforms, just use "code" from PATH
-		trace!("Using 'code' from PATH for version check (non-Windows)");
-		command = std::process::Command::new("code");
-	}
+	let editor = EditorCli::detect();
+	trace!("Using {} for version check", editor.path().display());
+
+	let mut command = editor.command();
 
 	let output = command // Use the determined command
 		.arg("--list-extensions")
@@ -372,90 +349,6 @@ fn get_vscode_version() -> Option<String> {
 	}
 }
 
-// Helper function specifically for Windows to find the VS Code or Cursor executable
-#[cfg(windows)]
-fn find_editor_cli_windows() -> Option<EditorCli> {
-	trace!("Attempting to find VS Code or Cursor executable on Windows");
-
-	// --- 1. Check for VS Code ---
-
-	// 1a. Check LOCALAPPDATA (User Install)
-	if let Ok(local_app_data) = env::var("LOCALAPPDATA") {
-		let vscode_user_path = PathBuf::from(local_app_data)
-			.join("Programs")
-			.join("Microsoft VS Code")
-			.join("bin")
-			.join("code.cmd"); // Prefer .cmd
-		if vscode_user_path.exists() {
-			trace!("Found VS Code (user) at: {}", vscode_user_path.display());
-			return Some(EditorCli::VsCode(vscode_user_path));
-		}
-		let vscode_user_path_exe = vscode_user_path.with_extension("exe");
-		if vscode_user_path_exe.exists() {
-			trace!("Found VS Code (user, exe) at: {}", vscode_user_path_exe.display());
-			return Some(EditorCli::VsCode(vscode_user_path_exe));
-		}
-	}
-
-	// 1b. Check ProgramFiles (System Install)
-	if let Ok(program_files) = env::var("ProgramFiles") {
-		let vscode_system_path = PathBuf::from(program_files)
-			.join("Microsoft VS Code")
-			.join("bin")
-			.join("code.cmd");
-		if vscode_system_path.exists() {
-			trace!("Found VS Code (system) at: {}", vscode_system_path.display());
-			return Some(EditorCli::VsCode(vscode_system_path));
-		}
-		let vscode_system_path_exe = vscode_system_path.with_extension("exe");
-		if vscode_system_path_exe.exists() {
-			trace!("Found VS Code (system, exe) at: {}", vscode_system_path_exe.display());
-			return Some(EditorCli::VsCode(vscode_system_path_exe));
-		}
-	}
-
-	// --- 2. Check for Cursor (only if VS Code wasn't found) ---
-
-	// Get the user profile path for extensions directory
-	let user_profile = match env::var("USERPROFILE") {
-		Ok(path) => PathBuf::from(path),
-		Err(_) => {
-			trace!("Could not get USERPROFILE environment variable");
-			return None;
-		}
-	};
-
-	// Determine Cursor extensions directory
-	let cursor_extensions_dir = user_profile.join(".cursor").join("extensions");
-	trace!(
-		"Cursor extensions directory would be at: {}",
-		cursor_extensions_dir.display()
-	);
-
-	if let Ok(local_app_data) = env::var("LOCALAPPDATA") {
-		let cursor_path = PathBuf::from(local_app_data)
-			.join("Programs")
-			.join("cursor") // Cursor specific path
-			.join("resources")
-			.join("app")
-			.join("bin")
-			.join("cursor.cmd"); // Prefer cursor.cmd
-		if cursor_path.exists() {
-			trace!("Found Cursor at: {}", cursor_path.display());
-			return Some(EditorCli::Cursor(cursor_path, cursor_extensions_dir));
-		}
-		let cursor_path_exe = cursor_path.with_extension("exe");
-		if cursor_path_exe.exists() {
-			trace!("Found Cursor (exe) at: {}", cursor_path_exe.display());
-			return Some(EditorCli::Cursor(cursor_path_exe, cursor_extensions_dir));
-		}
-	}
-
-	// --- 3. Fallback ---
-	trace!("Neither VS Code nor Cursor found in standard locations, will rely on PATH lookup for 'code'");
-	None // Indicate we didn't find either in standard locations
-}
-
 fn update_vscode(status: &mut UpdateStatus, prompt: bool, force: bool) -> Result<bool> {
 	println!("DEBUG: Starting VS Code extension update process");
 	trace!("Checking for VS Code extension updates");
@@ -542,18 +435,11 @@ fn update_vscode(status: &mut UpdateStatus, prompt: bool, force: bool) -> Result
 	println!("DEBUG: Update needed? {} (force={})", update_needed, force);
 
 	if update_needed {
-		if !prompt
-			|| logger::prompt(
-				&format!(
-					"New version of Argon VS Code extension: {} is available! Would you like to update?",
-					Paint::bold(latest_version)
-				),
-				true,
-			) {
+		if !prompt || logger::prompt(&t!("update.vscode_prompt", version = Paint::bold(latest_version)), true) {
 			if !prompt {
 				argon_info!(
-					"New version of Argon VS Code extension: {} is available! Updating..",
-					Paint::bold(latest_version)
+					"{}",
+					t!("update.vscode_updating", version = Paint::bold(latest_version))
 				);
 			}
 
@@ -595,7 +481,7 @@ fn update_vscode(status: &mut UpdateStatus, prompt: bool, force: bool) -> Result
 				}
 			}
 
-			argon_info!("Downloading VS Code extension...");
+			argon_info!("{}", t!("update.vscode_downloading"));
 			println!("DEBUG: Downloading from URL: {}", download_url); // Debug URL
 			trace!("Downloading from URL: {}", download_url);
 
@@ -612,54 +498,23 @@ fn update_vscode(status: &mut UpdateStatus, prompt: bool, force: bool) -> Result
 
 			if let Err(err) = download_result() {
 				println!("DEBUG: Download failed: {}", err);
-				argon_error!("Failed to download VS Code extension: {}", err);
+				argon_error!("{}", t!("update.vscode_download_failed", error = err));
 				return Ok(false);
 			}
 
-			argon_info!("Installing VS Code extension...");
+			argon_info!("{}", t!("update.vscode_installing"));
 			trace!("Running: code --install-extension {} --force", vsix_path.display());
 
 			// Determine editor configuration first
-			let (cli_path, using_cursor, cursor_extensions_dir) = {
-				#[cfg(windows)]
-				{
-					match find_editor_cli_windows() {
-						Some(EditorCli::VsCode(path)) => {
-							trace!("Determined editor: VS Code CLI at {}", path.display());
-							(path, false, PathBuf::new()) // Not cursor, empty extensions dir
-						}
-						Some(EditorCli::Cursor(path, extensions_dir)) => {
-							trace!(
-								"Determined editor: Cursor CLI at {} with extensions {}",
-								path.display(),
-								extensions_dir.display()
-							);
-							(path, true, extensions_dir) // Is cursor, use provided extensions dir
-						}
-						None => {
-							trace!("Determined editor: Fallback to 'code' from PATH");
-							(PathBuf::from("code"), false, PathBuf::new()) // Fallback, not cursor
-						}
-					}
-				}
-				#[cfg(not(windows))]
-				{
-					trace!("Determined editor: 'code' from PATH (non-Windows)");
-					(PathBuf::from("code"), false, PathBuf::new()) // Standard 'code', not cursor
-				}
-			};
+			let editor = EditorCli::detect();
+			trace!("Determined editor: {:?}", editor);
 
 			// Build the command based on determined configuration
-			let mut command = std::process::Command::new(&cli_path);
+			let mut command = editor.command();
 			command.arg("--install-extension");
 			command.arg(&vsix_path);
 			command.arg("--force");
 
-			// Add Cursor-specific argument if needed
-			if using_cursor && !cursor_extensions_dir.as_os_str().is_empty() {
-				command.arg("--extensions-dir").arg(&cursor_extensions_dir);
-			}
-
 			trace!("Running install command: {:?}", command);
 
 			// Execute the installation command
@@ -674,15 +529,15 @@ fn update_vscode(status: &mut UpdateStatus, prompt: bool, force: bool) -> Result
 
 					if output.status.success() {
 						// Verify the installation if we're using Cursor
-						if using_cursor && !cursor_extensions_dir.as_os_str().is_empty() {
+						if let Some(extensions_dir) = editor.extensions_dir() {
 							trace!("Verifying Cursor installation...");
 
 							// Wait a moment for the installation to complete
 							std::thread::sleep(std::time::Duration::from_secs(1));
 
 							// Create a verification command
-							let mut verify_cmd = std::process::Command::new(&cli_path); // Use determined cli_path
-							verify_cmd.arg("--extensions-dir").arg(&cursor_extensions_dir);
+							let mut verify_cmd = std::process::Command::new(editor.path()); // Use determined CLI path
+							verify_cmd.arg("--extensions-dir").arg(extensions_dir);
 							verify_cmd.arg("--list-extensions");
 							verify_cmd.arg("--show-versions");
 
@@ -700,13 +555,13 @@ fn update_vscode(status: &mut UpdateStatus, prompt: bool, force: bool) -> Result
 										// Continue with success logic
 									} else {
 										trace!("Verification failed! Expected {} but didn't find it", expected_line);
-										argon_warn!("Extension was reported as installed but verification failed. You may need to restart Cursor or manually install the extension.");
+										argon_warn!("{}", t!("update.vscode_unverified"));
 										// Continue anyway as the CLI reported success
 									}
 								}
 								Err(err) => {
 									trace!("Verification command failed: {}", err);
-									argon_warn!("Could not verify if the extension was correctly installed in Cursor. You may need to restart Cursor or check the extension manually.");
+									argon_warn!("{}", t!("update.vscode_cursor_unverified"));
 									// Continue anyway as the original install command succeeded
 								}
 							}
@@ -715,14 +570,17 @@ fn update_vscode(status: &mut UpdateStatus, prompt: bool, force: bool) -> Result
 						// Success logic - same as before
 						let _ = std::fs::remove_file(vsix_path);
 						argon_info!(
-							"VS Code extension updated! Please reload VS Code to apply changes. Visit {} to read the changelog",
-							Paint::bold(&"https://argon.wiki/changelog/argon-vscode")
+							"{}",
+							t!(
+								"update.vscode_updated",
+								changelog = Paint::bold(&"https://argon.wiki/changelog/argon-vscode")
+							)
 						);
 						status.vscode_version = latest_version_str; // Update status with owned String
 						return Ok(true);
 					} else {
 						// Error logic - same as before
-						argon_error!("Failed to install VS Code extension: {}", stderr);
+						argon_error!("{}", t!("update.vscode_install_failed", error = stderr));
 					}
 				}
 				Err(err) => {
@@ -731,10 +589,10 @@ fn update_vscode(status: &mut UpdateStatus, prompt: bool, force: bool) -> Result
 
 					if err.kind() == std::io::ErrorKind::NotFound {
 						// Improved Error Message for Not Found
-						argon_error!("Could not run the command-line tool for VS Code or Cursor. Please ensure either Visual Studio Code or Cursor is installed correctly and accessible via the system PATH, or installed in their standard locations. If already installed, try reinstalling and ensure any 'Add to PATH' options are checked during setup.");
+						argon_error!("{}", t!("update.editor_missing"));
 					} else {
 						// Generic error for other failures
-						argon_error!("Failed to run editor command-line tool: {}", err);
+						argon_error!("{}", t!("update.editor_failed", error = err));
 					}
 					// This block needs to return Result<bool>, matching the function signature.
 					// Since an error occurred, we return Ok(false) indicating update didn't succeed.
@@ -792,7 +650,7 @@ pub fn manual_update(cli: bool, plugin: bool, templates: bool, vscode: bool, for
 
 	// Update CLI first, as it might contain fixes for other update processes
 	if cli {
-		argon_info!("Checking for CLI updates...");
+		argon_info!("{}", t!("update.checking_cli"));
 		println!("DEBUG: Calling update_cli with auto_update={}", force);
 		if update_cli(force)? {
 			updated = true;
@@ -801,21 +659,21 @@ pub fn manual_update(cli: bool, plugin: bool, templates: bool, vscode: bool, for
 
 	// Then update other components
 	if plugin {
-		argon_info!("Checking for Plugin updates...");
+		argon_info!("{}", t!("update.checking_plugin"));
 		if update_plugin(&mut status, false, force)? {
 			updated = true;
 		}
 	}
 
 	if templates {
-		argon_info!("Checking for Template updates...");
+		argon_info!("{}", t!("update.checking_templates"));
 		if update_templates(&mut status, false, force)? {
 			updated = true;
 		}
 	}
 
 	if vscode {
-		argon_info!("Checking for VS Code extension updates...");
+		argon_info!("{}", t!("update.checking_vscode"));
 		if update_vscode(&mut status, false, force)? {
 			updated = true;
 		} else {
@@ -827,7 +685,7 @@ pub fn manual_update(cli: bool, plugin: bool, templates: bool, vscode: bool, for
 	set_status(&status)?;
 
 	if !updated {
-		argon_info!("All components are up to date!");
+		argon_info!("{}", t!("update.up_to_date"));
 	}
 
 	Ok(updated)
@@ -8,7 +8,9 @@ use rbx_reflection::ClassTag;
 use roblox_install::RobloxStudio;
 use std::{env, path::PathBuf, process::Command};
 
-use crate::Properties;
+use crate::{logger::LogFormat, Properties};
+
+pub mod editor;
 
 /// Returns the `.argon` directory
 pub fn get_argon_dir() -> Result<PathBuf> {
@@ -47,6 +49,25 @@ pub fn is_service(class: &str) -> bool {
 	has_tag || class == "StarterPlayerScripts" || class == "StarterCharacterScripts"
 }
 
+/// Services that exist exactly once in every place and mostly hold its
+/// configuration, e.g. `Lighting.ClockTime` or `StarterPlayer.CharacterWalkSpeed`.
+/// Projects that set `servicesPath` sync them even if they are not part of the
+/// tree, their properties are kept in data files like `services/Lighting.data.json`
+pub const SINGLETON_SERVICES: &[&str] = &[
+	"Lighting",
+	"MaterialService",
+	"Players",
+	"SoundService",
+	"StarterPlayer",
+	"TextChatService",
+	"VoiceChatService",
+];
+
+/// Checks if the given `class` is one of the `SINGLETON_SERVICES`
+pub fn is_singleton_service(class: &str) -> bool {
+	SINGLETON_SERVICES.contains(&class)
+}
+
 /// Checks if the given `class` is a script
 pub fn is_script(class: &str) -> bool {
 	class == "Script" || class == "LocalScript" || class == "ModuleScript"
@@ -135,6 +156,16 @@ pub fn env_log_style() -> WriteStyle {
 	}
 }
 
+/// Returns the `RUST_LOG_FORMAT` environment variable
+pub fn env_log_format() -> LogFormat {
+	let log_format = env::var("RUST_LOG_FORMAT").unwrap_or("human".to_owned());
+
+	match log_format.as_str() {
+		"json" => LogFormat::Json,
+		_ => LogFormat::Human,
+	}
+}
+
 /// Returns the `RUST_BACKTRACE` environment variable
 pub fn env_backtrace() -> bool {
 	let backtrace = env::var("RUST_BACKTRACE").unwrap_or("0".to_owned());
@@ -147,6 +178,12 @@ pub fn env_yes() -> bool {
 	yes == "1"
 }
 
+/// Returns the `ARGON_DAEMON` environment variable,
+/// pointing to the log directory of the daemon
+pub fn env_daemon() -> Option<PathBuf> {
+	env::var_os("ARGON_DAEMON").map(PathBuf::from)
+}
+
 /// Return line of code count from snapshot's properties
 pub fn count_loc_from_properties(properties: &Properties) -> usize {
 	let mut loc = 0;
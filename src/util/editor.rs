@@ -0,0 +1,239 @@
+use log::trace;
+use serde::Serialize;
+use std::{
+	fmt::{self, Display, Formatter},
+	io,
+	path::{Path, PathBuf},
+	process::Command,
+};
+
+#[cfg(windows)]
+use std::env;
+
+use crate::{config::Config, ext::PathExt};
+
+/// Line files are opened at when there is no better one
+const DEFAULT_LINE: u32 = 1;
+
+/// Detected code editor CLI
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditorCli {
+	VsCode(PathBuf),
+	/// CLI path and extensions directory
+	Cursor(PathBuf, PathBuf),
+}
+
+impl EditorCli {
+	/// Finds VS Code or Cursor in their standard install locations
+	/// on Windows, falls back to `code` from PATH everywhere else
+	pub fn detect() -> Self {
+		#[cfg(windows)]
+		if let Some(cli) = find_windows() {
+			return cli;
+		}
+
+		trace!("Using 'code' from PATH");
+
+		EditorCli::VsCode(PathBuf::from("code"))
+	}
+
+	pub fn path(&self) -> &Path {
+		match self {
+			EditorCli::VsCode(path) => path,
+			EditorCli::Cursor(path, _) => path,
+		}
+	}
+
+	pub fn extensions_dir(&self) -> Option<&Path> {
+		match self {
+			EditorCli::VsCode(_) => None,
+			EditorCli::Cursor(_, extensions_dir) => Some(extensions_dir),
+		}
+	}
+
+	/// Returns command running the CLI, Cursor
+	/// has to be pointed to its own extensions
+	pub fn command(&self) -> Command {
+		let mut command = Command::new(self.path());
+
+		if let Some(extensions_dir) = self.extensions_dir() {
+			command.arg("--extensions-dir").arg(extensions_dir);
+		}
+
+		command
+	}
+}
+
+/// Reason the file could not be opened, sent to clients as is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "error", rename_all = "camelCase")]
+pub enum OpenError {
+	NoInstance,
+	NoSource,
+	#[serde(rename_all = "camelCase")]
+	EditorNotFound {
+		command: String,
+	},
+	#[serde(rename_all = "camelCase")]
+	Failed {
+		message: String,
+	},
+}
+
+impl Display for OpenError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			OpenError::NoInstance => write!(f, "No instance with such ID or path"),
+			OpenError::NoSource => write!(f, "Instance has no source file that could be opened"),
+			OpenError::EditorNotFound { command } => write!(f, "Editor {} was not found", command),
+			OpenError::Failed { message } => write!(f, "Failed to open the editor: {}", message),
+		}
+	}
+}
+
+impl std::error::Error for OpenError {}
+
+/// Builds the command opening the file at the line. `template` is the
+/// `editor_command` setting with `{file}` and `{line}` placeholders, each
+/// of its words becomes a separate argument so paths can contain spaces.
+/// VS Code or Cursor is used when the template is empty
+pub fn command(file: &Path, line: Option<u32>, template: &str) -> Result<Command, OpenError> {
+	let line = line.unwrap_or(DEFAULT_LINE).max(1).to_string();
+
+	if template.trim().is_empty() {
+		let mut command = EditorCli::detect().command();
+		command.arg("--goto").arg(format!("{}:{}", file.to_string(), line));
+
+		return Ok(command);
+	}
+
+	let mut words = template
+		.split_whitespace()
+		.map(|word| word.replace("{file}", &file.to_string()).replace("{line}", &line));
+
+	let program = words.next().ok_or_else(|| OpenError::EditorNotFound {
+		command: template.to_owned(),
+	})?;
+
+	let mut command = Command::new(program);
+	command.args(words);
+
+	// Editors that take the file as the last argument do not need the placeholder
+	if !template.contains("{file}") {
+		command.arg(file);
+	}
+
+	Ok(command)
+}
+
+/// Opens the file in the editor configured with `editor_command`
+pub fn open(file: &Path, line: Option<u32>) -> Result<(), OpenError> {
+	let template = Config::new().editor_command.clone();
+
+	open_with(file, line, &template, |command| command.spawn().map(|_| ()))
+}
+
+/// Same as `open` but the command is run by `launch`
+pub fn open_with(
+	file: &Path,
+	line: Option<u32>,
+	template: &str,
+	launch: impl FnOnce(&mut Command) -> io::Result<()>,
+) -> Result<(), OpenError> {
+	let mut command = command(file, line, template)?;
+
+	trace!("Opening {} with {:?}", file.display(), command);
+
+	launch(&mut command).map_err(|err| match err.kind() {
+		io::ErrorKind::NotFound => OpenError::EditorNotFound {
+			command: command.get_program().to_string_lossy().into_owned(),
+		},
+		_ => OpenError::Failed {
+			message: err.to_string(),
+		},
+	})
+}
+
+// Finds VS Code executable first, Cursor otherwise
+#[cfg(windows)]
+fn find_windows() -> Option<EditorCli> {
+	trace!("Attempting to find VS Code or Cursor executable on Windows");
+
+	// --- 1. Check for VS Code ---
+
+	// 1a. Check LOCALAPPDATA (User Install)
+	if let Ok(local_app_data) = env::var("LOCALAPPDATA") {
+		let vscode_user_path = PathBuf::from(local_app_data)
+			.join("Programs")
+			.join("Microsoft VS Code")
+			.join("bin")
+			.join("code.cmd"); // Prefer .cmd
+		if vscode_user_path.exists() {
+			trace!("Found VS Code (user) at: {}", vscode_user_path.display());
+			return Some(EditorCli::VsCode(vscode_user_path));
+		}
+		let vscode_user_path_exe = vscode_user_path.with_extension("exe");
+		if vscode_user_path_exe.exists() {
+			trace!("Found VS Code (user, exe) at: {}", vscode_user_path_exe.display());
+			return Some(EditorCli::VsCode(vscode_user_path_exe));
+		}
+	}
+
+	// 1b. Check ProgramFiles (System Install)
+	if let Ok(program_files) = env::var("ProgramFiles") {
+		let vscode_system_path = PathBuf::from(program_files)
+			.join("Microsoft VS Code")
+			.join("bin")
+			.join("code.cmd");
+		if vscode_system_path.exists() {
+			trace!("Found VS Code (system) at: {}", vscode_system_path.display());
+			return Some(EditorCli::VsCode(vscode_system_path));
+		}
+		let vscode_system_path_exe = vscode_system_path.with_extension("exe");
+		if vscode_system_path_exe.exists() {
+			trace!("Found VS Code (system, exe) at: {}", vscode_system_path_exe.display());
+			return Some(EditorCli::VsCode(vscode_system_path_exe));
+		}
+	}
+
+	// --- 2. Check for Cursor (only if VS Code wasn't found) ---
+
+	// Get the user profile path for extensions directory
+	let user_profile = match env::var("USERPROFILE") {
+		Ok(path) => PathBuf::from(path),
+		Err(_) => {
+			trace!("Could not get USERPROFILE environment variable");
+			return None;
+		}
+	};
+
+	// Determine Cursor extensions directory
+	let cursor_extensions_dir = user_profile.join(".cursor").join("extensions");
+	trace!(
+		"Cursor extensions directory would be at: {}",
+		cursor_extensions_dir.display()
+	);
+
+	if let Ok(local_app_data) = env::var("LOCALAPPDATA") {
+		let cursor_path = PathBuf::from(local_app_data)
+			.join("Programs")
+			.join("cursor") // Cursor specific path
+			.join("resources")
+			.join("app")
+			.join("bin")
+			.join("cursor.cmd"); // Prefer cursor.cmd
+		if cursor_path.exists() {
+			trace!("Found Cursor at: {}", cursor_path.display());
+			return Some(EditorCli::Cursor(cursor_path, cursor_extensions_dir));
+		}
+		let cursor_path_exe = cursor_path.with_extension("exe");
+		if cursor_path_exe.exists() {
+			trace!("Found Cursor (exe) at: {}", cursor_path_exe.display());
+			return Some(EditorCli::Cursor(cursor_path_exe, cursor_extensions_dir));
+		}
+	}
+
+	// --- 3. Fallback ---
+	trace!("Neither VS Code nor Cursor found in standard locations, will rely on PATH lookup for 'code'");
+	None // Indicate we didn't find either in standard locations
+}
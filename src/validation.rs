@@ -0,0 +1,209 @@
+use path_clean::PathClean;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	fmt::{self, Display, Formatter},
+	fs, iter,
+	path::{Path, PathBuf},
+};
+
+use crate::{
+	logger,
+	middleware::Middleware,
+	project::{LayoutPreference, Project, ProjectNode, ProjectPath},
+	resolution::UnresolvedValue,
+};
+
+/// How serious a problem is, only errors fail `argon validate`
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+	Error,
+	Warning,
+}
+
+impl Display for Severity {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Severity::Error => write!(f, "error"),
+			Severity::Warning => write!(f, "warning"),
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Problem {
+	pub severity: Severity,
+	/// Node the problem belongs to, e.g. `tree/ReplicatedStorage`,
+	/// empty when the whole project is affected
+	pub node: String,
+	pub message: String,
+}
+
+impl Problem {
+	fn error(node: &str, message: impl Into<String>) -> Self {
+		Self {
+			severity: Severity::Error,
+			node: node.to_owned(),
+			message: message.into(),
+		}
+	}
+
+	fn warning(node: &str, message: impl Into<String>) -> Self {
+		Self {
+			severity: Severity::Warning,
+			node: node.to_owned(),
+			message: message.into(),
+		}
+	}
+}
+
+/// Whether any of the problems is an error
+pub fn has_errors(problems: &[Problem]) -> bool {
+	problems.iter().any(|problem| problem.severity == Severity::Error)
+}
+
+/// Validates the project file as it is on disk
+pub fn validate_file(project_path: &Path) -> Vec<Problem> {
+	match fs::read_to_string(project_path) {
+		Ok(contents) => validate(&contents, project_path),
+		Err(err) => vec![Problem::error(
+			"",
+			format!("Failed to read {}: {}", project_path.display(), err),
+		)],
+	}
+}
+
+/// Validates project contents as if they were saved at `project_path`, problems
+/// that stop the project from loading are returned alone as nothing else can be checked
+pub fn validate(contents: &str, project_path: &Path) -> Vec<Problem> {
+	let project = match Project::load_unsaved(contents, project_path) {
+		Ok(project) => project,
+		Err(err) => return vec![Problem::error("", logger::strip_ansi(&format!("{:#}", err)))],
+	};
+
+	let mut problems = vec![];
+	let mut paths = HashMap::new();
+
+	validate_node(&project, &project.node, "tree", &mut paths, &mut problems);
+	validate_layout_preferences(&project, &mut problems);
+
+	problems
+}
+
+/// Resolves `$path` of a node the same way as the project middleware does
+pub fn resolve_path(project: &Project, path: &ProjectPath) -> PathBuf {
+	project.path.with_file_name(path.path()).clean()
+}
+
+fn validate_node(
+	project: &Project,
+	node: &ProjectNode,
+	node_path: &str,
+	paths: &mut HashMap<PathBuf, String>,
+	problems: &mut Vec<Problem>,
+) {
+	if node.implicit {
+		return;
+	}
+
+	if node.class_name.is_some() && node.path.is_some() {
+		problems.push(Problem::error(
+			node_path,
+			"$className and $path cannot be set at the same time",
+		));
+	}
+
+	if let Some(class) = &node.class_name {
+		if !rbx_reflection_database::get().classes.contains_key(class.as_str()) {
+			problems.push(Problem::error(node_path, format!("Unknown class {}", class)));
+		} else {
+			for (property, value) in &node.properties {
+				if let UnresolvedValue::File(_) = value {
+					continue;
+				}
+
+				if let Err(err) = value.clone().resolve(class, property, None) {
+					problems.push(Problem::error(
+						node_path,
+						format!("Invalid property {}: {}", property, err),
+					));
+				}
+			}
+		}
+	}
+
+	if let Some(path) = &node.path {
+		let resolved = resolve_path(project, path);
+
+		if !resolved.exists() && matches!(path, ProjectPath::Required(_)) {
+			problems.push(Problem::error(
+				node_path,
+				format!(
+					"Path {} does not exist, use {{ \"optional\": ... }} if it is created later",
+					path.path().display()
+				),
+			));
+		}
+
+		if !resolved.starts_with(&project.workspace_dir) {
+			problems.push(Problem::warning(
+				node_path,
+				format!("Path {} is outside of the workspace", path.path().display()),
+			));
+		}
+
+		match paths.get(&resolved) {
+			Some(other) => problems.push(Problem::warning(
+				node_path,
+				format!(
+					"Path {} is also used by {}, only one of them can be synced back unless it sets $primary",
+					path.path().display(),
+					other
+				),
+			)),
+			None => {
+				paths.insert(resolved, node_path.to_owned());
+			}
+		}
+	}
+
+	for (name, child) in &node.tree {
+		validate_node(project, child, &format!("{}/{}", node_path, name), paths, problems);
+	}
+}
+
+fn validate_layout_preferences(project: &Project, problems: &mut Vec<Problem>) {
+	let database = rbx_reflection_database::get();
+
+	for (class, preference) in &project.layout_preferences {
+		if !database.classes.contains_key(class.as_str()) {
+			problems.push(Problem::warning(
+				"layoutPreferences",
+				format!("Unknown class {}, its layout preference is never used", class),
+			));
+			continue;
+		}
+
+		if *preference != LayoutPreference::File {
+			continue;
+		}
+
+		// Preference also applies to subclasses, e.g. `LuaSourceContainer` covers all scripts
+		let has_file = database.classes.keys().any(|subclass| {
+			Middleware::from_class(subclass, None).is_some()
+				&& iter::successors(Some(subclass.as_ref()), |class: &&str| {
+					database.classes.get(*class)?.superclass.as_deref()
+				})
+				.any(|superclass| superclass == class)
+		});
+
+		if !has_file {
+			problems.push(Problem::warning(
+				"layoutPreferences",
+				format!("{} is always written as a folder, file layout has no effect", class),
+			));
+		}
+	}
+}
@@ -1,11 +1,12 @@
-use crossbeam_channel::Receiver;
+use crossbeam_channel::{Receiver, Sender};
 use log::trace;
 use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use notify_debouncer_full::{new_debouncer, DebouncedEvent, Debouncer, FileIdMap};
 use std::{
+	fs,
 	io::{self, Result},
-	path::Path,
-	sync::{mpsc, Arc, RwLock},
+	path::{Path, PathBuf},
+	sync::{mpsc, Arc, Mutex, RwLock},
 	thread::Builder,
 	time::{Duration, Instant},
 };
@@ -17,13 +18,10 @@ use notify::event::DataChange;
 use notify::event::ModifyKind;
 
 #[cfg(target_os = "linux")]
-use {
-	notify::event::{AccessKind, AccessMode, RenameMode},
-	std::path::PathBuf,
-};
+use notify::event::{AccessKind, AccessMode, RenameMode};
 
 use super::VfsEvent;
-use crate::constants::SYNCBACK_DEBOUNCE_TIME;
+use crate::{constants::SYNCBACK_DEBOUNCE_TIME, lock};
 
 #[cfg(target_os = "linux")]
 const DEBOUNCE_TIME: Duration = Duration::from_micros(500);
@@ -40,21 +38,102 @@ struct DebounceContext {
 	path: PathBuf,
 }
 
-pub struct VfsDebouncer {
-	inner: Debouncer<RecommendedWatcher, FileIdMap>,
-	pause_state: Arc<RwLock<(bool, Instant)>>,
-	receiver: Receiver<VfsEvent>,
+/// OS watcher shared by VFS backends, e.g. of all members of a workspace.
+/// Every directory is watched once by its canonical path no matter how many
+/// backends watch it and how, events are delivered to every backend watching
+/// the path, translated back to the path the backend watches it with
+pub struct WatchHub {
+	inner: Mutex<Debouncer<RecommendedWatcher, FileIdMap>>,
+	state: Arc<Mutex<HubState>>,
 }
 
-impl VfsDebouncer {
+/// Whether the subscriber is paused and since when
+type PauseState = Arc<RwLock<(bool, Instant)>>;
+
+#[derive(Default)]
+struct HubState {
+	subscribers: Vec<Subscriber>,
+	last_id: usize,
+}
+
+struct Subscriber {
+	id: usize,
+	sender: Sender<VfsEvent>,
+	pause_state: PauseState,
+	watches: Vec<HubWatch>,
+}
+
+struct HubWatch {
+	/// Path the subscriber watches
+	path: PathBuf,
+	canonical: PathBuf,
+	recursive: bool,
+}
+
+impl HubWatch {
+	/// Returns event path translated to the watched path if it is covered by the watch
+	fn translate(&self, path: &Path) -> Option<PathBuf> {
+		let relative = path.strip_prefix(&self.canonical).ok()?;
+
+		if relative.as_os_str().is_empty() {
+			Some(self.path.clone())
+		} else if self.recursive || relative.components().count() == 1 {
+			Some(self.path.join(relative))
+		} else {
+			None
+		}
+	}
+}
+
+impl HubState {
+	/// Returns how the canonical path has to be watched, `None` if it is not watched by anyone
+	fn mode(&self, canonical: &Path) -> Option<bool> {
+		self.subscribers
+			.iter()
+			.flat_map(|subscriber| &subscriber.watches)
+			.filter(|watch| watch.canonical == canonical)
+			.map(|watch| watch.recursive)
+			.reduce(|a, b| a || b)
+	}
+
+	fn dispatch(&self, event: &VfsEvent) {
+		for subscriber in &self.subscribers {
+			let (is_paused, timestamp) = *subscriber.pause_state.read().unwrap();
+
+			if is_paused || timestamp.elapsed() < SYNCBACK_DEBOUNCE_TIME {
+				continue;
+			}
+
+			let mut paths: Vec<PathBuf> = subscriber
+				.watches
+				.iter()
+				.filter_map(|watch| watch.translate(event.path()))
+				.collect();
+
+			paths.sort();
+			paths.dedup();
+
+			for path in paths {
+				let event = match event {
+					VfsEvent::Create(_) => VfsEvent::Create(path),
+					VfsEvent::Delete(_) => VfsEvent::Delete(path),
+					VfsEvent::Write(_) => VfsEvent::Write(path),
+				};
+
+				subscriber.sender.send(event).ok();
+			}
+		}
+	}
+}
+
+impl WatchHub {
 	pub fn new() -> Self {
 		let (inner_sender, inner_receiver) = mpsc::channel();
-		let (sender, receiver) = crossbeam_channel::unbounded();
 
 		let debouncer = new_debouncer(Duration::from_millis(100), None, inner_sender, false).unwrap();
 
-		let pause_state = Arc::new(RwLock::new((false, Instant::now())));
-		let local_pause_state = pause_state.clone();
+		let state = Arc::new(Mutex::new(HubState::default()));
+		let local_state = state.clone();
 
 		Builder::new()
 			.name("debouncer".to_owned())
@@ -66,23 +145,21 @@ impl VfsDebouncer {
 				};
 
 				for events in inner_receiver {
-					let (is_paused, timestamp) = *local_pause_state.read().unwrap();
-
-					if is_paused || timestamp.elapsed() < SYNCBACK_DEBOUNCE_TIME {
+					let Ok(events) = events else {
 						continue;
-					}
+					};
 
-					for event in events.unwrap() {
+					for event in events {
 						trace!("Debouncing event, paths: {:?}, kind: {:?}", event.paths, event.kind);
 
 						#[cfg(not(target_os = "linux"))]
-						if let Some(event) = debounce(&event) {
-							sender.send(event).unwrap();
-						}
+						let event = debounce(&event);
 
 						#[cfg(target_os = "linux")]
-						if let Some(event) = debounce(&event, &mut context) {
-							sender.send(event).unwrap();
+						let event = debounce(&event, &mut context);
+
+						if let Some(event) = event {
+							lock!(local_state).dispatch(&event);
 						}
 					}
 				}
@@ -90,31 +167,194 @@ impl VfsDebouncer {
 			.unwrap();
 
 		Self {
-			inner: debouncer,
-			pause_state,
-			receiver,
+			inner: Mutex::new(debouncer),
+			state,
 		}
 	}
 
-	pub fn watch(&mut self, path: &Path, recursive: bool) -> Result<()> {
-		let recursive = if recursive {
-			RecursiveMode::Recursive
-		} else {
-			RecursiveMode::NonRecursive
+	/// Delivers the event to subscribers as if it came from the OS,
+	/// its path has to be canonical like paths of OS events are
+	pub fn dispatch(&self, event: &VfsEvent) {
+		lock!(self.state).dispatch(event)
+	}
+
+	/// Returns canonical paths watched by the OS, whether they
+	/// are watched recursively and how many watches they serve
+	pub fn watched(&self) -> Vec<(PathBuf, bool, usize)> {
+		let state = lock!(self.state);
+		let mut watched: Vec<(PathBuf, bool, usize)> = vec![];
+
+		for watch in state.subscribers.iter().flat_map(|subscriber| &subscriber.watches) {
+			match watched.iter_mut().find(|(path, _, _)| *path == watch.canonical) {
+				Some((_, recursive, count)) => {
+					*recursive |= watch.recursive;
+					*count += 1;
+				}
+				None => watched.push((watch.canonical.clone(), watch.recursive, 1)),
+			}
+		}
+
+		watched.sort();
+		watched
+	}
+
+	fn subscribe(&self) -> (usize, PauseState, Receiver<VfsEvent>) {
+		let (sender, receiver) = crossbeam_channel::unbounded();
+		let pause_state = Arc::new(RwLock::new((false, Instant::now())));
+
+		let mut state = lock!(self.state);
+		state.last_id += 1;
+
+		let id = state.last_id;
+
+		state.subscribers.push(Subscriber {
+			id,
+			sender,
+			pause_state: pause_state.clone(),
+			watches: vec![],
+		});
+
+		(id, pause_state, receiver)
+	}
+
+	fn unsubscribe(&self, id: usize) {
+		self.release(id, |_| true);
+		lock!(self.state).subscribers.retain(|subscriber| subscriber.id != id);
+	}
+
+	fn watch(&self, id: usize, path: &Path, recursive: bool) -> Result<()> {
+		let canonical = fs::canonicalize(path)?;
+		let mut state = lock!(self.state);
+
+		let previous = state.mode(&canonical);
+
+		let Some(subscriber) = state.subscribers.iter_mut().find(|subscriber| subscriber.id == id) else {
+			return Ok(());
 		};
 
-		self.inner.watcher().watch(path, recursive).map_err(map_error)?;
-		self.inner.cache().add_root(path, recursive);
+		match subscriber.watches.iter_mut().find(|watch| watch.path == path) {
+			Some(watch) => watch.recursive |= recursive,
+			None => subscriber.watches.push(HubWatch {
+				path: path.to_owned(),
+				canonical: canonical.clone(),
+				recursive,
+			}),
+		}
+
+		let current = state.mode(&canonical);
+
+		if current != previous {
+			let result = self.apply(&canonical, previous, current);
+
+			// Watch is not kept if the OS refused it
+			if result.is_err() && previous.is_none() {
+				for subscriber in &mut state.subscribers {
+					subscriber.watches.retain(|watch| watch.canonical != canonical);
+				}
+			}
+
+			result?;
+		}
 
 		Ok(())
 	}
 
-	pub fn unwatch(&mut self, path: &Path) -> Result<()> {
-		self.inner.watcher().unwatch(path).map_err(map_error)?;
-		self.inner.cache().remove_root(path);
+	/// Removes watches of the path and its descendants
+	fn unwatch(&self, id: usize, path: &Path) -> Result<()> {
+		self.release(id, |watch| watch.path.starts_with(path));
+
+		Ok(())
+	}
+
+	/// Removes matching watches of the subscriber, paths
+	/// no longer watched by anyone are unwatched in the OS
+	fn release(&self, id: usize, filter: impl Fn(&HubWatch) -> bool) {
+		let mut state = lock!(self.state);
+
+		let mut released: Vec<(PathBuf, Option<bool>)> = state
+			.subscribers
+			.iter()
+			.filter(|subscriber| subscriber.id == id)
+			.flat_map(|subscriber| &subscriber.watches)
+			.filter(|watch| filter(watch))
+			.map(|watch| (watch.canonical.clone(), state.mode(&watch.canonical)))
+			.collect();
+
+		released.sort();
+		released.dedup();
+
+		for subscriber in state.subscribers.iter_mut().filter(|subscriber| subscriber.id == id) {
+			subscriber.watches.retain(|watch| !filter(watch));
+		}
+
+		for (canonical, previous) in released {
+			let current = state.mode(&canonical);
+
+			if current != previous {
+				self.apply(&canonical, previous, current).ok();
+			}
+		}
+	}
+
+	/// Updates how the OS watches the canonical path
+	fn apply(&self, canonical: &Path, previous: Option<bool>, current: Option<bool>) -> Result<()> {
+		let mut inner = lock!(self.inner);
+
+		if previous.is_some() {
+			trace!("Unwatching {} in the OS", canonical.display());
+
+			inner.watcher().unwatch(canonical).ok();
+			inner.cache().remove_root(canonical);
+		}
+
+		if let Some(recursive) = current {
+			trace!("Watching {} in the OS (recursive: {})", canonical.display(), recursive);
+
+			let mode = if recursive {
+				RecursiveMode::Recursive
+			} else {
+				RecursiveMode::NonRecursive
+			};
+
+			inner.watcher().watch(canonical, mode).map_err(map_error)?;
+			inner.cache().add_root(canonical, mode);
+		}
 
 		Ok(())
 	}
+}
+
+/// Watches paths of a single VFS backend through a `WatchHub`
+pub struct VfsDebouncer {
+	hub: Arc<WatchHub>,
+	id: usize,
+	pause_state: Arc<RwLock<(bool, Instant)>>,
+	receiver: Receiver<VfsEvent>,
+}
+
+impl VfsDebouncer {
+	pub fn new() -> Self {
+		Self::with_hub(Arc::new(WatchHub::new()))
+	}
+
+	pub fn with_hub(hub: Arc<WatchHub>) -> Self {
+		let (id, pause_state, receiver) = hub.subscribe();
+
+		Self {
+			hub,
+			id,
+			pause_state,
+			receiver,
+		}
+	}
+
+	pub fn watch(&mut self, path: &Path, recursive: bool) -> Result<()> {
+		self.hub.watch(self.id, path, recursive)
+	}
+
+	pub fn unwatch(&mut self, path: &Path) -> Result<()> {
+		self.hub.unwatch(self.id, path)
+	}
 
 	pub fn pause(&mut self) {
 		*self.pause_state.write().unwrap() = (true, Instant::now());
@@ -129,6 +369,12 @@ impl VfsDebouncer {
 	}
 }
 
+impl Drop for VfsDebouncer {
+	fn drop(&mut self) {
+		self.hub.unsubscribe(self.id);
+	}
+}
+
 fn map_error(err: notify::Error) -> io::Error {
 	match err.kind {
 		notify::ErrorKind::Io(err) => err,
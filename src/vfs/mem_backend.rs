@@ -1,4 +1,4 @@
-use crossbeam_channel::Receiver;
+use crossbeam_channel::{Receiver, Sender};
 use std::{
 	collections::HashMap,
 	io::{Error, ErrorKind, Result},
@@ -15,19 +15,26 @@ pub enum VfsEntry {
 
 pub struct MemBackend {
 	inner: HashMap<PathBuf, VfsEntry>,
+	sender: Sender<VfsEvent>,
 	receiver: Receiver<VfsEvent>,
 }
 
 impl MemBackend {
 	pub fn new() -> Self {
-		let (_sender, receiver) = crossbeam_channel::unbounded();
+		let (sender, receiver) = crossbeam_channel::unbounded();
 
 		Self {
 			inner: HashMap::new(),
+			sender,
 			receiver,
 		}
 	}
 
+	/// Returns sender of synthetic events, nothing is sent automatically
+	pub fn sender(&self) -> Sender<VfsEvent> {
+		self.sender.clone()
+	}
+
 	pub fn get_entry(&self, path: &Path) -> Result<&VfsEntry> {
 		match self.inner.get(path) {
 			Some(entry) => Ok(entry),
@@ -61,6 +68,12 @@ impl VfsBackend for MemBackend {
 	}
 
 	fn write(&mut self, path: &Path, contents: &[u8]) -> Result<()> {
+		if !self.inner.contains_key(path) {
+			if let Some(VfsEntry::Directory(children)) = path.parent().and_then(|parent| self.inner.get_mut(parent)) {
+				children.push(path.to_owned());
+			}
+		}
+
 		let entry = self.inner.entry(path.to_owned()).or_insert(VfsEntry::File(vec![]));
 
 		match entry {
@@ -125,6 +138,10 @@ impl VfsBackend for MemBackend {
 			_ => {}
 		}
 
+		if let Some(VfsEntry::Directory(children)) = path.parent().and_then(|parent| self.inner.get_mut(parent)) {
+			children.retain(|child| child != path);
+		}
+
 		Ok(())
 	}
 
@@ -148,6 +165,11 @@ impl VfsBackend for MemBackend {
 		Ok(())
 	}
 
+	// Behaves like inotify so per-directory watching can be tested
+	fn watches_recursively(&self) -> bool {
+		false
+	}
+
 	fn pause(&mut self) {}
 
 	fn resume(&mut self) {}
@@ -1,16 +1,36 @@
 use crossbeam_channel::Receiver;
 use std::{
+	collections::HashMap,
 	io::Result,
 	path::{Path, PathBuf},
-	sync::Mutex,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Mutex,
+	},
+	thread,
+	time::SystemTime,
 };
 
-use self::{mem_backend::MemBackend, std_backend::StdBackend};
-use crate::lock;
+use self::{
+	debouncer::WatchHub,
+	mem_backend::MemBackend,
+	registry::WatchRegistry,
+	std_backend::StdBackend,
+	transaction::{Journal, Journals, Transaction, Undo},
+	undo_log::UndoLog,
+};
+use crate::{
+	core::{blobs, meta::IgnoreRule},
+	lock,
+};
 
 pub mod debouncer;
 pub mod mem_backend;
+pub mod registry;
 pub mod std_backend;
+pub mod transaction;
+pub mod undo_log;
+pub mod verify;
 
 #[derive(Debug, Clone)]
 pub enum VfsEvent {
@@ -24,6 +44,11 @@ pub trait VfsBackend: Send {
 	fn read_to_string(&self, path: &Path) -> Result<String>;
 	fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>>;
 
+	/// Content hash of the file, backends can stream it instead of reading the whole file
+	fn hash(&self, path: &Path) -> Result<String> {
+		Ok(blobs::digest_bytes(&self.read(path)?))
+	}
+
 	fn write(&mut self, path: &Path, contents: &[u8]) -> Result<()>;
 	fn create_dir(&mut self, path: &Path) -> Result<()>;
 	fn rename(&mut self, from: &Path, to: &Path) -> Result<()>;
@@ -33,8 +58,21 @@ pub trait VfsBackend: Send {
 	fn is_dir(&self, path: &Path) -> bool;
 	fn is_file(&self, path: &Path) -> bool;
 
+	/// Last modification time, `None` if the backend does not track it
+	fn modified(&self, _path: &Path) -> Option<SystemTime> {
+		None
+	}
+
 	fn watch(&mut self, path: &Path, recursive: bool) -> Result<()>;
 	fn unwatch(&mut self, path: &Path) -> Result<()>;
+	/// Whether recursive watches also cover directories created later,
+	/// otherwise `WatchRegistry` watches every directory separately
+	fn watches_recursively(&self) -> bool;
+	/// Whether the watcher never drops events, so changes
+	/// it missed don't have to be looked for in the background
+	fn reliable_events(&self) -> bool {
+		false
+	}
 	fn pause(&mut self);
 	fn resume(&mut self);
 
@@ -51,67 +89,324 @@ impl VfsEvent {
 
 pub struct Vfs {
 	inner: Mutex<Box<dyn VfsBackend>>,
+	journals: Mutex<Journals>,
+	watches: Mutex<WatchRegistry>,
+	verify_writes: AtomicBool,
+	/// Content hashes of verified writes, dropped once the file changes
+	written: Mutex<HashMap<PathBuf, String>>,
+	undo_log: Mutex<Option<Arc<UndoLog>>>,
 }
 
 impl Vfs {
 	pub fn new(watch: bool) -> Self {
 		Self {
 			inner: Mutex::new(Box::new(StdBackend::new(watch))),
+			journals: Mutex::new(HashMap::new()),
+			watches: Mutex::new(WatchRegistry::new()),
+			verify_writes: AtomicBool::new(false),
+			written: Mutex::new(HashMap::new()),
+			undo_log: Mutex::new(None),
+		}
+	}
+
+	/// Creates VFS that shares the OS watcher with other ones, see `WatchHub`
+	pub fn with_hub(hub: Arc<WatchHub>) -> Self {
+		Self {
+			inner: Mutex::new(Box::new(StdBackend::with_hub(hub))),
+			journals: Mutex::new(HashMap::new()),
+			watches: Mutex::new(WatchRegistry::new()),
+			verify_writes: AtomicBool::new(false),
+			written: Mutex::new(HashMap::new()),
+			undo_log: Mutex::new(None),
 		}
 	}
 
 	pub fn new_virtual() -> Self {
 		Self {
 			inner: Mutex::new(Box::new(MemBackend::new())),
+			journals: Mutex::new(HashMap::new()),
+			watches: Mutex::new(WatchRegistry::new()),
+			verify_writes: AtomicBool::new(false),
+			written: Mutex::new(HashMap::new()),
+			undo_log: Mutex::new(None),
+		}
+	}
+
+	pub fn from_backend(backend: impl VfsBackend + 'static) -> Self {
+		Self {
+			inner: Mutex::new(Box::new(backend)),
+			journals: Mutex::new(HashMap::new()),
+			watches: Mutex::new(WatchRegistry::new()),
+			verify_writes: AtomicBool::new(false),
+			written: Mutex::new(HashMap::new()),
+			undo_log: Mutex::new(None),
 		}
 	}
 
+	/// Returns the path that holds contents of the file for the current
+	/// thread, which is a temporary file if it has a staged write
+	fn resolve(&self, path: &Path) -> PathBuf {
+		let journals = lock!(self.journals);
+
+		transaction::staged(&journals, path).unwrap_or(path).to_owned()
+	}
+
 	pub fn read(&self, path: &Path) -> Result<Vec<u8>> {
-		lock!(self.inner).read(path)
+		let path = self.resolve(path);
+		lock!(self.inner).read(&path)
 	}
 
 	pub fn read_to_string(&self, path: &Path) -> Result<String> {
-		lock!(self.inner).read_to_string(path)
+		let path = self.resolve(path);
+		lock!(self.inner).read_to_string(&path)
 	}
 
+	/// Entries are sorted as the order of the file system is not stable,
+	/// files staged by open transactions are listed under their final paths
 	pub fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
-		lock!(self.inner).read_dir(path)
+		let staged = transaction::staged_paths(&lock!(self.journals));
+
+		let mut paths = lock!(self.inner).read_dir(path)?;
+		paths.retain(|path| !transaction::is_temp(path));
+
+		for staged in staged {
+			if staged.parent() == Some(path) && !paths.contains(&staged) {
+				paths.push(staged);
+			}
+		}
+
+		paths.sort();
+
+		Ok(paths)
 	}
 
 	pub fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
-		lock!(self.inner).write(path, contents)
+		let written = self.journaled(
+			"write",
+			path,
+			|backend| Undo::write(backend, path),
+			|journals, backend| transaction::write(journals, path, contents, backend),
+			|backend| backend.write(path, contents).map(|_| path.to_owned()),
+		)?;
+
+		if !self.verify_writes.load(Ordering::Relaxed) {
+			return Ok(());
+		}
+
+		let expected = blobs::digest_bytes(contents);
+
+		verify::verify(
+			path,
+			&expected,
+			|| lock!(self.inner).hash(&written),
+			|| lock!(self.inner).write(&written, contents),
+		)?;
+
+		lock!(self.written).insert(path.to_owned(), expected);
+
+		Ok(())
+	}
+
+	/// Enables reading every written file back, see `verify::verify`
+	pub fn set_verify_writes(&self, verify: bool) {
+		self.verify_writes.store(verify, Ordering::Relaxed);
+
+		if !verify {
+			lock!(self.written).clear();
+		}
+	}
+
+	/// Returns content hash of the last verified write of the file,
+	/// unless it has been changed or removed since then
+	pub fn last_write_hash(&self, path: &Path) -> Option<String> {
+		lock!(self.written).get(path).cloned()
+	}
+
+	pub fn hash(&self, path: &Path) -> Result<String> {
+		let path = self.resolve(path);
+		lock!(self.inner).hash(&path)
 	}
 
 	pub fn create_dir(&self, path: &Path) -> Result<()> {
-		lock!(self.inner).create_dir(path)
+		// Missing ancestors are created too, the topmost one is tracked so all of them get watched
+		let created = path
+			.ancestors()
+			.take_while(|path| !path.as_os_str().is_empty() && !self.exists(path))
+			.last()
+			.unwrap_or(path)
+			.to_owned();
+
+		self.journaled(
+			"create directory",
+			path,
+			|backend| Undo::create_dir(backend, path),
+			|journals, backend| transaction::create_dir(journals, path, backend),
+			|backend| backend.create_dir(path),
+		)?;
+
+		// Events of changes made by Argon are dropped while paused
+		self.track(&VfsEvent::Create(created));
+
+		Ok(())
 	}
 
 	pub fn rename(&self, from: &Path, to: &Path) -> Result<()> {
-		lock!(self.inner).rename(from, to)
+		self.journaled(
+			"move",
+			from,
+			|backend| Undo::rename(backend, from, to),
+			|journals, backend| transaction::rename(journals, from, to, backend),
+			|backend| backend.rename(from, to),
+		)?;
+
+		self.track(&VfsEvent::Delete(from.to_owned()));
+		self.track(&VfsEvent::Create(to.to_owned()));
+
+		Ok(())
 	}
 
 	pub fn remove(&self, path: &Path) -> Result<()> {
-		lock!(self.inner).remove(path)
+		self.journaled(
+			"remove",
+			path,
+			|backend| Undo::remove(backend, path),
+			|journals, backend| transaction::remove(journals, path, backend),
+			|backend| backend.remove(path),
+		)?;
+
+		self.track(&VfsEvent::Delete(path.to_owned()));
+
+		Ok(())
+	}
+
+	/// Starts a transaction, see `Transaction` for details
+	pub fn transaction(&self) -> Transaction<'_> {
+		Transaction::new(self)
+	}
+
+	/// Runs the operation, or its staged variant if there is a transaction open on the current
+	/// thread, recording how to undo it first if the undo log is recording
+	fn journaled<T, U, S, O>(&self, step: &str, path: &Path, undo: U, staged: S, operation: O) -> Result<T>
+	where
+		U: FnOnce(&dyn VfsBackend) -> Result<Vec<Undo>>,
+		S: FnOnce(&mut [Journal], &mut dyn VfsBackend) -> Result<T>,
+		O: FnOnce(&mut dyn VfsBackend) -> Result<T>,
+	{
+		let mut inner = lock!(self.inner);
+		let mut journals = lock!(self.journals);
+
+		let log = lock!(self.undo_log).clone().filter(|log| log.is_recording());
+
+		let Some(journals) = journals.get_mut(&thread::current().id()) else {
+			let Some(log) = log else {
+				return operation(&mut **inner);
+			};
+
+			let undo = undo(&**inner)?;
+			let result = operation(&mut **inner)?;
+			log.record(undo);
+
+			return Ok(result);
+		};
+
+		if log.is_some() {
+			let undo = undo(&**inner).map_err(|err| transaction::step_error(step, path, err))?;
+			journals.last_mut().unwrap().log(undo);
+		}
+
+		staged(journals, &mut **inner).map_err(|err| transaction::step_error(step, path, err))
+	}
+
+	/// Records operations made outside of failed transactions
+	/// to the log, so they can be undone later, see `UndoLog`
+	pub fn set_undo_log(&self, log: Option<Arc<UndoLog>>) {
+		*lock!(self.undo_log) = log;
+	}
+
+	/// Undoes operations made since the marker, returns their
+	/// number and events of the changes it made to the file system
+	pub fn rollback_to_marker(&self, name: &str) -> anyhow::Result<(usize, Vec<VfsEvent>)> {
+		let log = lock!(self.undo_log)
+			.clone()
+			.ok_or_else(|| anyhow::anyhow!("Undo log is not enabled"))?;
+
+		let mut events = vec![];
+
+		let count = log.rollback(name, |undo| {
+			let mut inner = lock!(self.inner);
+
+			for undo in undo.into_iter().rev() {
+				events.extend(undo.events(&**inner));
+				undo.apply(&mut **inner)?;
+			}
+
+			Ok(())
+		})?;
+
+		Ok((count, events))
 	}
 
 	pub fn exists(&self, path: &Path) -> bool {
-		lock!(self.inner).exists(path)
+		let path = self.resolve(path);
+		lock!(self.inner).exists(&path)
 	}
 
 	pub fn is_dir(&self, path: &Path) -> bool {
-		lock!(self.inner).is_dir(path)
+		let path = self.resolve(path);
+		lock!(self.inner).is_dir(&path)
 	}
 
 	pub fn is_file(&self, path: &Path) -> bool {
-		lock!(self.inner).is_file(path)
+		let path = self.resolve(path);
+		lock!(self.inner).is_file(&path)
+	}
+
+	pub fn modified(&self, path: &Path) -> Option<SystemTime> {
+		let path = self.resolve(path);
+		lock!(self.inner).modified(&path)
+	}
+
+	pub fn reliable_events(&self) -> bool {
+		lock!(self.inner).reliable_events()
 	}
 
+	/// Registers a path to watch, see `WatchRegistry` for details
 	pub fn watch(&self, path: &Path, recursive: bool) -> Result<()> {
-		lock!(self.inner).watch(path, recursive)
+		let mut inner = lock!(self.inner);
+		lock!(self.watches).register(&mut **inner, path, recursive)
 	}
 
 	pub fn unwatch(&self, path: &Path) -> Result<()> {
-		lock!(self.inner).unwatch(path)
+		let mut inner = lock!(self.inner);
+		lock!(self.watches).deregister(&mut **inner, path)
+	}
+
+	/// Updates watches after a change, has to be called for every received event
+	pub fn track(&self, event: &VfsEvent) {
+		self.forget_writes(event.path());
+
+		let mut inner = lock!(self.inner);
+		lock!(self.watches).track(&mut **inner, event)
+	}
+
+	/// Drops hashes of the path and its descendants that were changed outside of `write`
+	pub(super) fn forget_writes(&self, path: &Path) {
+		let mut written = lock!(self.written);
+
+		if !written.is_empty() {
+			written.retain(|written, _| !written.starts_with(path));
+		}
+	}
+
+	/// Replaces rules of paths that are never watched
+	pub fn set_ignore_rules(&self, rules: Vec<IgnoreRule>) {
+		let mut inner = lock!(self.inner);
+		lock!(self.watches).set_ignore_rules(&mut **inner, rules)
+	}
+
+	/// Returns paths watched by the backend and whether they are watched recursively
+	pub fn watched(&self) -> Vec<(PathBuf, bool)> {
+		lock!(self.watches).watched()
 	}
 
 	pub fn pause(&self) {
@@ -0,0 +1,171 @@
+use log::{trace, warn};
+use std::{
+	collections::BTreeMap,
+	io::Result,
+	path::{Path, PathBuf},
+};
+
+use super::{VfsBackend, VfsEvent};
+use crate::{constants::BLACKLISTED_PATHS, core::meta::IgnoreRule};
+
+/// Keeps track of what is watched and why, snapshots register roots and the
+/// registry decides what the backend actually watches. Backends that can't
+/// watch recursively (e.g. inotify) get every directory of recursive roots
+/// watched separately, including ones created later, which are registered
+/// from `Create` events. Ignored and blacklisted directories are never watched
+#[derive(Debug, Default)]
+pub struct WatchRegistry {
+	/// Paths registered by snapshots and whether they are watched recursively
+	roots: BTreeMap<PathBuf, bool>,
+	/// Paths watched by the backend and whether they are watched recursively
+	watched: BTreeMap<PathBuf, bool>,
+	ignore_rules: Vec<IgnoreRule>,
+}
+
+impl WatchRegistry {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn register(&mut self, backend: &mut dyn VfsBackend, path: &Path, recursive: bool) -> Result<()> {
+		// Snapshots register the same roots every time they are re-created
+		if self.roots.get(path).is_some_and(|watched| *watched || !recursive) && self.is_covered(path, false) {
+			return Ok(());
+		}
+
+		self.roots.insert(path.to_owned(), recursive);
+		self.apply(backend, path, recursive)
+	}
+
+	pub fn deregister(&mut self, backend: &mut dyn VfsBackend, path: &Path) -> Result<()> {
+		self.roots.retain(|root, _| !root.starts_with(path));
+		self.release(backend, path);
+
+		Ok(())
+	}
+
+	/// Updates watches after a change, directories created under recursive roots
+	/// get registered and removed ones are no longer watched
+	pub fn track(&mut self, backend: &mut dyn VfsBackend, event: &VfsEvent) {
+		match event {
+			VfsEvent::Create(path) => {
+				if !backend.is_dir(path) || !self.is_recursive(path) {
+					return;
+				}
+
+				if let Err(err) = self.apply(backend, path, true) {
+					warn!("Failed to watch created directory {}: {}", path.display(), err);
+				}
+			}
+			VfsEvent::Delete(path) => self.release(backend, path),
+			VfsEvent::Write(_) => {}
+		}
+	}
+
+	/// Replaces ignore rules, directories that became ignored are no longer
+	/// watched and ones that are not ignored anymore get watched again
+	pub fn set_ignore_rules(&mut self, backend: &mut dyn VfsBackend, rules: Vec<IgnoreRule>) {
+		if self.ignore_rules == rules {
+			return;
+		}
+
+		self.ignore_rules = rules;
+
+		let ignored: Vec<PathBuf> = self
+			.watched
+			.keys()
+			.filter(|path| self.is_ignored(path))
+			.cloned()
+			.collect();
+
+		for path in ignored {
+			self.release(backend, &path);
+		}
+
+		for (root, recursive) in self.roots.clone() {
+			if let Err(err) = self.apply(backend, &root, recursive) {
+				warn!("Failed to watch {}: {}", root.display(), err);
+			}
+		}
+	}
+
+	/// Returns paths watched by the backend and whether they are watched recursively
+	pub fn watched(&self) -> Vec<(PathBuf, bool)> {
+		self.watched
+			.iter()
+			.map(|(path, recursive)| (path.clone(), *recursive))
+			.collect()
+	}
+
+	fn apply(&mut self, backend: &mut dyn VfsBackend, path: &Path, recursive: bool) -> Result<()> {
+		if self.is_ignored(path) || !backend.exists(path) {
+			return Ok(());
+		}
+
+		let native = backend.watches_recursively();
+
+		if !self.is_covered(path, recursive && native) {
+			// Watches of descendants (or of the path itself but non-recursive)
+			// are redundant once the whole subtree is watched
+			if recursive && native {
+				self.release(backend, path);
+			}
+
+			trace!("Watching {} (recursive: {})", path.display(), recursive && native);
+
+			backend.watch(path, recursive && native)?;
+			self.watched.insert(path.to_owned(), recursive && native);
+		}
+
+		if recursive && !native && backend.is_dir(path) {
+			for child in backend.read_dir(path)? {
+				if backend.is_dir(&child) {
+					self.apply(backend, &child, true)?;
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	fn release(&mut self, backend: &mut dyn VfsBackend, path: &Path) {
+		let released: Vec<PathBuf> = self
+			.watched
+			.keys()
+			.filter(|watched| watched.starts_with(path))
+			.cloned()
+			.collect();
+
+		for path in released {
+			trace!("Unwatching {}", path.display());
+
+			// Paths that no longer exist are unwatched by the OS already
+			backend.unwatch(&path).ok();
+			self.watched.remove(&path);
+		}
+	}
+
+	fn is_covered(&self, path: &Path, recursive: bool) -> bool {
+		self.watched.iter().any(|(watched, watched_recursive)| {
+			if watched == path {
+				*watched_recursive || !recursive
+			} else {
+				*watched_recursive && path.starts_with(watched)
+			}
+		})
+	}
+
+	fn is_recursive(&self, path: &Path) -> bool {
+		self.roots
+			.iter()
+			.any(|(root, recursive)| *recursive && path.starts_with(root))
+	}
+
+	fn is_ignored(&self, path: &Path) -> bool {
+		path.components().any(|component| {
+			BLACKLISTED_PATHS
+				.iter()
+				.any(|blacklisted| component.as_os_str() == *blacklisted)
+		}) || self.ignore_rules.iter().any(|rule| rule.matches_with_dir(path))
+	}
+}
@@ -1,13 +1,22 @@
 use crossbeam_channel::Receiver;
 use std::{
+	borrow::Cow,
 	fs,
-	io::Result,
+	io::{Error, Result},
 	path::{Path, PathBuf},
+	sync::Arc,
+	time::SystemTime,
 };
 
-use super::{debouncer::VfsDebouncer, VfsBackend, VfsEvent};
+use super::{
+	debouncer::{VfsDebouncer, WatchHub},
+	verify, VfsBackend, VfsEvent,
+};
 use crate::config::Config;
 
+#[cfg(windows)]
+use crate::constants::MAX_PATH_LENGTH;
+
 pub struct StdBackend {
 	watching: bool,
 	debouncer: VfsDebouncer,
@@ -22,67 +31,155 @@ impl StdBackend {
 			watched_paths: Vec::new(),
 		}
 	}
+
+	/// Creates watching backend that shares the OS watcher with other backends
+	pub fn with_hub(hub: Arc<WatchHub>) -> Self {
+		Self {
+			watching: true,
+			debouncer: VfsDebouncer::with_hub(hub),
+			watched_paths: Vec::new(),
+		}
+	}
+}
+
+/// Returns path that should be passed to the OS, on Windows paths exceeding
+/// the legacy `MAX_PATH` limit (or all paths if `long_paths` setting is enabled)
+/// get the extended-length `\\?\` prefix, the original path is never modified
+/// so the prefix does not leak into the tree or project files
+#[cfg(windows)]
+pub fn os_path(path: &Path) -> Cow<'_, Path> {
+	use path_clean::PathClean;
+
+	let force = Config::new().long_paths;
+
+	if !force && path.is_absolute() && path.as_os_str().len() < MAX_PATH_LENGTH {
+		return Cow::Borrowed(path);
+	}
+
+	// Extended-length paths are not normalized by Windows,
+	// so they have to be absolute and without `.` or `..`
+	let absolute = match std::path::absolute(path) {
+		Ok(absolute) => absolute.clean(),
+		Err(_) => return Cow::Borrowed(path),
+	};
+
+	if !force && absolute.as_os_str().len() < MAX_PATH_LENGTH {
+		return Cow::Borrowed(path);
+	}
+
+	let prefixed = match absolute.to_str() {
+		Some(string) if string.starts_with(r"\\?\") => None,
+		Some(string) => match string.strip_prefix(r"\\") {
+			Some(unc) => Some(format!(r"\\?\UNC\{}", unc)),
+			None => Some(format!(r"\\?\{}", string)),
+		},
+		None => return Cow::Borrowed(path),
+	};
+
+	Cow::Owned(prefixed.map(PathBuf::from).unwrap_or(absolute))
+}
+
+#[cfg(not(windows))]
+pub fn os_path(path: &Path) -> Cow<'_, Path> {
+	Cow::Borrowed(path)
+}
+
+/// Copies permissions (mode bits on Unix, read-only attribute on Windows)
+/// of the file that is being replaced to the file that replaces it
+pub fn copy_permissions(from: &Path, to: &Path) -> Result<()> {
+	let permissions = fs::metadata(os_path(from))?.permissions();
+	fs::set_permissions(os_path(to), permissions)
+}
+
+/// Adds length of the offending path to the error
+/// as errors caused by too long paths are not descriptive
+fn with_length<T>(result: Result<T>, path: &Path) -> Result<T> {
+	result.map_err(|err| Error::new(err.kind(), format!("{} (path length: {})", err, path.as_os_str().len())))
 }
 
 impl VfsBackend for StdBackend {
 	fn read(&self, path: &Path) -> Result<Vec<u8>> {
-		fs::read(path)
+		fs::read(os_path(path))
 	}
 
 	fn read_to_string(&self, path: &Path) -> Result<String> {
-		fs::read_to_string(path)
+		fs::read_to_string(os_path(path))
 	}
 
 	fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
 		let mut paths = vec![];
 
-		for entry in fs::read_dir(path)? {
-			paths.push(entry?.path());
+		// Entries are joined with the original path so they stay in normal form
+		for entry in fs::read_dir(os_path(path))? {
+			paths.push(path.join(entry?.file_name()));
 		}
 
 		Ok(paths)
 	}
 
+	fn hash(&self, path: &Path) -> Result<String> {
+		verify::hash_file(path)
+	}
+
 	fn write(&mut self, path: &Path, contents: &[u8]) -> Result<()> {
-		fs::write(path, contents)
+		let os_path = os_path(path);
+
+		// Rewriting identical content would only bump modification time
+		// and invalidate build caches keyed on it, existing files keep
+		// their permissions as they are truncated instead of recreated
+		if Config::new().preserve_file_modes && fs::read(&os_path).is_ok_and(|current| current == contents) {
+			return Ok(());
+		}
+
+		with_length(fs::write(os_path, contents), path)
 	}
 
 	fn create_dir(&mut self, path: &Path) -> Result<()> {
-		fs::create_dir_all(path)
+		with_length(fs::create_dir_all(os_path(path)), path)
 	}
 
 	fn rename(&mut self, from: &Path, to: &Path) -> Result<()> {
-		fs::rename(from, to)
+		// Renaming keeps permissions and timestamps, unlike writing
+		// the file to the new path and removing the old one
+		with_length(fs::rename(os_path(from), os_path(to)), to)
 	}
 
 	fn remove(&mut self, path: &Path) -> Result<()> {
 		self.unwatch(path)?;
 
-		if Config::new().move_to_bin {
-			trash::delete(path).map_err(std::io::Error::other)
-		} else if path.is_dir() {
-			fs::remove_dir_all(path)
+		let result = if Config::new().move_to_bin {
+			trash::delete(path).map_err(Error::other)
+		} else if os_path(path).is_dir() {
+			fs::remove_dir_all(os_path(path))
 		} else {
-			fs::remove_file(path)
-		}
+			fs::remove_file(os_path(path))
+		};
+
+		with_length(result, path)
 	}
 
 	fn exists(&self, path: &Path) -> bool {
-		path.exists()
+		os_path(path).exists()
 	}
 
 	fn is_dir(&self, path: &Path) -> bool {
-		path.is_dir()
+		os_path(path).is_dir()
 	}
 
 	fn is_file(&self, path: &Path) -> bool {
-		path.is_file()
+		os_path(path).is_file()
+	}
+
+	fn modified(&self, path: &Path) -> Option<SystemTime> {
+		fs::metadata(os_path(path))
+			.and_then(|metadata| metadata.modified())
+			.ok()
 	}
 
 	fn watch(&mut self, path: &Path, recursive: bool) -> Result<()> {
 		let path = path.to_owned();
 
-		if !self.watching || self.watched_paths.iter().any(|p| path.starts_with(p)) {
+		if !self.watching || self.watched_paths.contains(&path) {
 			return Ok(());
 		}
 
@@ -112,6 +209,11 @@ impl VfsBackend for StdBackend {
 		Ok(())
 	}
 
+	fn watches_recursively(&self) -> bool {
+		// Other platforms watch every directory separately (inotify, kqueue)
+		cfg!(any(target_os = "windows", target_os = "macos"))
+	}
+
 	fn pause(&mut self) {
 		self.debouncer.pause()
 	}
@@ -0,0 +1,538 @@
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	io::{Error, Result},
+	marker::PhantomData,
+	path::{Path, PathBuf},
+	process,
+	sync::atomic::{AtomicUsize, Ordering},
+	thread::{self, ThreadId},
+};
+
+use super::{Vfs, VfsBackend, VfsEvent};
+use crate::lock;
+
+/// Reverse of a single operation made while the undo log is recording, see `UndoLog`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) enum Undo {
+	/// File or directory did not exist before
+	Remove(PathBuf),
+	/// File was overwritten or removed
+	Restore(PathBuf, Vec<u8>),
+	/// Directory was removed along with its contents,
+	/// directories have no contents and come before their children
+	RestoreDir(Vec<(PathBuf, Option<Vec<u8>>)>),
+	/// File or directory was moved from the first path to the second one
+	Rename(PathBuf, PathBuf),
+}
+
+impl Undo {
+	pub(super) fn write(backend: &dyn VfsBackend, path: &Path) -> Result<Vec<Self>> {
+		if backend.is_file(path) {
+			Ok(vec![Self::Restore(path.to_owned(), backend.read(path)?)])
+		} else {
+			Ok(vec![Self::Remove(path.to_owned())])
+		}
+	}
+
+	pub(super) fn create_dir(backend: &dyn VfsBackend, path: &Path) -> Result<Vec<Self>> {
+		// Only the topmost directory that does not exist yet has to be removed
+		let created = path
+			.ancestors()
+			.take_while(|ancestor| !ancestor.as_os_str().is_empty() && !backend.exists(ancestor))
+			.last();
+
+		Ok(created.map(|path| Self::Remove(path.to_owned())).into_iter().collect())
+	}
+
+	pub(super) fn rename(backend: &dyn VfsBackend, from: &Path, to: &Path) -> Result<Vec<Self>> {
+		let mut undo = if backend.exists(to) {
+			Self::remove(backend, to)?
+		} else {
+			vec![]
+		};
+
+		undo.push(Self::Rename(from.to_owned(), to.to_owned()));
+
+		Ok(undo)
+	}
+
+	pub(super) fn remove(backend: &dyn VfsBackend, path: &Path) -> Result<Vec<Self>> {
+		fn capture(backend: &dyn VfsBackend, path: &Path, entries: &mut Vec<(PathBuf, Option<Vec<u8>>)>) -> Result<()> {
+			if backend.is_dir(path) {
+				entries.push((path.to_owned(), None));
+
+				for child in backend.read_dir(path)? {
+					capture(backend, &child, entries)?;
+				}
+			} else {
+				entries.push((path.to_owned(), Some(backend.read(path)?)));
+			}
+
+			Ok(())
+		}
+
+		if backend.is_dir(path) {
+			let mut entries = vec![];
+			capture(backend, path, &mut entries)?;
+
+			Ok(vec![Self::RestoreDir(entries)])
+		} else if backend.exists(path) {
+			Ok(vec![Self::Restore(path.to_owned(), backend.read(path)?)])
+		} else {
+			Ok(vec![])
+		}
+	}
+
+	/// Events the undo will cause, so the changes can be processed like external ones
+	pub(super) fn events(&self, backend: &dyn VfsBackend) -> Vec<VfsEvent> {
+		match self {
+			Self::Remove(path) if backend.exists(path) => vec![VfsEvent::Delete(path.clone())],
+			Self::Remove(_) => vec![],
+			Self::Restore(path, _) if backend.exists(path) => vec![VfsEvent::Write(path.clone())],
+			Self::Restore(path, _) => vec![VfsEvent::Create(path.clone())],
+			Self::RestoreDir(entries) => entries
+				.first()
+				.map(|(path, _)| VfsEvent::Create(path.clone()))
+				.into_iter()
+				.collect(),
+			Self::Rename(from, to) => vec![VfsEvent::Delete(to.clone()), VfsEvent::Create(from.clone())],
+		}
+	}
+
+	pub(super) fn apply(self, backend: &mut dyn VfsBackend) -> Result<()> {
+		match self {
+			Self::Remove(path) => {
+				if backend.exists(&path) {
+					backend.remove(&path)?;
+				}
+			}
+			Self::Restore(path, contents) => backend.write(&path, &contents)?,
+			Self::RestoreDir(entries) => {
+				for (path, contents) in entries {
+					match contents {
+						Some(contents) => backend.write(&path, &contents)?,
+						None => backend.create_dir(&path)?,
+					}
+				}
+			}
+			Self::Rename(from, to) => backend.rename(&to, &from)?,
+		}
+
+		Ok(())
+	}
+}
+
+/// Suffix of temporary files that hold staged writes and
+/// moved aside files of open transactions, see `temp_path`
+const TEMP_SUFFIX: &str = ".argon-tmp";
+
+/// Returns a unique hidden path next to the given one, so moving
+/// between them never has to copy data across file systems
+fn temp_path(path: &Path) -> PathBuf {
+	static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+	let name = path.file_name().unwrap_or_default().to_string_lossy();
+	let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+	path.with_file_name(format!(".{}.{}-{}{}", name, process::id(), count, TEMP_SUFFIX))
+}
+
+/// Whether the path belongs to an open transaction
+/// and should not be visible to anything reading the directory
+pub(super) fn is_temp(path: &Path) -> bool {
+	path.file_name()
+		.is_some_and(|name| name.to_string_lossy().ends_with(TEMP_SUFFIX))
+}
+
+/// Step of an open transaction
+#[derive(Debug)]
+enum Step {
+	/// File is written to the temporary path and moved into place once the
+	/// outermost transaction is committed, so failures never leave it half-written
+	Staged { path: PathBuf, temp: PathBuf, placed: bool },
+	/// File or directory did not exist before
+	Created(PathBuf),
+	/// File or directory was moved out of the way instead of being removed or
+	/// overwritten, so rolling back does not have to keep its contents in memory
+	MovedAside { path: PathBuf, backup: PathBuf },
+	/// File or directory was moved from the first path to the second one
+	Renamed(PathBuf, PathBuf),
+}
+
+impl Step {
+	fn roll_back(self, backend: &mut dyn VfsBackend) -> Result<()> {
+		match self {
+			Self::Staged { temp: path, .. } | Self::Created(path) => {
+				if backend.exists(&path) {
+					backend.remove(&path)?;
+				}
+			}
+			// Steps are recorded before they are made, so the move may have failed
+			Self::MovedAside { path, backup } => {
+				if backend.exists(&backup) {
+					if backend.exists(&path) {
+						backend.remove(&path)?;
+					}
+
+					backend.rename(&backup, &path)?;
+				}
+			}
+			Self::Renamed(from, to) => {
+				if backend.exists(&to) {
+					backend.rename(&to, &from)?;
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Operations of a single open transaction
+#[derive(Debug, Default)]
+pub(super) struct Journal {
+	steps: Vec<Step>,
+	/// Reverse of the operations for the undo log, only captured while it is recording
+	logged: Vec<Undo>,
+}
+
+/// Journals of the transactions open on each thread, innermost last. Operations
+/// are recorded only by the transactions of the thread that made them
+pub(super) type Journals = HashMap<ThreadId, Vec<Journal>>;
+
+impl Journal {
+	pub(super) fn log(&mut self, undo: Vec<Undo>) {
+		self.logged.extend(undo);
+	}
+
+	/// Temporary file of the staged write of the path, if it has not been placed yet
+	fn staged(&self, path: &Path) -> Option<&Path> {
+		self.steps.iter().rev().find_map(|step| match step {
+			Step::Staged {
+				path: staged,
+				temp,
+				placed: false,
+			} if staged == path => Some(temp.as_path()),
+			_ => None,
+		})
+	}
+
+	fn staged_paths(&self) -> impl Iterator<Item = &Path> {
+		self.steps.iter().filter_map(|step| match step {
+			Step::Staged {
+				path, placed: false, ..
+			} => Some(path.as_path()),
+			_ => None,
+		})
+	}
+
+	/// Moves staged file into place, moving the current one aside. Steps are inserted
+	/// right after the staged one, they never touch the same paths as the following
+	/// steps as moving or removing any of its ancestors places the file first
+	fn place(&mut self, index: usize, backend: &mut dyn VfsBackend) -> Result<()> {
+		let Step::Staged {
+			path,
+			temp,
+			placed: placed @ false,
+		} = &mut self.steps[index]
+		else {
+			return Ok(());
+		};
+
+		let (path, temp) = (path.clone(), temp.clone());
+		*placed = true;
+
+		let mut steps = vec![];
+
+		let result = (|| {
+			if backend.exists(&path) {
+				let backup = temp_path(&path);
+				steps.push(Step::MovedAside {
+					path: path.clone(),
+					backup: backup.clone(),
+				});
+				backend.rename(&path, &backup)?;
+			}
+
+			steps.push(Step::Created(path.clone()));
+			backend.rename(&temp, &path)
+		})();
+
+		self.steps.splice(index + 1..index + 1, steps);
+
+		result.map_err(|err| step_error("write", &path, err))
+	}
+
+	/// Places every staged file, anything placed is rolled back with the journal if it fails
+	fn place_all(&mut self, backend: &mut dyn VfsBackend) -> Result<()> {
+		let mut index = 0;
+
+		while index < self.steps.len() {
+			self.place(index, backend)?;
+			index += 1;
+		}
+
+		Ok(())
+	}
+
+	/// Removes files that were moved aside, failures leave them behind but don't change the outcome
+	fn clean_up(&mut self, backend: &mut dyn VfsBackend) {
+		for step in &self.steps {
+			if let Step::MovedAside { backup, .. } = step {
+				if let Err(err) = backend.remove(backup) {
+					warn!("Failed to remove {}: {}", backup.display(), err);
+				}
+			}
+		}
+	}
+
+	fn roll_back(self, backend: &mut dyn VfsBackend) {
+		if !self.steps.is_empty() {
+			warn!("Rolling back {} file system operations", self.steps.len());
+		}
+
+		for step in self.steps.into_iter().rev() {
+			if let Err(err) = step.roll_back(backend) {
+				error!("Failed to roll back file system operation: {}", err);
+			}
+		}
+	}
+}
+
+/// Returns the temporary file of the staged write of the path in any
+/// transaction open on the current thread, so the thread sees its own writes
+pub(super) fn staged<'a>(journals: &'a Journals, path: &Path) -> Option<&'a Path> {
+	journals
+		.get(&thread::current().id())?
+		.iter()
+		.rev()
+		.find_map(|journal| journal.staged(path))
+}
+
+/// Returns paths of files staged by the transactions open on the current thread
+pub(super) fn staged_paths(journals: &Journals) -> Vec<PathBuf> {
+	journals
+		.get(&thread::current().id())
+		.into_iter()
+		.flatten()
+		.flat_map(|journal| journal.staged_paths().map(Path::to_owned))
+		.collect()
+}
+
+/// Places staged files at or inside the path before it is moved or removed
+fn place_inside(journals: &mut [Journal], path: &Path, backend: &mut dyn VfsBackend) -> Result<()> {
+	for journal in journals {
+		let staged: Vec<usize> = journal
+			.steps
+			.iter()
+			.enumerate()
+			.filter_map(|(index, step)| match step {
+				Step::Staged {
+					path: staged,
+					placed: false,
+					..
+				} if staged.starts_with(path) => Some(index),
+				_ => None,
+			})
+			.collect();
+
+		// Placing inserts steps, so later indices are handled first
+		for index in staged.into_iter().rev() {
+			journal.place(index, backend)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Writes the file to a temporary path, returns the path that was written
+pub(super) fn write(
+	journals: &mut [Journal],
+	path: &Path,
+	contents: &[u8],
+	backend: &mut dyn VfsBackend,
+) -> Result<PathBuf> {
+	if let Some(temp) = journals.iter().rev().find_map(|journal| journal.staged(path)) {
+		let temp = temp.to_owned();
+		backend.write(&temp, contents)?;
+
+		return Ok(temp);
+	}
+
+	let temp = temp_path(path);
+
+	// Recorded first so a partially written file is removed too
+	journals.last_mut().unwrap().steps.push(Step::Staged {
+		path: path.to_owned(),
+		temp: temp.clone(),
+		placed: false,
+	});
+
+	backend.write(&temp, contents)?;
+
+	Ok(temp)
+}
+
+pub(super) fn create_dir(journals: &mut [Journal], path: &Path, backend: &mut dyn VfsBackend) -> Result<()> {
+	// Only the topmost directory that does not exist yet has to be removed
+	let created = path
+		.ancestors()
+		.take_while(|ancestor| !ancestor.as_os_str().is_empty() && !backend.exists(ancestor))
+		.last();
+
+	if let Some(created) = created {
+		journals
+			.last_mut()
+			.unwrap()
+			.steps
+			.push(Step::Created(created.to_owned()));
+	}
+
+	backend.create_dir(path)
+}
+
+pub(super) fn rename(journals: &mut [Journal], from: &Path, to: &Path, backend: &mut dyn VfsBackend) -> Result<()> {
+	place_inside(journals, from, backend)?;
+	place_inside(journals, to, backend)?;
+
+	let journal = journals.last_mut().unwrap();
+
+	// Paths that only differ in case point to the same file on case-insensitive file systems
+	let same = from.to_string_lossy().to_lowercase() == to.to_string_lossy().to_lowercase();
+
+	if !same && backend.exists(to) {
+		let backup = temp_path(to);
+
+		journal.steps.push(Step::MovedAside {
+			path: to.to_owned(),
+			backup: backup.clone(),
+		});
+
+		backend.rename(to, &backup)?;
+	}
+
+	journal.steps.push(Step::Renamed(from.to_owned(), to.to_owned()));
+
+	backend.rename(from, to)
+}
+
+pub(super) fn remove(journals: &mut [Journal], path: &Path, backend: &mut dyn VfsBackend) -> Result<()> {
+	place_inside(journals, path, backend)?;
+
+	if !backend.exists(path) {
+		return backend.remove(path);
+	}
+
+	let backup = temp_path(path);
+
+	journals.last_mut().unwrap().steps.push(Step::MovedAside {
+		path: path.to_owned(),
+		backup: backup.clone(),
+	});
+
+	backend.unwatch(path)?;
+	backend.rename(path, &backup)
+}
+
+/// Names the step that failed, so it is known what was rolled back
+pub(super) fn step_error(step: &str, path: &Path, err: Error) -> Error {
+	Error::new(err.kind(), format!("Failed to {} {}: {}", step, path.display(), err))
+}
+
+/// Group of VFS operations that are undone in reverse order unless committed,
+/// dropping the transaction (e.g. when returning early with an error) rolls it back.
+///
+/// Written files are staged in temporary files and moved into place when the
+/// outermost transaction is committed, removed files are moved aside until then.
+/// Every transaction has its own journal and only records operations made on its
+/// thread, so it has to be committed or dropped on the thread that opened it.
+/// Transactions opened while another one is open only roll back their own
+/// operations, committing them hands the operations over to the outer one
+#[must_use]
+pub struct Transaction<'a> {
+	vfs: &'a Vfs,
+	finished: bool,
+	_thread: PhantomData<*const ()>,
+}
+
+impl<'a> Transaction<'a> {
+	pub(super) fn new(vfs: &'a Vfs) -> Self {
+		lock!(vfs.journals)
+			.entry(thread::current().id())
+			.or_default()
+			.push(Journal::default());
+
+		Self {
+			vfs,
+			finished: false,
+			_thread: PhantomData,
+		}
+	}
+
+	/// Moves staged files into place, everything is rolled back if that fails
+	pub fn commit(mut self) -> Result<()> {
+		self.finish(true)
+	}
+
+	fn finish(&mut self, commit: bool) -> Result<()> {
+		self.finished = true;
+
+		let thread = thread::current().id();
+		let mut inner = lock!(self.vfs.inner);
+		let mut journals = lock!(self.vfs.journals);
+
+		let open = journals
+			.get_mut(&thread)
+			.expect("Transaction was finished on another thread");
+		let mut journal = open.pop().unwrap();
+
+		if let Some(outer) = open.last_mut() {
+			if commit {
+				outer.steps.append(&mut journal.steps);
+				outer.logged.append(&mut journal.logged);
+
+				return Ok(());
+			}
+		} else {
+			journals.remove(&thread);
+		}
+
+		drop(journals);
+
+		let result = if commit {
+			journal.place_all(&mut **inner)
+		} else {
+			Ok(())
+		};
+
+		if commit && result.is_ok() {
+			journal.clean_up(&mut **inner);
+
+			// Operations of committed transactions can still be undone with the undo log
+			if let Some(log) = lock!(self.vfs.undo_log).as_ref() {
+				log.record(journal.logged);
+			}
+		} else {
+			let staged: Vec<PathBuf> = journal.staged_paths().map(Path::to_owned).collect();
+
+			journal.roll_back(&mut **inner);
+			drop(inner);
+
+			for path in staged {
+				self.vfs.forget_writes(&path);
+			}
+		}
+
+		result
+	}
+}
+
+impl Drop for Transaction<'_> {
+	fn drop(&mut self) {
+		if !self.finished {
+			self.finish(false).ok();
+		}
+	}
+}
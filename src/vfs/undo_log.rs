@@ -0,0 +1,275 @@
+use anyhow::{bail, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	fs::{self, File, OpenOptions},
+	io::{BufReader, BufWriter, ErrorKind, Write},
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex, OnceLock},
+};
+
+use super::transaction::Undo;
+use crate::lock;
+
+/// Point in the undo log that the workspace can be rolled back to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Marker {
+	pub name: String,
+	/// Unix timestamp in milliseconds
+	pub timestamp: i64,
+	/// Number of file system operations made since the marker
+	pub operations: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Record {
+	Marker { name: String, timestamp: i64 },
+	Undo(Vec<Undo>),
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+	/// Markers with the length of `undo` at the time they were added
+	markers: Vec<(String, i64, usize)>,
+	undo: Vec<Undo>,
+}
+
+/// Reverse of every file system operation made since the first marker, so the
+/// workspace can be rolled back to any of the markers. Operations are recorded
+/// only while there are markers and the log is appended to `.argon/undo.bin`
+/// so markers survive restarts
+#[derive(Debug)]
+pub struct UndoLog {
+	path: PathBuf,
+	inner: Mutex<Inner>,
+	/// Held for the whole rollback so rollbacks never overlap
+	rollback: Mutex<()>,
+}
+
+impl UndoLog {
+	/// Reads markers and operations recorded by the previous sessions
+	pub fn load(workspace_dir: &Path) -> Self {
+		let path = workspace_dir.join(".argon").join("undo.bin");
+		let mut inner = Inner::default();
+
+		if path.exists() {
+			if let Err(err) = read_records(&path, &mut inner) {
+				warn!("Failed to read undo log from {}: {}", path.display(), err);
+			}
+		}
+
+		Self {
+			path,
+			inner: Mutex::new(inner),
+			rollback: Mutex::new(()),
+		}
+	}
+
+	pub fn is_recording(&self) -> bool {
+		!lock!(self.inner).markers.is_empty()
+	}
+
+	/// Records reverse of a single operation, does nothing if there are no markers
+	pub(super) fn record(&self, undo: Vec<Undo>) {
+		let mut inner = lock!(self.inner);
+
+		if inner.markers.is_empty() || undo.is_empty() {
+			return;
+		}
+
+		let record = Record::Undo(undo);
+
+		if let Err(err) = self.append(&record) {
+			warn!("Failed to append to undo log: {}", err);
+		}
+
+		if let Record::Undo(undo) = record {
+			inner.undo.extend(undo);
+		}
+	}
+
+	pub fn add_marker(&self, name: &str) -> Result<Marker> {
+		let mut inner = lock!(self.inner);
+
+		if inner.markers.iter().any(|(marker, ..)| marker == name) {
+			bail!("Marker {} already exists", name);
+		}
+
+		let timestamp = chrono::Utc::now().timestamp_millis();
+
+		self.append(&Record::Marker {
+			name: name.to_owned(),
+			timestamp,
+		})?;
+
+		let position = inner.undo.len();
+		inner.markers.push((name.to_owned(), timestamp, position));
+
+		Ok(Marker {
+			name: name.to_owned(),
+			timestamp,
+			operations: 0,
+		})
+	}
+
+	/// Returns markers from the oldest to the newest
+	pub fn markers(&self) -> Vec<Marker> {
+		let inner = lock!(self.inner);
+
+		inner
+			.markers
+			.iter()
+			.map(|(name, timestamp, position)| Marker {
+				name: name.clone(),
+				timestamp: *timestamp,
+				operations: inner.undo.len() - position,
+			})
+			.collect()
+	}
+
+	/// Removes all markers and stops recording
+	pub fn clear(&self) -> Result<()> {
+		*lock!(self.inner) = Inner::default();
+
+		match fs::remove_file(&self.path) {
+			Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+			_ => Ok(()),
+		}
+	}
+
+	/// Takes reverse of the operations made since the marker, in the order they
+	/// were made, and drops all later markers. `apply` has to undo them while
+	/// no other rollback can start
+	pub(super) fn rollback<F>(&self, name: &str, apply: F) -> Result<usize>
+	where
+		F: FnOnce(Vec<Undo>) -> Result<()>,
+	{
+		let Ok(_rollback) = self.rollback.try_lock() else {
+			bail!("Another rollback is already in progress");
+		};
+
+		let undo = {
+			let mut inner = lock!(self.inner);
+
+			let Some(index) = inner.markers.iter().position(|(marker, ..)| marker == name) else {
+				bail!("Marker {} does not exist or was rolled back past", name);
+			};
+
+			let position = inner.markers[index].2;
+
+			inner.markers.truncate(index + 1);
+			let undo = inner.undo.split_off(position);
+
+			if let Err(err) = self.save(&inner) {
+				warn!("Failed to save undo log: {}", err);
+			}
+
+			undo
+		};
+
+		let count = undo.len();
+		apply(undo)?;
+
+		Ok(count)
+	}
+
+	fn append(&self, record: &Record) -> Result<()> {
+		fs::create_dir_all(self.path.parent().unwrap())?;
+
+		let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+		file.write_all(&rmp_serde::to_vec(record)?)?;
+
+		Ok(())
+	}
+
+	/// Rewrites the whole log, e.g. after dropping rolled back operations
+	fn save(&self, inner: &Inner) -> Result<()> {
+		fs::create_dir_all(self.path.parent().unwrap())?;
+
+		let mut writer = BufWriter::new(File::create(&self.path)?);
+		let mut markers = inner.markers.iter().peekable();
+
+		for (index, undo) in inner.undo.iter().enumerate() {
+			while let Some((name, timestamp, _)) = markers.next_if(|(.., position)| *position == index) {
+				rmp_serde::encode::write(
+					&mut writer,
+					&Record::Marker {
+						name: name.clone(),
+						timestamp: *timestamp,
+					},
+				)?;
+			}
+
+			rmp_serde::encode::write(&mut writer, &Record::Undo(vec![undo.clone()]))?;
+		}
+
+		for (name, timestamp, _) in markers {
+			rmp_serde::encode::write(
+				&mut writer,
+				&Record::Marker {
+					name: name.clone(),
+					timestamp: *timestamp,
+				},
+			)?;
+		}
+
+		writer.flush()?;
+
+		Ok(())
+	}
+}
+
+/// Registry of the workspace, see `skipped::get`
+pub fn get(workspace_dir: &Path) -> Arc<UndoLog> {
+	lock!(registries())
+		.entry(workspace_dir.to_owned())
+		.or_insert_with(|| Arc::new(UndoLog::load(workspace_dir)))
+		.clone()
+}
+
+/// Records the file that is about to be overwritten outside of the VFS, e.g. a project
+/// file, if the workspace has an undo log that is recording in this process
+pub fn record_write(workspace_dir: &Path, path: &Path) {
+	let Some(log) = lock!(registries()).get(workspace_dir).cloned() else {
+		return;
+	};
+
+	if !log.is_recording() {
+		return;
+	}
+
+	let undo = match fs::read(path) {
+		Ok(contents) => Undo::Restore(path.to_owned(), contents),
+		Err(_) => Undo::Remove(path.to_owned()),
+	};
+
+	log.record(vec![undo]);
+}
+
+fn registries() -> &'static Mutex<HashMap<PathBuf, Arc<UndoLog>>> {
+	static REGISTRIES: OnceLock<Mutex<HashMap<PathBuf, Arc<UndoLog>>>> = OnceLock::new();
+	REGISTRIES.get_or_init(Mutex::default)
+}
+
+fn read_records(path: &Path, inner: &mut Inner) -> Result<()> {
+	let mut reader = BufReader::new(File::open(path)?);
+
+	loop {
+		let record: Record = match rmp_serde::from_read(&mut reader) {
+			Ok(record) => record,
+			Err(rmp_serde::decode::Error::InvalidMarkerRead(err)) if err.kind() == ErrorKind::UnexpectedEof => {
+				break;
+			}
+			Err(err) => return Err(err.into()),
+		};
+
+		match record {
+			Record::Marker { name, timestamp } => inner.markers.push((name, timestamp, inner.undo.len())),
+			Record::Undo(undo) => inner.undo.extend(undo),
+		}
+	}
+
+	Ok(())
+}
@@ -0,0 +1,82 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::{
+	fs::File,
+	io::{Error, Result},
+	path::Path,
+	sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::std_backend::os_path;
+use crate::{argon_error, core::blobs, t};
+
+static VERIFIED: AtomicU64 = AtomicU64::new(0);
+static MISMATCHES: AtomicU64 = AtomicU64::new(0);
+static FAILURES: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyStats {
+	/// Writes that were read back
+	pub verified: u64,
+	/// Files that did not read back as written, including retries
+	pub mismatches: u64,
+	/// Files that still did not match after writing them again
+	pub failures: u64,
+}
+
+/// Returns verification stats of all writes made by this process
+pub fn stats() -> VerifyStats {
+	VerifyStats {
+		verified: VERIFIED.load(Ordering::Relaxed),
+		mismatches: MISMATCHES.load(Ordering::Relaxed),
+		failures: FAILURES.load(Ordering::Relaxed),
+	}
+}
+
+/// Streams content hash of the file on disk, see `blobs::digest_reader`
+pub fn hash_file(path: &Path) -> Result<String> {
+	blobs::digest_reader(File::open(os_path(path))?)
+}
+
+/// Checks that the file reads back with the `expected` hash of the written
+/// contents, otherwise it is written once more with `rewrite` before giving up.
+/// Catches writes that succeed but do not reach the disk intact, e.g. on failing drives
+pub fn verify<H, W>(path: &Path, expected: &str, hash: H, rewrite: W) -> Result<()>
+where
+	H: Fn() -> Result<String>,
+	W: FnOnce() -> Result<()>,
+{
+	let path_display = path.display().to_string();
+	let matches = || -> Result<bool> {
+		VERIFIED.fetch_add(1, Ordering::Relaxed);
+
+		if hash()? == expected {
+			return Ok(true);
+		}
+
+		MISMATCHES.fetch_add(1, Ordering::Relaxed);
+
+		Ok(false)
+	};
+
+	if matches()? {
+		return Ok(());
+	}
+
+	argon_error!("{}", t!("verify.mismatch", path = path_display.bold()));
+
+	rewrite()?;
+
+	if matches()? {
+		return Ok(());
+	}
+
+	FAILURES.fetch_add(1, Ordering::Relaxed);
+	argon_error!("{}", t!("verify.failed", path = path_display.bold()));
+
+	Err(Error::other(format!(
+		"Contents of {} do not match what was written, the disk might be failing",
+		path_display
+	)))
+}
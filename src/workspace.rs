@@ -13,7 +13,7 @@ use crate::{
 	config::Config,
 	ext::PathExt,
 	program::{Program, ProgramName},
-	util,
+	t, util,
 };
 
 #[derive(Debug)]
@@ -143,7 +143,7 @@ pub fn init(workspace: WorkspaceConfig) -> Result<()> {
 pub fn init_ts(workspace: WorkspaceConfig) -> Result<Option<PathBuf>> {
 	let package_manager = &Config::new().package_manager;
 
-	argon_info!("Waiting for {}..", package_manager.bold());
+	argon_info!("{}", t!("workspace.waiting", program = package_manager.bold()));
 
 	let template = workspace.template;
 	let mut project = workspace.project.to_owned();
@@ -197,10 +197,7 @@ pub fn init_ts(workspace: WorkspaceConfig) -> Result<Option<PathBuf>> {
 	let template_dir = util::get_argon_dir()?.join("templates").join(template);
 
 	if !template_dir.exists() {
-		argon_warn!(
-			"Template {} does not exist, additional files won't be added!",
-			template.bold()
-		);
+		argon_warn!("{}", t!("workspace.template_missing", template = template.bold()));
 
 		return Ok(Some(project));
 	}
@@ -218,28 +215,22 @@ pub fn init_ts(workspace: WorkspaceConfig) -> Result<Option<PathBuf>> {
 		}
 
 		match path.get_stem() {
-			"wally" => {
-				if workspace.wally || template == "package" {
-					let contents = fs::read_to_string(path)?;
-					let contents = contents.replace("$name", &project_name.to_lowercase());
-					let contents = contents.replace("$author", &util::get_username().to_lowercase());
+			"wally" if workspace.wally || template == "package" => {
+				let contents = fs::read_to_string(path)?;
+				let contents = contents.replace("$name", &project_name.to_lowercase());
+				let contents = contents.replace("$author", &util::get_username().to_lowercase());
 
-					fs::write(new_path, contents)?;
-				}
+				fs::write(new_path, contents)?;
 			}
-			"README" | "CHANGELOG" => {
-				if workspace.docs {
-					let contents = fs::read_to_string(path)?;
-					let contents = contents.replace("$name", project_name);
+			"README" | "CHANGELOG" if workspace.docs => {
+				let contents = fs::read_to_string(path)?;
+				let contents = contents.replace("$name", project_name);
 
-					fs::write(new_path, contents)?;
-				}
+				fs::write(new_path, contents)?;
 			}
-			"LICENSE" => {
-				if workspace.docs {
-					let fallback = fs::read_to_string(path)?;
-					add_license(&new_path, workspace.license, &fallback)?;
-				}
+			"LICENSE" if workspace.docs => {
+				let fallback = fs::read_to_string(path)?;
+				add_license(&new_path, workspace.license, &fallback)?;
 			}
 
 			_ => {}
@@ -311,7 +302,7 @@ fn add_license(path: &Path, license: &str, fallback: &str) -> Result<()> {
 
 			fs::write(path, license)?;
 
-			argon_warn!("Failed to add license: {}. Using basic fallback instead!", err);
+			argon_warn!("{}", t!("workspace.license_failed", error = err));
 
 			return Ok(());
 		}
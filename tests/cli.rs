@@ -0,0 +1,3102 @@
+mod common;
+
+#[cfg(unix)]
+mod watch_command {
+	use crate::common::TempDir;
+	use argon::{
+		cli::watch::{self, Watch},
+		core::Core,
+		project::Project,
+		server::Server,
+		sessions::Session,
+	};
+	use clap::Parser;
+	use std::{
+		collections::HashMap,
+		fs,
+		net::TcpListener,
+		path::{Path, PathBuf},
+		process::{self, Command},
+		sync::Arc,
+		thread::{self, JoinHandle},
+		time::{Duration, Instant},
+	};
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{ "name": "Watch", "tree": { "$className": "DataModel", "ReplicatedStorage": { "$path": "src" } } }"#,
+		)
+		.unwrap();
+
+		fs::write(dir.join("src/Module.luau"), "return 0").unwrap();
+		fs::write(dir.join("src/Other.txt"), "0").unwrap();
+
+		dir
+	}
+
+	fn session(pid: u32, project: &Path, port: Option<u16>) -> Session {
+		Session {
+			pid,
+			host: port.map(|_| String::from("localhost")),
+			port,
+			daemon: None,
+			project: Some(project.to_owned()),
+			workspace: None,
+			members: vec![],
+			mirror_port: None,
+		}
+	}
+
+	/// Parses `watch` that writes details of the first matching batch to `output` and exits with 3
+	fn watch(dir: &Path, session: Option<&str>) -> Watch {
+		let exec = format!(
+			r#"printf '%s\n%s\n%s' "$ARGON_CHANGED_CLASSES" "$ARGON_UPDATED_COUNT" "$ARGON_CHANGED_PATHS" > "{}"; exit 3"#,
+			dir.join("output.txt").display()
+		);
+
+		let mut args = vec![
+			String::from("watch"),
+			dir.display().to_string(),
+			String::from("--once"),
+			String::from("--debounce"),
+			String::from("50"),
+			String::from("--filter"),
+			String::from("ModuleScript"),
+			String::from("--exec"),
+			exec,
+		];
+
+		if let Some(session) = session {
+			args.insert(2, session.to_owned());
+		}
+
+		Watch::try_parse_from(args).unwrap()
+	}
+
+	/// Keeps changing both files until the watcher runs the command, as it may not be watching yet
+	fn change_until_finished(dir: &Path, handle: &JoinHandle<anyhow::Result<i32>>) {
+		let start = Instant::now();
+		let mut revision = 1;
+
+		while !handle.is_finished() {
+			assert!(start.elapsed() < Duration::from_secs(60), "Command was never run");
+
+			fs::write(dir.join("src/Other.txt"), revision.to_string()).unwrap();
+			fs::write(dir.join("src/Module.luau"), format!("return {}", revision)).unwrap();
+
+			revision += 1;
+			thread::sleep(Duration::from_millis(300));
+		}
+	}
+
+	fn assert_output(dir: &Path) {
+		let output = fs::read_to_string(dir.join("output.txt")).unwrap();
+		let lines: Vec<&str> = output.lines().collect();
+
+		// Changes of `Other.txt` do not match the filter
+		assert_eq!(lines[0], "ModuleScript");
+		assert!(lines[1].parse::<usize>().unwrap() >= 1);
+		assert_eq!(lines[2], dir.join("src/Module.luau").display().to_string());
+	}
+
+	#[test]
+	fn session_address() {
+		let project = PathBuf::from("/projects/game/default.project.json");
+		let other = PathBuf::from("/projects/other/default.project.json");
+
+		let mut finished = Command::new("true").spawn().unwrap();
+		finished.wait().unwrap();
+
+		let mut sessions = HashMap::new();
+		sessions.insert(String::from("dead"), session(finished.id(), &project, Some(8000)));
+		sessions.insert(String::from("other"), session(process::id(), &other, Some(8001)));
+		sessions.insert(String::from("headless"), session(process::id(), &other, None));
+
+		// Nothing is serving the project, so a new session is started
+		assert_eq!(watch::session_address(&sessions, None, &project).unwrap(), None);
+		assert_eq!(watch::session_address(&sessions, Some("dead"), &project).unwrap(), None);
+		assert_eq!(watch::session_address(&sessions, Some("new"), &project).unwrap(), None);
+
+		sessions.insert(String::from("serving"), session(process::id(), &project, Some(8002)));
+
+		assert_eq!(
+			watch::session_address(&sessions, None, &project).unwrap(),
+			Some(String::from("http://localhost:8002"))
+		);
+
+		// Explicit session is used even if it serves another project
+		assert_eq!(
+			watch::session_address(&sessions, Some("other"), &project).unwrap(),
+			Some(String::from("http://localhost:8001"))
+		);
+		assert!(watch::session_address(&sessions, Some("headless"), &project).is_err());
+	}
+
+	#[test]
+	fn attach() {
+		let dir = fixture("attach");
+
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+		let core = Arc::new(Core::new(project, true).unwrap());
+
+		let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+		let address = format!("http://127.0.0.1:{}", port);
+
+		thread::spawn(move || Server::new(core, "127.0.0.1", port).start());
+
+		let start = Instant::now();
+
+		while reqwest::blocking::get(format!("{}/events", address)).is_err() {
+			assert!(start.elapsed() < Duration::from_secs(30), "Server did not start");
+			thread::sleep(Duration::from_millis(50));
+		}
+
+		let watch = watch(&dir, None);
+		let handle = thread::spawn(move || watch.attach(&address));
+
+		change_until_finished(&dir, &handle);
+
+		assert_eq!(handle.join().unwrap().unwrap(), 3);
+		assert_output(&dir);
+	}
+
+	#[test]
+	fn start() {
+		let dir = fixture("start");
+
+		// Session that is not running, so the watcher builds the tree itself
+		let id = format!("watch-command-{}", process::id());
+		let watch = watch(&dir, Some(&id));
+		let handle = thread::spawn(move || watch.run());
+
+		change_until_finished(&dir, &handle);
+
+		assert_eq!(handle.join().unwrap().unwrap(), 3);
+		assert_output(&dir);
+	}
+
+	#[test]
+	fn nested_additions() {
+		let dir = fixture("nested");
+
+		let id = format!("watch-nested-{}", process::id());
+		let watch = watch(&dir, Some(&id));
+		let handle = thread::spawn(move || watch.run());
+
+		let start = Instant::now();
+		let mut revision = 1;
+
+		// New folders are added until the watcher runs the command, as it may not be watching yet
+		while !handle.is_finished() {
+			assert!(start.elapsed() < Duration::from_secs(60), "Command was never run");
+
+			let nested = dir.join(format!("src/Folder{}/Nested", revision));
+
+			fs::create_dir_all(&nested).unwrap();
+			fs::write(nested.join("Deep.luau"), "return 0").unwrap();
+
+			revision += 1;
+			thread::sleep(Duration::from_millis(300));
+		}
+
+		assert_eq!(handle.join().unwrap().unwrap(), 3);
+
+		// Only descendants of the added folders match the filter
+		let output = fs::read_to_string(dir.join("output.txt")).unwrap();
+		let lines: Vec<&str> = output.lines().collect();
+
+		assert_eq!(lines[0], "Folder,ModuleScript");
+		assert!(lines[2].split(':').any(|path| path.ends_with("Nested/Deep.luau")));
+	}
+}
+
+mod daemon {
+	use crate::common::TempDir;
+	use argon::{
+		daemon,
+		sessions::{self, Session},
+	};
+	use std::{
+		collections::HashMap,
+		fs,
+		io::Write,
+		path::{Path, PathBuf},
+	};
+
+	fn session(pid: u32, daemon: Option<&Path>) -> Session {
+		Session {
+			pid,
+			host: Some(String::from("localhost")),
+			port: Some(8000),
+			daemon: daemon.map(|path| path.to_owned()),
+			project: None,
+			workspace: None,
+			members: vec![],
+			mirror_port: None,
+		}
+	}
+
+	#[test]
+	fn finds_workspace_daemon() {
+		let workspace = PathBuf::from("/projects/game");
+		let other = PathBuf::from("/projects/other");
+
+		let mut active = HashMap::new();
+		active.insert(String::from("0"), session(1, None));
+		active.insert(String::from("1"), session(2, Some(&other)));
+
+		assert!(sessions::find_daemon(&active, &workspace).is_none());
+
+		active.insert(String::from("2"), session(3, Some(&workspace)));
+
+		let (id, found) = sessions::find_daemon(&active, &workspace).unwrap();
+
+		assert_eq!(id, "2");
+		assert_eq!(found.pid, 3);
+	}
+
+	#[test]
+	fn deserializes_legacy_sessions() {
+		let session: Session = toml::from_str("pid = 1\nhost = \"localhost\"\nport = 8000").unwrap();
+
+		assert_eq!(session.daemon, None);
+		assert_eq!(session.get_address(), Some(String::from("http://localhost:8000")));
+	}
+
+	#[test]
+	fn returns_last_lines() {
+		assert_eq!(daemon::last_lines("a\nb\nc\n", 2), vec!["b", "c"]);
+		assert_eq!(daemon::last_lines("a\nb", 5), vec!["a", "b"]);
+		assert!(daemon::last_lines("", 5).is_empty());
+	}
+
+	#[test]
+	fn follows_appended_logs() {
+		let workspace = TempDir::new("logs");
+		let path = daemon::get_log_path(&workspace);
+
+		let mut log = daemon::open_log(&workspace).unwrap();
+		log.write_all(b"first\n").unwrap();
+
+		let (text, offset) = daemon::read_from(&path, 0).unwrap();
+		assert_eq!(text, "first\n");
+
+		log.write_all(b"second\n").unwrap();
+
+		let (text, offset) = daemon::read_from(&path, offset).unwrap();
+		assert_eq!(text, "second\n");
+
+		// Truncated file should be read from the beginning
+		fs::write(&path, "new\n").unwrap();
+
+		let (text, _) = daemon::read_from(&path, offset).unwrap();
+		assert_eq!(text, "new\n");
+	}
+}
+
+mod i18n {
+	use argon::{cli::Cli, i18n};
+	use clap::CommandFactory;
+	use std::{ffi::OsStr, fs, path::Path};
+
+	fn collect_ids(dir: &Path, ids: &mut Vec<(String, String)>) {
+		for entry in fs::read_dir(dir).unwrap() {
+			let path = entry.unwrap().path();
+
+			if path.is_dir() {
+				collect_ids(&path, ids);
+				continue;
+			}
+
+			if path.extension() != Some(OsStr::new("rs")) {
+				continue;
+			}
+
+			let source = fs::read_to_string(&path).unwrap();
+
+			for (index, _) in source.match_indices("t!(") {
+				// Skip other macros that end with `t!`, like `print!`
+				if source[..index].ends_with(|char: char| char.is_alphanumeric() || char == '_') {
+					continue;
+				}
+
+				let rest = source[index + 3..].trim_start();
+
+				if let Some(rest) = rest.strip_prefix('"') {
+					let id = &rest[..rest.find('"').unwrap()];
+					ids.push((id.to_owned(), path.display().to_string()));
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn every_message_exists_in_fallback() {
+		let fallback = i18n::catalog(i18n::FALLBACK_LANGUAGE).unwrap();
+		let mut ids = vec![];
+
+		collect_ids(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src"), &mut ids);
+
+		assert!(!ids.is_empty());
+
+		for (id, path) in ids {
+			assert!(fallback.contains_key(&id), "Message {} used in {} is missing", id, path);
+		}
+	}
+
+	#[test]
+	fn every_subcommand_has_description() {
+		let fallback = i18n::catalog(i18n::FALLBACK_LANGUAGE).unwrap();
+
+		for subcommand in Cli::command().get_subcommands() {
+			let id = format!("cli.{}", subcommand.get_name());
+			assert!(fallback.contains_key(&id), "Description {} is missing", id);
+		}
+	}
+
+	#[test]
+	fn catalogs_match_fallback() {
+		let fallback = i18n::catalog(i18n::FALLBACK_LANGUAGE).unwrap();
+		let languages = i18n::languages();
+
+		assert!(languages.len() > 1);
+
+		for language in languages {
+			for (id, message) in i18n::catalog(language).unwrap() {
+				let original = fallback
+					.get(id)
+					.unwrap_or_else(|| panic!("Message {} from {} catalog is not in the fallback", id, language));
+
+				assert_eq!(
+					i18n::placeholders(message),
+					i18n::placeholders(original),
+					"Placeholders of {} differ in {} catalog",
+					id,
+					language
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn named_arguments() {
+		let first = 1;
+		let second = "two";
+
+		assert_eq!(
+			i18n::format("{second} then {first}", &[("first", &first), ("second", &second)]),
+			"two then 1"
+		);
+		assert_eq!(i18n::format("{{literal}} {missing}", &[]), "{literal} {missing}");
+		assert_eq!(i18n::placeholders("{b} {a} {b} {{c}}"), vec!["a", "b"]);
+	}
+
+	#[test]
+	fn language_selection() {
+		i18n::init("ja_JP.UTF-8");
+
+		assert_eq!(i18n::language(), "ja");
+		assert_eq!(argon::t!("crash.crashed"), "Argon がクラッシュしました！");
+		assert_eq!(
+			argon::t!("stop.stopped_process", pid = 7),
+			"Argon プロセスを停止しました。PID: 7"
+		);
+
+		i18n::init("xx");
+
+		assert_eq!(
+			argon::t!("stop.stopped_process", pid = 7),
+			"Stopped Argon process with PID: 7"
+		);
+		assert_eq!(argon::t!("missing.message"), "missing.message");
+
+		i18n::init("en");
+	}
+}
+
+mod clean {
+	use crate::common::TempDir;
+	use argon::{
+		clean::{self, Category},
+		core::{meta::Meta, tree::Tree},
+		middleware::new_snapshot,
+		project::Project,
+		vfs::Vfs,
+	};
+	use std::{
+		fs,
+		path::{Path, PathBuf},
+		time::SystemTime,
+	};
+
+	const PROJECT: &str = r#"{
+	"name": "Clean",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": { "$path": "src" }
+	},
+	"globIgnorePaths": ["**/vendor/**"]
+}"#;
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src/Folder")).unwrap();
+		fs::create_dir_all(dir.join("src/vendor")).unwrap();
+		fs::create_dir_all(dir.join(".argon/logs")).unwrap();
+
+		fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+
+		// Used data files
+		fs::write(dir.join("src/Module.luau"), "return {}").unwrap();
+		fs::write(
+			dir.join("src/Module.meta.json"),
+			r#"{"properties": {"Archivable": false}}"#,
+		)
+		.unwrap();
+		fs::write(dir.join("src/Folder/Child.luau"), "return 1").unwrap();
+		fs::write(
+			dir.join("src/Folder/init.meta.json"),
+			r#"{"className": "Configuration"}"#,
+		)
+		.unwrap();
+
+		// Data file of instance that no longer exists
+		fs::write(
+			dir.join("src/Removed.meta.json"),
+			r#"{"properties": {"Archivable": false}}"#,
+		)
+		.unwrap();
+
+		// Data file that does not change anything
+		fs::write(dir.join("src/Empty.luau"), "return nil").unwrap();
+		fs::write(dir.join("src/Empty.meta.json"), "{}").unwrap();
+
+		// Files of other tools matched by ignore globs
+		fs::write(dir.join("src/vendor/Tool.meta.json"), "{}").unwrap();
+
+		fs::write(dir.join(".argon/logs/daemon.log"), "log").unwrap();
+		fs::write(dir.join(".argon/history.jsonl"), "{}\n").unwrap();
+		fs::write(dir.join(".argon/default.project.json.tmp"), "{").unwrap();
+		fs::write(dir.join("lemonlogs.txt"), "log").unwrap();
+
+		dir
+	}
+
+	fn orphaned_data(dir: &Path) -> Vec<PathBuf> {
+		let path = dir.join("default.project.json");
+		let vfs = Vfs::new(false);
+
+		let project = Project::load(&path).unwrap();
+		let context = Meta::from_project(&project).context;
+		let tree = Tree::new(new_snapshot(&path, &context, &vfs).unwrap().unwrap());
+
+		clean::find_orphaned_data(dir, &tree, &context, &vfs)
+			.into_iter()
+			.map(|artifact| artifact.path)
+			.collect()
+	}
+
+	/// Every path in the directory with its size and modification time
+	fn state(dir: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+		let mut entries = vec![];
+
+		for entry in fs::read_dir(dir).unwrap() {
+			let path = entry.unwrap().path();
+			let metadata = fs::metadata(&path).unwrap();
+
+			if metadata.is_dir() {
+				entries.extend(state(&path));
+			}
+
+			entries.push((path, metadata.len(), metadata.modified().unwrap()));
+		}
+
+		entries.sort();
+		entries
+	}
+
+	#[test]
+	fn orphaned_data_detection() {
+		let dir = fixture("orphaned");
+
+		assert_eq!(
+			orphaned_data(&dir),
+			vec![dir.join("src/Empty.meta.json"), dir.join("src/Removed.meta.json")]
+		);
+	}
+
+	#[test]
+	fn categories() {
+		let dir = fixture("categories");
+
+		let paths = |categories: &[Category]| -> Vec<PathBuf> {
+			clean::find(&dir, categories)
+				.into_iter()
+				.map(|artifact| artifact.path)
+				.collect()
+		};
+
+		assert_eq!(
+			paths(&[Category::Logs]),
+			vec![dir.join(".argon/logs"), dir.join("lemonlogs.txt")]
+		);
+		assert_eq!(paths(&[Category::Cache]), vec![dir.join(".argon/history.jsonl")]);
+		assert_eq!(
+			paths(&[Category::Trash]),
+			vec![dir.join(".argon/default.project.json.tmp")]
+		);
+
+		let logs = clean::find(&dir, &[Category::Logs]);
+		assert_eq!(logs[0].size, 3);
+	}
+
+	#[test]
+	fn dry_run_does_not_touch_files() {
+		let dir = fixture("dry-run");
+		let before = state(&dir);
+
+		let categories = [Category::Cache, Category::Logs, Category::Trash, Category::OrphanedData];
+
+		assert!(!clean::find(&dir, &categories).is_empty());
+		assert!(!orphaned_data(&dir).is_empty());
+
+		assert_eq!(state(&dir), before);
+	}
+
+	#[test]
+	fn removes_only_inside_workspace() {
+		let dir = fixture("remove");
+		let outside = fixture("outside");
+
+		let mut artifacts = clean::find(&dir, &[Category::Logs, Category::Trash]);
+		artifacts.extend(clean::find(&outside, &[Category::Logs]));
+
+		clean::remove(&dir, &artifacts).unwrap();
+
+		assert!(!dir.join(".argon/logs").exists());
+		assert!(!dir.join("lemonlogs.txt").exists());
+		assert!(!dir.join(".argon/default.project.json.tmp").exists());
+		assert!(dir.join(".argon/history.jsonl").exists());
+
+		assert!(outside.join(".argon/logs").exists());
+		assert!(outside.join("lemonlogs.txt").exists());
+	}
+
+	#[test]
+	fn format_size() {
+		assert_eq!(clean::format_size(512), "512 B");
+		assert_eq!(clean::format_size(2048), "2.0 KB");
+		assert_eq!(clean::format_size(5 * 1024 * 1024 + 512 * 1024), "5.5 MB");
+	}
+}
+
+mod rojo {
+	use argon::cli::rojo::{translate, Translation};
+
+	fn args(args: &str) -> Vec<String> {
+		args.split_whitespace().map(|arg| arg.to_owned()).collect()
+	}
+
+	fn translated(rojo: &str) -> Translation {
+		translate(&args(rojo)).unwrap()
+	}
+
+	#[test]
+	fn serve() {
+		assert_eq!(translated("serve").args, args("serve"));
+		assert_eq!(
+			translated("serve game.project.json --port 8000 --address 0.0.0.0").args,
+			args("serve game.project.json --port 8000 --host 0.0.0.0")
+		);
+		assert_eq!(translated("serve --port=8000").args, args("serve --port 8000"));
+	}
+
+	#[test]
+	fn build() {
+		let translation = translated("build -o game.rbxlx");
+
+		assert_eq!(translation.args, args("build --output game.rbxlx"));
+		assert!(translation.ignored.is_empty());
+
+		assert_eq!(
+			translated("build --output model.rbxm place.project.json --watch").args,
+			args("build place.project.json --output model.rbxm --watch")
+		);
+	}
+
+	#[test]
+	fn sourcemap() {
+		assert_eq!(
+			translated("sourcemap default.project.json -o sourcemap.json --include-non-scripts").args,
+			args("sourcemap default.project.json --output sourcemap.json --non-scripts")
+		);
+		assert_eq!(
+			translated("sourcemap --watch --output=sourcemap.json").args,
+			args("sourcemap --watch --output sourcemap.json")
+		);
+	}
+
+	#[test]
+	fn init() {
+		assert_eq!(translated("init").args, args("init"));
+		assert_eq!(
+			translated("init my-game --kind plugin").args,
+			args("init my-game --template plugin")
+		);
+	}
+
+	#[test]
+	fn unsupported_flags() {
+		let translation = translated("sourcemap -o sourcemap.json --absolute --unknown");
+
+		assert_eq!(translation.args, args("sourcemap --output sourcemap.json"));
+		assert_eq!(translation.ignored, args("--absolute --unknown"));
+
+		let translation = translated("build -o plugin.rbxm --plugin MyPlugin.rbxm");
+
+		assert_eq!(translation.args, args("build --output plugin.rbxm"));
+		assert_eq!(translation.ignored, vec![String::from("--plugin MyPlugin.rbxm")]);
+
+		let translation = translated("serve first.project.json second.project.json -v");
+
+		assert_eq!(translation.args, args("serve first.project.json"));
+		assert_eq!(translation.ignored, args("second.project.json -v"));
+	}
+
+	#[test]
+	fn unsupported_commands() {
+		for rojo in ["", "upload --asset_id 1", "fmt-project", "plugin install"] {
+			assert!(translate(&args(rojo)).is_err(), "{} should not be supported", rojo);
+		}
+
+		let err = translate(&args("build -o")).unwrap_err();
+		assert!(err.to_string().contains("requires a value"));
+	}
+}
+
+mod crash_handler {
+	use crate::common::{start, TempDir};
+	use anyhow::bail;
+	use argon::crash_handler::{self, CrashReport, Delivery, Privacy, WorkspaceStats};
+	use backtrace::Backtrace;
+	use std::{
+		cell::Cell,
+		env, fs,
+		path::Path,
+		time::{Duration, Instant},
+	};
+
+	fn report(privacy: Privacy) -> CrashReport {
+		CrashReport::new(
+			"Something went wrong",
+			Some(String::from("src/core/mod.rs: 42")),
+			Some(Backtrace::new()),
+			Path::new("/home/user/Projects/Secret"),
+			privacy,
+		)
+	}
+
+	#[test]
+	fn serializer() {
+		let anonymous = report(Privacy::Anonymous);
+		let json: serde_json::Value = serde_json::from_str(&anonymous.to_json()).unwrap();
+
+		assert_eq!(json["message"], "Something went wrong");
+		assert_eq!(json["location"], "src/core/mod.rs: 42");
+		assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+		assert_eq!(json["os"], format!("{} {}", env::consts::OS, env::consts::ARCH));
+		assert!(json["workspacePath"].is_null());
+
+		// Workspace hash is stable but does not reveal the path
+		let workspace = json["workspace"].as_str().unwrap();
+
+		assert_eq!(workspace.len(), 16);
+		assert_eq!(workspace, report(Privacy::Full).workspace);
+		assert!(!anonymous.to_json().contains("Secret"));
+	}
+
+	#[test]
+	fn privacy() {
+		let anonymous = report(Privacy::Anonymous).backtrace.unwrap();
+		let full = report(Privacy::Full);
+
+		assert!(!anonymous.contains("crash_handler"));
+		assert!(full.backtrace.unwrap().contains("crash_handler"));
+		assert_eq!(full.workspace_path.as_deref(), Some("/home/user/Projects/Secret"));
+
+		assert_eq!(Privacy::from_setting("Full"), Privacy::Full);
+		assert_eq!(Privacy::from_setting("anonymous"), Privacy::Anonymous);
+		assert_eq!(Privacy::from_setting("unknown"), Privacy::Anonymous);
+	}
+
+	#[test]
+	fn sent_to_endpoint() {
+		let temp = TempDir::new("sent");
+		let dir = temp.join("crashes");
+		let report = report(Privacy::Anonymous);
+
+		let delivery = crash_handler::deliver(
+			&report,
+			"local",
+			Some("https://crashes.test"),
+			Some(&dir),
+			true,
+			|url, body| {
+				assert_eq!(url, "https://crashes.test");
+				assert_eq!(body, report.to_json());
+				Ok(())
+			},
+		);
+
+		assert_eq!(delivery, Delivery::Sent);
+		assert!(!dir.exists());
+	}
+
+	#[test]
+	fn saved_when_sending_fails() {
+		let temp = TempDir::new("failed");
+		let dir = temp.join("crashes");
+
+		let delivery = crash_handler::deliver(
+			&report(Privacy::Anonymous),
+			"local",
+			Some("https://crashes.test"),
+			Some(&dir),
+			true,
+			|_, _| bail!("Timed out"),
+		);
+
+		// Sending failed, so the user is not prompted even though they could be
+		let Delivery::Saved(path) = delivery else {
+			panic!("Expected local crash file, got {:?}", delivery);
+		};
+
+		assert!(path.starts_with(&dir));
+		assert_eq!(fs::read_to_string(&path).unwrap(), "local");
+
+		let delivery = crash_handler::deliver(
+			&report(Privacy::Anonymous),
+			"local",
+			Some("https://crashes.test"),
+			None,
+			true,
+			|_, _| bail!("Timed out"),
+		);
+
+		assert_eq!(delivery, Delivery::Failed);
+	}
+
+	#[test]
+	fn prompt_without_endpoint() {
+		let temp = TempDir::new("prompt");
+		let dir = temp.join("crashes");
+		let sent = Cell::new(false);
+
+		let send = |_: &str, _: &str| {
+			sent.set(true);
+			Ok(())
+		};
+
+		let delivery = crash_handler::deliver(&report(Privacy::Anonymous), "local", None, Some(&dir), true, send);
+
+		assert_eq!(delivery, Delivery::Prompt);
+		assert!(!dir.exists());
+
+		// There is no one to prompt so the report is saved
+		let delivery = crash_handler::deliver(&report(Privacy::Anonymous), "local", None, Some(&dir), false, send);
+
+		assert!(matches!(delivery, Delivery::Saved(_)));
+		assert!(!sent.get());
+	}
+
+	#[test]
+	fn workspace_stats() {
+		let dir = TempDir::new("stats");
+
+		fs::create_dir_all(dir.join("src/Secret/Nested")).unwrap();
+		fs::write(dir.join("src/Secret/Nested/Module.luau"), "return 1").unwrap();
+		fs::write(dir.join("src/Server.server.luau"), "print(1)").unwrap();
+		fs::write(dir.join("src/Value.txt"), "hello").unwrap();
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "Stats",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"Code": { "$path": "src" }
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		let core = start(&dir);
+		let stats = WorkspaceStats::collect(
+			&core.tree(),
+			&core.project(),
+			Duration::from_secs(42),
+			Instant::now() + Duration::from_secs(10),
+		)
+		.unwrap();
+
+		// DataModel, ReplicatedStorage, Code, Secret, Nested, Module, Server, Value
+		assert_eq!(stats.instances, 8);
+		assert_eq!(stats.max_depth, 5);
+		assert_eq!(stats.script_source_bytes, 16);
+		assert_eq!(stats.project_nodes, 3);
+		assert_eq!(stats.middleware["ModuleScript"], 1);
+		assert_eq!(stats.middleware["ServerScript"], 1);
+		assert_eq!(stats.middleware["StringValue"], 1);
+		assert_eq!(stats.uptime, 42);
+
+		// Stats never reveal names or paths
+		let with_stats = report(Privacy::Anonymous).with_stats(Some(stats));
+		let json: serde_json::Value = serde_json::from_str(&with_stats.to_json()).unwrap();
+
+		assert_eq!(json["stats"]["instances"], 8);
+		assert!(!with_stats.to_json().contains("Nested"));
+		assert!(!report(Privacy::Anonymous).to_json().contains("stats"));
+
+		// Collection gives up once the deadline passes
+		assert!(WorkspaceStats::collect(&core.tree(), &core.project(), Duration::ZERO, Instant::now()).is_none());
+	}
+
+	#[test]
+	fn guarded_stats() {
+		let stats = crash_handler::guarded(|| -> Option<WorkspaceStats> { panic!("Injected failure") });
+		assert!(stats.is_none());
+
+		assert_eq!(crash_handler::guarded(|| Some(1)), Some(1));
+	}
+}
+
+mod state {
+	use crate::common::TempDir;
+	use argon::{
+		config::Config,
+		sessions::Sessions,
+		state::{self, Migration, Schema, State},
+		updater::UpdateStatus,
+	};
+	use std::{fs, path::PathBuf, time::SystemTime};
+	use toml::{Table, Value};
+
+	// Written before VS Code extension updates were introduced
+	const UPDATE_STATUS_V0_LEGACY: &str = r#"
+plugin_version = "2.0.0"
+templates_version = 3
+
+[last_checked]
+secs_since_epoch = 1700000000
+nanos_since_epoch = 0
+"#;
+
+	const UPDATE_STATUS_V0: &str = r#"
+plugin_version = "2.0.0"
+templates_version = 3
+vscode_version = "1.2.0"
+
+[last_checked]
+secs_since_epoch = 1700000000
+nanos_since_epoch = 0
+"#;
+
+	const UPDATE_STATUS_V1: &str = r#"
+plugin_version = "2.0.0"
+schema_version = 1
+templates_version = 3
+vscode_version = "1.2.0"
+
+[last_checked]
+secs_since_epoch = 1700000000
+nanos_since_epoch = 0
+"#;
+
+	const SESSIONS_V0: &str = r#"
+last_session = "0"
+
+[active_sessions.0]
+pid = 1234
+host = "localhost"
+port = 8000
+"#;
+
+	const SESSIONS_V1: &str = r#"
+last_session = "0"
+schema_version = 1
+
+[active_sessions.0]
+pid = 1234
+host = "localhost"
+port = 8000
+project = "default.project.json"
+"#;
+
+	const CONFIG_V0: &str = r#"
+port = 8080
+use_git = false
+"#;
+
+	const CONFIG_V1: &str = r#"
+port = 8080
+schema_version = 1
+use_git = false
+"#;
+
+	fn last_checked(status: &UpdateStatus) -> u64 {
+		status
+			.last_checked
+			.duration_since(SystemTime::UNIX_EPOCH)
+			.unwrap()
+			.as_secs()
+	}
+
+	fn temp_file(name: &str) -> (TempDir, PathBuf) {
+		let dir = TempDir::new(name);
+		let path = dir.join(format!("{}.toml", name));
+
+		(dir, path)
+	}
+
+	#[test]
+	fn update_status() {
+		let legacy: UpdateStatus = state::parse(UPDATE_STATUS_V0_LEGACY).unwrap();
+
+		assert_eq!(legacy.vscode_version, "0.0.0");
+		assert_eq!(legacy.plugin_version, "2.0.0");
+		assert_eq!(legacy.templates_version, 3);
+		assert_eq!(last_checked(&legacy), 1_700_000_000);
+
+		for fixture in [UPDATE_STATUS_V0, UPDATE_STATUS_V1] {
+			let status: UpdateStatus = state::parse(fixture).unwrap();
+
+			assert_eq!(status.vscode_version, "1.2.0");
+			assert_eq!(status.plugin_version, "2.0.0");
+			assert_eq!(last_checked(&status), 1_700_000_000);
+		}
+	}
+
+	#[test]
+	fn sessions() {
+		let sessions: Sessions = state::parse(SESSIONS_V0).unwrap();
+
+		assert_eq!(sessions.last_session, "0");
+		assert_eq!(
+			sessions.active_sessions["0"].get_address().unwrap(),
+			"http://localhost:8000"
+		);
+		assert!(sessions.active_sessions["0"].project.is_none());
+
+		let sessions: Sessions = state::parse(SESSIONS_V1).unwrap();
+
+		assert_eq!(sessions.active_sessions["0"].pid, 1234);
+		assert!(sessions.active_sessions["0"].project.is_some());
+	}
+
+	#[test]
+	fn config() {
+		for fixture in [CONFIG_V0, CONFIG_V1] {
+			let table = state::upgrade::<Config>(fixture).unwrap();
+
+			assert_eq!(table.get("port"), Some(&Value::Integer(8080)));
+			assert_eq!(table.get("use_git"), Some(&Value::Boolean(false)));
+			assert!(table.get(state::SCHEMA_VERSION_KEY).is_none());
+		}
+	}
+
+	struct Renamed;
+
+	impl State for Renamed {
+		const NAME: &'static str = "Renamed";
+		const VERSION: u32 = 2;
+		const MIGRATIONS: &'static [Migration] = &[state::unversioned, |table| {
+			if let Some(value) = table.remove("old") {
+				table.insert("new".into(), value);
+			}
+
+			Ok(())
+		}];
+	}
+
+	#[test]
+	fn migrate() {
+		let mut table: Table = toml::from_str("old = 1").unwrap();
+
+		assert_eq!(state::migrate::<Renamed>(&mut table).unwrap(), Schema::Migrated(0));
+		assert_eq!(table.get("new"), Some(&Value::Integer(1)));
+		assert_eq!(state::version(&table).unwrap(), 2);
+
+		// Migrations before the stored version are skipped
+		let mut table: Table = toml::from_str("schema_version = 2\nold = 1").unwrap();
+
+		assert_eq!(state::migrate::<Renamed>(&mut table).unwrap(), Schema::Current);
+		assert_eq!(table.get("old"), Some(&Value::Integer(1)));
+
+		let mut table: Table = toml::from_str("schema_version = 3\nold = 1").unwrap();
+
+		assert_eq!(state::migrate::<Renamed>(&mut table).unwrap(), Schema::Newer(3));
+		assert_eq!(table.get("old"), Some(&Value::Integer(1)));
+		assert_eq!(state::version(&table).unwrap(), 3);
+	}
+
+	#[test]
+	fn save() {
+		let (_dir, path) = temp_file("save");
+		let status = UpdateStatus::default();
+
+		state::save(&path, &status).unwrap();
+
+		let table: Table = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+
+		assert_eq!(state::version(&table).unwrap(), UpdateStatus::VERSION);
+		assert!(!path.with_file_name("save.toml.tmp").exists());
+
+		let loaded: UpdateStatus = state::load(&path).unwrap();
+
+		assert_eq!(loaded.templates_version, status.templates_version);
+	}
+
+	#[test]
+	fn downgrade() {
+		let (_dir, path) = temp_file("downgrade");
+		let newer = UPDATE_STATUS_V1.replace("schema_version = 1", "schema_version = 99");
+
+		fs::write(&path, &newer).unwrap();
+
+		// Newer files are read on a best-effort basis but never overwritten
+		let status: UpdateStatus = state::load(&path).unwrap();
+		assert_eq!(status.vscode_version, "1.2.0");
+
+		state::save(&path, &UpdateStatus::default()).unwrap();
+		assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+
+		// Same applies to files that can't be read anymore
+		fs::write(&path, "schema_version = 99\nplugin_version = 2").unwrap();
+
+		assert!(state::load::<UpdateStatus>(&path).is_none());
+
+		state::save(&path, &UpdateStatus::default()).unwrap();
+		assert_eq!(
+			fs::read_to_string(&path).unwrap(),
+			"schema_version = 99\nplugin_version = 2"
+		);
+	}
+
+	#[test]
+	fn corrupted() {
+		let (_dir, path) = temp_file("corrupted");
+
+		fs::write(&path, "plugin_version = ").unwrap();
+
+		assert!(state::load::<UpdateStatus>(&path).is_none());
+
+		state::save(&path, &UpdateStatus::default()).unwrap();
+		assert!(state::load::<UpdateStatus>(&path).is_some());
+	}
+}
+
+mod workspace {
+	use crate::common::TempDir;
+	use argon::{
+		constants::SYNCBACK_DEBOUNCE_TIME,
+		core::Core,
+		manifest::{Member, WorkspaceManifest},
+		project::Project,
+		server::{Message, SyncDetails},
+		vfs::{debouncer::WatchHub, Vfs, VfsEvent},
+	};
+	use std::{fs, path::Path, sync::Arc, thread, time::Duration};
+
+	const TIMEOUT: Duration = Duration::from_secs(5);
+
+	fn project(name: &str) -> String {
+		format!(
+			r#"{{
+	"name": "{}",
+	"tree": {{
+		"$className": "DataModel",
+		"ServerScriptService": {{ "$path": "src" }},
+		"ReplicatedStorage": {{ "$path": "../shared" }}
+	}}
+}}"#,
+			name
+		)
+	}
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		for member in ["game", "plugin"] {
+			fs::create_dir_all(dir.join(member).join("src")).unwrap();
+			fs::write(dir.join(member).join("default.project.json"), project(member)).unwrap();
+			fs::write(dir.join(member).join("src/init.server.luau"), "print(1)").unwrap();
+		}
+
+		fs::create_dir_all(dir.join("shared/util")).unwrap();
+		fs::write(dir.join("shared/util/init.luau"), "return {}").unwrap();
+
+		fs::write(
+			dir.join("argon.workspace.json"),
+			r#"{ "members": [{ "path": "game" }, { "name": "studio-plugin", "path": "plugin/default.project.json" }] }"#,
+		)
+		.unwrap();
+
+		dir
+	}
+
+	fn core(hub: &Arc<WatchHub>, path: &Path) -> Core {
+		let project = Project::load(path).unwrap();
+		let core = Core::with_vfs(project, Vfs::with_hub(hub.clone())).unwrap();
+
+		core.queue().subscribe_internal().unwrap();
+		core
+	}
+
+	/// Waits for the first message matching the predicate, `None` if there is no such message
+	fn wait_for(core: &Core, predicate: impl Fn(&Message) -> bool) -> Option<Message> {
+		let queue = core.queue();
+
+		while let Some(message) = queue.get_with_timeout(0, TIMEOUT).unwrap() {
+			if predicate(&message) {
+				return Some(message);
+			}
+		}
+
+		None
+	}
+
+	#[test]
+	fn manifest() {
+		let dir = fixture("manifest");
+		let path = dir.join("argon.workspace.json");
+
+		let members = WorkspaceManifest::load(&path).unwrap().members(&path).unwrap();
+
+		assert_eq!(
+			members,
+			vec![
+				Member {
+					name: String::from("game"),
+					project: dir.join("game/default.project.json"),
+				},
+				Member {
+					name: String::from("studio-plugin"),
+					project: dir.join("plugin/default.project.json"),
+				},
+			]
+		);
+
+		for invalid in [
+			r#"{ "members": [] }"#,
+			r#"{ "members": [{ "path": "game" }, { "path": "game" }] }"#,
+			r#"{ "members": [{ "path": "game" }, { "name": "game", "path": "plugin" }] }"#,
+			r#"{ "members": [{ "name": "snapshot", "path": "game" }] }"#,
+			r#"{ "members": [{ "name": "game/client", "path": "game" }] }"#,
+		] {
+			let manifest: WorkspaceManifest = serde_json::from_str(invalid).unwrap();
+			assert!(manifest.members(&path).is_err(), "{}", invalid);
+		}
+	}
+
+	#[test]
+	fn shared_watches() {
+		let dir = fixture("shared-watches");
+		let hub = Arc::new(WatchHub::new());
+
+		let game = Vfs::with_hub(hub.clone());
+		let plugin = Vfs::with_hub(hub.clone());
+
+		// The same directory reached through different paths is watched once
+		game.watch(&dir.join("shared"), true).unwrap();
+		plugin.watch(&dir.join("plugin/../shared"), true).unwrap();
+
+		let shared = fs::canonicalize(dir.join("shared")).unwrap();
+		let watched = hub.watched();
+
+		assert!(!watched.is_empty());
+		assert!(watched
+			.iter()
+			.all(|(path, _, count)| path.starts_with(&shared) && *count == 2));
+
+		// Events of subscribers created just now are dropped like after syncback
+		thread::sleep(SYNCBACK_DEBOUNCE_TIME);
+
+		hub.dispatch(&VfsEvent::Write(shared.join("util/init.luau")));
+
+		assert!(matches!(
+			game.receiver().recv_timeout(TIMEOUT).unwrap(),
+			VfsEvent::Write(path) if path == dir.join("shared/util/init.luau")
+		));
+		assert!(matches!(
+			plugin.receiver().recv_timeout(TIMEOUT).unwrap(),
+			VfsEvent::Write(path) if path == dir.join("plugin/../shared/util/init.luau")
+		));
+
+		// Paths stay watched until the last member unwatches them
+		game.unwatch(&dir.join("shared")).unwrap();
+
+		assert!(hub.watched().iter().all(|(_, _, count)| *count == 1));
+
+		drop(plugin);
+
+		assert!(hub.watched().is_empty());
+	}
+
+	#[test]
+	fn independent_reload() {
+		let dir = fixture("independent-reload");
+		let hub = Arc::new(WatchHub::new());
+
+		let game = core(&hub, &dir.join("game/default.project.json"));
+		let plugin = core(&hub, &dir.join("plugin/default.project.json"));
+
+		thread::sleep(SYNCBACK_DEBOUNCE_TIME);
+
+		let game_project = dir.join("game/default.project.json");
+		fs::write(&game_project, project("renamed")).unwrap();
+
+		// OS event may come as well, handling it twice does not matter
+		hub.dispatch(&VfsEvent::Write(fs::canonicalize(&game_project).unwrap()));
+
+		assert!(wait_for(&game, |message| matches!(message, Message::SyncDetails(SyncDetails(_)))).is_some());
+		assert_eq!(game.name(), "renamed");
+
+		assert!(plugin
+			.queue()
+			.get_with_timeout(0, Duration::from_millis(500))
+			.unwrap()
+			.is_none());
+		assert_eq!(plugin.name(), "plugin");
+
+		// Both members see changes of the shared directory
+		let module = dir.join("shared/util/Added.luau");
+		fs::write(&module, "return 1").unwrap();
+
+		hub.dispatch(&VfsEvent::Create(fs::canonicalize(&module).unwrap()));
+
+		for core in [&game, &plugin] {
+			assert!(wait_for(core, |message| matches!(message, Message::SyncChanges(_))).is_some());
+			assert!(core.tree().get_ids(&module).is_some_and(|ids| !ids.is_empty()));
+		}
+	}
+}
+
+mod stdin_project {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::Core,
+		project::{self, Project, STDIN_PROJECT_NAME},
+	};
+	use std::{fs, path::Path};
+
+	const PROJECT: &str = r#"{
+	"name": "Generated",
+	"tree": {
+		"$className": "Folder",
+		"Source": { "$path": "src" },
+		"Value": {
+			"$className": "NumberValue",
+			"$properties": { "Value": 1.5 }
+		}
+	}
+}"#;
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+		fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+		fs::write(dir.join("src/init.luau"), "return {}").unwrap();
+		fs::write(dir.join("src/Module.luau"), "return 1").unwrap();
+
+		dir
+	}
+
+	fn from_stdin(dir: &Path) -> Core {
+		let project = Project::load_from_str(PROJECT, dir).unwrap();
+
+		assert!(project.from_stdin);
+		assert_eq!(project.path, dir.join(STDIN_PROJECT_NAME));
+		assert_eq!(&project.workspace_dir, dir);
+
+		Core::new(project, false).unwrap()
+	}
+
+	#[test]
+	fn build() {
+		let dir = fixture("build");
+
+		for ext in ["rbxm", "rbxmx"] {
+			let file = dir.join(format!("File.{}", ext));
+			let stdin = dir.join(format!("Stdin.{}", ext));
+
+			start(&dir).build(&file, ext == "rbxmx", true).unwrap();
+			from_stdin(&dir).build(&stdin, ext == "rbxmx", true).unwrap();
+
+			assert_eq!(fs::read(&file).unwrap(), fs::read(&stdin).unwrap());
+		}
+	}
+
+	#[test]
+	fn hash() {
+		let dir = fixture("hash");
+
+		assert_eq!(start(&dir).tree_hash(), from_stdin(&dir).tree_hash());
+	}
+
+	#[test]
+	fn paths() {
+		let dir = fixture("paths");
+		let core = from_stdin(&dir);
+
+		// `$path` entries are resolved relative to the workspace directory
+		assert!(core.tree().get_ids(&dir.join("src")).is_some());
+		assert!(core.tree().get_ids(&dir.join("src/Module.luau")).is_some());
+
+		let sourcemap = dir.join("sourcemap.json");
+		core.sourcemap(Some(sourcemap.clone()), true).unwrap();
+
+		let sourcemap = fs::read_to_string(sourcemap).unwrap();
+
+		assert!(sourcemap.contains("Module.luau"));
+		assert!(!sourcemap.contains(STDIN_PROJECT_NAME));
+
+		// Synthetic project file can't be reloaded
+		assert!(core.project().reload().is_err());
+	}
+
+	#[test]
+	fn workspace_dir() {
+		let dir = fixture("workspace-dir");
+
+		let err = project::stdin_path(None).unwrap_err();
+		assert!(err.to_string().contains("--workspace-dir"));
+
+		assert!(project::stdin_path(Some(&dir.join("missing"))).is_err());
+		assert_eq!(project::stdin_path(Some(&dir)).unwrap(), dir.join(STDIN_PROJECT_NAME));
+
+		assert!(Project::load_from_str("{ invalid", &dir).is_err());
+	}
+}
+
+mod builds {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{meta::Meta, processor::write, snapshot::AddedSnapshot, Core},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::{env, fs, path::Path, process::Command};
+
+	const PROJECT: &str = r#"{
+	"name": "Builds",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": { "$path": "src" },
+		"Workspace": {
+			"Part": {
+				"$className": "Part",
+				"$properties": { "Anchored": true, "Transparency": 0.5 }
+			},
+			"Model": { "$className": "Model" }
+		}
+	}
+}"#;
+
+	const STRINGS: &str = "Key,Source,en,de,es,fr,ja,pl\nHello,Hello,Hello,Hallo,Hola,Bonjour,Konnichiwa,Witaj\n";
+
+	/// Set for the child process started by `across_processes`
+	const CHILD_DIR: &str = "ARGON_TEST_BUILD_DIR";
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src/Nested")).unwrap();
+		fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+
+		for name in ["Zeta", "Alpha", "Module", "Gamma", "Beta"] {
+			fs::write(dir.join(format!("src/{}.luau", name)), format!("return '{}'", name)).unwrap();
+		}
+
+		fs::write(dir.join("src/Nested/init.server.luau"), "print('Nested')").unwrap();
+		fs::write(dir.join("src/Nested/Value.txt"), "Value").unwrap();
+		fs::write(dir.join("src/Strings.csv"), STRINGS).unwrap();
+
+		dir
+	}
+
+	/// Builds a fresh core from the given directory in both formats
+	fn build(dir: &Path, name: &str) -> [Vec<u8>; 2] {
+		let core = start(dir);
+
+		["rbxl", "rbxlx"].map(|ext| {
+			let path = dir.join(format!("{}.{}", name, ext));
+
+			core.build(&path, ext == "rbxlx", true).unwrap();
+			fs::read(path).unwrap()
+		})
+	}
+
+	fn storage(core: &Core) -> Ref {
+		let tree = core.tree();
+		let dom = tree.inner();
+
+		*dom.root()
+			.children()
+			.iter()
+			.find(|child| dom.get_by_ref(**child).unwrap().name == "ReplicatedStorage")
+			.unwrap()
+	}
+
+	#[test]
+	fn within_process() {
+		let dir = fixture("within");
+
+		assert_eq!(build(&dir, "First"), build(&dir, "Second"));
+	}
+
+	#[test]
+	fn across_processes() {
+		let dir = fixture("across");
+		let expected = build(&dir, "Parent");
+
+		// String hashers are seeded differently in every process
+		let status = Command::new(env::current_exe().unwrap())
+			.args(["builds::child_process", "--exact", "--test-threads=1"])
+			.env(CHILD_DIR, dir.path())
+			.status()
+			.unwrap();
+
+		assert!(status.success());
+		assert_eq!(fs::read(dir.join("Child.rbxl")).unwrap(), expected[0]);
+		assert_eq!(fs::read(dir.join("Child.rbxlx")).unwrap(), expected[1]);
+	}
+
+	#[test]
+	fn child_process() {
+		if let Ok(dir) = env::var(CHILD_DIR) {
+			build(Path::new(&dir), "Child");
+		}
+	}
+
+	#[test]
+	fn insertion_order() {
+		let dir = fixture("order");
+		let core = start(&dir);
+
+		// Instances added while serving end up last in the tree
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from("Source"), Variant::String(String::from("return 'Aardvark'")));
+
+		let snapshot = AddedSnapshot {
+			id: Ref::new(),
+			meta: Meta::new(),
+			parent: storage(&core),
+			name: String::from("Aardvark"),
+			class: Ustr::from("ModuleScript"),
+			properties,
+			children: vec![],
+		};
+
+		write::apply_addition(snapshot, &mut core.tree(), &Vfs::new(false)).unwrap();
+
+		let path = dir.join("Served.rbxlx");
+		core.build(&path, true, true).unwrap();
+
+		assert_eq!(fs::read(path).unwrap(), build(&dir, "Fresh")[1]);
+	}
+
+	#[test]
+	fn localized_diff() {
+		let dir = fixture("diff");
+		let before = String::from_utf8(build(&dir, "Before")[1].clone()).unwrap();
+
+		fs::write(dir.join("src/Module.luau"), "return 'Changed'").unwrap();
+
+		let after = String::from_utf8(build(&dir, "After")[1].clone()).unwrap();
+
+		let before: Vec<&str> = before.lines().collect();
+		let after: Vec<&str> = after.lines().collect();
+
+		assert_eq!(before.len(), after.len());
+
+		let changed: Vec<(&str, &str)> = before
+			.into_iter()
+			.zip(after)
+			.filter(|(before, after)| before != after)
+			.collect();
+
+		// Source of the module and the embedded tree hash
+		assert_eq!(changed.len(), 2, "{:#?}", changed);
+		assert!(changed.iter().any(|(_, after)| after.contains("return 'Changed'")));
+	}
+}
+
+mod mcp {
+	use crate::common::TempDir;
+	use anyhow::{anyhow, Result};
+	use argon::mcp::{self, Decision, Guard, McpConfig, Outcome, Permission};
+	use futures::executor::block_on;
+	use std::{cell::Cell, fs, path::Path};
+
+	const TOOL: &str = "roblox_developer_forum";
+
+	fn fixture(name: &str, config: &str) -> (TempDir, Guard) {
+		let dir = TempDir::new(name);
+
+		fs::write(dir.join("mcp.toml"), config).unwrap();
+
+		let guard = guard(&dir);
+
+		(dir, guard)
+	}
+
+	fn guard(dir: &Path) -> Guard {
+		Guard::new(McpConfig::load(&dir.join("mcp.toml")).unwrap(), dir)
+	}
+
+	/// Stands in for the Pinecone HTTP layer, counting requests sent to it
+	struct Pinecone {
+		requests: Cell<usize>,
+		response: Result<&'static str, &'static str>,
+	}
+
+	impl Pinecone {
+		fn new(response: Result<&'static str, &'static str>) -> Self {
+			Self {
+				requests: Cell::new(0),
+				response,
+			}
+		}
+
+		async fn query(&self) -> Result<String> {
+			self.requests.set(self.requests.get() + 1);
+			self.response.map(String::from).map_err(|err| anyhow!(err))
+		}
+	}
+
+	fn call(guard: &Guard, pinecone: &Pinecone) -> Outcome {
+		block_on(guard.call(TOOL, || pinecone.query()))
+	}
+
+	fn audit(dir: &Path) -> Vec<serde_json::Value> {
+		fs::read_to_string(dir.join("mcp-audit.jsonl"))
+			.unwrap_or_default()
+			.lines()
+			.map(|line| serde_json::from_str(line).unwrap())
+			.collect()
+	}
+
+	#[test]
+	fn config() {
+		let config: McpConfig = toml::from_str(
+			r#"
+		default = "deny"
+
+		[tools]
+		roblox_developer_forum = "allow"
+		luau_documentation = "ask"
+		"#,
+		)
+		.unwrap();
+
+		assert!(!config.offline);
+		assert_eq!(config.permission(TOOL), Permission::Allow);
+		assert_eq!(config.permission("luau_documentation"), Permission::Ask);
+		assert_eq!(config.permission("roblox_engine_documentation"), Permission::Deny);
+
+		// Tools have to be approved unless configured otherwise
+		let config = McpConfig::load(Path::new("missing/mcp.toml")).unwrap();
+		assert_eq!(config.permission(TOOL), Permission::Ask);
+
+		assert!(mcp::is_network_tool("luau_documentation"));
+		assert!(!mcp::is_network_tool("validate_project_edit"));
+		assert!(!mcp::is_tool("exec"));
+	}
+
+	#[test]
+	fn allow() {
+		let (dir, guard) = fixture("allow", r#"tools = { roblox_developer_forum = "allow" }"#);
+		let pinecone = Pinecone::new(Ok("context"));
+
+		assert!(matches!(call(&guard, &pinecone), Outcome::Ran(Ok(body)) if body == "context"));
+		assert_eq!(pinecone.requests.get(), 1);
+
+		let audit = audit(&dir);
+
+		assert_eq!(audit.len(), 1);
+		assert_eq!(audit[0]["tool"], TOOL);
+		assert_eq!(audit[0]["permission"], "allow");
+		assert_eq!(audit[0]["decision"], "allowed");
+		assert_eq!(audit[0]["success"], true);
+
+		// Failed requests are audited too
+		let pinecone = Pinecone::new(Err("Pinecone API error (500)"));
+
+		assert!(matches!(call(&guard, &pinecone), Outcome::Ran(Err(_))));
+		assert_eq!(self::audit(&dir)[1]["error"], "Pinecone API error (500)");
+	}
+
+	#[test]
+	fn deny() {
+		let (dir, guard) = fixture("deny", r#"default = "deny""#);
+		let pinecone = Pinecone::new(Ok("context"));
+
+		assert!(matches!(call(&guard, &pinecone), Outcome::Refused(Decision::Denied)));
+		assert_eq!(pinecone.requests.get(), 0);
+
+		// Approvals do not override denied tools
+		mcp::approve(&dir, TOOL).unwrap();
+
+		assert_eq!(guard.check(TOOL), Decision::Denied);
+		assert_eq!(audit(&dir)[0]["decision"], "denied");
+		assert!(audit(&dir)[0].get("success").is_none());
+	}
+
+	#[test]
+	fn ask() {
+		let (dir, guard) = fixture("ask", "");
+		let pinecone = Pinecone::new(Ok("context"));
+
+		// First call returns a structured result instead of running the tool
+		assert!(matches!(
+			call(&guard, &pinecone),
+			Outcome::Refused(Decision::NeedsApproval)
+		));
+		assert_eq!(pinecone.requests.get(), 0);
+
+		let refusal = Decision::NeedsApproval.to_json(TOOL);
+
+		assert_eq!(refusal["status"], "needs_approval");
+		assert_eq!(refusal["tool"], TOOL);
+		assert!(refusal["message"]
+			.as_str()
+			.unwrap()
+			.contains("argon mcp approve roblox_developer_forum"));
+
+		// Approval unlocks the tool for the rest of the session
+		mcp::approve(&dir, TOOL).unwrap();
+		assert_eq!(mcp::pending_approvals(&dir), vec![TOOL]);
+
+		assert!(matches!(call(&guard, &pinecone), Outcome::Ran(Ok(_))));
+		assert!(matches!(call(&guard, &pinecone), Outcome::Ran(Ok(_))));
+		assert_eq!(pinecone.requests.get(), 2);
+
+		// Approval is taken over by the session that used it
+		assert!(mcp::pending_approvals(&dir).is_empty());
+		assert_eq!(guard.check("luau_documentation"), Decision::NeedsApproval);
+		assert_eq!(self::guard(&dir).check(TOOL), Decision::NeedsApproval);
+
+		let decisions: Vec<_> = audit(&dir)
+			.iter()
+			.map(|entry| entry["decision"].as_str().unwrap().to_owned())
+			.collect();
+
+		assert_eq!(decisions, ["needs_approval", "approved", "approved"]);
+	}
+
+	#[test]
+	fn offline() {
+		let (dir, guard) = fixture(
+			"offline",
+			r#"
+		offline = true
+		default = "allow"
+		"#,
+		);
+		let pinecone = Pinecone::new(Ok("context"));
+
+		assert!(matches!(call(&guard, &pinecone), Outcome::Refused(Decision::Offline)));
+		assert_eq!(pinecone.requests.get(), 0);
+
+		let refusal = Decision::Offline.to_json(TOOL);
+
+		assert_eq!(refusal["status"], "offline");
+		assert!(refusal["message"].as_str().unwrap().contains("offline"));
+
+		// Approvals do not override the offline setting either
+		mcp::approve(&dir, TOOL).unwrap();
+
+		assert_eq!(guard.check(TOOL), Decision::Offline);
+		assert_eq!(audit(&dir)[0]["decision"], "offline");
+	}
+}
+
+// Hooks run `sh` and `tr`
+#[cfg(unix)]
+mod hooks {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{processor::write, snapshot::UpdatedSnapshot, Core},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{types::Variant, Ustr, UstrMap};
+	use std::{fs, path::Path};
+
+	const UPPERCASE: &str = r#"{ "command": ["sh", "-c", "echo run >> runs.log; tr a-z A-Z"], "glob": "src/*.txt" }"#;
+
+	fn fixture(name: &str, hooks: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+		fs::write(dir.join("src/Value.txt"), "hello").unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			format!(
+				r#"{{
+				"name": "Hooks",
+				"hooks": {},
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{ "$path": "src" }}
+				}}
+			}}"#,
+				hooks
+			),
+		)
+		.unwrap();
+
+		dir
+	}
+
+	fn value(core: &Core, path: &Path) -> Option<String> {
+		let tree = core.tree();
+		let id = tree.get_ids(path).unwrap()[0];
+
+		match tree.get_instance(id).unwrap().properties.get(&Ustr::from("Value")) {
+			Some(Variant::String(value)) => Some(value.clone()),
+			_ => None,
+		}
+	}
+
+	fn runs(dir: &Path) -> usize {
+		fs::read_to_string(dir.join("runs.log"))
+			.unwrap_or_default()
+			.lines()
+			.count()
+	}
+
+	#[test]
+	fn postsnapshot() {
+		let dir = fixture("postsnapshot", &format!(r#"{{ "postsnapshot": [{}] }}"#, UPPERCASE));
+		let core = start(&dir);
+
+		assert_eq!(value(&core, &dir.join("src/Value.txt")).unwrap(), "HELLO");
+
+		// Source file is never changed
+		assert_eq!(fs::read_to_string(dir.join("src/Value.txt")).unwrap(), "hello");
+	}
+
+	#[test]
+	fn prewrite() {
+		let dir = fixture(
+			"prewrite",
+			r#"{ "prewrite": [{ "command": ["tr", "a-z", "A-Z"], "middleware": ["StringValue"] }] }"#,
+		);
+
+		let core = start(&dir);
+		let id = core.tree().get_ids(&dir.join("src/Value.txt")).unwrap()[0];
+
+		assert_eq!(value(&core, &dir.join("src/Value.txt")).unwrap(), "hello");
+
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from("Value"), Variant::String(String::from("world")));
+
+		let mut update = UpdatedSnapshot::new(id);
+		update.properties = Some(properties);
+
+		write::apply_update(update, &mut core.tree(), &Vfs::new(false)).unwrap();
+
+		assert_eq!(fs::read_to_string(dir.join("src/Value.txt")).unwrap(), "WORLD");
+	}
+
+	#[test]
+	fn caching() {
+		let dir = fixture("caching", &format!(r#"{{ "postsnapshot": [{}] }}"#, UPPERCASE));
+
+		fs::write(dir.join("src/Same.txt"), "hello").unwrap();
+		fs::write(dir.join("src/Other.txt"), "other").unwrap();
+
+		let core = self::start(&dir);
+
+		assert_eq!(value(&core, &dir.join("src/Same.txt")).unwrap(), "HELLO");
+		assert_eq!(value(&core, &dir.join("src/Other.txt")).unwrap(), "OTHER");
+		assert_eq!(runs(&dir), 2);
+
+		// Cache is kept on disk so it outlives the session
+		drop(core);
+
+		let core = self::start(&dir);
+
+		assert_eq!(value(&core, &dir.join("src/Value.txt")).unwrap(), "HELLO");
+		assert_eq!(runs(&dir), 2);
+	}
+
+	#[test]
+	fn failures() {
+		let dir = fixture(
+			"failures",
+			r#"{ "postsnapshot": [
+			{ "command": ["sh", "-c", "exit 1"] },
+			{ "command": ["sleep", "5"], "timeout": 1 },
+			{ "command": ["../outside.sh"] }
+		] }"#,
+		);
+
+		// File is synced unprocessed when optional hooks fail
+		let core = start(&dir);
+		assert_eq!(value(&core, &dir.join("src/Value.txt")).unwrap(), "hello");
+
+		let dir = fixture(
+			"required",
+			r#"{ "postsnapshot": [{ "command": ["sh", "-c", "echo broken >&2; exit 1"], "required": true }] }"#,
+		);
+
+		let core = self::start(&dir);
+		let tree = core.tree();
+		let id = tree.get_ids(&dir.join("src/Value.txt")).unwrap()[0];
+
+		// Failure of required hook fails the file which becomes a placeholder
+		let error = tree.get_meta(id).unwrap().load_error.as_ref().unwrap();
+
+		assert!(error.error.contains("Required postsnapshot hook"), "{}", error.error);
+		assert!(error.error.contains("broken"));
+	}
+}
+
+mod import {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::tree::Tree,
+		import::{self, ImportOptions},
+		project::Project,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant, Vector3},
+		InstanceBuilder, Ustr, WeakDom,
+	};
+	use std::fs::{self, File};
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+		let root = dom.root_ref();
+
+		let storage = dom.insert(root, InstanceBuilder::new("ReplicatedStorage"));
+		let shared = dom.insert(storage, InstanceBuilder::new("Folder").with_name("Shared"));
+
+		dom.insert(
+			shared,
+			InstanceBuilder::new("ModuleScript")
+				.with_name("Util")
+				.with_property("Source", "return 1"),
+		);
+
+		let scripts = dom.insert(root, InstanceBuilder::new("ServerScriptService"));
+
+		dom.insert(
+			scripts,
+			InstanceBuilder::new("Script")
+				.with_name("Main")
+				.with_property("Source", "print(1)"),
+		);
+
+		let workspace = dom.insert(root, InstanceBuilder::new("Workspace"));
+
+		let baseplate = dom.insert(
+			workspace,
+			InstanceBuilder::new("Part")
+				.with_name("Baseplate")
+				.with_property("Size", Vector3::new(100.0, 1.0, 100.0))
+				.with_property("Anchored", true),
+		);
+
+		dom.insert(
+			workspace,
+			InstanceBuilder::new("ObjectValue")
+				.with_name("Target")
+				.with_property("Value", baseplate),
+		);
+
+		// Empty services are not imported
+		dom.insert(root, InstanceBuilder::new("Lighting"));
+
+		let file = File::create(dir.join("place.rbxlx")).unwrap();
+		rbx_xml::to_writer_default(file, &dom, dom.root().children()).unwrap();
+
+		dir
+	}
+
+	fn options(services: Option<Vec<String>>) -> ImportOptions {
+		ImportOptions {
+			project_name: String::from("Imported"),
+			services,
+		}
+	}
+
+	fn property(tree: &Tree, path: &str, name: &str) -> Option<Variant> {
+		let id = tree.find_by_path(path).unwrap();
+		tree.get_instance(id)
+			.unwrap()
+			.properties
+			.get(&Ustr::from(name))
+			.cloned()
+	}
+
+	fn count(tree: &Tree, id: Ref) -> usize {
+		let mut count = 0;
+		let mut stack = vec![id];
+
+		while let Some(id) = stack.pop() {
+			count += 1;
+			stack.extend(tree.get_instance(id).unwrap().children());
+		}
+
+		count
+	}
+
+	#[test]
+	fn import_place() {
+		let dir = fixture("place");
+		let dom = import::read_place(&dir.join("place.rbxlx")).unwrap();
+
+		let mut services = import::get_services(&dom);
+		services.sort();
+
+		assert_eq!(services, ["ReplicatedStorage", "ServerScriptService", "Workspace"]);
+
+		let report = import::import(&dom, &dir, &options(None)).unwrap();
+
+		assert_eq!(report.added, 5);
+		assert!(report.unrepresented.is_empty());
+		assert_eq!(report.lossy_properties.get("ObjectValue.Value"), Some(&1));
+
+		let project = Project::load(&report.project_path).unwrap();
+
+		assert_eq!(project.name, "Imported");
+		assert!(project.node.tree.contains_key("Workspace"));
+		assert!(!project.node.tree.contains_key("Lighting"));
+
+		let core = start(&dir);
+		let tree = core.tree();
+
+		assert_eq!(
+			property(&tree, "ReplicatedStorage.Shared.Util", "Source"),
+			Some(Variant::String(String::from("return 1")))
+		);
+		assert_eq!(
+			property(&tree, "ServerScriptService.Main", "Source"),
+			Some(Variant::String(String::from("print(1)")))
+		);
+		assert_eq!(
+			property(&tree, "Workspace.Baseplate", "Size"),
+			Some(Variant::Vector3(Vector3::new(100.0, 1.0, 100.0)))
+		);
+
+		// Default values are not written
+		assert!(property(&tree, "Workspace.Baseplate", "Transparency").is_none());
+	}
+
+	#[test]
+	fn selected_services() {
+		let dir = fixture("services");
+		let dom = import::read_place(&dir.join("place.rbxlx")).unwrap();
+
+		let report = import::import(&dom, &dir, &options(Some(vec![String::from("ServerScriptService")]))).unwrap();
+
+		assert_eq!(report.added, 1);
+
+		let project = Project::load(&report.project_path).unwrap();
+
+		assert!(project.node.tree.contains_key("ServerScriptService"));
+		assert!(!project.node.tree.contains_key("ReplicatedStorage"));
+		assert!(!dir.join("src/ReplicatedStorage").exists());
+	}
+
+	#[test]
+	fn idempotent() {
+		let dir = fixture("idempotent");
+		let dom = import::read_place(&dir.join("place.rbxlx")).unwrap();
+
+		import::import(&dom, &dir, &options(None)).unwrap();
+
+		let project = fs::read_to_string(dir.join("default.project.json")).unwrap();
+		let before = {
+			let core = start(&dir);
+			let tree = core.tree();
+			count(&tree, tree.root_ref())
+		};
+
+		let report = import::import(&dom, &dir, &options(None)).unwrap();
+
+		assert_eq!(report.added, 0);
+		assert_eq!(report.updated, 0);
+		assert_eq!(fs::read_to_string(dir.join("default.project.json")).unwrap(), project);
+
+		let core = start(&dir);
+		let tree = core.tree();
+
+		assert_eq!(count(&tree, tree.root_ref()), before);
+	}
+}
+
+mod bench {
+	use crate::common::TempDir;
+	use argon::bench::{self, BenchReport, Metrics, Operation};
+	use std::{collections::BTreeMap, fs, time::Duration};
+
+	fn report(p50: f64, p95: f64, throughput: f64) -> BenchReport {
+		BenchReport {
+			iterations: 10,
+			operations: BTreeMap::from([(String::from("smallEdit"), Metrics { p50, p95, throughput })]),
+		}
+	}
+
+	#[test]
+	fn sync() {
+		let fixture = TempDir::new("fixture");
+
+		fs::create_dir_all(fixture.join("Shared")).unwrap();
+		fs::write(fixture.join("Shared/Util.luau"), "return {}").unwrap();
+
+		let report = bench::run(Some(fixture.path()), 2).unwrap();
+
+		assert_eq!(report.iterations, 2);
+		assert_eq!(report.operations.len(), Operation::ALL.len());
+
+		for operation in Operation::ALL {
+			let metrics = &report.operations[&operation.to_string()];
+
+			assert!(metrics.p50 > 0.0);
+			assert!(metrics.p95 >= metrics.p50);
+			assert!(metrics.throughput > 0.0);
+		}
+
+		// Report can be used as a baseline
+		let json = serde_json::to_string(&report).unwrap();
+		let baseline: BenchReport = serde_json::from_str(&json).unwrap();
+
+		assert_eq!(
+			baseline.operations.keys().collect::<Vec<_>>(),
+			report.operations.keys().collect::<Vec<_>>()
+		);
+		assert!(bench::compare(&baseline, &report, 0.01).is_empty());
+	}
+
+	#[test]
+	fn percentiles() {
+		let latencies: Vec<Duration> = (1..=20).rev().map(Duration::from_millis).collect();
+		let metrics = Metrics::new(&latencies);
+
+		assert_eq!(metrics.p50, 10.0);
+		assert_eq!(metrics.p95, 19.0);
+		assert!((metrics.throughput - 20.0 / 0.21).abs() < 1e-6);
+	}
+
+	#[test]
+	fn threshold() {
+		assert_eq!(bench::parse_threshold("20%").unwrap(), 0.2);
+		assert_eq!(bench::parse_threshold("5").unwrap(), 0.05);
+		assert!(bench::parse_threshold("-5%").is_err());
+		assert!(bench::parse_threshold("fast").is_err());
+
+		let baseline = report(10.0, 20.0, 100.0);
+
+		// Within the threshold
+		assert!(bench::compare(&baseline, &report(11.9, 23.9, 85.0), 0.2).is_empty());
+
+		// Faster and higher throughput are never regressions
+		assert!(bench::compare(&baseline, &report(1.0, 2.0, 1000.0), 0.2).is_empty());
+
+		let regressions = bench::compare(&baseline, &report(12.5, 20.0, 70.0), 0.2);
+
+		assert_eq!(regressions.len(), 2);
+		assert_eq!(regressions[0].metric, "p50");
+		assert_eq!(regressions[0].change, 25.0);
+		assert_eq!(regressions[1].metric, "throughput");
+		assert!((regressions[1].change - 30.0).abs() < 1e-9);
+
+		// Operations missing from the baseline are skipped
+		let mut current = report(100.0, 100.0, 1.0);
+		current.operations = BTreeMap::from([(String::from("rename"), current.operations["smallEdit"].clone())]);
+
+		assert!(bench::compare(&baseline, &current, 0.2).is_empty());
+	}
+}
+
+mod open {
+	use argon::{
+		core::meta::{NodePath, Source, SourceEntry},
+		project::ProjectNode,
+		util::editor::{self, OpenError},
+	};
+	use std::{
+		ffi::OsStr,
+		io,
+		path::{Path, PathBuf},
+		process::Command,
+	};
+
+	fn args(command: &Command) -> Vec<&OsStr> {
+		command.get_args().collect()
+	}
+
+	#[test]
+	fn source_preference() {
+		let script = PathBuf::from("src/Module.luau");
+		let data = PathBuf::from("src/Module.meta.json");
+		let folder = PathBuf::from("src/Folder");
+
+		// Script file wins over the data file regardless of their order
+		let source = Source::file(&script)
+			.with_relevant(vec![SourceEntry::Data(data.clone()), SourceEntry::File(script.clone())]);
+
+		assert_eq!(source.get_editable(), Some(script.as_path()));
+
+		let mut source = Source::directory(&folder);
+		source.add_data(&data);
+
+		assert_eq!(source.get_editable(), Some(data.as_path()));
+
+		let mut source = Source::directory(&folder);
+		source.add_index(Path::new("src/.argon-data.json"), "Folder");
+
+		assert_eq!(source.get_editable(), Some(Path::new("src/.argon-data.json")));
+
+		// Plain folders have nothing to open
+		assert_eq!(Source::directory(&folder).get_editable(), None);
+
+		// Project nodes open the project file
+		let project = PathBuf::from("default.project.json");
+		let source = Source::project("Workspace", &project, ProjectNode::default(), NodePath::new());
+
+		assert_eq!(source.get_editable(), Some(project.as_path()));
+	}
+
+	#[test]
+	fn command_templating() {
+		let file = Path::new("/My Project/src/Module.luau");
+
+		let command = editor::command(file, Some(12), "nvim +{line} {file}").unwrap();
+
+		assert_eq!(command.get_program(), "nvim");
+		assert_eq!(args(&command), ["+12", "/My Project/src/Module.luau"]);
+
+		let command = editor::command(file, None, "zed {file}:{line}").unwrap();
+
+		assert_eq!(args(&command), ["/My Project/src/Module.luau:1"]);
+
+		// File is appended when the template does not reference it
+		let command = editor::command(file, Some(3), "subl -n").unwrap();
+
+		assert_eq!(args(&command), ["-n", "/My Project/src/Module.luau"]);
+
+		// VS Code or Cursor is used by default
+		let command = editor::command(file, Some(7), "").unwrap();
+
+		assert_eq!(
+			args(&command).last().copied(),
+			Some(OsStr::new("/My Project/src/Module.luau:7"))
+		);
+		assert!(args(&command).contains(&OsStr::new("--goto")));
+	}
+
+	#[test]
+	fn launch_errors() {
+		let file = Path::new("src/Module.luau");
+		let mut launched = vec![];
+
+		editor::open_with(file, Some(4), "edit {file}:{line}", |command| {
+			launched.push(
+				command
+					.get_args()
+					.map(|arg| arg.to_string_lossy().into_owned())
+					.collect::<Vec<_>>(),
+			);
+			Ok(())
+		})
+		.unwrap();
+
+		assert_eq!(launched, [["src/Module.luau:4"]]);
+
+		let err = editor::open_with(file, None, "missing {file}", |_| {
+			Err(io::Error::from(io::ErrorKind::NotFound))
+		})
+		.unwrap_err();
+
+		assert_eq!(
+			err,
+			OpenError::EditorNotFound {
+				command: String::from("missing")
+			}
+		);
+		assert_eq!(
+			serde_json::to_value(&err).unwrap(),
+			serde_json::json!({ "error": "editorNotFound", "command": "missing" })
+		);
+
+		let err = editor::open_with(file, None, "edit", |_| Err(io::Error::other("denied"))).unwrap_err();
+
+		assert!(matches!(err, OpenError::Failed { .. }));
+	}
+}
+
+mod apply_patch {
+	use crate::common::{start, TempDir};
+	use argon::core::{patch::Patch, Core};
+	use rbx_dom_weak::{
+		types::{Enum, Variant},
+		Ustr,
+	};
+	use std::{
+		collections::BTreeMap,
+		fs,
+		path::{Path, PathBuf},
+	};
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src/Map")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "ApplyPatch",
+			"tree": {
+				"$className": "DataModel",
+				"Workspace": {
+					"$path": "src"
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		for (name, data) in [
+			(
+				"Red",
+				r#"{ "className": "Part", "properties": { "Material": "Plastic", "Reflectance": 0.5 }, "attributes": { "Old": 1 } }"#,
+			),
+			(
+				"Blue",
+				r#"{ "className": "Part", "properties": { "Material": "Metal" }, "attributes": { "Old": 2 } }"#,
+			),
+			(
+				"Wedge",
+				r#"{ "className": "WedgePart", "properties": { "Material": "Plastic" } }"#,
+			),
+		] {
+			fs::create_dir_all(dir.join("src/Map").join(name)).unwrap();
+			fs::write(dir.join("src/Map").join(name).join("init.meta.json"), data).unwrap();
+		}
+
+		fs::write(dir.join("src/Map/Notes.txt"), "Plastic").unwrap();
+
+		dir
+	}
+
+	fn patch(json: &str) -> Patch {
+		serde_json::from_str(json).unwrap()
+	}
+
+	fn property(core: &Core, path: &str, name: &str) -> Option<Variant> {
+		let tree = core.tree();
+		let id = tree.find_by_path(path).unwrap();
+
+		tree.get_instance(id)
+			.and_then(|instance| instance.properties.get(&Ustr::from(name)).cloned())
+	}
+
+	/// Contents of all files in the directory, to tell whether anything was written
+	fn files(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+		let mut files = BTreeMap::new();
+
+		for entry in fs::read_dir(dir).unwrap() {
+			let path = entry.unwrap().path();
+
+			if path.is_dir() {
+				files.extend(self::files(&path));
+			} else {
+				files.insert(path.clone(), fs::read(&path).unwrap());
+			}
+		}
+
+		files
+	}
+
+	const ACTIONS: &str = r#"[
+	{ "select": "ClassName~BasePart Path~Map/*", "set": { "Material": "Brick" } },
+	{ "select": "ClassName=Part", "delete": ["Reflectance"] },
+	{ "select": "Path~Map/*", "renameAttribute": { "from": "Old", "to": "New" } },
+	{ "select": "Name~^(Red|Wedge)$", "addTag": "Patched" }
+]"#;
+
+	#[test]
+	fn actions() {
+		let dir = fixture("actions");
+		let report = start(&dir).apply_patch(&patch(ACTIONS), false, false).unwrap();
+
+		let touched: Vec<usize> = report
+			.operations
+			.iter()
+			.map(|operation| operation.instances.len())
+			.collect();
+
+		assert_eq!(touched, [3, 1, 2, 2]);
+		assert_eq!(report.operations[0].matched, 3);
+		assert_eq!(report.operations[2].matched, 4);
+		assert_eq!(report.failures(), 0);
+
+		// Changes are written to files, so a fresh tree has them too
+		let core = start(&dir);
+
+		for name in ["Red", "Blue", "Wedge"] {
+			assert_eq!(
+				property(&core, &format!("Workspace.Map.{}", name), "Material"),
+				Some(Variant::Enum(Enum::from_u32(848)))
+			);
+		}
+
+		assert_eq!(property(&core, "Workspace.Map.Red", "Reflectance"), None);
+
+		let Some(Variant::Attributes(attributes)) = property(&core, "Workspace.Map.Blue", "Attributes") else {
+			panic!("expected attributes");
+		};
+
+		assert_eq!(attributes.get("New"), Some(&Variant::Float64(2.0)));
+		assert_eq!(attributes.get("Old"), None);
+
+		let Some(Variant::Tags(tags)) = property(&core, "Workspace.Map.Wedge", "Tags") else {
+			panic!("expected tags");
+		};
+
+		assert_eq!(tags.iter().collect::<Vec<_>>(), ["Patched"]);
+		assert_eq!(property(&core, "Workspace.Map.Blue", "Tags"), None);
+
+		// Applying the same patch again changes nothing
+		let report = core.apply_patch(&patch(ACTIONS), false, false).unwrap();
+
+		assert!(report.operations.iter().all(|operation| operation.instances.is_empty()));
+	}
+
+	#[test]
+	fn dry_run_fidelity() {
+		let dir = fixture("dry-run");
+		let core = start(&dir);
+		let before = files(&dir);
+
+		let mut dry = core.apply_patch(&patch(ACTIONS), true, false).unwrap();
+
+		assert_eq!(files(&dir), before);
+		assert_eq!(
+			property(&core, "Workspace.Map.Red", "Material"),
+			Some(Variant::Enum(Enum::from_u32(256)))
+		);
+
+		let real = core.apply_patch(&patch(ACTIONS), false, false).unwrap();
+
+		assert!(dry.dry_run);
+		assert_ne!(files(&dir), before);
+
+		dry.dry_run = false;
+		assert_eq!(dry, real);
+
+		let change = &real.operations[0].instances[0].changes[0];
+
+		assert_eq!(real.operations[0].instances[0].path, "ApplyPatch/Workspace/Map/Blue");
+		assert_eq!(change.property, "Material");
+		assert_eq!(change.from, Some(serde_json::json!("Metal")));
+		assert_eq!(change.to, Some(serde_json::json!("Brick")));
+	}
+
+	#[test]
+	fn selectors() {
+		let dir = fixture("selectors");
+		let core = start(&dir);
+
+		let matched = |select: &str| {
+			let json = format!(r#"[{{ "select": "{}", "addTag": "Selected" }}]"#, select);
+			let report = core.apply_patch(&patch(&json), true, false).unwrap();
+
+			report.operations[0]
+				.instances
+				.iter()
+				.map(|instance| instance.path.rsplit('/').next().unwrap().to_owned())
+				.collect::<Vec<_>>()
+		};
+
+		// Property predicates compare enums by their values, Plastic is 256
+		assert_eq!(matched("ClassName~BasePart Material=256"), ["Red", "Wedge"]);
+		assert_eq!(matched("ClassName=Part Material=256"), ["Red"]);
+		assert_eq!(matched("Path~Map/* Value=Plastic"), ["Notes"]);
+		assert_eq!(matched("Name~d$ Reflectance=0.5"), ["Red"]);
+		assert_eq!(matched("Path~Map/B*"), ["Blue"]);
+		assert!(matched("ClassName=Model").is_empty());
+
+		// Invalid selectors fail the whole patch before anything is applied
+		assert!(core
+			.apply_patch(
+				&patch(r#"[{ "select": "Name~(", "delete": ["Material"] }]"#),
+				false,
+				false
+			)
+			.is_err());
+	}
+
+	#[test]
+	fn failures() {
+		let lenient = fixture("failures");
+		let failing = r#"[
+		{ "select": "ClassName~BasePart", "set": { "Material": "Papier" } },
+		{ "select": "Name=Red", "addTag": "Patched" }
+	]"#;
+
+		// Failures are listed and the remaining operations still run
+		let report = start(&lenient).apply_patch(&patch(failing), false, false).unwrap();
+
+		assert_eq!(report.operations.len(), 2);
+		assert_eq!(report.operations[0].failures.len(), 3);
+		assert!(report.operations[0].failures[0].error.contains("Papier"));
+		assert_eq!(report.operations[1].instances.len(), 1);
+
+		// Strict run stops at the first failure
+		let dir = fixture("failures-strict");
+		let report = start(&dir).apply_patch(&patch(failing), false, true).unwrap();
+
+		assert_eq!(report.operations.len(), 1);
+		assert_eq!(report.failures(), 1);
+		assert_eq!(property(&start(&dir), "Workspace.Map.Red", "Tags"), None);
+	}
+}
+
+mod trace {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			changes::Changes,
+			meta::Meta,
+			processor::WriteRequest,
+			snapshot::{AddedSnapshot, UpdatedSnapshot},
+			trace::{self, Trace},
+			Core,
+		},
+		vfs::VfsEvent,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::{
+		fs,
+		path::{Path, PathBuf},
+		thread,
+		time::Duration,
+	};
+
+	const SECRET: &str = "-- secret token 1234";
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src/Folder")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "Trace",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"$path": "src"
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		fs::write(dir.join("src/Module.luau"), "return 1").unwrap();
+		fs::write(dir.join("src/Folder/Child.luau"), "return 2").unwrap();
+
+		dir
+	}
+
+	/// Sends the input and waits until the processor is done with it
+	fn process(core: &Core, send: impl FnOnce()) {
+		let processed = core.processor().processed();
+		send();
+
+		while core.processor().processed() == processed {
+			thread::sleep(Duration::from_millis(1));
+		}
+	}
+
+	fn notify(core: &Core, event: VfsEvent) {
+		process(core, || core.processor().notify(event));
+	}
+
+	/// Records a session with external file changes and client changes
+	fn record(dir: &Path, redact: bool) -> PathBuf {
+		let path = dir.join("session.trace");
+
+		let core = start(dir);
+		core.record_trace(&path, redact).unwrap();
+
+		fs::write(dir.join("src/Module.luau"), SECRET).unwrap();
+		notify(&core, VfsEvent::Write(dir.join("src/Module.luau")));
+
+		fs::create_dir_all(dir.join("src/Created")).unwrap();
+		fs::write(dir.join("src/Created/init.luau"), "return 3").unwrap();
+		notify(&core, VfsEvent::Create(dir.join("src/Created")));
+
+		let (child, folder) = {
+			let tree = core.tree();
+
+			(
+				tree.find_by_path("ReplicatedStorage.Folder.Child").unwrap(),
+				tree.find_by_path("ReplicatedStorage.Folder").unwrap(),
+			)
+		};
+
+		let mut changes = Changes::new();
+
+		let mut update = UpdatedSnapshot::new(child);
+		update.properties = Some(UstrMap::from_iter([(
+			Ustr::from("Source"),
+			Variant::String(String::from("return 4")),
+		)]));
+
+		changes.update(update);
+		changes.additions.push(AddedSnapshot {
+			id: Ref::new(),
+			meta: Meta::new(),
+			parent: folder,
+			name: String::from("Added"),
+			class: Ustr::from("Folder"),
+			properties: UstrMap::default(),
+			children: vec![],
+		});
+
+		process(&core, || core.processor().write(WriteRequest { changes, client_id: 7 }));
+
+		fs::remove_file(dir.join("src/Module.luau")).unwrap();
+		notify(&core, VfsEvent::Delete(dir.join("src/Module.luau")));
+
+		core.finish_trace().unwrap();
+
+		path
+	}
+
+	#[test]
+	fn replay_matches_recording() {
+		let dir = fixture("replay");
+		let path = record(&dir, false);
+		let trace = Trace::read(&path).unwrap();
+
+		assert_eq!(trace.inputs.len(), 4);
+		assert!(trace.end.is_some());
+		assert!(dir.join("src/Folder/Added").is_dir());
+
+		// Trace embeds the starting workspace, so it replays on its own
+		let replayed = TempDir::new("replayed");
+
+		trace::prepare(&trace, None, &replayed).unwrap();
+		let report = trace::replay(&trace, &replayed, None).unwrap();
+
+		assert!(report.start_matches);
+		assert_eq!(report.replayed, 4);
+		assert_eq!(report.divergence, None);
+		assert!(report.is_identical());
+		assert_eq!(
+			fs::read_to_string(replayed.join("src/Folder/Child.luau")).unwrap(),
+			"return 4"
+		);
+		assert!(!replayed.join("src/Module.luau").exists());
+	}
+
+	#[test]
+	fn divergence() {
+		let dir = fixture("divergence");
+		let path = record(&dir, false);
+		let trace = Trace::read(&path).unwrap();
+
+		// Starting workspace with an extra file changes the tree after every input
+		let workspace = fixture("divergence-start");
+		fs::write(workspace.join("src/Extra.luau"), "return 5").unwrap();
+
+		let replayed = TempDir::new("diverged");
+
+		trace::prepare(&trace, Some(&workspace), &replayed).unwrap();
+		let report = trace::replay(&trace, &replayed, None).unwrap();
+
+		assert!(!report.start_matches);
+		assert!(!report.is_identical());
+
+		let divergence = report.divergence.unwrap();
+
+		assert_eq!(divergence.index, 1);
+		assert!(divergence.input.contains("Module.luau"));
+		assert_ne!(divergence.expected, divergence.actual);
+	}
+
+	#[test]
+	fn redacted() {
+		let dir = fixture("redacted");
+		let path = record(&dir, true);
+		let trace = Trace::read(&path).unwrap();
+
+		// Neither file contents nor client changes are stored
+		let bytes = fs::read(&path).unwrap();
+
+		assert!(!bytes.windows(SECRET.len()).any(|window| window == SECRET.as_bytes()));
+		assert!(trace.header.redacted);
+		assert_eq!(trace.header.files, None);
+
+		let replayed = TempDir::new("verified");
+
+		assert!(trace::prepare(&trace, None, &replayed).is_err());
+
+		// Redacted traces only verify the workspace against the end state
+		trace::prepare(&trace, Some(&dir), &replayed).unwrap();
+		let report = trace::replay(&trace, &replayed, None).unwrap();
+
+		assert!(report.verify_only);
+		assert_eq!(report.replayed, 0);
+		assert!(report.is_identical());
+
+		fs::write(dir.join("src/Folder/Child.luau"), "return 6").unwrap();
+
+		trace::prepare(&trace, Some(&dir), &replayed).unwrap();
+		let report = trace::replay(&trace, &replayed, None).unwrap();
+
+		assert!(!report.is_identical());
+		assert_ne!(report.expected.unwrap().files, report.actual.files);
+	}
+}
+
+mod test_runner {
+	use anyhow::Result;
+	use argon::{
+		core::test_runs::{
+			TestCase, TestError, TestErrorKind, TestReport, TestRun, TestRuns, TestStatus, TestSuite,
+			TEST_RESULTS_VERSION,
+		},
+		test_runner::{self, TestBridge, TestOptions},
+	};
+	use std::{cell::RefCell, collections::VecDeque, time::Duration};
+
+	/// Bridge that records executed code and returns scripted results
+	struct MockBridge {
+		executed: RefCell<Vec<String>>,
+		polls: RefCell<VecDeque<Option<TestRun>>>,
+	}
+
+	impl MockBridge {
+		fn new(polls: Vec<Option<TestRun>>) -> Self {
+			Self {
+				executed: RefCell::new(vec![]),
+				polls: RefCell::new(polls.into()),
+			}
+		}
+	}
+
+	impl TestBridge for MockBridge {
+		fn execute(&self, code: &str) -> Result<()> {
+			self.executed.borrow_mut().push(code.to_owned());
+			Ok(())
+		}
+
+		fn poll(&self, run_id: &str) -> Result<Option<TestRun>> {
+			assert!(self.executed.borrow()[0].contains(run_id));
+
+			let mut polls = self.polls.borrow_mut();
+
+			// The last result keeps being returned
+			Ok(if polls.len() > 1 {
+				polls.pop_front().unwrap()
+			} else {
+				polls.front().cloned().flatten()
+			})
+		}
+	}
+
+	fn options(timeout: u64) -> TestOptions {
+		TestOptions {
+			root: String::from("ReplicatedStorage/Tests"),
+			runner: None,
+			timeout: Duration::from_millis(timeout),
+			start_timeout: Duration::from_millis(timeout),
+		}
+	}
+
+	fn case(name: &str, status: TestStatus, message: Option<&str>) -> TestCase {
+		TestCase {
+			name: name.to_owned(),
+			status,
+			message: message.map(str::to_owned),
+			duration: None,
+		}
+	}
+
+	fn suite(name: &str, cases: Vec<TestCase>) -> TestSuite {
+		TestSuite {
+			name: name.to_owned(),
+			cases,
+			duration: Some(0.5),
+		}
+	}
+
+	#[test]
+	fn deserialization() {
+		let runs = TestRuns::new();
+
+		// Reports as encoded by `HttpService:JSONEncode`, nil fields are left out
+		let reports = [
+			r#"{ "version": 1, "runId": "run", "suites": [], "done": false }"#,
+			r#"{ "version": 1, "runId": "run", "done": false, "suites": [{
+			"name": "ReplicatedStorage/Tests/Math.spec",
+			"duration": 0.25,
+			"cases": [
+				{ "name": "add adds numbers", "status": "passed" },
+				{ "name": "sub subtracts numbers", "status": "failed", "message": "expected 1, got 2" }
+			]
+		}] }"#,
+			r#"{ "version": 1, "runId": "run", "suites": [], "done": true, "unknownField": 1 }"#,
+		];
+
+		for report in reports {
+			runs.report(serde_json::from_str::<TestReport>(report).unwrap())
+				.unwrap();
+		}
+
+		let run = runs.get("run").unwrap();
+
+		assert!(run.done);
+		assert_eq!(run.error, None);
+		assert_eq!(run.suites.len(), 1);
+		assert_eq!(run.count(TestStatus::Passed), 1);
+		assert_eq!(
+			run.suites[0].cases[1],
+			case("sub subtracts numbers", TestStatus::Failed, Some("expected 1, got 2"))
+		);
+
+		// Finished runs and other schema versions are rejected
+		let late = r#"{ "version": 1, "runId": "run", "suites": [], "done": true }"#;
+		assert!(runs.report(serde_json::from_str(late).unwrap()).is_err());
+
+		let future = format!(
+			r#"{{ "version": {}, "runId": "other", "done": true }}"#,
+			TEST_RESULTS_VERSION + 1
+		);
+		assert!(runs.report(serde_json::from_str(&future).unwrap()).is_err());
+		assert_eq!(runs.get("other"), None);
+
+		let error = r#"{ "version": 1, "runId": "missing", "done": true,
+		"error": { "kind": "runnerNotFound", "message": "Test runner TestEZ was not found" } }"#;
+		runs.report(serde_json::from_str(error).unwrap()).unwrap();
+
+		assert_eq!(
+			runs.get("missing").unwrap().error.unwrap().kind,
+			TestErrorKind::RunnerNotFound
+		);
+	}
+
+	#[test]
+	fn junit() {
+		let run = TestRun {
+			suites: vec![suite(
+				"Tests/Math.spec",
+				vec![
+					case("add", TestStatus::Passed, None),
+					case("compare <&>", TestStatus::Failed, Some("expected \"a\"\nstack")),
+					case("later", TestStatus::Skipped, None),
+				],
+			)],
+			done: false,
+			error: Some(TestError {
+				kind: TestErrorKind::Timeout,
+				message: String::from("Run did not finish"),
+			}),
+		};
+
+		assert_eq!(
+			test_runner::junit(&run),
+			r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="argon" tests="3" failures="1" skipped="1" errors="1" time="0.500">
+	<testsuite name="Tests/Math.spec" tests="3" failures="1" skipped="1" time="0.500">
+		<testcase classname="Tests/Math.spec" name="add" time="0.000"/>
+		<testcase classname="Tests/Math.spec" name="compare &lt;&amp;&gt;" time="0.000">
+			<failure message="expected &quot;a&quot;">expected &quot;a&quot;
+stack</failure>
+		</testcase>
+		<testcase classname="Tests/Math.spec" name="later" time="0.000">
+			<skipped/>
+		</testcase>
+	</testsuite>
+	<testsuite name="argon" tests="1" errors="1">
+		<testcase classname="argon" name="run">
+			<error type="Timeout" message="Run did not finish"/>
+		</testcase>
+	</testsuite>
+</testsuites>
+"#
+		);
+	}
+
+	#[test]
+	fn exit_codes() {
+		let passed = TestRun {
+			suites: vec![suite("Tests/A.spec", vec![case("a", TestStatus::Passed, None)])],
+			done: true,
+			error: None,
+		};
+
+		let mut failed = passed.clone();
+		failed.suites[0].cases.push(case("b", TestStatus::Failed, None));
+
+		let errored = TestRun {
+			error: Some(TestError {
+				kind: TestErrorKind::Runtime,
+				message: String::from("Runner failed"),
+			}),
+			..passed.clone()
+		};
+
+		assert_eq!(test_runner::exit_code(&passed), 0);
+		assert_eq!(test_runner::exit_code(&failed), 1);
+		assert_eq!(test_runner::exit_code(&errored), 2);
+	}
+
+	#[test]
+	fn bridge() {
+		let finished = TestRun {
+			suites: vec![suite("Tests/A.spec", vec![case("a", TestStatus::Failed, None)])],
+			done: true,
+			error: None,
+		};
+
+		let bridge = MockBridge::new(vec![None, Some(TestRun::default()), Some(finished.clone())]);
+		let run = test_runner::run(&bridge, &options(10_000), "http://localhost:8000/test-results").unwrap();
+
+		assert_eq!(run, finished);
+		assert_eq!(test_runner::exit_code(&run), 1);
+
+		let code = &bridge.executed.borrow()[0];
+
+		assert!(code.contains(r#"local ROOT = "ReplicatedStorage/Tests""#));
+		assert!(code.contains(r#"local URL = "http://localhost:8000/test-results""#));
+		assert!(code.contains("local RUNNER = nil"));
+		assert!(code.contains(&format!("local VERSION = {}", TEST_RESULTS_VERSION)));
+	}
+
+	#[test]
+	fn partial_results() {
+		let partial = TestRun {
+			suites: vec![suite("Tests/A.spec", vec![case("a", TestStatus::Passed, None)])],
+			done: false,
+			error: None,
+		};
+
+		// Studio stopped reporting in the middle of the run
+		let bridge = MockBridge::new(vec![Some(partial.clone())]);
+		let run = test_runner::run(&bridge, &options(0), "http://localhost/test-results").unwrap();
+
+		assert_eq!(run.suites, partial.suites);
+		assert_eq!(run.error.as_ref().unwrap().kind, TestErrorKind::Timeout);
+		assert_eq!(test_runner::exit_code(&run), 2);
+
+		// Studio never picked the bootstrap up
+		let bridge = MockBridge::new(vec![None]);
+		let run = test_runner::run(&bridge, &options(0), "http://localhost/test-results").unwrap();
+
+		assert!(run.suites.is_empty());
+		assert_eq!(run.error.unwrap().kind, TestErrorKind::NotStarted);
+	}
+}
+
+mod mcp_project {
+	use crate::common::TempDir;
+	use argon::{
+		mcp::{self, ProjectEdit},
+		project::Project,
+		validation::{self, Severity},
+	};
+	use serde_json::json;
+	use std::fs;
+
+	const PROJECT: &str = r#"{
+	"name": "Tools",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": {
+			"$path": "src"
+		},
+		"Workspace": {
+			"$className": "Workspace"
+		}
+	}
+}"#;
+
+	/// Creates a project with `src/Shared/Util.luau` mapped to `ReplicatedStorage`
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src").join("Shared")).unwrap();
+
+		fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+		fs::write(dir.join("src").join("Shared").join("Util.luau"), "return {}").unwrap();
+
+		dir
+	}
+
+	#[test]
+	fn broken_edit() {
+		let dir = fixture("broken");
+		let project_path = dir.join("default.project.json");
+
+		let broken = r#"{
+		"name": "Tools",
+		"tree": {
+			"$className": "DataModel",
+			"ReplicatedStorage": {
+				"$path": "source"
+			},
+			"Workspace": {
+				"$className": "Workspace",
+				"Base": {
+					"$className": "Part",
+					"$path": "src/Shared",
+					"$properties": { "Anchored": "yes" }
+				},
+				"Thing": {
+					"$className": "NotAClass"
+				}
+			}
+		}
+	}"#;
+
+		let problems = mcp::validate_edit(&project_path, &ProjectEdit::Contents(broken.to_owned())).unwrap();
+
+		// The edit is only validated, never saved
+		assert_eq!(fs::read_to_string(&project_path).unwrap(), PROJECT);
+
+		let summary: Vec<(Severity, &str)> = problems
+			.iter()
+			.map(|problem| (problem.severity, problem.node.as_str()))
+			.collect();
+
+		assert_eq!(
+			summary,
+			vec![
+				(Severity::Error, "tree/ReplicatedStorage"),
+				(Severity::Error, "tree/Workspace/Base"),
+				(Severity::Error, "tree/Workspace/Base"),
+				(Severity::Error, "tree/Workspace/Thing"),
+			]
+		);
+		assert!(problems[0].message.contains("source does not exist"));
+		assert!(problems[1].message.contains("$className and $path"));
+		assert!(problems[2].message.contains("Anchored"));
+
+		// Same problems as `argon validate` reports once the edit is saved
+		fs::write(&project_path, broken).unwrap();
+		assert_eq!(validation::validate_file(&project_path), problems);
+		assert!(validation::has_errors(&problems));
+	}
+
+	#[test]
+	fn merge_patch() {
+		let dir = fixture("merge-patch");
+		let project_path = dir.join("default.project.json");
+
+		let patch = json!({ "tree": { "ReplicatedStorage": { "Extra": { "$path": "extra" } } } });
+		let problems = mcp::validate_edit(&project_path, &ProjectEdit::MergePatch(patch)).unwrap();
+
+		assert_eq!(problems.len(), 1);
+		assert_eq!(problems[0].node, "tree/ReplicatedStorage/Extra");
+
+		// Null values remove keys
+		let patch = json!({ "tree": { "Workspace": null } });
+		assert!(mcp::validate_edit(&project_path, &ProjectEdit::MergePatch(patch))
+			.unwrap()
+			.is_empty());
+
+		// Projects that fail to load are reported as a single problem
+		let problems = mcp::validate_edit(&project_path, &ProjectEdit::Contents(String::from("{ \"tree\": "))).unwrap();
+
+		assert_eq!(problems.len(), 1);
+		assert_eq!(problems[0].node, "");
+		assert!(!problems[0].message.contains('\u{1b}'));
+	}
+
+	#[test]
+	fn skeleton() {
+		let dir = fixture("skeleton");
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+		let skeleton = mcp::project_skeleton(&project);
+
+		assert_eq!(skeleton.class_name.as_deref(), Some("DataModel"));
+		assert_eq!(skeleton.children.len(), 2);
+
+		let storage = &skeleton.children[0];
+
+		assert_eq!(storage.node, "tree/ReplicatedStorage");
+		assert_eq!(storage.path, Some(dir.join("src")));
+		assert_eq!(storage.exists, Some(true));
+		assert_eq!(storage.middleware.as_deref(), Some("Directory"));
+
+		let workspace = &skeleton.children[1];
+
+		assert_eq!(workspace.path, None);
+		assert_eq!(workspace.middleware, None);
+	}
+
+	#[test]
+	fn suggestions() {
+		let dir = fixture("suggestions");
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+		let suggest = |instance: Option<&str>, class: Option<&str>| mcp::suggest_paths(&project, instance, class, 10);
+
+		// Existing instances resolve to their files
+		let util = suggest(Some("ReplicatedStorage/Shared/Util"), None);
+
+		assert_eq!(util.len(), 1);
+		assert_eq!(util[0].path, dir.join("src").join("Shared").join("Util.luau"));
+		assert_eq!(util[0].middleware.as_deref(), Some("ModuleScript"));
+		assert!(util[0].exact);
+
+		// Missing ones to the closest existing ancestor
+		let missing = suggest(Some("ReplicatedStorage/Shared/Missing/Deeper"), None);
+
+		assert_eq!(missing[0].path, dir.join("src").join("Shared"));
+		assert_eq!(missing[0].instance, "ReplicatedStorage/Shared");
+		assert!(!missing[0].exact);
+
+		// Nodes without `$path` can only be changed in the project
+		let part = suggest(Some("Workspace/Part"), None);
+
+		assert_eq!(part[0].path, dir.join("default.project.json"));
+		assert_eq!(part[0].instance, "Workspace");
+
+		// Classes resolve to directories that already contain them
+		let modules = suggest(None, Some("ModuleScript"));
+
+		assert_eq!(modules.len(), 1);
+		assert_eq!(modules[0].path, dir.join("src").join("Shared"));
+		assert_eq!(modules[0].instance, "ReplicatedStorage/Shared");
+	}
+}
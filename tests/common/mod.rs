@@ -0,0 +1,224 @@
+//! Helpers shared by integration tests, every test crate uses only some of them
+#![allow(dead_code)]
+
+use argon::{
+	core::Core,
+	project::Project,
+	vfs::{std_backend::StdBackend, VfsBackend, VfsEvent},
+};
+use crossbeam_channel::Receiver;
+use std::{
+	env, fs,
+	io::{Error, ErrorKind, Result},
+	ops::Deref,
+	path::{Path, PathBuf},
+	process,
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Empty directory in the system temp dir that is removed
+/// with all of its contents when dropped, even if the test fails
+pub struct TempDir {
+	path: PathBuf,
+}
+
+impl TempDir {
+	/// Creates the directory named after the test crate, `name` and the process,
+	/// numbered so tests running at the same time never share it
+	pub fn new(name: &str) -> Self {
+		static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+		let path = env::temp_dir().join(format!(
+			"argon-{}-{}-{}-{}",
+			env!("CARGO_CRATE_NAME"),
+			name,
+			process::id(),
+			COUNT.fetch_add(1, Ordering::Relaxed)
+		));
+
+		fs::remove_dir_all(&path).ok();
+		fs::create_dir_all(&path).unwrap();
+
+		Self { path }
+	}
+
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+}
+
+impl Deref for TempDir {
+	type Target = Path;
+
+	fn deref(&self) -> &Path {
+		&self.path
+	}
+}
+
+impl AsRef<Path> for TempDir {
+	fn as_ref(&self) -> &Path {
+		&self.path
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		fs::remove_dir_all(&self.path).ok();
+	}
+}
+
+/// Starts core of `default.project.json` in the directory without watching files
+pub fn start(dir: &Path) -> Core {
+	Core::new(Project::load(&dir.join("default.project.json")).unwrap(), false).unwrap()
+}
+
+type ReadHook = Box<dyn Fn(&Path, &mut Vec<u8>) + Send>;
+type ReadDirHook = Box<dyn Fn(&Path, &mut Vec<PathBuf>) + Send>;
+type ChangeHook = Box<dyn Fn(&Path) -> Result<()> + Send>;
+
+/// Backend that passes everything to `StdBackend` without watching,
+/// hooks let tests observe or alter what goes through it
+pub struct WrappedBackend {
+	inner: StdBackend,
+	read: Option<ReadHook>,
+	read_dir: Option<ReadDirHook>,
+	write: Option<ChangeHook>,
+	change: Option<ChangeHook>,
+}
+
+impl WrappedBackend {
+	pub fn new() -> Self {
+		Self {
+			inner: StdBackend::new(false),
+			read: None,
+			read_dir: None,
+			write: None,
+			change: None,
+		}
+	}
+
+	/// Called with contents of every read file, including the ones read as strings
+	pub fn on_read(mut self, hook: impl Fn(&Path, &mut Vec<u8>) + Send + 'static) -> Self {
+		self.read = Some(Box::new(hook));
+		self
+	}
+
+	/// Called with paths listed in every read directory
+	pub fn on_read_dir(mut self, hook: impl Fn(&Path, &mut Vec<PathBuf>) + Send + 'static) -> Self {
+		self.read_dir = Some(Box::new(hook));
+		self
+	}
+
+	/// Called before every file write, its error fails the write
+	pub fn on_write(mut self, hook: impl Fn(&Path) -> Result<()> + Send + 'static) -> Self {
+		self.write = Some(Box::new(hook));
+		self
+	}
+
+	/// Called before every write, directory creation, rename and removal
+	/// with the affected path, its error fails the operation
+	pub fn on_change(mut self, hook: impl Fn(&Path) -> Result<()> + Send + 'static) -> Self {
+		self.change = Some(Box::new(hook));
+		self
+	}
+
+	fn check(hook: &Option<ChangeHook>, path: &Path) -> Result<()> {
+		match hook {
+			Some(hook) => hook(path),
+			None => Ok(()),
+		}
+	}
+}
+
+impl Default for WrappedBackend {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl VfsBackend for WrappedBackend {
+	fn read(&self, path: &Path) -> Result<Vec<u8>> {
+		let mut contents = self.inner.read(path)?;
+
+		if let Some(hook) = &self.read {
+			hook(path, &mut contents);
+		}
+
+		Ok(contents)
+	}
+
+	fn read_to_string(&self, path: &Path) -> Result<String> {
+		if self.read.is_none() {
+			return self.inner.read_to_string(path);
+		}
+
+		String::from_utf8(self.read(path)?).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+	}
+
+	fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+		let mut paths = self.inner.read_dir(path)?;
+
+		if let Some(hook) = &self.read_dir {
+			hook(path, &mut paths);
+		}
+
+		Ok(paths)
+	}
+
+	fn write(&mut self, path: &Path, contents: &[u8]) -> Result<()> {
+		Self::check(&self.change, path)?;
+		Self::check(&self.write, path)?;
+		self.inner.write(path, contents)
+	}
+
+	fn create_dir(&mut self, path: &Path) -> Result<()> {
+		Self::check(&self.change, path)?;
+		self.inner.create_dir(path)
+	}
+
+	fn rename(&mut self, from: &Path, to: &Path) -> Result<()> {
+		Self::check(&self.change, from)?;
+		self.inner.rename(from, to)
+	}
+
+	fn remove(&mut self, path: &Path) -> Result<()> {
+		Self::check(&self.change, path)?;
+		self.inner.remove(path)
+	}
+
+	fn exists(&self, path: &Path) -> bool {
+		self.inner.exists(path)
+	}
+
+	fn is_dir(&self, path: &Path) -> bool {
+		self.inner.is_dir(path)
+	}
+
+	fn is_file(&self, path: &Path) -> bool {
+		self.inner.is_file(path)
+	}
+
+	fn watch(&mut self, path: &Path, recursive: bool) -> Result<()> {
+		self.inner.watch(path, recursive)
+	}
+
+	fn unwatch(&mut self, path: &Path) -> Result<()> {
+		self.inner.unwatch(path)
+	}
+
+	fn pause(&mut self) {
+		self.inner.pause()
+	}
+
+	fn resume(&mut self) {
+		self.inner.resume()
+	}
+
+	fn watches_recursively(&self) -> bool {
+		self.inner.watches_recursively()
+	}
+
+	fn receiver(&self) -> Receiver<VfsEvent> {
+		self.inner.receiver()
+	}
+}
@@ -0,0 +1,3552 @@
+mod common;
+
+mod project {
+	mod defs {
+		use crate::common::TempDir;
+		use argon::{project::Project, resolution::UnresolvedValue};
+		use rbx_dom_weak::Ustr;
+		use serde_json::{json, Value};
+		use std::{env, fs, path::PathBuf};
+
+		const PROJECT: &str = r#"{
+		"name": "Defs",
+		"$defs": {
+			"colors": {
+				"primary": [1, 0, 0],
+				"accent": { "$ref": "colors.primary" }
+			},
+			"assets": { "prefix": "rbxassetid://" }
+		},
+		"tree": {
+			"$className": "DataModel",
+			"Workspace": {
+				"Part": {
+					"$className": "Part",
+					"$properties": {
+						"Color": { "$ref": "colors.accent" }
+					}
+				},
+				"Decal": {
+					"$className": "Decal",
+					"$properties": {
+						"Texture": "${assets.prefix}123"
+					}
+				}
+			}
+		}
+	}"#;
+
+		fn write_project(name: &str, contents: &str) -> (TempDir, PathBuf) {
+			let dir = TempDir::new(&format!("defs-{}", name));
+			let path = dir.join("default.project.json");
+
+			fs::write(&path, contents).unwrap();
+
+			(dir, path)
+		}
+
+		fn property(project: &Project, node: &str, property: &str) -> UnresolvedValue {
+			project.node.tree["Workspace"].tree[node].properties[&Ustr::from(property)].clone()
+		}
+
+		#[test]
+		fn resolves_nested_refs() {
+			let (_dir, path) = write_project("nested", PROJECT);
+			let project = Project::load(&path).unwrap();
+
+			assert_eq!(
+				property(&project, "Part", "Color"),
+				serde_json::from_value(json!([1, 0, 0])).unwrap()
+			);
+			assert_eq!(
+				property(&project, "Decal", "Texture").as_str(),
+				Some("rbxassetid://123")
+			);
+		}
+
+		#[test]
+		fn fails_on_missing_key() {
+			let (_dir, path) = write_project("missing", &PROJECT.replace("colors.accent", "colors.secondary"));
+			let err = Project::load(&path).unwrap_err().to_string();
+
+			assert!(err.contains("missing key: `secondary`"));
+			assert!(err.contains("tree/Workspace/Part"));
+		}
+
+		#[test]
+		fn requires_env_opt_in() {
+			env::set_var("ARGON_DEFS_TEST", "42");
+
+			let contents = PROJECT.replace("${assets.prefix}123", "${assets.prefix}${env:ARGON_DEFS_TEST}");
+
+			let (_dir, path) = write_project("env", &contents);
+			assert!(Project::load(&path).is_err());
+
+			fs::write(
+				&path,
+				contents.replacen("\"name\": \"Defs\",", "\"name\": \"Defs\", \"allowEnvRefs\": true,", 1),
+			)
+			.unwrap();
+
+			let project = Project::load(&path).unwrap();
+			assert_eq!(property(&project, "Decal", "Texture").as_str(), Some("rbxassetid://42"));
+		}
+
+		#[test]
+		fn save_preserves_refs() {
+			let (_dir, path) = write_project("save", PROJECT);
+			let mut project = Project::load(&path).unwrap();
+
+			// Simulate syncback changing one of the properties
+			project
+				.node
+				.tree
+				.get_mut("Workspace")
+				.unwrap()
+				.tree
+				.get_mut("Decal")
+				.unwrap()
+				.properties
+				.insert(
+					Ustr::from("Texture"),
+					serde_json::from_value(json!("rbxassetid://456")).unwrap(),
+				);
+
+			project.save(&path).unwrap();
+
+			let saved: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+
+			assert_eq!(
+				saved["tree"]["Workspace"]["Part"]["$properties"]["Color"],
+				json!({ "$ref": "colors.accent" })
+			);
+			assert_eq!(
+				saved["tree"]["Workspace"]["Decal"]["$properties"]["Texture"],
+				json!("rbxassetid://456")
+			);
+			assert_eq!(saved["$defs"]["colors"]["accent"], json!({ "$ref": "colors.primary" }));
+		}
+	}
+
+	mod reload {
+		use crate::common::TempDir;
+		use argon::project::Project;
+		use std::{fs, path::PathBuf, thread, time::Duration};
+
+		const PROJECT: &str = r#"{
+		"name": "Reload",
+		"tree": {
+			"$className": "DataModel",
+			"Workspace": { "$className": "Workspace" }
+		}
+	}"#;
+
+		fn fixture(name: &str) -> (TempDir, PathBuf) {
+			let dir = TempDir::new(&format!("reload-{}", name));
+			let path = dir.join("default.project.json");
+
+			fs::write(&path, PROJECT).unwrap();
+
+			(dir, path)
+		}
+
+		#[test]
+		fn recovers_from_truncated_file() {
+			let (_dir, path) = fixture("truncated");
+
+			// Simulate editor that truncates the file and writes it shortly after
+			fs::write(&path, &PROJECT[..PROJECT.len() / 2]).unwrap();
+
+			let writer = {
+				let path = path.clone();
+
+				thread::spawn(move || {
+					thread::sleep(Duration::from_millis(30));
+					fs::write(&path, PROJECT).unwrap();
+				})
+			};
+
+			let project = Project::load(&path).unwrap();
+			writer.join().unwrap();
+
+			assert_eq!(project.name, "Reload");
+			assert!(project.node.tree.contains_key("Workspace"));
+		}
+
+		#[test]
+		fn keeps_last_good_project() {
+			let (_dir, path) = fixture("stale");
+			let mut project = Project::load(&path).unwrap();
+
+			fs::write(&path, "").unwrap();
+
+			assert!(project.reload().is_err());
+			assert!(project.is_stale());
+			assert_eq!(project.name, "Reload");
+			assert!(project.node.tree.contains_key("Workspace"));
+
+			fs::write(&path, PROJECT).unwrap();
+
+			assert!(project.reload().is_ok());
+			assert!(!project.is_stale());
+		}
+
+		#[test]
+		fn edits_are_serialized() {
+			let (_dir, path) = fixture("edits");
+
+			let handles: Vec<_> = (0..8)
+				.map(|index| {
+					let path = path.clone();
+
+					thread::spawn(move || {
+						let mut project = Project::edit(&path).unwrap();
+						let node = project.node.tree["Workspace"].clone();
+
+						project.node.tree.insert(format!("Part{}", index), node);
+						project.save(&path).unwrap();
+					})
+				})
+				.collect();
+
+			for handle in handles {
+				handle.join().unwrap();
+			}
+
+			let project = Project::load(&path).unwrap();
+
+			for index in 0..8 {
+				assert!(project.node.tree.contains_key(&format!("Part{}", index)));
+			}
+		}
+	}
+
+	mod profiles {
+		use crate::common::TempDir;
+		use argon::{
+			core::{
+				meta::{Context, Meta, NodePath},
+				processor::write,
+				snapshot::{Snapshot, UpdatedSnapshot},
+				tree::Tree,
+			},
+			middleware::{new_snapshot, project::new_snapshot_node},
+			project::Project,
+			vfs::Vfs,
+		};
+		use rbx_dom_weak::{types::Variant, HashMapExt, Ustr, UstrMap};
+		use serde_json::Value;
+		use std::{
+			fs,
+			path::{Path, PathBuf},
+		};
+
+		const PROJECT: &str = r#"{
+		"name": "Profiles",
+		"profiles": ["dev", "prod"],
+		"tree": {
+			"$className": "DataModel",
+			"ReplicatedStorage": {
+				"Settings": {
+					"$className": "StringValue",
+					"$properties": { "Value": "quiet" },
+					"$profileProperties": {
+						"dev": { "Value": "verbose" }
+					}
+				},
+				"DebugTools": {
+					"$className": "Folder",
+					"$profiles": ["dev"]
+				},
+				"Analytics": {
+					"$className": "StringValue",
+					"$profiles": ["prod"],
+					"$properties": { "Value": "analytics-key" }
+				}
+			}
+		}
+	}"#;
+
+		fn fixture(name: &str) -> (TempDir, PathBuf) {
+			let dir = TempDir::new(&format!("profiles-{}", name));
+			let path = dir.join("default.project.json");
+
+			fs::write(&path, PROJECT).unwrap();
+
+			(dir, path)
+		}
+
+		fn snapshot(path: &Path, profile: &str) -> Snapshot {
+			let project = Project::load_with_profile(path, Some(profile)).unwrap();
+			let meta = Meta::from_project(&project);
+
+			new_snapshot_node(
+				&project.name,
+				path,
+				project.node,
+				NodePath::new(),
+				&meta.context,
+				&Vfs::new(false),
+			)
+			.unwrap()
+		}
+
+		fn storage(snapshot: &Snapshot) -> &Snapshot {
+			&snapshot.children[0]
+		}
+
+		fn child<'a>(snapshot: &'a Snapshot, name: &str) -> Option<&'a Snapshot> {
+			snapshot.children.iter().find(|child| child.name == name)
+		}
+
+		/// Text of the node's JSON object as it appears in the file
+		fn node_text<'a>(text: &'a str, name: &str) -> &'a str {
+			let start = text.find(&format!("\"{}\"", name)).unwrap();
+			let mut depth = 0;
+
+			for (index, char) in text[start..].char_indices() {
+				match char {
+					'{' => depth += 1,
+					'}' => {
+						depth -= 1;
+
+						if depth == 0 {
+							return &text[start..start + index + 1];
+						}
+					}
+					_ => {}
+				}
+			}
+
+			panic!("Node {} is not closed", name)
+		}
+
+		#[test]
+		fn trees_differ() {
+			let (_dir, path) = fixture("trees");
+
+			let dev = snapshot(&path, "dev");
+			let prod = snapshot(&path, "prod");
+
+			assert!(child(storage(&dev), "DebugTools").is_some());
+			assert!(child(storage(&dev), "Analytics").is_none());
+
+			assert!(child(storage(&prod), "DebugTools").is_none());
+			assert!(child(storage(&prod), "Analytics").is_some());
+
+			let value = |snapshot: &Snapshot| {
+				child(storage(snapshot), "Settings").unwrap().properties[&Ustr::from("Value")].clone()
+			};
+
+			assert_eq!(value(&dev), Variant::String(String::from("verbose")));
+			assert_eq!(value(&prod), Variant::String(String::from("quiet")));
+		}
+
+		#[test]
+		fn default_and_unknown_profiles() {
+			let (_dir, path) = fixture("default");
+
+			assert_eq!(Project::load_with_profile(&path, None).unwrap().profile.unwrap(), "dev");
+			assert!(Project::load_with_profile(&path, Some("staging")).is_err());
+
+			fs::write(&path, PROJECT.replace(r#"["prod"]"#, r#"["qa"]"#)).unwrap();
+
+			assert!(Project::load_with_profile(&path, Some("dev")).is_err());
+		}
+
+		#[test]
+		fn syncback_keeps_other_profiles() {
+			let (_dir, path) = fixture("syncback");
+
+			// Bring the fixture to the format used when saving
+			let project = Project::load_with_profile(&path, Some("dev")).unwrap();
+			project.save(&path).unwrap();
+
+			let before = fs::read_to_string(&path).unwrap();
+
+			let vfs = Vfs::new(false);
+			let mut tree = Tree::new(new_snapshot(&path, &Context::default(), &vfs).unwrap().unwrap());
+
+			let id = tree
+				.meta_map()
+				.keys()
+				.copied()
+				.find(|id| tree.get_instance(*id).unwrap().name == "Settings")
+				.unwrap();
+
+			let mut properties = UstrMap::new();
+			properties.insert(Ustr::from("Value"), Variant::String(String::from("debug")));
+
+			let mut update = UpdatedSnapshot::new(id);
+			update.properties = Some(properties);
+
+			write::apply_update(update, &mut tree, &vfs).unwrap();
+
+			let after = fs::read_to_string(&path).unwrap();
+
+			assert_eq!(node_text(&after, "Analytics"), node_text(&before, "Analytics"));
+			assert_eq!(node_text(&after, "DebugTools"), node_text(&before, "DebugTools"));
+
+			// Only the override of the active profile is changed
+			let project: Value = serde_json::from_str(&after).unwrap();
+			let settings = &project["tree"]["ReplicatedStorage"]["Settings"];
+
+			assert_eq!(settings["$properties"]["Value"], "quiet");
+			assert_eq!(settings["$profileProperties"]["dev"]["Value"], "debug");
+		}
+	}
+}
+
+mod root_node {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{helpers::syncback::serialize_node_properties, processor::write, snapshot::UpdatedSnapshot, Core},
+		project::{Project, ProjectNode},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Attributes, Tags, Variant},
+		Ustr,
+	};
+	use std::{fs, path::Path};
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "Root",
+			"tree": {
+				"$className": "DataModel",
+				"$attributes": { "Version": 1 },
+				"$tags": ["Place"],
+				"Workspace": {
+					"$properties": { "Gravity": 100 },
+					"$tags": ["Map"]
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		dir
+	}
+
+	fn root_node(dir: &Path) -> ProjectNode {
+		Project::load(&dir.join("default.project.json")).unwrap().node
+	}
+
+	fn property(core: &Core, path: &str, property: &str) -> Option<Variant> {
+		let tree = core.tree();
+		let id = tree.find_by_path(path)?;
+
+		tree.get_instance(id)?.properties.get(&Ustr::from(property)).cloned()
+	}
+
+	#[test]
+	fn snapshot() {
+		let dir = fixture("snapshot");
+		let core = start(&dir);
+
+		let Some(Variant::Attributes(attributes)) = property(&core, "", "Attributes") else {
+			panic!("Root has no attributes");
+		};
+
+		assert_eq!(attributes.get("Version"), Some(&Variant::Float64(1.0)));
+		assert_eq!(
+			property(&core, "", "Tags"),
+			Some(Variant::Tags(Tags::from(vec![String::from("Place")])))
+		);
+		assert_eq!(
+			property(&core, "Workspace", "Tags"),
+			Some(Variant::Tags(Tags::from(vec![String::from("Map")])))
+		);
+	}
+
+	#[test]
+	fn round_trip() {
+		let dir = fixture("round-trip");
+		let core = start(&dir);
+
+		// Snapshot properties serialized back give the same nodes
+		for (path, node) in [
+			("", root_node(&dir)),
+			("Workspace", root_node(&dir).tree["Workspace"].clone()),
+		] {
+			let tree = core.tree();
+			let instance = tree.get_instance(tree.find_by_path(path).unwrap()).unwrap();
+
+			let mut serialized = ProjectNode::default();
+			serialize_node_properties(&mut serialized, &instance.class, instance.properties.clone());
+
+			assert_eq!(
+				serde_json::to_value(&serialized.attributes).unwrap(),
+				serde_json::to_value(&node.attributes).unwrap(),
+				"Attributes of {:?} changed",
+				path
+			);
+			assert_eq!(serialized.tags, node.tags, "Tags of {:?} changed", path);
+			assert_eq!(
+				serde_json::to_value(&serialized.properties).unwrap(),
+				serde_json::to_value(&node.properties).unwrap(),
+				"Properties of {:?} changed",
+				path
+			);
+		}
+
+		let before = serde_json::to_value(root_node(&dir)).unwrap();
+
+		// Syncing back unchanged root and Workspace keeps the project as it was
+		for path in ["", "Workspace"] {
+			let mut tree = core.tree();
+			let id = tree.find_by_path(path).unwrap();
+
+			let mut update = UpdatedSnapshot::new(id);
+			update.properties = Some(tree.get_instance(id).unwrap().properties.clone());
+
+			write::apply_update(update, &mut tree, &Vfs::new(false)).unwrap();
+		}
+
+		assert_eq!(serde_json::to_value(root_node(&dir)).unwrap(), before);
+
+		// Changes of the root are written to the root node and read back the same
+		{
+			let mut tree = core.tree();
+			let id = tree.root_ref();
+
+			let mut properties = tree.get_instance(id).unwrap().properties.clone();
+			properties.insert(
+				Ustr::from("Attributes"),
+				Variant::Attributes(Attributes::from_iter([
+					(String::from("Version"), Variant::Float64(2.0)),
+					(String::from("Live"), Variant::Bool(true)),
+				])),
+			);
+			properties.insert(
+				Ustr::from("Tags"),
+				Variant::Tags(Tags::from(vec![String::from("Place"), String::from("Live")])),
+			);
+
+			let mut update = UpdatedSnapshot::new(id);
+			update.properties = Some(properties);
+
+			write::apply_update(update, &mut tree, &Vfs::new(false)).unwrap();
+		}
+
+		let root = property(&core, "", "Attributes");
+		let tags = property(&core, "", "Tags");
+
+		drop(core);
+
+		let core = start(&dir);
+
+		assert_eq!(property(&core, "", "Attributes"), root);
+		assert_eq!(property(&core, "", "Tags"), tags);
+		assert_eq!(
+			property(&core, "Workspace", "Tags"),
+			Some(Variant::Tags(Tags::from(vec![String::from("Map")])))
+		);
+		assert_eq!(property(&core, "Workspace", "Gravity"), Some(Variant::Float32(100.0)));
+
+		let Some(Variant::Attributes(attributes)) = root else {
+			panic!("Root has no attributes");
+		};
+
+		assert_eq!(attributes.get("Live"), Some(&Variant::Bool(true)));
+		assert_eq!(attributes.get("Version"), Some(&Variant::Float64(2.0)));
+	}
+}
+
+mod snapshot {
+	use crate::common::TempDir;
+	use argon::{
+		core::{meta::Context, snapshot::Snapshot, tree::Tree},
+		middleware::Middleware,
+		vfs::Vfs,
+		Properties,
+	};
+	use rbx_dom_weak::{
+		types::{Enum, Ref, Variant},
+		HashMapExt, Ustr, UstrMap,
+	};
+	use serde_json::json;
+	use std::{fs, time::Instant};
+
+	fn properties(entries: &[(&str, Variant)]) -> Properties {
+		let mut properties = UstrMap::new();
+
+		for (name, value) in entries {
+			properties.insert(Ustr::from(name), value.clone());
+		}
+
+		properties
+	}
+
+	fn part() -> Snapshot {
+		Snapshot::new()
+			.with_name("Part")
+			.with_class("Part")
+			.with_properties(properties(&[("Anchored", Variant::Bool(true))]))
+			.with_children(vec![Snapshot::new().with_name("Child")])
+	}
+
+	#[test]
+	fn serialized_shape() {
+		let value = serde_json::to_value(part()).unwrap();
+
+		let keys: Vec<&str> = value.as_object().unwrap().keys().map(String::as_str).collect();
+
+		assert_eq!(keys, ["id", "meta", "name", "class", "properties", "children"]);
+		assert_eq!(value["name"], json!("Part"));
+		assert_eq!(value["class"], json!("Part"));
+		assert_eq!(value["properties"]["Anchored"], json!({ "Bool": true }));
+		assert_eq!(value["children"][0]["class"], json!("Folder"));
+
+		let value = serde_json::to_value(part().as_new(Ref::none())).unwrap();
+
+		assert_eq!(value["name"], json!("Part"));
+		assert_eq!(value["class"], json!("Part"));
+	}
+
+	#[test]
+	fn msgpack_round_trip() {
+		let snapshot = part();
+
+		let encoded = rmp_serde::to_vec_named(&snapshot).unwrap();
+		let decoded: Snapshot = rmp_serde::from_slice(&encoded).unwrap();
+
+		assert_eq!(decoded.name, snapshot.name);
+		assert_eq!(decoded.class, snapshot.class);
+		assert_eq!(decoded.properties, snapshot.properties);
+		assert_eq!(decoded.children.len(), 1);
+
+		// Interned class has to be encoded the same way as a plain string
+		let class: String = rmp_serde::from_slice(&rmp_serde::to_vec(&snapshot.class).unwrap()).unwrap();
+
+		assert_eq!(class, "Part");
+	}
+
+	#[test]
+	fn owned_and_borrowed_builders() {
+		let borrowed = Snapshot::new().with_name("Part").with_class("Part");
+		let owned = Snapshot::new()
+			.with_name(String::from("Part"))
+			.with_class(Ustr::from("Part"));
+
+		assert_eq!(borrowed.name, owned.name);
+		assert_eq!(borrowed.class, owned.class);
+	}
+
+	#[test]
+	fn debug_truncates_source() {
+		let snapshot = Snapshot::new()
+			.with_class("ModuleScript")
+			.with_properties(properties(&[(
+				"Source",
+				Variant::String("local a = 1\nlocal b = 2\nreturn a + b".into()),
+			)]));
+
+		let debug = format!("{:?}", snapshot);
+
+		assert!(debug.contains("Truncated... (3 lines)"));
+		assert!(!debug.contains("local a"));
+	}
+
+	#[test]
+	fn middleware_write_borrows_properties() {
+		let dir = TempDir::new("middleware-write");
+
+		let vfs = Vfs::new(false);
+		let path = dir.join("Script.server.luau");
+
+		let properties = properties(&[
+			("Source", Variant::String("print('Hello, world!')".into())),
+			("Disabled", Variant::Bool(true)),
+		]);
+
+		let remaining = Middleware::ServerScript
+			.write(&properties, &path, &Context::default(), &vfs)
+			.unwrap();
+
+		assert_eq!(fs::read_to_string(&path).unwrap(), "print('Hello, world!')");
+		assert_eq!(remaining.len(), 1);
+		assert_eq!(remaining.get(&Ustr::from("Disabled")), Some(&Variant::Bool(true)));
+		assert_eq!(properties.len(), 2);
+	}
+
+	#[test]
+	fn run_context() {
+		let client = properties(&[("RunContext", Variant::Enum(Enum::from_u32(2)))]);
+		let legacy = properties(&[("RunContext", Variant::Enum(Enum::from_u32(0)))]);
+
+		assert_eq!(
+			Middleware::from_class("Script", Some(&client)),
+			Some(Middleware::ClientScript)
+		);
+		assert_eq!(
+			Middleware::from_class("Script", Some(&legacy)),
+			Some(Middleware::ServerScript)
+		);
+		assert_eq!(Middleware::from_class("Script", None), Some(Middleware::ServerScript));
+
+		let mut stripped = client.clone();
+		Middleware::strip_run_context("Script", &mut stripped);
+
+		assert!(stripped.is_empty());
+
+		let mut stripped = legacy.clone();
+		Middleware::strip_run_context("Script", &mut stripped);
+
+		assert_eq!(stripped, legacy);
+
+		let mut stripped = client.clone();
+		Middleware::strip_run_context("LocalScript", &mut stripped);
+
+		assert_eq!(stripped, client);
+	}
+
+	/// Synthetic tree of 200 folders with 100 scripts each,
+	/// run with `cargo test --release -- --ignored --nocapture`
+	#[test]
+	#[ignore]
+	fn benchmark_20k_instances() {
+		let source = "print('Hello, world!')\n".repeat(20);
+
+		let folders = (0..200)
+			.map(|folder| {
+				let scripts = (0..100)
+					.map(|script| {
+						Snapshot::new()
+							.with_name(format!("Script{}", script))
+							.with_class("ModuleScript")
+							.with_properties(properties(&[("Source", Variant::String(source.clone()))]))
+					})
+					.collect();
+
+				Snapshot::new()
+					.with_name(format!("Folder{}", folder))
+					.with_children(scripts)
+			})
+			.collect();
+
+		let snapshot = Snapshot::new().with_name("Root").with_children(folders);
+
+		let start = Instant::now();
+		let tree = Tree::new(snapshot.clone());
+		println!("Tree::new: {:?}", start.elapsed());
+
+		assert_eq!(tree.meta_map().len(), 20_201);
+
+		let start = Instant::now();
+		let encoded = rmp_serde::to_vec_named(&snapshot).unwrap();
+		println!("Serialize: {:?} ({} bytes)", start.elapsed(), encoded.len());
+
+		let start = Instant::now();
+		let decoded: Snapshot = rmp_serde::from_slice(&encoded).unwrap();
+		println!("Deserialize: {:?}", start.elapsed());
+
+		assert_eq!(decoded.children.len(), 200);
+	}
+}
+
+mod roblox_ts {
+	use crate::common::TempDir;
+	use argon::{
+		core::meta::{Context, Meta},
+		integration::TsLayout,
+		middleware::new_snapshot,
+		project::Project,
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{types::Variant, Ustr};
+	use std::{fs, path::Path, thread, time::Duration};
+
+	const PROJECT: &str = r#"{
+	"name": "TS",
+	"globIgnorePaths": ["**/package.json", "**/tsconfig.json"],
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": {
+			"Shared": { "$path": "src/shared" },
+			"rbxts_include": {
+				"$path": "include",
+				"node_modules": { "$className": "Folder", "@rbxts": { "$path": "node_modules/@rbxts" } }
+			}
+		}
+	}
+}"#;
+
+	const TSCONFIG: &str = r#"{
+	// Comments are allowed in tsconfig.json
+	"compilerOptions": {
+		"rootDir": "src", /* required */
+		"outDir": "out/compiled",
+		"baseUrl": "src"
+	}
+}"#;
+
+	/// Mimics compiled roblox-ts project with `outDir` set to `out/compiled`
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		let files = [
+			("default.project.json", PROJECT),
+			("tsconfig.json", TSCONFIG),
+			("package.json", "{}"),
+			("src/shared/module.ts", "export const value = 1;"),
+			("src/shared/index.ts", "export {};"),
+			("out/compiled/shared/module.luau", "local exports = {}\nreturn exports"),
+			("out/compiled/shared/init.luau", "return nil"),
+			("include/RuntimeLib.lua", "return {}"),
+			("node_modules/@rbxts/types/package.json", "{}"),
+		];
+
+		for (path, contents) in files {
+			let path = dir.join(path);
+
+			fs::create_dir_all(path.parent().unwrap()).unwrap();
+			fs::write(path, contents).unwrap();
+		}
+
+		dir
+	}
+
+	fn load(dir: &Path) -> Project {
+		Project::load(&dir.join("default.project.json")).unwrap()
+	}
+
+	#[test]
+	fn layout() {
+		let dir = fixture("layout");
+		let layout = load(&dir).ts_layout().unwrap();
+
+		assert_eq!(layout.root_dir, dir.join("src"));
+		assert_eq!(layout.out_dir, dir.join("out/compiled"));
+
+		assert_eq!(
+			layout.map_path(&dir.join("src/shared")),
+			dir.join("out/compiled/shared")
+		);
+		assert_eq!(
+			layout.map_path(&dir.join("src/shared/module.ts")),
+			dir.join("out/compiled/shared/module.luau")
+		);
+		assert_eq!(
+			layout.map_path(&dir.join("src/shared/index.tsx")),
+			dir.join("out/compiled/shared/init.luau")
+		);
+		assert_eq!(layout.map_path(&dir.join("include")), dir.join("include"));
+
+		// Defaults are used without `tsconfig.json`
+		let layout = TsLayout::load(Path::new("/project"));
+
+		assert_eq!(layout.root_dir, Path::new("/project/src"));
+		assert_eq!(layout.out_dir, Path::new("/project/out"));
+	}
+
+	#[test]
+	fn detection() {
+		let dir = fixture("detection");
+		let mut project = load(&dir);
+
+		assert!(project.is_ts());
+		assert!(project.ts_layout().is_some());
+
+		project.roblox_ts = Some(false);
+
+		assert!(project.ts_layout().is_none());
+
+		fs::remove_file(dir.join("tsconfig.json")).unwrap();
+		project.roblox_ts = None;
+
+		assert!(project.ts_layout().is_none());
+	}
+
+	#[test]
+	fn maps_project_paths_and_ignores_sources() {
+		let dir = fixture("mapping");
+		let vfs = Vfs::new(false);
+
+		let snapshot = new_snapshot(&dir.join("default.project.json"), &Context::default(), &vfs)
+			.unwrap()
+			.unwrap();
+
+		let storage = snapshot
+			.children
+			.iter()
+			.find(|child| child.name == "ReplicatedStorage")
+			.unwrap();
+		let shared = storage.children.iter().find(|child| child.name == "Shared").unwrap();
+
+		// `src/shared` is read from `out/compiled/shared`
+		assert_eq!(shared.class, "ModuleScript");
+		assert_eq!(
+			shared.properties.get(&Ustr::from("Source")),
+			Some(&Variant::String(String::from("return nil")))
+		);
+
+		let module = shared.children.iter().find(|child| child.name == "module").unwrap();
+
+		assert_eq!(
+			module.properties.get(&Ustr::from("Source")),
+			Some(&Variant::String(String::from("local exports = {}\nreturn exports")))
+		);
+
+		// TypeScript sources are never passed to middleware
+		let context = Meta::from_project(&load(&dir)).context;
+
+		assert!(new_snapshot(&dir.join("src/shared/module.ts"), &context, &vfs)
+			.unwrap()
+			.is_none());
+		assert!(new_snapshot(&dir.join("src/shared"), &context, &vfs).unwrap().is_none());
+		assert!(new_snapshot(&dir.join("include"), &context, &vfs).unwrap().is_some());
+	}
+
+	#[test]
+	fn compiled_output_is_read_only() {
+		let dir = fixture("read-only");
+		let filter = Meta::from_project(&load(&dir)).context.syncback_filter().clone();
+
+		assert!(filter.matches_path(&dir.join("out/compiled/shared/module.luau")));
+		assert!(filter.matches_path(&dir.join("out/compiled/shared")));
+		assert!(!filter.matches_path(&dir.join("include/RuntimeLib.lua")));
+		assert!(!filter.matches_path(&dir.join("src/shared/module.ts")));
+
+		// Not a roblox-ts project
+		let mut project = load(&dir);
+		project.roblox_ts = Some(false);
+
+		let filter = Meta::from_project(&project).context.syncback_filter().clone();
+
+		assert!(!filter.matches_path(&dir.join("out/compiled/shared/module.luau")));
+	}
+
+	#[test]
+	fn stale_output() {
+		let dir = fixture("stale");
+		let layout = load(&dir).ts_layout().unwrap();
+
+		fs::write(dir.join("out/compiled/shared/module.luau"), "return {}").unwrap();
+
+		assert!(!layout.is_stale());
+
+		thread::sleep(Duration::from_millis(20));
+		fs::write(dir.join("src/shared/module.ts"), "export const value = 2;").unwrap();
+
+		assert!(layout.is_stale());
+	}
+}
+
+mod external_middleware {
+	use crate::common::TempDir;
+	use anyhow::Result;
+	use argon::{
+		core::{
+			meta::{Context, SyncRule},
+			processor::write,
+			snapshot::{Snapshot, UpdatedSnapshot},
+			tree::Tree,
+			Core,
+		},
+		middleware::{external::MiddlewareImpl, Middleware},
+		project::Project,
+		vfs::Vfs,
+		Properties,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::{
+		fs,
+		path::{Path, PathBuf},
+	};
+
+	/// Reads `.upper.txt` files as `StringValue`s with uppercased contents
+	struct Upper;
+
+	impl MiddlewareImpl for Upper {
+		fn read(&self, path: &Path, _context: &Context, vfs: &Vfs) -> Result<Snapshot> {
+			let mut properties = UstrMap::default();
+			properties.insert(
+				Ustr::from("Value"),
+				Variant::String(vfs.read_to_string(path)?.to_uppercase()),
+			);
+
+			Ok(Snapshot::new().with_class("StringValue").with_properties(properties))
+		}
+
+		fn write(&self, properties: &Properties, path: &Path, vfs: &Vfs) -> Result<Properties> {
+			let mut properties = properties.clone();
+
+			if let Some(Variant::String(value)) = properties.remove(&Ustr::from("Value")) {
+				vfs.write(path, value.as_bytes())?;
+			}
+
+			Ok(properties)
+		}
+	}
+
+	fn upper() -> Middleware {
+		Middleware::External(String::from("upper"))
+	}
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "External",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": { "$path": "src" }
+			}
+		}"#,
+		)
+		.unwrap();
+
+		fs::write(dir.join("src/Greeting.upper.txt"), "hello").unwrap();
+		fs::write(dir.join("src/Plain.txt"), "plain").unwrap();
+
+		dir
+	}
+
+	fn start(dir: &Path) -> Core {
+		let rule = SyncRule::new(upper())
+			.with_pattern("*.upper.txt")
+			.with_suffix(".upper.txt");
+
+		Core::builder(Project::load(&dir.join("default.project.json")).unwrap())
+			.middleware(rule, Upper)
+			.build()
+			.unwrap()
+	}
+
+	fn find(tree: &Tree, path: PathBuf) -> Ref {
+		tree.get_ids(&path).unwrap()[0]
+	}
+
+	fn value(tree: &Tree, id: Ref) -> Variant {
+		tree.get_instance(id).unwrap().properties[&Ustr::from("Value")].clone()
+	}
+
+	#[test]
+	fn read() {
+		let dir = fixture("read");
+		let core = start(&dir);
+		let tree = core.tree();
+
+		let greeting = find(&tree, dir.join("src/Greeting.upper.txt"));
+		let instance = tree.get_instance(greeting).unwrap();
+
+		assert_eq!(instance.name, "Greeting");
+		assert_eq!(instance.class, "StringValue");
+		assert_eq!(value(&tree, greeting), Variant::String(String::from("HELLO")));
+
+		// Registered rules come first but files they don't match still use built-in middleware
+		let plain = find(&tree, dir.join("src/Plain.txt"));
+		assert_eq!(value(&tree, plain), Variant::String(String::from("plain")));
+	}
+
+	#[test]
+	fn write() {
+		let dir = fixture("write");
+		let core = start(&dir);
+
+		{
+			let mut tree = core.tree();
+			let greeting = find(&tree, dir.join("src/Greeting.upper.txt"));
+
+			let mut properties = UstrMap::default();
+			properties.insert(Ustr::from("Value"), Variant::String(String::from("goodbye")));
+
+			let mut update = UpdatedSnapshot::new(greeting);
+			update.properties = Some(properties);
+
+			write::apply_update(update, &mut tree, &Vfs::new(false)).unwrap();
+		}
+
+		// File keeps its format instead of being moved to a regular `.txt` file
+		assert_eq!(
+			fs::read_to_string(dir.join("src/Greeting.upper.txt")).unwrap(),
+			"goodbye"
+		);
+		assert!(!dir.join("src/Greeting.txt").exists());
+
+		drop(core);
+
+		let core = start(&dir);
+		let tree = core.tree();
+
+		let greeting = find(&tree, dir.join("src/Greeting.upper.txt"));
+		assert_eq!(value(&tree, greeting), Variant::String(String::from("GOODBYE")));
+	}
+
+	#[test]
+	fn location() {
+		let dir = fixture("location");
+		let core = start(&dir);
+		let tree = core.tree();
+
+		let greeting = find(&tree, dir.join("src/Greeting.upper.txt"));
+		let context = &tree.get_meta(greeting).unwrap().context;
+
+		let rules = context.sync_rules_of_type(&upper(), true);
+
+		assert_eq!(rules.len(), 1);
+		assert_eq!(
+			rules[0].locate(&dir.join("src/Greeting.upper.txt"), "Farewell", false),
+			Some(dir.join("src/Farewell.upper.txt"))
+		);
+
+		assert_eq!(context.sync_rules().next().map(|rule| &rule.middleware), Some(&upper()));
+	}
+
+	#[test]
+	fn unsupported_write() {
+		let vfs = Vfs::new_virtual();
+		let path = Path::new("Module.json");
+		let context = Context::default();
+
+		// Unsupported middleware fail like unregistered ones instead of panicking
+		let unregistered = Middleware::External(String::from("unregistered"));
+
+		for middleware in [Middleware::JsonModule, Middleware::InstanceData, unregistered] {
+			let err = middleware
+				.write(&Properties::default(), path, &context, &vfs)
+				.unwrap_err();
+
+			assert!(format!("{:#}", err).contains("Failed to write"));
+		}
+
+		let err = Middleware::RbxmModel
+			.write(&Properties::default(), path, &context, &vfs)
+			.unwrap_err();
+
+		assert!(format!("{:#}", err).contains("does not support writing"));
+		assert!(!vfs.exists(path));
+	}
+
+	#[test]
+	fn serialization() {
+		let rule: SyncRule =
+			serde_json::from_str(r#"{ "type": { "External": "upper" }, "pattern": "*.upper.txt" }"#).unwrap();
+
+		assert_eq!(rule.middleware, upper());
+		assert_eq!(upper().to_string(), "upper");
+	}
+}
+
+mod inference {
+	use crate::common::TempDir;
+	use argon::{
+		core::{
+			meta::{Meta, NodePath},
+			snapshot::Snapshot,
+		},
+		middleware::project::new_snapshot_node,
+		project::Project,
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{types::Variant, Ustr};
+	use std::{
+		fs,
+		path::{Path, PathBuf},
+	};
+
+	const PROJECT: &str = r#"{
+	"name": "Inference",
+	"classInference": [
+		{ "name": "Remotes", "parent": "ReplicatedStorage", "class": "Configuration" },
+		{ "name": "Assets", "class": "Model" }
+	],
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": {
+			"$path": "src/shared",
+			"Workspace": {},
+			"StarterCharacterScripts": {}
+		},
+		"StarterPlayer": {
+			"StarterPlayerScripts": {},
+			"StarterCharacterScripts": {}
+		},
+		"StarterCharacterScripts": {},
+		"Workspace": {
+			"$path": "src/workspace"
+		}
+	}
+}"#;
+
+	fn fixture(name: &str) -> (TempDir, PathBuf) {
+		let dir = TempDir::new(name);
+
+		for path in [
+			"src/shared/Lighting",
+			"src/shared/Remotes",
+			"src/shared/Assets",
+			"src/shared/Nested/Terrain",
+			"src/shared/Nested/Remotes",
+			"src/workspace/Terrain",
+			"src/workspace/Explicit",
+		] {
+			fs::create_dir_all(dir.join(path)).unwrap();
+		}
+
+		fs::write(
+			dir.join("src/workspace/Terrain/.data.json"),
+			r#"{ "properties": { "WaterWaveSize": 0.5 } }"#,
+		)
+		.unwrap();
+
+		fs::write(
+			dir.join("src/workspace/Explicit/.data.json"),
+			r#"{ "className": "Model" }"#,
+		)
+		.unwrap();
+
+		let path = dir.join("default.project.json");
+		fs::write(&path, PROJECT).unwrap();
+
+		(dir, path)
+	}
+
+	fn snapshot(path: &Path) -> Snapshot {
+		let project = Project::load(path).unwrap();
+		let meta = Meta::from_project(&project);
+
+		new_snapshot_node(
+			&project.name,
+			path,
+			project.node,
+			NodePath::new(),
+			&meta.context,
+			&Vfs::new(false),
+		)
+		.unwrap()
+	}
+
+	fn child<'a>(snapshot: &'a Snapshot, path: &str) -> &'a Snapshot {
+		path.split('.').fold(snapshot, |snapshot, name| {
+			snapshot
+				.children
+				.iter()
+				.find(|child| child.name == name)
+				.unwrap_or_else(|| panic!("Missing {}", name))
+		})
+	}
+
+	fn class<'a>(snapshot: &'a Snapshot, path: &str) -> &'a str {
+		&child(snapshot, path).class
+	}
+
+	#[test]
+	fn services_only_under_data_model() {
+		let (_dir, path) = fixture("services");
+		let snapshot = snapshot(&path);
+
+		assert_eq!(class(&snapshot, "ReplicatedStorage"), "ReplicatedStorage");
+		assert_eq!(class(&snapshot, "Workspace"), "Workspace");
+
+		// Service names deeper in the tree are regular folders
+		assert_eq!(class(&snapshot, "ReplicatedStorage.Workspace"), "Folder");
+		assert_eq!(class(&snapshot, "ReplicatedStorage.Lighting"), "Folder");
+	}
+
+	#[test]
+	fn starter_player_containers() {
+		let (_dir, path) = fixture("starter-player");
+		let snapshot = snapshot(&path);
+
+		assert_eq!(class(&snapshot, "StarterPlayer"), "StarterPlayer");
+		assert_eq!(
+			class(&snapshot, "StarterPlayer.StarterPlayerScripts"),
+			"StarterPlayerScripts"
+		);
+		assert_eq!(
+			class(&snapshot, "StarterPlayer.StarterCharacterScripts"),
+			"StarterCharacterScripts"
+		);
+
+		// Only `StarterPlayer` can contain them
+		assert_eq!(class(&snapshot, "StarterCharacterScripts"), "Folder");
+		assert_eq!(class(&snapshot, "ReplicatedStorage.StarterCharacterScripts"), "Folder");
+	}
+
+	#[test]
+	fn terrain_under_workspace() {
+		let (_dir, path) = fixture("terrain");
+		let snapshot = snapshot(&path);
+
+		let terrain = child(&snapshot, "Workspace.Terrain");
+
+		assert_eq!(terrain.class, "Terrain");
+		assert_eq!(
+			terrain.properties.get(&Ustr::from("WaterWaveSize")),
+			Some(&Variant::Float32(0.5))
+		);
+
+		assert_eq!(class(&snapshot, "ReplicatedStorage.Nested.Terrain"), "Folder");
+	}
+
+	#[test]
+	fn explicit_class_wins() {
+		let (_dir, path) = fixture("explicit");
+		let snapshot = snapshot(&path);
+
+		assert_eq!(class(&snapshot, "Workspace.Explicit"), "Model");
+	}
+
+	#[test]
+	fn project_rules() {
+		let (_dir, path) = fixture("rules");
+		let snapshot = snapshot(&path);
+
+		assert_eq!(class(&snapshot, "ReplicatedStorage.Remotes"), "Configuration");
+		assert_eq!(class(&snapshot, "ReplicatedStorage.Nested.Remotes"), "Folder");
+
+		// Rules without parent match anywhere
+		assert_eq!(class(&snapshot, "ReplicatedStorage.Assets"), "Model");
+	}
+
+	#[test]
+	fn unknown_ancestry() {
+		let dir = TempDir::new("inference-root");
+
+		let path = dir.join("default.project.json");
+		fs::write(&path, r#"{ "name": "Lighting", "tree": {} }"#).unwrap();
+
+		// Root has no parent so its name is all there is
+		assert_eq!(snapshot(&path).class, "Lighting");
+	}
+}
+
+mod load_errors {
+	use crate::common::TempDir;
+	use argon::{
+		constants::LOAD_ERROR_ATTRIBUTE,
+		core::{
+			meta::{Meta, NodePath},
+			snapshot::Snapshot,
+		},
+		middleware::{project::new_snapshot_node, Middleware},
+		project::Project,
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{types::Variant, Ustr};
+	use std::{
+		fs,
+		path::{Path, PathBuf},
+	};
+
+	fn fixture(name: &str, strict: bool) -> (TempDir, PathBuf) {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		fs::write(dir.join("src/Good.luau"), "return true").unwrap();
+		fs::write(dir.join("src/Other.server.luau"), "print('hello')").unwrap();
+
+		// UTF-16 with BOM, not valid UTF-8
+		fs::write(dir.join("src/Broken.luau"), [0xFF, 0xFE, 0x72, 0x00, 0xD8, 0x00]).unwrap();
+
+		let path = dir.join("default.project.json");
+
+		fs::write(
+			&path,
+			format!(
+				r#"{{ "name": "LoadErrors", "strictLoad": {}, "tree": {{ "$path": "src" }} }}"#,
+				strict
+			),
+		)
+		.unwrap();
+
+		(dir, path)
+	}
+
+	fn snapshot(path: &Path) -> anyhow::Result<Snapshot> {
+		let project = Project::load(path).unwrap();
+		let meta = Meta::from_project(&project);
+
+		new_snapshot_node(
+			&project.name,
+			path,
+			project.node,
+			NodePath::new(),
+			&meta.context,
+			&Vfs::new(false),
+		)
+	}
+
+	fn child<'a>(snapshot: &'a Snapshot, name: &str) -> &'a Snapshot {
+		snapshot
+			.children
+			.iter()
+			.find(|child| child.name == name)
+			.unwrap_or_else(|| panic!("Missing {}", name))
+	}
+
+	fn load_error(snapshot: &Snapshot) -> Option<&str> {
+		match snapshot.properties.get(&Ustr::from("Attributes")) {
+			Some(Variant::Attributes(attributes)) => match attributes.get(LOAD_ERROR_ATTRIBUTE) {
+				Some(Variant::String(error)) => Some(error),
+				_ => None,
+			},
+			_ => None,
+		}
+	}
+
+	#[test]
+	fn placeholder() {
+		let (_dir, path) = fixture("placeholder", false);
+		let snapshot = snapshot(&path).unwrap();
+
+		// Siblings still load
+		assert_eq!(child(&snapshot, "Good").class, "ModuleScript");
+		assert_eq!(child(&snapshot, "Other").class, "Script");
+
+		let broken = child(&snapshot, "Broken");
+
+		assert_eq!(broken.class, "Folder");
+		assert!(load_error(broken).is_some());
+
+		let error = broken.meta.load_error.as_ref().unwrap();
+
+		assert_eq!(error.path, path.parent().unwrap().join("src/Broken.luau"));
+		assert_eq!(error.middleware, Some(Middleware::ModuleScript));
+		assert_eq!(load_error(broken), Some(error.error.as_str()));
+
+		assert!(load_error(child(&snapshot, "Good")).is_none());
+	}
+
+	#[test]
+	fn strict_load() {
+		let (_dir, path) = fixture("strict", true);
+
+		assert!(snapshot(&path).is_err());
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn unreadable() {
+		use std::os::unix::fs::PermissionsExt;
+
+		let (_dir, path) = fixture("unreadable", false);
+		let unreadable = path.parent().unwrap().join("src/Secret.luau");
+
+		fs::write(&unreadable, "return 'secret'").unwrap();
+		fs::set_permissions(&unreadable, fs::Permissions::from_mode(0o000)).unwrap();
+
+		// Permissions are not enforced for privileged users
+		if fs::read(&unreadable).is_ok() {
+			return;
+		}
+
+		let snapshot = snapshot(&path).unwrap();
+
+		assert_eq!(child(&snapshot, "Good").class, "ModuleScript");
+		assert_eq!(child(&snapshot, "Secret").class, "Folder");
+		assert!(load_error(child(&snapshot, "Secret")).is_some());
+	}
+}
+
+mod services {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			processor::{read, write},
+			snapshot::UpdatedSnapshot,
+			Core,
+		},
+		project::Project,
+		util,
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::{fs, path::PathBuf};
+
+	const PROJECT: &str = r#"{
+	"name": "Services",
+	"servicesPath": "services",
+	"tree": {
+		"$className": "DataModel",
+		"SoundService": {
+			"$properties": { "RespectFilteringEnabled": true }
+		},
+		"StarterPlayer": {
+			"$path": { "optional": "services/StarterPlayer" },
+			"StarterPlayerScripts": { "$path": "src" }
+		}
+	}
+}"#;
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+		fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+
+		dir
+	}
+
+	fn find(core: &Core, service: &str) -> Ref {
+		let tree = core.tree();
+		let dom = tree.inner();
+
+		*dom.root()
+			.children()
+			.iter()
+			.find(|child| dom.get_by_ref(**child).unwrap().name == service)
+			.unwrap()
+	}
+
+	fn property(core: &Core, service: &str, property: &str) -> Option<Variant> {
+		let id = find(core, service);
+
+		core.tree()
+			.get_instance(id)
+			.and_then(|instance| instance.properties.get(&Ustr::from(property)).cloned())
+	}
+
+	#[test]
+	fn singleton_services() {
+		for service in util::SINGLETON_SERVICES {
+			assert!(util::is_service(service), "{} is not a service", service);
+			assert!(util::is_singleton_service(service));
+		}
+
+		// Services that hold instances rather than configuration
+		for service in ["Workspace", "ReplicatedStorage", "ServerScriptService", "StarterGui"] {
+			assert!(!util::is_singleton_service(service));
+		}
+
+		assert!(!util::is_singleton_service("StarterPlayerScripts"));
+		assert!(!util::is_singleton_service("Folder"));
+	}
+
+	#[test]
+	fn implicit_nodes() {
+		let dir = fixture("implicit");
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+		let lighting = &project.node.tree["Lighting"];
+
+		assert!(lighting.implicit);
+		assert_eq!(
+			lighting.path.as_ref().unwrap().path(),
+			PathBuf::from("services/Lighting")
+		);
+
+		// Nodes from the project file are kept as they are
+		assert!(!project.node.tree["SoundService"].implicit);
+		assert!(project.node.tree["SoundService"].path.is_none());
+
+		// Implicit nodes are never written to the project file
+		project.save(&dir.join("default.project.json")).unwrap();
+
+		let saved = fs::read_to_string(dir.join("default.project.json")).unwrap();
+
+		assert!(!saved.contains("Lighting"));
+		assert!(saved.contains("servicesPath"));
+
+		// Only places get implicit nodes
+		fs::write(
+			dir.join("model.project.json"),
+			r#"{ "servicesPath": "services", "tree": { "$className": "Folder" } }"#,
+		)
+		.unwrap();
+
+		let model = Project::load(&dir.join("model.project.json")).unwrap();
+		assert!(model.node.tree.is_empty());
+	}
+
+	#[test]
+	fn write_through() {
+		let dir = fixture("write-through");
+		let core = start(&dir);
+
+		assert!(property(&core, "Lighting", "ClockTime").is_none());
+
+		// Changing `Lighting.ClockTime` in Studio creates its data file
+		let lighting = find(&core, "Lighting");
+
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from("ClockTime"), Variant::Float32(6.5));
+
+		let mut update = UpdatedSnapshot::new(lighting);
+		update.properties = Some(properties);
+
+		write::apply_update(update, &mut core.tree(), &Vfs::new(false)).unwrap();
+
+		// Rojo mode is enabled by default so the data file uses the `.meta.json` suffix
+		let data = fs::read_to_string(dir.join("services/Lighting.meta.json")).unwrap();
+		assert!(data.contains("ClockTime"));
+
+		let project = fs::read_to_string(dir.join("default.project.json")).unwrap();
+		assert!(!project.contains("Lighting"));
+
+		// Editing the data file changes the service
+		fs::write(
+			dir.join("services/Lighting.meta.json"),
+			r#"{ "properties": { "ClockTime": 18.0 } }"#,
+		)
+		.unwrap();
+
+		let changes = read::process_changes(lighting, &mut core.tree(), &Vfs::new(false)).unwrap();
+		let properties = changes.updates[0].properties.as_ref().unwrap();
+
+		assert_eq!(changes.updates[0].id, lighting);
+		assert_eq!(properties[&Ustr::from("ClockTime")], Variant::Float32(18.0));
+		assert_eq!(property(&core, "Lighting", "ClockTime"), Some(Variant::Float32(18.0)));
+	}
+
+	#[test]
+	fn explicit_nodes() {
+		let dir = fixture("explicit");
+
+		fs::create_dir_all(dir.join("services")).unwrap();
+		fs::write(
+			dir.join("services/StarterPlayer.data.json"),
+			r#"{ "properties": { "CharacterWalkSpeed": 20.0 } }"#,
+		)
+		.unwrap();
+
+		let core = start(&dir);
+
+		// Node with children and a path backed by the data file alone
+		assert_eq!(
+			property(&core, "StarterPlayer", "CharacterWalkSpeed"),
+			Some(Variant::Float32(20.0))
+		);
+
+		// Nodes with only `$properties` round-trip through the project file
+		assert_eq!(
+			property(&core, "SoundService", "RespectFilteringEnabled"),
+			Some(Variant::Bool(true))
+		);
+	}
+}
+
+mod mirrors {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			changes::Changes,
+			helpers::mirrors,
+			processor::{read, write},
+			snapshot::UpdatedSnapshot,
+			tree::Tree,
+			Core,
+		},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::fs;
+
+	fn fixture(name: &str, primary: bool) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("shared")).unwrap();
+		fs::write(dir.join("shared/Module.luau"), "return 1").unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			format!(
+				r#"{{
+				"name": "Mirrors",
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{
+						"Shared": {{ "$path": "shared", "$primary": {} }}
+					}},
+					"ServerStorage": {{
+						"Tests": {{
+							"$className": "Folder",
+							"Shared": {{ "$path": "shared" }}
+						}}
+					}}
+				}}
+			}}"#,
+				primary
+			),
+		)
+		.unwrap();
+
+		dir
+	}
+
+	fn source(tree: &Tree, id: Ref) -> String {
+		match tree.get_instance(id).unwrap().properties.get(&Ustr::from("Source")) {
+			Some(Variant::String(source)) => source.clone(),
+			_ => String::new(),
+		}
+	}
+
+	/// Returns module of the primary node followed by the one of the mirror
+	fn modules(core: &Core) -> (Ref, Ref) {
+		let tree = core.tree();
+
+		(
+			tree.find_by_path("ReplicatedStorage.Shared.Module").unwrap(),
+			tree.find_by_path("ServerStorage.Tests.Shared.Module").unwrap(),
+		)
+	}
+
+	fn edit(id: Ref, source: &str) -> Changes {
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from("Source"), Variant::String(source.to_owned()));
+
+		let mut update = UpdatedSnapshot::new(id);
+		update.properties = Some(properties);
+
+		let mut changes = Changes::new();
+		changes.update(update);
+		changes
+	}
+
+	/// Applies changes the same way the processor does after a client event
+	fn syncback(core: &Core, changes: Changes) -> Changes {
+		let vfs = Vfs::new(false);
+		let mut tree = core.tree();
+		let copies = mirrors::find_stale_copies(&changes, &tree);
+
+		for snapshot in changes.updates {
+			write::apply_update(snapshot, &mut tree, &vfs).unwrap();
+		}
+
+		let mut mirrored = Changes::new();
+
+		for id in copies {
+			mirrored.extend(read::process_changes(id, &mut tree, &vfs).unwrap());
+		}
+
+		mirrored
+	}
+
+	#[test]
+	fn fan_out() {
+		let dir = fixture("fan-out", true);
+		let core = start(&dir);
+		let (primary, mirror) = modules(&core);
+		let vfs = Vfs::new(false);
+
+		fs::write(dir.join("shared/Module.luau"), "return 2").unwrap();
+
+		let mut tree = core.tree();
+		let ids = tree.get_ids(&dir.join("shared/Module.luau")).unwrap().clone();
+
+		assert_eq!(ids.len(), 2);
+
+		for id in ids {
+			read::process_changes(id, &mut tree, &vfs).unwrap();
+		}
+
+		assert_eq!(source(&tree, primary), "return 2");
+		assert_eq!(source(&tree, mirror), "return 2");
+
+		// Removing instance of one copy keeps the path of the other one
+		fs::write(dir.join("shared/New.luau"), "return 3").unwrap();
+
+		let nodes = tree.get_ids(&dir.join("shared")).unwrap().clone();
+
+		for id in &nodes {
+			read::process_changes(*id, &mut tree, &vfs).unwrap();
+		}
+
+		assert_eq!(tree.get_ids(&dir.join("shared/New.luau")).unwrap().len(), 2);
+
+		fs::remove_file(dir.join("shared/New.luau")).unwrap();
+		read::process_changes(nodes[0], &mut tree, &vfs).unwrap();
+
+		assert_eq!(tree.get_ids(&dir.join("shared/New.luau")).unwrap().len(), 1);
+
+		read::process_changes(nodes[1], &mut tree, &vfs).unwrap();
+
+		assert!(tree.get_ids(&dir.join("shared/New.luau")).is_none());
+		assert!(tree.find_by_path("ServerStorage.Tests.Shared.New").is_none());
+	}
+
+	#[test]
+	fn primary_syncback() {
+		let dir = fixture("primary", true);
+		let core = start(&dir);
+		let (primary, mirror) = modules(&core);
+
+		// Copies other than the primary one are read-only
+		assert!(syncback(&core, edit(mirror, "return 2")).is_empty());
+		assert_eq!(fs::read_to_string(dir.join("shared/Module.luau")).unwrap(), "return 1");
+
+		// Changes of the primary copy are written and propagated to the others
+		let mirrored = syncback(&core, edit(primary, "return 3"));
+
+		assert_eq!(fs::read_to_string(dir.join("shared/Module.luau")).unwrap(), "return 3");
+		assert_eq!(mirrored.updates.len(), 1);
+		assert_eq!(mirrored.updates[0].id, mirror);
+		assert_eq!(source(&core.tree(), mirror), "return 3");
+	}
+
+	#[test]
+	fn no_primary() {
+		let dir = fixture("no-primary", false);
+		let core = start(&dir);
+		let (primary, mirror) = modules(&core);
+
+		syncback(&core, edit(primary, "return 2"));
+		syncback(&core, edit(mirror, "return 3"));
+
+		assert_eq!(fs::read_to_string(dir.join("shared/Module.luau")).unwrap(), "return 1");
+	}
+}
+
+mod middleware_config {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			meta::Meta,
+			processor::write,
+			snapshot::{AddedSnapshot, Snapshot},
+			tree::Tree,
+		},
+		middleware::Middleware,
+		project::{Project, ScriptExtension},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::fs;
+
+	fn fixture(name: &str, config: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			format!(
+				r#"{{
+				"name": "MiddlewareConfig",
+				"middlewareConfig": {},
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{ "$path": "src" }}
+				}}
+			}}"#,
+				config
+			),
+		)
+		.unwrap();
+
+		dir
+	}
+
+	fn class(tree: &Tree, path: &str) -> Option<String> {
+		let id = tree.find_by_path(path)?;
+		Some(tree.get_instance(id).unwrap().class.to_string())
+	}
+
+	fn get_source(tree: &Tree, path: &str) -> String {
+		let id = tree.find_by_path(path).unwrap();
+
+		match tree.get_instance(id).unwrap().properties.get(&Ustr::from("Source")) {
+			Some(Variant::String(source)) => source.clone(),
+			_ => String::new(),
+		}
+	}
+
+	fn source(name: &str) -> UstrMap<Variant> {
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from("Source"), Variant::String(format!("-- {}", name)));
+		properties
+	}
+
+	/// Adds script from Studio to `ReplicatedStorage`
+	fn add(tree: &mut Tree, name: &str, class: &str, children: Vec<Snapshot>) {
+		let parent = tree.find_by_path("ReplicatedStorage").unwrap();
+
+		let snapshot = AddedSnapshot {
+			id: Ref::new(),
+			meta: Meta::new(),
+			parent,
+			name: name.to_owned(),
+			class: Ustr::from(class),
+			properties: source(name),
+			children,
+		};
+
+		write::apply_addition(snapshot, tree, &Vfs::new(false)).unwrap();
+	}
+
+	fn module(name: &str) -> Snapshot {
+		Snapshot::new()
+			.with_id(Ref::new())
+			.with_name(name)
+			.with_class("ModuleScript")
+			.with_properties(source(name))
+	}
+
+	#[test]
+	fn lua_extension() {
+		let dir = fixture("lua", r#"{ "scripts": { "extension": "lua" } }"#);
+
+		fs::write(dir.join("src/Old.lua"), "return 1").unwrap();
+		fs::write(dir.join("src/Other.luau"), "return 2").unwrap();
+
+		let core = start(&dir);
+		let mut tree = core.tree();
+
+		// Both extensions are read
+		assert_eq!(class(&tree, "ReplicatedStorage.Old").unwrap(), "ModuleScript");
+		assert_eq!(class(&tree, "ReplicatedStorage.Other").unwrap(), "ModuleScript");
+
+		let context = &tree.get_meta(tree.root_ref()).unwrap().context;
+
+		assert_eq!(context.middleware_config().scripts.extension(), ScriptExtension::Lua);
+		assert_eq!(
+			context.sync_rules_of_type(&Middleware::ServerScript, true)[0].locate(&dir.join("src/Main"), "Main", false),
+			Some(dir.join("src/Main.server.lua"))
+		);
+
+		add(&mut tree, "Main", "Script", vec![]);
+		add(&mut tree, "Util", "ModuleScript", vec![module("Child")]);
+
+		assert_eq!(fs::read_to_string(dir.join("src/Main.server.lua")).unwrap(), "-- Main");
+		assert_eq!(fs::read_to_string(dir.join("src/Util/init.lua")).unwrap(), "-- Util");
+		assert!(dir.join("src/Util/Child.lua").is_file());
+
+		drop(tree);
+		drop(core);
+
+		let core = start(&dir);
+		let tree = core.tree();
+
+		assert_eq!(class(&tree, "ReplicatedStorage.Main").unwrap(), "Script");
+		assert_eq!(get_source(&tree, "ReplicatedStorage.Util"), "-- Util");
+	}
+
+	#[test]
+	fn luau_extension() {
+		let dir = fixture("luau", r#"{ "scripts": { "extension": "luau" } }"#);
+
+		fs::write(dir.join("src/Old.lua"), "return 1").unwrap();
+
+		let core = start(&dir);
+		let mut tree = core.tree();
+
+		assert_eq!(class(&tree, "ReplicatedStorage.Old").unwrap(), "ModuleScript");
+
+		add(&mut tree, "Main", "LocalScript", vec![]);
+		add(&mut tree, "Util", "ModuleScript", vec![module("Child")]);
+
+		assert!(dir.join("src/Main.client.luau").is_file());
+		assert!(dir.join("src/Util/init.luau").is_file());
+		assert!(dir.join("src/Util/Child.luau").is_file());
+	}
+
+	#[test]
+	fn custom_names() {
+		let dir = fixture(
+			"custom",
+			r#"{
+			"scripts": { "serverSuffix": ".srv", "clientSuffix": ".cl", "initName": "index" },
+			"data": { "fileName": ".props.json" }
+		}"#,
+		);
+
+		fs::create_dir_all(dir.join("src/Folder")).unwrap();
+		fs::write(dir.join("src/Game.srv.luau"), "print(1)").unwrap();
+		fs::write(dir.join("src/Folder/index.luau"), "return 1").unwrap();
+		fs::write(
+			dir.join("src/Folder/.props.json"),
+			r#"{ "attributes": { "Value": 1 } }"#,
+		)
+		.unwrap();
+
+		let core = start(&dir);
+		let mut tree = core.tree();
+
+		assert_eq!(class(&tree, "ReplicatedStorage.Game").unwrap(), "Script");
+		assert_eq!(class(&tree, "ReplicatedStorage.Folder").unwrap(), "ModuleScript");
+		assert_eq!(get_source(&tree, "ReplicatedStorage.Folder"), "return 1");
+
+		let folder = tree.find_by_path("ReplicatedStorage.Folder").unwrap();
+		assert!(tree
+			.get_instance(folder)
+			.unwrap()
+			.properties
+			.contains_key(&Ustr::from("Attributes")));
+
+		let context = &tree.get_meta(tree.root_ref()).unwrap().context;
+
+		assert_eq!(
+			context.sync_rules_of_type(&Middleware::InstanceData, true)[0].locate(
+				&dir.join("src/Other"),
+				"Other",
+				true
+			),
+			Some(dir.join("src/Other/.props.json"))
+		);
+
+		add(&mut tree, "Client", "LocalScript", vec![]);
+		add(&mut tree, "Util", "ModuleScript", vec![module("Child")]);
+
+		assert!(dir.join("src/Client.cl.luau").is_file());
+		assert!(dir.join("src/Util/index.luau").is_file());
+	}
+
+	#[test]
+	fn invalid_config() {
+		let dir = fixture("invalid", r#"{ "scripts": { "serverSuffix": "" } }"#);
+
+		assert!(Project::load(&dir.join("default.project.json")).is_err());
+	}
+
+	#[test]
+	fn migrate_extensions() {
+		let dir = fixture("migrate", "{}");
+
+		fs::create_dir_all(dir.join("src/Folder")).unwrap();
+		fs::write(dir.join("src/Module.lua"), "return 1").unwrap();
+		fs::write(dir.join("src/Main.server.lua"), "print(1)").unwrap();
+		fs::write(dir.join("src/Folder/init.client.lua"), "print(2)").unwrap();
+		fs::write(dir.join("src/Folder/Child.lua"), "return 2").unwrap();
+		fs::write(dir.join("src/Done.luau"), "return 3").unwrap();
+		fs::write(dir.join("src/Notes.txt"), "text.lua").unwrap();
+
+		let core = start(&dir);
+		let migration = core.migrate_extensions(ScriptExtension::Luau).unwrap();
+
+		assert_eq!(migration.renamed.len(), 4);
+		assert!(migration.skipped.is_empty());
+
+		for path in [
+			"src/Module.luau",
+			"src/Main.server.luau",
+			"src/Folder/init.client.luau",
+			"src/Folder/Child.luau",
+			"src/Done.luau",
+			"src/Notes.txt",
+		] {
+			assert!(dir.join(path).is_file(), "{} is missing", path);
+		}
+
+		assert!(!dir.join("src/Module.lua").exists());
+		assert!(!dir.join("src/Folder/init.client.lua").exists());
+
+		// Sources of the live tree point to the new files
+		{
+			let tree = core.tree();
+			let id = tree.find_by_path("ReplicatedStorage.Folder").unwrap();
+
+			assert_eq!(
+				tree.get_meta(id).unwrap().source.get_file().unwrap().path(),
+				dir.join("src/Folder/init.client.luau")
+			);
+			assert_eq!(tree.get_ids(&dir.join("src/Module.luau")).unwrap().len(), 1);
+			assert!(tree.get_ids(&dir.join("src/Module.lua")).is_none());
+		}
+
+		drop(core);
+
+		// Migrating again does nothing and the workspace reads the same
+		let core = start(&dir);
+
+		assert!(core
+			.migrate_extensions(ScriptExtension::Luau)
+			.unwrap()
+			.renamed
+			.is_empty());
+
+		let tree = core.tree();
+
+		assert_eq!(class(&tree, "ReplicatedStorage.Main").unwrap(), "Script");
+		assert_eq!(class(&tree, "ReplicatedStorage.Folder").unwrap(), "LocalScript");
+		assert_eq!(get_source(&tree, "ReplicatedStorage.Folder.Child"), "return 2");
+	}
+}
+
+mod file_refs {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			meta::SourceEntry,
+			processor::{read, write},
+			snapshot::UpdatedSnapshot,
+			tree::Tree,
+		},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{types::Variant, Ustr, UstrMap};
+	use std::fs;
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src/Config")).unwrap();
+		fs::create_dir_all(dir.join("assets")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "FileRefs",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"$path": "src",
+					"Shader": {
+						"$className": "StringValue",
+						"$properties": { "Value": { "$file": "assets/shader.glsl" } }
+					}
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		fs::write(
+			dir.join("src/Config/.data.json"),
+			r#"{ "className": "StringValue", "properties": { "Value": { "$file": "../../assets/config.json" } } }"#,
+		)
+		.unwrap();
+
+		fs::write(dir.join("assets/shader.glsl"), "void main() {}").unwrap();
+		fs::write(dir.join("assets/config.json"), r#"{ "lives": 3 }"#).unwrap();
+
+		dir
+	}
+
+	fn value(tree: &Tree, path: &str) -> Option<String> {
+		let id = tree.find_by_path(path).unwrap();
+
+		match tree.get_instance(id).unwrap().properties.get(&Ustr::from("Value")) {
+			Some(Variant::String(value)) => Some(value.clone()),
+			_ => None,
+		}
+	}
+
+	fn update(tree: &mut Tree, path: &str, value: &str) {
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from("Value"), Variant::String(value.to_owned()));
+
+		let mut update = UpdatedSnapshot::new(tree.find_by_path(path).unwrap());
+		update.properties = Some(properties);
+
+		write::apply_update(update, tree, &Vfs::new(false)).unwrap();
+	}
+
+	#[test]
+	fn read_and_syncback() {
+		let dir = fixture("syncback");
+		let core = start(&dir);
+		let mut tree = core.tree();
+
+		assert_eq!(value(&tree, "ReplicatedStorage.Shader").unwrap(), "void main() {}");
+		assert_eq!(value(&tree, "ReplicatedStorage.Config").unwrap(), r#"{ "lives": 3 }"#);
+
+		// Referenced files are relevant sources of their instances
+		let id = tree.find_by_path("ReplicatedStorage.Config").unwrap();
+		assert!(tree
+			.get_meta(id)
+			.unwrap()
+			.source
+			.relevant()
+			.contains(&SourceEntry::Reference(dir.join("assets/config.json"))));
+
+		update(&mut tree, "ReplicatedStorage.Shader", "void main() { discard; }");
+		update(&mut tree, "ReplicatedStorage.Config", r#"{ "lives": 5 }"#);
+
+		// Values are written to the referenced files, not inlined
+		assert_eq!(
+			fs::read_to_string(dir.join("assets/shader.glsl")).unwrap(),
+			"void main() { discard; }"
+		);
+		assert_eq!(
+			fs::read_to_string(dir.join("assets/config.json")).unwrap(),
+			r#"{ "lives": 5 }"#
+		);
+		assert!(fs::read_to_string(dir.join("src/Config/.data.json"))
+			.unwrap()
+			.contains(r#""$file": "../../assets/config.json""#));
+		assert!(fs::read_to_string(dir.join("default.project.json"))
+			.unwrap()
+			.contains(r#""$file": "assets/shader.glsl""#));
+
+		drop(tree);
+		drop(core);
+
+		let core = start(&dir);
+		let tree = core.tree();
+
+		assert_eq!(
+			value(&tree, "ReplicatedStorage.Shader").unwrap(),
+			"void main() { discard; }"
+		);
+		assert_eq!(value(&tree, "ReplicatedStorage.Config").unwrap(), r#"{ "lives": 5 }"#);
+	}
+
+	#[test]
+	fn watch_propagation() {
+		let dir = fixture("watch");
+		let core = start(&dir);
+		let vfs = Vfs::new(false);
+		let mut tree = core.tree();
+
+		for (file, path, contents) in [
+			("assets/config.json", "ReplicatedStorage.Config", r#"{ "lives": 9 }"#),
+			("assets/shader.glsl", "ReplicatedStorage.Shader", "// changed"),
+		] {
+			fs::write(dir.join(file), contents).unwrap();
+
+			let id = tree.get_ids(&dir.join(file)).unwrap()[0];
+			let changes = read::process_changes(id, &mut tree, &vfs).unwrap();
+
+			assert_eq!(changes.updates.len(), 1);
+			assert_eq!(value(&tree, path).unwrap(), contents);
+		}
+	}
+
+	#[test]
+	fn missing_file() {
+		let dir = fixture("missing");
+		fs::remove_file(dir.join("assets/config.json")).unwrap();
+
+		let core = start(&dir);
+		let vfs = Vfs::new(false);
+		let mut tree = core.tree();
+
+		// Instance still loads without the property
+		assert!(value(&tree, "ReplicatedStorage.Config").is_none());
+
+		// And picks it up once the file is created
+		fs::write(dir.join("assets/config.json"), "{}").unwrap();
+
+		let id = tree.get_ids(&dir.join("assets/config.json")).unwrap()[0];
+		read::process_changes(id, &mut tree, &vfs).unwrap();
+
+		assert_eq!(value(&tree, "ReplicatedStorage.Config").unwrap(), "{}");
+	}
+
+	#[test]
+	fn claimed_files() {
+		let dir = fixture("claimed");
+
+		fs::write(dir.join("src/Config/Module.luau"), "return 1").unwrap();
+		fs::write(
+			dir.join("src/Config/.data.json"),
+			r#"{ "className": "StringValue", "properties": { "Value": { "$file": "Module.luau" } } }"#,
+		)
+		.unwrap();
+		fs::create_dir_all(dir.join("src/Part")).unwrap();
+		fs::write(
+			dir.join("src/Part/.data.json"),
+			r#"{ "className": "Part", "properties": { "Anchored": { "$file": "../../assets/config.json" } } }"#,
+		)
+		.unwrap();
+
+		let core = start(&dir);
+		let tree = core.tree();
+
+		// Script is not read twice
+		assert!(value(&tree, "ReplicatedStorage.Config").is_none());
+		assert_eq!(tree.get_ids(&dir.join("src/Config/Module.luau")).unwrap().len(), 1);
+		assert_eq!(
+			tree.get_instance(tree.find_by_path("ReplicatedStorage.Config.Module").unwrap())
+				.unwrap()
+				.class,
+			"ModuleScript"
+		);
+
+		// Only string properties can be read from files
+		let part = tree.find_by_path("ReplicatedStorage.Part").unwrap();
+		assert!(!tree
+			.get_instance(part)
+			.unwrap()
+			.properties
+			.contains_key(&Ustr::from("Anchored")));
+	}
+}
+
+mod yaml_data {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			meta::Meta,
+			processor::write,
+			snapshot::{AddedSnapshot, UpdatedSnapshot},
+			tree::Tree,
+			Core,
+		},
+		project::Project,
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Attributes, Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::fs;
+
+	fn fixture(name: &str, data_format: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src/Settings")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			format!(
+				r#"{{
+				"name": "YamlData",
+				"dataFormat": "{}",
+				"strictLoad": true,
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{
+						"$path": "src"
+					}}
+				}}
+			}}"#,
+				data_format
+			),
+		)
+		.unwrap();
+
+		fs::write(
+			dir.join("src/Settings/.data.yaml"),
+			"# Comments are allowed\nclassName: Configuration\nattributes:\n  no: yes\n  1: 2\ntags:\n  - Shared\n",
+		)
+		.unwrap();
+
+		fs::write(dir.join("src/Value.txt"), "hello").unwrap();
+		fs::write(dir.join("src/Value.data.yml"), "properties:\n  Archivable: false\n").unwrap();
+
+		dir
+	}
+
+	fn attributes(tree: &Tree, path: &str) -> Attributes {
+		let id = tree.find_by_path(path).unwrap();
+
+		match tree.get_instance(id).unwrap().properties.get(&Ustr::from("Attributes")) {
+			Some(Variant::Attributes(attributes)) => attributes.clone(),
+			_ => Attributes::new(),
+		}
+	}
+
+	#[test]
+	fn read() {
+		let dir = fixture("read", "json");
+		let core = start(&dir);
+		let tree = core.tree();
+
+		let id = tree.find_by_path("ReplicatedStorage.Settings").unwrap();
+		let instance = tree.get_instance(id).unwrap();
+
+		assert_eq!(instance.class, "Configuration");
+
+		// Keys that YAML could read as booleans or numbers stay strings
+		let attributes = attributes(&tree, "ReplicatedStorage.Settings");
+
+		assert_eq!(attributes.get("no"), Some(&Variant::String(String::from("yes"))));
+		assert_eq!(attributes.get("1"), Some(&Variant::Float64(2.0)));
+
+		let id = tree.find_by_path("ReplicatedStorage.Value").unwrap();
+		let instance = tree.get_instance(id).unwrap();
+
+		assert_eq!(instance.class, "StringValue");
+		assert_eq!(
+			instance.properties.get(&Ustr::from("Archivable")),
+			Some(&Variant::Bool(false))
+		);
+	}
+
+	#[test]
+	fn round_trip() {
+		let dir = fixture("round-trip", "json");
+
+		{
+			let core = start(&dir);
+			let mut tree = core.tree();
+
+			let mut attributes = attributes(&tree, "ReplicatedStorage.Settings");
+			attributes.insert(String::from("Lives"), Variant::Float64(3.0));
+
+			let mut update = UpdatedSnapshot::new(tree.find_by_path("ReplicatedStorage.Settings").unwrap());
+			update.properties = Some(UstrMap::from_iter([(
+				Ustr::from("Attributes"),
+				Variant::Attributes(attributes),
+			)]));
+
+			write::apply_update(update, &mut tree, &Vfs::new(false)).unwrap();
+
+			// New data files follow `dataFormat`, which is JSON here
+			let snapshot = AddedSnapshot {
+				id: Ref::new(),
+				meta: Meta::new(),
+				parent: tree.find_by_path("ReplicatedStorage").unwrap(),
+				name: String::from("Other"),
+				class: Ustr::from("Configuration"),
+				properties: UstrMap::default(),
+				children: vec![],
+			};
+
+			write::apply_addition(snapshot, &mut tree, &Vfs::new(false)).unwrap();
+		}
+
+		// Existing data file keeps its format
+		assert!(!dir.join("src/Settings/.data.json").exists());
+		assert!(fs::read_to_string(dir.join("src/Settings/.data.yaml"))
+			.unwrap()
+			.contains("Lives"));
+		assert!(!dir.join("src/Other/.data.yaml").exists());
+		assert!(fs::read_dir(dir.join("src/Other"))
+			.unwrap()
+			.all(|entry| entry.unwrap().path().extension().unwrap() == "json"));
+
+		let core = start(&dir);
+		let tree = core.tree();
+		let attributes = attributes(&tree, "ReplicatedStorage.Settings");
+
+		assert_eq!(attributes.get("Lives"), Some(&Variant::Float64(3.0)));
+		assert_eq!(attributes.get("no"), Some(&Variant::String(String::from("yes"))));
+		assert_eq!(attributes.get("1"), Some(&Variant::Float64(2.0)));
+	}
+
+	#[test]
+	fn data_format() {
+		let dir = fixture("data-format", "yaml");
+		let core = start(&dir);
+		let mut tree = core.tree();
+
+		let snapshot = AddedSnapshot {
+			id: Ref::new(),
+			meta: Meta::new(),
+			parent: tree.find_by_path("ReplicatedStorage").unwrap(),
+			name: String::from("Other"),
+			class: Ustr::from("Configuration"),
+			properties: UstrMap::default(),
+			children: vec![],
+		};
+
+		write::apply_addition(snapshot, &mut tree, &Vfs::new(false)).unwrap();
+
+		assert!(!dir.join("src/Other/.data.json").exists());
+		assert!(!dir.join("src/Other/init.meta.json").exists());
+		assert_eq!(
+			fs::read_to_string(dir.join("src/Other/.data.yaml")).unwrap(),
+			"className: Configuration\n"
+		);
+	}
+
+	#[test]
+	fn ambiguous() {
+		let dir = fixture("ambiguous", "json");
+
+		fs::write(dir.join("src/Settings/.data.json"), r#"{ "className": "Folder" }"#).unwrap();
+
+		let err = Core::new(Project::load(&dir.join("default.project.json")).unwrap(), false)
+			.err()
+			.unwrap();
+		assert!(format!("{:#}", err).contains("ambiguous"));
+	}
+}
+
+mod names {
+	use crate::common::TempDir;
+	use argon::{
+		core::{
+			helpers::{names, syncback::verify_name},
+			meta::{Context, Meta},
+			Core,
+		},
+		project::Project,
+		vfs::{Vfs, VfsEvent},
+	};
+	use std::{
+		fs, thread,
+		time::{Duration, Instant},
+	};
+
+	const COMPOSED: &str = "Caf\u{e9}";
+	const DECOMPOSED: &str = "Cafe\u{301}";
+
+	fn load(name: &str, rules: &str) -> (Project, TempDir) {
+		let dir = TempDir::new(name);
+		let project_path = dir.join("default.project.json");
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+		fs::write(
+			&project_path,
+			format!(
+				r#"{{
+				"name": "Names",
+				"nameRules": {},
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{
+						"$path": "src"
+					}}
+				}}
+			}}"#,
+				rules
+			),
+		)
+		.unwrap();
+
+		(Project::load(&project_path).unwrap(), dir)
+	}
+
+	/// Returns the verified name and its original name, `None` if the name was rejected
+	fn verify(name: &str, context: &Context) -> Option<(String, Option<String>)> {
+		let mut name = name.to_owned();
+		let mut meta = Meta::new().with_context(context);
+
+		verify_name(&mut name, &mut meta).then_some((name, meta.original_name))
+	}
+
+	#[test]
+	fn ascii_names() {
+		let (project, _dir) = load("ascii", r#"{ "policy": "unicode", "replacement": "_" }"#);
+		let context = Meta::from_project(&project).context;
+
+		for name in ["Folder", "My Script", "data-1.2", "CONSOLE"] {
+			assert_eq!(verify(name, &context), Some((name.to_owned(), None)));
+			assert_eq!(verify(name, &Context::default()), Some((name.to_owned(), None)));
+		}
+
+		assert_eq!(verify("CON", &context), verify("CON", &Context::default()));
+	}
+
+	#[test]
+	fn normalization() {
+		assert!(names::names_match(COMPOSED, DECOMPOSED));
+		assert!(!names::names_match(COMPOSED, "Cafe"));
+		assert_eq!(names::normalize(DECOMPOSED), COMPOSED);
+
+		assert_eq!(
+			names::strip_prefix(&format!("{}.luau", DECOMPOSED), COMPOSED),
+			Some(".luau")
+		);
+		assert_eq!(names::strip_prefix("Other.luau", COMPOSED), None);
+
+		// Decomposed names are written composed, Studio keeps the original one
+		assert_eq!(
+			verify(DECOMPOSED, &Context::default()),
+			Some((COMPOSED.to_owned(), Some(DECOMPOSED.to_owned())))
+		);
+
+		// Legitimate unicode names are kept as they are
+		assert_eq!(
+			verify("日本語", &Context::default()),
+			Some((String::from("日本語"), None))
+		);
+	}
+
+	#[test]
+	fn invisible_characters() {
+		for name in ["Zero\u{200B}Width", "Zero\u{FEFF}Width", "\u{202E}ZeroWidth\u{202C}"] {
+			let (verified, original_name) = verify(name, &Context::default()).unwrap();
+
+			assert_eq!(
+				verified,
+				name.chars()
+					.filter(|char| !names::is_invisible(*char))
+					.collect::<String>()
+			);
+			assert_eq!(original_name.as_deref(), Some(name));
+		}
+
+		assert_eq!(verify("\u{200B}", &Context::default()).unwrap().0, "EmptyName");
+	}
+
+	#[test]
+	fn ascii_only_policy() {
+		let (project, _dir) = load("ascii-only", r#"{ "policy": "ascii-only" }"#);
+		let context = Meta::from_project(&project).context;
+
+		assert_eq!(
+			verify(DECOMPOSED, &context),
+			Some((String::from("Caf_"), Some(DECOMPOSED.to_owned())))
+		);
+		assert_eq!(verify("日本", &context).unwrap().0, "__");
+
+		let (project, _dir) = load(
+			"ascii-only-replacement",
+			r#"{ "policy": "ascii-only", "replacement": "-" }"#,
+		);
+		let context = Meta::from_project(&project).context;
+
+		assert_eq!(verify("日本", &context).unwrap().0, "--");
+		assert_eq!(verify("A/B", &context).unwrap().0, "A-B");
+	}
+
+	#[test]
+	fn unicode_policy() {
+		let (project, _dir) = load("unicode", r#"{ "replacement": "_" }"#);
+		let context = Meta::from_project(&project).context;
+
+		assert_eq!(verify("A/B", &context).unwrap().0, "A_B");
+		assert_eq!(verify("日本\u{200D}", &context).unwrap().0, "日本");
+
+		// Replacement characters have to be valid themselves
+		let (project, _dir) = load("unicode-invalid", r#"{ "replacement": "/" }"#);
+
+		assert_eq!(verify("A/B", &Meta::from_project(&project).context).unwrap().0, "AB");
+	}
+
+	#[test]
+	fn strict_policy() {
+		let (project, _dir) = load("strict", r#"{ "policy": "strict", "replacement": "_" }"#);
+		let context = Meta::from_project(&project).context;
+
+		// Invisible characters are rejected instead of stripped
+		assert_eq!(verify("Zero\u{200B}Width", &context), None);
+
+		// Windows characters are forbidden on every platform
+		assert_eq!(verify("A:B?", &context).unwrap().0, "A_B_");
+		assert_eq!(verify(DECOMPOSED, &context).unwrap().0, COMPOSED);
+	}
+
+	#[test]
+	fn decomposed_file_matches_instance() {
+		let (project, dir) = load("matching", "{}");
+		let composed = dir.join(format!("src/{}.luau", COMPOSED));
+
+		let vfs = Vfs::new_virtual();
+
+		vfs.create_dir(&dir.join("src")).unwrap();
+		vfs.write(&dir.join("default.project.json"), &fs::read(&project.path).unwrap())
+			.unwrap();
+		vfs.write(&composed, b"return 1").unwrap();
+
+		let core = Core::with_vfs(project, vfs).unwrap();
+		let id = core
+			.tree()
+			.find_by_path(&format!("ReplicatedStorage.{}", COMPOSED))
+			.unwrap();
+
+		// File system normalizes the name like HFS+ on macOS
+		let decomposed = dir.join(format!("src/{}.luau", DECOMPOSED));
+		let processed = core.processor().processed();
+
+		core.vfs().rename(&composed, &decomposed).unwrap();
+		core.processor().notify(VfsEvent::Write(dir.join("src")));
+
+		let start = Instant::now();
+
+		while core.processor().processed() == processed {
+			assert!(start.elapsed() < Duration::from_secs(5), "event was not processed");
+			thread::sleep(Duration::from_millis(10));
+		}
+
+		let tree = core.tree();
+
+		assert_eq!(tree.get_instance(id).unwrap().name, COMPOSED);
+		assert_eq!(tree.get_ids(&decomposed), Some(&vec![id]));
+	}
+}
+
+mod data_storage {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			meta::Meta,
+			processor::write,
+			snapshot::{AddedSnapshot, UpdatedSnapshot},
+			tree::Tree,
+		},
+		project::DataStorage,
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Attributes, Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::{fs, path::Path};
+
+	fn fixture(name: &str, storage: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			format!(
+				r#"{{
+				"name": "DataStorage",
+				"dataStorage": "{}",
+				"strictLoad": true,
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{
+						"$path": "src"
+					}}
+				}}
+			}}"#,
+				storage
+			),
+		)
+		.unwrap();
+
+		fs::write(dir.join("src/Existing.luau"), "return nil").unwrap();
+
+		dir
+	}
+
+	fn attributes(value: f64) -> Variant {
+		Variant::Attributes(Attributes::from_iter([(
+			String::from("Value"),
+			Variant::Float64(value),
+		)]))
+	}
+
+	/// Lists instances with their classes and properties in a stable order
+	fn dump(tree: &Tree) -> Vec<String> {
+		let mut lines = vec![];
+		let mut stack = vec![(tree.root_ref(), String::new())];
+
+		while let Some((id, path)) = stack.pop() {
+			let instance = tree.get_instance(id).unwrap();
+			let path = format!("{}/{}", path, instance.name);
+
+			let mut properties: Vec<_> = instance
+				.properties
+				.iter()
+				.map(|(property, value)| format!("{}={:?}", property, value))
+				.collect();
+			properties.sort();
+
+			lines.push(format!("{} {} {}", path, instance.class, properties.join(" ")));
+
+			for child in instance.children() {
+				stack.push((*child, path.clone()));
+			}
+		}
+
+		lines.sort();
+		lines
+	}
+
+	/// Adds, updates, renames and removes instances the way Studio does
+	fn syncback(dir: &Path) {
+		let core = start(dir);
+		let mut tree = core.tree();
+		let vfs = Vfs::new(false);
+		let parent = tree.find_by_path("ReplicatedStorage").unwrap();
+
+		for (name, class, properties) in [
+			(
+				"Settings",
+				"Configuration",
+				UstrMap::from_iter([(Ustr::from("Attributes"), attributes(1.0))]),
+			),
+			(
+				"Module",
+				"ModuleScript",
+				UstrMap::from_iter([
+					(Ustr::from("Source"), Variant::String(String::from("return 1"))),
+					(Ustr::from("Attributes"), attributes(2.0)),
+				]),
+			),
+			(
+				"Removed",
+				"ModuleScript",
+				UstrMap::from_iter([
+					(Ustr::from("Source"), Variant::String(String::from("return 2"))),
+					(Ustr::from("Archivable"), Variant::Bool(false)),
+				]),
+			),
+		] {
+			let snapshot = AddedSnapshot {
+				id: Ref::new(),
+				meta: Meta::new(),
+				parent,
+				name: String::from(name),
+				class: Ustr::from(class),
+				properties,
+				children: vec![],
+			};
+
+			write::apply_addition(snapshot, &mut tree, &vfs).unwrap();
+		}
+
+		let mut update = UpdatedSnapshot::new(tree.find_by_path("ReplicatedStorage.Module").unwrap());
+		update.properties = Some(UstrMap::from_iter([
+			(Ustr::from("Source"), Variant::String(String::from("return 3"))),
+			(Ustr::from("Attributes"), attributes(3.0)),
+		]));
+
+		write::apply_update(update, &mut tree, &vfs).unwrap();
+
+		let mut update = UpdatedSnapshot::new(tree.find_by_path("ReplicatedStorage.Settings").unwrap());
+		update.name = Some(String::from("Options"));
+
+		write::apply_update(update, &mut tree, &vfs).unwrap();
+
+		let id = tree.find_by_path("ReplicatedStorage.Removed").unwrap();
+		write::apply_removal(id, &mut tree, &vfs).unwrap();
+	}
+
+	#[test]
+	fn storages_are_equivalent() {
+		let files = fixture("files", "files");
+		let index = fixture("index", "index");
+
+		syncback(&files);
+		syncback(&index);
+
+		assert!(files.join("src/Module.meta.json").exists());
+		assert_eq!(dump(&start(&files).tree()), dump(&start(&index).tree()));
+
+		// Data index replaces every data file
+		let index_path = index.join("src/.argon-data.json");
+		let contents = fs::read_to_string(&index_path).unwrap();
+
+		assert!(!index.join("src/Options/init.meta.json").exists());
+		assert!(!index.join("src/Module.meta.json").exists());
+		assert!(contents.contains("\"Options\""));
+		assert!(contents.contains("\"Module\""));
+		assert!(!contents.contains("\"Settings\""));
+		assert!(!contents.contains("\"Removed\""));
+
+		// Removing the last entry removes the index itself
+		{
+			let core = start(&index);
+			let mut tree = core.tree();
+			let vfs = Vfs::new(false);
+
+			for name in ["Options", "Module"] {
+				let id = tree.find_by_path(&format!("ReplicatedStorage.{}", name)).unwrap();
+				write::apply_removal(id, &mut tree, &vfs).unwrap();
+			}
+		}
+
+		assert!(!index_path.exists());
+	}
+
+	#[test]
+	fn data_files_take_precedence() {
+		let dir = fixture("precedence", "index");
+
+		fs::write(
+			dir.join("src/.argon-data.json"),
+			r#"{ "Existing": { "properties": { "Archivable": false } } }"#,
+		)
+		.unwrap();
+		fs::write(
+			dir.join("src/Existing.data.json"),
+			r#"{ "properties": { "Archivable": true } }"#,
+		)
+		.unwrap();
+
+		let core = start(&dir);
+		let tree = core.tree();
+		let id = tree.find_by_path("ReplicatedStorage.Existing").unwrap();
+
+		assert_eq!(
+			tree.get_instance(id).unwrap().properties.get(&Ustr::from("Archivable")),
+			Some(&Variant::Bool(true))
+		);
+
+		// Index entries are still used for instances without data files
+		fs::remove_file(dir.join("src/Existing.data.json")).unwrap();
+
+		let core = start(&dir);
+		let tree = core.tree();
+		let id = tree.find_by_path("ReplicatedStorage.Existing").unwrap();
+
+		assert_eq!(
+			tree.get_instance(id).unwrap().properties.get(&Ustr::from("Archivable")),
+			Some(&Variant::Bool(false))
+		);
+	}
+
+	#[test]
+	fn migration_round_trip() {
+		let dir = fixture("migration", "files");
+
+		syncback(&dir);
+
+		let before = dump(&start(&dir).tree());
+
+		let migration = start(&dir).migrate_data_storage(DataStorage::Index).unwrap();
+
+		assert_eq!(migration.migrated.len(), 2);
+		assert!(migration.skipped.is_empty());
+		assert!(dir.join("src/.argon-data.json").exists());
+		assert!(!dir.join("src/Options/init.meta.json").exists());
+		assert!(!dir.join("src/Module.meta.json").exists());
+		assert_eq!(dump(&start(&dir).tree()), before);
+
+		let migration = start(&dir).migrate_data_storage(DataStorage::Files).unwrap();
+
+		assert_eq!(migration.migrated.len(), 2);
+		assert!(!dir.join("src/.argon-data.json").exists());
+		assert!(dir.join("src/Options/init.meta.json").exists());
+		assert!(dir.join("src/Module.meta.json").exists());
+		assert_eq!(dump(&start(&dir).tree()), before);
+	}
+}
+
+mod init_conflicts {
+	use crate::common::{start, TempDir, WrappedBackend};
+	use argon::{
+		core::{
+			meta::{ReadOnlyReason, ReadOnlyRejection},
+			processor::{read, write},
+			snapshot::UpdatedSnapshot,
+			tree::Tree,
+			Core,
+		},
+		project::Project,
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{types::Variant, Ustr, UstrMap};
+	use std::fs;
+
+	/// Creates a project with `src/Conflict` directory holding the given files,
+	/// contents of every script is a comment with its own file name
+	fn fixture(name: &str, files: &[&str]) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src/Conflict")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "InitConflicts",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"$path": "src"
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		for file in files {
+			fs::write(dir.join("src/Conflict").join(file), format!("-- {}", file)).unwrap();
+		}
+
+		dir
+	}
+
+	/// Class and source of the conflicting directory
+	fn conflict(tree: &Tree) -> (String, Option<Variant>) {
+		let id = tree.find_by_path("ReplicatedStorage.Conflict").unwrap();
+		let instance = tree.get_instance(id).unwrap();
+
+		(
+			instance.class.to_string(),
+			instance.properties.get(&Ustr::from("Source")).cloned(),
+		)
+	}
+
+	fn source(file: &str) -> Option<Variant> {
+		Some(Variant::String(format!("-- {}", file)))
+	}
+
+	/// Lists instances with their classes and properties in a stable order
+	fn dump(tree: &Tree) -> Vec<String> {
+		let mut lines = vec![];
+		let mut stack = vec![(tree.root_ref(), String::new())];
+
+		while let Some((id, path)) = stack.pop() {
+			let instance = tree.get_instance(id).unwrap();
+			let path = format!("{}/{}", path, instance.name);
+
+			let mut properties: Vec<_> = instance
+				.properties
+				.iter()
+				.map(|(property, value)| format!("{}={:?}", property, value))
+				.collect();
+			properties.sort();
+
+			lines.push(format!("{} {} {}", path, instance.class, properties.join(" ")));
+
+			for child in instance.children() {
+				stack.push((*child, path.clone()));
+			}
+		}
+
+		lines.sort();
+		lines
+	}
+
+	#[test]
+	fn precedence() {
+		// From the most to the least preferred with default sync rules
+		let order = [
+			("Script", ".src.server.luau"),
+			("Script", "init.server.luau"),
+			("LocalScript", "init.client.luau"),
+			("ModuleScript", "init.luau"),
+			("Script", "init.server.lua"),
+			("ModuleScript", "init.lua"),
+		];
+
+		let dir = fixture("precedence", &order.map(|(_, file)| file));
+
+		for (index, (class, file)) in order.iter().enumerate() {
+			let core = start(&dir);
+			let tree = core.tree();
+			let id = tree.find_by_path("ReplicatedStorage.Conflict").unwrap();
+			let meta = tree.get_meta(id).unwrap();
+
+			assert_eq!(conflict(&tree), (class.to_string(), source(file)));
+
+			// Ignored files never show up as children
+			assert!(tree.get_instance(id).unwrap().children().is_empty());
+
+			match &meta.init_conflict {
+				Some(conflict) => {
+					assert_eq!(conflict.used, dir.join("src/Conflict").join(file));
+					assert_eq!(conflict.ignored.len(), order.len() - index - 1);
+				}
+				None => assert_eq!(index, order.len() - 1),
+			}
+
+			drop(tree);
+			fs::remove_file(dir.join("src/Conflict").join(file)).unwrap();
+		}
+	}
+
+	#[test]
+	fn listing_order_does_not_matter() {
+		let dir = fixture(
+			"listing",
+			&["init.luau", "init.server.luau", "init.client.luau", "Child.luau"],
+		);
+
+		let project = || Project::load(&dir.join("default.project.json")).unwrap();
+
+		let forward = Core::with_vfs(project(), Vfs::new(false)).unwrap();
+		let reversed = Core::with_vfs(
+			project(),
+			Vfs::from_backend(WrappedBackend::new().on_read_dir(|_, paths| {
+				paths.sort();
+				paths.reverse();
+			})),
+		)
+		.unwrap();
+
+		assert_eq!(dump(&forward.tree()), dump(&reversed.tree()));
+		assert_eq!(
+			conflict(&reversed.tree()),
+			(String::from("Script"), source("init.server.luau"))
+		);
+	}
+
+	#[test]
+	fn read_only_until_resolved() {
+		let dir = fixture("resolved", &["init.luau", "init.server.luau", "Child.luau"]);
+		let conflict_dir = dir.join("src/Conflict");
+
+		let core = start(&dir);
+		let mut tree = core.tree();
+		let vfs = core.vfs();
+
+		let id = tree.find_by_path("ReplicatedStorage.Conflict").unwrap();
+		let child = tree.find_by_path("ReplicatedStorage.Conflict.Child").unwrap();
+		let meta = tree.get_meta(id).unwrap();
+
+		assert_eq!(meta.read_only.as_ref().unwrap().reason, ReadOnlyReason::InitConflict);
+		assert_eq!(
+			meta.init_conflict.as_ref().unwrap().ignored,
+			[conflict_dir.join("init.luau")]
+		);
+
+		let mut update = UpdatedSnapshot::new(child);
+		update.properties = Some(UstrMap::from_iter([(
+			Ustr::from("Source"),
+			Variant::String(String::from("return 1")),
+		)]));
+
+		let rejected = write::apply_update(update, &mut tree, &vfs)
+			.unwrap_err()
+			.downcast::<ReadOnlyRejection>()
+			.unwrap();
+
+		assert_eq!(rejected.reason, ReadOnlyReason::InitConflict);
+		assert_eq!(rejected.path, conflict_dir);
+		assert_eq!(
+			fs::read_to_string(conflict_dir.join("Child.luau")).unwrap(),
+			"-- Child.luau"
+		);
+
+		// Removing the extra file clears the conflict
+		fs::remove_file(conflict_dir.join("init.luau")).unwrap();
+		read::process_changes(id, &mut tree, &vfs);
+
+		let id = tree.find_by_path("ReplicatedStorage.Conflict").unwrap();
+		let meta = tree.get_meta(id).unwrap();
+
+		assert_eq!(meta.init_conflict, None);
+		assert_eq!(meta.read_only, None);
+	}
+
+	#[test]
+	fn data_class_conflict() {
+		let dir = fixture("data", &["init.luau"]);
+
+		fs::write(dir.join("src/Conflict/init.meta.json"), r#"{ "className": "Folder" }"#).unwrap();
+
+		let core = start(&dir);
+		let tree = core.tree();
+		let id = tree.find_by_path("ReplicatedStorage.Conflict").unwrap();
+		let meta = tree.get_meta(id).unwrap();
+
+		// Script wins over the class of the data file
+		assert_eq!(conflict(&tree), (String::from("ModuleScript"), source("init.luau")));
+		assert_eq!(
+			meta.init_conflict.as_ref().unwrap().ignored,
+			[dir.join("src/Conflict/init.meta.json")]
+		);
+		assert_eq!(meta.read_only.as_ref().unwrap().reason, ReadOnlyReason::InitConflict);
+
+		drop(tree);
+		fs::write(
+			dir.join("src/Conflict/init.meta.json"),
+			r#"{ "className": "ModuleScript" }"#,
+		)
+		.unwrap();
+
+		let core = start(&dir);
+		let tree = core.tree();
+		let id = tree.find_by_path("ReplicatedStorage.Conflict").unwrap();
+
+		assert_eq!(tree.get_meta(id).unwrap().init_conflict, None);
+	}
+}
+
+mod glob {
+	use crate::common::TempDir;
+	use argon::{
+		core::Core,
+		glob::Glob,
+		project::{self, Project},
+	};
+	use std::{
+		fs,
+		path::{Path, PathBuf},
+	};
+
+	/// Creates `dir` inside a fresh temporary directory with `game.project.json` in it
+	fn workspace(test: &str, dir: &str) -> (TempDir, PathBuf) {
+		let root = TempDir::new(test);
+		let workspace = root.join(dir);
+
+		fs::create_dir_all(workspace.join("src")).unwrap();
+
+		fs::write(
+			workspace.join("game.project.json"),
+			r#"{
+			"name": "Glob",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"$path": "src"
+				}
+			},
+			"ignoreGlobs": ["**/*.spec.luau", "src/Ignored/**"]
+		}"#,
+		)
+		.unwrap();
+
+		(root, workspace)
+	}
+
+	#[test]
+	fn escape() {
+		assert_eq!(Glob::escape("Projects [2024]"), "Projects [[]2024[]]");
+		assert_eq!(Glob::escape("What?*"), "What[?][*]");
+
+		let glob = Glob::in_dir(Path::new("/home/Projects [2024]"), "*.project.json").unwrap();
+
+		assert!(glob.matches_path(Path::new("/home/Projects [2024]/default.project.json")));
+		assert!(!glob.matches_path(Path::new("/home/Projects 2/default.project.json")));
+	}
+
+	#[test]
+	fn separators() {
+		let glob = Glob::new("src\\Ignored\\**").unwrap();
+
+		// Patterns are stored the same way on every platform
+		assert_eq!(glob.as_str(), "src/Ignored/**");
+		assert!(glob.matches_path(Path::new("src/Ignored/Module.luau")));
+		assert!(glob.matches_path_with_dir(Path::new("src/Ignored")));
+		assert!(!glob.matches_path_with_dir(Path::new("src/Other")));
+	}
+
+	#[test]
+	fn resolve_brackets_and_spaces() {
+		let (root, workspace) = workspace("brackets", "Projects [2024] {old}");
+
+		// Sibling that an unescaped `[2024]` would match
+		fs::create_dir_all(root.join("Projects 2 {old}")).unwrap();
+		fs::write(root.join("Projects 2 {old}/other.project.json"), "{}").unwrap();
+
+		assert_eq!(
+			project::resolve(workspace.clone()).unwrap(),
+			workspace.join("game.project.json")
+		);
+	}
+
+	#[test]
+	fn resolve_unicode() {
+		let (_root, workspace) = workspace("unicode", "プロジェクト Ünïcode");
+
+		assert_eq!(
+			project::resolve(workspace.clone()).unwrap(),
+			workspace.join("game.project.json")
+		);
+	}
+
+	#[test]
+	fn ignore_globs() {
+		for (test, dir) in [("ignore-brackets", "Game [*] ?"), ("ignore-unicode", "ゲーム ü")] {
+			let (_root, workspace) = workspace(test, dir);
+
+			fs::create_dir_all(workspace.join("src/Ignored")).unwrap();
+			fs::write(workspace.join("src/Module.luau"), "return 1").unwrap();
+			fs::write(workspace.join("src/Module.spec.luau"), "return 2").unwrap();
+			fs::write(workspace.join("src/Ignored/Child.luau"), "return 3").unwrap();
+
+			let project = Project::load(&project::resolve(workspace.clone()).unwrap()).unwrap();
+			let core = Core::new(project, false).unwrap();
+			let tree = core.tree();
+
+			assert!(tree.find_by_path("ReplicatedStorage.Module").is_some());
+			assert!(tree.find_by_path("ReplicatedStorage.Module.spec").is_none());
+			assert!(tree.find_by_path("ReplicatedStorage.Ignored.Child").is_none());
+		}
+	}
+}
+
+mod asset_manifests {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			meta::{Meta, ReadOnlyReason, ReadOnlyRejection, SourceEntry},
+			processor::{read, write},
+			snapshot::{AddedSnapshot, UpdatedSnapshot},
+			tree::Tree,
+			Core,
+		},
+		project::Project,
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use serde_json::{json, Value};
+	use std::{fs, path::Path};
+
+	const MANIFEST: &str = r#"[
+	{ "name": "Click", "class": "Sound", "properties": { "Volume": 0.5 } },
+	{ "name": "Music", "className": "Sound", "properties": { "Looped": true } },
+	{
+		"name": "Ambience",
+		"class": "SoundGroup",
+		"allowChildren": true,
+		"children": [{ "name": "Wind", "class": "Sound" }]
+	}
+]"#;
+
+	fn fixture(name: &str, manifest: &str, strict: bool) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src/Sounds")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			format!(
+				r#"{{
+				"name": "AssetManifests",
+				"strictLoad": {},
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{
+						"$path": "src"
+					}}
+				}}
+			}}"#,
+				strict
+			),
+		)
+		.unwrap();
+
+		fs::write(dir.join("src/Sounds/sfx.assets.json"), manifest).unwrap();
+		fs::write(dir.join("src/Sounds/Player.luau"), "return nil").unwrap();
+
+		dir
+	}
+
+	fn manifest(dir: &Path) -> Value {
+		serde_json::from_str(&fs::read_to_string(dir.join("src/Sounds/sfx.assets.json")).unwrap()).unwrap()
+	}
+
+	fn children(tree: &Tree, path: &str) -> Vec<String> {
+		let id = tree.find_by_path(path).unwrap();
+		let mut names: Vec<String> = tree
+			.get_instance(id)
+			.unwrap()
+			.children()
+			.iter()
+			.map(|child| tree.get_instance(*child).unwrap().name.clone())
+			.collect();
+
+		names.sort();
+		names
+	}
+
+	fn property(tree: &Tree, path: &str, property: &str) -> Option<Variant> {
+		let id = tree.find_by_path(path)?;
+		tree.get_instance(id)?.properties.get(&Ustr::from(property)).cloned()
+	}
+
+	#[test]
+	fn expansion() {
+		let dir = fixture("expansion", MANIFEST, true);
+		let core = start(&dir);
+		let tree = core.tree();
+
+		assert_eq!(
+			children(&tree, "ReplicatedStorage.Sounds"),
+			vec!["Ambience", "Click", "Music", "Player"]
+		);
+		assert_eq!(children(&tree, "ReplicatedStorage.Sounds.Ambience"), vec!["Wind"]);
+
+		let click = tree.find_by_path("ReplicatedStorage.Sounds.Click").unwrap();
+		assert_eq!(tree.get_instance(click).unwrap().class, "Sound");
+
+		assert_eq!(
+			property(&tree, "ReplicatedStorage.Sounds.Click", "Volume"),
+			Some(Variant::Float32(0.5))
+		);
+		assert_eq!(
+			property(&tree, "ReplicatedStorage.Sounds.Music", "Looped"),
+			Some(Variant::Bool(true))
+		);
+
+		// Every instance is sourced to its own entry of the manifest
+		let wind = tree.find_by_path("ReplicatedStorage.Sounds.Ambience.Wind").unwrap();
+
+		assert_eq!(
+			tree.get_meta(wind).unwrap().source.get_manifest(),
+			Some(&SourceEntry::Manifest(
+				dir.join("src/Sounds/sfx.assets.json"),
+				vec![String::from("Ambience"), String::from("Wind")]
+			))
+		);
+	}
+
+	#[test]
+	fn pruning() {
+		let dir = fixture("pruning", MANIFEST, true);
+		let core = start(&dir);
+		let vfs = Vfs::new(false);
+		let mut tree = core.tree();
+
+		let click = tree.find_by_path("ReplicatedStorage.Sounds.Click").unwrap();
+		let music = tree.find_by_path("ReplicatedStorage.Sounds.Music").unwrap();
+
+		fs::write(
+			dir.join("src/Sounds/sfx.assets.json"),
+			r#"[
+			{ "name": "Click", "class": "Sound", "properties": { "Volume": 0.25 } },
+			{ "name": "Alarm", "class": "Sound" }
+		]"#,
+		)
+		.unwrap();
+
+		let path = dir.join("src/Sounds/sfx.assets.json");
+		let id = tree.get_ids(&path).unwrap()[0];
+		let changes = read::process_changes(id, &mut tree, &vfs).unwrap();
+
+		assert_eq!(
+			children(&tree, "ReplicatedStorage.Sounds"),
+			vec!["Alarm", "Click", "Player"]
+		);
+
+		// Instances still listed keep their identity
+		assert_eq!(tree.find_by_path("ReplicatedStorage.Sounds.Click"), Some(click));
+		assert_eq!(
+			property(&tree, "ReplicatedStorage.Sounds.Click", "Volume"),
+			Some(Variant::Float32(0.25))
+		);
+
+		assert!(changes.removals.contains(&music));
+		assert_eq!(changes.additions.len(), 1);
+	}
+
+	#[test]
+	fn syncback() {
+		let dir = fixture("syncback", MANIFEST, true);
+		let core = start(&dir);
+		let vfs = Vfs::new(false);
+		let mut tree = core.tree();
+
+		let click = tree.find_by_path("ReplicatedStorage.Sounds.Click").unwrap();
+		let ambience = tree.find_by_path("ReplicatedStorage.Sounds.Ambience").unwrap();
+		let wind = tree.find_by_path("ReplicatedStorage.Sounds.Ambience.Wind").unwrap();
+		let music = tree.find_by_path("ReplicatedStorage.Sounds.Music").unwrap();
+
+		let mut update = UpdatedSnapshot::new(click);
+		update.properties = Some(UstrMap::from_iter([
+			(Ustr::from("Volume"), Variant::Float32(0.75)),
+			(Ustr::from("Looped"), Variant::Bool(true)),
+		]));
+
+		write::apply_update(update, &mut tree, &vfs).unwrap();
+
+		assert_eq!(
+			manifest(&dir)[0],
+			json!({ "name": "Click", "class": "Sound", "properties": { "Looped": true, "Volume": 0.75 } })
+		);
+
+		// Renaming the entry moves keys of the entries nested in it
+		let mut update = UpdatedSnapshot::new(ambience);
+		update.name = Some(String::from("Weather"));
+
+		write::apply_update(update, &mut tree, &vfs).unwrap();
+
+		assert_eq!(manifest(&dir)[2]["name"], "Weather");
+		assert_eq!(
+			tree.get_meta(wind).unwrap().source.get_manifest(),
+			Some(&SourceEntry::Manifest(
+				dir.join("src/Sounds/sfx.assets.json"),
+				vec![String::from("Weather"), String::from("Wind")]
+			))
+		);
+
+		// Names of entries have to stay unique
+		let mut update = UpdatedSnapshot::new(music);
+		update.name = Some(String::from("Click"));
+
+		let err = write::apply_update(update, &mut tree, &vfs).unwrap_err();
+		assert!(format!("{:#}", err).contains("already has an entry named"));
+
+		write::apply_removal(wind, &mut tree, &vfs).unwrap();
+		write::apply_removal(music, &mut tree, &vfs).unwrap();
+
+		assert_eq!(
+			manifest(&dir),
+			json!([
+				{ "name": "Click", "class": "Sound", "properties": { "Looped": true, "Volume": 0.75 } },
+				{ "name": "Weather", "class": "SoundGroup", "allowChildren": true }
+			])
+		);
+
+		// Siblings written to files are not affected
+		assert!(dir.join("src/Sounds/Player.luau").exists());
+
+		// Tree read from the manifest matches the one after syncback
+		drop(tree);
+
+		let core = start(&dir);
+		let tree = core.tree();
+
+		assert_eq!(
+			children(&tree, "ReplicatedStorage.Sounds"),
+			vec!["Click", "Player", "Weather"]
+		);
+		assert_eq!(
+			property(&tree, "ReplicatedStorage.Sounds.Click", "Volume"),
+			Some(Variant::Float32(0.75))
+		);
+	}
+
+	fn added(parent: Ref, name: &str) -> AddedSnapshot {
+		AddedSnapshot {
+			id: Ref::new(),
+			meta: Meta::new(),
+			parent,
+			name: String::from(name),
+			class: Ustr::from("Sound"),
+			properties: UstrMap::from_iter([(Ustr::from("Volume"), Variant::Float32(0.1))]),
+			children: vec![],
+		}
+	}
+
+	#[test]
+	fn children_opt_in() {
+		let dir = fixture("children", MANIFEST, true);
+		let core = start(&dir);
+		let vfs = Vfs::new(false);
+		let mut tree = core.tree();
+
+		let click = tree.find_by_path("ReplicatedStorage.Sounds.Click").unwrap();
+		let wind = tree.find_by_path("ReplicatedStorage.Sounds.Ambience.Wind").unwrap();
+		let before = manifest(&dir);
+
+		let rejection = write::apply_addition(added(click, "Echo"), &mut tree, &vfs)
+			.unwrap_err()
+			.downcast::<ReadOnlyRejection>()
+			.unwrap();
+
+		assert_eq!(rejection.reason, ReadOnlyReason::AssetManifest);
+		assert_eq!(rejection.path, dir.join("src/Sounds/sfx.assets.json"));
+		assert!(rejection.message.contains("allowChildren"));
+		assert_eq!(manifest(&dir), before);
+
+		// Entries nested in one that allows children allow them too
+		let gust = added(wind, "Gust");
+		let id = gust.id;
+
+		write::apply_addition(gust, &mut tree, &vfs).unwrap();
+
+		assert_eq!(
+			manifest(&dir)[2]["children"][0]["children"],
+			json!([{ "name": "Gust", "className": "Sound", "properties": { "Volume": 0.1 } }])
+		);
+		assert_eq!(
+			tree.get_meta(id).unwrap().source.get_manifest(),
+			Some(&SourceEntry::Manifest(
+				dir.join("src/Sounds/sfx.assets.json"),
+				vec![String::from("Ambience"), String::from("Wind"), String::from("Gust")]
+			))
+		);
+	}
+
+	#[test]
+	fn duplicate_names() {
+		let manifest = r#"[
+		{ "name": "Click", "class": "Sound" },
+		{ "name": "Click", "class": "Sound" }
+	]"#;
+
+		let dir = fixture("duplicates-strict", manifest, true);
+		let err = Core::new(Project::load(&dir.join("default.project.json")).unwrap(), false)
+			.err()
+			.unwrap();
+
+		let err = format!("{:#}", err);
+
+		assert!(err.contains("Click"));
+		assert!(err.contains("listed more than once"));
+
+		// Without `strictLoad` only the manifest fails to load
+		let dir = fixture("duplicates", manifest, false);
+		let core = start(&dir);
+		let tree = core.tree();
+
+		assert_eq!(children(&tree, "ReplicatedStorage.Sounds"), vec!["Player", "sfx"]);
+
+		let placeholder = tree.find_by_path("ReplicatedStorage.Sounds.sfx").unwrap();
+		let load_error = tree.get_meta(placeholder).unwrap().load_error.as_ref().unwrap();
+
+		assert_eq!(load_error.path, dir.join("src/Sounds/sfx.assets.json"));
+		assert!(load_error.error.contains("listed more than once"));
+	}
+}
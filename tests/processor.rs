@@ -0,0 +1,7067 @@
+mod common;
+
+mod history {
+	use crate::common::{start, TempDir};
+	use actix_web::{
+		http::{header, StatusCode},
+		test::{self, TestRequest},
+		web::Data,
+		App,
+	};
+	use argon::{
+		config::Config,
+		core::{changes::Changes, lifecycle::CoreEvent, processor::WriteRequest, snapshot::UpdatedSnapshot, Core},
+		history::{self, HistoryEntry, Operation},
+		server::{
+			limits::{Limiter, Limits},
+			Server,
+		},
+		vfs::VfsEvent,
+	};
+	use rbx_dom_weak::{types::Variant, Ustr, UstrMap};
+	use serde_json::json;
+	use std::{fs, path::Path, process, sync::Arc, time::Duration};
+	use tokio::{sync::broadcast::Receiver, time};
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{ "name": "History", "tree": { "$className": "DataModel", "ReplicatedStorage": { "$path": "src" } } }"#,
+		)
+		.unwrap();
+
+		fs::write(dir.join("src/Main.luau"), "return 0").unwrap();
+		fs::write(dir.join("src/Other.luau"), "return 0").unwrap();
+
+		dir
+	}
+
+	/// Syncs back new source of `Main` as the client and waits until it is written
+	async fn syncback(core: &Core, receiver: &mut Receiver<CoreEvent>, source: &str) {
+		let id = core.tree().find_by_path("ReplicatedStorage.Main").unwrap();
+
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from("Source"), Variant::String(source.to_owned()));
+
+		let mut update = UpdatedSnapshot::new(id);
+		update.properties = Some(properties);
+
+		let mut changes = Changes::new();
+		changes.updates.push(update);
+
+		core.processor().write(WriteRequest { changes, client_id: 1 });
+
+		loop {
+			let event = time::timeout(Duration::from_secs(5), receiver.recv())
+				.await
+				.expect("syncback did not finish")
+				.unwrap();
+
+			if let CoreEvent::SyncbackFinished { error, .. } = event {
+				assert_eq!(error, None);
+				break;
+			}
+		}
+	}
+
+	/// Edits the file outside of Argon and waits until the watcher change is recorded
+	async fn edit(core: &Core, dir: &Path, file: &str, source: &str) {
+		let path = dir.join("src").join(file);
+		let recorded = history::read(dir).unwrap().len();
+
+		fs::write(&path, source).unwrap();
+		core.processor().notify(VfsEvent::Write(path));
+
+		for _ in 0..100 {
+			if history::read(dir).unwrap().len() > recorded {
+				return;
+			}
+
+			time::sleep(Duration::from_millis(50)).await;
+		}
+
+		panic!("external change was not recorded");
+	}
+
+	#[actix_web::test]
+	async fn interleaving() {
+		let dir = fixture("interleaving");
+		let core = Arc::new(start(&dir));
+		let mut receiver = core.subscribe();
+
+		let service = test::init_service(
+			App::new()
+				.app_data(Data::new(Arc::new(Limiter::new(Limits::new(&Config::default())))))
+				.service(Server::scope("", core.clone())),
+		)
+		.await;
+
+		let response = test::call_service(
+			&service,
+			TestRequest::post()
+				.uri("/subscribe")
+				.insert_header((header::CONTENT_TYPE, "application/msgpack"))
+				.set_payload(
+					rmp_serde::to_vec_named(&json!({ "clientId": 1, "name": "Studio", "gameId": 10, "placeId": 20 }))
+						.unwrap(),
+				)
+				.to_request(),
+		)
+		.await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		syncback(&core, &mut receiver, "return 1").await;
+		edit(&core, &dir, "Main.luau", "return 2").await;
+		syncback(&core, &mut receiver, "return 3").await;
+		edit(&core, &dir, "Other.luau", "return 4").await;
+
+		let main = dir.join("src/Main.luau");
+		let entries = history::read(&dir).unwrap();
+
+		let operations: Vec<&Operation> = entries.iter().map(|entry| &entry.operation).collect();
+		assert_eq!(
+			operations,
+			vec![
+				&Operation::Update,
+				&Operation::External,
+				&Operation::Update,
+				&Operation::External
+			]
+		);
+
+		assert!(entries.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+		assert!(entries.iter().all(|entry| entry.session == process::id()));
+
+		// Syncback is attributed to the client from the handshake, external edits to nobody
+		for entry in entries.iter().filter(|entry| !entry.is_external()) {
+			assert_eq!(entry.client_id, Some(1));
+			assert_eq!(entry.client_name.as_deref(), Some("Studio"));
+			assert_eq!(entry.game_id, Some(10));
+			assert_eq!(entry.place_id, Some(20));
+			assert_eq!(entry.files, vec![main.clone()]);
+		}
+
+		for entry in entries.iter().filter(|entry| entry.is_external()) {
+			assert_eq!(entry.client_id, None);
+			assert_eq!(entry.client_name, None);
+		}
+
+		assert_eq!(entries[1].files, vec![main.clone()]);
+		assert_eq!(entries[1].instance.as_deref(), entries[0].instance.as_deref());
+
+		// Blame of the file shows both sources of its changes in order but not the other file
+		let blamed = history::blame(&dir, &main, &main.to_string_lossy()).unwrap();
+
+		assert_eq!(blamed.len(), 3);
+		assert_eq!(
+			blamed.iter().map(HistoryEntry::is_external).collect::<Vec<_>>(),
+			vec![false, true, false]
+		);
+
+		// Blame of the instance path finds the same entries
+		let instance = entries[0].instance.clone().unwrap();
+		let blamed = history::blame(&dir, &dir.join("missing"), &instance).unwrap();
+
+		assert_eq!(blamed.len(), 3);
+	}
+}
+
+mod memory {
+	use crate::common::{TempDir, WrappedBackend};
+	use argon::{
+		core::{helpers::memory, meta::Context, processor::read, tree::Tree},
+		middleware::new_snapshot,
+		vfs::Vfs,
+		Properties,
+	};
+	use rbx_dom_weak::{types::Variant, Ustr};
+	use std::{
+		collections::BTreeMap,
+		fs,
+		path::Path,
+		sync::{Arc, Mutex},
+	};
+
+	fn source(index: usize, revision: usize) -> String {
+		format!("-- Script {} revision {}\n", index, revision) + &"print('Hello, world!')\n".repeat(500)
+	}
+
+	fn workspace(name: &str, scripts: usize) -> TempDir {
+		let dir = TempDir::new(name);
+
+		for index in 0..scripts {
+			fs::write(dir.join(format!("Script{}.luau", index)), source(index, 0)).unwrap();
+		}
+
+		fs::write(dir.join("Small.luau"), "return {}").unwrap();
+
+		dir
+	}
+
+	fn tree(dir: &Path, vfs: &Vfs, low_memory: bool) -> Tree {
+		let snapshot = new_snapshot(dir, &Context::default(), vfs).unwrap().unwrap();
+		let mut tree = Tree::new(snapshot);
+
+		if low_memory {
+			tree.evict_all();
+		}
+
+		tree
+	}
+
+	/// Materialized properties of every instance, keyed by its full name
+	fn state(tree: &Tree, vfs: &Vfs) -> BTreeMap<String, Properties> {
+		tree.meta_map()
+			.keys()
+			.map(|id| {
+				(
+					tree.get_full_name(*id).unwrap(),
+					memory::materialize(*id, tree, vfs).unwrap(),
+				)
+			})
+			.collect()
+	}
+
+	/// Number of bytes held by string properties in the tree
+	fn resident(tree: &Tree) -> usize {
+		tree.meta_map()
+			.keys()
+			.flat_map(|id| tree.get_instance(*id).unwrap().properties.values())
+			.map(|value| match value {
+				Variant::String(string) => string.len(),
+				_ => 0,
+			})
+			.sum()
+	}
+
+	#[test]
+	fn matches_normal_mode() {
+		let dir = workspace("state", 10);
+		let vfs = Vfs::new(false);
+
+		let mut normal = tree(&dir, &vfs, false);
+		let mut low = tree(&dir, &vfs, true);
+
+		assert_eq!(low.evicted_ids().count(), 10);
+		assert_eq!(state(&normal, &vfs), state(&low, &vfs));
+
+		// Unchanged file should not produce any updates
+		let path = dir.join("Script4.luau");
+		let id = low.get_ids(&path).unwrap()[0];
+
+		assert!(read::process_changes(id, &mut low, &vfs).unwrap().is_empty());
+
+		// Property update forces re-materialization of the evicted value
+		let path = dir.join("Script3.luau");
+		fs::write(&path, source(3, 1)).unwrap();
+
+		for tree in [&mut normal, &mut low] {
+			let id = tree.get_ids(&path).unwrap()[0];
+			let changes = read::process_changes(id, tree, &vfs).unwrap();
+
+			let properties = changes.updates[0].properties.as_ref().unwrap();
+
+			assert_eq!(changes.updates.len(), 1);
+			assert_eq!(
+				properties.get(&Ustr::from("Source")),
+				Some(&Variant::String(source(3, 1)))
+			);
+		}
+
+		let id = low.get_ids(&path).unwrap()[0];
+		assert!(low.get_evicted(id).is_none());
+
+		low.evict(id);
+
+		assert!(low.get_evicted(id).is_some());
+		assert_eq!(state(&normal, &vfs), state(&low, &vfs));
+	}
+
+	#[test]
+	fn evicts_large_values() {
+		let dir = workspace("smoke", 500);
+		let vfs = Vfs::new(false);
+
+		let normal = tree(&dir, &vfs, false);
+		let low = tree(&dir, &vfs, true);
+
+		let small = low.get_ids(&dir.join("Small.luau")).unwrap()[0];
+
+		assert!(low.get_evicted(small).is_none());
+		assert!(resident(&normal) > 500 * 10_000);
+		assert!(resident(&low) < resident(&normal) / 100);
+	}
+
+	#[test]
+	fn reads_owning_file() {
+		let dir = workspace("owning-file", 0);
+		let module = dir.join("Module");
+
+		fs::create_dir_all(module.join("Nested")).unwrap();
+		fs::write(module.join("init.luau"), source(0, 0)).unwrap();
+		fs::write(module.join("Nested/Deep.luau"), source(1, 0)).unwrap();
+
+		for index in 0..20 {
+			fs::write(module.join(format!("Child{}.luau", index)), "return {}").unwrap();
+		}
+
+		let reads = Arc::new(Mutex::new(vec![]));
+		let vfs = Vfs::from_backend(
+			WrappedBackend::new()
+				.on_read({
+					let reads = reads.clone();
+					move |path, _| reads.lock().unwrap().push(path.to_owned())
+				})
+				.on_read_dir({
+					let reads = reads.clone();
+					move |path, _| reads.lock().unwrap().push(path.to_owned())
+				}),
+		);
+
+		let low = tree(&dir, &vfs, true);
+		let id = low.get_ids(&module).unwrap()[0];
+
+		assert!(low.get_evicted(id).is_some());
+
+		reads.lock().unwrap().clear();
+
+		let properties = memory::materialize(id, &low, &vfs).unwrap();
+
+		assert_eq!(
+			properties.get(&Ustr::from("Source")),
+			Some(&Variant::String(source(0, 0)))
+		);
+
+		// Only the directory listing and the init file are read, none of the children
+		let reads = reads.lock().unwrap();
+
+		assert!(reads.contains(&module.join("init.luau")));
+		assert!(reads
+			.iter()
+			.all(|path| path == &module || path == &module.join("init.luau")));
+	}
+
+	#[test]
+	fn fails_if_unrecoverable() {
+		let dir = workspace("unrecoverable", 1);
+		let module = dir.join("Module");
+
+		fs::create_dir_all(&module).unwrap();
+		fs::write(module.join("init.luau"), source(1, 0)).unwrap();
+
+		let vfs = Vfs::new(false);
+		let low = tree(&dir, &vfs, true);
+
+		let script = low.get_ids(&dir.join("Script0.luau")).unwrap()[0];
+		let directory = low.get_ids(&module).unwrap()[0];
+
+		// Deleted file and directory that lost its init file before the VFS event was processed
+		fs::remove_file(dir.join("Script0.luau")).unwrap();
+		fs::remove_file(module.join("init.luau")).unwrap();
+
+		for id in [script, directory] {
+			assert!(memory::materialize(id, &low, &vfs).is_err());
+
+			let properties = memory::materialize_lossy(id, &low, &vfs).unwrap();
+			assert!(!properties.contains_key(&Ustr::from("Source")));
+		}
+	}
+
+	#[test]
+	fn fails_if_changed() {
+		let dir = workspace("changed", 1);
+		let path = dir.join("Script0.luau");
+
+		let vfs = Vfs::new(false);
+		let mut low = tree(&dir, &vfs, true);
+		let id = low.get_ids(&path).unwrap()[0];
+
+		// File changed but the VFS event was not processed yet
+		fs::write(&path, source(0, 1)).unwrap();
+
+		assert!(memory::materialize(id, &low, &vfs).is_err());
+
+		let properties = memory::materialize_lossy(id, &low, &vfs).unwrap();
+		assert!(!properties.contains_key(&Ustr::from("Source")));
+
+		// Processing the event reads the new value
+		let changes = read::process_changes(id, &mut low, &vfs).unwrap();
+		let properties = changes.updates[0].properties.as_ref().unwrap();
+
+		assert_eq!(
+			properties.get(&Ustr::from("Source")),
+			Some(&Variant::String(source(0, 1)))
+		);
+		assert_eq!(
+			memory::materialize(id, &low, &vfs).unwrap().get(&Ustr::from("Source")),
+			Some(&Variant::String(source(0, 1)))
+		);
+	}
+}
+
+mod syncback {
+	mod verify_name {
+		use argon::core::{helpers::syncback::verify_name, meta::Meta};
+
+		fn verify(name: &str) -> (String, Option<String>) {
+			let mut name = name.to_owned();
+			let mut meta = Meta::new();
+
+			assert!(verify_name(&mut name, &mut meta));
+
+			(name, meta.original_name)
+		}
+
+		#[test]
+		fn reserved_names() {
+			for reserved in ["CON", "PRN", "AUX", "NUL", "COM1", "COM9", "LPT1", "LPT9"] {
+				let (name, original_name) = verify(reserved);
+
+				assert_ne!(name, reserved);
+				assert_eq!(original_name.as_deref(), Some(reserved));
+			}
+
+			assert_eq!(verify("con").0, "conn");
+			assert_eq!(verify("Com1").0, "Com11");
+			assert_eq!(verify("NUL.txt").0, "NULL.txt");
+			assert_eq!(verify("aux.server").0, "auxx.server");
+		}
+
+		#[test]
+		fn similar_names() {
+			for valid in ["CONSOLE", "COM10", "LPT", "MyCON", "Null", "Printer"] {
+				assert_eq!(verify(valid), (valid.to_owned(), None));
+			}
+		}
+
+		#[test]
+		fn trailing_dots_and_spaces() {
+			assert_eq!(verify("Name.").0, "Name");
+			assert_eq!(verify("Name . .").0, "Name");
+			assert_eq!(verify("Name  ").0, "Name");
+			assert_eq!(verify("CON.").0, "CONN");
+			assert_eq!(verify("...").0, "EmptyName");
+
+			assert_eq!(verify("Name.").1.as_deref(), Some("Name."));
+		}
+
+		#[test]
+		fn clears_original_name() {
+			let mut name = String::from("Valid");
+			let mut meta = Meta::new().with_original_name(String::from("CON"));
+
+			assert!(verify_name(&mut name, &mut meta));
+			assert_eq!(meta.original_name, None);
+		}
+	}
+
+	#[cfg(windows)]
+	mod long_paths {
+		use crate::common::TempDir;
+		use argon::{
+			core::{
+				meta::{Context, Meta},
+				processor::write,
+				snapshot::{AddedSnapshot, Snapshot},
+				tree::Tree,
+			},
+			middleware::new_snapshot,
+			vfs::Vfs,
+		};
+		use rbx_dom_weak::{
+			types::{Ref, Variant},
+			HashMapExt, Ustr, UstrMap,
+		};
+		use std::{env, fs, process};
+
+		#[test]
+		fn nested_structure() {
+			let dir = TempDir::new("long-paths");
+
+			let vfs = Vfs::new(false);
+			let snapshot = new_snapshot(&dir, &Context::default(), &vfs).unwrap().unwrap();
+			let mut tree = Tree::new(snapshot);
+
+			let name = "NestedFolder".repeat(4);
+
+			let mut properties = UstrMap::new();
+			properties.insert(Ustr::from("Source"), Variant::String("return {}".into()));
+
+			let mut child = Snapshot::new()
+				.with_id(Ref::new())
+				.with_name("Module")
+				.with_class("ModuleScript")
+				.with_properties(properties);
+
+			for _ in 0..6 {
+				child = Snapshot::new()
+					.with_id(Ref::new())
+					.with_name(name.as_str())
+					.with_children(vec![child]);
+			}
+
+			let snapshot = AddedSnapshot {
+				id: Ref::new(),
+				meta: Meta::new(),
+				parent: tree.root_ref(),
+				name: name.clone(),
+				class: Ustr::from("Folder"),
+				properties: UstrMap::new(),
+				children: vec![child],
+			};
+
+			write::apply_addition(snapshot, &mut tree, &vfs).unwrap();
+
+			let path = (0..7).fold(dir.clone(), |path, _| path.join(&name)).join("Module.luau");
+
+			assert!(path.as_os_str().len() > 260);
+			assert_eq!(vfs.read_to_string(&path).unwrap(), "return {}");
+
+			// Stored paths should stay in the normal form
+			let id = tree.get_ids(&path).unwrap()[0];
+			let stored = tree.get_meta(id).unwrap().source.paths();
+
+			assert!(stored.iter().all(|path| !path.to_string_lossy().starts_with(r"\\?\")));
+
+			vfs.remove(&dir).ok();
+		}
+	}
+
+	mod collisions {
+		use crate::common::TempDir;
+		use argon::{
+			core::{
+				helpers::{collisions::Claims, syncback::verify_claim},
+				meta::{Context, Meta},
+				processor::write,
+				snapshot::{AddedSnapshot, Snapshot, UpdatedSnapshot},
+				tree::Tree,
+			},
+			middleware::new_snapshot,
+			vfs::Vfs,
+		};
+		use rbx_dom_weak::{
+			types::{Ref, Variant},
+			HashMapExt, Ustr, UstrMap,
+		};
+		use std::{
+			fs,
+			path::{Path, PathBuf},
+		};
+
+		fn fixture(name: &str) -> (TempDir, Tree, Vfs) {
+			let dir = TempDir::new(&format!("collisions-{}", name));
+
+			let vfs = Vfs::new(false);
+			let snapshot = new_snapshot(&dir, &Context::default(), &vfs).unwrap().unwrap();
+
+			(dir, Tree::new(snapshot), vfs)
+		}
+
+		fn source(value: &str) -> UstrMap<Variant> {
+			let mut properties = UstrMap::new();
+			properties.insert(Ustr::from("Source"), Variant::String(value.to_owned()));
+			properties
+		}
+
+		fn add(tree: &mut Tree, vfs: &Vfs, name: &str, class: &str, children: Vec<Snapshot>) -> Ref {
+			let id = Ref::new();
+
+			let snapshot = AddedSnapshot {
+				id,
+				meta: Meta::new(),
+				parent: tree.root_ref(),
+				name: name.to_owned(),
+				class: Ustr::from(class),
+				properties: if class == "Folder" {
+					UstrMap::new()
+				} else {
+					source("return {}")
+				},
+				children,
+			};
+
+			write::apply_addition(snapshot, tree, vfs).unwrap();
+
+			id
+		}
+
+		fn child() -> Snapshot {
+			Snapshot::new()
+				.with_id(Ref::new())
+				.with_name("Child")
+				.with_class("Folder")
+		}
+
+		fn path_of(tree: &Tree, id: Ref) -> PathBuf {
+			tree.get_meta(id).unwrap().source.get().path().unwrap().to_owned()
+		}
+
+		#[test]
+		fn case_fold() {
+			let owner = Ref::new();
+			let vfs = Vfs::new(false);
+
+			let mut claims = Claims::new(true);
+			assert_eq!(claims.claim(Path::new("src/Foo.luau"), owner), None);
+			assert_eq!(claims.claim(Path::new("src/Foo.luau"), owner), None);
+			assert_eq!(claims.claim(Path::new("src/FOO.luau"), Ref::new()), Some(owner));
+			assert_eq!(claims.owner(Path::new("src/foo.luau")), Some(owner));
+			assert_eq!(claims.owner(Path::new("other/foo.luau")), None);
+
+			let mut path = PathBuf::from("src/foo.luau");
+			let mut name = String::from("foo");
+			let mut meta = Meta::new();
+
+			assert_eq!(
+				verify_claim(&mut path, &mut name, &mut meta, &claims, &vfs),
+				Some(owner)
+			);
+			assert_eq!(path, PathBuf::from("src/foo_2.luau"));
+			assert_eq!(name, "foo_2");
+			assert_eq!(meta.original_name.as_deref(), Some("foo"));
+
+			let mut claims = Claims::new(false);
+			claims.claim(Path::new("src/Foo.luau"), owner);
+
+			let mut path = PathBuf::from("src/foo.luau");
+			let mut name = String::from("foo");
+
+			assert_eq!(
+				verify_claim(&mut path, &mut name, &mut Meta::new(), &claims, &vfs),
+				None
+			);
+			assert_eq!(path, PathBuf::from("src/foo.luau"));
+		}
+
+		#[test]
+		fn class_differs() {
+			let (dir, mut tree, vfs) = fixture("class-differs");
+
+			let folder = add(&mut tree, &vfs, "Foo", "Folder", vec![]);
+			let module = add(&mut tree, &vfs, "Foo", "ModuleScript", vec![child()]);
+
+			assert_eq!(path_of(&tree, folder), dir.join("Foo"));
+			assert_eq!(path_of(&tree, module), dir.join("Foo_2"));
+			assert!(dir.join("Foo_2/init.luau").exists());
+			assert!(dir.join("Foo_2/Child").is_dir());
+
+			// Both instances keep the name they have in Studio
+			assert_eq!(tree.get_instance(module).unwrap().name, "Foo");
+
+			let collisions: Vec<_> = tree.collisions().cloned().collect();
+
+			assert_eq!(collisions.len(), 1);
+			assert_eq!((collisions[0].id, collisions[0].other), (module, folder));
+			assert_eq!(collisions[0].path, dir.join("Foo"));
+			assert_eq!(collisions[0].renamed, dir.join("Foo_2"));
+
+			let mut update = UpdatedSnapshot::new(module);
+			update.name = Some(String::from("Bar"));
+
+			write::apply_update(update, &mut tree, &vfs).unwrap();
+
+			assert_eq!(tree.collisions().count(), 0);
+			assert!(dir.join("Bar/init.luau").exists());
+		}
+
+		#[test]
+		fn removal_resolves() {
+			let (_dir, mut tree, vfs) = fixture("removal-resolves");
+
+			let folder = add(&mut tree, &vfs, "Foo", "Folder", vec![]);
+			add(&mut tree, &vfs, "Foo", "Folder", vec![]);
+
+			assert_eq!(tree.collisions().count(), 1);
+
+			write::apply_removal(folder, &mut tree, &vfs).unwrap();
+
+			assert_eq!(tree.collisions().count(), 0);
+		}
+
+		#[test]
+		fn stable_across_syncs() {
+			let (dir, mut tree, vfs) = fixture("stable");
+
+			add(&mut tree, &vfs, "Foo", "Folder", vec![]);
+			add(&mut tree, &vfs, "Foo", "ModuleScript", vec![child()]);
+
+			let read = || new_snapshot(&dir, &Context::default(), &vfs).unwrap().unwrap();
+
+			// Reading the files back gives the same names every time
+			for _ in 0..2 {
+				let mut names: Vec<_> = read()
+					.children
+					.iter()
+					.map(|child| (child.name.clone(), child.meta.original_name.clone()))
+					.collect();
+				names.sort();
+
+				assert_eq!(
+					names,
+					vec![
+						(String::from("Foo"), None),
+						(String::from("Foo"), Some(String::from("Foo")))
+					]
+				);
+			}
+
+			let mut tree = Tree::new(read());
+			let module = tree.get_ids(&dir.join("Foo_2")).unwrap()[0];
+
+			let mut update = UpdatedSnapshot::new(module);
+			update.properties = Some(source("return 1"));
+
+			write::apply_update(update, &mut tree, &vfs).unwrap();
+
+			// Updating the instance does not move its files or forget its name
+			assert_eq!(path_of(&tree, module), dir.join("Foo_2"));
+			assert_eq!(fs::read_to_string(dir.join("Foo_2/init.luau")).unwrap(), "return 1");
+			assert_eq!(read().children.iter().filter(|child| child.name == "Foo").count(), 2);
+
+			// Next colliding sibling gets the next free suffix
+			let third = add(&mut tree, &vfs, "Foo", "Folder", vec![]);
+
+			assert_eq!(path_of(&tree, third), dir.join("Foo_3"));
+			assert!(dir.join("Foo").is_dir() && dir.join("Foo_2").is_dir());
+		}
+	}
+
+	mod collapse {
+		use crate::common::TempDir;
+		use argon::{
+			core::{meta::Meta, processor::write, snapshot::AddedSnapshot, tree::Tree, Core},
+			project::Project,
+			vfs::Vfs,
+		};
+		use rbx_dom_weak::{
+			types::{Ref, Variant},
+			Ustr, UstrMap,
+		};
+		use std::{fs, path::PathBuf};
+
+		fn fixture(name: &str, settings: &str) -> (TempDir, Core) {
+			let dir = TempDir::new(&format!("collapse-{}", name));
+
+			fs::create_dir_all(dir.join("src/Module")).unwrap();
+			fs::write(dir.join("src/Module/init.luau"), "return {}").unwrap();
+			fs::write(dir.join("src/Module/Child.luau"), "return 1").unwrap();
+			fs::write(dir.join("src/Script.luau"), "return 2").unwrap();
+
+			let project = format!(
+				r#"{{
+				"name": "Collapse",
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{ "$path": "src" }}
+				}},
+				{}
+			}}"#,
+				settings
+			);
+
+			fs::write(dir.join("default.project.json"), project).unwrap();
+
+			let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+			(dir, Core::new(project, false).unwrap())
+		}
+
+		fn find(tree: &Tree, path: PathBuf) -> Ref {
+			tree.get_ids(&path).unwrap()[0]
+		}
+
+		fn add(tree: &mut Tree, vfs: &Vfs, parent: Ref, name: &str) -> Ref {
+			let id = Ref::new();
+
+			let mut properties = UstrMap::default();
+			properties.insert(Ustr::from("Source"), Variant::String(String::from("return 3")));
+
+			let snapshot = AddedSnapshot {
+				id,
+				meta: Meta::new(),
+				parent,
+				name: name.to_owned(),
+				class: Ustr::from("ModuleScript"),
+				properties,
+				children: vec![],
+			};
+
+			write::apply_addition(snapshot, tree, vfs).unwrap();
+
+			id
+		}
+
+		#[test]
+		fn always() {
+			let (dir, core) = fixture("always", r#""collapseEmptyFolders": "always""#);
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			let child = find(&tree, dir.join("src/Module/Child.luau"));
+			write::apply_removal(child, &mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Module.luau").is_file());
+			assert!(!dir.join("src/Module").exists());
+		}
+
+		#[test]
+		fn never() {
+			let (dir, core) = fixture("never", r#""collapseEmptyFolders": "never""#);
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			let child = find(&tree, dir.join("src/Module/Child.luau"));
+			write::apply_removal(child, &mut tree, &vfs).unwrap();
+			write::finish_batch(&mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Module/init.luau").is_file());
+			assert!(!dir.join("src/Module.luau").exists());
+
+			// Files are not expanded into folders either
+			let script = find(&tree, dir.join("src/Script.luau"));
+			let child = add(&mut tree, &vfs, script, "Child");
+
+			assert!(dir.join("src/Script.luau").is_file());
+			assert!(!dir.join("src/Script").exists());
+			assert!(!tree.exists(child));
+		}
+
+		#[test]
+		fn batch_end() {
+			let (dir, core) = fixture("batch-end", r#""collapseEmptyFolders": "batch-end""#);
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			let module = find(&tree, dir.join("src/Module"));
+			let child = find(&tree, dir.join("src/Module/Child.luau"));
+
+			// Folder is only empty in the middle of the batch
+			write::apply_removal(child, &mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Module/init.luau").is_file());
+
+			let replacement = add(&mut tree, &vfs, module, "Replacement");
+			write::finish_batch(&mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Module/init.luau").is_file());
+			assert!(dir.join("src/Module/Replacement.luau").is_file());
+			assert!(!dir.join("src/Module.luau").exists());
+
+			// Folder that stays empty is collapsed once the batch ends
+			write::apply_removal(replacement, &mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Module").is_dir());
+
+			write::finish_batch(&mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Module.luau").is_file());
+			assert!(!dir.join("src/Module").exists());
+		}
+
+		#[test]
+		fn filtered_folder() {
+			let (dir, core) = fixture("filtered", r#""syncback": { "ignoreGlobs": ["src/Module"] }"#);
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			let child = find(&tree, dir.join("src/Module/Child.luau"));
+			write::apply_removal(child, &mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Module/init.luau").is_file());
+			assert!(!dir.join("src/Module.luau").exists());
+		}
+	}
+
+	mod layout_preferences {
+		use crate::common::TempDir;
+		use argon::{
+			core::{meta::Meta, processor::write, snapshot::AddedSnapshot, tree::Tree, Core},
+			project::Project,
+			validation::{self, Severity},
+			vfs::Vfs,
+		};
+		use rbx_dom_weak::{
+			types::{Ref, Variant},
+			Ustr, UstrMap,
+		};
+		use std::{fs, path::PathBuf};
+
+		fn project(settings: &str) -> String {
+			format!(
+				r#"{{
+				"name": "LayoutPreferences",
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{ "$path": "src" }}
+				}},
+				{}
+			}}"#,
+				settings
+			)
+		}
+
+		fn fixture(name: &str, settings: &str) -> (TempDir, Core) {
+			let dir = TempDir::new(&format!("layout-preferences-{}", name));
+
+			fs::create_dir_all(dir.join("src/Module")).unwrap();
+			fs::write(dir.join("src/Module/init.luau"), "return {}").unwrap();
+			fs::write(dir.join("src/Module/Child.luau"), "return 1").unwrap();
+			fs::write(dir.join("default.project.json"), project(settings)).unwrap();
+
+			let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+			(dir, Core::new(project, false).unwrap())
+		}
+
+		fn find(tree: &Tree, path: PathBuf) -> Ref {
+			tree.get_ids(&path).unwrap()[0]
+		}
+
+		fn add(tree: &mut Tree, vfs: &Vfs, parent: Ref, name: &str, class: &str) -> Ref {
+			let id = Ref::new();
+
+			let mut properties = UstrMap::default();
+
+			if class == "ModuleScript" {
+				properties.insert(Ustr::from("Source"), Variant::String(String::from("return 2")));
+			}
+
+			let snapshot = AddedSnapshot {
+				id,
+				meta: Meta::new(),
+				parent,
+				name: name.to_owned(),
+				class: Ustr::from(class),
+				properties,
+				children: vec![],
+			};
+
+			write::apply_addition(snapshot, tree, vfs).unwrap();
+
+			id
+		}
+
+		#[test]
+		fn folder() {
+			let (dir, core) = fixture(
+				"folder",
+				r#""collapseEmptyFolders": "always", "layoutPreferences": { "ModuleScript": "folder" }"#,
+			);
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+			let src = tree.find_by_path("ReplicatedStorage").unwrap();
+
+			add(&mut tree, &vfs, src, "Added", "ModuleScript");
+
+			assert!(dir.join("src/Added/init.luau").is_file());
+			assert!(!dir.join("src/Added.luau").exists());
+
+			// Folder stays even without children
+			let child = find(&tree, dir.join("src/Module/Child.luau"));
+			write::apply_removal(child, &mut tree, &vfs).unwrap();
+			write::finish_batch(&mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Module/init.luau").is_file());
+			assert!(!dir.join("src/Module.luau").exists());
+		}
+
+		#[test]
+		fn file() {
+			let (dir, core) = fixture(
+				"file",
+				r#""collapseEmptyFolders": "never", "layoutPreferences": { "LuaSourceContainer": "file", "Folder": "file" }"#,
+			);
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+			let src = tree.find_by_path("ReplicatedStorage").unwrap();
+
+			let added = add(&mut tree, &vfs, src, "Added", "ModuleScript");
+
+			assert!(dir.join("src/Added.luau").is_file());
+			assert!(!dir.join("src/Added").exists());
+
+			// First child still expands the file into a folder
+			add(&mut tree, &vfs, added, "Child", "ModuleScript");
+
+			assert!(dir.join("src/Added/init.luau").is_file());
+			assert!(dir.join("src/Added/Child.luau").is_file());
+
+			// Collapsed right away, regardless of `collapseEmptyFolders`
+			let child = find(&tree, dir.join("src/Module/Child.luau"));
+			write::apply_removal(child, &mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Module.luau").is_file());
+			assert!(!dir.join("src/Module").exists());
+
+			// Classes without a file middleware are always folders
+			add(&mut tree, &vfs, src, "Folder", "Folder");
+
+			assert!(dir.join("src/Folder").is_dir());
+		}
+
+		#[test]
+		fn auto() {
+			let (dir, core) = fixture("auto", r#""collapseEmptyFolders": "never""#);
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+			let src = tree.find_by_path("ReplicatedStorage").unwrap();
+
+			add(&mut tree, &vfs, src, "Added", "ModuleScript");
+
+			assert!(dir.join("src/Added.luau").is_file());
+			assert!(!dir.join("src/Added").exists());
+
+			let child = find(&tree, dir.join("src/Module/Child.luau"));
+			write::apply_removal(child, &mut tree, &vfs).unwrap();
+			write::finish_batch(&mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Module/init.luau").is_file());
+			assert!(!dir.join("src/Module.luau").exists());
+		}
+
+		#[test]
+		fn validation() {
+			let dir = TempDir::new("layout-preferences-validation");
+			let settings = r#""layoutPreferences": {
+			"BaseScript": "file",
+			"Folder": "file",
+			"Model": "folder",
+			"NotAClass": "auto"
+		}"#;
+			fs::create_dir_all(dir.join("src")).unwrap();
+
+			let problems = validation::validate(&project(settings), &dir.join("default.project.json"));
+			let messages = problems
+				.iter()
+				.inspect(|problem| assert_eq!(problem.severity, Severity::Warning))
+				.map(|problem| problem.message.as_str())
+				.collect::<Vec<_>>();
+
+			assert_eq!(
+				messages,
+				vec![
+					"Folder is always written as a folder, file layout has no effect",
+					"Unknown class NotAClass, its layout preference is never used",
+				]
+			);
+		}
+	}
+
+	mod error_context {
+		use crate::common::TempDir;
+		use argon::{
+			core::{
+				lifecycle::SyncbackResult,
+				meta::Meta,
+				processor::{origin::Origin, write},
+				snapshot::{AddedSnapshot, UpdatedSnapshot},
+				tree::Tree,
+				Core,
+			},
+			history::Operation,
+			project::Project,
+			vfs::Vfs,
+		};
+		use rbx_dom_weak::{
+			types::{Ref, Variant},
+			Ustr, UstrMap,
+		};
+		use std::{fs, path::PathBuf};
+
+		fn fixture(name: &str, settings: &str) -> (TempDir, Core) {
+			let dir = TempDir::new(&format!("error-context-{}", name));
+
+			fs::create_dir_all(dir.join("src/Modules")).unwrap();
+			fs::write(dir.join("src/Modules/Foo.luau"), "return {}").unwrap();
+
+			let project = format!(
+				r#"{{
+				"name": "ErrorContext",
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{ "$path": "src" }}
+				}}{}
+			}}"#,
+				settings
+			);
+
+			fs::write(dir.join("default.project.json"), project).unwrap();
+
+			let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+			(dir, Core::new(project, false).unwrap())
+		}
+
+		fn find(tree: &Tree, path: PathBuf) -> Ref {
+			tree.get_ids(&path).unwrap()[0]
+		}
+
+		#[test]
+		fn locate_failure() {
+			// Folder layout needs `childPattern` that the only rule of module scripts is missing
+			let (dir, core) = fixture(
+				"locate",
+				r#",
+				"syncRules": [
+					{ "type": "Project", "pattern": "*.project.json" },
+					{ "type": "ModuleScript", "pattern": "*.luau" }
+				],
+				"layoutPreferences": { "ModuleScript": "folder" }"#,
+			);
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			let id = Ref::new();
+			let parent = find(&tree, dir.join("src/Modules"));
+
+			let mut properties = UstrMap::default();
+			properties.insert(Ustr::from("Source"), Variant::String(String::from("return 1")));
+
+			let snapshot = AddedSnapshot {
+				id,
+				meta: Meta::new(),
+				parent,
+				name: String::from("Bar"),
+				class: Ustr::from("ModuleScript"),
+				properties,
+				children: vec![],
+			};
+
+			let result = write::apply_addition(snapshot, &mut tree, &vfs);
+			let err = result.as_ref().unwrap_err();
+
+			let origin = Origin {
+				id,
+				path: format!("{}.Bar", tree.get_full_name(parent).unwrap()),
+				class: String::from("ModuleScript"),
+			};
+
+			assert_eq!(Origin::find(err), Some(&origin));
+			assert_eq!(
+				format!("{:#}", err),
+				format!(
+					"{}: Failed to locate file path for parent: {}",
+					origin,
+					dir.join("src/Modules/Bar").display()
+				)
+			);
+
+			// Clients get the same error along with the instance to highlight
+			let result = SyncbackResult::new(Operation::Addition, id, &result);
+
+			assert_eq!(result.origin, Some(origin));
+			assert!(result.error.unwrap().starts_with("ModuleScript "));
+		}
+
+		#[test]
+		fn rename_failure() {
+			let (dir, core) = fixture("rename", "");
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			let id = find(&tree, dir.join("src/Modules/Foo.luau"));
+			let full_name = tree.get_full_name(id).unwrap();
+
+			// File is gone before the watcher could tell
+			fs::remove_file(dir.join("src/Modules/Foo.luau")).unwrap();
+
+			let mut update = UpdatedSnapshot::new(id);
+			update.name = Some(String::from("Renamed"));
+
+			let err = write::apply_update(update, &mut tree, &vfs).unwrap_err();
+			let message = format!("{:#}", err);
+
+			assert!(full_name.ends_with("ReplicatedStorage.Modules.Foo"));
+			assert_eq!(
+				Origin::find(&err),
+				Some(&Origin {
+					id,
+					path: full_name.clone(),
+					class: String::from("ModuleScript"),
+				})
+			);
+			assert!(
+				message.starts_with(&format!(
+					"ModuleScript {} ({}): Failed to rename {} to {}",
+					full_name,
+					id,
+					dir.join("src/Modules/Foo.luau").display(),
+					dir.join("src/Modules/Renamed.luau").display()
+				)),
+				"{}",
+				message
+			);
+		}
+	}
+
+	mod includes {
+		use crate::common::TempDir;
+		use argon::{
+			core::{meta::Context, processor::write, snapshot::UpdatedSnapshot, tree::Tree, Core},
+			middleware::data,
+			project::Project,
+			vfs::Vfs,
+		};
+		use rbx_dom_weak::{
+			types::{Attributes, Ref, Variant},
+			Ustr, UstrMap,
+		};
+		use std::{fs, path::PathBuf};
+
+		// Rojo mode is enabled by default so data files use the `.meta.json` suffix
+		fn fixture(name: &str, settings: &str) -> (TempDir, Core) {
+			let dir = TempDir::new(&format!("includes-{}", name));
+
+			fs::create_dir_all(dir.join("src")).unwrap();
+			fs::write(dir.join("src/Module.luau"), "return {}").unwrap();
+
+			let project = format!(
+				r#"{{
+				"name": "Includes",
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{ "$path": "src" }}
+				}}{}
+			}}"#,
+				settings
+			);
+
+			fs::write(dir.join("default.project.json"), project).unwrap();
+
+			let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+			(dir, Core::new(project, false).unwrap())
+		}
+
+		fn attributes(count: usize) -> Variant {
+			let mut attributes = Attributes::new();
+
+			for index in 0..count {
+				attributes.insert(format!("Setting{}", index), Variant::Float64(index as f64));
+			}
+
+			Variant::Attributes(attributes)
+		}
+
+		fn update(tree: &mut Tree, vfs: &Vfs, id: Ref, attributes: Variant) {
+			let mut properties = UstrMap::default();
+			properties.insert(Ustr::from("Source"), Variant::String(String::from("return {}")));
+			properties.insert(Ustr::from("Attributes"), attributes);
+
+			let mut snapshot = UpdatedSnapshot::new(id);
+			snapshot.properties = Some(properties);
+
+			write::apply_update(snapshot, tree, vfs).unwrap();
+		}
+
+		fn read(path: PathBuf, vfs: &Vfs) -> data::DataSnapshot {
+			data::read_data(&path, Some("ModuleScript"), &Context::default(), vfs).unwrap()
+		}
+
+		#[test]
+		fn round_trip() {
+			let (dir, core) = fixture("round-trip", r#", "dataIncludes": { "properties": ["Attributes"] }"#);
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			let id = tree.get_ids(&dir.join("src/Module.luau")).unwrap()[0];
+			update(&mut tree, &vfs, id, attributes(3));
+
+			let main = fs::read_to_string(dir.join("src/Module.meta.json")).unwrap();
+			let include = fs::read_to_string(dir.join("src/Module.Attributes.meta.json")).unwrap();
+
+			assert!(main.contains(r#""$include": "Module.Attributes.meta.json""#));
+			assert!(include.contains("Setting2"));
+
+			let data = read(dir.join("src/Module.meta.json"), &vfs);
+
+			assert_eq!(data.properties[&Ustr::from("Attributes")], attributes(3));
+			assert_eq!(data.includes, vec![dir.join("src/Module.Attributes.meta.json")]);
+			assert_eq!(
+				tree.get_meta(id).unwrap().source.get_includes(),
+				vec![dir.join("src/Module.Attributes.meta.json")]
+			);
+
+			// Writing the same properties again does not move anything around
+			update(&mut tree, &vfs, id, attributes(3));
+
+			assert_eq!(fs::read_to_string(dir.join("src/Module.meta.json")).unwrap(), main);
+			assert_eq!(
+				fs::read_to_string(dir.join("src/Module.Attributes.meta.json")).unwrap(),
+				include
+			);
+		}
+
+		#[test]
+		fn threshold() {
+			let (dir, core) = fixture("threshold", r#", "dataIncludes": { "threshold": 200 }"#);
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			let id = tree.get_ids(&dir.join("src/Module.luau")).unwrap()[0];
+
+			update(&mut tree, &vfs, id, attributes(2));
+			assert!(!dir.join("src/Module.Attributes.meta.json").exists());
+
+			update(&mut tree, &vfs, id, attributes(50));
+			assert!(dir.join("src/Module.Attributes.meta.json").exists());
+
+			// Once included, the property stays included even if it shrinks
+			update(&mut tree, &vfs, id, attributes(2));
+			assert!(dir.join("src/Module.Attributes.meta.json").exists());
+			assert_eq!(
+				read(dir.join("src/Module.meta.json"), &vfs).properties[&Ustr::from("Attributes")],
+				attributes(2)
+			);
+		}
+
+		#[test]
+		fn rename() {
+			let (dir, core) = fixture("rename", r#", "dataIncludes": { "properties": ["Attributes"] }"#);
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			let id = tree.get_ids(&dir.join("src/Module.luau")).unwrap()[0];
+			update(&mut tree, &vfs, id, attributes(3));
+
+			let mut snapshot = UpdatedSnapshot::new(id);
+			snapshot.name = Some(String::from("Renamed"));
+
+			write::apply_update(snapshot, &mut tree, &vfs).unwrap();
+
+			for name in ["Module.luau", "Module.meta.json", "Module.Attributes.meta.json"] {
+				assert!(!dir.join("src").join(name).exists());
+			}
+
+			assert!(dir.join("src/Renamed.luau").exists());
+			assert!(dir.join("src/Renamed.Attributes.meta.json").exists());
+
+			let data = read(dir.join("src/Renamed.meta.json"), &vfs);
+
+			assert_eq!(data.properties[&Ustr::from("Attributes")], attributes(3));
+			assert_eq!(
+				tree.get_meta(id).unwrap().source.get_includes(),
+				vec![dir.join("src/Renamed.Attributes.meta.json")]
+			);
+		}
+
+		#[test]
+		fn cycle() {
+			let (dir, _core) = fixture("cycle", "");
+			let vfs = Vfs::new(false);
+
+			fs::write(
+				dir.join("src/Module.meta.json"),
+				r#"{ "properties": { "Attributes": { "$include": "First.json" } } }"#,
+			)
+			.unwrap();
+			fs::write(
+				dir.join("src/First.json"),
+				r#"{ "Nested": { "$include": "Second.json" } }"#,
+			)
+			.unwrap();
+			fs::write(dir.join("src/Second.json"), r#"{ "$include": "First.json" }"#).unwrap();
+
+			let err = data::read_data(
+				&dir.join("src/Module.meta.json"),
+				Some("ModuleScript"),
+				&Context::default(),
+				&vfs,
+			)
+			.unwrap_err()
+			.to_string();
+
+			assert!(err.starts_with("Cyclic data file include"));
+			assert!(err.contains("First.json -> "));
+			assert!(err.ends_with("First.json"));
+		}
+
+		#[test]
+		fn disabled() {
+			let (dir, core) = fixture("disabled", "");
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			fs::write(
+				dir.join("src/Module.meta.json"),
+				r#"{ "properties": { "Attributes": { "$include": "Module.Attributes.meta.json" } } }"#,
+			)
+			.unwrap();
+			fs::write(dir.join("src/Module.Attributes.meta.json"), r#"{ "Setting0": 0.0 }"#).unwrap();
+
+			let data = read(dir.join("src/Module.meta.json"), &vfs);
+
+			assert_eq!(data.properties[&Ustr::from("Attributes")], attributes(1));
+
+			// Without the setting included properties are written back inline
+			let id = tree.get_ids(&dir.join("src/Module.luau")).unwrap()[0];
+			update(&mut tree, &vfs, id, attributes(2));
+
+			assert!(!dir.join("src/Module.Attributes.meta.json").exists());
+			assert_eq!(
+				read(dir.join("src/Module.meta.json"), &vfs).properties[&Ustr::from("Attributes")],
+				attributes(2)
+			);
+		}
+	}
+
+	mod markdown {
+		use crate::common::TempDir;
+		use argon::{
+			core::{processor::write, snapshot::UpdatedSnapshot, tree::Tree, Core},
+			project::Project,
+			vfs::Vfs,
+		};
+		use rbx_dom_weak::{
+			types::{Attributes, Ref, Variant},
+			Ustr, UstrMap,
+		};
+		use std::fs;
+
+		const DOC: &str = "---\ntitle: Design\n# Reviewed by the team\nversion: 2\n---\n# Design\n\nNotes\n";
+
+		fn fixture(name: &str, settings: &str) -> (TempDir, Core) {
+			let dir = TempDir::new(&format!("markdown-{}", name));
+
+			fs::create_dir_all(dir.join("src")).unwrap();
+			fs::write(dir.join("src/Doc.md"), DOC).unwrap();
+
+			let project = format!(
+				r#"{{
+				"name": "Markdown",
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{ "$path": "src" }}
+				}}{}
+			}}"#,
+				settings
+			);
+
+			fs::write(dir.join("default.project.json"), project).unwrap();
+
+			let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+			(dir, Core::new(project, false).unwrap())
+		}
+
+		fn update(tree: &mut Tree, vfs: &Vfs, id: Ref, value: &str, attributes: &[(&str, Variant)]) {
+			let mut map = Attributes::new();
+
+			for (key, attribute) in attributes {
+				map.insert(key.to_string(), attribute.clone());
+			}
+
+			let mut properties = UstrMap::default();
+			properties.insert(Ustr::from("Value"), Variant::String(value.to_owned()));
+			properties.insert(Ustr::from("Attributes"), Variant::Attributes(map));
+
+			let mut snapshot = UpdatedSnapshot::new(id);
+			snapshot.properties = Some(properties);
+
+			write::apply_update(snapshot, tree, vfs).unwrap();
+		}
+
+		#[test]
+		fn front_matter() {
+			let (dir, core) = fixture("front-matter", r#", "includeMarkdown": true"#);
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			let id = tree.get_ids(&dir.join("src/Doc.md")).unwrap()[0];
+			let instance = tree.get_instance(id).unwrap();
+
+			assert_eq!(instance.class.as_str(), "StringValue");
+			assert_eq!(
+				instance.properties[&Ustr::from("Value")],
+				Variant::String(String::from("# Design\n\nNotes\n"))
+			);
+
+			match &instance.properties[&Ustr::from("Attributes")] {
+				Variant::Attributes(attributes) => {
+					assert_eq!(attributes.get("title"), Some(&Variant::String(String::from("Design"))));
+					assert_eq!(attributes.get("version"), Some(&Variant::Float64(2.0)));
+				}
+				_ => panic!("Expected attributes"),
+			}
+
+			let title = ("title", Variant::String(String::from("Design")));
+
+			// Changing only the value keeps front-matter untouched
+			update(
+				&mut tree,
+				&vfs,
+				id,
+				"Updated\n",
+				&[title.clone(), ("version", Variant::Float64(2.0))],
+			);
+
+			assert_eq!(
+				fs::read_to_string(dir.join("src/Doc.md")).unwrap(),
+				"---\ntitle: Design\n# Reviewed by the team\nversion: 2\n---\nUpdated\n"
+			);
+
+			// Attribute changes update entries in place
+			update(
+				&mut tree,
+				&vfs,
+				id,
+				"Updated\n",
+				&[("version", Variant::Float64(3.0)), ("draft", Variant::Bool(true))],
+			);
+
+			assert_eq!(
+				fs::read_to_string(dir.join("src/Doc.md")).unwrap(),
+				"---\n# Reviewed by the team\nversion: 3\ndraft: true\n---\nUpdated\n"
+			);
+
+			assert!(!dir.join("src/Doc.txt").exists());
+			assert!(!dir.join("src/Doc.data.json").exists());
+		}
+
+		#[test]
+		fn size_limit() {
+			let (dir, core) = fixture("size-limit", r#", "includeMarkdown": true, "maxMarkdownSize": 32"#);
+			let tree = core.tree();
+
+			assert!(tree.get_ids(&dir.join("src/Doc.md")).is_none());
+		}
+
+		#[test]
+		fn disabled() {
+			let (dir, core) = fixture("disabled", r#", "includeMarkdown": false"#);
+			let tree = core.tree();
+
+			assert!(tree.get_ids(&dir.join("src/Doc.md")).is_none());
+
+			let root = tree.get_ids(&dir.join("src")).unwrap()[0];
+			assert!(tree.get_instance(root).unwrap().children().is_empty());
+		}
+
+		#[test]
+		fn rich_text_by_default() {
+			let (dir, core) = fixture("rich-text", "");
+			let tree = core.tree();
+
+			// Files are converted to rich text as before the setting existed
+			let id = tree.get_ids(&dir.join("src/Doc.md")).unwrap()[0];
+			let instance = tree.get_instance(id).unwrap();
+
+			assert_eq!(instance.class.as_str(), "StringValue");
+			match &instance.properties[&Ustr::from("Value")] {
+				Variant::String(value) => assert!(value.contains("<b>Design</b>"), "{}", value),
+				_ => panic!("Expected string value"),
+			}
+			assert!(!instance.properties.contains_key(&Ustr::from("Attributes")));
+		}
+	}
+
+	mod packages {
+		use crate::common::TempDir;
+		use argon::{
+			core::{
+				processor::write,
+				snapshot::{Snapshot, UpdatedSnapshot},
+				tree::Tree,
+				Core,
+			},
+			project::Project,
+			vfs::Vfs,
+		};
+		use rbx_dom_weak::{
+			types::{Ref, Variant},
+			Ustr, UstrMap,
+		};
+		use std::fs;
+
+		const PACKAGE: &str = r#"{
+		"className": "Model",
+		"children": [
+			{ "name": "PackageLink", "className": "PackageLink" },
+			{ "name": "Inner", "className": "ModuleScript", "properties": { "Source": "return 1" } }
+		]
+	}"#;
+
+		fn fixture(name: &str, settings: &str) -> (TempDir, Core) {
+			let dir = TempDir::new(&format!("packages-{}", name));
+
+			fs::create_dir_all(dir.join("src")).unwrap();
+			fs::write(dir.join("src/Package.model.json"), PACKAGE).unwrap();
+
+			let project = format!(
+				r#"{{
+				"name": "Packages",
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{ "$path": "src" }}
+				}}{}
+			}}"#,
+				settings
+			);
+
+			fs::write(dir.join("default.project.json"), project).unwrap();
+
+			let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+			(dir, Core::new(project, false).unwrap())
+		}
+
+		fn child(tree: &Tree, parent: Ref, name: &str) -> Option<Ref> {
+			tree.get_instance(parent)?
+				.children()
+				.iter()
+				.copied()
+				.find(|child| tree.get_instance(*child).unwrap().name == name)
+		}
+
+		fn script(name: &str) -> Snapshot {
+			let mut properties = UstrMap::default();
+			properties.insert(Ustr::from("Source"), Variant::String(String::from("return 2")));
+
+			Snapshot::new()
+				.with_id(Ref::new())
+				.with_name(name)
+				.with_class("ModuleScript")
+				.with_properties(properties)
+		}
+
+		/// Package as inserted from the toolbox, with metadata on its link
+		fn package(name: &str) -> Snapshot {
+			let mut properties = UstrMap::default();
+			properties.insert(Ustr::from("AutoUpdate"), Variant::Bool(true));
+			properties.insert(Ustr::from("VersionNumber"), Variant::Int64(3));
+
+			let link = Snapshot::new()
+				.with_id(Ref::new())
+				.with_name("PackageLink")
+				.with_class("PackageLink")
+				.with_properties(properties);
+
+			Snapshot::new()
+				.with_id(Ref::new())
+				.with_name(name)
+				.with_class("Model")
+				.with_children(vec![link, script("Internal")])
+		}
+
+		fn add(tree: &mut Tree, vfs: &Vfs, parent: Ref, snapshot: Snapshot) {
+			write::apply_addition(snapshot.into_new(parent), tree, vfs).unwrap();
+		}
+
+		#[test]
+		fn opaque() {
+			let (dir, core) = fixture("opaque", "");
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			let root = tree.get_ids(&dir.join("src")).unwrap()[0];
+			let existing = child(&tree, root, "Package").unwrap();
+			let inner = child(&tree, existing, "Inner").unwrap();
+
+			// Packages are still synced to Studio
+			assert!(child(&tree, existing, "PackageLink").is_some());
+
+			// New packages are not written at all
+			add(&mut tree, &vfs, root, package("Added"));
+
+			assert!(child(&tree, root, "Added").is_none());
+			assert_eq!(fs::read_dir(dir.join("src")).unwrap().count(), 1);
+
+			// Neither are changes inside of existing ones
+			add(&mut tree, &vfs, existing, script("Extra"));
+
+			let snapshot = UpdatedSnapshot {
+				name: Some(String::from("Renamed")),
+				..UpdatedSnapshot::new(inner)
+			};
+
+			write::apply_update(snapshot, &mut tree, &vfs).unwrap();
+			write::apply_removal(inner, &mut tree, &vfs).unwrap();
+
+			assert!(child(&tree, existing, "Extra").is_none());
+			assert_eq!(tree.get_instance(inner).unwrap().name, "Inner");
+			assert_eq!(fs::read_to_string(dir.join("src/Package.model.json")).unwrap(), PACKAGE);
+			assert_eq!(fs::read_dir(dir.join("src")).unwrap().count(), 1);
+		}
+
+		#[test]
+		fn managed() {
+			let (dir, core) = fixture("managed", r#", "managePackages": true"#);
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			let root = tree.get_ids(&dir.join("src")).unwrap()[0];
+
+			add(&mut tree, &vfs, root, package("Added"));
+
+			let added = child(&tree, root, "Added").unwrap();
+
+			assert!(child(&tree, added, "Internal").is_some());
+			assert!(dir.join("src/Added/Internal.luau").is_file());
+
+			// Link and its metadata never end up on disk
+			assert!(child(&tree, added, "PackageLink").is_none());
+
+			for entry in fs::read_dir(dir.join("src/Added")).unwrap() {
+				let path = entry.unwrap().path();
+
+				assert!(!path.to_string_lossy().contains("PackageLink"));
+				assert!(!fs::read_to_string(&path).unwrap().contains("AutoUpdate"));
+			}
+		}
+	}
+
+	mod file_modes {
+		use crate::common::TempDir;
+		use argon::{core::Core, project::Project, vfs::Vfs};
+		use std::{
+			fs::{self, File},
+			time::{Duration, SystemTime},
+		};
+
+		fn fixture(name: &str) -> (TempDir, Core) {
+			let dir = TempDir::new(&format!("file-modes-{}", name));
+
+			fs::create_dir_all(dir.join("src/Module")).unwrap();
+			fs::write(dir.join("src/Module/init.luau"), "return {}").unwrap();
+			fs::write(dir.join("src/Module/Child.luau"), "return 1").unwrap();
+			fs::write(dir.join("src/Script.luau"), "return 2").unwrap();
+
+			fs::write(
+				dir.join("default.project.json"),
+				r#"{
+				"name": "FileModes",
+				"tree": {
+					"$className": "DataModel",
+					"ReplicatedStorage": { "$path": "src" }
+				},
+				"collapseEmptyFolders": "always"
+			}"#,
+			)
+			.unwrap();
+
+			let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+			(dir, Core::new(project, false).unwrap())
+		}
+
+		#[cfg(unix)]
+		fn set_mode(path: &std::path::Path, mode: u32) {
+			use std::os::unix::fs::PermissionsExt;
+			fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+		}
+
+		#[cfg(unix)]
+		fn mode(path: &std::path::Path) -> u32 {
+			use std::os::unix::fs::PermissionsExt;
+			fs::metadata(path).unwrap().permissions().mode() & 0o777
+		}
+
+		#[cfg(unix)]
+		#[test]
+		fn executable_bit() {
+			use argon::core::{meta::Meta, processor::write, snapshot::AddedSnapshot};
+			use rbx_dom_weak::{
+				types::{Ref, Variant},
+				Ustr, UstrMap,
+			};
+
+			let (dir, core) = fixture("executable");
+			let (mut tree, vfs) = (core.tree(), Vfs::new(false));
+
+			set_mode(&dir.join("src/Module/init.luau"), 0o755);
+			set_mode(&dir.join("src/Script.luau"), 0o755);
+
+			// Folder to file
+			let child = tree.get_ids(&dir.join("src/Module/Child.luau")).unwrap()[0];
+			write::apply_removal(child, &mut tree, &vfs).unwrap();
+
+			assert_eq!(mode(&dir.join("src/Module.luau")), 0o755);
+
+			// File to folder
+			let mut properties = UstrMap::default();
+			properties.insert(Ustr::from("Source"), Variant::String(String::from("return 3")));
+
+			let script = tree.get_ids(&dir.join("src/Script.luau")).unwrap()[0];
+			let snapshot = AddedSnapshot {
+				id: Ref::new(),
+				meta: Meta::new(),
+				parent: script,
+				name: String::from("Child"),
+				class: Ustr::from("ModuleScript"),
+				properties,
+				children: vec![],
+			};
+
+			write::apply_addition(snapshot, &mut tree, &vfs).unwrap();
+
+			assert_eq!(mode(&dir.join("src/Script/init.luau")), 0o755);
+		}
+
+		#[cfg(unix)]
+		#[test]
+		fn project_file() {
+			let (dir, _core) = fixture("project");
+			let path = dir.join("default.project.json");
+
+			set_mode(&path, 0o750);
+
+			let mut project = Project::load(&path).unwrap();
+			project.name = String::from("Changed");
+			project.save(&path).unwrap();
+
+			assert_eq!(mode(&path), 0o750);
+		}
+
+		#[test]
+		fn unchanged_mtime() {
+			let (dir, _core) = fixture("mtime");
+			let path = dir.join("src/Script.luau");
+			let vfs = Vfs::new(false);
+
+			// Whole seconds as some file systems store timestamps with low precision
+			let past = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+			File::options()
+				.write(true)
+				.open(&path)
+				.unwrap()
+				.set_modified(past)
+				.unwrap();
+
+			vfs.write(&path, b"return 2").unwrap();
+			assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), past);
+
+			vfs.write(&path, b"return 3").unwrap();
+			assert_ne!(fs::metadata(&path).unwrap().modified().unwrap(), past);
+		}
+	}
+}
+
+mod filter {
+	mod merge {
+		use argon::{
+			core::meta::{Context, Meta, NodePath, SyncbackFilter},
+			middleware::project::new_snapshot_node,
+			project::{Project, ProjectFilter},
+			vfs::Vfs,
+		};
+		use std::path::Path;
+
+		fn filter(json: &str) -> ProjectFilter {
+			serde_json::from_str(json).unwrap()
+		}
+
+		fn layered(filters: &[&str]) -> SyncbackFilter {
+			let mut context = Context::default();
+
+			for json in filters {
+				context = context.with_filter(&filter(json), Path::new("/project"));
+			}
+
+			context.syncback_filter().clone()
+		}
+
+		/// Returns filters of instances at given paths from the project tree
+		fn project_filters(json: &str, paths: &[&[&str]]) -> Vec<SyncbackFilter> {
+			let mut project: Project = serde_json::from_str(json).unwrap();
+			project.roblox_ts = Some(false);
+
+			let context = Meta::from_project(&project).context;
+			let snapshot = new_snapshot_node(
+				"Game",
+				Path::new("/project/default.project.json"),
+				project.node,
+				NodePath::new(),
+				&context,
+				&Vfs::new_virtual(),
+			)
+			.unwrap();
+
+			paths
+				.iter()
+				.map(|path| {
+					let mut current = &snapshot;
+
+					for name in *path {
+						current = current.children.iter().find(|child| child.name == *name).unwrap();
+					}
+
+					current.meta.context.syncback_filter().clone()
+				})
+				.collect()
+		}
+
+		#[test]
+		fn no_layers() {
+			let filter = SyncbackFilter {
+				ignore_names: vec![String::from("Temp")],
+				ignore_classes: vec![String::from("Model")],
+				..SyncbackFilter::default()
+			};
+
+			assert!(filter.matches_name("Temp"));
+			assert!(!filter.matches_name("Other"));
+			assert!(filter.matches_class("Model"));
+			assert!(!filter.matches_class("Part"));
+		}
+
+		#[test]
+		fn exclude() {
+			let filter = layered(&[
+				r#"{"excludeNames": ["Gen*"], "excludeClasses": ["Model"], "excludePaths": ["generated/**"]}"#,
+			]);
+
+			assert!(filter.matches_name("Generated"));
+			assert!(!filter.matches_name("Map"));
+			assert!(filter.matches_class("Model"));
+			assert!(!filter.matches_class("Part"));
+			assert!(filter.matches_path(Path::new("/project/generated")));
+			assert!(filter.matches_path(Path::new("/project/generated/a.luau")));
+			assert!(!filter.matches_path(Path::new("/project/src/a.luau")));
+			assert!(!filter.matches_path(Path::new("/other/generated/a.luau")));
+		}
+
+		#[test]
+		fn include_overrides_global() {
+			let mut filter = layered(&[r#"{"includeClasses": ["Model"], "includeNames": ["Temp"]}"#]);
+
+			filter.ignore_classes = vec![String::from("Model"), String::from("Script")];
+			filter.ignore_names = vec![String::from("Temp")];
+
+			assert!(!filter.matches_class("Model"));
+			assert!(filter.matches_class("Script"));
+			assert!(!filter.matches_name("Temp"));
+		}
+
+		#[test]
+		fn include_wins_within_layer() {
+			let filter = layered(&[r#"{"excludeClasses": ["*"], "includeClasses": ["Model"]}"#]);
+
+			assert!(!filter.matches_class("Model"));
+			assert!(filter.matches_class("Part"));
+		}
+
+		#[test]
+		fn child_most_wins() {
+			// Parent allows what grandparent excludes
+			let filter = layered(&[r#"{"excludeClasses": ["Model"]}"#, r#"{"includeClasses": ["Model"]}"#]);
+
+			assert!(!filter.matches_class("Model"));
+
+			// Parent excludes what grandparent allows
+			let filter = layered(&[r#"{"includeClasses": ["Model"]}"#, r#"{"excludeClasses": ["Model"]}"#]);
+
+			assert!(filter.matches_class("Model"));
+
+			// Layers without a matching rule are skipped
+			let filter = layered(&[r#"{"excludeClasses": ["Model"]}"#, r#"{"excludeNames": ["Temp"]}"#]);
+
+			assert!(filter.matches_class("Model"));
+			assert!(filter.matches_name("Temp"));
+			assert!(!filter.matches_name("Map"));
+
+			let filter = layered(&[r#"{"excludePaths": ["**"]}"#, r#"{"includePaths": ["map/**"]}"#]);
+
+			assert!(!filter.matches_path(Path::new("/project/map/Part.model.json")));
+			assert!(filter.matches_path(Path::new("/project/generated/Part.model.json")));
+		}
+
+		#[test]
+		fn properties_stay_global() {
+			let mut filter = layered(&[r#"{"includeNames": ["*"]}"#]);
+			filter.ignore_properties = vec![String::from("Size")];
+
+			assert!(filter.matches_property("Size"));
+			assert!(!filter.matches_property("Color"));
+		}
+
+		#[test]
+		fn project_tree() {
+			let filters = project_filters(
+				r#"{
+				"tree": {
+					"$className": "DataModel",
+					"Workspace": {
+						"Map": {
+							"$className": "Folder",
+							"$filter": { "includeNames": ["*"], "includeClasses": ["*"], "includePaths": ["**"] },
+							"Nested": { "$className": "Folder" }
+						}
+					},
+					"ReplicatedStorage": {
+						"$filter": { "excludeClasses": ["Model"] },
+						"Generated": {
+							"$className": "Folder",
+							"$filter": { "excludeNames": ["*"], "excludeClasses": ["*"], "excludePaths": ["**"] }
+						},
+						"Models": {
+							"$className": "Folder",
+							"$filter": { "includeClasses": ["Model"] }
+						}
+					}
+				},
+				"syncback": { "ignoreClasses": ["Model"], "ignoreNames": ["Temp"] }
+			}"#,
+				&[
+					&["Workspace"],
+					&["Workspace", "Map", "Nested"],
+					&["ReplicatedStorage"],
+					&["ReplicatedStorage", "Generated"],
+					&["ReplicatedStorage", "Models"],
+				],
+			);
+
+			let [workspace, map, storage, generated, models] = filters.as_slice() else {
+				unreachable!()
+			};
+
+			// Global rules
+			assert!(workspace.layers.is_empty());
+			assert!(workspace.matches_class("Model"));
+			assert!(workspace.matches_name("Temp"));
+
+			// Everything syncable, inherited by descendants
+			assert!(!map.matches_class("Model"));
+			assert!(!map.matches_name("Temp"));
+			assert!(!map.matches_path(Path::new("/project/map/Temp.luau")));
+
+			assert!(storage.matches_class("Model"));
+			assert!(!storage.matches_class("Part"));
+
+			// Everything blocked
+			assert!(generated.matches_class("Part"));
+			assert!(generated.matches_name("Anything"));
+			assert!(generated.matches_path(Path::new("/project/src/generated")));
+
+			assert!(!models.matches_class("Model"));
+			assert!(models.matches_name("Temp"));
+		}
+	}
+
+	mod processor {
+		use crate::common::TempDir;
+		use argon::{
+			core::{
+				meta::{Context, Meta},
+				processor::write,
+				snapshot::AddedSnapshot,
+				tree::Tree,
+			},
+			middleware::new_snapshot,
+			vfs::Vfs,
+		};
+		use rbx_dom_weak::{types::Ref, Ustr, UstrMap};
+		use std::fs;
+
+		const PROJECT: &str = r#"{
+		"name": "Filters",
+		"tree": {
+			"$className": "DataModel",
+			"ReplicatedStorage": {
+				"$filter": { "excludeClasses": ["Model"] },
+				"Models": {
+					"$path": "models",
+					"$filter": { "includeClasses": ["Model"] }
+				},
+				"Blocked": { "$path": "blocked" }
+			}
+		}
+	}"#;
+
+		fn fixture() -> (TempDir, Tree, Vfs) {
+			let dir = TempDir::new("filter");
+
+			fs::create_dir_all(dir.join("models")).unwrap();
+			fs::create_dir_all(dir.join("blocked")).unwrap();
+			fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+
+			let vfs = Vfs::new(false);
+			let snapshot = new_snapshot(&dir.join("default.project.json"), &Context::default(), &vfs)
+				.unwrap()
+				.unwrap();
+
+			(dir, Tree::new(snapshot), vfs)
+		}
+
+		fn find(tree: &Tree, names: &[&str]) -> Ref {
+			let dom = tree.inner();
+			let mut current = tree.root_ref();
+
+			for name in names {
+				current = *dom
+					.get_by_ref(current)
+					.unwrap()
+					.children()
+					.iter()
+					.find(|child| dom.get_by_ref(**child).unwrap().name == *name)
+					.unwrap();
+			}
+
+			current
+		}
+
+		fn model(parent: Ref) -> AddedSnapshot {
+			AddedSnapshot {
+				id: Ref::new(),
+				meta: Meta::new(),
+				parent,
+				name: String::from("Car"),
+				class: Ustr::from("Model"),
+				properties: UstrMap::default(),
+				children: vec![],
+			}
+		}
+
+		#[test]
+		fn addition_allowed_by_parent() {
+			let (dir, mut tree, vfs) = fixture();
+
+			// Grandparent excludes `Model` but the parent allows it
+			let models = find(&tree, &["ReplicatedStorage", "Models"]);
+			write::apply_addition(model(models), &mut tree, &vfs).unwrap();
+
+			assert!(dir.join("models/Car").exists());
+
+			// Sibling only inherits the exclusion
+			let blocked = find(&tree, &["ReplicatedStorage", "Blocked"]);
+			write::apply_addition(model(blocked), &mut tree, &vfs).unwrap();
+
+			assert!(!dir.join("blocked/Car").exists());
+		}
+	}
+}
+
+mod sources {
+	mod validate {
+		use crate::common::TempDir;
+		use argon::{
+			core::meta::{Source, SourceEntry, SourceIssue},
+			vfs::Vfs,
+		};
+		use std::fs;
+
+		fn fixture(name: &str) -> TempDir {
+			let dir = TempDir::new(&format!("sources-{}", name));
+
+			fs::create_dir_all(dir.join("Folder")).unwrap();
+			fs::write(dir.join("Folder/init.luau"), "").unwrap();
+			fs::write(dir.join("Folder/init.meta.json"), "{}").unwrap();
+			fs::write(dir.join("Module.luau"), "").unwrap();
+
+			dir
+		}
+
+		#[test]
+		fn valid() {
+			let dir = fixture("valid");
+			let vfs = Vfs::new(false);
+
+			let mut source = Source::child_file(&dir.join("Folder"), &dir.join("Folder/init.luau"));
+			source.set_data(Some(&dir.join("Folder/init.meta.json")));
+
+			assert!(source.validate(&vfs).is_empty());
+			assert!(Source::file(&dir.join("Module.luau")).validate(&vfs).is_empty());
+			assert!(Source::directory(&dir.join("Folder")).validate(&vfs).is_empty());
+			assert!(Source::new().validate(&vfs).is_empty());
+		}
+
+		#[test]
+		fn issues() {
+			let dir = fixture("issues");
+			let vfs = Vfs::new(false);
+
+			let mut source = Source::file(&dir.join("Module.luau"));
+			source.add_file(&dir.join("Folder/init.luau"));
+
+			assert_eq!(source.validate(&vfs), [SourceIssue::Duplicate("File", 2)]);
+
+			let mut source = Source::file(&dir.join("Module.luau"));
+			source.set_data(Some(&dir.join("Module.meta.json")));
+
+			assert_eq!(
+				source.validate(&vfs),
+				[SourceIssue::Missing(SourceEntry::Data(dir.join("Module.meta.json")))]
+			);
+
+			let source = Source::directory(&dir.join("Module.luau"));
+
+			assert_eq!(
+				source.validate(&vfs),
+				[SourceIssue::WrongKind(SourceEntry::Folder(dir.join("Module.luau")))]
+			);
+
+			let source = Source::child_file(&dir.join("Folder"), &dir.join("Module.luau"));
+
+			assert_eq!(
+				source.validate(&vfs),
+				[SourceIssue::OutsideFolder(SourceEntry::File(dir.join("Module.luau")))]
+			);
+
+			let source =
+				Source::file(&dir.join("Module.luau")).with_relevant(vec![SourceEntry::Folder(dir.join("Folder"))]);
+
+			assert_eq!(
+				source.validate(&vfs),
+				[SourceIssue::DetachedInner(dir.join("Module.luau"))]
+			);
+		}
+
+		#[test]
+		fn repair() {
+			let dir = fixture("repair");
+			let vfs = Vfs::new(false);
+
+			let mut source = Source::child_file(&dir.join("Folder"), &dir.join("Folder/init.luau"));
+			source.add_file(&dir.join("Folder/init.luau"));
+			source.add_data(&dir.join("Folder/missing.meta.json"));
+			source.add_data(&dir.join("Folder/init.meta.json"));
+
+			source.repair(&vfs);
+
+			assert!(source.validate(&vfs).is_empty());
+			assert_eq!(
+				source.relevant(),
+				&vec![
+					SourceEntry::Folder(dir.join("Folder")),
+					SourceEntry::File(dir.join("Folder/init.luau")),
+					SourceEntry::Data(dir.join("Folder/init.meta.json")),
+				]
+			);
+		}
+	}
+
+	mod processor {
+		use crate::common::TempDir;
+		use argon::{
+			core::{
+				helpers::fsck,
+				meta::{Context, Meta},
+				processor::write,
+				snapshot::{AddedSnapshot, UpdatedSnapshot},
+				tree::Tree,
+			},
+			middleware::new_snapshot,
+			vfs::Vfs,
+		};
+		use rbx_dom_weak::{
+			types::{Ref, Variant},
+			HashMapExt, Ustr, UstrMap,
+		};
+		use std::{fs, path::Path};
+
+		const PROJECT: &str = r#"{
+		"name": "Sources",
+		"tree": {
+			"$className": "DataModel",
+			"ReplicatedStorage": { "$path": "src" }
+		}
+	}"#;
+
+		fn fixture(name: &str) -> (TempDir, Tree, Vfs) {
+			let dir = TempDir::new(&format!("processor-{}", name));
+
+			fs::create_dir_all(dir.join("src/Folder")).unwrap();
+			fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+			fs::write(dir.join("src/Module.luau"), "return {}").unwrap();
+			fs::write(dir.join("src/Folder/Child.luau"), "return 1").unwrap();
+
+			let vfs = Vfs::new(false);
+			let snapshot = new_snapshot(&dir.join("default.project.json"), &Context::default(), &vfs)
+				.unwrap()
+				.unwrap();
+
+			let tree = Tree::new(snapshot);
+
+			assert_valid(&tree, &vfs);
+
+			(dir, tree, vfs)
+		}
+
+		fn assert_valid(tree: &Tree, vfs: &Vfs) {
+			let issues = fsck::validate(tree.root_ref(), true, tree, vfs);
+			assert!(issues.is_empty(), "{:?}", issues);
+		}
+
+		fn find(tree: &Tree, path: &Path) -> Ref {
+			tree.get_ids(path).unwrap()[0]
+		}
+
+		fn source(value: &str) -> UstrMap<Variant> {
+			let mut properties = UstrMap::new();
+			properties.insert(Ustr::from("Source"), Variant::String(value.to_owned()));
+			properties
+		}
+
+		#[test]
+		fn rename_file() {
+			let (dir, mut tree, vfs) = fixture("rename-file");
+			let id = find(&tree, &dir.join("src/Module.luau"));
+
+			let mut update = UpdatedSnapshot::new(id);
+			update.name = Some(String::from("Renamed"));
+
+			write::apply_update(update, &mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Renamed.luau").exists());
+			assert!(!dir.join("src/Module.luau").exists());
+			assert_valid(&tree, &vfs);
+		}
+
+		#[test]
+		fn rename_folder() {
+			let (dir, mut tree, vfs) = fixture("rename-folder");
+			let id = find(&tree, &dir.join("src/Folder"));
+
+			let mut update = UpdatedSnapshot::new(id);
+			update.name = Some(String::from("Renamed"));
+
+			write::apply_update(update, &mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Renamed/Child.luau").exists());
+			assert!(!dir.join("src/Folder").exists());
+			assert_valid(&tree, &vfs);
+		}
+
+		#[test]
+		fn file_to_folder() {
+			let (dir, mut tree, vfs) = fixture("file-to-folder");
+			let id = find(&tree, &dir.join("src/Module.luau"));
+
+			let child = AddedSnapshot {
+				id: Ref::new(),
+				meta: Meta::new(),
+				parent: id,
+				name: String::from("Nested"),
+				class: Ustr::from("ModuleScript"),
+				properties: source("return 2"),
+				children: vec![],
+			};
+
+			write::apply_addition(child, &mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Module/init.luau").exists());
+			assert!(dir.join("src/Module/Nested.luau").exists());
+			assert!(!dir.join("src/Module.luau").exists());
+			assert_valid(&tree, &vfs);
+
+			// Renaming transformed instance moves the whole folder
+			let mut update = UpdatedSnapshot::new(id);
+			update.name = Some(String::from("Renamed"));
+
+			write::apply_update(update, &mut tree, &vfs).unwrap();
+
+			assert!(dir.join("src/Renamed/init.luau").exists());
+			assert_valid(&tree, &vfs);
+		}
+
+		#[test]
+		fn removal() {
+			let (dir, mut tree, vfs) = fixture("removal");
+			let id = find(&tree, &dir.join("src/Folder/Child.luau"));
+
+			write::apply_removal(id, &mut tree, &vfs).unwrap();
+
+			assert!(!dir.join("src/Folder/Child.luau").exists());
+			assert_valid(&tree, &vfs);
+		}
+	}
+}
+
+mod typegen {
+	use argon::core::{
+		changes::Changes,
+		snapshot::{Snapshot, UpdatedSnapshot},
+		tree::Tree,
+		typegen::{self, TypegenOptions},
+	};
+	use rbx_dom_weak::{types::Variant, HashMapExt, Ustr, UstrMap};
+
+	fn instance(name: &str, class: &str, children: Vec<Snapshot>) -> Snapshot {
+		Snapshot::new()
+			.with_name(name)
+			.with_class(class)
+			.with_children(children)
+	}
+
+	fn tree() -> Tree {
+		let storage = instance(
+			"ReplicatedStorage",
+			"ReplicatedStorage",
+			vec![
+				instance("Shared", "Folder", vec![instance("Util", "ModuleScript", vec![])]),
+				instance("Remote", "RemoteEvent", vec![]),
+				instance("Remote", "RemoteEvent", vec![]),
+				instance("Remote", "RemoteFunction", vec![]),
+				instance("my-model", "Model", vec![]),
+			],
+		);
+
+		Tree::new(instance("Game", "DataModel", vec![storage]))
+	}
+
+	fn options(depth: Option<usize>, root: Option<&str>) -> TypegenOptions {
+		TypegenOptions {
+			name: String::from("GameTree"),
+			depth,
+			root: root.map(String::from),
+		}
+	}
+
+	#[test]
+	fn sanitize() {
+		assert_eq!(typegen::sanitize("Part"), "Part");
+		assert_eq!(typegen::sanitize("my-model"), "my_model");
+		assert_eq!(typegen::sanitize("Hello World!"), "Hello_World_");
+		assert_eq!(typegen::sanitize("1st"), "_1st");
+		assert_eq!(typegen::sanitize("end"), "_end");
+		assert_eq!(typegen::sanitize(""), "_");
+	}
+
+	#[test]
+	fn duplicates() {
+		let types = typegen::generate(&tree(), &options(None, Some("ReplicatedStorage"))).unwrap();
+
+		assert!(types.contains("\tRemote: RemoteEvent,"));
+		assert!(types.contains("\tRemote_2: RemoteEvent,"));
+		assert!(types.contains("\tRemote_3: RemoteFunction,"));
+		assert!(types.contains("\t-- \"my-model\"\n\tmy_model: Model,"));
+	}
+
+	#[test]
+	fn deterministic() {
+		let first = typegen::generate(&tree(), &options(None, None)).unwrap();
+		let second = typegen::generate(&tree(), &options(None, None)).unwrap();
+
+		assert_eq!(first, second);
+		assert!(first.contains("export type GameTree = DataModel & {"));
+		assert!(first.ends_with("return nil\n"));
+	}
+
+	#[test]
+	fn filters() {
+		let types = typegen::generate(&tree(), &options(Some(1), None)).unwrap();
+
+		assert!(types.contains("\tReplicatedStorage: ReplicatedStorage,"));
+		assert!(!types.contains("Shared"));
+
+		let types = typegen::generate(&tree(), &options(None, Some("ReplicatedStorage/Shared"))).unwrap();
+
+		assert!(types.contains("export type GameTree = Folder & {"));
+		assert!(types.contains("\tUtil: ModuleScript,"));
+
+		assert!(typegen::generate(&tree(), &options(None, Some("ServerStorage"))).is_err());
+	}
+
+	#[test]
+	fn structural_changes() {
+		let tree = tree();
+		let id = tree.root_ref();
+
+		let mut properties = UstrMap::new();
+		properties.insert(Ustr::from("Value"), Variant::String("Hello".into()));
+
+		let mut changes = Changes::new();
+		let mut update = UpdatedSnapshot::new(id);
+		update.properties = Some(properties);
+		changes.update(update);
+
+		assert!(!typegen::is_structural(&changes));
+
+		let mut update = UpdatedSnapshot::new(id);
+		update.name = Some(String::from("Renamed"));
+		changes.update(update);
+
+		assert!(typegen::is_structural(&changes));
+
+		let mut changes = Changes::new();
+		changes.remove(id);
+
+		assert!(typegen::is_structural(&changes));
+	}
+}
+
+mod ephemeral {
+	use crate::common::{TempDir, WrappedBackend};
+	use argon::{
+		core::{
+			meta::Meta,
+			processor::write,
+			snapshot::{AddedSnapshot, UpdatedSnapshot},
+			tree::Tree,
+			Core,
+		},
+		project::Project,
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Attributes, Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::{
+		fs,
+		path::PathBuf,
+		sync::{
+			atomic::{AtomicUsize, Ordering},
+			Arc,
+		},
+	};
+
+	fn fixture(name: &str, settings: &str) -> (TempDir, Core) {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src/Scratch")).unwrap();
+
+		let project = format!(
+			r#"{{
+			"name": "Ephemeral",
+			"tree": {{
+				"$className": "DataModel",
+				"ReplicatedStorage": {{ "$path": "src" }}
+			}}{}
+		}}"#,
+			settings
+		);
+
+		fs::write(dir.join("default.project.json"), project).unwrap();
+
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+		(dir, Core::new(project, false).unwrap())
+	}
+
+	fn counting_vfs() -> (Vfs, Arc<AtomicUsize>) {
+		let writes = Arc::new(AtomicUsize::new(0));
+
+		let counter = writes.clone();
+
+		let vfs = Vfs::from_backend(WrappedBackend::new().on_change(move |_| {
+			counter.fetch_add(1, Ordering::SeqCst);
+			Ok(())
+		}));
+
+		(vfs, writes)
+	}
+
+	fn find(tree: &Tree, path: PathBuf) -> Ref {
+		tree.get_ids(&path).unwrap()[0]
+	}
+
+	fn part(parent: Ref, name: &str, ephemeral: bool) -> AddedSnapshot {
+		let mut properties = UstrMap::default();
+
+		if ephemeral {
+			let mut attributes = Attributes::new();
+			attributes.insert(String::from("__ArgonEphemeral"), Variant::Bool(true));
+
+			properties.insert(Ustr::from("Attributes"), Variant::Attributes(attributes));
+		}
+
+		AddedSnapshot {
+			id: Ref::new(),
+			meta: Meta::new(),
+			parent,
+			name: name.to_owned(),
+			class: Ustr::from("Part"),
+			properties,
+			children: vec![],
+		}
+	}
+
+	fn lifecycle(tree: &mut Tree, vfs: &Vfs, snapshot: AddedSnapshot) {
+		let id = snapshot.id;
+
+		write::apply_addition(snapshot, tree, vfs).unwrap();
+
+		assert!(tree.exists(id));
+		assert!(tree.get_meta(id).unwrap().source.is_ephemeral());
+
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from("Transparency"), Variant::Float32(0.5));
+
+		let mut update = UpdatedSnapshot::new(id);
+		update.name = Some(String::from("Renamed"));
+		update.properties = Some(properties);
+
+		write::apply_update(update, tree, vfs).unwrap();
+
+		let instance = tree.get_instance(id).unwrap();
+
+		assert_eq!(instance.name, "Renamed");
+		assert_eq!(
+			instance.properties.get(&Ustr::from("Transparency")),
+			Some(&Variant::Float32(0.5))
+		);
+
+		write::apply_removal(id, tree, vfs).unwrap();
+
+		assert!(!tree.exists(id));
+	}
+
+	#[test]
+	fn attribute() {
+		let (dir, core) = fixture("attribute", "");
+		let (vfs, writes) = counting_vfs();
+		let mut tree = core.tree();
+
+		let parent = find(&tree, dir.join("src"));
+		let mut snapshot = part(parent, "Marked", true);
+		let child = part(Ref::none(), "Child", false);
+		let child_id = child.id;
+
+		snapshot.children.push(child.into());
+
+		let id = snapshot.id;
+		write::apply_addition(snapshot, &mut tree, &vfs).unwrap();
+
+		// Descendants of ephemeral instances are ephemeral too
+		assert!(tree.get_meta(child_id).unwrap().source.is_ephemeral());
+		write::apply_removal(id, &mut tree, &vfs).unwrap();
+
+		lifecycle(&mut tree, &vfs, part(parent, "Marked", true));
+
+		assert_eq!(writes.load(Ordering::SeqCst), 0);
+		assert!(!dir.join("src/Marked.model.json").exists());
+	}
+
+	#[test]
+	fn scratch_path() {
+		let (dir, core) = fixture("scratch", r#", "scratchPaths": ["ReplicatedStorage.Scratch"]"#);
+		let (vfs, writes) = counting_vfs();
+		let mut tree = core.tree();
+
+		let scratch = find(&tree, dir.join("src/Scratch"));
+
+		lifecycle(&mut tree, &vfs, part(scratch, "Temporary", false));
+
+		assert_eq!(writes.load(Ordering::SeqCst), 0);
+
+		// Instances outside of scratch paths are still written
+		let parent = find(&tree, dir.join("src"));
+		write::apply_addition(part(parent, "Persistent", false), &mut tree, &vfs).unwrap();
+
+		assert!(writes.load(Ordering::SeqCst) > 0);
+	}
+
+	#[test]
+	fn snapshot_export() {
+		let (dir, core) = fixture("snapshot", "");
+		let (vfs, _) = counting_vfs();
+
+		let (id, parent) = {
+			let mut tree = core.tree();
+			let parent = find(&tree, dir.join("src"));
+			let snapshot = part(parent, "Marked", true);
+			let id = snapshot.id;
+
+			write::apply_addition(snapshot, &mut tree, &vfs).unwrap();
+
+			(id, parent)
+		};
+
+		let has_child = |include_ephemeral: bool| {
+			core.snapshot(parent, include_ephemeral)
+				.unwrap()
+				.children
+				.iter()
+				.any(|child| child.id == id)
+		};
+
+		assert!(!has_child(false));
+		assert!(has_child(true));
+	}
+}
+
+mod stable_ids {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			find::{FindQuery, FindRequest},
+			meta::Meta,
+			processor::write,
+			snapshot::{AddedSnapshot, UpdatedSnapshot},
+			stable_ids::StableIds,
+			tree::Tree,
+			Core,
+		},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::{collections::HashMap, fs, path::PathBuf};
+
+	const PROJECT: &str = r#"{
+	"name": "StableIds",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": { "$path": "src" }
+	}
+}"#;
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+		fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+		fs::write(dir.join("src/Module.luau"), "return 1").unwrap();
+		fs::write(dir.join("src/Script.luau"), "return 2").unwrap();
+
+		dir
+	}
+
+	/// Starts a new session, rebuilding the tree from scratch
+	fn find(tree: &Tree, path: PathBuf) -> Ref {
+		tree.get_ids(&path).unwrap()[0]
+	}
+
+	fn stable_id(core: &Core, path: PathBuf) -> String {
+		let tree = core.tree();
+		let id = find(&tree, path);
+
+		tree.get_meta(id).unwrap().stable_id.clone().unwrap()
+	}
+
+	#[test]
+	fn restart() {
+		let dir = fixture("restart");
+
+		let core = start(&dir);
+		let module = stable_id(&core, dir.join("src/Module.luau"));
+		let script = stable_id(&core, dir.join("src/Script.luau"));
+
+		assert_ne!(module, script);
+		assert!(dir.join(".argon/ids.json").is_file());
+
+		drop(core);
+
+		let core = start(&dir);
+
+		assert_eq!(stable_id(&core, dir.join("src/Module.luau")), module);
+		assert_eq!(stable_id(&core, dir.join("src/Script.luau")), script);
+	}
+
+	#[test]
+	fn rename() {
+		let dir = fixture("rename");
+
+		let core = start(&dir);
+		let module = stable_id(&core, dir.join("src/Module.luau"));
+
+		{
+			let mut tree = core.tree();
+			let id = find(&tree, dir.join("src/Module.luau"));
+
+			let snapshot = UpdatedSnapshot {
+				name: Some(String::from("Renamed")),
+				..UpdatedSnapshot::new(id)
+			};
+
+			write::apply_update(snapshot, &mut tree, &Vfs::new(false)).unwrap();
+			tree.save_stable_ids().unwrap();
+		}
+
+		assert!(dir.join("src/Renamed.luau").is_file());
+		assert_eq!(stable_id(&core, dir.join("src/Renamed.luau")), module);
+
+		drop(core);
+
+		let core = start(&dir);
+
+		assert_eq!(stable_id(&core, dir.join("src/Renamed.luau")), module);
+	}
+
+	#[test]
+	fn transform() {
+		let dir = fixture("transform");
+
+		let core = start(&dir);
+		let module = stable_id(&core, dir.join("src/Module.luau"));
+
+		{
+			let mut tree = core.tree();
+			let parent = find(&tree, dir.join("src/Module.luau"));
+
+			let mut properties = UstrMap::default();
+			properties.insert(Ustr::from("Source"), Variant::String(String::from("return 3")));
+
+			let snapshot = AddedSnapshot {
+				id: Ref::new(),
+				meta: Meta::new(),
+				parent,
+				name: String::from("Child"),
+				class: Ustr::from("ModuleScript"),
+				properties,
+				children: vec![],
+			};
+
+			write::apply_addition(snapshot, &mut tree, &Vfs::new(false)).unwrap();
+			tree.save_stable_ids().unwrap();
+		}
+
+		// File was transformed into a folder
+		assert!(dir.join("src/Module/init.luau").is_file());
+		assert_eq!(stable_id(&core, dir.join("src/Module/init.luau")), module);
+
+		let child = stable_id(&core, dir.join("src/Module/Child.luau"));
+
+		drop(core);
+
+		let core = start(&dir);
+
+		assert_eq!(stable_id(&core, dir.join("src/Module/init.luau")), module);
+		assert_eq!(stable_id(&core, dir.join("src/Module/Child.luau")), child);
+	}
+
+	#[test]
+	fn addressing() {
+		let dir = fixture("addressing");
+
+		let core = start(&dir);
+		let module = stable_id(&core, dir.join("src/Module.luau"));
+		let id = find(&core.tree(), dir.join("src/Module.luau"));
+
+		assert_eq!(core.resolve(Ref::none(), Some(&module)), Some(id));
+		assert_eq!(core.resolve(Ref::none(), Some("missing")), None);
+
+		let request = FindRequest::parse(&format!("id={}", module)).unwrap();
+		let page = core.find(&FindQuery::new(&request).unwrap(), None, 10).unwrap();
+
+		assert_eq!(page.total, 1);
+		assert_eq!(page.instances[0].stable_id.as_deref(), Some(module.as_str()));
+	}
+
+	#[test]
+	fn garbage_collection() {
+		let dir = fixture("gc");
+		let path = dir.join(".argon/ids.json");
+
+		let entries = r#"{
+		"DataModel.ReplicatedStorage.Old": { "id": "old", "seen": 0 },
+		"DataModel.ReplicatedStorage.Recent": { "id": "recent", "seen": 9999999999 }
+	}"#;
+
+		fs::create_dir_all(path.parent().unwrap()).unwrap();
+		fs::write(&path, entries).unwrap();
+
+		let mut stable_ids = StableIds::load(&dir, 0);
+		stable_ids.save(&HashMap::new()).unwrap();
+
+		// Retention of 0 keeps everything
+		assert!(fs::read_to_string(&path).unwrap().contains("\"old\""));
+
+		let mut stable_ids = StableIds::load(&dir, 30);
+		stable_ids.save(&HashMap::new()).unwrap();
+
+		let contents = fs::read_to_string(&path).unwrap();
+
+		assert!(!contents.contains("\"old\""));
+		assert!(contents.contains("\"recent\""));
+	}
+}
+
+mod backups {
+	use crate::common::TempDir;
+	use argon::{backups, config::Config, project::Project, sessions::Session};
+	use std::{
+		collections::HashMap,
+		fs,
+		path::{Path, PathBuf},
+		process,
+	};
+
+	const PROJECT: &str = r#"{
+	"name": "Backups",
+	"tree": {
+		"$className": "DataModel"
+	}
+}"#;
+
+	fn fixture(name: &str) -> (TempDir, PathBuf) {
+		let dir = TempDir::new(name);
+		let path = dir.join("default.project.json");
+
+		fs::write(&path, PROJECT).unwrap();
+
+		(dir, path)
+	}
+
+	fn read(path: &Path) -> String {
+		fs::read_to_string(path).unwrap()
+	}
+
+	#[test]
+	fn identical() {
+		let (_dir, path) = fixture("identical");
+
+		assert!(backups::create(&path, PROJECT.as_bytes()).unwrap().is_none());
+		assert!(backups::create(&path.with_file_name("new.project.json"), b"{}")
+			.unwrap()
+			.is_none());
+		assert!(backups::list(&path).unwrap().is_empty());
+	}
+
+	#[test]
+	fn batches() {
+		let (_dir, path) = fixture("batches");
+
+		let backup = backups::create(&path, b"1").unwrap().unwrap();
+		assert_eq!(read(&backup.path), PROJECT);
+
+		// Only the state from before the batch is kept
+		fs::write(&path, "1").unwrap();
+		assert!(backups::create(&path, b"2").unwrap().is_none());
+
+		backups::end_batch();
+
+		let backup = backups::create(&path, b"2").unwrap().unwrap();
+		assert_eq!(read(&backup.path), "1");
+		assert_eq!(backups::list(&path).unwrap().len(), 2);
+
+		let max_backups = Config::new().max_backups;
+
+		for index in 0..max_backups + 2 {
+			fs::write(&path, index.to_string()).unwrap();
+			backups::end_batch();
+			backups::create(&path, b"next").unwrap();
+		}
+
+		let list = backups::list(&path).unwrap();
+
+		assert_eq!(list.len(), max_backups);
+		assert_eq!(read(&list.last().unwrap().path), (max_backups + 1).to_string());
+	}
+
+	#[test]
+	fn restore() {
+		let (_dir, path) = fixture("restore");
+
+		let original = backups::create(&path, b"modified").unwrap().unwrap();
+		fs::write(&path, "modified").unwrap();
+
+		let restored = backups::restore(&path, None).unwrap();
+
+		assert_eq!(restored, original);
+		assert_eq!(read(&path), PROJECT);
+
+		// Content from before the restore is backed up too
+		let list = backups::list(&path).unwrap();
+		assert_eq!(list.len(), 2);
+
+		backups::restore(&path, Some(&list[1].timestamp)).unwrap();
+		assert_eq!(read(&path), "modified");
+
+		assert!(backups::restore(&path, Some("20000101T000000000")).is_err());
+	}
+
+	#[test]
+	fn project_save() {
+		let (_dir, path) = fixture("project-save");
+		let mut project = Project::load(&path).unwrap();
+		let path = project.workspace_dir.join("saved.project.json");
+
+		// New files have nothing to back up
+		project.save(&path).unwrap();
+		let saved = read(&path);
+
+		// Saving without changes leaves the file untouched
+		project.save(&path).unwrap();
+		assert!(backups::list(&path).unwrap().is_empty());
+
+		project.name = String::from("Changed");
+		project.save(&path).unwrap();
+
+		let list = backups::list(&path).unwrap();
+
+		assert_eq!(list.len(), 1);
+		assert_eq!(read(&list[0].path), saved);
+		assert!(read(&path).contains("Changed"));
+	}
+
+	#[test]
+	fn live_session() {
+		let (_dir, path) = fixture("live-session");
+
+		let session = |project: PathBuf| Session {
+			pid: process::id(),
+			host: Some(String::from("localhost")),
+			port: Some(8000),
+			daemon: None,
+			project: Some(project),
+			workspace: None,
+			members: vec![],
+			mirror_port: None,
+		};
+
+		let sessions = HashMap::from([(String::from("live"), session(path.clone()))]);
+		assert!(backups::check_sessions(&path, &sessions).is_err());
+
+		let sessions = HashMap::from([(
+			String::from("other"),
+			session(path.with_file_name("other.project.json")),
+		)]);
+		assert!(backups::check_sessions(&path, &sessions).is_ok());
+	}
+}
+
+mod transactions {
+	use crate::common::{TempDir, WrappedBackend};
+	use argon::{
+		core::{
+			meta::Meta,
+			processor::write,
+			snapshot::{AddedSnapshot, UpdatedSnapshot},
+			tree::Tree,
+			Core,
+		},
+		project::Project,
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Attributes, Ref, Variant},
+		HashMapExt, Ustr, UstrMap,
+	};
+	use std::{
+		collections::BTreeMap,
+		fs,
+		io::Error,
+		path::{Path, PathBuf},
+		sync::{
+			atomic::{AtomicUsize, Ordering},
+			Arc,
+		},
+		thread,
+	};
+
+	fn fixture(name: &str) -> (TempDir, Core) {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		fs::write(dir.join("src/Module.luau"), "return 1").unwrap();
+		fs::write(
+			dir.join("src/Module.data.json"),
+			r#"{ "attributes": { "Initial": true } }"#,
+		)
+		.unwrap();
+		fs::write(dir.join("src/Parent.luau"), "return 2").unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{ "name": "Transactions", "tree": { "$className": "DataModel", "ReplicatedStorage": { "$path": "src" } } }"#,
+		)
+		.unwrap();
+
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+		(dir, Core::new(project, false).unwrap())
+	}
+
+	fn faulty_vfs(fail_at: usize) -> (Vfs, Arc<AtomicUsize>) {
+		let operations = Arc::new(AtomicUsize::new(0));
+
+		let counter = operations.clone();
+
+		let vfs = Vfs::from_backend(WrappedBackend::new().on_change(move |path| {
+			if counter.fetch_add(1, Ordering::SeqCst) + 1 == fail_at {
+				return Err(Error::other(format!("injected failure at {}", path.display())));
+			}
+
+			Ok(())
+		}));
+
+		(vfs, operations)
+	}
+
+	/// Contents of all files and directories in the directory
+	fn disk(dir: &Path) -> BTreeMap<PathBuf, Option<Vec<u8>>> {
+		fn walk(path: &Path, entries: &mut BTreeMap<PathBuf, Option<Vec<u8>>>) {
+			for entry in fs::read_dir(path).unwrap() {
+				let path = entry.unwrap().path();
+
+				if path.is_dir() {
+					entries.insert(path.clone(), None);
+					walk(&path, entries);
+				} else {
+					entries.insert(path.clone(), Some(fs::read(&path).unwrap()));
+				}
+			}
+		}
+
+		let mut entries = BTreeMap::new();
+		walk(dir, &mut entries);
+
+		entries
+	}
+
+	fn is_temp(path: &Path) -> bool {
+		path.to_string_lossy().ends_with(".argon-tmp")
+	}
+
+	fn find(tree: &Tree, path: PathBuf) -> Ref {
+		tree.get_ids(&path).unwrap()[0]
+	}
+
+	/// Runs the change failing every write operation in turn until it succeeds,
+	/// after every failure disk and the instance meta have to be unchanged,
+	/// including temporary files of staged writes
+	fn assert_rollback<F>(name: &str, target: &str, change: F)
+	where
+		F: Fn(&mut Tree, Ref, &Vfs) -> anyhow::Result<()>,
+	{
+		for fail_at in 1.. {
+			let (dir, core) = fixture(&format!("{}-{}", name, fail_at));
+			let mut tree = core.tree();
+
+			let id = find(&tree, dir.join(target));
+			let meta = tree.get_meta(id).cloned();
+			let instance_name = tree.get_instance(id).unwrap().name.clone();
+			let children = tree.get_instance(id).unwrap().children().len();
+			let before = disk(&dir.join("src"));
+
+			let (vfs, operations) = faulty_vfs(fail_at);
+
+			match change(&mut tree, id, &vfs) {
+				Ok(()) => {
+					let (temp, after): (BTreeMap<_, _>, BTreeMap<_, _>) =
+						disk(&dir.join("src")).into_iter().partition(|(path, _)| is_temp(path));
+
+					assert_ne!(after, before);
+
+					// Failing to remove files that were moved aside leaves them behind but keeps the change
+					if operations.load(Ordering::SeqCst) < fail_at {
+						assert!(temp.is_empty(), "Temporary files were left behind: {:?}", temp.keys());
+						return;
+					}
+				}
+				Err(err) => {
+					assert!(
+						format!("{:#}", err).contains("injected failure"),
+						"Unexpected error: {:#}",
+						err
+					);
+
+					assert_eq!(disk(&dir.join("src")), before, "Step {} was not rolled back", fail_at);
+					assert_eq!(tree.get_meta(id).cloned(), meta);
+					assert_eq!(tree.get_instance(id).unwrap().name, instance_name);
+					assert_eq!(tree.get_instance(id).unwrap().children().len(), children);
+				}
+			}
+
+			assert!(fail_at < 20, "Change never succeeded");
+		}
+	}
+
+	#[test]
+	fn rename_and_update() {
+		assert_rollback("update", "src/Module.luau", |tree, id, vfs| {
+			let mut attributes = Attributes::new();
+			attributes.insert(String::from("Updated"), Variant::Bool(true));
+
+			let mut properties = UstrMap::new();
+			properties.insert(Ustr::from("Source"), Variant::String(String::from("return 3")));
+			properties.insert(Ustr::from("Attributes"), Variant::Attributes(attributes));
+
+			let mut update = UpdatedSnapshot::new(id);
+			update.name = Some(String::from("Renamed"));
+			update.properties = Some(properties);
+
+			write::apply_update(update, tree, vfs)
+		});
+	}
+
+	#[test]
+	fn file_to_folder() {
+		assert_rollback("expand", "src/Parent.luau", |tree, id, vfs| {
+			let mut properties = UstrMap::new();
+			properties.insert(Ustr::from("Source"), Variant::String(String::from("return 4")));
+
+			let snapshot = AddedSnapshot {
+				id: Ref::new(),
+				meta: Meta::new(),
+				parent: id,
+				name: String::from("Child"),
+				class: Ustr::from("ModuleScript"),
+				properties,
+				children: vec![],
+			};
+
+			write::apply_addition(snapshot, tree, vfs)
+		});
+	}
+
+	#[test]
+	fn staged_writes() {
+		let dir = TempDir::new("staged");
+		let path = dir.join("Module.luau");
+
+		fs::write(&path, "return 1").unwrap();
+
+		let vfs = Vfs::new(false);
+		let transaction = vfs.transaction();
+
+		vfs.write(&path, b"return 2").unwrap();
+		vfs.write(&dir.join("New.luau"), b"return 3").unwrap();
+
+		// Writes are only visible to the thread of the transaction until it is committed
+		assert_eq!(vfs.read_to_string(&path).unwrap(), "return 2");
+		assert_eq!(
+			vfs.read_dir(&dir).unwrap(),
+			vec![dir.join("Module.luau"), dir.join("New.luau")]
+		);
+		assert_eq!(fs::read_to_string(&path).unwrap(), "return 1");
+		assert!(!dir.join("New.luau").exists());
+
+		thread::scope(|scope| {
+			scope.spawn(|| {
+				assert_eq!(vfs.read_to_string(&path).unwrap(), "return 1");
+				assert_eq!(vfs.read_dir(&dir).unwrap(), vec![path.clone()]);
+			});
+		});
+
+		transaction.commit().unwrap();
+
+		let mut expected = BTreeMap::new();
+		expected.insert(dir.join("Module.luau"), Some(b"return 2".to_vec()));
+		expected.insert(dir.join("New.luau"), Some(b"return 3".to_vec()));
+
+		assert_eq!(disk(&dir), expected);
+	}
+
+	#[test]
+	fn nested_removal() {
+		let dir = TempDir::new("nested");
+		let folder = dir.join("Folder");
+
+		fs::create_dir_all(&folder).unwrap();
+		fs::write(folder.join("init.luau"), "return 1").unwrap();
+		fs::write(folder.join("Child.luau"), "return 2").unwrap();
+
+		let before = disk(&dir);
+		let vfs = Vfs::new(false);
+
+		let outer = vfs.transaction();
+
+		{
+			let inner = vfs.transaction();
+
+			vfs.remove(&folder).unwrap();
+			assert!(!vfs.exists(&folder));
+
+			inner.commit().unwrap();
+		}
+
+		// Removed directory is only moved aside until the outermost transaction is committed
+		assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+		drop(outer);
+		assert_eq!(disk(&dir), before);
+
+		let transaction = vfs.transaction();
+		vfs.remove(&folder).unwrap();
+		transaction.commit().unwrap();
+
+		assert!(disk(&dir).is_empty());
+	}
+}
+
+mod watch {
+	use argon::{
+		core::meta::IgnoreRule,
+		glob::Glob,
+		vfs::{mem_backend::MemBackend, registry::WatchRegistry, Vfs, VfsBackend, VfsEvent},
+	};
+	use std::path::{Path, PathBuf};
+
+	fn backend(dirs: &[&str]) -> MemBackend {
+		let mut backend = MemBackend::new();
+
+		for dir in dirs {
+			backend.create_dir(Path::new(dir)).unwrap();
+		}
+
+		backend.write(Path::new("project/src/init.luau"), b"").unwrap();
+
+		backend
+	}
+
+	/// Delivers synthetic event the same way the processor does
+	fn emit(registry: &mut WatchRegistry, backend: &mut MemBackend, event: VfsEvent) {
+		backend.sender().send(event).unwrap();
+
+		let event = backend.receiver().recv().unwrap();
+		registry.track(backend, &event);
+	}
+
+	fn watched(registry: &WatchRegistry) -> Vec<PathBuf> {
+		registry.watched().into_iter().map(|(path, _)| path).collect()
+	}
+
+	fn paths(paths: &[&str]) -> Vec<PathBuf> {
+		paths.iter().map(PathBuf::from).collect()
+	}
+
+	#[test]
+	fn per_directory() {
+		let mut backend = backend(&["project/src/a/b", "project/src/.argon"]);
+		let mut registry = WatchRegistry::new();
+
+		registry.register(&mut backend, Path::new("project/src"), true).unwrap();
+
+		// Blacklisted directories are never watched
+		assert_eq!(
+			watched(&registry),
+			paths(&["project/src", "project/src/a", "project/src/a/b"])
+		);
+		assert!(registry.watched().iter().all(|(_, recursive)| !recursive));
+
+		// Registering the same root again changes nothing
+		registry.register(&mut backend, Path::new("project/src"), true).unwrap();
+		assert_eq!(watched(&registry).len(), 3);
+	}
+
+	#[test]
+	fn create_after_start() {
+		let mut backend = backend(&["project/src"]);
+		let mut registry = WatchRegistry::new();
+
+		registry.register(&mut backend, Path::new("project/src"), true).unwrap();
+		registry
+			.register(&mut backend, Path::new("project/other"), false)
+			.unwrap();
+
+		// Directories can be created with their contents at once, e.g. by `git checkout`
+		backend.create_dir(Path::new("project/src/feature/server")).unwrap();
+		backend.write(Path::new("project/src/feature/init.luau"), b"").unwrap();
+
+		emit(
+			&mut registry,
+			&mut backend,
+			VfsEvent::Create(PathBuf::from("project/src/feature")),
+		);
+
+		assert_eq!(
+			watched(&registry),
+			paths(&["project/src", "project/src/feature", "project/src/feature/server"])
+		);
+
+		// Files and directories outside of recursive roots are not watched
+		backend.create_dir(Path::new("project/outside")).unwrap();
+
+		emit(
+			&mut registry,
+			&mut backend,
+			VfsEvent::Create(PathBuf::from("project/src/feature/init.luau")),
+		);
+		emit(
+			&mut registry,
+			&mut backend,
+			VfsEvent::Create(PathBuf::from("project/outside")),
+		);
+
+		assert_eq!(watched(&registry).len(), 3);
+	}
+
+	#[test]
+	fn remove() {
+		let mut backend = backend(&["project/src/a/b"]);
+		let mut registry = WatchRegistry::new();
+
+		registry.register(&mut backend, Path::new("project/src"), true).unwrap();
+
+		backend.remove(Path::new("project/src/a")).unwrap();
+		emit(
+			&mut registry,
+			&mut backend,
+			VfsEvent::Delete(PathBuf::from("project/src/a")),
+		);
+
+		assert_eq!(watched(&registry), paths(&["project/src"]));
+
+		// Root stays registered so recreated directories are watched again
+		backend.create_dir(Path::new("project/src/a")).unwrap();
+		emit(
+			&mut registry,
+			&mut backend,
+			VfsEvent::Create(PathBuf::from("project/src/a")),
+		);
+
+		assert_eq!(watched(&registry), paths(&["project/src", "project/src/a"]));
+
+		registry.deregister(&mut backend, Path::new("project/src")).unwrap();
+		assert!(watched(&registry).is_empty());
+	}
+
+	#[test]
+	fn ignore_change() {
+		let mut backend = backend(&["project/src/a/b", "project/src/c"]);
+		let mut registry = WatchRegistry::new();
+
+		let rules = IgnoreRule::from_globs(vec![Glob::new("src/a/**").unwrap()], PathBuf::from("project"));
+
+		registry.set_ignore_rules(&mut backend, rules.clone());
+		registry.register(&mut backend, Path::new("project/src"), true).unwrap();
+
+		assert_eq!(watched(&registry), paths(&["project/src", "project/src/c"]));
+
+		// Ignored directories are not registered when created either
+		backend.create_dir(Path::new("project/src/a/d")).unwrap();
+		emit(
+			&mut registry,
+			&mut backend,
+			VfsEvent::Create(PathBuf::from("project/src/a/d")),
+		);
+
+		assert_eq!(watched(&registry).len(), 2);
+
+		registry.set_ignore_rules(&mut backend, vec![]);
+
+		assert_eq!(
+			watched(&registry),
+			paths(&[
+				"project/src",
+				"project/src/a",
+				"project/src/a/b",
+				"project/src/a/d",
+				"project/src/c"
+			])
+		);
+
+		let rules = IgnoreRule::from_globs(vec![Glob::new("src/c").unwrap()], PathBuf::from("project"));
+		registry.set_ignore_rules(&mut backend, rules);
+
+		assert!(!watched(&registry).contains(&PathBuf::from("project/src/c")));
+		assert_eq!(watched(&registry).len(), 4);
+	}
+
+	#[test]
+	fn vfs_changes() {
+		let vfs = Vfs::from_backend(backend(&["project/src"]));
+
+		vfs.watch(Path::new("project/src"), true).unwrap();
+
+		// Events of directories created by Argon are dropped while the VFS is paused
+		vfs.create_dir(Path::new("project/src/a/b")).unwrap();
+
+		assert_eq!(
+			vfs.watched().into_iter().map(|(path, _)| path).collect::<Vec<_>>(),
+			paths(&["project/src", "project/src/a", "project/src/a/b"])
+		);
+
+		vfs.rename(Path::new("project/src/a/b"), Path::new("project/src/a/c"))
+			.unwrap();
+
+		assert_eq!(
+			vfs.watched().into_iter().map(|(path, _)| path).collect::<Vec<_>>(),
+			paths(&["project/src", "project/src/a", "project/src/a/c"])
+		);
+
+		vfs.remove(Path::new("project/src/a")).unwrap();
+
+		assert_eq!(vfs.watched().len(), 1);
+	}
+}
+
+mod tree_hash {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			snapshot::Snapshot,
+			tree::Tree,
+			tree_hash::{self, TreeHash, TREE_HASH_ATTRIBUTE, TREE_HASH_VERSION},
+		},
+		Properties,
+	};
+	use rbx_dom_weak::{
+		types::{Attributes, CFrame, Matrix3, Ref, Variant, Vector3},
+		Ustr,
+	};
+	use std::{fs, io::BufReader, path::PathBuf};
+
+	fn properties(entries: Vec<(&str, Variant)>) -> Properties {
+		let mut properties = Properties::default();
+
+		for (key, value) in entries {
+			properties.insert(Ustr::from(key), value);
+		}
+
+		properties
+	}
+
+	fn instance(name: &str, class: &str, entries: Vec<(&str, Variant)>) -> Snapshot {
+		Snapshot::new()
+			.with_name(name)
+			.with_class(class)
+			.with_properties(properties(entries))
+	}
+
+	fn hash(snapshot: &Snapshot) -> TreeHash {
+		tree_hash::hash_snapshot(snapshot, false)
+	}
+
+	fn value(variant: Variant) -> TreeHash {
+		hash(&instance("Value", "NumberValue", vec![("Value", variant)]))
+	}
+
+	#[test]
+	fn pinned() {
+		// Has to change together with `TREE_HASH_VERSION` only
+		let snapshot = instance("Root", "NumberValue", vec![("Value", Variant::Float64(0.5))]);
+
+		assert_eq!(TREE_HASH_VERSION, 1);
+		assert_eq!(hash(&snapshot).to_string(), "ee82f0b2311517c0c3ffe530aa912899");
+	}
+
+	#[test]
+	fn property_ordering() {
+		let entries = vec![
+			("Value", Variant::Float64(1.5)),
+			("Archivable", Variant::Bool(true)),
+			("Tags", Variant::String(String::from("tag"))),
+		];
+
+		let mut reversed = entries.clone();
+		reversed.reverse();
+
+		assert_eq!(
+			hash(&instance("Value", "NumberValue", entries)),
+			hash(&instance("Value", "NumberValue", reversed))
+		);
+
+		// Children are sorted by their hashes
+		let children = vec![instance("A", "Folder", vec![]), instance("B", "Folder", vec![])];
+
+		let mut reversed = children.clone();
+		reversed.reverse();
+
+		assert_eq!(
+			hash(&instance("Root", "Folder", vec![]).with_children(children)),
+			hash(&instance("Root", "Folder", vec![]).with_children(reversed))
+		);
+
+		// But property values can't be swapped
+		assert_ne!(
+			hash(&instance(
+				"Value",
+				"Folder",
+				vec![("A", Variant::Int32(1)), ("B", Variant::Int32(2))]
+			)),
+			hash(&instance(
+				"Value",
+				"Folder",
+				vec![("A", Variant::Int32(2)), ("B", Variant::Int32(1))]
+			))
+		);
+	}
+
+	#[test]
+	fn float_edge_cases() {
+		assert_eq!(tree_hash::normalize_float(-0.0).unwrap().to_bits(), 0.0f64.to_bits());
+		assert_eq!(tree_hash::normalize_float(1.23456789), Some(1.234567));
+		assert_eq!(tree_hash::normalize_float(-1.23456789), Some(-1.234567));
+		assert_eq!(tree_hash::normalize_float(f64::INFINITY), Some(999_999_999.0));
+		assert_eq!(tree_hash::normalize_float(f64::NEG_INFINITY), Some(-999_999_999.0));
+		assert_eq!(tree_hash::normalize_float(f64::MAX), Some(999_999_999.0));
+		assert_eq!(tree_hash::normalize_float(f64::NAN), None);
+
+		assert_eq!(value(Variant::Float64(-0.0)), value(Variant::Float64(0.0)));
+		assert_eq!(value(Variant::Float64(0.1234567)), value(Variant::Float64(0.1234561)));
+		assert_ne!(value(Variant::Float64(0.123456)), value(Variant::Float64(0.123457)));
+		assert_eq!(
+			value(Variant::Float64(f64::INFINITY)),
+			value(Variant::Float64(999_999_999.0))
+		);
+		assert_ne!(
+			value(Variant::Float64(f64::INFINITY)),
+			value(Variant::Float64(f64::NEG_INFINITY))
+		);
+		assert_eq!(value(Variant::Float64(f64::NAN)), value(Variant::Float64(-f64::NAN)));
+		assert_ne!(value(Variant::Float64(f64::NAN)), value(Variant::Float64(0.0)));
+
+		// Precision of the type does not matter, its type does
+		assert_eq!(value(Variant::Float32(0.1)), value(Variant::Float32(0.100_000_01)));
+		assert_ne!(value(Variant::Float32(1.0)), value(Variant::Float64(1.0)));
+
+		let cframe = |x: f32| CFrame::new(Vector3::new(x, 0.0, 0.0), Matrix3::identity());
+
+		assert_eq!(
+			value(Variant::CFrame(cframe(-0.0))),
+			value(Variant::CFrame(cframe(0.000_000_1)))
+		);
+		assert_ne!(value(Variant::CFrame(cframe(0.0))), value(Variant::CFrame(cframe(0.5))));
+	}
+
+	#[test]
+	fn unicode_names() {
+		assert_eq!(
+			hash(&instance("日本語", "Folder", vec![])),
+			hash(&instance("日本語", "Folder", vec![]))
+		);
+		assert_ne!(
+			hash(&instance("日本語", "Folder", vec![])),
+			hash(&instance("日本", "Folder", vec![]))
+		);
+
+		// Names are hashed as they are, without Unicode normalization
+		assert_ne!(
+			hash(&instance("caf\u{e9}", "Folder", vec![])),
+			hash(&instance("cafe\u{301}", "Folder", vec![]))
+		);
+
+		// Every string is prefixed with its length
+		assert_ne!(hash(&instance("ab", "c", vec![])), hash(&instance("a", "bc", vec![])));
+	}
+
+	#[test]
+	fn empty_and_missing() {
+		let missing = hash(&instance("Root", "Folder", vec![]));
+
+		assert_eq!(
+			missing,
+			hash(&instance(
+				"Root",
+				"Folder",
+				vec![("Attributes", Variant::Attributes(Attributes::new()))]
+			))
+		);
+		assert_ne!(
+			missing,
+			hash(&instance(
+				"Root",
+				"Folder",
+				vec![("Name", Variant::String(String::new()))]
+			))
+		);
+
+		// Embedded hash is never a part of the hash
+		let embedded = Attributes::new().with(TREE_HASH_ATTRIBUTE, "1:0");
+
+		assert_eq!(
+			missing,
+			hash(&instance(
+				"Root",
+				"Folder",
+				vec![("Attributes", Variant::Attributes(embedded))]
+			))
+		);
+
+		assert_ne!(
+			missing,
+			hash(&instance(
+				"Root",
+				"Folder",
+				vec![("Attributes", Variant::Attributes(Attributes::new().with("Key", true)))]
+			))
+		);
+
+		// Empty folder is different from no folder
+		assert_ne!(
+			missing,
+			hash(&instance("Root", "Folder", vec![]).with_children(vec![instance("Child", "Folder", vec![])]))
+		);
+	}
+
+	#[test]
+	fn excluded() {
+		// Meta and IDs are never hashed
+		let snapshot = instance("Root", "Folder", vec![]);
+
+		assert_eq!(hash(&snapshot), hash(&snapshot.clone().with_id(Ref::new())));
+
+		// Referents are hashed by paths of the referenced instances
+		let linked = |target: Ref| {
+			instance("Root", "Folder", vec![]).with_children(vec![
+				instance("Target", "Part", vec![]).with_id(target),
+				instance("Link", "ObjectValue", vec![("Value", Variant::Ref(target))]),
+			])
+		};
+
+		assert_eq!(hash(&linked(Ref::new())), hash(&linked(Ref::new())));
+
+		let outside = instance("Root", "Folder", vec![]).with_children(vec![
+			instance("Target", "Part", vec![]),
+			instance("Link", "ObjectValue", vec![("Value", Variant::Ref(Ref::new()))]),
+		]);
+
+		assert_ne!(hash(&linked(Ref::new())), hash(&outside));
+	}
+
+	#[test]
+	fn places() {
+		let place =
+			instance("Game", "DataModel", vec![]).with_children(vec![instance("Workspace", "Workspace", vec![])]);
+
+		// Root of the place is not serialized so it is not hashed either
+		assert_eq!(
+			tree_hash::hash_snapshot(&place, true),
+			tree_hash::hash_snapshot(&place.clone().with_name("Renamed"), true)
+		);
+		assert_ne!(
+			tree_hash::hash_snapshot(&place, true),
+			tree_hash::hash_snapshot(&place, false)
+		);
+	}
+
+	#[test]
+	fn snapshot_and_tree() {
+		let snapshot = instance(
+			"Root",
+			"Folder",
+			vec![("Attributes", Variant::Attributes(Attributes::new().with("Key", 1.5)))],
+		)
+		.with_children(vec![
+			instance("Value", "NumberValue", vec![("Value", Variant::Float64(2.5))]),
+			instance("Empty", "Folder", vec![]),
+		]);
+
+		let tree = Tree::new(snapshot.clone());
+
+		assert_eq!(hash(&snapshot), tree_hash::hash_dom(tree.inner(), &[tree.root_ref()]));
+	}
+
+	#[test]
+	fn embedded() {
+		let hash = hash(&instance("Root", "Folder", vec![]));
+
+		assert_eq!(TreeHash::from_embedded(&hash.to_embedded()), Some(hash));
+		assert_eq!(hash.to_string().len(), 32);
+
+		assert_eq!(
+			TreeHash::from_embedded(&format!("{}:{}", TREE_HASH_VERSION + 1, hash)),
+			None
+		);
+		assert_eq!(
+			tree_hash::embedded_version(&format!("{}:{}", TREE_HASH_VERSION + 1, hash)),
+			Some(TREE_HASH_VERSION + 1)
+		);
+		assert_eq!(TreeHash::from_embedded("invalid"), None);
+	}
+
+	#[test]
+	fn build() {
+		let dir = TempDir::new("tree-hash-build");
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{ "name": "Model", "tree": { "$path": "src" } }"#,
+		)
+		.unwrap();
+		fs::write(dir.join("src/init.luau"), "return {}").unwrap();
+		fs::write(dir.join("src/Value.luau"), "return 1").unwrap();
+
+		let core = start(&dir);
+		let hash = core.tree_hash();
+
+		let path: PathBuf = dir.join("Model.rbxm");
+		core.build(&path, false, true).unwrap();
+
+		// Building does not change the tree
+		assert_eq!(core.tree_hash(), hash);
+
+		let dom = rbx_binary::from_reader(BufReader::new(fs::File::open(&path).unwrap())).unwrap();
+		let top_level = dom.root().children().to_vec();
+
+		assert_eq!(tree_hash::find_embedded(&dom, &top_level), Some(hash.to_embedded()));
+		assert_eq!(tree_hash::hash_dom(&dom, &top_level), hash);
+	}
+}
+
+mod logging {
+	use crate::common::TempDir;
+	use argon::{
+		core::{
+			meta::{Context, Meta},
+			processor::write,
+			snapshot::{AddedSnapshot, UpdatedSnapshot},
+			tree::Tree,
+		},
+		logger::{self, LogFormat, Span},
+		middleware::new_snapshot,
+		vfs::Vfs,
+	};
+	use log::{Level, LevelFilter, Log, Metadata, Record};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::{cell::RefCell, fs, mem};
+
+	const PROJECT: &str = r#"{
+	"name": "Logging",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": { "$path": "src" }
+	}
+}"#;
+
+	#[derive(Debug)]
+	struct Captured {
+		level: Level,
+		message: String,
+		fields: Vec<(String, String)>,
+		span: Option<Span>,
+	}
+
+	thread_local! {
+		static CAPTURED: RefCell<Vec<Captured>> = const { RefCell::new(Vec::new()) };
+	}
+
+	/// Logger that keeps records of every thread separately so tests can run in parallel
+	struct CapturingLogger;
+
+	impl Log for CapturingLogger {
+		fn enabled(&self, _metadata: &Metadata) -> bool {
+			true
+		}
+
+		fn log(&self, record: &Record) {
+			let captured = Captured {
+				level: record.level(),
+				message: record.args().to_string(),
+				fields: logger::record_fields(record),
+				span: logger::current_span(),
+			};
+
+			CAPTURED.with_borrow_mut(|records| records.push(captured));
+		}
+
+		fn flush(&self) {}
+	}
+
+	static LOGGER: CapturingLogger = CapturingLogger;
+
+	fn capture() -> Vec<Captured> {
+		log::set_logger(&LOGGER).ok();
+		log::set_max_level(LevelFilter::Trace);
+
+		CAPTURED.with_borrow_mut(mem::take)
+	}
+
+	fn fixture(name: &str) -> (TempDir, Tree, Vfs) {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+		fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+
+		let vfs = Vfs::new(false);
+		let snapshot = new_snapshot(&dir.join("default.project.json"), &Context::default(), &vfs)
+			.unwrap()
+			.unwrap();
+
+		(dir, Tree::new(snapshot), vfs)
+	}
+
+	fn storage(tree: &Tree) -> Ref {
+		let dom = tree.inner();
+
+		*dom.root()
+			.children()
+			.iter()
+			.find(|child| dom.get_by_ref(**child).unwrap().name == "ReplicatedStorage")
+			.unwrap()
+	}
+
+	fn module(parent: Ref) -> AddedSnapshot {
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from("Source"), Variant::String(String::from("return 1")));
+
+		AddedSnapshot {
+			id: Ref::new(),
+			meta: Meta::new(),
+			parent,
+			name: String::from("Module"),
+			class: Ustr::from("ModuleScript"),
+			properties,
+			children: vec![],
+		}
+	}
+
+	fn field<'a>(fields: &'a [(impl AsRef<str>, String)], key: &str) -> Option<&'a str> {
+		fields
+			.iter()
+			.find(|(name, _)| name.as_ref() == key)
+			.map(|(_, value)| value.as_str())
+	}
+
+	#[test]
+	fn operation_spans() {
+		let (dir, mut tree, vfs) = fixture("spans");
+		let snapshot = module(storage(&tree));
+		let id = snapshot.id;
+
+		capture();
+
+		write::apply_addition(snapshot, &mut tree, &vfs).unwrap();
+
+		let added = capture();
+
+		assert!(dir.join("src/Module.luau").exists());
+		assert!(!added.is_empty());
+
+		for record in &added {
+			let span = record.span.as_ref().unwrap();
+
+			assert_eq!(span.name, "apply_addition");
+			assert_eq!(field(&span.fields, "id"), Some(id.to_string().as_str()));
+			assert_eq!(field(&span.fields, "name"), Some("Module"));
+			assert_eq!(field(&span.fields, "class"), Some("ModuleScript"));
+		}
+
+		// Decisions are logged as fields instead of being narrated
+		let located = added.iter().find(|record| record.message == "Located file").unwrap();
+
+		assert_eq!(located.level, Level::Trace);
+		assert!(field(&located.fields, "file_path").unwrap().ends_with("Module.luau"));
+		assert_eq!(field(&located.fields, "has_children"), Some("false"));
+
+		let mut update = UpdatedSnapshot::new(id);
+		update.name = Some(String::from("Renamed"));
+
+		write::apply_update(update, &mut tree, &vfs).unwrap();
+
+		let updated = capture();
+
+		assert!(dir.join("src/Renamed.luau").exists());
+
+		let renamed = updated
+			.iter()
+			.find(|record| record.message == "Renaming instance")
+			.unwrap();
+		let span = renamed.span.as_ref().unwrap();
+
+		assert_eq!(span.name, "apply_update");
+		assert_eq!(field(&span.fields, "name"), Some("Module"));
+		assert_eq!(field(&renamed.fields, "to"), Some("Renamed"));
+
+		write::apply_removal(id, &mut tree, &vfs).unwrap();
+
+		let removed = capture();
+
+		assert!(!dir.join("src/Renamed.luau").exists());
+		assert!(removed
+			.iter()
+			.all(|record| record.span.as_ref().unwrap().name == "apply_removal"));
+
+		// Every operation gets its own correlation ID
+		let ids = [&added[0], &updated[0], &removed[0]].map(|record| record.span.as_ref().unwrap().id);
+
+		assert_ne!(ids[0], ids[1]);
+		assert_ne!(ids[1], ids[2]);
+
+		// Spans are left once the operation finishes
+		assert!(logger::current_span().is_none());
+	}
+
+	#[test]
+	fn nested_spans() {
+		let outer = Span::new("outer").enter();
+
+		{
+			let _inner = Span::new("inner").with_field("key", 1).enter();
+			assert_eq!(logger::current_span().unwrap().name, "inner");
+		}
+
+		assert_eq!(logger::current_span().unwrap().name, "outer");
+
+		drop(outer);
+		assert!(logger::current_span().is_none());
+	}
+
+	/// Formats a record as if it was logged from the write processor
+	fn format_line(target: &str, level: Level, message: &str, fields: &[(&str, &str)], format: LogFormat) -> String {
+		logger::format_record(
+			&Record::builder()
+				.target(target)
+				.level(level)
+				.args(format_args!("{}", message))
+				.key_values(&fields)
+				.module_path(Some("argon::core::processor::write"))
+				.line(Some(20))
+				.build(),
+			format,
+		)
+	}
+
+	#[test]
+	fn human_format() {
+		colored::control::set_override(false);
+
+		// Output of the `argon_*` macros stays the same
+		assert_eq!(
+			format_line(
+				"argon_log",
+				Level::Info,
+				"Serving on: localhost:8000",
+				&[],
+				LogFormat::Human
+			),
+			"INFO: Serving on: localhost:8000"
+		);
+
+		let located = || {
+			format_line(
+				"argon::core::processor::write",
+				Level::Trace,
+				"Located file",
+				&[("id", "1"), ("path", "src/init.luau")],
+				LogFormat::Human,
+			)
+		};
+
+		assert_eq!(
+			located(),
+			"TRACE: Located file id=1 path=src/init.luau [argon::core::processor::write:20]"
+		);
+
+		let span = Span::new("apply_addition").with_field("name", "Module");
+		let id = span.id;
+		let _span = span.enter();
+
+		assert_eq!(
+			located(),
+			format!(
+			"TRACE: Located file id=1 path=src/init.luau {{apply_addition#{} name=Module}} [argon::core::processor::write:20]",
+			id
+		)
+		);
+	}
+
+	#[test]
+	fn json_format() {
+		let span = Span::new("apply_update").with_field("class", "Folder");
+		let id = span.id;
+		let _span = span.enter();
+
+		let line = format_line(
+			"argon::core::processor::write",
+			Level::Trace,
+			"Located file",
+			&[("id", "1")],
+			LogFormat::Json,
+		);
+		let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+		assert!(!line.contains('\n'));
+		assert_eq!(json["level"], "TRACE");
+		assert_eq!(json["message"], "Located file");
+		assert_eq!(json["fields"]["id"], "1");
+		assert_eq!(json["span"]["name"], "apply_update");
+		assert_eq!(json["span"]["id"], id);
+		assert_eq!(json["span"]["class"], "Folder");
+		assert_eq!(json["line"], 20);
+	}
+}
+
+mod deep_trees {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			meta::{Context, NodePath},
+			processor::write,
+			snapshot::{AddedSnapshot, Snapshot},
+			tree::Tree,
+		},
+		middleware::{new_snapshot, project::new_snapshot_node},
+		project::{Project, ProjectNode},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{types::Ref, InstanceBuilder, Ustr, WeakDom};
+	use std::{
+		fs::{self, File},
+		path::Path,
+	};
+
+	/// Deeper than the default `maxTreeDepth` and deep enough
+	/// to overflow the stack of any recursive walk
+	const DEPTH: usize = 10_000;
+
+	fn fixture(name: &str, max_tree_depth: Option<usize>) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		let max_tree_depth = max_tree_depth
+			.map(|depth| format!(r#""maxTreeDepth": {},"#, depth))
+			.unwrap_or_default();
+
+		fs::write(
+			dir.join("default.project.json"),
+			format!(
+				r#"{{
+				"name": "DeepTrees",
+				{}
+				"tree": {{
+					"$className": "DataModel",
+					"ReplicatedStorage": {{ "$path": "src" }}
+				}}
+			}}"#,
+				max_tree_depth
+			),
+		)
+		.unwrap();
+
+		dir
+	}
+
+	fn tree(dir: &Path) -> Tree {
+		let snapshot = new_snapshot(&dir.join("default.project.json"), &Context::default(), &Vfs::new(false))
+			.unwrap()
+			.unwrap();
+
+		Tree::new(snapshot)
+	}
+
+	/// Writes a model of `depth` folders, each one inside the previous one
+	fn write_chain(path: &Path, depth: usize) {
+		let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+		let mut parent = dom.root_ref();
+
+		for _ in 0..depth {
+			parent = dom.insert(parent, InstanceBuilder::new("Folder").with_name("Link"));
+		}
+
+		let top = dom.root().children().to_vec();
+		rbx_binary::to_writer(File::create(path).unwrap(), &dom, &top).unwrap();
+	}
+
+	fn folder(name: &str) -> Snapshot {
+		Snapshot::new().with_id(Ref::new()).with_name(name).with_class("Folder")
+	}
+
+	/// Builds a snapshot of `depth` folders from the innermost one
+	fn chain(depth: usize) -> Snapshot {
+		let mut chain = folder("L");
+
+		for _ in 1..depth {
+			chain = folder("L").with_children(vec![chain]);
+		}
+
+		chain
+	}
+
+	fn storage(tree: &Tree) -> Ref {
+		let dom = tree.inner();
+
+		*dom.root()
+			.children()
+			.iter()
+			.find(|child| dom.get_by_ref(**child).unwrap().name == "ReplicatedStorage")
+			.unwrap()
+	}
+
+	/// Returns depth of the deepest instance, following first children
+	fn depth(tree: &Tree, id: Ref) -> usize {
+		let dom = tree.inner();
+		let mut instance = dom.get_by_ref(id).unwrap();
+		let mut depth = 0;
+
+		while let Some(child) = instance.children().first() {
+			instance = dom.get_by_ref(*child).unwrap();
+			depth += 1;
+		}
+
+		depth
+	}
+
+	fn addition(parent: Ref, snapshot: Snapshot) -> AddedSnapshot {
+		snapshot.into_new(parent)
+	}
+
+	#[test]
+	fn snapshot_too_deep() {
+		let dir = fixture("snapshot-too-deep", None);
+		write_chain(&dir.join("src/Chain.rbxm"), DEPTH);
+
+		let tree = tree(&dir);
+		let storage = storage(&tree);
+
+		// Model is replaced with a placeholder naming the file
+		let chain = tree.inner().get_by_ref(storage).unwrap().children()[0];
+		let error = tree.get_meta(chain).unwrap().load_error.as_ref().unwrap();
+
+		assert_eq!(error.path, dir.join("src/Chain.rbxm"));
+		assert!(error.error.contains("maxTreeDepth"), "{}", error.error);
+		assert!(error.error.contains("Chain.rbxm"));
+		assert_eq!(depth(&tree, storage), 1);
+	}
+
+	#[test]
+	fn snapshot_within_limit() {
+		let dir = fixture("snapshot-within-limit", Some(DEPTH * 2));
+		write_chain(&dir.join("src/Chain.rbxm"), DEPTH);
+
+		let tree = tree(&dir);
+		let storage = storage(&tree);
+
+		assert_eq!(depth(&tree, storage), DEPTH);
+
+		let chain = tree.inner().get_by_ref(storage).unwrap().children()[0];
+		assert!(tree.get_meta(chain).unwrap().load_error.is_none());
+	}
+
+	#[test]
+	fn subtree_snapshot() {
+		let dir = fixture("subtree-snapshot", Some(DEPTH));
+		write_chain(&dir.join("src/Chain.rbxm"), DEPTH / 4);
+
+		let core = start(&dir);
+		let storage = storage(&core.tree());
+
+		// Snapshot is built and dropped level by level
+		let snapshot = core.snapshot(storage, false).unwrap();
+		let mut children = &snapshot.children;
+		let mut depth = 0;
+
+		while let Some(child) = children.first() {
+			children = &child.children;
+			depth += 1;
+		}
+
+		assert_eq!(depth, DEPTH / 4);
+	}
+
+	#[test]
+	fn directories_too_deep() {
+		let dir = fixture("directories-too-deep", Some(3));
+
+		fs::create_dir_all(dir.join("src/A/B/C")).unwrap();
+		fs::write(dir.join("src/A/B/C/Value.txt"), "Value").unwrap();
+
+		let tree = tree(&dir);
+		let storage = storage(&tree);
+
+		// `ReplicatedStorage.A.B` is at the maximum depth so its children fail to load
+		assert_eq!(depth(&tree, storage), 2);
+
+		let a = tree.inner().get_by_ref(storage).unwrap().children()[0];
+		let b = tree.inner().get_by_ref(a).unwrap().children()[0];
+		let error = tree.get_meta(b).unwrap().load_error.as_ref().unwrap();
+
+		assert_eq!(error.path, dir.join("src/A/B"));
+		assert!(error.error.contains("maxTreeDepth"));
+	}
+
+	#[test]
+	fn addition_too_deep() {
+		let dir = fixture("addition-too-deep", None);
+		let mut tree = tree(&dir);
+		let storage = storage(&tree);
+
+		let err = write::apply_addition(addition(storage, chain(DEPTH)), &mut tree, &Vfs::new(false)).unwrap_err();
+		let err = format!("{:#}", err);
+
+		assert!(err.contains("ReplicatedStorage.L"));
+		assert!(err.contains("maxTreeDepth"));
+
+		// Nothing is written before the depth is checked
+		assert!(!dir.join("src/L").exists());
+		assert!(tree.inner().get_by_ref(storage).unwrap().children().is_empty());
+	}
+
+	#[test]
+	fn addition_within_limit() {
+		let dir = fixture("addition-within-limit", None);
+		let mut tree = tree(&dir);
+		let storage = storage(&tree);
+
+		// Siblings are added in their original order, each one with its own descendants
+		let siblings = ["B", "A", "C"].map(|name| chain(3).with_name(name));
+		let snapshot = folder("Siblings").with_children(siblings.to_vec());
+
+		write::apply_addition(addition(storage, snapshot), &mut tree, &Vfs::new(false)).unwrap();
+
+		let dom = tree.inner();
+		let siblings = dom.get_by_ref(storage).unwrap().children()[0];
+		let names: Vec<&str> = dom
+			.get_by_ref(siblings)
+			.unwrap()
+			.children()
+			.iter()
+			.map(|child| dom.get_by_ref(*child).unwrap().name.as_str())
+			.collect();
+
+		assert_eq!(names, ["B", "A", "C"]);
+
+		for name in ["B", "A", "C"] {
+			assert!(dir.join("src/Siblings").join(name).join("L/L").is_dir());
+		}
+
+		// Deep hierarchies are written to disk level by level,
+		// how deep they can go is limited by the path length
+		let deep = addition(storage, chain(500).with_name("Deep"));
+
+		write::apply_addition(deep, &mut tree, &Vfs::new(false)).unwrap();
+
+		let deep = *tree.inner().get_by_ref(storage).unwrap().children().last().unwrap();
+
+		assert_eq!(depth(&tree, deep), 499);
+		assert!(dir.join("src/Deep").join("L/".repeat(498)).is_dir());
+	}
+
+	/// Builds a project node of `depth` folders from the innermost one
+	fn node_chain(depth: usize) -> ProjectNode {
+		let folder = || ProjectNode {
+			class_name: Some(Ustr::from("Folder")),
+			..ProjectNode::default()
+		};
+		let mut chain = folder();
+
+		for _ in 1..depth {
+			let mut node = folder();
+			node.tree.insert(String::from("L"), chain);
+			chain = node;
+		}
+
+		chain
+	}
+
+	#[test]
+	fn project_nodes() {
+		// Project files are limited by the JSON parser
+		// but nodes built by syncback are not
+		let mut node = node_chain(1);
+
+		for name in ["B", "A", "C"] {
+			node.tree.insert(name.to_owned(), node_chain(300));
+		}
+
+		let path = Path::new("default.project.json");
+		let snapshot = new_snapshot_node(
+			"Nodes",
+			path,
+			node,
+			NodePath::new(),
+			&Context::default(),
+			&Vfs::new(false),
+		)
+		.unwrap();
+
+		let names: Vec<&str> = snapshot.children.iter().map(|child| child.name.as_str()).collect();
+		assert_eq!(names, ["A", "B", "C"]);
+
+		for child in &snapshot.children {
+			let mut children = &child.children;
+			let mut depth = 1;
+
+			while let Some(child) = children.first() {
+				assert_eq!(child.name, "L");
+				children = &child.children;
+				depth += 1;
+			}
+
+			assert_eq!(depth, 300);
+		}
+	}
+
+	#[test]
+	fn project_node_addition() {
+		let dir = TempDir::new("project-node-addition");
+		let path = dir.join("default.project.json");
+
+		fs::write(
+			&path,
+			r#"{ "name": "DeepTrees", "tree": { "$className": "DataModel", "Nodes": { "$className": "Folder" } } }"#,
+		)
+		.unwrap();
+
+		let mut tree = tree(&dir);
+		let nodes = tree.find_by_path("Nodes").unwrap();
+
+		// Deep enough for the saved project to be loaded again
+		let snapshot = folder("Siblings").with_children(["B", "A"].map(|name| chain(100).with_name(name)).to_vec());
+
+		write::apply_addition(addition(nodes, snapshot), &mut tree, &Vfs::new(false)).unwrap();
+
+		// Every instance is inserted under its own parent
+		let siblings = tree.find_by_path("Nodes.Siblings").unwrap();
+		let b = tree.find_by_path("Nodes.Siblings.B").unwrap();
+
+		assert_eq!(tree.inner().get_by_ref(siblings).unwrap().children().len(), 2);
+		assert_eq!(tree.inner().get_by_ref(b).unwrap().parent(), siblings);
+		assert_eq!(depth(&tree, b), 99);
+
+		let project = Project::load(&path).unwrap();
+		let mut node = &project.node.tree["Nodes"].tree["Siblings"].tree["B"];
+		let mut levels = 1;
+
+		while let Some(child) = node.tree.get("L") {
+			node = child;
+			levels += 1;
+		}
+
+		assert_eq!(levels, 100);
+
+		// Saved project is loaded with the same hierarchy
+		let tree = self::tree(&dir);
+		let a = tree.find_by_path("Nodes.Siblings.A").unwrap();
+
+		assert_eq!(depth(&tree, a), 99);
+	}
+}
+
+mod frozen {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{changes::Changes, frozen::SyncDirection, processor::read, snapshot::UpdatedSnapshot, tree::Tree},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::{fs, path::PathBuf};
+
+	const PROJECT: &str = r#"{
+	"name": "Frozen",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": { "$path": "src" }
+	}
+}"#;
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+		fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+		fs::write(dir.join("src/Module.luau"), "return 1").unwrap();
+		fs::write(dir.join("src/Other.luau"), "return 2").unwrap();
+
+		dir
+	}
+
+	fn find(tree: &Tree, path: PathBuf) -> Ref {
+		tree.get_ids(&path).unwrap()[0]
+	}
+
+	fn source(tree: &Tree, id: Ref) -> String {
+		match tree.get_instance(id).unwrap().properties.get(&Ustr::from("Source")) {
+			Some(Variant::String(source)) => source.clone(),
+			_ => String::new(),
+		}
+	}
+
+	/// Studio change of the script source
+	fn edit(id: Ref, source: &str) -> Changes {
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from("Source"), Variant::String(source.to_owned()));
+
+		let mut update = UpdatedSnapshot::new(id);
+		update.properties = Some(properties);
+
+		let mut changes = Changes::new();
+		changes.update(update);
+		changes
+	}
+
+	#[test]
+	fn disk_changes() {
+		let dir = fixture("disk");
+		let core = start(&dir);
+
+		assert!(core.freeze("ReplicatedStorage.Module").unwrap());
+		assert!(!core.freeze("ReplicatedStorage.Module").unwrap());
+		assert!(core.freeze("ReplicatedStorage.Missing").is_err());
+
+		fs::write(dir.join("src/Module.luau"), "return 3").unwrap();
+		fs::write(dir.join("src/Other.luau"), "return 4").unwrap();
+
+		let vfs = Vfs::new(false);
+		let mut tree = core.tree();
+		let module = find(&tree, dir.join("src/Module.luau"));
+		let other = find(&tree, dir.join("src/Other.luau"));
+
+		// Frozen instance is left alone, the rest syncs as usual
+		assert!(read::process_changes(module, &mut tree, &vfs).unwrap().is_empty());
+		assert!(!read::process_changes(other, &mut tree, &vfs).unwrap().is_empty());
+
+		assert_eq!(source(&tree, module), "return 1");
+		assert_eq!(source(&tree, other), "return 4");
+
+		drop(tree);
+
+		let frozen = core.frozen();
+
+		assert_eq!(frozen.len(), 1);
+		assert_eq!(frozen[0].path, "ReplicatedStorage.Module");
+		assert_eq!(frozen[0].suppressed_to_studio, 1);
+		assert_eq!(frozen[0].suppressed_to_disk, 0);
+	}
+
+	#[test]
+	fn studio_changes() {
+		let dir = fixture("studio");
+		let core = start(&dir);
+
+		core.freeze("ReplicatedStorage.Module").unwrap();
+
+		let (module, other) = {
+			let tree = core.tree();
+			(
+				find(&tree, dir.join("src/Module.luau")),
+				find(&tree, dir.join("src/Other.luau")),
+			)
+		};
+
+		let mut changes = edit(module, "return 3");
+		changes.extend(edit(other, "return 4"));
+		changes.extend(edit(module, "return 5"));
+
+		let changes = core.tree().hold_frozen(changes);
+
+		assert_eq!(changes.updates.len(), 1);
+		assert_eq!(changes.updates[0].id, other);
+
+		let frozen = core.frozen();
+
+		assert_eq!(frozen[0].suppressed_to_disk, 2);
+		assert_eq!(frozen[0].suppressed(), 2);
+	}
+
+	#[test]
+	fn manual_sync() {
+		let dir = fixture("manual-sync");
+		let core = start(&dir);
+
+		assert!(core
+			.sync_frozen("ReplicatedStorage.Module", SyncDirection::ToDisk)
+			.is_err());
+
+		core.freeze("ReplicatedStorage.Module").unwrap();
+
+		let module = find(&core.tree(), dir.join("src/Module.luau"));
+
+		// Studio changes are written on request
+		let held = core.tree().hold_frozen(edit(module, "return 3"));
+		assert!(held.is_empty());
+
+		assert_eq!(
+			core.sync_frozen("ReplicatedStorage.Module", SyncDirection::ToDisk)
+				.unwrap(),
+			1
+		);
+
+		assert_eq!(fs::read_to_string(dir.join("src/Module.luau")).unwrap(), "return 3");
+		assert_eq!(core.frozen()[0].suppressed(), 0);
+
+		// Disk changes are sent to Studio on request
+		fs::write(dir.join("src/Module.luau"), "return 4").unwrap();
+
+		assert!(read::process_changes(module, &mut core.tree(), &Vfs::new(false))
+			.unwrap()
+			.is_empty());
+
+		assert!(
+			core.sync_frozen("ReplicatedStorage.Module", SyncDirection::ToStudio)
+				.unwrap() > 0
+		);
+		assert_eq!(source(&core.tree(), module), "return 4");
+		assert_eq!(core.pending().len(), 1);
+
+		// Instance stays frozen after manual sync
+		assert_eq!(core.frozen().len(), 1);
+		assert_eq!(core.frozen()[0].suppressed(), 0);
+	}
+
+	#[test]
+	fn persistence() {
+		let dir = fixture("persistence");
+		let core = start(&dir);
+
+		core.freeze("ReplicatedStorage.Module").unwrap();
+
+		let module = find(&core.tree(), dir.join("src/Module.luau"));
+
+		let rename = UpdatedSnapshot {
+			name: Some(String::from("Renamed")),
+			..UpdatedSnapshot::new(module)
+		};
+
+		let mut changes = Changes::new();
+		changes.update(rename);
+
+		core.tree().hold_frozen(changes);
+		core.sync_frozen("ReplicatedStorage.Module", SyncDirection::ToDisk)
+			.unwrap();
+
+		assert!(dir.join("src/Renamed.luau").is_file());
+		assert!(dir.join(".argon/frozen.json").is_file());
+
+		drop(core);
+
+		let core = start(&dir);
+		let frozen = core.frozen();
+
+		assert_eq!(frozen.len(), 1);
+		assert_eq!(frozen[0].path, "ReplicatedStorage.Renamed");
+		assert!(frozen[0].id.is_some());
+
+		assert!(core.unfreeze("ReplicatedStorage.Renamed").unwrap());
+		drop(core);
+
+		assert!(start(&dir).frozen().is_empty());
+	}
+}
+
+mod skipped {
+	use crate::common::{start, TempDir};
+	use argon::{
+		config::Config,
+		core::{
+			meta::Meta,
+			processor::write,
+			skipped::{RuleKind, SkippedOperation, SkippedQuery},
+			snapshot::{AddedSnapshot, UpdatedSnapshot},
+			tree::Tree,
+		},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::{
+		fs,
+		sync::{Mutex, MutexGuard, Once},
+	};
+
+	const CAPACITY: usize = 4;
+
+	/// Tests share the config so they run one at a time
+	fn lock() -> MutexGuard<'static, ()> {
+		static LOCK: Mutex<()> = Mutex::new(());
+		static CONFIG: Once = Once::new();
+
+		let guard = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+
+		CONFIG.call_once(|| {
+			Config::new_mut().max_skipped_entries = CAPACITY;
+		});
+
+		guard
+	}
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src/Generated")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "Skipped",
+			"syncback": {
+				"ignoreNames": ["Temp"],
+				"ignoreGlobs": ["src/Generated/**"]
+			},
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"$path": "src",
+					"$filter": { "excludeClasses": ["Model"] }
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		fs::write(dir.join("src/Generated/Value.txt"), "generated").unwrap();
+
+		dir
+	}
+
+	fn add(tree: &mut Tree, name: &str, class: &str) {
+		let snapshot = AddedSnapshot {
+			id: Ref::new(),
+			meta: Meta::new(),
+			parent: tree.find_by_path("ReplicatedStorage").unwrap(),
+			name: name.to_owned(),
+			class: Ustr::from(class),
+			properties: UstrMap::default(),
+			children: vec![],
+		};
+
+		write::apply_addition(snapshot, tree, &Vfs::new(false)).unwrap();
+	}
+
+	fn update(tree: &mut Tree, path: &str, value: &str) {
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from("Value"), Variant::String(value.to_owned()));
+
+		let mut update = UpdatedSnapshot::new(tree.find_by_path(path).unwrap());
+		update.properties = Some(properties);
+
+		write::apply_update(update, tree, &Vfs::new(false)).unwrap();
+	}
+
+	#[test]
+	fn records_operations() {
+		let _lock = lock();
+		let dir = fixture("records");
+		let core = start(&dir);
+
+		{
+			let mut tree = core.tree();
+
+			add(&mut tree, "Temp", "Folder");
+			add(&mut tree, "Car", "Model");
+			update(&mut tree, "ReplicatedStorage.Generated.Value", "changed");
+
+			let id = tree.find_by_path("ReplicatedStorage.Generated.Value").unwrap();
+			write::apply_removal(id, &mut tree, &Vfs::new(false)).unwrap();
+		}
+
+		// Nothing was written
+		assert!(!dir.join("src/Temp").exists());
+		assert!(!dir.join("src/Car").exists());
+		assert_eq!(
+			fs::read_to_string(dir.join("src/Generated/Value.txt")).unwrap(),
+			"generated"
+		);
+
+		let entries = core.skipped(&SkippedQuery::default());
+		assert_eq!(entries.len(), 4);
+
+		// The most recent first
+		let [collapse, value, model, temp] = entries.as_slice() else {
+			unreachable!()
+		};
+
+		assert_eq!(temp.path, "Skipped.ReplicatedStorage.Temp");
+		assert_eq!(temp.operation, SkippedOperation::Add);
+		assert_eq!(temp.rule.kind, RuleKind::Name);
+		assert_eq!(temp.rule.pattern, "Temp");
+		assert!(temp.file.is_none());
+
+		assert_eq!(model.path, "Skipped.ReplicatedStorage.Car");
+		assert_eq!(model.rule.kind, RuleKind::Class);
+		assert_eq!(model.rule.pattern, "Model");
+
+		// Update and removal of the same instance by the same rule are coalesced
+		assert_eq!(value.path, "Skipped.ReplicatedStorage.Generated.Value");
+		assert_eq!(value.operation, SkippedOperation::Remove);
+		assert_eq!(value.count, 2);
+		assert_eq!(value.rule.kind, RuleKind::Path);
+		assert_eq!(value.rule.pattern, "src/Generated/**");
+		assert_eq!(
+			value.file.as_deref(),
+			Some(dir.join("src/Generated/Value.txt").as_path())
+		);
+
+		// Parent left without children is not collapsed either
+		assert_eq!(collapse.path, "Skipped.ReplicatedStorage.Generated");
+		assert_eq!(collapse.operation, SkippedOperation::Update);
+		assert_eq!(collapse.rule, value.rule);
+
+		// Filtering by rule and time
+		let query = |kind, pattern: Option<&str>, since, until| SkippedQuery {
+			kind,
+			pattern: pattern.map(str::to_owned),
+			since,
+			until,
+		};
+
+		let now = chrono::Utc::now().timestamp();
+
+		assert_eq!(core.skipped(&query(Some(RuleKind::Path), None, None, None)).len(), 2);
+		assert_eq!(core.skipped(&query(None, Some("Model"), None, None)).len(), 1);
+		assert_eq!(
+			core.skipped(&query(Some(RuleKind::Name), Some("Model"), None, None))
+				.len(),
+			0
+		);
+		assert_eq!(
+			core.skipped(&query(None, None, Some(now - 60), Some(now + 60))).len(),
+			4
+		);
+		assert_eq!(core.skipped(&query(None, None, Some(now + 60), None)).len(), 0);
+		assert_eq!(core.skipped(&query(None, None, None, Some(now - 60))).len(), 0);
+
+		let stats = core.skipped_stats();
+
+		assert_eq!(stats.entries, 4);
+		assert_eq!(stats.skips, 5);
+		assert_eq!(stats.evicted, 0);
+	}
+
+	#[test]
+	fn coalescing() {
+		let _lock = lock();
+		let dir = fixture("coalescing");
+		let core = start(&dir);
+
+		{
+			let mut tree = core.tree();
+
+			for value in ["a", "b", "c"] {
+				update(&mut tree, "ReplicatedStorage.Generated.Value", value);
+			}
+
+			// Same name but different instances are kept apart
+			add(&mut tree, "Temp", "Folder");
+			add(&mut tree, "Temp", "Folder");
+		}
+
+		let entries = core.skipped(&SkippedQuery::default());
+		assert_eq!(entries.len(), 3);
+
+		let update = entries.iter().find(|entry| entry.rule.kind == RuleKind::Path).unwrap();
+
+		assert_eq!(update.count, 3);
+		assert!(update.first <= update.last);
+
+		assert!(entries
+			.iter()
+			.filter(|entry| entry.rule.kind == RuleKind::Name)
+			.all(|entry| entry.count == 1));
+
+		let stats = core.skipped_stats();
+
+		assert_eq!(stats.entries, 3);
+		assert_eq!(stats.skips, 5);
+	}
+
+	#[test]
+	fn bound() {
+		let _lock = lock();
+		let dir = fixture("bound");
+		let core = start(&dir);
+
+		{
+			let mut tree = core.tree();
+
+			update(&mut tree, "ReplicatedStorage.Generated.Value", "first");
+
+			for i in 0..CAPACITY + 2 {
+				add(&mut tree, &format!("Car{}", i), "Model");
+			}
+
+			// Skipping again moves the entry to the back
+			update(&mut tree, "ReplicatedStorage.Generated.Value", "last");
+		}
+
+		let entries = core.skipped(&SkippedQuery::default());
+		let paths: Vec<&str> = entries.iter().map(|entry| entry.path.as_str()).collect();
+
+		assert_eq!(
+			paths,
+			[
+				"Skipped.ReplicatedStorage.Generated.Value",
+				"Skipped.ReplicatedStorage.Car5",
+				"Skipped.ReplicatedStorage.Car4",
+				"Skipped.ReplicatedStorage.Car3",
+			]
+		);
+
+		// The first update was evicted, so counting starts over
+		assert_eq!(entries[0].count, 1);
+
+		let stats = core.skipped_stats();
+
+		assert_eq!(stats.entries, CAPACITY);
+		assert_eq!(stats.skips, CAPACITY as u64 + 4);
+		assert_eq!(stats.evicted, 4);
+	}
+}
+
+mod keep_unknowns {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{processor::write, snapshot::UpdatedSnapshot, unknowns::UnknownsGroup},
+		project::{KeepUnknowns, Project, ProjectNode},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::{collections::BTreeMap, fs};
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src/Assets")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "Unknowns",
+			"tree": {
+				"$className": "DataModel",
+				"Workspace": {
+					"$className": "Workspace",
+					"$keepUnknowns": { "classes": ["Camera"], "names": ["Debug*"] },
+					"$properties": { "Gravity": 100 }
+				},
+				"ReplicatedStorage": {
+					"$path": "src",
+					"$keepUnknowns": true
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		fs::write(
+			dir.join("src/Assets/.data.json"),
+			r#"{ "className": "Folder", "keepUnknowns": { "names": ["*.generated"] } }"#,
+		)
+		.unwrap();
+
+		dir
+	}
+
+	fn group(parent: Ref, classes: &[(&str, usize)]) -> UnknownsGroup {
+		UnknownsGroup {
+			parent,
+			classes: classes
+				.iter()
+				.map(|(class, count)| (class.to_string(), *count))
+				.collect::<BTreeMap<_, _>>(),
+		}
+	}
+
+	#[test]
+	fn serialization() {
+		let node: ProjectNode = serde_json::from_str(r#"{ "$keepUnknowns": true }"#).unwrap();
+		assert_eq!(node.keep_unknowns, Some(KeepUnknowns::All(true)));
+
+		let node: ProjectNode =
+			serde_json::from_str(r#"{ "$keepUnknowns": { "classes": ["Camera"], "names": ["Debug*"] } }"#).unwrap();
+
+		let keep_unknowns = node.keep_unknowns.clone().unwrap();
+		let filter = keep_unknowns.filter().unwrap();
+
+		assert_eq!(filter.classes, ["Camera"]);
+		assert!(keep_unknowns.is_kept());
+		assert!(keep_unknowns.matches("Camera", "Main"));
+		assert!(keep_unknowns.matches("Part", "DebugPart"));
+		assert!(!keep_unknowns.matches("Part", "Baseplate"));
+
+		// Both shapes are written back as they were read
+		assert_eq!(serde_json::to_string(&KeepUnknowns::All(false)).unwrap(), "false");
+		assert_eq!(
+			serde_json::to_value(&keep_unknowns).unwrap(),
+			serde_json::json!({ "classes": ["Camera"], "names": ["Debug*"] })
+		);
+
+		// Unknown filter keys are rejected instead of silently keeping everything
+		assert!(serde_json::from_str::<ProjectNode>(r#"{ "$keepUnknowns": { "class": ["Camera"] } }"#).is_err());
+
+		// Filters are combined with other sources of the same instance
+		let merged = keep_unknowns.clone().merge(KeepUnknowns::All(false));
+		assert_eq!(merged, keep_unknowns);
+		assert_eq!(keep_unknowns.merge(true.into()), KeepUnknowns::All(true));
+	}
+
+	#[test]
+	fn snapshot_meta() {
+		let dir = fixture("meta");
+		let core = start(&dir);
+		let tree = core.tree();
+
+		let meta = |path| tree.get_meta(tree.find_by_path(path).unwrap()).unwrap();
+
+		let workspace = meta("Workspace");
+		assert!(workspace.keep_unknowns);
+		assert_eq!(workspace.keep_unknowns_filter.as_ref().unwrap().classes, ["Camera"]);
+
+		let storage = meta("ReplicatedStorage");
+		assert!(storage.keep_unknowns);
+		assert!(storage.keep_unknowns_filter.is_none());
+
+		let assets = meta("ReplicatedStorage.Assets");
+		assert!(assets.keep_unknowns);
+		assert!(assets.get_keep_unknowns().matches("Folder", "Map.generated"));
+
+		// Plain boolean is still sent to clients as before
+		let value = serde_json::to_value(storage).unwrap();
+		assert_eq!(value["keepUnknowns"], true);
+		assert!(value.get("keepUnknownsFilter").is_none());
+	}
+
+	#[test]
+	fn preserved_through_updates() {
+		let dir = fixture("updates");
+		let core = start(&dir);
+
+		{
+			let mut tree = core.tree();
+
+			let mut properties = UstrMap::default();
+			properties.insert(Ustr::from("Gravity"), Variant::Float32(50.0));
+
+			let mut update = UpdatedSnapshot::new(tree.find_by_path("Workspace").unwrap());
+			update.properties = Some(properties);
+
+			write::apply_update(update, &mut tree, &Vfs::new(false)).unwrap();
+		}
+
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+		let workspace = &project.node.tree["Workspace"];
+
+		assert!(fs::read_to_string(dir.join("default.project.json"))
+			.unwrap()
+			.contains(r#""Gravity": 50"#));
+		assert_eq!(
+			serde_json::to_value(&workspace.keep_unknowns).unwrap(),
+			serde_json::json!({ "classes": ["Camera"], "names": ["Debug*"] })
+		);
+	}
+
+	#[test]
+	fn report_aggregation() {
+		let dir = fixture("report");
+		let core = start(&dir);
+
+		let (workspace, storage) = {
+			let tree = core.tree();
+			(
+				tree.find_by_path("Workspace").unwrap(),
+				tree.find_by_path("ReplicatedStorage").unwrap(),
+			)
+		};
+
+		core.report_unknowns(1, vec![group(workspace, &[("Camera", 1), ("Part", 2)])]);
+		core.report_unknowns(
+			2,
+			vec![
+				group(workspace, &[("Camera", 1), ("Part", 3)]),
+				group(storage, &[("Folder", 1)]),
+				// Parents that do not exist are left out
+				group(Ref::new(), &[("Model", 4)]),
+			],
+		);
+
+		let unknowns = core.unknowns();
+		assert_eq!(unknowns.len(), 2);
+
+		// Sorted by path
+		let [storage_summary, workspace_summary] = unknowns.as_slice() else {
+			unreachable!()
+		};
+
+		assert_eq!(workspace_summary.path, "Unknowns.Workspace");
+		assert_eq!(workspace_summary.classes["Camera"], 1);
+		assert_eq!(workspace_summary.classes["Part"], 3);
+		assert_eq!(workspace_summary.total, 4);
+		assert_eq!(workspace_summary.clients, [1, 2]);
+
+		assert_eq!(storage_summary.path, "Unknowns.ReplicatedStorage");
+		assert_eq!(storage_summary.total, 1);
+		assert_eq!(storage_summary.clients, [2]);
+
+		// Newer report replaces the previous one of the client
+		core.report_unknowns(2, vec![]);
+
+		let unknowns = core.unknowns();
+		assert_eq!(unknowns.len(), 1);
+		assert_eq!(unknowns[0].classes["Part"], 2);
+		assert_eq!(unknowns[0].clients, [1]);
+	}
+}
+
+mod project_batch {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			changes::Changes,
+			lifecycle::CoreEvent,
+			meta::Meta,
+			processor::{write, WriteRequest},
+			snapshot::{AddedSnapshot, UpdatedSnapshot},
+			tree::Tree,
+			Core,
+		},
+		project::{self, Project},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::{fs, path::Path, time::Duration};
+	use tokio::time;
+
+	const COUNT: usize = 50;
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "Batch",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"Items": {
+						"$className": "Folder"
+					}
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		dir
+	}
+
+	fn add_items(tree: &mut Tree) {
+		let parent = tree.find_by_path("ReplicatedStorage.Items").unwrap();
+
+		for i in 0..COUNT {
+			let snapshot = AddedSnapshot {
+				id: Ref::new(),
+				meta: Meta::new(),
+				parent,
+				name: format!("Item{}", i),
+				class: Ustr::from("StringValue"),
+				properties: UstrMap::default(),
+				children: vec![],
+			};
+
+			write::apply_addition(snapshot, tree, &Vfs::new(false)).unwrap();
+		}
+	}
+
+	fn items(dir: &Path) -> Vec<String> {
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+		project.node.tree["ReplicatedStorage"].tree["Items"]
+			.tree
+			.keys()
+			.cloned()
+			.collect()
+	}
+
+	#[test]
+	fn single_save() {
+		let dir = fixture("single");
+		let core = start(&dir);
+		let original = fs::read_to_string(dir.join("default.project.json")).unwrap();
+
+		project::begin_batch();
+
+		{
+			let mut tree = core.tree();
+			add_items(&mut tree);
+
+			// Later operations of the batch see nodes added by the earlier ones
+			let mut properties = UstrMap::default();
+			properties.insert(Ustr::from("Value"), Variant::String("changed".into()));
+
+			let mut update = UpdatedSnapshot::new(tree.find_by_path("ReplicatedStorage.Items.Item7").unwrap());
+			update.properties = Some(properties);
+
+			write::apply_update(update, &mut tree, &Vfs::new(false)).unwrap();
+
+			let id = tree.find_by_path("ReplicatedStorage.Items.Item8").unwrap();
+			write::apply_removal(id, &mut tree, &Vfs::new(false)).unwrap();
+		}
+
+		// Nothing is written until the batch ends
+		assert_eq!(fs::read_to_string(dir.join("default.project.json")).unwrap(), original);
+
+		assert_eq!(project::end_batch().unwrap(), 1);
+
+		let items = items(&dir);
+
+		assert_eq!(items.len(), COUNT - 1);
+		assert!(!items.contains(&"Item8".to_owned()));
+		assert!(fs::read_to_string(dir.join("default.project.json"))
+			.unwrap()
+			.contains(r#""Value": "changed""#));
+
+		// Edits outside of batches are saved right away
+		let mut tree = core.tree();
+		let id = tree.find_by_path("ReplicatedStorage.Items.Item0").unwrap();
+		write::apply_removal(id, &mut tree, &Vfs::new(false)).unwrap();
+
+		assert_eq!(self::items(&dir).len(), COUNT - 2);
+	}
+
+	#[test]
+	fn abort() {
+		let dir = fixture("abort");
+		let core = start(&dir);
+		let original = fs::read_to_string(dir.join("default.project.json")).unwrap();
+
+		project::begin_batch();
+		add_items(&mut core.tree());
+		project::abort_batch();
+
+		assert_eq!(fs::read_to_string(dir.join("default.project.json")).unwrap(), original);
+		assert_eq!(project::end_batch().unwrap(), 0);
+	}
+
+	fn update(core: &Core, path: &str, property: &str, value: &str) -> UpdatedSnapshot {
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from(property), Variant::String(value.to_owned()));
+
+		let mut update = UpdatedSnapshot::new(core.tree().find_by_path(path).unwrap());
+		update.properties = Some(properties);
+
+		update
+	}
+
+	#[actix_web::test]
+	async fn failed_operation() {
+		let dir = TempDir::new("failed");
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+		fs::write(dir.join("src/Main.luau"), "return 0").unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "Batch",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"First": { "$className": "StringValue" },
+					"Scripts": { "$path": "src" },
+					"Third": { "$className": "StringValue" }
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		let core = start(&dir);
+		let mut receiver = core.subscribe();
+
+		let mut changes = Changes::new();
+		changes
+			.updates
+			.push(update(&core, "ReplicatedStorage.First", "Value", "first"));
+		changes
+			.updates
+			.push(update(&core, "ReplicatedStorage.Scripts.Main", "Source", "return 1"));
+		changes
+			.updates
+			.push(update(&core, "ReplicatedStorage.Third", "Value", "third"));
+
+		// Writing the script fails once its file is replaced with a directory
+		fs::remove_file(dir.join("src/Main.luau")).unwrap();
+		fs::create_dir(dir.join("src/Main.luau")).unwrap();
+
+		core.processor().write(WriteRequest { changes, client_id: 1 });
+
+		loop {
+			let event = time::timeout(Duration::from_secs(5), receiver.recv())
+				.await
+				.expect("syncback did not finish")
+				.unwrap();
+
+			if let CoreEvent::SyncbackFinished { error, .. } = event {
+				assert!(error.is_some());
+				break;
+			}
+		}
+
+		// Edits of the operation applied before the failure are saved,
+		// so the project file agrees with the tree
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+		let node = |name: &str| &project.node.tree["ReplicatedStorage"].tree[name];
+
+		assert_eq!(
+			node("First").properties[&Ustr::from("Value")]
+				.clone()
+				.resolve_unambiguous()
+				.unwrap(),
+			Variant::String("first".into())
+		);
+		assert!(node("Third").properties.is_empty());
+
+		let tree = core.tree();
+		let value = |path: &str| {
+			tree.get_instance(tree.find_by_path(path).unwrap())
+				.unwrap()
+				.properties
+				.get(&Ustr::from("Value"))
+				.cloned()
+		};
+
+		assert_eq!(value("ReplicatedStorage.First"), Some(Variant::String("first".into())));
+		assert_ne!(value("ReplicatedStorage.Third"), Some(Variant::String("third".into())));
+	}
+}
+
+mod verify_writes {
+	use crate::common::{TempDir, WrappedBackend};
+	use argon::{
+		core::{blobs, meta::SourceIssue, processor::write, snapshot::UpdatedSnapshot, Core},
+		project::Project,
+		vfs::{
+			verify::{self, VerifyStats},
+			Vfs, VfsEvent,
+		},
+	};
+	use rbx_dom_weak::{types::Variant, Ustr, UstrMap};
+	use std::{
+		fs,
+		sync::{
+			atomic::{AtomicUsize, Ordering},
+			Arc, Mutex, MutexGuard,
+		},
+	};
+
+	struct Fixture {
+		dir: TempDir,
+		vfs: Vfs,
+		corrupt: Arc<AtomicUsize>,
+		writes: Arc<AtomicUsize>,
+	}
+
+	/// Stats are shared by the whole process so tests run one at a time
+	fn lock() -> MutexGuard<'static, ()> {
+		static LOCK: Mutex<()> = Mutex::new(());
+		LOCK.lock().unwrap_or_else(|err| err.into_inner())
+	}
+
+	fn fixture(name: &str) -> Fixture {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		fs::write(dir.join("src/Value.txt"), "initial").unwrap();
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "Verify",
+			"verifyWrites": true,
+			"tree": { "$className": "DataModel", "ReplicatedStorage": { "$path": "src" } }
+		}"#,
+		)
+		.unwrap();
+
+		let corrupt = Arc::new(AtomicUsize::new(0));
+		let writes = Arc::new(AtomicUsize::new(0));
+
+		let vfs = Vfs::from_backend(
+			WrappedBackend::new()
+				.on_read({
+					let corrupt = corrupt.clone();
+
+					move |_, contents| {
+						let corrupted = corrupt
+							.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| count.checked_sub(1))
+							.is_ok();
+
+						if corrupted {
+							contents.push(0);
+						}
+					}
+				})
+				.on_write({
+					let writes = writes.clone();
+
+					move |_| {
+						writes.fetch_add(1, Ordering::SeqCst);
+						Ok(())
+					}
+				}),
+		);
+
+		vfs.set_verify_writes(true);
+
+		Fixture {
+			dir,
+			vfs,
+			corrupt,
+			writes,
+		}
+	}
+
+	fn delta(before: &VerifyStats) -> VerifyStats {
+		let after = verify::stats();
+
+		VerifyStats {
+			verified: after.verified - before.verified,
+			mismatches: after.mismatches - before.mismatches,
+			failures: after.failures - before.failures,
+		}
+	}
+
+	#[test]
+	fn retry() {
+		let _lock = lock();
+		let fixture = fixture("retry");
+		let path = fixture.dir.join("src/Value.txt");
+		let stats = verify::stats();
+
+		// The first read back is corrupted, the second one after rewriting is fine
+		fixture.corrupt.store(1, Ordering::SeqCst);
+		fixture.vfs.write(&path, b"changed").unwrap();
+
+		assert_eq!(fixture.writes.load(Ordering::SeqCst), 2);
+		assert_eq!(fs::read_to_string(&path).unwrap(), "changed");
+		assert_eq!(
+			delta(&stats),
+			VerifyStats {
+				verified: 2,
+				mismatches: 1,
+				failures: 0,
+			}
+		);
+		assert_eq!(
+			fixture.vfs.last_write_hash(&path),
+			Some(blobs::digest_bytes(b"changed"))
+		);
+	}
+
+	#[test]
+	fn failure() {
+		let _lock = lock();
+		let fixture = fixture("failure");
+		let path = fixture.dir.join("src/Value.txt");
+		let stats = verify::stats();
+
+		fixture.corrupt.store(usize::MAX, Ordering::SeqCst);
+
+		let err = fixture.vfs.write(&path, b"changed").unwrap_err();
+
+		// Written only once more before giving up
+		assert_eq!(fixture.writes.load(Ordering::SeqCst), 2);
+		assert!(err.to_string().contains(&path.display().to_string()));
+		assert_eq!(
+			delta(&stats),
+			VerifyStats {
+				verified: 2,
+				mismatches: 2,
+				failures: 1,
+			}
+		);
+		assert!(fixture.vfs.last_write_hash(&path).is_none());
+
+		// Nothing is read back when disabled
+		let stats = verify::stats();
+
+		fixture.vfs.set_verify_writes(false);
+		fixture.vfs.write(&path, b"unverified").unwrap();
+
+		assert_eq!(delta(&stats), VerifyStats::default());
+	}
+
+	#[test]
+	fn last_write_hash() {
+		let _lock = lock();
+		let fixture = fixture("hash");
+		let path = fixture.dir.join("src/Value.txt");
+
+		let core = Core::new(Project::load(&fixture.dir.join("default.project.json")).unwrap(), false).unwrap();
+		let mut tree = core.tree();
+		let id = tree.get_ids(&path).unwrap()[0];
+
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from("Value"), Variant::String("synced back".into()));
+
+		let mut update = UpdatedSnapshot::new(id);
+		update.properties = Some(properties);
+
+		write::apply_update(update, &mut tree, &fixture.vfs).unwrap();
+
+		let validate = || tree.get_meta(id).unwrap().source.validate(&fixture.vfs);
+
+		assert_eq!(
+			fixture.vfs.last_write_hash(&path),
+			Some(blobs::digest_bytes(b"synced back"))
+		);
+		assert!(validate().is_empty());
+
+		// Changed behind Argon's back without an event, e.g. by a failing drive
+		fs::write(&path, "corrupted").unwrap();
+
+		assert!(matches!(validate().as_slice(), [SourceIssue::Corrupted(entry)] if entry.path() == path));
+
+		// Changes that were noticed are not reported
+		fixture.vfs.track(&VfsEvent::Write(path.clone()));
+
+		assert!(fixture.vfs.last_write_hash(&path).is_none());
+		assert!(validate().is_empty());
+
+		drop(tree);
+		fs::remove_dir_all(&fixture.dir).ok();
+	}
+
+	#[test]
+	fn project_setting() {
+		let _lock = lock();
+		let fixture = fixture("project");
+		let project_path = fixture.dir.join("default.project.json");
+		let stats = verify::stats();
+
+		let mut project = Project::load(&project_path).unwrap();
+		assert!(project.verifies_writes());
+
+		project.name = "Verified".into();
+		project.save(&project_path).unwrap();
+
+		assert_eq!(delta(&stats).verified, 1);
+		assert_eq!(Project::load(&project_path).unwrap().name, "Verified");
+	}
+}
+
+mod markers {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			meta::Meta,
+			processor::write,
+			snapshot::{AddedSnapshot, UpdatedSnapshot},
+		},
+		vfs::{
+			undo_log::{self, UndoLog},
+			Vfs,
+		},
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::{
+		collections::BTreeMap,
+		fs,
+		path::{Path, PathBuf},
+	};
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src/Folder")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "Markers",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"$path": "src"
+				},
+				"Lighting": {
+					"$properties": {
+						"Brightness": 2
+					}
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		fs::write(dir.join("src/Module.luau"), "return 1").unwrap();
+		fs::write(dir.join("src/Folder/Child.luau"), "return 2").unwrap();
+
+		dir
+	}
+
+	/// Contents of all files in the workspace except for Argon's own
+	fn state(dir: &Path) -> BTreeMap<PathBuf, Option<Vec<u8>>> {
+		fn walk(dir: &Path, root: &Path, state: &mut BTreeMap<PathBuf, Option<Vec<u8>>>) {
+			for entry in fs::read_dir(dir).unwrap() {
+				let path = entry.unwrap().path();
+
+				if path.file_name().unwrap() == ".argon" {
+					continue;
+				}
+
+				let relative = path.strip_prefix(root).unwrap().to_owned();
+
+				if path.is_dir() {
+					state.insert(relative, None);
+					walk(&path, root, state);
+				} else {
+					state.insert(relative, Some(fs::read(&path).unwrap()));
+				}
+			}
+		}
+
+		let mut state = BTreeMap::new();
+		walk(dir, dir, &mut state);
+		state
+	}
+
+	#[test]
+	fn undo_to_marker() {
+		let dir = fixture("undo");
+		let core = start(&dir);
+
+		let vfs = Vfs::new(false);
+		vfs.set_undo_log(Some(undo_log::get(&dir)));
+
+		let before = state(&dir);
+		core.add_marker("before").unwrap();
+
+		// Adding the same marker again is rejected
+		assert!(core.add_marker("before").is_err());
+
+		{
+			let mut tree = core.tree();
+
+			let snapshot = AddedSnapshot {
+				id: Ref::new(),
+				meta: Meta::new(),
+				parent: tree.find_by_path("ReplicatedStorage").unwrap(),
+				name: String::from("Added"),
+				class: Ustr::from("ModuleScript"),
+				properties: UstrMap::from_iter([(Ustr::from("Source"), Variant::String(String::from("return 3")))]),
+				children: vec![],
+			};
+
+			write::apply_addition(snapshot, &mut tree, &vfs).unwrap();
+
+			let mut update = UpdatedSnapshot::new(tree.find_by_path("ReplicatedStorage.Module").unwrap());
+			update.name = Some(String::from("Renamed"));
+
+			write::apply_update(update, &mut tree, &vfs).unwrap();
+		}
+
+		core.add_marker("middle").unwrap();
+
+		{
+			let mut tree = core.tree();
+
+			let mut update = UpdatedSnapshot::new(tree.find_by_path("Lighting").unwrap());
+			update.properties = Some(UstrMap::from_iter([(Ustr::from("Brightness"), Variant::Float32(5.0))]));
+
+			write::apply_update(update, &mut tree, &vfs).unwrap();
+
+			let id = tree.find_by_path("ReplicatedStorage.Folder").unwrap();
+			write::apply_removal(id, &mut tree, &vfs).unwrap();
+		}
+
+		assert!(dir.join("src/Added.luau").exists());
+		assert!(dir.join("src/Renamed.luau").exists());
+		assert!(!dir.join("src/Folder").exists());
+		assert!(fs::read_to_string(dir.join("default.project.json"))
+			.unwrap()
+			.contains('5'));
+
+		let markers = core.markers();
+
+		assert_eq!(markers.len(), 2);
+		assert!(markers[0].operations > markers[1].operations);
+		assert!(markers[1].operations > 0);
+
+		// Markers survive restarts
+		assert_eq!(UndoLog::load(&dir).markers(), markers);
+
+		let count = core.undo_to_marker("before").unwrap();
+
+		assert_eq!(count, markers[0].operations);
+		assert_eq!(state(&dir), before);
+
+		// Later markers are gone once rolled back past them
+		assert!(core.undo_to_marker("middle").is_err());
+
+		let markers = core.markers();
+
+		assert_eq!(markers.len(), 1);
+		assert_eq!(markers[0].name, "before");
+		assert_eq!(markers[0].operations, 0);
+		assert_eq!(UndoLog::load(&dir).markers(), markers);
+
+		core.clear_markers().unwrap();
+
+		assert!(core.markers().is_empty());
+		assert!(UndoLog::load(&dir).markers().is_empty());
+	}
+}
+
+mod integrity {
+	use crate::common::TempDir;
+	use argon::{config::Config, core::Core, project::Project, vfs::Vfs};
+	use std::{
+		fs,
+		sync::{Mutex, MutexGuard, Once},
+		thread,
+		time::{Duration, Instant},
+	};
+
+	const BATCH: usize = 2;
+
+	const PROJECT: &str = r#"{
+	"name": "Integrity",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": {
+			"$path": "src"
+		}
+	}
+}"#;
+
+	/// Tests share the config so they run one at a time
+	fn lock() -> MutexGuard<'static, ()> {
+		static LOCK: Mutex<()> = Mutex::new(());
+		static CONFIG: Once = Once::new();
+
+		let guard = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+
+		CONFIG.call_once(|| {
+			Config::new_mut().integrity_scan_batch = BATCH;
+		});
+
+		guard
+	}
+
+	/// Project file lives on disk while its sources only exist in the in-memory VFS
+	fn start(name: &str) -> (Core, TempDir) {
+		let dir = TempDir::new(name);
+		let project_path = dir.join("default.project.json");
+
+		fs::write(&project_path, PROJECT).unwrap();
+
+		let vfs = Vfs::new_virtual();
+
+		vfs.create_dir(&dir.join("src/Folder/Nested")).unwrap();
+		vfs.write(&project_path, PROJECT.as_bytes()).unwrap();
+		vfs.write(&dir.join("src/Folder/Nested/Deep.luau"), b"return 1")
+			.unwrap();
+		vfs.write(&dir.join("src/Folder/Script.luau"), b"return 2").unwrap();
+		vfs.write(&dir.join("src/Other.luau"), b"return 3").unwrap();
+
+		let core = Core::with_vfs(Project::load(&project_path).unwrap(), vfs).unwrap();
+
+		(core, dir)
+	}
+
+	/// Scans until the scanner has gone over all source paths once
+	fn full_pass(core: &Core) -> usize {
+		let passes = core.integrity_stats().passes;
+		let mut misses = 0;
+
+		while core.integrity_stats().passes == passes {
+			misses += core.scan_integrity();
+		}
+
+		misses
+	}
+
+	fn wait_for(condition: impl Fn() -> bool) -> bool {
+		let start = Instant::now();
+
+		while start.elapsed() < Duration::from_secs(5) {
+			if condition() {
+				return true;
+			}
+
+			thread::sleep(Duration::from_millis(10));
+		}
+
+		false
+	}
+
+	#[test]
+	fn reconciles_missed_deletion() {
+		let _lock = lock();
+		let (core, dir) = start("deletion");
+
+		assert!(core
+			.tree()
+			.find_by_path("ReplicatedStorage.Folder.Nested.Deep")
+			.is_some());
+
+		// The in-memory backend never emits events, so this deletion goes unnoticed
+		core.vfs().remove(&dir.join("src/Folder")).unwrap();
+
+		assert!(core.tree().find_by_path("ReplicatedStorage.Folder").is_some());
+
+		// Descendants of the directory are not reported separately
+		assert_eq!(full_pass(&core), 1);
+
+		assert!(wait_for(|| core
+			.tree()
+			.find_by_path("ReplicatedStorage.Folder")
+			.is_none()));
+		assert!(core.tree().find_by_path("ReplicatedStorage.Other").is_some());
+
+		let stats = core.integrity_stats();
+
+		assert_eq!(stats.misses, 1);
+		assert!(stats.checked >= stats.passes * BATCH as u64);
+
+		// The tree is consistent again
+		assert_eq!(full_pass(&core), 0);
+		assert_eq!(core.integrity_stats().misses, 1);
+	}
+
+	#[test]
+	fn consistent_tree() {
+		let _lock = lock();
+		let (core, _dir) = start("consistent");
+
+		assert_eq!(full_pass(&core), 0);
+		assert_eq!(full_pass(&core), 0);
+
+		let stats = core.integrity_stats();
+
+		assert_eq!(stats.passes, 2);
+		assert_eq!(stats.misses, 0);
+	}
+}
+
+mod lifecycle {
+	use crate::common::{start, TempDir};
+	use actix_web::{
+		http::{header, StatusCode},
+		test::{self, TestRequest},
+		web::Data,
+		App,
+	};
+	use argon::{
+		config::Config,
+		core::{
+			changes::Changes,
+			lifecycle::{CoreEvent, ProjectChanges},
+			meta::Meta,
+			processor::WriteRequest,
+			snapshot::AddedSnapshot,
+		},
+		history::Operation,
+		server::{
+			limits::{Limiter, Limits},
+			Server,
+		},
+		vfs::VfsEvent,
+	};
+	use rbx_dom_weak::{types::Ref, Ustr, UstrMap};
+	use serde_json::json;
+	use std::{fs, path::Path, sync::Arc, time::Duration};
+	use tokio::{
+		sync::broadcast::{
+			error::{RecvError, TryRecvError},
+			Receiver,
+		},
+		time,
+	};
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		write_project(&dir, 8000);
+
+		dir
+	}
+
+	fn write_project(dir: &Path, port: u16) {
+		let project = json!({
+			"name": "Lifecycle",
+			"port": port,
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"$path": "src"
+				}
+			}
+		});
+
+		fs::write(dir.join("default.project.json"), project.to_string()).unwrap();
+	}
+
+	fn request(path: &str, body: serde_json::Value) -> TestRequest {
+		TestRequest::post()
+			.uri(path)
+			.insert_header((header::CONTENT_TYPE, "application/msgpack"))
+			.set_payload(rmp_serde::to_vec_named(&body).unwrap())
+	}
+
+	async fn next(receiver: &mut Receiver<CoreEvent>) -> CoreEvent {
+		time::timeout(Duration::from_secs(5), receiver.recv())
+			.await
+			.expect("event was not emitted")
+			.unwrap()
+	}
+
+	#[actix_web::test]
+	async fn scripted_session() {
+		let dir = fixture("session");
+		let core = Arc::new(start(&dir));
+		let mut receiver = core.subscribe();
+
+		let service = test::init_service(
+			App::new()
+				.app_data(Data::new(Arc::new(Limiter::new(Limits::new(&Config::default())))))
+				.service(Server::scope("", core.clone())),
+		)
+		.await;
+
+		// Client connects
+		let response = test::call_service(
+			&service,
+			request("/subscribe", json!({ "clientId": 1, "name": "Studio" })).to_request(),
+		)
+		.await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		assert_eq!(
+			next(&mut receiver).await,
+			CoreEvent::ClientConnected {
+				client_id: 1,
+				name: String::from("Studio"),
+			}
+		);
+
+		// The whole tree fits into a single chunk
+		let progress = core.start_sync(1, false).unwrap();
+		core.ack_sync(1, 0).unwrap();
+
+		assert_eq!(
+			next(&mut receiver).await,
+			CoreEvent::InitialSyncComplete {
+				client_id: 1,
+				instances: progress.total,
+			}
+		);
+
+		// Client syncs back a new instance
+		let id = Ref::new();
+		let mut changes = Changes::new();
+
+		changes.additions.push(AddedSnapshot {
+			id,
+			meta: Meta::new(),
+			parent: core.tree().find_by_path("ReplicatedStorage").unwrap(),
+			name: String::from("Added"),
+			class: Ustr::from("Folder"),
+			properties: UstrMap::default(),
+			children: vec![],
+		});
+
+		core.processor().write(WriteRequest { changes, client_id: 1 });
+
+		assert_eq!(
+			next(&mut receiver).await,
+			CoreEvent::SyncbackStarted {
+				client_id: 1,
+				changes: 1
+			}
+		);
+
+		let CoreEvent::SyncbackFinished {
+			client_id,
+			results,
+			error,
+		} = next(&mut receiver).await
+		else {
+			panic!("expected finished syncback");
+		};
+
+		assert_eq!(client_id, 1);
+		assert_eq!(error, None);
+		assert_eq!(results.len(), 1);
+		assert_eq!(results[0].operation, Operation::Addition);
+		assert_eq!(results[0].id, id);
+		assert_eq!(results[0].error, None);
+		assert!(dir.join("src/Added").is_dir());
+
+		// Project file changes
+		let project_path = dir.join("default.project.json");
+
+		write_project(&dir, 8001);
+		core.processor().notify(VfsEvent::Write(project_path.clone()));
+
+		assert_eq!(
+			next(&mut receiver).await,
+			CoreEvent::ProjectReloaded {
+				path: project_path.clone(),
+				changes: ProjectChanges {
+					fields: vec![String::from("port")],
+				},
+			}
+		);
+
+		// Project file is deleted
+		fs::remove_file(&project_path).unwrap();
+		core.processor().notify(VfsEvent::Delete(project_path));
+
+		assert!(matches!(next(&mut receiver).await, CoreEvent::Fatal { .. }));
+
+		// Client disconnects
+		let response =
+			test::call_service(&service, request("/unsubscribe", json!({ "clientId": 1 })).to_request()).await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		assert_eq!(
+			next(&mut receiver).await,
+			CoreEvent::ClientDisconnected { client_id: 1 }
+		);
+
+		// Every action emitted exactly one event
+		assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+	}
+
+	#[actix_web::test]
+	async fn lagging_subscriber() {
+		let dir = fixture("lagging");
+		let core = start(&dir);
+		let mut receiver = core.subscribe();
+
+		// Emitting never waits for the subscriber
+		for client_id in 0..1000 {
+			core.emit(CoreEvent::ClientDisconnected { client_id });
+		}
+
+		assert!(matches!(receiver.try_recv(), Err(TryRecvError::Lagged(_))));
+		assert!(receiver.try_recv().is_ok());
+	}
+
+	#[actix_web::test]
+	async fn dropped_core() {
+		let dir = fixture("dropped");
+		let core = start(&dir);
+		let mut receiver = core.subscribe();
+
+		drop(core);
+
+		// Processor stops once its channels are disconnected, without a fatal error
+		let event = time::timeout(Duration::from_secs(5), receiver.recv())
+			.await
+			.expect("processor did not stop");
+
+		assert!(matches!(event, Err(RecvError::Closed)), "{:?}", event);
+	}
+}
+
+mod read_only {
+	use crate::common::TempDir;
+	use argon::{
+		core::{
+			meta::{Meta, ReadOnly, ReadOnlyReason, ReadOnlyRejection},
+			processor::write,
+			skipped::SkippedOperation,
+			snapshot::{AddedSnapshot, UpdatedSnapshot},
+			Core,
+		},
+		project::Project,
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		InstanceBuilder, Ustr, UstrMap, WeakDom,
+	};
+	use std::{
+		collections::BTreeMap,
+		fs,
+		path::{Path, PathBuf},
+	};
+
+	const PROJECT: &str = r#"{
+	"name": "ReadOnly",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": {
+			"$path": "src"
+		}
+	}
+}"#;
+
+	const REASONS: [ReadOnlyReason; 4] = [
+		ReadOnlyReason::ModelFile,
+		ReadOnlyReason::Archive,
+		ReadOnlyReason::CompilerOutput,
+		ReadOnlyReason::PackageInternal,
+	];
+
+	/// Project file lives on disk while its sources only exist in the in-memory VFS
+	fn start(name: &str) -> (Core, TempDir) {
+		let dir = TempDir::new(name);
+		let project_path = dir.join("default.project.json");
+
+		fs::write(&project_path, PROJECT).unwrap();
+
+		let vfs = Vfs::new_virtual();
+
+		vfs.create_dir(&dir.join("src/Sealed/Nested")).unwrap();
+		vfs.write(&project_path, PROJECT.as_bytes()).unwrap();
+		vfs.write(&dir.join("src/Sealed/Nested/Deep.luau"), b"return 1")
+			.unwrap();
+		vfs.write(&dir.join("src/Sealed/Script.luau"), b"return 2").unwrap();
+
+		let core = Core::with_vfs(Project::load(&project_path).unwrap(), vfs).unwrap();
+
+		(core, dir)
+	}
+
+	/// Contents of all files and directories in the directory, to tell whether anything was written
+	fn files(vfs: &Vfs, dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+		let mut files = BTreeMap::new();
+
+		for path in vfs.read_dir(dir).unwrap() {
+			if vfs.is_dir(&path) {
+				files.extend(self::files(vfs, &path));
+				files.insert(path, vec![]);
+			} else {
+				files.insert(path.clone(), vfs.read(&path).unwrap());
+			}
+		}
+
+		files
+	}
+
+	fn rejection(result: anyhow::Result<()>) -> ReadOnlyRejection {
+		result
+			.expect_err("operation was not rejected")
+			.downcast::<ReadOnlyRejection>()
+			.unwrap()
+	}
+
+	#[test]
+	fn rejects_operations() {
+		let (core, dir) = start("operations");
+		let source = dir.join("src/Sealed");
+
+		for reason in REASONS {
+			let mut tree = core.tree();
+			let vfs = core.vfs();
+
+			let sealed = tree.find_by_path("ReplicatedStorage.Sealed").unwrap();
+			let deep = tree.find_by_path("ReplicatedStorage.Sealed.Nested.Deep").unwrap();
+			let script = tree.find_by_path("ReplicatedStorage.Sealed.Script").unwrap();
+
+			let meta = tree.get_meta(sealed).unwrap().clone();
+			tree.update_meta(sealed, meta.with_read_only(ReadOnly::new(reason, &source)));
+
+			let before = files(&vfs, &dir);
+
+			// Property update of a descendant
+			let mut update = UpdatedSnapshot::new(deep);
+			update.properties = Some(UstrMap::from_iter([(
+				Ustr::from("Source"),
+				Variant::String(String::from("return 3")),
+			)]));
+
+			let rejected = rejection(write::apply_update(update, &mut tree, &vfs));
+
+			assert_eq!(rejected.id, deep);
+			assert_eq!(rejected.instance, "ReadOnly.ReplicatedStorage.Sealed.Nested.Deep");
+			assert_eq!(rejected.operation, SkippedOperation::Update);
+			assert_eq!(rejected.reason, reason);
+			assert_eq!(rejected.path, source);
+			assert!(rejected.message.contains(&reason.remedy()));
+
+			// Addition to the read-only instance itself
+			let id = Ref::new();
+			let addition = AddedSnapshot {
+				id,
+				meta: Meta::new(),
+				parent: sealed,
+				name: String::from("Added"),
+				class: Ustr::from("Folder"),
+				properties: UstrMap::default(),
+				children: vec![],
+			};
+
+			let rejected = rejection(write::apply_addition(addition, &mut tree, &vfs));
+
+			assert_eq!(rejected.id, id);
+			assert_eq!(rejected.instance, "ReadOnly.ReplicatedStorage.Sealed.Added");
+			assert_eq!(rejected.operation, SkippedOperation::Add);
+			assert_eq!(rejected.reason, reason);
+
+			// Removal of a descendant
+			let rejected = rejection(write::apply_removal(script, &mut tree, &vfs));
+
+			assert_eq!(rejected.id, script);
+			assert_eq!(rejected.operation, SkippedOperation::Remove);
+			assert_eq!(rejected.reason, reason);
+
+			assert_eq!(files(&vfs, &dir), before);
+			assert!(tree.exists(script));
+			assert!(!tree.exists(id));
+		}
+
+		let stats = core.skipped_stats();
+
+		assert_eq!(stats.read_only, 3 * REASONS.len() as u64);
+	}
+
+	#[test]
+	fn model_files_are_read_only() {
+		let dir = TempDir::new("read-only-model");
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+		fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+
+		let dom =
+			WeakDom::new(InstanceBuilder::new("Model").with_child(InstanceBuilder::new("Part").with_name("Handle")));
+		let mut model = vec![];
+
+		rbx_binary::to_writer(&mut model, &dom, &[dom.root_ref()]).unwrap();
+		fs::write(dir.join("src/Tool.rbxm"), &model).unwrap();
+
+		let core = Core::new(Project::load(&dir.join("default.project.json")).unwrap(), false).unwrap();
+		let mut tree = core.tree();
+
+		let tool = tree.find_by_path("ReplicatedStorage.Tool").unwrap();
+		let handle = tree.find_by_path("ReplicatedStorage.Tool.Handle").unwrap();
+
+		assert_eq!(
+			tree.get_meta(tool).unwrap().read_only,
+			Some(ReadOnly::new(ReadOnlyReason::ModelFile, &dir.join("src/Tool.rbxm")))
+		);
+
+		let mut update = UpdatedSnapshot::new(handle);
+		update.name = Some(String::from("Renamed"));
+
+		let rejected = rejection(write::apply_update(update, &mut tree, &core.vfs()));
+
+		assert_eq!(rejected.reason, ReadOnlyReason::ModelFile);
+		assert_eq!(rejected.path, dir.join("src/Tool.rbxm"));
+		assert_eq!(fs::read(dir.join("src/Tool.rbxm")).unwrap(), model);
+		assert_eq!(fs::read_dir(dir.join("src")).unwrap().count(), 1);
+
+		// Removing the model itself removes the whole file
+		write::apply_removal(tool, &mut tree, &core.vfs()).unwrap();
+
+		assert!(!dir.join("src/Tool.rbxm").exists());
+	}
+}
+
+mod sync_direction {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			frozen::SyncDirection,
+			snapshot::Snapshot,
+			sync_direction::{self, ChangedSide, StudioSubtree, TreeSummary},
+		},
+		Properties,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr,
+	};
+	use std::{fs, path::Path};
+
+	/// Creates a project with `ReplicatedStorage.Shared.Util` and `ReplicatedStorage.Other`
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src").join("Shared")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "SyncDirection",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"$path": "src"
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		fs::write(dir.join("src").join("Shared").join("Util.luau"), "return 1").unwrap();
+		fs::write(dir.join("src").join("Other.luau"), "return 1").unwrap();
+
+		dir
+	}
+
+	/// Summary of what Studio would send, the place is simulated
+	/// with a copy of the project where `edit` changed its files
+	fn studio(dir: &Path, edit: impl FnOnce(&Path)) -> TreeSummary {
+		let copy = dir.with_extension("studio");
+
+		fs::remove_dir_all(&copy).ok();
+		fs::create_dir_all(copy.join("src").join("Shared")).unwrap();
+
+		for path in ["default.project.json", "src/Other.luau", "src/Shared/Util.luau"] {
+			fs::copy(dir.join(path), copy.join(path)).unwrap();
+		}
+
+		edit(&copy);
+
+		let summary = start(&copy).sync_summary();
+		fs::remove_dir_all(&copy).ok();
+
+		summary
+	}
+
+	fn shared(source: &str) -> StudioSubtree {
+		let mut properties = Properties::default();
+		properties.insert(Ustr::from("Source"), Variant::String(source.to_owned()));
+
+		let util = Snapshot::new()
+			.with_id(Ref::new())
+			.with_name("Util")
+			.with_class("ModuleScript")
+			.with_properties(properties);
+
+		StudioSubtree {
+			service: String::from("ReplicatedStorage"),
+			name: String::from("Shared"),
+			instances: vec![Snapshot::new()
+				.with_id(Ref::new())
+				.with_name("Shared")
+				.with_children(vec![util])],
+		}
+	}
+
+	fn edit_util(dir: &Path) {
+		fs::write(dir.join("src").join("Shared").join("Util.luau"), "return 2").unwrap();
+	}
+
+	#[test]
+	fn only_filesystem() {
+		let dir = fixture("filesystem");
+		start(&dir).save_sync_base().unwrap();
+
+		let studio = studio(&dir, |_| {});
+		fs::write(dir.join("src").join("Other.luau"), "return 2").unwrap();
+
+		let core = start(&dir);
+		let plan = core.plan_sync(1, &studio);
+
+		assert_eq!(plan.changed, ChangedSide::Filesystem);
+		assert_eq!(plan.direction, Some(SyncDirection::ToStudio));
+		assert!(plan.is_divergent("ReplicatedStorage", "Other"));
+		assert!(!plan.is_divergent("ReplicatedStorage", "Shared"));
+
+		// Studio's side can't be written when the disk is newer
+		assert!(core.sync_from_studio(1, vec![shared("return 2")]).is_err());
+	}
+
+	#[test]
+	fn only_studio() {
+		let dir = fixture("studio");
+		start(&dir).save_sync_base().unwrap();
+
+		let studio = studio(&dir, edit_util);
+		let core = start(&dir);
+		let plan = core.plan_sync(1, &studio);
+
+		assert_eq!(plan.changed, ChangedSide::Studio);
+		assert_eq!(plan.direction, Some(SyncDirection::ToDisk));
+		assert!(plan.is_divergent("ReplicatedStorage", "Shared"));
+
+		// Syncing before Studio's side is written would revert it
+		assert!(core.start_sync(1, false).is_err());
+
+		let mut other = shared("return 2");
+		other.name = String::from("Other");
+
+		assert_eq!(core.sync_from_studio(1, vec![shared("return 2"), other]).unwrap(), 1);
+		assert_eq!(
+			fs::read_to_string(dir.join("src").join("Shared").join("Util.luau")).unwrap(),
+			"return 2"
+		);
+		assert_eq!(
+			fs::read_to_string(dir.join("src").join("Other.luau")).unwrap(),
+			"return 1"
+		);
+
+		// Both sides agree once the subtrees are written
+		let filesystem = core.sync_summary();
+		assert!(sync_direction::compare(None, &filesystem, &studio).is_empty());
+	}
+
+	#[test]
+	fn both_changed() {
+		let dir = fixture("both");
+		start(&dir).save_sync_base().unwrap();
+
+		let studio = studio(&dir, edit_util);
+		fs::write(dir.join("src").join("Other.luau"), "return 3").unwrap();
+
+		let core = start(&dir);
+		let plan = core.plan_sync(1, &studio);
+
+		assert_eq!(plan.changed, ChangedSide::Both);
+		assert_eq!(plan.direction, None);
+		assert!(plan.is_divergent("ReplicatedStorage", "Shared"));
+		assert!(plan.is_divergent("ReplicatedStorage", "Other"));
+
+		let shared_child = plan.divergent[0]
+			.children
+			.iter()
+			.find(|child| child.name == "Shared")
+			.unwrap();
+
+		assert_eq!(shared_child.base, shared_child.filesystem);
+		assert_ne!(shared_child.base, shared_child.studio);
+
+		assert!(core.start_sync(1, false).is_err());
+		assert!(core.resolve_sync(2, SyncDirection::ToDisk).is_err());
+
+		let resolved = core.resolve_sync(1, SyncDirection::ToDisk).unwrap();
+		assert_eq!(resolved.direction, Some(SyncDirection::ToDisk));
+
+		// Policies choose the side without asking
+		let divergent = plan.divergent.clone();
+
+		assert_eq!(
+			sync_direction::plan(divergent.clone(), "filesystem").direction,
+			Some(SyncDirection::ToStudio)
+		);
+		assert_eq!(
+			sync_direction::plan(divergent.clone(), "Studio").direction,
+			Some(SyncDirection::ToDisk)
+		);
+		assert_eq!(sync_direction::plan(divergent, "prompt").direction, None);
+	}
+
+	#[test]
+	fn no_base() {
+		let dir = fixture("no-base");
+
+		let studio = studio(&dir, edit_util);
+		let plan = start(&dir).plan_sync(1, &studio);
+
+		// The first session syncs from the disk like it always did
+		assert_eq!(plan.changed, ChangedSide::Filesystem);
+		assert_eq!(plan.direction, Some(SyncDirection::ToStudio));
+
+		// Services Studio did not summarize are not compared
+		let mut partial = studio.clone();
+		partial.services.clear();
+
+		assert!(sync_direction::compare(None, &start(&dir).sync_summary(), &partial).is_empty());
+	}
+}
+
+mod storm {
+	use crate::common::TempDir;
+	use argon::{
+		config::Config,
+		core::{processor::storm::StormDetector, queue::PlaceDetails, sync_direction::TreeSummary, Core},
+		project::Project,
+		server::{protocol::Negotiated, Message},
+		vfs::{mem_backend::MemBackend, Vfs, VfsBackend, VfsEvent},
+	};
+	use crossbeam_channel::Sender;
+	use std::{
+		fs,
+		path::Path,
+		thread,
+		time::{Duration, Instant},
+	};
+
+	const PROJECT: &str = r#"{
+	"name": "Storm",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": {
+			"$path": "src"
+		}
+	}
+}"#;
+
+	const FOLDERS: usize = 50;
+	const MODULES: usize = 100;
+	const CLIENT_ID: u32 = 1;
+	const TIMEOUT: Duration = Duration::from_secs(120);
+
+	/// Serves `FOLDERS` folders with `MODULES` modules each from the in-memory VFS,
+	/// returns sender of watcher events as nothing is sent automatically
+	fn serve(dir: &Path) -> (Core, Sender<VfsEvent>) {
+		let project_path = dir.join("default.project.json");
+
+		fs::write(&project_path, PROJECT).unwrap();
+
+		let mut backend = MemBackend::new();
+		let sender = backend.sender();
+
+		backend.write(&project_path, PROJECT.as_bytes()).unwrap();
+
+		for folder in 0..FOLDERS {
+			let folder_path = dir.join("src").join(format!("Folder{}", folder));
+			backend.create_dir(&folder_path).unwrap();
+
+			for module in 0..MODULES {
+				backend
+					.write(&folder_path.join(format!("Module{}.luau", module)), b"return 0")
+					.unwrap();
+			}
+		}
+
+		let core = Core::with_vfs(Project::load(&project_path).unwrap(), Vfs::from_backend(backend)).unwrap();
+
+		core.vfs().set_undo_log(None);
+		core.queue()
+			.subscribe(CLIENT_ID, "Storm", PlaceDetails::default(), Negotiated::default())
+			.unwrap();
+
+		(core, sender)
+	}
+
+	/// Simulates a branch switch: edits every module of all but the last folder,
+	/// adds a new folder and removes the last one, 5000 events in total
+	fn checkout(dir: &Path, core: &Core, sender: &Sender<VfsEvent>) -> usize {
+		let vfs = core.vfs();
+		let src = dir.join("src");
+		let mut events = vec![];
+
+		for folder in 0..FOLDERS - 1 {
+			for module in 0..MODULES {
+				let path = src
+					.join(format!("Folder{}", folder))
+					.join(format!("Module{}.luau", module));
+
+				vfs.write(&path, format!("return {}", folder * module).as_bytes())
+					.unwrap();
+				events.push(VfsEvent::Write(path));
+			}
+		}
+
+		vfs.create_dir(&src.join("Added")).unwrap();
+
+		for module in 0..MODULES - 1 {
+			let path = src.join("Added").join(format!("Module{}.luau", module));
+
+			vfs.write(&path, b"return {}").unwrap();
+			events.push(VfsEvent::Create(path));
+		}
+
+		let removed = src.join(format!("Folder{}", FOLDERS - 1));
+
+		vfs.remove(&removed).unwrap();
+		events.push(VfsEvent::Delete(removed));
+
+		let count = events.len();
+
+		for event in events {
+			sender.send(event).unwrap();
+		}
+
+		count
+	}
+
+	/// Number of change batches the client receives until the queue stays empty for a while
+	fn batches(core: &Core) -> usize {
+		let mut batches = 0;
+
+		while let Some(message) = core
+			.queue()
+			.get_with_timeout(CLIENT_ID, Duration::from_millis(500))
+			.unwrap()
+		{
+			if matches!(message, Message::SyncChanges(_)) {
+				batches += 1;
+			}
+		}
+
+		batches
+	}
+
+	fn wait_until(condition: impl Fn() -> bool) {
+		let start = Instant::now();
+
+		while !condition() {
+			assert!(start.elapsed() < TIMEOUT, "events were not processed in time");
+			thread::sleep(Duration::from_millis(10));
+		}
+	}
+
+	fn summary(core: &Core) -> (TreeSummary, usize) {
+		(core.sync_summary(), core.tree().meta_map().len())
+	}
+
+	#[test]
+	fn burst() {
+		// Naive processing handles every event on its own
+		Config::new_mut().storm_detection = false;
+
+		let naive_dir = TempDir::new("naive");
+		let (naive, sender) = serve(&naive_dir);
+		let count = checkout(&naive_dir, &naive, &sender);
+
+		assert_eq!(count, 5000);
+
+		wait_until(|| naive.processor().processed() >= count as u64);
+
+		let expected = summary(&naive);
+		assert!(batches(&naive) > 1);
+
+		// The rate threshold of one event engages storm mode right away,
+		// so the whole burst is collapsed no matter how fast it is sent
+		{
+			let mut config = Config::new_mut();
+
+			config.storm_detection = true;
+			config.storm_rate_threshold = 1;
+		}
+
+		let storm_dir = TempDir::new("storm");
+		let (storm, sender) = serve(&storm_dir);
+		let start = Instant::now();
+
+		checkout(&storm_dir, &storm, &sender);
+		wait_until(|| storm.processor().processed() >= 1);
+
+		let batches = batches(&storm);
+
+		assert!(start.elapsed() < Duration::from_secs(30));
+		assert_eq!(batches, 1);
+
+		assert_eq!(summary(&storm), expected);
+
+		{
+			let mut config = Config::new_mut();
+			let default = Config::default();
+
+			config.storm_detection = default.storm_detection;
+			config.storm_rate_threshold = default.storm_rate_threshold;
+		}
+	}
+
+	#[test]
+	fn thresholds() {
+		let mut detector = StormDetector::new();
+		let start = Instant::now();
+
+		// Events spread over more than a second never reach the rate
+		for index in 0..10 {
+			assert!(!detector.check(start + Duration::from_millis(200 * index), 0, 100, 10));
+		}
+
+		// Ten events within a second do
+		let burst = start + Duration::from_secs(5);
+
+		for index in 0..9 {
+			assert!(!detector.check(burst + Duration::from_millis(index), 0, 100, 10));
+		}
+
+		assert!(detector.check(burst + Duration::from_millis(9), 0, 100, 10));
+
+		// The rate starts over after the storm
+		assert!(!detector.check(burst + Duration::from_millis(10), 0, 100, 10));
+
+		// So do many events waiting to be processed
+		assert!(detector.check(burst + Duration::from_millis(11), 100, 100, 10));
+
+		// Zero disables the threshold
+		assert!(!detector.check(burst + Duration::from_millis(12), 1000, 0, 0));
+	}
+}
+
+mod property_reports {
+	use crate::common::TempDir;
+	use argon::{
+		codes::ErrorCode,
+		constants::MAX_PROPERTY_OUTCOMES,
+		core::{
+			meta::Meta,
+			processor::write,
+			property_reports::{self, PropertyOutcome, PropertyReport},
+			snapshot::{AddedSnapshot, UpdatedSnapshot},
+			Core,
+		},
+		project::Project,
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant, Vector3},
+		Ustr, UstrMap,
+	};
+	use std::fs;
+
+	const PROJECT: &str = r#"{
+	"name": "PropertyReports",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": {
+			"$path": "src"
+		}
+	},
+	"syncback": {
+		"ignoreProperties": ["Locked"]
+	},
+	"clampPropertyRanges": true
+}"#;
+
+	/// Project file lives on disk while its sources only exist in the in-memory VFS
+	fn start(name: &str) -> (Core, TempDir) {
+		let dir = TempDir::new(name);
+		let project_path = dir.join("default.project.json");
+
+		fs::write(&project_path, PROJECT).unwrap();
+
+		let vfs = Vfs::new_virtual();
+
+		vfs.create_dir(&dir.join("src")).unwrap();
+		vfs.write(&project_path, PROJECT.as_bytes()).unwrap();
+
+		let core = Core::with_vfs(Project::load(&project_path).unwrap(), vfs).unwrap();
+
+		(core, dir)
+	}
+
+	fn properties(properties: impl IntoIterator<Item = (&'static str, Variant)>) -> UstrMap<Variant> {
+		properties
+			.into_iter()
+			.map(|(property, value)| (Ustr::from(property), value))
+			.collect()
+	}
+
+	#[test]
+	fn reports_every_category() {
+		let (core, _dir) = start("categories");
+		let mut tree = core.tree();
+		let vfs = core.vfs();
+
+		let id = Ref::new();
+		let addition = AddedSnapshot {
+			id,
+			meta: Meta::new(),
+			parent: tree.find_by_path("ReplicatedStorage").unwrap(),
+			name: String::from("Part"),
+			class: Ustr::from("Part"),
+			properties: properties([
+				("Anchored", Variant::Bool(true)),
+				("Locked", Variant::Bool(true)),
+				("AssemblyMass", Variant::Float32(1.0)),
+				("Bogus", Variant::Bool(true)),
+				("CastShadow", Variant::Bool(true)),
+				("size", Variant::Vector3(Vector3::new(2.0, 2.0, 2.0))),
+				("Reflectance", Variant::Float32(2.0)),
+			]),
+			children: vec![],
+		};
+
+		property_reports::begin();
+		write::apply_addition(addition, &mut tree, &vfs).unwrap();
+
+		assert_eq!(
+			property_reports::finish(),
+			vec![PropertyReport {
+				id,
+				class: String::from("Part"),
+				properties: vec![
+					PropertyOutcome::skipped("AssemblyMass", ErrorCode::ReadOnly),
+					PropertyOutcome::skipped("Bogus", ErrorCode::Unknown),
+					PropertyOutcome::skipped("CastShadow", ErrorCode::DefaultStripped),
+					PropertyOutcome::skipped("Locked", ErrorCode::Filtered),
+					PropertyOutcome::transformed("Reflectance", ErrorCode::Clamped),
+					PropertyOutcome::transformed("size", ErrorCode::CanonicalRename).with_renamed("Size"),
+					PropertyOutcome::applied("Anchored"),
+				],
+				truncated: 0,
+			}]
+		);
+
+		let instance = tree.get_instance(id).unwrap();
+
+		assert_eq!(
+			instance.properties.get(&Ustr::from("Reflectance")),
+			Some(&Variant::Float32(1.0))
+		);
+		assert!(instance.properties.contains_key(&Ustr::from("Size")));
+		assert!(!instance.properties.contains_key(&Ustr::from("size")));
+		assert!(!instance.properties.contains_key(&Ustr::from("AssemblyMass")));
+
+		// Updates keep values equal to the default, they may reset a changed one
+		let mut update = UpdatedSnapshot::new(id);
+		update.properties = Some(properties([("Anchored", Variant::Bool(false))]));
+
+		property_reports::begin();
+		write::apply_update(update, &mut tree, &vfs).unwrap();
+
+		assert_eq!(
+			property_reports::finish(),
+			vec![PropertyReport {
+				id,
+				class: String::from("Part"),
+				properties: vec![PropertyOutcome::applied("Anchored")],
+				truncated: 0,
+			}]
+		);
+	}
+
+	#[test]
+	fn truncates_large_reports() {
+		let (core, _dir) = start("truncated");
+		let mut tree = core.tree();
+		let vfs = core.vfs();
+
+		let id = Ref::new();
+		let mut unknown: UstrMap<Variant> = (0..MAX_PROPERTY_OUTCOMES)
+			.map(|index| (Ustr::from(&format!("Bogus{:03}", index)), Variant::Bool(true)))
+			.collect();
+
+		unknown.insert(Ustr::from("Anchored"), Variant::Bool(true));
+		unknown.insert(Ustr::from("Archivable"), Variant::Bool(false));
+
+		let addition = AddedSnapshot {
+			id,
+			meta: Meta::new(),
+			parent: tree.find_by_path("ReplicatedStorage").unwrap(),
+			name: String::from("Part"),
+			class: Ustr::from("Part"),
+			properties: unknown,
+			children: vec![],
+		};
+
+		property_reports::begin();
+		write::apply_addition(addition, &mut tree, &vfs).unwrap();
+
+		let reports = property_reports::finish();
+
+		// Applied properties are the first to go
+		assert_eq!(reports.len(), 1);
+		assert_eq!(reports[0].properties.len(), MAX_PROPERTY_OUTCOMES);
+		assert_eq!(reports[0].truncated, 2);
+		assert!(reports[0]
+			.properties
+			.iter()
+			.all(|outcome| outcome.code == Some(ErrorCode::Unknown)));
+
+		// Nothing is collected outside of `begin` and `finish`
+		let mut update = UpdatedSnapshot::new(id);
+		update.properties = Some(properties([("Anchored", Variant::Bool(false))]));
+
+		write::apply_update(update, &mut tree, &vfs).unwrap();
+		assert!(property_reports::finish().is_empty());
+	}
+}
+
+mod ref_properties {
+	use crate::common::{start, TempDir};
+	use argon::{
+		core::{
+			processor::{read, write},
+			snapshot::UpdatedSnapshot,
+			tree::Tree,
+		},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::{
+		types::{CFrame, Matrix3, Ref, Variant, Vector3},
+		Ustr, UstrMap,
+	};
+	use serde_json::Value;
+	use std::{fs, path::Path};
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		for model in ["Car/Body", "Car/Handle", "Lamp"] {
+			fs::create_dir_all(dir.join("src").join(model)).unwrap();
+		}
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "RefProperties",
+			"tree": {
+				"$className": "DataModel",
+				"Workspace": {
+					"$path": "src"
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		fs::write(
+			dir.join("src/Car/.data.json"),
+			r#"{ "className": "Model", "properties": { "PrimaryPart": "./Body" } }"#,
+		)
+		.unwrap();
+
+		fs::write(dir.join("src/Car/Body/.data.json"), r#"{ "className": "Part" }"#).unwrap();
+		fs::write(dir.join("src/Car/Handle/.data.json"), r#"{ "className": "Part" }"#).unwrap();
+		fs::write(dir.join("src/Lamp/.data.json"), r#"{ "className": "Model" }"#).unwrap();
+
+		dir
+	}
+
+	fn data(dir: &Path, model: &str) -> Value {
+		let path = dir.join("src").join(model).join(".data.json");
+		serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+	}
+
+	fn property(tree: &Tree, path: &str, property: &str) -> Option<Variant> {
+		let id = tree.find_by_path(path)?;
+		tree.get_instance(id)?.properties.get(&Ustr::from(property)).cloned()
+	}
+
+	fn find(tree: &Tree, path: &str) -> Ref {
+		tree.find_by_path(path).unwrap()
+	}
+
+	fn update(tree: &mut Tree, id: Ref, properties: Vec<(&str, Variant)>) {
+		let mut update = UpdatedSnapshot::new(id);
+		update.properties = Some(UstrMap::from_iter(
+			properties
+				.into_iter()
+				.map(|(property, value)| (Ustr::from(property), value)),
+		));
+
+		write::apply_update(update, tree, &Vfs::new(false)).unwrap();
+	}
+
+	fn rename(tree: &mut Tree, id: Ref, name: &str) {
+		let mut update = UpdatedSnapshot::new(id);
+		update.name = Some(String::from(name));
+
+		write::apply_update(update, tree, &Vfs::new(false)).unwrap();
+	}
+
+	#[test]
+	fn read_paths() {
+		let dir = fixture("read");
+		let core = start(&dir);
+		let tree = core.tree();
+
+		let car = find(&tree, "Workspace.Car");
+		let body = find(&tree, "Workspace.Car.Body");
+
+		assert_eq!(
+			property(&tree, "Workspace.Car", "PrimaryPart"),
+			Some(Variant::Ref(body))
+		);
+		assert_eq!(tree.get_referrers(body), vec![car]);
+	}
+
+	#[test]
+	fn assignment() {
+		let dir = fixture("assignment");
+		let core = start(&dir);
+		let mut tree = core.tree();
+
+		let car = find(&tree, "Workspace.Car");
+		let lamp = find(&tree, "Workspace.Lamp");
+		let handle = find(&tree, "Workspace.Car.Handle");
+		let body = find(&tree, "Workspace.Car.Body");
+
+		let pivot = CFrame::new(Vector3::new(1.0, 2.0, 3.0), Matrix3::identity());
+
+		update(
+			&mut tree,
+			car,
+			vec![
+				("PrimaryPart", Variant::Ref(handle)),
+				("WorldPivot", Variant::CFrame(pivot)),
+			],
+		);
+		update(&mut tree, lamp, vec![("PrimaryPart", Variant::Ref(body))]);
+
+		// Paths are relative to the nearest common ancestor of both instances
+		assert_eq!(data(&dir, "Car")["properties"]["PrimaryPart"], "./Handle");
+		assert_eq!(data(&dir, "Lamp")["properties"]["PrimaryPart"], "../Car/Body");
+		assert!(data(&dir, "Car")["properties"].get("WorldPivot").is_some());
+
+		drop(tree);
+		drop(core);
+
+		// References are restored after the next resync
+		let core = start(&dir);
+		let tree = core.tree();
+
+		assert_eq!(
+			property(&tree, "Workspace.Car", "PrimaryPart"),
+			Some(Variant::Ref(find(&tree, "Workspace.Car.Handle")))
+		);
+		assert_eq!(
+			property(&tree, "Workspace.Lamp", "PrimaryPart"),
+			Some(Variant::Ref(find(&tree, "Workspace.Car.Body")))
+		);
+		assert_eq!(
+			property(&tree, "Workspace.Car", "WorldPivot"),
+			Some(Variant::CFrame(pivot))
+		);
+	}
+
+	#[test]
+	fn target_rename() {
+		let dir = fixture("rename");
+		let core = start(&dir);
+		let mut tree = core.tree();
+
+		let car = find(&tree, "Workspace.Car");
+		let lamp = find(&tree, "Workspace.Lamp");
+		let body = find(&tree, "Workspace.Car.Body");
+
+		update(&mut tree, lamp, vec![("PrimaryPart", Variant::Ref(body))]);
+
+		rename(&mut tree, body, "Chassis");
+
+		assert_eq!(data(&dir, "Car")["properties"]["PrimaryPart"], "./Chassis");
+		assert_eq!(data(&dir, "Lamp")["properties"]["PrimaryPart"], "../Car/Chassis");
+
+		// Renaming an ancestor of the target changes paths that go through it
+		rename(&mut tree, car, "Truck");
+
+		assert_eq!(data(&dir, "Truck")["properties"]["PrimaryPart"], "./Chassis");
+		assert_eq!(data(&dir, "Lamp")["properties"]["PrimaryPart"], "../Truck/Chassis");
+
+		drop(tree);
+		drop(core);
+
+		let core = start(&dir);
+		let tree = core.tree();
+
+		assert_eq!(
+			property(&tree, "Workspace.Lamp", "PrimaryPart"),
+			Some(Variant::Ref(find(&tree, "Workspace.Truck.Chassis")))
+		);
+	}
+
+	#[test]
+	fn deferred_resolution() {
+		let dir = fixture("deferred");
+		let core = start(&dir);
+		let vfs = Vfs::new(false);
+		let mut tree = core.tree();
+
+		let car = find(&tree, "Workspace.Car");
+
+		// Target is added in the same batch as the reference to it
+		fs::create_dir_all(dir.join("src/Car/Wheel")).unwrap();
+		fs::write(dir.join("src/Car/Wheel/.data.json"), r#"{ "className": "Part" }"#).unwrap();
+		fs::write(
+			dir.join("src/Car/.data.json"),
+			r#"{ "className": "Model", "properties": { "PrimaryPart": "./Wheel" } }"#,
+		)
+		.unwrap();
+
+		let changes = read::process_changes(car, &mut tree, &vfs).unwrap();
+		let wheel = find(&tree, "Workspace.Car.Wheel");
+
+		assert_eq!(
+			property(&tree, "Workspace.Car", "PrimaryPart"),
+			Some(Variant::Ref(wheel))
+		);
+		assert_eq!(tree.get_referrers(wheel), vec![car]);
+
+		let update = changes.updates.iter().find(|update| update.id == car).unwrap();
+		assert_eq!(
+			update.properties.as_ref().unwrap().get(&Ustr::from("PrimaryPart")),
+			Some(&Variant::Ref(wheel))
+		);
+	}
+
+	#[test]
+	fn external_placeholder() {
+		let dir = fixture("external");
+		let core = start(&dir);
+		let vfs = Vfs::new(false);
+		let mut tree = core.tree();
+
+		let lamp = find(&tree, "Workspace.Lamp");
+
+		// Instance that is not synced, e.g. one of a service outside of the project
+		let external = Ref::new();
+
+		update(&mut tree, lamp, vec![("PrimaryPart", Variant::Ref(external))]);
+
+		assert_eq!(data(&dir, "Lamp")["properties"]["PrimaryPart"], "@external");
+
+		// Placeholder keeps the value that is already there
+		fs::write(
+			dir.join("src/Lamp/.data.json"),
+			r#"{ "className": "Model", "properties": { "PrimaryPart": "@external", "LevelOfDetail": "Disabled" } }"#,
+		)
+		.unwrap();
+
+		read::process_changes(lamp, &mut tree, &vfs).unwrap();
+
+		assert_eq!(
+			property(&tree, "Workspace.Lamp", "PrimaryPart"),
+			Some(Variant::Ref(external))
+		);
+
+		drop(tree);
+		drop(core);
+
+		// There is nothing to point at after the resync
+		let core = start(&dir);
+		let tree = core.tree();
+
+		assert_eq!(property(&tree, "Workspace.Lamp", "PrimaryPart"), None);
+	}
+}
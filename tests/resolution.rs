@@ -11,7 +11,7 @@ mod unresolved_value {
 	// Based on Rojo's resolution::test (https://github.com/rojo-rbx/rojo/blob/master/src/resolution.rs#L249)
 	fn resolve(class: &str, property: &str, value: &str) -> Variant {
 		let unresolved: UnresolvedValue = serde_json::from_str(value).unwrap();
-		unresolved.resolve(class, property).unwrap()
+		unresolved.resolve(class, property, None).unwrap()
 	}
 
 	fn resolve_unambiguous(value: &str) -> Variant {
@@ -500,7 +500,7 @@ mod resolved_value {
 	fn cframe() {
 		assert_eq(
 			from_variant(CFrame::new(Vector3::new(1.2, 3.4, 5.6), Matrix3::identity())),
-			json!([1.2, 3.4, 5.6, 1, 0, 0, 0, 1, 0, 0, 0, 1]),
+			json!({"position": [1.2, 3.4, 5.6]}),
 		);
 	}
 
@@ -635,7 +635,7 @@ mod resolved_value {
 	fn optional_cframe() {
 		assert_eq(
 			from_variant(CFrame::new(Vector3::new(1.2, 3.4, 5.6), Matrix3::identity())),
-			json!([1.2, 3.4, 5.6, 1, 0, 0, 0, 1, 0, 0, 0, 1]),
+			json!({"position": [1.2, 3.4, 5.6]}),
 		);
 	}
 
@@ -758,3 +758,423 @@ mod resolved_value {
 		assert_eq(from_variant(Vector3int16::new(1, 2, 3)), json!([1, 2, 3]));
 	}
 }
+
+mod cache {
+	use argon::resolution::{find_descriptor, find_descriptor_uncached, find_enum, UnresolvedValue};
+	use rbx_dom_weak::types::{Enum, Variant};
+	use std::{ptr, time::Instant};
+
+	const PROPERTIES: [(&str, &str); 8] = [
+		("Part", "Anchored"),
+		("Part", "Material"),
+		("Part", "Name"),
+		("MeshPart", "Size"),
+		("TextLabel", "TextXAlignment"),
+		("Frame", "Visible"),
+		("Part", "NotAProperty"),
+		("NotAClass", "Name"),
+	];
+
+	#[test]
+	fn same_descriptors() {
+		// Second pass hits the cache
+		for _ in 0..2 {
+			for (class, property) in PROPERTIES {
+				let cached = find_descriptor(class, property);
+				let uncached = find_descriptor_uncached(class, property);
+
+				match (cached, uncached) {
+					(Some(cached), Some(uncached)) => assert!(ptr::eq(cached, uncached), "{}.{}", class, property),
+					(None, None) => {}
+					_ => panic!("Cached descriptor of {}.{} differs", class, property),
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn enum_tables() {
+		for (name, descriptor) in &rbx_reflection_database::get().enums {
+			let table = find_enum(name).unwrap();
+
+			assert!(ptr::eq(table.descriptor, descriptor));
+			assert!(ptr::eq(table, find_enum(name).unwrap()));
+
+			for (item, value) in &descriptor.items {
+				let name = table.name_of(*value).unwrap();
+
+				assert_eq!(descriptor.items[name], *value);
+				assert_eq!(table.value_of(item), Some(*value));
+			}
+		}
+
+		assert!(find_enum("NotAnEnum").is_none());
+	}
+
+	#[test]
+	fn enum_round_trip() {
+		let value = UnresolvedValue::from_variant(Variant::Enum(Enum::from_u32(256)), "Part", "Material");
+
+		assert_eq!(serde_json::to_string(&value).unwrap(), r#""Plastic""#);
+		assert_eq!(
+			value.resolve("Part", "Material", None).unwrap(),
+			Variant::Enum(Enum::from_u32(256))
+		);
+	}
+
+	/// Synthetic workload of mixed property resolutions,
+	/// run with `cargo test --release -- --ignored --nocapture`
+	#[test]
+	#[ignore]
+	fn benchmark_200k_properties() {
+		let run = |find: fn(&str, &str) -> bool| {
+			let start = Instant::now();
+
+			for index in 0..200_000 {
+				let (class, property) = PROPERTIES[index % PROPERTIES.len()];
+				assert_eq!(
+					find(class, property),
+					!class.starts_with("Not") && !property.starts_with("Not")
+				);
+			}
+
+			start.elapsed()
+		};
+
+		let uncached = run(|class, property| find_descriptor_uncached(class, property).is_some());
+		let cached = run(|class, property| find_descriptor(class, property).is_some());
+
+		println!("Uncached: {:?}, cached: {:?}", uncached, cached);
+
+		let start = Instant::now();
+
+		for index in 0..200_000 {
+			let variant = Variant::Enum(Enum::from_u32(if index % 2 == 0 { 256 } else { 512 }));
+			UnresolvedValue::from_variant(variant, "Part", "Material");
+		}
+
+		println!("Enum serialization: {:?}", start.elapsed());
+	}
+}
+
+mod ranges {
+	use argon::{
+		ranges::{PropertyRanges, Range},
+		resolution::UnresolvedValue,
+	};
+	use rbx_dom_weak::types::{Variant, Vector3};
+	use std::{collections::BTreeMap, path::Path};
+
+	fn resolve(class: &str, property: &str, value: &str, ranges: &PropertyRanges) -> Variant {
+		let unresolved: UnresolvedValue = serde_json::from_str(value).unwrap();
+
+		unresolved
+			.resolve_checked(class, property, ranges, Some(Path::new("src/Part.data.json")))
+			.unwrap()
+	}
+
+	#[test]
+	fn in_range() {
+		for ranges in [PropertyRanges::default(), PropertyRanges::new(BTreeMap::new(), true)] {
+			assert_eq!(resolve("Part", "Transparency", "0.5", &ranges), Variant::Float32(0.5));
+			assert_eq!(resolve("Part", "Transparency", "1", &ranges), Variant::Float32(1.0));
+			assert_eq!(resolve("Sound", "Volume", "10", &ranges), Variant::Float32(10.0));
+			assert_eq!(
+				resolve("Part", "Size", "[4, 1, 2]", &ranges),
+				Vector3::new(4.0, 1.0, 2.0).into()
+			);
+
+			// Properties without known ranges are never touched
+			assert_eq!(
+				resolve("Sound", "RollOffMaxDistance", "-5", &ranges),
+				Variant::Float32(-5.0)
+			);
+		}
+	}
+
+	#[test]
+	fn warn() {
+		let ranges = PropertyRanges::default();
+
+		assert_eq!(resolve("Part", "Transparency", "10", &ranges), Variant::Float32(10.0));
+		assert_eq!(resolve("Sound", "Volume", "100", &ranges), Variant::Float32(100.0));
+		assert_eq!(
+			resolve("Part", "Size", "[4, -1, 2]", &ranges),
+			Vector3::new(4.0, -1.0, 2.0).into()
+		);
+	}
+
+	#[test]
+	fn clamp() {
+		let ranges = PropertyRanges::new(BTreeMap::new(), true);
+
+		assert_eq!(resolve("Part", "Transparency", "10", &ranges), Variant::Float32(1.0));
+		assert_eq!(resolve("MeshPart", "Reflectance", "-2", &ranges), Variant::Float32(0.0));
+		assert_eq!(resolve("Sound", "Volume", "100", &ranges), Variant::Float32(10.0));
+		assert_eq!(
+			resolve("Part", "Size", "[4, -1, 2]", &ranges),
+			Vector3::new(4.0, 0.0, 2.0).into()
+		);
+	}
+
+	#[test]
+	fn overrides() {
+		let mut overrides = BTreeMap::new();
+		overrides.insert(
+			String::from("Sound.Volume"),
+			Range {
+				min: Some(0.0),
+				max: Some(2.0),
+			},
+		);
+		overrides.insert(
+			String::from("Light.Brightness"),
+			Range {
+				min: Some(0.0),
+				max: Some(5.0),
+			},
+		);
+
+		let ranges = PropertyRanges::new(overrides, true);
+
+		assert_eq!(resolve("Sound", "Volume", "5", &ranges), Variant::Float32(2.0));
+		assert_eq!(resolve("PointLight", "Brightness", "8", &ranges), Variant::Float32(5.0));
+		assert_eq!(resolve("Part", "Transparency", "0.5", &ranges), Variant::Float32(0.5));
+	}
+
+	#[test]
+	fn brick_color_numbers() {
+		for number in ["-1", "1.5", "70000"] {
+			let unresolved: UnresolvedValue = serde_json::from_str(number).unwrap();
+			assert!(unresolved.resolve("Part", "BrickColor", None).is_err());
+		}
+	}
+}
+
+mod examples {
+	use argon::resolution::{list_examples, UnresolvedValue};
+	use std::path::Path;
+
+	#[test]
+	fn small_lists() {
+		assert_eq!(list_examples(&[], None), "");
+		assert_eq!(list_examples(&["Only"], None), "Only");
+		assert_eq!(list_examples(&["First", "Second"], None), "First or Second");
+		assert_eq!(list_examples(&["A", "B", "C", "D", "E"], Some("Z")), "A, B, C, D or E");
+	}
+
+	#[test]
+	fn huge_lists() {
+		let values = (0..200).map(|index| format!("Item{:03}", index)).collect::<Vec<_>>();
+		let values = values.iter().map(String::as_str).collect::<Vec<_>>();
+
+		assert_eq!(
+			list_examples(&values, None),
+			"Item000, Item001, Item002, Item003, Item004 or 195 more"
+		);
+		assert_eq!(
+			list_examples(&values, Some("item150")),
+			"Item150, Item050, Item100, Item110, Item120 or 195 more"
+		);
+	}
+
+	#[test]
+	fn fuzzy_ordering() {
+		let values = ["Apple", "Glass", "Granite", "Grass", "Ground", "Plastic", "Sand"];
+
+		assert_eq!(
+			list_examples(&values, Some("gras")),
+			"Grass, Glass, Granite, Ground, Sand or 2 more"
+		);
+		assert_eq!(
+			list_examples(&values, Some("PLASTIK")),
+			"Plastic, Glass, Grass, Apple, Granite or 2 more"
+		);
+	}
+
+	#[test]
+	fn enum_error() {
+		let unresolved: UnresolvedValue = serde_json::from_str(r#""Grasss""#).unwrap();
+		let err = unresolved
+			.resolve("Part", "Material", Some(Path::new("src/Part.data.json")))
+			.unwrap_err()
+			.to_string();
+
+		assert!(err.contains("Got Grasss"));
+		assert!(err.contains("such as Grass,"));
+		assert!(err.ends_with("more in src/Part.data.json"));
+
+		let unresolved: UnresolvedValue = serde_json::from_str(&format!(r#""{}""#, "x".repeat(1000))).unwrap();
+		let err = unresolved.resolve("Part", "Material", None).unwrap_err().to_string();
+
+		assert!(err.len() < 300);
+	}
+}
+
+mod cframe_forms {
+	use approx::assert_relative_eq;
+	use argon::resolution::{AmbiguousValue, CFrameForm, UnresolvedValue};
+	use rbx_dom_weak::types::{CFrame, Matrix3, Variant, Vector3};
+	use serde_json::json;
+
+	fn resolve(value: serde_json::Value) -> CFrame {
+		let unresolved: UnresolvedValue = serde_json::from_value(value).unwrap();
+
+		match unresolved.resolve("Part", "CFrame", None).unwrap() {
+			Variant::CFrame(cf) => cf,
+			other => panic!("Expected CFrame, got {:?}", other),
+		}
+	}
+
+	fn write(cf: &CFrame) -> serde_json::Value {
+		serde_json::to_value(UnresolvedValue::Ambiguous(AmbiguousValue::from_cframe(cf, true))).unwrap()
+	}
+
+	fn assert_cframe_eq(a: &CFrame, b: &CFrame) {
+		let components = |cf: &CFrame| {
+			[
+				cf.position.x,
+				cf.position.y,
+				cf.position.z,
+				cf.orientation.x.x,
+				cf.orientation.x.y,
+				cf.orientation.x.z,
+				cf.orientation.y.x,
+				cf.orientation.y.y,
+				cf.orientation.y.z,
+				cf.orientation.z.x,
+				cf.orientation.z.y,
+				cf.orientation.z.z,
+			]
+		};
+
+		for (a, b) in components(a).iter().zip(components(b)) {
+			assert_relative_eq!(*a, b, epsilon = 1e-6);
+		}
+	}
+
+	#[test]
+	fn position_only() {
+		let expected = CFrame::new(Vector3::new(1.0, 2.0, 3.0), Matrix3::identity());
+
+		assert_cframe_eq(&resolve(json!([1, 2, 3])), &expected);
+		assert_cframe_eq(&resolve(json!({"position": [1, 2, 3]})), &expected);
+		assert_cframe_eq(
+			&resolve(json!({"position": [1, 2, 3], "rotation": [0, 0, 0]})),
+			&expected,
+		);
+
+		assert_eq!(write(&expected), json!({"position": [1, 2, 3]}));
+	}
+
+	#[test]
+	fn right_angles() {
+		// Rotation of 90° around each axis, rows of `CFrame.Angles`
+		let cases = [
+			([90.0, 0.0, 0.0], [[1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]]),
+			([0.0, 90.0, 0.0], [[0.0, 0.0, 1.0], [0.0, 1.0, 0.0], [-1.0, 0.0, 0.0]]),
+			([0.0, 0.0, 90.0], [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]),
+			(
+				[180.0, 0.0, -90.0],
+				[[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]],
+			),
+		];
+
+		for (rotation, rows) in cases {
+			let row = |index: usize| Vector3::new(rows[index][0], rows[index][1], rows[index][2]);
+			let expected = CFrame::new(Vector3::new(0.0, 5.0, 0.0), Matrix3::new(row(0), row(1), row(2)));
+
+			let cf = resolve(json!({"position": [0, 5, 0], "rotation": rotation}));
+			assert_cframe_eq(&cf, &expected);
+
+			let form = CFrameForm::from_cframe(&expected).unwrap();
+			assert_cframe_eq(&form.to_cframe(), &expected);
+		}
+	}
+
+	#[test]
+	fn arbitrary_angles() {
+		let cf = resolve(json!({"position": [1.5, -2, 0.25], "rotation": [12.5, -37.25, 101]}));
+
+		assert_eq!(
+			write(&cf),
+			json!({"position": [1.5, -2, 0.25], "rotation": [12.5, -37.25, 101]})
+		);
+
+		// Gimbal lock, X and Z rotations can't be told apart
+		let cf = resolve(json!({"position": [0, 0, 0], "rotation": [30, 90, 15]}));
+		let form = CFrameForm::from_cframe(&cf).unwrap();
+
+		assert_eq!(form.rotation[1], 90.0);
+		assert_cframe_eq(&form.to_cframe(), &cf);
+	}
+
+	#[test]
+	fn fallback() {
+		// Skewed matrix that is not a rotation at all
+		let skewed = CFrame::new(
+			Vector3::new(1.0, 2.0, 3.0),
+			Matrix3::new(
+				Vector3::new(1.0, 0.5, 0.0),
+				Vector3::new(0.0, 1.0, 0.0),
+				Vector3::new(0.0, 0.0, 1.0),
+			),
+		);
+
+		assert!(CFrameForm::from_cframe(&skewed).is_none());
+		assert_eq!(write(&skewed), json!([1, 2, 3, 1, 0.5, 0, 0, 1, 0, 0, 0, 1]));
+
+		// Rotation that is not clean to a thousandth of a degree
+		let cf = resolve(json!({"position": [0, 0, 0], "rotation": [10.0004, 0, 0]}));
+
+		assert!(CFrameForm::from_cframe(&cf).is_none());
+		assert_eq!(write(&cf).as_array().unwrap().len(), 12);
+
+		// Preference disabled
+		let cf = CFrame::new(Vector3::new(1.0, 2.0, 3.0), Matrix3::identity());
+
+		assert!(matches!(
+			AmbiguousValue::from_cframe(&cf, false),
+			AmbiguousValue::Array12(_)
+		));
+	}
+
+	#[test]
+	fn round_trip() {
+		let inputs = [
+			json!([4, 5, 6]),
+			json!({"position": [4, 5, 6], "rotation": [45, 30, -60]}),
+			json!({"position": [-1.25, 0, 8], "rotation": [179.5, -89.999, 0.001]}),
+			json!([1, 2, 3, 0, 0, 1, 0, 1, 0, -1, 0, 0]),
+			json!([1, 2, 3, 1, 0.5, 0, 0, 1, 0, 0, 0, 1]),
+		];
+
+		for input in inputs {
+			let original = resolve(input);
+			let written = write(&original);
+			let mut cf = resolve(written.clone());
+
+			assert_cframe_eq(&cf, &original);
+
+			// Writing and reading the value back repeatedly does not change it
+			for _ in 0..10 {
+				assert_eq!(write(&cf), written);
+				cf = resolve(write(&cf));
+			}
+
+			assert_cframe_eq(&cf, &original);
+		}
+	}
+
+	#[test]
+	fn unknown_fields() {
+		let unresolved: UnresolvedValue =
+			serde_json::from_value(json!({"position": [1, 2, 3], "scale": [1, 1, 1]})).unwrap();
+
+		assert!(matches!(
+			unresolved,
+			UnresolvedValue::Ambiguous(AmbiguousValue::Object(_))
+		));
+		assert!(unresolved.resolve("Part", "CFrame", None).is_err());
+	}
+}
@@ -0,0 +1,4967 @@
+mod common;
+
+mod place_ids {
+	use argon::project::Project;
+
+	fn project(json: &str) -> Project {
+		serde_json::from_str(json).unwrap()
+	}
+
+	#[test]
+	fn accepts_any_place_without_ids() {
+		let project = project(r#"{"tree": {"$className": "DataModel"}}"#);
+
+		assert!(project.check_place(None, None).is_ok());
+		assert!(project.check_place(Some(1), Some(2)).is_ok());
+	}
+
+	#[test]
+	fn rejects_wrong_place() {
+		let project = project(r#"{"tree": {"$className": "DataModel"}, "gameId": 1, "placeIds": [2, 3]}"#);
+
+		assert!(project.check_place(Some(1), Some(3)).is_ok());
+		assert!(project.check_place(Some(1), Some(4)).is_err());
+		assert!(project.check_place(Some(5), Some(2)).is_err());
+		assert!(project.is_strict_place());
+	}
+
+	#[test]
+	fn missing_ids_are_unknown() {
+		let project = project(r#"{"tree": {"$className": "DataModel"}, "gameId": 1, "placeIds": [2, 3]}"#);
+
+		assert!(project.check_place(None, None).is_ok());
+		assert!(project.check_place(Some(1), None).is_ok());
+		assert!(project.check_place(None, Some(4)).is_err());
+
+		assert!(project.is_place_unknown(None, Some(2)));
+		assert!(project.is_place_unknown(Some(1), None));
+		assert!(!project.is_place_unknown(Some(5), Some(4)));
+	}
+
+	#[test]
+	fn downgrades_to_warning() {
+		let mut project = project(r#"{"tree": {"$className": "DataModel"}, "gameId": 1, "strictPlaceIds": false}"#);
+
+		assert!(project.check_place(Some(2), None).is_err());
+		assert!(!project.is_strict_place());
+
+		project.strict_place_ids = None;
+		project.allow_any_place = true;
+
+		assert!(!project.is_strict_place());
+	}
+}
+
+mod handshake {
+	use crate::common::{start, TempDir};
+	use actix_web::{
+		http::{header, StatusCode},
+		test::{self, TestRequest},
+		web::Data,
+		App,
+	};
+	use argon::{
+		config::Config,
+		server::{
+			limits::{Limiter, Limits},
+			Message, Server,
+		},
+		vfs::VfsEvent,
+	};
+	use serde_json::{json, Value};
+	use std::{fs, path::Path, sync::Arc, time::Duration};
+
+	fn fixture(name: &str, settings: Value) -> TempDir {
+		let dir = TempDir::new(&format!("handshake-{}", name));
+
+		write_project(&dir, settings);
+
+		dir
+	}
+
+	fn write_project(dir: &Path, settings: Value) {
+		let mut project = json!({ "name": "Handshake", "tree": { "$className": "DataModel" } });
+		project
+			.as_object_mut()
+			.unwrap()
+			.extend(settings.as_object().unwrap().clone());
+
+		fs::write(dir.join("default.project.json"), project.to_string()).unwrap();
+	}
+
+	fn subscribe(client_id: u32, game_id: u64, place_id: u64) -> TestRequest {
+		let body = json!({ "clientId": client_id, "name": "Studio", "gameId": game_id, "placeId": place_id });
+
+		TestRequest::post()
+			.uri("/subscribe")
+			.insert_header((header::CONTENT_TYPE, "application/msgpack"))
+			.set_payload(rmp_serde::to_vec_named(&body).unwrap())
+	}
+
+	macro_rules! service {
+		($core:expr) => {
+			test::init_service(
+				App::new()
+					.app_data(Data::new(Arc::new(Limiter::new(Limits::new(&Config::default())))))
+					.service(Server::scope("", $core.clone())),
+			)
+			.await
+		};
+	}
+
+	#[actix_web::test]
+	async fn rejects_wrong_place() {
+		let dir = fixture("reject", json!({ "gameId": 1, "placeIds": [2] }));
+		let core = Arc::new(start(&dir));
+		let service = service!(core);
+
+		let response = test::call_service(&service, subscribe(1, 1, 5).to_request()).await;
+		assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+		// Error names both the expected and the reported place
+		let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+		assert!(body.contains("place IDs: [2]"), "{}", body);
+		assert!(body.contains("place ID: 5"), "{}", body);
+		assert!(core.queue().clients().is_empty());
+
+		let response = test::call_service(&service, subscribe(2, 1, 2).to_request()).await;
+		assert_eq!(response.status(), StatusCode::OK);
+	}
+
+	#[actix_web::test]
+	async fn accepts_unknown_place() {
+		let dir = fixture("unknown", json!({ "gameId": 1, "placeIds": [2] }));
+		let core = Arc::new(start(&dir));
+		let service = service!(core);
+
+		// Clients that don't report their place, e.g. unpublished ones, are not rejected
+		let body = json!({ "clientId": 1, "name": "Studio" });
+		let request = TestRequest::post()
+			.uri("/subscribe")
+			.insert_header((header::CONTENT_TYPE, "application/msgpack"))
+			.set_payload(rmp_serde::to_vec_named(&body).unwrap());
+
+		let response = test::call_service(&service, request.to_request()).await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		let clients = core.queue().clients();
+
+		assert_eq!(clients.len(), 1);
+		assert_eq!(clients[0].place.game_id, None);
+		assert_eq!(clients[0].place.place_id, None);
+	}
+
+	#[actix_web::test]
+	async fn warns_about_wrong_place() {
+		let dir = fixture("warn", json!({ "gameId": 1, "placeIds": [2], "strictPlaceIds": false }));
+		let core = Arc::new(start(&dir));
+		let service = service!(core);
+
+		let response = test::call_service(&service, subscribe(1, 1, 5).to_request()).await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		let clients = core.queue().clients();
+
+		assert_eq!(clients.len(), 1);
+		assert_eq!(clients[0].place.place_id, Some(5));
+	}
+
+	#[actix_web::test]
+	async fn revalidates_on_reload() {
+		let dir = fixture("reload", json!({ "gameId": 1, "placeIds": [2, 3] }));
+		let core = Arc::new(start(&dir));
+		let service = service!(core);
+
+		for (client_id, place_id) in [(1, 2), (2, 3)] {
+			let response = test::call_service(&service, subscribe(client_id, 1, place_id).to_request()).await;
+			assert_eq!(response.status(), StatusCode::OK);
+		}
+
+		// Place of the first client is no longer allowed
+		write_project(&dir, json!({ "gameId": 1, "placeIds": [3] }));
+		core.processor()
+			.notify(VfsEvent::Write(dir.join("default.project.json")));
+
+		let queue = core.queue();
+
+		let message = loop {
+			match queue.get_with_timeout(1, Duration::from_secs(5)).unwrap() {
+				Some(Message::Disconnect(disconnect)) => break disconnect.message,
+				Some(_) => continue,
+				None => panic!("Client was not disconnected"),
+			}
+		};
+
+		assert!(message.contains("place ID: 2"), "{}", message);
+
+		while let Some(message) = queue.get_with_timeout(2, Duration::from_millis(500)).unwrap() {
+			assert!(
+				!matches!(message, Message::Disconnect(_)),
+				"Matching client was disconnected"
+			);
+		}
+	}
+}
+
+mod snapshot_pages {
+	use crate::common::TempDir;
+	use actix_web::{
+		http::{header, StatusCode},
+		test::{self, TestRequest},
+		web::Data,
+		App,
+	};
+	use argon::{
+		config::Config,
+		core::{snapshot::Snapshot, Core},
+		project::Project,
+		server::{
+			limits::{Limiter, Limits},
+			Server,
+		},
+	};
+	use serde_json::{json, Value};
+	use std::{collections::HashMap, fs, sync::Arc};
+
+	/// Instances directly in the flat folder, so paging through it would be quadratic
+	/// if the next sibling was looked up by position
+	const FLAT: usize = 25_000;
+	const NESTED: usize = 250;
+	const NESTED_CHILDREN: usize = 100;
+
+	/// Creates a project with a flat folder of `flat` instances
+	/// and a folder of `nested` ones with children each
+	fn start(name: &str, flat: usize, nested: usize) -> (Arc<Core>, TempDir) {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{ "name": "Pages", "tree": { "$className": "DataModel", "ReplicatedStorage": { "$path": "src" } } }"#,
+		)
+		.unwrap();
+
+		let flat: Vec<Value> = (0..flat)
+			.map(|index| json!({ "name": format!("Flat{}", index), "className": "Folder" }))
+			.collect();
+
+		let nested: Vec<Value> = (0..nested)
+			.map(|index| {
+				let children: Vec<Value> = (0..NESTED_CHILDREN)
+					.map(|child| json!({ "name": format!("Child{}", child), "className": "Folder" }))
+					.collect();
+
+				json!({ "name": format!("Nested{}", index), "className": "Folder", "children": children })
+			})
+			.collect();
+
+		fs::write(
+			dir.join("src/Flat.model.json"),
+			json!({ "className": "Folder", "children": flat }).to_string(),
+		)
+		.unwrap();
+		fs::write(
+			dir.join("src/Nested.model.json"),
+			json!({ "className": "Folder", "children": nested }).to_string(),
+		)
+		.unwrap();
+
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+		(Arc::new(Core::new(project, false).unwrap()), dir)
+	}
+
+	fn page(cursor: Option<&str>, limit: usize) -> TestRequest {
+		let uri = match cursor {
+			Some(cursor) => format!("/snapshot?limit={}&cursor={}", limit, cursor),
+			None => format!("/snapshot?limit={}", limit),
+		};
+
+		TestRequest::get()
+			.uri(&uri)
+			.insert_header((header::ACCEPT, "application/json"))
+	}
+
+	macro_rules! service {
+		($core:expr) => {
+			test::init_service(
+				App::new()
+					.app_data(Data::new(Arc::new(Limiter::new(Limits::new(&Config::default())))))
+					.service(Server::scope("", $core.clone())),
+			)
+			.await
+		};
+	}
+
+	#[actix_web::test]
+	async fn reassembly() {
+		let (core, dir) = start("reassembly", FLAT, NESTED);
+		let service = service!(core);
+
+		let mut instances: Vec<Value> = vec![];
+		let mut cursor: Option<String> = None;
+		let mut pages = 0;
+
+		let tree_hash = loop {
+			let page: Value = test::call_and_read_body_json(&service, page(cursor.as_deref(), 5000).to_request()).await;
+
+			assert_eq!(page["restart"], json!(false));
+
+			instances.extend(page["instances"].as_array().unwrap().iter().cloned());
+			pages += 1;
+
+			match page["cursor"].as_str() {
+				Some(next) => {
+					assert!(page.get("treeHash").is_none());
+					cursor = Some(next.to_owned());
+				}
+				None => break page["treeHash"].as_str().unwrap().to_owned(),
+			}
+		};
+
+		assert!(instances.len() > FLAT + NESTED * NESTED_CHILDREN);
+		assert_eq!(pages, instances.len().div_ceil(5000));
+		assert_eq!(tree_hash, core.tree_hash().to_string());
+
+		let tree = core.tree();
+
+		// Parents always come before their children, so the tree can be rebuilt in one pass
+		let mut children: HashMap<String, Vec<String>> = HashMap::new();
+
+		let root = tree.root_ref().to_string();
+
+		for instance in &instances {
+			let id = instance["id"].as_str().unwrap().to_owned();
+
+			if id != root {
+				children
+					.get_mut(instance["parent"].as_str().unwrap())
+					.expect("Instance came before its parent")
+					.push(id.clone());
+			}
+
+			assert!(children.insert(id, vec![]).is_none(), "Instance was sent twice");
+		}
+
+		assert_eq!(children.len(), tree.inner().descendants().count());
+
+		for (id, received) in children {
+			let instance = tree.get_instance(id.parse().unwrap()).unwrap();
+			let expected: Vec<String> = instance.children().iter().map(ToString::to_string).collect();
+
+			assert_eq!(received, expected, "Children of {} are out of order", instance.name);
+		}
+
+		drop(tree);
+		fs::remove_dir_all(&dir).ok();
+	}
+
+	#[actix_web::test]
+	async fn scan_limit() {
+		let (core, _dir) = start("scan-limit", 500, 5);
+		let service = service!(core);
+
+		let mut instances: Vec<Value> = vec![];
+		let mut cursor: Option<String> = None;
+		let mut empty = 0;
+
+		// Flat folder comes first and is out of scope, so it is scanned in empty pages
+		let tree_hash = loop {
+			let uri = format!(
+				"/snapshot?limit=10&paths=ReplicatedStorage.Nested{}",
+				cursor
+					.as_ref()
+					.map(|cursor| format!("&cursor={}", cursor))
+					.unwrap_or_default()
+			);
+			let request = TestRequest::get()
+				.uri(&uri)
+				.insert_header((header::ACCEPT, "application/json"));
+			let page: Value = test::call_and_read_body_json(&service, request.to_request()).await;
+
+			let received = page["instances"].as_array().unwrap();
+
+			if received.is_empty() {
+				empty += 1;
+			}
+
+			instances.extend(received.iter().cloned());
+
+			match page["cursor"].as_str() {
+				Some(next) => cursor = Some(next.to_owned()),
+				None => break page["treeHash"].as_str().unwrap().to_owned(),
+			}
+		};
+
+		assert!(empty > 0);
+		assert_eq!(tree_hash, core.tree_hash().to_string());
+
+		// Scope and its ancestors, all of them are still sent
+		assert_eq!(instances.len(), 3 + 5 * (NESTED_CHILDREN + 1));
+		assert!(instances
+			.iter()
+			.all(|instance| !instance["name"].as_str().unwrap().starts_with("Flat")));
+	}
+
+	#[actix_web::test]
+	async fn restart() {
+		let (core, _dir) = start("restart", 500, 5);
+		let service = service!(core);
+
+		let first: Value = test::call_and_read_body_json(&service, page(None, 100).to_request()).await;
+		let cursor = first["cursor"].as_str().unwrap().to_owned();
+
+		// Cursor points past instances that are not sent yet and keeps working while the tree is the same
+		let second: Value = test::call_and_read_body_json(&service, page(Some(&cursor), 100).to_request()).await;
+		assert_eq!(second["restart"], json!(false));
+		assert_eq!(second["instances"][0]["id"], json!(cursor.split(':').nth(1).unwrap()));
+
+		{
+			let mut tree = core.tree();
+			let root = tree.root_ref();
+
+			tree.insert_instance(Snapshot::new().with_name("Added"), root);
+		}
+
+		let restarted: Value = test::call_and_read_body_json(&service, page(Some(&cursor), 100).to_request()).await;
+
+		assert_eq!(restarted["restart"], json!(true));
+		assert_eq!(restarted["instances"], json!([]));
+		assert_eq!(restarted["cursor"], Value::Null);
+		assert_ne!(restarted["generation"], first["generation"]);
+
+		// Starting over without a cursor goes through the changed tree
+		let again: Value = test::call_and_read_body_json(&service, page(None, 100).to_request()).await;
+
+		assert_eq!(again["restart"], json!(false));
+		assert_eq!(again["generation"], restarted["generation"]);
+
+		let response = test::call_service(&service, page(Some("0:invalid"), 100).to_request()).await;
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+	}
+}
+
+mod find {
+	use argon::{
+		core::{
+			find::{self, FindPage, FindQuery, FindRequest},
+			snapshot::Snapshot,
+			tree::Tree,
+		},
+		vfs::Vfs,
+		Properties,
+	};
+	use rbx_dom_weak::{types::Variant, HashMapExt, Ustr, UstrMap};
+	use std::sync::Mutex;
+
+	fn properties(entries: &[(&str, Variant)]) -> Properties {
+		let mut properties = UstrMap::new();
+
+		for (name, value) in entries {
+			properties.insert(Ustr::from(name), value.clone());
+		}
+
+		properties
+	}
+
+	fn instance(name: &str, class: &str, children: Vec<Snapshot>) -> Snapshot {
+		Snapshot::new()
+			.with_name(name)
+			.with_class(class)
+			.with_children(children)
+	}
+
+	fn tree() -> Mutex<Tree> {
+		let remotes = instance(
+			"Remotes",
+			"Folder",
+			vec![
+				instance("Fire", "RemoteEvent", vec![]),
+				instance("Fetch", "RemoteFunction", vec![]),
+			],
+		);
+
+		let storage = instance(
+			"ReplicatedStorage",
+			"ReplicatedStorage",
+			vec![
+				remotes,
+				instance("Greeting", "StringValue", vec![])
+					.with_properties(properties(&[("Value", Variant::String("Hello".into()))])),
+			],
+		);
+
+		let workspace = instance(
+			"Workspace",
+			"Workspace",
+			vec![
+				instance("Baseplate", "Part", vec![]).with_properties(properties(&[
+					("Anchored", Variant::Bool(true)),
+					("Transparency", Variant::Float32(0.5)),
+				])),
+				instance("Rock", "MeshPart", vec![]).with_properties(properties(&[("Anchored", Variant::Bool(false))])),
+				instance("Remotes", "Folder", vec![]),
+			],
+		);
+
+		Mutex::new(Tree::new(instance("Game", "DataModel", vec![storage, workspace])))
+	}
+
+	fn search(tree: &Mutex<Tree>, request: FindRequest) -> Vec<String> {
+		page(tree, request, None, 100)
+			.instances
+			.into_iter()
+			.map(|found| found.path)
+			.collect()
+	}
+
+	fn page(tree: &Mutex<Tree>, request: FindRequest, cursor: Option<&str>, limit: usize) -> FindPage {
+		let query = FindQuery::new(&request).unwrap();
+		find::find(tree, &Vfs::new_virtual(), &query, cursor, limit).unwrap()
+	}
+
+	#[test]
+	fn parse_query() {
+		let request = FindRequest::parse("ClassName=RemoteEvent path~Remotes/* Anchored=true Size=4").unwrap();
+
+		assert_eq!(
+			request,
+			FindRequest {
+				class: Some(String::from("RemoteEvent")),
+				path_glob: Some(String::from("Remotes/*")),
+				property: Some(String::from("Anchored:true,Size:4")),
+				..FindRequest::default()
+			}
+		);
+
+		let request = FindRequest::parse("name~^Re class~BasePart").unwrap();
+
+		assert_eq!(request.name_regex.as_deref(), Some("^Re"));
+		assert_eq!(request.class.as_deref(), Some("BasePart"));
+		assert!(request.subclasses);
+
+		assert!(FindRequest::parse("Remotes").is_err());
+		assert!(FindRequest::parse("Anchored~true").is_err());
+		assert!(FindQuery::new(&FindRequest::parse("name~(").unwrap()).is_err());
+	}
+
+	#[test]
+	fn name() {
+		let tree = tree();
+
+		let request = FindRequest {
+			name: Some(String::from("Remotes")),
+			..FindRequest::default()
+		};
+
+		assert_eq!(
+			search(&tree, request),
+			["Game/ReplicatedStorage/Remotes", "Game/Workspace/Remotes"]
+		);
+
+		let request = FindRequest {
+			name_regex: Some(String::from("^F[a-z]+$")),
+			..FindRequest::default()
+		};
+
+		assert_eq!(
+			search(&tree, request),
+			[
+				"Game/ReplicatedStorage/Remotes/Fetch",
+				"Game/ReplicatedStorage/Remotes/Fire"
+			]
+		);
+	}
+
+	#[test]
+	fn class() {
+		let tree = tree();
+
+		let request = FindRequest {
+			class: Some(String::from("BasePart")),
+			..FindRequest::default()
+		};
+
+		assert!(search(&tree, request.clone()).is_empty());
+
+		let request = FindRequest {
+			subclasses: true,
+			..request
+		};
+
+		assert_eq!(
+			search(&tree, request),
+			["Game/Workspace/Baseplate", "Game/Workspace/Rock"]
+		);
+
+		let request = FindRequest {
+			class: Some(String::from("RemoteEvent")),
+			..FindRequest::default()
+		};
+
+		assert_eq!(search(&tree, request), ["Game/ReplicatedStorage/Remotes/Fire"]);
+	}
+
+	#[test]
+	fn subclass_of() {
+		assert!(find::is_a("Part", "Part"));
+		assert!(find::is_a("Part", "BasePart"));
+		assert!(find::is_a("MeshPart", "BasePart"));
+		assert!(find::is_a("RemoteEvent", "Instance"));
+		assert!(find::is_a("Script", "LuaSourceContainer"));
+
+		assert!(!find::is_a("Folder", "BasePart"));
+		assert!(!find::is_a("BasePart", "Part"));
+		assert!(!find::is_a("NotAClass", "Instance"));
+	}
+
+	#[test]
+	fn path() {
+		let tree = tree();
+
+		let request = FindRequest::parse("path~Remotes/*").unwrap();
+
+		assert_eq!(
+			search(&tree, request),
+			[
+				"Game/ReplicatedStorage/Remotes/Fetch",
+				"Game/ReplicatedStorage/Remotes/Fire"
+			]
+		);
+
+		let request = FindRequest::parse("path~/Game/Workspace/*").unwrap();
+
+		assert_eq!(
+			search(&tree, request),
+			[
+				"Game/Workspace/Baseplate",
+				"Game/Workspace/Remotes",
+				"Game/Workspace/Rock"
+			]
+		);
+
+		let request = FindRequest::parse("path=Workspace/Rock").unwrap();
+
+		assert_eq!(search(&tree, request), ["Game/Workspace/Rock"]);
+	}
+
+	#[test]
+	fn property() {
+		let tree = tree();
+
+		assert_eq!(
+			search(&tree, FindRequest::parse("Anchored=true").unwrap()),
+			["Game/Workspace/Baseplate"]
+		);
+		assert_eq!(
+			search(&tree, FindRequest::parse("Anchored=false").unwrap()),
+			["Game/Workspace/Rock"]
+		);
+		assert_eq!(
+			search(&tree, FindRequest::parse("Transparency=0.5").unwrap()),
+			["Game/Workspace/Baseplate"]
+		);
+		assert_eq!(
+			search(&tree, FindRequest::parse("Value=Hello").unwrap()),
+			["Game/ReplicatedStorage/Greeting"]
+		);
+
+		assert!(search(&tree, FindRequest::parse("Value=Bye").unwrap()).is_empty());
+		assert!(search(&tree, FindRequest::parse("Missing=true").unwrap()).is_empty());
+
+		assert!(find::variant_eq(&Variant::Int32(4), "4"));
+		assert!(find::variant_eq(&Variant::Float64(1.5), "1.5"));
+		assert!(!find::variant_eq(&Variant::Bool(true), "yes"));
+	}
+
+	#[test]
+	fn combined() {
+		let tree = tree();
+
+		let request = FindRequest::parse("ClassName~BasePart path~Workspace/* Anchored=true").unwrap();
+
+		assert_eq!(search(&tree, request), ["Game/Workspace/Baseplate"]);
+	}
+
+	#[test]
+	fn pagination() {
+		let tree = tree();
+
+		let mut paths = vec![];
+		let mut cursor = None;
+
+		loop {
+			let page = page(&tree, FindRequest::default(), cursor.as_deref(), 3);
+
+			assert!(!page.restart);
+			assert_eq!(page.total, 10);
+
+			paths.extend(page.instances.into_iter().map(|found| found.path));
+
+			match page.cursor {
+				Some(next) => cursor = Some(next),
+				None => break,
+			}
+		}
+
+		let mut sorted = paths.clone();
+		sorted.sort();
+
+		assert_eq!(paths.len(), 10);
+		assert_eq!(paths, sorted);
+
+		// Changes to the tree invalidate previous cursors
+		let first = page(&tree, FindRequest::default(), None, 3);
+
+		{
+			let mut tree = tree.lock().unwrap();
+			let root = tree.root_ref();
+
+			tree.insert_instance(instance("Lighting", "Lighting", vec![]), root);
+		}
+
+		let page = page(&tree, FindRequest::default(), first.cursor.as_deref(), 3);
+
+		assert!(page.restart);
+		assert!(page.instances.is_empty());
+	}
+}
+
+mod blobs {
+	use argon::{
+		core::{
+			blobs::{self, BlobRef, BlobStore},
+			changes::Changes,
+			queue::{PlaceDetails, Queue},
+			snapshot::{Snapshot, UpdatedSnapshot},
+		},
+		server::{
+			protocol::{Capability, Negotiated},
+			Message, SyncBlobChanges, SyncChanges,
+		},
+		Properties,
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		HashMapExt, Ustr, UstrMap,
+	};
+	use std::{collections::HashMap, sync::Arc, time::Duration};
+
+	fn source(body: &str) -> Properties {
+		let mut properties = UstrMap::new();
+		properties.insert(Ustr::from("Source"), Variant::String(body.repeat(200)));
+		properties.insert(Ustr::from("Disabled"), Variant::Bool(false));
+		properties
+	}
+
+	fn script(name: &str, body: &str) -> Snapshot {
+		Snapshot::new()
+			.with_id(Ref::new())
+			.with_name(name)
+			.with_class("ModuleScript")
+			.with_properties(source(body))
+	}
+
+	/// Two identical scripts in a folder and an update with a different source
+	fn changes() -> Changes {
+		let mut changes = Changes::new();
+
+		let folder = Snapshot::new()
+			.with_id(Ref::new())
+			.with_name("Shared")
+			.with_children(vec![script("A", "return {}\n"), script("B", "return {}\n")]);
+
+		changes.add(folder, Ref::new());
+
+		let mut update = UpdatedSnapshot::new(Ref::new());
+		update.properties = Some(source("print('updated')\n"));
+
+		changes.update(update);
+		changes
+	}
+
+	fn blobs() -> Negotiated {
+		Negotiated::new("Client", None, &[Capability::Blobs]).unwrap()
+	}
+
+	fn receive(queue: &Queue, id: u32) -> Message {
+		queue.get_with_timeout(id, Duration::from_millis(10)).unwrap().unwrap()
+	}
+
+	fn blob_changes(message: Message) -> SyncBlobChanges {
+		match message {
+			Message::SyncBlobChanges(message) => message,
+			_ => panic!("Expected deduplicated changes"),
+		}
+	}
+
+	#[test]
+	fn deduplicate() {
+		let (deduplicated, refs, values) = blobs::deduplicate(&changes());
+
+		assert_eq!(refs.len(), 3);
+		assert_eq!(values.len(), 2);
+		assert_eq!(refs[0].hash, refs[1].hash);
+		assert_ne!(refs[0].hash, refs[2].hash);
+		assert_eq!(refs[0].size, 2000);
+
+		let children = &deduplicated.additions[0].children;
+
+		assert!(children
+			.iter()
+			.all(|child| !child.properties.contains_key(&Ustr::from("Source"))));
+		assert!(children
+			.iter()
+			.all(|child| child.properties.contains_key(&Ustr::from("Disabled"))));
+
+		// Small values stay inline
+		let mut properties = UstrMap::new();
+		properties.insert(Ustr::from("Source"), Variant::String(String::from("return {}")));
+
+		let mut small = Changes::new();
+		small.add(Snapshot::new().with_properties(properties), Ref::new());
+
+		assert!(blobs::deduplicate(&small).1.is_empty());
+	}
+
+	#[test]
+	fn refcounting() {
+		let store = BlobStore::new();
+		let value = Arc::new(Variant::String("a".repeat(2000)));
+		let (hash, size) = blobs::hash(&value).unwrap();
+
+		let blob_ref = BlobRef {
+			id: Ref::new(),
+			property: Ustr::from("Source"),
+			hash: hash.clone(),
+			size,
+		};
+
+		let values = HashMap::from([(hash.clone(), value.clone())]);
+
+		store.retain(&[blob_ref.clone(), blob_ref.clone()], &values);
+		store.retain(std::slice::from_ref(&blob_ref), &HashMap::new());
+
+		assert_eq!(store.len(), 1);
+		assert_eq!(store.refs(&hash), 3);
+
+		store.release(&[blob_ref.clone(), blob_ref.clone()]);
+
+		assert_eq!(store.get(&hash), Some(value));
+
+		store.release(&[blob_ref]);
+
+		assert!(store.is_empty());
+		assert_eq!(store.get(&hash), None);
+	}
+
+	#[test]
+	fn garbage_collection() {
+		let queue = Queue::new();
+
+		queue.subscribe(1, "First", PlaceDetails::default(), blobs()).unwrap();
+		queue.subscribe(2, "Second", PlaceDetails::default(), blobs()).unwrap();
+
+		queue.push(SyncChanges(changes()), None).unwrap();
+
+		// Both queued messages share the same two blobs
+		let refs = blob_changes(receive(&queue, 1)).blobs;
+		let hash = refs[0].hash.clone();
+
+		assert_eq!(queue.blobs().len(), 2);
+		assert_eq!(queue.blobs().refs(&hash), 4);
+
+		// Blobs of the first client stay until its next read
+		assert!(queue.get_with_timeout(1, Duration::from_millis(10)).unwrap().is_none());
+		assert_eq!(queue.blobs().refs(&hash), 2);
+
+		// Message queued for the second client is released on unsubscribe
+		queue.unsubscribe(2).unwrap();
+
+		assert!(queue.blobs().is_empty());
+
+		// Clients that did not advertise support receive values inline
+		queue
+			.subscribe(3, "Legacy", PlaceDetails::default(), Negotiated::default())
+			.unwrap();
+		queue.push(SyncChanges(changes()), None).unwrap();
+
+		let first = blob_changes(receive(&queue, 1));
+
+		match receive(&queue, 3) {
+			Message::SyncChanges(SyncChanges(changes)) => {
+				assert!(changes.additions[0].children[0]
+					.properties
+					.contains_key(&Ustr::from("Source")));
+			}
+			_ => panic!("Expected full changes"),
+		}
+
+		assert_eq!(queue.blobs().len(), 2);
+
+		queue.unsubscribe(1).unwrap();
+
+		assert!(queue.blobs().is_empty());
+		assert_eq!(first.blobs.len(), 3);
+	}
+
+	#[test]
+	fn reconstruction() {
+		let queue = Queue::new();
+		let original = changes();
+
+		queue.subscribe(1, "Client", PlaceDetails::default(), blobs()).unwrap();
+		queue.push(SyncChanges(original.clone()), None).unwrap();
+
+		let SyncBlobChanges { mut changes, blobs } = blob_changes(receive(&queue, 1));
+		let mut fetched = vec![];
+
+		blobs::restore(&mut changes, &blobs, |hash| {
+			fetched.push(hash.to_owned());
+			queue.blobs().get(hash).map(|value| value.as_ref().clone())
+		})
+		.unwrap();
+
+		// Identical sources are fetched only once
+		assert_eq!(fetched.len(), 2);
+
+		for (restored, original) in changes.additions[0]
+			.children
+			.iter()
+			.zip(&original.additions[0].children)
+		{
+			assert_eq!(restored.properties, original.properties);
+		}
+
+		assert_eq!(changes.updates[0].properties, original.updates[0].properties);
+
+		// Missing blob cannot be restored
+		let (mut changes, refs, _) = blobs::deduplicate(&original);
+
+		assert!(blobs::restore(&mut changes, &refs, |_| None).is_none());
+	}
+}
+
+mod initial_sync {
+	use argon::{
+		core::{
+			initial_sync::{self, InitialSync},
+			queue::{PlaceDetails, Queue},
+			snapshot::{AddedSnapshot, Snapshot},
+			tree::Tree,
+		},
+		server::{protocol::Negotiated, Message},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::types::Ref;
+	use std::{
+		collections::{HashSet, VecDeque},
+		sync::Mutex,
+		time::Duration,
+	};
+
+	const CHUNK_SIZE: usize = 20;
+	const WINDOW: usize = 2;
+
+	/// Root with 10 folders, each with 24 parts
+	fn tree() -> Mutex<Tree> {
+		let folders = (0..10)
+			.map(|folder| {
+				let parts = (0..24)
+					.map(|part| Snapshot::new().with_name(format!("Part{}", part)).with_class("Part"))
+					.collect();
+
+				Snapshot::new()
+					.with_name(format!("Folder{}", folder))
+					.with_children(parts)
+			})
+			.collect();
+
+		Mutex::new(Tree::new(Snapshot::new().with_name("Root").with_children(folders)))
+	}
+
+	fn all_ids(tree: &Mutex<Tree>) -> HashSet<Ref> {
+		tree.lock().unwrap().meta_map().keys().copied().collect()
+	}
+
+	/// Messages that are currently waiting in the client queue
+	fn drain(queue: &Queue, id: u32) -> Vec<Message> {
+		let mut messages = vec![];
+
+		while let Some(message) = queue.get_with_timeout(id, Duration::from_millis(10)).unwrap() {
+			messages.push(message);
+		}
+
+		messages
+	}
+
+	/// Client state, only acknowledged chunks are applied
+	#[derive(Default)]
+	struct Client {
+		applied: Vec<AddedSnapshot>,
+		checksum: Option<String>,
+		frames: Vec<(usize, usize)>,
+	}
+
+	impl Client {
+		/// Applies received messages and returns chunks to acknowledge
+		fn receive(&mut self, messages: Vec<Message>) -> Vec<usize> {
+			let mut chunks = vec![];
+
+			for message in messages {
+				match message {
+					Message::SyncChunk(chunk) => {
+						self.applied.extend(chunk.instances);
+						chunks.push(chunk.chunk);
+					}
+					Message::SyncProgress(progress) => {
+						assert_eq!(progress.phase, "initial_sync");
+						self.frames.push((progress.sent, progress.total));
+					}
+					Message::SyncComplete(complete) => {
+						assert_eq!(complete.total, self.applied.len());
+						self.checksum = Some(complete.checksum);
+					}
+					_ => panic!("Unexpected message"),
+				}
+			}
+
+			chunks
+		}
+
+		fn verify(&self, tree: &Mutex<Tree>) {
+			let ids: Vec<Ref> = self.applied.iter().map(|instance| instance.id).collect();
+			let unique: HashSet<Ref> = ids.iter().copied().collect();
+
+			assert_eq!(ids.len(), unique.len(), "duplicate instances");
+			assert_eq!(unique, all_ids(tree), "missing instances");
+			assert_eq!(self.checksum, Some(initial_sync::checksum(&self.applied)));
+		}
+	}
+
+	fn setup() -> (Mutex<Tree>, Vfs, Queue, InitialSync) {
+		let queue = Queue::new();
+		queue
+			.subscribe(1, "Client", PlaceDetails::default(), Negotiated::default())
+			.unwrap();
+
+		(
+			tree(),
+			Vfs::new_virtual(),
+			queue,
+			InitialSync::new(CHUNK_SIZE, WINDOW, Duration::from_secs(60)),
+		)
+	}
+
+	#[test]
+	fn slow_ack() {
+		let (tree, vfs, queue, sync) = setup();
+		let mut client = Client::default();
+
+		let progress = sync.start(1, false, &tree, &vfs, &queue).unwrap();
+
+		assert_eq!(progress.total, 251);
+		assert_eq!(progress.sent, 0);
+
+		let mut pending: VecDeque<usize> = client.receive(drain(&queue, 1)).into();
+
+		// Acknowledge one chunk at a time, server never runs ahead of the window
+		while let Some(chunk) = pending.pop_front() {
+			assert!(pending.len() < WINDOW);
+
+			sync.ack(1, chunk, &tree, &vfs, &queue).unwrap();
+			pending.extend(client.receive(drain(&queue, 1)));
+		}
+
+		client.verify(&tree);
+
+		// Progress is reported after every chunk and ends with the total
+		assert_eq!(client.frames.first(), Some(&(0, 251)));
+		assert_eq!(client.frames.last(), Some(&(251, 251)));
+		assert!(client.frames.windows(2).all(|frames| frames[0].0 <= frames[1].0));
+
+		assert!(!sync.is_syncing(1));
+		assert!(sync.ack(1, 0, &tree, &vfs, &queue).is_err());
+	}
+
+	#[test]
+	fn reconnect() {
+		let (tree, vfs, queue, sync) = setup();
+		let mut client = Client::default();
+
+		sync.start(1, false, &tree, &vfs, &queue).unwrap();
+
+		// Receive the first window but only acknowledge the first chunk
+		let messages = drain(&queue, 1);
+		let mut received = Client::default();
+		let chunks = received.receive(messages);
+
+		assert_eq!(chunks, [0, 1]);
+
+		client.applied.extend(received.applied.drain(..CHUNK_SIZE));
+		sync.ack(1, 0, &tree, &vfs, &queue).unwrap();
+
+		// Disconnect, chunks in the queue are lost
+		queue.unsubscribe(1).unwrap();
+		queue
+			.subscribe(1, "Client", PlaceDetails::default(), Negotiated::default())
+			.unwrap();
+
+		assert!(sync.is_syncing(1));
+
+		let progress = sync.start(1, true, &tree, &vfs, &queue).unwrap();
+
+		assert_eq!(progress.sent, CHUNK_SIZE);
+
+		loop {
+			let chunks = client.receive(drain(&queue, 1));
+
+			if chunks.is_empty() {
+				break;
+			}
+
+			for chunk in chunks {
+				sync.ack(1, chunk, &tree, &vfs, &queue).unwrap();
+			}
+		}
+
+		client.verify(&tree);
+
+		// Starting without resuming begins from scratch
+		let progress = sync.start(1, false, &tree, &vfs, &queue).unwrap();
+
+		assert_eq!(progress.sent, 0);
+		assert!(sync.is_syncing(1));
+	}
+
+	#[test]
+	fn removed_mid_sync() {
+		let (tree, vfs, queue, sync) = setup();
+		let mut client = Client::default();
+
+		sync.start(1, false, &tree, &vfs, &queue).unwrap();
+
+		// Remove the last folder before its chunks are sent
+		{
+			let mut tree = tree.lock().unwrap();
+			let root = tree.root_ref();
+			let folder = *tree.get_instance(root).unwrap().children().last().unwrap();
+
+			tree.remove_instance(folder);
+		}
+
+		loop {
+			let chunks = client.receive(drain(&queue, 1));
+
+			if chunks.is_empty() {
+				break;
+			}
+
+			for chunk in chunks {
+				sync.ack(1, chunk, &tree, &vfs, &queue).unwrap();
+			}
+		}
+
+		client.verify(&tree);
+		assert_eq!(client.applied.len(), 226);
+	}
+}
+
+mod events {
+	use argon::core::events::{EventKind, EventLog};
+	use tokio::sync::mpsc::error::TryRecvError;
+
+	fn diagnostic(message: &str) -> EventKind {
+		EventKind::Diagnostic {
+			level: String::from("info"),
+			message: message.to_owned(),
+			instance: None,
+		}
+	}
+
+	#[test]
+	fn sequence_numbers() {
+		let events = EventLog::new(8, 8);
+
+		assert_eq!(events.publish(diagnostic("first")), 1);
+		assert_eq!(events.publish(diagnostic("second")), 2);
+		assert_eq!(events.last_seq(), 2);
+	}
+
+	#[test]
+	fn replay_after_reconnect() {
+		let events = EventLog::new(8, 8);
+
+		for index in 0..5 {
+			events.publish(diagnostic(&index.to_string()));
+		}
+
+		let subscription = events.subscribe(Some(3));
+		let replay = subscription.replay.unwrap();
+
+		assert_eq!(replay.iter().map(|event| event.seq).collect::<Vec<_>>(), vec![4, 5]);
+		assert_eq!(subscription.latest, 5);
+
+		// Fully up to date client
+		assert!(events.subscribe(Some(5)).replay.unwrap().is_empty());
+
+		// New client does not receive history
+		assert!(events.subscribe(None).replay.unwrap().is_empty());
+	}
+
+	#[test]
+	fn resync_when_events_are_gone() {
+		let events = EventLog::new(4, 8);
+
+		for index in 0..10 {
+			events.publish(diagnostic(&index.to_string()));
+		}
+
+		// Events 3 to 6 are no longer buffered
+		assert!(events.subscribe(Some(2)).replay.is_none());
+		assert_eq!(events.subscribe(Some(6)).replay.unwrap().len(), 4);
+
+		// Sequence from before the server restart
+		assert!(events.subscribe(Some(100)).replay.is_none());
+	}
+
+	#[test]
+	fn live_events() {
+		let events = EventLog::new(8, 8);
+		let mut subscription = events.subscribe(None);
+
+		events.publish(diagnostic("live"));
+
+		let event = subscription.receiver.try_recv().unwrap();
+
+		assert_eq!(event.seq, 1);
+		assert_eq!(event.kind, diagnostic("live"));
+	}
+
+	#[test]
+	fn slow_subscriber_is_dropped() {
+		let events = EventLog::new(64, 2);
+
+		let mut slow = events.subscribe(None);
+		let mut fast = events.subscribe(None);
+
+		assert_eq!(events.subscribers(), 2);
+
+		for index in 0..10 {
+			events.publish(diagnostic(&index.to_string()));
+
+			// Keep up with the events
+			assert!(fast.receiver.try_recv().is_ok());
+		}
+
+		assert_eq!(events.subscribers(), 1);
+
+		// Already buffered events are still delivered before disconnecting
+		assert_eq!(slow.receiver.try_recv().unwrap().seq, 1);
+		assert_eq!(slow.receiver.try_recv().unwrap().seq, 2);
+		assert_eq!(slow.receiver.try_recv(), Err(TryRecvError::Disconnected));
+	}
+
+	#[test]
+	fn closed_subscriber_is_removed() {
+		let events = EventLog::new(8, 8);
+
+		drop(events.subscribe(None));
+		assert_eq!(events.subscribers(), 1);
+
+		events.publish(diagnostic("anyone"));
+		assert_eq!(events.subscribers(), 0);
+	}
+
+	#[test]
+	fn serialization() {
+		let events = EventLog::new(8, 8);
+		let mut subscription = events.subscribe(None);
+
+		events.publish(EventKind::Resync {
+			reason: String::from("test"),
+		});
+
+		let json = serde_json::to_value(&*subscription.receiver.try_recv().unwrap()).unwrap();
+
+		assert_eq!(
+			json,
+			serde_json::json!({ "seq": 1, "type": "resync", "reason": "test" })
+		);
+	}
+
+	#[test]
+	fn polling() {
+		let events = EventLog::new(3, 8);
+
+		for index in 0..5 {
+			events.publish(diagnostic(&index.to_string()));
+		}
+
+		// New client receives everything that is still buffered
+		let page = events.page(None);
+		assert_eq!(
+			page.events.iter().map(|event| event.seq).collect::<Vec<_>>(),
+			vec![3, 4, 5]
+		);
+		assert!(!page.resync);
+
+		let page = events.page(Some(4));
+		assert_eq!(page.events.len(), 1);
+		assert_eq!(page.latest, 5);
+
+		// Dropped events and server restarts require a resync
+		assert!(events.page(Some(1)).resync);
+		assert!(events.page(Some(10)).resync);
+		assert!(events.page(Some(10)).events.is_empty());
+	}
+}
+
+mod pending {
+	use crate::common::TempDir;
+	use argon::{
+		core::{
+			changes::Changes,
+			pending::{self, Pending, PendingKind},
+			processor::read,
+			queue::PlaceDetails,
+			snapshot::{Snapshot, UpdatedSnapshot},
+			tree::Tree,
+			Core,
+		},
+		project::Project,
+		server::{protocol::Negotiated, Message, SyncChanges},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::types::Ref;
+	use std::{collections::HashSet, fs, path::PathBuf};
+
+	const PROJECT: &str = r#"{
+	"name": "Pending",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": { "$path": "src" }
+	}
+}"#;
+
+	fn fixture(name: &str) -> (TempDir, Core) {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+		fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+		fs::write(dir.join("src/Updated.luau"), "return 1").unwrap();
+		fs::write(dir.join("src/Removed.luau"), "return 2").unwrap();
+
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+		(dir, Core::new(project, false).unwrap())
+	}
+
+	fn find(core: &Core, path: PathBuf) -> Ref {
+		core.tree().get_ids(&path).unwrap()[0]
+	}
+
+	/// Processes files the same way the processor does after a VFS event
+	fn process(core: &Core, paths: &[PathBuf]) -> Changes {
+		let vfs = Vfs::new(false);
+		let mut tree = core.tree();
+		let mut changes = Changes::new();
+
+		for path in paths {
+			let id = tree.get_ids(path).unwrap()[0];
+			changes.extend(read::process_changes(id, &mut tree, &vfs).unwrap());
+		}
+
+		if core.queue().clients().is_empty() {
+			core.pending().record(&changes, &tree);
+		}
+
+		changes
+	}
+
+	fn ids<'a>(ids: impl IntoIterator<Item = &'a Ref>) -> HashSet<Ref> {
+		ids.into_iter().copied().collect()
+	}
+
+	#[test]
+	fn targeted_resync() {
+		let (dir, core) = fixture("resync");
+		let queue = core.queue();
+
+		queue
+			.subscribe(1, "Studio", PlaceDetails::default(), Negotiated::default())
+			.unwrap();
+		core.unsubscribe(1).unwrap();
+
+		let updated = find(&core, dir.join("src/Updated.luau"));
+		let removed = find(&core, dir.join("src/Removed.luau"));
+
+		fs::write(dir.join("src/Updated.luau"), "return 3").unwrap();
+		fs::write(dir.join("src/Added.luau"), "return 4").unwrap();
+		fs::remove_file(dir.join("src/Removed.luau")).unwrap();
+
+		let changes = process(&core, &[dir.join("src")]);
+		let added = changes.additions[0].id;
+
+		let mut pending = core.pending().list();
+		pending.sort_by_key(|instance| instance.path.clone());
+
+		assert_eq!(pending.len(), 3);
+		assert_eq!(
+			pending
+				.iter()
+				.map(|instance| (instance.id, instance.kind))
+				.collect::<Vec<_>>(),
+			vec![
+				(removed, PendingKind::Removed),
+				(added, PendingKind::Added),
+				(updated, PendingKind::Updated),
+			]
+		);
+		assert!(pending[1]
+			.path
+			.as_ref()
+			.is_some_and(|path| path.ends_with("ReplicatedStorage.Added")));
+
+		queue
+			.subscribe(2, "Studio", PlaceDetails::default(), Negotiated::default())
+			.unwrap();
+
+		assert_eq!(core.resync_pending(2).unwrap(), 3);
+
+		let Some(Message::SyncChanges(SyncChanges(resync))) = queue.get_timeout(2).unwrap() else {
+			panic!("Expected targeted resync");
+		};
+
+		assert_eq!(ids(resync.additions.iter().map(|snapshot| &snapshot.id)), ids([&added]));
+		assert_eq!(ids(resync.updates.iter().map(|snapshot| &snapshot.id)), ids([&updated]));
+		assert_eq!(ids(&resync.removals), ids([&removed]));
+
+		assert!(core.pending().is_empty());
+		assert_eq!(core.resync_pending(2).unwrap(), 0);
+
+		// Changes received by a connected client are not pending
+		fs::write(dir.join("src/Updated.luau"), "return 5").unwrap();
+		process(&core, &[dir.join("src")]);
+
+		assert!(core.pending().is_empty());
+	}
+
+	#[test]
+	fn undelivered_changes() {
+		let (dir, core) = fixture("undelivered");
+		let queue = core.queue();
+		let updated = find(&core, dir.join("src/Updated.luau"));
+
+		queue
+			.subscribe(1, "Studio", PlaceDetails::default(), Negotiated::default())
+			.unwrap();
+
+		let mut changes = Changes::new();
+		changes.update(UpdatedSnapshot::new(updated));
+
+		queue.push(SyncChanges(changes), Some(1)).unwrap();
+		core.unsubscribe(1).unwrap();
+
+		let pending = core.pending().list();
+
+		assert_eq!(pending.len(), 1);
+		assert_eq!((pending[0].id, pending[0].kind), (updated, PendingKind::Updated));
+
+		// Full sync covers every pending instance
+		queue
+			.subscribe(2, "Studio", PlaceDetails::default(), Negotiated::default())
+			.unwrap();
+		core.start_sync(2, false).unwrap();
+
+		assert!(core.pending().is_empty());
+	}
+
+	#[test]
+	fn merging() {
+		let tree = Tree::new(Snapshot::new().with_name("Root"));
+		let pending = Pending::new();
+
+		let id = Ref::new();
+		let added = Snapshot::new().with_id(id).with_name("Added");
+
+		let mut changes = Changes::new();
+		changes.add(added, tree.root_ref());
+		pending.record(&changes, &tree);
+
+		let mut changes = Changes::new();
+		changes.update(UpdatedSnapshot::new(id));
+		pending.record(&changes, &tree);
+
+		assert_eq!(pending.list()[0].kind, PendingKind::Added);
+
+		// Client has never seen the instance so there is nothing to remove
+		let mut changes = Changes::new();
+		changes.remove(id);
+		pending.record(&changes, &tree);
+
+		assert!(pending.is_empty());
+		assert_eq!(pending.oldest(), None);
+	}
+
+	#[test]
+	fn age_format() {
+		assert_eq!(pending::format_age(42), "42s");
+		assert_eq!(pending::format_age(4 * 60 + 10), "4m");
+		assert_eq!(pending::format_age(3 * 3600), "3h");
+		assert_eq!(pending::format_age(2 * 86400 + 5), "2d");
+	}
+}
+
+mod protocol {
+	use argon::{
+		config::Config,
+		constants::{MIN_PROTOCOL_VERSION, PROTOCOL_VERSION},
+		core::{
+			changes::Changes,
+			queue::{PlaceDetails, Queue},
+			snapshot::Snapshot,
+		},
+		server::{
+			limits::Limits,
+			protocol::{Capability, Handshake, Negotiated},
+			Message, SyncChanges,
+		},
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr, UstrMap,
+	};
+	use std::time::Duration;
+
+	fn changes() -> Changes {
+		let mut properties = UstrMap::default();
+		properties.insert(Ustr::from("Source"), Variant::String("return {}\n".repeat(200)));
+
+		let script = Snapshot::new()
+			.with_id(Ref::new())
+			.with_name("Module")
+			.with_class("ModuleScript")
+			.with_properties(properties);
+
+		let mut changes = Changes::new();
+		changes.add(script, Ref::new());
+		changes
+	}
+
+	fn receive(queue: &Queue, id: u32) -> Message {
+		queue.get_with_timeout(id, Duration::from_millis(10)).unwrap().unwrap()
+	}
+
+	#[test]
+	fn legacy_client() {
+		let negotiated = Negotiated::new("Legacy", None, &[]).unwrap();
+
+		assert_eq!(negotiated, Negotiated::default());
+		assert_eq!(negotiated.protocol, MIN_PROTOCOL_VERSION);
+		assert!(!negotiated.supports(Capability::Blobs));
+	}
+
+	#[test]
+	fn outdated_client() {
+		let err = Negotiated::new("Studio", Some(MIN_PROTOCOL_VERSION - 1), &[Capability::Blobs]).unwrap_err();
+
+		assert!(err.to_string().contains("Studio"));
+	}
+
+	#[test]
+	fn newer_client() {
+		let capabilities: Vec<Capability> = serde_json::from_str(r#"["blobs", "somethingNew", "blobs"]"#).unwrap();
+		let negotiated = Negotiated::new("Studio", Some(PROTOCOL_VERSION + 1), &capabilities).unwrap();
+
+		assert_eq!(negotiated.protocol, PROTOCOL_VERSION);
+		assert_eq!(negotiated.capabilities, vec![Capability::Blobs]);
+
+		let limits = Limits::new(&Config::default());
+		let handshake = Handshake::new(&negotiated, &limits);
+
+		assert_eq!(handshake.protocol, PROTOCOL_VERSION);
+		assert_eq!(handshake.capabilities, vec![Capability::Blobs]);
+		assert_eq!(handshake.limits, limits);
+	}
+
+	#[test]
+	fn message_shaping() {
+		let queue = Queue::new();
+
+		let old = Negotiated::default();
+		let new = Negotiated::new("New", Some(PROTOCOL_VERSION), &[Capability::Blobs]).unwrap();
+
+		queue.subscribe(1, "Old", PlaceDetails::default(), old.clone()).unwrap();
+		queue.subscribe(2, "New", PlaceDetails::default(), new.clone()).unwrap();
+
+		queue.push(SyncChanges(changes()), None).unwrap();
+
+		assert!(matches!(receive(&queue, 1), Message::SyncChanges(_)));
+		assert!(matches!(receive(&queue, 2), Message::SyncBlobChanges(_)));
+
+		let clients = queue.clients();
+
+		assert_eq!(clients[0].negotiated, old);
+		assert_eq!(clients[1].negotiated, new);
+	}
+}
+
+mod limits {
+	use actix_web::{
+		http::{header, StatusCode},
+		middleware,
+		test::{self, TestRequest},
+		web::{self, Data},
+		App, HttpResponse,
+	};
+	use argon::{
+		config::Config,
+		server::limits::{self, LimitStats, Limiter, Limits, PayloadTooLarge, CLIENT_HEADER},
+	};
+	use std::sync::Arc;
+
+	fn limits() -> Limits {
+		Limits {
+			max_log_size: 16,
+			rate_limit: 1,
+			rate_limit_burst: 2,
+			..Limits::new(&Config::default())
+		}
+	}
+
+	macro_rules! service {
+		($limiter:expr) => {
+			test::init_service(
+				App::new()
+					.app_data(Data::new($limiter.clone()))
+					.wrap(middleware::from_fn(limits::middleware))
+					.route("/log", web::post().to(|| async { HttpResponse::Ok().finish() }))
+					.route("/read", web::post().to(|| async { HttpResponse::Ok().finish() }))
+					.route(
+						"/snapshot",
+						web::post().to(|| async { HttpResponse::Ok().finish() }),
+					),
+			)
+			.await
+		};
+	}
+
+	fn request(path: &str, client: u32, body: &str) -> TestRequest {
+		TestRequest::post()
+			.uri(path)
+			.insert_header((CLIENT_HEADER, client.to_string()))
+			.insert_header((header::CONTENT_LENGTH, body.len().to_string()))
+			.set_payload(body.to_owned())
+	}
+
+	#[actix_web::test]
+	async fn payload_too_large() {
+		let limiter = Arc::new(Limiter::new(limits()));
+		let service = service!(limiter);
+
+		let response = test::call_service(&service, request("/log", 1, &"x".repeat(32)).to_request()).await;
+		assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+		let body: PayloadTooLarge = test::read_body_json(response).await;
+
+		assert_eq!(
+			body,
+			PayloadTooLarge {
+				endpoint: String::from("/log"),
+				limit: 16,
+				size: Some(32),
+			}
+		);
+
+		// Other endpoints have their own limit
+		let response = test::call_service(&service, request("/read", 1, &"x".repeat(32)).to_request()).await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		assert_eq!(
+			limiter.stats(),
+			LimitStats {
+				payload_too_large: 1,
+				..LimitStats::default()
+			}
+		);
+	}
+
+	#[actix_web::test]
+	async fn rate_limited() {
+		let limiter = Arc::new(Limiter::new(limits()));
+		let service = service!(limiter);
+
+		for _ in 0..2 {
+			let response = test::call_service(&service, request("/read", 1, "").to_request()).await;
+			assert_eq!(response.status(), StatusCode::OK);
+		}
+
+		let response = test::call_service(&service, request("/read", 1, "").to_request()).await;
+
+		assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+		assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "1");
+
+		// Every client has its own bucket
+		let response = test::call_service(&service, request("/read", 2, "").to_request()).await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		assert_eq!(limiter.stats().rate_limited, 1);
+	}
+
+	#[actix_web::test]
+	async fn defaults() {
+		let limiter = Arc::new(Limiter::new(Limits::new(&Config::default())));
+		let service = service!(limiter);
+
+		// Initial snapshot followed by a busy session of logs and reads
+		let response = test::call_service(&service, request("/snapshot", 1, "{}").to_request()).await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		for index in 0..200 {
+			let path = if index % 2 == 0 { "/log" } else { "/read" };
+			let body = format!("Log message number {}", index);
+
+			let response = test::call_service(&service, request(path, 1, &body).to_request()).await;
+			assert_eq!(response.status(), StatusCode::OK);
+		}
+
+		assert_eq!(limiter.stats(), LimitStats::default());
+	}
+}
+
+mod dashboard {
+	use argon::{
+		core::queue::{Client, PlaceDetails},
+		dashboard::{
+			client::{Backoff, Fetched, Message, Poller, SessionClient, Update},
+			Connection, Dashboard, FeedEvent, FeedPage,
+		},
+		server::{limits::LimitStats, protocol::Negotiated, Health},
+	};
+	use std::{
+		collections::HashMap,
+		io::{Read, Write},
+		net::TcpListener,
+		thread,
+		time::{Duration, Instant},
+	};
+
+	fn health() -> Health {
+		Health {
+			version: String::from("1.0.0"),
+			project: String::from("Dashboard"),
+			pid: 1,
+			clients: 1,
+			stale_config: None,
+			pending: 0,
+			oldest_pending: None,
+			protocol: 0,
+			capabilities: vec![],
+			tree_hash: String::new(),
+			pause: Default::default(),
+		}
+	}
+
+	fn change(seq: u64, path: &str, class: &str) -> FeedEvent {
+		FeedEvent {
+			seq,
+			kind: String::from("instanceUpdated"),
+			id: None,
+			path: Some(path.to_owned()),
+			class: Some(class.to_owned()),
+			stable_id: None,
+			changed: vec![String::from("Source")],
+			level: None,
+			message: None,
+		}
+	}
+
+	fn diagnostic(seq: u64, message: &str) -> FeedEvent {
+		FeedEvent {
+			seq,
+			kind: String::from("diagnostic"),
+			id: None,
+			path: None,
+			class: None,
+			stable_id: None,
+			changed: vec![],
+			level: Some(String::from("error")),
+			message: Some(message.to_owned()),
+		}
+	}
+
+	fn update(events: Fetched<FeedPage>) -> Message {
+		Message::Update(Box::new(Update {
+			health: Fetched::Ok(health()),
+			clients: Fetched::Ok(vec![]),
+			pending: Fetched::Ok(vec![]),
+			stats: Fetched::Ok(LimitStats::default()),
+			events,
+		}))
+	}
+
+	fn page(events: Vec<FeedEvent>, latest: u64, resync: bool) -> Fetched<FeedPage> {
+		Fetched::Ok(FeedPage { events, latest, resync })
+	}
+
+	fn paths(dashboard: &Dashboard) -> Vec<String> {
+		dashboard
+			.events()
+			.iter()
+			.map(|event| event.path.clone().unwrap())
+			.collect()
+	}
+
+	/// Serves canned responses, paths without one respond with 404
+	fn serve(listener: TcpListener, responses: HashMap<&'static str, (&'static str, Vec<u8>)>) {
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				let Ok(mut stream) = stream else {
+					continue;
+				};
+
+				let mut request = vec![];
+				let mut buffer = [0; 1024];
+
+				while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+					match stream.read(&mut buffer) {
+						Ok(0) | Err(_) => break,
+						Ok(read) => request.extend_from_slice(&buffer[..read]),
+					}
+				}
+
+				let request = String::from_utf8_lossy(&request);
+				let path = request.split_whitespace().nth(1).unwrap_or_default();
+				let path = path.split('?').next().unwrap();
+
+				let (status, content_type, body) = match responses.get(path) {
+					Some((content_type, body)) => ("200 OK", *content_type, body.clone()),
+					None => ("404 Not Found", "text/plain", vec![]),
+				};
+
+				let head = format!(
+					"HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+					status,
+					content_type,
+					body.len()
+				);
+
+				stream.write_all(head.as_bytes()).ok();
+				stream.write_all(&body).ok();
+			}
+		});
+	}
+
+	#[test]
+	fn merge_events() {
+		let mut dashboard = Dashboard::new("0", "http://localhost:8000");
+
+		dashboard.apply(update(page(
+			vec![change(1, "Game.Module", "ModuleScript"), diagnostic(2, "Failed")],
+			2,
+			false,
+		)));
+
+		assert_eq!(dashboard.connection, Connection::Connected);
+		assert_eq!(paths(&dashboard), vec!["Game.Module"]);
+		assert_eq!(dashboard.diagnostics().count(), 1);
+
+		// Events that were already received are skipped
+		dashboard.apply(update(page(
+			vec![
+				change(2, "Game.Module", "ModuleScript"),
+				change(3, "Game.Script", "Script"),
+			],
+			3,
+			false,
+		)));
+
+		assert_eq!(paths(&dashboard), vec!["Game.Script", "Game.Module"]);
+		assert_eq!(dashboard.changes_per_poll(), vec![1, 1]);
+
+		// Server restarted and starts counting from the beginning
+		dashboard.apply(update(page(vec![change(1, "Game.Part", "Part")], 1, true)));
+
+		assert_eq!(paths(&dashboard), vec!["Game.Part", "Game.Script", "Game.Module"]);
+		assert_eq!(dashboard.resyncs, 1);
+
+		dashboard.apply(update(page(vec![change(2, "Game.Model", "Model")], 2, false)));
+		assert_eq!(paths(&dashboard).len(), 4);
+	}
+
+	#[test]
+	fn filter_and_pause() {
+		let mut dashboard = Dashboard::new("0", "http://localhost:8000");
+
+		dashboard.apply(update(page(
+			vec![
+				change(1, "Game.Remotes.Fire", "RemoteEvent"),
+				change(2, "Game.Module", "ModuleScript"),
+			],
+			2,
+			false,
+		)));
+
+		dashboard.set_filter("remote");
+		assert_eq!(paths(&dashboard), vec!["Game.Remotes.Fire"]);
+
+		dashboard.set_filter("modulescript");
+		assert_eq!(paths(&dashboard), vec!["Game.Module"]);
+
+		dashboard.set_filter("");
+		dashboard.toggle_pause();
+
+		dashboard.apply(update(page(vec![change(3, "Game.Script", "Script")], 3, false)));
+		assert_eq!(paths(&dashboard).len(), 2);
+
+		dashboard.select_next();
+		assert_eq!(dashboard.selected().unwrap().seq, 1);
+
+		dashboard.toggle_pause();
+		assert_eq!(paths(&dashboard).len(), 3);
+	}
+
+	#[test]
+	fn degradation() {
+		let mut dashboard = Dashboard::new("0", "http://localhost:8000");
+
+		dashboard.apply(update(page(vec![], 0, false)));
+		assert!(dashboard.stats.is_some());
+
+		// Failed requests keep the last known state
+		dashboard.apply(Message::Update(Box::new(Update {
+			health: Fetched::Ok(health()),
+			clients: Fetched::Failed(String::from("timed out")),
+			pending: Fetched::Ok(vec![]),
+			stats: Fetched::Missing,
+			events: Fetched::Missing,
+		})));
+
+		assert!(dashboard.clients.is_some());
+		assert!(dashboard.stats.is_none());
+		assert!(dashboard.is_missing("/stats"));
+		assert!(dashboard.is_missing("/events"));
+		assert!(!dashboard.is_missing("/clients"));
+
+		dashboard.apply(Message::Disconnected {
+			error: String::from("connection refused"),
+			retry_in: Duration::from_secs(1),
+		});
+
+		assert!(matches!(dashboard.connection, Connection::Disconnected { .. }));
+		assert!(dashboard.health.is_some());
+	}
+
+	#[test]
+	fn backoff() {
+		let mut backoff = Backoff::new();
+		let now = Instant::now();
+
+		assert!(backoff.is_due(now));
+
+		let first = backoff.failure(now);
+		let second = backoff.failure(now);
+
+		assert!(second > first);
+		assert!(!backoff.is_due(now));
+		assert!(backoff.is_due(now + second));
+
+		for _ in 0..20 {
+			backoff.failure(now);
+		}
+
+		assert!(backoff.failure(now) <= Duration::from_secs(10));
+
+		backoff.success();
+		assert_eq!(backoff.failures(), 0);
+	}
+
+	#[test]
+	fn reconnect() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let address = format!("http://{}", listener.local_addr().unwrap());
+
+		// Nothing is listening yet
+		drop(listener);
+
+		let mut poller = Poller::new(SessionClient::new(&address));
+		let now = Instant::now();
+
+		let retry_in = match poller.tick(now) {
+			Some(Message::Disconnected { retry_in, .. }) => retry_in,
+			message => panic!("Expected disconnection, got {:?}", message),
+		};
+
+		// Next attempt is not due yet
+		assert!(poller.tick(now).is_none());
+
+		let client = Client {
+			id: 7,
+			name: String::from("Studio"),
+			place: PlaceDetails::default(),
+			negotiated: Negotiated::default(),
+			filter: None,
+		};
+
+		// Older server without `/events` and `/stats`
+		serve(
+			TcpListener::bind(address.trim_start_matches("http://")).unwrap(),
+			HashMap::from([
+				("/health", ("application/json", serde_json::to_vec(&health()).unwrap())),
+				(
+					"/clients",
+					("application/msgpack", rmp_serde::to_vec_named(&vec![client]).unwrap()),
+				),
+				("/pending", ("application/json", b"[]".to_vec())),
+			]),
+		);
+
+		let update = match poller.tick(now + retry_in) {
+			Some(Message::Update(update)) => update,
+			message => panic!("Expected update, got {:?}", message),
+		};
+
+		assert_eq!(update.health.clone().ok().unwrap().project, "Dashboard");
+		assert_eq!(update.events, Fetched::Missing);
+		assert_eq!(update.stats, Fetched::Missing);
+
+		let mut dashboard = Dashboard::new("0", &address);
+		dashboard.apply(Message::Update(update));
+
+		assert_eq!(dashboard.clients.as_ref().unwrap()[0].name, "Studio");
+		assert!(dashboard.is_missing("/events"));
+	}
+}
+
+mod deferred {
+	use argon::{
+		core::{
+			deferred::{self, PropertyRequest},
+			initial_sync::InitialSync,
+			queue::{PlaceDetails, Queue},
+			snapshot::Snapshot,
+			tree::Tree,
+		},
+		project::LazyProperties,
+		server::{
+			protocol::{Capability, Negotiated},
+			Message, SyncChunk,
+		},
+		vfs::Vfs,
+		Properties,
+	};
+	use rbx_dom_weak::{
+		types::{BinaryString, Ref, Variant},
+		HashMapExt, Ustr, UstrMap,
+	};
+	use std::{sync::Mutex, time::Duration};
+
+	const THRESHOLD: usize = 100;
+
+	fn settings() -> LazyProperties {
+		LazyProperties {
+			properties: vec![String::from("Source")],
+			threshold: THRESHOLD,
+		}
+	}
+
+	fn script(name: &str, size: usize) -> Snapshot {
+		let mut properties = UstrMap::new();
+		properties.insert(Ustr::from("Source"), Variant::String("-".repeat(size)));
+
+		Snapshot::new()
+			.with_name(name)
+			.with_class("ModuleScript")
+			.with_properties(properties)
+	}
+
+	fn tree() -> Tree {
+		Tree::new(
+			Snapshot::new()
+				.with_name("Root")
+				.with_children(vec![script("Small", THRESHOLD - 1), script("Large", THRESHOLD * 10)]),
+		)
+	}
+
+	fn child(tree: &Tree, name: &str) -> Ref {
+		let root = tree.get_instance(tree.root_ref()).unwrap();
+
+		*root
+			.children()
+			.iter()
+			.find(|id| tree.get_instance(**id).unwrap().name == name)
+			.unwrap()
+	}
+
+	fn source() -> Ustr {
+		Ustr::from("Source")
+	}
+
+	/// Chunks received by the client during the whole initial sync
+	fn sync(negotiated: Negotiated) -> Vec<SyncChunk> {
+		let tree = Mutex::new(tree());
+		let vfs = Vfs::new_virtual();
+		let queue = Queue::new();
+		let sync = InitialSync::new(10, 2, Duration::from_secs(60)).with_lazy_properties(settings());
+
+		queue
+			.subscribe(1, "Client", PlaceDetails::default(), negotiated)
+			.unwrap();
+		sync.start(1, false, &tree, &vfs, &queue).unwrap();
+
+		let mut chunks = vec![];
+
+		while let Some(message) = queue.get_with_timeout(1, Duration::from_millis(10)).unwrap() {
+			if let Message::SyncChunk(chunk) = message {
+				chunks.push(chunk);
+			}
+		}
+
+		chunks
+	}
+
+	#[test]
+	fn threshold() {
+		let mut properties: Properties = UstrMap::new();
+
+		properties.insert(Ustr::from("Source"), Variant::String("-".repeat(THRESHOLD)));
+		properties.insert(Ustr::from("Value"), Variant::String("-".repeat(THRESHOLD * 10)));
+		properties.insert(
+			Ustr::from("SmoothGrid"),
+			Variant::BinaryString(BinaryString::from(vec![0; THRESHOLD * 2])),
+		);
+		properties.insert(
+			Ustr::from("PhysicsData"),
+			Variant::BinaryString(BinaryString::from(vec![0; THRESHOLD - 1])),
+		);
+
+		let id = Ref::new();
+		let mut deferred = deferred::defer(id, &mut properties, &settings());
+
+		deferred.sort_by_key(|deferred| deferred.property);
+
+		let names: Vec<&str> = deferred.iter().map(|deferred| deferred.property.as_str()).collect();
+
+		// Only designated strings and large binary strings
+		assert_eq!(names, vec!["SmoothGrid", "Source"]);
+		assert!(deferred.iter().all(|deferred| deferred.id == id));
+		assert_eq!(deferred[1].size, THRESHOLD);
+
+		assert!(properties.contains_key(&Ustr::from("Value")));
+		assert!(properties.contains_key(&Ustr::from("PhysicsData")));
+		assert!(!properties.contains_key(&source()));
+
+		// Nothing is deferred below the threshold
+		let mut properties = script("Small", THRESHOLD - 1).properties.clone();
+		assert!(deferred::defer(id, &mut properties, &settings()).is_empty());
+	}
+
+	#[test]
+	fn batch_order() {
+		let tree = tree();
+		let vfs = Vfs::new_virtual();
+
+		let small = child(&tree, "Small");
+		let large = child(&tree, "Large");
+
+		let requests = vec![
+			PropertyRequest {
+				id: large,
+				property: source(),
+			},
+			PropertyRequest {
+				id: Ref::new(),
+				property: source(),
+			},
+			PropertyRequest {
+				id: small,
+				property: Ustr::from("Missing"),
+			},
+			PropertyRequest {
+				id: small,
+				property: source(),
+			},
+		];
+
+		let values = deferred::read(&requests, &tree, &vfs);
+
+		assert_eq!(values.len(), requests.len());
+
+		for (value, request) in values.iter().zip(&requests) {
+			assert_eq!(value.id, request.id);
+			assert_eq!(value.property, request.property);
+		}
+
+		assert_eq!(values[0].value, Some(Variant::String("-".repeat(THRESHOLD * 10))));
+		assert_eq!(values[1].value, None);
+		assert_eq!(values[2].value, None);
+		assert_eq!(values[3].value, Some(Variant::String("-".repeat(THRESHOLD - 1))));
+	}
+
+	#[test]
+	fn capability_fallback() {
+		let lazy = sync(Negotiated::new("Studio", Some(2), &[Capability::LazyProperties]).unwrap());
+		let deferred: Vec<_> = lazy.iter().flat_map(|chunk| &chunk.deferred).collect();
+
+		assert_eq!(deferred.len(), 1);
+		assert_eq!(deferred[0].property, source());
+
+		let large = lazy
+			.iter()
+			.flat_map(|chunk| &chunk.instances)
+			.find(|instance| instance.name == "Large")
+			.unwrap();
+
+		assert_eq!(deferred[0].id, large.id);
+		assert!(!large.properties.contains_key(&source()));
+
+		// Old plugins get full values
+		let full = sync(Negotiated::default());
+
+		assert!(full.iter().all(|chunk| chunk.deferred.is_empty()));
+		assert!(full
+			.iter()
+			.flat_map(|chunk| &chunk.instances)
+			.filter(|instance| instance.class == "ModuleScript")
+			.all(|instance| instance.properties.contains_key(&source())));
+	}
+}
+
+mod path_index {
+	use argon::core::{path_index::Completion, snapshot::Snapshot, tree::Tree};
+	use rbx_dom_weak::types::Ref;
+
+	fn instance(name: &str, class: &str, children: Vec<Snapshot>) -> Snapshot {
+		Snapshot::new()
+			.with_name(name)
+			.with_class(class)
+			.with_children(children)
+	}
+
+	fn storage() -> Snapshot {
+		instance(
+			"ReplicatedStorage",
+			"ReplicatedStorage",
+			vec![
+				instance(
+					"Modules",
+					"Folder",
+					vec![
+						instance("ModuleA", "ModuleScript", vec![]),
+						instance("Util", "ModuleScript", vec![]),
+					],
+				),
+				instance("Model", "Model", vec![]),
+				instance("Remotes", "Folder", vec![instance("Modified", "RemoteEvent", vec![])]),
+			],
+		)
+	}
+
+	fn tree() -> Tree {
+		Tree::new(instance("Game", "DataModel", vec![storage()]))
+	}
+
+	fn child(tree: &Tree, path: &str) -> Ref {
+		path.split('/').fold(tree.root_ref(), |id, name| {
+			*tree
+				.get_instance(id)
+				.unwrap()
+				.children()
+				.iter()
+				.find(|child| tree.get_instance(**child).unwrap().name == name)
+				.unwrap_or_else(|| panic!("Missing {}", name))
+		})
+	}
+
+	fn paths(completions: &[Completion]) -> Vec<&str> {
+		completions.iter().map(|completion| completion.path.as_str()).collect()
+	}
+
+	fn complete(tree: &mut Tree, prefix: &str) -> Vec<String> {
+		tree.refresh_path_index();
+
+		paths(&tree.path_index().complete(prefix, 100))
+			.into_iter()
+			.map(String::from)
+			.collect()
+	}
+
+	#[test]
+	fn ranking() {
+		let mut tree = tree();
+
+		assert_eq!(
+			complete(&mut tree, "ReplicatedStorage/Mod"),
+			vec!["ReplicatedStorage/Model", "ReplicatedStorage/Modules"]
+		);
+
+		// Prefix matches first, then matches deeper in the tree ordered by depth
+		assert_eq!(
+			complete(&mut tree, "mod"),
+			vec![
+				"ReplicatedStorage/Model",
+				"ReplicatedStorage/Modules",
+				"ReplicatedStorage/Modules/ModuleA",
+				"ReplicatedStorage/Remotes/Modified",
+			]
+		);
+
+		assert_eq!(
+			complete(&mut tree, "replicatedstorage/"),
+			vec![
+				"ReplicatedStorage/Model",
+				"ReplicatedStorage/Modules",
+				"ReplicatedStorage/Remotes",
+			]
+		);
+
+		let completions = tree.path_index().complete("Remotes/Mod", 100);
+
+		assert_eq!(paths(&completions), vec!["ReplicatedStorage/Remotes/Modified"]);
+		assert_eq!(completions[0].class, "RemoteEvent");
+		assert_eq!(completions[0].id, child(&tree, "ReplicatedStorage/Remotes/Modified"));
+
+		// Limit keeps the best ranked ones
+		assert_eq!(
+			paths(&tree.path_index().complete("mod", 2)),
+			vec!["ReplicatedStorage/Model", "ReplicatedStorage/Modules"]
+		);
+	}
+
+	#[test]
+	fn stable_order() {
+		let mut tree = tree();
+		let first = complete(&mut tree, "m");
+
+		// Adding unrelated instances does not reorder existing matches
+		tree.insert_instance(instance("Lighting", "Lighting", vec![]), tree.root_ref());
+
+		let modules = child(&tree, "ReplicatedStorage/Modules");
+		let util = child(&tree, "ReplicatedStorage/Modules/Util");
+
+		tree.remove_instance(util);
+		tree.insert_instance(instance("Util", "ModuleScript", vec![]), modules);
+
+		assert_eq!(complete(&mut tree, "m"), first);
+	}
+
+	#[test]
+	fn incremental() {
+		let mut tree = tree();
+
+		// Add
+		let modules = child(&tree, "ReplicatedStorage/Modules");
+		tree.insert_instance_recursive(
+			instance("Shared", "Folder", vec![instance("Math", "ModuleScript", vec![])]),
+			modules,
+		);
+
+		assert_eq!(
+			complete(&mut tree, "ReplicatedStorage/Modules/Shared/"),
+			vec!["ReplicatedStorage/Modules/Shared/Math"]
+		);
+
+		// Rename, descendants follow without being touched
+		tree.get_instance_mut(modules).unwrap().name = String::from("Packages");
+		tree.refresh_path_index();
+
+		assert!(complete(&mut tree, "ReplicatedStorage/Modules").is_empty());
+		assert_eq!(
+			complete(&mut tree, "replicatedstorage/packages/shared/m"),
+			vec!["ReplicatedStorage/Packages/Shared/Math"]
+		);
+		assert_eq!(
+			tree.path_index().path(child(&tree, "ReplicatedStorage/Packages/Util")),
+			Some(String::from("Game/ReplicatedStorage/Packages/Util"))
+		);
+
+		// Remove
+		let shared = child(&tree, "ReplicatedStorage/Packages/Shared");
+		let math = child(&tree, "ReplicatedStorage/Packages/Shared/Math");
+
+		tree.remove_instance(shared);
+
+		assert!(complete(&mut tree, "math").is_empty());
+		assert_eq!(tree.path_index().path(math), None);
+
+		// Same result as an index built from scratch
+		let mut fresh = Tree::new(instance(
+			"Game",
+			"DataModel",
+			vec![instance(
+				"ReplicatedStorage",
+				"ReplicatedStorage",
+				vec![
+					instance(
+						"Packages",
+						"Folder",
+						vec![
+							instance("ModuleA", "ModuleScript", vec![]),
+							instance("Util", "ModuleScript", vec![]),
+						],
+					),
+					instance("Model", "Model", vec![]),
+					instance("Remotes", "Folder", vec![instance("Modified", "RemoteEvent", vec![])]),
+				],
+			)],
+		));
+
+		assert_eq!(tree.path_index().len(), fresh.path_index().len());
+		assert_eq!(complete(&mut tree, ""), complete(&mut fresh, ""));
+	}
+}
+
+mod class_meta {
+	use argon::{
+		class_meta::{self, IconRule, DEFAULT_ICON},
+		glob::Glob,
+	};
+
+	fn rule(class: &str, name: Option<&str>, icon: &str) -> IconRule {
+		IconRule {
+			class: class.to_owned(),
+			name: name.map(|name| Glob::new(name).unwrap()),
+			icon: icon.to_owned(),
+		}
+	}
+
+	#[test]
+	fn categories() {
+		assert_eq!(class_meta::category("Script"), "scripts");
+		assert_eq!(class_meta::category("ModuleScript"), "scripts");
+		assert_eq!(class_meta::category("IntValue"), "values");
+		assert_eq!(class_meta::category("RemoteEvent"), "networking");
+		assert_eq!(class_meta::category("Folder"), "containers");
+		assert_eq!(class_meta::category("Part"), "world");
+		assert_eq!(class_meta::category("RopeConstraint"), "physics");
+		assert_eq!(class_meta::category("Frame"), "gui");
+		assert_eq!(class_meta::category("PointLight"), "effects");
+		assert_eq!(class_meta::category("ReplicatedStorage"), "services");
+		assert_eq!(class_meta::category("Workspace"), "services");
+		assert_eq!(class_meta::category("NotAClass"), "other");
+	}
+
+	#[test]
+	fn metadata() {
+		let workspace = class_meta::get("Workspace", &[]);
+
+		assert!(workspace.service);
+		assert!(!workspace.creatable);
+		assert_eq!(workspace.icon, "workspace");
+		assert_eq!(workspace.superclasses.last().map(String::as_str), Some("Instance"));
+
+		let part = class_meta::get("Part", &[]);
+
+		assert!(!part.service);
+		assert!(part.creatable);
+		assert!(part.superclasses.contains(&String::from("BasePart")));
+
+		// Inherited from `BasePart`
+		assert_eq!(part.icon, "part");
+		assert_eq!(class_meta::get("MeshPart", &[]).icon, "mesh-part");
+		assert_eq!(class_meta::get("Instance", &[]).icon, DEFAULT_ICON);
+	}
+
+	#[test]
+	fn project_icons() {
+		let rules = [
+			rule("Part", None, "brick"),
+			rule("ModuleScript", Some("*Service"), "knit-service"),
+		];
+
+		assert_eq!(class_meta::icon("Part", &rules), "brick");
+		assert_eq!(class_meta::icon("ModuleScript", &rules), "module-script");
+
+		assert_eq!(
+			class_meta::get_instance("ModuleScript", "DataService", &rules).icon,
+			"knit-service"
+		);
+		assert_eq!(
+			class_meta::get_instance("ModuleScript", "Utils", &rules).icon,
+			"module-script"
+		);
+
+		let table = class_meta::table(&rules);
+
+		assert_eq!(table.classes["Part"].icon, "brick");
+		assert_eq!(table.rules, vec![rules[1].clone()]);
+	}
+
+	#[test]
+	fn etag() {
+		let version = rbx_reflection_database::get().version;
+		let etag = class_meta::etag(version, &[]);
+
+		assert_eq!(etag, class_meta::table(&[]).etag);
+		assert_eq!(etag, class_meta::etag(version, &[]));
+
+		let mut newer = version;
+		newer[1] += 1;
+
+		assert_ne!(etag, class_meta::etag(newer, &[]));
+		assert_ne!(etag, class_meta::etag(version, &[rule("Part", None, "brick")]));
+	}
+}
+
+mod durable_queues {
+	use crate::common::TempDir;
+	use argon::{
+		constants::PROTOCOL_VERSION,
+		core::{
+			changes::Changes,
+			checkpoint::{self, QueueCheckpoint, QueueCursor, Resume},
+			pending::PendingKind,
+			processor::read,
+			queue::PlaceDetails,
+			Core,
+		},
+		project::Project,
+		server::{
+			protocol::{Capability, Negotiated},
+			Message, SyncChanges, SyncComplete, SyncResume,
+		},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::types::Ref;
+	use std::{
+		fs,
+		path::{Path, PathBuf},
+		time::Duration,
+	};
+
+	const PROJECT: &str = r#"{
+	"name": "Durable",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": { "$path": "src" }
+	}
+}"#;
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+		fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+		fs::write(dir.join("src/Updated.luau"), "return 1").unwrap();
+		fs::write(dir.join("src/Removed.luau"), "return 2").unwrap();
+
+		dir
+	}
+
+	/// Starts a new in-process server with the client subscribed
+	fn start(dir: &Path) -> Core {
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+		let core = Core::new(project, false).unwrap();
+
+		let negotiated = Negotiated::new("Studio", Some(PROTOCOL_VERSION), &[Capability::Resume]).unwrap();
+
+		core.queue()
+			.subscribe(1, "Studio", PlaceDetails::default(), negotiated)
+			.unwrap();
+
+		core
+	}
+
+	fn find(core: &Core, path: PathBuf) -> Ref {
+		core.tree().get_ids(&path).unwrap()[0]
+	}
+
+	/// Processes files and queues the changes the same way the processor does
+	fn process(core: &Core, paths: &[PathBuf]) -> Changes {
+		let vfs = Vfs::new(false);
+		let mut tree = core.tree();
+		let mut changes = Changes::new();
+
+		for path in paths {
+			let id = tree.get_ids(path).unwrap()[0];
+			changes.extend(read::process_changes(id, &mut tree, &vfs).unwrap());
+		}
+
+		tree.save_stable_ids().unwrap();
+		core.queue().push(SyncChanges(changes.clone()), None).unwrap();
+
+		changes
+	}
+
+	/// Checkpoints queues and stops the server, returns cursor of the client
+	fn stop(core: Core, received: u64) -> QueueCursor {
+		core.checkpoint_queues().unwrap();
+
+		QueueCursor {
+			generation: core.queue_generation().to_owned(),
+			received,
+		}
+	}
+
+	fn next(core: &Core) -> Option<Message> {
+		core.queue().get_with_timeout(1, Duration::from_millis(100)).unwrap()
+	}
+
+	#[test]
+	fn continuation() {
+		let dir = fixture("continuation");
+		let core = start(&dir);
+
+		fs::write(dir.join("src/Updated.luau"), "return 3").unwrap();
+		process(&core, &[dir.join("src/Updated.luau")]);
+
+		// Client received the update before the restart
+		assert!(next(&core).is_some());
+
+		let updated = find(&core, dir.join("src/Updated.luau"));
+		let removed = find(&core, dir.join("src/Removed.luau"));
+
+		fs::write(dir.join("src/Added.luau"), "return 4").unwrap();
+		fs::remove_file(dir.join("src/Removed.luau")).unwrap();
+
+		let added = process(&core, &[dir.join("src")]).additions[0].id;
+
+		let cursor = stop(core, 1);
+		assert!(checkpoint::queue_path(&dir, 1).exists());
+
+		let core = start(&dir);
+
+		assert_ne!(cursor.generation, core.queue_generation());
+		assert_eq!(core.resume(1, &cursor).unwrap(), Resume::Continue);
+		assert!(!checkpoint::queue_path(&dir, 1).exists());
+
+		core.resync_pending(1).unwrap();
+
+		let Some(Message::SyncResume(SyncResume { refs })) = next(&core) else {
+			panic!("Expected SyncResume message");
+		};
+
+		assert_eq!(refs[&updated], find(&core, dir.join("src/Updated.luau")));
+		assert_eq!(refs[&added], find(&core, dir.join("src/Added.luau")));
+		assert!(!refs.contains_key(&removed));
+
+		let Some(Message::SyncChanges(SyncChanges(changes))) = next(&core) else {
+			panic!("Expected SyncChanges message");
+		};
+
+		// Update was received before the restart so it is not sent again
+		assert_eq!(
+			changes.additions.iter().map(|snapshot| snapshot.id).collect::<Vec<_>>(),
+			vec![refs[&added]]
+		);
+		assert!(changes.updates.is_empty());
+		assert_eq!(changes.removals, vec![removed]);
+
+		assert!(next(&core).is_none());
+	}
+
+	#[test]
+	fn changed_while_down() {
+		let dir = fixture("changed");
+		let core = start(&dir);
+
+		fs::write(dir.join("src/Updated.luau"), "return 3").unwrap();
+		process(&core, &[dir.join("src/Updated.luau")]);
+
+		let cursor = stop(core, 0);
+
+		fs::write(dir.join("src/Updated.luau"), "return 5").unwrap();
+
+		let core = start(&dir);
+
+		assert_eq!(core.resume(1, &cursor).unwrap(), Resume::Resync);
+		assert!(next(&core).is_none());
+	}
+
+	#[test]
+	fn cursor_mismatch() {
+		let dir = fixture("cursor");
+		let core = start(&dir);
+
+		fs::write(dir.join("src/Updated.luau"), "return 3").unwrap();
+		process(&core, &[dir.join("src/Updated.luau")]);
+
+		// Client claims it received a message that was never delivered
+		let cursor = stop(core, 1);
+		let core = start(&dir);
+
+		assert_eq!(core.resume(1, &cursor).unwrap(), Resume::Resync);
+		assert!(next(&core).is_none());
+
+		// Checkpoint is consumed by the first attempt
+		let core = start(&dir);
+
+		let unknown = QueueCursor {
+			generation: String::from("unknown"),
+			received: 0,
+		};
+
+		assert_eq!(core.resume(1, &unknown).unwrap(), Resume::Resync);
+	}
+
+	#[test]
+	fn volatile_messages() {
+		let dir = fixture("volatile");
+		let core = start(&dir);
+
+		// Part of the initial sync can't be restored
+		core.queue()
+			.push(
+				SyncComplete {
+					total: 0,
+					checksum: String::new(),
+				},
+				Some(1),
+			)
+			.unwrap();
+
+		let cursor = stop(core, 0);
+		let core = start(&dir);
+
+		assert_eq!(core.resume(1, &cursor).unwrap(), Resume::Resync);
+	}
+
+	#[test]
+	fn size_cap() {
+		let dir = fixture("size");
+		let path = checkpoint::queue_path(&dir, 1);
+
+		let checkpoint = QueueCheckpoint {
+			version: checkpoint::CHECKPOINT_VERSION,
+			client_id: 1,
+			generation: String::from("generation"),
+			delivered: 3,
+			tree_hash: String::from("hash"),
+			refs: (0..100).map(|index| (index.to_string(), Ref::new())).collect(),
+			entries: vec![],
+			invalid: false,
+		};
+
+		assert!(checkpoint.write(&path, usize::MAX).unwrap());
+		assert_eq!(QueueCheckpoint::read(&path).unwrap(), checkpoint);
+
+		assert!(!checkpoint.write(&path, 64).unwrap());
+
+		let written = QueueCheckpoint::read(&path).unwrap();
+
+		assert!(written.invalid);
+		assert!(written.refs.is_empty());
+		assert_eq!(written.delivered, 3);
+
+		// Invalid checkpoints always make their clients resync
+		let core = start(&dir);
+		let hash = core.tree_hash();
+		let restored = written.reconcile(&core.tree(), hash);
+
+		assert!(restored.resumed.is_none());
+	}
+
+	#[test]
+	fn coalesce() {
+		let (a, b, c, d) = (Ref::new(), Ref::new(), Ref::new(), Ref::new());
+
+		let coalesced = checkpoint::coalesce([
+			(a, PendingKind::Added),
+			(b, PendingKind::Updated),
+			(a, PendingKind::Updated),
+			(c, PendingKind::Added),
+			(b, PendingKind::Removed),
+			(c, PendingKind::Removed),
+			(d, PendingKind::Updated),
+		]);
+
+		assert_eq!(
+			coalesced,
+			vec![
+				(a, PendingKind::Added),
+				(b, PendingKind::Removed),
+				(d, PendingKind::Updated),
+			]
+		);
+	}
+}
+
+mod heartbeat {
+	use argon::{
+		core::{
+			heartbeat::{self, Heartbeats, NodeHash},
+			queue::{PlaceDetails, Queue},
+			snapshot::Snapshot,
+			tree::Tree,
+		},
+		server::{
+			protocol::{Capability, Negotiated},
+			Message,
+		},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::types::Ref;
+	use std::{collections::HashMap, mem, time::Duration};
+
+	const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+	const FNV_PRIME: u64 = 0x100000001b3;
+
+	/// Instance as the plugin keeps it
+	#[derive(Debug, Clone)]
+	struct Node {
+		name: String,
+		class: String,
+		parent: Ref,
+		children: Vec<Ref>,
+	}
+
+	/// Client tree simulated independently of the server implementation
+	struct Client {
+		nodes: HashMap<Ref, Node>,
+	}
+
+	impl Client {
+		fn mirror(tree: &Tree) -> Self {
+			let mut nodes = HashMap::new();
+			let mut stack = vec![tree.root_ref()];
+
+			while let Some(id) = stack.pop() {
+				let instance = tree.get_instance(id).unwrap();
+
+				nodes.insert(
+					id,
+					Node {
+						name: instance.name.clone(),
+						class: instance.class.to_string(),
+						parent: instance.parent(),
+						children: instance.children().to_vec(),
+					},
+				);
+
+				stack.extend(instance.children());
+			}
+
+			Self { nodes }
+		}
+
+		fn hash(&self, id: Ref) -> String {
+			fn walk(client: &Client, id: Ref) -> u64 {
+				let node = &client.nodes[&id];
+				let mut children: Vec<u64> = node.children.iter().map(|child| walk(client, *child)).collect();
+
+				children.sort();
+
+				let mut state = update(FNV_OFFSET, &format!("{}\t{}\t{}\n", id, node.name, node.class));
+
+				for child in children {
+					state = update(state, &format!("{:016x}\n", child));
+				}
+
+				state
+			}
+
+			format!("{:016x}", walk(self, id))
+		}
+
+		fn insert(&mut self, mut snapshot: Snapshot, parent: Ref) {
+			if self.nodes.contains_key(&snapshot.id) {
+				self.remove(snapshot.id);
+			}
+
+			if let Some(parent) = self.nodes.get_mut(&parent) {
+				parent.children.push(snapshot.id);
+			}
+
+			self.nodes.insert(
+				snapshot.id,
+				Node {
+					name: mem::take(&mut snapshot.name),
+					class: snapshot.class.to_string(),
+					parent,
+					children: vec![],
+				},
+			);
+
+			for child in mem::take(&mut snapshot.children) {
+				self.insert(child, snapshot.id);
+			}
+		}
+
+		fn remove(&mut self, id: Ref) {
+			let node = self.nodes.remove(&id).unwrap();
+
+			if let Some(parent) = self.nodes.get_mut(&node.parent) {
+				parent.children.retain(|child| *child != id);
+			}
+
+			for child in node.children {
+				self.remove(child);
+			}
+		}
+	}
+
+	fn update(mut state: u64, line: &str) -> u64 {
+		for byte in line.bytes() {
+			state ^= byte as u64;
+			state = state.wrapping_mul(FNV_PRIME);
+		}
+
+		state
+	}
+
+	/// 10 folders with 10 folders with 10 values, 1111 instances in total
+	fn tree() -> Tree {
+		let folders = (0..10)
+			.map(|i| {
+				Snapshot::new()
+					.with_name(format!("Folder{}", i))
+					.with_class("Folder")
+					.with_children(
+						(0..10)
+							.map(|j| {
+								Snapshot::new()
+									.with_name(format!("Folder{}", j))
+									.with_class("Folder")
+									.with_children(
+										(0..10)
+											.map(|k| {
+												Snapshot::new()
+													.with_name(format!("Value{}", k))
+													.with_class("StringValue")
+											})
+											.collect(),
+									)
+							})
+							.collect(),
+					)
+			})
+			.collect();
+
+		Tree::new(Snapshot::new().with_name("Root").with_children(folders))
+	}
+
+	fn find(tree: &Tree, path: &str) -> Ref {
+		tree.find_by_path(path).unwrap()
+	}
+
+	fn subscribe(queue: &Queue, id: u32, capabilities: &[Capability]) {
+		queue
+			.subscribe(
+				id,
+				"Client",
+				PlaceDetails::default(),
+				Negotiated::new("Client", None, capabilities).unwrap(),
+			)
+			.unwrap();
+	}
+
+	fn messages(queue: &Queue, id: u32) -> Vec<Message> {
+		let mut messages = vec![];
+
+		while let Some(message) = queue.get_with_timeout(id, Duration::from_millis(10)).unwrap() {
+			messages.push(message);
+		}
+
+		messages
+	}
+
+	/// Runs the plugin side of the exchange until the client tree matches,
+	/// returns number of heartbeats and number of hashes sent both ways
+	fn converge(client: &mut Client, heartbeats: &Heartbeats, tree: &Tree, vfs: &Vfs, queue: &Queue) -> (usize, usize) {
+		let mut rounds = 0;
+		let mut exchanged = 0;
+
+		loop {
+			rounds += 1;
+			assert!(rounds <= 5, "Client did not converge");
+
+			heartbeats.send(tree, queue, |_| false).unwrap();
+
+			let Some(Message::Heartbeat(heartbeat)) = messages(queue, 1).pop() else {
+				panic!("Heartbeat was not sent");
+			};
+
+			if client.hash(heartbeat.id) == heartbeat.hash {
+				return (rounds, exchanged);
+			}
+
+			let mut pending = vec![heartbeat.id];
+			let mut resync = vec![];
+
+			while !pending.is_empty() || !resync.is_empty() {
+				let compare: Vec<NodeHash> = pending
+					.drain(..)
+					.map(|id| NodeHash {
+						id,
+						hash: client.hash(id),
+					})
+					.collect();
+
+				exchanged += compare.len();
+
+				let comparison = heartbeats.compare(1, &compare, &resync, tree, vfs, queue).unwrap();
+
+				resync.clear();
+
+				for message in messages(queue, 1) {
+					if let Message::SyncSubtree(subtree) = message {
+						let parent = subtree.instance.parent;
+						client.insert((*subtree.instance).into(), parent);
+					}
+				}
+
+				for id in comparison.removed {
+					client.remove(id);
+				}
+
+				for divergent in comparison.divergent {
+					exchanged += divergent.children.len();
+
+					let mut narrowed = false;
+
+					for child in &divergent.children {
+						if !client.nodes.contains_key(&child.id) {
+							resync.push(child.id);
+							narrowed = true;
+						} else if client.hash(child.id) != child.hash {
+							pending.push(child.id);
+							narrowed = true;
+						}
+					}
+
+					let extra: Vec<Ref> = client.nodes[&divergent.id]
+						.children
+						.iter()
+						.filter(|id| !divergent.children.iter().any(|child| child.id == **id))
+						.copied()
+						.collect();
+
+					for id in extra {
+						client.remove(id);
+						narrowed = true;
+					}
+
+					// Children match so the instance itself differs
+					if !narrowed {
+						resync.push(divergent.id);
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn hash_ignores_order() {
+		let tree = tree();
+		let client = Client::mirror(&tree);
+
+		let root = tree.root_ref();
+		let hash = heartbeat::format_hash(heartbeat::hash_subtree(&tree, root).unwrap());
+
+		assert_eq!(client.hash(root), hash);
+
+		let mut reversed = Client::mirror(&tree);
+		reversed.nodes.get_mut(&root).unwrap().children.reverse();
+
+		assert_eq!(reversed.hash(root), hash);
+		assert!(heartbeat::hash_subtree(&tree, Ref::new()).is_none());
+	}
+
+	#[test]
+	fn capability() {
+		let tree = tree();
+		let queue = Queue::new();
+		let heartbeats = Heartbeats::new();
+
+		subscribe(&queue, 1, &[Capability::Heartbeat]);
+		subscribe(&queue, 2, &[]);
+		subscribe(&queue, 3, &[Capability::Heartbeat]);
+
+		// Clients in the middle of the initial sync are skipped
+		assert_eq!(heartbeats.send(&tree, &queue, |id| id == 3).unwrap(), 1);
+
+		assert!(matches!(messages(&queue, 1).as_slice(), [Message::Heartbeat(_)]));
+		assert!(messages(&queue, 2).is_empty());
+		assert!(messages(&queue, 3).is_empty());
+
+		assert_eq!(heartbeats.stats().sent, 1);
+	}
+
+	#[test]
+	fn consistent() {
+		let tree = tree();
+		let vfs = Vfs::new_virtual();
+		let queue = Queue::new();
+		let heartbeats = Heartbeats::new();
+
+		subscribe(&queue, 1, &[Capability::Heartbeat]);
+
+		let mut client = Client::mirror(&tree);
+
+		assert_eq!(converge(&mut client, &heartbeats, &tree, &vfs, &queue), (1, 0));
+
+		let stats = heartbeats.stats();
+
+		assert_eq!(stats.sent, 1);
+		assert_eq!(stats.mismatches, 0);
+		assert_eq!(stats.resynced_instances, 0);
+	}
+
+	#[test]
+	fn corrupted_client() {
+		let tree = tree();
+		let vfs = Vfs::new_virtual();
+		let queue = Queue::new();
+		let heartbeats = Heartbeats::new();
+
+		subscribe(&queue, 1, &[Capability::Heartbeat]);
+
+		let mut client = Client::mirror(&tree);
+
+		// Missed rename of a deep instance
+		let renamed = find(&tree, "Folder3.Folder7.Value5");
+		client.nodes.get_mut(&renamed).unwrap().name = String::from("Stale");
+
+		// Missed addition of a whole folder
+		client.remove(find(&tree, "Folder8.Folder2"));
+
+		// Missed removal
+		let parent = find(&tree, "Folder1");
+		client.insert(
+			Snapshot::new()
+				.with_id(Ref::new())
+				.with_name("Removed")
+				.with_class("Folder"),
+			parent,
+		);
+
+		assert_ne!(
+			client.hash(tree.root_ref()),
+			heartbeat::format_hash(heartbeat::hash_subtree(&tree, tree.root_ref()).unwrap())
+		);
+
+		let (rounds, exchanged) = converge(&mut client, &heartbeats, &tree, &vfs, &queue);
+
+		// One heartbeat to recover and one to confirm
+		assert_eq!(rounds, 2);
+
+		// Only divergent branches are compared, a full comparison would need 1111 hashes
+		assert!(exchanged <= 100, "Exchanged {} hashes", exchanged);
+
+		let stats = heartbeats.stats();
+
+		assert_eq!(stats.sent, 2);
+		assert_eq!(stats.mismatches, 1);
+		assert_eq!(stats.resynced_subtrees, 2);
+		assert_eq!(stats.resynced_instances, 12);
+
+		assert_eq!(client.nodes.len(), 1111);
+		assert_eq!(client.nodes[&renamed].name, "Value5");
+	}
+}
+
+mod mirror {
+	use crate::common::{start, TempDir};
+	use actix_web::{
+		http::{header, Method, StatusCode},
+		test::{self, TestRequest},
+		web::Data,
+		App,
+	};
+	use argon::{
+		config::Config,
+		core::{changes::Changes, events::EventKind},
+		server::{
+			limits::{Limiter, Limits},
+			mirror::{self, MirrorToken},
+			Server, SyncChanges,
+		},
+	};
+	use serde_json::Value;
+	use std::{fs, sync::Arc, time::Duration};
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "Mirror",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"$className": "ReplicatedStorage"
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		dir
+	}
+
+	macro_rules! mirror {
+		($core:expr, $token:expr) => {{
+			let mut app = App::new()
+				.app_data(Data::new(Arc::new(Limiter::new(Limits::new(&Config::default())))))
+				.wrap(actix_web::middleware::from_fn(mirror::middleware));
+
+			if let Some(token) = $token {
+				app = app.app_data(Data::new(MirrorToken(String::from(token))));
+			}
+
+			test::init_service(app.service(Server::mirror_scope("", $core.clone()))).await
+		}};
+	}
+
+	#[actix_web::test]
+	async fn mutating_routes_are_absent() {
+		let dir = fixture("routes");
+		let core = Arc::new(start(&dir));
+		let service = mirror!(core, None::<&str>);
+
+		for path in [
+			"/subscribe",
+			"/unsubscribe",
+			"/read",
+			"/write",
+			"/exec",
+			"/open",
+			"/stop",
+			"/freeze",
+			"/sync",
+			"/log",
+			"/marker",
+			"/undo",
+		] {
+			let request = TestRequest::post().uri(path).to_request();
+			let response = test::call_service(&service, request).await;
+
+			assert_eq!(response.status(), StatusCode::NOT_FOUND, "{}", path);
+		}
+
+		for path in ["/health", "/details", "/stats", "/events", "/snapshot?page=0"] {
+			let request = TestRequest::get().uri(path).to_request();
+			let response = test::call_service(&service, request).await;
+
+			assert_eq!(response.status(), StatusCode::OK, "{}", path);
+		}
+	}
+
+	#[actix_web::test]
+	async fn token() {
+		let dir = fixture("token");
+		let core = Arc::new(start(&dir));
+		let service = mirror!(core, Some("secret"));
+
+		let response = test::call_service(&service, TestRequest::get().uri("/health").to_request()).await;
+		assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+		let request = TestRequest::get()
+			.uri("/health")
+			.insert_header((header::AUTHORIZATION, "Bearer wrong"))
+			.to_request();
+
+		assert_eq!(
+			test::call_service(&service, request).await.status(),
+			StatusCode::UNAUTHORIZED
+		);
+
+		let request = TestRequest::get()
+			.uri("/health")
+			.insert_header((header::AUTHORIZATION, "Bearer secret"))
+			.to_request();
+
+		assert_eq!(test::call_service(&service, request).await.status(), StatusCode::OK);
+
+		let request = TestRequest::default()
+			.method(Method::GET)
+			.uri("/events?token=secret")
+			.to_request();
+
+		assert_eq!(test::call_service(&service, request).await.status(), StatusCode::OK);
+	}
+
+	#[actix_web::test]
+	async fn primary_queue_is_untouched() {
+		let dir = fixture("queue");
+		let core = Arc::new(start(&dir));
+		let service = mirror!(core, None::<&str>);
+
+		let queue = core.queue();
+		queue.subscribe_internal().unwrap();
+
+		queue.push(SyncChanges(Changes::new()), None).unwrap();
+		let seq = core.events().publish(EventKind::Diagnostic {
+			level: String::from("info"),
+			message: String::from("Hello mirror"),
+			instance: None,
+		});
+
+		// Mirror consumer reads events twice, as it would when polling
+		for _ in 0..2 {
+			let request = TestRequest::get().uri("/events").to_request();
+			let page: Value = test::call_and_read_body_json(&service, request).await;
+
+			assert!(page.to_string().contains("Hello mirror"));
+		}
+
+		let request = TestRequest::get().uri(&format!("/events?since={}", seq)).to_request();
+		let response = test::call_service(&service, request).await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		// Primary client still gets its message exactly once
+		let timeout = Duration::from_millis(100);
+
+		assert!(queue.is_subscribed(0));
+		assert!(queue.get_with_timeout(0, timeout).unwrap().is_some());
+		assert!(queue.get_with_timeout(0, timeout).unwrap().is_none());
+	}
+}
+
+mod upload {
+	use crate::common::{start, TempDir};
+	use actix_web::{
+		http::{header, StatusCode},
+		middleware,
+		test::{self, TestRequest},
+		web::Data,
+		App,
+	};
+	use argon::{
+		config::Config,
+		core::{changes::Changes, meta::Meta, snapshot::AddedSnapshot},
+		server::{
+			limits::{self, Limiter, Limits, PayloadTooLarge},
+			upload::{UploadError, Uploads},
+			Server,
+		},
+	};
+	use rbx_dom_weak::{types::Ref, Ustr, UstrMap};
+	use serde::Serialize;
+	use serde_bytes::ByteBuf;
+	use std::{
+		fs,
+		sync::Arc,
+		thread,
+		time::{Duration, Instant},
+	};
+
+	#[derive(Serialize)]
+	#[serde(rename_all = "camelCase")]
+	struct WriteRequest<'a> {
+		changes: &'a Changes,
+		client_id: u32,
+	}
+
+	#[derive(Serialize)]
+	#[serde(rename_all = "camelCase")]
+	struct UploadRequest<'a> {
+		client_id: u32,
+		transfer_id: &'a str,
+		index: usize,
+		total: usize,
+		data: ByteBuf,
+	}
+
+	#[derive(Serialize)]
+	#[serde(rename_all = "camelCase")]
+	struct CommitRequest<'a> {
+		client_id: u32,
+		transfer_id: &'a str,
+	}
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "Upload",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"$path": "src"
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		dir
+	}
+
+	fn request(path: &str, body: &impl Serialize) -> TestRequest {
+		let body = rmp_serde::to_vec_named(body).unwrap();
+
+		TestRequest::post()
+			.uri(path)
+			.insert_header((header::CONTENT_TYPE, "application/msgpack"))
+			.insert_header((header::CONTENT_LENGTH, body.len().to_string()))
+			.set_payload(body)
+	}
+
+	#[actix_web::test]
+	async fn chunked_syncback() {
+		let dir = fixture("chunked");
+		let core = Arc::new(start(&dir));
+
+		// Chunks are small enough to get through, the whole changes are not
+		let limits = Limits {
+			max_request_size: 256,
+			max_payload_size: 256,
+			..Limits::new(&Config::default())
+		};
+
+		let service = test::init_service(
+			App::new()
+				.app_data(Data::new(Arc::new(Limiter::new(limits.clone()))))
+				.app_data(Data::new(Arc::new(Uploads::new(4096, Duration::from_secs(60)))))
+				.app_data(limits::msgpack_config(&limits))
+				.wrap(middleware::from_fn(limits::middleware))
+				.service(Server::scope("", core.clone())),
+		)
+		.await;
+
+		let response = test::call_service(
+			&service,
+			request("/subscribe", &serde_json::json!({ "clientId": 1, "name": "Studio" })).to_request(),
+		)
+		.await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		let mut changes = Changes::new();
+		let parent = core.tree().find_by_path("ReplicatedStorage").unwrap();
+
+		for index in 0..4 {
+			changes.additions.push(AddedSnapshot {
+				id: Ref::new(),
+				meta: Meta::new(),
+				parent,
+				name: format!("Uploaded{}", index),
+				class: Ustr::from("Folder"),
+				properties: UstrMap::default(),
+				children: vec![],
+			});
+		}
+
+		let write = WriteRequest {
+			changes: &changes,
+			client_id: 1,
+		};
+
+		// Too large for a single request
+		let response = test::call_service(&service, request("/write", &write).to_request()).await;
+		assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+		let body: PayloadTooLarge = test::read_body_json(response).await;
+
+		assert_eq!(body.endpoint, "/write");
+		assert_eq!(body.limit, 256);
+		assert!(body.size.unwrap() > 256);
+
+		let contents = rmp_serde::to_vec_named(&write).unwrap();
+		let chunks: Vec<&[u8]> = contents.chunks(128).collect();
+
+		// Chunks can arrive in any order
+		for index in (0..chunks.len()).rev() {
+			let chunk = UploadRequest {
+				client_id: 1,
+				transfer_id: "transfer",
+				index,
+				total: chunks.len(),
+				data: ByteBuf::from(chunks[index].to_vec()),
+			};
+
+			let response = test::call_service(&service, request("/upload", &chunk).to_request()).await;
+			assert_eq!(response.status(), StatusCode::OK);
+		}
+
+		let commit = CommitRequest {
+			client_id: 1,
+			transfer_id: "transfer",
+		};
+
+		let response = test::call_service(&service, request("/commit", &commit).to_request()).await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		let start = Instant::now();
+
+		while !(0..4).all(|index| dir.join(format!("src/Uploaded{}", index)).is_dir()) {
+			assert!(start.elapsed() < Duration::from_secs(5), "changes were not applied");
+			thread::sleep(Duration::from_millis(10));
+		}
+
+		// Committed uploads are gone
+		let response = test::call_service(&service, request("/commit", &commit).to_request()).await;
+		assert_eq!(response.status(), StatusCode::NOT_FOUND);
+	}
+
+	#[test]
+	fn incomplete_upload() {
+		let uploads = Uploads::new(1024, Duration::from_secs(60));
+
+		uploads.add(1, "transfer", 0, 2, vec![1, 2, 3]).unwrap();
+
+		assert_eq!(
+			uploads.commit(1, "transfer"),
+			Err(UploadError::Incomplete { missing: 1 })
+		);
+
+		// Total has to match the previous chunks
+		assert_eq!(
+			uploads.add(1, "transfer", 1, 3, vec![4]),
+			Err(UploadError::InvalidChunk)
+		);
+
+		uploads.add(1, "transfer", 1, 2, vec![4]).unwrap();
+
+		// Other clients can't commit it
+		assert_eq!(uploads.commit(2, "transfer"), Err(UploadError::Unknown));
+		assert_eq!(uploads.commit(1, "transfer"), Ok(vec![1, 2, 3, 4]));
+	}
+
+	#[test]
+	fn bounded_storage() {
+		let uploads = Uploads::new(8, Duration::from_secs(60));
+
+		uploads.add(1, "first", 0, 2, vec![0; 6]).unwrap();
+
+		// Storage is shared by all uploads
+		assert_eq!(
+			uploads.add(2, "second", 0, 1, vec![0; 4]),
+			Err(UploadError::TooLarge { limit: 8, size: 10 })
+		);
+
+		// Replaced chunk does not count twice
+		uploads.add(1, "first", 0, 2, vec![0; 8]).unwrap();
+	}
+
+	#[test]
+	fn abandoned_upload_expires() {
+		let uploads = Uploads::new(1024, Duration::from_millis(50));
+
+		uploads.add(1, "abandoned", 0, 2, vec![0; 16]).unwrap();
+		assert_eq!(uploads.len(), 1);
+
+		thread::sleep(Duration::from_millis(100));
+
+		assert_eq!(uploads.expire(), 1);
+		assert!(uploads.is_empty());
+		assert_eq!(uploads.commit(1, "abandoned"), Err(UploadError::Unknown));
+	}
+}
+
+mod asset {
+	use crate::common::{start, TempDir};
+	use argon::{
+		assets::{self, AssetClass, AssetStore},
+		clean,
+		constants::PROTOCOL_VERSION,
+		core::{queue::PlaceDetails, Core},
+		project::Project,
+		server::{
+			protocol::{Capability, Negotiated},
+			Message, SyncChanges,
+		},
+	};
+	use rbx_dom_weak::{
+		types::{Ref, Variant},
+		Ustr,
+	};
+	use std::{collections::HashMap, fs, iter, time::Duration};
+
+	const PNG: &[u8] = b"\x89PNG\r\n\x1a\nnot really an image";
+
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "Asset",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"$path": "src"
+				}
+			}
+		}"#,
+		)
+		.unwrap();
+
+		dir
+	}
+
+	fn property(core: &Core, id: Ref, name: &str) -> Option<Variant> {
+		core.tree()
+			.get_instance(id)
+			.and_then(|instance| instance.properties.get(&Ustr::from(name)).cloned())
+	}
+
+	#[test]
+	fn add_asset() {
+		let dir = fixture("add");
+		let core = start(&dir);
+
+		let asset = core.add_asset("Logo.png", PNG, "ReplicatedStorage.Logo", None).unwrap();
+
+		assert_eq!(asset.class, AssetClass::Decal);
+		assert_eq!(asset.path, "Asset.ReplicatedStorage.Logo");
+
+		// Instance references the placeholder of the stored asset
+		let id = core.tree().find_by_path("ReplicatedStorage.Logo").unwrap();
+
+		assert_eq!(core.tree().get_instance(id).unwrap().class, "Decal");
+		assert_eq!(
+			property(&core, id, "Texture"),
+			Some(assets::content_value(&assets::placeholder(&asset.key)))
+		);
+
+		let Some(Variant::Attributes(attributes)) = property(&core, id, "Attributes") else {
+			panic!("expected attributes");
+		};
+
+		assert!(attributes
+			.iter()
+			.any(|(_, value)| *value == Variant::String(asset.key.clone())));
+
+		// File is copied into the store and recorded in the mapping
+		let store = AssetStore::new(&dir.join("assets"));
+
+		assert!(asset.key.ends_with(".png"));
+		assert_eq!(fs::read(store.path(&asset.key)).unwrap(), PNG);
+
+		let mapping = store.mapping().unwrap();
+		let entry = &mapping[&asset.key];
+
+		assert_eq!(entry.source, "Logo.png");
+		assert_eq!(entry.class, AssetClass::Decal);
+		assert_eq!(entry.asset_id, None);
+
+		// Existing child keeps its name, the same file is stored once
+		let second = core.add_asset("Logo.png", PNG, "ReplicatedStorage.Logo", None).unwrap();
+
+		assert_eq!(second.path, "Asset.ReplicatedStorage.Logo2");
+		assert_eq!(second.key, asset.key);
+		assert_eq!(store.keys(), vec![asset.key.clone()]);
+
+		// Uploaded IDs replace placeholders
+		let updated = core
+			.apply_asset_ids(&HashMap::from([(asset.key.clone(), 1234)]))
+			.unwrap();
+
+		assert_eq!(updated, 2);
+		assert_eq!(
+			property(&core, id, "Texture"),
+			Some(assets::content_value("rbxassetid://1234"))
+		);
+		assert_eq!(store.mapping().unwrap()[&asset.key].asset_id, Some(1234));
+
+		// Instances are written to disk
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+		let reloaded = Core::new(project, false).unwrap();
+		let id = reloaded.tree().find_by_path("ReplicatedStorage.Logo2").unwrap();
+
+		assert_eq!(
+			property(&reloaded, id, "Texture"),
+			Some(assets::content_value("rbxassetid://1234"))
+		);
+	}
+
+	#[test]
+	fn synced_to_clients() {
+		let dir = fixture("synced");
+		let core = start(&dir);
+
+		let negotiated = Negotiated::new("Studio", Some(PROTOCOL_VERSION), &[Capability::Resume]).unwrap();
+
+		core.queue()
+			.subscribe(1, "Studio", PlaceDetails::default(), negotiated)
+			.unwrap();
+
+		core.add_asset("Logo.png", PNG, "ReplicatedStorage.Logo", None).unwrap();
+
+		// Instance is added by the processor, which sends it to every client
+		let added = iter::from_fn(|| core.queue().get_with_timeout(1, Duration::from_millis(100)).unwrap())
+			.find_map(|message| match message {
+				Message::SyncChanges(SyncChanges(changes)) => changes.additions.into_iter().next(),
+				_ => None,
+			})
+			.unwrap();
+
+		assert_eq!(added.name, "Logo");
+		assert_eq!(added.class, "Decal");
+		assert_eq!(added.id, core.tree().find_by_path("ReplicatedStorage.Logo").unwrap());
+	}
+
+	#[test]
+	fn unknown_extension() {
+		let dir = fixture("unknown");
+		let core = start(&dir);
+
+		assert!(core
+			.add_asset("notes.txt", b"", "ReplicatedStorage.Notes", None)
+			.is_err());
+
+		// The class can be given explicitly
+		let asset = core
+			.add_asset("notes.txt", b"", "ReplicatedStorage.Notes", Some(AssetClass::Sound))
+			.unwrap();
+
+		let id = core.tree().find_by_path("ReplicatedStorage.Notes").unwrap();
+
+		assert_eq!(asset.class, AssetClass::Sound);
+		assert!(property(&core, id, "SoundId").is_some());
+	}
+
+	#[test]
+	fn unused_assets() {
+		let dir = fixture("unused");
+		let core = start(&dir);
+
+		let used = core.add_asset("Logo.png", PNG, "ReplicatedStorage.Logo", None).unwrap();
+
+		let store = AssetStore::new(&dir.join("assets"));
+
+		let unused = store.store("Unused.png", b"unused").unwrap();
+
+		let artifacts = clean::find_unused_assets(&dir.join("assets"), &core.tree());
+
+		assert_eq!(artifacts.len(), 1);
+		assert_eq!(artifacts[0].path, store.path(&unused));
+
+		clean::remove(&dir, &artifacts).unwrap();
+
+		assert_eq!(store.keys(), vec![used.key]);
+	}
+}
+
+mod settings {
+	use crate::common::TempDir;
+	use actix_web::{
+		http::{header, StatusCode},
+		test::{self, TestRequest},
+		web::Data,
+		App,
+	};
+	use argon::{
+		config::{Config, ConfigKind},
+		core::Core,
+		logger,
+		project::Project,
+		server::{
+			limits::{Limiter, Limits},
+			Server,
+		},
+	};
+	use log::LevelFilter;
+	use serde_json::{json, Value};
+	use std::{fs, sync::Arc};
+	use tokio::sync::{Mutex, MutexGuard};
+
+	/// Tests share the config and session settings so they run one at a time
+	async fn lock() -> MutexGuard<'static, ()> {
+		static LOCK: Mutex<()> = Mutex::const_new(());
+		LOCK.lock().await
+	}
+
+	/// Creates a project and loads an empty workspace config from its directory
+	fn fixture(name: &str) -> TempDir {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+
+		fs::write(
+			dir.join("default.project.json"),
+			r#"{
+			"name": "Settings",
+			"tree": {
+				"$className": "DataModel",
+				"ReplicatedStorage": {
+					"$path": "src"
+				}
+			},
+			"ignoreGlobs": ["**/*.spec.luau"]
+		}"#,
+		)
+		.unwrap();
+
+		Config::load_virtual(ConfigKind::Workspace(dir.join("argon.toml"))).unwrap();
+
+		dir
+	}
+
+	fn msgpack(request: TestRequest, body: Value) -> TestRequest {
+		request
+			.insert_header((header::CONTENT_TYPE, "application/msgpack"))
+			.set_payload(rmp_serde::to_vec_named(&body).unwrap())
+	}
+
+	/// Serves the project of `dir`, primary server has client `1` subscribed
+	macro_rules! service {
+	($dir:expr, $mirror:expr) => {{
+		let core = Arc::new(Core::new(Project::load(&$dir.join("default.project.json")).unwrap(), false).unwrap());
+		let app = App::new().app_data(Data::new(Arc::new(Limiter::new(Limits::new(&Config::default())))));
+
+		if $mirror {
+			test::init_service(app.service(Server::mirror_scope("", core))).await
+		} else {
+			let service = test::init_service(app.service(Server::scope("", core))).await;
+			let request = msgpack(TestRequest::post().uri("/subscribe"), json!({ "clientId": 1, "name": "Studio" }));
+
+			assert_eq!(
+				test::call_service(&service, request.to_request()).await.status(),
+				StatusCode::OK
+			);
+
+			service
+		}
+	}};
+}
+
+	macro_rules! patch {
+		($service:expr, $body:expr $(,)?) => {{
+			let request = msgpack(TestRequest::patch().uri("/settings"), $body).to_request();
+			let response = test::call_service(&$service, request).await;
+			let status = response.status();
+
+			(
+				status,
+				serde_json::from_slice::<Value>(&test::read_body(response).await).unwrap_or_default(),
+			)
+		}};
+	}
+
+	macro_rules! get {
+		($service:expr) => {
+			test::call_and_read_body_json::<_, _, Value>(&$service, TestRequest::get().uri("/settings").to_request())
+				.await
+		};
+	}
+
+	#[actix_web::test]
+	async fn listing() {
+		let _lock = lock().await;
+		let dir = fixture("listing");
+		let service = service!(dir, false);
+
+		let settings = get!(service);
+
+		assert_eq!(settings["config"]["rename_instances"]["value"], json!(true));
+		assert_eq!(settings["config"]["rename_instances"]["source"], json!("default"));
+		assert_eq!(settings["config"]["rename_instances"]["changeable"], json!(true));
+		assert_eq!(settings["config"]["lua_extension"]["changeable"], json!(false));
+		assert_eq!(settings["config"]["log_level"]["source"], json!("cli"));
+
+		assert_eq!(settings["project"]["ignoreGlobs"]["value"], json!(["**/*.spec.luau"]));
+		assert_eq!(settings["project"]["ignoreGlobs"]["source"], json!("project"));
+		assert_eq!(
+			settings["project"]["middlewareConfig"]["value"]["scripts"]["extension"],
+			json!("luau")
+		);
+
+		// Settings that are not curated for clients are left out
+		assert!(settings["config"].get("host").is_none());
+	}
+
+	#[actix_web::test]
+	async fn whitelist() {
+		let _lock = lock().await;
+		let dir = fixture("whitelist");
+		let service = service!(dir, false);
+
+		let (status, body) = patch!(
+			service,
+			json!({
+				"clientId": 1,
+				"settings": {
+					"keep_duplicates": true,
+					"changes_threshold": "many",
+					"lua_extension": true,
+					"host": "0.0.0.0",
+					"unknown_setting": 1,
+					"log_level": "loud",
+				},
+			}),
+		);
+
+		assert_eq!(status, StatusCode::BAD_REQUEST);
+
+		let errors = body["errors"].as_object().unwrap();
+
+		assert_eq!(errors.len(), 5);
+		assert!(errors["changes_threshold"]
+			.as_str()
+			.unwrap()
+			.starts_with("Invalid value"));
+		assert_eq!(errors["lua_extension"], json!("Setting can't be changed at runtime"));
+		assert_eq!(errors["host"], json!("Setting is not available to clients"));
+		assert_eq!(errors["unknown_setting"], json!("Unknown setting"));
+		assert!(errors.contains_key("log_level"));
+
+		// Nothing is applied when any setting is rejected
+		assert!(!Config::new().keep_duplicates);
+
+		let (status, body) = patch!(
+			service,
+			json!({ "clientId": 1, "settings": { "middlewareConfig": {} } }),
+		);
+
+		assert_eq!(status, StatusCode::BAD_REQUEST);
+		assert_eq!(body["errors"]["middlewareConfig"], json!("Unknown setting"));
+	}
+
+	#[actix_web::test]
+	async fn persistence() {
+		let _lock = lock().await;
+		let dir = fixture("persistence");
+		let service = service!(dir, false);
+		let config_path = dir.join("argon.toml");
+
+		// Session-only changes are applied live but never saved
+		let (status, body) = patch!(service, json!({ "clientId": 1, "settings": { "move_to_bin": true } }),);
+
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(body["config"]["move_to_bin"]["value"], json!(true));
+		assert_eq!(body["config"]["move_to_bin"]["source"], json!("session"));
+		assert!(Config::new().move_to_bin);
+		assert!(!config_path.exists());
+
+		// Persisted changes are saved to the loaded config file
+		let (status, body) = patch!(
+			service,
+			json!({ "clientId": 1, "settings": { "changes_threshold": 20 }, "persist": true }),
+		);
+
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(body["config"]["changes_threshold"]["source"], json!("workspace"));
+		assert_eq!(Config::new().changes_threshold, 20);
+
+		let stored = fs::read_to_string(&config_path).unwrap();
+
+		assert!(stored.contains("changes_threshold = 20"));
+		assert!(!stored.contains("move_to_bin"));
+		assert!(Config::new().move_to_bin);
+
+		// Persisting a session-only setting moves it to the file
+		let (status, _) = patch!(
+			service,
+			json!({ "clientId": 1, "settings": { "move_to_bin": true }, "persist": true }),
+		);
+
+		assert_eq!(status, StatusCode::OK);
+		assert!(fs::read_to_string(&config_path).unwrap().contains("move_to_bin = true"));
+
+		let settings = get!(service);
+		assert_eq!(settings["config"]["move_to_bin"]["source"], json!("workspace"));
+
+		for setting in ["move_to_bin", "changes_threshold"] {
+			let default = Config::default().get(setting).unwrap().to_string();
+			Config::new_mut().set(setting, &default).unwrap();
+		}
+	}
+
+	#[actix_web::test]
+	async fn log_level() {
+		let _lock = lock().await;
+		let dir = fixture("log-level");
+		let service = service!(dir, false);
+
+		let (status, body) = patch!(
+			service,
+			json!({ "clientId": 1, "settings": { "log_level": "DEBUG" }, "persist": true }),
+		);
+
+		assert_eq!(status, StatusCode::BAD_REQUEST);
+		assert!(body["errors"]["log_level"]
+			.as_str()
+			.unwrap()
+			.contains("can't be persisted"));
+
+		let (status, body) = patch!(service, json!({ "clientId": 1, "settings": { "log_level": "debug" } }),);
+
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(body["config"]["log_level"]["value"], json!("DEBUG"));
+		assert_eq!(body["config"]["log_level"]["source"], json!("session"));
+		assert_eq!(logger::verbosity(), LevelFilter::Debug);
+
+		logger::set_verbosity(LevelFilter::Off);
+	}
+
+	#[actix_web::test]
+	async fn observers() {
+		let _lock = lock().await;
+		let dir = fixture("observers");
+
+		// Clients have to be subscribed to change settings
+		let primary = service!(dir, false);
+		let (status, _) = patch!(primary, json!({ "clientId": 2, "settings": { "history": false } }));
+
+		assert_eq!(status, StatusCode::UNAUTHORIZED);
+		assert!(Config::new().history);
+
+		// Mirror consumers can only read them
+		let mirror = service!(dir, true);
+		let (status, _) = patch!(mirror, json!({ "clientId": 1, "settings": { "history": false } }));
+
+		assert_ne!(status, StatusCode::OK);
+		assert!(Config::new().history);
+
+		assert_eq!(get!(mirror)["config"]["history"]["source"], json!("default"));
+	}
+}
+
+mod pause {
+	use crate::common::TempDir;
+	use actix_web::{
+		http::{header, StatusCode},
+		test::{self, TestRequest},
+		web::Data,
+		App,
+	};
+	use argon::{
+		config::Config,
+		constants::PROTOCOL_VERSION,
+		core::{changes::Changes, processor::pause::PauseStatus, queue::PlaceDetails, Core},
+		project::Project,
+		server::{
+			limits::{Limiter, Limits},
+			protocol::{Capability, Negotiated},
+			Message, PauseChanged, Server,
+		},
+		vfs::{mem_backend::MemBackend, Vfs, VfsBackend, VfsEvent},
+	};
+	use crossbeam_channel::Sender;
+	use rbx_dom_weak::{types::Variant, Ustr};
+	use serde_json::{json, Value};
+	use std::{
+		fs,
+		path::Path,
+		sync::Arc,
+		thread,
+		time::{Duration, Instant},
+	};
+
+	const PROJECT: &str = r#"{
+	"name": "Pause",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": {
+			"$path": "src"
+		}
+	}
+}"#;
+
+	const FOLDERS: usize = 10;
+	const MODULES: usize = 100;
+	const CLIENT_ID: u32 = 1;
+	const TIMEOUT: Duration = Duration::from_secs(60);
+
+	/// Serves `FOLDERS` folders with `MODULES` modules each from the in-memory VFS,
+	/// returns sender of watcher events as nothing is sent automatically
+	fn serve(dir: &Path) -> (Arc<Core>, Sender<VfsEvent>) {
+		let project_path = dir.join("default.project.json");
+
+		fs::write(&project_path, PROJECT).unwrap();
+
+		let mut backend = MemBackend::new();
+		let sender = backend.sender();
+
+		backend.write(&project_path, PROJECT.as_bytes()).unwrap();
+
+		for folder in 0..FOLDERS {
+			let folder_path = dir.join("src").join(format!("Folder{}", folder));
+			backend.create_dir(&folder_path).unwrap();
+
+			for module in 0..MODULES {
+				backend
+					.write(&folder_path.join(format!("Module{}.luau", module)), b"return 0")
+					.unwrap();
+			}
+		}
+
+		let core = Core::with_vfs(Project::load(&project_path).unwrap(), Vfs::from_backend(backend)).unwrap();
+		let negotiated = Negotiated::new("Pause", Some(PROTOCOL_VERSION), &[Capability::Pause]).unwrap();
+
+		core.vfs().set_undo_log(None);
+		core.queue()
+			.subscribe(CLIENT_ID, "Pause", PlaceDetails::default(), negotiated)
+			.unwrap();
+
+		(Arc::new(core), sender)
+	}
+
+	/// Edits every module of all but the last folder, adds a new folder
+	/// and removes the last one, returns the number of events sent
+	fn checkout(dir: &Path, core: &Core, sender: &Sender<VfsEvent>) -> usize {
+		let vfs = core.vfs();
+		let src = dir.join("src");
+		let mut events = vec![];
+
+		for folder in 0..FOLDERS - 1 {
+			for module in 0..MODULES {
+				let path = src
+					.join(format!("Folder{}", folder))
+					.join(format!("Module{}.luau", module));
+
+				vfs.write(&path, format!("return {}", module).as_bytes()).unwrap();
+				events.push(VfsEvent::Write(path));
+			}
+		}
+
+		vfs.create_dir(&src.join("Added")).unwrap();
+
+		for module in 0..MODULES {
+			let path = src.join("Added").join(format!("Module{}.luau", module));
+
+			vfs.write(&path, b"return {}").unwrap();
+			events.push(VfsEvent::Create(path));
+		}
+
+		let removed = src.join(format!("Folder{}", FOLDERS - 1));
+
+		vfs.remove(&removed).unwrap();
+		events.push(VfsEvent::Delete(removed));
+
+		let count = events.len();
+
+		for event in events {
+			sender.send(event).unwrap();
+		}
+
+		count
+	}
+
+	fn source(core: &Core, path: &str) -> Option<String> {
+		let tree = core.tree();
+		let instance = tree.get_instance(tree.find_by_path(path)?)?;
+
+		match instance.properties.get(&Ustr::from("Source")) {
+			Some(Variant::String(source)) => Some(source.clone()),
+			_ => None,
+		}
+	}
+
+	/// Pause status sent to the client, skipping any other messages
+	fn next_pause(core: &Core) -> PauseStatus {
+		while let Some(message) = core
+			.queue()
+			.get_with_timeout(CLIENT_ID, Duration::from_secs(5))
+			.unwrap()
+		{
+			if let Message::PauseChanged(PauseChanged(status)) = message {
+				return status;
+			}
+		}
+
+		panic!("pause status was not sent");
+	}
+
+	/// Number of change batches the client receives until the queue stays empty for a while
+	fn batches(core: &Core) -> usize {
+		let mut batches = 0;
+
+		while let Some(message) = core
+			.queue()
+			.get_with_timeout(CLIENT_ID, Duration::from_millis(500))
+			.unwrap()
+		{
+			if matches!(message, Message::SyncChanges(_)) {
+				batches += 1;
+			}
+		}
+
+		batches
+	}
+
+	fn wait_until(condition: impl Fn() -> bool) {
+		let start = Instant::now();
+
+		while !condition() {
+			assert!(start.elapsed() < TIMEOUT, "condition was not met in time");
+			thread::sleep(Duration::from_millis(10));
+		}
+	}
+
+	fn post(path: &str, body: Value) -> TestRequest {
+		TestRequest::post()
+			.uri(path)
+			.insert_header((header::CONTENT_TYPE, "application/msgpack"))
+			.set_payload(rmp_serde::to_vec_named(&body).unwrap())
+	}
+
+	/// Calls the service, returns status and JSON body of the response
+	macro_rules! call {
+		($service:expr, $request:expr) => {{
+			let response = test::call_service($service, $request.to_request()).await;
+			let status = response.status();
+
+			(status, test::read_body_json::<Value, _>(response).await)
+		}};
+	}
+
+	#[actix_web::test]
+	async fn pause_and_resume() {
+		let dir = TempDir::new("resume");
+		let (core, sender) = serve(&dir);
+
+		let service = test::init_service(
+			App::new()
+				.app_data(Data::new(Arc::new(Limiter::new(Limits::new(&Config::default())))))
+				.service(Server::scope("", core.clone())),
+		)
+		.await;
+
+		// Zero timeout never resumes on its own
+		let (status, body) = call!(&service, post("/pause", json!({ "timeout": 0 })));
+
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(body, json!({ "paused": true, "resumesIn": null, "backlog": 0 }));
+		assert!(next_pause(&core).paused);
+
+		let processed = core.processor().processed();
+		let count = checkout(&dir, &core, &sender);
+
+		wait_until(|| core.processor().pause_status().backlog == count);
+
+		assert_eq!(core.processor().processed(), processed);
+		assert_eq!(
+			source(&core, "ReplicatedStorage.Folder0.Module1").as_deref(),
+			Some("return 0")
+		);
+
+		// Syncback is rejected with the status, so Studio can tell the user
+		let write = json!({
+			"clientId": CLIENT_ID,
+			"changes": serde_json::to_value(Changes::new()).unwrap(),
+		});
+		let (status, body) = call!(&service, post("/write", write));
+
+		assert_eq!(status, StatusCode::LOCKED);
+		assert_eq!(
+			body,
+			json!({ "status": "paused", "paused": true, "resumesIn": null, "backlog": count })
+		);
+
+		let (_, health) = call!(&service, TestRequest::get().uri("/health"));
+		assert_eq!(health["pause"]["paused"], json!(true));
+
+		// Held back events are rescanned at once and converge to the state on disk
+		let (status, body) = call!(&service, post("/resume", json!({})));
+
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(body, json!(true));
+		assert!(!next_pause(&core).paused);
+
+		wait_until(|| core.processor().processed() > processed);
+
+		assert_eq!(batches(&core), 1);
+		assert_eq!(core.processor().pause_status(), PauseStatus::default());
+
+		for folder in 0..FOLDERS - 1 {
+			for module in 0..MODULES {
+				let path = format!("ReplicatedStorage.Folder{}.Module{}", folder, module);
+				let expected = format!("return {}", module);
+
+				assert_eq!(source(&core, &path), Some(expected));
+			}
+		}
+
+		for module in 0..MODULES {
+			let path = format!("ReplicatedStorage.Added.Module{}", module);
+			assert_eq!(source(&core, &path).as_deref(), Some("return {}"));
+		}
+
+		let removed = format!("ReplicatedStorage.Folder{}", FOLDERS - 1);
+		assert!(core.tree().find_by_path(&removed).is_none());
+
+		let (_, body) = call!(&service, post("/resume", json!({})));
+		assert_eq!(body, json!(false));
+	}
+
+	#[test]
+	fn resumes_after_timeout() {
+		let dir = TempDir::new("timeout");
+		let (core, sender) = serve(&dir);
+
+		assert!(core.processor().pause(Some(Duration::from_millis(200))));
+		assert!(next_pause(&core).paused);
+
+		let processed = core.processor().processed();
+		let path = dir.join("src/Folder0/Module0.luau");
+
+		core.vfs().write(&path, b"return 1").unwrap();
+		sender.send(VfsEvent::Write(path)).unwrap();
+
+		// Small backlogs are processed event by event
+		assert!(!next_pause(&core).paused);
+		wait_until(|| core.processor().processed() > processed);
+
+		assert!(!core.processor().is_paused());
+		assert_eq!(
+			source(&core, "ReplicatedStorage.Folder0.Module0").as_deref(),
+			Some("return 1")
+		);
+	}
+}
+
+mod ui {
+	use crate::common::TempDir;
+	use actix_web::{
+		dev::ServiceResponse,
+		http::{header, StatusCode},
+		middleware,
+		test::{self, TestRequest},
+		web::{self, Data},
+		App,
+	};
+	use argon::{
+		config::Config,
+		core::Core,
+		project::Project,
+		server::{
+			limits::{Limiter, Limits},
+			mirror::{self, MirrorToken},
+			ui, Server,
+		},
+	};
+	use serde_json::{json, Value};
+	use std::{fs, sync::Arc};
+	use tokio::sync::{Mutex, MutexGuard};
+
+	const PROJECT: &str = r#"{
+	"name": "Dashboard",
+	"tree": {
+		"$className": "DataModel",
+		"ReplicatedStorage": {
+			"$path": "src"
+		}
+	}
+}"#;
+
+	/// Tests share the config so they run one at a time
+	async fn lock() -> MutexGuard<'static, ()> {
+		static LOCK: Mutex<()> = Mutex::const_new(());
+		LOCK.lock().await
+	}
+
+	fn start(name: &str) -> (Arc<Core>, TempDir) {
+		let dir = TempDir::new(name);
+
+		fs::create_dir_all(dir.join("src")).unwrap();
+		fs::write(dir.join("default.project.json"), PROJECT).unwrap();
+		fs::write(dir.join("src/Module.luau"), "return {}").unwrap();
+
+		let project = Project::load(&dir.join("default.project.json")).unwrap();
+
+		(Arc::new(Core::new(project, false).unwrap()), dir)
+	}
+
+	macro_rules! primary {
+		($core:expr) => {
+			test::init_service(
+				App::new()
+					.app_data(Data::new(Arc::new(Limiter::new(Limits::new(&Config::default())))))
+					.service(Server::scope("", $core.clone()))
+					.default_service(web::to(ui::not_supported)),
+			)
+			.await
+		};
+	}
+
+	fn header<B>(response: &ServiceResponse<B>, name: header::HeaderName) -> &str {
+		response.headers().get(name).unwrap().to_str().unwrap()
+	}
+
+	#[actix_web::test]
+	async fn assets() {
+		let _lock = lock().await;
+		let (core, _dir) = start("assets");
+		let service = primary!(core);
+
+		let response = test::call_service(&service, TestRequest::get().uri("/ui").to_request()).await;
+
+		assert_eq!(response.status(), StatusCode::SEE_OTHER);
+		assert_eq!(header(&response, header::LOCATION), "ui/");
+
+		let response = test::call_service(&service, TestRequest::get().uri("/ui/").to_request()).await;
+
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(header(&response, header::CONTENT_TYPE), "text/html; charset=utf-8");
+		assert_eq!(header(&response, header::CACHE_CONTROL), "no-cache");
+
+		let etag = header(&response, header::ETAG).to_owned();
+		let index = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+
+		// Scripts and styles are inlined, so the page loads with a single request
+		assert!(index.contains("<script>"));
+		assert!(!index.contains(r#"src="app.js""#));
+		assert!(!index.contains(r#"href="style.css""#));
+
+		for (path, content_type) in [
+			("/ui/index.html", "text/html; charset=utf-8"),
+			("/ui/app.js", "application/javascript; charset=utf-8"),
+			("/ui/style.css", "text/css; charset=utf-8"),
+		] {
+			let response = test::call_service(&service, TestRequest::get().uri(path).to_request()).await;
+
+			assert_eq!(response.status(), StatusCode::OK, "{}", path);
+			assert_eq!(header(&response, header::CONTENT_TYPE), content_type, "{}", path);
+		}
+
+		let request = TestRequest::get()
+			.uri("/ui/")
+			.insert_header((header::IF_NONE_MATCH, etag.as_str()))
+			.to_request();
+		let response = test::call_service(&service, request).await;
+
+		assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+		assert_eq!(header(&response, header::ETAG), etag);
+
+		for path in ["/ui/missing.js", "/ui/../Cargo.toml"] {
+			let response = test::call_service(&service, TestRequest::get().uri(path).to_request()).await;
+			assert_eq!(response.status(), StatusCode::NOT_FOUND, "{}", path);
+		}
+	}
+
+	#[actix_web::test]
+	async fn json_endpoints() {
+		let _lock = lock().await;
+		let (core, _dir) = start("json");
+		let service = primary!(core);
+
+		let json = |path: &str| {
+			TestRequest::get()
+				.uri(path)
+				.insert_header((header::ACCEPT, "application/json"))
+				.to_request()
+		};
+
+		let page: Value = test::call_and_read_body_json(&service, json("/snapshot")).await;
+		let names = page["instances"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|instance| instance["name"].as_str().unwrap())
+			.collect::<Vec<_>>();
+
+		assert!(names.contains(&"Module"));
+
+		let clients: Value = test::call_and_read_body_json(&service, json("/clients")).await;
+		assert_eq!(clients, json!([]));
+
+		// Endpoints of newer servers degrade gracefully
+		let response = test::call_service(&service, json("/unknown")).await;
+		assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+		let body: Value = test::read_body_json(response).await;
+		assert_eq!(
+			body,
+			json!({ "supported": false, "message": "Not supported by this server" })
+		);
+	}
+
+	#[actix_web::test]
+	async fn disabled() {
+		let _lock = lock().await;
+		let (core, _dir) = start("disabled");
+		let service = primary!(core);
+
+		Config::new_mut().dashboard = false;
+
+		for path in ["/ui", "/ui/", "/ui/app.js"] {
+			let response = test::call_service(&service, TestRequest::get().uri(path).to_request()).await;
+			assert_eq!(response.status(), StatusCode::NOT_FOUND, "{}", path);
+		}
+
+		Config::new_mut().dashboard = true;
+
+		let response = test::call_service(&service, TestRequest::get().uri("/ui/").to_request()).await;
+		assert_eq!(response.status(), StatusCode::OK);
+	}
+
+	#[actix_web::test]
+	async fn mirror_token() {
+		let _lock = lock().await;
+		let (core, _dir) = start("mirror");
+
+		let service = test::init_service(
+			App::new()
+				.app_data(Data::new(Arc::new(Limiter::new(Limits::new(&Config::default())))))
+				.app_data(Data::new(MirrorToken(String::from("secret"))))
+				.wrap(middleware::from_fn(mirror::middleware))
+				.service(Server::mirror_scope("", core.clone())),
+		)
+		.await;
+
+		for path in ["/ui", "/ui/", "/ui/app.js"] {
+			let response = test::call_service(&service, TestRequest::get().uri(path).to_request()).await;
+			assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", path);
+		}
+
+		// Token of the page URL is kept, the dashboard passes it to every request
+		let request = TestRequest::get().uri("/ui?token=secret").to_request();
+		let response = test::call_service(&service, request).await;
+
+		assert_eq!(response.status(), StatusCode::SEE_OTHER);
+		assert_eq!(header(&response, header::LOCATION), "ui/?token=secret");
+
+		let request = TestRequest::get().uri("/ui/?token=secret").to_request();
+		assert_eq!(test::call_service(&service, request).await.status(), StatusCode::OK);
+
+		let request = TestRequest::get()
+			.uri("/ui/")
+			.insert_header((header::AUTHORIZATION, "Bearer secret"))
+			.to_request();
+
+		assert_eq!(test::call_service(&service, request).await.status(), StatusCode::OK);
+	}
+}
+
+mod subscription_filters {
+	use argon::{
+		core::{
+			changes::Changes,
+			initial_sync::InitialSync,
+			queue::{PlaceDetails, Queue},
+			snapshot::{Snapshot, UpdatedSnapshot},
+			subscription::SubscriptionFilter,
+			tree::Tree,
+		},
+		server::{protocol::Negotiated, Message, SyncChanges},
+		vfs::Vfs,
+	};
+	use rbx_dom_weak::types::Ref;
+	use std::{collections::HashMap, mem, sync::Mutex, time::Duration};
+
+	fn tree() -> Tree {
+		let module = |name: &str| Snapshot::new().with_name(name).with_class("ModuleScript");
+		let part = |name: &str| Snapshot::new().with_name(name).with_class("Part");
+
+		Tree::new(
+			Snapshot::new()
+				.with_name("Game")
+				.with_class("DataModel")
+				.with_children(vec![
+					Snapshot::new()
+						.with_name("ReplicatedStorage")
+						.with_class("ReplicatedStorage")
+						.with_children(vec![
+							Snapshot::new()
+								.with_name("Modules")
+								.with_children(vec![module("A"), module("B")]),
+							Snapshot::new().with_name("Old").with_children(vec![module("C")]),
+						]),
+					Snapshot::new()
+						.with_name("Workspace")
+						.with_class("Workspace")
+						.with_children(vec![part("Part1"), part("Part2")]),
+				]),
+		)
+	}
+
+	fn subscribe(queue: &Queue, id: u32) {
+		queue
+			.subscribe(id, "Client", PlaceDetails::default(), Negotiated::default())
+			.unwrap();
+	}
+
+	fn filter(paths: &[&str], classes: &[&str]) -> Option<SubscriptionFilter> {
+		Some(SubscriptionFilter {
+			paths: paths.iter().map(|path| path.to_string()).collect(),
+			classes: classes.iter().map(|class| class.to_string()).collect(),
+		})
+	}
+
+	fn snapshot(tree: &Tree, id: Ref) -> Snapshot {
+		let instance = tree.get_instance(id).unwrap();
+
+		Snapshot::new()
+			.with_id(id)
+			.with_name(&instance.name)
+			.with_class(instance.class)
+			.with_children(instance.children().iter().map(|child| snapshot(tree, *child)).collect())
+	}
+
+	fn rename(tree: &mut Tree, path: &str, name: &str) -> Changes {
+		let id = tree.find_by_path(path).unwrap();
+		tree.get_instance_mut(id).unwrap().name = name.to_owned();
+
+		let mut update = UpdatedSnapshot::new(id);
+		update.name = Some(name.to_owned());
+
+		let mut changes = Changes::new();
+		changes.update(update);
+		changes
+	}
+
+	/// Tree of the client built from received changes
+	#[derive(Default)]
+	struct Client {
+		instances: HashMap<Ref, (Ref, String)>,
+	}
+
+	impl Client {
+		/// Whole tree, the way a client gets it from the initial sync
+		fn with_tree(tree: &Tree) -> Self {
+			let mut client = Self::default();
+			let mut next = Some(tree.root_ref());
+
+			while let Some(id) = next {
+				let instance = tree.get_instance(id).unwrap();
+				client.instances.insert(id, (instance.parent(), instance.name.clone()));
+				next = tree.next_in_order(id);
+			}
+
+			client
+		}
+
+		/// Instances in scope of the filter, the way a client gets them from a filtered snapshot
+		fn with_filter(tree: &Tree, filter: &Option<SubscriptionFilter>) -> Self {
+			let mut client = Self::with_tree(tree);
+			let filter = filter.as_ref().unwrap();
+
+			client.instances.retain(|id, _| filter.contains(*id, tree));
+			client
+		}
+
+		/// Applies received changes, returns number of changes messages
+		fn receive(&mut self, queue: &Queue, id: u32) -> usize {
+			let mut received = 0;
+
+			while let Some(message) = queue.get_with_timeout(id, Duration::from_millis(10)).unwrap() {
+				if let Message::SyncChanges(SyncChanges(changes)) = message {
+					self.apply(changes);
+					received += 1;
+				}
+			}
+
+			received
+		}
+
+		fn apply(&mut self, changes: Changes) {
+			for id in changes.removals {
+				self.remove(id);
+			}
+
+			for snapshot in changes.additions {
+				let parent = snapshot.parent;
+				self.add(Snapshot::from(snapshot), parent);
+			}
+
+			for snapshot in changes.updates {
+				let instance = self.instances.get_mut(&snapshot.id).expect("updated unknown instance");
+
+				if let Some(name) = snapshot.name {
+					instance.1 = name;
+				}
+			}
+		}
+
+		fn add(&mut self, mut snapshot: Snapshot, parent: Ref) {
+			assert!(
+				self.instances
+					.insert(snapshot.id, (parent, mem::take(&mut snapshot.name)))
+					.is_none(),
+				"added known instance"
+			);
+
+			for child in mem::take(&mut snapshot.children) {
+				self.add(child, snapshot.id);
+			}
+		}
+
+		fn remove(&mut self, id: Ref) {
+			assert!(self.instances.remove(&id).is_some(), "removed unknown instance");
+
+			let children: Vec<Ref> = self
+				.instances
+				.iter()
+				.filter(|(_, (parent, _))| *parent == id)
+				.map(|(id, _)| *id)
+				.collect();
+
+			for child in children {
+				self.remove(child);
+			}
+		}
+
+		/// Full names of instances the client has, without the root
+		fn paths(&self) -> Vec<String> {
+			let mut paths: Vec<String> = self
+				.instances
+				.keys()
+				.filter_map(|id| {
+					let mut names = vec![];
+					let mut current = *id;
+
+					// Instances whose parents are out of scope are listed by their names
+					while let Some((parent, name)) = self.instances.get(&current) {
+						if parent.is_none() {
+							break;
+						}
+
+						names.push(name.as_str());
+						current = *parent;
+					}
+
+					names.reverse();
+
+					(!names.is_empty()).then(|| names.join("."))
+				})
+				.collect();
+
+			paths.sort();
+			paths
+		}
+	}
+
+	#[test]
+	fn unfiltered_clients() {
+		let mut tree = tree();
+		let vfs = Vfs::new_virtual();
+		let queue = Queue::new();
+
+		subscribe(&queue, 1);
+		subscribe(&queue, 2);
+
+		queue.set_filter(2, filter(&["ReplicatedStorage"], &[]), &tree).unwrap();
+
+		let mut unfiltered = Client::with_tree(&tree);
+		let mut filtered = Client::default();
+
+		// Initial sync only streams instances in scope
+		let tree_lock = Mutex::new(tree);
+		let sync = InitialSync::new(20, 2, Duration::from_secs(60));
+
+		assert_eq!(sync.start(1, false, &tree_lock, &vfs, &queue).unwrap().total, 10);
+		assert_eq!(sync.start(2, false, &tree_lock, &vfs, &queue).unwrap().total, 7);
+
+		while queue.get_with_timeout(1, Duration::from_millis(10)).unwrap().is_some() {}
+
+		while let Some(message) = queue.get_with_timeout(2, Duration::from_millis(10)).unwrap() {
+			if let Message::SyncChunk(chunk) = message {
+				for instance in chunk.instances {
+					filtered.instances.insert(instance.id, (instance.parent, instance.name));
+				}
+			}
+		}
+
+		tree = tree_lock.into_inner().unwrap();
+
+		let workspace = tree.find_by_path("Workspace").unwrap();
+		let modules = tree.find_by_path("ReplicatedStorage.Modules").unwrap();
+
+		let mut changes = Changes::new();
+		let part = Snapshot::new().with_name("Part3").with_class("Part");
+		let module = Snapshot::new().with_name("D").with_class("ModuleScript");
+
+		changes.add(part.clone().with_id(tree.insert_instance(part, workspace)), workspace);
+		changes.add(module.clone().with_id(tree.insert_instance(module, modules)), modules);
+
+		queue.push_changes(changes, None, &tree, &vfs).unwrap();
+
+		assert_eq!(unfiltered.receive(&queue, 1), 1);
+		assert_eq!(filtered.receive(&queue, 2), 1);
+
+		assert!(unfiltered.paths().contains(&String::from("Workspace.Part3")));
+		assert!(unfiltered
+			.paths()
+			.contains(&String::from("ReplicatedStorage.Modules.D")));
+
+		assert_eq!(
+			filtered.paths(),
+			[
+				"ReplicatedStorage",
+				"ReplicatedStorage.Modules",
+				"ReplicatedStorage.Modules.A",
+				"ReplicatedStorage.Modules.B",
+				"ReplicatedStorage.Modules.D",
+				"ReplicatedStorage.Old",
+				"ReplicatedStorage.Old.C",
+			]
+		);
+
+		// Changes out of scope are not sent at all
+		let changes = rename(&mut tree, "Workspace.Part1", "Base");
+		queue.push_changes(changes, None, &tree, &vfs).unwrap();
+
+		assert_eq!(unfiltered.receive(&queue, 1), 1);
+		assert_eq!(filtered.receive(&queue, 2), 0);
+
+		assert_eq!(unfiltered.paths().len(), tree.meta_map().len() - 1);
+		assert!(unfiltered.paths().contains(&String::from("Workspace.Base")));
+
+		// Filter is shown along with the client
+		let clients = queue.clients();
+
+		assert_eq!(clients[0].filter, None);
+		assert_eq!(clients[1].filter, filter(&["ReplicatedStorage"], &[]));
+	}
+
+	#[test]
+	fn boundary_moves() {
+		let mut tree = tree();
+		let vfs = Vfs::new_virtual();
+		let queue = Queue::new();
+
+		subscribe(&queue, 1);
+		queue
+			.set_filter(1, filter(&["ReplicatedStorage.Modules"], &[]), &tree)
+			.unwrap();
+
+		let mut client = Client::with_filter(&tree, &filter(&["ReplicatedStorage.Modules"], &[]));
+
+		assert_eq!(
+			client.paths(),
+			[
+				"ReplicatedStorage",
+				"ReplicatedStorage.Modules",
+				"ReplicatedStorage.Modules.A",
+				"ReplicatedStorage.Modules.B",
+			]
+		);
+
+		// Moving out of scope is a removal, moving in an addition with descendants
+		let a = tree.find_by_path("ReplicatedStorage.Modules.A").unwrap();
+
+		let mut changes = rename(&mut tree, "ReplicatedStorage.Modules", "Legacy");
+		changes.extend(rename(&mut tree, "ReplicatedStorage.Old", "Modules"));
+
+		queue.push_changes(changes, None, &tree, &vfs).unwrap();
+		client.receive(&queue, 1);
+
+		assert_eq!(
+			client.paths(),
+			[
+				"ReplicatedStorage",
+				"ReplicatedStorage.Modules",
+				"ReplicatedStorage.Modules.C"
+			]
+		);
+
+		// Instances that left the scope get no changes
+		let mut changes = Changes::new();
+		let mut update = UpdatedSnapshot::new(a);
+
+		update.properties = Some(Default::default());
+		changes.update(update);
+
+		queue.push_changes(changes, None, &tree, &vfs).unwrap();
+		assert_eq!(client.receive(&queue, 1), 0);
+
+		// Removal of an instance in scope
+		let c = tree.find_by_path("ReplicatedStorage.Modules.C").unwrap();
+		tree.remove_instance(c);
+
+		let mut changes = Changes::new();
+		changes.remove(c);
+
+		queue.push_changes(changes, None, &tree, &vfs).unwrap();
+		client.receive(&queue, 1);
+
+		assert_eq!(client.paths(), ["ReplicatedStorage", "ReplicatedStorage.Modules"]);
+	}
+
+	#[test]
+	fn class_filter() {
+		let mut tree = tree();
+		let vfs = Vfs::new_virtual();
+		let queue = Queue::new();
+		let scripts = filter(&[], &["LuaSourceContainer"]);
+
+		subscribe(&queue, 1);
+		queue.set_filter(1, scripts.clone(), &tree).unwrap();
+
+		// Instances in scope whose parents are not come after the pruned snapshot
+		let pruned = scripts
+			.as_ref()
+			.unwrap()
+			.prune(snapshot(&tree, tree.root_ref()).as_new(Ref::none()), &tree);
+		let mut names: Vec<&str> = pruned.iter().map(|snapshot| snapshot.name.as_str()).collect();
+
+		names.sort();
+		assert_eq!(names, ["A", "B", "C"]);
+
+		let mut client = Client::with_filter(&tree, &scripts);
+		assert_eq!(client.paths(), ["A", "B", "C"]);
+
+		// Changing the class moves the instance into scope
+		let part = tree.find_by_path("Workspace.Part1").unwrap();
+		tree.get_instance_mut(part).unwrap().class = "Script".into();
+
+		let mut update = UpdatedSnapshot::new(part);
+		update.class = Some("Script".into());
+
+		let mut changes = Changes::new();
+		changes.update(update);
+
+		queue.push_changes(changes, None, &tree, &vfs).unwrap();
+		client.receive(&queue, 1);
+
+		assert_eq!(client.paths(), ["A", "B", "C", "Part1"]);
+
+		// Removing a folder removes scripts in it, even though the folder is out of scope
+		let folder = tree.find_by_path("ReplicatedStorage.Old").unwrap();
+		tree.remove_instance(folder);
+
+		let mut changes = Changes::new();
+		changes.remove(folder);
+
+		queue.push_changes(changes, None, &tree, &vfs).unwrap();
+		client.receive(&queue, 1);
+
+		assert_eq!(client.paths(), ["A", "B", "Part1"]);
+	}
+
+	#[test]
+	fn filter_changes() {
+		let tree = tree();
+		let vfs = Vfs::new_virtual();
+		let queue = Queue::new();
+
+		subscribe(&queue, 1);
+
+		let mut client = Client::with_tree(&tree);
+
+		// Narrowing the scope removes instances that left it
+		queue
+			.change_filter(1, filter(&["Workspace"], &[]), &tree, &vfs)
+			.unwrap();
+		client.receive(&queue, 1);
+
+		assert_eq!(client.paths(), ["Workspace", "Workspace.Part1", "Workspace.Part2"]);
+		assert_eq!(queue.filter(1), filter(&["Workspace"], &[]));
+
+		// Moving the scope sends both removals and additions, classes
+		// apply to the subtrees while their ancestors are always in scope
+		queue
+			.change_filter(
+				1,
+				filter(&["ReplicatedStorage.Modules"], &["ModuleScript"]),
+				&tree,
+				&vfs,
+			)
+			.unwrap();
+		client.receive(&queue, 1);
+
+		assert_eq!(client.paths(), ["A", "B", "ReplicatedStorage"]);
+
+		// Same filter changes nothing
+		let changed = queue
+			.change_filter(
+				1,
+				filter(&["ReplicatedStorage.Modules"], &["ModuleScript"]),
+				&tree,
+				&vfs,
+			)
+			.unwrap();
+
+		assert_eq!(changed, 0);
+
+		// Removing the filter brings the whole tree back
+		queue.change_filter(1, None, &tree, &vfs).unwrap();
+		client.receive(&queue, 1);
+
+		assert_eq!(client.paths(), Client::with_tree(&tree).paths());
+		assert_eq!(queue.filter(1), None);
+		assert!(queue.change_filter(2, None, &tree, &vfs).is_err());
+	}
+}